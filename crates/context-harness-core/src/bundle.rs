@@ -0,0 +1,177 @@
+//! Static search bundles written by `ctx export`.
+//!
+//! A bundle is the `data.json` consumed by static sites: every document
+//! and chunk in the index, and optionally one embedding vector per chunk.
+//! [`ExportData::load_into`] fills any [`Store`] from a bundle, which is
+//! how the browser build (`--features wasm`) gets an [`InMemoryStore`]
+//! to run [`search`](crate::search::search) against.
+//!
+//! [`InMemoryStore`]: crate::store::memory::InMemoryStore
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::models::{Chunk, Document};
+use crate::store::Store;
+
+/// Export payload used by `ctx export`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportData {
+    pub documents: Vec<ExportDocument>,
+    pub chunks: Vec<ExportChunk>,
+    /// Model that produced the chunk vectors, when they were exported.
+    /// Query vectors passed to semantic or hybrid search must come from it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embedding_model: Option<String>,
+    /// Dimension of the exported chunk vectors.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embedding_dims: Option<usize>,
+}
+
+/// Exported document row.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportDocument {
+    pub id: String,
+    pub source: String,
+    pub source_id: String,
+    pub source_url: Option<String>,
+    pub title: Option<String>,
+    pub updated_at: i64,
+    pub body: String,
+}
+
+/// Exported chunk row.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportChunk {
+    pub id: String,
+    pub document_id: String,
+    pub chunk_index: i64,
+    pub text: String,
+    /// Embedding vector (`ctx export --vectors`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vector: Option<Vec<f32>>,
+}
+
+impl ExportData {
+    /// Parse a bundle from its JSON text.
+    pub fn from_json(json: &str) -> Result<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// Number of chunks that carry a vector.
+    pub fn vector_count(&self) -> usize {
+        self.chunks.iter().filter(|c| c.vector.is_some()).count()
+    }
+
+    /// Write every document, chunk, and vector in the bundle to `store`.
+    ///
+    /// Fails if a vector's length differs from `embedding_dims` (or from
+    /// the first vector when the bundle does not record dims), since mixed
+    /// dimensions make cosine scores meaningless.
+    pub async fn load_into<S: Store>(&self, store: &S) -> Result<()> {
+        let dims = self.embedding_dims.or_else(|| {
+            self.chunks
+                .iter()
+                .find_map(|c| c.vector.as_ref().map(Vec::len))
+        });
+        let model = self.embedding_model.as_deref().unwrap_or_default();
+
+        for doc in &self.documents {
+            store.upsert_document(&doc.to_document()).await?;
+        }
+
+        let mut start = 0;
+        while start < self.chunks.len() {
+            let doc_id = &self.chunks[start].document_id;
+            let end = self.chunks[start..]
+                .iter()
+                .position(|c| &c.document_id != doc_id)
+                .map_or(self.chunks.len(), |n| start + n);
+            let group = &self.chunks[start..end];
+
+            let chunks: Vec<Chunk> = group.iter().map(ExportChunk::to_chunk).collect();
+            store.replace_chunks(doc_id, &chunks, None).await?;
+            for (exported, chunk) in group.iter().zip(&chunks) {
+                let Some(vector) = &exported.vector else {
+                    continue;
+                };
+                if Some(vector.len()) != dims {
+                    bail!(
+                        "chunk {} has a {}-dimensional vector, expected {}",
+                        exported.id,
+                        vector.len(),
+                        dims.unwrap_or_default()
+                    );
+                }
+                store
+                    .upsert_embedding(&chunk.id, doc_id, vector, model, vector.len(), &chunk.hash)
+                    .await?;
+            }
+            start = end;
+        }
+        Ok(())
+    }
+}
+
+impl ExportDocument {
+    fn to_document(&self) -> Document {
+        Document {
+            id: self.id.clone(),
+            source: self.source.clone(),
+            source_id: self.source_id.clone(),
+            source_url: self.source_url.clone(),
+            title: self.title.clone(),
+            author: None,
+            created_at: self.updated_at,
+            updated_at: self.updated_at,
+            content_type: "text/plain".to_string(),
+            body: self.body.clone(),
+            metadata_json: "{}".to_string(),
+            raw_json: None,
+            dedup_hash: String::new(),
+            parent_source_id: None,
+            summary: None,
+        }
+    }
+}
+
+impl ExportChunk {
+    fn to_chunk(&self) -> Chunk {
+        Chunk {
+            id: self.id.clone(),
+            document_id: self.document_id.clone(),
+            chunk_index: self.chunk_index,
+            text: self.text.clone(),
+            hash: String::new(),
+            metadata: serde_json::Map::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundle_without_vectors_omits_embedding_fields() {
+        let data = ExportData {
+            documents: Vec::new(),
+            chunks: vec![ExportChunk {
+                id: "c1".to_string(),
+                document_id: "d1".to_string(),
+                chunk_index: 0,
+                text: "alpha".to_string(),
+                vector: None,
+            }],
+            embedding_model: None,
+            embedding_dims: None,
+        };
+        let json = serde_json::to_value(&data).unwrap();
+        assert!(json.get("embedding_model").is_none());
+        assert!(json["chunks"][0].get("vector").is_none());
+
+        let parsed = ExportData::from_json(&json.to_string()).unwrap();
+        assert_eq!(parsed, data);
+        assert_eq!(parsed.vector_count(), 0);
+    }
+}
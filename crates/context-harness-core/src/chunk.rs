@@ -1,8 +1,10 @@
-//! Paragraph-boundary text chunker.
+//! Paragraph- and line-boundary text chunker.
 //!
 //! Splits document body text into [`Chunk`]s that respect a configurable
-//! `max_tokens` limit. Splitting occurs on paragraph boundaries (`\n\n`)
-//! to preserve semantic coherence within each chunk.
+//! `max_tokens` limit. By default splitting occurs on paragraph boundaries
+//! (`\n\n`) to preserve semantic coherence within each chunk;
+//! [`ChunkStrategy::Line`] splits on single newlines instead, which suits
+//! code, logs, and other text without blank-line structure.
 //!
 //! Each chunk receives a deterministic UUID derived from its document ID
 //! and index, plus a SHA-256 hash of its text for staleness detection
@@ -11,7 +13,7 @@
 //! # Algorithm
 //!
 //! 1. Convert `max_tokens` to `max_chars` using a 4 chars/token ratio.
-//! 2. Split text on `\n\n` paragraph boundaries.
+//! 2. Split text on `\n\n` paragraph boundaries (or `\n` line boundaries).
 //! 3. Accumulate paragraphs into a buffer until adding the next paragraph
 //!    would exceed `max_chars`.
 //! 4. When exceeded, flush the buffer as a chunk and start a new one.
@@ -19,6 +21,9 @@
 //!    the nearest newline or space boundary.
 //! 6. Guarantee at least one chunk per document (even for empty text).
 //!
+//! [`apply_overlap`] then optionally prefixes each chunk with the tail of
+//! the previous one so context spanning a boundary appears in both.
+//!
 //! # Example
 //!
 //! ```rust
@@ -28,6 +33,14 @@
 //! assert_eq!(chunks.len(), 1);
 //! assert_eq!(chunks[0].chunk_index, 0);
 //! ```
+//!
+//! # Chunk Metadata
+//!
+//! [`annotate_chunks`] copies selected document fields onto every chunk
+//! and, for markdown documents, records the heading breadcrumb in effect
+//! at the start of each chunk under [`HEADING_KEY`]. Chunks of a document
+//! stitched from several files record the file they came from under
+//! [`SECTION_SOURCE_ID_KEY`] and [`SECTION_SOURCE_URL_KEY`].
 
 use sha2::{Digest, Sha256};
 use uuid::Uuid;
@@ -59,27 +72,85 @@ const CHARS_PER_TOKEN: usize = 4;
 /// - Chunks are split on `\n\n` boundaries when possible.
 /// - Oversized paragraphs are hard-split at space/newline boundaries.
 pub fn chunk_text(document_id: &str, text: &str, max_tokens: usize) -> Vec<Chunk> {
+    chunk_text_with(document_id, text, ChunkStrategy::Paragraph, max_tokens)
+}
+
+/// Where [`chunk_text_with`] is allowed to split text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChunkStrategy {
+    /// Split on blank lines (`\n\n`), trimming each paragraph.
+    #[default]
+    Paragraph,
+    /// Split on single newlines, keeping leading indentation.
+    Line,
+}
+
+impl ChunkStrategy {
+    /// Parse a strategy name (`"paragraph"` or `"line"`).
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "paragraph" => Some(Self::Paragraph),
+            "line" => Some(Self::Line),
+            _ => None,
+        }
+    }
+
+    /// The strategy's configuration name.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Paragraph => "paragraph",
+            Self::Line => "line",
+        }
+    }
+
+    fn separator(&self) -> &'static str {
+        match self {
+            Self::Paragraph => "\n\n",
+            Self::Line => "\n",
+        }
+    }
+
+    fn trim<'a>(&self, unit: &'a str) -> &'a str {
+        match self {
+            Self::Paragraph => unit.trim(),
+            Self::Line => unit.trim_end(),
+        }
+    }
+}
+
+/// Split text into chunks using `strategy`, respecting `max_tokens`.
+///
+/// [`chunk_text`] is this function with [`ChunkStrategy::Paragraph`]; the
+/// same guarantees apply, with "paragraph" read as "line" for
+/// [`ChunkStrategy::Line`].
+pub fn chunk_text_with(
+    document_id: &str,
+    text: &str,
+    strategy: ChunkStrategy,
+    max_tokens: usize,
+) -> Vec<Chunk> {
     let max_chars = max_tokens * CHARS_PER_TOKEN;
+    let separator = strategy.separator();
 
     if text.is_empty() {
         return vec![make_chunk(document_id, 0, text)];
     }
 
-    let paragraphs: Vec<&str> = text.split("\n\n").collect();
+    let paragraphs: Vec<&str> = text.split(separator).collect();
     let mut chunks = Vec::new();
     let mut current_buf = String::new();
     let mut chunk_index: i64 = 0;
 
     for para in paragraphs {
-        let trimmed = para.trim();
-        if trimmed.is_empty() {
+        let trimmed = strategy.trim(para);
+        if trimmed.trim().is_empty() {
             continue;
         }
 
         let would_be = if current_buf.is_empty() {
             trimmed.len()
         } else {
-            current_buf.len() + 2 + trimmed.len()
+            current_buf.len() + separator.len() + trimmed.len()
         };
 
         if would_be > max_chars && !current_buf.is_empty() {
@@ -134,7 +205,7 @@ pub fn chunk_text(document_id: &str, text: &str, max_tokens: usize) -> Vec<Chunk
             }
         } else {
             if !current_buf.is_empty() {
-                current_buf.push_str("\n\n");
+                current_buf.push_str(separator);
             }
             current_buf.push_str(trimmed);
         }
@@ -151,6 +222,48 @@ pub fn chunk_text(document_id: &str, text: &str, max_tokens: usize) -> Vec<Chunk
     chunks
 }
 
+/// Prefix every chunk after the first with up to `overlap_tokens` of the
+/// previous chunk's trailing text, then recompute its hash.
+///
+/// The overlap starts on a word boundary where possible and is joined to
+/// the chunk with a newline. Call this after [`annotate_chunks`] so
+/// heading breadcrumbs are located from each chunk's own text.
+pub fn apply_overlap(chunks: &mut [Chunk], overlap_tokens: usize) {
+    if overlap_tokens == 0 || chunks.len() < 2 {
+        return;
+    }
+    let overlap_chars = overlap_tokens * CHARS_PER_TOKEN;
+    let tails: Vec<String> = chunks
+        .iter()
+        .map(|c| overlap_tail(&c.text, overlap_chars).to_string())
+        .collect();
+    for (chunk, tail) in chunks.iter_mut().skip(1).zip(tails) {
+        if tail.is_empty() {
+            continue;
+        }
+        let text = format!("{}\n{}", tail, chunk.text);
+        let fresh = make_chunk(&chunk.document_id, chunk.chunk_index, &text);
+        chunk.text = fresh.text;
+        chunk.hash = fresh.hash;
+    }
+}
+
+/// The last `max_chars` bytes of `text`, advanced to a word boundary.
+fn overlap_tail(text: &str, max_chars: usize) -> &str {
+    if text.len() <= max_chars {
+        return text.trim();
+    }
+    let mut start = text.len() - max_chars;
+    while !text.is_char_boundary(start) {
+        start += 1;
+    }
+    let tail = &text[start..];
+    match tail.find(char::is_whitespace) {
+        Some(i) if !tail[i..].trim().is_empty() => tail[i..].trim(),
+        _ => tail.trim(),
+    }
+}
+
 /// Snap a byte index back to the nearest valid UTF-8 char boundary.
 fn snap_to_char_boundary(s: &str, index: usize) -> usize {
     if index >= s.len() {
@@ -175,7 +288,162 @@ fn make_chunk(document_id: &str, index: i64, text: &str) -> Chunk {
         chunk_index: index,
         text: text.to_string(),
         hash,
+        metadata: serde_json::Map::new(),
+    }
+}
+
+/// Chunk metadata key holding the markdown heading breadcrumb
+/// (e.g. `"Deployment > Rollback > Database"`).
+pub const HEADING_KEY: &str = "heading";
+
+/// Chunk metadata key holding the `source_id` of the file a chunk of a
+/// stitched document came from.
+pub const SECTION_SOURCE_ID_KEY: &str = "section_source_id";
+
+/// Chunk metadata key holding the `source_url` of the file a chunk of a
+/// stitched document came from, when it has one.
+pub const SECTION_SOURCE_URL_KEY: &str = "section_source_url";
+
+/// Separator between headings in a breadcrumb.
+const BREADCRUMB_SEPARATOR: &str = " > ";
+
+/// Returns `true` if a document should be treated as markdown for
+/// heading extraction, based on its content type or file extension.
+pub fn is_markdown(source_id: &str, content_type: &str) -> bool {
+    if content_type == "text/markdown" {
+        return true;
+    }
+    let lower = source_id.to_lowercase();
+    lower.ends_with(".md") || lower.ends_with(".markdown") || lower.ends_with(".mdx")
+}
+
+/// Attach chunk-level metadata to chunks produced by [`chunk_text`].
+///
+/// Every chunk receives a copy of `inherited` (document fields selected by
+/// the caller). When `markdown` is true, chunks under at least one heading
+/// also receive a [`HEADING_KEY`] breadcrumb computed by [`heading_breadcrumbs`].
+pub fn annotate_chunks(
+    chunks: &mut [Chunk],
+    text: &str,
+    inherited: &serde_json::Map<String, serde_json::Value>,
+    markdown: bool,
+) {
+    let breadcrumbs = if markdown {
+        heading_breadcrumbs(text, chunks)
+    } else {
+        vec![None; chunks.len()]
+    };
+
+    for (chunk, crumb) in chunks.iter_mut().zip(breadcrumbs) {
+        for (k, v) in inherited {
+            chunk.metadata.insert(k.clone(), v.clone());
+        }
+        if let Some(crumb) = crumb {
+            chunk
+                .metadata
+                .insert(HEADING_KEY.to_string(), serde_json::Value::String(crumb));
+        }
+    }
+}
+
+/// Compute the markdown heading breadcrumb in effect at the start of each chunk.
+///
+/// ATX headings (`#` … `######`) are tracked as a stack; a heading pops
+/// every open heading of the same or deeper level. Headings inside fenced
+/// code blocks are ignored. A chunk that opens with a heading includes
+/// that heading in its own breadcrumb.
+///
+/// Chunks are located in `text` in order, so the slice must come from
+/// [`chunk_text`] over the same text.
+pub fn heading_breadcrumbs(text: &str, chunks: &[Chunk]) -> Vec<Option<String>> {
+    let headings = atx_headings(text);
+
+    let mut result = Vec::with_capacity(chunks.len());
+    let mut stack: Vec<(usize, String)> = Vec::new();
+    let mut next_heading = 0usize;
+    let mut cursor = 0usize;
+
+    for chunk in chunks {
+        let first_line = chunk.text.lines().next().unwrap_or("").trim();
+        let start = if first_line.is_empty() {
+            cursor
+        } else {
+            text[cursor..]
+                .find(first_line)
+                .map(|i| cursor + i)
+                .unwrap_or(cursor)
+        };
+
+        while next_heading < headings.len() && headings[next_heading].0 <= start {
+            let (_, level, ref title) = headings[next_heading];
+            while stack.last().is_some_and(|(l, _)| *l >= level) {
+                stack.pop();
+            }
+            stack.push((level, title.clone()));
+            next_heading += 1;
+        }
+
+        result.push(if stack.is_empty() {
+            None
+        } else {
+            Some(
+                stack
+                    .iter()
+                    .map(|(_, t)| t.as_str())
+                    .collect::<Vec<_>>()
+                    .join(BREADCRUMB_SEPARATOR),
+            )
+        });
+
+        cursor = snap_to_char_boundary(text, start + first_line.len().max(1));
+    }
+
+    result
+}
+
+/// The titles of the ATX headings in a markdown document, in order.
+/// Headings inside fenced code blocks are ignored.
+pub fn markdown_headings(text: &str) -> Vec<String> {
+    atx_headings(text)
+        .into_iter()
+        .map(|(_, _, title)| title)
+        .collect()
+}
+
+/// ATX headings outside fenced code blocks, as `(byte offset, level, title)`.
+fn atx_headings(text: &str) -> Vec<(usize, usize, String)> {
+    let mut headings = Vec::new();
+    let mut in_fence = false;
+    let mut offset = 0usize;
+    for line in text.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+        } else if !in_fence {
+            if let Some((level, title)) = parse_atx_heading(trimmed) {
+                headings.push((offset, level, title));
+            }
+        }
+        offset += line.len();
+    }
+    headings
+}
+
+/// Parse an ATX heading line (`## Title ##`) into `(level, title)`.
+fn parse_atx_heading(line: &str) -> Option<(usize, String)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    if level == 0 || level > 6 {
+        return None;
+    }
+    let rest = &line[level..];
+    if !rest.is_empty() && !rest.starts_with(' ') && !rest.starts_with('\t') {
+        return None;
+    }
+    let title = rest.trim().trim_end_matches('#').trim();
+    if title.is_empty() {
+        return None;
     }
+    Some((level, title.to_string()))
 }
 
 #[cfg(test)]
@@ -250,4 +518,69 @@ mod tests {
             assert_eq!(a.chunk_index, b.chunk_index);
         }
     }
+
+    #[test]
+    fn test_line_strategy_splits_on_newlines() {
+        let text = "fn main() {\n    println!(\"one\");\n    println!(\"two\");\n}";
+        let chunks = chunk_text_with("doc1", text, ChunkStrategy::Line, 5);
+        assert!(chunks.len() > 1);
+        assert!(chunks.iter().any(|c| c.text.starts_with("    println!")));
+        assert_eq!(ChunkStrategy::parse("line"), Some(ChunkStrategy::Line));
+        assert_eq!(ChunkStrategy::parse("sentence"), None);
+    }
+
+    #[test]
+    fn test_apply_overlap_prefixes_previous_tail() {
+        let text = "alpha beta gamma delta\n\nepsilon zeta eta theta";
+        let mut chunks = chunk_text("doc1", text, 6);
+        assert_eq!(chunks.len(), 2);
+        let original_hash = chunks[1].hash.clone();
+        apply_overlap(&mut chunks, 2);
+        assert_eq!(chunks[0].text, "alpha beta gamma delta");
+        assert_eq!(chunks[1].text, "delta\nepsilon zeta eta theta");
+        assert_ne!(chunks[1].hash, original_hash);
+    }
+
+    #[test]
+    fn test_heading_breadcrumbs() {
+        let text = "# Guide\n\nIntro text.\n\n## Install\n\nRun cargo.\n\n### Linux\n\napt stuff.\n\n## Usage\n\n```\n# not a heading\n```\n\nUse it.";
+        let chunks = chunk_text("doc1", text, 4);
+        let crumbs = heading_breadcrumbs(text, &chunks);
+        assert_eq!(crumbs.len(), chunks.len());
+
+        let crumb_for = |needle: &str| {
+            let i = chunks.iter().position(|c| c.text.contains(needle)).unwrap();
+            crumbs[i].clone()
+        };
+        assert_eq!(crumb_for("Intro text."), Some("Guide".to_string()));
+        assert_eq!(
+            crumb_for("apt stuff."),
+            Some("Guide > Install > Linux".to_string())
+        );
+        assert_eq!(crumb_for("Use it."), Some("Guide > Usage".to_string()));
+    }
+
+    #[test]
+    fn test_markdown_headings_skip_fences() {
+        let text = "# Guide\n\n```sh\n# not a heading\n```\n\n## Install ##\n#hashtag";
+        assert_eq!(markdown_headings(text), vec!["Guide", "Install"]);
+    }
+
+    #[test]
+    fn test_annotate_chunks_inherits_metadata() {
+        let text = "Plain text.\n\nNo headings here.";
+        let mut chunks = chunk_text("doc1", text, 700);
+        let mut inherited = serde_json::Map::new();
+        inherited.insert("lang".to_string(), serde_json::json!("en"));
+        annotate_chunks(&mut chunks, text, &inherited, true);
+        assert_eq!(chunks[0].metadata["lang"], "en");
+        assert!(!chunks[0].metadata.contains_key(HEADING_KEY));
+    }
+
+    #[test]
+    fn test_is_markdown() {
+        assert!(is_markdown("docs/README.md", "text/plain"));
+        assert!(is_markdown("notes", "text/markdown"));
+        assert!(!is_markdown("src/main.rs", "text/plain"));
+    }
 }
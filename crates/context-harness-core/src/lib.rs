@@ -5,10 +5,15 @@
 //!
 //! This crate contains no tokio, sqlx, filesystem I/O, or other
 //! native-only dependencies. It compiles to both native targets and
-//! `wasm32-unknown-unknown`.
+//! `wasm32-unknown-unknown`. With the `wasm` feature, the `wasm` module adds
+//! JavaScript bindings for searching a `ctx export` bundle in the browser.
 
+pub mod bundle;
 pub mod chunk;
 pub mod embedding;
 pub mod models;
+pub mod query;
 pub mod search;
 pub mod store;
+#[cfg(feature = "wasm")]
+pub mod wasm;
@@ -44,6 +44,7 @@ use chrono::{DateTime, Utc};
 /// | `metadata_json` | Connector-specific metadata as a JSON string |
 /// | `raw_json` | Optional raw API response for debugging |
 /// | `raw_bytes` | When set, the pipeline runs extraction and sets `body` before upsert; content_type identifies the format |
+/// | `parent_source_id` | `source_id` of the parent item in the same source (e.g. the page an attachment belongs to) |
 #[derive(Debug, Clone)]
 pub struct SourceItem {
     /// Connector name: `"filesystem"`, `"git"`, or `"s3"`.
@@ -70,6 +71,9 @@ pub struct SourceItem {
     pub raw_json: Option<String>,
     /// When set, the pipeline runs extraction and sets body from the result before upsert; content_type identifies the format.
     pub raw_bytes: Option<Vec<u8>>,
+    /// `source_id` of the parent item in the same source, for attachments,
+    /// sub-pages, and other hierarchical content.
+    pub parent_source_id: Option<String>,
 }
 
 /// Normalized document stored in the `documents` table.
@@ -108,8 +112,14 @@ pub struct Document {
     pub metadata_json: String,
     /// Raw connector response.
     pub raw_json: Option<String>,
-    /// SHA-256 hash for deduplication: `H(source || source_id || updated_at || body)`.
+    /// SHA-256 hash for deduplication: `H(source || source_id || updated_at || body)`,
+    /// plus `parent_source_id` when set.
     pub dedup_hash: String,
+    /// `source_id` of the parent document in the same source, if any.
+    pub parent_source_id: Option<String>,
+    /// Short summary of a long body, generated at ingest when `[summary]`
+    /// is enabled and indexed for keyword search with the document's chunks.
+    pub summary: Option<String>,
 }
 
 /// A chunk of a document's body text, stored in the `chunks` table.
@@ -121,6 +131,8 @@ pub struct Document {
 /// - A contiguous `chunk_index` starting at 0
 /// - A SHA-256 `hash` of its text content, used by the embedding pipeline
 ///   to detect when re-embedding is needed (staleness detection)
+/// - Optional `metadata` inherited from the parent document plus
+///   chunk-specific fields such as the markdown heading breadcrumb
 #[derive(Debug, Clone)]
 pub struct Chunk {
     /// UUID v4 primary key.
@@ -133,6 +145,8 @@ pub struct Chunk {
     pub text: String,
     /// SHA-256 hash of `text`, used for embedding staleness detection.
     pub hash: String,
+    /// Chunk-level metadata (inherited document fields, `heading` breadcrumb).
+    pub metadata: serde_json::Map<String, serde_json::Value>,
 }
 
 /// A search result returned from the query engine.
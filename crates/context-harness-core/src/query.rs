@@ -0,0 +1,155 @@
+//! Inline search modifiers.
+//!
+//! A query such as `source:git after:2024-01-01 type:md "exact phrase" deploy`
+//! carries its filters in the text, so callers that only pass a query string
+//! (MCP agents, the search box of an exported bundle) can still narrow it.
+//! [`parse_query`] splits such a query into the remaining search text and
+//! the filters it names:
+//!
+//! | Modifier | Filter |
+//! |----------|--------|
+//! | `source:<source>` | Source filter; a bare connector type (`source:git`) matches each of its instances |
+//! | `after:<YYYY-MM-DD>`, `since:` | Updated on or after the date (like `--since`) |
+//! | `before:<YYYY-MM-DD>`, `until:` | Updated on or before the date (like `--as-of`) |
+//! | `type:<type>` | File extension or content type (see [`type_matches`](crate::search::type_matches)); repeat to allow several |
+//! | `ref:<ref>` | Git branch or tag; a chunk filter on [`REF_KEY`] (see [`ParsedQuery::ref_filter`]) |
+//!
+//! Modifier names are case-insensitive. For `source`, `after`, `before`, and
+//! `ref` the last occurrence wins. Anything else — unknown names (`foo:bar`,
+//! URLs), empty values, or dates that do not parse — is kept as search
+//! text. Double-quoted phrases are kept with their quotes; the keyword
+//! index matches them as exact phrases.
+
+use chrono::NaiveDate;
+
+/// Chunk metadata key holding the Git ref a document was read from.
+pub const REF_KEY: &str = "ref";
+
+/// A query split into search text and modifier filters.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParsedQuery {
+    /// The query without its modifiers.
+    pub text: String,
+    /// `source:` value.
+    pub source: Option<String>,
+    /// `after:` / `since:` date (`YYYY-MM-DD`).
+    pub since: Option<String>,
+    /// `before:` / `until:` date (`YYYY-MM-DD`).
+    pub as_of: Option<String>,
+    /// `type:` values, lowercased and without a leading `.`.
+    pub types: Vec<String>,
+    /// `ref:` value.
+    pub git_ref: Option<String>,
+}
+
+impl ParsedQuery {
+    /// Whether the query named any modifier.
+    pub fn has_modifiers(&self) -> bool {
+        self.source.is_some()
+            || self.since.is_some()
+            || self.as_of.is_some()
+            || !self.types.is_empty()
+            || self.git_ref.is_some()
+    }
+
+    /// The `ref:` modifier as a chunk metadata filter, matched like any
+    /// other (case-insensitive substring, so `ref:release` keeps every
+    /// release branch).
+    pub fn ref_filter(&self) -> Option<(String, String)> {
+        self.git_ref
+            .as_ref()
+            .map(|value| (REF_KEY.to_string(), value.clone()))
+    }
+}
+
+/// Split `query` into search text and modifier filters.
+pub fn parse_query(query: &str) -> ParsedQuery {
+    let mut parsed = ParsedQuery::default();
+    let mut terms: Vec<&str> = Vec::new();
+    let mut rest = query.trim_start();
+    while !rest.is_empty() {
+        let token;
+        if let Some(phrase) = rest.strip_prefix('"') {
+            // A phrase runs to the closing quote, or to the end of the query.
+            let end = phrase.find('"').map_or(rest.len(), |i| i + 2);
+            token = &rest[..end];
+            rest = &rest[end..];
+        } else {
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            token = &rest[..end];
+            rest = &rest[end..];
+            if apply_modifier(&mut parsed, token) {
+                rest = rest.trim_start();
+                continue;
+            }
+        }
+        terms.push(token);
+        rest = rest.trim_start();
+    }
+    parsed.text = terms.join(" ");
+    parsed
+}
+
+/// Record `token` in `parsed` if it is a valid modifier.
+fn apply_modifier(parsed: &mut ParsedQuery, token: &str) -> bool {
+    let Some((name, value)) = token.split_once(':') else {
+        return false;
+    };
+    if value.is_empty() {
+        return false;
+    }
+    let is_date = || NaiveDate::parse_from_str(value, "%Y-%m-%d").is_ok();
+    match name.to_ascii_lowercase().as_str() {
+        "source" => parsed.source = Some(value.to_string()),
+        "ref" => parsed.git_ref = Some(value.to_string()),
+        "after" | "since" if is_date() => parsed.since = Some(value.to_string()),
+        "before" | "until" if is_date() => parsed.as_of = Some(value.to_string()),
+        "type" => match value.trim_start_matches('.') {
+            "" => return false,
+            ty => parsed.types.push(ty.to_lowercase()),
+        },
+        _ => return false,
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_query_modifiers() {
+        let parsed = parse_query(r#"source:git after:2024-01-01 type:md "exact phrase" deploy"#);
+        assert_eq!(parsed.text, r#""exact phrase" deploy"#);
+        assert_eq!(parsed.source.as_deref(), Some("git"));
+        assert_eq!(parsed.since.as_deref(), Some("2024-01-01"));
+        assert_eq!(parsed.as_of, None);
+        assert_eq!(parsed.types, vec!["md"]);
+        assert!(parsed.has_modifiers());
+
+        let parsed = parse_query("Before:2024-06-30 source:script:jira type:.MD type:pdf x");
+        assert_eq!(parsed.text, "x");
+        assert_eq!(parsed.source.as_deref(), Some("script:jira"));
+        assert_eq!(parsed.as_of.as_deref(), Some("2024-06-30"));
+        assert_eq!(parsed.types, vec!["md", "pdf"]);
+
+        let parsed = parse_query("ref:release/1.4 rollback");
+        assert_eq!(parsed.text, "rollback");
+        assert_eq!(
+            parsed.ref_filter(),
+            Some(("ref".to_string(), "release/1.4".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_query_keeps_unknown_modifiers_as_text() {
+        let parsed = parse_query("foo:bar after:yesterday type: https://example.com \"open");
+        assert_eq!(
+            parsed.text,
+            "foo:bar after:yesterday type: https://example.com \"open"
+        );
+        assert!(!parsed.has_modifiers());
+        assert_eq!(parse_query("  plain   words ").text, "plain words");
+        assert_eq!(parse_query("source:git").text, "");
+    }
+}
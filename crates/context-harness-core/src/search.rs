@@ -10,16 +10,30 @@
 //! 1. Fetch `candidate_k_keyword` keyword candidates (BM25 rank).
 //! 2. Fetch `candidate_k_vector` vector candidates (cosine similarity).
 //! 3. Normalize both sets to `[0, 1]` using min-max normalization.
-//! 4. Merge: `score = (1 - α) × keyword + α × semantic`.
-//! 5. Group by document (MAX aggregation).
+//! 4. Merge: `score = (1 - α) × keyword + α × semantic`. A hybrid
+//!    candidate whose chunk has no embedding yet (see
+//!    [`Store::unembedded_chunks`]) scores on keyword relevance alone, so a
+//!    partially embedded corpus doesn't bury the unembedded part.
+//! 5. Group by document (MAX aggregation), dropping documents outside the
+//!    `since` / `as_of` window or the `allowed_sources` scope.
 //! 6. Sort by score (desc), updated_at (desc), id (asc).
 //! 7. Truncate to `final_limit`.
+//! 8. Auto-merge: with [`SearchParams::auto_merge_min_chunks`] set, a
+//!    result whose document has at least that many matching chunks close
+//!    together (see [`merge_span`]) returns the text of the whole span,
+//!    first to last of them, as its snippet instead of one chunk's excerpt.
+//!
+//! Callers may then fold results into [`SearchResultGroup`]s by parent
+//! document with [`group_by_parent`], into [`FacetGroup`]s by source,
+//! content type, or path prefix with [`group_by_facet`], or count them per
+//! facet value with [`facets`].
 
-use anyhow::{bail, Result};
+use anyhow::{bail, Context, Result};
 use chrono::NaiveDate;
-use serde::Serialize;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 
+use crate::chunk::{HEADING_KEY, SECTION_SOURCE_ID_KEY, SECTION_SOURCE_URL_KEY};
 use crate::store::{ChunkCandidate, DocumentMetadata, Store};
 
 /// Retrieval tuning parameters, decoupled from application config.
@@ -33,6 +47,9 @@ pub struct SearchParams {
     pub candidate_k_vector: i64,
     /// Maximum results to return.
     pub final_limit: i64,
+    /// Matching chunks of one document needed to merge them into one
+    /// snippet spanning their range; `0` disables auto-merging.
+    pub auto_merge_min_chunks: usize,
 }
 
 /// Bundles all inputs for a single search invocation.
@@ -44,18 +61,35 @@ pub struct SearchRequest<'a> {
     pub query_vec: Option<&'a [f32]>,
     /// `"keyword"`, `"semantic"`, or `"hybrid"`.
     pub mode: &'a str,
-    /// Only return results from this connector source.
+    /// Only return results from this connector source (see
+    /// [`source_filter_matches`]).
     pub source_filter: Option<&'a str>,
+    /// Only return results from sources matching one of these patterns
+    /// (see [`source_matches`]). `None` allows every source.
+    pub allowed_sources: Option<&'a [String]>,
     /// Only return documents updated after this date (`YYYY-MM-DD`).
     pub since: Option<&'a str>,
+    /// Only return documents last updated on or before this date
+    /// (`YYYY-MM-DD`), approximating the corpus as it stood then.
+    ///
+    /// Applied to the retrieved candidates, like `since`: documents edited
+    /// later still take candidate slots, so fewer than `final_limit`
+    /// results may remain. Earlier versions of edited documents are not
+    /// kept, so they are excluded rather than shown as they were.
+    pub as_of: Option<&'a str>,
     /// Retrieval tuning parameters.
     pub params: SearchParams,
     /// If true, populate [`ScoreExplanation`] on each result.
     pub explain: bool,
+    /// Chunk metadata filters as `(key, value)` pairs; see [`chunk_metadata_matches`].
+    pub chunk_filters: &'a [(String, String)],
+    /// Only return documents of one of these types (see [`type_matches`]).
+    /// Empty allows every type.
+    pub types: &'a [String],
 }
 
 /// A search result matching the `SCHEMAS.md` `context.search` response shape.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchResultItem {
     /// Document UUID.
     pub id: String,
@@ -67,30 +101,291 @@ pub struct SearchResultItem {
     pub source: String,
     /// Identifier within the source.
     pub source_id: String,
+    /// MIME type of the document (e.g. `text/markdown`); absent in results
+    /// from remote indexes that do not report it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
     /// Last modification timestamp (ISO 8601).
     pub updated_at: String,
     /// Text excerpt from the best-matching chunk.
     pub snippet: String,
     /// Web-browsable URL, if available.
     pub source_url: Option<String>,
-    /// Scoring breakdown (populated when `explain` is true).
+    /// Markdown heading breadcrumb of the best-matching chunk, if any.
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub heading: Option<String>,
+    /// For a document stitched from several files, the `source_id` of the
+    /// file the best-matching chunk came from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub section_source_id: Option<String>,
+    /// URL of that file, if available.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub section_source_url: Option<String>,
+    /// UUID of the best-matching chunk (the first chunk of a pinned
+    /// document); absent for title-search hits.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chunk_id: Option<String>,
+    /// UUID of the parent document (e.g. the page an attachment belongs to).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<String>,
+    /// Scoring breakdown (populated when `explain` is true).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub explain: Option<ScoreExplanation>,
+    /// Name of the index the result came from, set by federated search.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin: Option<String>,
+    /// Ingest-time summary of the document, for long documents when
+    /// `[summary]` is enabled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    /// Document type assigned at ingest (e.g. `runbook`), when
+    /// `[classify]` is enabled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub doc_type: Option<String>,
 }
 
-/// Scoring breakdown for a search result.
+/// Search results that share a parent document.
+///
+/// Produced by [`group_by_parent`]. A group is keyed by the parent's UUID, or
+/// by the result's own UUID when it has no parent.
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchResultGroup {
+    /// UUID of the parent document (or of the top-level result itself).
+    pub parent_id: String,
+    /// Best score among the group's results.
+    pub score: f64,
+    /// Results in the group, best first.
+    pub results: Vec<SearchResultItem>,
+}
+
+/// Group ranked results by parent document.
+///
+/// Groups are ordered by their best-scoring member, and results keep their
+/// relative order within a group, so the input ranking is preserved.
+pub fn group_by_parent(results: Vec<SearchResultItem>) -> Vec<SearchResultGroup> {
+    let mut groups: Vec<SearchResultGroup> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    for item in results {
+        let key = item.parent_id.clone().unwrap_or_else(|| item.id.clone());
+        match index.get(&key) {
+            Some(&i) => {
+                let group = &mut groups[i];
+                group.score = group.score.max(item.score);
+                group.results.push(item);
+            }
+            None => {
+                index.insert(key.clone(), groups.len());
+                groups.push(SearchResultGroup {
+                    parent_id: key,
+                    score: item.score,
+                    results: vec![item],
+                });
+            }
+        }
+    }
+    groups
+}
+
+/// Default number of directories kept by [`path_prefix`] (`docs/runbooks/`).
+pub const DEFAULT_PREFIX_DEPTH: usize = 2;
+
+/// A field that results can be grouped and counted by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FacetField {
+    /// Leading directories of `source_id` (see [`path_prefix`]).
+    PathPrefix,
+    /// Connector source, e.g. `git:platform`.
+    Source,
+    /// Document MIME type; `unknown` when absent.
+    ContentType,
+}
+
+impl FacetField {
+    /// Parse `path_prefix`, `source`, or `content_type`.
+    pub fn parse(name: &str) -> Result<Self> {
+        match name {
+            "path_prefix" => Ok(Self::PathPrefix),
+            "source" => Ok(Self::Source),
+            "content_type" => Ok(Self::ContentType),
+            other => bail!(
+                "unknown group-by field '{}' (expected path_prefix, source, or content_type)",
+                other
+            ),
+        }
+    }
+
+    /// The value of this field for `item`.
+    pub fn value_of(self, item: &SearchResultItem, depth: usize) -> String {
+        match self {
+            Self::PathPrefix => path_prefix(&item.source_id, depth),
+            Self::Source => item.source.clone(),
+            Self::ContentType => item
+                .content_type
+                .clone()
+                .unwrap_or_else(|| "unknown".to_string()),
+        }
+    }
+}
+
+/// Up to `depth` leading directories of a source ID, with a trailing `/`.
+///
+/// `docs/runbooks/deploy/rollback.md` gives `docs/runbooks/` at depth 2,
+/// and an S3 key is treated the same way. An ID without a directory (a
+/// top-level file, or a ticket key like `OPS-4127`) gives `""`.
+pub fn path_prefix(source_id: &str, depth: usize) -> String {
+    let dirs: Vec<&str> = match source_id.rsplit_once('/') {
+        Some((dir, _)) => dir.split('/').filter(|s| !s.is_empty()).collect(),
+        None => Vec::new(),
+    };
+    dirs.iter()
+        .take(depth)
+        .map(|dir| format!("{}/", dir))
+        .collect()
+}
+
+/// Search results sharing one value of a [`FacetField`].
+///
+/// Produced by [`group_by_facet`].
 #[derive(Debug, Clone, Serialize)]
+pub struct FacetGroup {
+    /// The shared value, e.g. `docs/runbooks/`.
+    pub value: String,
+    /// Best score among the group's results.
+    pub score: f64,
+    /// Results in the group, best first.
+    pub results: Vec<SearchResultItem>,
+}
+
+/// Group ranked results by a facet value.
+///
+/// Like [`group_by_parent`], groups are ordered by their best-scoring
+/// member and results keep their relative order within a group.
+pub fn group_by_facet(
+    results: Vec<SearchResultItem>,
+    field: FacetField,
+    depth: usize,
+) -> Vec<FacetGroup> {
+    let mut groups: Vec<FacetGroup> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    for item in results {
+        let value = field.value_of(&item, depth);
+        match index.get(&value) {
+            Some(&i) => {
+                let group = &mut groups[i];
+                group.score = group.score.max(item.score);
+                group.results.push(item);
+            }
+            None => {
+                index.insert(value.clone(), groups.len());
+                groups.push(FacetGroup {
+                    value,
+                    score: item.score,
+                    results: vec![item],
+                });
+            }
+        }
+    }
+    groups
+}
+
+/// Number of results with one facet value.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FacetCount {
+    pub value: String,
+    pub count: usize,
+}
+
+/// Result counts per value of every [`FacetField`], most common first.
+///
+/// Counts cover the results they were computed from, not the whole index.
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchFacets {
+    pub path_prefix: Vec<FacetCount>,
+    pub source: Vec<FacetCount>,
+    pub content_type: Vec<FacetCount>,
+}
+
+/// Count `results` per source, content type, and path prefix.
+pub fn facets(results: &[SearchResultItem], depth: usize) -> SearchFacets {
+    let count = |field: FacetField| {
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for item in results {
+            *counts.entry(field.value_of(item, depth)).or_default() += 1;
+        }
+        let mut counts: Vec<FacetCount> = counts
+            .into_iter()
+            .map(|(value, count)| FacetCount { value, count })
+            .collect();
+        counts.sort_by(|a, b| b.count.cmp(&a.count).then(a.value.cmp(&b.value)));
+        counts
+    };
+    SearchFacets {
+        path_prefix: count(FacetField::PathPrefix),
+        source: count(FacetField::Source),
+        content_type: count(FacetField::ContentType),
+    }
+}
+
+/// Scoring breakdown for a search result.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScoreExplanation {
     /// Normalized keyword score (0.0 if absent from keyword candidates).
     pub keyword_score: f64,
     /// Normalized semantic score (0.0 if absent from vector candidates).
     pub semantic_score: f64,
-    /// The alpha weight used: `hybrid = (1-α)*keyword + α*semantic`.
+    /// The alpha weight used: `hybrid = (1-α)*keyword + α*semantic`; 0.0
+    /// in hybrid mode when the best-matching chunk has no embedding.
     pub alpha: f64,
     /// Number of keyword candidates retrieved.
     pub keyword_candidates: usize,
     /// Number of vector candidates retrieved.
     pub vector_candidates: usize,
+    /// Score multiplier applied by curation (`ctx curate boost`); 1.0 if none.
+    #[serde(default = "default_boost")]
+    pub boost: f64,
+    /// Whether curation pinned the result to the top for this query.
+    #[serde(default)]
+    pub pinned: bool,
+    /// Score multiplier from how often the document was retrieved before;
+    /// 1.0 if none.
+    #[serde(default = "default_boost")]
+    pub popularity: f64,
+}
+
+fn default_boost() -> f64 {
+    1.0
+}
+
+/// How much of a search's scope has embeddings, reported with semantic and
+/// hybrid results.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct EmbeddingCoverage {
+    /// Chunks in scope that have an embedding.
+    pub embedded_chunks: i64,
+    /// Chunks in scope.
+    pub total_chunks: i64,
+    /// `embedded_chunks / total_chunks`; 1.0 for an empty scope.
+    pub ratio: f64,
+}
+
+impl EmbeddingCoverage {
+    pub fn new(embedded_chunks: i64, total_chunks: i64) -> Self {
+        let ratio = if total_chunks > 0 {
+            embedded_chunks as f64 / total_chunks as f64
+        } else {
+            1.0
+        };
+        Self {
+            embedded_chunks,
+            total_chunks,
+            ratio,
+        }
+    }
+
+    /// Whether every chunk in scope has an embedding.
+    pub fn is_complete(&self) -> bool {
+        self.embedded_chunks >= self.total_chunks
+    }
 }
 
 /// Run a hybrid search against a [`Store`] backend.
@@ -166,6 +461,17 @@ pub async fn search<S: Store>(store: &S, req: &SearchRequest<'_>) -> Result<Vec<
         all_chunks.entry(c.chunk_id.clone()).or_insert(c);
     }
 
+    if !req.chunk_filters.is_empty() {
+        let mut kept: HashMap<String, &ChunkCandidate> = HashMap::new();
+        for (chunk_id, cand) in all_chunks {
+            let meta = store.get_chunk_metadata(&chunk_id).await?;
+            if meta.is_some_and(|m| chunk_metadata_matches(&m, req.chunk_filters)) {
+                kept.insert(chunk_id, cand);
+            }
+        }
+        all_chunks = kept;
+    }
+
     let effective_alpha = match req.mode {
         "keyword" => 0.0,
         "semantic" => 1.0,
@@ -173,27 +479,47 @@ pub async fn search<S: Store>(store: &S, req: &SearchRequest<'_>) -> Result<Vec<
     };
 
     struct ScoredChunk {
+        chunk_id: String,
         document_id: String,
         hybrid_score: f64,
         keyword_score: f64,
         semantic_score: f64,
+        alpha: f64,
         snippet: String,
     }
 
     let kw_count = keyword_candidates.len();
     let vec_count = vector_candidates.len();
 
+    let unembedded = if req.mode == "hybrid" {
+        let keyword_only: Vec<String> = all_chunks
+            .keys()
+            .filter(|id| !vec_map.contains_key(id.as_str()))
+            .cloned()
+            .collect();
+        store.unembedded_chunks(&keyword_only).await?
+    } else {
+        HashSet::new()
+    };
+
     let mut scored_chunks: Vec<ScoredChunk> = all_chunks
         .iter()
         .map(|(chunk_id, cand)| {
             let k = kw_map.get(chunk_id.as_str()).copied().unwrap_or(0.0);
             let v = vec_map.get(chunk_id.as_str()).copied().unwrap_or(0.0);
-            let hybrid = (1.0 - effective_alpha) * k + effective_alpha * v;
+            let alpha = if unembedded.contains(chunk_id) {
+                0.0
+            } else {
+                effective_alpha
+            };
+            let hybrid = (1.0 - alpha) * k + alpha * v;
             ScoredChunk {
+                chunk_id: chunk_id.clone(),
                 document_id: cand.document_id.clone(),
                 hybrid_score: hybrid,
                 keyword_score: k,
                 semantic_score: v,
+                alpha,
                 snippet: cand.snippet.clone(),
             }
         })
@@ -204,7 +530,9 @@ pub async fn search<S: Store>(store: &S, req: &SearchRequest<'_>) -> Result<Vec<
         doc_score: f64,
         keyword_score: f64,
         semantic_score: f64,
+        alpha: f64,
         best_snippet: String,
+        best_chunk_id: String,
     }
 
     let mut doc_map: HashMap<String, DocResult> = HashMap::new();
@@ -223,16 +551,21 @@ pub async fn search<S: Store>(store: &S, req: &SearchRequest<'_>) -> Result<Vec<
                 doc_score: sc.hybrid_score,
                 keyword_score: sc.keyword_score,
                 semantic_score: sc.semantic_score,
+                alpha: sc.alpha,
                 best_snippet: sc.snippet.clone(),
+                best_chunk_id: sc.chunk_id.clone(),
             });
         if sc.hybrid_score > entry.doc_score {
             entry.doc_score = sc.hybrid_score;
             entry.keyword_score = sc.keyword_score;
             entry.semantic_score = sc.semantic_score;
+            entry.alpha = sc.alpha;
             entry.best_snippet = sc.snippet.clone();
+            entry.best_chunk_id = sc.chunk_id.clone();
         }
     }
 
+    let as_of_ts = req.as_of.map(end_of_day_ts).transpose()?;
     let mut results: Vec<SearchResultItem> = Vec::new();
 
     for doc_result in doc_map.values() {
@@ -240,10 +573,25 @@ pub async fn search<S: Store>(store: &S, req: &SearchRequest<'_>) -> Result<Vec<
             store.get_document_metadata(&doc_result.doc_id).await?;
 
         if let Some(meta) = meta {
-            if let Some(src) = req.source_filter {
-                if meta.source != src {
-                    continue;
-                }
+            if req
+                .source_filter
+                .is_some_and(|filter| !source_filter_matches(filter, &meta.source))
+            {
+                continue;
+            }
+            if !type_matches(
+                req.types,
+                &meta.source_id,
+                &meta.content_type,
+                meta.doc_type.as_deref(),
+            ) {
+                continue;
+            }
+            if req
+                .allowed_sources
+                .is_some_and(|patterns| !source_matches(patterns, &meta.source))
+            {
+                continue;
             }
 
             if let Some(since_str) = req.since {
@@ -258,30 +606,57 @@ pub async fn search<S: Store>(store: &S, req: &SearchRequest<'_>) -> Result<Vec<
                 }
             }
 
+            if as_of_ts.is_some_and(|as_of| meta.updated_at > as_of) {
+                continue;
+            }
+
             let updated_at_iso = format_ts_iso(meta.updated_at);
 
             let explanation = if req.explain {
                 Some(ScoreExplanation {
                     keyword_score: doc_result.keyword_score,
                     semantic_score: doc_result.semantic_score,
-                    alpha: effective_alpha,
+                    alpha: doc_result.alpha,
                     keyword_candidates: kw_count,
                     vector_candidates: vec_count,
+                    boost: 1.0,
+                    pinned: false,
+                    popularity: 1.0,
                 })
             } else {
                 None
             };
 
+            let chunk_meta = store
+                .get_chunk_metadata(&doc_result.best_chunk_id)
+                .await?
+                .unwrap_or_default();
+            let chunk_field = |key: &str| {
+                chunk_meta
+                    .get(key)
+                    .and_then(|v| v.as_str())
+                    .map(str::to_string)
+            };
+
             results.push(SearchResultItem {
                 id: meta.id,
                 score: doc_result.doc_score,
                 title: meta.title,
                 source: meta.source,
                 source_id: meta.source_id,
+                content_type: Some(meta.content_type),
                 updated_at: updated_at_iso,
                 snippet: doc_result.best_snippet.clone(),
                 source_url: meta.source_url,
+                heading: chunk_field(HEADING_KEY),
+                section_source_id: chunk_field(SECTION_SOURCE_ID_KEY),
+                section_source_url: chunk_field(SECTION_SOURCE_URL_KEY),
+                chunk_id: Some(doc_result.best_chunk_id.clone()),
+                parent_id: meta.parent_id,
                 explain: explanation,
+                origin: None,
+                summary: meta.summary,
+                doc_type: meta.doc_type,
             });
         }
     }
@@ -296,9 +671,182 @@ pub async fn search<S: Store>(store: &S, req: &SearchRequest<'_>) -> Result<Vec<
 
     results.truncate(req.params.final_limit as usize);
 
+    if req.params.auto_merge_min_chunks > 1 {
+        let mut hits_by_doc: HashMap<&str, Vec<&str>> = HashMap::new();
+        for sc in &scored_chunks {
+            hits_by_doc
+                .entry(sc.document_id.as_str())
+                .or_default()
+                .push(sc.chunk_id.as_str());
+        }
+        auto_merge(store, &hits_by_doc, req, &mut results).await?;
+    }
+
     Ok(results)
 }
 
+/// Chunks of a document farther apart than this many unmatched chunks are
+/// not merged into one span.
+pub const AUTO_MERGE_MAX_GAP: i64 = 1;
+
+/// Replace the snippet of each result whose document has enough nearby
+/// matching chunks with the text of their span (see [`merge_span`]).
+async fn auto_merge<S: Store>(
+    store: &S,
+    hits_by_doc: &HashMap<&str, Vec<&str>>,
+    req: &SearchRequest<'_>,
+    results: &mut [SearchResultItem],
+) -> Result<()> {
+    let min_chunks = req.params.auto_merge_min_chunks;
+    let hit_ids: Vec<String> = results
+        .iter()
+        .filter_map(|r| hits_by_doc.get(r.id.as_str()))
+        .filter(|hits| hits.len() >= min_chunks)
+        .flatten()
+        .map(|id| id.to_string())
+        .collect();
+    if hit_ids.is_empty() {
+        return Ok(());
+    }
+    let indexes = store.chunk_indexes(&hit_ids).await?;
+
+    for result in results.iter_mut() {
+        let (Some(hits), Some(best)) = (
+            hits_by_doc.get(result.id.as_str()),
+            result.chunk_id.as_ref().and_then(|id| indexes.get(id)),
+        ) else {
+            continue;
+        };
+        let hit_indexes: Vec<i64> = hits
+            .iter()
+            .filter_map(|id| indexes.get(*id).copied())
+            .collect();
+        let Some((first, last)) = merge_span(&hit_indexes, *best, min_chunks, AUTO_MERGE_MAX_GAP)
+        else {
+            continue;
+        };
+        let Some(doc) = store.get_document(&result.id).await? else {
+            continue;
+        };
+        let text: Vec<&str> = doc
+            .chunks
+            .iter()
+            .filter(|c| (first..=last).contains(&c.index))
+            .map(|c| c.text.as_str())
+            .collect();
+        if !text.is_empty() {
+            result.snippet = text.join("\n\n");
+        }
+    }
+    Ok(())
+}
+
+/// The chunk-index range `(first, last)` to merge around the best chunk
+/// `best`, given the indexes of a document's matching chunks.
+///
+/// Starting from `best`, the span grows over matching chunks at most
+/// `max_gap` unmatched chunks away from it; it is returned only if it
+/// takes in at least `min_chunks` matching chunks.
+pub fn merge_span(hits: &[i64], best: i64, min_chunks: usize, max_gap: i64) -> Option<(i64, i64)> {
+    let mut sorted: Vec<i64> = hits.to_vec();
+    sorted.sort_unstable();
+    sorted.dedup();
+    let pos = sorted.iter().position(|&i| i == best)?;
+
+    let mut start = pos;
+    while start > 0 && sorted[start] - sorted[start - 1] <= max_gap + 1 {
+        start -= 1;
+    }
+    let mut end = pos;
+    while end + 1 < sorted.len() && sorted[end + 1] - sorted[end] <= max_gap + 1 {
+        end += 1;
+    }
+    (end - start + 1 >= min_chunks.max(2)).then_some((sorted[start], sorted[end]))
+}
+
+/// Returns `true` if chunk metadata satisfies every `(key, value)` filter.
+///
+/// String values match case-insensitively as substrings, so `heading=install`
+/// matches `"Guide > Install > Linux"` and `path=docs/` matches any path under
+/// `docs/`. Numbers and booleans match their string form exactly; arrays match
+/// if any element matches. A missing key never matches.
+pub fn chunk_metadata_matches(
+    metadata: &serde_json::Map<String, serde_json::Value>,
+    filters: &[(String, String)],
+) -> bool {
+    fn value_matches(value: &serde_json::Value, expected: &str) -> bool {
+        match value {
+            serde_json::Value::String(s) => s.to_lowercase().contains(&expected.to_lowercase()),
+            serde_json::Value::Array(items) => items.iter().any(|v| value_matches(v, expected)),
+            serde_json::Value::Null => false,
+            other => serde_json::to_string(other).is_ok_and(|s| s == expected),
+        }
+    }
+
+    filters.iter().all(|(key, expected)| {
+        metadata
+            .get(key)
+            .is_some_and(|v| value_matches(v, expected))
+    })
+}
+
+/// Returns `true` if `source` matches any pattern: exactly, or by prefix
+/// when the pattern ends in `*` (`git:*` matches every Git instance, `*`
+/// matches everything).
+pub fn source_matches(patterns: &[String], source: &str) -> bool {
+    patterns
+        .iter()
+        .any(|pattern| match pattern.strip_suffix('*') {
+            Some(prefix) => source.starts_with(prefix),
+            None => pattern == source,
+        })
+}
+
+/// Returns `true` if `source` passes a source filter: the same source, or
+/// any instance of it when the filter is a bare connector type (`git`
+/// matches `git:platform`).
+pub fn source_filter_matches(filter: &str, source: &str) -> bool {
+    source == filter
+        || (!filter.contains(':')
+            && source
+                .split_once(':')
+                .is_some_and(|(connector_type, _)| connector_type == filter))
+}
+
+/// Returns `true` if a document is one of `types` (or `types` is empty).
+///
+/// A type matches the extension of `source_id` (`md`), the content type
+/// (`text/markdown`), its subtype (`markdown`), or the document type
+/// assigned at ingest (`runbook`), ignoring case.
+pub fn type_matches(
+    types: &[String],
+    source_id: &str,
+    content_type: &str,
+    doc_type: Option<&str>,
+) -> bool {
+    if types.is_empty() {
+        return true;
+    }
+    let extension = source_id
+        .rsplit_once('.')
+        .map(|(_, ext)| ext)
+        .filter(|ext| !ext.contains('/'));
+    let subtype = content_type.split_once('/').map(|(_, sub)| sub);
+    types.iter().any(|ty| {
+        extension.is_some_and(|ext| ext.eq_ignore_ascii_case(ty))
+            || content_type.eq_ignore_ascii_case(ty)
+            || subtype.is_some_and(|sub| sub.eq_ignore_ascii_case(ty))
+            || doc_type.is_some_and(|dt| dt.eq_ignore_ascii_case(ty))
+    })
+}
+
+/// Last second (UTC) of a `YYYY-MM-DD` date, as a Unix timestamp.
+pub fn end_of_day_ts(date: &str) -> Result<i64> {
+    let date = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .with_context(|| format!("invalid as-of date '{}': expected YYYY-MM-DD", date))?;
+    Ok(date.and_hms_opt(23, 59, 59).unwrap().and_utc().timestamp())
+}
+
 /// Format a Unix timestamp as ISO 8601.
 pub fn format_ts_iso(ts: i64) -> String {
     chrono::DateTime::from_timestamp(ts, 0)
@@ -349,6 +897,178 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_end_of_day_ts() {
+        assert_eq!(end_of_day_ts("2024-06-01").unwrap(), 1_717_286_399);
+        assert!(end_of_day_ts("2024-13-01").is_err());
+        assert!(end_of_day_ts("June 1").is_err());
+    }
+
+    #[test]
+    fn test_chunk_metadata_matches() {
+        let meta = serde_json::json!({
+            "heading": "Guide > Install > Linux",
+            "tags": ["ops", "db"],
+            "draft": false
+        });
+        let meta = meta.as_object().unwrap();
+        let f = |k: &str, v: &str| vec![(k.to_string(), v.to_string())];
+
+        assert!(chunk_metadata_matches(meta, &[]));
+        assert!(chunk_metadata_matches(meta, &f("heading", "install")));
+        assert!(chunk_metadata_matches(meta, &f("tags", "db")));
+        assert!(chunk_metadata_matches(meta, &f("draft", "false")));
+        assert!(!chunk_metadata_matches(meta, &f("heading", "usage")));
+        assert!(!chunk_metadata_matches(meta, &f("missing", "x")));
+    }
+
+    #[test]
+    fn test_source_matches() {
+        let patterns = vec!["filesystem:hr".to_string(), "git:*".to_string()];
+        assert!(source_matches(&patterns, "filesystem:hr"));
+        assert!(source_matches(&patterns, "git:platform"));
+        assert!(!source_matches(&patterns, "filesystem:hr-archive"));
+        assert!(!source_matches(&patterns, "s3:runbooks"));
+        assert!(source_matches(&["*".to_string()], "s3:runbooks"));
+        assert!(!source_matches(&[], "s3:runbooks"));
+    }
+
+    #[test]
+    fn test_source_filter_and_type_matches() {
+        assert!(source_filter_matches("git:platform", "git:platform"));
+        assert!(source_filter_matches("git", "git:platform"));
+        assert!(!source_filter_matches("git", "github:platform"));
+        assert!(!source_filter_matches("git:platform", "git:docs"));
+
+        let types = |t: &[&str]| t.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert!(type_matches(&[], "notes.txt", "text/plain", None));
+        assert!(type_matches(
+            &types(&["md"]),
+            "docs/a.MD",
+            "text/markdown",
+            None
+        ));
+        assert!(type_matches(
+            &types(&["markdown"]),
+            "page-1",
+            "text/markdown",
+            None
+        ));
+        assert!(type_matches(
+            &types(&["pdf", "text/plain"]),
+            "a",
+            "text/plain",
+            None
+        ));
+        assert!(!type_matches(
+            &types(&["md"]),
+            "v1.2/readme",
+            "text/plain",
+            None
+        ));
+        assert!(type_matches(
+            &types(&["Runbook"]),
+            "ops/db.md",
+            "text/markdown",
+            Some("runbook")
+        ));
+        assert!(!type_matches(
+            &types(&["runbook"]),
+            "ops/db.md",
+            "text/markdown",
+            Some("adr")
+        ));
+    }
+
+    fn make_result(id: &str, parent_id: Option<&str>, score: f64) -> SearchResultItem {
+        SearchResultItem {
+            id: id.to_string(),
+            score,
+            title: None,
+            source: "test".to_string(),
+            source_id: id.to_string(),
+            content_type: None,
+            updated_at: String::new(),
+            snippet: String::new(),
+            source_url: None,
+            heading: None,
+            section_source_id: None,
+            section_source_url: None,
+            chunk_id: None,
+            parent_id: parent_id.map(str::to_string),
+            explain: None,
+            origin: None,
+            summary: None,
+            doc_type: None,
+        }
+    }
+
+    #[test]
+    fn test_group_by_parent() {
+        let groups = group_by_parent(vec![
+            make_result("attachment", Some("page"), 0.9),
+            make_result("other", None, 0.8),
+            make_result("page", None, 0.5),
+            make_result("comment", Some("page"), 0.4),
+        ]);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].parent_id, "page");
+        assert!((groups[0].score - 0.9).abs() < 1e-9);
+        let ids: Vec<&str> = groups[0].results.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, ["attachment", "page", "comment"]);
+        assert_eq!(groups[1].parent_id, "other");
+    }
+
+    #[test]
+    fn test_path_prefix() {
+        assert_eq!(
+            path_prefix("docs/runbooks/deploy/rollback.md", 2),
+            "docs/runbooks/"
+        );
+        assert_eq!(path_prefix("docs/runbooks/deploy.md", 1), "docs/");
+        assert_eq!(path_prefix("docs/intro.md", 2), "docs/");
+        assert_eq!(path_prefix("README.md", 2), "");
+        assert_eq!(path_prefix("OPS-4127", 2), "");
+    }
+
+    #[test]
+    fn test_group_by_facet_and_facets() {
+        let results = vec![
+            make_result("docs/runbooks/deploy.md", None, 0.9),
+            make_result("src/main.rs", None, 0.8),
+            make_result("docs/runbooks/rollback.md", None, 0.5),
+        ];
+        let groups = group_by_facet(results.clone(), FacetField::PathPrefix, 2);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].value, "docs/runbooks/");
+        assert_eq!(groups[0].results.len(), 2);
+        assert_eq!(groups[1].value, "src/");
+
+        let facets = facets(&results, 2);
+        assert_eq!(
+            facets.path_prefix[0],
+            FacetCount {
+                value: "docs/runbooks/".to_string(),
+                count: 2
+            }
+        );
+        assert_eq!(facets.source.len(), 1);
+        assert_eq!(facets.content_type[0].value, "unknown");
+        assert!(FacetField::parse("directory").is_err());
+    }
+
+    #[test]
+    fn test_merge_span() {
+        // Hits at 2, 3, and 5 (one gap), and a stray one at 9.
+        let hits = [9, 3, 5, 2];
+        assert_eq!(merge_span(&hits, 3, 2, 1), Some((2, 5)));
+        assert_eq!(merge_span(&hits, 3, 3, 1), Some((2, 5)));
+        assert_eq!(merge_span(&hits, 3, 4, 1), None);
+        assert_eq!(merge_span(&hits, 3, 2, 0), Some((2, 3)));
+        assert_eq!(merge_span(&hits, 9, 2, 1), None);
+        assert_eq!(merge_span(&hits, 7, 2, 1), None);
+    }
+
     #[test]
     fn test_normalize_empty() {
         let result = normalize_scores(&[]);
@@ -4,7 +4,7 @@
 //! Vector search is brute-force cosine similarity over all stored vectors.
 //! Keyword search returns an empty result set (no FTS index).
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::RwLock;
 
 use anyhow::Result;
@@ -12,7 +12,9 @@ use async_trait::async_trait;
 
 use crate::models::{Chunk, Document};
 
-use super::{ChunkCandidate, ChunkResponse, DocumentMetadata, DocumentResponse, Store};
+use super::{
+    ChunkCandidate, ChunkResponse, DocumentChild, DocumentMetadata, DocumentResponse, Store,
+};
 
 struct StoredDoc {
     doc: Document,
@@ -56,6 +58,14 @@ impl Default for InMemoryStore {
     }
 }
 
+/// Resolve a document's `parent_source_id` to the parent's UUID.
+fn parent_id(docs: &HashMap<String, StoredDoc>, doc: &Document) -> Option<String> {
+    let parent = doc.parent_source_id.as_deref()?;
+    docs.values()
+        .find(|s| s.doc.source == doc.source && s.doc.source_id == parent)
+        .map(|s| s.doc.id.clone())
+}
+
 fn format_ts_iso(ts: i64) -> String {
     chrono::DateTime::from_timestamp(ts, 0)
         .map(|dt| dt.format("%Y-%m-%dT%H:%M:%SZ").to_string())
@@ -159,10 +169,25 @@ impl Store for InMemoryStore {
             .map(|sc| ChunkResponse {
                 index: sc.chunk.chunk_index,
                 text: sc.chunk.text.clone(),
+                metadata: sc.chunk.metadata.clone(),
             })
             .collect();
         chunk_responses.sort_by_key(|c| c.index);
 
+        let mut children: Vec<DocumentChild> = docs
+            .values()
+            .filter(|s| {
+                s.doc.source == stored.doc.source
+                    && s.doc.parent_source_id.as_deref() == Some(stored.doc.source_id.as_str())
+            })
+            .map(|s| DocumentChild {
+                id: s.doc.id.clone(),
+                source_id: s.doc.source_id.clone(),
+                title: s.doc.title.clone(),
+            })
+            .collect();
+        children.sort_by(|a, b| a.source_id.cmp(&b.source_id));
+
         Ok(Some(DocumentResponse {
             id: stored.doc.id.clone(),
             source: stored.doc.source.clone(),
@@ -174,8 +199,11 @@ impl Store for InMemoryStore {
             updated_at: format_ts_iso(stored.doc.updated_at),
             content_type: stored.doc.content_type.clone(),
             body: stored.doc.body.clone(),
+            summary: stored.doc.summary.clone(),
             metadata: stored.metadata_json_parsed.clone(),
             chunks: chunk_responses,
+            parent_id: parent_id(&docs, &stored.doc),
+            children,
         }))
     }
 
@@ -187,10 +215,29 @@ impl Store for InMemoryStore {
             source: s.doc.source.clone(),
             source_id: s.doc.source_id.clone(),
             source_url: s.doc.source_url.clone(),
+            content_type: s.doc.content_type.clone(),
             updated_at: s.doc.updated_at,
+            parent_id: parent_id(&docs, &s.doc),
+            summary: s.doc.summary.clone(),
+            doc_type: s
+                .metadata_json_parsed
+                .get("doc_type")
+                .and_then(|v| v.as_str())
+                .map(str::to_string),
         }))
     }
 
+    async fn get_chunk_metadata(
+        &self,
+        chunk_id: &str,
+    ) -> Result<Option<serde_json::Map<String, serde_json::Value>>> {
+        let chunks_guard = self.chunks.read().unwrap();
+        Ok(chunks_guard
+            .iter()
+            .find(|sc| sc.chunk.id == chunk_id)
+            .map(|sc| sc.chunk.metadata.clone()))
+    }
+
     async fn keyword_search(
         &self,
         query: &str,
@@ -199,7 +246,19 @@ impl Store for InMemoryStore {
         _since: Option<&str>,
     ) -> Result<Vec<ChunkCandidate>> {
         let query_lower = query.to_lowercase();
-        let terms: Vec<&str> = query_lower.split_whitespace().collect();
+        // Quoted phrases match as a whole; other words match individually.
+        let terms: Vec<&str> = query_lower
+            .split('"')
+            .enumerate()
+            .flat_map(|(i, part)| {
+                if i % 2 == 1 {
+                    vec![part.trim()]
+                } else {
+                    part.split_whitespace().collect()
+                }
+            })
+            .filter(|t| !t.is_empty())
+            .collect();
         if terms.is_empty() {
             return Ok(Vec::new());
         }
@@ -265,4 +324,22 @@ impl Store for InMemoryStore {
         candidates.truncate(limit as usize);
         Ok(candidates)
     }
+
+    async fn unembedded_chunks(&self, chunk_ids: &[String]) -> Result<HashSet<String>> {
+        let vecs = self.vectors.read().unwrap();
+        Ok(chunk_ids
+            .iter()
+            .filter(|id| !vecs.iter().any(|sv| &sv.chunk_id == *id))
+            .cloned()
+            .collect())
+    }
+
+    async fn chunk_indexes(&self, chunk_ids: &[String]) -> Result<HashMap<String, i64>> {
+        let chunks_guard = self.chunks.read().unwrap();
+        Ok(chunks_guard
+            .iter()
+            .filter(|sc| chunk_ids.contains(&sc.chunk.id))
+            .map(|sc| (sc.chunk.id.clone(), sc.chunk.chunk_index))
+            .collect())
+    }
 }
@@ -11,6 +11,7 @@ pub mod memory;
 use anyhow::Result;
 use async_trait::async_trait;
 use serde::Serialize;
+use std::collections::{HashMap, HashSet};
 
 use crate::models::{Chunk, Document};
 
@@ -45,8 +46,26 @@ pub struct DocumentResponse {
     pub updated_at: String,
     pub content_type: String,
     pub body: String,
+    /// Ingest-time summary of a long body, if one was generated.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
     pub metadata: serde_json::Value,
     pub chunks: Vec<ChunkResponse>,
+    /// UUID of the parent document, when the connector reported one that
+    /// has been ingested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<String>,
+    /// Direct children (attachments, sub-pages) of this document.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<DocumentChild>,
+}
+
+/// A child document listed in a [`DocumentResponse`].
+#[derive(Debug, Clone, Serialize)]
+pub struct DocumentChild {
+    pub id: String,
+    pub source_id: String,
+    pub title: Option<String>,
 }
 
 /// A single chunk within a [`DocumentResponse`].
@@ -54,6 +73,9 @@ pub struct DocumentResponse {
 pub struct ChunkResponse {
     pub index: i64,
     pub text: String,
+    /// Chunk-level metadata (inherited document fields, heading breadcrumb).
+    #[serde(skip_serializing_if = "serde_json::Map::is_empty")]
+    pub metadata: serde_json::Map<String, serde_json::Value>,
 }
 
 /// Lightweight document metadata for search result enrichment.
@@ -67,7 +89,14 @@ pub struct DocumentMetadata {
     pub source: String,
     pub source_id: String,
     pub source_url: Option<String>,
+    pub content_type: String,
     pub updated_at: i64,
+    /// UUID of the parent document, if any.
+    pub parent_id: Option<String>,
+    /// Ingest-time summary of a long body, if any.
+    pub summary: Option<String>,
+    /// `doc_type` from the document's metadata, if any.
+    pub doc_type: Option<String>,
 }
 
 /// Abstract storage backend for Context Harness.
@@ -85,8 +114,11 @@ pub struct DocumentMetadata {
 /// | [`upsert_embedding`](Store::upsert_embedding) | Store an embedding vector for a chunk |
 /// | [`get_document`](Store::get_document) | Retrieve full document with chunks |
 /// | [`get_document_metadata`](Store::get_document_metadata) | Retrieve lightweight doc metadata |
+/// | [`get_chunk_metadata`](Store::get_chunk_metadata) | Retrieve chunk-level metadata |
 /// | [`keyword_search`](Store::keyword_search) | Full-text keyword search |
 /// | [`vector_search`](Store::vector_search) | Cosine similarity vector search |
+/// | [`unembedded_chunks`](Store::unembedded_chunks) | Find chunks without an embedding |
+/// | [`chunk_indexes`](Store::chunk_indexes) | Look up chunks' positions in their documents |
 #[async_trait]
 pub trait Store: Send + Sync {
     /// Insert or update a document.
@@ -119,6 +151,12 @@ pub trait Store: Send + Sync {
     /// Retrieve lightweight metadata for a document, by ID.
     async fn get_document_metadata(&self, id: &str) -> Result<Option<DocumentMetadata>>;
 
+    /// Retrieve the chunk-level metadata object for a chunk, by ID.
+    async fn get_chunk_metadata(
+        &self,
+        chunk_id: &str,
+    ) -> Result<Option<serde_json::Map<String, serde_json::Value>>>;
+
     /// Perform keyword (full-text) search, returning candidate chunks.
     async fn keyword_search(
         &self,
@@ -136,4 +174,16 @@ pub trait Store: Send + Sync {
         source: Option<&str>,
         since: Option<&str>,
     ) -> Result<Vec<ChunkCandidate>>;
+
+    /// Of `chunk_ids`, the ones without a stored embedding vector.
+    ///
+    /// Hybrid search scores these chunks on keyword relevance alone rather
+    /// than treating their missing semantic score as zero.
+    async fn unembedded_chunks(&self, chunk_ids: &[String]) -> Result<HashSet<String>>;
+
+    /// The `chunk_index` of each of `chunk_ids` that exists, by chunk ID.
+    ///
+    /// Auto-merging search uses these to find matching chunks that sit
+    /// close together in their document.
+    async fn chunk_indexes(&self, chunk_ids: &[String]) -> Result<HashMap<String, i64>>;
 }
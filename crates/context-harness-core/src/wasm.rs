@@ -0,0 +1,239 @@
+//! JavaScript bindings for client-side search (`--features wasm`).
+//!
+//! The `context-harness-wasm` crate links them into a `cdylib`; build the
+//! package with:
+//!
+//! ```text
+//! wasm-pack build crates/context-harness-wasm --target web
+//! ```
+//!
+//! The package exposes the chunker, score normalization, and [`CtxIndex`],
+//! an [`InMemoryStore`] loaded from a `ctx export` bundle that runs the same
+//! [`search`] as the CLI, so a static site gets real keyword, semantic, and
+//! hybrid scoring without a server:
+//!
+//! ```js
+//! import init, { CtxIndex } from './pkg/context_harness_wasm.js';
+//! await init();
+//! const index = CtxIndex.fromExport(await (await fetch('/data.json')).text());
+//! const hits = index.search('rollback a deploy', { limit: 5 });
+//! // With `ctx export --vectors`, pass a query vector from `index.embeddingModel`:
+//! const hybrid = index.search('rollback a deploy', { mode: 'hybrid', queryVector });
+//! ```
+//!
+//! Every [`InMemoryStore`] operation completes without yielding, so the
+//! exported methods are synchronous.
+
+use std::future::Future;
+use std::pin::pin;
+use std::task::{Context, Poll, Waker};
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::bundle::ExportData;
+use crate::chunk::{chunk_text_with, ChunkStrategy};
+use crate::query::parse_query;
+use crate::search::{search, SearchParams, SearchRequest};
+use crate::store::memory::InMemoryStore;
+use crate::store::ChunkCandidate;
+
+/// Candidates fetched per retrieval mode, matching `[retrieval]` defaults.
+const CANDIDATE_K: i64 = 80;
+
+/// Run a future that never yields, as every [`InMemoryStore`] call does.
+fn ready<F: Future>(fut: F) -> F::Output {
+    let mut fut = pin!(fut);
+    match fut.as_mut().poll(&mut Context::from_waker(Waker::noop())) {
+        Poll::Ready(out) => out,
+        Poll::Pending => unreachable!("in-memory store futures are always ready"),
+    }
+}
+
+fn js_error(err: anyhow::Error) -> JsError {
+    JsError::new(&format!("{:#}", err))
+}
+
+fn to_js<T: Serialize>(value: &T) -> Result<JsValue, JsError> {
+    serde_wasm_bindgen::to_value(value).map_err(|e| JsError::new(&e.to_string()))
+}
+
+/// A chunk as returned by [`chunk_text_js`].
+#[derive(Serialize)]
+struct JsChunk {
+    id: String,
+    document_id: String,
+    chunk_index: i64,
+    text: String,
+    hash: String,
+}
+
+/// `chunkText(documentId, text, maxTokens, strategy?)` — split text the way
+/// `ctx sync` does. `strategy` is `"paragraph"` (default) or `"line"`.
+#[wasm_bindgen(js_name = chunkText)]
+pub fn chunk_text_js(
+    document_id: &str,
+    text: &str,
+    max_tokens: usize,
+    strategy: Option<String>,
+) -> Result<JsValue, JsError> {
+    let strategy = match strategy.as_deref() {
+        None => ChunkStrategy::Paragraph,
+        Some(name) => ChunkStrategy::parse(name)
+            .ok_or_else(|| JsError::new(&format!("unknown chunk strategy '{}'", name)))?,
+    };
+    let chunks: Vec<JsChunk> = chunk_text_with(document_id, text, strategy, max_tokens)
+        .into_iter()
+        .map(|c| JsChunk {
+            id: c.id,
+            document_id: c.document_id,
+            chunk_index: c.chunk_index,
+            text: c.text,
+            hash: c.hash,
+        })
+        .collect();
+    to_js(&chunks)
+}
+
+/// `normalizeScores(scores)` — min-max normalize raw scores to `[0, 1]`,
+/// as search does before merging keyword and semantic candidates.
+#[wasm_bindgen(js_name = normalizeScores)]
+pub fn normalize_scores_js(scores: Vec<f64>) -> Vec<f64> {
+    let candidates: Vec<ChunkCandidate> = scores
+        .into_iter()
+        .map(|raw_score| ChunkCandidate {
+            chunk_id: String::new(),
+            document_id: String::new(),
+            raw_score,
+            snippet: String::new(),
+        })
+        .collect();
+    crate::search::normalize_scores(&candidates)
+        .into_iter()
+        .map(|(_, score)| score)
+        .collect()
+}
+
+/// `cosineSimilarity(a, b)` — cosine similarity of two vectors.
+#[wasm_bindgen(js_name = cosineSimilarity)]
+pub fn cosine_similarity_js(a: Vec<f32>, b: Vec<f32>) -> f32 {
+    crate::embedding::cosine_similarity(&a, &b)
+}
+
+/// Options accepted by [`CtxIndex::search`]. Field names are camelCase.
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
+struct SearchOptions {
+    mode: Option<String>,
+    limit: Option<i64>,
+    alpha: Option<f64>,
+    query_vector: Option<Vec<f32>>,
+    source: Option<String>,
+    since: Option<String>,
+    as_of: Option<String>,
+    explain: bool,
+}
+
+/// A searchable index loaded from a `ctx export` bundle.
+#[wasm_bindgen]
+pub struct CtxIndex {
+    store: InMemoryStore,
+    documents: usize,
+    chunks: usize,
+    vectors: usize,
+    embedding_model: Option<String>,
+    embedding_dims: Option<usize>,
+}
+
+#[wasm_bindgen]
+impl CtxIndex {
+    /// `CtxIndex.fromExport(json)` — load the text of a `data.json` bundle.
+    #[wasm_bindgen(js_name = fromExport)]
+    pub fn from_export(json: &str) -> Result<CtxIndex, JsError> {
+        let data = ExportData::from_json(json).map_err(js_error)?;
+        let store = InMemoryStore::new();
+        ready(data.load_into(&store)).map_err(js_error)?;
+        Ok(CtxIndex {
+            store,
+            documents: data.documents.len(),
+            chunks: data.chunks.len(),
+            vectors: data.vector_count(),
+            embedding_model: data.embedding_model,
+            embedding_dims: data.embedding_dims,
+        })
+    }
+
+    #[wasm_bindgen(getter, js_name = documentCount)]
+    pub fn document_count(&self) -> usize {
+        self.documents
+    }
+
+    #[wasm_bindgen(getter, js_name = chunkCount)]
+    pub fn chunk_count(&self) -> usize {
+        self.chunks
+    }
+
+    /// Number of chunks with a vector; `0` means keyword search only.
+    #[wasm_bindgen(getter, js_name = vectorCount)]
+    pub fn vector_count(&self) -> usize {
+        self.vectors
+    }
+
+    /// Model the bundle's vectors came from; query vectors must match it.
+    #[wasm_bindgen(getter, js_name = embeddingModel)]
+    pub fn embedding_model(&self) -> Option<String> {
+        self.embedding_model.clone()
+    }
+
+    #[wasm_bindgen(getter, js_name = embeddingDims)]
+    pub fn embedding_dims(&self) -> Option<usize> {
+        self.embedding_dims
+    }
+
+    /// `index.search(query, options?)` — returns `context.search` result
+    /// items. Options: `mode` (`"keyword"` by default, or `"semantic"` /
+    /// `"hybrid"` with `queryVector`), `limit` (12), `alpha` (0.6),
+    /// `source`, `since`, `asOf`, and `explain`. Inline modifiers in `query`
+    /// (`source:git type:md`, see [`crate::query`]) fill the options not set.
+    pub fn search(&self, query: &str, options: JsValue) -> Result<JsValue, JsError> {
+        let options: SearchOptions = if options.is_undefined() || options.is_null() {
+            SearchOptions::default()
+        } else {
+            serde_wasm_bindgen::from_value(options).map_err(|e| JsError::new(&e.to_string()))?
+        };
+        if let (Some(vector), Some(dims)) = (&options.query_vector, self.embedding_dims) {
+            if vector.len() != dims {
+                return Err(JsError::new(&format!(
+                    "query vector has {} dimensions, the index has {}",
+                    vector.len(),
+                    dims
+                )));
+            }
+        }
+
+        let limit = options.limit.unwrap_or(12).max(1);
+        let parsed = parse_query(query);
+        let chunk_filters: Vec<(String, String)> = parsed.ref_filter().into_iter().collect();
+        let req = SearchRequest {
+            query: &parsed.text,
+            query_vec: options.query_vector.as_deref(),
+            mode: options.mode.as_deref().unwrap_or("keyword"),
+            source_filter: options.source.as_deref().or(parsed.source.as_deref()),
+            allowed_sources: None,
+            since: options.since.as_deref().or(parsed.since.as_deref()),
+            as_of: options.as_of.as_deref().or(parsed.as_of.as_deref()),
+            params: SearchParams {
+                hybrid_alpha: options.alpha.unwrap_or(0.6),
+                candidate_k_keyword: CANDIDATE_K.max(limit),
+                candidate_k_vector: CANDIDATE_K.max(limit),
+                final_limit: limit,
+                auto_merge_min_chunks: 0,
+            },
+            explain: options.explain,
+            chunk_filters: &chunk_filters,
+            types: &parsed.types,
+        };
+        let results = ready(search(&self.store, &req)).map_err(js_error)?;
+        to_js(&results)
+    }
+}
@@ -0,0 +1,13 @@
+//! # Context Harness WASM
+//!
+//! Links `context-harness-core` with its `wasm` feature into a `cdylib`, so
+//! the core crate itself stays a plain library. Build the JavaScript
+//! package with:
+//!
+//! ```text
+//! wasm-pack build crates/context-harness-wasm --target web
+//! ```
+//!
+//! See [`context_harness_core::wasm`] for the exported API.
+
+pub use context_harness_core::wasm::*;
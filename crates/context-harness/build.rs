@@ -0,0 +1,8 @@
+//! Generates the gRPC service from `proto/context_harness.proto` when the
+//! `grpc` feature is enabled. Needs `protoc` on `PATH` (or `PROTOC` set).
+
+fn main() {
+    #[cfg(feature = "grpc")]
+    tonic_prost_build::compile_protos("proto/context_harness.proto")
+        .expect("failed to compile proto/context_harness.proto (is protoc installed?)");
+}
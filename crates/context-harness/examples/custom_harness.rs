@@ -152,6 +152,7 @@ impl Connector for JsonConnector {
                 metadata_json: "{}".to_string(),
                 raw_json: None,
                 raw_bytes: None,
+                parent_source_id: None,
             })
             .collect();
 
@@ -320,7 +321,7 @@ When answering:
         Ok(AgentPrompt {
             system,
             tools: self.tools(),
-            messages: vec![],
+            ..Default::default()
         })
     }
 }
@@ -0,0 +1,654 @@
+//! Agent chat loop: `ctx agent run`.
+//!
+//! Resolves an agent's prompt (TOML or Lua), connects to the chat model
+//! configured in `[llm]`, and runs the tool-calling loop locally: the model's
+//! tool calls are executed against the built-in and Lua tools the agent
+//! exposes, and the results are fed back until the model answers in text.
+//!
+//! # Providers
+//!
+//! Both `openai` and `ollama` are called through the OpenAI-compatible
+//! `POST {url}/chat/completions` endpoint with `stream: true`. Text deltas
+//! are printed as they arrive; tool calls are printed as `[tool]` lines.
+//! Non-streaming JSON responses are accepted as well.
+//!
+//! # Usage
+//!
+//! ```bash
+//! # Interactive session (type /exit or Ctrl-D to quit)
+//! ctx agent run code-reviewer
+//!
+//! # One-shot
+//! ctx agent run incident-responder --arg service=auth -m "Why is login slow?"
+//! ```
+//!
+//! Each user message allows at most `[llm] max_turns` model calls.
+
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Value};
+use std::io::Write;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::AsyncBufReadExt;
+
+use crate::agent_script::{args_to_json, load_agent_definitions, LuaAgentAdapter};
+use crate::agents::{AgentRegistry, ModelHints, SuggestedToolCall};
+use crate::config::{Config, LlmConfig};
+use crate::scrub;
+use crate::secrets;
+use crate::tool_script::{load_tool_definitions, validate_params, LuaToolAdapter};
+use crate::traits::{Tool, ToolContext, ToolRegistry};
+
+/// Longest tool result excerpt printed to the terminal.
+const TOOL_PREVIEW_CHARS: usize = 120;
+
+/// CLI entry point for `ctx agent run`.
+///
+/// With `message`, sends that single message and exits after the model's
+/// answer; otherwise reads messages from stdin until EOF or `/exit`.
+pub async fn run_agent(
+    name: &str,
+    args: Vec<(String, String)>,
+    message: Option<String>,
+    config: &Config,
+) -> Result<()> {
+    if !config.llm.is_enabled() {
+        bail!("LLM provider is disabled. Set [llm] provider and model in config.");
+    }
+    let config = Arc::new(config.clone());
+    let mut client = ChatClient::from_config(&config.llm)?;
+
+    let mut agents = AgentRegistry::from_config(&config)?;
+    for def in load_agent_definitions(&config)? {
+        agents.register(Box::new(LuaAgentAdapter::new(def, config.clone())));
+    }
+    let agent = agents
+        .find(name)
+        .ok_or_else(|| anyhow::anyhow!("Agent '{}' not found in config", name))?;
+
+    let mut registry = ToolRegistry::with_builtins();
+    for def in load_tool_definitions(&config)? {
+        registry.register(Box::new(LuaToolAdapter::new(def, config.clone())));
+    }
+    let registry = Arc::new(registry);
+
+    let ctx = ToolContext::new(config.clone()).with_tools(registry.clone());
+    let prompt = agent.resolve(args_to_json(&args), &ctx).await?;
+    let tool_names = if prompt.tools.is_empty() {
+        agent.tools()
+    } else {
+        prompt.tools.clone()
+    };
+    let mut tools: Vec<&dyn Tool> = Vec::new();
+    for tool_name in &tool_names {
+        match registry.find(tool_name) {
+            Some(tool) => tools.push(tool),
+            None => tracing::warn!("agent tool '{}' is not registered", tool_name),
+        }
+    }
+    let tool_specs: Vec<Value> = tools.iter().map(|t| tool_spec(*t)).collect();
+    client.apply_hints(&prompt.model);
+
+    println!(
+        "Agent: {} ({}) · model: {} · tools: {}",
+        agent.name(),
+        agent.source(),
+        client.model,
+        if tools.is_empty() {
+            "(none)".to_string()
+        } else {
+            tools
+                .iter()
+                .map(|t| t.name())
+                .collect::<Vec<_>>()
+                .join(", ")
+        }
+    );
+
+    let mut messages = vec![json!({ "role": "system", "content": prompt.system })];
+    for msg in &prompt.messages {
+        messages.push(json!({ "role": msg.role, "content": msg.content }));
+    }
+    for id in &prompt.resources {
+        match ctx.get(id).await {
+            Ok(doc) => {
+                println!("  [resource] {}", doc.title.as_deref().unwrap_or(&doc.id));
+                messages.push(json!({
+                    "role": "user",
+                    "content": format!(
+                        "Attached document {} ({}):\n\n{}",
+                        doc.id,
+                        doc.title.as_deref().unwrap_or(&doc.source_id),
+                        doc.body
+                    ),
+                }));
+            }
+            Err(e) => tracing::warn!("agent resource '{}' unavailable: {:#}", id, e),
+        }
+    }
+    run_suggested_calls(&prompt.tool_calls, &mut messages, &tools, &ctx).await;
+
+    if let Some(message) = message {
+        println!();
+        println!("> {}", message);
+        return run_turn(&client, &mut messages, &tools, &tool_specs, &ctx, message).await;
+    }
+
+    println!("Type a message, or /exit to quit.");
+    let mut lines = tokio::io::BufReader::new(tokio::io::stdin()).lines();
+    loop {
+        print!("\n> ");
+        std::io::stdout().flush()?;
+        let Some(line) = lines.next_line().await? else {
+            println!();
+            break;
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if line == "/exit" || line == "/quit" {
+            break;
+        }
+        if let Err(e) = run_turn(
+            &client,
+            &mut messages,
+            &tools,
+            &tool_specs,
+            &ctx,
+            line.to_string(),
+        )
+        .await
+        {
+            eprintln!("Error: {}", scrub::scrub(&format!("{:#}", e)));
+        }
+    }
+    Ok(())
+}
+
+/// Send one user message and loop until the model answers without tool calls.
+async fn run_turn(
+    client: &ChatClient,
+    messages: &mut Vec<Value>,
+    tools: &[&dyn Tool],
+    tool_specs: &[Value],
+    ctx: &ToolContext,
+    user_message: String,
+) -> Result<()> {
+    messages.push(json!({ "role": "user", "content": user_message }));
+
+    for _ in 0..client.max_turns {
+        let mut stdout = std::io::stdout();
+        let reply = client
+            .chat(messages, tool_specs, &mut |delta| {
+                print!("{}", delta);
+                let _ = stdout.flush();
+            })
+            .await?;
+        if !reply.content.is_empty() {
+            println!();
+        }
+        messages.push(reply.to_message());
+
+        if reply.tool_calls.is_empty() {
+            return Ok(());
+        }
+        for call in &reply.tool_calls {
+            let output = call_tool(tools, call, ctx).await;
+            messages.push(json!({
+                "role": "tool",
+                "tool_call_id": call.id,
+                "content": output,
+            }));
+        }
+    }
+
+    tracing::warn!(
+        "stopped after {} model calls without a final answer (llm.max_turns)",
+        client.max_turns
+    );
+    Ok(())
+}
+
+/// Run the agent's suggested first tool calls and add them to the history
+/// as if the model had made them, so the first turn starts with the results.
+async fn run_suggested_calls(
+    suggested: &[SuggestedToolCall],
+    messages: &mut Vec<Value>,
+    tools: &[&dyn Tool],
+    ctx: &ToolContext,
+) {
+    if suggested.is_empty() {
+        return;
+    }
+    let calls: Vec<ToolCall> = suggested
+        .iter()
+        .enumerate()
+        .map(|(i, s)| ToolCall {
+            id: format!("suggested_{}", i + 1),
+            name: s.tool.clone(),
+            arguments: s.arguments.to_string(),
+        })
+        .collect();
+    messages.push(
+        AssistantReply {
+            content: String::new(),
+            tool_calls: calls.clone(),
+        }
+        .to_message(),
+    );
+    for call in &calls {
+        let output = call_tool(tools, call, ctx).await;
+        messages.push(json!({
+            "role": "tool",
+            "tool_call_id": call.id,
+            "content": output,
+        }));
+    }
+}
+
+/// Execute one tool call, returning the text sent back to the model.
+///
+/// Failures are reported to the model rather than aborting the session.
+async fn call_tool(tools: &[&dyn Tool], call: &ToolCall, ctx: &ToolContext) -> String {
+    println!("  [tool] {} {}", call.name, call.arguments.trim());
+    let started = Instant::now();
+
+    let result = async {
+        let tool = tools
+            .iter()
+            .find(|t| t.name() == call.name)
+            .ok_or_else(|| anyhow::anyhow!("no tool named '{}' is available", call.name))?;
+        let params: Value = if call.arguments.trim().is_empty() {
+            json!({})
+        } else {
+            serde_json::from_str(&call.arguments).context("tool arguments are not valid JSON")?
+        };
+        let params = validate_params(&tool.parameters_schema(), &params)?;
+        tool.execute(params, ctx).await
+    }
+    .await;
+
+    match result {
+        Ok(value) => {
+            let text = serde_json::to_string(&value).unwrap_or_default();
+            println!(
+                "  [tool] {} ok ({:.0?}) {}",
+                call.name,
+                started.elapsed(),
+                preview(&text)
+            );
+            text
+        }
+        Err(e) => {
+            println!("  [tool] {} failed: {:#}", call.name, e);
+            format!("error: {:#}", e)
+        }
+    }
+}
+
+fn preview(text: &str) -> String {
+    if text.chars().count() <= TOOL_PREVIEW_CHARS {
+        text.to_string()
+    } else {
+        let cut: String = text.chars().take(TOOL_PREVIEW_CHARS).collect();
+        format!("{}…", cut)
+    }
+}
+
+/// OpenAI function-calling declaration for a tool.
+fn tool_spec(tool: &dyn Tool) -> Value {
+    json!({
+        "type": "function",
+        "function": {
+            "name": tool.name(),
+            "description": tool.description(),
+            "parameters": tool.parameters_schema(),
+        }
+    })
+}
+
+// ============ Chat client ============
+
+/// Minimal OpenAI-compatible chat completions client.
+pub(crate) struct ChatClient {
+    http: reqwest::Client,
+    endpoint: String,
+    model: String,
+    api_key: Option<String>,
+    temperature: Option<f32>,
+    top_p: Option<f32>,
+    max_tokens: Option<u32>,
+    max_turns: usize,
+}
+
+impl ChatClient {
+    pub(crate) fn from_config(llm: &LlmConfig) -> Result<Self> {
+        let model = llm
+            .model
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("llm.model required"))?;
+        let (default_url, api_key) = match llm.provider.as_str() {
+            "openai" => {
+                let key = secrets::resolve_or_env(llm.api_key.as_deref(), "OPENAI_API_KEY")?
+                    .ok_or_else(|| {
+                        anyhow::anyhow!("llm.api_key not configured and OPENAI_API_KEY not set")
+                    })?;
+                ("https://api.openai.com/v1", Some(key))
+            }
+            "ollama" => (
+                "http://localhost:11434/v1",
+                llm.api_key.as_deref().map(secrets::resolve).transpose()?,
+            ),
+            other => bail!("Unknown llm provider: '{}'", other),
+        };
+        let base = llm
+            .url
+            .as_deref()
+            .unwrap_or(default_url)
+            .trim_end_matches('/');
+
+        Ok(Self {
+            http: reqwest::Client::builder()
+                .timeout(Duration::from_secs(llm.timeout_secs))
+                .build()?,
+            endpoint: format!("{}/chat/completions", base),
+            model,
+            api_key,
+            temperature: llm.temperature,
+            top_p: None,
+            max_tokens: None,
+            max_turns: llm.max_turns,
+        })
+    }
+
+    /// Run one chat completion without tools and return the reply text.
+    pub(crate) async fn complete(&self, messages: &[Value]) -> Result<String> {
+        Ok(self.chat(messages, &[], &mut |_| {}).await?.content)
+    }
+
+    /// Let the agent's model hints override the `[llm]` settings.
+    fn apply_hints(&mut self, hints: &ModelHints) {
+        if hints.temperature.is_some() {
+            self.temperature = hints.temperature;
+        }
+        if hints.top_p.is_some() {
+            self.top_p = hints.top_p;
+        }
+        if hints.max_tokens.is_some() {
+            self.max_tokens = hints.max_tokens;
+        }
+    }
+
+    /// Run one chat completion, calling `on_text` with each text delta.
+    async fn chat(
+        &self,
+        messages: &[Value],
+        tools: &[Value],
+        on_text: &mut (dyn FnMut(&str) + Send),
+    ) -> Result<AssistantReply> {
+        let mut body = json!({
+            "model": self.model,
+            "messages": messages,
+            "stream": true,
+        });
+        if !tools.is_empty() {
+            body["tools"] = json!(tools);
+        }
+        if let Some(t) = self.temperature {
+            body["temperature"] = json!(t);
+        }
+        if let Some(p) = self.top_p {
+            body["top_p"] = json!(p);
+        }
+        if let Some(n) = self.max_tokens {
+            body["max_tokens"] = json!(n);
+        }
+
+        let mut request = self.http.post(&self.endpoint).json(&body);
+        if let Some(ref key) = self.api_key {
+            request = request.bearer_auth(key);
+        }
+        let mut response = request
+            .send()
+            .await
+            .with_context(|| format!("Failed to reach {}", self.endpoint))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let text = response.text().await.unwrap_or_default();
+            bail!("Chat API error {}: {}", status, text);
+        }
+
+        let streaming = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|ct| ct.contains("text/event-stream"));
+        if !streaming {
+            let json: Value = response.json().await?;
+            let reply = parse_completion(&json)?;
+            on_text(&reply.content);
+            return Ok(reply);
+        }
+
+        let mut acc = StreamAccumulator::default();
+        let mut buf: Vec<u8> = Vec::new();
+        while let Some(bytes) = response.chunk().await? {
+            buf.extend_from_slice(&bytes);
+            while let Some(pos) = buf.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = buf.drain(..=pos).collect();
+                let line = String::from_utf8_lossy(&line);
+                if let Some(delta) = acc.push_line(line.trim())? {
+                    on_text(&delta);
+                }
+            }
+        }
+        if let Some(delta) = acc.push_line(String::from_utf8_lossy(&buf).trim())? {
+            on_text(&delta);
+        }
+        Ok(acc.finish())
+    }
+}
+
+/// A tool call requested by the model.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct ToolCall {
+    id: String,
+    name: String,
+    /// JSON-encoded arguments, as sent by the model.
+    arguments: String,
+}
+
+/// The model's complete reply for one chat call.
+#[derive(Debug, Default)]
+struct AssistantReply {
+    content: String,
+    tool_calls: Vec<ToolCall>,
+}
+
+impl AssistantReply {
+    /// The reply as an assistant message for the conversation history.
+    fn to_message(&self) -> Value {
+        let mut msg = json!({ "role": "assistant", "content": self.content });
+        if !self.tool_calls.is_empty() {
+            msg["tool_calls"] = self
+                .tool_calls
+                .iter()
+                .map(|c| {
+                    json!({
+                        "id": c.id,
+                        "type": "function",
+                        "function": { "name": c.name, "arguments": c.arguments },
+                    })
+                })
+                .collect();
+        }
+        msg
+    }
+}
+
+/// Parse a non-streaming `chat/completions` response.
+fn parse_completion(json: &Value) -> Result<AssistantReply> {
+    let message = json
+        .pointer("/choices/0/message")
+        .ok_or_else(|| anyhow::anyhow!("Invalid chat response: missing choices[0].message"))?;
+    let tool_calls = message
+        .get("tool_calls")
+        .and_then(|c| c.as_array())
+        .map(|calls| {
+            calls
+                .iter()
+                .enumerate()
+                .map(|(i, c)| ToolCall {
+                    id: c["id"]
+                        .as_str()
+                        .map(str::to_string)
+                        .unwrap_or_else(|| format!("call_{}", i)),
+                    name: c["function"]["name"].as_str().unwrap_or("").to_string(),
+                    arguments: match &c["function"]["arguments"] {
+                        Value::String(s) => s.clone(),
+                        Value::Null => String::new(),
+                        other => other.to_string(),
+                    },
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    Ok(AssistantReply {
+        content: message["content"].as_str().unwrap_or("").to_string(),
+        tool_calls,
+    })
+}
+
+/// Assembles a reply from server-sent `chat.completion.chunk` events.
+#[derive(Debug, Default)]
+struct StreamAccumulator {
+    content: String,
+    tool_calls: Vec<ToolCall>,
+}
+
+impl StreamAccumulator {
+    /// Consume one SSE line, returning any new text.
+    fn push_line(&mut self, line: &str) -> Result<Option<String>> {
+        let Some(data) = line.strip_prefix("data:") else {
+            return Ok(None);
+        };
+        let data = data.trim();
+        if data.is_empty() || data == "[DONE]" {
+            return Ok(None);
+        }
+        let event: Value = serde_json::from_str(data)
+            .with_context(|| format!("Invalid chat stream event: {}", data))?;
+        if let Some(err) = event.get("error") {
+            bail!("Chat API error: {}", err);
+        }
+        let Some(delta) = event.pointer("/choices/0/delta") else {
+            return Ok(None);
+        };
+
+        if let Some(calls) = delta.get("tool_calls").and_then(|c| c.as_array()) {
+            for call in calls {
+                let index = match call["index"].as_u64() {
+                    Some(i) => i as usize,
+                    None if call["id"].is_string() => self.tool_calls.len(),
+                    None => self.tool_calls.len().saturating_sub(1),
+                };
+                while self.tool_calls.len() <= index {
+                    let n = self.tool_calls.len();
+                    self.tool_calls.push(ToolCall {
+                        id: format!("call_{}", n),
+                        ..Default::default()
+                    });
+                }
+                let slot = &mut self.tool_calls[index];
+                if let Some(id) = call["id"].as_str() {
+                    slot.id = id.to_string();
+                }
+                if let Some(name) = call["function"]["name"].as_str() {
+                    slot.name.push_str(name);
+                }
+                match &call["function"]["arguments"] {
+                    Value::String(s) => slot.arguments.push_str(s),
+                    Value::Null => {}
+                    other => slot.arguments.push_str(&other.to_string()),
+                }
+            }
+        }
+
+        match delta["content"].as_str() {
+            Some(text) if !text.is_empty() => {
+                self.content.push_str(text);
+                Ok(Some(text.to_string()))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn finish(self) -> AssistantReply {
+        AssistantReply {
+            content: self.content,
+            tool_calls: self.tool_calls,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accumulates_text_and_fragmented_tool_calls() {
+        let mut acc = StreamAccumulator::default();
+        let events = [
+            r#"data: {"choices":[{"delta":{"role":"assistant","content":"Let me "}}]}"#,
+            r#"data: {"choices":[{"delta":{"content":"check."}}]}"#,
+            r#"data: {"choices":[{"delta":{"tool_calls":[{"index":0,"id":"call_a","type":"function","function":{"name":"search","arguments":""}}]}}]}"#,
+            r#"data: {"choices":[{"delta":{"tool_calls":[{"index":0,"function":{"arguments":"{\"query\":"}}]}}]}"#,
+            r#"data: {"choices":[{"delta":{"tool_calls":[{"index":0,"function":{"arguments":"\"deploy\"}"}}]}}]}"#,
+            r#"data: {"choices":[{"delta":{"tool_calls":[{"index":1,"id":"call_b","function":{"name":"sources","arguments":"{}"}}]}}]}"#,
+            "",
+            "data: [DONE]",
+        ];
+        let mut printed = String::new();
+        for e in events {
+            if let Some(t) = acc.push_line(e).unwrap() {
+                printed.push_str(&t);
+            }
+        }
+        let reply = acc.finish();
+        assert_eq!(printed, "Let me check.");
+        assert_eq!(reply.content, "Let me check.");
+        assert_eq!(reply.tool_calls.len(), 2);
+        assert_eq!(reply.tool_calls[0].id, "call_a");
+        assert_eq!(reply.tool_calls[0].name, "search");
+        assert_eq!(reply.tool_calls[0].arguments, r#"{"query":"deploy"}"#);
+        assert_eq!(reply.tool_calls[1].name, "sources");
+
+        let msg = reply.to_message();
+        assert_eq!(msg["tool_calls"][0]["function"]["name"], "search");
+    }
+
+    #[test]
+    fn parses_non_streaming_response_with_object_arguments() {
+        let json = json!({
+            "choices": [{ "message": {
+                "role": "assistant",
+                "content": null,
+                "tool_calls": [{ "function": { "name": "get", "arguments": { "id": "abc" } } }]
+            }}]
+        });
+        let reply = parse_completion(&json).unwrap();
+        assert_eq!(reply.content, "");
+        assert_eq!(reply.tool_calls[0].id, "call_0");
+        assert_eq!(reply.tool_calls[0].arguments, r#"{"id":"abc"}"#);
+    }
+
+    #[test]
+    fn stream_errors_are_reported() {
+        let mut acc = StreamAccumulator::default();
+        assert!(acc
+            .push_line(r#"data: {"error":{"message":"model not found"}}"#)
+            .is_err());
+    }
+}
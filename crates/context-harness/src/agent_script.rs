@@ -13,6 +13,9 @@
 //! - `context.search(query, opts?)` — search the knowledge base
 //! - `context.get(id)` — retrieve a document by UUID
 //! - `context.sources()` — list connector status
+//! - `context.sample(prompt, opts?)` — ask the MCP client's model for a
+//!   completion, when the prompt was requested over MCP (see
+//!   [`ToolContext::sample`])
 //!
 //! The agent-specific config from `ctx.toml` is passed as the second
 //! argument to `agent.resolve(args, config, context)`.
@@ -57,12 +60,18 @@ use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
-use crate::agents::{Agent, AgentArgument, AgentPrompt, PromptMessage};
+use crate::agents::{
+    Agent, AgentArgument, AgentPrompt, AgentRegistry, ModelHints, PromptMessage, SuggestedToolCall,
+};
 use crate::config::{Config, ScriptAgentConfig};
 use crate::get::get_document;
-use crate::lua_runtime::{json_value_to_lua, register_all_host_apis, toml_table_to_lua};
+use crate::lua_runtime::{
+    json_value_to_lua, lua_value_to_json, register_all_host_apis, toml_table_to_lua, EnvAccess,
+};
+use crate::registry::{self, RegistryManager, ScriptTrust};
 use crate::search::search_documents;
 use crate::sources::get_sources;
+use crate::tool_script::sample_function;
 use crate::traits::ToolContext;
 
 // ═══════════════════════════════════════════════════════════════════════
@@ -91,6 +100,8 @@ pub struct AgentDefinition {
     pub config: toml::Table,
     /// Maximum execution time in seconds.
     pub timeout: u64,
+    /// Environment variables the script's `env.get` may read.
+    pub env: EnvAccess,
 }
 
 // ═══════════════════════════════════════════════════════════════════════
@@ -137,8 +148,8 @@ impl Agent for LuaAgentAdapter {
         self.definition.arguments.clone()
     }
 
-    async fn resolve(&self, args: serde_json::Value, _ctx: &ToolContext) -> Result<AgentPrompt> {
-        resolve_agent(&self.definition, args, &self.config).await
+    async fn resolve(&self, args: serde_json::Value, ctx: &ToolContext) -> Result<AgentPrompt> {
+        resolve_agent(&self.definition, args, &self.config, ctx).await
     }
 }
 
@@ -156,8 +167,10 @@ impl Agent for LuaAgentAdapter {
 pub fn load_agent_definitions(config: &Config) -> Result<Vec<AgentDefinition>> {
     let mut agents = Vec::new();
 
+    let registries = RegistryManager::from_config(config);
     for (name, agent_config) in &config.agents.script {
-        let agent_def = load_single_agent(name, agent_config)
+        let trust = registries.script_trust(&agent_config.path);
+        let agent_def = load_single_agent(name, agent_config, &trust)
             .with_context(|| format!("Failed to load agent script '{}'", name))?;
         agents.push(agent_def);
     }
@@ -166,13 +179,21 @@ pub fn load_agent_definitions(config: &Config) -> Result<Vec<AgentDefinition>> {
 }
 
 /// Load a single agent script and extract its definition.
-pub fn load_single_agent(name: &str, agent_config: &ScriptAgentConfig) -> Result<AgentDefinition> {
+///
+/// The script must pass `trust`, the checks of the registry it comes from
+/// (see [`crate::registry`]), before it runs.
+pub fn load_single_agent(
+    name: &str,
+    agent_config: &ScriptAgentConfig,
+    trust: &ScriptTrust,
+) -> Result<AgentDefinition> {
     let script_src = std::fs::read_to_string(&agent_config.path).with_context(|| {
         format!(
             "Failed to read agent script: {}",
             agent_config.path.display()
         )
     })?;
+    trust.verify(script_src.as_bytes())?;
 
     // Create a temporary Lua VM just to extract metadata
     let lua = Lua::new();
@@ -211,6 +232,7 @@ pub fn load_single_agent(name: &str, agent_config: &ScriptAgentConfig) -> Result
         script_source: script_src,
         config: agent_config.extra.clone(),
         timeout: agent_config.timeout,
+        env: EnvAccess::resolve(agent_config.env_allowlist.as_deref(), trust.installed()),
     })
 }
 
@@ -259,15 +281,18 @@ fn extract_arguments(table: &LuaTable) -> Result<Vec<AgentArgument>> {
 ///
 /// Spawns a blocking thread, creates a sandboxed Lua VM with all host APIs
 /// plus the context bridge, and calls `agent.resolve(args, config, context)`.
+/// `ctx` backs `context.sample`.
 pub async fn resolve_agent(
     agent: &AgentDefinition,
     args: serde_json::Value,
     app_config: &Config,
+    ctx: &ToolContext,
 ) -> Result<AgentPrompt> {
     let agent = agent.clone();
     let config = app_config.clone();
+    let ctx = ctx.clone();
 
-    tokio::task::spawn_blocking(move || run_lua_agent(&agent, args, &config))
+    tokio::task::spawn_blocking(move || run_lua_agent(&agent, args, &config, ctx))
         .await
         .context("Lua agent task panicked")?
 }
@@ -277,6 +302,7 @@ fn run_lua_agent(
     agent: &AgentDefinition,
     args: serde_json::Value,
     config: &Config,
+    ctx: ToolContext,
 ) -> Result<AgentPrompt> {
     let script_dir = agent
         .script_path
@@ -305,10 +331,10 @@ fn run_lua_agent(
 
     // Register all shared host APIs
     let log_name = format!("agent:{}", agent.name);
-    register_all_host_apis(&lua, &log_name, &script_dir)?;
+    register_all_host_apis(&lua, &log_name, &script_dir, &config.db.path, &agent.env)?;
 
-    // Register context bridge (search, get, sources)
-    register_agent_context_bridge(&lua, config)?;
+    // Register context bridge (search, get, sources, sample)
+    register_agent_context_bridge(&lua, config, ctx)?;
 
     // Load and execute the script
     lua.load(&agent.script_source)
@@ -367,7 +393,13 @@ fn run_lua_agent(
 ///     system = "You are...",
 ///     messages = {
 ///         { role = "assistant", content = "I'm ready..." },
-///     }
+///     },
+///     -- optional
+///     tool_calls = {
+///         { tool = "search", arguments = { query = "auth" }, reason = "..." },
+///     },
+///     resources = { "doc-uuid" },
+///     model = { temperature = 0.2, max_tokens = 800 },
 /// }
 /// ```
 fn lua_result_to_agent_prompt(value: LuaValue) -> Result<AgentPrompt> {
@@ -409,10 +441,63 @@ fn lua_result_to_agent_prompt(value: LuaValue) -> Result<AgentPrompt> {
                 vec![]
             };
 
+            // Extract suggested tool calls (optional)
+            let mut tool_calls = Vec::new();
+            if let Ok(calls_table) = table.get::<LuaTable>("tool_calls") {
+                for i in 1..=calls_table.raw_len() {
+                    let call: LuaTable = calls_table.raw_get::<LuaTable>(i).map_err(|_| {
+                        anyhow::anyhow!("agent.resolve(): tool_calls[{}] must be a table", i)
+                    })?;
+                    let tool: String = call.get::<String>("tool").map_err(|_| {
+                        anyhow::anyhow!("agent.resolve(): tool_calls[{}] is missing 'tool'", i)
+                    })?;
+                    let arguments = match call.get::<LuaValue>("arguments") {
+                        Ok(LuaValue::Nil) | Err(_) => serde_json::json!({}),
+                        Ok(value) => match lua_value_to_json(value)? {
+                            serde_json::Value::Array(a) if a.is_empty() => serde_json::json!({}),
+                            serde_json::Value::Object(map) => serde_json::Value::Object(map),
+                            _ => bail!(
+                                "agent.resolve(): tool_calls[{}].arguments must be a table",
+                                i
+                            ),
+                        },
+                    };
+                    let reason = call.get::<Option<String>>("reason").unwrap_or(None);
+                    tool_calls.push(SuggestedToolCall {
+                        tool,
+                        arguments,
+                        reason,
+                    });
+                }
+            }
+
+            // Extract resources to attach (optional)
+            let mut resources = Vec::new();
+            if let Ok(res_table) = table.get::<LuaTable>("resources") {
+                for i in 1..=res_table.raw_len() {
+                    if let Ok(id) = res_table.raw_get::<String>(i) {
+                        resources.push(id);
+                    }
+                }
+            }
+
+            // Extract model hints (optional)
+            let model = match table.get::<LuaTable>("model") {
+                Ok(hints) => ModelHints {
+                    temperature: hints.get::<Option<f32>>("temperature").unwrap_or(None),
+                    top_p: hints.get::<Option<f32>>("top_p").unwrap_or(None),
+                    max_tokens: hints.get::<Option<u32>>("max_tokens").unwrap_or(None),
+                },
+                Err(_) => ModelHints::default(),
+            };
+
             Ok(AgentPrompt {
                 system,
                 tools,
                 messages,
+                tool_calls,
+                resources,
+                model,
             })
         }
         _ => {
@@ -430,9 +515,13 @@ fn lua_result_to_agent_prompt(value: LuaValue) -> Result<AgentPrompt> {
 
 /// Register the `context` table in the Lua VM for agent scripts.
 ///
-/// Provides `context.search`, `context.get`, and `context.sources`.
-/// Uses the same bridge pattern as tool scripts.
-fn register_agent_context_bridge(lua: &Lua, config: &Config) -> LuaResult<()> {
+/// Provides `context.search`, `context.get`, `context.sources`, and
+/// `context.sample`. Uses the same bridge pattern as tool scripts.
+fn register_agent_context_bridge(
+    lua: &Lua,
+    config: &Config,
+    tool_ctx: ToolContext,
+) -> LuaResult<()> {
     let ctx = lua.create_table()?;
 
     // context.search(query, opts?) → results
@@ -494,6 +583,9 @@ fn register_agent_context_bridge(lua: &Lua, config: &Config) -> LuaResult<()> {
         })?,
     )?;
 
+    // context.sample(prompt, opts?) → { text, model, stop_reason }
+    ctx.set("sample", sample_function(lua, tool_ctx)?)?;
+
     lua.globals().set("context", ctx)?;
     Ok(())
 }
@@ -650,6 +742,10 @@ your responses in the indexed knowledge base.
                 ),
             }},
         }},
+        -- Optional hints for the client:
+        -- tool_calls = {{ {{ tool = "search", arguments = {{ query = topic }} }} }},
+        -- resources = {{ results[1].id }},         -- documents to attach
+        -- model = {{ temperature = 0.2 }},         -- also top_p, max_tokens
     }}
 end
 
@@ -674,43 +770,46 @@ return agent
     Ok(())
 }
 
-/// Test an agent script by resolving its prompt.
+/// Test an agent by resolving its prompt.
 ///
-/// Loads the script, executes `agent.resolve()` with the provided arguments,
-/// and prints the resulting system prompt, tools, and messages.
+/// Inline TOML agents resolve through [`AgentRegistry::from_config`], so
+/// their `context` searches run against the index. Lua agents load the
+/// script and execute `agent.resolve()`. Either way the resulting system
+/// prompt, tools, and messages are printed.
 pub async fn test_agent(name: &str, args: Vec<(String, String)>, config: &Config) -> Result<()> {
-    let agent_config = config
-        .agents
-        .script
-        .get(name)
-        .ok_or_else(|| anyhow::anyhow!("Agent '{}' not found in config", name))?;
-
-    let agent_def = load_single_agent(name, agent_config)?;
-
-    println!("Agent: {}", agent_def.name);
-    println!("Source: lua ({})", agent_def.script_path.display());
-    println!(
-        "Tools: {}",
-        if agent_def.tools.is_empty() {
-            "(none defined)".to_string()
-        } else {
-            agent_def.tools.join(", ")
-        }
-    );
-    println!();
-
-    // Build args JSON
-    let mut args_json = serde_json::Map::new();
-    for (k, v) in &args {
-        let json_val = serde_json::from_str::<serde_json::Value>(v)
-            .unwrap_or_else(|_| serde_json::Value::String(v.clone()));
-        args_json.insert(k.clone(), json_val);
-    }
-    let args_value = serde_json::Value::Object(args_json);
+    let args_value = args_to_json(&args);
+
+    let (prompt, elapsed) = if config.agents.inline.contains_key(name) {
+        let agents = AgentRegistry::from_config(config)?;
+        let agent = agents
+            .find(name)
+            .ok_or_else(|| anyhow::anyhow!("Agent '{}' not found in config", name))?;
+        print_agent_header(agent.name(), "toml", &agent.tools());
+
+        let ctx = ToolContext::new(Arc::new(config.clone()));
+        let start = Instant::now();
+        let prompt = agent.resolve(args_value, &ctx).await?;
+        (prompt, start.elapsed())
+    } else {
+        let agent_config = config
+            .agents
+            .script
+            .get(name)
+            .ok_or_else(|| anyhow::anyhow!("Agent '{}' not found in config", name))?;
+
+        let trust = registry::script_trust(config, &agent_config.path);
+        let agent_def = load_single_agent(name, agent_config, &trust)?;
+        print_agent_header(
+            &agent_def.name,
+            &format!("lua ({})", agent_def.script_path.display()),
+            &agent_def.tools,
+        );
 
-    let start = Instant::now();
-    let prompt = resolve_agent(&agent_def, args_value, config).await?;
-    let elapsed = start.elapsed();
+        let ctx = ToolContext::new(Arc::new(config.clone()));
+        let start = Instant::now();
+        let prompt = resolve_agent(&agent_def, args_value, config, &ctx).await?;
+        (prompt, start.elapsed())
+    };
 
     println!("System prompt ({} chars):", prompt.system.len());
     for line in prompt.system.lines() {
@@ -730,12 +829,65 @@ pub async fn test_agent(name: &str, args: Vec<(String, String)>, config: &Config
         }
     }
 
+    if !prompt.tool_calls.is_empty() {
+        println!();
+        println!("Suggested tool calls ({}):", prompt.tool_calls.len());
+        for call in &prompt.tool_calls {
+            print!("  {} {}", call.tool, call.arguments);
+            match call.reason {
+                Some(ref reason) => println!("  — {}", reason),
+                None => println!(),
+            }
+        }
+    }
+
+    if !prompt.resources.is_empty() {
+        println!();
+        println!("Resources: {}", prompt.resources.join(", "));
+    }
+
+    if !prompt.model.is_empty() {
+        println!();
+        println!(
+            "Model hints: {}",
+            serde_json::to_string(&prompt.model).unwrap_or_default()
+        );
+    }
+
     println!();
     println!("Resolved in {:.0?}", elapsed);
 
     Ok(())
 }
 
+fn print_agent_header(name: &str, source: &str, tools: &[String]) {
+    println!("Agent: {}", name);
+    println!("Source: {}", source);
+    println!(
+        "Tools: {}",
+        if tools.is_empty() {
+            "(none defined)".to_string()
+        } else {
+            tools.join(", ")
+        }
+    );
+    println!();
+}
+
+/// Build an agent arguments object from CLI `--arg key=value` pairs.
+///
+/// Values that parse as JSON (numbers, booleans, arrays) keep their type;
+/// everything else becomes a string.
+pub(crate) fn args_to_json(args: &[(String, String)]) -> serde_json::Value {
+    let mut args_json = serde_json::Map::new();
+    for (k, v) in args {
+        let json_val = serde_json::from_str::<serde_json::Value>(v)
+            .unwrap_or_else(|_| serde_json::Value::String(v.clone()));
+        args_json.insert(k.clone(), json_val);
+    }
+    serde_json::Value::Object(args_json)
+}
+
 /// List all configured agents and print their info.
 pub fn list_agents(config: &Config) -> Result<()> {
     let mut count = 0;
@@ -42,13 +42,13 @@
 //!
 //! See `docs/AGENTS.md` for the full specification.
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use async_trait::async_trait;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 use crate::config::Config;
-use crate::traits::ToolContext;
+use crate::traits::{SearchOptions, ToolContext};
 
 // ═══════════════════════════════════════════════════════════════════════
 // Agent Trait
@@ -89,7 +89,7 @@ use crate::traits::ToolContext;
 ///         Ok(AgentPrompt {
 ///             system: "You are a software architect.".to_string(),
 ///             tools: self.tools(),
-///             messages: vec![],
+///             ..Default::default()
 ///         })
 ///     }
 /// }
@@ -156,7 +156,7 @@ pub struct AgentArgument {
 ///
 /// Returned by [`Agent::resolve`]. The client (Cursor, Claude, etc.)
 /// uses this to configure the LLM conversation.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct AgentPrompt {
     /// The system prompt text.
     pub system: String,
@@ -165,6 +165,16 @@ pub struct AgentPrompt {
     /// Optional additional messages to inject at conversation start.
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub messages: Vec<PromptMessage>,
+    /// Tool calls the agent suggests making first (e.g. a search for the
+    /// user's service before answering).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<SuggestedToolCall>,
+    /// Document IDs to attach to the conversation as resources.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub resources: Vec<String>,
+    /// Model parameter hints (temperature, token limits).
+    #[serde(skip_serializing_if = "ModelHints::is_empty")]
+    pub model: ModelHints,
 }
 
 /// A message to inject into the conversation.
@@ -179,6 +189,141 @@ pub struct PromptMessage {
     pub content: String,
 }
 
+/// A tool call an agent suggests making at the start of a conversation.
+///
+/// Clients may run these before the first model turn or show them to the
+/// model as hints; they are never executed by the server itself.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SuggestedToolCall {
+    /// Tool name (e.g. `"search"`).
+    pub tool: String,
+    /// Tool parameters as a JSON object.
+    #[serde(default = "empty_object")]
+    pub arguments: Value,
+    /// Why the agent wants this call, shown alongside the suggestion.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+fn empty_object() -> Value {
+    Value::Object(serde_json::Map::new())
+}
+
+/// A search an inline agent runs at resolve time to ground its prompt.
+///
+/// Declared as `[[agents.inline.<name>.context]]`. The `query` may contain
+/// `{{arg}}` placeholders filled from the resolve arguments; the top
+/// `limit` hits are appended to the system prompt under a
+/// `## Relevant context` section.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ContextQuery {
+    /// Search query, optionally templated with `{{arg}}` placeholders.
+    pub query: String,
+    /// Search mode: `"keyword"`, `"semantic"`, or `"hybrid"`. Default: `"keyword"`.
+    #[serde(default = "default_context_mode")]
+    pub mode: String,
+    /// Number of snippets to inject. Default: `3`.
+    #[serde(default = "default_context_limit")]
+    pub limit: i64,
+    /// Restrict the search to one source (e.g. `"git:platform"`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// Heading for this block in the prompt. Defaults to the rendered query.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub heading: Option<String>,
+}
+
+fn default_context_mode() -> String {
+    "keyword".to_string()
+}
+
+fn default_context_limit() -> i64 {
+    3
+}
+
+impl ContextQuery {
+    /// Names of the `{{arg}}` placeholders in `query`, in order of appearance.
+    pub fn placeholders(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        let mut rest = self.query.as_str();
+        while let Some(start) = rest.find("{{") {
+            let Some(len) = rest[start + 2..].find("}}") else {
+                break;
+            };
+            let name = rest[start + 2..start + 2 + len].trim().to_string();
+            if !name.is_empty() && !names.contains(&name) {
+                names.push(name);
+            }
+            rest = &rest[start + 2 + len + 2..];
+        }
+        names
+    }
+
+    /// Fill the placeholders from `args`.
+    ///
+    /// Returns `None` when a placeholder has no non-empty argument, so the
+    /// block is skipped rather than searched with a half-rendered query.
+    pub fn render(&self, args: &Value) -> Option<String> {
+        let mut query = self.query.clone();
+        for name in self.placeholders() {
+            let value = match args.get(&name)? {
+                Value::String(s) => s.clone(),
+                Value::Null => return None,
+                other => other.to_string(),
+            };
+            if value.trim().is_empty() {
+                return None;
+            }
+            query = replace_placeholder(&query, &name, &value);
+        }
+        Some(query)
+    }
+}
+
+/// Replace `{{name}}` (with optional inner whitespace) by `value`.
+fn replace_placeholder(template: &str, name: &str, value: &str) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        out.push_str(&rest[..start]);
+        if rest[start + 2..start + 2 + len].trim() == name {
+            out.push_str(value);
+        } else {
+            out.push_str(&rest[start..start + 2 + len + 2]);
+        }
+        rest = &rest[start + 2 + len + 2..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Model parameter hints returned by an agent.
+///
+/// All fields are optional; unset fields leave the client's own settings
+/// (e.g. `[llm] temperature`) in place.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ModelHints {
+    /// Sampling temperature.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    /// Nucleus sampling cutoff.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    /// Maximum tokens per model response.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+}
+
+impl ModelHints {
+    /// True when no hint is set.
+    pub fn is_empty(&self) -> bool {
+        self.temperature.is_none() && self.top_p.is_none() && self.max_tokens.is_none()
+    }
+}
+
 /// Serializable agent info for the `/agents/list` endpoint.
 #[derive(Debug, Clone, Serialize)]
 pub struct AgentInfo {
@@ -200,8 +345,10 @@ pub struct AgentInfo {
 
 /// An agent defined inline in TOML configuration.
 ///
-/// The simplest agent type — has a static system prompt and fixed tool
-/// list. No dynamic context injection or arguments.
+/// The simplest agent type — a system prompt and fixed tool list. Any
+/// [`ContextQuery`] blocks are searched at resolve time and their snippets
+/// appended to the prompt; their `{{arg}}` placeholders become the agent's
+/// (optional) arguments.
 ///
 /// Created automatically by [`AgentRegistry::from_config`] for each
 /// `[agents.inline.<name>]` entry.
@@ -210,6 +357,10 @@ pub struct TomlAgent {
     description: String,
     tools: Vec<String>,
     system_prompt: String,
+    tool_calls: Vec<SuggestedToolCall>,
+    resources: Vec<String>,
+    model: ModelHints,
+    context: Vec<ContextQuery>,
 }
 
 impl TomlAgent {
@@ -225,8 +376,77 @@ impl TomlAgent {
             description,
             tools,
             system_prompt,
+            tool_calls: Vec::new(),
+            resources: Vec::new(),
+            model: ModelHints::default(),
+            context: Vec::new(),
         }
     }
+
+    /// Attach suggested tool calls, preloaded resources, and model hints
+    /// to the prompt this agent resolves to.
+    pub fn with_hints(
+        mut self,
+        tool_calls: Vec<SuggestedToolCall>,
+        resources: Vec<String>,
+        model: ModelHints,
+    ) -> Self {
+        self.tool_calls = tool_calls;
+        self.resources = resources;
+        self.model = model;
+        self
+    }
+
+    /// Attach search blocks run at resolve time to ground the system prompt.
+    pub fn with_context(mut self, context: Vec<ContextQuery>) -> Self {
+        self.context = context;
+        self
+    }
+
+    /// Run the context queries and render the hits as a prompt section.
+    ///
+    /// Returns `None` when no query produced a hit.
+    async fn context_section(&self, args: &Value, ctx: &ToolContext) -> Result<Option<String>> {
+        let mut blocks = Vec::new();
+        for block in &self.context {
+            let Some(query) = block.render(args) else {
+                continue;
+            };
+            let hits = ctx
+                .search(
+                    &query,
+                    SearchOptions {
+                        mode: Some(block.mode.clone()),
+                        limit: Some(block.limit),
+                        source: block.source.clone(),
+                    },
+                )
+                .await
+                .with_context(|| format!("agent '{}': context query '{}'", self.name, query))?;
+            if hits.is_empty() {
+                continue;
+            }
+            let mut text = format!("### {}\n", block.heading.as_deref().unwrap_or(&query));
+            for (i, hit) in hits.iter().enumerate() {
+                text.push_str(&format!(
+                    "\n{}. {} ({}, id {})\n{}\n",
+                    i + 1,
+                    hit.title.as_deref().unwrap_or(&hit.source_id),
+                    hit.source,
+                    hit.id,
+                    hit.snippet.trim()
+                ));
+            }
+            blocks.push(text);
+        }
+        if blocks.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(format!(
+            "## Relevant context\n\n{}",
+            blocks.join("\n")
+        )))
+    }
 }
 
 #[async_trait]
@@ -247,11 +467,35 @@ impl Agent for TomlAgent {
         "toml"
     }
 
-    async fn resolve(&self, _args: Value, _ctx: &ToolContext) -> Result<AgentPrompt> {
+    fn arguments(&self) -> Vec<AgentArgument> {
+        let mut names: Vec<String> = Vec::new();
+        for name in self.context.iter().flat_map(ContextQuery::placeholders) {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        names
+            .into_iter()
+            .map(|name| AgentArgument {
+                description: format!("Fills {{{{{}}}}} in the agent's context queries", name),
+                name,
+                required: false,
+            })
+            .collect()
+    }
+
+    async fn resolve(&self, args: Value, ctx: &ToolContext) -> Result<AgentPrompt> {
+        let mut system = self.system_prompt.clone();
+        if let Some(section) = self.context_section(&args, ctx).await? {
+            system = format!("{}\n\n{}", system.trim_end(), section);
+        }
         Ok(AgentPrompt {
-            system: self.system_prompt.clone(),
+            system,
             tools: self.tools.clone(),
             messages: vec![],
+            tool_calls: self.tool_calls.clone(),
+            resources: self.resources.clone(),
+            model: self.model.clone(),
         })
     }
 }
@@ -294,12 +538,20 @@ impl AgentRegistry {
 
         // Load inline TOML agents
         for (name, cfg) in &config.agents.inline {
-            registry.register(Box::new(TomlAgent::new(
-                name.clone(),
-                cfg.description.clone(),
-                cfg.tools.clone(),
-                cfg.system_prompt.clone(),
-            )));
+            registry.register(Box::new(
+                TomlAgent::new(
+                    name.clone(),
+                    cfg.description.clone(),
+                    cfg.tools.clone(),
+                    cfg.system_prompt.clone(),
+                )
+                .with_hints(
+                    cfg.tool_calls.clone(),
+                    cfg.resources.clone(),
+                    cfg.model.clone(),
+                )
+                .with_context(cfg.context.clone()),
+            ));
         }
 
         // Lua agents are loaded in agent_script::load_agent_definitions
@@ -343,3 +595,58 @@ impl Default for AgentRegistry {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn block(query: &str) -> ContextQuery {
+        ContextQuery {
+            query: query.to_string(),
+            mode: default_context_mode(),
+            limit: default_context_limit(),
+            source: None,
+            heading: None,
+        }
+    }
+
+    #[test]
+    fn context_query_placeholders_are_deduplicated() {
+        let q = block("{{service}} runbook for {{ env }} and {{service}}");
+        assert_eq!(q.placeholders(), vec!["service", "env"]);
+    }
+
+    #[test]
+    fn context_query_renders_from_args() {
+        let q = block("{{service}} runbook for {{ env }}");
+        assert_eq!(
+            q.render(&json!({"service": "billing", "env": "prod"})),
+            Some("billing runbook for prod".to_string())
+        );
+        assert_eq!(q.render(&json!({"service": "billing"})), None);
+        assert_eq!(q.render(&json!({"service": " ", "env": "prod"})), None);
+        assert_eq!(
+            block("deploy checklist").render(&Value::Null),
+            Some("deploy checklist".to_string())
+        );
+    }
+
+    #[test]
+    fn toml_agent_arguments_come_from_context_placeholders() {
+        let agent = TomlAgent::new(
+            "oncall".to_string(),
+            "On-call helper".to_string(),
+            vec!["search".to_string()],
+            "You are on call.".to_string(),
+        )
+        .with_context(vec![
+            block("{{service}} runbook"),
+            block("{{service}} alerts"),
+        ]);
+        let args = agent.arguments();
+        assert_eq!(args.len(), 1);
+        assert_eq!(args[0].name, "service");
+        assert!(!args[0].required);
+    }
+}
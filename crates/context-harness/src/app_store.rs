@@ -10,16 +10,22 @@ use async_trait::async_trait;
 use serde::Serialize;
 use sha2::{Digest, Sha256};
 use sqlx::{Row, SqlitePool};
+use std::collections::{HashMap, HashSet};
 use uuid::Uuid;
 
+pub use context_harness_core::bundle::{ExportChunk, ExportData, ExportDocument};
+use context_harness_core::embedding::blob_to_vec;
 use context_harness_core::models::{Chunk, Document};
 use context_harness_core::store::{ChunkCandidate, DocumentMetadata, DocumentResponse, Store};
 
+use crate::chunk;
 use crate::config::Config;
 use crate::db;
+use crate::keyword_index::{self, OpenSearchIndex};
 use crate::migrate;
 use crate::models::SourceItem;
-use crate::sqlite_store::SqliteStore;
+use crate::sqlite_store::{DocumentWrite, SqliteStore};
+use crate::titles;
 use crate::vector_index::{self, VectorRecord};
 
 /// A chunk that needs embedding because its embedding is missing or stale.
@@ -27,12 +33,43 @@ use crate::vector_index::{self, VectorRecord};
 pub struct PendingChunk {
     pub chunk_id: String,
     pub document_id: String,
+    /// Text to embed: the chunk text with its context prepended
+    /// ([`chunk::embedding_text`]).
     pub text: String,
     pub text_hash: String,
 }
 
+/// Embedding state of every chunk relative to the current model, dims, and
+/// the chunker fingerprint of its source's effective chunking settings.
+///
+/// Each chunk is counted once, under the first reason that applies (model,
+/// then dims, then chunker, then text). `resync` is counted separately.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct EmbeddingFreshness {
+    pub fresh: i64,
+    /// Embedded with a different model.
+    pub stale_model: i64,
+    /// Embedded with the same model but different dimensions.
+    pub stale_dims: i64,
+    /// Embedded from chunks produced under different chunking settings.
+    pub stale_chunker: i64,
+    /// Chunk text changed since it was embedded.
+    pub stale_text: i64,
+    pub missing: i64,
+    /// Chunks produced under chunking settings that no longer match their
+    /// source's configuration; `ctx sync` re-chunks them.
+    pub resync: i64,
+}
+
+impl EmbeddingFreshness {
+    /// Chunks with an outdated embedding.
+    pub fn stale(&self) -> i64 {
+        self.stale_model + self.stale_dims + self.stale_chunker + self.stale_text
+    }
+}
+
 /// Per-source database statistics.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct SourceStats {
     pub source: String,
     pub doc_count: i64,
@@ -51,32 +88,22 @@ pub struct StoreStats {
     pub sources: Vec<SourceStats>,
 }
 
-/// Export payload used by `ctx export`.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
-pub struct ExportData {
-    pub documents: Vec<ExportDocument>,
-    pub chunks: Vec<ExportChunk>,
-}
-
-/// Exported document row.
+/// A recently updated document, used by the `overview` tool.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
-pub struct ExportDocument {
+pub struct RecentDocument {
     pub id: String,
     pub source: String,
     pub source_id: String,
-    pub source_url: Option<String>,
     pub title: Option<String>,
     pub updated_at: i64,
-    pub body: String,
 }
 
-/// Exported chunk row.
+/// Document count under a directory prefix of `source_id`.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
-pub struct ExportChunk {
-    pub id: String,
-    pub document_id: String,
-    pub chunk_index: i64,
-    pub text: String,
+pub struct DirectoryCount {
+    pub source: String,
+    pub directory: String,
+    pub doc_count: i64,
 }
 
 /// App-level storage operations layered on top of core search storage.
@@ -86,22 +113,43 @@ pub trait AppStore: Store {
     async fn initialize(&self) -> Result<()>;
     async fn get_checkpoint(&self, source: &str) -> Result<Option<i64>>;
     async fn set_checkpoint(&self, source: &str, cursor: i64) -> Result<()>;
+    /// Connector state saved with the checkpoint (see
+    /// [`Connector::checkpoint_state`](crate::traits::Connector::checkpoint_state)).
+    async fn get_checkpoint_state(&self, source: &str) -> Result<Option<String>>;
+    /// Save connector state on an existing checkpoint.
+    async fn set_checkpoint_state(&self, source: &str, state: &str) -> Result<()>;
+    #[allow(dead_code)]
     async fn upsert_source_item(&self, item: &SourceItem) -> Result<String>;
+    /// Chunks with no embedding for `model`, or whose embedding is stale
+    /// (text hash, dims, or chunker fingerprint differ).
     async fn find_pending_chunks(
         &self,
         model: &str,
+        dims: usize,
         limit: Option<usize>,
     ) -> Result<Vec<PendingChunk>>;
-    async fn get_embedding_hash(&self, chunk_id: &str, model: &str) -> Result<Option<String>>;
+    /// Text hash of a chunk's embedding, if it matches `model`, `dims`, and
+    /// the current chunker fingerprint.
+    async fn get_embedding_hash(
+        &self,
+        chunk_id: &str,
+        model: &str,
+        dims: usize,
+    ) -> Result<Option<String>>;
+    async fn embedding_freshness(&self, model: &str, dims: usize) -> Result<EmbeddingFreshness>;
     async fn clear_embeddings(&self) -> Result<()>;
     async fn stats(&self) -> Result<StoreStats>;
-    async fn export_index(&self) -> Result<ExportData>;
+    async fn recent_documents(&self, limit: usize) -> Result<Vec<RecentDocument>>;
+    async fn top_directories(&self, limit: usize) -> Result<Vec<DirectoryCount>>;
+    /// Every document and chunk, plus chunk vectors when `with_vectors`.
+    async fn export_index(&self, with_vectors: bool) -> Result<ExportData>;
 }
 
 /// SQLite-backed [`AppStore`] implementation.
 pub struct SqliteAppStore {
     config: Config,
     pool: SqlitePool,
+    keyword_index: Option<OpenSearchIndex>,
 }
 
 impl SqliteAppStore {
@@ -110,6 +158,7 @@ impl SqliteAppStore {
         Ok(Self {
             config: config.clone(),
             pool,
+            keyword_index: keyword_index::configured_index(config)?,
         })
     }
 
@@ -126,15 +175,124 @@ impl SqliteAppStore {
         self.pool.close().await;
     }
 
+    /// Make chunks mirrored to an external keyword index searchable.
+    /// A no-op for the SQLite FTS5 backend.
+    pub async fn refresh_keyword_index(&self) -> Result<()> {
+        match &self.keyword_index {
+            Some(index) => index.mirror_refresh().await,
+            None => Ok(()),
+        }
+    }
+
+    /// Delete the documents stored for `source_id` in `source` (an
+    /// archive's members included) from SQLite, the vector index, and the
+    /// external keyword index. Returns the deleted document IDs.
+    pub async fn delete_source_item(&self, source: &str, source_id: &str) -> Result<Vec<String>> {
+        let ids = self
+            .core_store()
+            .document_ids_for_source_id(source, source_id)
+            .await?;
+        self.delete_documents(&ids).await?;
+        Ok(ids)
+    }
+
+    /// Delete the documents `ids` from SQLite in one transaction (see
+    /// [`SqliteStore::delete_documents`]), then from the vector index and
+    /// the external keyword index.
+    pub async fn delete_documents(&self, ids: &[String]) -> Result<()> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        self.core_store().delete_documents(ids).await?;
+        vector_index::remove_configured_sidecar(&self.config)?;
+        if let Some(index) = &self.keyword_index {
+            index.mirror_deletes(ids).await?;
+        }
+        Ok(())
+    }
+
+    /// The document `item` is stored as, with its precomputed
+    /// [`dedup_hash`]. Its ID is the stored document's when
+    /// `(source, source_id)` exists, otherwise a new UUID.
+    pub async fn source_item_document(
+        &self,
+        item: &SourceItem,
+        dedup_hash: String,
+    ) -> Result<Document> {
+        source_item_to_document(&self.pool, item, dedup_hash).await
+    }
+
+    /// Upsert `doc` with its chunks in one transaction (see
+    /// [`SqliteStore::write_document`]), retrying while the database is
+    /// busy, then update the vector and keyword indexes unless nothing
+    /// changed.
+    pub async fn write_document(&self, doc: &Document, chunks: &[Chunk]) -> Result<DocumentWrite> {
+        let chunker = self.chunker_fingerprint(&doc.source);
+        let written = db::retry_busy(self.config.db.write_retries, || async {
+            self.core_store()
+                .write_document(doc, chunks, &chunker)
+                .await
+        })
+        .await?;
+        if !written.unchanged {
+            vector_index::remove_configured_sidecar(&self.config)?;
+            if let Some(index) = &self.keyword_index {
+                index.mirror_document(&self.pool, &written.id).await?;
+            }
+        }
+        Ok(written)
+    }
+
     fn core_store(&self) -> SqliteStore {
-        SqliteStore::new(self.pool.clone())
+        SqliteStore::new(self.pool.clone()).with_retrieval(&self.config.retrieval)
+    }
+
+    /// Chunker fingerprint of `source`'s effective chunking settings.
+    fn chunker_fingerprint(&self, source: &str) -> String {
+        let chunking = match self
+            .config
+            .connectors
+            .chunking_overrides()
+            .into_iter()
+            .find(|(label, _)| label == source)
+        {
+            Some((_, overrides)) => self.config.chunking.with_override(overrides),
+            None => self.config.chunking.clone(),
+        };
+        chunk::config_fingerprint(&chunking)
+    }
+
+    /// SQL expression for the expected chunker fingerprint of a row whose
+    /// document is aliased `d`: the fingerprint of `[chunking]` merged with
+    /// the source connector's overrides.
+    fn expected_chunker_sql(&self) -> String {
+        let default = format!("'{}'", chunk::config_fingerprint(&self.config.chunking));
+        let overrides = self.config.connectors.chunking_overrides();
+        if overrides.is_empty() {
+            return default;
+        }
+        let mut sql = String::from("CASE d.source");
+        for (label, overrides) in overrides {
+            sql.push_str(&format!(
+                " WHEN '{}' THEN '{}'",
+                label.replace('\'', "''"),
+                chunk::config_fingerprint(&self.config.chunking.with_override(overrides))
+            ));
+        }
+        sql.push_str(&format!(" ELSE {} END", default));
+        sql
     }
 }
 
 #[async_trait]
 impl Store for SqliteAppStore {
     async fn upsert_document(&self, doc: &Document) -> Result<String> {
-        self.core_store().upsert_document(doc).await
+        db::retry_busy(self.config.db.write_retries, || async {
+            let id = self.core_store().upsert_document(doc).await?;
+            titles::index_document(&self.pool, doc).await?;
+            Ok(id)
+        })
+        .await
     }
 
     async fn replace_chunks(
@@ -143,10 +301,26 @@ impl Store for SqliteAppStore {
         chunks: &[Chunk],
         vectors: Option<&[Vec<f32>]>,
     ) -> Result<()> {
-        self.core_store()
-            .replace_chunks(doc_id, chunks, vectors)
+        db::retry_busy(self.config.db.write_retries, || async {
+            self.core_store()
+                .replace_chunks(doc_id, chunks, vectors)
+                .await?;
+            sqlx::query(&format!(
+                "UPDATE chunks SET chunker = (SELECT {} FROM documents d WHERE d.id = ?) \
+                 WHERE document_id = ?",
+                self.expected_chunker_sql()
+            ))
+            .bind(doc_id)
+            .bind(doc_id)
+            .execute(&self.pool)
             .await?;
+            Ok(())
+        })
+        .await?;
         vector_index::remove_configured_sidecar(&self.config)?;
+        if let Some(index) = &self.keyword_index {
+            index.mirror_document(&self.pool, doc_id).await?;
+        }
         Ok(())
     }
 
@@ -159,9 +333,22 @@ impl Store for SqliteAppStore {
         dims: usize,
         content_hash: &str,
     ) -> Result<()> {
-        self.core_store()
-            .upsert_embedding(chunk_id, doc_id, vector, model, dims, content_hash)
+        db::retry_busy(self.config.db.write_retries, || async {
+            self.core_store()
+                .upsert_embedding(chunk_id, doc_id, vector, model, dims, content_hash)
+                .await?;
+            sqlx::query(&format!(
+                "UPDATE embeddings SET chunker = (SELECT {} FROM documents d WHERE d.id = ?) \
+                 WHERE chunk_id = ?",
+                self.expected_chunker_sql()
+            ))
+            .bind(doc_id)
+            .bind(chunk_id)
+            .execute(&self.pool)
             .await?;
+            Ok(())
+        })
+        .await?;
         let record = VectorRecord {
             chunk_id: chunk_id.to_string(),
             document_id: doc_id.to_string(),
@@ -182,6 +369,13 @@ impl Store for SqliteAppStore {
         self.core_store().get_document_metadata(id).await
     }
 
+    async fn get_chunk_metadata(
+        &self,
+        chunk_id: &str,
+    ) -> Result<Option<serde_json::Map<String, serde_json::Value>>> {
+        self.core_store().get_chunk_metadata(chunk_id).await
+    }
+
     async fn keyword_search(
         &self,
         query: &str,
@@ -205,6 +399,14 @@ impl Store for SqliteAppStore {
             .vector_search(query_vec, limit, source, since)
             .await
     }
+
+    async fn unembedded_chunks(&self, chunk_ids: &[String]) -> Result<HashSet<String>> {
+        self.core_store().unembedded_chunks(chunk_ids).await
+    }
+
+    async fn chunk_indexes(&self, chunk_ids: &[String]) -> Result<HashMap<String, i64>> {
+        self.core_store().chunk_indexes(chunk_ids).await
+    }
 }
 
 #[async_trait]
@@ -225,44 +427,75 @@ impl AppStore for SqliteAppStore {
 
     async fn set_checkpoint(&self, source: &str, cursor: i64) -> Result<()> {
         let now = chrono::Utc::now().timestamp();
-        sqlx::query(
-            r#"
-            INSERT INTO checkpoints (source, cursor, updated_at) VALUES (?, ?, ?)
-            ON CONFLICT(source) DO UPDATE SET cursor = excluded.cursor, updated_at = excluded.updated_at
-            "#,
-        )
-        .bind(source)
-        .bind(cursor.to_string())
-        .bind(now)
-        .execute(&self.pool)
-        .await?;
+        db::retry_busy(self.config.db.write_retries, || async {
+            sqlx::query(
+                r#"
+                INSERT INTO checkpoints (source, cursor, updated_at) VALUES (?, ?, ?)
+                ON CONFLICT(source) DO UPDATE SET cursor = excluded.cursor, updated_at = excluded.updated_at
+                "#,
+            )
+            .bind(source)
+            .bind(cursor.to_string())
+            .bind(now)
+            .execute(&self.pool)
+            .await?;
+            Ok(())
+        })
+        .await
+    }
 
-        Ok(())
+    async fn get_checkpoint_state(&self, source: &str) -> Result<Option<String>> {
+        let state: Option<Option<String>> =
+            sqlx::query_scalar("SELECT state FROM checkpoints WHERE source = ?")
+                .bind(source)
+                .fetch_optional(&self.pool)
+                .await?;
+        Ok(state.flatten())
+    }
+
+    async fn set_checkpoint_state(&self, source: &str, state: &str) -> Result<()> {
+        db::retry_busy(self.config.db.write_retries, || async {
+            sqlx::query("UPDATE checkpoints SET state = ? WHERE source = ?")
+                .bind(state)
+                .bind(source)
+                .execute(&self.pool)
+                .await?;
+            Ok(())
+        })
+        .await
     }
 
     async fn upsert_source_item(&self, item: &SourceItem) -> Result<String> {
-        let doc = source_item_to_document(&self.pool, item).await?;
+        let doc = source_item_to_document(&self.pool, item, dedup_hash(item)).await?;
         self.upsert_document(&doc).await
     }
 
     async fn find_pending_chunks(
         &self,
         model: &str,
+        dims: usize,
         limit: Option<usize>,
     ) -> Result<Vec<PendingChunk>> {
         let limit_val = limit.unwrap_or(usize::MAX) as i64;
 
-        let rows = sqlx::query(
+        let rows = sqlx::query(&format!(
             r#"
-            SELECT c.id AS chunk_id, c.document_id, c.text, c.hash AS chunk_hash
+            SELECT c.id AS chunk_id, c.document_id, c.text, c.hash AS chunk_hash,
+                   c.metadata_json
             FROM chunks c
+            LEFT JOIN documents d ON d.id = c.document_id
             LEFT JOIN embeddings e ON e.chunk_id = c.id AND e.model = ?
-            WHERE e.chunk_id IS NULL OR e.hash != c.hash
+            WHERE e.chunk_id IS NULL
+               OR e.hash != c.hash
+               OR e.dims != ?
+               OR e.chunker IS NOT ({})
             ORDER BY c.document_id, c.chunk_index
             LIMIT ?
             "#,
-        )
+            self.expected_chunker_sql()
+        ))
         .bind(model)
+        .bind(dims as i64)
         .bind(limit_val)
         .fetch_all(&self.pool)
         .await?;
@@ -271,26 +504,89 @@ impl AppStore for SqliteAppStore {
             .iter()
             .map(|row| {
                 let text: String = row.get("text");
+                let metadata: String = row.get("metadata_json");
+                let metadata = serde_json::from_str(&metadata).unwrap_or_default();
                 PendingChunk {
                     chunk_id: row.get("chunk_id"),
                     document_id: row.get("document_id"),
                     text_hash: row.get("chunk_hash"),
-                    text,
+                    text: chunk::embedding_text(&text, &metadata),
                 }
             })
             .collect())
     }
 
-    async fn get_embedding_hash(&self, chunk_id: &str, model: &str) -> Result<Option<String>> {
-        let hash =
-            sqlx::query_scalar("SELECT hash FROM embeddings WHERE chunk_id = ? AND model = ?")
-                .bind(chunk_id)
-                .bind(model)
-                .fetch_optional(&self.pool)
-                .await?;
+    async fn get_embedding_hash(
+        &self,
+        chunk_id: &str,
+        model: &str,
+        dims: usize,
+    ) -> Result<Option<String>> {
+        let hash = sqlx::query_scalar(&format!(
+            r#"
+            SELECT e.hash FROM embeddings e
+            JOIN chunks c ON c.id = e.chunk_id
+            LEFT JOIN documents d ON d.id = c.document_id
+            WHERE e.chunk_id = ? AND e.model = ? AND e.dims = ? AND e.chunker = ({})
+            "#,
+            self.expected_chunker_sql()
+        ))
+        .bind(chunk_id)
+        .bind(model)
+        .bind(dims as i64)
+        .fetch_optional(&self.pool)
+        .await?;
         Ok(hash)
     }
 
+    async fn embedding_freshness(&self, model: &str, dims: usize) -> Result<EmbeddingFreshness> {
+        let expected = self.expected_chunker_sql();
+        let rows = sqlx::query(&format!(
+            r#"
+            SELECT
+                CASE
+                    WHEN e.chunk_id IS NULL THEN 'missing'
+                    WHEN e.model != ? THEN 'model'
+                    WHEN e.dims != ? THEN 'dims'
+                    WHEN e.chunker IS NOT ({}) THEN 'chunker'
+                    WHEN e.hash != c.hash THEN 'text'
+                    ELSE 'fresh'
+                END AS state,
+                COUNT(*) AS n
+            FROM chunks c
+            LEFT JOIN documents d ON d.id = c.document_id
+            LEFT JOIN embeddings e ON e.chunk_id = c.id
+            GROUP BY state
+            "#,
+            expected
+        ))
+        .bind(model)
+        .bind(dims as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut freshness = EmbeddingFreshness::default();
+        for row in &rows {
+            let n: i64 = row.get("n");
+            match row.get::<String, _>("state").as_str() {
+                "missing" => freshness.missing = n,
+                "model" => freshness.stale_model = n,
+                "dims" => freshness.stale_dims = n,
+                "chunker" => freshness.stale_chunker = n,
+                "text" => freshness.stale_text = n,
+                _ => freshness.fresh = n,
+            }
+        }
+        freshness.resync = sqlx::query_scalar(&format!(
+            "SELECT COUNT(*) FROM chunks c LEFT JOIN documents d ON d.id = c.document_id \
+             WHERE c.chunker IS NOT ({})",
+            expected
+        ))
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(freshness)
+    }
+
     async fn clear_embeddings(&self) -> Result<()> {
         sqlx::query("DELETE FROM chunk_vectors")
             .execute(&self.pool)
@@ -298,6 +594,10 @@ impl AppStore for SqliteAppStore {
         sqlx::query("DELETE FROM embeddings")
             .execute(&self.pool)
             .await?;
+        migrate::create_document_vectors_table(&self.pool).await?;
+        sqlx::query("DELETE FROM document_vectors")
+            .execute(&self.pool)
+            .await?;
         vector_index::remove_configured_sidecar(&self.config)?;
         Ok(())
     }
@@ -366,7 +666,59 @@ impl AppStore for SqliteAppStore {
         })
     }
 
-    async fn export_index(&self) -> Result<ExportData> {
+    async fn recent_documents(&self, limit: usize) -> Result<Vec<RecentDocument>> {
+        let rows = sqlx::query(
+            "SELECT id, source, source_id, title, updated_at \
+             FROM documents ORDER BY updated_at DESC, id LIMIT ?",
+        )
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| RecentDocument {
+                id: row.get("id"),
+                source: row.get("source"),
+                source_id: row.get("source_id"),
+                title: row.get("title"),
+                updated_at: row.get("updated_at"),
+            })
+            .collect())
+    }
+
+    async fn top_directories(&self, limit: usize) -> Result<Vec<DirectoryCount>> {
+        let rows = sqlx::query("SELECT source, source_id FROM documents")
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut counts: HashMap<(String, String), i64> = HashMap::new();
+        for row in &rows {
+            let source: String = row.get("source");
+            let source_id: String = row.get("source_id");
+            let directory = source_id_directory(&source_id);
+            *counts.entry((source, directory)).or_insert(0) += 1;
+        }
+
+        let mut dirs: Vec<DirectoryCount> = counts
+            .into_iter()
+            .map(|((source, directory), doc_count)| DirectoryCount {
+                source,
+                directory,
+                doc_count,
+            })
+            .collect();
+        dirs.sort_by(|a, b| {
+            b.doc_count
+                .cmp(&a.doc_count)
+                .then_with(|| a.source.cmp(&b.source))
+                .then_with(|| a.directory.cmp(&b.directory))
+        });
+        dirs.truncate(limit);
+        Ok(dirs)
+    }
+
+    async fn export_index(&self, with_vectors: bool) -> Result<ExportData> {
         let doc_rows = sqlx::query(
             "SELECT id, source, source_id, source_url, title, updated_at, body \
              FROM documents ORDER BY source_id",
@@ -381,6 +733,35 @@ impl AppStore for SqliteAppStore {
         .fetch_all(&self.pool)
         .await?;
 
+        // Export only the vectors of the most common (model, dims) pair:
+        // query vectors can match one model, and mixed dims can't be compared.
+        let embedding: Option<(String, i64)> = if with_vectors {
+            sqlx::query_as(
+                "SELECT model, dims FROM embeddings \
+                 GROUP BY model, dims ORDER BY COUNT(*) DESC, model LIMIT 1",
+            )
+            .fetch_optional(&self.pool)
+            .await?
+        } else {
+            None
+        };
+        let mut vectors: HashMap<String, Vec<f32>> = HashMap::new();
+        if let Some((model, dims)) = &embedding {
+            let rows = sqlx::query(
+                "SELECT cv.chunk_id, cv.embedding FROM chunk_vectors cv \
+                 JOIN embeddings e ON e.chunk_id = cv.chunk_id \
+                 WHERE e.model = ? AND e.dims = ?",
+            )
+            .bind(model)
+            .bind(dims)
+            .fetch_all(&self.pool)
+            .await?;
+            for row in rows {
+                let blob: Vec<u8> = row.get("embedding");
+                vectors.insert(row.get("chunk_id"), blob_to_vec(&blob));
+            }
+        }
+
         let documents = doc_rows
             .iter()
             .map(|row| ExportDocument {
@@ -396,20 +777,36 @@ impl AppStore for SqliteAppStore {
 
         let chunks = chunk_rows
             .iter()
-            .map(|row| ExportChunk {
-                id: row.get("id"),
-                document_id: row.get("document_id"),
-                chunk_index: row.get("chunk_index"),
-                text: row.get("text"),
+            .map(|row| {
+                let id: String = row.get("id");
+                ExportChunk {
+                    vector: vectors.remove(&id),
+                    id,
+                    document_id: row.get("document_id"),
+                    chunk_index: row.get("chunk_index"),
+                    text: row.get("text"),
+                }
             })
             .collect();
 
-        Ok(ExportData { documents, chunks })
+        let (embedding_model, embedding_dims) = match embedding {
+            Some((model, dims)) => (Some(model), Some(dims as usize)),
+            None => (None, None),
+        };
+        Ok(ExportData {
+            documents,
+            chunks,
+            embedding_model,
+            embedding_dims,
+        })
     }
 }
 
-async fn source_item_to_document(pool: &SqlitePool, item: &SourceItem) -> Result<Document> {
-    let dedup_hash = dedup_hash(item);
+async fn source_item_to_document(
+    pool: &SqlitePool,
+    item: &SourceItem,
+    dedup_hash: String,
+) -> Result<Document> {
     let existing_id: Option<String> =
         sqlx::query_scalar("SELECT id FROM documents WHERE source = ? AND source_id = ?")
             .bind(&item.source)
@@ -431,15 +828,20 @@ async fn source_item_to_document(pool: &SqlitePool, item: &SourceItem) -> Result
         metadata_json: item.metadata_json.clone(),
         raw_json: item.raw_json.clone(),
         dedup_hash,
+        parent_source_id: item.parent_source_id.clone(),
+        summary: None,
     })
 }
 
-fn dedup_hash(item: &SourceItem) -> String {
+pub(crate) fn dedup_hash(item: &SourceItem) -> String {
     let mut hasher = Sha256::new();
     hasher.update(item.source.as_bytes());
     hasher.update(item.source_id.as_bytes());
     hasher.update(item.updated_at.timestamp().to_le_bytes());
     hasher.update(item.body.as_bytes());
+    if let Some(parent) = &item.parent_source_id {
+        hasher.update(parent.as_bytes());
+    }
     format!("{:x}", hasher.finalize())
 }
 
@@ -448,3 +850,12 @@ pub(crate) fn hash_text(text: &str) -> String {
     hasher.update(text.as_bytes());
     format!("{:x}", hasher.finalize())
 }
+
+/// Parent directory of a path-like `source_id` (`"docs/ops/deploy.md"` →
+/// `"docs/ops"`). Top-level items and non-path IDs map to `"."`.
+fn source_id_directory(source_id: &str) -> String {
+    match source_id.trim_end_matches('/').rsplit_once(['/', '\\']) {
+        Some((dir, _)) if !dir.is_empty() => dir.to_string(),
+        _ => ".".to_string(),
+    }
+}
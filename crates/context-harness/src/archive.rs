@@ -0,0 +1,411 @@
+//! Reading `.zip`, `.tar`, and `.tar.gz` archives for ingestion.
+//!
+//! The filesystem and S3 connectors can descend into archives (see
+//! [`ArchiveConfig`]) so that release tarballs and zipped doc bundles are
+//! indexed like ordinary files. Each regular file inside becomes a document
+//! whose `source_id` joins the archive's own ID and the inner path with
+//! [`SEPARATOR`]:
+//!
+//! ```text
+//! releases/v2.1.tar.gz!/docs/upgrade.md
+//! bundle.zip!/api.tar.gz!/reference.md     # nested, with max_depth = 2
+//! ```
+//!
+//! # Limits
+//!
+//! - `max_depth` — archives found inside an archive are opened only while
+//!   the nesting depth stays within this limit; deeper ones are skipped.
+//! - `max_entry_bytes` — larger inner files are skipped and counted as too
+//!   large.
+//! - `max_total_bytes` — an archive larger than this, or whose contents add
+//!   up to more than this uncompressed, is rejected as a whole. This bounds
+//!   memory use and guards against decompression bombs.
+//!
+//! Inner paths are normalized to `/` separators; entries with absolute
+//! paths or `..` components, directories, links, and other special entries
+//! are ignored.
+//!
+//! Tar support covers ustar, GNU long names, and pax `path` headers, which
+//! is what `tar`, `git archive`, and common release tooling produce.
+
+use anyhow::{bail, Context, Result};
+use flate2::read::GzDecoder;
+use std::io::{Cursor, Read};
+
+use crate::config::ArchiveConfig;
+
+/// Separator between an archive's ID and a path inside it.
+pub const SEPARATOR: &str = "!/";
+
+/// Tar block size.
+const BLOCK: usize = 512;
+
+/// Supported archive formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveKind {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+impl ArchiveKind {
+    /// Format of `path` judged by its extension, if it is an archive.
+    pub fn from_path(path: &str) -> Option<Self> {
+        let lower = path.to_lowercase();
+        if lower.ends_with(".zip") {
+            Some(Self::Zip)
+        } else if lower.ends_with(".tar.gz") || lower.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else if lower.ends_with(".tar") {
+            Some(Self::Tar)
+        } else {
+            None
+        }
+    }
+}
+
+/// A regular file read from an archive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveEntry {
+    /// Path inside the archive; nested archives are joined with [`SEPARATOR`].
+    pub path: String,
+    /// Uncompressed contents.
+    pub bytes: Vec<u8>,
+}
+
+/// Files read from an archive and the number skipped for size.
+#[derive(Debug, Default)]
+pub struct ArchiveContents {
+    /// Regular files, in archive order.
+    pub entries: Vec<ArchiveEntry>,
+    /// Files over `max_entry_bytes`.
+    pub too_large: u64,
+}
+
+/// Read every file in the archive `bytes`, whose format is taken from `name`.
+///
+/// Fails if the archive is corrupt, is not a supported format, or exceeds
+/// `max_total_bytes`.
+pub fn read_archive(name: &str, bytes: &[u8], config: &ArchiveConfig) -> Result<ArchiveContents> {
+    let kind = ArchiveKind::from_path(name).with_context(|| format!("not an archive: {}", name))?;
+    if bytes.len() as u64 > config.max_total_bytes {
+        bail!(
+            "{} is larger than archives.max_total_bytes ({} bytes)",
+            name,
+            config.max_total_bytes
+        );
+    }
+    let mut reader = ArchiveReader {
+        config,
+        contents: ArchiveContents::default(),
+        total: 0,
+    };
+    reader
+        .read(kind, bytes, "", 1)
+        .with_context(|| format!("failed to read archive {}", name))?;
+    Ok(reader.contents)
+}
+
+struct ArchiveReader<'a> {
+    config: &'a ArchiveConfig,
+    contents: ArchiveContents,
+    /// Uncompressed bytes read so far, across nesting levels.
+    total: u64,
+}
+
+impl ArchiveReader<'_> {
+    fn read(&mut self, kind: ArchiveKind, bytes: &[u8], prefix: &str, depth: usize) -> Result<()> {
+        let mut files = Vec::new();
+        match kind {
+            ArchiveKind::Zip => self.read_zip(bytes, &mut files)?,
+            ArchiveKind::Tar => self.read_tar(bytes, &mut files)?,
+            ArchiveKind::TarGz => self.read_tar(GzDecoder::new(bytes), &mut files)?,
+        }
+
+        for (path, data) in files {
+            let path = format!("{}{}", prefix, path);
+            match ArchiveKind::from_path(&path) {
+                Some(inner) if depth < self.config.max_depth => {
+                    let prefix = format!("{}{}", path, SEPARATOR);
+                    self.read(inner, &data, &prefix, depth + 1)
+                        .with_context(|| format!("failed to read nested archive {}", path))?;
+                }
+                Some(_) => {
+                    tracing::debug!("skipping nested archive {} (archives.max_depth)", path);
+                }
+                None => self
+                    .contents
+                    .entries
+                    .push(ArchiveEntry { path, bytes: data }),
+            }
+        }
+        Ok(())
+    }
+
+    /// Read `size` bytes of one entry, enforcing the size limits. Returns
+    /// `None` (having consumed the data) when the entry is too large.
+    fn read_entry(&mut self, source: impl Read, size: u64) -> Result<Option<Vec<u8>>> {
+        if size > self.config.max_entry_bytes {
+            std::io::copy(&mut source.take(size), &mut std::io::sink())?;
+            self.contents.too_large += 1;
+            return Ok(None);
+        }
+        self.total += size;
+        if self.total > self.config.max_total_bytes {
+            bail!(
+                "contents exceed archives.max_total_bytes ({} bytes)",
+                self.config.max_total_bytes
+            );
+        }
+        // Sizes in headers are not trusted beyond the limit already checked.
+        let mut data = Vec::with_capacity(size as usize);
+        source.take(size).read_to_end(&mut data)?;
+        if (data.len() as u64) < size {
+            bail!("unexpected end of archive");
+        }
+        Ok(Some(data))
+    }
+
+    fn read_zip(&mut self, bytes: &[u8], files: &mut Vec<(String, Vec<u8>)>) -> Result<()> {
+        let mut zip = zip::ZipArchive::new(Cursor::new(bytes))?;
+        for i in 0..zip.len() {
+            let file = zip.by_index(i)?;
+            if !file.is_file() {
+                continue;
+            }
+            let Some(path) = normalize_path(file.name()) else {
+                continue;
+            };
+            let size = file.size();
+            if let Some(data) = self.read_entry(file, size)? {
+                files.push((path, data));
+            }
+        }
+        Ok(())
+    }
+
+    fn read_tar(
+        &mut self,
+        mut source: impl Read,
+        files: &mut Vec<(String, Vec<u8>)>,
+    ) -> Result<()> {
+        let mut long_name: Option<String> = None;
+        let mut header = [0u8; BLOCK];
+        loop {
+            if !read_block(&mut source, &mut header)? || header.iter().all(|&b| b == 0) {
+                return Ok(());
+            }
+            let size = parse_size(&header[124..136])?;
+            let padding = (BLOCK as u64 - size % BLOCK as u64) % BLOCK as u64;
+            let typeflag = header[156];
+
+            match typeflag {
+                // GNU long name / pax extended header: the data names the next entry.
+                b'L' | b'x' => {
+                    let data = read_meta(&mut source, size)?;
+                    let name = if typeflag == b'L' {
+                        Some(cstr(&data))
+                    } else {
+                        pax_path(&data)
+                    };
+                    if name.is_some() {
+                        long_name = name;
+                    }
+                }
+                b'0' | 0 | b'7' => {
+                    let name = long_name.take().unwrap_or_else(|| header_name(&header));
+                    let data = self.read_entry(&mut source, size)?;
+                    if let (Some(path), Some(data)) = (normalize_path(&name), data) {
+                        files.push((path, data));
+                    }
+                }
+                _ => {
+                    long_name = None;
+                    std::io::copy(&mut (&mut source).take(size), &mut std::io::sink())?;
+                }
+            }
+            std::io::copy(&mut (&mut source).take(padding), &mut std::io::sink())?;
+        }
+    }
+}
+
+/// Read one tar block; `false` at a clean end of input.
+fn read_block(source: &mut impl Read, block: &mut [u8; BLOCK]) -> Result<bool> {
+    let mut filled = 0;
+    while filled < BLOCK {
+        let n = source.read(&mut block[filled..])?;
+        if n == 0 {
+            if filled == 0 {
+                return Ok(false);
+            }
+            bail!("truncated tar header");
+        }
+        filled += n;
+    }
+    Ok(true)
+}
+
+/// Read a small metadata entry (long name or pax header).
+fn read_meta(source: &mut impl Read, size: u64) -> Result<Vec<u8>> {
+    if size > 1024 * 1024 {
+        bail!("tar metadata entry too large ({} bytes)", size);
+    }
+    let mut data = Vec::with_capacity(size as usize);
+    source.take(size).read_to_end(&mut data)?;
+    Ok(data)
+}
+
+/// Entry name from a ustar header, including the `prefix` field.
+fn header_name(header: &[u8; BLOCK]) -> String {
+    let name = cstr(&header[0..100]);
+    if &header[257..262] == b"ustar" {
+        let prefix = cstr(&header[345..500]);
+        if !prefix.is_empty() {
+            return format!("{}/{}", prefix, name);
+        }
+    }
+    name
+}
+
+/// Octal size field, or the GNU base-256 encoding for large files.
+fn parse_size(field: &[u8]) -> Result<u64> {
+    if field[0] & 0x80 != 0 {
+        let mut size = u64::from(field[0] & 0x7f);
+        for &b in &field[1..] {
+            size = size
+                .checked_mul(256)
+                .and_then(|s| s.checked_add(u64::from(b)))
+                .context("tar entry size overflows")?;
+        }
+        return Ok(size);
+    }
+    let text = cstr(field);
+    let text = text.trim();
+    if text.is_empty() {
+        return Ok(0);
+    }
+    u64::from_str_radix(text, 8).with_context(|| format!("invalid tar size field '{}'", text))
+}
+
+/// `path` record from a pax extended header (`"<len> path=<value>\n"`).
+fn pax_path(data: &[u8]) -> Option<String> {
+    String::from_utf8_lossy(data).lines().find_map(|record| {
+        let (_, kv) = record.split_once(' ')?;
+        kv.strip_prefix("path=").map(str::to_string)
+    })
+}
+
+/// NUL-terminated string field.
+fn cstr(field: &[u8]) -> String {
+    let end = field.iter().position(|&b| b == 0).unwrap_or(field.len());
+    String::from_utf8_lossy(&field[..end]).into_owned()
+}
+
+/// Normalize an inner path to `/` separators, rejecting absolute paths and
+/// `..` components.
+fn normalize_path(name: &str) -> Option<String> {
+    let name = name.replace('\\', "/");
+    if name.starts_with('/') {
+        return None;
+    }
+    let mut parts = Vec::new();
+    for part in name.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => return None,
+            part => parts.push(part),
+        }
+    }
+    (!parts.is_empty()).then(|| parts.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn tar(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut out = Vec::new();
+        for (name, data) in files {
+            let mut header = [0u8; BLOCK];
+            header[..name.len()].copy_from_slice(name.as_bytes());
+            header[124..135].copy_from_slice(format!("{:011o}", data.len()).as_bytes());
+            header[156] = b'0';
+            header[257..263].copy_from_slice(b"ustar\0");
+            out.extend_from_slice(&header);
+            out.extend_from_slice(data);
+            out.resize(out.len().div_ceil(BLOCK) * BLOCK, 0);
+        }
+        out.resize(out.len() + 2 * BLOCK, 0);
+        out
+    }
+
+    fn zip(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, data) in files {
+            writer
+                .start_file(*name, zip::write::SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(data).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    fn config(max_depth: usize) -> ArchiveConfig {
+        ArchiveConfig {
+            enabled: true,
+            max_depth,
+            ..ArchiveConfig::default()
+        }
+    }
+
+    fn paths(contents: &ArchiveContents) -> Vec<&str> {
+        contents.entries.iter().map(|e| e.path.as_str()).collect()
+    }
+
+    #[test]
+    fn reads_tar_gz_and_zip() {
+        let raw = tar(&[("./docs/a.md", b"# A"), ("../evil.md", b"x")]);
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+        gz.write_all(&raw).unwrap();
+        let contents = read_archive("v1.tar.gz", &gz.finish().unwrap(), &config(1)).unwrap();
+        assert_eq!(paths(&contents), vec!["docs/a.md"]);
+        assert_eq!(contents.entries[0].bytes, b"# A");
+
+        let contents = read_archive("b.zip", &zip(&[("x/y.txt", b"hi")]), &config(1)).unwrap();
+        assert_eq!(paths(&contents), vec!["x/y.txt"]);
+    }
+
+    #[test]
+    fn nested_archives_respect_max_depth() {
+        let inner = tar(&[("ref.md", b"ref")]);
+        let outer = zip(&[("api.tar", &inner), ("readme.md", b"hi")]);
+
+        let contents = read_archive("bundle.zip", &outer, &config(1)).unwrap();
+        assert_eq!(paths(&contents), vec!["readme.md"]);
+
+        let contents = read_archive("bundle.zip", &outer, &config(2)).unwrap();
+        assert_eq!(paths(&contents), vec!["api.tar!/ref.md", "readme.md"]);
+    }
+
+    #[test]
+    fn size_limits_skip_entries_and_reject_bombs() {
+        let archive = tar(&[("big.md", &[b'a'; 100]), ("small.md", b"ok")]);
+        let mut limits = config(1);
+        limits.max_entry_bytes = 50;
+        let contents = read_archive("x.tar", &archive, &limits).unwrap();
+        assert_eq!(paths(&contents), vec!["small.md"]);
+        assert_eq!(contents.too_large, 1);
+
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+        gz.write_all(&tar(&[("zeros.md", &[0u8; 20_000])])).unwrap();
+        let mut limits = config(1);
+        limits.max_total_bytes = 10_000;
+        let err = read_archive("bomb.tgz", &gz.finish().unwrap(), &limits).unwrap_err();
+        assert!(
+            format!("{:#}", err).contains("max_total_bytes"),
+            "{:#}",
+            err
+        );
+    }
+}
@@ -0,0 +1,300 @@
+//! RAG-ready context blocks: `ctx search --context N` and the
+//! `context.assemble` tool.
+//!
+//! Agents usually build prompt context from search results by hand:
+//! fetch each chunk, note where it came from, and stop before the prompt
+//! grows too long. [`assemble_context`] does this in one step. It loads the
+//! full text of each result's best-matching chunk and joins the chunks in
+//! rank order, each under a numbered citation:
+//!
+//! ```text
+//! [1] Deploy runbook — git:platform / runbooks/deploy.md § Deploy > Rollback
+//! https://github.com/acme/platform/blob/main/runbooks/deploy.md
+//!
+//! To roll back, redeploy the previous tag with ...
+//!
+//! [2] Incident 4127 — script:jira / OPS-4127
+//!
+//! The rollback stalled because ...
+//! ```
+//!
+//! The block stays within a token budget, estimated at four characters per
+//! token. The chunk that crosses the budget is cut at a word boundary and
+//! ends with `…` (the first chunk is always included, cut if need be);
+//! later chunks are left out. Results whose chunk is not in the local
+//! database (federated indexes, title-search hits) contribute their snippet
+//! instead.
+//!
+//! A passage from a document stitched from several files (see
+//! [`crate::stitch`]) is cited by the file it came from.
+
+use anyhow::Result;
+use serde::Serialize;
+
+use context_harness_core::search::SearchResultItem;
+
+use crate::config::Config;
+use crate::db;
+
+/// Default token budget of an assembled block.
+pub const DEFAULT_MAX_TOKENS: usize = 2000;
+
+/// Token estimate used for the budget (same as chunking).
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Smallest remainder of the budget worth filling with a cut chunk, in tokens.
+const MIN_PARTIAL_TOKENS: usize = 32;
+
+/// Where a numbered passage of the block came from.
+#[derive(Debug, Clone, Serialize)]
+pub struct Citation {
+    /// The `[n]` label of the passage.
+    pub index: usize,
+    /// Document UUID.
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chunk_id: Option<String>,
+    pub title: Option<String>,
+    pub source: String,
+    pub source_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub heading: Option<String>,
+    pub score: f64,
+}
+
+/// A context block ready to paste into a prompt.
+#[derive(Debug, Clone, Serialize)]
+pub struct AssembledContext {
+    /// The cited passages, separated by blank lines.
+    pub context: String,
+    /// Estimated tokens of `context`.
+    pub tokens: usize,
+    /// Whether the budget cut a passage or left results out.
+    pub truncated: bool,
+    /// One entry per passage in `context`, in order.
+    pub citations: Vec<Citation>,
+}
+
+/// Assemble the best chunks of `results`, in order, into one block of at
+/// most `max_tokens` estimated tokens.
+pub async fn assemble_context(
+    config: &Config,
+    results: &[SearchResultItem],
+    max_tokens: usize,
+) -> Result<AssembledContext> {
+    let pool = db::connect_reader(config).await?;
+    let mut passages = Vec::with_capacity(results.len());
+    for result in results {
+        let text: Option<String> = match result.chunk_id {
+            Some(ref chunk_id) => {
+                sqlx::query_scalar("SELECT text FROM chunks WHERE id = ?")
+                    .bind(chunk_id)
+                    .fetch_optional(&pool)
+                    .await?
+            }
+            None => None,
+        };
+        passages.push(text.unwrap_or_else(|| result.snippet.clone()));
+    }
+    db::close_reader(config, pool).await;
+    Ok(assemble(results, &passages, max_tokens))
+}
+
+/// Join `passages` (one per result) under their citations within the budget.
+fn assemble(
+    results: &[SearchResultItem],
+    passages: &[String],
+    max_tokens: usize,
+) -> AssembledContext {
+    let budget = max_tokens * CHARS_PER_TOKEN;
+    let mut context = String::new();
+    let mut citations = Vec::new();
+    let mut truncated = false;
+
+    for (result, passage) in results.iter().zip(passages) {
+        let separator = if context.is_empty() { "" } else { "\n\n" };
+        let header = citation_header(citations.len() + 1, result);
+        let used = context.chars().count() + separator.len() + header.chars().count();
+        let remaining = budget.saturating_sub(used);
+        let passage = passage.trim();
+        let passage_chars = passage.chars().count();
+
+        let text = if passage_chars <= remaining {
+            passage.to_string()
+        } else {
+            truncated = true;
+            // The first passage is always cut to fit; later ones only when
+            // enough of the budget is left to be useful.
+            if !citations.is_empty() && remaining < MIN_PARTIAL_TOKENS * CHARS_PER_TOKEN {
+                break;
+            }
+            cut_at_word(passage, remaining.saturating_sub(1))
+        };
+
+        context.push_str(separator);
+        context.push_str(&header);
+        context.push_str(&text);
+        citations.push(Citation {
+            index: citations.len() + 1,
+            id: result.id.clone(),
+            chunk_id: result.chunk_id.clone(),
+            title: result.title.clone(),
+            source: result.source.clone(),
+            source_id: cited_source_id(result).to_string(),
+            source_url: cited_source_url(result).map(str::to_string),
+            heading: result.heading.clone(),
+            score: result.score,
+        });
+        if truncated {
+            break;
+        }
+    }
+
+    AssembledContext {
+        tokens: context.chars().count().div_ceil(CHARS_PER_TOKEN),
+        context,
+        truncated,
+        citations,
+    }
+}
+
+/// `[n] Title — source / source_id § heading`, the URL on the next line,
+/// then a blank line.
+fn citation_header(index: usize, result: &SearchResultItem) -> String {
+    let mut header = format!("[{}] ", index);
+    if let Some(ref title) = result.title {
+        header.push_str(&format!("{} — ", title));
+    }
+    header.push_str(&format!("{} / {}", result.source, cited_source_id(result)));
+    if let Some(ref heading) = result.heading {
+        header.push_str(&format!(" § {}", heading));
+    }
+    header.push('\n');
+    if let Some(url) = cited_source_url(result) {
+        header.push_str(url);
+        header.push('\n');
+    }
+    header.push('\n');
+    header
+}
+
+/// The `source_id` a passage is cited by: the file a stitched document's
+/// chunk came from, otherwise the document's.
+fn cited_source_id(result: &SearchResultItem) -> &str {
+    result
+        .section_source_id
+        .as_deref()
+        .unwrap_or(&result.source_id)
+}
+
+/// The URL of the file a passage is cited by, if any.
+fn cited_source_url(result: &SearchResultItem) -> Option<&str> {
+    match result.section_source_id {
+        Some(_) => result.section_source_url.as_deref(),
+        None => result.source_url.as_deref(),
+    }
+}
+
+/// The longest prefix of `text` up to `max_chars` characters that ends at
+/// a word boundary, followed by `…`.
+fn cut_at_word(text: &str, max_chars: usize) -> String {
+    let end = text
+        .char_indices()
+        .nth(max_chars)
+        .map_or(text.len(), |(i, _)| i);
+    let prefix = &text[..end];
+    let prefix = match prefix.rfind(char::is_whitespace) {
+        Some(i) if i > 0 => &prefix[..i],
+        _ => prefix,
+    };
+    format!("{}…", prefix.trim_end())
+}
+
+/// Print an assembled block for `ctx search --context`.
+pub fn print_context(assembled: &AssembledContext) {
+    if assembled.citations.is_empty() {
+        println!("No results.");
+        return;
+    }
+    println!("{}", assembled.context);
+    if assembled.truncated {
+        eprintln!(
+            "context: {} passages, ~{} tokens (truncated to the budget)",
+            assembled.citations.len(),
+            assembled.tokens
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(id: &str, title: &str) -> SearchResultItem {
+        SearchResultItem {
+            id: id.to_string(),
+            score: 1.0,
+            title: Some(title.to_string()),
+            source: "filesystem:docs".to_string(),
+            source_id: format!("{}.md", id),
+            content_type: None,
+            updated_at: String::new(),
+            snippet: String::new(),
+            source_url: None,
+            heading: Some("Setup".to_string()),
+            section_source_id: None,
+            section_source_url: None,
+            chunk_id: None,
+            parent_id: None,
+            explain: None,
+            origin: None,
+            summary: None,
+            doc_type: None,
+        }
+    }
+
+    #[test]
+    fn passages_are_cited_in_order_within_the_budget() {
+        let results = [
+            result("a", "Alpha"),
+            result("b", "Beta"),
+            result("c", "Gamma"),
+        ];
+        let passages = [
+            "alpha text".to_string(),
+            "beta ".repeat(100),
+            "gamma".to_string(),
+        ];
+
+        let full = assemble(&results, &passages, 10_000);
+        assert!(!full.truncated);
+        assert_eq!(full.citations.len(), 3);
+        assert!(full
+            .context
+            .starts_with("[1] Alpha — filesystem:docs / a.md § Setup\n\nalpha text\n\n[2] Beta"));
+
+        let cut = assemble(&results, &passages, 100);
+        assert!(cut.truncated);
+        assert_eq!(cut.citations.len(), 2);
+        assert!(cut.context.ends_with("beta…"), "{}", cut.context);
+        assert!(cut.tokens <= 100);
+        assert!(!cut.context.contains("gamma"));
+    }
+
+    #[test]
+    fn stitched_passages_cite_their_file() {
+        let mut stitched = result("handbook", "Handbook");
+        stitched.source_id = "handbook/".to_string();
+        stitched.source_url = Some("https://docs.example.com/handbook".to_string());
+        stitched.section_source_id = Some("handbook/leave.md".to_string());
+
+        let block = assemble(&[stitched], &["Leave policy".to_string()], 1000);
+        assert!(block
+            .context
+            .starts_with("[1] Handbook — filesystem:docs / handbook/leave.md § Setup\n\n"));
+        assert_eq!(block.citations[0].source_id, "handbook/leave.md");
+        assert_eq!(block.citations[0].source_url, None);
+    }
+}
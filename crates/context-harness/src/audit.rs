@@ -0,0 +1,374 @@
+//! Tool invocation audit log.
+//!
+//! Records every tool call made through the HTTP server (`POST /tools/{name}`)
+//! and the MCP endpoint (`tools/call`) when `[tools.audit] enabled = true`.
+//! Each entry captures:
+//!
+//! - the tool name and transport (`http`, `grpc`, or `mcp`; `tool` for
+//!   calls one tool makes to another through `context.call_tool`),
+//! - the caller identity — a fingerprint of the API key presented in the
+//!   `Authorization: Bearer …` or `X-Api-Key` header (the key itself is
+//!   never stored), falling back to the MCP client name,
+//! - the call parameters, with configured keys redacted,
+//! - the wall-clock duration and outcome (`ok` / `error` plus message).
+//!
+//! Documents removed from the index because their source item is gone
+//! (see [`crate::ingest::delete_source_items`]) are recorded too, as tool
+//! `delete` with transport `sync` and the `source`, `source_id`, and deleted
+//! document IDs as parameters. Documents deleted with `ctx rm` (see
+//! [`crate::rm`]) are recorded as tool `delete` with transport `cli`.
+//!
+//! # Sinks
+//!
+//! | Sink | Storage |
+//! |------|---------|
+//! | `sqlite` | `tool_audit` table in the main database (default) |
+//! | `jsonl` | One JSON object per line, appended to `tools.audit.path` |
+//!
+//! Audit failures never fail the tool call — they are reported on stderr.
+//!
+//! # Querying
+//!
+//! ```bash
+//! ctx tool log                      # 50 most recent calls
+//! ctx tool log --tool search -n 10  # filter by tool
+//! ctx tool log --json               # machine-readable output
+//! ```
+
+use anyhow::{Context, Result};
+use axum::http::HeaderMap;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::{Row, SqlitePool};
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::auth;
+use crate::config::Config;
+use crate::db;
+use crate::migrate;
+use crate::scrub;
+
+/// Replacement value for redacted parameters.
+const REDACTED: &str = "[REDACTED]";
+
+/// A single recorded tool invocation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Unix timestamp (seconds) when the call started.
+    pub ts: i64,
+    /// Tool name as called.
+    pub tool: String,
+    /// Caller identity (`key:<fingerprint>` or `client:<name>`), if known.
+    pub caller: Option<String>,
+    /// Transport the call arrived on: `"http"`, `"grpc"`, or `"mcp"`; `"tool"` for
+    /// nested tool calls, `"sync"` for index deletions, and `"cli"` for
+    /// `ctx rm`.
+    pub transport: String,
+    /// Call parameters after redaction.
+    pub params: serde_json::Value,
+    /// Execution time in milliseconds.
+    pub duration_ms: u64,
+    /// `"ok"` or `"error"`.
+    pub outcome: String,
+    /// Error message when `outcome` is `"error"`.
+    pub error: Option<String>,
+}
+
+impl AuditEntry {
+    /// Build an entry from a completed call.
+    pub fn new(
+        tool: &str,
+        caller: Option<String>,
+        transport: &str,
+        params: serde_json::Value,
+        duration: Duration,
+        error: Option<String>,
+    ) -> Self {
+        Self {
+            ts: chrono::Utc::now().timestamp() - duration.as_secs() as i64,
+            tool: tool.to_string(),
+            caller,
+            transport: transport.to_string(),
+            params,
+            duration_ms: duration.as_millis() as u64,
+            outcome: if error.is_some() { "error" } else { "ok" }.to_string(),
+            error,
+        }
+    }
+}
+
+enum AuditSink {
+    Sqlite(SqlitePool),
+    Jsonl { path: PathBuf, lock: Mutex<()> },
+}
+
+/// Open handle to the configured audit sink.
+///
+/// Created once at server startup and shared between the HTTP handlers
+/// and the MCP bridge.
+pub struct AuditLog {
+    sink: AuditSink,
+    redact: Vec<String>,
+}
+
+impl AuditLog {
+    /// Open the audit sink described by `[tools.audit]`.
+    ///
+    /// Returns `Ok(None)` when auditing is disabled. For the SQLite sink
+    /// the `tool_audit` table is created if it does not exist yet.
+    pub async fn open(config: &Config) -> Result<Option<Self>> {
+        let audit = &config.tools.audit;
+        if !audit.enabled {
+            return Ok(None);
+        }
+
+        let sink = match audit.sink.as_str() {
+            "jsonl" => {
+                let path = jsonl_path(config);
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                AuditSink::Jsonl {
+                    path,
+                    lock: Mutex::new(()),
+                }
+            }
+            _ => {
+                let pool = db::connect(config).await?;
+                migrate::create_tool_audit_table(&pool).await?;
+                AuditSink::Sqlite(pool)
+            }
+        };
+
+        Ok(Some(Self {
+            sink,
+            redact: audit.redact.clone(),
+        }))
+    }
+
+    /// Record a tool call. Parameters are redacted before writing.
+    ///
+    /// Errors are logged to stderr and otherwise ignored so that a broken
+    /// audit sink never turns into a failed tool call.
+    pub async fn record(&self, mut entry: AuditEntry) {
+        entry.params = redact_params(&entry.params, &self.redact);
+        entry.error = entry.error.as_deref().map(scrub::scrub);
+        if let Err(e) = self.write(&entry).await {
+            tracing::warn!("failed to write tool audit entry: {}", e);
+        }
+    }
+
+    async fn write(&self, entry: &AuditEntry) -> Result<()> {
+        match &self.sink {
+            AuditSink::Sqlite(pool) => {
+                sqlx::query(
+                    "INSERT INTO tool_audit \
+                     (ts, tool, caller, transport, params_json, duration_ms, outcome, error) \
+                     VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+                )
+                .bind(entry.ts)
+                .bind(&entry.tool)
+                .bind(&entry.caller)
+                .bind(&entry.transport)
+                .bind(serde_json::to_string(&entry.params)?)
+                .bind(entry.duration_ms as i64)
+                .bind(&entry.outcome)
+                .bind(&entry.error)
+                .execute(pool)
+                .await?;
+            }
+            AuditSink::Jsonl { path, lock } => {
+                let line = serde_json::to_string(entry)?;
+                let _guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+                let mut file = std::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .with_context(|| format!("opening {}", path.display()))?;
+                writeln!(file, "{}", line)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Resolve the JSONL sink path: `tools.audit.path`, or `tool-audit.jsonl`
+/// next to the database file.
+fn jsonl_path(config: &Config) -> PathBuf {
+    config
+        .tools
+        .audit
+        .path
+        .clone()
+        .unwrap_or_else(|| config.db.path.with_file_name("tool-audit.jsonl"))
+}
+
+/// Replace the values of sensitive keys with `"[REDACTED]"`.
+///
+/// Key matching is case-insensitive and recurses into nested objects and
+/// arrays, so `{"auth": {"Token": "…"}}` is redacted by a `token` rule.
+pub fn redact_params(params: &serde_json::Value, keys: &[String]) -> serde_json::Value {
+    match params {
+        serde_json::Value::Object(map) => {
+            let redacted = map
+                .iter()
+                .map(|(k, v)| {
+                    if keys.iter().any(|r| r.eq_ignore_ascii_case(k)) {
+                        (k.clone(), serde_json::Value::String(REDACTED.to_string()))
+                    } else {
+                        (k.clone(), redact_params(v, keys))
+                    }
+                })
+                .collect();
+            serde_json::Value::Object(redacted)
+        }
+        serde_json::Value::Array(items) => {
+            serde_json::Value::Array(items.iter().map(|v| redact_params(v, keys)).collect())
+        }
+        other => other.clone(),
+    }
+}
+
+/// Derive the caller identity from request headers.
+///
+/// Looks for `Authorization: Bearer <key>` or `X-Api-Key: <key>` and
+/// returns `key:<first 12 hex chars of SHA-256(key)>`. The key itself is
+/// never recorded.
+pub fn caller_from_headers(headers: &HeaderMap) -> Option<String> {
+    auth::presented_key(headers).map(|key| key_fingerprint(&key))
+}
+
+/// Short, stable fingerprint of an API key: `key:<12 hex chars>`.
+pub fn key_fingerprint(key: &str) -> String {
+    let digest = Sha256::digest(key.as_bytes());
+    format!("key:{}", &hex::encode(digest)[..12])
+}
+
+/// Read audit entries, newest first.
+///
+/// Reads from the configured sink regardless of `enabled`, so past logs
+/// remain queryable after auditing is switched off.
+pub async fn read_entries(
+    config: &Config,
+    tool: Option<&str>,
+    limit: usize,
+) -> Result<Vec<AuditEntry>> {
+    match config.tools.audit.sink.as_str() {
+        "jsonl" => {
+            let path = jsonl_path(config);
+            if !path.exists() {
+                return Ok(Vec::new());
+            }
+            let file = std::fs::File::open(&path)
+                .with_context(|| format!("opening {}", path.display()))?;
+            let mut entries: Vec<AuditEntry> = Vec::new();
+            for line in std::io::BufReader::new(file).lines() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let entry: AuditEntry = match serde_json::from_str(&line) {
+                    Ok(e) => e,
+                    Err(_) => continue,
+                };
+                if tool.is_some_and(|t| t != entry.tool) {
+                    continue;
+                }
+                entries.push(entry);
+            }
+            entries.reverse();
+            entries.truncate(limit);
+            Ok(entries)
+        }
+        _ => {
+            let pool = db::connect(config).await?;
+            migrate::create_tool_audit_table(&pool).await?;
+            let rows = sqlx::query(
+                "SELECT ts, tool, caller, transport, params_json, duration_ms, outcome, error \
+                 FROM tool_audit \
+                 WHERE (? IS NULL OR tool = ?) \
+                 ORDER BY ts DESC, id DESC \
+                 LIMIT ?",
+            )
+            .bind(tool)
+            .bind(tool)
+            .bind(limit as i64)
+            .fetch_all(&pool)
+            .await?;
+            pool.close().await;
+
+            Ok(rows
+                .iter()
+                .map(|row| {
+                    let params_json: String = row.get("params_json");
+                    let duration_ms: i64 = row.get("duration_ms");
+                    AuditEntry {
+                        ts: row.get("ts"),
+                        tool: row.get("tool"),
+                        caller: row.get("caller"),
+                        transport: row.get("transport"),
+                        params: serde_json::from_str(&params_json)
+                            .unwrap_or(serde_json::Value::Null),
+                        duration_ms: duration_ms as u64,
+                        outcome: row.get("outcome"),
+                        error: row.get("error"),
+                    }
+                })
+                .collect())
+        }
+    }
+}
+
+/// CLI entry point for `ctx tool log`.
+pub async fn run_tool_log(
+    config: &Config,
+    tool: Option<&str>,
+    limit: usize,
+    json: bool,
+) -> Result<()> {
+    let entries = read_entries(config, tool, limit).await?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+        return Ok(());
+    }
+
+    if !config.tools.audit.enabled {
+        eprintln!(
+            "Note: tool auditing is disabled. Set [tools.audit] enabled = true to record calls."
+        );
+    }
+
+    if entries.is_empty() {
+        println!("No tool calls recorded.");
+        return Ok(());
+    }
+
+    println!(
+        "{:<17} {:<20} {:<18} {:<5} {:>8}  {:<7} PARAMS",
+        "TIME", "TOOL", "CALLER", "VIA", "MS", "OUTCOME"
+    );
+    for e in &entries {
+        let time = chrono::DateTime::from_timestamp(e.ts, 0)
+            .map(|dt| dt.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|| e.ts.to_string());
+        println!(
+            "{:<17} {:<20} {:<18} {:<5} {:>8}  {:<7} {}",
+            time,
+            e.tool,
+            e.caller.as_deref().unwrap_or("-"),
+            e.transport,
+            e.duration_ms,
+            e.outcome,
+            e.params
+        );
+        if let Some(ref msg) = e.error {
+            println!("{:<17} error: {}", "", msg);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,202 @@
+//! API keys and per-key source access for the HTTP server.
+//!
+//! When `[[server.auth.keys]]` are configured, `ctx serve mcp` requires one
+//! of them on every request except the `/health`, `/livez`, `/readyz`, and
+//! `/metrics` probes, sent as `Authorization: Bearer <key>` or
+//! `X-Api-Key: <key>`. A missing or unknown key gets `401 unauthorized`.
+//!
+//! # Source scopes
+//!
+//! A key's `allowed_sources` patterns, plus the patterns of its
+//! `allowed_collections`, form its [`SourceScope`]. A key without either
+//! list is unscoped and sees everything. For a scoped key:
+//!
+//! | Call | Behavior |
+//! |------|----------|
+//! | `search` | Results are limited to the scope; a `source` filter outside it is `403 forbidden` |
+//! | `get` | A document outside the scope is `403 forbidden` (batch mode: a per-id error) |
+//! | `sources`, `overview`, `capabilities` | Only sources (and collections) inside the scope are listed and counted |
+//! | Lua and custom Rust tools and agents | `403 forbidden` — they read the database directly |
+//! | `POST /admin/reload`, `/admin/jobs` | `403 forbidden` |
+//!
+//! Tool and agent lists only show what the key may call. The same rules
+//! apply to `/tools/{name}`, `/rpc`, and `/mcp`.
+//!
+//! Keys are compared by SHA-256 digest and never logged.
+
+use anyhow::{bail, Result};
+use axum::http::HeaderMap;
+use context_harness_core::search::source_matches;
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+
+use crate::config::ServerAuthConfig;
+use crate::secrets;
+
+/// Prefix of errors for requests outside a key's scope; mapped to `403`.
+pub const FORBIDDEN: &str = "forbidden";
+
+/// Source patterns an API key may read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceScope {
+    patterns: Vec<String>,
+}
+
+impl SourceScope {
+    /// A scope allowing the sources matching `patterns`.
+    pub fn new(patterns: Vec<String>) -> Self {
+        Self { patterns }
+    }
+
+    /// The patterns, as passed to search as `allowed_sources`.
+    pub fn patterns(&self) -> &[String] {
+        &self.patterns
+    }
+
+    /// Whether `source` (e.g. `git:platform`) is inside the scope.
+    pub fn allows(&self, source: &str) -> bool {
+        source_matches(&self.patterns, source)
+    }
+
+    /// Fail with a `forbidden` error unless document `id` from `source` is
+    /// inside the scope.
+    pub fn check_document(&self, id: &str, source: &str) -> Result<()> {
+        if !self.allows(source) {
+            bail!(
+                "{}: document {} is outside the sources this API key may read",
+                FORBIDDEN,
+                id
+            );
+        }
+        Ok(())
+    }
+}
+
+/// An authenticated API key.
+#[derive(Debug, Clone)]
+pub struct ApiKey {
+    /// `name` from `[[server.auth.keys]]`.
+    pub name: String,
+    /// `None` when the key may read every source.
+    pub scope: Option<Arc<SourceScope>>,
+}
+
+/// The configured API keys, with `secret://` references resolved.
+pub struct ApiKeys {
+    keys: Vec<([u8; 32], ApiKey)>,
+}
+
+impl ApiKeys {
+    /// Resolve `[server.auth]`, or `None` when no keys are configured.
+    pub fn from_config(auth: &ServerAuthConfig) -> Result<Option<Self>> {
+        if auth.keys.is_empty() {
+            return Ok(None);
+        }
+        let mut keys = Vec::with_capacity(auth.keys.len());
+        for key in &auth.keys {
+            let secret = secrets::resolve(&key.key)?;
+            let scoped = !key.allowed_sources.is_empty() || !key.allowed_collections.is_empty();
+            let scope = scoped.then(|| {
+                let mut patterns = key.allowed_sources.clone();
+                for collection in &key.allowed_collections {
+                    patterns.extend(
+                        auth.collections
+                            .get(collection)
+                            .into_iter()
+                            .flatten()
+                            .cloned(),
+                    );
+                }
+                Arc::new(SourceScope::new(patterns))
+            });
+            keys.push((
+                digest(&secret),
+                ApiKey {
+                    name: key.name.clone(),
+                    scope,
+                },
+            ));
+        }
+        Ok(Some(Self { keys }))
+    }
+
+    /// The key presented in `headers`, if it is one of the configured keys.
+    pub fn authenticate(&self, headers: &HeaderMap) -> Option<&ApiKey> {
+        let presented = digest(&presented_key(headers)?);
+        self.keys
+            .iter()
+            .find(|(key, _)| *key == presented)
+            .map(|(_, key)| key)
+    }
+}
+
+/// The API key sent as `X-Api-Key: <key>` or `Authorization: Bearer <key>`.
+pub fn presented_key(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get("x-api-key")
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.trim().to_string())
+        .or_else(|| {
+            headers
+                .get("authorization")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|s| s.trim().strip_prefix("Bearer "))
+                .map(|s| s.trim().to_string())
+        })
+        .filter(|k| !k.is_empty())
+}
+
+fn digest(key: &str) -> [u8; 32] {
+    Sha256::digest(key.as_bytes()).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ApiKeyConfig;
+
+    #[test]
+    fn keys_resolve_scopes_from_sources_and_collections() {
+        let auth = ServerAuthConfig {
+            keys: vec![
+                ApiKeyConfig {
+                    name: "eng".to_string(),
+                    key: "eng-key".to_string(),
+                    allowed_sources: vec!["filesystem:docs".to_string()],
+                    allowed_collections: vec!["code".to_string()],
+                },
+                ApiKeyConfig {
+                    name: "admin".to_string(),
+                    key: "admin-key".to_string(),
+                    allowed_sources: Vec::new(),
+                    allowed_collections: Vec::new(),
+                },
+            ],
+            collections: [("code".to_string(), vec!["git:*".to_string()])].into(),
+        };
+        let keys = ApiKeys::from_config(&auth).unwrap().unwrap();
+
+        let mut headers = HeaderMap::new();
+        headers.insert("authorization", "Bearer eng-key".parse().unwrap());
+        let eng = keys.authenticate(&headers).unwrap();
+        assert_eq!(eng.name, "eng");
+        let scope = eng.scope.as_ref().unwrap();
+        assert!(scope.allows("filesystem:docs") && scope.allows("git:platform"));
+        assert!(!scope.allows("filesystem:hr"));
+        assert!(scope
+            .check_document("d1", "filesystem:hr")
+            .unwrap_err()
+            .to_string()
+            .starts_with(FORBIDDEN));
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-api-key", "admin-key".parse().unwrap());
+        assert!(keys.authenticate(&headers).unwrap().scope.is_none());
+
+        headers.insert("x-api-key", "wrong".parse().unwrap());
+        assert!(keys.authenticate(&headers).is_none());
+        assert!(ApiKeys::from_config(&ServerAuthConfig::default())
+            .unwrap()
+            .is_none());
+    }
+}
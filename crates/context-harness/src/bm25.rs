@@ -0,0 +1,286 @@
+//! BM25 with configurable `k1` and `b` for the FTS5 indexes.
+//!
+//! FTS5's `bm25()` takes per-column weights but fixes `k1 = 1.2` and
+//! `b = 0.75`. With other `[retrieval.bm25]` values, keyword search takes
+//! the best [`RESCORE_POOL`] × limit matches by `bm25()` and rescores them
+//! with the same formula and the configured constants:
+//!
+//! ```text
+//! score = Σ idf(p) × f(p) × (k1 + 1) / (f(p) + k1 × (1 − b + b × len / avg_len))
+//! idf(p) = ln((N − n(p) + 0.5) / (n(p) + 0.5))
+//! ```
+//!
+//! summed over the phrases `p` of the query (each word of an unquoted
+//! query is a phrase). `f(p)` is the phrase's occurrences in the row, each
+//! counted with its column's `[retrieval.weights]` weight; `len` is the
+//! row's length in tokens over all columns, `avg_len` the index average,
+//! `N` the number of rows, and `n(p)` the rows matching `p`. Occurrences
+//! come from `highlight()` and lengths from the index's `_docsize` and
+//! `_data` tables, so they follow the index tokenizer.
+//!
+//! Column weights scale `f(p)` before saturation: with a low `k1`, a
+//! heavily weighted title match saturates almost at once, so raising
+//! `title` does less than with the default `k1`. `b` uses the unweighted
+//! length of all columns, so long summaries count against a row even with
+//! `summary = 0`.
+//!
+//! With the default constants `bm25()` ranks on its own and nothing is
+//! rescored.
+
+use anyhow::Result;
+use sqlx::{Row, SqlitePool};
+use std::collections::HashMap;
+
+use crate::config::Bm25Params;
+
+/// Matches rescored per result wanted when `k1` or `b` is not FTS5's.
+pub const RESCORE_POOL: i64 = 5;
+
+/// Rows bound per `IN (...)` list.
+const BATCH: usize = 500;
+
+/// An FTS5 table and its number of columns, indexed or not.
+#[derive(Debug, Clone, Copy)]
+pub struct FtsTable {
+    pub name: &'static str,
+    pub columns: usize,
+}
+
+/// `chunks_fts(chunk_id, document_id, text, title, summary)`.
+pub const CHUNKS: FtsTable = FtsTable {
+    name: "chunks_fts",
+    columns: 5,
+};
+
+/// `titles_fts(document_id, title, headings)`.
+pub const TITLES: FtsTable = FtsTable {
+    name: "titles_fts",
+    columns: 3,
+};
+
+/// BM25 scores (higher is better) of the `rowids` of `table` for the FTS5
+/// query `fts_query`. `weights` pairs each scored column's index with its
+/// weight. Rows without a match score `0.0`.
+pub async fn scores(
+    pool: &SqlitePool,
+    table: FtsTable,
+    weights: &[(usize, f64)],
+    params: Bm25Params,
+    fts_query: &str,
+    rowids: &[i64],
+) -> Result<HashMap<i64, f64>> {
+    let mut scores: HashMap<i64, f64> = rowids.iter().map(|&id| (id, 0.0)).collect();
+    let Some((rows, avg_len)) = averages(pool, table).await? else {
+        return Ok(scores);
+    };
+    let lengths = row_lengths(pool, table, rowids).await?;
+
+    let highlights: Vec<String> = weights
+        .iter()
+        .map(|(col, _)| format!("highlight({}, {}, char(1), '')", table.name, col))
+        .collect();
+    for phrase in query_phrases(fts_query) {
+        let hits: i64 = sqlx::query_scalar(&format!(
+            "SELECT count(*) FROM {0} WHERE {0} MATCH ?",
+            table.name
+        ))
+        .bind(&phrase)
+        .fetch_one(pool)
+        .await?;
+        let idf = idf(rows, hits);
+
+        for batch in rowids.chunks(BATCH) {
+            let sql = format!(
+                "SELECT rowid, {} FROM {1} WHERE {1} MATCH ? AND rowid IN ({2})",
+                highlights.join(", "),
+                table.name,
+                vec!["?"; batch.len()].join(", ")
+            );
+            let mut query = sqlx::query(&sql).bind(&phrase);
+            for id in batch {
+                query = query.bind(id);
+            }
+            for row in query.fetch_all(pool).await? {
+                let rowid: i64 = row.get(0);
+                let freq: f64 = weights
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (_, weight))| {
+                        let text: Option<String> = row.get(i + 1);
+                        weight * text.map_or(0, |t| t.matches('\u{1}').count()) as f64
+                    })
+                    .sum();
+                let len = lengths.get(&rowid).copied().unwrap_or(0) as f64;
+                *scores.entry(rowid).or_default() += term_score(params, idf, freq, len, avg_len);
+            }
+        }
+    }
+    Ok(scores)
+}
+
+/// `idf(p)` as FTS5 computes it, floored just above zero so that a phrase
+/// in more than half the rows still counts a little.
+fn idf(rows: u64, hits: i64) -> f64 {
+    let (n, hits) = (rows as f64, hits as f64);
+    let idf = ((n - hits + 0.5) / (hits + 0.5)).ln();
+    if idf <= 0.0 {
+        1e-6
+    } else {
+        idf
+    }
+}
+
+/// One phrase's contribution to a row's score.
+fn term_score(params: Bm25Params, idf: f64, freq: f64, len: f64, avg_len: f64) -> f64 {
+    let norm = if avg_len > 0.0 {
+        1.0 - params.b + params.b * len / avg_len
+    } else {
+        1.0
+    };
+    idf * freq * (params.k1 + 1.0) / (freq + params.k1 * norm)
+}
+
+/// The row count and average row length (tokens over all columns) from
+/// the averages record of `table`, or `None` for an empty index.
+async fn averages(pool: &SqlitePool, table: FtsTable) -> Result<Option<(u64, f64)>> {
+    let block: Option<Vec<u8>> = sqlx::query_scalar(&format!(
+        "SELECT block FROM {}_data WHERE id = 1",
+        table.name
+    ))
+    .fetch_optional(pool)
+    .await?;
+    let Some(block) = block else {
+        return Ok(None);
+    };
+    let mut pos = 0;
+    let rows = read_varint(&block, &mut pos).unwrap_or(0);
+    let total: u64 = (0..table.columns)
+        .map_while(|_| read_varint(&block, &mut pos))
+        .sum();
+    if rows == 0 {
+        return Ok(None);
+    }
+    Ok(Some((rows, total as f64 / rows as f64)))
+}
+
+/// Length in tokens over all columns of each of `rowids`.
+async fn row_lengths(
+    pool: &SqlitePool,
+    table: FtsTable,
+    rowids: &[i64],
+) -> Result<HashMap<i64, u64>> {
+    let mut lengths = HashMap::new();
+    for batch in rowids.chunks(BATCH) {
+        let sql = format!(
+            "SELECT id, sz FROM {}_docsize WHERE id IN ({})",
+            table.name,
+            vec!["?"; batch.len()].join(", ")
+        );
+        let mut query = sqlx::query_as::<_, (i64, Vec<u8>)>(&sql);
+        for id in batch {
+            query = query.bind(id);
+        }
+        for (id, sizes) in query.fetch_all(pool).await? {
+            let mut pos = 0;
+            let len: u64 = (0..table.columns)
+                .map_while(|_| read_varint(&sizes, &mut pos))
+                .sum();
+            lengths.insert(id, len);
+        }
+    }
+    Ok(lengths)
+}
+
+/// Read an SQLite varint (as used by FTS5 records) at `*pos`.
+fn read_varint(buf: &[u8], pos: &mut usize) -> Option<u64> {
+    let mut value = 0u64;
+    for i in 0..9 {
+        let byte = *buf.get(*pos)?;
+        *pos += 1;
+        if i == 8 {
+            return Some((value << 8) | u64::from(byte));
+        }
+        value = (value << 7) | u64::from(byte & 0x7f);
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+/// The phrases of an FTS5 query as built by
+/// [`crate::sqlite_store::SqliteStore`]: quoted strings and bare words,
+/// without `AND`/`OR`/`NOT` and parentheses, each quoted.
+fn query_phrases(fts_query: &str) -> Vec<String> {
+    let mut phrases = Vec::new();
+    let mut chars = fts_query.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '"' {
+            let mut phrase = String::new();
+            while let Some(c) = chars.next() {
+                if c == '"' {
+                    if chars.peek() == Some(&'"') {
+                        chars.next();
+                        phrase.push_str("\"\"");
+                        continue;
+                    }
+                    break;
+                }
+                phrase.push(c);
+            }
+            phrases.push(format!("\"{}\"", phrase));
+        } else if c.is_alphanumeric() || c == '_' {
+            let mut word = c.to_string();
+            while let Some(&c) = chars.peek() {
+                if !(c.is_alphanumeric() || c == '_') {
+                    break;
+                }
+                word.push(c);
+                chars.next();
+            }
+            if !matches!(word.as_str(), "AND" | "OR" | "NOT") {
+                phrases.push(format!("\"{}\"", word));
+            }
+        }
+    }
+    phrases
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn phrases_of_plain_fuzzy_and_quoted_queries() {
+        assert_eq!(
+            query_phrases(r#"deploy "blue green" runbook"#),
+            vec![r#""deploy""#, r#""blue green""#, r#""runbook""#]
+        );
+        assert_eq!(
+            query_phrases(r#"("deploy" OR "deploys") AND ("say ""hi""")"#),
+            vec![r#""deploy""#, r#""deploys""#, r#""say ""hi""""#]
+        );
+    }
+
+    #[test]
+    fn varints_match_sqlite_encoding() {
+        let mut pos = 0;
+        let buf = [0x05, 0x81, 0x00, 0xff, 0x7f];
+        assert_eq!(read_varint(&buf, &mut pos), Some(5));
+        assert_eq!(read_varint(&buf, &mut pos), Some(128));
+        assert_eq!(read_varint(&buf, &mut pos), Some(0x3fff));
+        assert_eq!(read_varint(&buf, &mut pos), None);
+    }
+
+    #[test]
+    fn lower_b_penalizes_long_rows_less() {
+        let default = Bm25Params { k1: 1.2, b: 0.75 };
+        let flat = Bm25Params { k1: 1.2, b: 0.0 };
+        let long = term_score(default, 1.0, 2.0, 200.0, 50.0);
+        assert!(term_score(flat, 1.0, 2.0, 200.0, 50.0) > long);
+        assert_eq!(
+            term_score(flat, 1.0, 2.0, 200.0, 50.0),
+            term_score(flat, 1.0, 2.0, 10.0, 50.0)
+        );
+    }
+}
@@ -0,0 +1,194 @@
+//! What this index can do: the `capabilities` tool and `GET /capabilities`.
+//!
+//! Agents otherwise learn the search modes, filter names, and inline query
+//! syntax by trial and error. [`capabilities`] reports them together with
+//! what is specific to this deployment:
+//!
+//! - the search modes that work, which depends on whether `[embedding]` has
+//!   a provider, and how much of the corpus is embedded;
+//! - the filters of the `search` tool and the inline modifiers of its query
+//!   (see [`crate::search::parse_query`]);
+//! - the configured sources and `[server.auth.collections]`, limited to an
+//!   API key's scope;
+//! - the federated indexes, when `[federation]` is enabled.
+//!
+//! The filter, modifier, and grouping lists mirror the `search` tool's
+//! parameters; change them together.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::auth::SourceScope;
+use crate::config::Config;
+use crate::db;
+use crate::search::{embedding_coverage, EmbeddingCoverage};
+use crate::sources::get_sources;
+
+/// A filter, modifier, or option with a one-line description.
+#[derive(Debug, Clone, Serialize)]
+pub struct Described {
+    pub name: &'static str,
+    pub description: &'static str,
+}
+
+/// The embedding side of search.
+#[derive(Debug, Clone, Serialize)]
+pub struct EmbeddingCapability {
+    /// Whether `[embedding]` has a provider; semantic and hybrid search
+    /// need one.
+    pub enabled: bool,
+    pub provider: String,
+    pub model: Option<String>,
+    pub dims: Option<usize>,
+    /// How much of the readable corpus is embedded; absent when embeddings
+    /// are disabled.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coverage: Option<EmbeddingCoverage>,
+}
+
+/// Inline query syntax understood by the `search` tool.
+#[derive(Debug, Clone, Serialize)]
+pub struct QuerySyntax {
+    /// `name:value` modifiers that become filters.
+    pub modifiers: Vec<Described>,
+    pub phrases: &'static str,
+    pub example: &'static str,
+}
+
+/// The `capabilities` response.
+#[derive(Debug, Clone, Serialize)]
+pub struct Capabilities {
+    /// Search modes that work on this index.
+    pub modes: Vec<&'static str>,
+    pub default_mode: &'static str,
+    pub embeddings: EmbeddingCapability,
+    /// Keys of the `search` tool's `filters` object.
+    pub filters: Vec<Described>,
+    pub query_syntax: QuerySyntax,
+    /// Values of the `search` tool's `group_by`.
+    pub group_by: Vec<&'static str>,
+    /// Values of the `search` tool's `scope`.
+    pub scopes: Vec<Described>,
+    /// Configured sources the caller may read.
+    pub sources: Vec<String>,
+    /// `[server.auth.collections]` the caller may read, with their source
+    /// patterns.
+    pub collections: BTreeMap<String, Vec<String>>,
+    /// Federated index names, when `[federation]` is enabled.
+    pub federated_indexes: Vec<String>,
+}
+
+/// Describe the search capabilities of `config`'s index, limited to
+/// `scope` when the caller's API key has one.
+///
+/// # Errors
+///
+/// Returns an error if embeddings are enabled and their coverage can't be
+/// read from the database.
+pub async fn capabilities(config: &Config, scope: Option<&SourceScope>) -> Result<Capabilities> {
+    let enabled = config.embedding.is_enabled();
+    let coverage = if enabled {
+        let pool = db::connect_reader(config).await?;
+        let coverage =
+            embedding_coverage(&pool, None, None, None, scope.map(SourceScope::patterns)).await;
+        db::close_reader(config, pool).await;
+        Some(coverage?)
+    } else {
+        None
+    };
+    let modes = if enabled {
+        vec!["keyword", "semantic", "hybrid"]
+    } else {
+        vec!["keyword"]
+    };
+
+    let sources = get_sources(config)
+        .into_iter()
+        .map(|s| s.name)
+        .filter(|name| scope.is_none_or(|scope| scope.allows(name)))
+        .collect();
+    let collections = config
+        .server
+        .auth
+        .collections
+        .iter()
+        .filter(|(_, patterns)| {
+            scope.is_none_or(|scope| patterns.iter().all(|p| scope.patterns().contains(p)))
+        })
+        .map(|(name, patterns)| (name.clone(), patterns.clone()))
+        .collect();
+    let federated_indexes = if config.federation.enabled {
+        config.federation.indexes.keys().cloned().collect()
+    } else {
+        Vec::new()
+    };
+
+    Ok(Capabilities {
+        modes,
+        default_mode: "keyword",
+        embeddings: EmbeddingCapability {
+            enabled,
+            provider: config.embedding.provider.clone(),
+            model: config.embedding.model.clone(),
+            dims: config.embedding.dims,
+            coverage,
+        },
+        filters: vec![
+            Described {
+                name: "source",
+                description:
+                    "Connector source, e.g. git:platform; a bare type (git) matches each instance",
+            },
+            Described {
+                name: "since",
+                description: "Only documents updated on or after this date (YYYY-MM-DD)",
+            },
+            Described {
+                name: "as_of",
+                description:
+                    "Search the corpus as of this date (YYYY-MM-DD); later updates are excluded",
+            },
+            Described {
+                name: "metadata",
+                description: "Chunk metadata substring matches, e.g. {\"heading\": \"install\"}",
+            },
+        ],
+        query_syntax: QuerySyntax {
+            modifiers: vec![
+                Described {
+                    name: "source:<source>",
+                    description: "Same as filters.source",
+                },
+                Described {
+                    name: "after:<YYYY-MM-DD>",
+                    description: "Same as filters.since; since: is an alias",
+                },
+                Described {
+                    name: "before:<YYYY-MM-DD>",
+                    description: "Same as filters.as_of; until: is an alias",
+                },
+                Described {
+                    name: "type:<type>",
+                    description: "File extension (md), content type, or document type (runbook); repeat to allow several",
+                },
+            ],
+            phrases: "Double-quoted text matches as an exact phrase in keyword search",
+            example: "source:git after:2024-01-01 type:md \"blue-green\" rollback",
+        },
+        group_by: vec!["path_prefix", "source", "content_type"],
+        scopes: vec![
+            Described {
+                name: "chunks",
+                description: "Document content (default)",
+            },
+            Described {
+                name: "titles",
+                description: "Titles and headings only, one hit per document; keyword mode",
+            },
+        ],
+        sources,
+        collections,
+        federated_indexes,
+    })
+}
@@ -9,5 +9,71 @@
 //! assert_eq!(chunks.len(), 1);
 //! assert_eq!(chunks[0].chunk_index, 0);
 //! ```
+//!
+//! # Fingerprint
+//!
+//! [`config_fingerprint`] identifies the chunking settings that shape chunk
+//! text. It is recorded on every chunk and embedding row, computed from the
+//! effective settings of the chunk's source ([`crate::config::Config::chunking_for`]),
+//! so `ctx embed pending` can tell when vectors were built from chunks
+//! produced under different settings.
+//!
+//! # Embedding text
+//!
+//! [`embedding_text`] is what gets embedded for a chunk: its text, preceded
+//! by the `context` metadata written by [`crate::contextualize`] when
+//! `[chunking.contextualize]` is enabled.
 
 pub use context_harness_core::chunk::*;
+
+use sha2::{Digest, Sha256};
+
+use crate::config::ChunkingConfig;
+use crate::contextualize::CONTEXT_KEY;
+
+/// Version of the chunking algorithm; bump when its output changes.
+const CHUNKER_VERSION: u32 = 1;
+
+/// Short hash of the chunker version and the settings that affect chunk text.
+///
+/// Metadata-only settings (`inherit_metadata`, `heading_breadcrumbs`) are
+/// excluded because they do not change what gets embedded. Neither is
+/// `contextualize`: the chunk hash already covers the context. `normalize`
+/// only contributes when enabled, so fingerprints from before it existed
+/// still match.
+pub fn config_fingerprint(config: &ChunkingConfig) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(format!(
+        "{}/v{};max_tokens={};overlap_tokens={}",
+        strategy(config).as_str(),
+        CHUNKER_VERSION,
+        config.max_tokens,
+        config.overlap_tokens
+    ));
+    let normalize = &config.normalize;
+    if normalize.enabled {
+        hasher.update(format!(
+            ";normalize=frontmatter:{},markdown:{},emoji:{},whitespace:{},remove:{:?}",
+            normalize.strip_frontmatter,
+            normalize.strip_markdown,
+            normalize.strip_emoji,
+            normalize.collapse_whitespace,
+            normalize.remove
+        ));
+    }
+    format!("{:x}", hasher.finalize())[..16].to_string()
+}
+
+/// The configured split strategy (validated by `load_config`).
+pub fn strategy(config: &ChunkingConfig) -> ChunkStrategy {
+    ChunkStrategy::parse(&config.strategy).unwrap_or_default()
+}
+
+/// The text embedded for a chunk: its `context` metadata, if any, then a
+/// blank line and the chunk text.
+pub fn embedding_text(text: &str, metadata: &serde_json::Map<String, serde_json::Value>) -> String {
+    match metadata.get(CONTEXT_KEY).and_then(|v| v.as_str()) {
+        Some(context) if !context.is_empty() => format!("{}\n\n{}", context, text),
+        _ => text.to_string(),
+    }
+}
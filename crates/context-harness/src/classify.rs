@@ -0,0 +1,469 @@
+//! Ingest-time document types: `[classify]`.
+//!
+//! Agents asking "how do I roll back the payments service" want the
+//! runbook, not the meeting where rollbacks were discussed. When enabled,
+//! each document is tagged with a `doc_type` at ingest:
+//!
+//! 1. **Rules.** Each type has path globs (matched against `source_id`),
+//!    title patterns, and body patterns (matched against the first
+//!    [`BODY_CHARS`] characters). A document matches a type when a path or
+//!    title pattern matches, or when at least `min_body_matches` body
+//!    patterns do. Types from `[classify.types]` are tried first, in name
+//!    order, then the built-in ones:
+//!
+//!    | Type | Recognized by |
+//!    |------|---------------|
+//!    | `runbook` | `runbooks/`, `playbooks/`; "Runbook" titles; symptom, mitigation, escalation, and rollback sections |
+//!    | `adr` | `adr/`, `decisions/`; "ADR-12" titles; status, context, decision, and consequences sections |
+//!    | `api_reference` | `api/`, OpenAPI and Swagger files; `GET /path` lines, request/response bodies, status codes |
+//!    | `meeting_notes` | `meetings/`, `minutes/`; meeting, standup, and retro titles; attendees, agenda, and action items |
+//!
+//! 2. **Nearest centroid** (`embedding = true`). A document no rule matches
+//!    is embedded (title and start of body) and compared with the mean
+//!    embedding of each type's `examples`. It gets the closest type when
+//!    the cosine similarity reaches `min_similarity`. The type is reused
+//!    while the document's `dedup_hash` is unchanged, so re-syncing does
+//!    not embed it again. A failed call is logged and leaves the document
+//!    untyped.
+//!
+//! A `doc_type` the connector already put in the document's metadata (for
+//! example from front matter) is kept. The type is stored in
+//! `documents.metadata_json`. `ctx search --type runbook` and `type:runbook`
+//! in queries filter on it; `ctx stats` counts documents by type. Changing
+//! the rules affects documents as they are next written; `ctx sync
+//! <connector> --full` reclassifies the ones already indexed.
+
+use anyhow::{Context, Result};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use regex::Regex;
+use sqlx::SqlitePool;
+use std::collections::BTreeMap;
+use tokio::sync::OnceCell;
+
+use context_harness_core::embedding::cosine_similarity;
+
+use crate::config::{ClassifyConfig, Config, DocTypeRule, EmbeddingConfig};
+use crate::embedding::{self, EmbeddingProvider};
+use crate::models::Document;
+use crate::text::truncate_chars;
+
+/// Characters of the body that body patterns and the centroid embedding see.
+pub const BODY_CHARS: usize = 4000;
+
+/// Characters of the body embedded for the nearest-centroid fallback.
+const EMBED_CHARS: usize = 2000;
+
+/// The metadata key holding a document's type.
+pub const DOC_TYPE_KEY: &str = "doc_type";
+
+/// A built-in document type rule; see [`DocTypeRule`] for the fields.
+struct BuiltinRule {
+    name: &'static str,
+    paths: &'static [&'static str],
+    titles: &'static [&'static str],
+    body: &'static [&'static str],
+    min_body_matches: usize,
+}
+
+/// Built-in rules, used unless `[classify.types]` defines the same name.
+const BUILTIN_RULES: &[BuiltinRule] = &[
+    BuiltinRule {
+        name: "runbook",
+        paths: &["**/runbook*/**", "**/*runbook*", "**/playbook*/**"],
+        titles: &[r"(?i)\b(runbook|playbook)\b"],
+        body: &[
+            r"(?im)^#+\s*(symptoms?|alerts?)\b",
+            r"(?im)^#+\s*(mitigation|remediation|resolution)\b",
+            r"(?im)^#+\s*escalation\b",
+            r"(?im)^#+\s*(rollback|roll back)\b",
+            r"(?i)\bon-?call\b",
+        ],
+        min_body_matches: 2,
+    },
+    BuiltinRule {
+        name: "adr",
+        paths: &[
+            "**/adr/**",
+            "**/adrs/**",
+            "**/decisions/**",
+            "**/adr-[0-9]*",
+        ],
+        titles: &[r"(?i)\badr[-\s]?\d+\b", r"(?i)\barchitecture decision\b"],
+        body: &[
+            r"(?im)^#+\s*status\b",
+            r"(?im)^#+\s*context\b",
+            r"(?im)^#+\s*decision\b",
+            r"(?im)^#+\s*consequences\b",
+        ],
+        min_body_matches: 3,
+    },
+    BuiltinRule {
+        name: "api_reference",
+        paths: &["**/api/**", "**/*openapi*", "**/*swagger*"],
+        titles: &[r"(?i)\bapi (reference|docs?)\b", r"(?i)\bendpoints?\b"],
+        body: &[
+            r"(?m)^\s*`?(GET|POST|PUT|PATCH|DELETE)\s+/",
+            r"(?i)\b(request|response) (body|schema)\b",
+            r"(?i)\bstatus codes?\b",
+            r"(?i)\bquery param(eter)?s?\b",
+            r"(?i)\bcurl\s+-",
+        ],
+        min_body_matches: 2,
+    },
+    BuiltinRule {
+        name: "meeting_notes",
+        paths: &["**/meetings/**", "**/meeting-notes/**", "**/minutes/**"],
+        titles: &[r"(?i)\b(meeting|minutes|standup|stand-up|retro|retrospective)\b"],
+        body: &[
+            r"(?im)^(#+\s*|\*\*)?(attendees|participants)\b",
+            r"(?im)^(#+\s*|\*\*)?agenda\b",
+            r"(?im)^(#+\s*|\*\*)?(action items|next steps)\b",
+            r"(?i)\bnext meeting\b",
+        ],
+        min_body_matches: 2,
+    },
+];
+
+/// A type's rule with its globs and patterns compiled.
+pub struct CompiledRule {
+    name: String,
+    paths: Option<GlobSet>,
+    titles: Vec<Regex>,
+    body: Vec<Regex>,
+    min_body_matches: usize,
+    examples: Vec<String>,
+}
+
+impl CompiledRule {
+    fn compile(name: &str, rule: &DocTypeRule) -> Result<Self> {
+        let paths = if rule.paths.is_empty() {
+            None
+        } else {
+            let mut globs = GlobSetBuilder::new();
+            for glob in &rule.paths {
+                globs.add(Glob::new(glob).with_context(|| format!("{}: path {}", name, glob))?);
+            }
+            Some(globs.build()?)
+        };
+        let regexes = |patterns: &[String]| -> Result<Vec<Regex>> {
+            patterns
+                .iter()
+                .map(|p| Regex::new(p).with_context(|| format!("{}: pattern {}", name, p)))
+                .collect()
+        };
+        Ok(Self {
+            name: name.to_string(),
+            paths,
+            titles: regexes(&rule.title_patterns)?,
+            body: regexes(&rule.body_patterns)?,
+            min_body_matches: rule.min_body_matches.max(1),
+            examples: rule.examples.clone(),
+        })
+    }
+
+    fn matches(&self, source_id: &str, title: &str, body: &str) -> bool {
+        if self.paths.as_ref().is_some_and(|p| p.is_match(source_id)) {
+            return true;
+        }
+        if self.titles.iter().any(|re| re.is_match(title)) {
+            return true;
+        }
+        !self.body.is_empty()
+            && self.body.iter().filter(|re| re.is_match(body)).count() >= self.min_body_matches
+    }
+}
+
+/// The rules of `[classify]`, custom types first, then the built-in ones
+/// that no custom type replaces.
+///
+/// # Errors
+///
+/// Returns an error naming the type if a glob or pattern does not compile.
+pub fn compile_rules(settings: &ClassifyConfig) -> Result<Vec<CompiledRule>> {
+    let mut rules = Vec::new();
+    for (name, rule) in &settings.types {
+        rules.push(CompiledRule::compile(name, rule)?);
+    }
+    if settings.builtin {
+        for builtin in BUILTIN_RULES {
+            if settings.types.contains_key(builtin.name) {
+                continue;
+            }
+            let strings = |v: &[&str]| v.iter().map(|s| s.to_string()).collect();
+            let rule = DocTypeRule {
+                paths: strings(builtin.paths),
+                title_patterns: strings(builtin.titles),
+                body_patterns: strings(builtin.body),
+                min_body_matches: builtin.min_body_matches,
+                examples: Vec::new(),
+            };
+            rules.push(CompiledRule::compile(builtin.name, &rule)?);
+        }
+    }
+    Ok(rules)
+}
+
+/// Nearest-centroid fallback: the provider and the centroids, computed on
+/// first use.
+struct Centroids {
+    provider: Box<dyn EmbeddingProvider>,
+    embedding: EmbeddingConfig,
+    min_similarity: f32,
+    examples: BTreeMap<String, Vec<String>>,
+    vectors: OnceCell<Vec<(String, Vec<f32>)>>,
+}
+
+/// Tags documents with their `doc_type`.
+pub struct Classifier {
+    rules: Vec<CompiledRule>,
+    centroids: Option<Centroids>,
+}
+
+impl Classifier {
+    /// The classifier for `[classify]`, or `None` when it is disabled.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a rule does not compile, or if `embedding = true`
+    /// and the embedding provider cannot be created.
+    pub fn from_config(config: &Config) -> Result<Option<Self>> {
+        let settings = &config.classify;
+        if !settings.enabled {
+            return Ok(None);
+        }
+        let rules = compile_rules(settings)?;
+        let examples: BTreeMap<String, Vec<String>> = rules
+            .iter()
+            .filter(|r| !r.examples.is_empty())
+            .map(|r| (r.name.clone(), r.examples.clone()))
+            .collect();
+        let centroids = if settings.embedding && !examples.is_empty() {
+            Some(Centroids {
+                provider: embedding::create_provider(&config.embedding)?,
+                embedding: config.embedding.clone(),
+                min_similarity: settings.min_similarity,
+                examples,
+                vectors: OnceCell::new(),
+            })
+        } else {
+            None
+        };
+        Ok(Some(Self { rules, centroids }))
+    }
+
+    /// Store the type of `doc` in its metadata, unless the connector set
+    /// one. Leaves the metadata as it is when no type applies.
+    pub async fn classify(&self, pool: &SqlitePool, doc: &mut Document) -> Result<()> {
+        let mut metadata: serde_json::Map<String, serde_json::Value> =
+            serde_json::from_str(&doc.metadata_json).unwrap_or_default();
+        if metadata
+            .get(DOC_TYPE_KEY)
+            .and_then(|v| v.as_str())
+            .is_some_and(|t| !t.is_empty())
+        {
+            return Ok(());
+        }
+        let title = doc.title.as_deref().unwrap_or_default();
+        let body = truncate_chars(&doc.body, BODY_CHARS);
+        let doc_type = match self.match_rules(&doc.source_id, title, body) {
+            Some(name) => Some(name.to_string()),
+            None => self.nearest(pool, doc).await?,
+        };
+        if let Some(doc_type) = doc_type {
+            metadata.insert(DOC_TYPE_KEY.to_string(), doc_type.into());
+            doc.metadata_json = serde_json::Value::Object(metadata).to_string();
+        }
+        Ok(())
+    }
+
+    /// The first type whose rule matches.
+    fn match_rules(&self, source_id: &str, title: &str, body: &str) -> Option<&str> {
+        self.rules
+            .iter()
+            .find(|r| r.matches(source_id, title, body))
+            .map(|r| r.name.as_str())
+    }
+
+    /// The type with the nearest centroid, if close enough.
+    async fn nearest(&self, pool: &SqlitePool, doc: &Document) -> Result<Option<String>> {
+        let Some(ref centroids) = self.centroids else {
+            return Ok(None);
+        };
+        let previous: Option<Option<String>> = sqlx::query_scalar(
+            "SELECT CASE WHEN json_valid(metadata_json) \
+             THEN json_extract(metadata_json, '$.doc_type') END \
+             FROM documents WHERE id = ? AND dedup_hash = ?",
+        )
+        .bind(&doc.id)
+        .bind(&doc.dedup_hash)
+        .fetch_optional(pool)
+        .await?;
+        if let Some(previous) = previous {
+            return Ok(previous);
+        }
+
+        let vectors = match centroids
+            .vectors
+            .get_or_try_init(|| centroids.compute())
+            .await
+        {
+            Ok(vectors) => vectors,
+            Err(e) => {
+                tracing::warn!("doc type centroids: {:#}", e);
+                return Ok(None);
+            }
+        };
+        let text = format!(
+            "{}\n\n{}",
+            doc.title.as_deref().unwrap_or_default(),
+            truncate_chars(&doc.body, EMBED_CHARS)
+        );
+        let vector = match embedding::embed_texts(
+            centroids.provider.as_ref(),
+            &centroids.embedding,
+            &[text],
+        )
+        .await
+        {
+            Ok(mut v) if !v.is_empty() => v.swap_remove(0),
+            Ok(_) => return Ok(None),
+            Err(e) => {
+                tracing::warn!("doc type {}: {:#}", doc.source_id, e);
+                return Ok(None);
+            }
+        };
+        Ok(vectors
+            .iter()
+            .map(|(name, centroid)| (name, cosine_similarity(&vector, centroid)))
+            .filter(|(_, similarity)| *similarity >= centroids.min_similarity)
+            .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+            .map(|(name, _)| name.clone()))
+    }
+}
+
+impl Centroids {
+    /// Embed every type's examples and average them.
+    async fn compute(&self) -> Result<Vec<(String, Vec<f32>)>> {
+        let mut centroids = Vec::new();
+        for (name, examples) in &self.examples {
+            let vectors =
+                embedding::embed_texts(self.provider.as_ref(), &self.embedding, examples).await?;
+            let Some(dims) = vectors.first().map(Vec::len) else {
+                continue;
+            };
+            let mut centroid = vec![0.0f32; dims];
+            for vector in &vectors {
+                for (c, x) in centroid.iter_mut().zip(vector) {
+                    *c += x / vectors.len() as f32;
+                }
+            }
+            centroids.push((name.clone(), centroid));
+        }
+        Ok(centroids)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn builtin() -> Vec<CompiledRule> {
+        compile_rules(&ClassifyConfig::default()).unwrap()
+    }
+
+    fn classify(
+        rules: &[CompiledRule],
+        source_id: &str,
+        title: &str,
+        body: &str,
+    ) -> Option<String> {
+        rules
+            .iter()
+            .find(|r| r.matches(source_id, title, body))
+            .map(|r| r.name.clone())
+    }
+
+    #[test]
+    fn builtin_rules_recognize_common_types() {
+        let rules = builtin();
+        assert_eq!(
+            classify(&rules, "ops/runbooks/payments.md", "Payments", "").as_deref(),
+            Some("runbook")
+        );
+        assert_eq!(
+            classify(
+                &rules,
+                "docs/0007-queue.md",
+                "Use SQS for the job queue",
+                "# Status\nAccepted\n# Context\nWe need...\n# Decision\nSQS.\n"
+            )
+            .as_deref(),
+            Some("adr")
+        );
+        assert_eq!(
+            classify(
+                &rules,
+                "docs/users.md",
+                "Users",
+                "`GET /v1/users` lists users.\n\nStatus codes: 200, 404.\n"
+            )
+            .as_deref(),
+            Some("api_reference")
+        );
+        assert_eq!(
+            classify(&rules, "notes/2024-03-01.md", "Weekly standup", "").as_deref(),
+            Some("meeting_notes")
+        );
+        assert_eq!(
+            classify(&rules, "docs/intro.md", "Introduction", "Welcome."),
+            None
+        );
+    }
+
+    #[test]
+    fn one_body_pattern_is_not_enough() {
+        let rules = builtin();
+        assert_eq!(
+            classify(&rules, "docs/a.md", "Notes", "# Status\nAll green.\n"),
+            None
+        );
+    }
+
+    #[test]
+    fn custom_types_come_first_and_replace_builtins() {
+        let mut settings = ClassifyConfig::default();
+        settings.types.insert(
+            "runbook".into(),
+            DocTypeRule {
+                paths: vec!["**/ops/**".into()],
+                ..DocTypeRule::default()
+            },
+        );
+        settings.types.insert(
+            "postmortem".into(),
+            DocTypeRule {
+                title_patterns: vec!["(?i)post-?mortem".into()],
+                ..DocTypeRule::default()
+            },
+        );
+        let rules = compile_rules(&settings).unwrap();
+        assert_eq!(
+            classify(&rules, "ops/db.md", "Runbook: postmortem of the outage", "").as_deref(),
+            Some("postmortem")
+        );
+        assert_eq!(
+            classify(&rules, "runbooks/db.md", "Database", ""),
+            None,
+            "the built-in runbook rule was replaced"
+        );
+
+        settings.types.insert(
+            "broken".into(),
+            DocTypeRule {
+                body_patterns: vec!["(".into()],
+                ..DocTypeRule::default()
+            },
+        );
+        assert!(compile_rules(&settings).is_err());
+    }
+}
@@ -48,23 +48,65 @@
 //! - **Filesystem** (`[connectors.filesystem.<name>]`) — scan a local directory
 //! - **Git** (`[connectors.git.<name>]`) — clone/pull a Git repository
 //! - **S3** (`[connectors.s3.<name>]`) — list and download from an S3 bucket
+//! - **SharePoint** (`[connectors.sharepoint.<name>]`) — SharePoint document libraries and OneDrive via Microsoft Graph
+//! - **Zendesk** (`[connectors.zendesk.<name>]`) — support tickets with their comment threads, and help-center articles
+//! - **Stack Overflow** (`[connectors.stackoverflow.<name>]`) — questions with their accepted and top answers, from Stack Overflow for Teams or a public site
 //! - **Script** (`[connectors.script.<name>]`) — custom Lua-scripted data sources
 //!
 //! # Validation
 //!
 //! [`load_config`] performs the following validations:
-//! - `chunking.max_tokens > 0`
+//! - `db.gc_interval_hours >= 1` when set
+//! - `db.profile` is `balanced`, `read-heavy`, or `write-heavy`
+//! - `chunking.max_tokens > 0`; `chunking.strategy` is `paragraph` or `line`; `overlap_tokens < max_tokens`
+//! - `[connectors.<type>.<name>.chunking]` overrides obey the same rules once merged with `[chunking]`
+//! - `[[connectors.<type>.<name>.stitch]]` rules have valid `include` globs and a `group_by` of `directory` or `rule`; `rule` needs a `name`
 //! - `retrieval.final_limit >= 1`
 //! - `retrieval.hybrid_alpha ∈ [0.0, 1.0]`
-//! - When embedding provider is `openai` or `ollama`: `model` and `dims` must be set
-//! - Embedding provider must be one of: `"disabled"`, `"openai"`, `"ollama"`, `"local"`
+//! - `retrieval.fts_tokenizer` is `unicode61`, `porter`, or `trigram`
+//! - `retrieval.weights` `text`, `title`, and `summary` are `>= 0` and not all zero
+//! - `retrieval.bm25` (and `.titles`) `k1 >= 0` and `b ∈ [0.0, 1.0]`
+//! - `retrieval.popularity_weight >= 0` and `popularity_half_life_days > 0`
+//! - `retrieval.auto_merge_min_chunks` is `0` or `>= 2`
+//! - `keyword_index.backend` is `sqlite`, `opensearch`, or `elasticsearch`; the latter two need `url`
+//! - When embedding provider is `openai` or `ollama`: `model` and `dims` must be set; `bedrock` and `vertex` need `model`
+//! - Embedding provider must be one of: `"disabled"`, `"openai"`, `"ollama"`, `"local"`, `"bedrock"`, `"vertex"`
+//! - `embedding.providers` starts with `provider` when both are set, names each provider once, and every fallback passes the same checks with its `[embedding.fallback.<provider>]` settings; set dims agree
+//! - `embedding.truncate` is `start`, `end`, or `middle`; `max_input_tokens` exceeds the prefixes
+//! - `embedding.document_vectors` is `off`, `mean`, or `title`; `route_documents >= 1` unless `off`
+//! - `[llm]` provider is `disabled`, `openai`, or `ollama`; `model` is set unless disabled
+//! - `[chunking.contextualize]` templates use known placeholders; `llm = true` needs `[llm]`
+//! - `[chunking.normalize]` `remove` patterns compile
+//! - `[summary]` `max_chars >= 1`; `llm = true` needs `[llm]`
+//! - `[classify]` globs and patterns compile; `embedding = true` needs `[embedding]`; `min_similarity ∈ (0.0, 1.0]`
+//! - `[memory]` `source` is not empty; `retention_days >= 1` when set; `max_bytes >= 1`
+//! - `[redaction]` detectors are known built-ins and custom patterns compile
+//! - `[storage]` eviction is `lru` or `oldest`; `max_size_mb > 0` and `warn_at_percent ∈ [1, 100]`
+//! - `[sync]` `max_bandwidth_mbps > 0`, `download_retries >= 1`, `timeout_secs >= 1`, `cpu_threads >= 1`, and `stale_after_hours` and every `stale_after` value >= 1; `content_dedup` is `keep-all`, `link`, or `skip`
+//! - `[[notifications.webhooks]]` set a `url`, `format` is `json` or `slack`, `on` and `runs` use known values
+//! - `telemetry.sample_ratio ∈ [0.0, 1.0]`, `timeout_secs >= 1`, and `service_name` is not empty
+//! - `update.check_interval_hours >= 1`, `repo` is `owner/name`, and `public_key` is a minisign key
+//! - `server.watch_interval_secs >= 1` when `server.watch` is enabled
+//! - `[server.limits.tools]` entries are at least 1
+//! - `[server.responses]` `max_body_bytes` and `max_chunks` are at least 1 when set
+//! - `[[server.auth.keys]]` have unique non-empty names and keys; `allowed_collections` are defined
+//! - Each `[federation.indexes.<name>]` sets exactly one of `db` or `url`; `local` is reserved
+//! - Each `[connectors.filesystem.<name>]` has `min_file_size <= max_file_size`
+//! - Enabled `archives` on filesystem and S3 connectors have `max_depth >= 1` and non-zero byte limits
+//! - S3 `list_concurrency` is at least 1, and a `select` table has a non-empty `expression` and valid `globs`
+//! - Each `[connectors.sharepoint.<name>]` sets exactly one of `site`, `drive_id`, or `user`
+//! - Each `[connectors.zendesk.<name>]` sets `subdomain` or `base_url`, reads tickets or articles, and uses known `statuses`
+//! - Each `[connectors.stackoverflow.<name>]` sets `team` or `tags`, and a non-empty `site`
+//! - `[[agents.inline.<name>.context]]` blocks have a non-empty `query`, a known `mode`, and `limit >= 1`
 
 use anyhow::{Context, Result};
 use serde::Deserialize;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
 
+use crate::agents::{ContextQuery, ModelHints, SuggestedToolCall};
 use crate::ctx_dirs::{self, ConfigSourceKind};
+use crate::scrub;
 
 /// Top-level configuration structure.
 ///
@@ -81,10 +123,16 @@ pub struct Config {
     /// Embedding provider settings (defaults to disabled).
     #[serde(default)]
     pub embedding: EmbeddingConfig,
+    /// Chat model used by `ctx agent run` and `[chunking.contextualize]` (defaults to disabled).
+    #[serde(default)]
+    pub llm: LlmConfig,
     /// Optional vector-index acceleration settings (defaults to auto with SQLite fallback).
     #[serde(default)]
     #[allow(dead_code)]
     pub vector_index: VectorIndexConfig,
+    /// Optional external keyword index (defaults to SQLite FTS5).
+    #[serde(default)]
+    pub keyword_index: KeywordIndexConfig,
     /// HTTP server bind address.
     #[allow(dead_code)]
     pub server: ServerConfig,
@@ -100,6 +148,37 @@ pub struct Config {
     /// Extension registry configurations (all optional).
     #[serde(default)]
     pub registries: HashMap<String, RegistryConfig>,
+    /// Ingest-time PII redaction (disabled unless connectors are listed).
+    #[serde(default)]
+    pub redaction: RedactionConfig,
+    /// Ingest-time summaries of long documents (disabled by default).
+    #[serde(default)]
+    pub summary: SummaryConfig,
+    /// Ingest-time document type classification (disabled by default).
+    #[serde(default)]
+    pub classify: ClassifyConfig,
+    /// The `memory.append` tool for conversation transcripts (disabled by default).
+    #[serde(default)]
+    pub memory: MemoryConfig,
+    /// Index size budget and eviction policy (unlimited by default).
+    #[serde(default)]
+    pub storage: StorageConfig,
+    /// Download throttling and retry settings for remote connectors.
+    #[serde(default)]
+    pub sync: SyncConfig,
+    /// Other indexes searched together with this one (none by default).
+    #[serde(default)]
+    pub federation: FederationConfig,
+    /// Webhooks notified when sync and embed runs finish (none by default).
+    #[serde(default)]
+    pub notifications: NotificationsConfig,
+    /// OpenTelemetry trace export (disabled by default).
+    #[serde(default)]
+    pub telemetry: TelemetryConfig,
+    /// `ctx self update` release source and the startup version check
+    /// (off by default).
+    #[serde(default)]
+    pub update: UpdateConfig,
 }
 
 impl Config {
@@ -109,10 +188,21 @@ impl Config {
         Self {
             db: DbConfig {
                 path: ctx_dirs::workspace_db_path(),
+                busy_timeout_ms: default_busy_timeout_ms(),
+                write_retries: default_write_retries(),
+                read_pool: false,
+                gc_interval_hours: None,
+                profile: default_db_profile(),
+                warm_up: false,
             },
             chunking: ChunkingConfig {
                 max_tokens: 700,
+                strategy: default_chunk_strategy(),
                 overlap_tokens: 0,
+                inherit_metadata: Vec::new(),
+                heading_breadcrumbs: true,
+                contextualize: ContextualizeConfig::default(),
+                normalize: NormalizeConfig::default(),
             },
             retrieval: RetrievalConfig {
                 hybrid_alpha: default_hybrid_alpha(),
@@ -122,16 +212,62 @@ impl Config {
                 group_by: default_group_by(),
                 doc_agg: default_doc_agg(),
                 max_chunks_per_doc: default_max_chunks_per_doc(),
+                fts_tokenizer: default_fts_tokenizer(),
+                fuzzy: false,
+                weights: FtsWeights::default(),
+                bm25: Bm25Config::default(),
+                stop_words: Vec::new(),
+                track_access: true,
+                popularity_weight: 0.0,
+                popularity_half_life_days: default_popularity_half_life_days(),
+                auto_merge_min_chunks: 0,
             },
             embedding: EmbeddingConfig::default(),
+            llm: LlmConfig::default(),
             vector_index: VectorIndexConfig::default(),
+            keyword_index: KeywordIndexConfig::default(),
             server: ServerConfig {
                 bind: "127.0.0.1:7331".to_string(),
+                shutdown_grace_secs: default_shutdown_grace_secs(),
+                search_cache_size: default_search_cache_size(),
+                search_cache_ttl_secs: default_search_cache_ttl_secs(),
+                watch: false,
+                watch_interval_secs: default_watch_interval_secs(),
+                auth: ServerAuthConfig::default(),
+                grpc_bind: None,
+                limits: ServerLimitsConfig::default(),
+                responses: ServerResponsesConfig::default(),
             },
             connectors: ConnectorsConfig::default(),
             tools: ToolsConfig::default(),
             agents: AgentsConfig::default(),
             registries: HashMap::new(),
+            redaction: RedactionConfig::default(),
+            summary: SummaryConfig::default(),
+            classify: ClassifyConfig::default(),
+            memory: MemoryConfig::default(),
+            storage: StorageConfig::default(),
+            sync: SyncConfig::default(),
+            federation: FederationConfig::default(),
+            notifications: NotificationsConfig::default(),
+            telemetry: TelemetryConfig::default(),
+            update: UpdateConfig::default(),
+        }
+    }
+
+    /// Effective chunking settings for a source label (`"type:name"`):
+    /// `[chunking]` with the connector's `chunking` overrides applied.
+    pub fn chunking_for(&self, source_label: &str) -> ChunkingConfig {
+        match self.connectors.chunking_override(source_label) {
+            Some(overrides) => self.chunking.with_override(overrides),
+            None => self.chunking.clone(),
+        }
+    }
+
+    /// Set `allow_unsigned` on every registry (`--allow-unsigned`).
+    pub fn allow_unsigned_extensions(&mut self) {
+        for registry in self.registries.values_mut() {
+            registry.allow_unsigned = true;
         }
     }
 }
@@ -147,11 +283,51 @@ pub struct ResolvedConfig {
 /// Database configuration.
 ///
 /// Specifies the path to the SQLite database file. The file and its
-/// parent directories are created automatically on first use.
+/// parent directories are created automatically on first use. The other
+/// settings tune how processes sharing the file (e.g. `ctx serve mcp` and a
+/// cron `ctx sync`) wait for each other; see [`crate::db`].
 #[derive(Debug, Deserialize, Clone)]
 pub struct DbConfig {
     /// Path to the SQLite database file (e.g. `".ctx/data/ctx.sqlite"`).
     pub path: PathBuf,
+    /// How long a connection waits for another writer's lock before
+    /// failing with `database is locked`, in milliseconds. Default: `30000`.
+    #[serde(default = "default_busy_timeout_ms")]
+    pub busy_timeout_ms: u64,
+    /// How many times a write that still finds the database busy is
+    /// retried, with backoff. Default: `3`.
+    #[serde(default = "default_write_retries")]
+    pub write_retries: u32,
+    /// Serve searches and document reads from a shared read-only pool, so
+    /// a long-running server never holds write locks for them. Default: `false`.
+    #[serde(default)]
+    pub read_pool: bool,
+    /// Run `ctx db gc` every this many hours while `ctx serve mcp` is
+    /// running. Default: unset (never).
+    #[serde(default)]
+    pub gc_interval_hours: Option<u64>,
+    /// SQLite tuning: `"balanced"`, `"read-heavy"` (memory-mapped reads and
+    /// a larger page cache, for servers), or `"write-heavy"` (fewer WAL
+    /// checkpoints, for bulk syncs). See [`crate::db`]. Default: `"balanced"`.
+    #[serde(default = "default_db_profile")]
+    pub profile: String,
+    /// Read the FTS and vector pages and prepare the search statements
+    /// when `ctx serve mcp` starts, so the first queries are not cold.
+    /// Default: `false`.
+    #[serde(default)]
+    pub warm_up: bool,
+}
+
+fn default_db_profile() -> String {
+    "balanced".to_string()
+}
+
+fn default_busy_timeout_ms() -> u64 {
+    30_000
+}
+
+fn default_write_retries() -> u32 {
+    3
 }
 
 /// Text chunking parameters.
@@ -163,16 +339,223 @@ pub struct ChunkingConfig {
     /// Maximum tokens per chunk. Chunks are split on paragraph boundaries
     /// to stay within this limit. Converted to characters via `max_tokens × 4`.
     pub max_tokens: usize,
-    /// Number of overlapping tokens between adjacent chunks (reserved for future use).
+    /// Where text may be split: `"paragraph"` (blank lines) or `"line"`
+    /// (single newlines). Default: `"paragraph"`.
+    #[serde(default = "default_chunk_strategy")]
+    pub strategy: String,
+    /// Tokens of the previous chunk's tail repeated at the start of the
+    /// next chunk. Default: `0`.
     #[serde(default = "default_overlap")]
-    #[allow(dead_code)]
     pub overlap_tokens: usize,
+    /// Document fields copied onto every chunk as chunk-level metadata.
+    /// Keys are looked up in the document's `metadata_json` first, then in
+    /// the document columns (`source`, `source_id`, `source_url`, `title`,
+    /// `author`, `content_type`). Default: none.
+    #[serde(default)]
+    pub inherit_metadata: Vec<String>,
+    /// Record the markdown heading breadcrumb of each chunk under the
+    /// `heading` metadata key. Default: `true`.
+    #[serde(default = "default_true")]
+    pub heading_breadcrumbs: bool,
+    /// Context line prepended to each chunk before embedding. Default: off.
+    #[serde(default)]
+    pub contextualize: ContextualizeConfig,
+    /// Text cleanup applied to document bodies before chunking. Default: off.
+    #[serde(default)]
+    pub normalize: NormalizeConfig,
+}
+
+impl ChunkingConfig {
+    /// These settings with any fields set in `overrides` replaced.
+    pub fn with_override(&self, overrides: &ChunkingOverride) -> Self {
+        Self {
+            max_tokens: overrides.max_tokens.unwrap_or(self.max_tokens),
+            strategy: overrides
+                .strategy
+                .clone()
+                .unwrap_or_else(|| self.strategy.clone()),
+            overlap_tokens: overrides.overlap_tokens.unwrap_or(self.overlap_tokens),
+            normalize: overrides
+                .normalize
+                .clone()
+                .unwrap_or_else(|| self.normalize.clone()),
+            ..self.clone()
+        }
+    }
 }
 
 fn default_overlap() -> usize {
     0
 }
 
+/// Contextual chunk headers: a short text situating each chunk in its
+/// document, prepended to the chunk before embedding.
+///
+/// The context is stored under the chunk's `context` metadata key and never
+/// becomes part of the chunk text, so snippets, keyword search, and `ctx get`
+/// are unchanged. See [`crate::contextualize`].
+///
+/// # Example
+///
+/// ```toml
+/// [chunking.contextualize]
+/// enabled = true
+/// template = "{title}\n{heading}\n{summary}"
+/// llm = true                     # generate {summary} with [llm]
+/// ```
+#[derive(Debug, Deserialize, Clone)]
+pub struct ContextualizeConfig {
+    /// Prepend a context line to each chunk before embedding. Default: `false`.
+    #[serde(default)]
+    pub enabled: bool,
+    /// Context template. Placeholders: `{title}`, `{heading}`, `{source}`,
+    /// `{source_id}`, `{summary}`. Lines whose placeholders are all empty
+    /// are dropped. Default: `"{title}\n{heading}\n{summary}"`.
+    #[serde(default = "default_contextualize_template")]
+    pub template: String,
+    /// Generate `{summary}` with the `[llm]` model: one or two sentences
+    /// situating the chunk within its document. Default: `false`.
+    #[serde(default)]
+    pub llm: bool,
+    /// Document tokens sent to the model with each chunk. Longer documents
+    /// are cut. Default: `4000`.
+    #[serde(default = "default_contextualize_document_tokens")]
+    pub max_document_tokens: usize,
+}
+
+impl Default for ContextualizeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            template: default_contextualize_template(),
+            llm: false,
+            max_document_tokens: default_contextualize_document_tokens(),
+        }
+    }
+}
+
+fn default_contextualize_template() -> String {
+    "{title}\n{heading}\n{summary}".to_string()
+}
+
+fn default_contextualize_document_tokens() -> usize {
+    4000
+}
+
+/// Chunk text normalization: markdown, front matter, emoji, and boilerplate
+/// removed from a document body before it is chunked.
+///
+/// The stored document body is unchanged, so `ctx get` returns the original
+/// text. See [`crate::normalize`].
+///
+/// # Example
+///
+/// ```toml
+/// [chunking.normalize]
+/// enabled = true
+/// strip_emoji = true
+/// remove = ['^Was this page helpful\?.*$']
+/// ```
+#[derive(Debug, Deserialize, Clone)]
+pub struct NormalizeConfig {
+    /// Normalize text before chunking. Default: `false`.
+    #[serde(default)]
+    pub enabled: bool,
+    /// Drop a leading `---` or `+++` front matter block. Default: `true`.
+    #[serde(default = "default_true")]
+    pub strip_frontmatter: bool,
+    /// Unwrap links, images, emphasis, and inline code, and drop fence
+    /// lines, HTML tags, and rules. Headings are kept. Default: `true`.
+    #[serde(default = "default_true")]
+    pub strip_markdown: bool,
+    /// Remove emoji. Default: `false`.
+    #[serde(default)]
+    pub strip_emoji: bool,
+    /// Collapse runs of spaces and blank lines. Default: `true`.
+    #[serde(default = "default_true")]
+    pub collapse_whitespace: bool,
+    /// Regexes whose matches are deleted, e.g. navigation and footer lines.
+    /// `^` and `$` match at line boundaries. Default: none.
+    #[serde(default)]
+    pub remove: Vec<String>,
+}
+
+impl Default for NormalizeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            strip_frontmatter: true,
+            strip_markdown: true,
+            strip_emoji: false,
+            collapse_whitespace: true,
+            remove: Vec::new(),
+        }
+    }
+}
+
+fn default_chunk_strategy() -> String {
+    "paragraph".to_string()
+}
+
+/// Per-connector chunking overrides, merged over `[chunking]` for items
+/// from that connector. See [`Config::chunking_for`].
+///
+/// # Example
+///
+/// ```toml
+/// [connectors.git.platform.chunking]
+/// strategy = "line"
+/// max_tokens = 300
+/// overlap_tokens = 40
+/// ```
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct ChunkingOverride {
+    /// Replaces `chunking.strategy`.
+    #[serde(default)]
+    pub strategy: Option<String>,
+    /// Replaces `chunking.max_tokens`.
+    #[serde(default)]
+    pub max_tokens: Option<usize>,
+    /// Replaces `chunking.overlap_tokens`.
+    #[serde(default)]
+    pub overlap_tokens: Option<usize>,
+    /// Replaces `[chunking.normalize]` as a whole.
+    #[serde(default)]
+    pub normalize: Option<NormalizeConfig>,
+}
+
+/// A rule combining a connector's items into stitched documents, one per
+/// directory or one for the whole rule. See [`crate::stitch`].
+///
+/// # Example
+///
+/// ```toml
+/// [[connectors.git.platform.stitch]]
+/// include = ["docs/guides/**/*.md"]   # one document per directory
+///
+/// [[connectors.git.platform.stitch]]
+/// include = ["docs/adr/*.md"]
+/// group_by = "rule"
+/// name = "Architecture decisions"
+/// ```
+#[derive(Debug, Deserialize, Clone)]
+pub struct StitchRule {
+    /// Globs over `source_id` selecting the items to stitch.
+    pub include: Vec<String>,
+    /// `"directory"` stitches the items of each directory into one
+    /// document; `"rule"` stitches every matching item into one. Default:
+    /// `"directory"`.
+    #[serde(default = "default_stitch_group_by")]
+    pub group_by: String,
+    /// `source_id` and title of the document with `group_by = "rule"`.
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+fn default_stitch_group_by() -> String {
+    "directory".to_string()
+}
+
 /// Search and retrieval tuning parameters.
 ///
 /// These settings control how keyword and semantic search results are
@@ -219,6 +602,176 @@ pub struct RetrievalConfig {
     #[serde(default = "default_max_chunks_per_doc")]
     #[allow(dead_code)]
     pub max_chunks_per_doc: usize,
+    /// FTS5 tokenizer for the keyword index: `"unicode61"` (default),
+    /// `"porter"` (English stemming), or `"trigram"` (substring and typo
+    /// tolerant). Applied by `ctx init`, which rebuilds the index on change.
+    #[serde(default = "default_fts_tokenizer")]
+    pub fts_tokenizer: String,
+    /// Expand keyword query terms to indexed terms within a small edit
+    /// distance. Can also be enabled per query with `ctx search --fuzzy`.
+    #[serde(default)]
+    pub fuzzy: bool,
+    /// BM25 column weights for keyword search (`[retrieval.weights]`).
+    #[serde(default)]
+    pub weights: FtsWeights,
+    /// BM25 `k1` and `b` for the keyword indexes (`[retrieval.bm25]`).
+    #[serde(default)]
+    pub bm25: Bm25Config,
+    /// Words dropped from keyword queries before matching (case-insensitive),
+    /// e.g. `["the", "a", "of"]`. A query made only of stop words is kept
+    /// as-is. Default: none.
+    #[serde(default)]
+    pub stop_words: Vec<String>,
+    /// Record when search and get return each document, for
+    /// `ctx stats --popular` and the popularity boost. `[storage]` LRU
+    /// eviction records them regardless. Default: `true`.
+    #[serde(default = "default_true")]
+    pub track_access: bool,
+    /// Popularity boost: scores are multiplied by `1 + weight × popularity`,
+    /// see [`crate::popularity`]. Default: `0.0` (off).
+    #[serde(default)]
+    pub popularity_weight: f64,
+    /// Days after which a document's popularity halves without new
+    /// retrievals. Default: `30`.
+    #[serde(default = "default_popularity_half_life_days")]
+    pub popularity_half_life_days: f64,
+    /// Auto-merging retrieval: when at least this many chunks of one
+    /// document match close together, the result's snippet is the text of
+    /// all chunks from the first to the last of them instead of one
+    /// excerpt. `0` (default) disables it; otherwise `>= 2`.
+    #[serde(default)]
+    pub auto_merge_min_chunks: usize,
+}
+
+/// BM25 column weights for the keyword index.
+///
+/// Each chunk is indexed with its text and its document's title and
+/// summary (see [`SummaryConfig`]); a match in a column counts `weight`
+/// times. Set `title = 0.0` to ignore titles.
+///
+/// ```toml
+/// [retrieval.weights]
+/// text = 1.0
+/// title = 2.0
+/// summary = 1.5
+/// ```
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+pub struct FtsWeights {
+    /// Weight of chunk text. Default: `1.0`.
+    #[serde(default = "default_text_weight")]
+    pub text: f64,
+    /// Weight of the document title. Default: `2.0`.
+    #[serde(default = "default_title_weight")]
+    pub title: f64,
+    /// Weight of the document summary. Default: `1.5`.
+    #[serde(default = "default_summary_weight")]
+    pub summary: f64,
+}
+
+impl Default for FtsWeights {
+    fn default() -> Self {
+        Self {
+            text: default_text_weight(),
+            title: default_title_weight(),
+            summary: default_summary_weight(),
+        }
+    }
+}
+
+/// BM25 term-frequency saturation (`k1`) and length normalization (`b`).
+///
+/// The defaults are the constants of FTS5's `bm25()`. Lower `k1` lets
+/// repeated terms count for less; lower `b` penalizes long chunks less,
+/// which suits corpora of short, similar-length items such as tickets.
+/// The values apply to the chunk index and the external keyword index;
+/// the title index (`ctx search --in titles`) can override them. See
+/// [`crate::bm25`] for how they combine with `[retrieval.weights]`.
+///
+/// ```toml
+/// [retrieval.bm25]
+/// k1 = 0.9
+/// b = 0.4
+///
+/// [retrieval.bm25.titles]
+/// b = 0.0
+/// ```
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+pub struct Bm25Config {
+    /// Term-frequency saturation, `>= 0`. Default: `1.2`.
+    #[serde(default = "default_bm25_k1")]
+    pub k1: f64,
+    /// Length normalization, in `[0.0, 1.0]`. Default: `0.75`.
+    #[serde(default = "default_bm25_b")]
+    pub b: f64,
+    /// Overrides for the title index; unset values inherit `k1` and `b`.
+    #[serde(default)]
+    pub titles: Bm25Override,
+}
+
+/// `k1` and `b` of one index, overriding `[retrieval.bm25]`.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Default)]
+pub struct Bm25Override {
+    pub k1: Option<f64>,
+    pub b: Option<f64>,
+}
+
+/// The BM25 constants applied to one index.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bm25Params {
+    pub k1: f64,
+    pub b: f64,
+}
+
+impl Bm25Params {
+    /// `true` for FTS5's own constants, which `bm25()` applies directly.
+    pub fn is_builtin(&self) -> bool {
+        self.k1 == default_bm25_k1() && self.b == default_bm25_b()
+    }
+}
+
+impl Bm25Config {
+    /// Constants of the chunk index and the external keyword index.
+    pub fn chunks(&self) -> Bm25Params {
+        Bm25Params {
+            k1: self.k1,
+            b: self.b,
+        }
+    }
+
+    /// Constants of the title index.
+    pub fn titles(&self) -> Bm25Params {
+        Bm25Params {
+            k1: self.titles.k1.unwrap_or(self.k1),
+            b: self.titles.b.unwrap_or(self.b),
+        }
+    }
+}
+
+impl Default for Bm25Config {
+    fn default() -> Self {
+        Self {
+            k1: default_bm25_k1(),
+            b: default_bm25_b(),
+            titles: Bm25Override::default(),
+        }
+    }
+}
+
+fn default_bm25_k1() -> f64 {
+    1.2
+}
+fn default_bm25_b() -> f64 {
+    0.75
+}
+
+fn default_text_weight() -> f64 {
+    1.0
+}
+fn default_title_weight() -> f64 {
+    2.0
+}
+fn default_summary_weight() -> f64 {
+    1.5
 }
 
 fn default_hybrid_alpha() -> f64 {
@@ -239,6 +792,12 @@ fn default_doc_agg() -> String {
 fn default_max_chunks_per_doc() -> usize {
     3
 }
+fn default_fts_tokenizer() -> String {
+    "unicode61".to_string()
+}
+fn default_popularity_half_life_days() -> f64 {
+    30.0
+}
 
 /// Embedding provider configuration.
 ///
@@ -254,22 +813,59 @@ fn default_max_chunks_per_doc() -> usize {
 /// | `"openai"` | OpenAI API (`text-embedding-3-small`, etc.) |
 /// | `"ollama"` | Local Ollama instance (`nomic-embed-text`, etc.) |
 /// | `"local"` | Built-in models via fastembed (primary) or tract (musl/Intel Mac) (`all-minilm-l6-v2`, etc.) |
+/// | `"bedrock"` | AWS Bedrock (`amazon.titan-embed-text-v2:0`, `cohere.embed-english-v3`, etc.) |
+/// | `"vertex"` | Google Vertex AI (`text-embedding-005`, `text-embedding-004`, etc.) |
 ///
-/// When using `"openai"`, the `OPENAI_API_KEY` environment variable must be set.
+/// When using `"openai"`, set `api_key` (a `secret://` reference is
+/// recommended) or the `OPENAI_API_KEY` environment variable.
 /// When using `"ollama"`, an Ollama instance must be running (default: `http://localhost:11434`).
 /// When using `"local"`, the model is downloaded on first use and cached in `~/.cache/huggingface/`.
+/// When using `"bedrock"`, requests are signed with SigV4 using `access_key_id`,
+/// `secret_access_key`, and `session_token` or the usual `AWS_*` environment
+/// variables, in `region` (or `AWS_REGION`).
+/// When using `"vertex"`, requests authenticate as the service account in
+/// `credentials_file` (or `GOOGLE_APPLICATION_CREDENTIALS`), in `project`
+/// and `region` (default `us-central1`).
+///
+/// # Fallback
+///
+/// `providers` lists providers to try in order; a call that fails on one
+/// moves on to the next (see [`crate::embedding`]). The first entry is the
+/// primary and uses the settings above. Later entries share them too,
+/// except `model`, `dims`, `url`, and `api_key`, which come from their
+/// `[embedding.fallback.<provider>]` table:
+///
+/// ```toml
+/// [embedding]
+/// providers = ["ollama", "local"]
+/// model = "nomic-embed-text"
+/// dims = 768
+///
+/// [embedding.fallback.local]
+/// model = "nomic-embed-text-v1.5"
+/// ```
 #[derive(Debug, Deserialize, Clone)]
 pub struct EmbeddingConfig {
-    /// Provider name: `"disabled"`, `"openai"`, `"ollama"`, or `"local"`. Default: `"disabled"`.
+    /// Provider name: `"disabled"`, `"openai"`, `"ollama"`, `"local"`, `"bedrock"`, or `"vertex"`. Default: `"disabled"`.
+    /// Set to the first of `providers` when only those are given.
     #[serde(default = "default_provider")]
     pub provider: String,
+    /// Providers to try in order, e.g. `["openai", "local"]`. Default: just
+    /// `provider`.
+    #[serde(default)]
+    pub providers: Vec<String>,
+    /// Settings of the fallback providers, keyed by provider name.
+    #[serde(default)]
+    pub fallback: HashMap<String, EmbeddingFallbackConfig>,
     /// Embedding model name (e.g. `"text-embedding-3-small"`, `"nomic-embed-text"`,
     /// `"all-minilm-l6-v2"`). Required for `openai` and `ollama`; optional for `local`
     /// (defaults to `"all-minilm-l6-v2"`).
     #[serde(default)]
     pub model: Option<String>,
     /// Embedding vector dimensionality (e.g. `1536` for `text-embedding-3-small`).
-    /// Required for `openai` and `ollama`; auto-detected for `local`.
+    /// Required for `openai` and `ollama`; auto-detected for `local` and for
+    /// the Titan and Cohere models on `bedrock` (Titan v2 also accepts 256 or 512)
+    /// and for `vertex` (768, or any smaller size).
     #[serde(default)]
     pub dims: Option<usize>,
     /// Number of texts to embed per batch. Default: `64`.
@@ -282,27 +878,219 @@ pub struct EmbeddingConfig {
     #[serde(default = "default_timeout_secs")]
     pub timeout_secs: u64,
     /// Base URL for Ollama API. Default: `"http://localhost:11434"`.
+    /// For `bedrock`, overrides the `https://bedrock-runtime.<region>.amazonaws.com`
+    /// endpoint (e.g. a VPC endpoint); for `vertex`,
+    /// `https://<region>-aiplatform.googleapis.com`.
     #[serde(default)]
     pub url: Option<String>,
+    /// How long cached query embeddings stay valid, in seconds. Repeated
+    /// searches with the same normalized query reuse the cached vector
+    /// instead of calling the provider. `0` disables the cache.
+    /// Default: `86400` (one day).
+    #[serde(default = "default_query_cache_ttl_secs")]
+    pub query_cache_ttl_secs: u64,
+    /// API key for hosted providers, usually a `secret://` reference (see
+    /// [`crate::secrets`]). Falls back to `OPENAI_API_KEY` when unset.
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// Text prepended to every search query before embedding, e.g.
+    /// `"query: "` for E5 or `"Represent this sentence for searching
+    /// relevant passages: "` for BGE. Default: none.
+    #[serde(default)]
+    pub query_prefix: String,
+    /// Text prepended to every chunk before embedding, e.g. `"passage: "`
+    /// for E5. Default: none.
+    #[serde(default)]
+    pub document_prefix: String,
+    /// Maximum input length in tokens (estimated at 4 characters per token,
+    /// like the chunker), prefix included. Longer inputs are cut according
+    /// to `truncate`. Default: no limit.
+    #[serde(default)]
+    pub max_input_tokens: Option<usize>,
+    /// Which part of an over-long input is cut: `"end"` (default) keeps the
+    /// beginning, `"start"` keeps the end, `"middle"` keeps both ends.
+    #[serde(default = "default_truncate")]
+    pub truncate: String,
+    /// AWS region for `bedrock`. Falls back to `AWS_REGION`, then `AWS_DEFAULT_REGION`.
+    /// Google Cloud region for `vertex`. Falls back to `GOOGLE_CLOUD_LOCATION`,
+    /// then `"us-central1"`.
+    #[serde(default)]
+    pub region: Option<String>,
+    /// AWS access key ID for `bedrock` (`secret://` allowed). Falls back to `AWS_ACCESS_KEY_ID`.
+    #[serde(default)]
+    pub access_key_id: Option<String>,
+    /// AWS secret access key for `bedrock` (`secret://` allowed). Falls back to `AWS_SECRET_ACCESS_KEY`.
+    #[serde(default)]
+    pub secret_access_key: Option<String>,
+    /// AWS session token for `bedrock` (`secret://` allowed). Falls back to `AWS_SESSION_TOKEN`.
+    #[serde(default)]
+    pub session_token: Option<String>,
+    /// Service account key file (JSON) for `vertex`. Falls back to
+    /// `GOOGLE_APPLICATION_CREDENTIALS`.
+    #[serde(default)]
+    pub credentials_file: Option<PathBuf>,
+    /// Google Cloud project for `vertex`. Falls back to the key's
+    /// `project_id`, then `GOOGLE_CLOUD_PROJECT`.
+    #[serde(default)]
+    pub project: Option<String>,
+    /// Search and embed during sync even when stored vectors have a
+    /// different dimension than the provider (see
+    /// [`crate::embedding::check_stored_dims`]). `--force` on `ctx search`
+    /// and `ctx sync` sets this for one run. Default: `false`.
+    #[serde(default)]
+    pub allow_dims_mismatch: bool,
+    /// Document-level vectors used to route semantic search (see
+    /// [`crate::doc_vectors`]): `"off"` (default), `"mean"` to average each
+    /// document's chunk vectors, or `"title"` to embed its title and summary.
+    #[serde(default = "default_document_vectors")]
+    pub document_vectors: String,
+    /// How many documents the document-level stage keeps before chunk
+    /// vectors are scored. Ignored when `document_vectors` is `"off"`.
+    /// Default: `200`.
+    #[serde(default = "default_route_documents")]
+    pub route_documents: usize,
 }
 
 impl Default for EmbeddingConfig {
     fn default() -> Self {
         Self {
             provider: "disabled".to_string(),
+            providers: Vec::new(),
+            fallback: HashMap::new(),
             model: None,
             dims: None,
             batch_size: 64,
             max_retries: 5,
             timeout_secs: 30,
             url: None,
+            query_cache_ttl_secs: default_query_cache_ttl_secs(),
+            api_key: None,
+            query_prefix: String::new(),
+            document_prefix: String::new(),
+            max_input_tokens: None,
+            truncate: default_truncate(),
+            region: None,
+            access_key_id: None,
+            secret_access_key: None,
+            session_token: None,
+            credentials_file: None,
+            project: None,
+            allow_dims_mismatch: false,
+            document_vectors: default_document_vectors(),
+            route_documents: default_route_documents(),
         }
     }
 }
 
+/// Settings of a fallback embedding provider (`[embedding.fallback.<provider>]`).
+///
+/// Unset fields are unset for the fallback, not taken from `[embedding]`:
+/// the primary's model rarely exists on another provider.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct EmbeddingFallbackConfig {
+    /// Model name on this provider.
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Vector dimensionality; must match the primary's.
+    #[serde(default)]
+    pub dims: Option<usize>,
+    /// Base URL, as `[embedding] url`.
+    #[serde(default)]
+    pub url: Option<String>,
+    /// API key, as `[embedding] api_key`.
+    #[serde(default)]
+    pub api_key: Option<String>,
+}
+
 fn default_provider() -> String {
     "disabled".to_string()
 }
+
+fn default_truncate() -> String {
+    "end".to_string()
+}
+
+fn default_document_vectors() -> String {
+    "off".to_string()
+}
+
+fn default_route_documents() -> usize {
+    200
+}
+
+/// Chat model configuration for `ctx agent run` and LLM chunk contexts.
+///
+/// Both providers are called through the OpenAI-compatible
+/// `/chat/completions` API with streaming and tool calling.
+///
+/// # Example
+///
+/// ```toml
+/// [llm]
+/// provider = "openai"            # "disabled" | "openai" | "ollama"
+/// model = "gpt-4o-mini"
+/// api_key = "secret://openai"    # default: OPENAI_API_KEY
+/// max_turns = 8
+/// ```
+#[derive(Debug, Deserialize, Clone)]
+pub struct LlmConfig {
+    /// Provider name: `"disabled"`, `"openai"`, or `"ollama"`. Default: `"disabled"`.
+    #[serde(default = "default_provider")]
+    pub provider: String,
+    /// Chat model name (e.g. `"gpt-4o-mini"`, `"llama3.1"`). Required unless disabled.
+    #[serde(default)]
+    pub model: Option<String>,
+    /// API base URL. Default: `https://api.openai.com/v1` for `openai`,
+    /// `http://localhost:11434/v1` for `ollama`.
+    #[serde(default)]
+    pub url: Option<String>,
+    /// API key, usually a `secret://` reference. Falls back to
+    /// `OPENAI_API_KEY`; not needed for Ollama.
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// Sampling temperature. Default: provider default.
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    /// Maximum model calls per user message (tool-calling rounds). Default: `8`.
+    #[serde(default = "default_llm_max_turns")]
+    pub max_turns: usize,
+    /// HTTP timeout per model call in seconds. Default: `120`.
+    #[serde(default = "default_llm_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+impl Default for LlmConfig {
+    fn default() -> Self {
+        Self {
+            provider: "disabled".to_string(),
+            model: None,
+            url: None,
+            api_key: None,
+            temperature: None,
+            max_turns: default_llm_max_turns(),
+            timeout_secs: default_llm_timeout_secs(),
+        }
+    }
+}
+
+impl LlmConfig {
+    /// Whether a chat provider is configured.
+    pub fn is_enabled(&self) -> bool {
+        self.provider != "disabled"
+    }
+}
+
+fn default_llm_max_turns() -> usize {
+    8
+}
+
+fn default_llm_timeout_secs() -> u64 {
+    120
+}
+
+fn default_query_cache_ttl_secs() -> u64 {
+    86_400
+}
 fn default_batch_size() -> usize {
     64
 }
@@ -373,45 +1161,1136 @@ fn default_vector_fallback() -> String {
     "sqlite".to_string()
 }
 
-/// HTTP server configuration.
-#[derive(Debug, Deserialize, Clone)]
-pub struct ServerConfig {
-    /// Socket address to bind to (e.g. `"127.0.0.1:7331"`).
-    pub bind: String,
-}
-
-/// Container for all connector configurations.
+/// Optional external keyword index.
 ///
-/// All connector types use named instances — you can configure multiple
-/// of each type. For example:
+/// With `backend = "opensearch"` (or `"elasticsearch"`), chunks are mirrored
+/// into the named index after every sync and keyword candidates come from a
+/// BM25 `multi_match` query there. SQLite stays canonical and vectors stay
+/// local.
 ///
 /// ```toml
-/// [connectors.git.platform]
-/// url = "https://github.com/acme/platform.git"
-///
-/// [connectors.git.auth-service]
-/// url = "https://github.com/acme/auth-service.git"
+/// [keyword_index]
+/// backend = "opensearch"
+/// url = "https://search.internal:9200"
+/// index = "context-harness"
+/// username = "ctx"
+/// password = "secret://opensearch-password"
 /// ```
-///
-/// Use `ctx sync git` to sync all git connectors, or `ctx sync git:platform`
+#[derive(Debug, Deserialize, Clone)]
+pub struct KeywordIndexConfig {
+    /// `"sqlite"` (FTS5, the default), `"opensearch"`, or `"elasticsearch"`.
+    #[serde(default = "default_keyword_backend")]
+    pub backend: String,
+    /// Cluster base URL, e.g. `http://localhost:9200`.
+    #[serde(default)]
+    pub url: Option<String>,
+    /// Index holding one document per chunk. Default: `"context-harness"`.
+    #[serde(default = "default_keyword_index_name")]
+    pub index: String,
+    /// Basic-auth user name.
+    #[serde(default)]
+    pub username: Option<String>,
+    /// Basic-auth password (`secret://` allowed).
+    #[serde(default)]
+    pub password: Option<String>,
+    /// Elasticsearch API key sent as `Authorization: ApiKey` (`secret://` allowed).
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// What to do when the cluster fails: `"sqlite"` (default) answers keyword
+    /// queries from FTS5 and keeps syncing; `"disabled"` fails the command.
+    #[serde(default = "default_keyword_fallback")]
+    pub fallback: String,
+    /// Per-request timeout in seconds. Default: 10.
+    #[serde(default = "default_keyword_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+impl KeywordIndexConfig {
+    /// Whether keyword search is delegated to an external cluster.
+    pub fn is_external(&self) -> bool {
+        self.backend != "sqlite"
+    }
+}
+
+impl Default for KeywordIndexConfig {
+    fn default() -> Self {
+        Self {
+            backend: default_keyword_backend(),
+            url: None,
+            index: default_keyword_index_name(),
+            username: None,
+            password: None,
+            api_key: None,
+            fallback: default_keyword_fallback(),
+            timeout_secs: default_keyword_timeout_secs(),
+        }
+    }
+}
+
+fn default_keyword_backend() -> String {
+    "sqlite".to_string()
+}
+
+fn default_keyword_index_name() -> String {
+    "context-harness".to_string()
+}
+
+fn default_keyword_fallback() -> String {
+    "sqlite".to_string()
+}
+
+fn default_keyword_timeout_secs() -> u64 {
+    10
+}
+
+/// HTTP server configuration.
+#[derive(Debug, Deserialize, Clone)]
+pub struct ServerConfig {
+    /// Socket address to bind to (e.g. `"127.0.0.1:7331"`).
+    pub bind: String,
+    /// Seconds to wait for in-flight tool calls after SIGTERM/Ctrl-C before
+    /// exiting anyway (default: 30).
+    #[serde(default = "default_shutdown_grace_secs")]
+    pub shutdown_grace_secs: u64,
+    /// Maximum cached `search` responses; `0` disables the cache (default: 256).
+    #[serde(default = "default_search_cache_size")]
+    pub search_cache_size: usize,
+    /// Seconds a cached `search` response stays valid (default: 300).
+    #[serde(default = "default_search_cache_ttl_secs")]
+    pub search_cache_ttl_secs: u64,
+    /// Reload tools, agents, and connectors when the config file or one of
+    /// their scripts changes (default: false). See [`crate::reload`].
+    #[serde(default)]
+    pub watch: bool,
+    /// Seconds between change checks when `watch` is enabled (default: 2).
+    #[serde(default = "default_watch_interval_secs")]
+    pub watch_interval_secs: u64,
+    /// API keys and the sources each may read. See [`crate::auth`].
+    #[serde(default)]
+    pub auth: ServerAuthConfig,
+    /// Socket address for the gRPC interface (e.g. `"127.0.0.1:7332"`).
+    /// Requires a build with `--features grpc`. Default: none (no gRPC).
+    #[serde(default)]
+    pub grpc_bind: Option<String>,
+    /// Concurrency limits for tool calls. See [`crate::limits`].
+    #[serde(default)]
+    pub limits: ServerLimitsConfig,
+    /// Default size limits of `get` and `search` responses. See
+    /// [`crate::response_limits`].
+    #[serde(default)]
+    pub responses: ServerResponsesConfig,
+}
+
+/// Concurrency limits for tool calls served over HTTP, MCP, and gRPC.
+///
+/// A call that finds its slots taken waits in a queue for up to
+/// `queue_timeout_ms`. When the queue is full or the wait times out, the
+/// call is rejected with `429 Too Many Requests` and a `Retry-After`
+/// header. Tools called from other tools (`context.call_tool`) run inside
+/// their caller's slot and are not limited again.
+///
+/// # Example
+///
+/// ```toml
+/// [server.limits]
+/// max_concurrent = 32          # all tools together; 0 = unlimited
+/// max_queued = 64
+/// queue_timeout_ms = 5000
+///
+/// [server.limits.tools]
+/// summarize = 2                # Lua tool that calls an LLM
+/// search = 16
+/// ```
+#[derive(Debug, Deserialize, Clone)]
+pub struct ServerLimitsConfig {
+    /// Tool calls that may run at once across all tools; `0` means
+    /// unlimited (default: 0).
+    #[serde(default)]
+    pub max_concurrent: usize,
+    /// Per-tool limits on concurrent calls, by tool name (default: none).
+    #[serde(default)]
+    pub tools: BTreeMap<String, usize>,
+    /// Calls that may wait for a slot at once; further calls are rejected
+    /// immediately (default: 64).
+    #[serde(default = "default_max_queued")]
+    pub max_queued: usize,
+    /// Milliseconds a queued call waits for a slot (default: 5000).
+    #[serde(default = "default_queue_timeout_ms")]
+    pub queue_timeout_ms: u64,
+    /// `Retry-After` seconds sent with rejected calls (default: 1).
+    #[serde(default = "default_retry_after_secs")]
+    pub retry_after_secs: u64,
+}
+
+impl Default for ServerLimitsConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrent: 0,
+            tools: BTreeMap::new(),
+            max_queued: default_max_queued(),
+            queue_timeout_ms: default_queue_timeout_ms(),
+            retry_after_secs: default_retry_after_secs(),
+        }
+    }
+}
+
+impl ServerLimitsConfig {
+    /// `true` when any limit is configured.
+    pub fn is_enabled(&self) -> bool {
+        self.max_concurrent > 0 || !self.tools.is_empty()
+    }
+}
+
+fn default_max_queued() -> usize {
+    64
+}
+
+fn default_queue_timeout_ms() -> u64 {
+    5000
+}
+
+fn default_retry_after_secs() -> u64 {
+    1
+}
+
+/// Default size limits of `get` and `search` responses.
+///
+/// A call's own `max_body_bytes` / `max_chunks` parameters override these.
+/// Cut responses report what was left out and the offset to request next.
+///
+/// # Example
+///
+/// ```toml
+/// [server.responses]
+/// max_body_bytes = 100_000     # get: body bytes; search: snippet bytes
+/// max_chunks = 50              # get: chunks; search: results
+/// ```
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct ServerResponsesConfig {
+    /// Bytes of a `get` body, or of all `search` snippets together
+    /// (default: unlimited).
+    #[serde(default)]
+    pub max_body_bytes: Option<usize>,
+    /// Chunks of a `get` response, or `search` results (default: unlimited).
+    #[serde(default)]
+    pub max_chunks: Option<usize>,
+}
+
+/// API keys required by the server, each optionally limited to some sources.
+///
+/// With no keys configured the server is open. Otherwise every request
+/// except the health, readiness, and metrics probes must send one of the
+/// keys as `Authorization: Bearer <key>` or `X-Api-Key: <key>`.
+///
+/// # Example
+///
+/// ```toml
+/// [server.auth.collections]
+/// engineering = ["git:*", "filesystem:docs"]
+///
+/// [[server.auth.keys]]
+/// name = "hr-bot"
+/// key = "secret://ctx-hr-key"
+/// allowed_sources = ["sharepoint:people"]
+///
+/// [[server.auth.keys]]
+/// name = "eng"
+/// key = "secret://ctx-eng-key"
+/// allowed_collections = ["engineering"]
+///
+/// [[server.auth.keys]]
+/// name = "admin"                # no allowed_* lists: every source
+/// key = "secret://ctx-admin-key"
+/// ```
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct ServerAuthConfig {
+    /// Accepted API keys. Default: none (no authentication).
+    #[serde(default)]
+    pub keys: Vec<ApiKeyConfig>,
+    /// Named groups of source patterns, referenced by `allowed_collections`.
+    #[serde(default)]
+    pub collections: BTreeMap<String, Vec<String>>,
+}
+
+/// One `[[server.auth.keys]]` entry.
+///
+/// Source patterns match a source exactly (`filesystem:hr`) or, ending in
+/// `*`, by prefix (`git:*`). A key with neither `allowed_sources` nor
+/// `allowed_collections` may read every source.
+#[derive(Debug, Deserialize, Clone)]
+pub struct ApiKeyConfig {
+    /// Name used in logs and error messages.
+    pub name: String,
+    /// The key itself (`secret://` allowed).
+    pub key: String,
+    /// Source patterns this key may read.
+    #[serde(default)]
+    pub allowed_sources: Vec<String>,
+    /// `[server.auth.collections]` this key may read.
+    #[serde(default)]
+    pub allowed_collections: Vec<String>,
+}
+
+fn default_shutdown_grace_secs() -> u64 {
+    30
+}
+
+fn default_search_cache_size() -> usize {
+    256
+}
+
+fn default_search_cache_ttl_secs() -> u64 {
+    300
+}
+
+fn default_watch_interval_secs() -> u64 {
+    2
+}
+
+/// Container for all connector configurations.
+///
+/// All connector types use named instances — you can configure multiple
+/// of each type. For example:
+///
+/// ```toml
+/// [connectors.git.platform]
+/// url = "https://github.com/acme/platform.git"
+///
+/// [connectors.git.auth-service]
+/// url = "https://github.com/acme/auth-service.git"
+/// ```
+///
+/// Use `ctx sync git` to sync all git connectors, or `ctx sync git:platform`
 /// for a specific one. `ctx sync all` syncs everything.
 #[derive(Debug, Deserialize, Clone, Default)]
 pub struct ConnectorsConfig {
     /// Named filesystem connectors: walk local directories.
     #[serde(default)]
-    pub filesystem: HashMap<String, FilesystemConnectorConfig>,
-    /// Named Git connectors: clone and scan Git repositories.
+    pub filesystem: HashMap<String, FilesystemConnectorConfig>,
+    /// Named Git connectors: clone and scan Git repositories.
+    #[serde(default)]
+    pub git: HashMap<String, GitConnectorConfig>,
+    /// Named S3 connectors: list and download from S3 buckets.
+    #[serde(default)]
+    pub s3: HashMap<String, S3ConnectorConfig>,
+    /// Named SharePoint / OneDrive connectors (Microsoft Graph).
+    #[serde(default)]
+    pub sharepoint: HashMap<String, SharePointConnectorConfig>,
+    /// Named Zendesk connectors: tickets and help-center articles.
+    #[serde(default)]
+    pub zendesk: HashMap<String, ZendeskConnectorConfig>,
+    /// Named Stack Overflow connectors: questions with their answers.
+    #[serde(default)]
+    pub stackoverflow: HashMap<String, StackOverflowConnectorConfig>,
+    /// Named Lua script connectors.
+    /// Each key is a connector name, each value contains the script path
+    /// and arbitrary config keys passed to the Lua `connector.scan()` function.
+    /// See `docs/LUA_CONNECTORS.md` for the full specification.
+    #[serde(default)]
+    pub script: HashMap<String, ScriptConnectorConfig>,
+}
+
+impl ConnectorsConfig {
+    /// The `chunking` overrides of the connector named by `source_label`.
+    pub fn chunking_override(&self, source_label: &str) -> Option<&ChunkingOverride> {
+        let (kind, name) = source_label.split_once(':')?;
+        match kind {
+            "filesystem" => self.filesystem.get(name)?.chunking.as_ref(),
+            "git" => self.git.get(name)?.chunking.as_ref(),
+            "s3" => self.s3.get(name)?.chunking.as_ref(),
+            "sharepoint" => self.sharepoint.get(name)?.chunking.as_ref(),
+            "zendesk" => self.zendesk.get(name)?.chunking.as_ref(),
+            "stackoverflow" => self.stackoverflow.get(name)?.chunking.as_ref(),
+            "script" => self.script.get(name)?.chunking.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// The `stitch` rules of the connector named by `source_label`.
+    pub fn stitch_rules(&self, source_label: &str) -> &[StitchRule] {
+        let Some((kind, name)) = source_label.split_once(':') else {
+            return &[];
+        };
+        let rules = match kind {
+            "filesystem" => self.filesystem.get(name).map(|c| &c.stitch),
+            "git" => self.git.get(name).map(|c| &c.stitch),
+            "s3" => self.s3.get(name).map(|c| &c.stitch),
+            "sharepoint" => self.sharepoint.get(name).map(|c| &c.stitch),
+            "zendesk" => self.zendesk.get(name).map(|c| &c.stitch),
+            "stackoverflow" => self.stackoverflow.get(name).map(|c| &c.stitch),
+            "script" => self.script.get(name).map(|c| &c.stitch),
+            _ => None,
+        };
+        rules.map_or(&[], Vec::as_slice)
+    }
+
+    /// Source labels of every connector, sorted.
+    pub fn source_labels(&self) -> Vec<String> {
+        let mut labels: Vec<String> = self
+            .filesystem
+            .keys()
+            .map(|name| format!("filesystem:{}", name))
+            .chain(self.git.keys().map(|name| format!("git:{}", name)))
+            .chain(self.s3.keys().map(|name| format!("s3:{}", name)))
+            .chain(
+                self.sharepoint
+                    .keys()
+                    .map(|name| format!("sharepoint:{}", name)),
+            )
+            .chain(self.zendesk.keys().map(|name| format!("zendesk:{}", name)))
+            .chain(
+                self.stackoverflow
+                    .keys()
+                    .map(|name| format!("stackoverflow:{}", name)),
+            )
+            .chain(self.script.keys().map(|name| format!("script:{}", name)))
+            .collect();
+        labels.sort();
+        labels
+    }
+
+    /// Every configured `chunking` override, keyed by source label.
+    pub fn chunking_overrides(&self) -> Vec<(String, &ChunkingOverride)> {
+        let mut overrides = Vec::new();
+        for (name, c) in &self.filesystem {
+            if let Some(chunking) = &c.chunking {
+                overrides.push((format!("filesystem:{}", name), chunking));
+            }
+        }
+        for (name, c) in &self.git {
+            if let Some(chunking) = &c.chunking {
+                overrides.push((format!("git:{}", name), chunking));
+            }
+        }
+        for (name, c) in &self.s3 {
+            if let Some(chunking) = &c.chunking {
+                overrides.push((format!("s3:{}", name), chunking));
+            }
+        }
+        for (name, c) in &self.sharepoint {
+            if let Some(chunking) = &c.chunking {
+                overrides.push((format!("sharepoint:{}", name), chunking));
+            }
+        }
+        for (name, c) in &self.zendesk {
+            if let Some(chunking) = &c.chunking {
+                overrides.push((format!("zendesk:{}", name), chunking));
+            }
+        }
+        for (name, c) in &self.stackoverflow {
+            if let Some(chunking) = &c.chunking {
+                overrides.push((format!("stackoverflow:{}", name), chunking));
+            }
+        }
+        for (name, c) in &self.script {
+            if let Some(chunking) = &c.chunking {
+                overrides.push((format!("script:{}", name), chunking));
+            }
+        }
+        overrides.sort_by(|a, b| a.0.cmp(&b.0));
+        overrides
+    }
+}
+
+/// Ingest-time document summaries: `[summary]`.
+///
+/// Documents whose body is at least `min_chars` long get a summary of at
+/// most `max_chars`, stored with the document, indexed for keyword search
+/// with `[retrieval.weights] summary`, and returned in search results. The
+/// summary is extractive (the body's most representative sentences) unless
+/// `llm = true`. See [`crate::summarize`].
+///
+/// # Example
+///
+/// ```toml
+/// [summary]
+/// enabled = true
+/// min_chars = 8000
+/// max_chars = 600
+/// llm = true                     # write summaries with [llm]
+/// ```
+#[derive(Debug, Deserialize, Clone)]
+pub struct SummaryConfig {
+    /// Summarize long documents at ingest. Default: `false`.
+    #[serde(default)]
+    pub enabled: bool,
+    /// Body length in characters from which a document is summarized.
+    /// Default: `8000`.
+    #[serde(default = "default_summary_min_chars")]
+    pub min_chars: usize,
+    /// Maximum summary length in characters. Default: `600`.
+    #[serde(default = "default_summary_max_chars")]
+    pub max_chars: usize,
+    /// Write summaries with the `[llm]` model instead of extracting
+    /// sentences. A failed call falls back to the extractive summary.
+    /// Default: `false`.
+    #[serde(default)]
+    pub llm: bool,
+    /// Document tokens sent to the model. Longer documents are cut.
+    /// Default: `8000`.
+    #[serde(default = "default_summary_document_tokens")]
+    pub max_document_tokens: usize,
+}
+
+impl Default for SummaryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_chars: default_summary_min_chars(),
+            max_chars: default_summary_max_chars(),
+            llm: false,
+            max_document_tokens: default_summary_document_tokens(),
+        }
+    }
+}
+
+fn default_summary_min_chars() -> usize {
+    8000
+}
+
+fn default_summary_max_chars() -> usize {
+    600
+}
+
+fn default_summary_document_tokens() -> usize {
+    8000
+}
+
+/// Conversation memory: `[memory]`.
+///
+/// With `enabled = true`, `ctx serve mcp` registers the `memory.append`
+/// tool, which writes chat transcripts through the ingest pipeline under
+/// the `source` label so later sessions can search them. See
+/// [`crate::memory`].
+///
+/// # Example
+///
+/// ```toml
+/// [memory]
+/// enabled = true
+/// source = "memory"              # source label of appended transcripts
+/// retention_days = 90            # delete older transcripts; default: keep
+/// dedup = true                   # skip transcripts already stored
+/// max_bytes = 1048576
+/// ```
+#[derive(Debug, Deserialize, Clone)]
+pub struct MemoryConfig {
+    /// Register the `memory.append` tool. Default: `false`.
+    #[serde(default)]
+    pub enabled: bool,
+    /// Source label transcripts are stored under. Default: `"memory"`.
+    #[serde(default = "default_memory_source")]
+    pub source: String,
+    /// Delete transcripts last updated more than this many days ago on
+    /// each append. Default: keep them.
+    #[serde(default)]
+    pub retention_days: Option<u64>,
+    /// Return the stored document instead of adding a transcript whose
+    /// text is already stored; a call can override it. Default: `true`.
+    #[serde(default = "default_true")]
+    pub dedup: bool,
+    /// Largest transcript accepted, in bytes. Default: `1048576` (1 MiB).
+    #[serde(default = "default_memory_max_bytes")]
+    pub max_bytes: usize,
+}
+
+impl Default for MemoryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            source: default_memory_source(),
+            retention_days: None,
+            dedup: true,
+            max_bytes: default_memory_max_bytes(),
+        }
+    }
+}
+
+fn default_memory_source() -> String {
+    "memory".to_string()
+}
+
+fn default_memory_max_bytes() -> usize {
+    1024 * 1024
+}
+
+/// Ingest-time document types: `[classify]`.
+///
+/// Each document is tagged with the first type whose rule matches, stored
+/// as `doc_type` in its metadata, filterable with `ctx search --type` and
+/// `type:` in queries, and counted by `ctx stats`. Rules in `types` are
+/// tried before the built-in `runbook`, `adr`, `api_reference`, and
+/// `meeting_notes` rules; a rule with a built-in name replaces it. With
+/// `embedding = true`, documents no rule matches get the type whose
+/// `examples` are closest, if any is close enough. See [`crate::classify`].
+///
+/// # Example
+///
+/// ```toml
+/// [classify]
+/// enabled = true
+///
+/// [classify.types.postmortem]
+/// paths = ["**/postmortems/**"]
+/// title_patterns = ["(?i)post-?mortem"]
+/// body_patterns = ["(?im)^#+ *root cause", "(?im)^#+ *timeline"]
+/// examples = ["Incident review: timeline, root cause, and follow-up actions"]
+/// ```
+#[derive(Debug, Deserialize, Clone)]
+pub struct ClassifyConfig {
+    /// Tag documents with a type at ingest. Default: `false`.
+    #[serde(default)]
+    pub enabled: bool,
+    /// Use the built-in rules after those in `types`. Default: `true`.
+    #[serde(default = "default_true")]
+    pub builtin: bool,
+    /// Custom types, by name.
+    #[serde(default)]
+    pub types: BTreeMap<String, DocTypeRule>,
+    /// Fall back to the type with the nearest `examples` centroid, using
+    /// `[embedding]`. Default: `false`.
+    #[serde(default)]
+    pub embedding: bool,
+    /// Cosine similarity a document needs to its nearest centroid to get
+    /// that type. Default: `0.5`.
+    #[serde(default = "default_classify_min_similarity")]
+    pub min_similarity: f32,
+}
+
+impl Default for ClassifyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            builtin: true,
+            types: BTreeMap::new(),
+            embedding: false,
+            min_similarity: default_classify_min_similarity(),
+        }
+    }
+}
+
+/// Patterns that identify one document type. A document matches when a
+/// path glob or title pattern matches, or when at least
+/// `min_body_matches` of the body patterns do.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct DocTypeRule {
+    /// Globs matched against the document's `source_id`.
+    #[serde(default)]
+    pub paths: Vec<String>,
+    /// Regular expressions matched against the title.
+    #[serde(default)]
+    pub title_patterns: Vec<String>,
+    /// Regular expressions matched against the start of the body.
+    #[serde(default)]
+    pub body_patterns: Vec<String>,
+    /// Body patterns that must match. Default: `2`.
+    #[serde(default = "default_min_body_matches")]
+    pub min_body_matches: usize,
+    /// Sample texts whose mean embedding is the type's centroid for
+    /// `embedding = true`.
+    #[serde(default)]
+    pub examples: Vec<String>,
+}
+
+fn default_classify_min_similarity() -> f32 {
+    0.5
+}
+
+fn default_min_body_matches() -> usize {
+    2
+}
+
+/// Ingest-time redaction configuration.
+///
+/// Items from the listed connectors have emails, phone numbers, API keys,
+/// and any custom patterns replaced before they are stored. See
+/// [`crate::redact`] for the built-in detectors.
+///
+/// # Example
+///
+/// ```toml
+/// [redaction]
+/// connectors = ["script:zendesk"]
+/// detectors = ["email", "phone", "api_key"]
+/// replacement = "[REDACTED:{kind}]"
+///
+/// [redaction.patterns]
+/// employee_id = 'EMP-\d{6}'
+/// ```
+#[derive(Debug, Deserialize, Clone)]
+pub struct RedactionConfig {
+    /// Connectors to redact: `"all"`, a type (`"git"`), or `"type:name"`.
+    /// Default: none.
+    #[serde(default)]
+    pub connectors: Vec<String>,
+    /// Built-in detectors to apply. Default: all of them.
+    #[serde(default = "default_redaction_detectors")]
+    pub detectors: Vec<String>,
+    /// Replacement text; `{kind}` becomes the detector name.
+    #[serde(default = "default_redaction_replacement")]
+    pub replacement: String,
+    /// Additional named regex detectors.
+    #[serde(default)]
+    pub patterns: HashMap<String, String>,
+}
+
+impl Default for RedactionConfig {
+    fn default() -> Self {
+        Self {
+            connectors: Vec::new(),
+            detectors: default_redaction_detectors(),
+            replacement: default_redaction_replacement(),
+            patterns: HashMap::new(),
+        }
+    }
+}
+
+fn default_redaction_detectors() -> Vec<String> {
+    crate::redact::BUILTIN_DETECTORS
+        .iter()
+        .map(|s| s.to_string())
+        .collect()
+}
+
+fn default_redaction_replacement() -> String {
+    "[REDACTED:{kind}]".to_string()
+}
+
+/// Index size budget.
+///
+/// When `max_size_mb` is set, `ctx sync` evicts documents after ingesting
+/// until the database fits the budget again. See [`crate::storage`].
+///
+/// # Example
+///
+/// ```toml
+/// [storage]
+/// max_size_mb = 500
+/// eviction = "lru"          # or "oldest"
+/// warn_at_percent = 90
+/// ```
+#[derive(Debug, Deserialize, Clone)]
+pub struct StorageConfig {
+    /// Maximum database size in MiB. Default: unlimited.
+    #[serde(default)]
+    pub max_size_mb: Option<u64>,
+    /// Which documents to evict first: `"lru"` (least recently returned by
+    /// search or get) or `"oldest"` (oldest `updated_at`). Default: `"lru"`.
+    #[serde(default = "default_eviction")]
+    pub eviction: String,
+    /// Warn after sync once the database reaches this share of the budget.
+    /// Default: `90`.
+    #[serde(default = "default_warn_at_percent")]
+    pub warn_at_percent: u8,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            max_size_mb: None,
+            eviction: default_eviction(),
+            warn_at_percent: default_warn_at_percent(),
+        }
+    }
+}
+
+impl StorageConfig {
+    /// The budget in bytes, if one is configured.
+    pub fn budget_bytes(&self) -> Option<u64> {
+        self.max_size_mb.map(|mb| mb * 1024 * 1024)
+    }
+
+    /// Whether LRU eviction needs search and get to record retrievals.
+    pub fn tracks_retrievals(&self) -> bool {
+        self.max_size_mb.is_some() && self.eviction == "lru"
+    }
+}
+
+/// Sync settings: inline embedding and downloads shared by the remote
+/// connectors.
+///
+/// With `embed` on (the default) and an embedding provider configured,
+/// `ctx sync` embeds chunks in batches as it stores them, so no separate
+/// `ctx embed pending` pass is needed. `ctx sync --embed` / `--no-embed`
+/// override it for one run.
+///
+/// S3 object downloads are throttled to `max_bandwidth_mbps` and resume
+/// from partial files after a failure; Git clones resume from a partially
+/// fetched cache directory. See [`crate::download`].
+///
+/// With `timeout_secs` set, each connector gets that long to scan and
+/// ingest. A connector that runs over is stopped like a failed scan: the
+/// documents it already wrote are kept and its checkpoint is unchanged.
+///
+/// Extraction, redaction, hashing, normalization, and chunking run on a
+/// pool of `cpu_threads` threads while documents are written and embedded
+/// on the async runtime.
+///
+/// An item that fails to fetch, extract, or write is reported and recorded
+/// for `ctx sync --retry-failed` while the sync continues; `strict = true`
+/// fails the sync on the first one instead (see [`crate::sync_failures`]).
+///
+/// `content_dedup` handles items whose body another source already stored
+/// (see [`crate::content_dedup`]).
+///
+/// # Example
+///
+/// ```toml
+/// [sync]
+/// embed = false               # leave embedding to `ctx embed pending`
+/// max_bandwidth_mbps = 20     # megabits per second; default: unlimited
+/// download_retries = 3
+/// keep_raw = ["script:jira"]  # keep items for `ctx reprocess`
+/// timeout_secs = 1800         # per connector; default: unlimited
+/// cpu_threads = 4             # default: one per CPU
+/// stale_after_hours = 48      # `ctx sources` flags older syncs as stale
+/// strict = false              # true: the first failed item fails the sync
+/// content_dedup = "link"      # same content from two sources: keep-all, link, or skip
+///
+/// [sync.stale_after]          # per type or instance, in hours
+/// "git:platform" = 6
+/// ```
+#[derive(Debug, Deserialize, Clone)]
+pub struct SyncConfig {
+    /// Embed chunks while syncing when `[embedding]` is enabled. Default: `true`.
+    #[serde(default = "default_true")]
+    pub embed: bool,
+    /// Download bandwidth cap in megabits per second. Default: unlimited.
+    #[serde(default)]
+    pub max_bandwidth_mbps: Option<f64>,
+    /// Attempts per download (S3 object or Git fetch) in one sync before
+    /// giving up until the next sync. Default: `3`.
+    #[serde(default = "default_download_retries")]
+    pub download_retries: u32,
+    /// Connectors whose items are kept as delivered, for `ctx reprocess`:
+    /// `"all"`, a type, or an instance (see [`crate::raw_items`]).
+    /// Default: none.
+    #[serde(default)]
+    pub keep_raw: Vec<String>,
+    /// Time limit for each connector's scan and ingest, in seconds.
+    /// Default: unlimited.
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+    /// Threads for the CPU-bound ingest stages (extraction, redaction,
+    /// hashing, normalization, chunking). Default: one per CPU.
+    #[serde(default)]
+    pub cpu_threads: Option<usize>,
+    /// Hours after its last completed sync that `ctx sources` reports a
+    /// source as stale. Default: `48`.
+    #[serde(default = "default_stale_after_hours")]
+    pub stale_after_hours: u64,
+    /// Per-source staleness thresholds in hours, keyed by connector type or
+    /// instance (see [`crate::sync_status`]). Default: none.
+    #[serde(default)]
+    pub stale_after: BTreeMap<String, u64>,
+    /// Fail the sync on the first item that cannot be fetched, extracted,
+    /// or written, instead of recording it and continuing. Default: `false`.
+    #[serde(default)]
+    pub strict: bool,
+    /// What to do with an item whose content another source already
+    /// stored: `"keep-all"` (default), `"link"` (store it without chunks,
+    /// linked to the first), or `"skip"`.
+    #[serde(default = "default_content_dedup")]
+    pub content_dedup: String,
+}
+
+impl Default for SyncConfig {
+    fn default() -> Self {
+        Self {
+            embed: true,
+            max_bandwidth_mbps: None,
+            download_retries: default_download_retries(),
+            keep_raw: Vec::new(),
+            timeout_secs: None,
+            cpu_threads: None,
+            stale_after_hours: default_stale_after_hours(),
+            stale_after: BTreeMap::new(),
+            strict: false,
+            content_dedup: default_content_dedup(),
+        }
+    }
+}
+
+fn default_download_retries() -> u32 {
+    3
+}
+
+fn default_stale_after_hours() -> u64 {
+    48
+}
+
+fn default_content_dedup() -> String {
+    "keep-all".to_string()
+}
+
+/// Webhook notifications for finished `ctx sync` and `ctx embed` runs.
+///
+/// Each webhook receives a summary of the run (counts, duration, and any
+/// errors) as generic JSON or as a Slack message. See [`crate::notify`].
+///
+/// # Example
+///
+/// ```toml
+/// [[notifications.webhooks]]
+/// url = "secret://slack-webhook"
+/// format = "slack"
+/// on = ["failure"]
+///
+/// [[notifications.webhooks]]
+/// url = "https://ops.example.com/hooks/ctx"
+/// runs = ["sync"]
+/// ```
+#[derive(Debug, Deserialize, Clone)]
+pub struct NotificationsConfig {
+    /// Webhooks to notify. Default: none.
+    #[serde(default)]
+    pub webhooks: Vec<WebhookConfig>,
+    /// Per-request timeout in seconds. Default: `10`.
+    #[serde(default = "default_notify_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        Self {
+            webhooks: Vec::new(),
+            timeout_secs: default_notify_timeout_secs(),
+        }
+    }
+}
+
+/// One webhook in `[[notifications.webhooks]]`.
+#[derive(Debug, Deserialize, Clone)]
+pub struct WebhookConfig {
+    /// Endpoint to POST to; may be a `secret://` reference.
+    pub url: String,
+    /// Payload format: `"json"` (the run report) or `"slack"` (incoming
+    /// webhook message). Default: `"json"`.
+    #[serde(default = "default_webhook_format")]
+    pub format: String,
+    /// Outcomes to notify on: `"success"`, `"failure"`. Default: both.
+    #[serde(default = "default_webhook_on")]
+    pub on: Vec<String>,
+    /// Runs to notify about: `"sync"`, `"embed"`. Default: both.
+    #[serde(default = "default_webhook_runs")]
+    pub runs: Vec<String>,
+}
+
+fn default_notify_timeout_secs() -> u64 {
+    10
+}
+
+fn default_webhook_format() -> String {
+    "json".to_string()
+}
+
+fn default_webhook_on() -> Vec<String> {
+    vec!["success".to_string(), "failure".to_string()]
+}
+
+fn default_webhook_runs() -> Vec<String> {
+    vec!["sync".to_string(), "embed".to_string()]
+}
+
+/// OpenTelemetry trace export over OTLP/HTTP.
+///
+/// Exports the spans of sync runs (ingest, embedding calls, writes) and of
+/// server requests (MCP requests, tool execution). See [`crate::telemetry`].
+///
+/// # Example
+///
+/// ```toml
+/// [telemetry]
+/// enabled = true
+/// endpoint = "http://otel-collector:4318"
+/// service_name = "ctx-docs"
+/// sample_ratio = 0.25
+///
+/// [telemetry.headers]
+/// x-honeycomb-team = "secret://honeycomb-key"
+/// ```
+#[derive(Debug, Deserialize, Clone)]
+pub struct TelemetryConfig {
+    /// Export traces. Default: `false`.
+    #[serde(default)]
+    pub enabled: bool,
+    /// OTLP/HTTP base URL; `/v1/traces` is appended. Default: the
+    /// `OTEL_EXPORTER_OTLP_ENDPOINT` environment variable, or
+    /// `http://localhost:4318`.
+    #[serde(default)]
+    pub endpoint: Option<String>,
+    /// Headers sent with every export, e.g. backend API keys. Values may be
+    /// `secret://` references. Default: none.
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// `service.name` resource attribute. Default: `"context-harness"`.
+    #[serde(default = "default_telemetry_service_name")]
+    pub service_name: String,
+    /// Share of traces recorded, from `0.0` to `1.0`. Traces continued from
+    /// an incoming `traceparent` follow the caller's sampling decision.
+    /// Default: `1.0`.
+    #[serde(default = "default_telemetry_sample_ratio")]
+    pub sample_ratio: f64,
+    /// Export request timeout in seconds. Default: `10`.
+    #[serde(default = "default_telemetry_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: None,
+            headers: BTreeMap::new(),
+            service_name: default_telemetry_service_name(),
+            sample_ratio: default_telemetry_sample_ratio(),
+            timeout_secs: default_telemetry_timeout_secs(),
+        }
+    }
+}
+
+fn default_telemetry_service_name() -> String {
+    "context-harness".to_string()
+}
+
+fn default_telemetry_sample_ratio() -> f64 {
+    1.0
+}
+
+fn default_telemetry_timeout_secs() -> u64 {
+    10
+}
+
+/// Where `ctx self update` finds releases, and the startup version check.
+///
+/// See [`crate::self_update`].
+///
+/// # Example
+///
+/// ```toml
+/// [update]
+/// check = true
+/// check_interval_hours = 24
+/// public_key = "RWS43i3famiUEdJhuANcZmHBo55NvbZFTgTsMofR6OgJK6kJ6jnnftpV"
+/// ```
+#[derive(Debug, Deserialize, Clone)]
+pub struct UpdateConfig {
+    /// Print a notice on stderr when a newer release exists. Default:
+    /// `false`.
+    #[serde(default)]
+    pub check: bool,
+    /// Hours between the startup check's release lookups. Default: `24`.
+    #[serde(default = "default_update_check_interval_hours")]
+    pub check_interval_hours: u64,
+    /// GitHub repository publishing releases, as `owner/name`. Default:
+    /// `"parallax-labs/context-harness"`.
+    #[serde(default = "default_update_repo")]
+    pub repo: String,
+    /// GitHub API base URL. Default: `"https://api.github.com"`.
+    #[serde(default = "default_update_api_url")]
+    pub api_url: String,
+    /// Minisign public key releases are signed with. When set, updates
+    /// require a valid `.minisig` signature besides the checksum. Default:
+    /// none (checksum only).
     #[serde(default)]
-    pub git: HashMap<String, GitConnectorConfig>,
-    /// Named S3 connectors: list and download from S3 buckets.
+    pub public_key: Option<String>,
+}
+
+impl Default for UpdateConfig {
+    fn default() -> Self {
+        Self {
+            check: false,
+            check_interval_hours: default_update_check_interval_hours(),
+            repo: default_update_repo(),
+            api_url: default_update_api_url(),
+            public_key: None,
+        }
+    }
+}
+
+fn default_update_check_interval_hours() -> u64 {
+    24
+}
+
+fn default_update_repo() -> String {
+    "parallax-labs/context-harness".to_string()
+}
+
+fn default_update_api_url() -> String {
+    "https://api.github.com".to_string()
+}
+
+/// Search federation across several indexes.
+///
+/// Federated searches run the query against this config's own database
+/// (origin `"local"`) and every configured index, then merge the results.
+/// See [`crate::federation`].
+///
+/// # Example
+///
+/// ```toml
+/// [federation]
+/// enabled = true               # federate every search; otherwise use --federated
+///
+/// [federation.indexes.work]
+/// db = "~/work/.ctx/data/ctx.sqlite"
+///
+/// [federation.indexes.wiki]
+/// url = "http://wiki-box:7331"
+/// api_key = "secret://wiki-ctx"
+/// ```
+#[derive(Debug, Deserialize, Clone)]
+pub struct FederationConfig {
+    /// Federate every `search` (CLI and server) without `--federated`.
+    /// Default: `false`.
     #[serde(default)]
-    pub s3: HashMap<String, S3ConnectorConfig>,
-    /// Named Lua script connectors.
-    /// Each key is a connector name, each value contains the script path
-    /// and arbitrary config keys passed to the Lua `connector.scan()` function.
-    /// See `docs/LUA_CONNECTORS.md` for the full specification.
+    pub enabled: bool,
+    /// Include this config's own database as origin `"local"`. Default: `true`.
+    #[serde(default = "default_true")]
+    pub include_local: bool,
+    /// Timeout for each remote index request in seconds. Default: `10`.
+    #[serde(default = "default_federation_timeout_secs")]
+    pub timeout_secs: u64,
+    /// Named indexes to search, keyed by the origin name shown on results.
     #[serde(default)]
-    pub script: HashMap<String, ScriptConnectorConfig>,
+    pub indexes: BTreeMap<String, FederatedIndexConfig>,
+}
+
+impl Default for FederationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            include_local: true,
+            timeout_secs: default_federation_timeout_secs(),
+            indexes: BTreeMap::new(),
+        }
+    }
+}
+
+fn default_federation_timeout_secs() -> u64 {
+    10
+}
+
+/// One federated index: another local database or a remote ctx server.
+#[derive(Debug, Deserialize, Clone)]
+pub struct FederatedIndexConfig {
+    /// Path to another Context Harness SQLite database.
+    #[serde(default)]
+    pub db: Option<PathBuf>,
+    /// Base URL of a running `ctx serve mcp` server.
+    #[serde(default)]
+    pub url: Option<String>,
+    /// Bearer token sent to the remote server (`secret://` allowed).
+    #[serde(default)]
+    pub api_key: Option<String>,
+}
+
+fn default_eviction() -> String {
+    "lru".to_string()
+}
+
+fn default_warn_at_percent() -> u8 {
+    90
 }
 
 /// Lua script connector configuration.
@@ -439,6 +2318,16 @@ pub struct ScriptConnectorConfig {
     /// Maximum execution time in seconds. Default: `300`.
     #[serde(default = "default_script_timeout")]
     pub timeout: u64,
+    /// Chunking overrides for this connector's documents.
+    #[serde(default)]
+    pub chunking: Option<ChunkingOverride>,
+    /// Rules combining this connector's items into stitched documents.
+    #[serde(default)]
+    pub stitch: Vec<StitchRule>,
+    /// Environment variables the script's `env.get` may read. Default: all
+    /// of them, or none for scripts installed from a registry.
+    #[serde(default)]
+    pub env_allowlist: Option<Vec<String>>,
     /// All other config keys — passed to the Lua `connector.scan()` function.
     #[serde(flatten)]
     pub extra: toml::Table,
@@ -453,13 +2342,89 @@ fn default_script_timeout() -> u64 {
 /// Tool scripts are Lua files that define MCP tools agents can discover
 /// and call via the HTTP server. See `docs/LUA_TOOLS.md` for the full
 /// specification.
-#[derive(Debug, Deserialize, Clone, Default)]
+#[derive(Debug, Deserialize, Clone)]
 pub struct ToolsConfig {
     /// Named Lua tool scripts.
     /// Each key is the tool name, each value contains the script path
     /// and arbitrary config keys accessible via `context.config` in the script.
     #[serde(default)]
     pub script: HashMap<String, ScriptToolConfig>,
+    /// Tool invocation audit log (disabled by default).
+    #[serde(default)]
+    pub audit: ToolAuditConfig,
+    /// How deeply tools may call each other through `context.call_tool`
+    /// (see [`crate::traits::ToolContext::call_tool`]). A tool called by a
+    /// client runs at depth 0; `0` disables composition. Default: `4`.
+    #[serde(default = "default_max_call_depth")]
+    pub max_call_depth: usize,
+}
+
+impl Default for ToolsConfig {
+    fn default() -> Self {
+        Self {
+            script: HashMap::new(),
+            audit: ToolAuditConfig::default(),
+            max_call_depth: default_max_call_depth(),
+        }
+    }
+}
+
+fn default_max_call_depth() -> usize {
+    4
+}
+
+/// Tool invocation audit log configuration.
+///
+/// When enabled, every tool call made through the HTTP server or the MCP
+/// endpoint is recorded with the tool name, caller identity, redacted
+/// parameters, duration, and outcome. Entries are queryable via `ctx tool log`.
+///
+/// # Example
+///
+/// ```toml
+/// [tools.audit]
+/// enabled = true
+/// sink = "sqlite"          # or "jsonl"
+/// redact = ["password", "token", "secret", "api_key"]
+/// ```
+#[derive(Debug, Deserialize, Clone)]
+pub struct ToolAuditConfig {
+    /// Whether tool calls are recorded. Default: `false`.
+    #[serde(default)]
+    pub enabled: bool,
+    /// Where entries are written: `"sqlite"` (the `tool_audit` table) or
+    /// `"jsonl"` (one JSON object per line). Default: `"sqlite"`.
+    #[serde(default = "default_audit_sink")]
+    pub sink: String,
+    /// JSONL file path. Default: `tool-audit.jsonl` next to the database.
+    #[serde(default)]
+    pub path: Option<PathBuf>,
+    /// Parameter keys whose values are replaced with `"[REDACTED]"`.
+    /// Matching is case-insensitive and applies to nested objects.
+    #[serde(default = "default_audit_redact")]
+    pub redact: Vec<String>,
+}
+
+impl Default for ToolAuditConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            sink: default_audit_sink(),
+            path: None,
+            redact: default_audit_redact(),
+        }
+    }
+}
+
+fn default_audit_sink() -> String {
+    "sqlite".to_string()
+}
+
+fn default_audit_redact() -> Vec<String> {
+    ["password", "token", "secret", "api_key", "authorization"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect()
 }
 
 /// Lua tool script configuration.
@@ -486,6 +2451,10 @@ pub struct ScriptToolConfig {
     /// Maximum execution time in seconds. Default: `30`.
     #[serde(default = "default_tool_timeout")]
     pub timeout: u64,
+    /// Environment variables the script's `env.get` may read. Default: all
+    /// of them, or none for scripts installed from a registry.
+    #[serde(default)]
+    pub env_allowlist: Option<Vec<String>>,
     /// All other config keys — accessible via `context.config` in the script.
     #[serde(flatten)]
     pub extra: toml::Table,
@@ -541,6 +2510,20 @@ pub struct AgentsConfig {
 /// You are a software architect. Search for ADRs and design
 /// docs to ground your recommendations.
 /// """
+/// resources = ["0b6f3c1e-..."]   # document IDs attached to the prompt
+/// model = { temperature = 0.2 }
+///
+/// [[agents.inline.architect.tool_calls]]
+/// tool = "search"
+/// arguments = { query = "architecture decision record" }
+/// reason = "Find existing ADRs first"
+///
+/// # searched at resolve time; hits are appended to the system prompt
+/// [[agents.inline.architect.context]]
+/// query = "{{component}} design"
+/// mode = "hybrid"
+/// limit = 3
+/// heading = "Existing designs"
 /// ```
 #[derive(Debug, Deserialize, Clone)]
 pub struct InlineAgentConfig {
@@ -550,6 +2533,18 @@ pub struct InlineAgentConfig {
     pub tools: Vec<String>,
     /// The system prompt text.
     pub system_prompt: String,
+    /// Tool calls suggested at the start of the conversation.
+    #[serde(default)]
+    pub tool_calls: Vec<SuggestedToolCall>,
+    /// Document IDs attached to the prompt as resources.
+    #[serde(default)]
+    pub resources: Vec<String>,
+    /// Model parameter hints (`temperature`, `top_p`, `max_tokens`).
+    #[serde(default)]
+    pub model: ModelHints,
+    /// Searches whose top hits are injected into the system prompt.
+    #[serde(default)]
+    pub context: Vec<ContextQuery>,
 }
 
 /// Lua script agent configuration.
@@ -576,6 +2571,10 @@ pub struct ScriptAgentConfig {
     /// Maximum execution time in seconds. Default: `30`.
     #[serde(default = "default_agent_timeout")]
     pub timeout: u64,
+    /// Environment variables the script's `env.get` may read. Default: all
+    /// of them, or none for scripts installed from a registry.
+    #[serde(default)]
+    pub env_allowlist: Option<Vec<String>>,
     /// All other config keys — passed to the Lua `agent.resolve()` function.
     #[serde(flatten)]
     pub extra: toml::Table,
@@ -600,6 +2599,7 @@ fn default_agent_timeout() -> u64 {
 /// path = "~/.local/share/ctx/registries/community"
 /// readonly = true
 /// auto_update = true
+/// public_key = "RWS43i3famiUEdJhuANcZmHBo55NvbZFTgTsMofR6OgJK6kJ6jnnftpV"
 /// ```
 #[derive(Debug, Deserialize, Clone)]
 pub struct RegistryConfig {
@@ -618,6 +2618,14 @@ pub struct RegistryConfig {
     #[serde(default)]
     #[allow(dead_code)]
     pub auto_update: bool,
+    /// Minisign public key (`RW…`) the registry's scripts must be signed
+    /// with. See [`crate::registry`] for how scripts are verified.
+    #[serde(default)]
+    pub public_key: Option<String>,
+    /// Run scripts from this registry that have no checksum or signature.
+    /// `--allow-unsigned` sets this for every registry.
+    #[serde(default)]
+    pub allow_unsigned: bool,
 }
 
 /// Filesystem connector configuration.
@@ -634,6 +2642,8 @@ pub struct RegistryConfig {
 /// exclude_globs = ["**/drafts/**"]
 /// follow_symlinks = false
 /// max_extract_bytes = 50_000_000
+/// max_file_size = 1_000_000
+/// text_extensions = [".log"]
 /// ```
 #[derive(Debug, Deserialize, Clone)]
 pub struct FilesystemConnectorConfig {
@@ -651,6 +2661,28 @@ pub struct FilesystemConnectorConfig {
     /// Files larger than this (bytes) are not extracted; they are skipped and counted in extraction skipped. Default: 50_000_000.
     #[serde(default = "default_max_extract_bytes")]
     pub max_extract_bytes: u64,
+    /// Skip files larger than this many bytes. Default: no limit.
+    #[serde(default)]
+    pub max_file_size: Option<u64>,
+    /// Skip files smaller than this many bytes. Default: `0`.
+    #[serde(default)]
+    pub min_file_size: u64,
+    /// Skip files that look binary (NUL bytes or invalid UTF-8). Default: `true`.
+    #[serde(default = "default_true")]
+    pub detect_binary: bool,
+    /// Extensions (e.g. `".log"`) always read as text, bypassing binary
+    /// detection; invalid UTF-8 is replaced rather than skipped. Default: `[]`.
+    #[serde(default)]
+    pub text_extensions: Vec<String>,
+    /// Descend into `.zip`, `.tar`, and `.tar.gz` files (disabled by default).
+    #[serde(default)]
+    pub archives: ArchiveConfig,
+    /// Chunking overrides for this connector's documents.
+    #[serde(default)]
+    pub chunking: Option<ChunkingOverride>,
+    /// Rules combining this connector's items into stitched documents.
+    #[serde(default)]
+    pub stitch: Vec<StitchRule>,
 }
 
 /// Git connector configuration.
@@ -676,6 +2708,14 @@ pub struct GitConnectorConfig {
     /// Branch to clone/pull. Default: `"main"`.
     #[serde(default = "default_git_branch")]
     pub branch: String,
+    /// Branches to index, as names or globs (`"release/*"`). Default: `[]`,
+    /// which indexes `branch` only. Setting this or `tags` prefixes source
+    /// IDs with the ref (see [`crate::connector_git`]).
+    #[serde(default)]
+    pub branches: Vec<String>,
+    /// Tags to index, as names or globs (`"v2.*"`). Default: `[]`.
+    #[serde(default)]
+    pub tags: Vec<String>,
     /// Subdirectory within the repo to scan. Default: `"."` (entire repo).
     #[serde(default = "default_git_root")]
     pub root: String,
@@ -692,6 +2732,12 @@ pub struct GitConnectorConfig {
     /// when using the workspace DB, otherwise `<db-dir>/.git-cache/<url-hash>/`.
     #[serde(default)]
     pub cache_dir: Option<PathBuf>,
+    /// Chunking overrides for this connector's documents.
+    #[serde(default)]
+    pub chunking: Option<ChunkingOverride>,
+    /// Rules combining this connector's items into stitched documents.
+    #[serde(default)]
+    pub stitch: Vec<StitchRule>,
 }
 
 /// Amazon S3 connector configuration.
@@ -722,19 +2768,395 @@ pub struct S3ConnectorConfig {
     pub bucket: String,
     /// Key prefix to filter objects. Default: `""` (entire bucket).
     #[serde(default)]
-    pub prefix: String,
-    /// AWS region. Default: `"us-east-1"`.
-    #[serde(default = "default_s3_region")]
-    pub region: String,
-    /// Glob patterns for object keys to include. Default: `["**/*.md", "**/*.txt"]`.
-    #[serde(default = "default_include_globs")]
-    pub include_globs: Vec<String>,
-    /// Glob patterns for object keys to exclude. Default: `[]`.
+    pub prefix: String,
+    /// AWS region. Default: `"us-east-1"`.
+    #[serde(default = "default_s3_region")]
+    pub region: String,
+    /// Glob patterns for object keys to include. Default: `["**/*.md", "**/*.txt"]`.
+    #[serde(default = "default_include_globs")]
+    pub include_globs: Vec<String>,
+    /// Glob patterns for object keys to exclude. Default: `[]`.
+    #[serde(default)]
+    pub exclude_globs: Vec<String>,
+    /// Custom endpoint URL for S3-compatible services (MinIO, LocalStack).
+    #[serde(default)]
+    pub endpoint_url: Option<String>,
+    /// Access key ID, usually a `secret://` reference. Falls back to
+    /// `AWS_ACCESS_KEY_ID` when unset.
+    #[serde(default)]
+    pub access_key_id: Option<String>,
+    /// Secret access key, usually a `secret://` reference. Falls back to
+    /// `AWS_SECRET_ACCESS_KEY` when unset.
+    #[serde(default)]
+    pub secret_access_key: Option<String>,
+    /// Session token for temporary credentials. Falls back to
+    /// `AWS_SESSION_TOKEN` when unset.
+    #[serde(default)]
+    pub session_token: Option<String>,
+    /// Directory for partial downloads and the download checkpoint.
+    /// Default: `.ctx/cache/s3/<name>/` when using the workspace DB,
+    /// otherwise `<db-dir>/.s3-cache/<name>/`.
+    #[serde(default)]
+    pub cache_dir: Option<PathBuf>,
+    /// Descend into `.zip`, `.tar`, and `.tar.gz` objects (disabled by default).
+    #[serde(default)]
+    pub archives: ArchiveConfig,
+    /// Number of listings in flight. Above `1`, the sub-prefixes directly
+    /// under `prefix` (split at `/`) are listed concurrently. Default: `1`.
+    #[serde(default = "default_s3_list_concurrency")]
+    pub list_concurrency: usize,
+    /// Filter JSON and CSV objects server-side with S3 Select instead of
+    /// downloading them whole (disabled by default).
+    #[serde(default)]
+    pub select: Option<S3SelectConfig>,
+    /// Chunking overrides for this connector's documents.
+    #[serde(default)]
+    pub chunking: Option<ChunkingOverride>,
+    /// Rules combining this connector's items into stitched documents.
+    #[serde(default)]
+    pub stitch: Vec<StitchRule>,
+}
+
+fn default_s3_list_concurrency() -> usize {
+    1
+}
+
+/// S3 Select pre-filtering for one S3 connector instance.
+///
+/// Objects matching `globs` (and the connector's include globs) are read
+/// with `SelectObjectContent`: S3 runs `expression` and returns only the
+/// matching records, as JSON Lines. Objects with no matching records are
+/// skipped. The input format follows the extension: `.csv`, `.json`
+/// (one JSON document), or `.jsonl` / `.ndjson` (one record per line),
+/// each optionally `.gz`-compressed. See [`crate::connector_s3`].
+///
+/// # Example
+///
+/// ```toml
+/// [connectors.s3.tickets.select]
+/// expression = "SELECT s.title, s.body FROM S3Object s WHERE s.team = 'platform'"
+/// globs = ["exports/**/*.jsonl.gz"]
+/// ```
+#[derive(Debug, Deserialize, Clone)]
+pub struct S3SelectConfig {
+    /// SQL expression run against each object, in S3 Select syntax.
+    pub expression: String,
+    /// Object keys (relative to `prefix`) to read through S3 Select.
+    /// Default: `["**/*.csv", "**/*.json", "**/*.jsonl", "**/*.ndjson"]`
+    /// and their `.gz` variants.
+    #[serde(default = "default_s3_select_globs")]
+    pub globs: Vec<String>,
+    /// Whether CSV objects start with a header row, so columns can be
+    /// referenced by name (`s.title`) rather than position (`s._1`).
+    /// Default: `true`.
+    #[serde(default = "default_true")]
+    pub csv_header: bool,
+}
+
+fn default_s3_select_globs() -> Vec<String> {
+    ["csv", "json", "jsonl", "ndjson"]
+        .iter()
+        .flat_map(|ext| [format!("**/*.{}", ext), format!("**/*.{}.gz", ext)])
+        .collect()
+}
+
+/// Archive ingestion settings for the filesystem and S3 connectors.
+///
+/// When enabled, archives that are not excluded are opened and each file
+/// inside becomes a document whose `source_id` is
+/// `<archive path>!/<inner path>`. Include and exclude globs are matched
+/// against that full ID. See [`crate::archive`].
+///
+/// # Example
+///
+/// ```toml
+/// [connectors.filesystem.releases.archives]
+/// enabled = true
+/// max_depth = 1               # archives inside archives are skipped
+/// max_entry_bytes = 10_000_000
+/// max_total_bytes = 200_000_000
+/// ```
+#[derive(Debug, Deserialize, Clone)]
+pub struct ArchiveConfig {
+    /// Whether archives are opened. Default: `false`.
+    #[serde(default)]
+    pub enabled: bool,
+    /// Levels of archive nesting to descend into; `1` opens top-level
+    /// archives only. Default: `1`.
+    #[serde(default = "default_archive_max_depth")]
+    pub max_depth: usize,
+    /// Files inside an archive larger than this (bytes, uncompressed) are
+    /// skipped. Default: `10_000_000`.
+    #[serde(default = "default_archive_max_entry_bytes")]
+    pub max_entry_bytes: u64,
+    /// Archives larger than this, or whose contents add up to more than
+    /// this (bytes, uncompressed), are skipped. Default: `200_000_000`.
+    #[serde(default = "default_archive_max_total_bytes")]
+    pub max_total_bytes: u64,
+}
+
+impl Default for ArchiveConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_depth: default_archive_max_depth(),
+            max_entry_bytes: default_archive_max_entry_bytes(),
+            max_total_bytes: default_archive_max_total_bytes(),
+        }
+    }
+}
+
+fn default_archive_max_depth() -> usize {
+    1
+}
+
+fn default_archive_max_entry_bytes() -> u64 {
+    10_000_000
+}
+
+fn default_archive_max_total_bytes() -> u64 {
+    200_000_000
+}
+
+/// SharePoint / OneDrive connector configuration.
+///
+/// Reads a document library (drive) through Microsoft Graph using an Entra ID
+/// app registration with client-credentials auth. Exactly one of `site`,
+/// `drive_id`, or `user` selects the drive. See [`crate::connector_sharepoint`]
+/// for the full implementation.
+///
+/// # Example
+///
+/// ```toml
+/// [connectors.sharepoint.eng]
+/// tenant_id = "contoso.onmicrosoft.com"
+/// client_id = "00000000-0000-0000-0000-000000000000"
+/// client_secret = "secret://sharepoint-eng"   # default: AZURE_CLIENT_SECRET
+/// site = "contoso.sharepoint.com:/sites/Engineering"
+/// drive = "Documents"
+/// folder = "Runbooks"
+/// ```
+#[derive(Debug, Deserialize, Clone)]
+pub struct SharePointConnectorConfig {
+    /// Directory (tenant) ID or domain.
+    pub tenant_id: String,
+    /// Application (client) ID of the app registration.
+    pub client_id: String,
+    /// Client secret, usually a `secret://` reference. Falls back to
+    /// `AZURE_CLIENT_SECRET` when unset.
+    #[serde(default)]
+    pub client_secret: Option<String>,
+    /// SharePoint site as `"<hostname>:/<site path>"` or a Graph site ID.
+    #[serde(default)]
+    pub site: Option<String>,
+    /// Document library name within `site`. Default: the site's default library.
+    #[serde(default)]
+    pub drive: Option<String>,
+    /// Graph drive ID, instead of `site`.
+    #[serde(default)]
+    pub drive_id: Option<String>,
+    /// User principal name whose OneDrive to read, instead of `site`.
+    #[serde(default)]
+    pub user: Option<String>,
+    /// Folder path within the drive to index. Default: the whole drive.
+    #[serde(default)]
+    pub folder: Option<String>,
+    /// Glob patterns for paths (relative to `folder`) to include.
+    /// Default: Markdown, text, PDF, and Office documents.
+    #[serde(default = "default_sharepoint_include_globs")]
+    pub include_globs: Vec<String>,
+    /// Glob patterns for paths to exclude. Default: `[]`.
+    #[serde(default)]
+    pub exclude_globs: Vec<String>,
+    /// Use Graph delta queries so later syncs only fetch changes. Default: `true`.
+    #[serde(default = "default_true")]
+    pub delta: bool,
+    /// Files larger than this are not downloaded. Default: 50 MB.
+    #[serde(default = "default_max_extract_bytes")]
+    pub max_extract_bytes: u64,
+    /// Graph API base URL. Default: `https://graph.microsoft.com/v1.0`.
+    #[serde(default = "default_graph_url")]
+    pub graph_url: String,
+    /// Identity platform base URL. Default: `https://login.microsoftonline.com`.
+    #[serde(default = "default_authority_url")]
+    pub authority_url: String,
+    /// Chunking overrides for this connector's documents.
+    #[serde(default)]
+    pub chunking: Option<ChunkingOverride>,
+    /// Rules combining this connector's items into stitched documents.
+    #[serde(default)]
+    pub stitch: Vec<StitchRule>,
+}
+
+fn default_sharepoint_include_globs() -> Vec<String> {
+    ["md", "txt", "pdf", "docx", "pptx", "xlsx"]
+        .iter()
+        .map(|ext| format!("**/*.{}", ext))
+        .collect()
+}
+
+fn default_graph_url() -> String {
+    "https://graph.microsoft.com/v1.0".to_string()
+}
+
+fn default_authority_url() -> String {
+    "https://login.microsoftonline.com".to_string()
+}
+
+/// Zendesk connector configuration.
+///
+/// Reads support tickets (each with its comment thread) and help-center
+/// articles through the Zendesk incremental export APIs, authenticating
+/// with an agent's email and API token. See [`crate::connector_zendesk`]
+/// for the full implementation.
+///
+/// # Example
+///
+/// ```toml
+/// [connectors.zendesk.support]
+/// subdomain = "acme"
+/// email = "bot@acme.com"
+/// api_token = "secret://zendesk-support"   # default: ZENDESK_API_TOKEN
+/// statuses = ["open", "pending", "solved"]
+/// brands = ["Acme Cloud"]
+/// ```
+#[derive(Debug, Deserialize, Clone)]
+pub struct ZendeskConnectorConfig {
+    /// Account subdomain: `acme` for `acme.zendesk.com`.
+    #[serde(default)]
+    pub subdomain: Option<String>,
+    /// Email of the agent that owns the API token.
+    pub email: String,
+    /// API token, usually a `secret://` reference. Falls back to
+    /// `ZENDESK_API_TOKEN` when unset.
+    #[serde(default)]
+    pub api_token: Option<String>,
+    /// Index tickets and their comments. Default: `true`.
+    #[serde(default = "default_true")]
+    pub tickets: bool,
+    /// Index published help-center articles. Default: `true`.
+    #[serde(default = "default_true")]
+    pub articles: bool,
+    /// Ticket statuses to index (`new`, `open`, `pending`, `hold`,
+    /// `solved`, `closed`). Default: all; deleted tickets are never indexed.
+    #[serde(default)]
+    pub statuses: Vec<String>,
+    /// Brands whose tickets to index, by name, subdomain, or ID.
+    /// Default: all brands.
+    #[serde(default)]
+    pub brands: Vec<String>,
+    /// Include internal notes in ticket threads. Default: `false`.
+    #[serde(default)]
+    pub internal_notes: bool,
+    /// Resume from the stored export cursors so later syncs only fetch
+    /// changes. Default: `true`.
+    #[serde(default = "default_true")]
+    pub incremental: bool,
+    /// API base URL, instead of `https://<subdomain>.zendesk.com`.
+    #[serde(default)]
+    pub base_url: Option<String>,
+    /// Chunking overrides for this connector's documents.
+    #[serde(default)]
+    pub chunking: Option<ChunkingOverride>,
+    /// Rules combining this connector's items into stitched documents.
+    #[serde(default)]
+    pub stitch: Vec<StitchRule>,
+}
+
+impl ZendeskConnectorConfig {
+    /// Ticket statuses accepted in `statuses`.
+    pub const STATUSES: [&'static str; 6] = ["new", "open", "pending", "hold", "solved", "closed"];
+
+    /// API base URL without a trailing slash.
+    pub fn api_base(&self) -> String {
+        match (&self.base_url, &self.subdomain) {
+            (Some(url), _) => url.trim_end_matches('/').to_string(),
+            (None, Some(subdomain)) => format!("https://{}.zendesk.com", subdomain),
+            (None, None) => String::new(),
+        }
+    }
+}
+
+/// Stack Overflow connector configuration.
+///
+/// Reads questions with their accepted and top answers through the Stack
+/// Exchange API 2.3, from a Stack Overflow for Teams team (with a personal
+/// access token) or from a public site by tag. See
+/// [`crate::connector_stackoverflow`] for the full implementation.
+///
+/// # Example
+///
+/// ```toml
+/// [connectors.stackoverflow.eng]
+/// team = "acme"
+/// access_token = "secret://so-teams"   # default: STACKOVERFLOW_ACCESS_TOKEN
+/// tags = ["deploy"]
+/// answers = 3
+/// ```
+#[derive(Debug, Deserialize, Clone)]
+pub struct StackOverflowConnectorConfig {
+    /// Stack Overflow for Teams team slug: `acme` for
+    /// `stackoverflowteams.com/c/acme`. Unset reads the public `site`.
+    #[serde(default)]
+    pub team: Option<String>,
+    /// Personal access token for `team`, usually a `secret://` reference.
+    /// Falls back to `STACKOVERFLOW_ACCESS_TOKEN` when unset.
+    #[serde(default)]
+    pub access_token: Option<String>,
+    /// Public Stack Exchange site, when `team` is unset. Default:
+    /// `"stackoverflow"`.
+    #[serde(default = "default_stackoverflow_site")]
+    pub site: String,
+    /// Index questions carrying any of these tags. Required for a public
+    /// site; default for a team: every question.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Stack Apps key, raising a public site's daily quota.
+    #[serde(default)]
+    pub key: Option<String>,
+    /// Only index questions with an accepted answer. Default: `true`.
+    #[serde(default = "default_true")]
+    pub accepted_only: bool,
+    /// Highest-scoring answers included besides the accepted one.
+    /// Default: `3`.
+    #[serde(default = "default_stackoverflow_answers")]
+    pub answers: usize,
+    /// Skip questions scoring below this. Default: none.
+    #[serde(default)]
+    pub min_score: Option<i64>,
+    /// Resume from the stored activity date so later syncs only fetch
+    /// questions active since. Default: `true`.
+    #[serde(default = "default_true")]
+    pub incremental: bool,
+    /// API base URL, instead of `https://api.stackoverflowteams.com/2.3`
+    /// for a team or `https://api.stackexchange.com/2.3` for a site.
+    #[serde(default)]
+    pub base_url: Option<String>,
+    /// Chunking overrides for this connector's documents.
     #[serde(default)]
-    pub exclude_globs: Vec<String>,
-    /// Custom endpoint URL for S3-compatible services (MinIO, LocalStack).
+    pub chunking: Option<ChunkingOverride>,
+    /// Rules combining this connector's items into stitched documents.
     #[serde(default)]
-    pub endpoint_url: Option<String>,
+    pub stitch: Vec<StitchRule>,
+}
+
+impl StackOverflowConnectorConfig {
+    /// API base URL without a trailing slash.
+    pub fn api_base(&self) -> String {
+        match (&self.base_url, &self.team) {
+            (Some(url), _) => url.trim_end_matches('/').to_string(),
+            (None, Some(_)) => "https://api.stackoverflowteams.com/2.3".to_string(),
+            (None, None) => "https://api.stackexchange.com/2.3".to_string(),
+        }
+    }
+}
+
+fn default_stackoverflow_site() -> String {
+    "stackoverflow".to_string()
+}
+
+fn default_stackoverflow_answers() -> usize {
+    3
 }
 
 fn default_git_branch() -> String {
@@ -766,6 +3188,29 @@ impl EmbeddingConfig {
     pub fn is_enabled(&self) -> bool {
         self.provider != "disabled"
     }
+
+    /// The configuration of each provider in `providers`, in order, or of
+    /// `provider` alone. Each has a single `provider` and no `providers`.
+    pub fn chain(&self) -> Vec<EmbeddingConfig> {
+        let single = EmbeddingConfig {
+            providers: Vec::new(),
+            fallback: HashMap::new(),
+            ..self.clone()
+        };
+        let mut chain = vec![single.clone()];
+        for name in self.providers.iter().skip(1) {
+            let fallback = self.fallback.get(name).cloned().unwrap_or_default();
+            chain.push(EmbeddingConfig {
+                provider: name.clone(),
+                model: fallback.model,
+                dims: fallback.dims,
+                url: fallback.url,
+                api_key: fallback.api_key,
+                ..single.clone()
+            });
+        }
+        chain
+    }
 }
 
 /// Load and validate a configuration file from disk.
@@ -778,11 +3223,24 @@ impl EmbeddingConfig {
 ///
 /// Returns an error if:
 /// - The file cannot be read or parsed
-/// - `chunking.max_tokens` is zero
+/// - `chunking.max_tokens` is zero, `strategy` is unknown, or `overlap_tokens >= max_tokens`
+///   (also checked for each connector's merged `chunking` overrides)
 /// - `retrieval.final_limit` is less than 1
 /// - `retrieval.hybrid_alpha` is outside `[0.0, 1.0]`
+/// - `retrieval.fts_tokenizer` is not a supported tokenizer
+/// - `retrieval.weights` has a negative weight or all weights are zero
+/// - `retrieval.bm25` has a negative `k1` or a `b` outside `[0.0, 1.0]`
+/// - `retrieval.popularity_weight` is negative or `popularity_half_life_days` is not positive
+/// - `retrieval.auto_merge_min_chunks` is `1`
+/// - `keyword_index` names an unknown backend or omits `url` for a cluster
 /// - Embedding provider is enabled but `model` or `dims` is missing/zero
 /// - Unknown embedding provider name
+/// - `[chunking.contextualize]` uses an unknown placeholder, or sets `llm` without an `[llm]` provider
+/// - `[summary]` has `max_chars = 0`, or sets `llm` without an `[llm]` provider
+/// - A `[[notifications.webhooks]]` entry has no `url` or an unknown `format`, outcome, or run
+/// - `telemetry.sample_ratio` is outside `[0.0, 1.0]`, `timeout_secs` is zero, or `service_name` is empty
+/// - `update.check_interval_hours` is zero, `repo` is not `owner/name`, or `public_key` does not parse
+/// - A `[[server.auth.keys]]` entry is unnamed, duplicated, or names an undefined collection
 #[allow(dead_code)]
 pub fn load_config(path: &Path) -> Result<Config> {
     load_config_file(path)
@@ -839,11 +3297,7 @@ pub fn ensure_workspace_config_for_init(explicit_path: Option<&Path>) -> Result<
         return Ok(paths.resolve().path);
     }
 
-    let ctx_dir = ctx_dirs::workspace_dir();
-    std::fs::create_dir_all(ctx_dirs::workspace_data_dir())?;
-    std::fs::create_dir_all(ctx_dirs::workspace_cache_dir())?;
-    std::fs::write(ctx_dir.join(".gitignore"), "data/\ncache/\n")?;
-
+    create_workspace_dirs()?;
     let config_path = ctx_dirs::workspace_config_path();
     if !config_path.exists() {
         std::fs::write(&config_path, default_workspace_config_toml())?;
@@ -851,11 +3305,24 @@ pub fn ensure_workspace_config_for_init(explicit_path: Option<&Path>) -> Result<
     Ok(Some(config_path))
 }
 
+/// Create `./.ctx` with its data and cache directories, which are
+/// git-ignored.
+pub fn create_workspace_dirs() -> Result<()> {
+    let ctx_dir = ctx_dirs::workspace_dir();
+    std::fs::create_dir_all(ctx_dirs::workspace_data_dir())?;
+    std::fs::create_dir_all(ctx_dirs::workspace_cache_dir())?;
+    std::fs::write(ctx_dir.join(".gitignore"), "data/\ncache/\n")?;
+    Ok(())
+}
+
 fn load_config_file(path: &Path) -> Result<Config> {
     let content = std::fs::read_to_string(path)
         .with_context(|| format!("Failed to read config file: {}", path.display()))?;
 
     let config: Config = toml::from_str(&content).with_context(|| "Failed to parse config file")?;
+    if let Ok(value) = toml::from_str::<toml::Value>(&content) {
+        scrub::register_config(&value);
+    }
     validate_config(config)
 }
 
@@ -866,16 +3333,149 @@ fn load_config_value(path: &Path) -> Result<toml::Value> {
 }
 
 fn config_from_value(value: toml::Value) -> Result<Config> {
+    scrub::register_config(&value);
     let config: Config = value
         .try_into()
         .with_context(|| "Failed to parse config file")?;
     validate_config(config)
 }
 
-fn validate_config(config: Config) -> Result<Config> {
+fn validate_archives(section: &str, archives: &ArchiveConfig) -> Result<()> {
+    if !archives.enabled {
+        return Ok(());
+    }
+    if archives.max_depth == 0 {
+        anyhow::bail!("{}.archives.max_depth must be >= 1", section);
+    }
+    if archives.max_entry_bytes == 0 || archives.max_total_bytes == 0 {
+        anyhow::bail!(
+            "{}.archives: max_entry_bytes and max_total_bytes must be > 0",
+            section
+        );
+    }
+    Ok(())
+}
+
+fn validate_chunking(section: &str, chunking: &ChunkingConfig) -> Result<()> {
+    if chunking.max_tokens == 0 {
+        anyhow::bail!("{}.max_tokens must be > 0", section);
+    }
+    if crate::chunk::ChunkStrategy::parse(&chunking.strategy).is_none() {
+        anyhow::bail!(
+            "{}.strategy must be 'paragraph' or 'line', got '{}'",
+            section,
+            chunking.strategy
+        );
+    }
+    if chunking.overlap_tokens >= chunking.max_tokens {
+        anyhow::bail!("{}.overlap_tokens must be less than max_tokens", section);
+    }
+    crate::normalize::Normalizer::from_config(&chunking.normalize)
+        .with_context(|| format!("invalid {}.normalize", section))?;
+    Ok(())
+}
+
+fn validate_stitch_rule(section: &str, rule: &StitchRule) -> Result<()> {
+    if rule.include.is_empty() {
+        anyhow::bail!("{}.include must list at least one glob", section);
+    }
+    for glob in &rule.include {
+        globset::Glob::new(glob)
+            .map_err(|e| anyhow::anyhow!("{}: invalid glob '{}': {}", section, glob, e))?;
+    }
+    match rule.group_by.as_str() {
+        "directory" => {}
+        "rule" => {
+            if rule.name.as_deref().is_none_or(|n| n.trim().is_empty()) {
+                anyhow::bail!("{}: group_by = \"rule\" needs a name", section);
+            }
+        }
+        other => anyhow::bail!(
+            "{}.group_by must be 'directory' or 'rule', got '{}'",
+            section,
+            other
+        ),
+    }
+    Ok(())
+}
+
+/// Check the settings one embedding provider needs; `section` names the
+/// table they came from.
+fn validate_embedding_provider(section: &str, provider: &EmbeddingConfig) -> Result<()> {
+    match provider.provider.as_str() {
+        "disabled" => {}
+        "openai" | "ollama" => {
+            if provider.dims.is_none() || provider.dims == Some(0) {
+                anyhow::bail!(
+                    "{}.dims must be > 0 when provider is '{}'",
+                    section,
+                    provider.provider
+                );
+            }
+            if provider.model.is_none() {
+                anyhow::bail!(
+                    "{}.model must be specified when provider is '{}'",
+                    section,
+                    provider.provider
+                );
+            }
+        }
+        "local" => {
+            // model and dims are optional for local — defaults applied at runtime
+        }
+        "bedrock" | "vertex" => {
+            if provider.model.is_none() {
+                anyhow::bail!(
+                    "{}.model must be specified when provider is '{}'",
+                    section,
+                    provider.provider
+                );
+            }
+            if provider.dims == Some(0) {
+                anyhow::bail!(
+                    "{}.dims must be > 0 when provider is '{}'",
+                    section,
+                    provider.provider
+                );
+            }
+        }
+        other => anyhow::bail!(
+            "Unknown embedding provider: '{}'. Must be disabled, openai, ollama, local, bedrock, or vertex.",
+            other
+        ),
+    }
+
+    Ok(())
+}
+
+fn validate_config(mut config: Config) -> Result<Config> {
     // Validate chunking
-    if config.chunking.max_tokens == 0 {
-        anyhow::bail!("chunking.max_tokens must be > 0");
+    validate_chunking("chunking", &config.chunking)?;
+    for (label, overrides) in config.connectors.chunking_overrides() {
+        validate_chunking(
+            &format!("connectors.{}.chunking", label.replacen(':', ".", 1)),
+            &config.chunking.with_override(overrides),
+        )?;
+    }
+
+    for label in config.connectors.source_labels() {
+        for (i, rule) in config.connectors.stitch_rules(&label).iter().enumerate() {
+            validate_stitch_rule(
+                &format!("connectors.{}.stitch[{}]", label.replacen(':', ".", 1), i),
+                rule,
+            )?;
+        }
+    }
+
+    match config.db.profile.as_str() {
+        "balanced" | "read-heavy" | "write-heavy" => {}
+        other => anyhow::bail!(
+            "Unknown db.profile: '{}'. Must be balanced, read-heavy, or write-heavy.",
+            other
+        ),
+    }
+    if config.db.gc_interval_hours == Some(0) {
+        anyhow::bail!("db.gc_interval_hours must be >= 1");
     }
 
     // Validate retrieval
@@ -887,32 +3487,200 @@ fn validate_config(config: Config) -> Result<Config> {
         anyhow::bail!("retrieval.hybrid_alpha must be in [0.0, 1.0]");
     }
 
+    match config.retrieval.fts_tokenizer.as_str() {
+        "unicode61" | "porter" | "trigram" => {}
+        other => anyhow::bail!(
+            "retrieval.fts_tokenizer must be unicode61, porter, or trigram (got '{}')",
+            other
+        ),
+    }
+
+    let weights = config.retrieval.weights;
+    if ![weights.text, weights.title, weights.summary]
+        .iter()
+        .all(|w| w.is_finite() && *w >= 0.0)
+        || weights.text + weights.title + weights.summary <= 0.0
+    {
+        anyhow::bail!("retrieval.weights.text, .title, and .summary must be >= 0, and not all 0");
+    }
+    let bm25 = config.retrieval.bm25;
+    for (section, params) in [
+        ("retrieval.bm25", bm25.chunks()),
+        ("retrieval.bm25.titles", bm25.titles()),
+    ] {
+        if !(params.k1.is_finite() && params.k1 >= 0.0) {
+            anyhow::bail!("{}.k1 must be >= 0 (got {})", section, params.k1);
+        }
+        if !(0.0..=1.0).contains(&params.b) {
+            anyhow::bail!("{}.b must be in [0.0, 1.0] (got {})", section, params.b);
+        }
+    }
+    if !(config.retrieval.popularity_weight.is_finite()
+        && config.retrieval.popularity_weight >= 0.0)
+    {
+        anyhow::bail!("retrieval.popularity_weight must be >= 0");
+    }
+    if !(config.retrieval.popularity_half_life_days.is_finite()
+        && config.retrieval.popularity_half_life_days > 0.0)
+    {
+        anyhow::bail!("retrieval.popularity_half_life_days must be > 0");
+    }
+
+    if config.retrieval.auto_merge_min_chunks == 1 {
+        anyhow::bail!("retrieval.auto_merge_min_chunks must be 0 (off) or >= 2");
+    }
+
     // Validate embedding
-    match config.embedding.provider.as_str() {
-        "disabled" => {}
-        "openai" | "ollama" => {
-            if config.embedding.dims.is_none() || config.embedding.dims == Some(0) {
+    let embedding = &mut config.embedding;
+    if let Some(first) = embedding.providers.first() {
+        if embedding.provider == "disabled" {
+            embedding.provider = first.clone();
+        } else if embedding.provider != *first {
+            anyhow::bail!(
+                "embedding.provider ('{}') must be the first of embedding.providers ('{}')",
+                embedding.provider,
+                first
+            );
+        }
+    }
+    for (i, name) in embedding.providers.iter().enumerate() {
+        if embedding.providers[..i].contains(name) {
+            anyhow::bail!("embedding.providers lists '{}' more than once", name);
+        }
+        if name == "disabled" {
+            anyhow::bail!("embedding.providers must not include 'disabled'");
+        }
+    }
+    if let Some(name) = embedding
+        .fallback
+        .keys()
+        .find(|name| !embedding.providers.iter().skip(1).any(|p| p == *name))
+    {
+        anyhow::bail!(
+            "[embedding.fallback.{}] is not a fallback in embedding.providers",
+            name
+        );
+    }
+    let chain = config.embedding.chain();
+    for (i, provider) in chain.iter().enumerate() {
+        let section = if i == 0 {
+            "embedding".to_string()
+        } else {
+            format!("embedding.fallback.{}", provider.provider)
+        };
+        validate_embedding_provider(&section, provider)?;
+    }
+    if let Some(dims) = chain[0].dims {
+        for fallback in &chain[1..] {
+            if fallback.dims.is_some_and(|d| d != dims) {
                 anyhow::bail!(
-                    "embedding.dims must be > 0 when provider is '{}'",
-                    config.embedding.provider
+                    "embedding.fallback.{}.dims must match embedding.dims ({}): vectors of different sizes cannot be compared",
+                    fallback.provider,
+                    dims
                 );
             }
-            if config.embedding.model.is_none() {
+        }
+    }
+
+    match config.embedding.truncate.as_str() {
+        "start" | "end" | "middle" => {}
+        other => anyhow::bail!(
+            "Unknown embedding.truncate: '{}'. Must be start, end, or middle.",
+            other
+        ),
+    }
+    match config.embedding.document_vectors.as_str() {
+        "off" | "mean" | "title" => {}
+        other => anyhow::bail!(
+            "Unknown embedding.document_vectors: '{}'. Must be off, mean, or title.",
+            other
+        ),
+    }
+    if config.embedding.document_vectors != "off" && config.embedding.route_documents == 0 {
+        anyhow::bail!("embedding.route_documents must be >= 1 when document_vectors is enabled");
+    }
+    if let Some(max) = config.embedding.max_input_tokens {
+        let prefix_tokens = config
+            .embedding
+            .query_prefix
+            .chars()
+            .count()
+            .max(config.embedding.document_prefix.chars().count())
+            .div_ceil(4);
+        if max <= prefix_tokens {
+            anyhow::bail!(
+                "embedding.max_input_tokens ({}) must leave room for the query and document prefixes",
+                max
+            );
+        }
+    }
+
+    match config.llm.provider.as_str() {
+        "disabled" => {}
+        "openai" | "ollama" => {
+            if config.llm.model.is_none() {
                 anyhow::bail!(
-                    "embedding.model must be specified when provider is '{}'",
-                    config.embedding.provider
+                    "llm.model must be specified when provider is '{}'",
+                    config.llm.provider
                 );
             }
-        }
-        "local" => {
-            // model and dims are optional for local — defaults applied at runtime
+            if config.llm.max_turns == 0 {
+                anyhow::bail!("llm.max_turns must be >= 1");
+            }
         }
         other => anyhow::bail!(
-            "Unknown embedding provider: '{}'. Must be disabled, openai, ollama, or local.",
+            "Unknown llm provider: '{}'. Must be disabled, openai, or ollama.",
             other
         ),
     }
 
+    let contextualize = &config.chunking.contextualize;
+    if contextualize.enabled {
+        crate::contextualize::validate_template(&contextualize.template)
+            .context("invalid chunking.contextualize.template")?;
+        if contextualize.llm && !config.llm.is_enabled() {
+            anyhow::bail!("chunking.contextualize.llm requires an [llm] provider");
+        }
+        if contextualize.llm && contextualize.max_document_tokens == 0 {
+            anyhow::bail!("chunking.contextualize.max_document_tokens must be > 0");
+        }
+    }
+
+    let summary = &config.summary;
+    if summary.enabled {
+        if summary.max_chars == 0 {
+            anyhow::bail!("summary.max_chars must be > 0");
+        }
+        if summary.llm && !config.llm.is_enabled() {
+            anyhow::bail!("summary.llm requires an [llm] provider");
+        }
+        if summary.llm && summary.max_document_tokens == 0 {
+            anyhow::bail!("summary.max_document_tokens must be > 0");
+        }
+    }
+
+    let memory = &config.memory;
+    if memory.source.trim().is_empty() {
+        anyhow::bail!("memory.source must not be empty");
+    }
+    if memory.retention_days == Some(0) {
+        anyhow::bail!("memory.retention_days must be >= 1");
+    }
+    if memory.max_bytes == 0 {
+        anyhow::bail!("memory.max_bytes must be >= 1");
+    }
+
+    let classify = &config.classify;
+    if classify.enabled {
+        crate::classify::compile_rules(classify).context("invalid [classify] rule")?;
+        if classify.embedding && !config.embedding.is_enabled() {
+            anyhow::bail!("classify.embedding requires an [embedding] provider");
+        }
+        if !(classify.min_similarity > 0.0 && classify.min_similarity <= 1.0) {
+            anyhow::bail!("classify.min_similarity must be in (0.0, 1.0]");
+        }
+    }
+
     match config.vector_index.backend.as_str() {
         "auto" | "zvec" | "sqlite" | "disabled" => {}
         other => anyhow::bail!(
@@ -942,6 +3710,316 @@ fn validate_config(config: Config) -> Result<Config> {
         ),
     }
 
+    match config.keyword_index.backend.as_str() {
+        "sqlite" => {}
+        "opensearch" | "elasticsearch" => {
+            if config.keyword_index.url.is_none() {
+                anyhow::bail!(
+                    "keyword_index.url is required when keyword_index.backend is '{}'",
+                    config.keyword_index.backend
+                );
+            }
+            if config.keyword_index.index.trim().is_empty() {
+                anyhow::bail!("keyword_index.index must not be empty");
+            }
+        }
+        other => anyhow::bail!(
+            "Unknown keyword_index.backend: '{}'. Must be sqlite, opensearch, or elasticsearch.",
+            other
+        ),
+    }
+
+    match config.keyword_index.fallback.as_str() {
+        "sqlite" | "disabled" => {}
+        other => anyhow::bail!(
+            "Unknown keyword_index.fallback: '{}'. Must be sqlite or disabled.",
+            other
+        ),
+    }
+
+    if config.keyword_index.timeout_secs == 0 {
+        anyhow::bail!("keyword_index.timeout_secs must be >= 1");
+    }
+
+    crate::redact::Redactor::from_config(&config.redaction)?;
+
+    match config.storage.eviction.as_str() {
+        "lru" | "oldest" => {}
+        other => anyhow::bail!(
+            "Unknown storage.eviction: '{}'. Must be lru or oldest.",
+            other
+        ),
+    }
+    if config.storage.max_size_mb == Some(0) {
+        anyhow::bail!("storage.max_size_mb must be > 0");
+    }
+    if !(1..=100).contains(&config.storage.warn_at_percent) {
+        anyhow::bail!("storage.warn_at_percent must be between 1 and 100");
+    }
+
+    if let Some(mbps) = config.sync.max_bandwidth_mbps {
+        if !mbps.is_finite() || mbps <= 0.0 {
+            anyhow::bail!("sync.max_bandwidth_mbps must be > 0");
+        }
+    }
+    if config.sync.download_retries == 0 {
+        anyhow::bail!("sync.download_retries must be >= 1");
+    }
+    if config.sync.timeout_secs == Some(0) {
+        anyhow::bail!("sync.timeout_secs must be >= 1");
+    }
+    if config.sync.cpu_threads == Some(0) {
+        anyhow::bail!("sync.cpu_threads must be >= 1");
+    }
+    if config.sync.stale_after_hours == 0 {
+        anyhow::bail!("sync.stale_after_hours must be >= 1");
+    }
+    for (source, hours) in &config.sync.stale_after {
+        if *hours == 0 {
+            anyhow::bail!("sync.stale_after.\"{}\" must be >= 1", source);
+        }
+    }
+    if crate::content_dedup::DedupMode::parse(&config.sync.content_dedup).is_none() {
+        anyhow::bail!(
+            "Unknown sync.content_dedup: '{}'. Must be keep-all, link, or skip.",
+            config.sync.content_dedup
+        );
+    }
+
+    if config.notifications.timeout_secs == 0 {
+        anyhow::bail!("notifications.timeout_secs must be >= 1");
+    }
+    for (i, hook) in config.notifications.webhooks.iter().enumerate() {
+        if hook.url.trim().is_empty() {
+            anyhow::bail!("notifications.webhooks[{}]: url must be set", i);
+        }
+        if !matches!(hook.format.as_str(), "json" | "slack") {
+            anyhow::bail!(
+                "notifications.webhooks[{}]: format must be 'json' or 'slack', got '{}'",
+                i,
+                hook.format
+            );
+        }
+        if let Some(bad) = hook
+            .on
+            .iter()
+            .find(|o| !matches!(o.as_str(), "success" | "failure"))
+        {
+            anyhow::bail!(
+                "notifications.webhooks[{}]: unknown outcome '{}' in on (expected success or failure)",
+                i,
+                bad
+            );
+        }
+        if let Some(bad) = hook
+            .runs
+            .iter()
+            .find(|r| !matches!(r.as_str(), "sync" | "embed"))
+        {
+            anyhow::bail!(
+                "notifications.webhooks[{}]: unknown run '{}' in runs (expected sync or embed)",
+                i,
+                bad
+            );
+        }
+    }
+
+    if !(0.0..=1.0).contains(&config.telemetry.sample_ratio) {
+        anyhow::bail!(
+            "telemetry.sample_ratio must be between 0.0 and 1.0, got {}",
+            config.telemetry.sample_ratio
+        );
+    }
+    if config.telemetry.timeout_secs == 0 {
+        anyhow::bail!("telemetry.timeout_secs must be >= 1");
+    }
+    if config.telemetry.service_name.trim().is_empty() {
+        anyhow::bail!("telemetry.service_name must not be empty");
+    }
+
+    if config.update.check_interval_hours == 0 {
+        anyhow::bail!("update.check_interval_hours must be >= 1");
+    }
+    let repo_parts: Vec<&str> = config.update.repo.split('/').collect();
+    if repo_parts.len() != 2 || repo_parts.iter().any(|part| part.trim().is_empty()) {
+        anyhow::bail!(
+            "update.repo must be 'owner/name', got '{}'",
+            config.update.repo
+        );
+    }
+    if let Some(key) = &config.update.public_key {
+        if let Err(e) = crate::registry::minisign_public_key(key) {
+            anyhow::bail!("update.public_key: {}", e);
+        }
+    }
+
+    if config.server.watch && config.server.watch_interval_secs == 0 {
+        anyhow::bail!("server.watch_interval_secs must be >= 1");
+    }
+
+    let responses = &config.server.responses;
+    if responses.max_body_bytes == Some(0) || responses.max_chunks == Some(0) {
+        anyhow::bail!(
+            "server.responses: max_body_bytes and max_chunks must be >= 1 (omit them for no limit)"
+        );
+    }
+
+    if let Some((tool, _)) = config.server.limits.tools.iter().find(|(_, n)| **n == 0) {
+        anyhow::bail!(
+            "server.limits.tools.{}: limit must be >= 1 (remove the entry for no limit)",
+            tool
+        );
+    }
+
+    let auth = &config.server.auth;
+    for (i, key) in auth.keys.iter().enumerate() {
+        if key.name.trim().is_empty() || key.key.trim().is_empty() {
+            anyhow::bail!("server.auth.keys[{}]: name and key must not be empty", i);
+        }
+        if auth.keys[..i].iter().any(|k| k.name == key.name) {
+            anyhow::bail!("server.auth.keys: duplicate name '{}'", key.name);
+        }
+        if let Some(unknown) = key
+            .allowed_collections
+            .iter()
+            .find(|c| !auth.collections.contains_key(*c))
+        {
+            anyhow::bail!(
+                "server.auth.keys[{}] ({}): unknown collection '{}' (define it under [server.auth.collections])",
+                i,
+                key.name,
+                unknown
+            );
+        }
+    }
+
+    for (name, index) in &config.federation.indexes {
+        if name == crate::federation::LOCAL_ORIGIN {
+            anyhow::bail!("federation.indexes.local: 'local' is reserved for this database");
+        }
+        if index.db.is_some() == index.url.is_some() {
+            anyhow::bail!("federation.indexes.{}: set exactly one of db or url", name);
+        }
+    }
+    if config.federation.enabled && config.federation.indexes.is_empty() {
+        anyhow::bail!("federation.enabled requires at least one index");
+    }
+
+    for (name, fs) in &config.connectors.filesystem {
+        if fs.max_file_size.is_some_and(|max| max < fs.min_file_size) {
+            anyhow::bail!(
+                "connectors.filesystem.{}: min_file_size must not exceed max_file_size",
+                name
+            );
+        }
+        validate_archives(&format!("connectors.filesystem.{}", name), &fs.archives)?;
+    }
+    for (name, s3) in &config.connectors.s3 {
+        validate_archives(&format!("connectors.s3.{}", name), &s3.archives)?;
+        if s3.list_concurrency == 0 {
+            anyhow::bail!(
+                "connectors.s3.{}: list_concurrency must be at least 1",
+                name
+            );
+        }
+        if let Some(ref select) = s3.select {
+            if select.expression.trim().is_empty() {
+                anyhow::bail!(
+                    "connectors.s3.{}.select: expression must not be empty",
+                    name
+                );
+            }
+            for glob in &select.globs {
+                globset::Glob::new(glob).map_err(|e| {
+                    anyhow::anyhow!(
+                        "connectors.s3.{}.select: invalid glob '{}': {}",
+                        name,
+                        glob,
+                        e
+                    )
+                })?;
+            }
+        }
+    }
+
+    for (name, sp) in &config.connectors.sharepoint {
+        let selectors = [sp.site.is_some(), sp.drive_id.is_some(), sp.user.is_some()];
+        if selectors.iter().filter(|s| **s).count() != 1 {
+            anyhow::bail!(
+                "connectors.sharepoint.{}: set exactly one of site, drive_id, or user",
+                name
+            );
+        }
+        if sp.drive.is_some() && sp.site.is_none() {
+            anyhow::bail!(
+                "connectors.sharepoint.{}: drive requires site (use drive_id to select a drive directly)",
+                name
+            );
+        }
+    }
+
+    for (name, zd) in &config.connectors.zendesk {
+        if zd.subdomain.is_none() && zd.base_url.is_none() {
+            anyhow::bail!("connectors.zendesk.{}: set subdomain or base_url", name);
+        }
+        if !zd.tickets && !zd.articles {
+            anyhow::bail!(
+                "connectors.zendesk.{}: tickets and articles are both disabled",
+                name
+            );
+        }
+        if let Some(status) = zd
+            .statuses
+            .iter()
+            .find(|s| !ZendeskConnectorConfig::STATUSES.contains(&s.as_str()))
+        {
+            anyhow::bail!(
+                "connectors.zendesk.{}: unknown ticket status '{}' (expected one of: {})",
+                name,
+                status,
+                ZendeskConnectorConfig::STATUSES.join(", ")
+            );
+        }
+    }
+
+    for (name, so) in &config.connectors.stackoverflow {
+        if so.team.is_none() && so.tags.is_empty() {
+            anyhow::bail!(
+                "connectors.stackoverflow.{}: set team, or tags to read from a public site",
+                name
+            );
+        }
+        if so.team.is_none() && so.site.trim().is_empty() {
+            anyhow::bail!("connectors.stackoverflow.{}: site must not be empty", name);
+        }
+    }
+
+    for (name, agent) in &config.agents.inline {
+        for block in &agent.context {
+            if block.query.trim().is_empty() {
+                anyhow::bail!("agents.inline.{}: context query must not be empty", name);
+            }
+            if !matches!(block.mode.as_str(), "keyword" | "semantic" | "hybrid") {
+                anyhow::bail!(
+                    "agents.inline.{}: unknown context mode '{}' (expected keyword, semantic, or hybrid)",
+                    name,
+                    block.mode
+                );
+            }
+            if block.limit < 1 {
+                anyhow::bail!("agents.inline.{}: context limit must be at least 1", name);
+            }
+        }
+    }
+
+    match config.tools.audit.sink.as_str() {
+        "sqlite" | "jsonl" => {}
+        other => anyhow::bail!(
+            "Unknown tools.audit.sink: '{}'. Must be sqlite or jsonl.",
+            other
+        ),
+    }
+
     Ok(config)
 }
 
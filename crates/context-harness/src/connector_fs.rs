@@ -11,8 +11,33 @@
 //! include_globs = ["**/*.md", "**/*.txt"]
 //! exclude_globs = ["**/drafts/**"]
 //! follow_symlinks = false
+//! max_file_size = 1_000_000
+//! text_extensions = [".log"]
 //! ```
 //!
+//! # Size Limits and Binary Detection
+//!
+//! Matching files outside `min_file_size..=max_file_size` are skipped.
+//! With `detect_binary` (the default), files whose first 8 KiB contain a
+//! NUL byte or invalid UTF-8 are skipped too, unless their extension is in
+//! `text_extensions`. Extractable formats (PDF, Office) are exempt from
+//! binary detection. Skipped files are counted per reason and shown in the
+//! sync summary. A file that cannot be read (e.g. permission denied) does
+//! not stop the scan; it is reported as a failed item (see
+//! [`crate::sync_failures`]).
+//!
+//! # Archives
+//!
+//! With `[connectors.filesystem.<name>.archives] enabled = true`, `.zip`,
+//! `.tar`, and `.tar.gz` files that are not excluded are opened and each
+//! file inside is ingested like a regular file (same size limits, binary
+//! detection, and extraction), with `source_id`
+//! `<archive path>!/<inner path>` (e.g. `releases/v2.tar.gz!/docs/upgrade.md`).
+//! Include and exclude globs are matched against that full ID, so the
+//! default `**/*.md` selects Markdown files inside archives. Inner files take
+//! the archive's modification time. See [`crate::archive`] for nesting and
+//! size limits; archives that cannot be read are skipped and counted.
+//!
 //! # Default Excludes
 //!
 //! The following directories are always excluded regardless of configuration:
@@ -24,27 +49,37 @@
 //!
 //! Each file becomes a [`SourceItem`] with:
 //! - `source`: `"filesystem:<name>"` (e.g. `"filesystem:docs"`)
-//! - `source_id`: relative path from root (e.g. `"guides/deploy.md"`)
+//! - `source_id`: relative path from root with `/` separators on every
+//!   platform (e.g. `"guides/deploy.md"`)
 //! - `source_url`: `file://` URI
 //! - `updated_at`: filesystem modification time
 //! - `body`: file contents as UTF-8
 
-use anyhow::{bail, Result};
+use anyhow::{bail, Context, Result};
 use async_trait::async_trait;
 use chrono::{TimeZone, Utc};
 use globset::{Glob, GlobSet, GlobSetBuilder};
-use std::path::Path;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
 use walkdir::WalkDir;
 
+use crate::archive::{self, ArchiveKind};
 use crate::config::FilesystemConnectorConfig;
+use crate::ctx_dirs;
 use crate::models::SourceItem;
-use crate::traits::Connector;
+use crate::sync_failures::ItemFailure;
+use crate::traits::{Connector, ConnectorHealth};
+
+/// Leading bytes inspected by [`looks_binary`].
+const SNIFF_BYTES: usize = 8192;
 
 /// Binary file extensions that are read as bytes and extracted (spec §2.2).
 const BINARY_EXTENSIONS: &[&str] = &[".pdf", ".docx", ".pptx", ".xlsx"];
 
 /// Extension to MIME type per spec §4.1.
-fn binary_content_type(ext: &str) -> Option<&'static str> {
+pub(crate) fn binary_content_type(ext: &str) -> Option<&'static str> {
     match ext.to_lowercase().as_str() {
         ".pdf" => Some("application/pdf"),
         ".docx" => Some("application/vnd.openxmlformats-officedocument.wordprocessingml.document"),
@@ -84,12 +119,21 @@ pub struct FilesystemConnector {
     name: String,
     /// Configuration for this filesystem connector instance.
     config: FilesystemConnectorConfig,
+    /// Skip counts from the most recent scan.
+    skipped: Mutex<SkippedFiles>,
+    /// Files the most recent scan could not read.
+    failed: Mutex<Vec<ItemFailure>>,
 }
 
 impl FilesystemConnector {
     /// Create a new filesystem connector instance.
     pub fn new(name: String, config: FilesystemConnectorConfig) -> Self {
-        Self { name, config }
+        Self {
+            name,
+            config,
+            skipped: Mutex::new(SkippedFiles::default()),
+            failed: Mutex::new(Vec::new()),
+        }
     }
 }
 
@@ -108,14 +152,100 @@ impl Connector for FilesystemConnector {
     }
 
     async fn scan(&self) -> Result<Vec<SourceItem>> {
-        scan_filesystem(&self.name, &self.config)
+        let scan = scan_filesystem(&self.name, &self.config)?;
+        *self.skipped.lock().unwrap() = scan.skipped;
+        *self.failed.lock().unwrap() = scan.failed;
+        Ok(scan.items)
+    }
+
+    fn skipped(&self) -> BTreeMap<String, u64> {
+        self.skipped.lock().unwrap().by_reason()
+    }
+
+    fn failed(&self) -> Vec<ItemFailure> {
+        self.failed.lock().unwrap().clone()
+    }
+
+    async fn health(&self) -> ConnectorHealth {
+        let root = &self.config.root;
+        ConnectorHealth::probe(async move {
+            let entries = std::fs::read_dir(root)
+                .with_context(|| format!("cannot read root {}", root.display()))?;
+            Ok(format!(
+                "root: {} ({} entries)",
+                root.display(),
+                entries.count()
+            ))
+        })
+        .await
+    }
+}
+
+/// Matching files a scan left out, by reason.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SkippedFiles {
+    /// Over `max_file_size` (or `max_extract_bytes` for extractable formats).
+    pub too_large: u64,
+    /// Under `min_file_size`.
+    pub too_small: u64,
+    /// Detected as binary, or not valid UTF-8.
+    pub binary: u64,
+    /// Archives that could not be read (corrupt, or over `max_total_bytes`
+    /// once decompressed).
+    pub unreadable_archive: u64,
+}
+
+impl SkippedFiles {
+    /// Non-zero counts keyed by reason, as reported by [`Connector::skipped`].
+    pub fn by_reason(&self) -> BTreeMap<String, u64> {
+        [
+            ("binary", self.binary),
+            ("too large", self.too_large),
+            ("too small", self.too_small),
+            ("unreadable archive", self.unreadable_archive),
+        ]
+        .into_iter()
+        .filter(|(_, n)| *n > 0)
+        .map(|(reason, n)| (reason.to_string(), n))
+        .collect()
+    }
+
+    fn record(&mut self, reason: SkipReason) {
+        match reason {
+            SkipReason::TooLarge => self.too_large += 1,
+            SkipReason::TooSmall => self.too_small += 1,
+            SkipReason::Binary => self.binary += 1,
+            SkipReason::UnreadableArchive => self.unreadable_archive += 1,
+        }
     }
 }
 
+/// Items and skip counts from [`scan_filesystem`].
+#[derive(Debug)]
+pub struct FilesystemScan {
+    /// Files to ingest, sorted by `source_id`.
+    pub items: Vec<SourceItem>,
+    /// Matching files left out by size limits or binary detection.
+    pub skipped: SkippedFiles,
+    /// Matching files that could not be read.
+    pub failed: Vec<ItemFailure>,
+}
+
+/// Why [`file_to_source_item`] left a file out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SkipReason {
+    TooLarge,
+    TooSmall,
+    Binary,
+    UnreadableArchive,
+}
+
 /// Scan a local directory and produce [`SourceItem`]s.
 ///
 /// Walks the configured `root` directory, applies include/exclude globs,
-/// reads each matching file, and returns a sorted list of `SourceItem`s.
+/// reads each matching file, and returns a sorted list of `SourceItem`s
+/// along with counts of matching files skipped for size or binary content
+/// and the files that could not be read.
 ///
 /// # Arguments
 ///
@@ -132,26 +262,13 @@ impl Connector for FilesystemConnector {
 pub fn scan_filesystem(
     name: &str,
     fs_config: &FilesystemConnectorConfig,
-) -> Result<Vec<SourceItem>> {
-    let root = &fs_config.root;
-    if !root.exists() {
-        bail!(
-            "Filesystem connector root does not exist: {}",
-            root.display()
-        );
-    }
-
-    let include_set = build_globset(&fs_config.include_globs)?;
-
-    let mut default_excludes = vec![
-        "**/.git/**".to_string(),
-        "**/target/**".to_string(),
-        "**/node_modules/**".to_string(),
-    ];
-    default_excludes.extend(fs_config.exclude_globs.clone());
-    let exclude_set = build_globset(&default_excludes)?;
+) -> Result<FilesystemScan> {
+    let (root, include_set, exclude_set) = root_and_globs(fs_config)?;
+    let root = &root;
 
     let mut items = Vec::new();
+    let mut skipped = SkippedFiles::default();
+    let mut failed = Vec::new();
 
     let walker = WalkDir::new(root).follow_links(fs_config.follow_symlinks);
     for entry in walker {
@@ -161,121 +278,347 @@ pub fn scan_filesystem(
         }
 
         let path = entry.path();
-        let relative = path.strip_prefix(root).unwrap_or(path);
-        let rel_str = relative.to_string_lossy().to_string();
+        let rel_str = relative_source_id(root, path);
 
         // Apply exclude patterns
         if exclude_set.is_match(&rel_str) {
             continue;
         }
 
+        let source_label = format!("filesystem:{}", name);
+        if fs_config.archives.enabled && ArchiveKind::from_path(&rel_str).is_some() {
+            items.extend(archive_to_source_items(
+                path,
+                &rel_str,
+                &source_label,
+                fs_config,
+                (&include_set, &exclude_set),
+                &mut skipped,
+            )?);
+            continue;
+        }
+
         // Apply include patterns
         if !include_set.is_match(&rel_str) {
             continue;
         }
 
-        let source_label = format!("filesystem:{}", name);
-        if let Some(item) = file_to_source_item(path, &rel_str, &source_label, fs_config)? {
-            items.push(item);
+        match file_to_source_item(path, &rel_str, &source_label, fs_config) {
+            Ok(Ok(item)) => items.push(item),
+            Ok(Err(reason)) => skipped.record(reason),
+            Err(e) => failed.push(ItemFailure::new(&rel_str, "fetch", format!("{:#}", e))),
         }
     }
 
     // Sort for deterministic ordering
     items.sort_by(|a, b| a.source_id.cmp(&b.source_id));
 
-    Ok(items)
+    Ok(FilesystemScan {
+        items,
+        skipped,
+        failed,
+    })
+}
+
+/// Size and modification time of a file, compared by `ctx watch` to
+/// notice changes without reading the file.
+pub type FileStamp = (u64, Option<SystemTime>);
+
+/// Files under the connector's root that a scan would read (archives
+/// included when enabled), by `source_id`.
+///
+/// Only metadata is read, so this is cheap enough for `ctx watch` to call
+/// every few seconds.
+pub fn list_files(fs_config: &FilesystemConnectorConfig) -> Result<BTreeMap<String, FileStamp>> {
+    let (root, include_set, exclude_set) = root_and_globs(fs_config)?;
+    let mut files = BTreeMap::new();
+    for entry in WalkDir::new(&root).follow_links(fs_config.follow_symlinks) {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let rel_str = relative_source_id(&root, entry.path());
+        let archive = fs_config.archives.enabled && ArchiveKind::from_path(&rel_str).is_some();
+        if exclude_set.is_match(&rel_str) || !(archive || include_set.is_match(&rel_str)) {
+            continue;
+        }
+        let metadata = entry.metadata()?;
+        files.insert(rel_str, (metadata.len(), metadata.modified().ok()));
+    }
+    Ok(files)
+}
+
+/// The connector's root (after `~` expansion) with its include and exclude
+/// glob sets; the excludes always cover `.git`, `target`, and `node_modules`.
+fn root_and_globs(fs_config: &FilesystemConnectorConfig) -> Result<(PathBuf, GlobSet, GlobSet)> {
+    let root = ctx_dirs::expand_tilde(&fs_config.root);
+    if !root.exists() {
+        bail!(
+            "Filesystem connector root does not exist: {}",
+            root.display()
+        );
+    }
+
+    let include_set = build_globset(&fs_config.include_globs)?;
+
+    let mut default_excludes = vec![
+        "**/.git/**".to_string(),
+        "**/target/**".to_string(),
+        "**/node_modules/**".to_string(),
+    ];
+    default_excludes.extend(fs_config.exclude_globs.clone());
+    let exclude_set = build_globset(&default_excludes)?;
+
+    Ok((root, include_set, exclude_set))
 }
 
-/// Convert a single file to a [`SourceItem`], or `None` if the file should be skipped (spec §2.2).
+/// Relative path of `path` under `root` with `/` separators on every
+/// platform, so `source_id`s and glob matching are identical on Windows.
+fn relative_source_id(root: &Path, path: &Path) -> String {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Build a `file://` URL for a path.
+///
+/// Windows drive paths become `file:///C:/...` and UNC paths
+/// (`\\server\share\...`) become `file://server/share/...`.
+pub(crate) fn file_url(path: &Path) -> String {
+    let s = path.display().to_string();
+    if let Some(unc) = s.strip_prefix(r"\\") {
+        format!("file://{}", unc.replace('\\', "/"))
+    } else if s.as_bytes().get(1) == Some(&b':') {
+        format!("file:///{}", s.replace('\\', "/"))
+    } else {
+        format!("file://{}", s)
+    }
+}
+
+/// Convert a single file to a [`SourceItem`], or the reason it was skipped (spec §2.2).
 ///
 /// For files with a supported binary extension (.pdf, .docx, .pptx, .xlsx), reads raw bytes
-/// and returns an item with `raw_bytes` set and empty `body`. Otherwise reads as UTF-8; on
-/// decode failure and binary extension, falls back to raw bytes; else skips (returns `None`).
+/// and returns an item with `raw_bytes` set and empty `body`. Other files are read as UTF-8
+/// text after the size limits and binary detection; extensions listed in `text_extensions`
+/// skip detection and are decoded lossily.
 fn file_to_source_item(
     path: &Path,
     relative_path: &str,
     source: &str,
     fs_config: &FilesystemConnectorConfig,
-) -> Result<Option<SourceItem>> {
+) -> Result<std::result::Result<SourceItem, SkipReason>> {
     let metadata = std::fs::metadata(path)?;
-    let modified = metadata
-        .modified()
-        .unwrap_or(std::time::SystemTime::UNIX_EPOCH);
-    let modified_secs = modified
-        .duration_since(std::time::SystemTime::UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_secs() as i64;
+    let size = metadata.len();
+    if let Err(reason) = check_size(size, fs_config) {
+        return Ok(Err(reason));
+    }
 
+    let ext = extension(relative_path);
+    if BINARY_EXTENSIONS.contains(&ext.as_str()) && size > fs_config.max_extract_bytes {
+        return Ok(Err(SkipReason::TooLarge));
+    }
+
+    let bytes = std::fs::read(path)?;
     let title = path
         .file_name()
         .map(|n| n.to_string_lossy().to_string())
         .unwrap_or_default();
+    Ok(decode_content(&ext, bytes, fs_config).map(|content| {
+        build_item(
+            source,
+            relative_path,
+            file_url(path),
+            title,
+            modified_secs(&metadata),
+            content,
+            "{}".to_string(),
+        )
+    }))
+}
 
-    let ext = path
+/// Open an archive and convert the files inside that match the globs to
+/// [`SourceItem`]s with `source_id`s of the form `<archive>!/<inner path>`.
+///
+/// Unreadable or oversized archives are skipped and counted rather than
+/// failing the scan.
+fn archive_to_source_items(
+    path: &Path,
+    relative_path: &str,
+    source: &str,
+    fs_config: &FilesystemConnectorConfig,
+    globs: (&GlobSet, &GlobSet),
+    skipped: &mut SkippedFiles,
+) -> Result<Vec<SourceItem>> {
+    let (include_set, exclude_set) = globs;
+    let metadata = std::fs::metadata(path)?;
+    if metadata.len() > fs_config.archives.max_total_bytes {
+        skipped.record(SkipReason::TooLarge);
+        return Ok(Vec::new());
+    }
+
+    let bytes = std::fs::read(path)?;
+    let contents = match archive::read_archive(relative_path, &bytes, &fs_config.archives) {
+        Ok(contents) => contents,
+        Err(e) => {
+            tracing::warn!("skipping archive {}: {:#}", path.display(), e);
+            skipped.record(SkipReason::UnreadableArchive);
+            return Ok(Vec::new());
+        }
+    };
+    skipped.too_large += contents.too_large;
+
+    let modified = modified_secs(&metadata);
+    let mut items = Vec::new();
+    for entry in contents.entries {
+        let source_id = format!("{}{}{}", relative_path, archive::SEPARATOR, entry.path);
+        if exclude_set.is_match(&source_id) || !include_set.is_match(&source_id) {
+            continue;
+        }
+        if let Err(reason) = check_size(entry.bytes.len() as u64, fs_config) {
+            skipped.record(reason);
+            continue;
+        }
+
+        let title = entry
+            .path
+            .rsplit('/')
+            .next()
+            .unwrap_or_default()
+            .to_string();
+        let metadata_json = serde_json::json!({
+            "archive": relative_path,
+            "archive_path": entry.path,
+        })
+        .to_string();
+        match decode_content(&extension(&entry.path), entry.bytes, fs_config) {
+            Ok(content) => items.push(build_item(
+                source,
+                &source_id,
+                file_url(path),
+                title,
+                modified,
+                content,
+                metadata_json,
+            )),
+            Err(reason) => skipped.record(reason),
+        }
+    }
+    Ok(items)
+}
+
+/// Content type, text body, and raw bytes (for extractable formats) of a file.
+type FileContent = (&'static str, String, Option<Vec<u8>>);
+
+/// Apply `min_file_size` / `max_file_size`.
+fn check_size(
+    size: u64,
+    fs_config: &FilesystemConnectorConfig,
+) -> std::result::Result<(), SkipReason> {
+    if fs_config.max_file_size.is_some_and(|max| size > max) {
+        return Err(SkipReason::TooLarge);
+    }
+    if size < fs_config.min_file_size {
+        return Err(SkipReason::TooSmall);
+    }
+    Ok(())
+}
+
+/// Decode file bytes by extension: extractable formats are kept as raw bytes,
+/// everything else must pass binary detection and be UTF-8.
+fn decode_content(
+    ext: &str,
+    bytes: Vec<u8>,
+    fs_config: &FilesystemConnectorConfig,
+) -> std::result::Result<FileContent, SkipReason> {
+    let is_binary_ext = BINARY_EXTENSIONS.contains(&ext);
+    if let (true, Some(mime)) = (is_binary_ext, binary_content_type(ext)) {
+        if bytes.len() as u64 > fs_config.max_extract_bytes {
+            return Err(SkipReason::TooLarge);
+        }
+        return Ok((mime, String::new(), Some(bytes)));
+    }
+
+    if is_text_extension(ext, &fs_config.text_extensions) {
+        let body = String::from_utf8_lossy(&bytes).into_owned();
+        return Ok(("text/plain", body, None));
+    }
+    if fs_config.detect_binary && looks_binary(&bytes[..bytes.len().min(SNIFF_BYTES)]) {
+        return Err(SkipReason::Binary);
+    }
+    match String::from_utf8(bytes) {
+        Ok(body) => Ok(("text/plain", body, None)),
+        Err(_) => Err(SkipReason::Binary),
+    }
+}
+
+/// Build a filesystem [`SourceItem`].
+fn build_item(
+    source: &str,
+    source_id: &str,
+    source_url: String,
+    title: String,
+    modified_secs: i64,
+    (content_type, body, raw_bytes): FileContent,
+    metadata_json: String,
+) -> SourceItem {
+    SourceItem {
+        source: source.to_string(),
+        source_id: source_id.to_string(),
+        source_url: Some(source_url),
+        title: Some(title),
+        author: None,
+        created_at: Utc.timestamp_opt(modified_secs, 0).unwrap(),
+        updated_at: Utc.timestamp_opt(modified_secs, 0).unwrap(),
+        content_type: content_type.to_string(),
+        body,
+        metadata_json,
+        raw_json: None,
+        raw_bytes,
+        parent_source_id: None,
+    }
+}
+
+/// Lowercase extension with a leading dot (e.g. `".md"`), or `""`.
+pub(crate) fn extension(path: &str) -> String {
+    Path::new(path)
         .extension()
         .map(|e| format!(".{}", e.to_string_lossy()))
         .unwrap_or_default()
-        .to_lowercase();
-    let is_binary_ext = BINARY_EXTENSIONS.contains(&ext.as_str());
-    let content_type_from_ext = binary_content_type(&ext);
+        .to_lowercase()
+}
 
-    if let (true, Some(mime)) = (is_binary_ext, content_type_from_ext) {
-        if metadata.len() > fs_config.max_extract_bytes {
-            return Ok(None);
-        }
-        let bytes = std::fs::read(path)?;
-        return Ok(Some(SourceItem {
-            source: source.to_string(),
-            source_id: relative_path.to_string(),
-            source_url: Some(format!("file://{}", path.display())),
-            title: Some(title),
-            author: None,
-            created_at: Utc.timestamp_opt(modified_secs, 0).unwrap(),
-            updated_at: Utc.timestamp_opt(modified_secs, 0).unwrap(),
-            content_type: mime.to_string(),
-            body: String::new(),
-            metadata_json: "{}".to_string(),
-            raw_json: None,
-            raw_bytes: Some(bytes),
-        }));
-    }
-
-    match std::fs::read_to_string(path) {
-        Ok(body) => Ok(Some(SourceItem {
-            source: source.to_string(),
-            source_id: relative_path.to_string(),
-            source_url: Some(format!("file://{}", path.display())),
-            title: Some(title),
-            author: None,
-            created_at: Utc.timestamp_opt(modified_secs, 0).unwrap(),
-            updated_at: Utc.timestamp_opt(modified_secs, 0).unwrap(),
-            content_type: "text/plain".to_string(),
-            body,
-            metadata_json: "{}".to_string(),
-            raw_json: None,
-            raw_bytes: None,
-        })),
-        Err(_) => {
-            if let (true, Some(mime)) = (is_binary_ext, content_type_from_ext) {
-                let bytes = std::fs::read(path)?;
-                Ok(Some(SourceItem {
-                    source: source.to_string(),
-                    source_id: relative_path.to_string(),
-                    source_url: Some(format!("file://{}", path.display())),
-                    title: Some(title),
-                    author: None,
-                    created_at: Utc.timestamp_opt(modified_secs, 0).unwrap(),
-                    updated_at: Utc.timestamp_opt(modified_secs, 0).unwrap(),
-                    content_type: mime.to_string(),
-                    body: String::new(),
-                    metadata_json: "{}".to_string(),
-                    raw_json: None,
-                    raw_bytes: Some(bytes),
-                }))
-            } else {
-                Ok(None)
-            }
-        }
+/// Modification time in Unix seconds (epoch if unavailable).
+fn modified_secs(metadata: &std::fs::Metadata) -> i64 {
+    metadata
+        .modified()
+        .unwrap_or(std::time::SystemTime::UNIX_EPOCH)
+        .duration_since(std::time::SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+}
+
+/// Whether `ext` (lowercase, with leading dot) is in the `text_extensions`
+/// override list, which may be written with or without the dot.
+fn is_text_extension(ext: &str, text_extensions: &[String]) -> bool {
+    !ext.is_empty()
+        && text_extensions
+            .iter()
+            .any(|e| e.trim_start_matches('.').eq_ignore_ascii_case(&ext[1..]))
+}
+
+/// Heuristic binary check on the start of a file: any NUL byte, or bytes
+/// that are not valid UTF-8. A multi-byte sequence cut off by the end of
+/// the sample does not count.
+fn looks_binary(sample: &[u8]) -> bool {
+    if sample.contains(&0) {
+        return true;
+    }
+    match std::str::from_utf8(sample) {
+        Ok(_) => false,
+        Err(e) => e.error_len().is_some(),
     }
 }
 
@@ -287,3 +630,48 @@ fn build_globset(patterns: &[String]) -> Result<GlobSet> {
     }
     Ok(builder.build()?)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relative_source_id_uses_forward_slashes() {
+        let root = Path::new("repo");
+        let path = root.join("docs").join("guides").join("deploy.md");
+        assert_eq!(relative_source_id(root, &path), "docs/guides/deploy.md");
+        assert_eq!(relative_source_id(root, &root.join("a.md")), "a.md");
+    }
+
+    #[test]
+    fn file_url_handles_unix_drive_and_unc_paths() {
+        assert_eq!(
+            file_url(Path::new("/srv/docs/a.md")),
+            "file:///srv/docs/a.md"
+        );
+        assert_eq!(file_url(Path::new(r"C:\docs\a.md")), "file:///C:/docs/a.md");
+        assert_eq!(
+            file_url(Path::new(r"\\server\share\a.md")),
+            "file://server/share/a.md"
+        );
+    }
+
+    #[test]
+    fn looks_binary_flags_nul_and_invalid_utf8() {
+        assert!(!looks_binary(b"plain text\n"));
+        assert!(!looks_binary("caf\u{e9}".as_bytes()));
+        assert!(looks_binary(b"PK\x03\x04\x00\x00"));
+        assert!(looks_binary(b"\xff\xfe latin junk"));
+        // A UTF-8 sequence cut off by the sample boundary is not binary.
+        assert!(!looks_binary(&"ab\u{e9}".as_bytes()[..3]));
+    }
+
+    #[test]
+    fn is_text_extension_ignores_dot_and_case() {
+        let overrides = vec!["log".to_string(), ".CSV".to_string()];
+        assert!(is_text_extension(".log", &overrides));
+        assert!(is_text_extension(".csv", &overrides));
+        assert!(!is_text_extension(".bin", &overrides));
+        assert!(!is_text_extension("", &overrides));
+    }
+}
@@ -22,13 +22,43 @@
 //! `.ctx/cache/git/<url-hash>/` for workspace-default configs). Subsequent
 //! syncs do `git fetch && reset`.
 //!
+//! The first clone is built with `git init` + `git fetch` so that an
+//! interrupted clone leaves a reusable repository behind. Full clones
+//! (`shallow = false`) fetch the branch tip first and then deepen the
+//! history in steps, so work finished before a failure is kept. Failed
+//! fetches are retried up to `[sync] download_retries` times; attempts are
+//! recorded in `.git/ctx-download.json` until the clone completes.
+//!
 //! # Metadata Extraction
 //!
 //! For each file, the connector extracts:
 //! - **`updated_at`** — last commit timestamp from `git log -1 --format=%ct`
 //! - **`author`** — last committer name from `git log -1 --format=%an`
 //! - **`source_url`** — web URL (GitHub/GitLab blob link) for the file
-//! - **`metadata_json`** — JSON with `git_sha` and `repo_url`
+//! - **`metadata_json`** — JSON with `git_sha`, `repo_url`, and `ref` (the
+//!   branch or tag the file was read from)
+//!
+//! # Branches and Tags
+//!
+//! By default only `branch` is indexed. `branches` and `tags` take names or
+//! globs matched against the remote's refs (`git ls-remote`); each matching
+//! ref is fetched, checked out, and walked in turn:
+//!
+//! ```toml
+//! [connectors.git.platform]
+//! url = "https://github.com/acme/platform.git"
+//! branches = ["main", "release/*"]
+//! tags = ["v2.*"]
+//! ```
+//!
+//! With either list set, source IDs are prefixed with the ref
+//! (`release/1.4:docs/deploy.md`) so the same path on two refs are two
+//! documents; without them, source IDs stay plain paths. `ctx search` keeps
+//! one ref's documents with the `ref:` modifier (`ref:release/1.4`).
+//!
+//! Refs created after the first sync are only picked up if their files
+//! were committed after the checkpoint; run `ctx sync --full` after adding
+//! a pattern or cutting a branch from an old commit.
 //!
 //! # Web URL Generation
 //!
@@ -51,10 +81,11 @@ use std::path::{Path, PathBuf};
 use std::process::Command;
 use walkdir::WalkDir;
 
-use crate::config::GitConnectorConfig;
+use crate::config::{GitConnectorConfig, SyncConfig};
 use crate::ctx_dirs;
+use crate::download::{retry_delay, DownloadCheckpoint};
 use crate::models::SourceItem;
-use crate::traits::Connector;
+use crate::traits::{Connector, ConnectorHealth};
 
 // ═══════════════════════════════════════════════════════════════════════
 // Connector trait implementation
@@ -69,16 +100,24 @@ pub struct GitConnector {
     name: String,
     /// Configuration for this Git connector instance.
     config: GitConnectorConfig,
+    /// Retry settings from `[sync]`.
+    sync: SyncConfig,
     /// Path to the SQLite database, used to derive the default cache directory.
     db_path: PathBuf,
 }
 
 impl GitConnector {
     /// Create a new Git connector instance.
-    pub fn new(name: String, config: GitConnectorConfig, db_path: PathBuf) -> Self {
+    pub fn new(
+        name: String,
+        config: GitConnectorConfig,
+        sync: SyncConfig,
+        db_path: PathBuf,
+    ) -> Self {
         Self {
             name,
             config,
+            sync,
             db_path,
         }
     }
@@ -99,7 +138,16 @@ impl Connector for GitConnector {
     }
 
     async fn scan(&self) -> Result<Vec<SourceItem>> {
-        scan_git(&self.name, &self.config, &self.db_path)
+        scan_git(&self.name, &self.config, &self.sync, &self.db_path)
+    }
+
+    async fn health(&self) -> ConnectorHealth {
+        let url = &self.config.url;
+        ConnectorHealth::probe(async move {
+            let head = git_ls_remote(url, &self.config.branch).await?;
+            Ok(format!("repo: {} ({})", url, head))
+        })
+        .await
     }
 }
 
@@ -108,8 +156,9 @@ impl Connector for GitConnector {
 /// # Workflow
 ///
 /// 1. Determine a local cache directory for the clone.
-/// 2. Clone (shallow if configured) or pull to update.
-/// 3. Walk files under the configured `root` subdirectory.
+/// 2. Clone (shallow if configured), resume a partial clone, or pull to update.
+/// 3. Walk files under the configured `root` subdirectory of `branch`, or
+///    of each ref matching `branches` / `tags` (see the module docs).
 /// 4. Apply include/exclude globs.
 /// 5. Extract per-file metadata from `git log`.
 /// 6. Generate web-browsable URLs.
@@ -119,17 +168,21 @@ impl Connector for GitConnector {
 /// - `name` — the instance name (e.g. `"platform"`). Used as part of the
 ///   source identifier: `"git:<name>"`.
 /// - `git_config` — the Git connector configuration for this instance.
+/// - `sync` — retry settings for fetches.
 /// - `db_path` — path to the SQLite database, used to derive the default cache directory.
 ///
 /// # Errors
 ///
 /// Returns an error if:
 /// - `git` binary is not available
-/// - Clone or pull fails
-/// - The configured `root` subdirectory does not exist in the repo
+/// - Clone, pull, or the fetch of a matching ref fails
+/// - The configured `root` subdirectory does not exist in the repo (with
+///   `branches` / `tags`, refs without it are skipped)
+/// - No ref matches `branches` / `tags`
 pub fn scan_git(
     name: &str,
     git_config: &GitConnectorConfig,
+    sync: &SyncConfig,
     db_path: &Path,
 ) -> Result<Vec<SourceItem>> {
     // Determine cache directory
@@ -147,35 +200,106 @@ pub fn scan_git(
         }
     };
 
-    // Clone or pull
-    if cache_dir.join(".git").exists() {
-        git_pull(&cache_dir, &git_config.branch)?;
+    // Clone, resume an interrupted clone, or pull
+    if cache_dir.join(".git").exists() && has_commit(&cache_dir, "HEAD") {
+        git_pull(&cache_dir, &git_config.branch, sync.download_retries)?;
     } else {
         git_clone(
             &git_config.url,
             &git_config.branch,
             git_config.shallow,
             &cache_dir,
+            sync.download_retries,
         )?;
     }
 
-    // Resolve the scan root within the cloned repo
-    let scan_root = if git_config.root == "." {
-        cache_dir.clone()
+    let source_label = format!("git:{}", name);
+    if git_config.branches.is_empty() && git_config.tags.is_empty() {
+        let scan_root = scan_root(&cache_dir, git_config);
+        if !scan_root.exists() {
+            bail!(
+                "Git connector root '{}' does not exist in repo {}",
+                git_config.root,
+                git_config.url
+            );
+        }
+        let mut items = walk_ref(
+            &cache_dir,
+            &scan_root,
+            git_config,
+            &source_label,
+            &git_config.branch,
+            false,
+        )?;
+        items.sort_by(|a, b| a.source_id.cmp(&b.source_id));
+        return Ok(items);
+    }
+
+    let mut items = Vec::new();
+    for git_ref in list_refs(&cache_dir, git_config)? {
+        fetch_ref(
+            &cache_dir,
+            &git_ref,
+            git_config.shallow,
+            sync.download_retries,
+        )?;
+        git_in(
+            &cache_dir,
+            &["checkout", "-q", "-f", "--detach", &git_ref.local_ref()],
+        )?;
+        let scan_root = scan_root(&cache_dir, git_config);
+        if !scan_root.exists() {
+            tracing::warn!(
+                "{}: root '{}' does not exist on {}, skipping it",
+                source_label,
+                git_config.root,
+                git_ref.name
+            );
+            continue;
+        }
+        items.extend(walk_ref(
+            &cache_dir,
+            &scan_root,
+            git_config,
+            &source_label,
+            &git_ref.name,
+            true,
+        )?);
+    }
+
+    // Leave the cache on `branch` for the next pull.
+    let branch = &git_config.branch;
+    let remote_ref = format!("refs/remotes/origin/{}", branch);
+    git_in(
+        &cache_dir,
+        &["checkout", "-q", "-f", "-B", branch, &remote_ref],
+    )?;
+
+    items.sort_by(|a, b| a.source_id.cmp(&b.source_id));
+    Ok(items)
+}
+
+/// The directory to walk: `root` within the checkout.
+fn scan_root(cache_dir: &Path, git_config: &GitConnectorConfig) -> PathBuf {
+    if git_config.root == "." {
+        cache_dir.to_path_buf()
     } else {
         cache_dir.join(&git_config.root)
-    };
-
-    if !scan_root.exists() {
-        bail!(
-            "Git connector root '{}' does not exist in repo {}",
-            git_config.root,
-            git_config.url
-        );
     }
+}
 
+/// Walk the checked-out `git_ref` and produce its items, with source IDs
+/// prefixed by the ref when `prefix_ids` is set.
+fn walk_ref(
+    cache_dir: &Path,
+    scan_root: &Path,
+    git_config: &GitConnectorConfig,
+    source_label: &str,
+    git_ref: &str,
+    prefix_ids: bool,
+) -> Result<Vec<SourceItem>> {
     // Get the HEAD commit SHA for metadata
-    let head_sha = git_head_sha(&cache_dir).unwrap_or_else(|_| "unknown".to_string());
+    let head_sha = git_head_sha(cache_dir).unwrap_or_else(|_| "unknown".to_string());
 
     // Build glob sets
     let include_set = build_globset(&git_config.include_globs)?;
@@ -190,14 +314,14 @@ pub fn scan_git(
 
     let mut items = Vec::new();
 
-    for entry in WalkDir::new(&scan_root) {
+    for entry in WalkDir::new(scan_root) {
         let entry = entry?;
         if !entry.file_type().is_file() {
             continue;
         }
 
         let path = entry.path();
-        let relative = path.strip_prefix(&scan_root).unwrap_or(path);
+        let relative = path.strip_prefix(scan_root).unwrap_or(path);
         let rel_str = relative.to_string_lossy().to_string();
 
         if exclude_set.is_match(&rel_str) {
@@ -207,79 +331,313 @@ pub fn scan_git(
             continue;
         }
 
-        let source_label = format!("git:{}", name);
-        let item = file_to_source_item(
+        let mut item = file_to_source_item(
             path,
             &rel_str,
-            &cache_dir,
+            cache_dir,
             &git_config.url,
             &head_sha,
-            &source_label,
+            git_ref,
+            source_label,
         )?;
+        if prefix_ids {
+            item.source_id = format!("{}:{}", git_ref, item.source_id);
+        }
         items.push(item);
     }
-
-    items.sort_by(|a, b| a.source_id.cmp(&b.source_id));
     Ok(items)
 }
 
-/// Clone a Git repository into the cache directory.
-///
-/// Supports shallow clones (`--depth 1`) and single-branch checkout.
-fn git_clone(url: &str, branch: &str, shallow: bool, dest: &Path) -> Result<()> {
-    std::fs::create_dir_all(dest)
-        .with_context(|| format!("Failed to create cache directory: {}", dest.display()))?;
+/// A branch or tag selected by `branches` / `tags`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct GitRef {
+    /// Short name, e.g. `release/1.4` or `v2.0`.
+    name: String,
+    tag: bool,
+}
 
-    let mut cmd = Command::new("git");
-    cmd.args(["clone", "--branch", branch, "--single-branch"]);
-    if shallow {
-        cmd.args(["--depth", "1"]);
+impl GitRef {
+    /// The ref on the remote.
+    fn remote_ref(&self) -> String {
+        if self.tag {
+            format!("refs/tags/{}", self.name)
+        } else {
+            format!("refs/heads/{}", self.name)
+        }
     }
-    cmd.arg(url);
-    cmd.arg(dest);
-
-    let output = cmd
-        .output()
-        .with_context(|| "Failed to execute 'git clone'. Is git installed?")?;
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        bail!("git clone failed: {}", stderr.trim());
+    /// The ref it is fetched into.
+    fn local_ref(&self) -> String {
+        if self.tag {
+            format!("refs/tags/{}", self.name)
+        } else {
+            format!("refs/remotes/origin/{}", self.name)
+        }
     }
-
-    Ok(())
 }
 
-/// Update an existing cached repository via fetch + hard reset.
-fn git_pull(repo_dir: &Path, branch: &str) -> Result<()> {
-    // Fetch the latest changes
+/// Branches and tags on the remote matching `branches` (or `branch` when
+/// that is empty) and `tags`, branches first.
+fn list_refs(repo_dir: &Path, git_config: &GitConnectorConfig) -> Result<Vec<GitRef>> {
     let output = Command::new("git")
-        .args(["fetch", "origin", branch])
+        .args(["ls-remote", "--heads", "--tags", "origin"])
+        .env("GIT_TERMINAL_PROMPT", "0")
         .current_dir(repo_dir)
         .output()
-        .with_context(|| "Failed to execute 'git fetch'")?;
-
+        .with_context(|| "Failed to execute 'git ls-remote'. Is git installed?")?;
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
-        bail!("git fetch failed: {}", stderr.trim());
+        bail!("git ls-remote failed: {}", stderr.trim());
+    }
+    let branch_patterns = if git_config.branches.is_empty() {
+        std::slice::from_ref(&git_config.branch)
+    } else {
+        git_config.branches.as_slice()
+    };
+    let refs = select_refs(
+        &String::from_utf8_lossy(&output.stdout),
+        &build_globset(branch_patterns)?,
+        &build_globset(&git_config.tags)?,
+    );
+    if refs.is_empty() {
+        bail!(
+            "no branches or tags of {} match branches {:?} or tags {:?}",
+            git_config.url,
+            branch_patterns,
+            git_config.tags
+        );
     }
+    Ok(refs)
+}
+
+/// Pick the refs of `git ls-remote` output matching the branch and tag globs.
+fn select_refs(ls_remote: &str, branches: &GlobSet, tags: &GlobSet) -> Vec<GitRef> {
+    let mut refs: Vec<GitRef> = ls_remote
+        .lines()
+        .filter_map(|line| line.split_once('\t').map(|(_, name)| name))
+        .filter(|name| !name.ends_with("^{}"))
+        .filter_map(|name| {
+            if let Some(branch) = name.strip_prefix("refs/heads/") {
+                branches.is_match(branch).then(|| GitRef {
+                    name: branch.to_string(),
+                    tag: false,
+                })
+            } else {
+                let tag = name.strip_prefix("refs/tags/")?;
+                tags.is_match(tag).then(|| GitRef {
+                    name: tag.to_string(),
+                    tag: true,
+                })
+            }
+        })
+        .collect();
+    refs.sort_by(|a, b| a.tag.cmp(&b.tag).then_with(|| a.name.cmp(&b.name)));
+    refs
+}
+
+/// Fetch `git_ref` into its local ref, retrying like [`git_pull`].
+fn fetch_ref(repo_dir: &Path, git_ref: &GitRef, shallow: bool, retries: u32) -> Result<()> {
+    let checkpoint_path = repo_dir.join(".git").join("ctx-download.json");
+    let mut checkpoint = DownloadCheckpoint::load(&checkpoint_path);
+    checkpoint.entry("fetch", &git_ref.name);
+
+    let refspec = format!("+{}:{}", git_ref.remote_ref(), git_ref.local_ref());
+    let mut args = vec!["fetch"];
+    if shallow {
+        args.extend(["--depth", "1"]);
+    }
+    args.extend(["origin", refspec.as_str()]);
+    fetch_with_retries(
+        repo_dir,
+        "fetch",
+        &args,
+        retries,
+        &mut checkpoint,
+        &checkpoint_path,
+    )?;
+    checkpoint.complete("fetch");
+    checkpoint.save(&checkpoint_path)
+}
+
+/// Commits fetched per step when deepening a full clone.
+const DEEPEN_STEP: u32 = 1000;
+
+/// Clone a Git repository into the cache directory, resuming a partial clone.
+///
+/// Uses `git init` + `git fetch` instead of `git clone`, which refuses to
+/// reuse a non-empty directory. Whatever an earlier attempt fetched is kept:
+/// the tip is only fetched if `origin/<branch>` is missing, and full clones
+/// keep deepening until `.git/shallow` disappears.
+fn git_clone(url: &str, branch: &str, shallow: bool, dest: &Path, retries: u32) -> Result<()> {
+    std::fs::create_dir_all(dest)
+        .with_context(|| format!("Failed to create cache directory: {}", dest.display()))?;
+
+    if !dest.join(".git").exists() {
+        git_in(dest, &["init", "-q"])?;
+    }
+    if git_in(dest, &["remote", "get-url", "origin"]).is_ok() {
+        git_in(dest, &["remote", "set-url", "origin", url])?;
+    } else {
+        git_in(dest, &["remote", "add", "origin", url])?;
+    }
+
+    let checkpoint_path = dest.join(".git").join("ctx-download.json");
+    let mut checkpoint = DownloadCheckpoint::load(&checkpoint_path);
+    if let Some(entry) = checkpoint.entries.get("clone").filter(|e| e.attempts > 0) {
+        tracing::info!(
+            "Resuming partial clone of {} in {} ({} failed attempts so far)",
+            url,
+            dest.display(),
+            entry.attempts
+        );
+    }
+    checkpoint.entry("clone", branch);
+
+    let remote_ref = format!("refs/remotes/origin/{}", branch);
+    if !has_commit(dest, &remote_ref) {
+        fetch_with_retries(
+            dest,
+            "clone",
+            &["fetch", "--depth", "1", "origin", branch],
+            retries,
+            &mut checkpoint,
+            &checkpoint_path,
+        )?;
+    }
+    if !shallow {
+        let deepen = format!("--deepen={}", DEEPEN_STEP);
+        let shallow_file = dest.join(".git").join("shallow");
+        while let Ok(before) = std::fs::read(&shallow_file) {
+            fetch_with_retries(
+                dest,
+                "clone",
+                &["fetch", &deepen, "origin", branch],
+                retries,
+                &mut checkpoint,
+                &checkpoint_path,
+            )?;
+            if std::fs::read(&shallow_file).is_ok_and(|after| after == before) {
+                break;
+            }
+        }
+    }
+
+    git_in(dest, &["checkout", "-q", "-f", "-B", branch, &remote_ref])?;
+
+    checkpoint.complete("clone");
+    checkpoint.save(&checkpoint_path)
+}
+
+/// Update an existing cached repository via fetch + hard reset.
+fn git_pull(repo_dir: &Path, branch: &str, retries: u32) -> Result<()> {
+    let checkpoint_path = repo_dir.join(".git").join("ctx-download.json");
+    let mut checkpoint = DownloadCheckpoint::load(&checkpoint_path);
+    checkpoint.entry("fetch", branch);
+
+    // Fetch the latest changes
+    fetch_with_retries(
+        repo_dir,
+        "fetch",
+        &["fetch", "origin", branch],
+        retries,
+        &mut checkpoint,
+        &checkpoint_path,
+    )?;
+    checkpoint.complete("fetch");
+    checkpoint.save(&checkpoint_path)?;
 
     // Reset to the fetched branch
     let remote_ref = format!("origin/{}", branch);
+    git_in(repo_dir, &["reset", "--hard", &remote_ref])
+}
+
+/// Run a `git fetch`, retrying with backoff and recording failures in the
+/// download checkpoint.
+fn fetch_with_retries(
+    repo_dir: &Path,
+    key: &str,
+    args: &[&str],
+    retries: u32,
+    checkpoint: &mut DownloadCheckpoint,
+    checkpoint_path: &Path,
+) -> Result<()> {
+    let mut attempt = 1;
+    loop {
+        let err = match git_in(repo_dir, args) {
+            Ok(()) => return Ok(()),
+            Err(e) => e,
+        };
+        let total = checkpoint.record_failure(key, 0, &err);
+        checkpoint.save(checkpoint_path)?;
+        if attempt >= retries {
+            return Err(err.context(format!(
+                "giving up after {} attempts ({} in total); fetched data in {} is reused on the next sync",
+                attempt,
+                total,
+                repo_dir.display()
+            )));
+        }
+        let delay = retry_delay(attempt);
+        tracing::warn!(
+            "{:#}; retrying in {}s (attempt {}/{})",
+            err,
+            delay.as_secs(),
+            attempt + 1,
+            retries
+        );
+        std::thread::sleep(delay);
+        attempt += 1;
+    }
+}
+
+/// Run a git command in `repo_dir`, failing with its stderr.
+fn git_in(repo_dir: &Path, args: &[&str]) -> Result<()> {
     let output = Command::new("git")
-        .args(["reset", "--hard", &remote_ref])
+        .args(args)
         .current_dir(repo_dir)
         .output()
-        .with_context(|| "Failed to execute 'git reset'")?;
+        .with_context(|| format!("Failed to execute 'git {}'. Is git installed?", args[0]))?;
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
-        bail!("git reset failed: {}", stderr.trim());
+        bail!("git {} failed: {}", args[0], stderr.trim());
     }
-
     Ok(())
 }
 
+/// Whether `rev` resolves to a commit in `repo_dir`.
+fn has_commit(repo_dir: &Path, rev: &str) -> bool {
+    let spec = format!("{}^{{commit}}", rev);
+    git_in(repo_dir, &["rev-parse", "--verify", "-q", &spec]).is_ok()
+}
+
+/// Check that `branch` exists on the remote without cloning.
+///
+/// Runs `git ls-remote` with terminal prompts disabled so a missing
+/// credential fails fast instead of waiting for input.
+async fn git_ls_remote(url: &str, branch: &str) -> Result<String> {
+    let output = tokio::process::Command::new("git")
+        .args(["ls-remote", "--exit-code", "--heads", url, branch])
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .kill_on_drop(true)
+        .output()
+        .await
+        .with_context(|| "Failed to execute 'git ls-remote'. Is git installed?")?;
+
+    match output.status.code() {
+        Some(0) => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let sha: String = stdout.chars().take(12).collect();
+            Ok(format!("{} @ {}", branch, sha))
+        }
+        Some(2) => bail!("branch '{}' not found at {}", branch, url),
+        _ => {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            bail!("git ls-remote failed: {}", stderr.trim());
+        }
+    }
+}
+
 /// Get the HEAD commit SHA of a repository.
 fn git_head_sha(repo_dir: &Path) -> Result<String> {
     let output = Command::new("git")
@@ -347,6 +705,7 @@ fn file_to_source_item(
     repo_dir: &Path,
     repo_url: &str,
     head_sha: &str,
+    git_ref: &str,
     source: &str,
 ) -> Result<SourceItem> {
     let body = std::fs::read_to_string(path).unwrap_or_default();
@@ -375,6 +734,7 @@ fn file_to_source_item(
     let metadata = serde_json::json!({
         "git_sha": head_sha,
         "repo_url": repo_url,
+        "ref": git_ref,
     });
 
     Ok(SourceItem {
@@ -390,6 +750,7 @@ fn file_to_source_item(
         metadata_json: metadata.to_string(),
         raw_json: None,
         raw_bytes: None,
+        parent_source_id: None,
     })
 }
 
@@ -5,9 +5,9 @@
 //! glob-based filtering on object keys, and supports custom endpoints for
 //! S3-compatible services (MinIO, LocalStack).
 //!
-//! Uses only pure-Rust dependencies (`hmac`, `sha2`) for AWS signing — no
-//! C library dependencies like `aws-lc-sys`, making it compatible with
-//! all build environments including Nix.
+//! Signing uses the pure-Rust helpers in [`crate::aws_sigv4`] — no C
+//! library dependencies like `aws-lc-sys`, making it compatible with all
+//! build environments including Nix.
 //!
 //! # Configuration
 //!
@@ -31,13 +31,62 @@
 //!
 //! All S3 requests are signed using
 //! [AWS Signature Version 4](https://docs.aws.amazon.com/AmazonS3/latest/API/sigv4-auth-using-authorization-header.html).
-//! The signing implementation uses HMAC-SHA256 (`hmac` + `sha2` crates).
+//! The HMAC-SHA256 signing helpers live in [`crate::aws_sigv4`].
 //!
 //! # Pagination
 //!
 //! Large buckets (1000+ objects) are handled automatically via the
 //! `ListObjectsV2` continuation token mechanism.
 //!
+//! # Prefix Sharding
+//!
+//! Listing is sequential page by page, which dominates a sync of a bucket
+//! with millions of keys. With `list_concurrency = N` above 1, the connector
+//! first lists one level under `prefix` with `delimiter=/`, then lists each
+//! sub-prefix found there (`2024/`, `2025/`, …) with up to N listings in
+//! flight. Keys directly under `prefix` come from the first listing.
+//!
+//! # S3 Select
+//!
+//! With a `[connectors.s3.<name>.select]` table, included objects that
+//! match its `globs` are read with `SelectObjectContent` instead of `GET`:
+//! S3 evaluates the SQL `expression` against the CSV or JSON object and
+//! returns only the matching records, as JSON Lines, which become the
+//! document body. Objects without matching records are skipped. The
+//! response arrives as an AWS event stream whose `Records` payloads are
+//! concatenated (see [`parse_select_event_stream`]).
+//!
+//! ```toml
+//! [connectors.s3.tickets]
+//! bucket = "acme-exports"
+//! include_globs = ["**/*.jsonl.gz"]
+//! list_concurrency = 8
+//!
+//! [connectors.s3.tickets.select]
+//! expression = "SELECT s.title, s.body FROM S3Object s WHERE s.team = 'platform'"
+//! ```
+//!
+//! # Resumable Downloads
+//!
+//! Objects are streamed to a partial file under the connector's cache
+//! directory (`.ctx/cache/s3/<name>/partial/`), throttled by
+//! `[sync] max_bandwidth_mbps`. A failed download is retried up to
+//! `[sync] download_retries` times with a ranged `GET` (`Range: bytes=N-`,
+//! `If-Match: <etag>`) that continues where the last attempt stopped.
+//! Attempts and byte counts are kept in `downloads.json` next to the partial
+//! files, so the next sync resumes too; a changed ETag starts over. An
+//! object whose attempts all fail is reported as a failed item of the
+//! sync (see [`crate::sync_failures`]).
+//!
+//! # Archives
+//!
+//! With `[connectors.s3.<name>.archives] enabled = true`, `.zip`, `.tar`,
+//! and `.tar.gz` objects are downloaded and each file inside becomes a
+//! document with `source_id` `<key>!/<inner path>` (see [`crate::archive`]).
+//! Include and exclude globs are matched against that path relative to the
+//! prefix, so `include_globs = ["**/*.md"]` selects Markdown files inside
+//! archives too. Inner files take the archive object's modification time.
+//!
 //! # Content Type Detection
 //!
 //! File extensions are mapped to MIME types:
@@ -56,12 +105,23 @@ use anyhow::{bail, Context, Result};
 use async_trait::async_trait;
 use chrono::{TimeZone, Utc};
 use globset::{Glob, GlobSet, GlobSetBuilder};
-use hmac::{Hmac, Mac};
-use sha2::{Digest, Sha256};
-
-use crate::config::S3ConnectorConfig;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+use crate::archive::{self, ArchiveKind};
+use crate::aws_sigv4::{
+    derive_signing_key, hex_hmac_sha256, hex_sha256, uri_encode, AwsCredentials,
+};
+use crate::config::{S3ConnectorConfig, S3SelectConfig, SyncConfig};
+use crate::ctx_dirs;
+use crate::download::{retry_delay, BandwidthLimiter, DownloadCheckpoint};
 use crate::models::SourceItem;
-use crate::traits::Connector;
+use crate::sync_failures::ItemFailure;
+use crate::traits::{Connector, ConnectorHealth};
 
 // ═══════════════════════════════════════════════════════════════════════
 // Connector trait implementation
@@ -76,12 +136,29 @@ pub struct S3Connector {
     name: String,
     /// Configuration for this S3 connector instance.
     config: S3ConnectorConfig,
+    /// Bandwidth and retry settings from `[sync]`.
+    sync: SyncConfig,
+    /// Path to the SQLite database, used to derive the default cache directory.
+    db_path: PathBuf,
+    /// Objects the most recent scan could not download.
+    failed: Mutex<Vec<ItemFailure>>,
 }
 
 impl S3Connector {
     /// Create a new S3 connector instance.
-    pub fn new(name: String, config: S3ConnectorConfig) -> Self {
-        Self { name, config }
+    pub fn new(
+        name: String,
+        config: S3ConnectorConfig,
+        sync: SyncConfig,
+        db_path: PathBuf,
+    ) -> Self {
+        Self {
+            name,
+            config,
+            sync,
+            db_path,
+            failed: Mutex::new(Vec::new()),
+        }
     }
 }
 
@@ -100,11 +177,33 @@ impl Connector for S3Connector {
     }
 
     async fn scan(&self) -> Result<Vec<SourceItem>> {
-        scan_s3(&self.name, &self.config).await
+        let cache_dir = s3_cache_dir(&self.name, &self.config, &self.db_path);
+        let scan = scan_s3(&self.name, &self.config, &self.sync, &cache_dir).await?;
+        *self.failed.lock().unwrap() = scan.failed;
+        Ok(scan.items)
+    }
+
+    fn failed(&self) -> Vec<ItemFailure> {
+        self.failed.lock().unwrap().clone()
+    }
+
+    async fn health(&self) -> ConnectorHealth {
+        ConnectorHealth::probe(async {
+            let creds = s3_credentials(&self.config)?;
+            head_bucket(&self.config, &creds).await
+        })
+        .await
     }
 }
 
-type HmacSha256 = Hmac<Sha256>;
+/// Items and download failures from [`scan_s3`].
+#[derive(Debug)]
+pub struct S3Scan {
+    /// Objects (and files inside archive objects), sorted by `source_id`.
+    pub items: Vec<SourceItem>,
+    /// Objects whose download or select failed on every attempt.
+    pub failed: Vec<ItemFailure>,
+}
 
 /// Scan an S3 bucket and produce [`SourceItem`]s.
 ///
@@ -113,27 +212,36 @@ type HmacSha256 = Hmac<Sha256>;
 /// # Workflow
 ///
 /// 1. Read AWS credentials from environment variables.
-/// 2. List all objects in the bucket (with pagination).
+/// 2. List all objects in the bucket (with pagination, sharded by
+///    sub-prefix when `list_concurrency` is above 1).
 /// 3. Apply include/exclude glob filters.
-/// 4. Download each matching object's content.
-/// 5. Return sorted `SourceItem`s with S3 metadata.
-///
-/// # Errors
+/// 4. Download each matching object's content, resuming partial downloads,
+///    and unpack archives when enabled. Objects matching the `select`
+///    globs are filtered with S3 Select instead.
+/// 5. Return sorted `SourceItem`s with S3 metadata, and the objects that
+///    could not be downloaded.
 ///
 /// # Arguments
 ///
 /// - `name` — the instance name (e.g. `"runbooks"`). Used as part of the
 ///   source identifier: `"s3:<name>"`.
 /// - `s3_config` — the S3 connector configuration for this instance.
+/// - `sync` — bandwidth cap and retry count.
+/// - `cache_dir` — where partial downloads and their checkpoint are kept.
 ///
 /// # Errors
 ///
 /// Returns an error if:
-/// - AWS credentials are not set in environment
+/// - AWS credentials are not configured or set in the environment
 /// - S3 API requests fail (network or auth errors)
 /// - Object listing or download fails
-pub async fn scan_s3(name: &str, s3_config: &S3ConnectorConfig) -> Result<Vec<SourceItem>> {
-    let creds = AwsCredentials::from_env()?;
+pub async fn scan_s3(
+    name: &str,
+    s3_config: &S3ConnectorConfig,
+    sync: &SyncConfig,
+    cache_dir: &Path,
+) -> Result<S3Scan> {
+    let creds = s3_credentials(s3_config)?;
 
     // Build glob sets
     let include_set = build_globset(&s3_config.include_globs)?;
@@ -141,12 +249,16 @@ pub async fn scan_s3(name: &str, s3_config: &S3ConnectorConfig) -> Result<Vec<So
     let mut default_excludes = vec!["**/.git/**".to_string(), "**/node_modules/**".to_string()];
     default_excludes.extend(s3_config.exclude_globs.clone());
     let exclude_set = build_globset(&default_excludes)?;
+    let select_set = match s3_config.select {
+        Some(ref select) => Some(build_globset(&select.globs)?),
+        None => None,
+    };
 
     // List all objects
     let objects = list_objects(s3_config, &creds).await?;
 
     let mut items = Vec::new();
-    let client = reqwest::Client::new();
+    let mut downloader = Downloader::new(creds, sync, cache_dir);
 
     for obj in &objects {
         // Compute relative key (strip prefix for glob matching)
@@ -160,82 +272,341 @@ pub async fn scan_s3(name: &str, s3_config: &S3ConnectorConfig) -> Result<Vec<So
                 .unwrap_or_else(|| obj.key.clone())
         };
 
-        // Apply glob filters
+        // Apply glob filters; archives are filtered by the files inside them
         if exclude_set.is_match(&rel_key) {
             continue;
         }
-        if !include_set.is_match(&rel_key) {
+        let is_archive = s3_config.archives.enabled && ArchiveKind::from_path(&rel_key).is_some();
+        if !is_archive && !include_set.is_match(&rel_key) {
+            continue;
+        }
+        if is_archive && obj.size as u64 > s3_config.archives.max_total_bytes {
+            tracing::warn!(
+                "skipping archive s3://{}/{}: larger than archives.max_total_bytes",
+                s3_config.bucket,
+                obj.key
+            );
             continue;
         }
 
+        if let (Some(select), Some(select_set)) = (&s3_config.select, &select_set) {
+            if !is_archive && select_set.is_match(&rel_key) {
+                match downloader.select(s3_config, select, obj).await {
+                    Some(records) if !records.is_empty() => {
+                        let metadata = serde_json::json!({
+                            "bucket": s3_config.bucket,
+                            "etag": obj.etag,
+                            "size": obj.size,
+                            "selected_bytes": records.len(),
+                        });
+                        items.push(object_item(
+                            name, s3_config, obj, &obj.key, &records, metadata,
+                        ));
+                    }
+                    Some(_) => tracing::debug!(
+                        "s3://{}/{}: no records match the select expression",
+                        s3_config.bucket,
+                        obj.key
+                    ),
+                    None => {}
+                }
+                continue;
+            }
+        }
+
         // Download the object
-        let body = match download_object(s3_config, &creds, &client, &obj.key).await {
-            Ok(b) => b,
+        let Some(bytes) = downloader.fetch(s3_config, obj).await else {
+            continue;
+        };
+
+        if !is_archive {
+            let metadata = serde_json::json!({
+                "bucket": s3_config.bucket,
+                "etag": obj.etag,
+                "size": obj.size,
+            });
+            items.push(object_item(
+                name, s3_config, obj, &obj.key, &bytes, metadata,
+            ));
+            continue;
+        }
+
+        let contents = match archive::read_archive(&obj.key, &bytes, &s3_config.archives) {
+            Ok(contents) => contents,
             Err(e) => {
-                eprintln!(
-                    "Warning: failed to download s3://{}/{}: {}",
-                    s3_config.bucket, obj.key, e
+                tracing::warn!(
+                    "skipping archive s3://{}/{}: {:#}",
+                    s3_config.bucket,
+                    obj.key,
+                    e
                 );
                 continue;
             }
         };
+        for entry in contents.entries {
+            let rel_id = format!("{}{}{}", rel_key, archive::SEPARATOR, entry.path);
+            if exclude_set.is_match(&rel_id) || !include_set.is_match(&rel_id) {
+                continue;
+            }
+            let source_id = format!("{}{}{}", obj.key, archive::SEPARATOR, entry.path);
+            let metadata = serde_json::json!({
+                "bucket": s3_config.bucket,
+                "etag": obj.etag,
+                "size": entry.bytes.len(),
+                "archive": obj.key,
+                "archive_path": entry.path,
+            });
+            items.push(object_item(
+                name,
+                s3_config,
+                obj,
+                &source_id,
+                &entry.bytes,
+                metadata,
+            ));
+        }
+    }
 
-        let title = obj.key.rsplit('/').next().unwrap_or(&obj.key).to_string();
-        let source_url = format!("s3://{}/{}", s3_config.bucket, obj.key);
+    let listed: HashSet<&str> = objects.iter().map(|o| o.key.as_str()).collect();
+    let failed = downloader.finish(&listed)?;
 
-        let metadata = serde_json::json!({
-            "bucket": s3_config.bucket,
-            "etag": obj.etag,
-            "size": obj.size,
-        });
+    items.sort_by(|a, b| a.source_id.cmp(&b.source_id));
+    Ok(S3Scan { items, failed })
+}
 
-        items.push(SourceItem {
-            source: format!("s3:{}", name),
-            source_id: obj.key.clone(),
-            source_url: Some(source_url),
-            title: Some(title),
-            author: None,
-            created_at: Utc.timestamp_opt(obj.last_modified, 0).unwrap(),
-            updated_at: Utc.timestamp_opt(obj.last_modified, 0).unwrap(),
-            content_type: detect_content_type(&obj.key),
-            body,
-            metadata_json: metadata.to_string(),
-            raw_json: None,
-            raw_bytes: None,
-        });
+/// Build the [`SourceItem`] for an object, or for a file inside an archive
+/// object (`source_id` = `<key>!/<inner path>`).
+fn object_item(
+    name: &str,
+    s3_config: &S3ConnectorConfig,
+    obj: &S3Object,
+    source_id: &str,
+    bytes: &[u8],
+    metadata: serde_json::Value,
+) -> SourceItem {
+    let title = source_id
+        .rsplit('/')
+        .next()
+        .unwrap_or(source_id)
+        .to_string();
+    SourceItem {
+        source: format!("s3:{}", name),
+        source_id: source_id.to_string(),
+        source_url: Some(format!("s3://{}/{}", s3_config.bucket, obj.key)),
+        title: Some(title),
+        author: None,
+        created_at: Utc.timestamp_opt(obj.last_modified, 0).unwrap(),
+        updated_at: Utc.timestamp_opt(obj.last_modified, 0).unwrap(),
+        content_type: detect_content_type(source_id),
+        body: String::from_utf8_lossy(bytes).to_string(),
+        metadata_json: metadata.to_string(),
+        raw_json: None,
+        raw_bytes: None,
+        parent_source_id: None,
     }
-
-    items.sort_by(|a, b| a.source_id.cmp(&b.source_id));
-    Ok(items)
 }
 
-// ============ AWS Credentials ============
+/// Cache directory for partial downloads of one connector instance.
+fn s3_cache_dir(name: &str, s3_config: &S3ConnectorConfig, db_path: &Path) -> PathBuf {
+    match &s3_config.cache_dir {
+        Some(dir) => dir.clone(),
+        None if ctx_dirs::is_default_workspace_db_path(db_path) => {
+            ctx_dirs::workspace_cache_dir().join("s3").join(name)
+        }
+        None => db_path
+            .parent()
+            .unwrap_or_else(|| Path::new("."))
+            .join(".s3-cache")
+            .join(name),
+    }
+}
 
-/// AWS credentials loaded from environment variables.
-struct AwsCredentials {
-    access_key_id: String,
-    secret_access_key: String,
-    session_token: Option<String>,
+// ============ Resumable Downloads ============
+
+/// Downloads objects through partial files, retrying and resuming failures.
+struct Downloader {
+    creds: AwsCredentials,
+    client: reqwest::Client,
+    limiter: BandwidthLimiter,
+    checkpoint: DownloadCheckpoint,
+    checkpoint_path: PathBuf,
+    partial_dir: PathBuf,
+    retries: u32,
+    /// Objects that failed on every attempt.
+    failed: Vec<ItemFailure>,
 }
 
-impl AwsCredentials {
-    /// Load credentials from `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY`,
-    /// and optionally `AWS_SESSION_TOKEN`.
-    fn from_env() -> Result<Self> {
-        let access_key_id = std::env::var("AWS_ACCESS_KEY_ID")
-            .context("AWS_ACCESS_KEY_ID environment variable not set")?;
-        let secret_access_key = std::env::var("AWS_SECRET_ACCESS_KEY")
-            .context("AWS_SECRET_ACCESS_KEY environment variable not set")?;
-        let session_token = std::env::var("AWS_SESSION_TOKEN").ok();
-
-        Ok(Self {
-            access_key_id,
-            secret_access_key,
-            session_token,
-        })
+impl Downloader {
+    fn new(creds: AwsCredentials, sync: &SyncConfig, cache_dir: &Path) -> Self {
+        let checkpoint_path = cache_dir.join("downloads.json");
+        Self {
+            creds,
+            client: reqwest::Client::new(),
+            limiter: BandwidthLimiter::new(sync.max_bandwidth_mbps),
+            checkpoint: DownloadCheckpoint::load(&checkpoint_path),
+            checkpoint_path,
+            partial_dir: cache_dir.join("partial"),
+            retries: sync.download_retries,
+            failed: Vec::new(),
+        }
+    }
+
+    fn partial_path(&self, key: &str) -> PathBuf {
+        self.partial_dir.join(&hex_sha256(key.as_bytes())[..16])
+    }
+
+    /// Download one object, or `None` after all attempts failed.
+    async fn fetch(&mut self, s3_config: &S3ConnectorConfig, obj: &S3Object) -> Option<Vec<u8>> {
+        let partial = self.partial_path(&obj.key);
+        let (_, valid) = self.checkpoint.entry(&obj.key, &obj.etag);
+        if !valid {
+            let _ = std::fs::remove_file(&partial);
+        }
+
+        for attempt in 1..=self.retries {
+            let result = download_object(
+                s3_config,
+                &self.creds,
+                &self.client,
+                obj,
+                &partial,
+                &mut self.limiter,
+            )
+            .await
+            .and_then(|()| {
+                std::fs::read(&partial)
+                    .with_context(|| format!("Failed to read {}", partial.display()))
+            });
+
+            match result {
+                Ok(bytes) => {
+                    let _ = std::fs::remove_file(&partial);
+                    self.checkpoint.complete(&obj.key);
+                    return Some(bytes);
+                }
+                Err(e) => {
+                    let on_disk = std::fs::metadata(&partial).map(|m| m.len()).unwrap_or(0);
+                    let total = self.checkpoint.record_failure(&obj.key, on_disk, &e);
+                    if let Err(save_err) = self.checkpoint.save(&self.checkpoint_path) {
+                        tracing::warn!("{:#}", save_err);
+                    }
+                    if attempt == self.retries {
+                        tracing::warn!(
+                            "failed to download s3://{}/{} ({} attempts, {} bytes kept for the next sync): {:#}",
+                            s3_config.bucket, obj.key, total, on_disk, e
+                        );
+                        self.failed.push(ItemFailure::new(
+                            &obj.key,
+                            "fetch",
+                            format!("download failed after {} attempts: {:#}", total, e),
+                        ));
+                        return None;
+                    }
+                    let delay = retry_delay(attempt);
+                    tracing::warn!(
+                        "download of s3://{}/{} failed at byte {} ({:#}); retrying in {}s",
+                        s3_config.bucket,
+                        obj.key,
+                        on_disk,
+                        e,
+                        delay.as_secs()
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+            }
+        }
+        None
+    }
+
+    /// Read the records of one object that match the `select` expression,
+    /// or `None` after all attempts failed. Select responses cannot be
+    /// resumed, so a failed attempt starts over.
+    async fn select(
+        &mut self,
+        s3_config: &S3ConnectorConfig,
+        select: &S3SelectConfig,
+        obj: &S3Object,
+    ) -> Option<Vec<u8>> {
+        let request = match select_request_xml(select, &obj.key) {
+            Ok(request) => request,
+            Err(e) => {
+                tracing::warn!("skipping s3://{}/{}: {:#}", s3_config.bucket, obj.key, e);
+                return None;
+            }
+        };
+        for attempt in 1..=self.retries {
+            let result = select_object(
+                s3_config,
+                &self.creds,
+                &self.client,
+                &obj.key,
+                &request,
+                &mut self.limiter,
+            )
+            .await;
+            match result {
+                Ok(records) => return Some(records),
+                Err(e) if attempt == self.retries => {
+                    tracing::warn!(
+                        "failed to select from s3://{}/{} ({} attempts): {:#}",
+                        s3_config.bucket,
+                        obj.key,
+                        attempt,
+                        e
+                    );
+                    self.failed.push(ItemFailure::new(
+                        &obj.key,
+                        "fetch",
+                        format!("select failed after {} attempts: {:#}", attempt, e),
+                    ));
+                }
+                Err(e) => {
+                    let delay = retry_delay(attempt);
+                    tracing::warn!(
+                        "select from s3://{}/{} failed ({:#}); retrying in {}s",
+                        s3_config.bucket,
+                        obj.key,
+                        e,
+                        delay.as_secs()
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+            }
+        }
+        None
+    }
+
+    /// Drop checkpoint entries and partial files for objects that are gone,
+    /// then persist what is still outstanding. Returns the objects that
+    /// failed.
+    fn finish(mut self, listed: &HashSet<&str>) -> Result<Vec<ItemFailure>> {
+        let stale: Vec<String> = self
+            .checkpoint
+            .entries
+            .keys()
+            .filter(|k| !listed.contains(k.as_str()))
+            .cloned()
+            .collect();
+        for key in stale {
+            let _ = std::fs::remove_file(self.partial_path(&key));
+            self.checkpoint.complete(&key);
+        }
+        self.checkpoint.save(&self.checkpoint_path)?;
+        Ok(self.failed)
     }
 }
 
+/// Load credentials from the connector's `access_key_id`,
+/// `secret_access_key`, and `session_token` settings, falling back to
+/// `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY`, and `AWS_SESSION_TOKEN`.
+fn s3_credentials(s3_config: &S3ConnectorConfig) -> Result<AwsCredentials> {
+    AwsCredentials::load(
+        s3_config.access_key_id.as_deref(),
+        s3_config.secret_access_key.as_deref(),
+        s3_config.session_token.as_deref(),
+    )
+}
+
 // ============ S3 Object Listing ============
 
 /// Metadata for a single S3 object, parsed from `ListObjectsV2` XML response.
@@ -250,16 +621,63 @@ struct S3Object {
     size: i64,
 }
 
-/// List all objects in the configured S3 bucket, handling pagination.
+/// One `ListObjectsV2` listing, all pages.
+#[derive(Default)]
+struct Listing {
+    objects: Vec<S3Object>,
+    /// Sub-prefixes rolled up by the delimiter (`<prefix>2024/`).
+    common_prefixes: Vec<String>,
+}
+
+/// List all objects under the configured prefix, handling pagination.
 ///
-/// Uses `ListObjectsV2` with `max-keys=1000` per page. Automatically
-/// follows `NextContinuationToken` until all objects are retrieved.
+/// With `list_concurrency` above 1, the sub-prefixes one level below the
+/// prefix are listed concurrently (see the module docs).
 async fn list_objects(
     s3_config: &S3ConnectorConfig,
     creds: &AwsCredentials,
 ) -> Result<Vec<S3Object>> {
     let client = reqwest::Client::new();
-    let mut objects = Vec::new();
+    if s3_config.list_concurrency <= 1 {
+        let listing = list_prefix(s3_config, creds, &client, &s3_config.prefix, None).await?;
+        return Ok(listing.objects);
+    }
+
+    let top = list_prefix(s3_config, creds, &client, &s3_config.prefix, Some("/")).await?;
+    let mut objects = top.objects;
+    let limit = Arc::new(Semaphore::new(s3_config.list_concurrency));
+    let mut shards = JoinSet::new();
+    for shard in top.common_prefixes {
+        let (limit, s3_config, creds, client) = (
+            limit.clone(),
+            s3_config.clone(),
+            creds.clone(),
+            client.clone(),
+        );
+        shards.spawn(async move {
+            let _permit = limit.acquire_owned().await?;
+            list_prefix(&s3_config, &creds, &client, &shard, None).await
+        });
+    }
+    while let Some(listing) = shards.join_next().await {
+        objects.extend(listing.context("S3 listing task panicked")??.objects);
+    }
+    objects.sort_by(|a, b| a.key.cmp(&b.key));
+    Ok(objects)
+}
+
+/// List the objects under `prefix`, following `NextContinuationToken`
+/// through every page of `ListObjectsV2` (`max-keys=1000`). With a
+/// `delimiter`, keys below the next delimiter are rolled up into
+/// [`Listing::common_prefixes`].
+async fn list_prefix(
+    s3_config: &S3ConnectorConfig,
+    creds: &AwsCredentials,
+    client: &reqwest::Client,
+    prefix: &str,
+    delimiter: Option<&str>,
+) -> Result<Listing> {
+    let mut listing = Listing::default();
     let mut continuation_token: Option<String> = None;
 
     loop {
@@ -268,8 +686,12 @@ async fn list_objects(
             ("max-keys".to_string(), "1000".to_string()),
         ];
 
-        if !s3_config.prefix.is_empty() {
-            query_params.push(("prefix".to_string(), s3_config.prefix.clone()));
+        if !prefix.is_empty() {
+            query_params.push(("prefix".to_string(), prefix.to_string()));
+        }
+
+        if let Some(delimiter) = delimiter {
+            query_params.push(("delimiter".to_string(), delimiter.to_string()));
         }
 
         if let Some(ref token) = continuation_token {
@@ -357,7 +779,7 @@ async fn list_objects(
             anyhow::anyhow!(
                 "Failed to list S3 objects in s3://{}/{}: {}",
                 s3_config.bucket,
-                s3_config.prefix,
+                prefix,
                 e
             )
         })?;
@@ -374,7 +796,10 @@ async fn list_objects(
 
         let xml_body = resp.text().await?;
         let (batch, is_truncated, next_token) = parse_list_objects_response(&xml_body)?;
-        objects.extend(batch);
+        listing.objects.extend(batch);
+        listing
+            .common_prefixes
+            .extend(parse_common_prefixes(&xml_body));
 
         if is_truncated {
             continuation_token = next_token;
@@ -383,16 +808,25 @@ async fn list_objects(
         }
     }
 
-    Ok(objects)
+    Ok(listing)
 }
 
-/// Download a single object's content from S3 using a signed GET request.
+/// Download a single object into `partial` using a signed GET request.
+///
+/// Existing partial data is continued with a ranged request guarded by the
+/// object's ETag. The server may answer `200` (range ignored, start over),
+/// `206` (append), `416` (nothing left to read), or `412` (the object
+/// changed since listing; the partial is discarded).
 async fn download_object(
     s3_config: &S3ConnectorConfig,
     creds: &AwsCredentials,
     client: &reqwest::Client,
-    key: &str,
-) -> Result<String> {
+    obj: &S3Object,
+    partial: &Path,
+    limiter: &mut BandwidthLimiter,
+) -> Result<()> {
+    let key = obj.key.as_str();
+    let offset = std::fs::metadata(partial).map(|m| m.len()).unwrap_or(0);
     let host = s3_host(s3_config);
     let encoded_key = key.split('/').map(uri_encode).collect::<Vec<_>>().join("/");
     let url = format!("https://{}/{}", host, encoded_key);
@@ -460,22 +894,371 @@ async fn download_object(
     if let Some(ref token) = creds.session_token {
         req_builder = req_builder.header("x-amz-security-token", token);
     }
+    if offset > 0 {
+        req_builder = req_builder
+            .header("Range", format!("bytes={}-", offset))
+            .header("If-Match", format!("\"{}\"", obj.etag));
+    }
 
-    let resp = req_builder
+    let mut resp = req_builder
         .send()
         .await
         .map_err(|e| anyhow::anyhow!("Failed to get s3://{}/{}: {}", s3_config.bucket, key, e))?;
 
+    let append = match resp.status().as_u16() {
+        200 => false,
+        206 => true,
+        416 => return Ok(()),
+        412 => {
+            let _ = std::fs::remove_file(partial);
+            bail!("object changed since it was listed");
+        }
+        _ => bail!(
+            "S3 GetObject failed (HTTP {}) for key '{}'",
+            resp.status(),
+            key
+        ),
+    };
+
+    if let Some(parent) = partial.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(append)
+        .truncate(!append)
+        .open(partial)
+        .await
+        .with_context(|| format!("Failed to open {}", partial.display()))?;
+    while let Some(chunk) = resp.chunk().await? {
+        file.write_all(&chunk).await?;
+        limiter.consume(chunk.len()).await;
+    }
+    file.flush().await?;
+    Ok(())
+}
+
+// ============ S3 Select ============
+
+/// Run a `SelectObjectContent` request for `key` and return the records
+/// it selected.
+async fn select_object(
+    s3_config: &S3ConnectorConfig,
+    creds: &AwsCredentials,
+    client: &reqwest::Client,
+    key: &str,
+    request: &str,
+    limiter: &mut BandwidthLimiter,
+) -> Result<Vec<u8>> {
+    let host = s3_host(s3_config);
+    let encoded_key = key.split('/').map(uri_encode).collect::<Vec<_>>().join("/");
+    let canonical_querystring = "select=&select-type=2";
+    let url = format!("https://{}/{}?{}", host, encoded_key, canonical_querystring);
+
+    let now = Utc::now();
+    let date_stamp = now.format("%Y%m%d").to_string();
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+
+    let payload_hash = hex_sha256(request.as_bytes());
+
+    let mut headers = vec![
+        ("host".to_string(), host.clone()),
+        ("x-amz-content-sha256".to_string(), payload_hash.clone()),
+        ("x-amz-date".to_string(), amz_date.clone()),
+    ];
+    if let Some(ref token) = creds.session_token {
+        headers.push(("x-amz-security-token".to_string(), token.clone()));
+    }
+    headers.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let signed_headers: String = headers
+        .iter()
+        .map(|(k, _)| k.as_str())
+        .collect::<Vec<_>>()
+        .join(";");
+
+    let canonical_headers: String = headers
+        .iter()
+        .map(|(k, v)| format!("{}:{}\n", k, v))
+        .collect();
+
+    let canonical_request = format!(
+        "POST\n/{}\n{}\n{}\n{}\n{}",
+        encoded_key, canonical_querystring, canonical_headers, signed_headers, payload_hash
+    );
+
+    let credential_scope = format!("{}/{}/s3/aws4_request", date_stamp, s3_config.region);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        credential_scope,
+        hex_sha256(canonical_request.as_bytes())
+    );
+
+    let signing_key = derive_signing_key(
+        &creds.secret_access_key,
+        &date_stamp,
+        &s3_config.region,
+        "s3",
+    );
+    let signature = hex_hmac_sha256(&signing_key, string_to_sign.as_bytes());
+
+    let authorization = format!(
+        "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+        creds.access_key_id, credential_scope, signed_headers, signature
+    );
+
+    let mut req_builder = client
+        .post(&url)
+        .header("Authorization", &authorization)
+        .header("x-amz-content-sha256", &payload_hash)
+        .header("x-amz-date", &amz_date)
+        .body(request.to_string());
+
+    if let Some(ref token) = creds.session_token {
+        req_builder = req_builder.header("x-amz-security-token", token);
+    }
+
+    let mut resp = req_builder.send().await.map_err(|e| {
+        anyhow::anyhow!(
+            "Failed to select from s3://{}/{}: {}",
+            s3_config.bucket,
+            key,
+            e
+        )
+    })?;
+
     if !resp.status().is_success() {
         let status = resp.status();
-        bail!("S3 GetObject failed (HTTP {}) for key '{}'", status, key);
+        let body = resp.text().await.unwrap_or_default();
+        bail!(
+            "S3 SelectObjectContent failed (HTTP {}) for key '{}': {}",
+            status,
+            key,
+            extract_xml_value(&body, "Message").unwrap_or(body)
+        );
+    }
+
+    let mut stream = Vec::new();
+    while let Some(chunk) = resp.chunk().await? {
+        stream.extend_from_slice(&chunk);
+        limiter.consume(chunk.len()).await;
+    }
+    parse_select_event_stream(&stream)
+}
+
+/// Build the `SelectObjectContentRequest` body for `key`, choosing the
+/// input serialization from its extension.
+fn select_request_xml(select: &S3SelectConfig, key: &str) -> Result<String> {
+    let (name, compression) = match key.strip_suffix(".gz") {
+        Some(name) => (name, "GZIP"),
+        None => (key, "NONE"),
+    };
+    let input = match name.rsplit('.').next() {
+        Some("csv") => format!(
+            "<CSV><FileHeaderInfo>{}</FileHeaderInfo></CSV>",
+            if select.csv_header { "USE" } else { "NONE" }
+        ),
+        Some("json") => "<JSON><Type>DOCUMENT</Type></JSON>".to_string(),
+        Some("jsonl" | "ndjson") => "<JSON><Type>LINES</Type></JSON>".to_string(),
+        _ => bail!("S3 Select reads .csv, .json, .jsonl, and .ndjson objects only"),
+    };
+    Ok(format!(
+        "<SelectObjectContentRequest xmlns=\"http://s3.amazonaws.com/doc/2006-03-01/\">\
+         <Expression>{}</Expression><ExpressionType>SQL</ExpressionType>\
+         <InputSerialization><CompressionType>{}</CompressionType>{}</InputSerialization>\
+         <OutputSerialization><JSON></JSON></OutputSerialization>\
+         </SelectObjectContentRequest>",
+        xml_escape(&select.expression),
+        compression,
+        input
+    ))
+}
+
+/// Concatenate the `Records` payloads of a `SelectObjectContent` response.
+///
+/// The response is an AWS event stream: each message is a 12-byte prelude
+/// (total length, headers length, prelude CRC, all big-endian `u32`),
+/// headers, the payload, and a 4-byte message CRC. Headers are a name
+/// length byte, the name, a value type byte (7 = string), a big-endian
+/// `u16` length, and the value. CRCs are not checked; the transport
+/// already is. An `error` message fails the whole read.
+fn parse_select_event_stream(stream: &[u8]) -> Result<Vec<u8>> {
+    const PRELUDE: usize = 12;
+    let be_u32 = |b: &[u8]| u32::from_be_bytes([b[0], b[1], b[2], b[3]]) as usize;
+
+    let mut records = Vec::new();
+    let mut rest = stream;
+    while !rest.is_empty() {
+        if rest.len() < PRELUDE + 4 {
+            bail!("truncated S3 Select event stream");
+        }
+        let total = be_u32(&rest[0..4]);
+        let headers_len = be_u32(&rest[4..8]);
+        if total < PRELUDE + headers_len + 4 || total > rest.len() {
+            bail!("malformed S3 Select event stream message");
+        }
+        let mut headers = &rest[PRELUDE..PRELUDE + headers_len];
+        let payload = &rest[PRELUDE + headers_len..total - 4];
+
+        let (mut message_type, mut event_type, mut error) = (None, None, None);
+        while !headers.is_empty() {
+            let name_len = headers[0] as usize;
+            if headers.len() < 1 + name_len + 3 {
+                bail!("malformed S3 Select event stream header");
+            }
+            let name = String::from_utf8_lossy(&headers[1..1 + name_len]).to_string();
+            let value_type = headers[1 + name_len];
+            if value_type != 7 {
+                bail!(
+                    "unsupported S3 Select event stream header type {}",
+                    value_type
+                );
+            }
+            let at = 2 + name_len;
+            let value_len = u16::from_be_bytes([headers[at], headers[at + 1]]) as usize;
+            let value_end = at + 2 + value_len;
+            if headers.len() < value_end {
+                bail!("malformed S3 Select event stream header");
+            }
+            let value = String::from_utf8_lossy(&headers[at + 2..value_end]).to_string();
+            match name.as_str() {
+                ":message-type" => message_type = Some(value),
+                ":event-type" => event_type = Some(value),
+                ":error-message" => error = Some(value),
+                ":error-code" if error.is_none() => error = Some(value),
+                _ => {}
+            }
+            headers = &headers[value_end..];
+        }
+
+        if message_type.as_deref() == Some("error") {
+            bail!(
+                "S3 Select error: {}",
+                error.unwrap_or_else(|| "unknown".to_string())
+            );
+        }
+        if event_type.as_deref() == Some("Records") {
+            records.extend_from_slice(payload);
+        }
+        rest = &rest[total..];
+    }
+    Ok(records)
+}
+
+/// Escape text for an XML element.
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Check bucket access with a signed `HeadBucket` request.
+///
+/// Cheaper than listing: no body is returned, and the status code alone
+/// distinguishes bad credentials, a missing bucket, and a wrong region.
+async fn head_bucket(s3_config: &S3ConnectorConfig, creds: &AwsCredentials) -> Result<String> {
+    let host = s3_host(s3_config);
+    let url = format!("https://{}/", host);
+
+    let now = Utc::now();
+    let date_stamp = now.format("%Y%m%d").to_string();
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+
+    let payload_hash = hex_sha256(b"");
+
+    let mut headers = vec![
+        ("host".to_string(), host.clone()),
+        ("x-amz-content-sha256".to_string(), payload_hash.clone()),
+        ("x-amz-date".to_string(), amz_date.clone()),
+    ];
+    if let Some(ref token) = creds.session_token {
+        headers.push(("x-amz-security-token".to_string(), token.clone()));
+    }
+    headers.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let signed_headers: String = headers
+        .iter()
+        .map(|(k, _)| k.as_str())
+        .collect::<Vec<_>>()
+        .join(";");
+
+    let canonical_headers: String = headers
+        .iter()
+        .map(|(k, v)| format!("{}:{}\n", k, v))
+        .collect();
+
+    let canonical_request = format!(
+        "HEAD\n/\n\n{}\n{}\n{}",
+        canonical_headers, signed_headers, payload_hash
+    );
+
+    let credential_scope = format!("{}/{}/s3/aws4_request", date_stamp, s3_config.region);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        credential_scope,
+        hex_sha256(canonical_request.as_bytes())
+    );
+
+    let signing_key = derive_signing_key(
+        &creds.secret_access_key,
+        &date_stamp,
+        &s3_config.region,
+        "s3",
+    );
+    let signature = hex_hmac_sha256(&signing_key, string_to_sign.as_bytes());
+
+    let authorization = format!(
+        "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+        creds.access_key_id, credential_scope, signed_headers, signature
+    );
+
+    let mut req_builder = reqwest::Client::new()
+        .head(&url)
+        .header("Authorization", &authorization)
+        .header("x-amz-content-sha256", &payload_hash)
+        .header("x-amz-date", &amz_date);
+
+    if let Some(ref token) = creds.session_token {
+        req_builder = req_builder.header("x-amz-security-token", token);
     }
 
-    let bytes = resp.bytes().await?;
-    Ok(String::from_utf8_lossy(&bytes).to_string())
+    let resp = req_builder
+        .send()
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to reach s3://{}: {}", s3_config.bucket, e))?;
+
+    let status = resp.status();
+    match status.as_u16() {
+        200..=299 => Ok(format!(
+            "bucket: {} ({})",
+            s3_config.bucket, s3_config.region
+        )),
+        301 => {
+            let actual = resp
+                .headers()
+                .get("x-amz-bucket-region")
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or("unknown");
+            bail!(
+                "bucket '{}' is in region {}, not {}",
+                s3_config.bucket,
+                actual,
+                s3_config.region
+            )
+        }
+        403 => bail!(
+            "access denied to bucket '{}' (check credentials and s3:ListBucket permission)",
+            s3_config.bucket
+        ),
+        404 => bail!("bucket '{}' not found", s3_config.bucket),
+        _ => bail!("S3 HeadBucket failed (HTTP {})", status),
+    }
 }
 
-// ============ AWS SigV4 Helpers ============
+// ============ Endpoint ============
 
 /// Compute the S3 hostname for the configured bucket and region.
 ///
@@ -494,62 +1277,6 @@ fn s3_host(s3_config: &S3ConnectorConfig) -> String {
     }
 }
 
-/// Compute the hex-encoded SHA-256 hash of data.
-fn hex_sha256(data: &[u8]) -> String {
-    let mut hasher = Sha256::new();
-    hasher.update(data);
-    hex::encode(hasher.finalize())
-}
-
-/// Compute HMAC-SHA256 of data with the given key.
-fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
-    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC can take key of any size");
-    mac.update(data);
-    mac.finalize().into_bytes().to_vec()
-}
-
-/// Compute hex-encoded HMAC-SHA256.
-fn hex_hmac_sha256(key: &[u8], data: &[u8]) -> String {
-    hex::encode(hmac_sha256(key, data))
-}
-
-/// Derive the AWS SigV4 signing key for a given date, region, and service.
-///
-/// ```text
-/// kDate    = HMAC("AWS4" + secret, dateStamp)
-/// kRegion  = HMAC(kDate, region)
-/// kService = HMAC(kRegion, service)
-/// kSigning = HMAC(kService, "aws4_request")
-/// ```
-fn derive_signing_key(secret_key: &str, date_stamp: &str, region: &str, service: &str) -> Vec<u8> {
-    let k_date = hmac_sha256(
-        format!("AWS4{}", secret_key).as_bytes(),
-        date_stamp.as_bytes(),
-    );
-    let k_region = hmac_sha256(&k_date, region.as_bytes());
-    let k_service = hmac_sha256(&k_region, service.as_bytes());
-    hmac_sha256(&k_service, b"aws4_request")
-}
-
-/// URI-encode a string per RFC 3986 (used in SigV4 canonical requests).
-///
-/// Encodes all characters except unreserved characters:
-/// `A-Z a-z 0-9 - _ . ~`
-fn uri_encode(s: &str) -> String {
-    let mut result = String::new();
-    for byte in s.bytes() {
-        match byte {
-            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
-                result.push(byte as char);
-            }
-            _ => {
-                result.push_str(&format!("%{:02X}", byte));
-            }
-        }
-    }
-    result
-}
-
 // ============ XML Parsing (minimal, no extra deps) ============
 
 /// Parse a `ListObjectsV2` XML response into a list of [`S3Object`]s.
@@ -606,6 +1333,25 @@ fn parse_list_objects_response(xml: &str) -> Result<(Vec<S3Object>, bool, Option
     Ok((objects, is_truncated, next_token))
 }
 
+/// Parse the `<CommonPrefixes>` of a delimited `ListObjectsV2` response.
+fn parse_common_prefixes(xml: &str) -> Vec<String> {
+    let mut prefixes = Vec::new();
+    let mut remaining = xml;
+    while let Some(start) = remaining.find("<CommonPrefixes>") {
+        let block_start = start + "<CommonPrefixes>".len();
+        let Some(end) = remaining[block_start..].find("</CommonPrefixes>") else {
+            break;
+        };
+        if let Some(prefix) =
+            extract_xml_value(&remaining[block_start..block_start + end], "Prefix")
+        {
+            prefixes.push(prefix);
+        }
+        remaining = &remaining[block_start + end..];
+    }
+    prefixes
+}
+
 /// Extract the text content of an XML tag (simple, non-nested).
 fn extract_xml_value(xml: &str, tag: &str) -> Option<String> {
     let open = format!("<{}>", tag);
@@ -640,3 +1386,91 @@ fn build_globset(patterns: &[String]) -> Result<GlobSet> {
     }
     Ok(builder.build()?)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event_message(headers: &[(&str, &str)], payload: &[u8]) -> Vec<u8> {
+        let mut header_bytes = Vec::new();
+        for (name, value) in headers {
+            header_bytes.push(name.len() as u8);
+            header_bytes.extend_from_slice(name.as_bytes());
+            header_bytes.push(7);
+            header_bytes.extend_from_slice(&(value.len() as u16).to_be_bytes());
+            header_bytes.extend_from_slice(value.as_bytes());
+        }
+        let total = 12 + header_bytes.len() + payload.len() + 4;
+        let mut message = Vec::new();
+        message.extend_from_slice(&(total as u32).to_be_bytes());
+        message.extend_from_slice(&(header_bytes.len() as u32).to_be_bytes());
+        message.extend_from_slice(&[0; 4]);
+        message.extend_from_slice(&header_bytes);
+        message.extend_from_slice(payload);
+        message.extend_from_slice(&[0; 4]);
+        message
+    }
+
+    #[test]
+    fn select_event_stream_concatenates_records() {
+        let records = |payload: &[u8]| {
+            event_message(
+                &[(":message-type", "event"), (":event-type", "Records")],
+                payload,
+            )
+        };
+        let mut stream = records(b"{\"title\":\"a\"}\n");
+        stream.extend(event_message(
+            &[(":message-type", "event"), (":event-type", "Stats")],
+            b"<Stats/>",
+        ));
+        stream.extend(records(b"{\"title\":\"b\"}\n"));
+        stream.extend(event_message(
+            &[(":message-type", "event"), (":event-type", "End")],
+            b"",
+        ));
+        assert_eq!(
+            parse_select_event_stream(&stream).unwrap(),
+            b"{\"title\":\"a\"}\n{\"title\":\"b\"}\n"
+        );
+
+        let error = event_message(
+            &[
+                (":message-type", "error"),
+                (":error-code", "InvalidQuery"),
+                (":error-message", "bad column"),
+            ],
+            b"",
+        );
+        let err = parse_select_event_stream(&error).unwrap_err();
+        assert!(err.to_string().contains("bad column"), "{}", err);
+        assert!(parse_select_event_stream(&stream[..20]).is_err());
+    }
+
+    #[test]
+    fn select_request_follows_extension() {
+        let select = S3SelectConfig {
+            expression: "SELECT * FROM S3Object s WHERE s.n < 3 AND s.team = 'a&b'".to_string(),
+            globs: Vec::new(),
+            csv_header: true,
+        };
+        let csv = select_request_xml(&select, "exports/tickets.csv.gz").unwrap();
+        assert!(csv.contains("<CompressionType>GZIP</CompressionType><CSV><FileHeaderInfo>USE"));
+        assert!(csv.contains("s.n &lt; 3 AND s.team = 'a&amp;b'"));
+        let lines = select_request_xml(&select, "exports/tickets.ndjson").unwrap();
+        assert!(lines.contains("<CompressionType>NONE</CompressionType><JSON><Type>LINES</Type>"));
+        assert!(select_request_xml(&select, "notes.md").is_err());
+    }
+
+    #[test]
+    fn common_prefixes_are_parsed_apart_from_the_listing_prefix() {
+        let xml = "<ListBucketResult><Prefix>logs/</Prefix>\
+            <Contents><Key>logs/readme.md</Key><Size>3</Size></Contents>\
+            <CommonPrefixes><Prefix>logs/2024/</Prefix></CommonPrefixes>\
+            <CommonPrefixes><Prefix>logs/2025/</Prefix></CommonPrefixes>\
+            </ListBucketResult>";
+        assert_eq!(parse_common_prefixes(xml), ["logs/2024/", "logs/2025/"]);
+        let (objects, _, _) = parse_list_objects_response(xml).unwrap();
+        assert_eq!(objects.len(), 1);
+    }
+}
@@ -0,0 +1,308 @@
+//! Native Rust connector scaffolding (`ctx connector init --lang rust`).
+//!
+//! Generates `crates/context-harness/src/connector_<name>.rs` in a Context
+//! Harness checkout: a config struct, a [`Connector`](crate::traits::Connector)
+//! implementation that emits one sample item, and unit tests. The command
+//! then prints the lines that wire the module in, which mirror the
+//! built-in connectors:
+//!
+//! | File | Addition |
+//! |------|----------|
+//! | `lib.rs`, `main.rs` | `mod connector_<name>;` |
+//! | `config.rs` | A `<name>` map in `ConnectorsConfig` and its `chunking` override |
+//! | `traits.rs` | A loop in `ConnectorRegistry::from_config` |
+//! | `ingest.rs` | The connector type in the `ctx sync <type>` filter |
+//!
+//! Names are lowercase ASCII letters, digits, `-`, and `_`, starting with a
+//! letter; `-` becomes `_` in the module name. Built-in connector types
+//! cannot be reused.
+
+use anyhow::{bail, Result};
+use std::path::Path;
+
+/// Crate source directory, relative to the checkout root.
+const SRC_DIR: &str = "crates/context-harness/src";
+
+/// Connector types that already exist.
+const RESERVED: &[&str] = &[
+    "filesystem",
+    "git",
+    "s3",
+    "sharepoint",
+    "zendesk",
+    "stackoverflow",
+    "script",
+    "custom",
+];
+
+/// Module name for `name`: `-` replaced by `_`.
+fn module_name(name: &str) -> String {
+    name.replace('-', "_")
+}
+
+/// Type name prefix for `name`: `confluence-cloud` → `ConfluenceCloud`.
+fn type_name(name: &str) -> String {
+    name.split(['-', '_'])
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+fn validate_name(name: &str) -> Result<()> {
+    let valid = name.starts_with(|c: char| c.is_ascii_lowercase())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+    if !valid {
+        bail!(
+            "invalid connector name '{}': use lowercase letters, digits, '-' and '_', starting with a letter",
+            name
+        );
+    }
+    if RESERVED.contains(&module_name(name).as_str()) {
+        bail!("'{}' is a built-in connector type", name);
+    }
+    Ok(())
+}
+
+/// Source of the generated module.
+fn render(name: &str) -> String {
+    let module = module_name(name);
+    let ty = type_name(name);
+    format!(
+        r####"//! {ty} connector.
+//!
+//! TODO: describe what this connector reads and how it authenticates.
+//!
+//! # Configuration
+//!
+//! ```toml
+//! [connectors.{module}.main]
+//! url = "https://api.example.com"
+//! api_token = "secret://{name}"    # optional
+//! ```
+//!
+//! # Documents
+//!
+//! TODO: describe the documents each scan produces and their `source_id`s.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::Utc;
+use serde::Deserialize;
+
+use crate::config::ChunkingOverride;
+use crate::models::SourceItem;
+use crate::traits::Connector;
+
+/// Configuration of one `[connectors.{module}.<name>]` instance.
+#[derive(Debug, Clone, Deserialize)]
+pub struct {ty}ConnectorConfig {{
+    /// Base URL of the API to read.
+    pub url: String,
+    /// API token; accepts `secret://` references.
+    #[serde(default)]
+    pub api_token: Option<String>,
+    /// Per-connector chunking overrides.
+    #[serde(default)]
+    pub chunking: Option<ChunkingOverride>,
+}}
+
+/// A {ty} connector instance that implements the [`Connector`] trait.
+pub struct {ty}Connector {{
+    /// Instance name (e.g. `"main"`).
+    name: String,
+    /// Configuration for this connector instance.
+    config: {ty}ConnectorConfig,
+}}
+
+impl {ty}Connector {{
+    /// Create a new {ty} connector instance.
+    pub fn new(name: String, config: {ty}ConnectorConfig) -> Self {{
+        Self {{ name, config }}
+    }}
+}}
+
+#[async_trait]
+impl Connector for {ty}Connector {{
+    fn name(&self) -> &str {{
+        &self.name
+    }}
+
+    fn description(&self) -> &str {{
+        "TODO: describe what this connector ingests"
+    }}
+
+    fn connector_type(&self) -> &str {{
+        "{module}"
+    }}
+
+    async fn scan(&self) -> Result<Vec<SourceItem>> {{
+        // TODO: fetch items from `self.config.url`. Override `scan_batches`
+        // instead to stream large sources, and `health` to probe the API
+        // for `ctx sources`.
+        let now = Utc::now();
+        Ok(vec![SourceItem {{
+            source: self.source_label(),
+            source_id: "example".to_string(),
+            source_url: Some(format!("{{}}/items/example", self.config.url)),
+            title: Some("Example item".to_string()),
+            author: None,
+            created_at: now,
+            updated_at: now,
+            content_type: "text/plain".to_string(),
+            body: "Replace this item with documents from the source.".to_string(),
+            metadata_json: "{{}}".to_string(),
+            raw_json: None,
+            raw_bytes: None,
+            parent_source_id: None,
+        }}])
+    }}
+}}
+
+#[cfg(test)]
+mod tests {{
+    use super::*;
+
+    fn connector() -> {ty}Connector {{
+        {ty}Connector::new(
+            "main".to_string(),
+            {ty}ConnectorConfig {{
+                url: "https://api.example.com".to_string(),
+                api_token: None,
+                chunking: None,
+            }},
+        )
+    }}
+
+    #[test]
+    fn config_parses_from_toml() {{
+        let config: {ty}ConnectorConfig =
+            toml::from_str(r#"url = "https://api.example.com""#).unwrap();
+        assert_eq!(config.url, "https://api.example.com");
+        assert!(config.api_token.is_none());
+    }}
+
+    #[tokio::test]
+    async fn scan_labels_items_with_the_source() {{
+        let items = connector().scan().await.unwrap();
+        assert!(!items.is_empty());
+        for item in &items {{
+            assert_eq!(item.source, "{module}:main");
+            assert!(!item.source_id.is_empty());
+            assert!(!item.body.is_empty());
+        }}
+    }}
+}}
+"####,
+        ty = ty,
+        module = module,
+        name = name,
+    )
+}
+
+/// Lines to add elsewhere in the crate to register the connector.
+fn registration(name: &str) -> String {
+    let module = module_name(name);
+    let ty = type_name(name);
+    format!(
+        r#"  src/lib.rs:
+    pub mod connector_{module};
+
+  src/main.rs:
+    mod connector_{module};
+
+  src/config.rs, in ConnectorsConfig:
+    /// Named {ty} connectors.
+    #[serde(default)]
+    pub {module}: HashMap<String, crate::connector_{module}::{ty}ConnectorConfig>,
+
+  src/config.rs, in ConnectorsConfig::chunking_override (and chunking_overrides):
+    "{module}" => self.{module}.get(name)?.chunking.as_ref(),
+
+  src/traits.rs, in ConnectorRegistry::from_config:
+    for (name, cfg) in &config.connectors.{module} {{
+        registry.register(Box::new(
+            crate::connector_{module}::{ty}Connector::new(name.clone(), cfg.clone()),
+        ));
+    }}
+
+  src/ingest.rs, in the connector type filter:
+    "filesystem" | "git" | ... | "{module}"
+"#,
+        module = module,
+        ty = ty,
+    )
+}
+
+/// Generate `connector_<name>.rs` under `root` and print how to register it.
+///
+/// # Errors
+///
+/// Returns an error if the name is invalid or a built-in type, `root` is
+/// not a Context Harness checkout, or the module already exists.
+pub fn scaffold_rust_connector(root: &Path, name: &str) -> Result<()> {
+    validate_name(name)?;
+    let src = root.join(SRC_DIR);
+    if !src.join("traits.rs").exists() {
+        bail!(
+            "{} not found: run `ctx connector init --lang rust` from the root of a context-harness checkout",
+            src.display()
+        );
+    }
+
+    let module = module_name(name);
+    let path = src.join(format!("connector_{}.rs", module));
+    if path.exists() {
+        bail!("Connector module already exists: {}", path.display());
+    }
+    std::fs::write(&path, render(name))?;
+
+    println!("Created connector: {}", path.display());
+    println!();
+    println!("Register it:");
+    println!();
+    print!("{}", registration(name));
+    println!();
+    println!("Add to your ctx.toml:");
+    println!();
+    println!("  [connectors.{}.main]", module);
+    println!("  url = \"https://api.example.com\"");
+    println!();
+    println!("Then test and sync:");
+    println!();
+    println!("  cargo test -p context-harness connector_{}", module);
+    println!("  ctx sync {}:main", module);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_map_to_module_and_type() {
+        assert_eq!(module_name("confluence-cloud"), "confluence_cloud");
+        assert_eq!(type_name("confluence-cloud"), "ConfluenceCloud");
+        assert_eq!(type_name("jira"), "Jira");
+        assert!(validate_name("jira2").is_ok());
+        assert!(validate_name("Jira").is_err());
+        assert!(validate_name("2jira").is_err());
+        assert!(validate_name("my.connector").is_err());
+        assert!(validate_name("zendesk").is_err());
+    }
+
+    #[test]
+    fn rendered_module_names_the_connector_type() {
+        let source = render("confluence-cloud");
+        assert!(source.contains("pub struct ConfluenceCloudConnectorConfig {"));
+        assert!(source.contains("impl Connector for ConfluenceCloudConnector {"));
+        assert!(source.contains(r#""confluence_cloud:main""#));
+    }
+}
@@ -1,8 +1,31 @@
 //! Lua scripted connector runtime.
 //!
 //! Loads `.lua` connector scripts at runtime and executes them in a sandboxed
-//! Lua 5.4 VM. Each script implements `connector.scan(config) → items[]`,
+//! Lua 5.4 VM. Each script implements `connector.scan(config, state) → items[]`,
 //! returning documents that flow into the standard ingestion pipeline.
+//! An optional `connector.health(config) → ok, message` hook backs the
+//! live probe in `ctx sources`.
+//!
+//! # Streaming
+//!
+//! Instead of building one large array, `scan` may pass each item to
+//! `connector.emit(item)` and return nothing. Emitted items are converted
+//! immediately and handed to the ingest pipeline in batches of
+//! [`EMIT_BATCH_SIZE`], so memory stays bounded for very large sources.
+//! `emit` blocks while earlier batches are being ingested; that wait does
+//! not count toward the script's `timeout`. Items returned from `scan` are
+//! ingested after the emitted ones.
+//!
+//! # Checkpoints
+//!
+//! For incremental sync, a script keeps its own cursor (a page token, the
+//! API's last-modified time) with `checkpoint.set(value)`; any value
+//! `json.encode` accepts works. It is saved in the connector's row of the
+//! `checkpoints` table when the sync completes, and the next sync passes
+//! it to `connector.scan(config, state)` as `state`. `checkpoint.get()`
+//! returns the latest value: the one passed in, or the last one set.
+//! A failed, timed-out, interrupted, or `--dry-run` sync saves nothing,
+//! and `ctx sync --full` passes `nil`.
 //!
 //! # Architecture
 //!
@@ -10,6 +33,11 @@
 //! to avoid blocking the async runtime. HTTP calls use `reqwest::blocking`,
 //! and `sleep()` uses `std::thread::sleep`.
 //!
+//! A sync that is interrupted or runs past `[sync] timeout_secs` cancels
+//! the scan: the VM's instruction hook stops the script with a
+//! `scan cancelled` error the next time it runs Lua code, so a script
+//! blocked in `sleep()` or an HTTP call stops once that call returns.
+//!
 //! # Host APIs
 //!
 //! Scripts have access to sandboxed host APIs provided by [`crate::lua_runtime`]:
@@ -24,6 +52,8 @@
 //! | `base64` | `encode`, `decode` |
 //! | `crypto` | `sha256`, `hmac_sha256` |
 //! | `sleep` | `sleep(seconds)` |
+//! | `cache` | `get`, `set` (per-script cache with TTL; see [`crate::script_cache`]) |
+//! | `checkpoint` | `get`, `set` (scan only; see [Checkpoints](#checkpoints)) |
 //!
 //! # Configuration
 //!
@@ -41,13 +71,25 @@ use anyhow::{bail, Context, Result};
 use async_trait::async_trait;
 use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
 use mlua::prelude::*;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
 
+use std::sync::{Arc, Mutex};
+
 use crate::config::{Config, ScriptConnectorConfig};
-use crate::lua_runtime::{register_all_host_apis, toml_table_to_lua};
+use crate::lua_runtime::{
+    json_value_to_lua, lua_value_to_json, register_all_host_apis, toml_table_to_lua, EnvAccess,
+};
 use crate::models::SourceItem;
-use crate::traits::Connector;
+use crate::registry::{self, ScriptTrust};
+use crate::traits::{CancellationToken, Connector, ConnectorHealth, ItemSink};
+
+/// Items `connector.emit` buffers before handing them to the ingest pipeline.
+pub const EMIT_BATCH_SIZE: usize = 100;
+
+/// The value behind `checkpoint.get`/`checkpoint.set`, shared with the VM
+/// running the scan. `Null` when nothing is saved.
+pub type ScriptCheckpoint = Arc<Mutex<serde_json::Value>>;
 
 // ═══════════════════════════════════════════════════════════════════════
 // Connector trait implementation
@@ -62,12 +104,38 @@ pub struct ScriptConnector {
     name: String,
     /// Configuration for this script connector instance.
     config: ScriptConnectorConfig,
+    /// SQLite database holding the script's `cache` entries.
+    db_path: PathBuf,
+    /// State saved with `checkpoint.set`, loaded before each scan.
+    checkpoint: ScriptCheckpoint,
+    /// Checks the script must pass before each run, when it comes from a
+    /// registry.
+    trust: ScriptTrust,
 }
 
 impl ScriptConnector {
     /// Create a new script connector instance.
-    pub fn new(name: String, config: ScriptConnectorConfig) -> Self {
-        Self { name, config }
+    pub fn new(name: String, config: ScriptConnectorConfig, db_path: PathBuf) -> Self {
+        Self {
+            name,
+            config,
+            db_path,
+            checkpoint: ScriptCheckpoint::default(),
+            trust: ScriptTrust::default(),
+        }
+    }
+
+    /// Verify the script against its registry's checksum and signature
+    /// before every scan and health check (see [`crate::registry`]).
+    pub fn with_trust(mut self, trust: ScriptTrust) -> Self {
+        self.trust = trust;
+        self
+    }
+
+    /// Environment variables the script may read: its `env_allowlist`, or
+    /// none when it comes from a registry and has no allowlist.
+    fn env(&self) -> EnvAccess {
+        EnvAccess::resolve(self.config.env_allowlist.as_deref(), self.trust.installed())
     }
 }
 
@@ -86,7 +154,62 @@ impl Connector for ScriptConnector {
     }
 
     async fn scan(&self) -> Result<Vec<SourceItem>> {
-        scan_script(&self.name, &self.config).await
+        self.trust.verify_file(&self.config.path)?;
+        scan_script(
+            &self.name,
+            &self.config,
+            &self.db_path,
+            &self.env(),
+            &self.checkpoint,
+        )
+        .await
+    }
+
+    async fn scan_batches(&self, sink: ItemSink) -> Result<()> {
+        let cancel = CancellationToken::new();
+        self.scan_cancellable(sink, cancel).await
+    }
+
+    async fn scan_cancellable(&self, sink: ItemSink, cancel: CancellationToken) -> Result<()> {
+        self.trust.verify_file(&self.config.path)?;
+        stream_script(
+            &self.name,
+            &self.config,
+            &self.db_path,
+            &self.env(),
+            &self.checkpoint,
+            sink,
+            cancel,
+        )
+        .await
+    }
+
+    fn resume_from(&self, state: Option<String>) {
+        let value = match state.map(|s| serde_json::from_str(&s)) {
+            Some(Ok(value)) => value,
+            Some(Err(e)) => {
+                tracing::warn!(
+                    "script:{}: ignoring unreadable checkpoint: {}",
+                    self.name,
+                    e
+                );
+                serde_json::Value::Null
+            }
+            None => serde_json::Value::Null,
+        };
+        *self.checkpoint.lock().unwrap() = value;
+    }
+
+    fn checkpoint_state(&self) -> Option<String> {
+        Some(self.checkpoint.lock().unwrap().to_string())
+    }
+
+    async fn health(&self) -> ConnectorHealth {
+        ConnectorHealth::probe(async {
+            self.trust.verify_file(&self.config.path)?;
+            health_script(&self.name, &self.config, &self.db_path, &self.env()).await
+        })
+        .await
     }
 }
 
@@ -97,20 +220,130 @@ impl Connector for ScriptConnector {
 /// Scan a Lua script connector and return the ingested items.
 ///
 /// Spawns the Lua VM on a blocking thread to avoid blocking the async
-/// runtime. The script's `connector.scan(config)` is called with the
-/// TOML config section (minus `path` and `timeout`) converted to a Lua table.
+/// runtime. The script's `connector.scan(config, state)` is called with the
+/// TOML config section (minus `path` and `timeout`) converted to a Lua table
+/// and the value of `checkpoint`, which `checkpoint.set` updates.
 pub async fn scan_script(
     name: &str,
     script_config: &ScriptConnectorConfig,
+    db_path: &Path,
+    env: &EnvAccess,
+    checkpoint: &ScriptCheckpoint,
 ) -> Result<Vec<SourceItem>> {
     let path = script_config.path.clone();
     let extra = script_config.extra.clone();
     let name = name.to_string();
     let timeout = script_config.timeout;
+    let db_path = db_path.to_path_buf();
+    let env = env.clone();
+    let checkpoint = Arc::clone(checkpoint);
+
+    tokio::task::spawn_blocking(move || {
+        run_lua_scan(
+            &path,
+            &extra,
+            &name,
+            timeout,
+            &db_path,
+            &env,
+            checkpoint,
+            None,
+            CancellationToken::new(),
+        )
+    })
+    .await
+    .context("Lua connector task panicked")?
+}
 
-    tokio::task::spawn_blocking(move || run_lua_scan(&path, &extra, &name, timeout))
-        .await
-        .context("Lua connector task panicked")?
+/// Scan a Lua script connector, sending items to `sink` in batches of up
+/// to [`EMIT_BATCH_SIZE`] as the script emits them.
+///
+/// The script is stopped with a `scan cancelled` error once `cancel` fires.
+pub async fn stream_script(
+    name: &str,
+    script_config: &ScriptConnectorConfig,
+    db_path: &Path,
+    env: &EnvAccess,
+    checkpoint: &ScriptCheckpoint,
+    sink: ItemSink,
+    cancel: CancellationToken,
+) -> Result<()> {
+    let path = script_config.path.clone();
+    let extra = script_config.extra.clone();
+    let name = name.to_string();
+    let timeout = script_config.timeout;
+    let db_path = db_path.to_path_buf();
+    let env = env.clone();
+    let checkpoint = Arc::clone(checkpoint);
+
+    tokio::task::spawn_blocking(move || {
+        run_lua_scan(
+            &path,
+            &extra,
+            &name,
+            timeout,
+            &db_path,
+            &env,
+            checkpoint,
+            Some(sink),
+            cancel,
+        )
+        .map(|_| ())
+    })
+    .await
+    .context("Lua connector task panicked")?
+}
+
+/// Run a Lua script connector's `connector.health(config)` hook.
+///
+/// The hook returns `ok, message`; `false` or a raised error marks the
+/// connector unhealthy. Scripts without a hook are healthy once they
+/// load and define `connector.scan`. The hook is bounded by the
+/// script's `timeout` or [`ConnectorHealth::TIMEOUT`], whichever is shorter.
+pub async fn health_script(
+    name: &str,
+    script_config: &ScriptConnectorConfig,
+    db_path: &Path,
+    env: &EnvAccess,
+) -> Result<String> {
+    let path = script_config.path.clone();
+    let extra = script_config.extra.clone();
+    let name = name.to_string();
+    let timeout = script_config
+        .timeout
+        .min(ConnectorHealth::TIMEOUT.as_secs());
+    let db_path = db_path.to_path_buf();
+    let env = env.clone();
+
+    tokio::task::spawn_blocking(move || {
+        run_lua_health(&path, &extra, &name, timeout, &db_path, &env)
+    })
+    .await
+    .context("Lua connector task panicked")?
+}
+
+/// Check that a Lua script connector loads and defines `connector.scan`,
+/// without scanning.
+///
+/// Used when tools, agents, and connectors are reloaded at runtime (see
+/// [`crate::reload`]).
+pub fn check_script(
+    name: &str,
+    script_config: &ScriptConnectorConfig,
+    db_path: &Path,
+    env: &EnvAccess,
+) -> Result<()> {
+    let lua = Lua::new();
+    load_connector_table(
+        &lua,
+        &script_config.path,
+        name,
+        script_config.timeout,
+        db_path,
+        env,
+    )
+    .with_context(|| format!("Failed to load connector script '{}'", name))?;
+    Ok(())
 }
 
 /// Scaffold a new connector script from a template.
@@ -153,9 +386,14 @@ connector = {{
 }}
 
 --- Scan the data source and return a list of items to ingest.
+--- For large sources, call connector.emit(item) for each item instead
+--- and return nothing; items are ingested in batches as you go.
+--- For incremental sync, save a cursor with checkpoint.set(value); the
+--- next sync passes it back as `state`.
 --- @param config table Configuration from ctx.toml
+--- @param state any Value saved with checkpoint.set by the last sync, or nil
 --- @return table Array of source item tables
-function connector.scan(config)
+function connector.scan(config, state)
     local items = {{}}
 
     -- Example: fetch from an API
@@ -180,6 +418,14 @@ function connector.scan(config)
     log.info("Fetched " .. #items .. " items")
     return items
 end
+
+--- Optional: cheap reachability check used by `ctx sources`.
+--- @param config table Configuration from ctx.toml
+--- @return boolean, string Healthy flag and a short detail message
+-- function connector.health(config)
+--     local resp = http.get(config.url .. "/api/ping")
+--     return resp.ok, "HTTP " .. resp.status
+-- end
 "#,
         name = name,
         name_upper = name.to_uppercase().replace('-', "_"),
@@ -206,33 +452,50 @@ end
 /// any errors. Useful for development and debugging.
 pub async fn test_script(path: &Path, config: &Config, source: Option<&str>) -> Result<()> {
     let script_path = path.to_path_buf();
-
-    let extra = if let Some(name) = source {
-        config
-            .connectors
-            .script
-            .get(name)
-            .map(|sc| sc.extra.clone())
-            .unwrap_or_default()
-    } else {
-        toml::Table::new()
-    };
+    let trust = registry::script_trust(config, path);
+    trust.verify_file(path)?;
+
+    let script_config = source.and_then(|name| config.connectors.script.get(name));
+    let extra = script_config.map(|sc| sc.extra.clone()).unwrap_or_default();
+    let env = EnvAccess::resolve(
+        script_config.and_then(|sc| sc.env_allowlist.as_deref()),
+        trust.installed(),
+    );
 
     let name = source.unwrap_or("test").to_string();
 
     println!("Testing connector: {} ({})", name, script_path.display());
 
+    let checkpoint = ScriptCheckpoint::default();
     let items = {
         let p = script_path.clone();
         let e = extra;
         let n = name.clone();
-        tokio::task::spawn_blocking(move || run_lua_scan(&p, &e, &n, 300))
-            .await
-            .context("Lua connector task panicked")??
+        let db_path = config.db.path.clone();
+        let checkpoint = Arc::clone(&checkpoint);
+        tokio::task::spawn_blocking(move || {
+            run_lua_scan(
+                &p,
+                &e,
+                &n,
+                300,
+                &db_path,
+                &env,
+                checkpoint,
+                None,
+                CancellationToken::new(),
+            )
+        })
+        .await
+        .context("Lua connector task panicked")??
     };
 
     println!("  ✓ Script loaded and executed");
     println!("  ✓ Returned {} items", items.len());
+    let checkpoint = checkpoint.lock().unwrap().clone();
+    if !checkpoint.is_null() {
+        println!("  ✓ Checkpoint set (not saved): {}", checkpoint);
+    }
 
     let valid = items
         .iter()
@@ -273,27 +536,245 @@ pub async fn test_script(path: &Path, config: &Config, source: Option<&str>) ->
 /// This function runs synchronously on a blocking thread. It:
 /// 1. Creates a sandboxed Lua VM via [`crate::lua_runtime`]
 /// 2. Loads and executes the script
-/// 3. Calls `connector.scan(config)`
-/// 4. Converts the returned Lua table to `Vec<SourceItem>`
+/// 3. Installs `connector.emit` and the `checkpoint` module, and calls
+///    `connector.scan(config, state)`
+/// 4. Converts emitted items and the returned Lua table to `Vec<SourceItem>`
+///
+/// With a `sink`, items are sent to it in batches and the returned vector
+/// is empty.
+#[allow(clippy::too_many_arguments)]
 fn run_lua_scan(
     script_path: &Path,
     extra: &toml::Table,
     name: &str,
     timeout_secs: u64,
+    db_path: &Path,
+    env: &EnvAccess,
+    checkpoint: ScriptCheckpoint,
+    sink: Option<ItemSink>,
+    cancel: CancellationToken,
 ) -> Result<Vec<SourceItem>> {
+    let lua = Lua::new();
+    let deadline =
+        load_connector_script(&lua, script_path, name, timeout_secs, db_path, env, cancel)?;
+
+    // Build the config table (with env var expansion)
+    let config_table = toml_table_to_lua(&lua, extra)?;
+
+    // Call connector.scan(config, state)
+    let connector: LuaTable = lua
+        .globals()
+        .get::<LuaTable>("connector")
+        .map_err(|e| anyhow::anyhow!("Script must define a global 'connector' table: {}", e))?;
+
+    let scan: LuaFunction = connector
+        .get::<LuaFunction>("scan")
+        .map_err(|e| anyhow::anyhow!("connector.scan function not defined: {}", e))?;
+
+    let emitter = Arc::new(Mutex::new(Emitter {
+        connector_name: name.to_string(),
+        sink,
+        deadline,
+        buffer: Vec::new(),
+        emitted: 0,
+    }));
+    let emit_state = Arc::clone(&emitter);
+    let emit = lua.create_function(move |_, item: LuaTable| {
+        let mut emitter = emit_state.lock().unwrap();
+        emitter
+            .emit(item)
+            .map_err(|e| LuaError::RuntimeError(format!("{:#}", e)))
+    })?;
+    connector.set("emit", emit)?;
+
+    let state = json_value_to_lua(&lua, &checkpoint.lock().unwrap())?;
+    register_checkpoint_api(&lua, checkpoint)?;
+
+    let result: Option<LuaTable> = scan
+        .call::<Option<LuaTable>>((config_table, state))
+        .map_err(|e| {
+            anyhow::anyhow!(
+                "connector.scan() failed in '{}': {}",
+                script_path.display(),
+                e
+            )
+        })?;
+
+    // Convert Lua result to Vec<SourceItem>, after anything emitted
+    let returned = match result {
+        Some(table) => lua_table_to_source_items(table, name)?,
+        None => Vec::new(),
+    };
+    let mut emitter = emitter.lock().unwrap();
+    emitter.buffer.extend(returned);
+    emitter.flush()?;
+    Ok(std::mem::take(&mut emitter.buffer))
+}
+
+/// Install `checkpoint.get()` and `checkpoint.set(value)` over `checkpoint`.
+fn register_checkpoint_api(lua: &Lua, checkpoint: ScriptCheckpoint) -> LuaResult<()> {
+    let module = lua.create_table()?;
+
+    let current = Arc::clone(&checkpoint);
+    module.set(
+        "get",
+        lua.create_function(move |lua, ()| json_value_to_lua(lua, &current.lock().unwrap()))?,
+    )?;
+    module.set(
+        "set",
+        lua.create_function(move |_, value: LuaValue| {
+            *checkpoint.lock().unwrap() = lua_value_to_json(value)?;
+            Ok(())
+        })?,
+    )?;
+
+    lua.globals().set("checkpoint", module)
+}
+
+/// State behind `connector.emit`: converts items as they are emitted and,
+/// when streaming, sends them on in batches.
+struct Emitter {
+    connector_name: String,
+    sink: Option<ItemSink>,
+    /// The script's timeout deadline, pushed back while `emit` waits on the sink.
+    deadline: Arc<Mutex<Instant>>,
+    buffer: Vec<SourceItem>,
+    emitted: i64,
+}
+
+impl Emitter {
+    fn emit(&mut self, item: LuaTable) -> Result<()> {
+        self.emitted += 1;
+        if let Some(item) = lua_item_to_source_item(&item, self.emitted, &self.connector_name) {
+            self.buffer.push(item);
+        }
+        if self.buffer.len() >= EMIT_BATCH_SIZE {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Send buffered items to the sink. Without a sink, items stay buffered.
+    fn flush(&mut self) -> Result<()> {
+        let Some(sink) = &self.sink else {
+            return Ok(());
+        };
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let batch = std::mem::take(&mut self.buffer);
+        let waited = Instant::now();
+        sink.blocking_send(batch)
+            .map_err(|_| anyhow::anyhow!("ingest stopped before the scan finished"))?;
+        *self.deadline.lock().unwrap() += waited.elapsed();
+        Ok(())
+    }
+}
+
+/// Load a connector script and call its optional `connector.health` hook.
+///
+/// Runs synchronously on a blocking thread, like [`run_lua_scan`].
+/// Load a connector script and return its `connector` table, checking that
+/// `connector.scan` is defined.
+fn load_connector_table(
+    lua: &Lua,
+    script_path: &Path,
+    name: &str,
+    timeout_secs: u64,
+    db_path: &Path,
+    env: &EnvAccess,
+) -> Result<LuaTable> {
+    load_connector_script(
+        lua,
+        script_path,
+        name,
+        timeout_secs,
+        db_path,
+        env,
+        CancellationToken::new(),
+    )?;
+
+    let connector: LuaTable = lua
+        .globals()
+        .get::<LuaTable>("connector")
+        .map_err(|e| anyhow::anyhow!("Script must define a global 'connector' table: {}", e))?;
+
+    connector
+        .get::<LuaFunction>("scan")
+        .map_err(|e| anyhow::anyhow!("connector.scan function not defined: {}", e))?;
+
+    Ok(connector)
+}
+
+fn run_lua_health(
+    script_path: &Path,
+    extra: &toml::Table,
+    name: &str,
+    timeout_secs: u64,
+    db_path: &Path,
+    env: &EnvAccess,
+) -> Result<String> {
+    let lua = Lua::new();
+    let connector = load_connector_table(&lua, script_path, name, timeout_secs, db_path, env)?;
+
+    let Some(health) = connector
+        .get::<Option<LuaFunction>>("health")
+        .map_err(|e| anyhow::anyhow!("connector.health must be a function: {}", e))?
+    else {
+        return Ok("script loaded (no connector.health hook)".to_string());
+    };
+
+    let config_table = toml_table_to_lua(&lua, extra)?;
+    let (ok, message) = health
+        .call::<(bool, Option<String>)>(config_table)
+        .map_err(|e| anyhow::anyhow!("connector.health() failed: {}", e))?;
+    let message = message.unwrap_or_default();
+
+    if ok {
+        Ok(if message.is_empty() {
+            "connector.health ok".to_string()
+        } else {
+            message
+        })
+    } else if message.is_empty() {
+        bail!("connector.health returned false")
+    } else {
+        bail!("{}", message)
+    }
+}
+
+/// Create the sandboxed VM state for a connector script and execute it.
+///
+/// Installs the timeout and cancellation hook and the shared host APIs from
+/// [`crate::lua_runtime`], then runs the script body so it can define
+/// the global `connector` table.
+///
+/// Returns the timeout deadline, which callers may push back.
+fn load_connector_script(
+    lua: &Lua,
+    script_path: &Path,
+    name: &str,
+    timeout_secs: u64,
+    db_path: &Path,
+    env: &EnvAccess,
+    cancel: CancellationToken,
+) -> Result<Arc<Mutex<Instant>>> {
     let script_src = std::fs::read_to_string(script_path)
         .with_context(|| format!("Failed to read connector script: {}", script_path.display()))?;
 
     let script_dir = script_path.parent().unwrap_or(Path::new(".")).to_path_buf();
 
-    let lua = Lua::new();
-
-    // Set up timeout via instruction hook
-    let deadline = Instant::now() + Duration::from_secs(timeout_secs);
+    // Set up timeout and cancellation via instruction hook
+    let deadline = Arc::new(Mutex::new(
+        Instant::now() + Duration::from_secs(timeout_secs),
+    ));
+    let hook_deadline = Arc::clone(&deadline);
     lua.set_hook(
         mlua::HookTriggers::new().every_nth_instruction(10_000),
         move |_lua, _debug| {
-            if Instant::now() > deadline {
+            if cancel.is_cancelled() {
+                Err(mlua::Error::RuntimeError("scan cancelled".to_string()))
+            } else if Instant::now() > *hook_deadline.lock().unwrap() {
                 Err(mlua::Error::RuntimeError(format!(
                     "script timed out after {} seconds",
                     timeout_secs
@@ -306,7 +787,7 @@ fn run_lua_scan(
 
     // Register all shared host APIs
     let log_name = format!("script:{}", name);
-    register_all_host_apis(&lua, &log_name, &script_dir)?;
+    register_all_host_apis(lua, &log_name, &script_dir, db_path, env)?;
 
     // Load and execute the script
     lua.load(&script_src)
@@ -320,29 +801,7 @@ fn run_lua_scan(
             )
         })?;
 
-    // Build the config table (with env var expansion)
-    let config_table = toml_table_to_lua(&lua, extra)?;
-
-    // Call connector.scan(config)
-    let connector: LuaTable = lua
-        .globals()
-        .get::<LuaTable>("connector")
-        .map_err(|e| anyhow::anyhow!("Script must define a global 'connector' table: {}", e))?;
-
-    let scan: LuaFunction = connector
-        .get::<LuaFunction>("scan")
-        .map_err(|e| anyhow::anyhow!("connector.scan function not defined: {}", e))?;
-
-    let result: LuaTable = scan.call::<LuaTable>(config_table).map_err(|e| {
-        anyhow::anyhow!(
-            "connector.scan() failed in '{}': {}",
-            script_path.display(),
-            e
-        )
-    })?;
-
-    // Convert Lua result to Vec<SourceItem>
-    lua_table_to_source_items(result, name)
+    Ok(deadline)
 }
 
 // ═══════════════════════════════════════════════════════════════════════
@@ -355,80 +814,97 @@ fn run_lua_scan(
 /// warnings and skipped — they do not cause the sync to fail.
 fn lua_table_to_source_items(table: LuaTable, connector_name: &str) -> Result<Vec<SourceItem>> {
     let mut items = Vec::new();
-    let default_source = format!("script:{}", connector_name);
 
     for pair in table.pairs::<i64, LuaTable>() {
         let (idx, item_table) =
             pair.map_err(|e| anyhow::anyhow!("Invalid item in scan result: {}", e))?;
+        if let Some(item) = lua_item_to_source_item(&item_table, idx, connector_name) {
+            items.push(item);
+        }
+    }
 
-        // Required: source_id
-        let source_id: String = match item_table.get::<String>("source_id") {
-            Ok(v) => v,
-            Err(_) => {
-                eprintln!(
-                    "[script:{}] WARN: Skipping item at index {}: missing 'source_id'",
-                    connector_name, idx
-                );
-                continue;
-            }
-        };
+    Ok(items)
+}
 
-        // Required: body
-        let body: String = match item_table.get::<String>("body") {
-            Ok(v) => v,
-            Err(_) => {
-                eprintln!(
-                    "[script:{}] WARN: Skipping item '{}': missing 'body'",
-                    connector_name, source_id
-                );
-                continue;
-            }
-        };
+/// Convert one returned or emitted Lua item table into a SourceItem.
+///
+/// Returns `None`, with a warning, when a required field is missing or
+/// the body is empty. `idx` is the item's position, for the warning.
+fn lua_item_to_source_item(
+    item_table: &LuaTable,
+    idx: i64,
+    connector_name: &str,
+) -> Option<SourceItem> {
+    let default_source = format!("script:{}", connector_name);
 
-        if body.is_empty() {
-            eprintln!(
-                "[script:{}] WARN: Skipping item '{}': empty body",
-                connector_name, source_id
+    // Required: source_id
+    let source_id: String = match item_table.get::<String>("source_id") {
+        Ok(v) => v,
+        Err(_) => {
+            tracing::warn!(
+                script = %default_source,
+                "Skipping item at index {}: missing 'source_id'",
+                idx
             );
-            continue;
+            return None;
         }
+    };
 
-        // Optional fields
-        let source: String = item_table
-            .get::<String>("source")
-            .unwrap_or_else(|_| default_source.clone());
-        let title: Option<String> = item_table.get::<String>("title").ok();
-        let author: Option<String> = item_table.get::<String>("author").ok();
-        let source_url: Option<String> = item_table.get::<String>("source_url").ok();
-        let content_type: String = item_table
-            .get::<String>("content_type")
-            .unwrap_or_else(|_| "text/plain".to_string());
-        let metadata_json: String = item_table
-            .get::<String>("metadata_json")
-            .unwrap_or_else(|_| "{}".to_string());
-
-        // Timestamps
-        let now = Utc::now();
-        let updated_at = parse_lua_timestamp(&item_table, "updated_at").unwrap_or(now);
-        let created_at = parse_lua_timestamp(&item_table, "created_at").unwrap_or(updated_at);
-
-        items.push(SourceItem {
-            source,
-            source_id,
-            source_url,
-            title,
-            author,
-            created_at,
-            updated_at,
-            content_type,
-            body,
-            metadata_json,
-            raw_json: None,
-            raw_bytes: None,
-        });
+    // Required: body
+    let body: String = match item_table.get::<String>("body") {
+        Ok(v) => v,
+        Err(_) => {
+            tracing::warn!(
+                script = %default_source,
+                "Skipping item '{}': missing 'body'",
+                source_id
+            );
+            return None;
+        }
+    };
+
+    if body.is_empty() {
+        tracing::warn!(
+            script = %default_source,
+            "Skipping item '{}': empty body",
+            source_id
+        );
+        return None;
     }
 
-    Ok(items)
+    // Optional fields
+    let source: String = item_table.get::<String>("source").unwrap_or(default_source);
+    let title: Option<String> = item_table.get::<String>("title").ok();
+    let author: Option<String> = item_table.get::<String>("author").ok();
+    let source_url: Option<String> = item_table.get::<String>("source_url").ok();
+    let content_type: String = item_table
+        .get::<String>("content_type")
+        .unwrap_or_else(|_| "text/plain".to_string());
+    let metadata_json: String = item_table
+        .get::<String>("metadata_json")
+        .unwrap_or_else(|_| "{}".to_string());
+    let parent_source_id: Option<String> = item_table.get::<String>("parent_id").ok();
+
+    // Timestamps
+    let now = Utc::now();
+    let updated_at = parse_lua_timestamp(item_table, "updated_at").unwrap_or(now);
+    let created_at = parse_lua_timestamp(item_table, "created_at").unwrap_or(updated_at);
+
+    Some(SourceItem {
+        source,
+        source_id,
+        source_url,
+        title,
+        author,
+        created_at,
+        updated_at,
+        content_type,
+        body,
+        metadata_json,
+        raw_json: None,
+        raw_bytes: None,
+        parent_source_id,
+    })
 }
 
 /// Parse a timestamp from a Lua table field.
@@ -0,0 +1,686 @@
+//! SharePoint / OneDrive connector.
+//!
+//! Reads files from a SharePoint document library or a user's OneDrive
+//! through Microsoft Graph, authenticating as an Entra ID app registration
+//! with the OAuth 2.0 client-credentials flow. The app needs the
+//! `Sites.Read.All` (or `Sites.Selected`) and, for OneDrive, `Files.Read.All`
+//! application permissions.
+//!
+//! # Configuration
+//!
+//! ```toml
+//! [connectors.sharepoint.eng]
+//! tenant_id = "contoso.onmicrosoft.com"
+//! client_id = "00000000-0000-0000-0000-000000000000"
+//! client_secret = "secret://sharepoint-eng"   # default: AZURE_CLIENT_SECRET
+//! site = "contoso.sharepoint.com:/sites/Engineering"
+//! drive = "Documents"                         # default: the site's default library
+//! folder = "Runbooks"                         # default: the whole drive
+//! include_globs = ["**/*.md", "**/*.docx", "**/*.pdf"]
+//! ```
+//!
+//! The drive is selected by exactly one of:
+//!
+//! | Key | Drive |
+//! |-----|-------|
+//! | `site` (+ optional `drive`) | A site's document library, by name or the default one |
+//! | `drive_id` | Any drive by Graph ID |
+//! | `user` | A user's OneDrive, by user principal name |
+//!
+//! # Incremental Sync
+//!
+//! Files are listed with a [delta query] on the drive root. The delta link
+//! returned at the end of a scan is saved with the sync checkpoint (see
+//! [`Connector::checkpoint_state`]), so the next scan only receives changed
+//! items. Delta responses omit item paths, so the state also keeps the
+//! drive's folder tree to resolve them. `ctx sync --full` starts a new
+//! delta query; set `delta = false` to enumerate the whole drive on every
+//! scan.
+//!
+//! Deleted files are not removed from the index.
+//!
+//! # Content
+//!
+//! Markdown and text files are decoded as UTF-8. PDF, DOCX, PPTX, and XLSX
+//! files are downloaded as bytes and converted by the ingest pipeline's
+//! extractor ([`crate::extract`]); files over `max_extract_bytes` are not
+//! downloaded.
+//!
+//! Throttled requests (`429`, `503`) are retried up to three times,
+//! honouring `Retry-After` (see [`crate::http_retry`]).
+//!
+//! [delta query]: https://learn.microsoft.com/graph/api/driveitem-delta
+
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::config::SharePointConnectorConfig;
+use crate::extract;
+use crate::http_retry::{self, Reply};
+use crate::models::SourceItem;
+use crate::secrets;
+use crate::traits::{Connector, ConnectorHealth};
+
+// ═══════════════════════════════════════════════════════════════════════
+// Connector trait implementation
+// ═══════════════════════════════════════════════════════════════════════
+
+/// A SharePoint / OneDrive connector instance that implements the
+/// [`Connector`] trait.
+pub struct SharePointConnector {
+    /// Instance name (e.g. `"eng"`).
+    name: String,
+    /// Configuration for this connector instance.
+    config: SharePointConnectorConfig,
+    /// Delta state from the last checkpoint, replaced by each scan.
+    state: Mutex<Option<String>>,
+}
+
+impl SharePointConnector {
+    /// Create a new SharePoint connector instance.
+    pub fn new(name: String, config: SharePointConnectorConfig) -> Self {
+        Self {
+            name,
+            config,
+            state: Mutex::new(None),
+        }
+    }
+}
+
+#[async_trait]
+impl Connector for SharePointConnector {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "Read SharePoint document libraries and OneDrive via Microsoft Graph"
+    }
+
+    fn connector_type(&self) -> &str {
+        "sharepoint"
+    }
+
+    async fn scan(&self) -> Result<Vec<SourceItem>> {
+        let previous = self.state.lock().unwrap().clone();
+        let scan = scan_sharepoint(&self.name, &self.config, previous.as_deref()).await?;
+        *self.state.lock().unwrap() = scan.state;
+        Ok(scan.items)
+    }
+
+    fn resume_from(&self, state: Option<String>) {
+        *self.state.lock().unwrap() = state;
+    }
+
+    fn checkpoint_state(&self) -> Option<String> {
+        self.state.lock().unwrap().clone()
+    }
+
+    async fn health(&self) -> ConnectorHealth {
+        ConnectorHealth::probe(async {
+            let client = reqwest::Client::builder()
+                .timeout(ConnectorHealth::TIMEOUT)
+                .build()?;
+            let token = acquire_token(&client, &self.config).await?;
+            let graph = GraphClient {
+                client,
+                base: self.config.graph_url.trim_end_matches('/').to_string(),
+                token,
+            };
+            let drive_id = resolve_drive(&graph, &self.config).await?;
+            Ok(format!("drive {}", drive_id))
+        })
+        .await
+    }
+}
+
+/// Items and the delta state to checkpoint from [`scan_sharepoint`].
+#[derive(Debug)]
+pub struct SharePointScan {
+    /// Changed files, sorted by `source_id`.
+    pub items: Vec<SourceItem>,
+    /// Delta link and folder tree as JSON, or `None` with `delta = false`.
+    pub state: Option<String>,
+}
+
+/// Scan a SharePoint or OneDrive drive and produce [`SourceItem`]s.
+///
+/// # Workflow
+///
+/// 1. Acquire an app-only access token.
+/// 2. Resolve the drive from `site`/`drive`, `drive_id`, or `user`.
+/// 3. Run a delta query, starting from the delta link in `previous` if
+///    any.
+/// 4. Filter files by `folder` and include/exclude globs.
+/// 5. Download each matching file and return the new delta state.
+///
+/// # Errors
+///
+/// Returns an error if authentication fails, the drive cannot be resolved,
+/// or the delta query fails. Individual download failures are logged and
+/// the file is skipped.
+pub async fn scan_sharepoint(
+    name: &str,
+    sp_config: &SharePointConnectorConfig,
+    previous: Option<&str>,
+) -> Result<SharePointScan> {
+    let include_set = build_globset(&sp_config.include_globs)?;
+    let exclude_set = build_globset(&sp_config.exclude_globs)?;
+
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(120))
+        .build()?;
+    let token = acquire_token(&client, sp_config).await?;
+    let graph = GraphClient {
+        client,
+        base: sp_config.graph_url.trim_end_matches('/').to_string(),
+        token,
+    };
+    let drive_id = resolve_drive(&graph, sp_config).await?;
+
+    // State for another drive (the config changed) starts a new delta query.
+    let resumed = previous
+        .filter(|_| sp_config.delta)
+        .map(serde_json::from_str::<DeltaState>);
+    let mut state = match resumed {
+        Some(Ok(state)) if state.drive_id == drive_id => state,
+        Some(Err(e)) => {
+            tracing::warn!("sharepoint:{}: ignoring unreadable checkpoint: {}", name, e);
+            DeltaState::new(&drive_id)
+        }
+        _ => DeltaState::new(&drive_id),
+    };
+
+    let (changes, delta_link) = fetch_delta(&graph, &drive_id, state.delta_link.as_deref()).await?;
+    let files = state.apply(&changes);
+
+    let folder = sp_config
+        .folder
+        .as_deref()
+        .map(|f| f.trim_matches('/'))
+        .filter(|f| !f.is_empty());
+
+    let source = format!("sharepoint:{}", name);
+    let mut items = Vec::new();
+    for file in files {
+        let Some(path) = state.path_of(&file) else {
+            tracing::warn!(
+                "skipping SharePoint item {} ({}): parent folder unknown",
+                file.id,
+                file.name
+            );
+            continue;
+        };
+        let rel_path = match folder {
+            Some(f) => match strip_folder(&path, f) {
+                Some(rel) => rel,
+                None => continue,
+            },
+            None => path.clone(),
+        };
+        if exclude_set.is_match(&rel_path) || !include_set.is_match(&rel_path) {
+            continue;
+        }
+
+        let (content_type, binary) = content_type_for(&file.name);
+        if binary && file.size > sp_config.max_extract_bytes {
+            tracing::warn!(
+                "skipping {} (size {} > max_extract_bytes {})",
+                path,
+                file.size,
+                sp_config.max_extract_bytes
+            );
+            continue;
+        }
+
+        let bytes = match graph
+            .get_bytes(&format!("/drives/{}/items/{}/content", drive_id, file.id))
+            .await
+        {
+            Ok(b) => b,
+            Err(e) => {
+                tracing::warn!("failed to download {}: {:#}", path, e);
+                continue;
+            }
+        };
+        let (body, raw_bytes) = if binary {
+            (String::new(), Some(bytes))
+        } else {
+            (String::from_utf8_lossy(&bytes).into_owned(), None)
+        };
+
+        let metadata = serde_json::json!({
+            "drive_id": drive_id,
+            "item_id": file.id,
+            "etag": file.etag,
+            "size": file.size,
+            "path": path,
+        });
+
+        items.push(SourceItem {
+            source: source.clone(),
+            source_id: path,
+            source_url: file.web_url.clone(),
+            title: Some(file.name.clone()),
+            author: file.author.clone(),
+            created_at: file.created_at,
+            updated_at: file.updated_at,
+            content_type: content_type.to_string(),
+            body,
+            metadata_json: metadata.to_string(),
+            raw_json: None,
+            raw_bytes,
+            parent_source_id: None,
+        });
+    }
+
+    let state = if sp_config.delta {
+        state.delta_link = Some(delta_link);
+        Some(serde_json::to_string(&state)?)
+    } else {
+        None
+    };
+
+    items.sort_by(|a, b| a.source_id.cmp(&b.source_id));
+    Ok(SharePointScan { items, state })
+}
+
+// ============ Graph access ============
+
+/// Request an app-only Graph token with the client-credentials grant.
+async fn acquire_token(
+    client: &reqwest::Client,
+    sp_config: &SharePointConnectorConfig,
+) -> Result<String> {
+    let secret =
+        secrets::resolve_or_env(sp_config.client_secret.as_deref(), "AZURE_CLIENT_SECRET")?
+            .context("client_secret not configured and AZURE_CLIENT_SECRET not set")?;
+    let url = format!(
+        "{}/{}/oauth2/v2.0/token",
+        sp_config.authority_url.trim_end_matches('/'),
+        sp_config.tenant_id
+    );
+    let resp = client
+        .post(&url)
+        .form(&[
+            ("grant_type", "client_credentials"),
+            ("client_id", sp_config.client_id.as_str()),
+            ("client_secret", secret.as_str()),
+            ("scope", "https://graph.microsoft.com/.default"),
+        ])
+        .send()
+        .await
+        .with_context(|| format!("Failed to reach {}", url))?;
+
+    let status = resp.status();
+    let body: Value = resp.json().await.unwrap_or(Value::Null);
+    if !status.is_success() {
+        let detail = body["error_description"]
+            .as_str()
+            .or_else(|| body["error"].as_str())
+            .unwrap_or("no details");
+        bail!("SharePoint token request failed ({}): {}", status, detail);
+    }
+    body["access_token"]
+        .as_str()
+        .map(str::to_string)
+        .context("Token response missing access_token")
+}
+
+/// Authenticated Graph client with throttling retries.
+struct GraphClient {
+    client: reqwest::Client,
+    base: String,
+    token: String,
+}
+
+impl GraphClient {
+    /// GET a Graph path (relative to the base URL) or an absolute `@odata` link.
+    async fn get(&self, path_or_url: &str) -> Result<Reply> {
+        let url = if path_or_url.starts_with("http") {
+            path_or_url.to_string()
+        } else {
+            format!("{}{}", self.base, path_or_url)
+        };
+
+        let reply = http_retry::send(self.client.get(&url).bearer_auth(&self.token))
+            .await
+            .with_context(|| format!("Graph request failed: {}", url))?;
+        if !reply.status.is_success() {
+            bail!(
+                "Graph API error {} for {}: {}",
+                reply.status,
+                url,
+                graph_error(&reply.text())
+            );
+        }
+        Ok(reply)
+    }
+
+    async fn get_json(&self, path_or_url: &str) -> Result<Value> {
+        self.get(path_or_url).await?.json()
+    }
+
+    async fn get_bytes(&self, path: &str) -> Result<Vec<u8>> {
+        Ok(self.get(path).await?.body)
+    }
+}
+
+/// Extract `error.message` from a Graph error body, or return it as-is.
+fn graph_error(body: &str) -> String {
+    serde_json::from_str::<Value>(body)
+        .ok()
+        .and_then(|v| v["error"]["message"].as_str().map(str::to_string))
+        .unwrap_or_else(|| body.to_string())
+}
+
+/// Resolve the configured drive to a Graph drive ID.
+async fn resolve_drive(
+    graph: &GraphClient,
+    sp_config: &SharePointConnectorConfig,
+) -> Result<String> {
+    if let Some(ref id) = sp_config.drive_id {
+        return Ok(id.clone());
+    }
+    if let Some(ref user) = sp_config.user {
+        let drive = graph.get_json(&format!("/users/{}/drive", user)).await?;
+        return drive["id"]
+            .as_str()
+            .map(str::to_string)
+            .with_context(|| format!("No OneDrive found for user '{}'", user));
+    }
+    let Some(ref site) = sp_config.site else {
+        bail!("SharePoint connector needs one of site, drive_id, or user");
+    };
+
+    let site_json = graph.get_json(&format!("/sites/{}", site)).await?;
+    let site_id = site_json["id"]
+        .as_str()
+        .with_context(|| format!("SharePoint site '{}' not found", site))?;
+
+    match sp_config.drive {
+        None => {
+            let drive = graph.get_json(&format!("/sites/{}/drive", site_id)).await?;
+            drive["id"]
+                .as_str()
+                .map(str::to_string)
+                .context("Site has no default document library")
+        }
+        Some(ref wanted) => {
+            let drives = graph
+                .get_json(&format!("/sites/{}/drives", site_id))
+                .await?;
+            let list = drives["value"].as_array().cloned().unwrap_or_default();
+            list.iter()
+                .find(|d| d["name"].as_str() == Some(wanted.as_str()))
+                .and_then(|d| d["id"].as_str())
+                .map(str::to_string)
+                .with_context(|| {
+                    let names: Vec<&str> = list.iter().filter_map(|d| d["name"].as_str()).collect();
+                    format!(
+                        "No document library '{}' in site '{}'. Available: {}",
+                        wanted,
+                        site,
+                        names.join(", ")
+                    )
+                })
+        }
+    }
+}
+
+/// Follow a delta query to the end, returning all changed items and the
+/// delta link for the next scan.
+async fn fetch_delta(
+    graph: &GraphClient,
+    drive_id: &str,
+    delta_link: Option<&str>,
+) -> Result<(Vec<Value>, String)> {
+    let mut next = delta_link
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("/drives/{}/root/delta", drive_id));
+    let mut items = Vec::new();
+
+    loop {
+        let page = graph.get_json(&next).await?;
+        if let Some(values) = page["value"].as_array() {
+            items.extend(values.iter().cloned());
+        }
+        if let Some(link) = page["@odata.nextLink"].as_str() {
+            next = link.to_string();
+        } else if let Some(link) = page["@odata.deltaLink"].as_str() {
+            return Ok((items, link.to_string()));
+        } else {
+            bail!("Delta response has neither @odata.nextLink nor @odata.deltaLink");
+        }
+    }
+}
+
+// ============ Delta state ============
+
+/// A folder in the drive tree, used to rebuild paths from delta responses.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+struct FolderEntry {
+    name: String,
+    parent_id: Option<String>,
+}
+
+/// Delta link and folder tree, kept in the sync checkpoint between scans.
+#[derive(Debug, Serialize, Deserialize)]
+struct DeltaState {
+    drive_id: String,
+    delta_link: Option<String>,
+    root_id: Option<String>,
+    folders: BTreeMap<String, FolderEntry>,
+}
+
+/// A changed file from a delta response.
+#[derive(Debug, Clone)]
+struct DriveFile {
+    id: String,
+    name: String,
+    parent_id: Option<String>,
+    size: u64,
+    etag: Option<String>,
+    web_url: Option<String>,
+    author: Option<String>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl DeltaState {
+    fn new(drive_id: &str) -> Self {
+        Self {
+            drive_id: drive_id.to_string(),
+            delta_link: None,
+            root_id: None,
+            folders: BTreeMap::new(),
+        }
+    }
+
+    /// Fold delta items into the folder tree and return the live files.
+    fn apply(&mut self, changes: &[Value]) -> Vec<DriveFile> {
+        let mut files = Vec::new();
+        for item in changes {
+            let Some(id) = item["id"].as_str() else {
+                continue;
+            };
+            let deleted = !item["deleted"].is_null();
+            if !item["root"].is_null() {
+                self.root_id = Some(id.to_string());
+            } else if !item["folder"].is_null() || (deleted && self.folders.contains_key(id)) {
+                if deleted {
+                    self.folders.remove(id);
+                } else {
+                    self.folders.insert(
+                        id.to_string(),
+                        FolderEntry {
+                            name: item["name"].as_str().unwrap_or_default().to_string(),
+                            parent_id: item["parentReference"]["id"].as_str().map(str::to_string),
+                        },
+                    );
+                }
+            } else if !deleted && !item["file"].is_null() {
+                files.push(DriveFile {
+                    id: id.to_string(),
+                    name: item["name"].as_str().unwrap_or_default().to_string(),
+                    parent_id: item["parentReference"]["id"].as_str().map(str::to_string),
+                    size: item["size"].as_u64().unwrap_or(0),
+                    etag: item["eTag"].as_str().map(str::to_string),
+                    web_url: item["webUrl"].as_str().map(str::to_string),
+                    author: item["lastModifiedBy"]["user"]["displayName"]
+                        .as_str()
+                        .or_else(|| item["createdBy"]["user"]["displayName"].as_str())
+                        .map(str::to_string),
+                    created_at: parse_time(&item["createdDateTime"]),
+                    updated_at: parse_time(&item["lastModifiedDateTime"]),
+                });
+            }
+        }
+        files
+    }
+
+    /// Path of a file relative to the drive root, or `None` if a parent
+    /// folder is not in the tree.
+    fn path_of(&self, file: &DriveFile) -> Option<String> {
+        let mut parts = vec![file.name.clone()];
+        let mut current = file.parent_id.clone();
+        // Bounded walk guards against cycles in a corrupted checkpoint.
+        for _ in 0..=self.folders.len() {
+            match current {
+                None => return Some(join_path(parts)),
+                Some(ref id) if Some(id) == self.root_id.as_ref() => return Some(join_path(parts)),
+                Some(ref id) => {
+                    let folder = self.folders.get(id)?;
+                    parts.push(folder.name.clone());
+                    current = folder.parent_id.clone();
+                }
+            }
+        }
+        None
+    }
+}
+
+fn join_path(mut parts: Vec<String>) -> String {
+    parts.reverse();
+    parts.join("/")
+}
+
+fn parse_time(value: &Value) -> DateTime<Utc> {
+    value
+        .as_str()
+        .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+        .map(|t| t.with_timezone(&Utc))
+        .unwrap_or_else(Utc::now)
+}
+
+// ============ Filtering ============
+
+/// Strip a folder prefix (case-insensitive, as in SharePoint) from a path.
+fn strip_folder(path: &str, folder: &str) -> Option<String> {
+    let prefix_len = folder.len();
+    if path.len() > prefix_len
+        && path.is_char_boundary(prefix_len)
+        && path[..prefix_len].eq_ignore_ascii_case(folder)
+        && path[prefix_len..].starts_with('/')
+    {
+        Some(path[prefix_len + 1..].to_string())
+    } else {
+        None
+    }
+}
+
+/// Content type for a file name, and whether it needs binary extraction.
+fn content_type_for(name: &str) -> (&'static str, bool) {
+    let ext = name
+        .rsplit_once('.')
+        .map(|(_, e)| e.to_lowercase())
+        .unwrap_or_default();
+    match ext.as_str() {
+        "pdf" => (extract::MIME_PDF, true),
+        "docx" => (extract::MIME_DOCX, true),
+        "pptx" => (extract::MIME_PPTX, true),
+        "xlsx" => (extract::MIME_XLSX, true),
+        "md" => ("text/markdown", false),
+        "json" => ("application/json", false),
+        "yaml" | "yml" => ("text/yaml", false),
+        "html" | "htm" => ("text/html", false),
+        _ => ("text/plain", false),
+    }
+}
+
+fn build_globset(patterns: &[String]) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(Glob::new(pattern)?);
+    }
+    Ok(builder.build()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn resolves_paths_from_delta_folder_tree() {
+        let mut state = DeltaState::new("drive-1");
+        let files = state.apply(&[
+            json!({"id": "root", "name": "root", "root": {}, "folder": {}}),
+            json!({"id": "f1", "name": "Runbooks", "folder": {}, "parentReference": {"id": "root"}}),
+            json!({"id": "f2", "name": "Payments", "folder": {}, "parentReference": {"id": "f1"}}),
+            json!({"id": "a", "name": "deploy.md", "file": {}, "size": 10,
+                   "parentReference": {"id": "f2"},
+                   "lastModifiedDateTime": "2026-01-02T03:04:05Z",
+                   "lastModifiedBy": {"user": {"displayName": "Dana"}}}),
+            json!({"id": "b", "name": "old.md", "file": {}, "deleted": {"state": "deleted"},
+                   "parentReference": {"id": "f1"}}),
+        ]);
+        assert_eq!(files.len(), 1);
+        assert_eq!(
+            state.path_of(&files[0]).as_deref(),
+            Some("Runbooks/Payments/deploy.md")
+        );
+        assert_eq!(files[0].author.as_deref(), Some("Dana"));
+        assert_eq!(
+            files[0].updated_at.to_rfc3339(),
+            "2026-01-02T03:04:05+00:00"
+        );
+
+        // An incremental delta only carries the changed file; the stored
+        // tree still resolves its path.
+        let files = state.apply(&[json!({
+            "id": "c", "name": "rollback.docx", "file": {}, "parentReference": {"id": "f2"}
+        })]);
+        assert_eq!(
+            state.path_of(&files[0]).as_deref(),
+            Some("Runbooks/Payments/rollback.docx")
+        );
+
+        state.apply(&[json!({"id": "f2", "deleted": {}})]);
+        assert_eq!(state.path_of(&files[0]), None);
+    }
+
+    #[test]
+    fn folder_prefix_is_case_insensitive() {
+        assert_eq!(
+            strip_folder("Runbooks/deploy.md", "runbooks").as_deref(),
+            Some("deploy.md")
+        );
+        assert_eq!(strip_folder("RunbooksOld/deploy.md", "Runbooks"), None);
+        assert_eq!(strip_folder("Runbooks", "Runbooks"), None);
+    }
+
+    #[test]
+    fn office_files_are_extracted() {
+        assert_eq!(content_type_for("Plan.DOCX"), (extract::MIME_DOCX, true));
+        assert_eq!(content_type_for("notes.md"), ("text/markdown", false));
+        assert_eq!(content_type_for("README"), ("text/plain", false));
+    }
+}
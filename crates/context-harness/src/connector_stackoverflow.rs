@@ -0,0 +1,742 @@
+//! Stack Overflow connector.
+//!
+//! Reads questions and their answers through the [Stack Exchange API 2.3],
+//! either from a public site (questions carrying one of the configured
+//! `tags`) or from a Stack Overflow for Teams team, authenticating with a
+//! personal access token.
+//!
+//! # Configuration
+//!
+//! ```toml
+//! [connectors.stackoverflow.eng]
+//! team = "acme"                             # stackoverflowteams.com/c/acme
+//! access_token = "secret://so-teams"        # default: STACKOVERFLOW_ACCESS_TOKEN
+//! tags = ["deploy", "postgres"]             # default (Teams only): every question
+//! answers = 3                               # top answers besides the accepted one
+//! accepted_only = true
+//!
+//! [connectors.stackoverflow.rust]
+//! site = "stackoverflow"                    # public site; tags are required
+//! tags = ["tokio"]
+//! min_score = 5
+//! ```
+//!
+//! # Documents
+//!
+//! Each question becomes one document (`questions/<id>`) holding the
+//! question, its accepted answer, and the `answers` highest-scoring other
+//! answers, HTML converted to Markdown with code blocks kept:
+//!
+//! ````text
+//! # How do I cancel a spawned task?
+//!
+//! Score: 42 · Answers: 3 · Accepted · Tags: rust, tokio
+//!
+//! I spawn a task with `tokio::spawn` and need to stop it.
+//!
+//! ## Accepted answer — Jane Doe (score 30, 2026-01-02)
+//!
+//! Keep the `JoinHandle` and abort it:
+//!
+//! ```
+//! handle.abort();
+//! ```
+//! ````
+//!
+//! The metadata records the question's `score`, `answer_count`,
+//! `view_count`, and `tags`, and the accepted answer's score. With
+//! `accepted_only = true` (the default), questions without an accepted
+//! answer are skipped; `min_score` skips low-scoring questions.
+//!
+//! # Incremental Sync
+//!
+//! Questions are listed by activity date. The latest `last_activity_date`
+//! seen is saved with the sync checkpoint (see
+//! [`Connector::checkpoint_state`]), and the next scan only asks for
+//! questions active since, so new answers, edits, and votes bring a
+//! question back. Changing `team`, `site`, or `tags` starts over.
+//! `ctx sync --full` lists every question again; set `incremental = false`
+//! to do so on every scan. Deleted questions, and questions that lose their
+//! accepted answer, are not removed from the index.
+//!
+//! Throttled requests (`429`, `503`, and the API's `throttle_violation`)
+//! are retried up to three times (see [`crate::http_retry`]), and the
+//! API's `backoff` is honoured between requests. Public sites allow 300 requests a day without an app
+//! `key` and 10,000 with one.
+//!
+//! [Stack Exchange API 2.3]: https://api.stackexchange.com/docs
+
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::io::Read;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::config::StackOverflowConnectorConfig;
+use crate::connector_zendesk::decode_entities;
+use crate::http_retry;
+use crate::models::SourceItem;
+use crate::secrets;
+use crate::traits::{Connector, ConnectorHealth};
+
+/// Safety bound on pages followed per listing.
+const MAX_PAGES: usize = 10_000;
+/// Items per page, and question IDs per answers request (the API maximum).
+const PAGE_SIZE: usize = 100;
+/// API error ID of a throttle violation.
+const THROTTLE_VIOLATION: i64 = 502;
+
+// ═══════════════════════════════════════════════════════════════════════
+// Connector trait implementation
+// ═══════════════════════════════════════════════════════════════════════
+
+/// A Stack Overflow connector instance that implements the [`Connector`] trait.
+pub struct StackOverflowConnector {
+    /// Instance name (e.g. `"eng"`).
+    name: String,
+    /// Configuration for this connector instance.
+    config: StackOverflowConnectorConfig,
+    /// Activity date from the last checkpoint, replaced by each scan.
+    state: Mutex<Option<String>>,
+}
+
+impl StackOverflowConnector {
+    /// Create a new Stack Overflow connector instance.
+    pub fn new(name: String, config: StackOverflowConnectorConfig) -> Self {
+        Self {
+            name,
+            config,
+            state: Mutex::new(None),
+        }
+    }
+}
+
+#[async_trait]
+impl Connector for StackOverflowConnector {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "Read Stack Overflow (or Stack Overflow for Teams) questions with their top answers"
+    }
+
+    fn connector_type(&self) -> &str {
+        "stackoverflow"
+    }
+
+    async fn scan(&self) -> Result<Vec<SourceItem>> {
+        let previous = self.state.lock().unwrap().clone();
+        let scan = scan_stackoverflow(&self.name, &self.config, previous.as_deref()).await?;
+        *self.state.lock().unwrap() = scan.state;
+        Ok(scan.items)
+    }
+
+    fn resume_from(&self, state: Option<String>) {
+        *self.state.lock().unwrap() = state;
+    }
+
+    fn checkpoint_state(&self) -> Option<String> {
+        self.state.lock().unwrap().clone()
+    }
+
+    async fn health(&self) -> ConnectorHealth {
+        ConnectorHealth::probe(async {
+            let api = StackClient::new(&self.config, ConnectorHealth::TIMEOUT)?;
+            let page = api
+                .get_json("/questions", &[("pagesize", "1".to_string())])
+                .await?;
+            Ok(match page["quota_remaining"].as_i64() {
+                Some(quota) => format!("{} ({} requests left today)", api.scope, quota),
+                None => api.scope.clone(),
+            })
+        })
+        .await
+    }
+}
+
+/// Items and activity date to checkpoint from [`scan_stackoverflow`].
+#[derive(Debug)]
+pub struct StackOverflowScan {
+    /// Questions with their answers, sorted by `source_id`.
+    pub items: Vec<SourceItem>,
+    /// Activity date as JSON, or `None` with `incremental = false`.
+    pub state: Option<String>,
+}
+
+/// Scan a Stack Exchange site or team and produce [`SourceItem`]s.
+///
+/// # Workflow
+///
+/// 1. List questions active since the activity date in `previous`, once
+///    per tag (or once for a team without `tags`).
+/// 2. Keep those passing `accepted_only` and `min_score`.
+/// 3. Fetch their answers, 100 questions per request.
+/// 4. Return the latest activity date seen.
+///
+/// # Errors
+///
+/// Returns an error if a team's access token is missing, authentication
+/// fails, or a request fails after its retries.
+pub async fn scan_stackoverflow(
+    name: &str,
+    so_config: &StackOverflowConnectorConfig,
+    previous: Option<&str>,
+) -> Result<StackOverflowScan> {
+    let api = StackClient::new(so_config, Duration::from_secs(120))?;
+    let source = format!("stackoverflow:{}", name);
+    let scope = format!("{} [{}]", api.scope, so_config.tags.join(", "));
+
+    // A date for another site, team, or tag list (the config changed)
+    // starts over.
+    let resumed = previous
+        .filter(|_| so_config.incremental)
+        .map(serde_json::from_str::<ActivityState>);
+    let mut state = match resumed {
+        Some(Ok(state)) if state.scope == scope => state,
+        Some(Err(e)) => {
+            tracing::warn!(
+                "stackoverflow:{}: ignoring unreadable checkpoint: {}",
+                name,
+                e
+            );
+            ActivityState::new(&scope)
+        }
+        _ => ActivityState::new(&scope),
+    };
+
+    let questions = list_questions(&api, &so_config.tags, state.from_date).await?;
+    let latest = questions
+        .iter()
+        .filter_map(|q| q["last_activity_date"].as_i64())
+        .max();
+    let selected: Vec<&Value> = questions
+        .iter()
+        .filter(|q| question_selected(q, so_config.accepted_only, so_config.min_score))
+        .collect();
+
+    let ids: Vec<u64> = selected
+        .iter()
+        .filter_map(|q| q["question_id"].as_u64())
+        .collect();
+    let answers = fetch_answers(&api, &ids).await?;
+    let mut items: Vec<SourceItem> = selected
+        .iter()
+        .map(|q| {
+            let id = q["question_id"].as_u64().unwrap_or_default();
+            let thread = answers.get(&id).map(Vec::as_slice).unwrap_or_default();
+            question_item(&source, q, thread, so_config.answers)
+        })
+        .collect();
+
+    state.from_date = latest.or(state.from_date);
+    let state = if so_config.incremental {
+        Some(serde_json::to_string(&state)?)
+    } else {
+        None
+    };
+
+    items.sort_by(|a, b| a.source_id.cmp(&b.source_id));
+    Ok(StackOverflowScan { items, state })
+}
+
+// ============ API access ============
+
+/// Stack Exchange API client for one site or team, with throttling retries.
+struct StackClient {
+    client: reqwest::Client,
+    base: String,
+    /// `site` or `team` parameter, and `key` when set.
+    params: Vec<(&'static str, String)>,
+    /// Teams personal access token.
+    token: Option<String>,
+    /// What this client reads, e.g. `team acme` or `site stackoverflow`.
+    scope: String,
+}
+
+impl StackClient {
+    fn new(so_config: &StackOverflowConnectorConfig, timeout: Duration) -> Result<Self> {
+        let (mut params, token, scope) = match &so_config.team {
+            Some(team) => {
+                let token = secrets::resolve_or_env(
+                    so_config.access_token.as_deref(),
+                    "STACKOVERFLOW_ACCESS_TOKEN",
+                )?
+                .context("access_token not configured and STACKOVERFLOW_ACCESS_TOKEN not set")?;
+                (
+                    vec![("team", team.clone())],
+                    Some(token),
+                    format!("team {}", team),
+                )
+            }
+            None => (
+                vec![("site", so_config.site.clone())],
+                None,
+                format!("site {}", so_config.site),
+            ),
+        };
+        if let Some(key) = &so_config.key {
+            params.push(("key", secrets::resolve(key)?));
+        }
+        Ok(Self {
+            client: reqwest::Client::builder().timeout(timeout).build()?,
+            base: so_config.api_base(),
+            params,
+            token,
+            scope,
+        })
+    }
+
+    /// GET an API method with `query` added to the site or team parameters.
+    async fn get_json(&self, method: &str, query: &[(&str, String)]) -> Result<Value> {
+        let url = reqwest::Url::parse_with_params(
+            &format!("{}{}", self.base, method),
+            self.params
+                .iter()
+                .map(|(k, v)| (*k, v.as_str()))
+                .chain(query.iter().map(|(k, v)| (*k, v.as_str()))),
+        )?;
+
+        let mut request = self.client.get(url);
+        if let Some(token) = &self.token {
+            request = request.header("X-API-Access-Token", token);
+        }
+        let reply = http_retry::send_with(request, is_throttle_violation)
+            .await
+            .with_context(|| format!("Stack Exchange request failed: {}", method))?;
+        let body = decode_body(&reply.body)?;
+        let json: Option<Value> = serde_json::from_slice(&body).ok();
+
+        if !reply.status.is_success() {
+            let message = json
+                .as_ref()
+                .and_then(|j| j["error_message"].as_str())
+                .map(str::to_string)
+                .unwrap_or_else(|| String::from_utf8_lossy(&body).into_owned());
+            bail!(
+                "Stack Exchange API error {} for {}: {}",
+                reply.status,
+                method,
+                message
+            );
+        }
+        let page = json.context("Stack Exchange API returned invalid JSON")?;
+        if let Some(wait) = page["backoff"].as_u64() {
+            let wait = wait.min(http_retry::MAX_RETRY_AFTER_SECS);
+            tokio::time::sleep(Duration::from_secs(wait)).await;
+        }
+        Ok(page)
+    }
+}
+
+/// Whether an error body reports a `throttle_violation`, which the API
+/// sends with status `400` rather than `429`.
+fn is_throttle_violation(body: &[u8]) -> bool {
+    decode_body(body)
+        .ok()
+        .and_then(|body| serde_json::from_slice::<Value>(&body).ok())
+        .and_then(|json| json["error_id"].as_i64())
+        == Some(THROTTLE_VIOLATION)
+}
+
+/// The API compresses every response; decompress gzip bodies, which
+/// arrive without being decoded by the HTTP client.
+fn decode_body(bytes: &[u8]) -> Result<Vec<u8>> {
+    if !bytes.starts_with(&[0x1f, 0x8b]) {
+        return Ok(bytes.to_vec());
+    }
+    let mut out = Vec::new();
+    GzDecoder::new(bytes)
+        .read_to_end(&mut out)
+        .context("Failed to decompress Stack Exchange response")?;
+    Ok(out)
+}
+
+/// Follow the pages of a listing, collecting its `items`.
+async fn fetch_pages(
+    api: &StackClient,
+    method: &str,
+    query: &[(&str, String)],
+) -> Result<Vec<Value>> {
+    let mut items = Vec::new();
+    for page in 1..=MAX_PAGES {
+        let mut query = query.to_vec();
+        query.push(("page", page.to_string()));
+        query.push(("pagesize", PAGE_SIZE.to_string()));
+        let body = api.get_json(method, &query).await?;
+        items.extend(body["items"].as_array().into_iter().flatten().cloned());
+        if !body["has_more"].as_bool().unwrap_or(false) {
+            return Ok(items);
+        }
+    }
+    bail!("{} did not end after {} pages", method, MAX_PAGES)
+}
+
+/// Questions active since `from_date` (default: all), with their bodies,
+/// carrying any of `tags` (default: all questions).
+async fn list_questions(
+    api: &StackClient,
+    tags: &[String],
+    from_date: Option<i64>,
+) -> Result<Vec<Value>> {
+    let mut query = vec![
+        ("order", "desc".to_string()),
+        ("sort", "activity".to_string()),
+        ("filter", "withbody".to_string()),
+    ];
+    if let Some(date) = from_date {
+        query.push(("fromdate", date.to_string()));
+    }
+
+    let mut questions: BTreeMap<u64, Value> = BTreeMap::new();
+    let tag_queries: Vec<Option<&String>> = if tags.is_empty() {
+        vec![None]
+    } else {
+        tags.iter().map(Some).collect()
+    };
+    for tag in tag_queries {
+        let mut query = query.clone();
+        if let Some(tag) = tag {
+            query.push(("tagged", tag.clone()));
+        }
+        for question in fetch_pages(api, "/questions", &query).await? {
+            if let Some(id) = question["question_id"].as_u64() {
+                questions.insert(id, question);
+            }
+        }
+    }
+    Ok(questions.into_values().collect())
+}
+
+/// Answers of each of `question_ids`, with their bodies, by question ID.
+async fn fetch_answers(
+    api: &StackClient,
+    question_ids: &[u64],
+) -> Result<HashMap<u64, Vec<Value>>> {
+    let mut answers: HashMap<u64, Vec<Value>> = HashMap::new();
+    for batch in question_ids.chunks(PAGE_SIZE) {
+        let ids: Vec<String> = batch.iter().map(u64::to_string).collect();
+        let method = format!("/questions/{}/answers", ids.join(";"));
+        let query = [
+            ("order", "desc".to_string()),
+            ("sort", "votes".to_string()),
+            ("filter", "withbody".to_string()),
+        ];
+        for answer in fetch_pages(api, &method, &query).await? {
+            if let Some(id) = answer["question_id"].as_u64() {
+                answers.entry(id).or_default().push(answer);
+            }
+        }
+    }
+    Ok(answers)
+}
+
+// ============ Documents ============
+
+/// Whether a question passes `accepted_only` and `min_score`.
+fn question_selected(question: &Value, accepted_only: bool, min_score: Option<i64>) -> bool {
+    if accepted_only && question["accepted_answer_id"].as_u64().is_none() {
+        return false;
+    }
+    min_score.is_none_or(|min| question["score"].as_i64().unwrap_or(0) >= min)
+}
+
+/// Build the document of a question with its accepted answer and up to
+/// `top_answers` other answers, highest score first.
+fn question_item(
+    source: &str,
+    question: &Value,
+    answers: &[Value],
+    top_answers: usize,
+) -> SourceItem {
+    let id = question["question_id"].as_u64().unwrap_or_default();
+    let title = question["title"]
+        .as_str()
+        .map(decode_entities)
+        .filter(|t| !t.trim().is_empty())
+        .unwrap_or_else(|| format!("Question {}", id));
+    let tags: Vec<&str> = question["tags"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|t| t.as_str())
+        .collect();
+    let accepted_id = question["accepted_answer_id"].as_u64();
+
+    let mut others: Vec<&Value> = answers
+        .iter()
+        .filter(|a| accepted_id.is_none() || a["answer_id"].as_u64() != accepted_id)
+        .collect();
+    others.sort_by_key(|a| std::cmp::Reverse(a["score"].as_i64().unwrap_or(0)));
+    let accepted = answers
+        .iter()
+        .find(|a| accepted_id.is_some() && a["answer_id"].as_u64() == accepted_id);
+    let included: Vec<(&Value, bool)> = accepted
+        .map(|a| (a, true))
+        .into_iter()
+        .chain(others.into_iter().take(top_answers).map(|a| (a, false)))
+        .collect();
+
+    let mut facts = vec![
+        format!("Score: {}", question["score"].as_i64().unwrap_or(0)),
+        format!(
+            "Answers: {}",
+            question["answer_count"].as_u64().unwrap_or(0)
+        ),
+    ];
+    if accepted_id.is_some() {
+        facts.push("Accepted".to_string());
+    }
+    if !tags.is_empty() {
+        facts.push(format!("Tags: {}", tags.join(", ")));
+    }
+
+    let mut body = format!("# {}\n\n{}\n", title, facts.join(" · "));
+    let text = html_to_markdown(question["body"].as_str().unwrap_or_default());
+    if !text.is_empty() {
+        body.push_str(&format!("\n{}\n", text));
+    }
+    for (answer, is_accepted) in &included {
+        let heading = if *is_accepted {
+            "Accepted answer"
+        } else {
+            "Answer"
+        };
+        body.push_str(&format!(
+            "\n## {} — {} (score {}",
+            heading,
+            display_name(answer),
+            answer["score"].as_i64().unwrap_or(0)
+        ));
+        if let Some(at) = parse_time(&answer["creation_date"]) {
+            body.push_str(&format!(", {}", at.format("%Y-%m-%d")));
+        }
+        body.push_str(&format!(
+            ")\n\n{}\n",
+            html_to_markdown(answer["body"].as_str().unwrap_or_default())
+        ));
+    }
+
+    let metadata = serde_json::json!({
+        "question_id": id,
+        "score": question["score"],
+        "answer_count": question["answer_count"],
+        "view_count": question["view_count"],
+        "accepted_answer_id": accepted_id,
+        "accepted_answer_score": accepted.map(|a| a["score"].clone()),
+        "tags": tags,
+        "answers": included.len(),
+    });
+
+    let created_at = parse_time(&question["creation_date"]).unwrap_or_else(Utc::now);
+    SourceItem {
+        source: source.to_string(),
+        source_id: format!("questions/{}", id),
+        source_url: question["link"].as_str().map(str::to_string),
+        title: Some(title),
+        author: question["owner"]["display_name"]
+            .as_str()
+            .map(decode_entities),
+        created_at,
+        updated_at: parse_time(&question["last_activity_date"]).unwrap_or(created_at),
+        content_type: "text/markdown".to_string(),
+        body,
+        metadata_json: metadata.to_string(),
+        raw_json: None,
+        raw_bytes: None,
+        parent_source_id: None,
+    }
+}
+
+fn display_name(post: &Value) -> String {
+    post["owner"]["display_name"]
+        .as_str()
+        .map(decode_entities)
+        .unwrap_or_else(|| "Unknown".to_string())
+}
+
+/// Markdown of a post's HTML body: `<pre>` blocks become fenced code
+/// blocks kept verbatim, inline `<code>` backticks, list items bullets,
+/// and wrapped prose lines are joined.
+fn html_to_markdown(html: &str) -> String {
+    let mut blocks: Vec<String> = Vec::new();
+    let mut prose = String::new();
+    let mut code: Option<String> = None;
+    let mut rest = html;
+
+    while let Some(start) = rest.find('<') {
+        let text = decode_entities(&rest[..start]);
+        match &mut code {
+            Some(code) => code.push_str(&text),
+            None => prose.push_str(&text),
+        }
+        let Some(end) = rest[start..].find('>') else {
+            rest = "";
+            break;
+        };
+        let tag = rest[start + 1..start + end].trim().to_ascii_lowercase();
+        rest = &rest[start + end + 1..];
+
+        let closing = tag.starts_with('/');
+        let name: String = tag
+            .trim_start_matches('/')
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect();
+        match name.as_str() {
+            "pre" if !closing => {
+                blocks.extend(paragraphs(&std::mem::take(&mut prose)));
+                code = Some(String::new());
+            }
+            "pre" => {
+                if let Some(code) = code.take() {
+                    blocks.push(format!("```\n{}\n```", code.trim_end_matches('\n')));
+                }
+            }
+            _ if code.is_some() => {}
+            "code" => prose.push('`'),
+            "li" if !closing => prose.push_str("\n- "),
+            "br" => prose.push('\n'),
+            "p" | "div" | "ul" | "ol" | "blockquote" | "table" | "tr" | "hr" | "h1" | "h2"
+            | "h3" | "h4" | "h5" | "h6" => prose.push_str("\n\n"),
+            _ => {}
+        }
+    }
+    let text = decode_entities(rest);
+    match code {
+        Some(mut code) => {
+            code.push_str(&text);
+            blocks.push(format!("```\n{}\n```", code.trim_end_matches('\n')));
+        }
+        None => {
+            prose.push_str(&text);
+            blocks.extend(paragraphs(&prose));
+        }
+    }
+    blocks.join("\n\n")
+}
+
+/// Paragraphs of converted prose, each with its wrapped lines joined and
+/// list items on their own lines.
+fn paragraphs(prose: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    for block in prose.split("\n\n") {
+        let mut lines: Vec<String> = Vec::new();
+        for line in block.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            match lines.last_mut() {
+                Some(last) if !line.starts_with("- ") => {
+                    last.push(' ');
+                    last.push_str(line);
+                }
+                _ => lines.push(line.to_string()),
+            }
+        }
+        if !lines.is_empty() {
+            out.push(lines.join("\n"));
+        }
+    }
+    out
+}
+
+/// A Stack Exchange date (Unix seconds).
+fn parse_time(value: &Value) -> Option<DateTime<Utc>> {
+    value
+        .as_i64()
+        .and_then(|ts| DateTime::from_timestamp(ts, 0))
+}
+
+// ============ Activity state ============
+
+/// Activity date, kept in the sync checkpoint between scans.
+#[derive(Debug, Serialize, Deserialize)]
+struct ActivityState {
+    /// Site or team and tags the date belongs to; state for another scope
+    /// is ignored.
+    scope: String,
+    /// Latest `last_activity_date` seen, the next scan's `fromdate`.
+    from_date: Option<i64>,
+}
+
+impl ActivityState {
+    fn new(scope: &str) -> Self {
+        Self {
+            scope: scope.to_string(),
+            from_date: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn answer(id: u64, score: i64, name: &str, body: &str) -> Value {
+        json!({
+            "answer_id": id, "question_id": 7, "score": score, "body": body,
+            "owner": {"display_name": name}, "creation_date": 1767312000,
+        })
+    }
+
+    #[test]
+    fn question_combines_accepted_and_top_answers() {
+        let question = json!({
+            "question_id": 7, "title": "How do I cancel a &quot;spawned&quot; task?",
+            "score": 42, "answer_count": 4, "view_count": 900, "accepted_answer_id": 11,
+            "tags": ["rust", "tokio"], "link": "https://stackoverflow.com/q/7",
+            "owner": {"display_name": "Sam"},
+            "body": "<p>I spawn a task with <code>tokio::spawn</code>\nand need to stop it.</p>",
+            "creation_date": 1767225600, "last_activity_date": 1767398400,
+        });
+        let answers = [
+            answer(10, 50, "Bob", "<p>Use a channel.</p>"),
+            answer(
+                11,
+                30,
+                "Jane Doe",
+                "<p>Keep the handle:</p><pre><code>let h = spawn(f);\nh.abort();\n</code></pre>",
+            ),
+            answer(12, 2, "Kim", "<p>Low score.</p>"),
+            answer(13, 9, "Lee", "<p>Drop the runtime.</p>"),
+        ];
+
+        let item = question_item("stackoverflow:rust", &question, &answers, 2);
+        assert_eq!(item.source_id, "questions/7");
+        assert_eq!(
+            item.title.as_deref(),
+            Some("How do I cancel a \"spawned\" task?")
+        );
+        assert_eq!(item.author.as_deref(), Some("Sam"));
+        assert_eq!(item.updated_at.timestamp(), 1767398400);
+        assert!(item.body.starts_with(
+            "# How do I cancel a \"spawned\" task?\n\nScore: 42 · Answers: 4 · Accepted · Tags: rust, tokio\n\n\
+             I spawn a task with `tokio::spawn` and need to stop it.\n\n\
+             ## Accepted answer — Jane Doe (score 30, 2026-01-02)\n\n\
+             Keep the handle:\n\n```\nlet h = spawn(f);\nh.abort();\n```\n"
+        ), "{}", item.body);
+        let bob = item.body.find("## Answer — Bob (score 50").unwrap();
+        let lee = item.body.find("## Answer — Lee (score 9").unwrap();
+        assert!(bob < lee);
+        assert!(!item.body.contains("Low score."));
+
+        let metadata: Value = serde_json::from_str(&item.metadata_json).unwrap();
+        assert_eq!(metadata["score"], 42);
+        assert_eq!(metadata["accepted_answer_score"], 30);
+        assert_eq!(metadata["answers"], 3);
+    }
+
+    #[test]
+    fn questions_filter_by_accepted_answer_and_score() {
+        let accepted = json!({"question_id": 1, "score": 3, "accepted_answer_id": 9});
+        let open = json!({"question_id": 2, "score": 8});
+        assert!(question_selected(&accepted, true, None));
+        assert!(!question_selected(&open, true, None));
+        assert!(question_selected(&open, false, Some(5)));
+        assert!(!question_selected(&accepted, true, Some(5)));
+    }
+}
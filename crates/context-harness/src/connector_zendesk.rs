@@ -0,0 +1,852 @@
+//! Zendesk connector.
+//!
+//! Reads support tickets and help-center articles through the Zendesk
+//! REST API, authenticating as an agent with an API token
+//! (`<email>/token:<api_token>` basic auth). The agent needs read access to
+//! the tickets it should index.
+//!
+//! # Configuration
+//!
+//! ```toml
+//! [connectors.zendesk.support]
+//! subdomain = "acme"                        # acme.zendesk.com
+//! email = "bot@acme.com"
+//! api_token = "secret://zendesk-support"    # default: ZENDESK_API_TOKEN
+//! statuses = ["open", "pending", "solved"]  # default: all but deleted
+//! brands = ["Acme Cloud"]                   # default: all brands
+//! internal_notes = false
+//! ```
+//!
+//! # Documents
+//!
+//! Each ticket becomes one document (`tickets/<id>`) whose body threads its
+//! comments in order, the first being the ticket description:
+//!
+//! ```text
+//! # Refund for duplicate charge
+//!
+//! Status: solved · Priority: high · Type: incident · Tags: billing
+//!
+//! ## Dana Smith — 2026-02-01 09:14 UTC
+//!
+//! I was charged twice for the March invoice.
+//!
+//! ## Lee Park (agent) — 2026-02-01 10:02 UTC (internal note)
+//!
+//! Confirmed in Stripe; refunding the second charge.
+//! ```
+//!
+//! Internal notes are left out unless `internal_notes = true`. Each
+//! published help-center article becomes one document (`articles/<id>`),
+//! its HTML body converted to plain text. Drafts are skipped.
+//!
+//! # Incremental Sync
+//!
+//! Tickets come from the cursor-based [incremental ticket export], articles
+//! from the [incremental article export]. The ticket cursor and the article
+//! export's `end_time` are saved with the sync checkpoint (see
+//! [`Connector::checkpoint_state`]), so the next scan only receives tickets
+//! and articles changed since. When a ticket's comments cannot be fetched,
+//! the ticket is reported as a failed item and the ticket cursor is not
+//! advanced. `ctx sync --full` exports everything again; set
+//! `incremental = false` to do so on every scan.
+//!
+//! `statuses` and `brands` filter tickets after export; `brands` matches a
+//! brand's name, subdomain, or ID. Deleted tickets are not removed from the
+//! index.
+//!
+//! Rate-limited requests (`429`, `503`) are retried up to three times,
+//! honouring `Retry-After` (see [`crate::http_retry`]).
+//!
+//! [incremental ticket export]: https://developer.zendesk.com/api-reference/ticketing/ticket-management/incremental_exports/#incremental-ticket-export-cursor-based
+//! [incremental article export]: https://developer.zendesk.com/api-reference/help_center/help-center-api/incremental_export/
+
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::config::ZendeskConnectorConfig;
+use crate::http_retry;
+use crate::models::SourceItem;
+use crate::secrets;
+use crate::sync_failures::ItemFailure;
+use crate::traits::{Connector, ConnectorHealth};
+
+/// Safety bound on pages followed per export or comment thread.
+const MAX_PAGES: usize = 10_000;
+
+// ═══════════════════════════════════════════════════════════════════════
+// Connector trait implementation
+// ═══════════════════════════════════════════════════════════════════════
+
+/// A Zendesk connector instance that implements the [`Connector`] trait.
+pub struct ZendeskConnector {
+    /// Instance name (e.g. `"support"`).
+    name: String,
+    /// Configuration for this connector instance.
+    config: ZendeskConnectorConfig,
+    /// Export cursors from the last checkpoint, replaced by each scan.
+    state: Mutex<Option<String>>,
+    /// Tickets the most recent scan could not fetch comments for.
+    failed: Mutex<Vec<ItemFailure>>,
+}
+
+impl ZendeskConnector {
+    /// Create a new Zendesk connector instance.
+    pub fn new(name: String, config: ZendeskConnectorConfig) -> Self {
+        Self {
+            name,
+            config,
+            state: Mutex::new(None),
+            failed: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+#[async_trait]
+impl Connector for ZendeskConnector {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "Read Zendesk tickets with their comment threads and help-center articles"
+    }
+
+    fn connector_type(&self) -> &str {
+        "zendesk"
+    }
+
+    async fn scan(&self) -> Result<Vec<SourceItem>> {
+        let previous = self.state.lock().unwrap().clone();
+        let scan = scan_zendesk(&self.name, &self.config, previous.as_deref()).await?;
+        *self.state.lock().unwrap() = scan.state;
+        *self.failed.lock().unwrap() = scan.failed;
+        Ok(scan.items)
+    }
+
+    fn failed(&self) -> Vec<ItemFailure> {
+        self.failed.lock().unwrap().clone()
+    }
+
+    fn resume_from(&self, state: Option<String>) {
+        *self.state.lock().unwrap() = state;
+    }
+
+    fn checkpoint_state(&self) -> Option<String> {
+        self.state.lock().unwrap().clone()
+    }
+
+    async fn health(&self) -> ConnectorHealth {
+        ConnectorHealth::probe(async {
+            let api = ZendeskClient::new(&self.config, ConnectorHealth::TIMEOUT)?;
+            let me = api.get_json("/api/v2/users/me.json").await?;
+            match me["user"]["role"].as_str() {
+                Some("end-user") | Some("anonymous") | None => bail!(
+                    "signed in as '{}', which is not an agent (check email and api_token)",
+                    me["user"]["email"].as_str().unwrap_or("anonymous")
+                ),
+                Some(role) => Ok(format!(
+                    "{} ({})",
+                    me["user"]["email"].as_str().unwrap_or_default(),
+                    role
+                )),
+            }
+        })
+        .await
+    }
+}
+
+/// Items, export cursors to checkpoint, and failed tickets from
+/// [`scan_zendesk`].
+#[derive(Debug)]
+pub struct ZendeskScan {
+    /// Tickets and articles, sorted by `source_id`.
+    pub items: Vec<SourceItem>,
+    /// Export cursors as JSON, or `None` with `incremental = false`.
+    pub state: Option<String>,
+    /// Tickets whose comments could not be fetched.
+    pub failed: Vec<ItemFailure>,
+}
+
+/// Scan a Zendesk account and produce [`SourceItem`]s.
+///
+/// # Workflow
+///
+/// 1. Resolve `brands` to brand IDs.
+/// 2. Export tickets changed since the cursor in `previous`, keep those
+///    matching `statuses` and `brands`, and fetch each one's comment
+///    thread.
+/// 3. Export articles changed since the `end_time` in `previous`.
+/// 4. Return the new cursors. The ticket cursor stays where it was if any
+///    ticket's comments could not be fetched.
+///
+/// # Errors
+///
+/// Returns an error if the API token is missing, authentication fails, a
+/// configured brand does not exist, or an export request fails. A failed
+/// comment fetch is logged and the ticket is reported in
+/// [`ZendeskScan::failed`].
+pub async fn scan_zendesk(
+    name: &str,
+    zd_config: &ZendeskConnectorConfig,
+    previous: Option<&str>,
+) -> Result<ZendeskScan> {
+    let api = ZendeskClient::new(zd_config, Duration::from_secs(120))?;
+    let source = format!("zendesk:{}", name);
+
+    // Cursors for another account (the config changed) start over.
+    let resumed = previous
+        .filter(|_| zd_config.incremental)
+        .map(serde_json::from_str::<ExportState>);
+    let mut state = match resumed {
+        Some(Ok(state)) if state.base_url == api.base => state,
+        Some(Err(e)) => {
+            tracing::warn!("zendesk:{}: ignoring unreadable checkpoint: {}", name, e);
+            ExportState::new(&api.base)
+        }
+        _ => ExportState::new(&api.base),
+    };
+
+    let mut items = Vec::new();
+    let mut failed = Vec::new();
+    if zd_config.tickets {
+        let brands = resolve_brands(&api, &zd_config.brands).await?;
+        let (tickets, cursor) = export_tickets(&api, state.ticket_cursor.as_deref()).await?;
+        for ticket in tickets {
+            if !ticket_selected(&ticket, &zd_config.statuses, brands.as_ref()) {
+                continue;
+            }
+            let id = ticket["id"].as_u64().unwrap_or_default();
+            let thread = match fetch_comments(&api, id).await {
+                Ok(thread) => thread,
+                Err(e) => {
+                    tracing::warn!("skipping Zendesk ticket {}: {:#}", id, e);
+                    failed.push(ItemFailure::new(
+                        &format!("tickets/{}", id),
+                        "fetch",
+                        format!("{:#}", e),
+                    ));
+                    continue;
+                }
+            };
+            items.push(ticket_item(
+                &source,
+                &api.base,
+                &ticket,
+                &thread,
+                zd_config.internal_notes,
+            ));
+        }
+        // Tickets skipped above are exported again by the next scan.
+        if failed.is_empty() {
+            state.ticket_cursor = cursor.or(state.ticket_cursor);
+        }
+    }
+
+    if zd_config.articles {
+        let (articles, end_time) = export_articles(&api, state.article_start_time).await?;
+        for article in articles {
+            if article["draft"].as_bool().unwrap_or(false) {
+                continue;
+            }
+            items.push(article_item(&source, &article));
+        }
+        state.article_start_time = end_time.or(state.article_start_time);
+    }
+
+    let state = if zd_config.incremental {
+        Some(serde_json::to_string(&state)?)
+    } else {
+        None
+    };
+
+    items.sort_by(|a, b| a.source_id.cmp(&b.source_id));
+    Ok(ZendeskScan {
+        items,
+        state,
+        failed,
+    })
+}
+
+// ============ API access ============
+
+/// Authenticated Zendesk client with rate-limit retries.
+struct ZendeskClient {
+    client: reqwest::Client,
+    base: String,
+    user: String,
+    token: String,
+}
+
+impl ZendeskClient {
+    fn new(zd_config: &ZendeskConnectorConfig, timeout: Duration) -> Result<Self> {
+        let token = secrets::resolve_or_env(zd_config.api_token.as_deref(), "ZENDESK_API_TOKEN")?
+            .context("api_token not configured and ZENDESK_API_TOKEN not set")?;
+        Ok(Self {
+            client: reqwest::Client::builder().timeout(timeout).build()?,
+            base: zd_config.api_base(),
+            user: format!("{}/token", zd_config.email),
+            token,
+        })
+    }
+
+    /// GET an API path (relative to the base URL) or an absolute page link.
+    async fn get_json(&self, path_or_url: &str) -> Result<Value> {
+        let url = if path_or_url.starts_with("http") {
+            path_or_url.to_string()
+        } else {
+            format!("{}{}", self.base, path_or_url)
+        };
+
+        let request = self
+            .client
+            .get(&url)
+            .basic_auth(&self.user, Some(&self.token));
+        let reply = http_retry::send(request)
+            .await
+            .with_context(|| format!("Zendesk request failed: {}", url))?;
+        if !reply.status.is_success() {
+            bail!(
+                "Zendesk API error {} for {}: {}",
+                reply.status,
+                url,
+                api_error(&reply.text())
+            );
+        }
+        reply.json()
+    }
+}
+
+/// Extract the message from a Zendesk error body, or return it as-is.
+fn api_error(body: &str) -> String {
+    let Ok(v) = serde_json::from_str::<Value>(body) else {
+        return body.to_string();
+    };
+    v["error"]["message"]
+        .as_str()
+        .or_else(|| v["description"].as_str())
+        .or_else(|| v["error"].as_str())
+        .map(str::to_string)
+        .unwrap_or_else(|| body.to_string())
+}
+
+/// Brand IDs selected by `wanted` (names, subdomains, or IDs), or `None`
+/// for all brands.
+async fn resolve_brands(api: &ZendeskClient, wanted: &[String]) -> Result<Option<HashSet<u64>>> {
+    if wanted.is_empty() {
+        return Ok(None);
+    }
+    let body = api.get_json("/api/v2/brands.json").await?;
+    let brands = body["brands"].as_array().cloned().unwrap_or_default();
+    let mut ids = HashSet::new();
+    for name in wanted {
+        let brand = brands.iter().find(|b| {
+            b["name"]
+                .as_str()
+                .is_some_and(|n| n.eq_ignore_ascii_case(name))
+                || b["subdomain"].as_str() == Some(name.as_str())
+                || b["id"].as_u64().map(|id| id.to_string()).as_deref() == Some(name.as_str())
+        });
+        match brand.and_then(|b| b["id"].as_u64()) {
+            Some(id) => {
+                ids.insert(id);
+            }
+            None => {
+                let names: Vec<&str> = brands.iter().filter_map(|b| b["name"].as_str()).collect();
+                bail!(
+                    "No Zendesk brand '{}'. Available: {}",
+                    name,
+                    names.join(", ")
+                );
+            }
+        }
+    }
+    Ok(Some(ids))
+}
+
+/// Follow the cursor-based ticket export to the end of the stream,
+/// returning the latest version of each changed ticket and the cursor for
+/// the next scan.
+async fn export_tickets(
+    api: &ZendeskClient,
+    cursor: Option<&str>,
+) -> Result<(Vec<Value>, Option<String>)> {
+    let mut next = match cursor {
+        Some(c) => reqwest::Url::parse_with_params(
+            &format!("{}/api/v2/incremental/tickets/cursor.json", api.base),
+            &[("cursor", c)],
+        )?
+        .to_string(),
+        None => "/api/v2/incremental/tickets/cursor.json?start_time=0".to_string(),
+    };
+    let mut tickets: BTreeMap<u64, Value> = BTreeMap::new();
+    let mut after_cursor = None;
+
+    for _ in 0..MAX_PAGES {
+        let page = api.get_json(&next).await?;
+        for ticket in page["tickets"].as_array().into_iter().flatten() {
+            if let Some(id) = ticket["id"].as_u64() {
+                tickets.insert(id, ticket.clone());
+            }
+        }
+        if let Some(c) = page["after_cursor"].as_str() {
+            after_cursor = Some(c.to_string());
+        }
+        if page["end_of_stream"].as_bool().unwrap_or(true) {
+            return Ok((tickets.into_values().collect(), after_cursor));
+        }
+        next = match page["after_url"].as_str() {
+            Some(url) => url.to_string(),
+            None => bail!("Ticket export page has neither after_url nor end_of_stream"),
+        };
+    }
+    bail!("Ticket export did not end after {} pages", MAX_PAGES)
+}
+
+/// Follow the article export from `start_time` (default: the beginning),
+/// returning changed articles and the `end_time` for the next scan.
+async fn export_articles(
+    api: &ZendeskClient,
+    start_time: Option<i64>,
+) -> Result<(Vec<Value>, Option<i64>)> {
+    let mut next = format!(
+        "/api/v2/help_center/incremental/articles.json?start_time={}",
+        start_time.unwrap_or(0)
+    );
+    let mut articles: BTreeMap<u64, Value> = BTreeMap::new();
+    let mut end_time = None;
+
+    for _ in 0..MAX_PAGES {
+        let page = api.get_json(&next).await?;
+        for article in page["articles"].as_array().into_iter().flatten() {
+            if let Some(id) = article["id"].as_u64() {
+                articles.insert(id, article.clone());
+            }
+        }
+        if let Some(t) = page["end_time"].as_i64() {
+            end_time = Some(t);
+        }
+        match page["next_page"].as_str() {
+            // The export repeats its last page with the same end_time once
+            // it has caught up.
+            Some(url) if url != next && page["count"].as_u64() != Some(0) => next = url.to_string(),
+            _ => return Ok((articles.into_values().collect(), end_time)),
+        }
+    }
+    bail!("Article export did not end after {} pages", MAX_PAGES)
+}
+
+/// One comment of a ticket thread.
+#[derive(Debug, Clone)]
+struct Comment {
+    author: String,
+    body: String,
+    public: bool,
+    created_at: Option<DateTime<Utc>>,
+}
+
+/// Fetch a ticket's comments, oldest first, with their authors' names.
+async fn fetch_comments(api: &ZendeskClient, ticket_id: u64) -> Result<Vec<Comment>> {
+    let mut next = format!(
+        "/api/v2/tickets/{}/comments.json?include=users&sort_order=asc",
+        ticket_id
+    );
+    let mut comments = Vec::new();
+    let mut users: HashMap<u64, String> = HashMap::new();
+
+    for _ in 0..MAX_PAGES {
+        let page = api.get_json(&next).await?;
+        for user in page["users"].as_array().into_iter().flatten() {
+            if let (Some(id), Some(name)) = (user["id"].as_u64(), user["name"].as_str()) {
+                let label = match user["role"].as_str() {
+                    Some("agent") | Some("admin") => format!("{} (agent)", name),
+                    _ => name.to_string(),
+                };
+                users.insert(id, label);
+            }
+        }
+        comments.extend(page["comments"].as_array().into_iter().flatten().cloned());
+        match page["next_page"].as_str() {
+            Some(url) if url != next => next = url.to_string(),
+            _ => break,
+        }
+    }
+
+    Ok(comments
+        .iter()
+        .map(|c| Comment {
+            author: c["author_id"]
+                .as_u64()
+                .and_then(|id| users.get(&id).cloned())
+                .unwrap_or_else(|| "Unknown".to_string()),
+            body: c["plain_body"]
+                .as_str()
+                .or_else(|| c["body"].as_str())
+                .unwrap_or_default()
+                .trim()
+                .to_string(),
+            public: c["public"].as_bool().unwrap_or(true),
+            created_at: parse_time(&c["created_at"]),
+        })
+        .collect())
+}
+
+// ============ Documents ============
+
+/// Whether a ticket passes the status and brand filters. Deleted tickets
+/// never do.
+fn ticket_selected(ticket: &Value, statuses: &[String], brands: Option<&HashSet<u64>>) -> bool {
+    let status = ticket["status"].as_str().unwrap_or_default();
+    if status == "deleted" {
+        return false;
+    }
+    if !statuses.is_empty() && !statuses.iter().any(|s| s == status) {
+        return false;
+    }
+    brands.is_none_or(|ids| {
+        ticket["brand_id"]
+            .as_u64()
+            .is_some_and(|id| ids.contains(&id))
+    })
+}
+
+/// Build the document of a ticket, threading its comments into the body.
+fn ticket_item(
+    source: &str,
+    base_url: &str,
+    ticket: &Value,
+    thread: &[Comment],
+    internal_notes: bool,
+) -> SourceItem {
+    let id = ticket["id"].as_u64().unwrap_or_default();
+    let subject = ticket["subject"]
+        .as_str()
+        .filter(|s| !s.trim().is_empty())
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("Ticket #{}", id));
+    let tags: Vec<&str> = ticket["tags"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|t| t.as_str())
+        .collect();
+
+    let mut facts = Vec::new();
+    for (label, key) in [
+        ("Status", "status"),
+        ("Priority", "priority"),
+        ("Type", "type"),
+    ] {
+        if let Some(value) = ticket[key].as_str() {
+            facts.push(format!("{}: {}", label, value));
+        }
+    }
+    if !tags.is_empty() {
+        facts.push(format!("Tags: {}", tags.join(", ")));
+    }
+
+    let mut body = format!("# {}\n", subject);
+    if !facts.is_empty() {
+        body.push_str(&format!("\n{}\n", facts.join(" · ")));
+    }
+    let mut included = 0;
+    for comment in thread {
+        if !comment.public && !internal_notes {
+            continue;
+        }
+        included += 1;
+        body.push_str(&format!("\n## {}", comment.author));
+        if let Some(at) = comment.created_at {
+            body.push_str(&format!(" — {}", at.format("%Y-%m-%d %H:%M UTC")));
+        }
+        if !comment.public {
+            body.push_str(" (internal note)");
+        }
+        body.push_str(&format!("\n\n{}\n", comment.body));
+    }
+
+    let metadata = serde_json::json!({
+        "ticket_id": id,
+        "status": ticket["status"],
+        "priority": ticket["priority"],
+        "type": ticket["type"],
+        "brand_id": ticket["brand_id"],
+        "tags": tags,
+        "requester_id": ticket["requester_id"],
+        "assignee_id": ticket["assignee_id"],
+        "comments": included,
+    });
+
+    let created_at = parse_time(&ticket["created_at"]).unwrap_or_else(Utc::now);
+    SourceItem {
+        source: source.to_string(),
+        source_id: format!("tickets/{}", id),
+        source_url: Some(format!("{}/agent/tickets/{}", base_url, id)),
+        title: Some(subject),
+        author: thread.first().map(|c| c.author.clone()),
+        created_at,
+        updated_at: parse_time(&ticket["updated_at"]).unwrap_or(created_at),
+        content_type: "text/markdown".to_string(),
+        body,
+        metadata_json: metadata.to_string(),
+        raw_json: None,
+        raw_bytes: None,
+        parent_source_id: None,
+    }
+}
+
+/// Build the document of a help-center article.
+fn article_item(source: &str, article: &Value) -> SourceItem {
+    let id = article["id"].as_u64().unwrap_or_default();
+    let title = article["title"].as_str().unwrap_or_default().to_string();
+    let text = html_to_text(article["body"].as_str().unwrap_or_default());
+    let metadata = serde_json::json!({
+        "article_id": id,
+        "locale": article["locale"],
+        "section_id": article["section_id"],
+        "labels": article["label_names"],
+    });
+
+    let created_at = parse_time(&article["created_at"]).unwrap_or_else(Utc::now);
+    SourceItem {
+        source: source.to_string(),
+        source_id: format!("articles/{}", id),
+        source_url: article["html_url"].as_str().map(str::to_string),
+        title: Some(title.clone()),
+        author: None,
+        created_at,
+        updated_at: parse_time(&article["updated_at"]).unwrap_or(created_at),
+        content_type: "text/markdown".to_string(),
+        body: format!("# {}\n\n{}", title, text),
+        metadata_json: metadata.to_string(),
+        raw_json: None,
+        raw_bytes: None,
+        parent_source_id: None,
+    }
+}
+
+/// Plain text of an article's HTML body: block elements become line
+/// breaks, list items bullets, and scripts and styles are dropped.
+fn html_to_text(html: &str) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+    let mut skip_until: Option<&str> = None;
+
+    while let Some(start) = rest.find('<') {
+        if skip_until.is_none() {
+            out.push_str(&decode_entities(&rest[..start]));
+        }
+        let Some(end) = rest[start..].find('>') else {
+            rest = "";
+            break;
+        };
+        let tag = rest[start + 1..start + end].trim().to_ascii_lowercase();
+        rest = &rest[start + end + 1..];
+
+        let closing = tag.starts_with('/');
+        let name: String = tag
+            .trim_start_matches('/')
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect();
+        if let Some(until) = skip_until {
+            if closing && name == until {
+                skip_until = None;
+            }
+            continue;
+        }
+        match name.as_str() {
+            "script" | "style" if !closing => {
+                skip_until = Some(if name == "script" { "script" } else { "style" })
+            }
+            "li" if !closing => out.push_str("\n- "),
+            "br" => out.push('\n'),
+            "p" | "div" | "ul" | "ol" | "tr" | "pre" | "blockquote" | "table" | "h1" | "h2"
+            | "h3" | "h4" | "h5" | "h6" => out.push_str("\n\n"),
+            _ => {}
+        }
+    }
+    if skip_until.is_none() {
+        out.push_str(&decode_entities(rest));
+    }
+
+    // Within a paragraph, wrapped source lines are joined and list items
+    // keep their own line.
+    let mut paragraphs = Vec::new();
+    for block in out.split("\n\n") {
+        let mut lines: Vec<String> = Vec::new();
+        for line in block.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            match lines.last_mut() {
+                Some(last) if !line.starts_with("- ") => {
+                    last.push(' ');
+                    last.push_str(line);
+                }
+                _ => lines.push(line.to_string()),
+            }
+        }
+        if !lines.is_empty() {
+            paragraphs.push(lines.join("\n"));
+        }
+    }
+    paragraphs.join("\n\n")
+}
+
+/// Decode the HTML entities common in article bodies.
+pub(crate) fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let decoded = rest.find(';').filter(|&semi| semi <= 10).and_then(|semi| {
+            let entity = &rest[1..semi];
+            let ch = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                _ => entity
+                    .strip_prefix("#x")
+                    .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                    .or_else(|| entity.strip_prefix('#').and_then(|d| d.parse().ok()))
+                    .and_then(char::from_u32),
+            };
+            ch.map(|c| (c, semi))
+        });
+        match decoded {
+            Some((c, semi)) => {
+                out.push(c);
+                rest = &rest[semi + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn parse_time(value: &Value) -> Option<DateTime<Utc>> {
+    value
+        .as_str()
+        .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+        .map(|t| t.with_timezone(&Utc))
+}
+
+// ============ Cursor state ============
+
+/// Export cursors, kept in the sync checkpoint between scans.
+#[derive(Debug, Serialize, Deserialize)]
+struct ExportState {
+    /// Account the cursors belong to; state for another account is ignored.
+    base_url: String,
+    ticket_cursor: Option<String>,
+    article_start_time: Option<i64>,
+}
+
+impl ExportState {
+    fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.to_string(),
+            ticket_cursor: None,
+            article_start_time: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn comment(author: &str, body: &str, public: bool) -> Comment {
+        Comment {
+            author: author.to_string(),
+            body: body.to_string(),
+            public,
+            created_at: parse_time(&json!("2026-02-01T09:14:00Z")),
+        }
+    }
+
+    #[test]
+    fn ticket_threads_comments_and_hides_internal_notes() {
+        let ticket = json!({
+            "id": 42, "subject": "Refund for duplicate charge", "status": "solved",
+            "priority": "high", "tags": ["billing"],
+            "created_at": "2026-02-01T09:00:00Z", "updated_at": "2026-02-03T10:00:00Z",
+        });
+        let thread = [
+            comment("Dana Smith", "I was charged twice.", true),
+            comment("Lee Park (agent)", "Confirmed in Stripe.", false),
+            comment("Lee Park (agent)", "Refunded the second charge.", true),
+        ];
+
+        let item = ticket_item(
+            "zendesk:support",
+            "https://acme.zendesk.com",
+            &ticket,
+            &thread,
+            false,
+        );
+        assert_eq!(item.source_id, "tickets/42");
+        assert_eq!(
+            item.source_url.as_deref(),
+            Some("https://acme.zendesk.com/agent/tickets/42")
+        );
+        assert_eq!(item.author.as_deref(), Some("Dana Smith"));
+        assert!(item.body.starts_with(
+            "# Refund for duplicate charge\n\nStatus: solved · Priority: high · Tags: billing\n\n## Dana Smith — 2026-02-01 09:14 UTC\n\nI was charged twice.\n"
+        ), "{}", item.body);
+        assert!(item.body.contains("Refunded the second charge."));
+        assert!(!item.body.contains("Stripe"));
+        assert_eq!(item.updated_at.to_rfc3339(), "2026-02-03T10:00:00+00:00");
+
+        let item = ticket_item("zendesk:support", "", &ticket, &thread, true);
+        assert!(item
+            .body
+            .contains("(internal note)\n\nConfirmed in Stripe."));
+    }
+
+    #[test]
+    fn tickets_filter_by_status_and_brand() {
+        let ticket = json!({"id": 1, "status": "open", "brand_id": 7});
+        let brands: HashSet<u64> = [7].into();
+        assert!(ticket_selected(&ticket, &[], None));
+        assert!(ticket_selected(
+            &ticket,
+            &["open".to_string()],
+            Some(&brands)
+        ));
+        assert!(!ticket_selected(&ticket, &["solved".to_string()], None));
+        assert!(!ticket_selected(&ticket, &[], Some(&[8].into())));
+        assert!(!ticket_selected(
+            &json!({"id": 2, "status": "deleted"}),
+            &[],
+            None
+        ));
+    }
+
+    #[test]
+    fn article_html_becomes_text() {
+        assert_eq!(
+            html_to_text(
+                "<h2>Reset your password</h2><p>Open <b>Settings</b> &amp; choose\n<em>Security</em>.</p>\
+                 <ul><li>Enter a new password</li><li>Save</li></ul><script>track()</script><p>Done&#33;</p>"
+            ),
+            "Reset your password\n\nOpen Settings & choose Security.\n\n- Enter a new password\n- Save\n\nDone!"
+        );
+    }
+}
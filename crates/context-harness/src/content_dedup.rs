@@ -0,0 +1,192 @@
+//! Cross-connector deduplication by content hash.
+//!
+//! The same file synced through two connectors (a git repository and a
+//! filesystem checkout of it) is two documents with the same body, and
+//! both show up in search. With `[sync] content_dedup` set, each document
+//! written by a sync records the SHA-256 of its body (trimmed, with line
+//! endings normalized) as `content_sha256` in its metadata, and an item
+//! whose body matches a document of another source is handled by mode:
+//!
+//! | Mode | Duplicate |
+//! |------|-----------|
+//! | `keep-all` (default) | Stored like any other document |
+//! | `link` | Stored without chunks, with `duplicate_of` naming the canonical document |
+//! | `skip` | Not stored |
+//!
+//! The canonical document is the first stored with that content. With
+//! `link`, it lists every duplicate in a `duplicates` metadata array of
+//! `{id, source, source_id, source_url}`, so all source references are kept
+//! while search returns the content once. A duplicate whose content later
+//! changes is stored in full at its next write.
+//!
+//! Only documents written since `content_dedup` was set have a hash; run
+//! `ctx sync --full` to deduplicate existing ones.
+
+use anyhow::Result;
+use serde_json::{Map, Value};
+use sqlx::SqlitePool;
+
+use crate::app_store;
+use crate::models::Document;
+
+/// Metadata key of a document's content hash.
+pub const CONTENT_HASH_KEY: &str = "content_sha256";
+/// Metadata key of a linked duplicate's canonical document ID.
+pub const DUPLICATE_OF_KEY: &str = "duplicate_of";
+/// Metadata key of a canonical document's duplicates.
+pub const DUPLICATES_KEY: &str = "duplicates";
+
+/// How an item whose content another source already stored is handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DedupMode {
+    KeepAll,
+    Link,
+    Skip,
+}
+
+impl DedupMode {
+    /// Parse a `[sync] content_dedup` value.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "keep-all" => Some(Self::KeepAll),
+            "link" => Some(Self::Link),
+            "skip" => Some(Self::Skip),
+            _ => None,
+        }
+    }
+}
+
+/// The document of another source holding the same content.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Canonical {
+    pub id: String,
+    pub source: String,
+    pub source_id: String,
+}
+
+/// SHA-256 of `body`, trimmed and with `\r\n` line endings as `\n`.
+/// `None` for an empty body, which is never deduplicated.
+pub fn content_hash(body: &str) -> Option<String> {
+    let text = body.replace("\r\n", "\n");
+    let text = text.trim();
+    (!text.is_empty()).then(|| app_store::hash_text(text))
+}
+
+/// The canonical document with content `hash` outside `doc`'s source.
+pub async fn find_canonical(
+    pool: &SqlitePool,
+    hash: &str,
+    doc: &Document,
+) -> Result<Option<Canonical>> {
+    let row: Option<(String, String, String)> = sqlx::query_as(
+        "SELECT id, source, source_id FROM documents \
+         WHERE json_extract(metadata_json, '$.content_sha256') = ? AND source != ? \
+           AND json_extract(metadata_json, '$.duplicate_of') IS NULL \
+         ORDER BY rowid LIMIT 1",
+    )
+    .bind(hash)
+    .bind(&doc.source)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.map(|(id, source, source_id)| Canonical {
+        id,
+        source,
+        source_id,
+    }))
+}
+
+/// Record `hash` on `doc` and, as it is stored in full, the duplicates
+/// linked to it.
+pub async fn mark_canonical(pool: &SqlitePool, doc: &mut Document, hash: &str) -> Result<()> {
+    let duplicates = duplicates_of(pool, &doc.id).await?;
+    update_metadata(&mut doc.metadata_json, |metadata| {
+        metadata.insert(CONTENT_HASH_KEY.to_string(), Value::from(hash));
+        if duplicates.is_empty() {
+            metadata.remove(DUPLICATES_KEY);
+        } else {
+            metadata.insert(DUPLICATES_KEY.to_string(), Value::Array(duplicates));
+        }
+    });
+    Ok(())
+}
+
+/// Record `hash` on `doc` as a duplicate of `canonical`.
+pub fn mark_duplicate(doc: &mut Document, hash: &str, canonical: &Canonical) {
+    update_metadata(&mut doc.metadata_json, |metadata| {
+        metadata.insert(CONTENT_HASH_KEY.to_string(), Value::from(hash));
+        metadata.insert(
+            DUPLICATE_OF_KEY.to_string(),
+            Value::from(canonical.id.as_str()),
+        );
+        metadata.remove(DUPLICATES_KEY);
+    });
+}
+
+/// Rewrite the `duplicates` list of the canonical document `id`.
+pub async fn refresh_duplicates(pool: &SqlitePool, id: &str) -> Result<()> {
+    let duplicates = duplicates_of(pool, id).await?;
+    let metadata_json: Option<String> =
+        sqlx::query_scalar("SELECT metadata_json FROM documents WHERE id = ?")
+            .bind(id)
+            .fetch_optional(pool)
+            .await?;
+    let Some(mut metadata_json) = metadata_json else {
+        return Ok(());
+    };
+    update_metadata(&mut metadata_json, |metadata| {
+        metadata.insert(DUPLICATES_KEY.to_string(), Value::Array(duplicates));
+    });
+    sqlx::query("UPDATE documents SET metadata_json = ? WHERE id = ?")
+        .bind(&metadata_json)
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// The duplicates linked to document `id`, as metadata entries.
+async fn duplicates_of(pool: &SqlitePool, id: &str) -> Result<Vec<Value>> {
+    let rows: Vec<(String, String, String, Option<String>)> = sqlx::query_as(
+        "SELECT id, source, source_id, source_url FROM documents \
+         WHERE json_extract(metadata_json, '$.duplicate_of') = ? ORDER BY source, source_id",
+    )
+    .bind(id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|(id, source, source_id, source_url)| {
+            serde_json::json!({
+                "id": id,
+                "source": source,
+                "source_id": source_id,
+                "source_url": source_url,
+            })
+        })
+        .collect())
+}
+
+/// Apply `f` to a metadata JSON object, replacing anything else with one.
+fn update_metadata(metadata_json: &mut String, f: impl FnOnce(&mut Map<String, Value>)) {
+    let mut metadata = match serde_json::from_str(metadata_json) {
+        Ok(Value::Object(map)) => map,
+        _ => Map::new(),
+    };
+    f(&mut metadata);
+    *metadata_json = Value::Object(metadata).to_string();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hash_ignores_line_endings_and_outer_whitespace() {
+        assert_eq!(
+            content_hash("# Deploy\r\n\r\nRun it.\r\n"),
+            content_hash("\n# Deploy\n\nRun it.")
+        );
+        assert_ne!(content_hash("# Deploy"), content_hash("# Deploy!"));
+        assert_eq!(content_hash(" \n "), None);
+    }
+}
@@ -0,0 +1,274 @@
+//! Contextual chunk headers: `[chunking.contextualize]`.
+//!
+//! A chunk cut from the middle of a document often loses what it is about
+//! ("the service restarts after 30 seconds" — which service?). When enabled,
+//! a short context naming the document, the section, and optionally an
+//! LLM-written summary is prepended to each chunk before embedding.
+//!
+//! The context is stored under the chunk's `context` metadata key
+//! ([`CONTEXT_KEY`]); the chunk text used for snippets, keyword search, and
+//! `ctx get` is unchanged. The chunk hash covers the embedded text
+//! ([`crate::chunk::embedding_text`]), so a changed context re-embeds the
+//! chunk like a changed text would.
+//!
+//! # Template
+//!
+//! | Placeholder | Value |
+//! |-------------|-------|
+//! | `{title}` | Document title |
+//! | `{heading}` | Markdown heading breadcrumb of the chunk (`heading` metadata) |
+//! | `{source}` | Connector label, e.g. `filesystem:docs` |
+//! | `{source_id}` | Item id within the connector, e.g. `guides/deploy.md` |
+//! | `{summary}` | LLM-generated context, when `llm = true` |
+//!
+//! Lines whose placeholders are all empty are dropped, so the default
+//! `"{title}\n{heading}\n{summary}"` works for documents without headings.
+//!
+//! # LLM summaries
+//!
+//! With `llm = true`, the `[llm]` model receives the document (cut to
+//! `max_document_tokens`) and the chunk, and answers with one or two
+//! sentences situating the chunk. The summary is kept under
+//! [`SUMMARY_KEY`] and reused while the chunk text is unchanged, so
+//! re-syncing a document does not call the model again for chunks it has
+//! already seen. A failed call is logged and leaves `{summary}` empty.
+
+use anyhow::{bail, Result};
+use serde_json::{json, Value};
+use sqlx::{Row, SqlitePool};
+use std::collections::HashMap;
+
+use crate::agent_run::ChatClient;
+use crate::app_store::hash_text;
+use crate::chunk::{self, HEADING_KEY};
+use crate::config::{Config, ContextualizeConfig};
+use crate::models::{Chunk, SourceItem};
+use crate::text::truncate_chars;
+
+/// Chunk metadata key holding the rendered context.
+pub const CONTEXT_KEY: &str = "context";
+
+/// Chunk metadata key holding the LLM-generated `{summary}`.
+pub const SUMMARY_KEY: &str = "context_summary";
+
+/// Template placeholders, in the order they are documented.
+const PLACEHOLDERS: [&str; 5] = ["title", "heading", "source", "source_id", "summary"];
+
+/// Characters per token, the same estimate the chunker uses.
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Renders and stores the context of each chunk of a document.
+pub struct Contextualizer {
+    settings: ContextualizeConfig,
+    client: Option<ChatClient>,
+}
+
+impl Contextualizer {
+    /// The contextualizer for `[chunking.contextualize]`, or `None` when it
+    /// is disabled.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `llm = true` and the `[llm]` client cannot be
+    /// created (for example, a missing API key).
+    pub fn from_config(config: &Config) -> Result<Option<Self>> {
+        let settings = &config.chunking.contextualize;
+        if !settings.enabled {
+            return Ok(None);
+        }
+        let client = if settings.llm {
+            Some(ChatClient::from_config(&config.llm)?)
+        } else {
+            None
+        };
+        Ok(Some(Self {
+            settings: settings.clone(),
+            client,
+        }))
+    }
+
+    /// Set the `context` metadata of each chunk of `item` and update the
+    /// chunk hashes to cover it. `document_id` identifies the chunks stored
+    /// by the previous sync, whose summaries are reused.
+    pub async fn apply(
+        &self,
+        pool: &SqlitePool,
+        document_id: &str,
+        item: &SourceItem,
+        chunks: &mut [Chunk],
+    ) -> Result<()> {
+        let mut previous = match self.client {
+            Some(_) => previous_summaries(pool, document_id).await?,
+            None => HashMap::new(),
+        };
+
+        for chunk in chunks.iter_mut() {
+            let summary = match self.client {
+                Some(ref client) => match previous.remove(&chunk.text) {
+                    Some(summary) => summary,
+                    None => self.summarize(client, item, &chunk.text).await,
+                },
+                None => String::new(),
+            };
+            let heading = chunk
+                .metadata
+                .get(HEADING_KEY)
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string();
+            let context = render(
+                &self.settings.template,
+                &[
+                    ("title", item.title.as_deref().unwrap_or_default()),
+                    ("heading", &heading),
+                    ("source", &item.source),
+                    ("source_id", &item.source_id),
+                    ("summary", &summary),
+                ],
+            );
+
+            if !summary.is_empty() {
+                chunk
+                    .metadata
+                    .insert(SUMMARY_KEY.to_string(), Value::String(summary));
+            }
+            if !context.is_empty() {
+                chunk
+                    .metadata
+                    .insert(CONTEXT_KEY.to_string(), Value::String(context));
+            }
+            chunk.hash = hash_text(&chunk::embedding_text(&chunk.text, &chunk.metadata));
+        }
+        Ok(())
+    }
+
+    /// Ask the model to situate `text` within the document. Failures are
+    /// logged and yield an empty summary.
+    async fn summarize(&self, client: &ChatClient, item: &SourceItem, text: &str) -> String {
+        let document = truncate_chars(
+            &item.body,
+            self.settings.max_document_tokens * CHARS_PER_TOKEN,
+        );
+        let prompt = format!(
+            "<document>\n{}\n</document>\n\
+             Here is a chunk of the document above:\n\
+             <chunk>\n{}\n</chunk>\n\
+             Give a short, succinct context (one or two sentences) that situates this chunk \
+             within the overall document, to improve search retrieval of the chunk. \
+             Answer only with the context and nothing else.",
+            document, text
+        );
+        let messages = [json!({ "role": "user", "content": prompt })];
+        match client.complete(&messages).await {
+            Ok(summary) => summary.split_whitespace().collect::<Vec<_>>().join(" "),
+            Err(e) => {
+                tracing::warn!("contextualize {}: {:#}", item.source_id, e);
+                String::new()
+            }
+        }
+    }
+}
+
+/// Check that `template` only uses known placeholders and closes its braces.
+pub fn validate_template(template: &str) -> Result<()> {
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else {
+            bail!("unclosed '{{' in '{}'", template);
+        };
+        let name = &rest[start + 1..start + len];
+        if !PLACEHOLDERS.contains(&name) {
+            bail!(
+                "unknown placeholder '{{{}}}'; use one of: {}",
+                name,
+                PLACEHOLDERS
+                    .iter()
+                    .map(|p| format!("{{{}}}", p))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
+        rest = &rest[start + len + 1..];
+    }
+    Ok(())
+}
+
+/// Fill in `template`, dropping lines whose placeholders are all empty.
+fn render(template: &str, values: &[(&str, &str)]) -> String {
+    let mut lines = Vec::new();
+    for line in template.lines() {
+        let mut rendered = line.to_string();
+        let mut placeholders = 0;
+        let mut filled = 0;
+        for (name, value) in values {
+            let placeholder = format!("{{{}}}", name);
+            if rendered.contains(&placeholder) {
+                placeholders += 1;
+                if !value.trim().is_empty() {
+                    filled += 1;
+                }
+                rendered = rendered.replace(&placeholder, value.trim());
+            }
+        }
+        if placeholders > 0 && filled == 0 {
+            continue;
+        }
+        let rendered = rendered.trim();
+        if !rendered.is_empty() {
+            lines.push(rendered.to_string());
+        }
+    }
+    lines.join("\n")
+}
+
+/// Summaries of the chunks stored for `document_id`, keyed by chunk text.
+async fn previous_summaries(
+    pool: &SqlitePool,
+    document_id: &str,
+) -> Result<HashMap<String, String>> {
+    let rows = sqlx::query("SELECT text, metadata_json FROM chunks WHERE document_id = ?")
+        .bind(document_id)
+        .fetch_all(pool)
+        .await?;
+    let mut summaries = HashMap::new();
+    for row in rows {
+        let metadata: String = row.get("metadata_json");
+        let summary = serde_json::from_str::<Value>(&metadata)
+            .ok()
+            .and_then(|m| m.get(SUMMARY_KEY)?.as_str().map(str::to_string));
+        if let Some(summary) = summary {
+            summaries.insert(row.get("text"), summary);
+        }
+    }
+    Ok(summaries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_drops_lines_without_values() {
+        let values = [
+            ("title", "Deploy Guide"),
+            ("heading", ""),
+            ("summary", "How the API is rolled out."),
+        ];
+        assert_eq!(
+            render("{title}\n{heading}\n{summary}", &values),
+            "Deploy Guide\nHow the API is rolled out."
+        );
+        assert_eq!(render("Section: {heading}", &values), "");
+        assert_eq!(
+            render("Doc: {title} ({heading})", &values),
+            "Doc: Deploy Guide ()"
+        );
+    }
+
+    #[test]
+    fn validate_template_rejects_unknown_placeholders() {
+        assert!(validate_template("{title} > {heading}").is_ok());
+        assert!(validate_template("{author}").is_err());
+        assert!(validate_template("{title").is_err());
+    }
+}
@@ -2,6 +2,10 @@
 //!
 //! Workspace-local files live under `.ctx/`. User-global files use XDG base
 //! directories with an app directory named `ctx` (without a leading dot).
+//!
+//! The home directory is `HOME` on every platform. On Windows, where `HOME`
+//! is usually unset, `USERPROFILE` and then `HOMEDRIVE` + `HOMEPATH` are
+//! used instead, so the XDG-style defaults land under the user profile.
 
 use std::env;
 use std::path::{Path, PathBuf};
@@ -179,17 +183,52 @@ fn absolute_env_path(var: &str) -> Option<PathBuf> {
     if path.is_absolute() {
         Some(path)
     } else {
-        eprintln!(
-            "Warning: ignoring relative path in {}: {}",
-            var,
-            path.display()
-        );
+        tracing::warn!("ignoring relative path in {}: {}", var, path.display());
         None
     }
 }
 
-fn home_dir() -> Option<PathBuf> {
-    env::var_os("HOME").map(PathBuf::from)
+/// Resolve the user's home directory.
+///
+/// `HOME` wins when set (including under MSYS/Git Bash on Windows). On
+/// Windows, falls back to `USERPROFILE`, then `HOMEDRIVE` + `HOMEPATH`.
+pub fn home_dir() -> Option<PathBuf> {
+    if let Some(home) = non_empty_env("HOME") {
+        return Some(PathBuf::from(home));
+    }
+    if cfg!(windows) {
+        if let Some(profile) = non_empty_env("USERPROFILE") {
+            return Some(PathBuf::from(profile));
+        }
+        if let (Some(drive), Some(path)) = (non_empty_env("HOMEDRIVE"), non_empty_env("HOMEPATH")) {
+            let mut home = drive;
+            home.push(path);
+            return Some(PathBuf::from(home));
+        }
+    }
+    None
+}
+
+fn non_empty_env(var: &str) -> Option<std::ffi::OsString> {
+    env::var_os(var).filter(|v| !v.is_empty())
+}
+
+/// Expand a leading `~` to the user's home directory.
+///
+/// Accepts both `~/` and `~\` so Windows-style config paths work.
+/// Paths without a leading `~`, or with `~user` forms, are returned unchanged.
+pub fn expand_tilde(path: &Path) -> PathBuf {
+    let s = path.to_string_lossy();
+    let rest = if s == "~" {
+        Some("")
+    } else {
+        s.strip_prefix("~/").or_else(|| s.strip_prefix("~\\"))
+    };
+    match (rest, home_dir()) {
+        (Some(""), Some(home)) => home,
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => path.to_path_buf(),
+    }
 }
 
 pub fn is_default_workspace_db_path(path: &Path) -> bool {
@@ -278,6 +317,60 @@ mod tests {
         );
     }
 
+    #[test]
+    fn home_dir_prefers_home() {
+        with_env(
+            &[
+                ("HOME", Some("/tmp/ctx-home")),
+                ("USERPROFILE", Some("/tmp/ctx-profile")),
+            ],
+            |_| {
+                assert_eq!(home_dir(), Some(PathBuf::from("/tmp/ctx-home")));
+            },
+        );
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn home_dir_falls_back_to_windows_profile() {
+        with_env(
+            &[
+                ("HOME", None),
+                ("USERPROFILE", None),
+                ("HOMEDRIVE", Some("C:")),
+                ("HOMEPATH", Some("\\Users\\ctx")),
+            ],
+            |_| {
+                assert_eq!(home_dir(), Some(PathBuf::from("C:\\Users\\ctx")));
+            },
+        );
+        with_env(
+            &[("HOME", None), ("USERPROFILE", Some("C:\\Users\\profile"))],
+            |_| {
+                assert_eq!(home_dir(), Some(PathBuf::from("C:\\Users\\profile")));
+            },
+        );
+    }
+
+    #[test]
+    fn expand_tilde_accepts_both_separators() {
+        with_env(&[("HOME", Some("/tmp/ctx-home"))], |_| {
+            assert_eq!(
+                expand_tilde(Path::new("~/foo/bar")),
+                PathBuf::from("/tmp/ctx-home").join("foo/bar")
+            );
+            assert_eq!(
+                expand_tilde(Path::new("~\\foo")),
+                PathBuf::from("/tmp/ctx-home").join("foo")
+            );
+            assert_eq!(expand_tilde(Path::new("~")), PathBuf::from("/tmp/ctx-home"));
+            assert_eq!(
+                expand_tilde(Path::new("~other/x")),
+                PathBuf::from("~other/x")
+            );
+        });
+    }
+
     #[test]
     fn explicit_and_env_config_bypass_discovery() {
         with_env(&[("CTX_CONFIG", Some("/tmp/from-env.toml"))], |_| {
@@ -0,0 +1,521 @@
+//! Manual search curation: pins, boosts, and a blocklist.
+//!
+//! Some documents must always surface for certain queries, and some should
+//! never surface at all. `ctx curate` records those decisions in three
+//! tables that every search consults after ranking:
+//!
+//! | Table | Command | Effect |
+//! |-------|---------|--------|
+//! | `curation_pins` | `ctx curate pin <id> --query "deploy"` | The document is listed first when the query matches |
+//! | `curation_boosts` | `ctx curate boost <id> --weight 1.5` | The document's score is multiplied by the weight |
+//! | `curation_blocks` | `ctx curate block <id>` | The document is never returned |
+//!
+//! # Query Matching
+//!
+//! Pin queries are stored normalized: lowercased words, punctuation
+//! dropped. A pin matches a search whose words contain the pin's words as
+//! a contiguous run, so a pin on `"deploy"` matches `"how do I deploy?"`
+//! but not `"deployment"`.
+//!
+//! # Ranking
+//!
+//! Blocked documents are dropped (search asks for extra candidates so the
+//! limit is still filled). Boosted scores are re-sorted and may exceed 1.0.
+//! Matching pins come first, in the order they were created, and take the
+//! top score of the result set so merged (federated) rankings keep them on
+//! top. A pinned document that did not rank at all is added from the
+//! database if it passes the search's source and date filters; with
+//! metadata filters, only pinned documents that ranked are moved up.
+//!
+//! With `--explain`, each result shows its `boost` and whether it was
+//! `pinned`. Every change bumps the index generation, so the server's
+//! search cache picks it up.
+
+use anyhow::{bail, Result};
+use chrono::NaiveDate;
+use context_harness_core::search::{
+    end_of_day_ts, format_ts_iso, source_filter_matches, source_matches, type_matches,
+    ScoreExplanation, SearchRequest, SearchResultItem,
+};
+use context_harness_core::store::Store;
+use sqlx::{Row, SqlitePool};
+use std::collections::{HashMap, HashSet};
+
+use crate::config::Config;
+use crate::db;
+use crate::migrate;
+use crate::search_cache;
+use crate::sqlite_store::SqliteStore;
+
+/// Curation rules loaded from the database.
+#[derive(Debug, Default)]
+pub struct Curation {
+    /// `(document_id, normalized query)`, oldest first.
+    pins: Vec<(String, String)>,
+    boosts: HashMap<String, f64>,
+    blocked: HashSet<String>,
+}
+
+impl Curation {
+    /// Load every rule. A database without curation tables has no rules.
+    pub async fn load(pool: &SqlitePool) -> Result<Self> {
+        let tables: Vec<String> = sqlx::query_scalar(
+            "SELECT name FROM sqlite_master WHERE type = 'table' AND name LIKE 'curation_%'",
+        )
+        .fetch_all(pool)
+        .await?;
+        if tables.len() < 3 {
+            return Ok(Self::default());
+        }
+
+        let pins = sqlx::query_as::<_, (String, String)>(
+            "SELECT document_id, query FROM curation_pins ORDER BY created_at, rowid",
+        )
+        .fetch_all(pool)
+        .await?;
+        let boosts =
+            sqlx::query_as::<_, (String, f64)>("SELECT document_id, weight FROM curation_boosts")
+                .fetch_all(pool)
+                .await?
+                .into_iter()
+                .collect();
+        let blocked = sqlx::query_scalar::<_, String>("SELECT document_id FROM curation_blocks")
+            .fetch_all(pool)
+            .await?
+            .into_iter()
+            .collect();
+        Ok(Self {
+            pins,
+            boosts,
+            blocked,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pins.is_empty() && self.boosts.is_empty() && self.blocked.is_empty()
+    }
+
+    /// Extra candidates to request so blocked documents don't shrink the result list.
+    pub fn extra_candidates(&self) -> i64 {
+        self.blocked.len() as i64
+    }
+
+    /// Whether `ctx curate block` hid the document from retrieval.
+    pub fn is_blocked(&self, id: &str) -> bool {
+        self.blocked.contains(id)
+    }
+
+    /// Documents pinned for `query`, in pin order, without blocked ones.
+    fn pinned_for(&self, query: &str) -> Vec<&str> {
+        let words: Vec<String> = normalize_query(query)
+            .split(' ')
+            .map(str::to_string)
+            .collect();
+        let mut ids: Vec<&str> = Vec::new();
+        for (id, pin) in &self.pins {
+            let pin_words: Vec<&str> = pin.split(' ').collect();
+            let matches = words
+                .windows(pin_words.len())
+                .any(|w| w.iter().zip(&pin_words).all(|(a, b)| a == b));
+            if matches && !self.blocked.contains(id) && !ids.contains(&id.as_str()) {
+                ids.push(id);
+            }
+        }
+        ids
+    }
+
+    /// Apply blocks, boosts, and pins to ranked results, keeping at most `limit`.
+    pub async fn apply(
+        &self,
+        pool: &SqlitePool,
+        req: &SearchRequest<'_>,
+        results: Vec<SearchResultItem>,
+        limit: usize,
+    ) -> Result<Vec<SearchResultItem>> {
+        if self.is_empty() {
+            let mut results = results;
+            results.truncate(limit);
+            return Ok(results);
+        }
+
+        let pinned_ids = self.pinned_for(req.query);
+        let template = results.iter().find_map(|r| r.explain.clone());
+        let mut pinned: Vec<SearchResultItem> = Vec::new();
+        let mut ranked: Vec<SearchResultItem> = Vec::new();
+        for mut item in results {
+            if self.blocked.contains(&item.id) {
+                continue;
+            }
+            if let Some(&weight) = self.boosts.get(&item.id) {
+                item.score *= weight;
+                if let Some(ex) = item.explain.as_mut() {
+                    ex.boost = weight;
+                }
+            }
+            if pinned_ids.contains(&item.id.as_str()) {
+                pinned.push(item);
+            } else {
+                ranked.push(item);
+            }
+        }
+        ranked.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then(b.updated_at.cmp(&a.updated_at))
+                .then(a.id.cmp(&b.id))
+        });
+
+        let top_score = pinned
+            .iter()
+            .chain(ranked.iter())
+            .map(|r| r.score)
+            .fold(f64::NAN, f64::max);
+        let top_score = if top_score.is_nan() { 1.0 } else { top_score };
+
+        let mut out: Vec<SearchResultItem> = Vec::new();
+        for id in pinned_ids {
+            let item = match pinned.iter().position(|r| r.id == id) {
+                Some(pos) => Some(pinned.remove(pos)),
+                None if req.chunk_filters.is_empty() => unranked_pin(pool, id, req).await?,
+                None => None,
+            };
+            let Some(mut item) = item else { continue };
+            item.score = top_score;
+            if req.explain {
+                let ex = item.explain.get_or_insert_with(|| ScoreExplanation {
+                    keyword_score: 0.0,
+                    semantic_score: 0.0,
+                    alpha: template
+                        .as_ref()
+                        .map_or(req.params.hybrid_alpha, |t| t.alpha),
+                    keyword_candidates: template.as_ref().map_or(0, |t| t.keyword_candidates),
+                    vector_candidates: template.as_ref().map_or(0, |t| t.vector_candidates),
+                    boost: 1.0,
+                    pinned: false,
+                    popularity: 1.0,
+                });
+                ex.boost = self.boosts.get(id).copied().unwrap_or(1.0);
+                ex.pinned = true;
+            }
+            out.push(item);
+        }
+        out.extend(ranked);
+        out.truncate(limit);
+        Ok(out)
+    }
+}
+
+/// Build a result for a pinned document that didn't rank, if it passes the
+/// request's source, type, and date filters.
+async fn unranked_pin(
+    pool: &SqlitePool,
+    id: &str,
+    req: &SearchRequest<'_>,
+) -> Result<Option<SearchResultItem>> {
+    let store = SqliteStore::new(pool.clone());
+    let Some(meta) = store.get_document_metadata(id).await? else {
+        return Ok(None);
+    };
+    if req
+        .source_filter
+        .is_some_and(|filter| !source_filter_matches(filter, &meta.source))
+        || !type_matches(
+            req.types,
+            &meta.source_id,
+            &meta.content_type,
+            meta.doc_type.as_deref(),
+        )
+        || req
+            .allowed_sources
+            .is_some_and(|patterns| !source_matches(patterns, &meta.source))
+    {
+        return Ok(None);
+    }
+    if let Some(since) = req.since {
+        let since_ts = NaiveDate::parse_from_str(since, "%Y-%m-%d")?
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+            .and_utc()
+            .timestamp();
+        if meta.updated_at < since_ts {
+            return Ok(None);
+        }
+    }
+    if let Some(as_of) = req.as_of {
+        if meta.updated_at > end_of_day_ts(as_of)? {
+            return Ok(None);
+        }
+    }
+
+    let first_chunk: Option<(String, String)> = sqlx::query_as(
+        "SELECT id, substr(text, 1, 240) FROM chunks WHERE document_id = ? ORDER BY chunk_index LIMIT 1",
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+    let (chunk_id, snippet) = first_chunk.unzip();
+
+    Ok(Some(SearchResultItem {
+        id: meta.id,
+        score: 0.0,
+        title: meta.title,
+        source: meta.source,
+        source_id: meta.source_id,
+        content_type: Some(meta.content_type),
+        updated_at: format_ts_iso(meta.updated_at),
+        snippet: snippet.unwrap_or_default(),
+        source_url: meta.source_url,
+        heading: None,
+        section_source_id: None,
+        section_source_url: None,
+        chunk_id,
+        parent_id: meta.parent_id,
+        explain: None,
+        origin: None,
+        summary: meta.summary,
+        doc_type: meta.doc_type,
+    }))
+}
+
+/// Lowercased words of `query`, punctuation dropped, joined by single spaces.
+pub fn normalize_query(query: &str) -> String {
+    query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Connect, create the curation tables, and check that `id` is a document.
+async fn open_for_document(config: &Config, id: &str) -> Result<SqlitePool> {
+    let pool = db::connect(config).await?;
+    migrate::create_curation_tables(&pool).await?;
+    let exists: Option<String> = sqlx::query_scalar("SELECT id FROM documents WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&pool)
+        .await?;
+    if exists.is_none() {
+        pool.close().await;
+        bail!("Document not found: {}", id);
+    }
+    Ok(pool)
+}
+
+async fn finish(pool: SqlitePool) -> Result<()> {
+    search_cache::bump_index_generation(&pool).await?;
+    pool.close().await;
+    Ok(())
+}
+
+/// `ctx curate pin` — list `id` first whenever a search matches `query`.
+pub async fn run_pin(config: &Config, id: &str, query: &str) -> Result<()> {
+    let normalized = normalize_query(query);
+    if normalized.is_empty() {
+        bail!("Pin query must contain at least one word");
+    }
+    let pool = open_for_document(config, id).await?;
+    sqlx::query(
+        "INSERT OR IGNORE INTO curation_pins (document_id, query, created_at) VALUES (?, ?, ?)",
+    )
+    .bind(id)
+    .bind(&normalized)
+    .bind(chrono::Utc::now().timestamp())
+    .execute(&pool)
+    .await?;
+    println!("pinned {} for \"{}\"", id, normalized);
+    finish(pool).await
+}
+
+/// `ctx curate unpin` — remove one pin of `id`, or all of them without `query`.
+pub async fn run_unpin(config: &Config, id: &str, query: Option<&str>) -> Result<()> {
+    let pool = db::connect(config).await?;
+    migrate::create_curation_tables(&pool).await?;
+    let removed = match query {
+        Some(q) => {
+            sqlx::query("DELETE FROM curation_pins WHERE document_id = ? AND query = ?")
+                .bind(id)
+                .bind(normalize_query(q))
+                .execute(&pool)
+                .await?
+        }
+        None => {
+            sqlx::query("DELETE FROM curation_pins WHERE document_id = ?")
+                .bind(id)
+                .execute(&pool)
+                .await?
+        }
+    }
+    .rows_affected();
+    println!("removed {} pin(s) for {}", removed, id);
+    finish(pool).await
+}
+
+/// `ctx curate boost` — multiply the score of `id` by `weight` in every search.
+pub async fn run_boost(config: &Config, id: &str, weight: f64) -> Result<()> {
+    if !weight.is_finite() || weight <= 0.0 {
+        bail!("Boost weight must be a positive number, got {}", weight);
+    }
+    let pool = open_for_document(config, id).await?;
+    sqlx::query(
+        r#"
+        INSERT INTO curation_boosts (document_id, weight, created_at) VALUES (?, ?, ?)
+        ON CONFLICT(document_id) DO UPDATE SET weight = excluded.weight
+        "#,
+    )
+    .bind(id)
+    .bind(weight)
+    .bind(chrono::Utc::now().timestamp())
+    .execute(&pool)
+    .await?;
+    println!("boosted {} x{:.2}", id, weight);
+    finish(pool).await
+}
+
+/// `ctx curate unboost` — remove the boost of `id`.
+pub async fn run_unboost(config: &Config, id: &str) -> Result<()> {
+    let pool = db::connect(config).await?;
+    migrate::create_curation_tables(&pool).await?;
+    let removed = sqlx::query("DELETE FROM curation_boosts WHERE document_id = ?")
+        .bind(id)
+        .execute(&pool)
+        .await?
+        .rows_affected();
+    println!("removed {} boost(s) for {}", removed, id);
+    finish(pool).await
+}
+
+/// `ctx curate block` — never return `id` from search.
+pub async fn run_block(config: &Config, id: &str, reason: Option<&str>) -> Result<()> {
+    let pool = open_for_document(config, id).await?;
+    sqlx::query(
+        r#"
+        INSERT INTO curation_blocks (document_id, reason, created_at) VALUES (?, ?, ?)
+        ON CONFLICT(document_id) DO UPDATE SET reason = excluded.reason
+        "#,
+    )
+    .bind(id)
+    .bind(reason)
+    .bind(chrono::Utc::now().timestamp())
+    .execute(&pool)
+    .await?;
+    println!("blocked {}", id);
+    finish(pool).await
+}
+
+/// `ctx curate unblock` — allow `id` in search results again.
+pub async fn run_unblock(config: &Config, id: &str) -> Result<()> {
+    let pool = db::connect(config).await?;
+    migrate::create_curation_tables(&pool).await?;
+    let removed = sqlx::query("DELETE FROM curation_blocks WHERE document_id = ?")
+        .bind(id)
+        .execute(&pool)
+        .await?
+        .rows_affected();
+    println!("removed {} block(s) for {}", removed, id);
+    finish(pool).await
+}
+
+/// `ctx curate list` — print every pin, boost, and block with its document.
+pub async fn run_list(config: &Config) -> Result<()> {
+    let pool = db::connect(config).await?;
+    migrate::create_curation_tables(&pool).await?;
+
+    let describe = |row: &sqlx::sqlite::SqliteRow| -> String {
+        let id: String = row.get("document_id");
+        match row.get::<Option<String>, _>("source") {
+            Some(source) => format!(
+                "{} ({} / {})",
+                id,
+                source,
+                row.get::<Option<String>, _>("title")
+                    .unwrap_or_else(|| "(untitled)".to_string())
+            ),
+            None => format!("{} (missing)", id),
+        }
+    };
+
+    let pins = sqlx::query(
+        r#"
+        SELECT p.document_id, p.query, d.source, d.title
+        FROM curation_pins p LEFT JOIN documents d ON d.id = p.document_id
+        ORDER BY p.query, p.created_at
+        "#,
+    )
+    .fetch_all(&pool)
+    .await?;
+    let boosts = sqlx::query(
+        r#"
+        SELECT b.document_id, b.weight, d.source, d.title
+        FROM curation_boosts b LEFT JOIN documents d ON d.id = b.document_id
+        ORDER BY b.weight DESC
+        "#,
+    )
+    .fetch_all(&pool)
+    .await?;
+    let blocks = sqlx::query(
+        r#"
+        SELECT k.document_id, k.reason, d.source, d.title
+        FROM curation_blocks k LEFT JOIN documents d ON d.id = k.document_id
+        ORDER BY k.created_at
+        "#,
+    )
+    .fetch_all(&pool)
+    .await?;
+    pool.close().await;
+
+    if pins.is_empty() && boosts.is_empty() && blocks.is_empty() {
+        println!("No curation rules.");
+        return Ok(());
+    }
+    if !pins.is_empty() {
+        println!("pins");
+        for row in &pins {
+            let query: String = row.get("query");
+            println!("  \"{}\" → {}", query, describe(row));
+        }
+    }
+    if !boosts.is_empty() {
+        println!("boosts");
+        for row in &boosts {
+            let weight: f64 = row.get("weight");
+            println!("  x{:.2} {}", weight, describe(row));
+        }
+    }
+    if !blocks.is_empty() {
+        println!("blocks");
+        for row in &blocks {
+            match row.get::<Option<String>, _>("reason") {
+                Some(reason) => println!("  {} — {}", describe(row), reason),
+                None => println!("  {}", describe(row)),
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pins_match_contiguous_query_words() {
+        let curation = Curation {
+            pins: vec![
+                ("doc-a".to_string(), normalize_query("Deploy")),
+                ("doc-b".to_string(), normalize_query("roll back")),
+                ("doc-c".to_string(), normalize_query("deploy")),
+            ],
+            boosts: HashMap::new(),
+            blocked: HashSet::from(["doc-c".to_string()]),
+        };
+        assert_eq!(curation.pinned_for("How do I deploy?"), vec!["doc-a"]);
+        assert!(curation.pinned_for("deployment guide").is_empty());
+        assert_eq!(
+            curation.pinned_for("roll-back the deploy"),
+            vec!["doc-a", "doc-b"]
+        );
+        assert!(curation.pinned_for("back roll").is_empty());
+    }
+}
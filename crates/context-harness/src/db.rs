@@ -14,17 +14,78 @@
 //!
 //! Uses `sqlx::SqlitePool` with up to 5 concurrent connections.
 //! Connections are reused across requests for efficiency.
+//!
+//! # Concurrent Writers
+//!
+//! SQLite allows one writer at a time per database file, across all
+//! processes. Writers coordinate in three layers:
+//!
+//! 1. **Busy timeout** — a connection that finds the write lock taken waits
+//!    up to `[db] busy_timeout_ms` (default 30 s) for it.
+//! 2. **Immediate transactions** — multi-statement writes start with
+//!    [`begin_write`] (`BEGIN IMMEDIATE`), taking the write lock before
+//!    their first read. A deferred transaction that reads and then writes
+//!    fails with `database is locked` at once, without waiting, when another
+//!    process wrote in between.
+//! 3. **Retry** — writes that can collide with another process (document,
+//!    chunk, embedding, and checkpoint upserts, and retrieval tracking) run
+//!    through [`retry_busy`], which retries `SQLITE_BUSY` and
+//!    `SQLITE_LOCKED` up to `[db] write_retries` times with backoff.
+//!
+//! # Read Pool
+//!
+//! With `[db] read_pool = true`, searches and document reads use
+//! [`connect_reader`]: one read-only pool per database, shared for the life
+//! of the process. Readers never take write locks, and a server stops
+//! opening a pool per request. Best-effort writes made while reading
+//! (retrieval tracking, the query-embedding cache) go through a regular
+//! connection.
+//!
+//! # Tuning Profiles
+//!
+//! `[db] profile` sets SQLite pragmas on every connection:
+//!
+//! | Profile | `mmap_size` | `cache_size` | `synchronous` | `wal_autocheckpoint` |
+//! |---------|-------------|--------------|---------------|----------------------|
+//! | `balanced` (default) | SQLite default (off) | SQLite default (2 MiB) | `FULL` | 1000 pages |
+//! | `read-heavy` | 256 MiB | 64 MiB | `NORMAL` | 1000 pages |
+//! | `write-heavy` | off | 32 MiB | `NORMAL` | 10000 pages |
+//!
+//! `read-heavy` suits a long-running `ctx serve mcp`: reads come from the
+//! memory-mapped file and a larger page cache. `write-heavy` suits bulk
+//! syncs: fewer WAL checkpoints interrupt them. `NORMAL` is durable in WAL
+//! mode except for the last transactions before a power loss. Pools
+//! opened without a config ([`connect_path`]) use `balanced`. Pair
+//! `read-heavy` with `[db] warm_up` (see [`crate::warmup`]) to load the
+//! index into memory before the first query.
 
 use anyhow::Result;
-use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions, SqliteSynchronous};
+use sqlx::{Sqlite, Transaction};
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
 
 use crate::config::Config;
 
+/// Busy timeout of [`connect_path`], which has no config to read.
+const DEFAULT_BUSY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Connections in each shared read-only pool.
+const READ_POOL_CONNECTIONS: u32 = 8;
+
+/// First backoff of [`retry_busy`]; doubled on each retry.
+const RETRY_BACKOFF: Duration = Duration::from_millis(100);
+
 /// Create a connection pool to the configured SQLite database.
 ///
 /// - Creates the database file and parent directories if they don't exist.
 /// - Enables WAL journal mode for concurrent read/write.
+/// - Waits up to `[db] busy_timeout_ms` for other writers.
+/// - Applies the `[db] profile` pragmas.
 /// - Returns a pool with up to 5 connections.
 ///
 /// # Arguments
@@ -35,8 +96,24 @@ use crate::config::Config;
 ///
 /// Returns an error if the database cannot be created or connected to.
 pub async fn connect(config: &Config) -> Result<SqlitePool> {
-    let db_path = &config.db.path;
+    open(
+        &config.db.path,
+        Duration::from_millis(config.db.busy_timeout_ms),
+        &config.db.profile,
+    )
+    .await
+}
+
+/// Create a connection pool to the SQLite database at `db_path`.
+///
+/// Same as [`connect`] with the default busy timeout, for callers that
+/// only hold the database path (e.g. the Lua `cache` module of script
+/// connectors).
+pub async fn connect_path(db_path: &Path) -> Result<SqlitePool> {
+    open(db_path, DEFAULT_BUSY_TIMEOUT, "balanced").await
+}
 
+async fn open(db_path: &Path, busy_timeout: Duration, profile: &str) -> Result<SqlitePool> {
     // Ensure parent directory exists
     if let Some(parent) = db_path.parent() {
         std::fs::create_dir_all(parent)?;
@@ -44,7 +121,9 @@ pub async fn connect(config: &Config) -> Result<SqlitePool> {
 
     let options = SqliteConnectOptions::from_str(&format!("sqlite:{}", db_path.display()))?
         .create_if_missing(true)
-        .journal_mode(sqlx::sqlite::SqliteJournalMode::Wal);
+        .journal_mode(sqlx::sqlite::SqliteJournalMode::Wal)
+        .busy_timeout(busy_timeout);
+    let options = apply_profile(options, profile);
 
     let pool = SqlitePoolOptions::new()
         .max_connections(5)
@@ -53,3 +132,186 @@ pub async fn connect(config: &Config) -> Result<SqlitePool> {
 
     Ok(pool)
 }
+
+/// Set the pragmas of a `[db] profile` (see the module docs). Unknown
+/// profiles, rejected by config validation, leave `options` unchanged.
+fn apply_profile(options: SqliteConnectOptions, profile: &str) -> SqliteConnectOptions {
+    match profile {
+        "read-heavy" => options
+            .pragma("mmap_size", "268435456")
+            .pragma("cache_size", "-65536")
+            .synchronous(SqliteSynchronous::Normal)
+            .pragma("wal_autocheckpoint", "1000"),
+        "write-heavy" => options
+            .pragma("mmap_size", "0")
+            .pragma("cache_size", "-32768")
+            .synchronous(SqliteSynchronous::Normal)
+            .pragma("wal_autocheckpoint", "10000"),
+        _ => options,
+    }
+}
+
+/// Pool for searches and document reads.
+///
+/// With `[db] read_pool = true`, a clone of the process-wide read-only pool
+/// of the database, created on first use; release it with
+/// [`close_reader`], which leaves the shared pool open. Otherwise the same
+/// as [`connect`].
+pub async fn connect_reader(config: &Config) -> Result<SqlitePool> {
+    if !config.db.read_pool {
+        return connect(config).await;
+    }
+    static READERS: OnceLock<Mutex<HashMap<PathBuf, SqlitePool>>> = OnceLock::new();
+    let readers = READERS.get_or_init(Default::default);
+    if let Some(pool) = readers.lock().unwrap().get(&config.db.path) {
+        if !pool.is_closed() {
+            return Ok(pool.clone());
+        }
+    }
+
+    // The schema must exist before a read-only connection can use it.
+    connect(config).await?.close().await;
+    let options = SqliteConnectOptions::from_str(&format!("sqlite:{}", config.db.path.display()))?
+        .read_only(true)
+        .busy_timeout(Duration::from_millis(config.db.busy_timeout_ms));
+    let options = apply_profile(options, &config.db.profile);
+    let pool = SqlitePoolOptions::new()
+        .max_connections(READ_POOL_CONNECTIONS)
+        .connect_with(options)
+        .await?;
+    readers
+        .lock()
+        .unwrap()
+        .insert(config.db.path.clone(), pool.clone());
+    Ok(pool)
+}
+
+/// Release a pool from [`connect_reader`]: closes it unless it is the
+/// shared read pool.
+pub async fn close_reader(config: &Config, pool: SqlitePool) {
+    if !config.db.read_pool {
+        pool.close().await;
+    }
+}
+
+/// Pool for best-effort writes made while reading with `pool` from
+/// [`connect_reader`]: `pool` itself, or a new read-write pool when `pool`
+/// is the read-only one. Close the result when it is not `pool`.
+pub async fn writer_for(config: &Config, pool: &SqlitePool) -> Result<SqlitePool> {
+    if config.db.read_pool {
+        connect(config).await
+    } else {
+        Ok(pool.clone())
+    }
+}
+
+/// Start a write transaction with `BEGIN IMMEDIATE`, taking the database
+/// write lock up front (waiting for it per the busy timeout) so the
+/// transaction cannot fail later when it first writes.
+pub async fn begin_write(pool: &SqlitePool) -> Result<Transaction<'static, Sqlite>> {
+    Ok(pool.begin_with("BEGIN IMMEDIATE").await?)
+}
+
+/// Whether `err` is SQLite reporting the database busy or locked
+/// (`SQLITE_BUSY`, `SQLITE_LOCKED`, or one of their extended codes).
+pub fn is_busy(err: &anyhow::Error) -> bool {
+    let Some(sqlx::Error::Database(db)) = err.downcast_ref::<sqlx::Error>() else {
+        return false;
+    };
+    db.code()
+        .and_then(|code| code.parse::<i32>().ok())
+        .is_some_and(|code| matches!(code & 0xff, 5 | 6))
+}
+
+/// Run `op`, retrying it up to `retries` times while it fails because the
+/// database is busy, waiting 100 ms, 200 ms, 400 ms, … in between.
+///
+/// `op` must be safe to repeat: a failed attempt's transaction has been
+/// rolled back, but statements it ran outside a transaction have not.
+pub async fn retry_busy<T, F, Fut>(retries: u32, mut op: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut attempt = 0;
+    loop {
+        match op().await {
+            Err(e) if attempt < retries && is_busy(&e) => {
+                let backoff = RETRY_BACKOFF * 2u32.saturating_pow(attempt);
+                tracing::debug!(attempt, ?backoff, "database busy, retrying: {}", e);
+                tokio::time::sleep(backoff).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn immediate_writers_wait_and_busy_writes_retry() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join("ctx.sqlite");
+        let first = open(&path, Duration::from_millis(50), "balanced")
+            .await
+            .unwrap();
+        let second = open(&path, Duration::from_millis(50), "balanced")
+            .await
+            .unwrap();
+        sqlx::query("CREATE TABLE t (n INTEGER)")
+            .execute(&first)
+            .await
+            .unwrap();
+
+        let held = begin_write(&first).await.unwrap();
+        let err = begin_write(&second).await.unwrap_err();
+        assert!(is_busy(&err), "{:#}", err);
+
+        let release = async {
+            tokio::time::sleep(Duration::from_millis(150)).await;
+            held.commit().await.unwrap();
+        };
+        let write = retry_busy(3, || async {
+            let mut tx = begin_write(&second).await?;
+            sqlx::query("INSERT INTO t VALUES (1)")
+                .execute(&mut *tx)
+                .await?;
+            tx.commit().await?;
+            Ok(())
+        });
+        let ((), written) = tokio::join!(release, write);
+        written.unwrap();
+
+        assert!(!is_busy(&anyhow::anyhow!("database is locked")));
+    }
+
+    #[tokio::test]
+    async fn profiles_set_pragmas() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join("ctx.sqlite");
+        let pragma = |pool: SqlitePool, name: &'static str| async move {
+            sqlx::query_scalar::<_, i64>(&format!("PRAGMA {}", name))
+                .fetch_one(&pool)
+                .await
+                .unwrap()
+        };
+
+        let read = open(&path, DEFAULT_BUSY_TIMEOUT, "read-heavy")
+            .await
+            .unwrap();
+        assert_eq!(pragma(read.clone(), "mmap_size").await, 268_435_456);
+        assert_eq!(pragma(read.clone(), "cache_size").await, -65_536);
+        assert_eq!(pragma(read.clone(), "synchronous").await, 1);
+
+        let write = open(&path, DEFAULT_BUSY_TIMEOUT, "write-heavy")
+            .await
+            .unwrap();
+        assert_eq!(pragma(write.clone(), "wal_autocheckpoint").await, 10_000);
+
+        let balanced = open(&path, DEFAULT_BUSY_TIMEOUT, "balanced").await.unwrap();
+        assert_eq!(pragma(balanced.clone(), "synchronous").await, 2);
+    }
+}
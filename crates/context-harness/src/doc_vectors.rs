@@ -0,0 +1,206 @@
+//! Document-level vectors for routing semantic search.
+//!
+//! The SQLite vector scan reads every chunk vector for every query, which
+//! dominates search latency once a corpus reaches millions of chunks. With
+//! `[embedding] document_vectors` set, each document also gets one vector
+//! in the `document_vectors` table. Semantic and hybrid search rank those
+//! first and score only the chunk vectors of the closest
+//! `embedding.route_documents` documents (see
+//! [`crate::sqlite_store::SqliteStore::with_document_routing`]).
+//!
+//! | Method | Document vector |
+//! |--------|-----------------|
+//! | `mean` | Average of the document's normalized chunk vectors; no provider calls |
+//! | `title` | Embedding of the title and summary, or of the title and first chunk when there is no summary |
+//!
+//! # Freshness
+//!
+//! [`refresh`] runs after `ctx embed pending`, `ctx embed rebuild`, and
+//! inline embedding during sync. It rebuilds the vectors of documents whose
+//! chunk embeddings are newer than their document vector, or whose vector
+//! was built with another method or model, and drops the vectors of
+//! documents that no longer have chunk vectors. Documents without a
+//! document vector are always scanned, so routing never hides chunks
+//! embedded since the last refresh.
+//!
+//! Routing applies to the SQLite vector scan only; a `zvec` sidecar is
+//! already indexed.
+
+use anyhow::Result;
+use sqlx::{Row, SqlitePool};
+
+use context_harness_core::embedding::{blob_to_vec, vec_to_blob};
+
+use crate::config::Config;
+use crate::embedding::{self, EmbeddingProvider};
+use crate::migrate;
+
+/// Rebuild stale document vectors. Returns how many were written.
+///
+/// Does nothing when `embedding.document_vectors` is `"off"`. Failed
+/// provider batches for the `title` method are logged and left for the
+/// next refresh.
+///
+/// # Errors
+///
+/// Returns an error if the database can't be read or written.
+pub async fn refresh(
+    config: &Config,
+    pool: &SqlitePool,
+    provider: &dyn EmbeddingProvider,
+) -> Result<u64> {
+    let method = config.embedding.document_vectors.as_str();
+    if method == "off" {
+        return Ok(0);
+    }
+    migrate::create_document_vectors_table(pool).await?;
+
+    sqlx::query(
+        "DELETE FROM document_vectors WHERE NOT EXISTS \
+         (SELECT 1 FROM chunk_vectors cv WHERE cv.document_id = document_vectors.document_id)",
+    )
+    .execute(pool)
+    .await?;
+
+    let model = provider.model_name().to_string();
+    let stale: Vec<String> = sqlx::query_scalar(
+        "SELECT d.id FROM documents d \
+         WHERE EXISTS (SELECT 1 FROM chunk_vectors cv WHERE cv.document_id = d.id) \
+         AND NOT EXISTS (SELECT 1 FROM document_vectors dv WHERE dv.document_id = d.id \
+             AND dv.method = ? AND dv.model = ? AND dv.created_at >= \
+             (SELECT MAX(e.created_at) FROM embeddings e JOIN chunks c ON c.id = e.chunk_id \
+              WHERE c.document_id = d.id))",
+    )
+    .bind(method)
+    .bind(&model)
+    .fetch_all(pool)
+    .await?;
+
+    let mut written = 0u64;
+    if method == "mean" {
+        for doc_id in &stale {
+            let blobs: Vec<Vec<u8>> =
+                sqlx::query_scalar("SELECT embedding FROM chunk_vectors WHERE document_id = ?")
+                    .bind(doc_id)
+                    .fetch_all(pool)
+                    .await?;
+            let vectors: Vec<Vec<f32>> = blobs.iter().map(|b| blob_to_vec(b)).collect();
+            if let Some(mean) = mean_vector(&vectors) {
+                store(pool, doc_id, method, &model, &mean).await?;
+                written += 1;
+            }
+        }
+        return Ok(written);
+    }
+
+    for batch in stale.chunks(config.embedding.batch_size.max(1)) {
+        let mut texts = Vec::with_capacity(batch.len());
+        for doc_id in batch {
+            texts.push(title_text(pool, doc_id).await?);
+        }
+        match embedding::embed_texts(provider, &config.embedding, &texts).await {
+            Ok(vectors) => {
+                for (doc_id, vector) in batch.iter().zip(vectors.iter()) {
+                    store(pool, doc_id, method, &model, vector).await?;
+                    written += 1;
+                }
+            }
+            Err(e) => tracing::warn!("document vector batch failed: {}", e),
+        }
+    }
+    Ok(written)
+}
+
+/// The text embedded for a document by the `title` method.
+async fn title_text(pool: &SqlitePool, doc_id: &str) -> Result<String> {
+    let row = sqlx::query(
+        "SELECT d.title, d.summary, \
+         (SELECT c.text FROM chunks c WHERE c.document_id = d.id ORDER BY c.chunk_index LIMIT 1) \
+         AS first_chunk \
+         FROM documents d WHERE d.id = ?",
+    )
+    .bind(doc_id)
+    .fetch_one(pool)
+    .await?;
+    let title: Option<String> = row.get("title");
+    let summary: Option<String> = row.get("summary");
+    let first_chunk: Option<String> = row.get("first_chunk");
+    let body = summary
+        .filter(|s| !s.trim().is_empty())
+        .or(first_chunk)
+        .unwrap_or_default();
+    Ok(match title {
+        Some(title) if !title.trim().is_empty() => format!("{}\n\n{}", title, body),
+        _ => body,
+    })
+}
+
+async fn store(
+    pool: &SqlitePool,
+    doc_id: &str,
+    method: &str,
+    model: &str,
+    vector: &[f32],
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO document_vectors (document_id, method, model, dims, created_at, embedding)
+        VALUES (?, ?, ?, ?, ?, ?)
+        ON CONFLICT(document_id) DO UPDATE SET
+            method = excluded.method,
+            model = excluded.model,
+            dims = excluded.dims,
+            created_at = excluded.created_at,
+            embedding = excluded.embedding
+        "#,
+    )
+    .bind(doc_id)
+    .bind(method)
+    .bind(model)
+    .bind(vector.len() as i64)
+    .bind(chrono::Utc::now().timestamp())
+    .bind(vec_to_blob(vector))
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Average of `vectors` after scaling each to unit length, so long and
+/// short chunks weigh the same. `None` when there are no vectors of one
+/// common dimension.
+fn mean_vector(vectors: &[Vec<f32>]) -> Option<Vec<f32>> {
+    let dims = vectors.first()?.len();
+    if dims == 0 || vectors.iter().any(|v| v.len() != dims) {
+        return None;
+    }
+    let mut mean = vec![0.0f32; dims];
+    for vector in vectors {
+        let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+        if norm == 0.0 {
+            continue;
+        }
+        for (m, x) in mean.iter_mut().zip(vector) {
+            *m += x / norm;
+        }
+    }
+    let count = vectors.len() as f32;
+    mean.iter_mut().for_each(|m| *m /= count);
+    Some(mean)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mean_vector_weighs_chunks_equally() {
+        let mean = mean_vector(&[vec![2.0, 0.0], vec![0.0, 10.0]]).unwrap();
+        assert_eq!(mean, vec![0.5, 0.5]);
+    }
+
+    #[test]
+    fn mean_vector_rejects_mixed_dimensions() {
+        assert!(mean_vector(&[vec![1.0, 0.0], vec![1.0]]).is_none());
+        assert!(mean_vector(&[]).is_none());
+    }
+}
@@ -0,0 +1,206 @@
+//! Throttled, resumable downloads for remote connectors.
+//!
+//! Large initial syncs can saturate a slow uplink and, without any state,
+//! restart from zero after a dropped connection. This module provides the
+//! two pieces the S3 and Git connectors share:
+//!
+//! - [`BandwidthLimiter`] — caps download throughput at
+//!   `[sync] max_bandwidth_mbps` by sleeping between chunks.
+//! - [`DownloadCheckpoint`] — a small JSON file recording, per download,
+//!   how many bytes are already on disk, how many attempts were made, and
+//!   the last error. S3 uses it to resume objects with ranged `GET`s; Git
+//!   uses it to report an interrupted clone that is being resumed.
+//!
+//! # Configuration
+//!
+//! ```toml
+//! [sync]
+//! max_bandwidth_mbps = 20
+//! download_retries = 3
+//! ```
+//!
+//! Retries back off exponentially (1s, 2s, 4s, … capped at 30s). A download
+//! that still fails is skipped for this sync; its checkpoint entry keeps the
+//! partial data for the next one.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Longest wait between retries.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// Throughput cap for a sequence of downloads.
+///
+/// Tracks bytes received since creation and sleeps whenever the average
+/// rate would exceed the limit. An unlimited limiter never sleeps.
+#[derive(Debug)]
+pub struct BandwidthLimiter {
+    bytes_per_sec: Option<f64>,
+    started: Instant,
+    received: u64,
+}
+
+impl BandwidthLimiter {
+    /// Create a limiter for `mbps` megabits per second (`None` = unlimited).
+    pub fn new(mbps: Option<f64>) -> Self {
+        Self {
+            bytes_per_sec: mbps.map(|m| m * 1_000_000.0 / 8.0),
+            started: Instant::now(),
+            received: 0,
+        }
+    }
+
+    /// Account for `bytes` just received and wait if the cap is exceeded.
+    pub async fn consume(&mut self, bytes: usize) {
+        if let Some(wait) = self.record(bytes) {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Record `bytes` and return how long to pause, if at all.
+    fn record(&mut self, bytes: usize) -> Option<Duration> {
+        let rate = self.bytes_per_sec?;
+        self.received += bytes as u64;
+        let due = Duration::from_secs_f64(self.received as f64 / rate);
+        due.checked_sub(self.started.elapsed())
+            .filter(|wait| !wait.is_zero())
+    }
+}
+
+/// Delay before retry number `attempt` (1-based).
+pub fn retry_delay(attempt: u32) -> Duration {
+    let secs = 1u64 << attempt.saturating_sub(1).min(5);
+    Duration::from_secs(secs).min(MAX_RETRY_DELAY)
+}
+
+/// Progress of one download that has not completed yet.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct DownloadEntry {
+    /// Version of the remote content the partial data belongs to
+    /// (S3 ETag, Git branch). A different version discards the partial.
+    pub etag: String,
+    /// Bytes already on disk.
+    pub bytes: u64,
+    /// Failed attempts so far, across syncs.
+    pub attempts: u32,
+    /// Error from the most recent failed attempt.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
+
+/// Unfinished downloads for one connector instance, persisted as JSON.
+///
+/// Entries are removed once a download completes, so the file only lists
+/// work that is still outstanding.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DownloadCheckpoint {
+    /// Entries keyed by object key (S3) or operation (`"clone"` for Git).
+    pub entries: BTreeMap<String, DownloadEntry>,
+}
+
+impl DownloadCheckpoint {
+    /// Load the checkpoint at `path`; a missing or unreadable file is empty.
+    pub fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    /// Write the checkpoint, or remove the file when nothing is outstanding.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if self.entries.is_empty() {
+            if path.exists() {
+                std::fs::remove_file(path)
+                    .with_context(|| format!("Failed to remove {}", path.display()))?;
+            }
+            return Ok(());
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// The entry for `key`, reset if it belongs to another `etag`.
+    ///
+    /// Returns the entry and whether existing partial data is still valid.
+    pub fn entry(&mut self, key: &str, etag: &str) -> (&mut DownloadEntry, bool) {
+        let entry = self.entries.entry(key.to_string()).or_default();
+        let valid = entry.etag == etag;
+        if !valid {
+            *entry = DownloadEntry {
+                etag: etag.to_string(),
+                ..Default::default()
+            };
+        }
+        (entry, valid)
+    }
+
+    /// Record a failed attempt for `key`.
+    pub fn record_failure(&mut self, key: &str, bytes: u64, error: &anyhow::Error) -> u32 {
+        let entry = self.entries.entry(key.to_string()).or_default();
+        entry.bytes = bytes;
+        entry.attempts += 1;
+        entry.last_error = Some(format!("{:#}", error));
+        entry.attempts
+    }
+
+    /// Forget `key` after it completed.
+    pub fn complete(&mut self, key: &str) {
+        self.entries.remove(key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limiter_waits_once_rate_is_exceeded() {
+        let mut unlimited = BandwidthLimiter::new(None);
+        assert_eq!(unlimited.record(10_000_000), None);
+
+        // 8 Mbit/s = 1 MB/s: 2 MB received immediately is ~2s ahead.
+        let mut limiter = BandwidthLimiter::new(Some(8.0));
+        let wait = limiter.record(2_000_000).unwrap();
+        assert!(wait > Duration::from_millis(1900) && wait <= Duration::from_secs(2));
+    }
+
+    #[test]
+    fn retry_delay_backs_off_and_caps() {
+        assert_eq!(retry_delay(1), Duration::from_secs(1));
+        assert_eq!(retry_delay(3), Duration::from_secs(4));
+        assert_eq!(retry_delay(20), MAX_RETRY_DELAY);
+    }
+
+    #[test]
+    fn checkpoint_round_trips_and_resets_on_new_etag() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("downloads.json");
+
+        let mut checkpoint = DownloadCheckpoint::default();
+        checkpoint.entry("a.md", "v1");
+        let attempts = checkpoint.record_failure("a.md", 512, &anyhow::anyhow!("reset"));
+        assert_eq!(attempts, 1);
+        checkpoint.save(&path).unwrap();
+
+        let mut loaded = DownloadCheckpoint::load(&path);
+        let (entry, valid) = loaded.entry("a.md", "v1");
+        assert!(valid);
+        assert_eq!(entry.bytes, 512);
+        assert_eq!(entry.last_error.as_deref(), Some("reset"));
+
+        let (entry, valid) = loaded.entry("a.md", "v2");
+        assert!(!valid);
+        assert_eq!(entry.attempts, 0);
+
+        loaded.complete("a.md");
+        loaded.save(&path).unwrap();
+        assert!(!path.exists());
+    }
+}
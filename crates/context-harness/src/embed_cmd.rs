@@ -4,32 +4,66 @@
 //!
 //! - **[`run_embed_pending`]** — backfill missing or stale embeddings
 //! - **[`run_embed_rebuild`]** — delete and regenerate all embeddings
-//! - **[`embed_chunks_inline`]** — embed chunks during sync (non-fatal)
+//! - **[`InlineEmbedder`]** — embed chunks during sync (non-fatal)
+//!
+//! All three refresh document vectors afterwards when
+//! `embedding.document_vectors` is enabled (see [`crate::doc_vectors`]).
 //!
 //! # Staleness Detection
 //!
-//! Each chunk's text is hashed (SHA-256). When the hash in the `embeddings`
-//! table differs from the chunk's current hash, the embedding is considered
-//! stale and will be re-generated by `embed pending`.
+//! Each embedding row records the model, dims, chunk text hash (SHA-256),
+//! and the chunker fingerprint ([`crate::chunk::config_fingerprint`]) it was
+//! built with. When any of them differs from the current chunk and config,
+//! the embedding is considered stale and will be re-generated by
+//! `embed pending`. `ctx stats` shows the breakdown by reason.
+//!
+//! The expected fingerprint is per source: `[chunking]` merged with the
+//! connector's `chunking` overrides. Chunks record the fingerprint they were
+//! produced under too, so chunks left over from older settings are reported
+//! as needing a re-sync.
 //!
 //! # Batching
 //!
 //! Embeddings are generated in batches (configurable via `embedding.batch_size`
 //! or `--batch-size` flag). Each batch is a single API call to the embedding
 //! provider. Failed batches are logged but don't abort the entire operation.
+//!
+//! # Jobs
+//!
+//! `pending` and `rebuild` (dry runs excepted) run as `embed` jobs, which
+//! `ctx jobs` can pause and cancel between batches (see [`crate::jobs`]).
+//! A cancelled run keeps the embeddings written so far and fails; `ctx
+//! embed pending` embeds the rest.
+//!
+//! # Notifications
+//!
+//! When `pending` or `rebuild` finishes (dry runs excepted), the totals are
+//! sent to the matching `[notifications]` webhooks. A run with any failed
+//! chunks is reported as a failure.
 
 use anyhow::{bail, Result};
 use context_harness_core::store::Store;
+use sqlx::SqlitePool;
+use std::collections::BTreeMap;
+use std::time::Instant;
 
-use crate::app_store::{hash_text, AppStore, SqliteAppStore};
+use crate::app_store::{hash_text, AppStore, EmbeddingFreshness, SqliteAppStore};
+use crate::chunk;
 use crate::config::Config;
+use crate::doc_vectors;
 use crate::embedding;
+use crate::jobs::{Job, JobControl};
+use crate::models::Chunk;
+use crate::notify::{self, RunReport};
+use crate::search_cache;
+use crate::traits::CancellationToken;
 
 /// Backfill embeddings for chunks that are missing or have stale hashes.
 ///
 /// Finds all chunks where either:
 /// 1. No embedding exists for the current model, or
-/// 2. The embedding's stored hash doesn't match the chunk's current text hash.
+/// 2. The embedding's stored hash doesn't match the chunk's current text hash, or
+/// 3. The embedding's dims or chunker fingerprint don't match the current config.
 ///
 /// # Arguments
 ///
@@ -46,6 +80,39 @@ pub async fn run_embed_pending(
     limit: Option<usize>,
     batch_size_override: Option<usize>,
     dry_run: bool,
+) -> Result<()> {
+    let started = Instant::now();
+    let mut totals = EmbedTotals::default();
+    let job = if dry_run || !config.embedding.is_enabled() {
+        None
+    } else {
+        Some(Job::start(config, "embed", "pending", CancellationToken::new()).await?)
+    };
+    let result = embed_pending(
+        config,
+        limit,
+        batch_size_override,
+        dry_run,
+        job.as_ref().map(Job::control),
+        &mut totals,
+    )
+    .await;
+    if let Some(job) = job {
+        job.finish(&result).await;
+    }
+    if !dry_run {
+        totals.notify(config, "pending", started, &result).await;
+    }
+    result
+}
+
+async fn embed_pending(
+    config: &Config,
+    limit: Option<usize>,
+    batch_size_override: Option<usize>,
+    dry_run: bool,
+    job: Option<JobControl>,
+    totals: &mut EmbedTotals,
 ) -> Result<()> {
     if !config.embedding.is_enabled() {
         bail!("Embedding provider is disabled. Set [embedding] provider in config.");
@@ -56,26 +123,38 @@ pub async fn run_embed_pending(
     let store = SqliteAppStore::connect(config).await?;
     let batch_size = batch_size_override.unwrap_or(config.embedding.batch_size);
 
-    // Find chunks missing embeddings or with stale hashes
-    let pending = store.find_pending_chunks(&model_name, limit).await?;
+    // Find chunks missing embeddings or with stale hashes/fingerprints
+    let pending = store
+        .find_pending_chunks(&model_name, provider.dims(), limit)
+        .await?;
+    let freshness = store
+        .embedding_freshness(&model_name, provider.dims())
+        .await?;
 
     if dry_run {
         println!("embed pending (dry-run)");
         println!("  chunks needing embeddings: {}", pending.len());
+        print_stale_reasons(&freshness);
         return Ok(());
     }
 
     if pending.is_empty() {
         println!("embed pending");
         println!("  all chunks up to date");
+        refresh_document_vectors(config, store.pool(), provider.as_ref()).await?;
+        store.close().await;
         return Ok(());
     }
 
     let total = pending.len();
-    let mut embedded = 0u64;
-    let mut failed = 0u64;
+    totals.total = total as u64;
 
     for batch in pending.chunks(batch_size) {
+        if let Some(ref job) = job {
+            if !job.proceed().await {
+                break;
+            }
+        }
         let texts: Vec<String> = batch.iter().map(|p| p.text.clone()).collect();
 
         match embedding::embed_texts(provider.as_ref(), &config.embedding, &texts).await {
@@ -91,20 +170,34 @@ pub async fn run_embed_pending(
                             &item.text_hash,
                         )
                         .await?;
-                    embedded += 1;
+                    totals.embedded += 1;
                 }
             }
             Err(e) => {
-                eprintln!("Warning: embedding batch failed: {}", e);
-                failed += batch.len() as u64;
+                tracing::warn!("embedding batch failed: {}", e);
+                totals.failed += batch.len() as u64;
             }
         }
     }
 
+    if totals.embedded > 0 {
+        search_cache::bump_index_generation(store.pool()).await?;
+    }
+    if job.as_ref().is_some_and(JobControl::is_cancelled) {
+        store.close().await;
+        bail!(
+            "Embedding cancelled after {} of {} chunks; run `ctx embed pending` to embed the rest.",
+            totals.embedded + totals.failed,
+            total
+        );
+    }
+
     println!("embed pending");
     println!("  total pending: {}", total);
-    println!("  embedded: {}", embedded);
-    println!("  failed: {}", failed);
+    print_stale_reasons(&freshness);
+    println!("  embedded: {}", totals.embedded);
+    println!("  failed: {}", totals.failed);
+    refresh_document_vectors(config, store.pool(), provider.as_ref()).await?;
 
     store.close().await;
     Ok(())
@@ -124,6 +217,33 @@ pub async fn run_embed_pending(
 ///
 /// Returns an error if the embedding provider is disabled.
 pub async fn run_embed_rebuild(config: &Config, batch_size_override: Option<usize>) -> Result<()> {
+    let started = Instant::now();
+    let mut totals = EmbedTotals::default();
+    let job = if config.embedding.is_enabled() {
+        Some(Job::start(config, "embed", "rebuild", CancellationToken::new()).await?)
+    } else {
+        None
+    };
+    let result = embed_rebuild(
+        config,
+        batch_size_override,
+        job.as_ref().map(Job::control),
+        &mut totals,
+    )
+    .await;
+    if let Some(job) = job {
+        job.finish(&result).await;
+    }
+    totals.notify(config, "rebuild", started, &result).await;
+    result
+}
+
+async fn embed_rebuild(
+    config: &Config,
+    batch_size_override: Option<usize>,
+    job: Option<JobControl>,
+    totals: &mut EmbedTotals,
+) -> Result<()> {
     if !config.embedding.is_enabled() {
         bail!("Embedding provider is disabled. Set [embedding] provider in config.");
     }
@@ -139,19 +259,26 @@ pub async fn run_embed_rebuild(config: &Config, batch_size_override: Option<usiz
     println!("embed rebuild — cleared existing embeddings");
 
     // Get all chunks
-    let all_chunks = store.find_pending_chunks(&model_name, None).await?;
+    let all_chunks = store
+        .find_pending_chunks(&model_name, provider.dims(), None)
+        .await?;
 
     if all_chunks.is_empty() {
+        search_cache::bump_index_generation(store.pool()).await?;
         println!("  no chunks to embed");
         store.close().await;
         return Ok(());
     }
 
     let total = all_chunks.len();
-    let mut embedded = 0u64;
-    let mut failed = 0u64;
+    totals.total = total as u64;
 
     for batch in all_chunks.chunks(batch_size) {
+        if let Some(ref job) = job {
+            if !job.proceed().await {
+                break;
+            }
+        }
         let texts: Vec<String> = batch.iter().map(|p| p.text.clone()).collect();
 
         match embedding::embed_texts(provider.as_ref(), &config.embedding, &texts).await {
@@ -167,64 +294,216 @@ pub async fn run_embed_rebuild(config: &Config, batch_size_override: Option<usiz
                             &item.text_hash,
                         )
                         .await?;
-                    embedded += 1;
+                    totals.embedded += 1;
                 }
             }
             Err(e) => {
-                eprintln!("Warning: embedding batch failed: {}", e);
-                failed += batch.len() as u64;
+                tracing::warn!("embedding batch failed: {}", e);
+                totals.failed += batch.len() as u64;
             }
         }
     }
 
+    search_cache::bump_index_generation(store.pool()).await?;
+    if job.as_ref().is_some_and(JobControl::is_cancelled) {
+        store.close().await;
+        bail!(
+            "Embedding rebuild cancelled after {} of {} chunks; run `ctx embed pending` to embed the rest.",
+            totals.embedded + totals.failed,
+            total
+        );
+    }
+
     println!("embed rebuild");
     println!("  total chunks: {}", total);
-    println!("  embedded: {}", embedded);
-    println!("  failed: {}", failed);
+    println!("  embedded: {}", totals.embedded);
+    println!("  failed: {}", totals.failed);
+    refresh_document_vectors(config, store.pool(), provider.as_ref()).await?;
 
     store.close().await;
     Ok(())
 }
 
-/// Embed chunks during sync (inline). Non-fatal on failure.
-///
-/// Called by [`crate::ingest::run_sync`] after chunking each document.
-/// Checks each chunk for existing, up-to-date embeddings before
-/// calling the provider, avoiding redundant API calls.
+/// Rebuild stale document vectors (see [`doc_vectors`]) and report how
+/// many were written, when `embedding.document_vectors` is enabled.
+async fn refresh_document_vectors(
+    config: &Config,
+    pool: &SqlitePool,
+    provider: &dyn embedding::EmbeddingProvider,
+) -> Result<()> {
+    if config.embedding.document_vectors == "off" {
+        return Ok(());
+    }
+    let written = doc_vectors::refresh(config, pool, provider).await?;
+    if written > 0 {
+        search_cache::bump_index_generation(pool).await?;
+    }
+    println!("  document vectors refreshed: {}", written);
+    Ok(())
+}
+
+/// Chunk counts of an `embed pending` or `embed rebuild` run.
+#[derive(Default)]
+struct EmbedTotals {
+    total: u64,
+    embedded: u64,
+    failed: u64,
+}
+
+impl EmbedTotals {
+    async fn notify(&self, config: &Config, target: &str, started: Instant, result: &Result<()>) {
+        let counts = BTreeMap::from([
+            ("total".to_string(), self.total),
+            ("embedded".to_string(), self.embedded),
+            ("failed".to_string(), self.failed),
+        ]);
+        let mut errors = Vec::new();
+        if self.failed > 0 {
+            errors.push(format!("{} chunks failed to embed", self.failed));
+        }
+        let report = RunReport::new("embed", target, started.elapsed(), counts, errors, result);
+        notify::send(config, &report).await;
+    }
+}
+
+/// Print why existing embeddings are stale, one line per non-zero reason.
+fn print_stale_reasons(freshness: &EmbeddingFreshness) {
+    for (reason, n) in [
+        ("missing", freshness.missing),
+        ("stale (model changed)", freshness.stale_model),
+        ("stale (dims changed)", freshness.stale_dims),
+        ("stale (chunking changed)", freshness.stale_chunker),
+        ("stale (text changed)", freshness.stale_text),
+    ] {
+        if n > 0 {
+            println!("    {}: {}", reason, n);
+        }
+    }
+    if freshness.resync > 0 {
+        println!(
+            "    chunks from older chunking settings (run ctx sync to re-chunk): {}",
+            freshness.resync
+        );
+    }
+}
+
+/// Embeds chunks as `ctx sync` stores them. Non-fatal on failure.
 ///
-/// # Returns
+/// One embedder (and one provider) serves a whole sync run. Chunks from
+/// consecutive documents are queued and sent to the provider in
+/// `embedding.batch_size` batches, so small documents don't each cost a
+/// request. [`crate::ingest`] flushes the queue after every connector.
 ///
-/// A tuple `(embedded, pending)`:
-/// - `embedded` — number of chunks successfully embedded (or already up-to-date)
-/// - `pending` — number of chunks that failed to embed
-pub async fn embed_chunks_inline(
-    config: &Config,
-    store: &impl AppStore,
-    chunks: &[crate::models::Chunk],
-) -> (u64, u64) {
-    if !config.embedding.is_enabled() {
-        return (0, 0);
+/// Chunks whose stored embedding already matches their text hash are
+/// counted as embedded without calling the provider.
+pub struct InlineEmbedder {
+    provider: Option<Box<dyn embedding::EmbeddingProvider>>,
+    queue: Vec<Chunk>,
+}
+
+impl InlineEmbedder {
+    /// Create an embedder for a sync run, or `None` when embeddings are
+    /// disabled.
+    pub fn new(config: &Config) -> Option<Self> {
+        if !config.embedding.is_enabled() {
+            return None;
+        }
+        let provider = match embedding::create_provider(&config.embedding) {
+            Ok(p) => Some(p),
+            Err(e) => {
+                tracing::warn!("could not create embedding provider: {}", e);
+                None
+            }
+        };
+        Some(Self {
+            provider,
+            queue: Vec::new(),
+        })
     }
 
-    let provider = match embedding::create_provider(&config.embedding) {
-        Ok(p) => p,
-        Err(e) => {
-            eprintln!("Warning: could not create embedding provider: {}", e);
-            return (0, chunks.len() as u64);
+    /// Fail if stored vectors have another dimension than this embedder's
+    /// provider (see [`embedding::check_stored_dims`]).
+    pub async fn check_dims(&self, config: &Config, pool: &SqlitePool) -> Result<()> {
+        match self.provider {
+            Some(ref provider) => {
+                embedding::check_stored_dims(pool, &config.embedding, provider.dims()).await
+            }
+            None => Ok(()),
         }
-    };
+    }
 
-    let model_name = provider.model_name().to_string();
-    let mut embedded = 0u64;
-    let mut pending = 0u64;
+    /// Queue a document's freshly stored chunks, embedding full batches.
+    ///
+    /// Queued chunks from an earlier version of the same document were
+    /// replaced in the database and are dropped.
+    ///
+    /// Returns `(embedded, pending)` for the batches sent.
+    pub async fn push(
+        &mut self,
+        config: &Config,
+        store: &impl AppStore,
+        chunks: Vec<Chunk>,
+    ) -> (u64, u64) {
+        if let Some(first) = chunks.first() {
+            let doc_id = first.document_id.clone();
+            self.queue.retain(|c| c.document_id != doc_id);
+        }
+        self.queue.extend(chunks);
+
+        let batch_size = config.embedding.batch_size.max(1);
+        let (mut embedded, mut pending) = (0, 0);
+        while self.queue.len() >= batch_size {
+            let batch: Vec<Chunk> = self.queue.drain(..batch_size).collect();
+            let (ok, failed) = self.embed_batch(config, store, &batch).await;
+            embedded += ok;
+            pending += failed;
+        }
+        (embedded, pending)
+    }
+
+    /// Rebuild stale document vectors after inline embedding. Failures are
+    /// logged; `ctx embed pending` retries them.
+    pub async fn refresh_document_vectors(&self, config: &Config, pool: &SqlitePool) {
+        let Some(ref provider) = self.provider else {
+            return;
+        };
+        if let Err(e) = doc_vectors::refresh(config, pool, provider.as_ref()).await {
+            tracing::warn!("document vector refresh failed: {}", e);
+        }
+    }
+
+    /// Embed every queued chunk. Returns `(embedded, pending)`.
+    pub async fn flush(&mut self, config: &Config, store: &impl AppStore) -> (u64, u64) {
+        let queue = std::mem::take(&mut self.queue);
+        let (mut embedded, mut pending) = (0, 0);
+        for batch in queue.chunks(config.embedding.batch_size.max(1)) {
+            let (ok, failed) = self.embed_batch(config, store, batch).await;
+            embedded += ok;
+            pending += failed;
+        }
+        (embedded, pending)
+    }
+
+    async fn embed_batch(
+        &self,
+        config: &Config,
+        store: &impl AppStore,
+        batch: &[Chunk],
+    ) -> (u64, u64) {
+        let Some(ref provider) = self.provider else {
+            return (0, batch.len() as u64);
+        };
+        let model_name = provider.model_name();
+        let mut embedded = 0u64;
+        let mut pending = 0u64;
 
-    for batch in chunks.chunks(config.embedding.batch_size) {
         // Check which chunks need embedding
         let mut need_embedding = Vec::new();
         for chunk in batch {
-            let text_hash = hash_text(&chunk.text);
+            let text = chunk::embedding_text(&chunk.text, &chunk.metadata);
+            let text_hash = hash_text(&text);
             let existing = store
-                .get_embedding_hash(&chunk.id, &model_name)
+                .get_embedding_hash(&chunk.id, model_name, provider.dims())
                 .await
                 .unwrap_or(None);
 
@@ -234,30 +513,33 @@ pub async fn embed_chunks_inline(
                 continue;
             }
 
-            need_embedding.push((chunk, text_hash));
+            need_embedding.push((chunk, text, text_hash));
         }
 
         if need_embedding.is_empty() {
-            continue;
+            return (embedded, pending);
         }
 
-        let texts: Vec<String> = need_embedding.iter().map(|(c, _)| c.text.clone()).collect();
+        let texts: Vec<String> = need_embedding
+            .iter()
+            .map(|(_, text, _)| text.clone())
+            .collect();
 
         match embedding::embed_texts(provider.as_ref(), &config.embedding, &texts).await {
             Ok(vectors) => {
-                for ((chunk, text_hash), vec) in need_embedding.iter().zip(vectors.iter()) {
+                for ((chunk, _, text_hash), vec) in need_embedding.iter().zip(vectors.iter()) {
                     if let Err(e) = store
                         .upsert_embedding(
                             &chunk.id,
                             &chunk.document_id,
                             vec,
-                            &model_name,
+                            model_name,
                             provider.dims(),
                             text_hash,
                         )
                         .await
                     {
-                        eprintln!("Warning: failed to store embedding for {}: {}", chunk.id, e);
+                        tracing::warn!("failed to store embedding for {}: {}", chunk.id, e);
                         pending += 1;
                     } else {
                         embedded += 1;
@@ -265,11 +547,11 @@ pub async fn embed_chunks_inline(
                 }
             }
             Err(e) => {
-                eprintln!("Warning: embedding batch failed: {}", e);
+                tracing::warn!("embedding batch failed: {}", e);
                 pending += need_embedding.len() as u64;
             }
         }
-    }
 
-    (embedded, pending)
+        (embedded, pending)
+    }
 }
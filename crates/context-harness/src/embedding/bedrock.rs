@@ -0,0 +1,284 @@
+//! AWS Bedrock embedding provider.
+//!
+//! Calls `POST /model/<model>/invoke` on the Bedrock runtime endpoint
+//! (`https://bedrock-runtime.<region>.amazonaws.com`, or `[embedding] url`),
+//! signed with SigV4 via [`crate::aws_sigv4`].
+//!
+//! # Models
+//!
+//! | Family | Model IDs | Default dims | Requests |
+//! |--------|-----------|--------------|----------|
+//! | Titan | `amazon.titan-embed-text-v1` | 1536 | One text per call |
+//! | Titan v2 | `amazon.titan-embed-text-v2:0` | 1024 (256, 512) | One text per call, `dimensions` + `normalize` |
+//! | Cohere | `cohere.embed-english-v3`, `cohere.embed-multilingual-v3` | 1024 | Up to 96 texts per call |
+//!
+//! Cohere models are told whether they embed a `search_query` or a
+//! `search_document`. Model IDs with a cross-region inference profile
+//! prefix (`us.`, `eu.`, …) are accepted too.
+//!
+//! Transient errors (HTTP 429 throttling, 5xx, network) are retried with
+//! the same exponential backoff as the other hosted providers.
+
+use anyhow::{bail, Context, Result};
+use std::time::Duration;
+
+use super::{send_with_retry, EmbeddingProvider};
+use crate::aws_sigv4::{self, uri_encode, AwsCredentials};
+use crate::config::EmbeddingConfig;
+
+/// Most texts Cohere accepts in one request.
+const COHERE_MAX_TEXTS: usize = 96;
+
+/// Embedding model families served by Bedrock.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Family {
+    Titan { v2: bool },
+    Cohere,
+}
+
+impl Family {
+    fn of(model: &str) -> Result<Self> {
+        if model.contains("amazon.titan-embed-text-v2") {
+            Ok(Family::Titan { v2: true })
+        } else if model.contains("amazon.titan-embed-text") {
+            Ok(Family::Titan { v2: false })
+        } else if model.contains("cohere.embed") {
+            Ok(Family::Cohere)
+        } else {
+            bail!(
+                "Unsupported Bedrock embedding model '{}'. Supported: amazon.titan-embed-text-v1, \
+                 amazon.titan-embed-text-v2:0, cohere.embed-english-v3, cohere.embed-multilingual-v3",
+                model
+            )
+        }
+    }
+
+    fn default_dims(self) -> usize {
+        match self {
+            Family::Titan { v2: false } => 1536,
+            Family::Titan { v2: true } | Family::Cohere => 1024,
+        }
+    }
+}
+
+/// Embedding provider using AWS Bedrock.
+pub struct BedrockProvider {
+    model: String,
+    dims: usize,
+}
+
+impl BedrockProvider {
+    /// Create a Bedrock provider from configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `model` is missing or unsupported, no region is
+    /// configured, or no AWS credentials are available.
+    pub fn new(config: &EmbeddingConfig) -> Result<Self> {
+        let (model, _, dims) = resolve_model(config)?;
+        region(config)?;
+        credentials(config)?;
+        Ok(Self { model, dims })
+    }
+}
+
+/// The configured model, its family, and its dimensionality.
+fn resolve_model(config: &EmbeddingConfig) -> Result<(String, Family, usize)> {
+    let model = config
+        .model
+        .clone()
+        .ok_or_else(|| anyhow::anyhow!("embedding.model required for Bedrock provider"))?;
+    let family = Family::of(&model)?;
+    let dims = config.dims.unwrap_or(family.default_dims());
+    if family == (Family::Titan { v2: true }) && ![256, 512, 1024].contains(&dims) {
+        bail!(
+            "embedding.dims for Titan v2 must be 256, 512, or 1024, got {}",
+            dims
+        );
+    }
+    Ok((model, family, dims))
+}
+
+impl EmbeddingProvider for BedrockProvider {
+    fn model_name(&self) -> &str {
+        &self.model
+    }
+    fn dims(&self) -> usize {
+        self.dims
+    }
+}
+
+/// `[embedding] region`, then `AWS_REGION`, then `AWS_DEFAULT_REGION`.
+fn region(config: &EmbeddingConfig) -> Result<String> {
+    config
+        .region
+        .clone()
+        .or_else(|| std::env::var("AWS_REGION").ok())
+        .or_else(|| std::env::var("AWS_DEFAULT_REGION").ok())
+        .filter(|r| !r.is_empty())
+        .context("embedding.region not configured and AWS_REGION not set")
+}
+
+fn credentials(config: &EmbeddingConfig) -> Result<AwsCredentials> {
+    AwsCredentials::load(
+        config.access_key_id.as_deref(),
+        config.secret_access_key.as_deref(),
+        config.session_token.as_deref(),
+    )
+}
+
+/// Embed `texts` with Bedrock. `query` selects Cohere's `search_query`
+/// input type instead of `search_document`.
+pub async fn embed_bedrock(
+    config: &EmbeddingConfig,
+    texts: &[String],
+    query: bool,
+) -> Result<Vec<Vec<f32>>> {
+    let (model, family, dims) = resolve_model(config)?;
+    let client = BedrockClient {
+        http: reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .build()?,
+        creds: credentials(config)?,
+        region: region(config)?,
+        config,
+    };
+    let endpoint = client.endpoint();
+    let url = reqwest::Url::parse(&format!("{}/model/{}/invoke", endpoint, uri_encode(&model)))
+        .with_context(|| format!("invalid Bedrock endpoint '{}'", endpoint))?;
+
+    let mut embeddings = Vec::with_capacity(texts.len());
+    match family {
+        Family::Titan { v2 } => {
+            for text in texts {
+                let mut body = serde_json::json!({ "inputText": text });
+                if v2 {
+                    body["dimensions"] = serde_json::json!(dims);
+                    body["normalize"] = serde_json::json!(true);
+                }
+                let json = client.invoke(&url, &body).await?;
+                embeddings.push(parse_vector(json.get("embedding"))?);
+            }
+        }
+        Family::Cohere => {
+            for batch in texts.chunks(COHERE_MAX_TEXTS) {
+                let body = serde_json::json!({
+                    "texts": batch,
+                    "input_type": if query { "search_query" } else { "search_document" },
+                    "truncate": "END",
+                });
+                let json = client.invoke(&url, &body).await?;
+                embeddings.extend(parse_cohere_response(&json)?);
+            }
+        }
+    }
+    Ok(embeddings)
+}
+
+struct BedrockClient<'a> {
+    http: reqwest::Client,
+    creds: AwsCredentials,
+    region: String,
+    config: &'a EmbeddingConfig,
+}
+
+impl BedrockClient<'_> {
+    fn endpoint(&self) -> String {
+        match self.config.url {
+            Some(ref url) => url.trim_end_matches('/').to_string(),
+            None => format!("https://bedrock-runtime.{}.amazonaws.com", self.region),
+        }
+    }
+
+    /// Send one signed `InvokeModel` request, retrying transient errors.
+    async fn invoke(
+        &self,
+        url: &reqwest::Url,
+        body: &serde_json::Value,
+    ) -> Result<serde_json::Value> {
+        let payload = serde_json::to_vec(body)?;
+        let host = match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{}:{}", host, port),
+            (Some(host), None) => host.to_string(),
+            (None, _) => bail!("Bedrock endpoint has no host: {}", url),
+        };
+        // Non-S3 services sign each path segment URI-encoded twice.
+        let canonical_uri = url
+            .path()
+            .split('/')
+            .map(uri_encode)
+            .collect::<Vec<_>>()
+            .join("/");
+
+        send_with_retry(self.config.max_retries, "Bedrock", || {
+            let headers = aws_sigv4::sign(
+                "POST",
+                &host,
+                &canonical_uri,
+                &payload,
+                &self.creds,
+                &self.region,
+                "bedrock",
+                chrono::Utc::now(),
+            );
+            let mut req = self
+                .http
+                .post(url.clone())
+                .header("Content-Type", "application/json")
+                .header("Accept", "application/json")
+                .body(payload.clone());
+            for (name, value) in headers {
+                req = req.header(name, value);
+            }
+            req
+        })
+        .await
+    }
+}
+
+fn parse_vector(value: Option<&serde_json::Value>) -> Result<Vec<f32>> {
+    Ok(value
+        .and_then(|v| v.as_array())
+        .ok_or_else(|| anyhow::anyhow!("Invalid Bedrock response: missing embedding"))?
+        .iter()
+        .map(|v| v.as_f64().unwrap_or(0.0) as f32)
+        .collect())
+}
+
+/// Cohere returns `embeddings` as a list of vectors, or as
+/// `{"float": [...]}` when embedding types were requested.
+fn parse_cohere_response(json: &serde_json::Value) -> Result<Vec<Vec<f32>>> {
+    let embeddings = json.get("embeddings");
+    let list = embeddings
+        .and_then(|e| e.get("float"))
+        .or(embeddings)
+        .and_then(|e| e.as_array())
+        .ok_or_else(|| anyhow::anyhow!("Invalid Bedrock response: missing embeddings array"))?;
+    list.iter().map(|v| parse_vector(Some(v))).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn model_families_and_default_dims() {
+        let titan2 = Family::of("amazon.titan-embed-text-v2:0").unwrap();
+        assert_eq!(titan2, Family::Titan { v2: true });
+        assert_eq!(titan2.default_dims(), 1024);
+        assert_eq!(
+            Family::of("amazon.titan-embed-text-v1")
+                .unwrap()
+                .default_dims(),
+            1536
+        );
+        assert_eq!(
+            Family::of("us.cohere.embed-multilingual-v3").unwrap(),
+            Family::Cohere
+        );
+        assert!(Family::of("anthropic.claude-v2").is_err());
+
+        let typed = serde_json::json!({"embeddings": {"float": [[0.5, 1.0]]}});
+        assert_eq!(parse_cohere_response(&typed).unwrap(), vec![vec![0.5, 1.0]]);
+    }
+}
@@ -5,12 +5,40 @@
 //! - **[`OpenAIProvider`]** — calls the OpenAI embeddings API with batching, retry, and backoff.
 //! - **[`OllamaProvider`]** — calls a local Ollama instance's `/api/embed` endpoint.
 //! - **[`LocalProvider`]** — runs models locally via fastembed (primary) or tract (musl/Intel Mac); no network calls after model download.
+//! - **[`BedrockProvider`]** — calls AWS Bedrock (Titan, Cohere) with SigV4-signed requests (see [`bedrock`]).
+//! - **[`VertexProvider`]** — calls Google Vertex AI (`text-embedding-005`, …) with a service account (see [`vertex`]).
 //!
 //! Also provides vector utilities for working with sqlite-vec:
 //! - [`cosine_similarity`] — compute similarity between two embedding vectors
 //! - [`vec_to_blob`] — encode a `Vec<f32>` as little-endian bytes for SQLite BLOB storage
 //! - [`blob_to_vec`] — decode a SQLite BLOB back into a `Vec<f32>`
 //!
+//! # Dimension Guardrails
+//!
+//! Vectors of different dimensions cannot be compared: after `[embedding]
+//! dims` or the model changes size, similarity against the old vectors is
+//! meaningless. [`create_checked_provider`] compares the provider's dims with
+//! the vectors in the `embeddings` table and fails with instructions to
+//! re-embed (`ctx embed rebuild`, or `ctx embed pending`, which re-embeds
+//! stale vectors) instead of returning junk results. Semantic and hybrid
+//! search and inline embedding during sync use it; `--force` (or
+//! `[embedding] allow_dims_mismatch = true`) skips the check.
+//!
+//! # Fallback Chain
+//!
+//! With `[embedding] providers = ["openai", "local"]`, [`embed_texts`] and
+//! [`embed_query`] try each provider in order (after its own retries) and
+//! return the first that succeeds. A call served by a fallback, and each
+//! provider that failed, is logged as a warning, and every attempt gets its
+//! own `embedding.request` span naming its provider. [`provider_counters`]
+//! counts calls served and failed per provider for `/metrics`.
+//!
+//! All providers in the chain must return vectors of one dimension:
+//! [`create_provider`] compares their dims, and a fallback whose vectors
+//! differ from the primary's is an error rather than a silent mix. Equal
+//! dims are not enough for meaningful scores, though: fallbacks should
+//! serve the same model (say `nomic-embed-text` on Ollama and locally).
+//!
 //! # Provider Selection
 //!
 //! Use [`create_provider`] to instantiate the appropriate provider based
@@ -26,35 +54,63 @@
 //!
 //! # Retry Strategy
 //!
-//! The OpenAI and Ollama providers use exponential backoff for transient errors:
+//! The hosted providers use exponential backoff for transient errors:
 //! - HTTP 429 (rate limited) and 5xx (server error) → retry
 //! - HTTP 4xx (client error, not 429) → fail immediately
 //! - Network errors → retry
 //! - Backoff: 1s, 2s, 4s, 8s, 16s, 32s (capped at 2^5)
 
+pub mod bedrock;
 #[cfg(feature = "local-embeddings-tract")]
 mod local_tract;
+pub mod vertex;
 
 use anyhow::{bail, Result};
+use sqlx::SqlitePool;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
 use std::time::Duration;
+use tracing::Instrument;
 
 use crate::config::EmbeddingConfig;
+use crate::telemetry;
 
 #[allow(unused_imports)]
 pub use context_harness_core::embedding::{
     blob_to_vec, cosine_similarity, vec_to_blob, EmbeddingProvider,
 };
 
-/// Embed a batch of texts using the configured provider.
+pub use bedrock::BedrockProvider;
+pub use vertex::VertexProvider;
+
+/// Characters per token, the same estimate the chunker uses.
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Calls served and failed per provider since startup.
+static PROVIDER_COUNTERS: Mutex<BTreeMap<String, ProviderCounters>> = Mutex::new(BTreeMap::new());
+
+/// Embedding calls of one provider, counted by [`embed_texts`] and
+/// [`embed_query`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProviderCounters {
+    /// Calls this provider returned vectors for.
+    pub served: u64,
+    /// Calls that failed on this provider (and moved on to the next one,
+    /// if any).
+    pub failed: u64,
+}
+
+/// Embed a batch of document chunks using the configured provider.
 ///
-/// This is the main entry point for generating embeddings. It dispatches
-/// to the appropriate backend based on the config's `provider` field.
+/// This is the main entry point for generating embeddings. Each text is
+/// prepared with [`document_input`], then the batch is dispatched to the
+/// appropriate backend based on the config's `provider` field.
 ///
 /// # Arguments
 ///
-/// * `_provider` — Provider instance (used for metadata; dispatch is config-based).
+/// * `provider` — Provider instance (used for its dims; dispatch is config-based).
 /// * `config` — Embedding configuration with provider, model, and retry settings.
-/// * `texts` — Batch of text strings to embed.
+/// * `texts` — Batch of chunk texts to embed.
 ///
 /// # Returns
 ///
@@ -65,14 +121,113 @@ pub use context_harness_core::embedding::{
 /// - `"disabled"` provider: always returns an error.
 /// - `"openai"` provider: returns an error if the API key is missing,
 ///   the API returns a non-retryable error, or all retries are exhausted.
+/// - `"bedrock"` provider: the same, for the region and AWS credentials.
+/// - `"vertex"` provider: the same, for the service account key and project.
+/// - With `providers`, only when every provider in the chain fails, or a
+///   fallback returns vectors of other dims than `provider`.
 pub async fn embed_texts(
-    _provider: &dyn EmbeddingProvider,
+    provider: &dyn EmbeddingProvider,
+    config: &EmbeddingConfig,
+    texts: &[String],
+) -> Result<Vec<Vec<f32>>> {
+    let inputs: Vec<String> = texts
+        .iter()
+        .map(|text| document_input(config, text))
+        .collect();
+    embed_inputs(config, provider.dims(), &inputs, false).await
+}
+
+/// Send prepared inputs to each provider of the fallback chain in turn
+/// (see the module docs) until one succeeds. Vectors from a fallback must
+/// have `dims` entries, unless `dims` is `0`.
+async fn embed_inputs(
+    config: &EmbeddingConfig,
+    dims: usize,
+    texts: &[String],
+    query: bool,
+) -> Result<Vec<Vec<f32>>> {
+    let chain = config.chain();
+    let mut errors = Vec::new();
+    for (i, provider) in chain.iter().enumerate() {
+        match embed_with(provider, texts, query).await {
+            Ok(vectors) => {
+                count_call(&provider.provider, true);
+                if i == 0 {
+                    return Ok(vectors);
+                }
+                if let Some(v) = vectors.iter().find(|v| dims != 0 && v.len() != dims) {
+                    bail!(
+                        "fallback embedding provider '{}' returned {}-dim vectors, but '{}' \
+                         returns {}; set [embedding.fallback.{}] model and dims to match",
+                        provider.provider,
+                        v.len(),
+                        chain[0].provider,
+                        dims,
+                        provider.provider
+                    );
+                }
+                tracing::warn!(
+                    provider = %provider.provider,
+                    failed = %chain[..i]
+                        .iter()
+                        .map(|c| c.provider.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    inputs = texts.len(),
+                    "embedding call served by fallback provider"
+                );
+                return Ok(vectors);
+            }
+            Err(e) => {
+                count_call(&provider.provider, false);
+                if chain.len() == 1 {
+                    return Err(e);
+                }
+                tracing::warn!(
+                    provider = %provider.provider,
+                    error = %e,
+                    "embedding provider failed"
+                );
+                errors.push(format!("{}: {:#}", provider.provider, e));
+            }
+        }
+    }
+    bail!("all embedding providers failed: {}", errors.join("; "))
+}
+
+/// Send prepared inputs to one backend inside an `embedding.request` span
+/// (see [`crate::telemetry`]). `query` tells providers with separate query
+/// and document modes (Cohere on Bedrock, Vertex AI) which to use.
+async fn embed_with(
+    config: &EmbeddingConfig,
+    texts: &[String],
+    query: bool,
+) -> Result<Vec<Vec<f32>>> {
+    let span = telemetry::embedding_span(
+        &config.provider,
+        config.model.as_deref().unwrap_or_default(),
+        texts.len(),
+        query,
+    );
+    let result = send_inputs(config, texts, query)
+        .instrument(span.clone())
+        .await;
+    if result.is_err() {
+        span.record("otel.status_code", "error");
+    }
+    result
+}
+
+async fn send_inputs(
     config: &EmbeddingConfig,
     texts: &[String],
+    query: bool,
 ) -> Result<Vec<Vec<f32>>> {
     match config.provider.as_str() {
         "openai" => embed_openai(config, texts).await,
         "ollama" => embed_ollama(config, texts).await,
+        "bedrock" => bedrock::embed_bedrock(config, texts, query).await,
+        "vertex" => vertex::embed_vertex(config, texts, query).await,
         #[cfg(feature = "local-embeddings-fastembed")]
         "local" => embed_local_fastembed(config, texts).await,
         #[cfg(all(feature = "local-embeddings-tract", not(feature = "local-embeddings-fastembed")))]
@@ -86,22 +241,83 @@ pub async fn embed_texts(
     }
 }
 
-/// Embed a single query text.
+/// Embed a single search query.
 ///
-/// Convenience wrapper around [`embed_texts`] for single-text use cases
-/// (e.g. embedding a search query for semantic search).
+/// Like [`embed_texts`], but the text is prepared with [`query_input`], so
+/// models trained with asymmetric prefixes (E5, BGE) see a query prefix.
 pub async fn embed_query(
     provider: &dyn EmbeddingProvider,
     config: &EmbeddingConfig,
     text: &str,
 ) -> Result<Vec<f32>> {
-    let results = embed_texts(provider, config, &[text.to_string()]).await?;
+    let results = embed_inputs(config, provider.dims(), &[query_input(config, text)], true).await?;
     results
         .into_iter()
         .next()
         .ok_or_else(|| anyhow::anyhow!("Empty embedding response"))
 }
 
+fn count_call(provider: &str, served: bool) {
+    let mut counters = PROVIDER_COUNTERS.lock().unwrap_or_else(|e| e.into_inner());
+    let counter = counters.entry(provider.to_string()).or_default();
+    if served {
+        counter.served += 1;
+    } else {
+        counter.failed += 1;
+    }
+}
+
+/// Embedding calls per provider recorded by this process since startup.
+pub fn provider_counters() -> Vec<(String, ProviderCounters)> {
+    let counters = PROVIDER_COUNTERS.lock().unwrap_or_else(|e| e.into_inner());
+    counters.iter().map(|(k, v)| (k.clone(), *v)).collect()
+}
+
+/// The provider input for a document chunk: `[embedding] document_prefix`
+/// followed by the chunk, cut to `max_input_tokens`.
+pub fn document_input(config: &EmbeddingConfig, text: &str) -> String {
+    prepare_input(config, &config.document_prefix, text)
+}
+
+/// The provider input for a search query: `[embedding] query_prefix`
+/// followed by the query, cut to `max_input_tokens`.
+pub fn query_input(config: &EmbeddingConfig, text: &str) -> String {
+    prepare_input(config, &config.query_prefix, text)
+}
+
+fn prepare_input(config: &EmbeddingConfig, prefix: &str, text: &str) -> String {
+    let Some(max_tokens) = config.max_input_tokens else {
+        return format!("{}{}", prefix, text);
+    };
+    let budget = (max_tokens * CHARS_PER_TOKEN).saturating_sub(prefix.chars().count());
+    format!(
+        "{}{}",
+        prefix,
+        truncate_chars(text, budget, &config.truncate)
+    )
+}
+
+/// Cut `text` to at most `max_chars` characters, removing its `"end"`,
+/// `"start"`, or `"middle"`.
+fn truncate_chars(text: &str, max_chars: usize, strategy: &str) -> String {
+    let len = text.chars().count();
+    if len <= max_chars {
+        return text.to_string();
+    }
+    match strategy {
+        "start" => text.chars().skip(len - max_chars).collect(),
+        "middle" => {
+            let head = max_chars.div_ceil(2);
+            let tail = max_chars - head;
+            text.chars()
+                .take(head)
+                .chain(text.chars().skip(len - tail))
+                .collect()
+        }
+        _ => text.chars().take(max_chars).collect(),
+    }
+}
+
 // ============ Disabled Provider ============
 
 /// A no-op embedding provider that always returns errors.
@@ -124,7 +340,8 @@ impl EmbeddingProvider for DisabledProvider {
 /// Embedding provider using the OpenAI API.
 ///
 /// Calls the `POST /v1/embeddings` endpoint with the configured model.
-/// Requires the `OPENAI_API_KEY` environment variable to be set.
+/// Reads the API key from `[embedding] api_key` (which may be a `secret://`
+/// reference) or the `OPENAI_API_KEY` environment variable.
 ///
 /// # Features
 ///
@@ -144,7 +361,7 @@ impl OpenAIProvider {
     /// # Errors
     ///
     /// Returns an error if `model` or `dims` is not set in config,
-    /// or if `OPENAI_API_KEY` is not in the environment.
+    /// or if no API key is configured.
     pub fn new(config: &EmbeddingConfig) -> Result<Self> {
         let model = config
             .model
@@ -155,8 +372,10 @@ impl OpenAIProvider {
             .ok_or_else(|| anyhow::anyhow!("embedding.dims required for OpenAI provider"))?;
 
         // Verify API key is available
-        if std::env::var("OPENAI_API_KEY").is_err() {
-            bail!("OPENAI_API_KEY environment variable not set");
+        if openai_api_key(config)?.is_none() {
+            bail!(
+                "embedding.api_key not configured and OPENAI_API_KEY environment variable not set"
+            );
         }
 
         Ok(Self { model, dims })
@@ -172,6 +391,11 @@ impl EmbeddingProvider for OpenAIProvider {
     }
 }
 
+/// Resolve the OpenAI API key from config or `OPENAI_API_KEY`.
+fn openai_api_key(config: &EmbeddingConfig) -> Result<Option<String>> {
+    crate::secrets::resolve_or_env(config.api_key.as_deref(), "OPENAI_API_KEY")
+}
+
 /// Call the OpenAI embeddings API with retry/backoff.
 ///
 /// Sends a batch of texts to `POST https://api.openai.com/v1/embeddings`
@@ -182,8 +406,8 @@ impl EmbeddingProvider for OpenAIProvider {
 /// - HTTP 4xx (not 429) → fail immediately
 /// - Network error → retry
 async fn embed_openai(config: &EmbeddingConfig, texts: &[String]) -> Result<Vec<Vec<f32>>> {
-    let api_key =
-        std::env::var("OPENAI_API_KEY").map_err(|_| anyhow::anyhow!("OPENAI_API_KEY not set"))?;
+    let api_key = openai_api_key(config)?
+        .ok_or_else(|| anyhow::anyhow!("embedding.api_key or OPENAI_API_KEY not set"))?;
 
     let model = config
         .model
@@ -199,46 +423,54 @@ async fn embed_openai(config: &EmbeddingConfig, texts: &[String]) -> Result<Vec<
         "input": texts,
     });
 
+    let json = send_with_retry(config.max_retries, "OpenAI", || {
+        client
+            .post("https://api.openai.com/v1/embeddings")
+            .header("Authorization", format!("Bearer {}", api_key))
+            .json(&body)
+    })
+    .await?;
+    parse_openai_response(&json)
+}
+
+/// Send a request under the retry strategy in the module docs and return
+/// its JSON body.
+///
+/// `request` builds each attempt, so signed requests are signed afresh.
+/// `service` names the API in errors (`"OpenAI API error 400: …"`).
+async fn send_with_retry(
+    max_retries: u32,
+    service: &str,
+    request: impl Fn() -> reqwest::RequestBuilder,
+) -> Result<serde_json::Value> {
     let mut last_err = None;
 
-    for attempt in 0..=config.max_retries {
+    for attempt in 0..=max_retries {
         if attempt > 0 {
             // Exponential backoff: 1s, 2s, 4s, 8s, ...
             let delay = Duration::from_secs(1 << (attempt - 1).min(5));
             tokio::time::sleep(delay).await;
         }
 
-        let resp = client
-            .post("https://api.openai.com/v1/embeddings")
-            .header("Authorization", format!("Bearer {}", api_key))
-            .header("Content-Type", "application/json")
-            .json(&body)
-            .send()
-            .await;
-
-        match resp {
+        match request().send().await {
             Ok(response) => {
                 let status = response.status();
 
                 if status.is_success() {
-                    let json: serde_json::Value = response.json().await?;
-                    return parse_openai_response(&json);
+                    return Ok(response.json().await?);
                 }
 
+                let body_text = response.text().await.unwrap_or_default();
+                let err = anyhow::anyhow!("{} API error {}: {}", service, status, body_text);
+
                 // Rate limited or server error — retry
                 if status.as_u16() == 429 || status.is_server_error() {
-                    let body_text = response.text().await.unwrap_or_default();
-                    last_err = Some(anyhow::anyhow!(
-                        "OpenAI API error {}: {}",
-                        status,
-                        body_text
-                    ));
+                    last_err = Some(err);
                     continue;
                 }
 
                 // Client error (not 429) — don't retry
-                let body_text = response.text().await.unwrap_or_default();
-                bail!("OpenAI API error {}: {}", status, body_text);
+                return Err(err);
             }
             Err(e) => {
                 last_err = Some(e.into());
@@ -247,7 +479,7 @@ async fn embed_openai(config: &EmbeddingConfig, texts: &[String]) -> Result<Vec<
         }
     }
 
-    Err(last_err.unwrap_or_else(|| anyhow::anyhow!("Embedding failed after retries")))
+    Err(last_err.unwrap_or_else(|| anyhow::anyhow!("{} request failed after retries", service)))
 }
 
 /// Parse the OpenAI embeddings API response JSON.
@@ -548,16 +780,65 @@ async fn embed_local_tract(config: &EmbeddingConfig, texts: &[String]) -> Result
 /// | `"openai"` | [`OpenAIProvider`] |
 /// | `"ollama"` | [`OllamaProvider`] |
 /// | `"local"` | `LocalProvider` (fastembed or tract, see features) |
+/// | `"bedrock"` | [`BedrockProvider`] |
+/// | `"vertex"` | [`VertexProvider`] |
+///
+/// With a fallback chain (`providers`), every provider in it is created to
+/// compare dims, and the first that could be created is returned. One that
+/// cannot (a missing API key) is only logged, so calls still go to the
+/// providers that work.
 ///
 /// # Errors
 ///
 /// Returns an error for unknown provider names or if the provider
-/// cannot be initialized (missing config, API key, or feature flag).
+/// cannot be initialized (missing config, API key, or feature flag); with
+/// a chain, if none can be, or two return different dims.
 pub fn create_provider(config: &EmbeddingConfig) -> Result<Box<dyn EmbeddingProvider>> {
+    let chain = config.chain();
+    if chain.len() == 1 {
+        return create_single_provider(config);
+    }
+    let mut created: Vec<(&str, Box<dyn EmbeddingProvider>)> = Vec::new();
+    let mut errors = Vec::new();
+    for link in &chain {
+        match create_single_provider(link) {
+            Ok(provider) => created.push((link.provider.as_str(), provider)),
+            Err(e) => {
+                tracing::warn!(provider = %link.provider, error = %e, "embedding provider unavailable");
+                errors.push(format!("{}: {:#}", link.provider, e));
+            }
+        }
+    }
+    if created.is_empty() {
+        bail!(
+            "no embedding provider could be created: {}",
+            errors.join("; ")
+        );
+    }
+    let (first, first_provider) = &created[0];
+    if let Some((other, provider)) = created[1..]
+        .iter()
+        .find(|(_, p)| p.dims() != first_provider.dims())
+    {
+        bail!(
+            "embedding providers '{}' ({} dims) and '{}' ({} dims) return vectors of different \
+             sizes; every provider in [embedding] providers needs the same dims",
+            first,
+            first_provider.dims(),
+            other,
+            provider.dims()
+        );
+    }
+    Ok(created.swap_remove(0).1)
+}
+
+fn create_single_provider(config: &EmbeddingConfig) -> Result<Box<dyn EmbeddingProvider>> {
     match config.provider.as_str() {
         "disabled" => Ok(Box::new(DisabledProvider)),
         "openai" => Ok(Box::new(OpenAIProvider::new(config)?)),
         "ollama" => Ok(Box::new(OllamaProvider::new(config)?)),
+        "bedrock" => Ok(Box::new(BedrockProvider::new(config)?)),
+        "vertex" => Ok(Box::new(VertexProvider::new(config)?)),
         #[cfg(any(feature = "local-embeddings-fastembed", feature = "local-embeddings-tract"))]
         "local" => Ok(Box::new(LocalProvider::new(config)?)),
         #[cfg(not(any(feature = "local-embeddings-fastembed", feature = "local-embeddings-tract")))]
@@ -567,3 +848,157 @@ pub fn create_provider(config: &EmbeddingConfig) -> Result<Box<dyn EmbeddingProv
         other => bail!("Unknown embedding provider: {}", other),
     }
 }
+
+/// [`create_provider`], then [`check_stored_dims`] against `pool`.
+pub async fn create_checked_provider(
+    config: &EmbeddingConfig,
+    pool: &SqlitePool,
+) -> Result<Box<dyn EmbeddingProvider>> {
+    let provider = create_provider(config)?;
+    check_stored_dims(pool, config, provider.dims()).await?;
+    Ok(provider)
+}
+
+/// Fail when the `embeddings` table holds vectors whose dimension is not
+/// `dims`, unless `allow_dims_mismatch` is set.
+///
+/// The error names the stored and configured dims and how to re-embed.
+pub async fn check_stored_dims(
+    pool: &SqlitePool,
+    config: &EmbeddingConfig,
+    dims: usize,
+) -> Result<()> {
+    if config.allow_dims_mismatch {
+        return Ok(());
+    }
+    let mismatched: Vec<(i64, String, i64)> = sqlx::query_as(
+        "SELECT dims, model, COUNT(*) FROM embeddings WHERE dims != ? \
+         GROUP BY dims, model ORDER BY COUNT(*) DESC",
+    )
+    .bind(dims as i64)
+    .fetch_all(pool)
+    .await?;
+    let Some((stored_dims, model, _)) = mismatched.first() else {
+        return Ok(());
+    };
+    let count: i64 = mismatched.iter().map(|(_, _, n)| n).sum();
+    bail!(
+        "embedding dimension mismatch: the provider returns {}-dim vectors but {} stored embeddings \
+         have other dims (e.g. {} from model '{}'). Comparing them gives meaningless scores. \
+         Run `ctx embed rebuild` to re-embed everything, or `ctx embed pending` to re-embed \
+         the stale vectors; pass --force (or set [embedding] allow_dims_mismatch = true) to \
+         proceed anyway",
+        dims,
+        count,
+        stored_dims,
+        model
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(max_input_tokens: Option<usize>, truncate: &str) -> EmbeddingConfig {
+        EmbeddingConfig {
+            query_prefix: "query: ".to_string(),
+            document_prefix: "passage: ".to_string(),
+            max_input_tokens,
+            truncate: truncate.to_string(),
+            ..EmbeddingConfig::default()
+        }
+    }
+
+    #[test]
+    fn inputs_get_their_prefix() {
+        let cfg = config(None, "end");
+        assert_eq!(query_input(&cfg, "how to deploy"), "query: how to deploy");
+        assert_eq!(
+            document_input(&cfg, "Deploy with ctx."),
+            "passage: Deploy with ctx."
+        );
+        assert_eq!(
+            document_input(&EmbeddingConfig::default(), "unchanged"),
+            "unchanged"
+        );
+    }
+
+    #[test]
+    fn truncation_keeps_the_prefix_within_the_budget() {
+        // 4 tokens = 16 chars; "passage: " takes 9, leaving 7 for the text.
+        let text = "abcdefghijklmnop";
+        assert_eq!(
+            document_input(&config(Some(4), "end"), text),
+            "passage: abcdefg"
+        );
+        assert_eq!(
+            document_input(&config(Some(4), "start"), text),
+            "passage: jklmnop"
+        );
+        assert_eq!(
+            document_input(&config(Some(4), "middle"), text),
+            "passage: abcdnop"
+        );
+        assert_eq!(
+            document_input(&config(Some(8), "end"), text),
+            format!("passage: {}", text)
+        );
+    }
+
+    #[test]
+    fn truncation_counts_characters_not_bytes() {
+        assert_eq!(truncate_chars("héllo wörld", 5, "end"), "héllo");
+        assert_eq!(truncate_chars("héllo wörld", 5, "start"), "wörld");
+    }
+
+    #[tokio::test]
+    async fn failed_provider_falls_back_to_the_next() {
+        // A fake Ollama answering every input with a 3-dim vector.
+        let app = axum::Router::new().route(
+            "/api/embed",
+            axum::routing::post(
+                |axum::Json(body): axum::Json<serde_json::Value>| async move {
+                    let n = body["input"].as_array().map_or(0, |a| a.len());
+                    axum::Json(serde_json::json!({ "embeddings": vec![[0.1, 0.2, 0.3]; n] }))
+                },
+            ),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        // Vertex fails before any request: its key file does not exist.
+        let config = EmbeddingConfig {
+            provider: "vertex".to_string(),
+            providers: vec!["vertex".to_string(), "ollama".to_string()],
+            model: Some("text-embedding-005".to_string()),
+            credentials_file: Some("/nonexistent/key.json".into()),
+            fallback: [(
+                "ollama".to_string(),
+                crate::config::EmbeddingFallbackConfig {
+                    model: Some("nomic-embed-text".to_string()),
+                    dims: Some(3),
+                    url: Some(url),
+                    api_key: None,
+                },
+            )]
+            .into(),
+            max_retries: 0,
+            ..EmbeddingConfig::default()
+        };
+        let texts = vec!["one".to_string(), "two".to_string()];
+
+        let vectors = embed_inputs(&config, 3, &texts, false).await.unwrap();
+        assert_eq!(vectors.len(), 2);
+        let counters: BTreeMap<_, _> = provider_counters().into_iter().collect();
+        assert!(counters["ollama"].served >= 1);
+        assert!(counters["vertex"].failed >= 1);
+
+        let err = embed_inputs(&config, 768, &texts, false).await.unwrap_err();
+        assert!(
+            err.to_string().contains("returned 3-dim vectors"),
+            "{}",
+            err
+        );
+    }
+}
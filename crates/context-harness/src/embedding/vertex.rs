@@ -0,0 +1,415 @@
+//! Google Vertex AI embedding provider.
+//!
+//! Calls the `predict` method of a Google text embedding model on the
+//! regional Vertex AI endpoint:
+//!
+//! ```text
+//! POST https://<region>-aiplatform.googleapis.com/v1/projects/<project>/locations/<region>/publishers/google/models/<model>:predict
+//! ```
+//!
+//! `[embedding] url` replaces the `https://<region>-aiplatform.googleapis.com`
+//! part, e.g. `https://aiplatform.googleapis.com` with `region = "global"`
+//! or a Private Service Connect endpoint.
+//!
+//! # Models
+//!
+//! | Model | Default dims | Notes |
+//! |-------|--------------|-------|
+//! | `text-embedding-005` | 768 | English and code |
+//! | `text-embedding-004` | 768 | English |
+//! | `text-multilingual-embedding-002` | 768 | Multilingual |
+//!
+//! `dims` below 768 is sent as `outputDimensionality`. Chunks are embedded
+//! with task type `RETRIEVAL_DOCUMENT` and search queries with
+//! `RETRIEVAL_QUERY`. A request holds at most 250 texts and about 20,000
+//! tokens, so large batches are split.
+//!
+//! # Authentication
+//!
+//! Requests carry an OAuth access token for a service account. The key
+//! file (the JSON downloaded from the Cloud console) is read from
+//! `[embedding] credentials_file` or `GOOGLE_APPLICATION_CREDENTIALS`; a
+//! JWT signed with its private key is exchanged for a token, which is
+//! reused until shortly before it expires. The project defaults to the
+//! key's `project_id`.
+//!
+//! Transient errors (HTTP 429 quota, 5xx, network) are retried with the
+//! same exponential backoff as the other hosted providers.
+
+use anyhow::{bail, Context, Result};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use rsa::pkcs1v15::SigningKey;
+use rsa::pkcs8::DecodePrivateKey;
+use rsa::signature::{SignatureEncoding, Signer};
+use serde::Deserialize;
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use super::{send_with_retry, EmbeddingProvider, CHARS_PER_TOKEN};
+use crate::config::EmbeddingConfig;
+
+/// Most texts Vertex AI accepts in one request.
+const MAX_TEXTS: usize = 250;
+
+/// Most tokens Vertex AI accepts in one request.
+const MAX_TOKENS: usize = 20_000;
+
+/// Largest (and default) output dimensionality.
+const MAX_DIMS: usize = 768;
+
+const DEFAULT_REGION: &str = "us-central1";
+
+const SCOPE: &str = "https://www.googleapis.com/auth/cloud-platform";
+
+/// Models this provider knows the request format of.
+const MODELS: &[&str] = &[
+    "text-embedding-005",
+    "text-embedding-004",
+    "text-multilingual-embedding-002",
+];
+
+/// Embedding provider using Google Vertex AI.
+pub struct VertexProvider {
+    model: String,
+    dims: usize,
+}
+
+impl VertexProvider {
+    /// Create a Vertex AI provider from configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `model` is missing or unsupported, `dims` is out
+    /// of range, or no readable service account key or project is found.
+    pub fn new(config: &EmbeddingConfig) -> Result<Self> {
+        let (model, dims) = resolve_model(config)?;
+        let key = ServiceAccountKey::load(config)?;
+        project(config, &key)?;
+        Ok(Self { model, dims })
+    }
+}
+
+impl EmbeddingProvider for VertexProvider {
+    fn model_name(&self) -> &str {
+        &self.model
+    }
+    fn dims(&self) -> usize {
+        self.dims
+    }
+}
+
+/// The configured model and its dimensionality.
+fn resolve_model(config: &EmbeddingConfig) -> Result<(String, usize)> {
+    let model = config
+        .model
+        .clone()
+        .ok_or_else(|| anyhow::anyhow!("embedding.model required for Vertex AI provider"))?;
+    if !MODELS.contains(&model.as_str()) {
+        bail!(
+            "Unsupported Vertex AI embedding model '{}'. Supported: {}",
+            model,
+            MODELS.join(", ")
+        );
+    }
+    let dims = config.dims.unwrap_or(MAX_DIMS);
+    if !(1..=MAX_DIMS).contains(&dims) {
+        bail!(
+            "embedding.dims for {} must be between 1 and {}, got {}",
+            model,
+            MAX_DIMS,
+            dims
+        );
+    }
+    Ok((model, dims))
+}
+
+/// `[embedding] region`, then `GOOGLE_CLOUD_LOCATION`, then `us-central1`.
+fn region(config: &EmbeddingConfig) -> String {
+    config
+        .region
+        .clone()
+        .or_else(|| std::env::var("GOOGLE_CLOUD_LOCATION").ok())
+        .filter(|r| !r.is_empty())
+        .unwrap_or_else(|| DEFAULT_REGION.to_string())
+}
+
+/// `[embedding] project`, then the key's `project_id`, then `GOOGLE_CLOUD_PROJECT`.
+fn project(config: &EmbeddingConfig, key: &ServiceAccountKey) -> Result<String> {
+    config
+        .project
+        .clone()
+        .or_else(|| key.project_id.clone())
+        .or_else(|| std::env::var("GOOGLE_CLOUD_PROJECT").ok())
+        .filter(|p| !p.is_empty())
+        .context("embedding.project not configured and the service account key has no project_id")
+}
+
+/// `predict` URL for `model`.
+fn endpoint(config: &EmbeddingConfig, project: &str, model: &str) -> String {
+    let region = region(config);
+    let base = match config.url {
+        Some(ref url) => url.trim_end_matches('/').to_string(),
+        None => format!("https://{}-aiplatform.googleapis.com", region),
+    };
+    format!(
+        "{}/v1/projects/{}/locations/{}/publishers/google/models/{}:predict",
+        base, project, region, model
+    )
+}
+
+/// Embed `texts` with Vertex AI. `query` selects task type
+/// `RETRIEVAL_QUERY` instead of `RETRIEVAL_DOCUMENT`.
+pub async fn embed_vertex(
+    config: &EmbeddingConfig,
+    texts: &[String],
+    query: bool,
+) -> Result<Vec<Vec<f32>>> {
+    let (model, dims) = resolve_model(config)?;
+    let key = ServiceAccountKey::load(config)?;
+    let url = endpoint(config, &project(config, &key)?, &model);
+    let http = reqwest::Client::builder()
+        .timeout(Duration::from_secs(config.timeout_secs))
+        .build()?;
+    let token = access_token(&http, config, &key).await?;
+    let task_type = if query {
+        "RETRIEVAL_QUERY"
+    } else {
+        "RETRIEVAL_DOCUMENT"
+    };
+
+    let mut embeddings = Vec::with_capacity(texts.len());
+    for batch in request_batches(texts) {
+        let instances: Vec<serde_json::Value> = batch
+            .iter()
+            .map(|text| serde_json::json!({ "content": text, "task_type": task_type }))
+            .collect();
+        let mut parameters = serde_json::json!({ "autoTruncate": true });
+        if dims < MAX_DIMS {
+            parameters["outputDimensionality"] = serde_json::json!(dims);
+        }
+        let body = serde_json::json!({ "instances": instances, "parameters": parameters });
+
+        let json = send_with_retry(config.max_retries, "Vertex AI", || {
+            http.post(&url).bearer_auth(&token).json(&body)
+        })
+        .await?;
+        embeddings.extend(parse_response(&json)?);
+    }
+    Ok(embeddings)
+}
+
+/// Split `texts` into requests within [`MAX_TEXTS`] and, by the chunker's
+/// 4-characters-per-token estimate, [`MAX_TOKENS`].
+fn request_batches(texts: &[String]) -> Vec<&[String]> {
+    let mut batches = Vec::new();
+    let mut start = 0;
+    let mut tokens = 0;
+    for (i, text) in texts.iter().enumerate() {
+        let text_tokens = text.chars().count().div_ceil(CHARS_PER_TOKEN);
+        if i > start && (i - start == MAX_TEXTS || tokens + text_tokens > MAX_TOKENS) {
+            batches.push(&texts[start..i]);
+            start = i;
+            tokens = 0;
+        }
+        tokens += text_tokens;
+    }
+    if start < texts.len() {
+        batches.push(&texts[start..]);
+    }
+    batches
+}
+
+/// Vectors from `predictions[].embeddings.values`, in input order.
+fn parse_response(json: &serde_json::Value) -> Result<Vec<Vec<f32>>> {
+    json.get("predictions")
+        .and_then(|p| p.as_array())
+        .ok_or_else(|| anyhow::anyhow!("Invalid Vertex AI response: missing predictions array"))?
+        .iter()
+        .map(|prediction| {
+            Ok(prediction
+                .pointer("/embeddings/values")
+                .and_then(|v| v.as_array())
+                .ok_or_else(|| anyhow::anyhow!("Invalid Vertex AI response: missing embedding"))?
+                .iter()
+                .map(|v| v.as_f64().unwrap_or(0.0) as f32)
+                .collect())
+        })
+        .collect()
+}
+
+// ============ Service account auth ============
+
+/// The fields of a service account key file this provider uses.
+#[derive(Debug, Deserialize)]
+struct ServiceAccountKey {
+    client_email: String,
+    private_key: String,
+    #[serde(default = "default_token_uri")]
+    token_uri: String,
+    #[serde(default)]
+    project_id: Option<String>,
+}
+
+fn default_token_uri() -> String {
+    "https://oauth2.googleapis.com/token".to_string()
+}
+
+impl ServiceAccountKey {
+    /// Read the key named by `[embedding] credentials_file` or
+    /// `GOOGLE_APPLICATION_CREDENTIALS`.
+    fn load(config: &EmbeddingConfig) -> Result<Self> {
+        let path = config
+            .credentials_file
+            .clone()
+            .or_else(|| std::env::var_os("GOOGLE_APPLICATION_CREDENTIALS").map(PathBuf::from))
+            .context(
+                "embedding.credentials_file not configured and GOOGLE_APPLICATION_CREDENTIALS not set",
+            )?;
+        let text = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read service account key {}", path.display()))?;
+        serde_json::from_str(&text)
+            .with_context(|| format!("{} is not a service account key", path.display()))
+    }
+
+    /// A signed JWT asserting this account, for the token exchange.
+    fn assertion(&self, now: i64) -> Result<String> {
+        let key = rsa::RsaPrivateKey::from_pkcs8_pem(&self.private_key)
+            .context("Invalid private_key in service account key")?;
+        let header = URL_SAFE_NO_PAD.encode(r#"{"alg":"RS256","typ":"JWT"}"#);
+        let claims = URL_SAFE_NO_PAD.encode(
+            serde_json::json!({
+                "iss": self.client_email,
+                "scope": SCOPE,
+                "aud": self.token_uri,
+                "iat": now,
+                "exp": now + 3600,
+            })
+            .to_string(),
+        );
+        let signing_input = format!("{}.{}", header, claims);
+        let signature = SigningKey::<Sha256>::new(key).sign(signing_input.as_bytes());
+        Ok(format!(
+            "{}.{}",
+            signing_input,
+            URL_SAFE_NO_PAD.encode(signature.to_bytes())
+        ))
+    }
+}
+
+/// Access tokens by service account, with the instant they stop being used.
+fn token_cache() -> &'static Mutex<HashMap<String, (String, Instant)>> {
+    static TOKENS: OnceLock<Mutex<HashMap<String, (String, Instant)>>> = OnceLock::new();
+    TOKENS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// A cached access token for `key`, or a new one from its token endpoint.
+async fn access_token(
+    http: &reqwest::Client,
+    config: &EmbeddingConfig,
+    key: &ServiceAccountKey,
+) -> Result<String> {
+    let cached = token_cache()
+        .lock()
+        .unwrap()
+        .get(&key.client_email)
+        .cloned();
+    if let Some((token, until)) = cached {
+        if Instant::now() < until {
+            return Ok(token);
+        }
+    }
+
+    let assertion = key.assertion(chrono::Utc::now().timestamp())?;
+    let json = send_with_retry(config.max_retries, "Google OAuth", || {
+        http.post(&key.token_uri).form(&[
+            ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"),
+            ("assertion", assertion.as_str()),
+        ])
+    })
+    .await?;
+    let token = json["access_token"]
+        .as_str()
+        .context("Token response missing access_token")?
+        .to_string();
+    // Refresh a minute early so a request never carries an expired token.
+    let lifetime = json["expires_in"]
+        .as_u64()
+        .unwrap_or(3600)
+        .saturating_sub(60);
+    token_cache().lock().unwrap().insert(
+        key.client_email.clone(),
+        (
+            token.clone(),
+            Instant::now() + Duration::from_secs(lifetime),
+        ),
+    );
+    Ok(token)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn models_and_dims_are_validated() {
+        let config = |model: &str, dims: Option<usize>| EmbeddingConfig {
+            provider: "vertex".to_string(),
+            model: Some(model.to_string()),
+            dims,
+            ..EmbeddingConfig::default()
+        };
+        assert_eq!(
+            resolve_model(&config("text-embedding-005", None)).unwrap(),
+            ("text-embedding-005".to_string(), 768)
+        );
+        assert_eq!(
+            resolve_model(&config("text-embedding-004", Some(256)))
+                .unwrap()
+                .1,
+            256
+        );
+        assert!(resolve_model(&config("text-embedding-005", Some(1024))).is_err());
+        assert!(resolve_model(&config("textembedding-gecko@001", None)).is_err());
+    }
+
+    #[test]
+    fn batches_respect_text_and_token_limits() {
+        let short = vec!["x".repeat(4); 600];
+        let sizes: Vec<usize> = request_batches(&short).iter().map(|b| b.len()).collect();
+        assert_eq!(sizes, vec![250, 250, 100]);
+
+        // 8,000 tokens each: two fit under 20,000, a third does not.
+        let long = vec!["x".repeat(32_000); 5];
+        let sizes: Vec<usize> = request_batches(&long).iter().map(|b| b.len()).collect();
+        assert_eq!(sizes, vec![2, 2, 1]);
+        assert!(request_batches(&[]).is_empty());
+    }
+
+    #[test]
+    fn regional_endpoint_and_response() {
+        let config = EmbeddingConfig {
+            region: Some("europe-west4".to_string()),
+            ..EmbeddingConfig::default()
+        };
+        assert_eq!(
+            endpoint(&config, "acme", "text-embedding-005"),
+            "https://europe-west4-aiplatform.googleapis.com/v1/projects/acme/locations/\
+             europe-west4/publishers/google/models/text-embedding-005:predict"
+        );
+
+        let json = serde_json::json!({
+            "predictions": [
+                {"embeddings": {"values": [0.5, 1.0], "statistics": {"token_count": 2}}},
+                {"embeddings": {"values": [0.25, 0.0]}}
+            ]
+        });
+        assert_eq!(
+            parse_response(&json).unwrap(),
+            vec![vec![0.5, 1.0], vec![0.25, 0.0]]
+        );
+    }
+}
@@ -1,25 +1,91 @@
-//! Export the search index as JSON for static site search.
+//! Export the search index as JSON for static site search, or as Markdown.
 //!
 //! Produces a `data.json` file containing all documents and chunks,
 //! suitable for use with `ctx-search.js` on static sites. Replaces
 //! the Python one-liner previously used in `build-docs.sh`.
+//!
+//! With `--vectors`, each chunk also carries its embedding so the browser
+//! build of `context-harness-core` (`--features wasm`) can run semantic
+//! and hybrid search. The bundle format is
+//! [`ExportData`](crate::app_store::ExportData).
+//!
+//! With `--format markdown`, each document is written to its own `.md`
+//! file under `<out>/<source>/`, at its `source_id` path, behind a YAML
+//! front matter block:
+//!
+//! ```text
+//! ---
+//! title: "Deploy runbook"
+//! source: "filesystem:docs"
+//! source_id: "runbooks/deploy.md"
+//! source_url: "file:///srv/docs/runbooks/deploy.md"
+//! tags: ["deploy","payments"]
+//! updated_at: "2026-03-01T09:30:00+00:00"
+//! ---
+//! ```
+//!
+//! `tags` comes from the document's metadata (memories, Zendesk tickets)
+//! and `doc_type` (`[classify]`) is added when set. The directory opens
+//! as an Obsidian vault or static site content, and indexing it with a
+//! `[connectors.filesystem]` connector drops the front matter again
+//! (`[chunking.normalize] strip_frontmatter`).
 
-use anyhow::Result;
-use std::path::Path;
+use anyhow::{bail, Result};
+use serde_json::Value;
+use sqlx::{Row, SqlitePool};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 
 use crate::app_store::{AppStore, SqliteAppStore};
 use crate::config::Config;
+use crate::db;
 
-/// Export documents and chunks as JSON.
+/// Export the index as `format` (`json` or `markdown`).
 ///
-/// If `output` is `Some`, writes to that file path. Otherwise writes
-/// to stdout for piping.
-pub async fn run_export(config: &Config, output: Option<&Path>) -> Result<()> {
+/// For JSON, writes to `output` when it is `Some` and to stdout otherwise;
+/// `with_vectors` includes chunk embeddings. Markdown needs `output` as
+/// the directory to write to.
+pub async fn run_export(
+    config: &Config,
+    format: &str,
+    output: Option<&Path>,
+    with_vectors: bool,
+) -> Result<()> {
+    if format != "markdown" {
+        return export_json(config, output, with_vectors).await;
+    }
+    if with_vectors {
+        bail!("--vectors applies to --format json only");
+    }
+    let Some(dir) = output else {
+        bail!("--format markdown needs --out <dir>");
+    };
+    let pool = db::connect(config).await?;
+    let result = export_markdown(&pool, dir).await;
+    pool.close().await;
+    let (documents, sources) = result?;
+    eprintln!(
+        "Exported {} documents from {} sources to {}",
+        documents,
+        sources,
+        dir.display()
+    );
+    Ok(())
+}
+
+/// Export documents and chunks as JSON.
+async fn export_json(config: &Config, output: Option<&Path>, with_vectors: bool) -> Result<()> {
     let store = SqliteAppStore::connect(config).await?;
-    let data = store.export_index().await?;
+    let data = store.export_index(with_vectors).await?;
     let doc_count = data.documents.len();
     let chunk_count = data.chunks.len();
-    let json = serde_json::to_string_pretty(&data)?;
+    let vector_count = data.vector_count();
+    let json = if with_vectors {
+        // Vectors make pretty-printed output several times larger.
+        serde_json::to_string(&data)?
+    } else {
+        serde_json::to_string_pretty(&data)?
+    };
 
     match output {
         Some(path) => {
@@ -33,6 +99,17 @@ pub async fn run_export(config: &Config, output: Option<&Path>) -> Result<()> {
                 chunk_count,
                 path.display()
             );
+            if with_vectors {
+                match &data.embedding_model {
+                    Some(model) => eprintln!(
+                        "Included {} vectors ({}, {} dims)",
+                        vector_count,
+                        model,
+                        data.embedding_dims.unwrap_or_default()
+                    ),
+                    None => eprintln!("No embeddings stored; run `ctx embed pending` first"),
+                }
+            }
         }
         None => {
             println!("{}", json);
@@ -42,3 +119,176 @@ pub async fn run_export(config: &Config, output: Option<&Path>) -> Result<()> {
     store.close().await;
     Ok(())
 }
+
+/// Write every document as a front-mattered Markdown file under `dir`.
+/// Returns the number of documents and of sources written.
+pub async fn export_markdown(pool: &SqlitePool, dir: &Path) -> Result<(usize, usize)> {
+    let rows = sqlx::query(
+        "SELECT source, source_id, source_url, title, updated_at, body, metadata_json \
+         FROM documents ORDER BY source, source_id",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut taken: HashSet<PathBuf> = HashSet::new();
+    let mut sources: HashSet<String> = HashSet::new();
+    for row in &rows {
+        let source: String = row.get("source");
+        let source_id: String = row.get("source_id");
+        let metadata: Value = serde_json::from_str(row.get("metadata_json")).unwrap_or(Value::Null);
+
+        let path = unique_path(&mut taken, markdown_path(&source, &source_id));
+        let target = dir.join(&path);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut text = front_matter(
+            row.get::<Option<String>, _>("title").as_deref(),
+            &source,
+            &source_id,
+            row.get::<Option<String>, _>("source_url").as_deref(),
+            &metadata,
+            row.get("updated_at"),
+        );
+        text.push_str(row.get::<&str, _>("body").trim_end());
+        text.push('\n');
+        std::fs::write(&target, text)?;
+        sources.insert(source);
+    }
+    Ok((rows.len(), sources.len()))
+}
+
+/// The YAML front matter block for one document. Strings are written as
+/// JSON strings, which YAML reads as double-quoted scalars.
+fn front_matter(
+    title: Option<&str>,
+    source: &str,
+    source_id: &str,
+    source_url: Option<&str>,
+    metadata: &Value,
+    updated_at: i64,
+) -> String {
+    let quote = |s: &str| Value::from(s).to_string();
+    let mut out = String::from("---\n");
+    out.push_str(&format!("title: {}\n", quote(title.unwrap_or(source_id))));
+    out.push_str(&format!("source: {}\n", quote(source)));
+    out.push_str(&format!("source_id: {}\n", quote(source_id)));
+    if let Some(url) = source_url {
+        out.push_str(&format!("source_url: {}\n", quote(url)));
+    }
+    let tags: Vec<&str> = metadata["tags"]
+        .as_array()
+        .map(|tags| tags.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    if !tags.is_empty() {
+        out.push_str(&format!("tags: {}\n", Value::from(tags)));
+    }
+    if let Some(doc_type) = metadata["doc_type"].as_str() {
+        out.push_str(&format!("doc_type: {}\n", quote(doc_type)));
+    }
+    if let Some(time) = chrono::DateTime::from_timestamp(updated_at, 0) {
+        out.push_str(&format!("updated_at: {}\n", quote(&time.to_rfc3339())));
+    }
+    out.push_str("---\n\n");
+    out
+}
+
+/// `<source>/<source_id>.md`, relative, with each segment reduced to
+/// characters safe in file names on every platform. A `source_id` that
+/// already ends in `.md` keeps its name.
+fn markdown_path(source: &str, source_id: &str) -> PathBuf {
+    let mut path = PathBuf::from(file_segment(source));
+    let id = source_id
+        .split_once("://")
+        .map_or(source_id, |(_, rest)| rest);
+    let segments: Vec<String> = id
+        .split(['/', '\\'])
+        .filter(|s| !s.is_empty() && *s != "." && *s != "..")
+        .map(file_segment)
+        .collect();
+    match segments.split_last() {
+        Some((name, dirs)) => {
+            path.extend(dirs);
+            if name.to_ascii_lowercase().ends_with(".md") {
+                path.push(name);
+            } else {
+                path.push(format!("{}.md", name));
+            }
+        }
+        None => path.push("untitled.md"),
+    }
+    path
+}
+
+/// `segment` with characters other than letters, digits, `.`, `-`, `_`,
+/// and spaces replaced by `-`, or `-` when nothing else is left.
+fn file_segment(segment: &str) -> String {
+    let cleaned: String = segment
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '.' | '-' | '_' | ' ') {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    match cleaned.trim_matches(['.', ' ']) {
+        "" => "-".to_string(),
+        trimmed => trimmed.to_string(),
+    }
+}
+
+/// `path`, or `path` with `-2`, `-3`, … before `.md` when an earlier
+/// document was written there (compared case-insensitively, for
+/// case-insensitive file systems).
+fn unique_path(taken: &mut HashSet<PathBuf>, path: PathBuf) -> PathBuf {
+    let key = |p: &Path| PathBuf::from(p.to_string_lossy().to_lowercase());
+    let mut candidate = path.clone();
+    let stem = path.with_extension("");
+    let mut n = 2;
+    while !taken.insert(key(&candidate)) {
+        candidate = PathBuf::from(format!("{}-{}.md", stem.display(), n));
+        n += 1;
+    }
+    candidate
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paths_follow_source_and_source_id() {
+        assert_eq!(
+            markdown_path("filesystem:docs", "runbooks/deploy.md"),
+            PathBuf::from("filesystem-docs/runbooks/deploy.md")
+        );
+        assert_eq!(
+            markdown_path("git:platform", "../notes/a:b.txt"),
+            PathBuf::from("git-platform/notes/a-b.txt.md")
+        );
+        assert_eq!(
+            markdown_path("script:jira", "https://jira.example.com/browse/OPS-1"),
+            PathBuf::from("script-jira/jira.example.com/browse/OPS-1.md")
+        );
+
+        let mut taken = HashSet::new();
+        let first = unique_path(&mut taken, markdown_path("fs", "Notes.md"));
+        let second = unique_path(&mut taken, markdown_path("fs", "notes.md"));
+        assert_eq!(first, PathBuf::from("fs/Notes.md"));
+        assert_eq!(second, PathBuf::from("fs/notes-2.md"));
+    }
+
+    #[test]
+    fn front_matter_quotes_values_and_lists_tags() {
+        let metadata = serde_json::json!({ "tags": ["deploy", 3, "ops"], "doc_type": "runbook" });
+        let text = front_matter(Some("Deploy: \"blue\""), "memory", "m1", None, &metadata, 0);
+        assert_eq!(
+            text,
+            "---\ntitle: \"Deploy: \\\"blue\\\"\"\nsource: \"memory\"\nsource_id: \"m1\"\n\
+             tags: [\"deploy\",\"ops\"]\ndoc_type: \"runbook\"\n\
+             updated_at: \"1970-01-01T00:00:00+00:00\"\n---\n\n"
+        );
+    }
+}
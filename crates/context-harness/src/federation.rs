@@ -0,0 +1,318 @@
+//! Search federation across several indexes.
+//!
+//! A federated search runs one query against this config's own database and
+//! every index listed under `[federation.indexes]` — other local SQLite
+//! databases or remote `ctx serve mcp` servers — concurrently. Each index's
+//! scores are re-normalized to `[0, 1]` so that no index dominates merely
+//! because of its score distribution, then all results are merged, sorted by
+//! score, truncated to the requested limit, and tagged with the name of the
+//! index they came from (`origin`).
+//!
+//! # Configuration
+//!
+//! ```toml
+//! [federation]
+//! enabled = false      # true = federate every search; otherwise --federated
+//! include_local = true # also search this config's database as "local"
+//! timeout_secs = 10    # per remote request
+//!
+//! [federation.indexes.work]
+//! db = "~/work/.ctx/data/ctx.sqlite"
+//!
+//! [federation.indexes.wiki]
+//! url = "http://wiki-box:7331"
+//! api_key = "secret://wiki-ctx"
+//! ```
+//!
+//! An index that fails (missing database, unreachable server) is reported in
+//! [`FederatedResults::errors`] and skipped; the search only fails when every
+//! index does.
+
+use anyhow::{bail, Context, Result};
+use context_harness_core::search::{source_matches, type_matches};
+use std::time::Duration;
+use tokio::task::JoinSet;
+
+use crate::config::{Config, FederatedIndexConfig};
+use crate::ctx_dirs;
+use crate::search::{search_documents_filtered, SearchResultItem};
+use crate::secrets;
+
+/// Origin name of this config's own database in federated results.
+pub const LOCAL_ORIGIN: &str = "local";
+
+/// Search parameters sent to every federated index.
+#[derive(Debug, Clone, Default)]
+pub struct FederatedQuery {
+    pub query: String,
+    pub mode: String,
+    pub source: Option<String>,
+    pub since: Option<String>,
+    pub as_of: Option<String>,
+    pub limit: Option<i64>,
+    pub explain: bool,
+    pub chunk_filters: Vec<(String, String)>,
+    /// Document types (see [`type_matches`]). Remote results are filtered
+    /// by them after they arrive.
+    pub types: Vec<String>,
+    pub fuzzy: bool,
+    /// The caller's source scope (see [`crate::auth`]). Remote results are
+    /// filtered by it after they arrive.
+    pub allowed_sources: Option<Vec<String>>,
+}
+
+/// Merged results of a federated search.
+#[derive(Debug, Default)]
+pub struct FederatedResults {
+    /// Results from all indexes, best first, each with `origin` set.
+    pub results: Vec<SearchResultItem>,
+    /// `(index, error)` for every index that could not be searched.
+    pub errors: Vec<(String, String)>,
+}
+
+/// Run `query` against every configured index and merge the results.
+pub async fn federated_search(config: &Config, query: &FederatedQuery) -> Result<FederatedResults> {
+    let federation = &config.federation;
+    let timeout = Duration::from_secs(federation.timeout_secs);
+    let mut tasks = JoinSet::new();
+
+    if federation.include_local {
+        let config = config.clone();
+        let query = query.clone();
+        tasks.spawn(async move {
+            let results = search_local(&config, &query).await;
+            (LOCAL_ORIGIN.to_string(), results)
+        });
+    }
+    for (name, index) in &federation.indexes {
+        let name = name.clone();
+        let index = index.clone();
+        let config = config.clone();
+        let query = query.clone();
+        tasks.spawn(async move {
+            let results = search_index(&config, &index, &query, timeout).await;
+            (name, results)
+        });
+    }
+
+    let mut per_index = Vec::new();
+    let mut errors = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok((name, Ok(results))) => per_index.push((name, results)),
+            Ok((name, Err(e))) => errors.push((name, format!("{:#}", e))),
+            Err(e) => errors.push(("?".to_string(), e.to_string())),
+        }
+    }
+    errors.sort();
+
+    if per_index.is_empty() {
+        match errors.first() {
+            Some((name, err)) => {
+                bail!("Federated search failed on every index ({}: {})", name, err)
+            }
+            None => bail!("Federated search has no indexes; add [federation.indexes]"),
+        }
+    }
+
+    let limit = query.limit.unwrap_or(config.retrieval.final_limit).max(0) as usize;
+    Ok(FederatedResults {
+        results: merge(per_index, limit),
+        errors,
+    })
+}
+
+/// Search this config's own database.
+async fn search_local(config: &Config, query: &FederatedQuery) -> Result<Vec<SearchResultItem>> {
+    let mut config = config.clone();
+    config.retrieval.fuzzy |= query.fuzzy;
+    search_documents_filtered(
+        &config,
+        &query.query,
+        &query.mode,
+        query.source.as_deref(),
+        query.since.as_deref(),
+        query.as_of.as_deref(),
+        query.limit,
+        query.explain,
+        &query.chunk_filters,
+        &query.types,
+        query.allowed_sources.as_deref(),
+    )
+    .await
+}
+
+/// Search one `[federation.indexes.<name>]` entry.
+async fn search_index(
+    config: &Config,
+    index: &FederatedIndexConfig,
+    query: &FederatedQuery,
+    timeout: Duration,
+) -> Result<Vec<SearchResultItem>> {
+    if let Some(ref db) = index.db {
+        let path = ctx_dirs::expand_tilde(db);
+        if !path.exists() {
+            bail!("database not found: {}", path.display());
+        }
+        let mut config = config.clone();
+        config.db.path = path;
+        return search_local(&config, query).await;
+    }
+    let mut results = match index.url {
+        Some(ref url) => search_remote(url, index.api_key.as_deref(), query, timeout).await?,
+        None => bail!("index has neither db nor url"),
+    };
+    if let Some(ref patterns) = query.allowed_sources {
+        results.retain(|r| source_matches(patterns, &r.source));
+    }
+    results.retain(|r| {
+        type_matches(
+            &query.types,
+            &r.source_id,
+            r.content_type.as_deref().unwrap_or_default(),
+            r.doc_type.as_deref(),
+        )
+    });
+    Ok(results)
+}
+
+/// Call `POST {url}/tools/search` on a remote ctx server.
+async fn search_remote(
+    url: &str,
+    api_key: Option<&str>,
+    query: &FederatedQuery,
+    timeout: Duration,
+) -> Result<Vec<SearchResultItem>> {
+    let metadata: serde_json::Map<String, serde_json::Value> = query
+        .chunk_filters
+        .iter()
+        .map(|(k, v)| (k.clone(), serde_json::Value::String(v.clone())))
+        .collect();
+    // `federated: false` stops a remote that federates by default from
+    // fanning out again.
+    let body = serde_json::json!({
+        "query": query.query,
+        "mode": query.mode,
+        "limit": query.limit,
+        "fuzzy": query.fuzzy,
+        "federated": false,
+        "filters": {
+            "source": query.source,
+            "since": query.since,
+            "as_of": query.as_of,
+            "metadata": metadata,
+        },
+    });
+
+    let client = reqwest::Client::builder().timeout(timeout).build()?;
+    let endpoint = format!("{}/tools/search", url.trim_end_matches('/'));
+    let mut request = client.post(&endpoint).json(&body);
+    if let Some(key) = api_key {
+        request = request.bearer_auth(secrets::resolve(key)?);
+    }
+    let response = request
+        .send()
+        .await
+        .with_context(|| format!("request to {} failed", endpoint))?;
+    let status = response.status();
+    if !status.is_success() {
+        let text = response.text().await.unwrap_or_default();
+        bail!("{} returned {}: {}", endpoint, status, text.trim());
+    }
+
+    let mut payload: serde_json::Value = response.json().await?;
+    let results = payload
+        .get_mut("result")
+        .and_then(|r| r.get_mut("results"))
+        .map(serde_json::Value::take)
+        .context("response has no result.results")?;
+    serde_json::from_value(results).context("unexpected search result format")
+}
+
+/// Re-normalize each index's scores, tag origins, and merge best-first.
+fn merge(per_index: Vec<(String, Vec<SearchResultItem>)>, limit: usize) -> Vec<SearchResultItem> {
+    let mut merged = Vec::new();
+    for (name, mut results) in per_index {
+        let min = results
+            .iter()
+            .map(|r| r.score)
+            .fold(f64::INFINITY, f64::min);
+        let max = results
+            .iter()
+            .map(|r| r.score)
+            .fold(f64::NEG_INFINITY, f64::max);
+        for result in &mut results {
+            result.score = if max > min {
+                (result.score - min) / (max - min)
+            } else {
+                1.0
+            };
+            result.origin = Some(name.clone());
+        }
+        merged.extend(results);
+    }
+    merged.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.origin.cmp(&b.origin))
+    });
+    merged.truncate(limit);
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(id: &str, score: f64) -> SearchResultItem {
+        SearchResultItem {
+            id: id.to_string(),
+            score,
+            title: None,
+            source: "filesystem".to_string(),
+            source_id: id.to_string(),
+            content_type: None,
+            updated_at: "2024-01-01T00:00:00Z".to_string(),
+            snippet: String::new(),
+            source_url: None,
+            heading: None,
+            section_source_id: None,
+            section_source_url: None,
+            chunk_id: None,
+            parent_id: None,
+            explain: None,
+            origin: None,
+            summary: None,
+            doc_type: None,
+        }
+    }
+
+    #[test]
+    fn merge_renormalizes_per_index_and_tags_origin() {
+        let merged = merge(
+            vec![
+                ("local".to_string(), vec![item("a", 9.0), item("b", 3.0)]),
+                (
+                    "work".to_string(),
+                    vec![item("c", 0.4), item("d", 0.3), item("e", 0.2)],
+                ),
+            ],
+            4,
+        );
+        let ids: Vec<(&str, f64)> = merged.iter().map(|r| (r.id.as_str(), r.score)).collect();
+        assert_eq!(ids.len(), 4);
+        assert_eq!(ids[0], ("a", 1.0));
+        assert_eq!(ids[1], ("c", 1.0));
+        assert_eq!(ids[2].0, "d");
+        assert!((ids[2].1 - 0.5).abs() < 1e-9);
+        assert_eq!(merged[0].origin.as_deref(), Some("local"));
+        assert_eq!(merged[1].origin.as_deref(), Some("work"));
+    }
+
+    #[test]
+    fn merge_gives_single_result_full_score() {
+        let merged = merge(vec![("work".to_string(), vec![item("a", 0.1)])], 10);
+        assert_eq!(merged[0].score, 1.0);
+    }
+}
@@ -0,0 +1,329 @@
+//! Database garbage collection (`ctx db gc`).
+//!
+//! Deleting documents frees their rows, but SQLite keeps the pages, and
+//! rows left behind by interrupted writes or older versions (chunks of
+//! deleted documents, embeddings and vectors of replaced chunks) are never
+//! looked at again. A collection:
+//!
+//! 1. Deletes chunks whose document is gone, then embeddings and
+//!    `chunk_vectors` rows whose chunk is gone, document vectors of
+//!    documents without chunk vectors, and retrieval records of deleted
+//!    documents.
+//! 2. Repairs the FTS indexes: `chunks_fts` rows of deleted chunks are
+//!    removed and chunks missing from it are indexed; `titles_fts` is
+//!    rebuilt if it is out of step with `documents`. `chunks_fts` is then
+//!    merged (`optimize`).
+//! 3. Returns free pages to the file system. The first collection switches
+//!    the database to incremental auto-vacuum with one full `VACUUM`; later
+//!    ones run `PRAGMA incremental_vacuum`, which only moves free pages.
+//!    The WAL is checkpointed and truncated last.
+//!
+//! The size of the database file and its WAL is measured before and after.
+//! With `[db] gc_interval_hours` set, `ctx serve mcp` runs a collection on
+//! that interval and logs the same report.
+//!
+//! A vector sidecar (`[vector_index] backend = "zvec"`) is not touched;
+//! `ctx vector-index rebuild` rebuilds it from SQLite.
+
+use anyhow::Result;
+use serde::Serialize;
+use sqlx::SqlitePool;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::config::Config;
+use crate::db;
+use crate::migrate;
+use crate::search_cache;
+use crate::stats::format_bytes;
+
+/// Outcome of [`collect`].
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct GcReport {
+    /// Chunks whose document no longer exists.
+    pub orphan_chunks: u64,
+    /// Embedding metadata rows whose chunk no longer exists.
+    pub orphan_embeddings: u64,
+    /// Vector blobs whose chunk no longer exists, plus document vectors
+    /// of documents that have no chunk vectors left.
+    pub orphan_vectors: u64,
+    /// Retrieval records of deleted documents.
+    pub orphan_access: u64,
+    /// `chunks_fts` rows of deleted chunks.
+    pub stale_fts_rows: u64,
+    /// Chunks that were missing from `chunks_fts`.
+    pub missing_fts_rows: u64,
+    /// Whether `titles_fts` was rebuilt.
+    pub titles_rebuilt: bool,
+    /// Whether this run switched the database to incremental auto-vacuum.
+    pub vacuum_enabled: bool,
+    /// Database file plus WAL, in bytes, before the collection.
+    pub size_before: u64,
+    /// Database file plus WAL, in bytes, after the collection.
+    pub size_after: u64,
+}
+
+impl GcReport {
+    /// Total rows deleted or added.
+    pub fn rows_changed(&self) -> u64 {
+        self.orphan_chunks
+            + self.orphan_embeddings
+            + self.orphan_vectors
+            + self.orphan_access
+            + self.stale_fts_rows
+            + self.missing_fts_rows
+    }
+
+    /// Bytes returned to the file system (zero if the files grew).
+    pub fn reclaimed(&self) -> u64 {
+        self.size_before.saturating_sub(self.size_after)
+    }
+}
+
+/// Run a full collection on the configured database.
+///
+/// # Errors
+///
+/// Returns an error if the database cannot be opened or a statement fails.
+/// `VACUUM` waits up to `[db] busy_timeout_ms` for other writers.
+pub async fn collect(config: &Config) -> Result<GcReport> {
+    let mut report = GcReport {
+        size_before: file_bytes(&config.db.path),
+        ..GcReport::default()
+    };
+
+    let pool = db::connect(config).await?;
+    let result = collect_with(config, &pool, &mut report).await;
+    pool.close().await;
+    result?;
+
+    report.size_after = file_bytes(&config.db.path);
+    Ok(report)
+}
+
+async fn collect_with(config: &Config, pool: &SqlitePool, report: &mut GcReport) -> Result<()> {
+    let has_access: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type='table' AND name='document_access')",
+    )
+    .fetch_one(pool)
+    .await?;
+    let has_document_vectors: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type='table' AND name='document_vectors')",
+    )
+    .fetch_one(pool)
+    .await?;
+
+    let mut tx = db::begin_write(pool).await?;
+    report.orphan_chunks = sqlx::query(
+        "DELETE FROM chunks WHERE NOT EXISTS (SELECT 1 FROM documents d WHERE d.id = chunks.document_id)",
+    )
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    report.orphan_embeddings = sqlx::query(
+        "DELETE FROM embeddings WHERE NOT EXISTS (SELECT 1 FROM chunks c WHERE c.id = embeddings.chunk_id)",
+    )
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    report.orphan_vectors = sqlx::query(
+        "DELETE FROM chunk_vectors WHERE NOT EXISTS (SELECT 1 FROM chunks c WHERE c.id = chunk_vectors.chunk_id)",
+    )
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    if has_document_vectors {
+        report.orphan_vectors += sqlx::query(
+            "DELETE FROM document_vectors WHERE NOT EXISTS \
+             (SELECT 1 FROM chunk_vectors cv WHERE cv.document_id = document_vectors.document_id)",
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    }
+    if has_access {
+        report.orphan_access = sqlx::query(
+            "DELETE FROM document_access WHERE NOT EXISTS \
+             (SELECT 1 FROM documents d WHERE d.id = document_access.document_id)",
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    }
+    report.stale_fts_rows =
+        sqlx::query("DELETE FROM chunks_fts WHERE chunk_id NOT IN (SELECT id FROM chunks)")
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+    report.missing_fts_rows = sqlx::query(
+        "INSERT INTO chunks_fts (chunk_id, document_id, text, title, summary) \
+         SELECT c.id, c.document_id, c.text, d.title, d.summary FROM chunks c \
+         LEFT JOIN documents d ON d.id = c.document_id \
+         WHERE c.id NOT IN (SELECT chunk_id FROM chunks_fts)",
+    )
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    tx.commit().await?;
+
+    sqlx::query("INSERT INTO chunks_fts (chunks_fts) VALUES ('optimize')")
+        .execute(pool)
+        .await?;
+
+    if titles_out_of_step(pool).await? {
+        sqlx::query("DROP TABLE titles_fts").execute(pool).await?;
+        migrate::ensure_titles_table(pool, &config.retrieval.fts_tokenizer).await?;
+        report.titles_rebuilt = true;
+    }
+
+    if report.stale_fts_rows + report.missing_fts_rows > 0 || report.titles_rebuilt {
+        search_cache::bump_index_generation(pool).await?;
+    }
+
+    report.vacuum_enabled = vacuum(pool).await?;
+    Ok(())
+}
+
+/// Whether `titles_fts` exists and lacks a document or holds a deleted one.
+async fn titles_out_of_step(pool: &SqlitePool) -> Result<bool> {
+    let exists: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type='table' AND name='titles_fts')",
+    )
+    .fetch_one(pool)
+    .await?;
+    if !exists {
+        return Ok(false);
+    }
+    Ok(sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM titles_fts WHERE document_id NOT IN (SELECT id FROM documents)) \
+         OR EXISTS (SELECT 1 FROM documents WHERE id NOT IN (SELECT document_id FROM titles_fts))",
+    )
+    .fetch_one(pool)
+    .await?)
+}
+
+/// Release free pages and truncate the WAL. Returns `true` when the
+/// database was switched to incremental auto-vacuum by this call.
+///
+/// `auto_vacuum` is a per-file setting read through the connection that
+/// changed it, so every statement runs on one connection.
+async fn vacuum(pool: &SqlitePool) -> Result<bool> {
+    let mut conn = pool.acquire().await?;
+    let mode: i64 = sqlx::query_scalar("PRAGMA auto_vacuum")
+        .fetch_one(&mut *conn)
+        .await?;
+    // 2 = INCREMENTAL. Changing the mode only takes effect with a VACUUM.
+    let switched = mode != 2;
+    if switched {
+        sqlx::query("PRAGMA auto_vacuum = INCREMENTAL")
+            .execute(&mut *conn)
+            .await?;
+        sqlx::query("VACUUM").execute(&mut *conn).await?;
+    } else {
+        sqlx::query("PRAGMA incremental_vacuum")
+            .execute(&mut *conn)
+            .await?;
+    }
+    sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)")
+        .execute(&mut *conn)
+        .await?;
+    Ok(switched)
+}
+
+/// Size of the database file plus its `-wal` file.
+fn file_bytes(path: &Path) -> u64 {
+    let len = |suffix: &str| {
+        let mut path = path.to_path_buf().into_os_string();
+        path.push(suffix);
+        std::fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+    };
+    len("") + len("-wal")
+}
+
+/// Size change of a report, e.g. `"431.2 MB → 402.0 MB (-29.2 MB)"`.
+pub fn size_delta(report: &GcReport) -> String {
+    let delta = if report.size_after > report.size_before {
+        format!("+{}", format_bytes(report.size_after - report.size_before))
+    } else {
+        format!("-{}", format_bytes(report.reclaimed()))
+    };
+    format!(
+        "{} → {} ({})",
+        format_bytes(report.size_before),
+        format_bytes(report.size_after),
+        delta
+    )
+}
+
+/// Run `ctx db gc`: collect and print what was removed and reclaimed.
+pub async fn run_db_gc(config: &Config) -> Result<()> {
+    let report = collect(config).await?;
+
+    println!("db gc");
+    println!("  orphaned chunks removed: {}", report.orphan_chunks);
+    println!(
+        "  orphaned embeddings removed: {}",
+        report.orphan_embeddings
+    );
+    println!("  orphaned vectors removed: {}", report.orphan_vectors);
+    println!(
+        "  orphaned access records removed: {}",
+        report.orphan_access
+    );
+    println!(
+        "  fts rows: {} stale removed, {} missing added",
+        report.stale_fts_rows, report.missing_fts_rows
+    );
+    if report.titles_rebuilt {
+        println!("  titles_fts: rebuilt");
+    }
+    if report.vacuum_enabled {
+        println!("  vacuum: switched to incremental auto-vacuum (full VACUUM)");
+    } else {
+        println!("  vacuum: incremental");
+    }
+    println!("  size: {}", size_delta(&report));
+    println!("ok");
+    Ok(())
+}
+
+/// Collect every `interval` for `ctx serve mcp`, logging each report.
+///
+/// The first collection runs one interval after the server starts.
+pub async fn run_scheduled(config: Arc<Config>, interval: Duration) {
+    let mut ticker = tokio::time::interval(interval);
+    ticker.tick().await;
+    loop {
+        ticker.tick().await;
+        match collect(&config).await {
+            Ok(report) => tracing::info!(
+                "db gc: {} rows removed or repaired, size {}",
+                report.rows_changed(),
+                size_delta(&report)
+            ),
+            Err(e) => tracing::warn!("db gc failed: {:#}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn size_delta_reports_growth_and_shrinkage() {
+        let shrunk = GcReport {
+            size_before: 3 * 1024 * 1024,
+            size_after: 1024 * 1024,
+            ..GcReport::default()
+        };
+        assert_eq!(size_delta(&shrunk), "3.0 MB → 1.0 MB (-2.0 MB)");
+        let grown = GcReport {
+            size_before: 1024 * 1024,
+            size_after: 2 * 1024 * 1024,
+            ..GcReport::default()
+        };
+        assert_eq!(size_delta(&grown), "1.0 MB → 2.0 MB (+1.0 MB)");
+    }
+}
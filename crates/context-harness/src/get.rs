@@ -9,33 +9,50 @@
 //! ```bash
 //! # Retrieve a document by UUID
 //! ctx get 550e8400-e29b-41d4-a716-446655440000
+//!
+//! # Retrieve several documents at once, without chunks
+//! ctx get --ids 550e8400-...,6ba7b810-... --no-chunks --max-body-chars 2000
 //! ```
 //!
+//! # Batch Retrieval
+//!
+//! [`get_documents`] fetches up to [`MAX_BATCH_IDS`] documents over one
+//! connection and returns them in request order. A missing document yields
+//! a per-id error instead of failing the whole batch.
+//!
 //! # Response Shape
 //!
 //! The response matches the `context.get` schema defined in `docs/SCHEMAS.md`,
 //! including full document metadata, body text, and all chunks ordered by index.
+//! Documents in a hierarchy also carry `parent_id` and their direct `children`.
 
 use anyhow::{bail, Result};
+use serde::Serialize;
 
 use context_harness_core::store::Store;
 #[allow(unused_imports)]
-pub use context_harness_core::store::{ChunkResponse, DocumentResponse};
+pub use context_harness_core::store::{ChunkResponse, DocumentChild, DocumentResponse};
 
 use crate::config::Config;
 use crate::db;
+use crate::response_limits::{limit_document, DocumentTruncation, SizeLimits};
+use crate::scrub;
 use crate::sqlite_store::SqliteStore;
+use crate::storage;
 
 /// Retrieves a document by its UUID, including all associated chunks.
 ///
 /// This is the core retrieval function used by both the CLI (`ctx get`)
 /// and the HTTP server (`POST /tools/get`).
 pub async fn get_document(config: &Config, id: &str) -> Result<DocumentResponse> {
-    let pool = db::connect(config).await?;
+    let pool = db::connect_reader(config).await?;
     let store = SqliteStore::new(pool.clone());
 
     let result = store.get_document(id).await?;
-    pool.close().await;
+    if result.is_some() {
+        storage::record_retrievals(config, &pool, &[id]).await;
+    }
+    db::close_reader(config, pool).await;
 
     match result {
         Some(doc) => Ok(doc),
@@ -43,16 +60,174 @@ pub async fn get_document(config: &Config, id: &str) -> Result<DocumentResponse>
     }
 }
 
+/// Maximum number of ids accepted by a single batch request.
+pub const MAX_BATCH_IDS: usize = 100;
+
+/// Shaping options for batch retrieval.
+#[derive(Debug, Clone)]
+pub struct GetOptions {
+    /// Include each document's chunks (default `true`).
+    pub include_chunks: bool,
+    /// Truncate bodies to at most this many characters.
+    pub max_body_chars: Option<usize>,
+    /// Body byte and chunk limits of each document (see
+    /// [`crate::response_limits`]).
+    pub limits: SizeLimits,
+}
+
+impl Default for GetOptions {
+    fn default() -> Self {
+        Self {
+            include_chunks: true,
+            max_body_chars: None,
+            limits: SizeLimits::default(),
+        }
+    }
+}
+
+/// One entry of a batch response: either a document or an error.
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchGetItem {
+    /// The requested id.
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub document: Option<DocumentResponse>,
+    /// Whether the body was cut to `max_body_chars`.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+    /// What `max_body_bytes` / `max_chunks` left out.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub truncation: Option<DocumentTruncation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Retrieves several documents by UUID, in request order.
+///
+/// Fails only for invalid requests (no ids, too many ids) or database
+/// errors; unknown ids are reported per item.
+pub async fn get_documents(
+    config: &Config,
+    ids: &[String],
+    options: &GetOptions,
+) -> Result<Vec<BatchGetItem>> {
+    if ids.is_empty() {
+        bail!("ids must not be empty");
+    }
+    if ids.len() > MAX_BATCH_IDS {
+        bail!("too many ids: {} (maximum is {})", ids.len(), MAX_BATCH_IDS);
+    }
+
+    let pool = db::connect_reader(config).await?;
+    let store = SqliteStore::new(pool.clone());
+
+    let mut items = Vec::with_capacity(ids.len());
+    for id in ids {
+        let item = match store.get_document(id).await {
+            Ok(Some(mut doc)) => {
+                if !options.include_chunks {
+                    doc.chunks.clear();
+                }
+                let truncated = options
+                    .max_body_chars
+                    .is_some_and(|max| truncate_chars(&mut doc.body, max));
+                let truncation = limit_document(&mut doc, &options.limits, 0, 0);
+                BatchGetItem {
+                    id: id.clone(),
+                    document: Some(doc),
+                    truncated,
+                    truncation,
+                    error: None,
+                }
+            }
+            Ok(None) => BatchGetItem {
+                id: id.clone(),
+                document: None,
+                truncated: false,
+                truncation: None,
+                error: Some(format!("document not found: {}", id)),
+            },
+            Err(e) => BatchGetItem {
+                id: id.clone(),
+                document: None,
+                truncated: false,
+                truncation: None,
+                error: Some(e.to_string()),
+            },
+        };
+        items.push(item);
+    }
+    let found: Vec<&str> = items
+        .iter()
+        .filter(|item| item.document.is_some())
+        .map(|item| item.id.as_str())
+        .collect();
+    storage::record_retrievals(config, &pool, &found).await;
+    db::close_reader(config, pool).await;
+    Ok(items)
+}
+
+/// Cut `s` to at most `max` characters. Returns `true` if anything was removed.
+fn truncate_chars(s: &mut String, max: usize) -> bool {
+    match s.char_indices().nth(max) {
+        Some((idx, _)) => {
+            s.truncate(idx);
+            true
+        }
+        None => false,
+    }
+}
+
 /// CLI entry point for `ctx get <id>`.
 pub async fn run_get(config: &Config, id: &str) -> Result<()> {
     let doc = match get_document(config, id).await {
         Ok(d) => d,
         Err(e) => {
-            eprintln!("Error: {}", e);
+            eprintln!("Error: {}", scrub::scrub(&e.to_string()));
             std::process::exit(1);
         }
     };
 
+    print_document(&doc, true);
+    Ok(())
+}
+
+/// CLI entry point for `ctx get --ids a,b,c`.
+///
+/// Prints each document in request order; exits non-zero if any id failed.
+pub async fn run_get_many(config: &Config, ids: &[String], options: &GetOptions) -> Result<()> {
+    let items = get_documents(config, ids, options).await?;
+    let mut failed = 0;
+    for item in &items {
+        match (&item.document, &item.error) {
+            (Some(doc), _) => {
+                print_document(doc, options.include_chunks);
+                if item.truncated {
+                    println!("(body truncated)");
+                    println!();
+                }
+            }
+            (None, error) => {
+                failed += 1;
+                eprintln!(
+                    "Error: {}",
+                    error.as_deref().unwrap_or("document not found")
+                );
+            }
+        }
+    }
+    if failed > 0 {
+        eprintln!(
+            "{} of {} documents could not be retrieved",
+            failed,
+            items.len()
+        );
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+pub(crate) fn print_document(doc: &DocumentResponse, show_chunks: bool) {
     println!("--- Document ---");
     println!("id:           {}", doc.id);
     println!(
@@ -71,18 +246,43 @@ pub async fn run_get(config: &Config, id: &str) -> Result<()> {
     println!("updated_at:   {}", doc.updated_at);
     println!("content_type: {}", doc.content_type);
     println!("metadata:     {}", doc.metadata);
+    if let Some(ref parent) = doc.parent_id {
+        println!("parent_id:    {}", parent);
+    }
     println!();
 
+    if !doc.children.is_empty() {
+        println!("--- Children ({}) ---", doc.children.len());
+        for child in &doc.children {
+            println!(
+                "{}  {}",
+                child.id,
+                child.title.as_deref().unwrap_or(&child.source_id)
+            );
+        }
+        println!();
+    }
+
+    if let Some(ref summary) = doc.summary {
+        println!("--- Summary ---");
+        println!("{}", summary);
+        println!();
+    }
+
     println!("--- Body ---");
     println!("{}", doc.body);
     println!();
 
+    if !show_chunks {
+        return;
+    }
     println!("--- Chunks ({}) ---", doc.chunks.len());
     for chunk in &doc.chunks {
-        println!("[chunk {}]", chunk.index);
+        match chunk.metadata.get("heading").and_then(|h| h.as_str()) {
+            Some(heading) => println!("[chunk {}] {}", chunk.index, heading),
+            None => println!("[chunk {}]", chunk.index),
+        }
         println!("{}", chunk.text);
         println!();
     }
-
-    Ok(())
 }
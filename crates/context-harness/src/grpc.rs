@@ -0,0 +1,319 @@
+//! gRPC interface for the server (`--features grpc`).
+//!
+//! With `[server] grpc_bind` set, `ctx serve mcp` also serves the
+//! `context_harness.v1.ContextHarness` service defined in
+//! `proto/context_harness.proto`. The Rust code is generated from it at build
+//! time by `tonic-prost-build`, which needs `protoc`.
+//!
+//! | RPC | HTTP equivalent |
+//! |-----|-----------------|
+//! | `Search` | `POST /tools/search` |
+//! | `Get` | `POST /tools/get` |
+//! | `Sources` | `POST /tools/sources` |
+//! | `ListTools` | `GET /tools/list` |
+//! | `CallTool` | `POST /tools/{name}` |
+//!
+//! Every call goes through the same handlers as the HTTP API
+//! (`server::call_tool`, `server::list_tools`), so API keys, source
+//! scopes, the search cache, the audit log (transport `grpc`), and shutdown
+//! draining behave the same. The API key is read from the `authorization`
+//! or `x-api-key` metadata. HTTP error codes map to gRPC status codes:
+//!
+//! | Error code | gRPC status |
+//! |------------|-------------|
+//! | `bad_request`, `embeddings_disabled` | `INVALID_ARGUMENT` |
+//! | `unauthorized` | `UNAUTHENTICATED` |
+//! | `forbidden` | `PERMISSION_DENIED` |
+//! | `not_found` | `NOT_FOUND` |
+//! | `timeout` | `DEADLINE_EXCEEDED` |
+//! | `unavailable` | `UNAVAILABLE` |
+//! | `tool_error`, other | `INTERNAL` |
+
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+use std::future::Future;
+use std::sync::Arc;
+use tonic::{Request, Response, Status};
+
+use crate::audit;
+use crate::auth::{self, ApiKeys, SourceScope};
+use crate::limits;
+use crate::scrub;
+use crate::server::{self, AppError, AppState};
+use crate::traits::ToolRegistry;
+
+/// Code generated from `proto/context_harness.proto`.
+pub mod proto {
+    tonic::include_proto!("context_harness.v1");
+}
+
+use proto::context_harness_server::{ContextHarness, ContextHarnessServer};
+
+/// The `ContextHarness` service, backed by the HTTP server's state.
+pub(crate) struct GrpcService {
+    state: AppState,
+    extra_tools: Arc<ToolRegistry>,
+    keys: Option<Arc<ApiKeys>>,
+}
+
+impl GrpcService {
+    pub(crate) fn new(
+        state: AppState,
+        extra_tools: Arc<ToolRegistry>,
+        keys: Option<Arc<ApiKeys>>,
+    ) -> Self {
+        Self {
+            state,
+            extra_tools,
+            keys,
+        }
+    }
+
+    /// Check the request's API key. Returns the caller fingerprint for the
+    /// audit log and the key's source scope.
+    fn authenticate<T>(
+        &self,
+        request: &Request<T>,
+    ) -> Result<(Option<String>, Option<Arc<SourceScope>>), Status> {
+        let headers = request.metadata().clone().into_headers();
+        let caller = audit::caller_from_headers(&headers);
+        let Some(ref keys) = self.keys else {
+            return Ok((caller, None));
+        };
+        match keys.authenticate(&headers) {
+            Some(key) => {
+                tracing::debug!(key = %key.name, "authenticated");
+                Ok((caller, key.scope.clone()))
+            }
+            None => Err(Status::unauthenticated("missing or invalid API key")),
+        }
+    }
+
+    /// Run a tool through the shared handler on behalf of `request`.
+    async fn call<T>(
+        &self,
+        request: &Request<T>,
+        name: &str,
+        params: Value,
+    ) -> Result<Value, Status> {
+        let (caller, scope) = self.authenticate(request)?;
+        server::call_tool(
+            &self.state,
+            &self.extra_tools,
+            name,
+            params,
+            caller,
+            "grpc",
+            scope,
+        )
+        .await
+        .map_err(status_of)
+    }
+}
+
+#[tonic::async_trait]
+impl ContextHarness for GrpcService {
+    async fn search(
+        &self,
+        request: Request<proto::SearchRequest>,
+    ) -> Result<Response<proto::SearchResponse>, Status> {
+        let req = request.get_ref();
+        let mut filters = json!({});
+        if let Some(ref source) = req.source {
+            filters["source"] = json!(source);
+        }
+        if let Some(ref since) = req.since {
+            filters["since"] = json!(since);
+        }
+        if let Some(ref as_of) = req.as_of {
+            filters["as_of"] = json!(as_of);
+        }
+        if !req.metadata.is_empty() {
+            filters["metadata"] = json!(req.metadata);
+        }
+        let mut params = json!({
+            "query": req.query,
+            "fuzzy": req.fuzzy,
+            "filters": filters,
+        });
+        if !req.mode.is_empty() {
+            params["mode"] = json!(req.mode);
+        }
+        if !req.scope.is_empty() {
+            params["scope"] = json!(req.scope);
+        }
+        if let Some(limit) = req.limit {
+            params["limit"] = json!(limit);
+        }
+        if let Some(federated) = req.federated {
+            params["federated"] = json!(federated);
+        }
+
+        let result = self.call(&request, "search", params).await?;
+        let results = array(&result, "results")
+            .iter()
+            .map(|r| proto::SearchResult {
+                id: string(r, "id"),
+                score: r["score"].as_f64().unwrap_or_default(),
+                title: opt_string(r, "title"),
+                source: string(r, "source"),
+                source_id: string(r, "source_id"),
+                updated_at: string(r, "updated_at"),
+                snippet: string(r, "snippet"),
+                source_url: opt_string(r, "source_url"),
+                heading: opt_string(r, "heading"),
+                parent_id: opt_string(r, "parent_id"),
+                origin: opt_string(r, "origin"),
+            })
+            .collect();
+        Ok(Response::new(proto::SearchResponse { results }))
+    }
+
+    async fn get(
+        &self,
+        request: Request<proto::GetRequest>,
+    ) -> Result<Response<proto::Document>, Status> {
+        let params = json!({ "id": request.get_ref().id });
+        let doc = self.call(&request, "get", params).await?;
+        let chunks = array(&doc, "chunks")
+            .iter()
+            .map(|c| proto::Chunk {
+                index: c["index"].as_i64().unwrap_or_default(),
+                text: string(c, "text"),
+                metadata_json: json_string(&c["metadata"]),
+            })
+            .collect();
+        Ok(Response::new(proto::Document {
+            id: string(&doc, "id"),
+            source: string(&doc, "source"),
+            source_id: string(&doc, "source_id"),
+            source_url: opt_string(&doc, "source_url"),
+            title: opt_string(&doc, "title"),
+            author: opt_string(&doc, "author"),
+            created_at: string(&doc, "created_at"),
+            updated_at: string(&doc, "updated_at"),
+            content_type: string(&doc, "content_type"),
+            body: string(&doc, "body"),
+            metadata_json: json_string(&doc["metadata"]),
+            chunks,
+            parent_id: opt_string(&doc, "parent_id"),
+        }))
+    }
+
+    async fn sources(
+        &self,
+        request: Request<proto::SourcesRequest>,
+    ) -> Result<Response<proto::SourcesResponse>, Status> {
+        let params = json!({ "probe": request.get_ref().probe });
+        let result = self.call(&request, "sources", params).await?;
+        let sources = array(&result, "sources")
+            .iter()
+            .map(|s| proto::Source {
+                name: string(s, "name"),
+                configured: s["configured"].as_bool().unwrap_or_default(),
+                healthy: s["healthy"].as_bool().unwrap_or_default(),
+                notes: opt_string(s, "notes"),
+                latency_ms: s["latency_ms"].as_u64(),
+                error: opt_string(s, "error"),
+            })
+            .collect();
+        Ok(Response::new(proto::SourcesResponse { sources }))
+    }
+
+    async fn list_tools(
+        &self,
+        request: Request<proto::ListToolsRequest>,
+    ) -> Result<Response<proto::ListToolsResponse>, Status> {
+        let (_, scope) = self.authenticate(&request)?;
+        let tools = server::list_tools(&self.state, &self.extra_tools, scope.is_some())
+            .into_iter()
+            .map(|t| proto::Tool {
+                parameters_json: json_string(&t.parameters),
+                name: t.name,
+                description: t.description,
+                builtin: t.builtin,
+            })
+            .collect();
+        Ok(Response::new(proto::ListToolsResponse { tools }))
+    }
+
+    async fn call_tool(
+        &self,
+        request: Request<proto::CallToolRequest>,
+    ) -> Result<Response<proto::CallToolResponse>, Status> {
+        let req = request.get_ref();
+        let params: Value = if req.params_json.trim().is_empty() {
+            json!({})
+        } else {
+            serde_json::from_str(&req.params_json).map_err(|e| {
+                Status::invalid_argument(format!("params_json is not valid JSON: {}", e))
+            })?
+        };
+        let result = self.call(&request, &req.name, params).await?;
+        Ok(Response::new(proto::CallToolResponse {
+            result_json: json_string(&result),
+        }))
+    }
+}
+
+/// Serve `service` on `bind` until `shutdown` resolves. Calls still running
+/// then are allowed to finish.
+///
+/// # Errors
+///
+/// Returns an error if the address cannot be bound.
+pub(crate) async fn serve(
+    bind: &str,
+    service: GrpcService,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) -> Result<tokio::task::JoinHandle<()>> {
+    let listener = tokio::net::TcpListener::bind(bind)
+        .await
+        .with_context(|| format!("failed to bind gRPC address {}", bind))?;
+    let incoming = tonic::transport::server::TcpIncoming::from(listener);
+    Ok(tokio::spawn(async move {
+        let result = tonic::transport::Server::builder()
+            .add_service(ContextHarnessServer::new(service))
+            .serve_with_incoming_shutdown(incoming, shutdown)
+            .await;
+        if let Err(e) = result {
+            tracing::error!("gRPC server failed: {}", e);
+        }
+    }))
+}
+
+/// The gRPC status for an HTTP API error.
+fn status_of(err: AppError) -> Status {
+    let message = scrub::scrub(&err.message);
+    match err.code.as_str() {
+        "bad_request" | "embeddings_disabled" => Status::invalid_argument(message),
+        "unauthorized" => Status::unauthenticated(message),
+        auth::FORBIDDEN => Status::permission_denied(message),
+        "not_found" => Status::not_found(message),
+        "timeout" => Status::deadline_exceeded(message),
+        "unavailable" => Status::unavailable(message),
+        limits::RATE_LIMITED => Status::resource_exhausted(message),
+        _ => Status::internal(message),
+    }
+}
+
+fn array<'a>(value: &'a Value, key: &str) -> &'a [Value] {
+    value[key].as_array().map(Vec::as_slice).unwrap_or_default()
+}
+
+fn string(value: &Value, key: &str) -> String {
+    value[key].as_str().unwrap_or_default().to_string()
+}
+
+fn opt_string(value: &Value, key: &str) -> Option<String> {
+    value[key].as_str().map(str::to_string)
+}
+
+/// `value` as JSON text; `{}` when absent.
+fn json_string(value: &Value) -> String {
+    if value.is_null() {
+        "{}".to_string()
+    } else {
+        value.to_string()
+    }
+}
@@ -0,0 +1,317 @@
+//! Git hook installer for daemon-free incremental ingestion.
+//!
+//! `ctx hooks install` writes `post-commit` and `post-merge` hooks into the
+//! current repository that run a targeted sync of the matching git connector
+//! in the background after every commit or pull:
+//!
+//! ```bash
+//! ctx --config <path> sync git:<name> --since <date of HEAD~1> --no-progress
+//! ```
+//!
+//! # Connector Selection
+//!
+//! The connector is chosen with `--connector <name>`, or detected by matching
+//! each `[connectors.git.*]` `url` against the repository's top-level
+//! directory and its `origin` remote. Detection fails if zero or several
+//! connectors match.
+//!
+//! # Existing Hooks
+//!
+//! The ctx section is delimited by marker comments and appended to any
+//! existing hook, so hand-written hooks are preserved. Re-running `install`
+//! replaces the section in place; `ctx hooks uninstall` removes it (and the
+//! hook file, if nothing else is left). Hooks are resolved through
+//! `git rev-parse --git-path hooks`, so `core.hooksPath` and worktrees work.
+//!
+//! Sync output is appended to `ctx-sync.log` in the git directory.
+
+use anyhow::{bail, Context, Result};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::config::Config;
+use crate::ctx_dirs;
+
+/// Hooks managed by `ctx hooks install`.
+const HOOK_NAMES: &[&str] = &["post-commit", "post-merge"];
+
+const BEGIN_MARKER: &str = "# >>> ctx hooks >>>";
+const END_MARKER: &str = "# <<< ctx hooks <<<";
+
+/// CLI entry point for `ctx hooks install`.
+pub fn run_install(
+    config: &Config,
+    config_path: Option<&Path>,
+    connector: Option<&str>,
+) -> Result<()> {
+    let repo_root = git_output(None, &["rev-parse", "--show-toplevel"])
+        .context("ctx hooks must be run inside a git repository")?;
+    let repo_root = PathBuf::from(repo_root);
+    let name = match connector {
+        Some(name) => {
+            if !config.connectors.git.contains_key(name) {
+                bail!("No git connector named '{}' in config", name);
+            }
+            name.to_string()
+        }
+        None => detect_connector(config, &repo_root)?,
+    };
+
+    let ctx_bin = std::env::current_exe()
+        .map(|p| p.display().to_string())
+        .unwrap_or_else(|_| "ctx".to_string());
+    let config_path = config_path.map(|p| {
+        std::path::absolute(p)
+            .unwrap_or_else(|_| p.to_path_buf())
+            .display()
+            .to_string()
+    });
+    let block = render_hook_block(&ctx_bin, config_path.as_deref(), &name);
+
+    let hooks_dir = hooks_dir(&repo_root)?;
+    std::fs::create_dir_all(&hooks_dir)?;
+    for hook in HOOK_NAMES {
+        let path = hooks_dir.join(hook);
+        let existing = std::fs::read_to_string(&path).ok();
+        std::fs::write(&path, merge_hook(existing.as_deref(), &block))
+            .with_context(|| format!("writing {}", path.display()))?;
+        make_executable(&path)?;
+        println!("Installed {}", path.display());
+    }
+    println!(
+        "Commits and merges in {} will now sync git:{}.",
+        repo_root.display(),
+        name
+    );
+    Ok(())
+}
+
+/// CLI entry point for `ctx hooks uninstall`.
+pub fn run_uninstall() -> Result<()> {
+    let repo_root = git_output(None, &["rev-parse", "--show-toplevel"])
+        .context("ctx hooks must be run inside a git repository")?;
+    let hooks_dir = hooks_dir(Path::new(&repo_root))?;
+
+    for hook in HOOK_NAMES {
+        let path = hooks_dir.join(hook);
+        let Ok(existing) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        if !existing.contains(BEGIN_MARKER) {
+            continue;
+        }
+        match remove_hook_block(&existing) {
+            Some(rest) => std::fs::write(&path, rest)?,
+            None => std::fs::remove_file(&path)?,
+        }
+        println!("Removed ctx section from {}", path.display());
+    }
+    Ok(())
+}
+
+/// Pick the git connector whose `url` points at this repository.
+fn detect_connector(config: &Config, repo_root: &Path) -> Result<String> {
+    let origin = git_output(Some(repo_root), &["remote", "get-url", "origin"]).ok();
+    let repo_root = repo_root.canonicalize().ok();
+
+    let mut matches: Vec<&String> = config
+        .connectors
+        .git
+        .iter()
+        .filter(|(_, cfg)| {
+            let local = ctx_dirs::expand_tilde(Path::new(&cfg.url))
+                .canonicalize()
+                .ok();
+            (local.is_some() && local == repo_root)
+                || origin
+                    .as_deref()
+                    .is_some_and(|o| normalize_remote(o) == normalize_remote(&cfg.url))
+        })
+        .map(|(name, _)| name)
+        .collect();
+    matches.sort();
+
+    match matches.as_slice() {
+        [name] => Ok((*name).clone()),
+        [] => bail!(
+            "No [connectors.git.*] entry matches this repository. \
+             Add one, or pass --connector <name>."
+        ),
+        many => bail!(
+            "Several git connectors match this repository ({}). Pass --connector <name>.",
+            many.iter()
+                .map(|s| s.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+/// Reduce a remote URL to `host/path` so `https://` and `git@` forms compare equal.
+fn normalize_remote(url: &str) -> String {
+    let url = url.trim().trim_end_matches('/').trim_end_matches(".git");
+    let url = url.split_once("://").map(|(_, rest)| rest).unwrap_or(url);
+    let url = url.rsplit_once('@').map(|(_, rest)| rest).unwrap_or(url);
+    url.replacen(':', "/", 1).to_lowercase()
+}
+
+/// Render the marker-delimited hook section.
+fn render_hook_block(ctx_bin: &str, config_path: Option<&str>, connector: &str) -> String {
+    let config_arg = config_path
+        .map(|p| format!(" --config {}", shell_quote(p)))
+        .unwrap_or_default();
+    format!(
+        "{begin}\n\
+         # Managed by `ctx hooks install`; remove with `ctx hooks uninstall`.\n\
+         ctx_since=$(git log -1 --format=%cs HEAD~1 2>/dev/null || date +%Y-%m-%d)\n\
+         ctx_log=\"$(git rev-parse --git-dir)/ctx-sync.log\"\n\
+         ( {bin}{config} sync {source} --since \"$ctx_since\" --no-progress >>\"$ctx_log\" 2>&1 & )\n\
+         {end}\n",
+        begin = BEGIN_MARKER,
+        end = END_MARKER,
+        bin = shell_quote(ctx_bin),
+        config = config_arg,
+        source = shell_quote(&format!("git:{}", connector)),
+    )
+}
+
+/// Insert or replace the ctx section in an existing hook script.
+fn merge_hook(existing: Option<&str>, block: &str) -> String {
+    match existing.and_then(remove_hook_block) {
+        Some(rest) => format!("{}\n\n{}", rest.trim_end(), block),
+        None => format!("#!/bin/sh\n{}", block),
+    }
+}
+
+/// Strip the ctx section from a hook script.
+///
+/// Returns `None` when nothing but a shebang would remain.
+fn remove_hook_block(existing: &str) -> Option<String> {
+    let mut out = String::new();
+    let mut inside = false;
+    for line in existing.lines() {
+        if line.trim() == BEGIN_MARKER {
+            inside = true;
+            continue;
+        }
+        if line.trim() == END_MARKER {
+            inside = false;
+            continue;
+        }
+        if !inside {
+            out.push_str(line);
+            out.push('\n');
+        }
+    }
+    let meaningful = out
+        .lines()
+        .any(|l| !l.trim().is_empty() && !l.starts_with("#!"));
+    meaningful.then(|| out.trim_end().to_string() + "\n")
+}
+
+/// Quote a value for POSIX `sh`.
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+fn hooks_dir(repo_root: &Path) -> Result<PathBuf> {
+    let path = PathBuf::from(git_output(
+        Some(repo_root),
+        &["rev-parse", "--git-path", "hooks"],
+    )?);
+    Ok(if path.is_absolute() {
+        path
+    } else {
+        repo_root.join(path)
+    })
+}
+
+fn git_output(dir: Option<&Path>, args: &[&str]) -> Result<String> {
+    let mut cmd = Command::new("git");
+    cmd.args(args);
+    if let Some(dir) = dir {
+        cmd.current_dir(dir);
+    }
+    let output = cmd
+        .output()
+        .with_context(|| format!("Failed to execute 'git {}'", args.join(" ")))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("git {} failed: {}", args.join(" "), stderr.trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    let mut perms = std::fs::metadata(path)?.permissions();
+    perms.set_mode(perms.mode() | 0o755);
+    std::fs::set_permissions(path, perms)?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn make_executable(_path: &Path) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge_creates_preserves_and_replaces() {
+        let block = render_hook_block("/usr/bin/ctx", Some("/repo/.ctx/config.toml"), "docs");
+        assert!(block.contains("sync 'git:docs' --since"));
+        assert!(block.contains("--config '/repo/.ctx/config.toml'"));
+
+        let fresh = merge_hook(None, &block);
+        assert!(fresh.starts_with("#!/bin/sh\n"));
+
+        let custom = "#!/bin/sh\necho hello\n";
+        let merged = merge_hook(Some(custom), &block);
+        assert!(merged.starts_with(custom.trim_end()));
+        assert_eq!(merged.matches(BEGIN_MARKER).count(), 1);
+
+        let other = render_hook_block("/usr/bin/ctx", None, "platform");
+        let replaced = merge_hook(Some(&merged), &other);
+        assert_eq!(replaced.matches(BEGIN_MARKER).count(), 1);
+        assert!(replaced.contains("git:platform"));
+        assert!(!replaced.contains("git:docs"));
+        assert!(replaced.contains("echo hello"));
+    }
+
+    #[test]
+    fn connector_names_are_quoted() {
+        let block = render_hook_block("ctx", None, "my docs; rm -rf ~");
+        assert!(block.contains("sync 'git:my docs; rm -rf ~' --since"));
+
+        let block = render_hook_block("ctx", None, "it's");
+        assert!(block.contains(r"sync 'git:it'\''s' --since"));
+    }
+
+    #[test]
+    fn remove_drops_empty_hooks() {
+        let block = render_hook_block("ctx", None, "docs");
+        assert_eq!(remove_hook_block(&merge_hook(None, &block)), None);
+
+        let merged = merge_hook(Some("#!/bin/sh\necho hello\n"), &block);
+        assert_eq!(
+            remove_hook_block(&merged).as_deref(),
+            Some("#!/bin/sh\necho hello\n")
+        );
+    }
+
+    #[test]
+    fn normalize_remote_matches_ssh_and_https() {
+        assert_eq!(
+            normalize_remote("git@github.com:acme/Platform.git"),
+            normalize_remote("https://github.com/acme/platform")
+        );
+        assert_ne!(
+            normalize_remote("https://github.com/acme/platform"),
+            normalize_remote("https://github.com/acme/other")
+        );
+    }
+}
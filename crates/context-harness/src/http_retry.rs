@@ -0,0 +1,101 @@
+//! Throttling retries for connector API requests.
+//!
+//! Microsoft Graph, Zendesk, and the Stack Exchange API answer bursts of
+//! requests with `429 Too Many Requests` or `503 Service Unavailable`,
+//! usually with a `Retry-After` header. [`send`] retries such responses up
+//! to three times, waiting for `Retry-After` (at most a minute) or 2, 4,
+//! then 8 seconds without one, and hands back the final response for the
+//! connector to interpret.
+
+use anyhow::{Context, Result};
+use reqwest::{RequestBuilder, StatusCode};
+use serde::de::DeserializeOwned;
+use std::time::Duration;
+
+/// Attempts per request when throttled.
+pub const MAX_ATTEMPTS: usize = 4;
+/// Longest `Retry-After` wait honoured, in seconds.
+pub const MAX_RETRY_AFTER_SECS: u64 = 60;
+
+/// A response read to the end.
+#[derive(Debug)]
+pub struct Reply {
+    pub status: StatusCode,
+    pub body: Vec<u8>,
+}
+
+impl Reply {
+    /// The body as text, for error messages.
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+
+    /// Parse the body as JSON.
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T> {
+        serde_json::from_slice(&self.body).context("response is not valid JSON")
+    }
+}
+
+/// Send `request`, retrying `429` and `503` responses.
+///
+/// Returns the last response whatever its status. Fails only if the
+/// request cannot be sent or its body cannot be read.
+pub async fn send(request: RequestBuilder) -> Result<Reply> {
+    send_with(request, |_| false).await
+}
+
+/// Like [`send`], also retrying error responses whose body `throttled`
+/// recognizes, for APIs that report throttling in an ordinary error.
+pub async fn send_with(
+    request: RequestBuilder,
+    throttled: impl Fn(&[u8]) -> bool,
+) -> Result<Reply> {
+    for attempt in 1..=MAX_ATTEMPTS {
+        let resp = request
+            .try_clone()
+            .context("streaming request bodies cannot be retried")?
+            .send()
+            .await?;
+        let status = resp.status();
+        let retry_after = resp
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse::<u64>().ok());
+        let body = resp.bytes().await?.to_vec();
+
+        let retry = status == StatusCode::TOO_MANY_REQUESTS
+            || status == StatusCode::SERVICE_UNAVAILABLE
+            || (!status.is_success() && throttled(&body));
+        if retry && attempt < MAX_ATTEMPTS {
+            tokio::time::sleep(retry_wait(attempt, retry_after)).await;
+            continue;
+        }
+        return Ok(Reply { status, body });
+    }
+    unreachable!("loop returns on the last attempt")
+}
+
+/// Wait before retrying after attempt `attempt` (1-based): `Retry-After`
+/// when the server sent one, otherwise `2^attempt` seconds, capped at
+/// [`MAX_RETRY_AFTER_SECS`].
+fn retry_wait(attempt: usize, retry_after: Option<u64>) -> Duration {
+    let secs = retry_after.unwrap_or(1 << attempt.min(6));
+    Duration::from_secs(secs.min(MAX_RETRY_AFTER_SECS))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retry_wait_prefers_retry_after_and_caps() {
+        assert_eq!(retry_wait(1, None), Duration::from_secs(2));
+        assert_eq!(retry_wait(3, None), Duration::from_secs(8));
+        assert_eq!(retry_wait(1, Some(5)), Duration::from_secs(5));
+        assert_eq!(
+            retry_wait(1, Some(3600)),
+            Duration::from_secs(MAX_RETRY_AFTER_SECS)
+        );
+    }
+}
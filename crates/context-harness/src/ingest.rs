@@ -14,17 +14,94 @@
 //!    - `"git"` — all instances of a type
 //!    - `"all"` — every configured connector
 //! 2. **Scan (parallel)** — dispatches to the appropriate connector for each
-//!    target, running scans concurrently via [`tokio::task::JoinSet`].
+//!    target, running scans concurrently via [`tokio::task::JoinSet`]. Files a
+//!    connector skipped while scanning (e.g. binary or oversized) are counted
+//!    via [`Connector::skipped`].
 //! 3. **Filter** — applies checkpoint, `--since`, `--until`, and `--limit`
 //!    filters to each connector's items.
-//! 4. **Upsert documents** — inserts or updates each item in the `documents`
-//!    table, computing a SHA-256 deduplication hash.
-//! 5. **Replace chunks** — deletes old chunks (and their embeddings/FTS entries)
-//!    for the document, then inserts fresh chunks.
-//! 6. **Inline embed** — if embeddings are enabled, embeds new chunks
-//!    immediately (non-fatal: failures are logged but do not abort the sync).
-//! 7. **Update checkpoint** — persists the latest `updated_at` timestamp
+//! 4. **Redact** — for connectors listed in `[redaction] connectors`,
+//!    replaces emails, phone numbers, API keys, and custom patterns in the
+//!    title and body (see [`crate::redact`]).
+//! 5. **Upsert documents** — inserts or updates each item in the `documents`
+//!    table, computing a SHA-256 deduplication hash. With `[summary]`
+//!    enabled, long documents are stored with a summary (see
+//!    [`crate::summarize`]); with `[classify]` enabled, each document is
+//!    tagged with a `doc_type` (see [`crate::classify`]).
+//! 6. **Replace chunks** — deletes old chunks (and their embeddings/FTS entries)
+//!    for the document, then inserts fresh chunks using the connector's
+//!    effective chunking settings ([`Config::chunking_for`]). Chunks inherit
+//!    the document fields listed in `[chunking] inherit_metadata`, a Git
+//!    `ref`, and, for markdown, a `heading` breadcrumb. Items matching a connector's
+//!    `stitch` rules are kept aside instead, and the stitched documents
+//!    they belong to are rebuilt once the connector's items are written
+//!    (see [`crate::stitch`]).
+//! 7. **Inline embed** — if embeddings are enabled and `[sync] embed` is on,
+//!    queues new chunks and embeds them in `embedding.batch_size` batches
+//!    with one provider for the whole run (see
+//!    [`crate::embed_cmd::InlineEmbedder`]). Non-fatal: failed chunks are
+//!    counted as pending for `ctx embed pending`.
+//! 8. **Update checkpoint** — persists the latest `updated_at` timestamp
 //!    so the next incremental sync can skip unchanged items.
+//! 9. **Enforce storage budget** — with `[storage] max_size_mb` set, evicts
+//!    documents until the database fits (see [`crate::storage`]).
+//! 10. **Notify** — unless dry-running, posts a summary of the run to the
+//!     matching `[notifications]` webhooks (see [`crate::notify`]).
+//!
+//! # Time Limits and Interruption
+//!
+//! Each connector runs under a [`CancellationToken`]. It fires when the
+//! connector has run for `[sync] timeout_secs`, or for every connector when
+//! the user presses Ctrl-C (a second Ctrl-C exits at once) or cancels the
+//! sync's job with `ctx jobs cancel` (see [`crate::jobs`]). Ingest stops
+//! between documents, so every document written so far is complete with
+//! its chunks and embeddings, and the scan is stopped through
+//! [`Connector::scan_cancellable`]. `ctx jobs pause` holds ingest at the
+//! same point until the job is resumed or cancelled.
+//!
+//! A connector that timed out is reported like a failed scan and the sync
+//! moves on to the next one. Ctrl-C skips the remaining connectors, prints
+//! what was written, and fails the run. Either way the stopped connector's
+//! checkpoint is left unchanged: items arrive in no particular order, so
+//! the next sync revisits them, and documents that were already written
+//! are rewritten without new chunks or embeddings. Connectors that
+//! finished before the interrupt keep their new checkpoints.
+//!
+//! # Item Failures
+//!
+//! An item that fails to extract or write, or that the connector could not
+//! fetch ([`Connector::failed`]), does not stop the sync: it is listed in
+//! the connector's summary, reported as an `item_failed` progress event,
+//! and recorded in `sync_failures` while the remaining items are ingested.
+//! The checkpoint still advances, so [`run_retry_failed`] (`ctx sync
+//! --retry-failed`) scans the connector again and ingests only the
+//! recorded items. `[sync] strict` (`--strict`) fails the sync on the first
+//! failed item instead. See [`crate::sync_failures`].
+//!
+//! # CPU-Bound Stages
+//!
+//! Extraction, redaction, hashing, normalization, and chunking need no I/O,
+//! so they run on a separate pool of `[sync] cpu_threads` threads (default:
+//! one per CPU) instead of the async runtime. A connector's items are
+//! prepared there in groups, the next group in parallel while the current
+//! one is written to SQLite and embedded, and are still written in the
+//! order the connector delivered them.
+//!
+//! # Reprocessing
+//!
+//! Connectors listed in `[sync] keep_raw` have each item stored as
+//! delivered, before extraction (see [`crate::raw_items`]).
+//! [`run_reprocess`] feeds those items through steps 4–7 again without
+//! contacting the source, e.g. after changing `[redaction]` or
+//! `[chunking]`. Checkpoints are not touched.
+//!
+//! # Deletion
+//!
+//! Connectors only report the items that exist, so a sync never removes
+//! documents. [`delete_source_items`] removes the documents of items a
+//! connector no longer has, keyed by `(source, source_id)`; `ctx watch`
+//! calls it when a file disappears (see [`crate::watch`]). An item that was
+//! stitched into another document is removed from it instead, and the
+//! document is rebuilt.
 //!
 //! # Deduplication
 //!
@@ -33,12 +110,33 @@
 //! upsert. The `dedup_hash` field is a SHA-256 digest of source + source_id +
 //! updated_at + body, enabling downstream consumers to detect changes.
 //!
+//! The upsert, chunk replacement, and FTS update of a document run in one
+//! transaction keyed by `(source, source_id, dedup_hash)` (see
+//! [`crate::sqlite_store::SqliteStore::write_document`]). A sync that
+//! crashes mid-batch and restarts rewrites each document whole, and a
+//! document whose hash and chunks are unchanged keeps its chunk IDs and
+//! embeddings.
+//!
+//! With `[sync] content_dedup`, an item whose body matches a document of
+//! another source is stored without chunks and linked to it, or skipped
+//! (see [`crate::content_dedup`]).
+//!
 //! # Checkpointing
 //!
 //! Checkpoints are stored in the `checkpoints` table as `(source, cursor)`
 //! pairs. The cursor is the maximum `updated_at` timestamp seen during the
 //! sync. On subsequent runs, only items newer than the checkpoint are processed.
 //!
+//! A connector can also keep its own cursor, such as a page token, in the
+//! checkpoint's `state` column: [`Connector::resume_from`] receives it before
+//! the scan and [`Connector::checkpoint_state`] is saved after a completed
+//! one. Lua connectors use this through `checkpoint.get`/`checkpoint.set`
+//! (see [`crate::connector_script`]). `--full` starts both from scratch.
+//!
+//! Each connector's outcome (completed with its item count, or failed with
+//! the error) is also recorded in `source_sync_status` for `ctx sources`
+//! (see [`crate::sync_status`]).
+//!
 //! # Multi-Instance Connectors
 //!
 //! All connector types support named instances. Documents are tagged with
@@ -46,35 +144,104 @@
 //! When syncing a type (e.g. `ctx sync git`), all instances of that type
 //! are scanned in parallel.
 
-use anyhow::{bail, Result};
+use anyhow::{bail, Context, Result};
 use chrono::NaiveDate;
-use context_harness_core::store::Store;
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use rayon::ThreadPool;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashSet};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, oneshot};
+use tracing::Instrument;
 
-use crate::app_store::{AppStore, SqliteAppStore};
-use crate::chunk::chunk_text;
-use crate::config::Config;
-use crate::embed_cmd;
+use crate::app_store::{self, AppStore, SqliteAppStore};
+use crate::audit::{AuditEntry, AuditLog};
+use crate::chunk::{
+    self, annotate_chunks, apply_overlap, chunk_text_with, is_markdown, ChunkStrategy,
+    SECTION_SOURCE_ID_KEY, SECTION_SOURCE_URL_KEY,
+};
+use crate::classify::Classifier;
+use crate::config::{ChunkingConfig, Config};
+use crate::content_dedup::{self, Canonical, DedupMode};
+use crate::contextualize::Contextualizer;
+use crate::embed_cmd::InlineEmbedder;
 use crate::extract;
-use crate::models::SourceItem;
+use crate::jobs::{Job, JobControl};
+use crate::migrate;
+use crate::models::{Chunk, Document, SourceItem};
+use crate::normalize::Normalizer;
+use crate::notify::{self, RunReport};
 use crate::progress::{SyncProgressEvent, SyncProgressReporter};
-use crate::traits::{Connector, ConnectorRegistry};
+use crate::raw_items;
+use crate::redact::{self, RedactionCounts, Redactor};
+use crate::search::REF_KEY;
+use crate::search_cache;
+use crate::stats::{self, format_bytes};
+use crate::stitch::{self, Stitcher};
+use crate::storage;
+use crate::summarize::Summarizer;
+use crate::sync_failures::{self, ItemFailure};
+use crate::sync_status;
+use crate::traits::{CancellationToken, Connector, ConnectorRegistry};
+
+/// Select the document fields copied onto each chunk (`[chunking] inherit_metadata`).
+///
+/// Each key is looked up in the item's `metadata_json` first, then in its
+/// top-level fields. Missing keys are skipped. A Git `ref` is always
+/// copied, so the `ref:` search modifier can filter on it.
+fn inherited_chunk_metadata(
+    item: &SourceItem,
+    keys: &[String],
+) -> serde_json::Map<String, serde_json::Value> {
+    let mut out = serde_json::Map::new();
+    let doc_meta: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_str(&item.metadata_json).unwrap_or_default();
+    if let Some(git_ref) = doc_meta.get(REF_KEY) {
+        out.insert(REF_KEY.to_string(), git_ref.clone());
+    }
 
-/// Default max extract size when connector is not filesystem or name not found (spec §4.1).
+    for key in keys {
+        let value = match doc_meta.get(key) {
+            Some(v) => Some(v.clone()),
+            None => match key.as_str() {
+                "source" => Some(item.source.clone().into()),
+                "source_id" => Some(item.source_id.clone().into()),
+                "source_url" => item.source_url.clone().map(Into::into),
+                "title" => item.title.clone().map(Into::into),
+                "author" => item.author.clone().map(Into::into),
+                "content_type" => Some(item.content_type.clone().into()),
+                _ => None,
+            },
+        };
+        if let Some(v) = value {
+            out.insert(key.clone(), v);
+        }
+    }
+    out
+}
+
+/// Default max extract size when the connector has no `max_extract_bytes` setting (spec §4.1).
 const DEFAULT_MAX_EXTRACT_BYTES: u64 = 50_000_000;
 
-/// Resolve max_extract_bytes for a source from config. Parses "filesystem:name" and looks up
-/// the connector config; non-filesystem or unknown name uses DEFAULT_MAX_EXTRACT_BYTES.
+/// Resolve max_extract_bytes for a source from config. Parses "filesystem:name" or
+/// "sharepoint:name" and looks up the connector config; other or unknown sources use
+/// DEFAULT_MAX_EXTRACT_BYTES.
 fn max_extract_bytes_for_source(config: &Config, source_label: &str) -> u64 {
-    if let Some(name) = source_label.strip_prefix("filesystem:") {
-        config
+    let limit = match source_label.split_once(':') {
+        Some(("filesystem", name)) => config
             .connectors
             .filesystem
             .get(name)
-            .map(|c| c.max_extract_bytes)
-            .unwrap_or(DEFAULT_MAX_EXTRACT_BYTES)
-    } else {
-        DEFAULT_MAX_EXTRACT_BYTES
-    }
+            .map(|c| c.max_extract_bytes),
+        Some(("sharepoint", name)) => config
+            .connectors
+            .sharepoint
+            .get(name)
+            .map(|c| c.max_extract_bytes),
+        _ => None,
+    };
+    limit.unwrap_or(DEFAULT_MAX_EXTRACT_BYTES)
 }
 
 /// Resolve a connector argument into a filtered list of connectors to scan.
@@ -90,11 +257,14 @@ fn max_extract_bytes_for_source(config: &Config, source_label: &str) -> u64 {
 /// | `"git"` | All connectors of type `"git"` |
 /// | `"filesystem"` | All connectors of type `"filesystem"` |
 /// | `"s3"` | All connectors of type `"s3"` |
+/// | `"sharepoint"` | All connectors of type `"sharepoint"` |
+/// | `"zendesk"` | All connectors of type `"zendesk"` |
+/// | `"stackoverflow"` | All connectors of type `"stackoverflow"` |
 /// | `"script"` | All connectors of type `"script"` |
 /// | `"custom"` | All connectors of type `"custom"` |
 /// | `"git:platform"` | Specific named instance |
 /// | `"custom:myconn"` | Specific named instance |
-fn resolve_connectors<'a>(
+pub(crate) fn resolve_connectors<'a>(
     registry: &'a ConnectorRegistry,
     connector_arg: &str,
 ) -> Result<Vec<&'a dyn Connector>> {
@@ -106,8 +276,20 @@ fn resolve_connectors<'a>(
             }
             Ok(all.iter().map(|c| c.as_ref()).collect())
         }
-        // Type-level filter: "git", "filesystem", "s3", "script", "custom"
-        conn_type if matches!(conn_type, "filesystem" | "git" | "s3" | "script" | "custom") => {
+        // Type-level filter: "git", "filesystem", "s3", "sharepoint", "zendesk", "stackoverflow", "script", "custom"
+        conn_type
+            if matches!(
+                conn_type,
+                "filesystem"
+                    | "git"
+                    | "s3"
+                    | "sharepoint"
+                    | "zendesk"
+                    | "stackoverflow"
+                    | "script"
+                    | "custom"
+            ) =>
+        {
             let matched = registry.connectors_by_type(conn_type);
             if matched.is_empty() {
                 bail!("No {} connectors configured.", conn_type);
@@ -137,7 +319,7 @@ fn resolve_connectors<'a>(
                 Ok(vec![conn])
             } else {
                 bail!(
-                    "Unknown connector: '{}'. Use: all, filesystem, git, s3, script, custom, or type:name",
+                    "Unknown connector: '{}'. Use: all, filesystem, git, s3, sharepoint, zendesk, stackoverflow, script, custom, or type:name",
                     other
                 );
             }
@@ -166,7 +348,8 @@ fn resolve_connectors<'a>(
 ///
 /// Returns an error if:
 /// - The specified connector is unknown or not configured.
-/// - A connector fails to scan (e.g., network error).
+/// - Every connector fails to scan (e.g., network error) or times out.
+/// - The sync is interrupted with Ctrl-C.
 /// - A database operation fails.
 #[allow(clippy::too_many_arguments)]
 pub async fn run_sync(
@@ -205,26 +388,47 @@ pub async fn run_sync_with_extensions(
     limit: Option<usize>,
     extra_connectors: &ConnectorRegistry,
 ) -> Result<()> {
-    // Build combined connector list from config + extras
-    let built_in = ConnectorRegistry::from_config(config);
-
-    // Resolve from built-in registry (ignore error if extras might match)
-    let mut resolved: Vec<&dyn Connector> = match resolve_connectors(&built_in, connector) {
-        Ok(r) => r,
-        Err(_) if !extra_connectors.is_empty() => Vec::new(),
-        Err(e) => return Err(e),
-    };
+    let started = Instant::now();
+    let mut summary = SyncSummary::default();
+    let result = async {
+        // Build combined connector list from config + extras
+        let built_in = ConnectorRegistry::from_config(config);
 
-    // Also resolve from extras
-    if let Ok(extras) = resolve_connectors(extra_connectors, connector) {
-        resolved.extend(extras);
-    }
+        // Resolve from built-in registry (ignore error if extras might match)
+        let mut resolved: Vec<&dyn Connector> = match resolve_connectors(&built_in, connector) {
+            Ok(r) => r,
+            Err(_) if !extra_connectors.is_empty() => Vec::new(),
+            Err(e) => return Err(e),
+        };
 
-    if resolved.is_empty() {
-        bail!("No connectors matched '{}'.", connector);
-    }
+        // Also resolve from extras
+        if let Ok(extras) = resolve_connectors(extra_connectors, connector) {
+            resolved.extend(extras);
+        }
 
-    run_connectors(config, &resolved, full, dry_run, since, until, limit, None).await
+        if resolved.is_empty() {
+            bail!("No connectors matched '{}'.", connector);
+        }
+
+        run_connectors(
+            config,
+            &resolved,
+            full,
+            dry_run,
+            since,
+            until,
+            limit,
+            None,
+            &mut summary,
+            false,
+        )
+        .await
+    }
+    .await;
+    if !dry_run {
+        summary.notify(config, connector, started, &result).await;
+    }
+    result
 }
 
 /// Runs the ingestion pipeline with a pre-built [`ConnectorRegistry`].
@@ -243,28 +447,314 @@ pub async fn run_sync_with_registry(
     registry: &ConnectorRegistry,
     progress: Option<&dyn SyncProgressReporter>,
 ) -> Result<()> {
-    let connectors = resolve_connectors(registry, connector)?;
-    run_connectors(
+    let started = Instant::now();
+    let mut summary = SyncSummary::default();
+    let result = match resolve_connectors(registry, connector) {
+        Ok(connectors) => {
+            run_connectors(
+                config,
+                &connectors,
+                full,
+                dry_run,
+                since,
+                until,
+                limit,
+                progress,
+                &mut summary,
+                false,
+            )
+            .await
+        }
+        Err(e) => Err(e),
+    };
+    if !dry_run {
+        summary.notify(config, connector, started, &result).await;
+    }
+    result
+}
+
+/// Retry the items earlier syncs of `connector` recorded as failed.
+///
+/// This is the entry point for `ctx sync <connector> --retry-failed`. Each
+/// matching connector with recorded failures is scanned again from the
+/// start, and only the failed items are ingested. Checkpoints and
+/// connector state are left unchanged. Failures of items the scan no
+/// longer delivers are dropped.
+///
+/// # Errors
+///
+/// Same as [`run_sync`].
+pub async fn run_retry_failed(
+    config: &Config,
+    connector: &str,
+    progress: Option<&dyn SyncProgressReporter>,
+) -> Result<()> {
+    let registry = ConnectorRegistry::from_config(config);
+    let connectors = resolve_connectors(&registry, connector)?;
+    let store = SqliteAppStore::connect(config).await?;
+    let mut pending = Vec::new();
+    for conn in connectors {
+        if !sync_failures::failed_ids(store.pool(), &conn.source_label())
+            .await?
+            .is_empty()
+        {
+            pending.push(conn);
+        }
+    }
+    store.close().await;
+    if pending.is_empty() {
+        println!("No failed items to retry for {}.", connector);
+        return Ok(());
+    }
+
+    let started = Instant::now();
+    let mut summary = SyncSummary::default();
+    let result = run_connectors(
         config,
-        &connectors,
-        full,
-        dry_run,
-        since,
-        until,
-        limit,
+        &pending,
+        false,
+        false,
+        None,
+        None,
+        None,
         progress,
+        &mut summary,
+        true,
     )
-    .await
+    .await;
+    summary.notify(config, connector, started, &result).await;
+    result
+}
+
+/// Delete the documents of items that no longer exist in `source`.
+///
+/// Each `source_id` is removed with its chunks, FTS rows, embeddings, and
+/// vectors, and an archive's `source_id` removes its members too. Every
+/// deleted item is recorded in the `[tools.audit]` log as a `delete` entry
+/// with transport `sync`. Members of stitched documents are removed from
+/// them and the documents rebuilt. Returns the number of documents deleted.
+pub async fn delete_source_items(
+    config: &Config,
+    source: &str,
+    source_ids: &[String],
+) -> Result<u64> {
+    if source_ids.is_empty() {
+        return Ok(0);
+    }
+    let store = SqliteAppStore::connect(config).await?;
+    let audit = AuditLog::open(config).await?;
+    let mut deleted = 0;
+    for source_id in source_ids {
+        let started = Instant::now();
+        let result = store.delete_source_item(source, source_id).await;
+        if let Some(ref audit) = audit {
+            let params = serde_json::json!({
+                "source": source,
+                "source_id": source_id,
+                "documents": result.as_ref().ok(),
+            });
+            let error = result.as_ref().err().map(|e| e.to_string());
+            audit
+                .record(AuditEntry::new(
+                    "delete",
+                    None,
+                    "sync",
+                    params,
+                    started.elapsed(),
+                    error,
+                ))
+                .await;
+        }
+        deleted += result?.len() as u64;
+    }
+
+    // Stitched items are rebuilt into their documents without them.
+    migrate::create_stitch_members_table(store.pool()).await?;
+    let groups = stitch::remove_members(store.pool(), source, source_ids).await?;
+    if !groups.is_empty() {
+        let mut ingest = ConnectorIngest::new(config, source, None, false, None)?;
+        for group in &groups {
+            ingest.write_stitched(&store, group, None).await?;
+        }
+    }
+
+    if deleted > 0 || !groups.is_empty() {
+        search_cache::bump_index_generation(store.pool()).await?;
+        store.refresh_keyword_index().await?;
+    }
+    store.close().await;
+    Ok(deleted)
+}
+
+/// Items loaded from `raw_items` per query during [`run_reprocess`].
+const REPROCESS_PAGE: i64 = 200;
+
+/// Re-run extraction, redaction, chunking, and embedding on the items
+/// kept for the connectors matching `selector` (`"all"`, a type, or an
+/// instance), without contacting their sources.
+///
+/// Items are read from `raw_items` (see [`crate::raw_items`]) and written
+/// like a sync would; unchanged documents keep their chunks and
+/// embeddings. Checkpoints are not touched.
+///
+/// # Errors
+///
+/// Returns an error if no items are kept for `selector`, or a database
+/// operation fails.
+pub async fn run_reprocess(config: &Config, selector: &str) -> Result<()> {
+    let store = SqliteAppStore::connect(config).await?;
+    migrate::ensure_titles_table(store.pool(), &config.retrieval.fts_tokenizer).await?;
+    migrate::create_raw_items_table(store.pool()).await?;
+    migrate::create_stitch_members_table(store.pool()).await?;
+    if config.sync.content_dedup != "keep-all" {
+        migrate::create_content_hash_indexes(store.pool()).await?;
+    }
+
+    let labels: Vec<String> = raw_items::connectors(store.pool())
+        .await?
+        .into_iter()
+        .filter(|label| raw_items::matches(selector, label))
+        .collect();
+    if labels.is_empty() {
+        store.close().await;
+        bail!(
+            "No raw items kept for '{}'. Add the connector to [sync] keep_raw and sync it first.",
+            selector
+        );
+    }
+
+    let mut embedder = if config.sync.embed {
+        InlineEmbedder::new(config)
+    } else {
+        None
+    };
+    if let Some(ref embedder) = embedder {
+        embedder.check_dims(config, store.pool()).await?;
+    }
+
+    for label in labels {
+        let mut ingest = ConnectorIngest::new(config, &label, None, false, embedder.as_mut())?;
+        ingest.keep_raw = false;
+        let mut after: Option<(String, String)> = None;
+        loop {
+            let items =
+                raw_items::page(store.pool(), &label, after.as_ref(), REPROCESS_PAGE).await?;
+            let Some(last) = items.last() else {
+                break;
+            };
+            after = Some((last.source.clone(), last.source_id.clone()));
+            ingest.fetched += items.len() as u64;
+            ingest
+                .ingest_items(&store, items, None, &CancellationToken::new())
+                .await?;
+        }
+        ingest.flush_stitched(&store, None).await?;
+        ingest.flush_embeddings(&store, None).await;
+        if ingest.docs_upserted > 0 {
+            search_cache::bump_index_generation(store.pool()).await?;
+            store.refresh_keyword_index().await?;
+        }
+
+        println!("reprocess {}", label);
+        println!("  raw items: {}", ingest.fetched);
+        ingest.print_written();
+        println!("ok");
+    }
+
+    enforce_storage_budget(config, store.pool()).await?;
+    store.close().await;
+    Ok(())
+}
+
+/// Totals across every connector of a sync, sent to `[notifications]`.
+#[derive(Default)]
+struct SyncSummary {
+    connectors: u64,
+    fetched: u64,
+    documents_upserted: u64,
+    chunks_written: u64,
+    embeddings_written: u64,
+    embeddings_pending: u64,
+    extraction_skipped: u64,
+    duplicates: u64,
+    items_failed: u64,
+    /// Failed items per connector, for the end-of-sync report.
+    failed_by_source: Vec<(String, usize)>,
+    scan_errors: Vec<String>,
+}
+
+impl SyncSummary {
+    fn add(&mut self, ingest: &ConnectorIngest) {
+        self.connectors += 1;
+        self.fetched += ingest.fetched;
+        self.documents_upserted += ingest.docs_upserted;
+        self.chunks_written += ingest.chunks_written;
+        self.embeddings_written += ingest.embeddings_written;
+        self.embeddings_pending += ingest.embeddings_pending;
+        self.extraction_skipped += ingest.extraction_skipped;
+        self.duplicates += ingest.duplicates;
+        self.items_failed += ingest.failures.len() as u64;
+        if !ingest.failures.is_empty() {
+            self.failed_by_source
+                .push((ingest.source_label.clone(), ingest.failures.len()));
+        }
+    }
+
+    async fn notify(self, config: &Config, connector: &str, started: Instant, result: &Result<()>) {
+        let counts: BTreeMap<String, u64> = [
+            ("connectors", self.connectors),
+            ("fetched", self.fetched),
+            ("documents_upserted", self.documents_upserted),
+            ("chunks_written", self.chunks_written),
+            ("embeddings_written", self.embeddings_written),
+            ("embeddings_pending", self.embeddings_pending),
+            ("extraction_skipped", self.extraction_skipped),
+            ("duplicates", self.duplicates),
+            ("items_failed", self.items_failed),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v))
+        .collect();
+        // "All connector scans failed" already lists every scan error.
+        let mut scan_errors = self.scan_errors;
+        if let Err(e) = result {
+            let message = format!("{:#}", e);
+            scan_errors.retain(|err| !message.contains(err.as_str()));
+        }
+        let report = RunReport::new(
+            "sync",
+            connector,
+            started.elapsed(),
+            counts,
+            scan_errors,
+            result,
+        );
+        notify::send(config, &report).await;
+    }
 }
 
 /// Report progress every N items during ingest (avoids flooding stderr).
 const INGEST_PROGRESS_INTERVAL: u64 = 10;
 
+/// Depth of the channel between a connector's scan and the ingest loop, in
+/// batches. Scans block once this many batches are waiting.
+const SCAN_CHANNEL_CAPACITY: usize = 2;
+
 /// Core sync engine: scans connectors sequentially, ingests items.
 ///
-/// Scans are run sequentially since connectors hold references. Each
-/// connector's items flow through the standard checkpoint → filter → upsert →
-/// chunk → embed pipeline.
+/// Each connector streams batches through [`Connector::scan_batches`] into a
+/// bounded channel that is drained while the scan is still running, so a
+/// large source is never held in memory all at once. Items flow through the
+/// standard checkpoint → filter → upsert → chunk → embed pipeline. A failed
+/// or timed-out scan is reported as a warning and leaves that connector's
+/// checkpoint unchanged; the sync fails only when every scan fails. Ctrl-C
+/// stops the sync after the current document (see the module docs).
+///
+/// With `retry_failed`, only the items recorded in `sync_failures` are
+/// ingested, and checkpoints and sync status are left unchanged.
+///
+/// Unless dry-running, the sync is registered as a `sync` job, which
+/// `ctx jobs` can pause and cancel (see [`crate::jobs`]).
 #[allow(clippy::too_many_arguments)]
 async fn run_connectors(
     config: &Config,
@@ -275,189 +765,1362 @@ async fn run_connectors(
     until: Option<String>,
     limit: Option<usize>,
     progress: Option<&dyn SyncProgressReporter>,
+    summary: &mut SyncSummary,
+    retry_failed: bool,
+) -> Result<()> {
+    let interrupt = CancellationToken::new();
+    let _ctrl_c = cancel_on_ctrl_c(interrupt.clone());
+    let job = if dry_run {
+        None
+    } else {
+        let mut labels: Vec<String> = connectors.iter().map(|c| c.source_label()).collect();
+        labels.sort();
+        Some(Job::start(config, "sync", &labels.join(", "), interrupt.clone()).await?)
+    };
+    let result = sync_connectors(
+        config,
+        connectors,
+        full,
+        dry_run,
+        ItemFilters::new(since.as_deref(), until.as_deref(), limit)?,
+        progress,
+        summary,
+        retry_failed,
+        &interrupt,
+        job.as_ref().map(Job::control),
+    )
+    .await;
+    if let Some(job) = job {
+        job.finish(&result).await;
+    }
+    result
+}
+
+/// The body of [`run_connectors`], stopped by `interrupt` and paused
+/// through `job`.
+#[allow(clippy::too_many_arguments)]
+async fn sync_connectors(
+    config: &Config,
+    connectors: &[&dyn Connector],
+    full: bool,
+    dry_run: bool,
+    filters: ItemFilters,
+    progress: Option<&dyn SyncProgressReporter>,
+    summary: &mut SyncSummary,
+    retry_failed: bool,
+    interrupt: &CancellationToken,
+    job: Option<JobControl>,
 ) -> Result<()> {
     if connectors.len() > 1 {
         println!("Syncing {} connector instances...", connectors.len());
     }
 
-    // Scan all connectors and collect results
-    let mut scan_results: Vec<(String, Vec<SourceItem>)> = Vec::new();
-    let mut scan_errors: Vec<String> = Vec::new();
+    // Sort for deterministic output ordering
+    let mut ordered: Vec<&dyn Connector> = connectors.to_vec();
+    ordered.sort_by_key(|c| c.source_label());
 
-    for conn in connectors {
+    // Ingest each connector's items (sequential — SQLite writes are serialized)
+    let store = SqliteAppStore::connect(config).await?;
+    if !dry_run {
+        migrate::ensure_titles_table(store.pool(), &config.retrieval.fts_tokenizer).await?;
+        migrate::create_stitch_members_table(store.pool()).await?;
+        if config.sync.content_dedup != "keep-all" {
+            migrate::create_content_hash_indexes(store.pool()).await?;
+        }
+        if !config.sync.keep_raw.is_empty() {
+            migrate::create_raw_items_table(store.pool()).await?;
+        }
+    }
+    let mut embedder = if dry_run || !config.sync.embed {
+        None
+    } else {
+        InlineEmbedder::new(config)
+    };
+    if let Some(ref embedder) = embedder {
+        embedder.check_dims(config, store.pool()).await?;
+    }
+
+    let timeout = config.sync.timeout_secs.map(Duration::from_secs);
+    let mut completed = 0;
+    let mut not_started: Vec<String> = Vec::new();
+
+    for conn in ordered {
         let label = conn.source_label();
+        if interrupt.is_cancelled() {
+            not_started.push(label);
+            continue;
+        }
         if let Some(p) = progress {
             p.report(SyncProgressEvent::Discovering {
-                connector: label.to_string(),
+                connector: label.clone(),
             });
         }
-        match conn.scan().await {
-            Ok(items) => {
-                scan_results.push((label, items));
+
+        let (checkpoint, state) = if full {
+            (None, None)
+        } else {
+            (
+                store.get_checkpoint(&label).await?,
+                store.get_checkpoint_state(&label).await?,
+            )
+        };
+        // A retry scans from the start: the failed items are behind the
+        // checkpoint and the connector's own cursor.
+        conn.resume_from(if retry_failed { None } else { state });
+        let mut ingest =
+            ConnectorIngest::new(config, &label, checkpoint, dry_run, embedder.as_mut())?;
+        ingest.collect_failures = !config.sync.strict;
+        ingest.strict = config.sync.strict;
+        ingest.job = job.clone();
+        if !dry_run {
+            ingest.previously_failed = sync_failures::failed_ids(store.pool(), &label).await?;
+        }
+        if retry_failed {
+            ingest.retry = Some(ingest.previously_failed.clone());
+        }
+
+        let span = tracing::info_span!("sync", connector = %label);
+        let started = Instant::now();
+        let cancel = interrupt.child_token();
+        let (tx, rx) = mpsc::channel(SCAN_CHANNEL_CAPACITY);
+        let work = async {
+            tokio::join!(
+                conn.scan_cancellable(tx, cancel.clone()),
+                ingest.consume(&store, rx, &filters, progress, &cancel)
+            )
+        }
+        .instrument(span.clone());
+        let (scanned, drained) = match timeout {
+            Some(limit) => {
+                tokio::pin!(work);
+                tokio::select! {
+                    done = &mut work => done,
+                    _ = tokio::time::sleep(limit) => {
+                        cancel.cancel();
+                        work.await
+                    }
+                }
+            }
+            None => work.await,
+        };
+        let drained = drained?;
+        ingest
+            .flush_stitched(&store, progress)
+            .instrument(span.clone())
+            .await?;
+        ingest
+            .flush_embeddings(&store, progress)
+            .instrument(span.clone())
+            .await;
+
+        let outcome = if drained && scanned.is_ok() {
+            ScanOutcome::Completed
+        } else if interrupt.is_cancelled() {
+            ScanOutcome::Interrupted
+        } else if cancel.is_cancelled() {
+            ScanOutcome::TimedOut
+        } else {
+            ScanOutcome::Failed
+        };
+        if outcome == ScanOutcome::Completed && !dry_run {
+            for failure in conn.failed() {
+                ingest.item_failed(failure, progress)?;
+            }
+        }
+        match outcome {
+            ScanOutcome::Completed => {
+                tracing::debug!(
+                    connector = %label,
+                    items = ingest.scanned,
+                    elapsed_ms = started.elapsed().as_millis() as u64,
+                    "scan completed"
+                );
+                ingest.finish(&store, conn, outcome).await?;
+                if !dry_run && !retry_failed {
+                    sync_status::record_success(store.pool(), &label, ingest.fetched).await?;
+                }
+                summary.add(&ingest);
+                completed += 1;
+            }
+            ScanOutcome::Interrupted => {
+                tracing::warn!("sync of {} interrupted", label);
+                ingest.finish(&store, conn, outcome).await?;
+                summary.add(&ingest);
             }
-            Err(e) => {
-                scan_errors.push(format!("{}: {:#}", label, e));
+            ScanOutcome::TimedOut | ScanOutcome::Failed => {
+                let err = match scanned {
+                    Err(e) if outcome == ScanOutcome::Failed => format!("{}: {:#}", label, e),
+                    _ => format!(
+                        "{}: timed out after {}s ([sync] timeout_secs)",
+                        label,
+                        timeout.unwrap_or_default().as_secs()
+                    ),
+                };
+                tracing::warn!("scan failed: {}", err);
+                if !dry_run {
+                    let prefix = format!("{}: ", label);
+                    let reason = err.strip_prefix(&prefix).unwrap_or(&err);
+                    sync_status::record_failure(store.pool(), &label, reason).await?;
+                }
+                summary.scan_errors.push(err);
+                if ingest.scanned > 0 {
+                    ingest.finish(&store, conn, outcome).await?;
+                    summary.add(&ingest);
+                }
             }
         }
     }
 
-    // Report scan errors but continue with successful scans
-    for err in &scan_errors {
-        eprintln!("Warning: scan failed: {}", err);
+    sync_failures::print_report(&summary.failed_by_source);
+
+    if interrupt.is_cancelled() {
+        if !dry_run {
+            enforce_storage_budget(config, store.pool()).await?;
+        }
+        store.close().await;
+        if !not_started.is_empty() {
+            println!("not started: {}", not_started.join(", "));
+        }
+        bail!(
+            "Sync interrupted: {} of {} connectors completed. Documents written so far were kept; run the sync again to continue.",
+            completed,
+            connectors.len()
+        );
     }
 
-    if scan_results.is_empty() && !scan_errors.is_empty() {
-        bail!("All connector scans failed:\n{}", scan_errors.join("\n"));
+    if !connectors.is_empty() && summary.scan_errors.len() == connectors.len() {
+        store.close().await;
+        bail!(
+            "All connector scans failed:\n{}",
+            summary.scan_errors.join("\n")
+        );
     }
 
-    // Sort for deterministic output ordering
-    scan_results.sort_by(|a, b| a.0.cmp(&b.0));
+    if !dry_run {
+        enforce_storage_budget(config, store.pool()).await?;
+        if let Err(e) = stats::record_snapshot(&store).await {
+            tracing::warn!("failed to record stats snapshot: {:#}", e);
+        }
+    }
 
-    // Ingest each target's items (sequential — SQLite writes are serialized)
-    let store = SqliteAppStore::connect(config).await?;
+    store.close().await;
+    Ok(())
+}
 
-    for (source_label, mut items) in scan_results {
-        // Load checkpoint
-        let checkpoint: Option<i64> = if full {
-            None
+/// How a connector's scan ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ScanOutcome {
+    Completed,
+    Failed,
+    /// Stopped after `[sync] timeout_secs`.
+    TimedOut,
+    /// Stopped by Ctrl-C or `ctx jobs cancel`.
+    Interrupted,
+}
+
+impl ScanOutcome {
+    fn describe(self) -> &'static str {
+        match self {
+            ScanOutcome::Completed => "completed",
+            ScanOutcome::Failed => "scan failed",
+            ScanOutcome::TimedOut => "timed out",
+            ScanOutcome::Interrupted => "interrupted",
+        }
+    }
+}
+
+/// Aborts the Ctrl-C listener of a sync when dropped.
+struct CtrlCListener(tokio::task::JoinHandle<()>);
+
+impl Drop for CtrlCListener {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Cancel `token` on the first Ctrl-C and exit on the second, until the
+/// returned listener is dropped.
+fn cancel_on_ctrl_c(token: CancellationToken) -> CtrlCListener {
+    CtrlCListener(tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_err() {
+            return;
+        }
+        eprintln!("Interrupted: finishing the current document (Ctrl-C again to exit now)");
+        token.cancel();
+        if tokio::signal::ctrl_c().await.is_ok() {
+            std::process::exit(130);
+        }
+    }))
+}
+
+/// The `--since`, `--until`, and `--limit` filters of a sync.
+struct ItemFilters {
+    since_ts: Option<i64>,
+    until_ts: Option<i64>,
+    limit: Option<usize>,
+}
+
+impl ItemFilters {
+    fn new(since: Option<&str>, until: Option<&str>, limit: Option<usize>) -> Result<Self> {
+        let since_ts = match since {
+            Some(s) => Some(
+                NaiveDate::parse_from_str(s, "%Y-%m-%d")?
+                    .and_hms_opt(0, 0, 0)
+                    .unwrap()
+                    .and_utc()
+                    .timestamp(),
+            ),
+            None => None,
+        };
+        let until_ts = match until {
+            Some(s) => Some(
+                NaiveDate::parse_from_str(s, "%Y-%m-%d")?
+                    .and_hms_opt(23, 59, 59)
+                    .unwrap()
+                    .and_utc()
+                    .timestamp(),
+            ),
+            None => None,
+        };
+        Ok(Self {
+            since_ts,
+            until_ts,
+            limit,
+        })
+    }
+}
+
+/// Items prepared on the CPU pool at a time while the previous group is
+/// written.
+const PREPARE_GROUP: usize = 256;
+
+static CPU_POOL: OnceLock<ThreadPool> = OnceLock::new();
+
+/// The process-wide pool for the CPU-bound ingest stages, sized by the
+/// first config that asks for it (`[sync] cpu_threads`, default one thread
+/// per CPU).
+fn cpu_pool(config: &Config) -> Result<&'static ThreadPool> {
+    if let Some(pool) = CPU_POOL.get() {
+        return Ok(pool);
+    }
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(config.sync.cpu_threads.unwrap_or(0))
+        .thread_name(|i| format!("ctx-cpu-{}", i))
+        // The panic message is printed by the panic hook; the waiting
+        // ingest sees the dropped result channel and fails.
+        .panic_handler(|_| {})
+        .build()
+        .context("Failed to start the ingest CPU pool")?;
+    Ok(CPU_POOL.get_or_init(|| pool))
+}
+
+/// The CPU-bound stages of one connector's ingest: extraction, redaction,
+/// hashing, normalization, and chunking. Runs on the [`cpu_pool`].
+struct Preparer {
+    chunking: ChunkingConfig,
+    strategy: ChunkStrategy,
+    normalizer: Option<Normalizer>,
+    max_extract_bytes: u64,
+    redactor: Option<Redactor>,
+    stitcher: Option<Stitcher>,
+}
+
+/// An item [`Preparer::prepare`] finished with.
+struct Prepared {
+    /// `None` when extraction skipped the item.
+    item: Option<PreparedItem>,
+    /// Why extraction failed, when it did.
+    failed: Option<ItemFailure>,
+    redactions: RedactionCounts,
+    timings: StageTimings,
+}
+
+/// An item ready to be written.
+enum PreparedItem {
+    /// A document of its own.
+    Document {
+        item: SourceItem,
+        dedup_hash: String,
+        /// Chunks with an empty `document_id`, filled in once the document
+        /// ID is known.
+        chunks: Vec<Chunk>,
+    },
+    /// A member of the stitched document `group` (see [`crate::stitch`]).
+    Member { item: SourceItem, group: String },
+}
+
+impl Preparer {
+    /// The text chunked for `body`: normalized when `[chunking.normalize]`
+    /// is enabled for this connector. The stored body stays as it is.
+    fn chunk_input<'b>(&self, body: &'b str) -> Cow<'b, str> {
+        match self.normalizer {
+            Some(ref normalizer) => Cow::Owned(normalizer.normalize(body)),
+            None => Cow::Borrowed(body),
+        }
+    }
+
+    /// Extract, redact, hash, and chunk one item. Members of stitched
+    /// documents are chunked when their document is rebuilt.
+    fn prepare(&self, mut item: SourceItem) -> Prepared {
+        let mut out = Prepared {
+            item: None,
+            failed: None,
+            redactions: RedactionCounts::new(),
+            timings: StageTimings::default(),
+        };
+
+        if let Some(bytes) = item.raw_bytes.take() {
+            let started = Instant::now();
+            if bytes.len() as u64 > self.max_extract_bytes {
+                tracing::warn!(
+                    "skipping {} (size {} > max_extract_bytes {})",
+                    item.source_id,
+                    bytes.len(),
+                    self.max_extract_bytes
+                );
+                return out;
+            }
+            let extracted = extract::extract_text(&bytes, &item.content_type);
+            out.timings.add("extract", started.elapsed());
+            match extracted {
+                Ok(text) => item.body = text,
+                Err(e) => {
+                    tracing::warn!("extraction failed for {}: {}", item.source_id, e);
+                    out.failed = Some(ItemFailure::new(&item.source_id, "extract", e));
+                    return out;
+                }
+            }
+        }
+
+        if let Some(ref redactor) = self.redactor {
+            let started = Instant::now();
+            item.body = redactor.redact(&item.body, &mut out.redactions);
+            if let Some(ref title) = item.title {
+                item.title = Some(redactor.redact(title, &mut out.redactions));
+            }
+            out.timings.add("redact", started.elapsed());
+        }
+
+        if let Some(group) = self
+            .stitcher
+            .as_ref()
+            .and_then(|s| s.group(&item.source_id))
+        {
+            out.item = Some(PreparedItem::Member { item, group });
+            return out;
+        }
+
+        let started = Instant::now();
+        let dedup_hash = app_store::dedup_hash(&item);
+        out.timings.add("hash", started.elapsed());
+
+        let started = Instant::now();
+        let chunks = self.chunk(&item);
+        out.timings.add("chunk", started.elapsed());
+
+        out.item = Some(PreparedItem::Document {
+            item,
+            dedup_hash,
+            chunks,
+        });
+        out
+    }
+
+    /// Chunks of `item`'s body, with an empty `document_id`.
+    fn chunk(&self, item: &SourceItem) -> Vec<Chunk> {
+        let text = self.chunk_input(&item.body);
+        let mut chunks = chunk_text_with("", &text, self.strategy, self.chunking.max_tokens);
+        let markdown =
+            self.chunking.heading_breadcrumbs && is_markdown(&item.source_id, &item.content_type);
+        let inherited = inherited_chunk_metadata(item, &self.chunking.inherit_metadata);
+        annotate_chunks(&mut chunks, &text, &inherited, markdown);
+        apply_overlap(&mut chunks, self.chunking.overlap_tokens);
+        chunks
+    }
+
+    /// Chunks of a stitched document: each member chunked on its own,
+    /// tagged with the member's `source_id` and `source_url`, and numbered
+    /// in order.
+    fn chunk_sections(&self, members: &[SourceItem]) -> Vec<Chunk> {
+        let mut chunks = Vec::new();
+        for member in members {
+            for mut chunk in self.chunk(member) {
+                chunk.chunk_index = chunks.len() as i64;
+                chunk.metadata.insert(
+                    SECTION_SOURCE_ID_KEY.to_string(),
+                    member.source_id.clone().into(),
+                );
+                if let Some(ref url) = member.source_url {
+                    chunk
+                        .metadata
+                        .insert(SECTION_SOURCE_URL_KEY.to_string(), url.clone().into());
+                }
+                chunks.push(chunk);
+            }
+        }
+        chunks
+    }
+}
+
+/// Ingest state for one connector instance across the batches of its scan.
+struct ConnectorIngest<'a> {
+    config: &'a Config,
+    source_label: String,
+    checkpoint: Option<i64>,
+    dry_run: bool,
+    /// Extraction, redaction, and chunking settings, shared with the CPU pool.
+    prep: Arc<Preparer>,
+    /// Pool the CPU-bound stages run on (`[sync] cpu_threads`).
+    cpu: &'static ThreadPool,
+    redactions: RedactionCounts,
+    /// Store items in `raw_items` before processing (`[sync] keep_raw`).
+    keep_raw: bool,
+    contextualizer: Option<Contextualizer>,
+    summarizer: Option<Summarizer>,
+    classifier: Option<Classifier>,
+    /// Run-wide inline embedder; `None` when chunks are left for `ctx embed pending`.
+    embedder: Option<&'a mut InlineEmbedder>,
+    /// Items received from the scan, before filtering.
+    scanned: u64,
+    /// Items that passed the checkpoint and `--since`/`--until`/`--limit` filters.
+    fetched: u64,
+    estimated_chunks: u64,
+    docs_upserted: u64,
+    chunks_written: u64,
+    embeddings_written: u64,
+    embeddings_pending: u64,
+    extraction_skipped: u64,
+    /// Items kept as members of stitched documents.
+    stitched: u64,
+    /// `[sync] content_dedup` mode.
+    content_dedup: DedupMode,
+    /// Items linked to or skipped for another source's document with the
+    /// same content.
+    duplicates: u64,
+    /// Canonical document of the last duplicate, for [`ingest_one`].
+    last_duplicate_of: Option<String>,
+    /// Record failed items and continue, instead of failing on write errors.
+    collect_failures: bool,
+    /// Fail on extraction errors too, instead of counting them as skipped.
+    strict: bool,
+    /// Items that could not be fetched, extracted, or written.
+    failures: Vec<ItemFailure>,
+    /// Source IDs with a failure recorded by an earlier sync, cleared from
+    /// `sync_failures` once written.
+    previously_failed: HashSet<String>,
+    /// With `--retry-failed`: the recorded failures not yet delivered by
+    /// the scan. Only these are ingested, whatever the checkpoint.
+    retry: Option<HashSet<String>>,
+    max_updated: i64,
+    /// Per-stage time, recorded only for `ctx bench ingest`.
+    timings: Option<StageTimings>,
+    /// Pause state of the sync's job; writes wait while it is paused.
+    job: Option<JobControl>,
+}
+
+impl<'a> ConnectorIngest<'a> {
+    fn new(
+        config: &'a Config,
+        source_label: &str,
+        checkpoint: Option<i64>,
+        dry_run: bool,
+        embedder: Option<&'a mut InlineEmbedder>,
+    ) -> Result<Self> {
+        let chunking = config.chunking_for(source_label);
+        let redactor = if redact::enabled_for(&config.redaction, source_label) {
+            Some(Redactor::from_config(&config.redaction)?)
         } else {
-            store.get_checkpoint(&source_label).await?
+            None
         };
+        let prep = Preparer {
+            strategy: chunk::strategy(&chunking),
+            normalizer: Normalizer::from_config(&chunking.normalize)?,
+            chunking,
+            max_extract_bytes: max_extract_bytes_for_source(config, source_label),
+            redactor,
+            stitcher: Stitcher::from_config(config, source_label)?,
+        };
+        Ok(Self {
+            config,
+            source_label: source_label.to_string(),
+            checkpoint,
+            dry_run,
+            prep: Arc::new(prep),
+            cpu: cpu_pool(config)?,
+            redactions: RedactionCounts::new(),
+            keep_raw: raw_items::enabled_for(&config.sync, source_label),
+            contextualizer: Contextualizer::from_config(config)?,
+            summarizer: Summarizer::from_config(config)?,
+            classifier: Classifier::from_config(config)?,
+            embedder,
+            scanned: 0,
+            fetched: 0,
+            estimated_chunks: 0,
+            docs_upserted: 0,
+            chunks_written: 0,
+            embeddings_written: 0,
+            embeddings_pending: 0,
+            extraction_skipped: 0,
+            stitched: 0,
+            content_dedup: DedupMode::parse(&config.sync.content_dedup)
+                .unwrap_or(DedupMode::KeepAll),
+            duplicates: 0,
+            last_duplicate_of: None,
+            collect_failures: false,
+            strict: false,
+            failures: Vec::new(),
+            previously_failed: HashSet::new(),
+            retry: None,
+            max_updated: checkpoint.unwrap_or(0),
+            timings: None,
+            job: None,
+        })
+    }
 
-        // Filter by checkpoint (skip files not modified since checkpoint)
-        if let Some(cp) = checkpoint {
-            items.retain(|item| item.updated_at.timestamp() > cp);
-        }
-
-        // Apply --since filter
-        if let Some(ref since_str) = since {
-            let since_date = NaiveDate::parse_from_str(since_str, "%Y-%m-%d")?;
-            let since_ts = since_date
-                .and_hms_opt(0, 0, 0)
-                .unwrap()
-                .and_utc()
-                .timestamp();
-            items.retain(|item| item.updated_at.timestamp() >= since_ts);
-        }
-
-        // Apply --until filter
-        if let Some(ref until_str) = until {
-            let until_date = NaiveDate::parse_from_str(until_str, "%Y-%m-%d")?;
-            let until_ts = until_date
-                .and_hms_opt(23, 59, 59)
-                .unwrap()
-                .and_utc()
-                .timestamp();
-            items.retain(|item| item.updated_at.timestamp() <= until_ts);
-        }
-
-        // Apply --limit (per connector instance)
-        if let Some(lim) = limit {
-            items.truncate(lim);
-        }
-
-        if dry_run {
-            println!("sync {} (dry-run)", source_label);
-            println!("  items found: {}", items.len());
-            let total_chunks: usize = items
-                .iter()
-                .map(|item| chunk_text("tmp", &item.body, config.chunking.max_tokens).len())
-                .sum();
-            println!("  estimated chunks: {}", total_chunks);
-            continue;
+    /// Add the time since `started` to `stage` when timing stages.
+    fn record(&mut self, stage: &'static str, started: Instant) {
+        if let Some(ref mut timings) = self.timings {
+            timings.add(stage, started.elapsed());
         }
+    }
+
+    /// Whether an item passes the checkpoint (or, when retrying, is a
+    /// recorded failure) and the date filters.
+    fn accepts(&self, item: &SourceItem, filters: &ItemFilters) -> bool {
+        let ts = item.updated_at.timestamp();
+        let pending = match self.retry {
+            Some(ref ids) => ids.contains(&item.source_id),
+            // Skip items not modified since the checkpoint
+            None => self.checkpoint.is_none_or(|cp| ts > cp),
+        };
+        pending
+            && filters.since_ts.is_none_or(|since| ts >= since)
+            && filters.until_ts.is_none_or(|until| ts <= until)
+    }
 
-        let mut docs_upserted = 0u64;
-        let mut chunks_written = 0u64;
-        let mut embeddings_written = 0u64;
-        let mut embeddings_pending = 0u64;
-        let mut extraction_skipped = 0u64;
-        let mut max_updated: i64 = checkpoint.unwrap_or(0);
-        let max_extract_bytes = max_extract_bytes_for_source(config, &source_label);
-        let total_items = items.len() as u64;
+    /// Drain item batches from a running scan, ingesting each as it arrives.
+    ///
+    /// Returns `false` if `cancel` fired before the scan's batches ran
+    /// out; the document being written when it fired is finished first.
+    async fn consume(
+        &mut self,
+        store: &SqliteAppStore,
+        mut rx: mpsc::Receiver<Vec<SourceItem>>,
+        filters: &ItemFilters,
+        progress: Option<&dyn SyncProgressReporter>,
+        cancel: &CancellationToken,
+    ) -> Result<bool> {
+        loop {
+            let batch = tokio::select! {
+                biased;
+                _ = cancel.cancelled() => return Ok(false),
+                batch = rx.recv() => match batch {
+                    Some(batch) => batch,
+                    None => return Ok(true),
+                },
+            };
+            self.scanned += batch.len() as u64;
+            let mut items: Vec<SourceItem> = batch
+                .into_iter()
+                .filter(|item| self.accepts(item, filters))
+                .collect();
+            // Apply --limit (per connector instance)
+            if let Some(lim) = filters.limit {
+                items.truncate(lim.saturating_sub(self.fetched as usize));
+            }
+            if items.is_empty() {
+                continue;
+            }
+            if let Some(ref mut ids) = self.retry {
+                for item in &items {
+                    ids.remove(&item.source_id);
+                }
+            }
+            self.fetched += items.len() as u64;
 
-        if let Some(p) = progress {
-            if total_items > 0 {
-                p.report(SyncProgressEvent::Ingesting {
-                    connector: source_label.clone(),
-                    n: 0,
-                    total: total_items,
-                });
+            if self.dry_run {
+                self.estimated_chunks += items
+                    .iter()
+                    .map(|item| {
+                        let prep = &self.prep;
+                        let text = prep.chunk_input(&item.body);
+                        chunk_text_with("tmp", &text, prep.strategy, prep.chunking.max_tokens).len()
+                            as u64
+                    })
+                    .sum::<u64>();
+                continue;
+            }
+
+            self.report_progress(progress);
+            if !self.ingest_items(store, items, progress, cancel).await? {
+                return Ok(false);
+            }
+        }
+    }
+
+    /// Keep (with `[sync] keep_raw`), prepare, write, and embed `items` in
+    /// order.
+    ///
+    /// Items are prepared on the CPU pool in groups of [`PREPARE_GROUP`],
+    /// the next group while the current one is written. Returns `false` if
+    /// `cancel` fired first; the document being written when it fired is
+    /// finished first.
+    async fn ingest_items(
+        &mut self,
+        store: &SqliteAppStore,
+        items: Vec<SourceItem>,
+        progress: Option<&dyn SyncProgressReporter>,
+        cancel: &CancellationToken,
+    ) -> Result<bool> {
+        let mut items = items.into_iter();
+        let mut next = self.start_prepare(store, &mut items).await?;
+        while let Some(group) = next {
+            let group = group
+                .await
+                .context("Preparing items failed: a CPU pool worker panicked")?;
+            next = self.start_prepare(store, &mut items).await?;
+            for prepared in group {
+                if let Some(ref job) = self.job {
+                    job.wait_while_paused(cancel).await;
+                }
+                if cancel.is_cancelled() {
+                    return Ok(false);
+                }
+                self.write_prepared(store, prepared, progress).await?;
+            }
+        }
+        Ok(true)
+    }
+
+    /// Keep the next [`PREPARE_GROUP`] of `items` (with `[sync] keep_raw`)
+    /// and start preparing them on the CPU pool. `None` once `items` is
+    /// exhausted.
+    async fn start_prepare(
+        &mut self,
+        store: &SqliteAppStore,
+        items: &mut impl Iterator<Item = SourceItem>,
+    ) -> Result<Option<oneshot::Receiver<Vec<Prepared>>>> {
+        let group: Vec<SourceItem> = items.by_ref().take(PREPARE_GROUP).collect();
+        if group.is_empty() {
+            return Ok(None);
+        }
+        if let Some(ref stitcher) = self.prep.stitcher {
+            // A stitched item no longer has a document of its own. Deleting
+            // it also drops its raw item, so this runs before keeping it.
+            for item in &group {
+                if stitcher.group(&item.source_id).is_some() {
+                    store
+                        .delete_source_item(&item.source, &item.source_id)
+                        .await?;
+                }
+            }
+        }
+        if self.keep_raw {
+            let started = Instant::now();
+            for item in &group {
+                raw_items::store(store.pool(), &self.source_label, item).await?;
             }
+            self.record("raw", started);
         }
+        let prep = Arc::clone(&self.prep);
+        let (tx, rx) = oneshot::channel();
+        self.cpu.spawn(move || {
+            let prepared = group
+                .into_par_iter()
+                .map(|item| prep.prepare(item))
+                .collect();
+            // The receiver is gone when ingest stopped early.
+            let _ = tx.send(prepared);
+        });
+        Ok(Some(rx))
+    }
 
-        for item in items.iter_mut() {
-            if let Some(ref bytes) = item.raw_bytes {
-                if bytes.len() as u64 > max_extract_bytes {
-                    extraction_skipped += 1;
-                    eprintln!(
-                        "Warning: skipping {} (size {} > max_extract_bytes {})",
-                        item.source_id,
-                        bytes.len(),
-                        max_extract_bytes
-                    );
-                    continue;
+    /// Upsert, summarize, classify, contextualize, and embed one prepared item.
+    async fn write_prepared(
+        &mut self,
+        store: &SqliteAppStore,
+        prepared: Prepared,
+        progress: Option<&dyn SyncProgressReporter>,
+    ) -> Result<()> {
+        for (kind, n) in prepared.redactions {
+            *self.redactions.entry(kind).or_default() += n;
+        }
+        if let Some(ref mut timings) = self.timings {
+            for &(stage, elapsed) in prepared.timings.stages() {
+                timings.add(stage, elapsed);
+            }
+        }
+        let (source_id, written) = match prepared.item {
+            Some(PreparedItem::Document {
+                item,
+                dedup_hash,
+                chunks,
+            }) => {
+                let source_id = item.source_id.clone();
+                let span = tracing::info_span!("ingest.document", source_id = %source_id);
+                let written = self
+                    .write_item(store, item, dedup_hash, chunks, progress)
+                    .instrument(span)
+                    .await;
+                (source_id, written)
+            }
+            Some(PreparedItem::Member { item, group }) => {
+                let started = Instant::now();
+                let written = stitch::store_member(store.pool(), &group, &item).await;
+                self.record("write", started);
+                if written.is_ok() {
+                    self.stitched += 1;
+                    self.max_updated = self.max_updated.max(item.updated_at.timestamp());
                 }
-                match extract::extract_text(bytes, &item.content_type) {
-                    Ok(text) => {
-                        item.body = text;
-                        item.raw_bytes = None;
+                (item.source_id, written)
+            }
+            None => {
+                self.extraction_skipped += 1;
+                if let Some(failure) = prepared.failed {
+                    if self.strict {
+                        bail!(
+                            "extraction failed for {}: {} (strict sync)",
+                            failure.source_id,
+                            failure.error
+                        );
                     }
-                    Err(e) => {
-                        extraction_skipped += 1;
-                        eprintln!("Warning: extraction failed for {}: {}", item.source_id, e);
-                        continue;
+                    if self.collect_failures {
+                        self.item_failed(failure, progress)?;
                     }
                 }
+                return Ok(());
+            }
+        };
+        match written {
+            Ok(()) => {
+                if self.previously_failed.remove(&source_id) {
+                    sync_failures::clear(store.pool(), &self.source_label, &source_id).await?;
+                }
+                Ok(())
             }
+            Err(e) => self
+                .item_failed(
+                    ItemFailure::new(&source_id, "write", format!("{:#}", e)),
+                    progress,
+                )
+                .map_err(|_| e),
+        }
+    }
+
+    /// Record an item that could not be fetched, extracted, or written and
+    /// carry on, or fail when not collecting failures (`[sync] strict`).
+    fn item_failed(
+        &mut self,
+        failure: ItemFailure,
+        progress: Option<&dyn SyncProgressReporter>,
+    ) -> Result<()> {
+        if !self.collect_failures {
+            bail!(
+                "{} failed for {}: {}",
+                failure.stage,
+                failure.source_id,
+                failure.error
+            );
+        }
+        tracing::warn!(
+            "{} failed for {}: {}",
+            failure.stage,
+            failure.source_id,
+            failure.error
+        );
+        if let Some(p) = progress {
+            p.report(SyncProgressEvent::ItemFailed {
+                connector: self.source_label.clone(),
+                source_id: failure.source_id.clone(),
+                stage: failure.stage.clone(),
+                error: failure.error.clone(),
+            });
+        }
+        self.failures.push(failure);
+        Ok(())
+    }
 
-            let doc_id = store.upsert_source_item(item).await?;
-            let chunks = chunk_text(&doc_id, &item.body, config.chunking.max_tokens);
-            let chunk_count = chunks.len() as u64;
-            store.replace_chunks(&doc_id, &chunks, None).await?;
-
-            // Inline embedding (non-fatal)
-            let (emb_ok, emb_pending) =
-                embed_cmd::embed_chunks_inline(config, &store, &chunks).await;
-            embeddings_written += emb_ok;
-            embeddings_pending += emb_pending;
-
-            docs_upserted += 1;
-            chunks_written += chunk_count;
-
-            if let Some(p) = progress {
-                let n = docs_upserted;
-                if n.is_multiple_of(INGEST_PROGRESS_INTERVAL) || n == total_items {
-                    p.report(SyncProgressEvent::Ingesting {
-                        connector: source_label.clone(),
-                        n,
-                        total: total_items,
-                    });
+    /// Rebuild the stitched documents of this connector with new or
+    /// changed members (see [`crate::stitch`]).
+    async fn flush_stitched(
+        &mut self,
+        store: &SqliteAppStore,
+        progress: Option<&dyn SyncProgressReporter>,
+    ) -> Result<()> {
+        if self.dry_run {
+            return Ok(());
+        }
+        for group in stitch::pending_groups(store.pool(), &self.source_label).await? {
+            self.write_stitched(store, &group, progress).await?;
+        }
+        Ok(())
+    }
+
+    /// Write the stitched document `group` from its stored members, or
+    /// delete it when none are left.
+    async fn write_stitched(
+        &mut self,
+        store: &SqliteAppStore,
+        group: &str,
+        progress: Option<&dyn SyncProgressReporter>,
+    ) -> Result<()> {
+        let members = stitch::members(store.pool(), &self.source_label, group).await?;
+        if members.is_empty() {
+            store.delete_source_item(&self.source_label, group).await?;
+            return Ok(());
+        }
+        let item = stitch::stitched_item(&self.source_label, group, &members);
+        let dedup_hash = app_store::dedup_hash(&item);
+        let chunks = self.prep.chunk_sections(&members);
+        let span = tracing::info_span!("ingest.document", source_id = %group);
+        self.write_item(store, item, dedup_hash, chunks, progress)
+            .instrument(span)
+            .await?;
+        stitch::mark_built(store.pool(), &self.source_label, group).await
+    }
+
+    /// Write `item` as a document with `chunks`, whose document ID is
+    /// filled in here, and queue the chunks for embedding.
+    async fn write_item(
+        &mut self,
+        store: &SqliteAppStore,
+        item: SourceItem,
+        dedup_hash: String,
+        mut chunks: Vec<Chunk>,
+        progress: Option<&dyn SyncProgressReporter>,
+    ) -> Result<()> {
+        let started = Instant::now();
+        let mut doc = store.source_item_document(&item, dedup_hash).await?;
+        if self.content_dedup != DedupMode::KeepAll {
+            if let Some(hash) = content_dedup::content_hash(&doc.body) {
+                match content_dedup::find_canonical(store.pool(), &hash, &doc).await? {
+                    Some(canonical) => {
+                        return self
+                            .write_duplicate(store, &item, doc, &hash, canonical, started)
+                            .await;
+                    }
+                    None => content_dedup::mark_canonical(store.pool(), &mut doc, &hash).await?,
                 }
             }
+        }
+        let doc_id = doc.id.clone();
+        let mut write_time = started.elapsed();
+
+        if let Some(ref summarizer) = self.summarizer {
+            let started = Instant::now();
+            doc.summary = summarizer.summarize(store.pool(), &doc).await?;
+            self.record("summarize", started);
+        }
+        if let Some(ref classifier) = self.classifier {
+            let started = Instant::now();
+            classifier.classify(store.pool(), &mut doc).await?;
+            self.record("classify", started);
+        }
+
+        for chunk in &mut chunks {
+            chunk.document_id = doc_id.clone();
+        }
+        if let Some(ref contextualizer) = self.contextualizer {
+            let started = Instant::now();
+            contextualizer
+                .apply(store.pool(), &doc_id, &item, &mut chunks)
+                .await?;
+            self.record("contextualize", started);
+        }
+        let chunk_count = chunks.len() as u64;
+        // One transaction per document: a crash leaves the old version or
+        // the new one, and rewriting an unchanged document keeps its chunks.
+        let started = Instant::now();
+        let written = store
+            .write_document(&doc, &chunks)
+            .instrument(tracing::info_span!("store.write", chunks = chunks.len()))
+            .await?;
+        write_time += started.elapsed();
+        for (chunk, id) in chunks.iter_mut().zip(written.chunk_ids) {
+            chunk.id = id;
+            chunk.document_id = written.id.clone();
+        }
+        if let Some(ref mut timings) = self.timings {
+            timings.add("write", write_time);
+        }
+
+        // Inline embedding (non-fatal)
+        if let Some(embedder) = self.embedder.as_deref_mut() {
+            let started = Instant::now();
+            let (emb_ok, emb_pending) = embedder.push(self.config, store, chunks).await;
+            self.embeddings_written += emb_ok;
+            self.embeddings_pending += emb_pending;
+            self.record("embed", started);
+        } else if self.config.embedding.is_enabled() {
+            self.embeddings_pending += chunk_count;
+        }
+
+        self.docs_upserted += 1;
+        self.chunks_written += chunk_count;
+
+        let n = self.docs_upserted;
+        if n.is_multiple_of(INGEST_PROGRESS_INTERVAL) || n == self.fetched {
+            self.report_progress(progress);
+        }
+
+        let ts = item.updated_at.timestamp();
+        if ts > self.max_updated {
+            self.max_updated = ts;
+        }
+        Ok(())
+    }
+
+    /// Store `doc`, whose content `canonical` already holds, as
+    /// `[sync] content_dedup` says: without chunks, linked to `canonical`
+    /// (`link`), or not at all (`skip`).
+    async fn write_duplicate(
+        &mut self,
+        store: &SqliteAppStore,
+        item: &SourceItem,
+        mut doc: Document,
+        hash: &str,
+        canonical: Canonical,
+        started: Instant,
+    ) -> Result<()> {
+        if self.content_dedup == DedupMode::Link {
+            content_dedup::mark_duplicate(&mut doc, hash, &canonical);
+            store.write_document(&doc, &[]).await?;
+            content_dedup::refresh_duplicates(store.pool(), &canonical.id).await?;
+            self.docs_upserted += 1;
+        } else {
+            store
+                .delete_source_item(&doc.source, &doc.source_id)
+                .await?;
+        }
+        self.record("write", started);
+        tracing::debug!(
+            source_id = %doc.source_id,
+            canonical = %format!("{}:{}", canonical.source, canonical.source_id),
+            "duplicate content"
+        );
+        self.duplicates += 1;
+        self.last_duplicate_of = Some(canonical.id);
+        self.max_updated = self.max_updated.max(item.updated_at.timestamp());
+        Ok(())
+    }
+
+    /// Embed the chunks still queued from this connector.
+    async fn flush_embeddings(
+        &mut self,
+        store: &SqliteAppStore,
+        progress: Option<&dyn SyncProgressReporter>,
+    ) {
+        let Some(embedder) = self.embedder.as_deref_mut() else {
+            return;
+        };
+        let started = Instant::now();
+        let (emb_ok, emb_pending) = embedder.flush(self.config, store).await;
+        if self.embeddings_written + emb_ok > 0 {
+            embedder
+                .refresh_document_vectors(self.config, store.pool())
+                .await;
+        }
+        self.embeddings_written += emb_ok;
+        self.embeddings_pending += emb_pending;
+        self.record("embed", started);
+        if emb_ok + emb_pending > 0 {
+            self.report_progress(progress);
+        }
+    }
+
+    /// Report ingest progress, with the embedded chunk count when embedding inline.
+    fn report_progress(&self, progress: Option<&dyn SyncProgressReporter>) {
+        if let Some(p) = progress {
+            p.report(SyncProgressEvent::Ingesting {
+                connector: self.source_label.clone(),
+                n: self.docs_upserted,
+                total: self.fetched,
+                embedded: self.embedder.as_ref().map(|_| self.embeddings_written),
+            });
+        }
+    }
 
-            let ts = item.updated_at.timestamp();
-            if ts > max_updated {
-                max_updated = ts;
+    /// Print the connector's summary, record its failed items, and, if its
+    /// scan completed, advance its checkpoint and save the connector's
+    /// checkpoint state. A completed retry leaves both unchanged and drops
+    /// the failures of items the scan no longer delivers.
+    async fn finish(
+        &self,
+        store: &SqliteAppStore,
+        conn: &dyn Connector,
+        outcome: ScanOutcome,
+    ) -> Result<()> {
+        let scan_skipped = conn.skipped();
+        let scan_completed = outcome == ScanOutcome::Completed;
+        let label = &self.source_label;
+        if self.dry_run {
+            println!("sync {} (dry-run)", label);
+            println!("  items found: {}", self.fetched);
+            print_scan_skipped(&scan_skipped);
+            println!("  estimated chunks: {}", self.estimated_chunks);
+            return Ok(());
+        }
+
+        sync_failures::record(store.pool(), label, &self.failures).await?;
+        let mut gone = 0;
+        match self.retry {
+            Some(ref missing) if scan_completed => {
+                for source_id in missing {
+                    sync_failures::clear(store.pool(), label, source_id).await?;
+                }
+                gone = missing.len();
             }
+            Some(_) => {}
+            // Update checkpoint
+            None if scan_completed => {
+                store.set_checkpoint(label, self.max_updated).await?;
+                if let Some(state) = conn.checkpoint_state() {
+                    store.set_checkpoint_state(label, &state).await?;
+                }
+            }
+            None => {}
+        }
+        if self.docs_upserted > 0 {
+            search_cache::bump_index_generation(store.pool()).await?;
+            store.refresh_keyword_index().await?;
         }
 
-        // Update checkpoint
-        store.set_checkpoint(&source_label, max_updated).await?;
+        println!("sync {}", label);
+        println!("  fetched: {} items", self.fetched);
+        print_scan_skipped(&scan_skipped);
+        self.print_written();
+        sync_failures::print_failures(&self.failures);
+        if gone > 0 {
+            println!("  no longer in the source: {} failed items", gone);
+        }
+        if scan_completed && self.retry.is_some() {
+            println!("  checkpoint: unchanged (retry)");
+            println!("ok");
+        } else if scan_completed {
+            println!("  checkpoint: {}", self.max_updated);
+            println!("ok");
+        } else {
+            println!("  checkpoint: unchanged ({})", outcome.describe());
+            println!("incomplete");
+        }
+        Ok(())
+    }
 
-        println!("sync {}", source_label);
-        println!("  fetched: {} items", items.len());
-        println!("  upserted documents: {}", docs_upserted);
-        println!("  chunks written: {}", chunks_written);
-        println!("  extraction skipped: {}", extraction_skipped);
-        if config.embedding.is_enabled() {
-            println!("  embeddings written: {}", embeddings_written);
-            println!("  embeddings pending: {}", embeddings_pending);
+    /// Print the document, chunk, redaction, and embedding counts.
+    fn print_written(&self) {
+        println!("  upserted documents: {}", self.docs_upserted);
+        println!("  chunks written: {}", self.chunks_written);
+        println!("  extraction skipped: {}", self.extraction_skipped);
+        if self.prep.stitcher.is_some() {
+            println!("  stitched items: {}", self.stitched);
         }
-        println!("  checkpoint: {}", max_updated);
-        println!("ok");
+        match self.content_dedup {
+            DedupMode::KeepAll => {}
+            DedupMode::Link => println!("  duplicates linked: {}", self.duplicates),
+            DedupMode::Skip => println!("  duplicates skipped: {}", self.duplicates),
+        }
+        if self.prep.redactor.is_some() {
+            println!("  redactions: {}", redact::summarize(&self.redactions));
+        }
+        if self.config.embedding.is_enabled() {
+            println!("  embeddings written: {}", self.embeddings_written);
+            println!("  embeddings pending: {}", self.embeddings_pending);
+        }
+    }
+}
+
+/// Time per pipeline stage, in the order stages first ran.
+///
+/// Stage names: `scan`, `raw` (storing items for `[sync] keep_raw`), `extract`, `redact`, `hash`, `chunk`,
+/// `summarize`, `classify`, `contextualize`, `write` (SQLite upserts and chunk replacement), and
+/// `embed`. Stages that never ran (e.g. `redact` for a connector without
+/// redaction) are absent.
+///
+/// `extract`, `redact`, `hash`, and `chunk` run on the CPU pool and are
+/// summed across its threads, so with more than one thread they can add
+/// up to more than the wall time of the run.
+#[derive(Debug, Clone, Default)]
+pub struct StageTimings {
+    stages: Vec<(&'static str, Duration)>,
+}
+
+impl StageTimings {
+    /// Add `elapsed` to `stage`.
+    pub fn add(&mut self, stage: &'static str, elapsed: Duration) {
+        match self.stages.iter_mut().find(|(name, _)| *name == stage) {
+            Some((_, total)) => *total += elapsed,
+            None => self.stages.push((stage, elapsed)),
+        }
+    }
+
+    /// `(stage, total)` pairs in pipeline order.
+    pub fn stages(&self) -> &[(&'static str, Duration)] {
+        &self.stages
     }
 
+    /// Sum of all stages.
+    pub fn total(&self) -> Duration {
+        self.stages.iter().map(|(_, d)| *d).sum()
+    }
+}
+
+/// Outcome of [`ingest_timed`].
+pub struct TimedIngest {
+    pub timings: StageTimings,
+    /// Wall time of the whole ingest.
+    pub elapsed: Duration,
+    pub documents: u64,
+    pub chunks: u64,
+    pub embeddings: u64,
+    pub extraction_skipped: u64,
+}
+
+/// Ingest `items` of `source_label` into `store` through the sync pipeline,
+/// timing each stage. Checkpoints are not touched. With `embed`, chunks are
+/// embedded inline when embeddings are enabled.
+///
+/// Used by `ctx bench ingest` (see [`crate::bench`]).
+pub async fn ingest_timed(
+    config: &Config,
+    store: &SqliteAppStore,
+    source_label: &str,
+    items: Vec<SourceItem>,
+    embed: bool,
+) -> Result<TimedIngest> {
+    let mut embedder = if embed {
+        InlineEmbedder::new(config)
+    } else {
+        None
+    };
+    let mut ingest = ConnectorIngest::new(config, source_label, None, false, embedder.as_mut())?;
+    ingest.timings = Some(StageTimings::default());
+    let started = Instant::now();
+    ingest
+        .ingest_items(store, items, None, &CancellationToken::new())
+        .await?;
+    ingest.flush_stitched(store, None).await?;
+    ingest.flush_embeddings(store, None).await;
+    Ok(TimedIngest {
+        timings: ingest.timings.take().unwrap_or_default(),
+        elapsed: started.elapsed(),
+        documents: ingest.docs_upserted,
+        chunks: ingest.chunks_written,
+        embeddings: ingest.embeddings_written,
+        extraction_skipped: ingest.extraction_skipped,
+    })
+}
+
+/// Ingest one `item` through the sync pipeline and return the ID of the
+/// document it was written to (its stitched document, if a `stitch` rule
+/// matches it). Checkpoints are not touched.
+///
+/// Used by `ctx add` (see [`crate::add`]).
+///
+/// # Errors
+///
+/// Returns an error if extraction produced no document, or a database
+/// operation fails.
+pub async fn ingest_one(config: &Config, item: SourceItem) -> Result<String> {
+    let store = SqliteAppStore::connect(config).await?;
+    migrate::ensure_titles_table(store.pool(), &config.retrieval.fts_tokenizer).await?;
+    migrate::create_stitch_members_table(store.pool()).await?;
+    if config.sync.content_dedup != "keep-all" {
+        migrate::create_content_hash_indexes(store.pool()).await?;
+    }
+    if !config.sync.keep_raw.is_empty() {
+        migrate::create_raw_items_table(store.pool()).await?;
+    }
+
+    let mut embedder = if config.sync.embed {
+        InlineEmbedder::new(config)
+    } else {
+        None
+    };
+    if let Some(ref embedder) = embedder {
+        embedder.check_dims(config, store.pool()).await?;
+    }
+
+    let source = item.source.clone();
+    let mut source_id = item.source_id.clone();
+    let mut ingest = ConnectorIngest::new(config, &source, None, false, embedder.as_mut())?;
+    ingest.fetched = 1;
+    ingest
+        .ingest_items(&store, vec![item], None, &CancellationToken::new())
+        .await?;
+    ingest.flush_stitched(&store, None).await?;
+    ingest.flush_embeddings(&store, None).await;
+    if ingest.extraction_skipped > 0 {
+        store.close().await;
+        bail!("No text could be extracted from {}", source_id);
+    }
+    if let Some(group) = ingest
+        .prep
+        .stitcher
+        .as_ref()
+        .and_then(|s| s.group(&source_id))
+    {
+        source_id = group;
+    }
+    search_cache::bump_index_generation(store.pool()).await?;
+    store.refresh_keyword_index().await?;
+
+    // A skipped duplicate is not stored; its content is the canonical's.
+    let id: Option<String> =
+        sqlx::query_scalar("SELECT id FROM documents WHERE source = ? AND source_id = ?")
+            .bind(&source)
+            .bind(&source_id)
+            .fetch_optional(store.pool())
+            .await?;
+    let id = id
+        .or(ingest.last_duplicate_of.take())
+        .with_context(|| format!("{} was not stored", source_id))?;
+    enforce_storage_budget(config, store.pool()).await?;
     store.close().await;
+    Ok(id)
+}
+
+/// Print the connector's scan-time skip counts, e.g.
+/// `scan skipped: 3 (binary 1, too large 2)`. Prints nothing when empty.
+fn print_scan_skipped(skipped: &BTreeMap<String, u64>) {
+    let total: u64 = skipped.values().sum();
+    if total == 0 {
+        return;
+    }
+    let parts: Vec<String> = skipped
+        .iter()
+        .map(|(k, v)| format!("{} {}", k, v))
+        .collect();
+    println!("  scan skipped: {} ({})", total, parts.join(", "));
+}
+
+/// Apply `[storage] max_size_mb` after a sync and report evictions or a
+/// warning when the database is close to the budget.
+async fn enforce_storage_budget(config: &Config, pool: &sqlx::SqlitePool) -> Result<()> {
+    let Some(report) = storage::enforce_budget(config, pool).await? else {
+        return Ok(());
+    };
+
+    if report.total_evicted() > 0 {
+        let by_source: Vec<String> = report
+            .evicted
+            .iter()
+            .map(|(source, n)| format!("{} {}", source, n))
+            .collect();
+        println!("storage");
+        println!(
+            "  evicted documents: {} ({})",
+            report.total_evicted(),
+            by_source.join(", ")
+        );
+        println!(
+            "  size: {} -> {} (budget {})",
+            format_bytes(report.size_before),
+            format_bytes(report.size_after),
+            format_bytes(report.budget_bytes)
+        );
+    }
+    if let Some(warning) = storage::budget_warning(config, report.size_after) {
+        tracing::warn!("{}", warning);
+    }
     Ok(())
 }
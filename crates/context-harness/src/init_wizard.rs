@@ -0,0 +1,508 @@
+//! `ctx init --interactive`: a first-run setup wizard.
+//!
+//! Instead of starting from a TOML template, the wizard asks for:
+//!
+//! 1. where the database goes;
+//! 2. the sources to index — local directories, Git repositories, and S3
+//!    buckets, as many as needed;
+//! 3. the embedding provider: none (keyword search only), `local`,
+//!    `openai`, or `ollama`;
+//! 4. the port `ctx serve mcp` listens on.
+//!
+//! The answers are rendered by [`render_config`] and validated like any
+//! config file before they replace the target file. `ctx init` then runs
+//! the migrations, and the wizard offers an initial `ctx sync all`.
+//!
+//! Answers are read line by line from stdin, with prompts on stderr, so
+//! the wizard can be scripted by piping answers in. An empty answer takes
+//! the default shown in brackets.
+
+use anyhow::{bail, Context, Result};
+use std::fmt::Write as _;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use crate::config;
+use crate::ctx_dirs;
+
+/// A source to index, as answered in the wizard.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SourceAnswer {
+    Filesystem {
+        name: String,
+        root: String,
+    },
+    Git {
+        name: String,
+        url: String,
+        branch: String,
+    },
+    S3 {
+        name: String,
+        bucket: String,
+        prefix: String,
+        region: String,
+    },
+}
+
+impl SourceAnswer {
+    fn label(&self) -> (&'static str, &str) {
+        match self {
+            SourceAnswer::Filesystem { name, .. } => ("filesystem", name),
+            SourceAnswer::Git { name, .. } => ("git", name),
+            SourceAnswer::S3 { name, .. } => ("s3", name),
+        }
+    }
+}
+
+/// The embedding provider chosen in the wizard.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EmbeddingAnswer {
+    None,
+    Local,
+    OpenAi {
+        model: String,
+        dims: usize,
+    },
+    Ollama {
+        url: String,
+        model: String,
+        dims: usize,
+    },
+}
+
+/// Everything the wizard asks for.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WizardAnswers {
+    pub db_path: String,
+    pub sources: Vec<SourceAnswer>,
+    pub embedding: EmbeddingAnswer,
+    pub port: u16,
+}
+
+/// Line-based prompts on stderr, answered from `input`.
+pub struct Prompter<R> {
+    input: R,
+}
+
+impl Prompter<std::io::StdinLock<'static>> {
+    /// Prompt on the terminal, or on whatever is piped to stdin.
+    pub fn stdin() -> Self {
+        Self::new(std::io::stdin().lock())
+    }
+}
+
+impl<R: BufRead> Prompter<R> {
+    pub fn new(input: R) -> Self {
+        Self { input }
+    }
+
+    /// Ask `question`; an empty answer returns `default`.
+    fn ask(&mut self, question: &str, default: &str) -> Result<String> {
+        if default.is_empty() {
+            eprint!("{}: ", question);
+        } else {
+            eprint!("{} [{}]: ", question, default);
+        }
+        std::io::stderr().flush()?;
+        let mut line = String::new();
+        if self.input.read_line(&mut line)? == 0 {
+            bail!("Setup cancelled: input ended before all questions were answered.");
+        }
+        let answer = line.trim();
+        Ok(if answer.is_empty() {
+            default.to_string()
+        } else {
+            answer.to_string()
+        })
+    }
+
+    /// Ask until the answer is non-empty.
+    fn ask_required(&mut self, question: &str) -> Result<String> {
+        loop {
+            let answer = self.ask(question, "")?;
+            if !answer.is_empty() {
+                return Ok(answer);
+            }
+            eprintln!("  An answer is required.");
+        }
+    }
+
+    /// Ask until the answer is one of `choices`.
+    fn choose(&mut self, question: &str, choices: &[&str], default: &str) -> Result<String> {
+        let question = format!("{} ({})", question, choices.join("/"));
+        loop {
+            let answer = self.ask(&question, default)?.to_lowercase();
+            if choices.contains(&answer.as_str()) {
+                return Ok(answer);
+            }
+            eprintln!("  Please answer one of: {}.", choices.join(", "));
+        }
+    }
+
+    /// Ask until the answer parses as a positive number.
+    fn ask_number<T: std::str::FromStr + PartialOrd + Default>(
+        &mut self,
+        question: &str,
+        default: &str,
+    ) -> Result<T> {
+        loop {
+            match self.ask(question, default)?.parse::<T>() {
+                Ok(n) if n > T::default() => return Ok(n),
+                _ => eprintln!("  Please enter a positive number."),
+            }
+        }
+    }
+
+    /// Ask a yes/no question.
+    pub fn confirm(&mut self, question: &str, default: bool) -> Result<bool> {
+        let hint = if default { "Y/n" } else { "y/N" };
+        loop {
+            let answer = self.ask(&format!("{} [{}]", question, hint), "")?;
+            match answer.to_lowercase().as_str() {
+                "" => return Ok(default),
+                "y" | "yes" => return Ok(true),
+                "n" | "no" => return Ok(false),
+                _ => eprintln!("  Please answer y or n."),
+            }
+        }
+    }
+
+    /// Ask all the wizard's questions.
+    pub fn collect(&mut self, default_db_path: &str) -> Result<WizardAnswers> {
+        eprintln!("Database");
+        let db_path = self.ask("  SQLite file", default_db_path)?;
+
+        eprintln!("Sources");
+        let mut sources: Vec<SourceAnswer> = Vec::new();
+        loop {
+            let default = if sources.is_empty() {
+                "directory"
+            } else {
+                "done"
+            };
+            let kind = self.choose(
+                "  Add a source",
+                &["directory", "git", "s3", "done"],
+                default,
+            )?;
+            let source = match kind.as_str() {
+                "directory" => {
+                    let root = self.ask("    Directory", ".")?;
+                    let name = self.source_name(&sources, "filesystem", "docs")?;
+                    SourceAnswer::Filesystem { name, root }
+                }
+                "git" => {
+                    let url = self.ask_required("    Repository URL")?;
+                    let branch = self.ask("    Branch", "main")?;
+                    let name = self.source_name(&sources, "git", &repo_name(&url))?;
+                    SourceAnswer::Git { name, url, branch }
+                }
+                "s3" => {
+                    let bucket = self.ask_required("    Bucket")?;
+                    let prefix = self.ask("    Key prefix", "")?;
+                    let region = self.ask("    Region", "us-east-1")?;
+                    let name = self.source_name(&sources, "s3", &bucket)?;
+                    SourceAnswer::S3 {
+                        name,
+                        bucket,
+                        prefix,
+                        region,
+                    }
+                }
+                _ => break,
+            };
+            sources.push(source);
+        }
+
+        eprintln!("Embeddings");
+        let provider = self.choose(
+            "  Provider for semantic search",
+            &["none", "local", "openai", "ollama"],
+            "none",
+        )?;
+        let embedding = match provider.as_str() {
+            "local" => EmbeddingAnswer::Local,
+            "openai" => EmbeddingAnswer::OpenAi {
+                model: self.ask("    Model", "text-embedding-3-small")?,
+                dims: self.ask_number("    Dimensions", "1536")?,
+            },
+            "ollama" => EmbeddingAnswer::Ollama {
+                url: self.ask("    Ollama URL", "http://localhost:11434")?,
+                model: self.ask("    Model", "nomic-embed-text")?,
+                dims: self.ask_number("    Dimensions", "768")?,
+            },
+            _ => EmbeddingAnswer::None,
+        };
+
+        eprintln!("Server");
+        let port = self.ask_number("  Port for `ctx serve mcp`", "7331")?;
+
+        Ok(WizardAnswers {
+            db_path,
+            sources,
+            embedding,
+            port,
+        })
+    }
+
+    /// Ask for a source's name, defaulting to `suggested` made unique
+    /// among the sources of the same type.
+    fn source_name(
+        &mut self,
+        sources: &[SourceAnswer],
+        kind: &str,
+        suggested: &str,
+    ) -> Result<String> {
+        let taken = |name: &str| sources.iter().any(|s| s.label() == (kind, name));
+        let mut default = sanitize_name(suggested);
+        let mut n = 2;
+        while taken(&default) {
+            default = format!("{}-{}", sanitize_name(suggested), n);
+            n += 1;
+        }
+        loop {
+            let name = sanitize_name(&self.ask("    Name", &default)?);
+            if !taken(&name) {
+                return Ok(name);
+            }
+            eprintln!("  There is already a {} source named {}.", kind, name);
+        }
+    }
+}
+
+/// The last path segment of a repository URL, without `.git`.
+fn repo_name(url: &str) -> String {
+    let last = url
+        .trim_end_matches('/')
+        .rsplit(['/', ':'])
+        .next()
+        .unwrap_or("repo");
+    last.trim_end_matches(".git").to_string()
+}
+
+/// Lowercase `name`, keeping letters, digits, `-`, and `_`.
+fn sanitize_name(name: &str) -> String {
+    let cleaned: String = name
+        .to_lowercase()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    let cleaned = cleaned.trim_matches('-').to_string();
+    if cleaned.is_empty() {
+        "default".to_string()
+    } else {
+        cleaned
+    }
+}
+
+/// A TOML string literal.
+fn quote(value: &str) -> String {
+    toml::Value::String(value.to_string()).to_string()
+}
+
+/// Render `answers` as a config file.
+pub fn render_config(answers: &WizardAnswers) -> String {
+    let mut out = String::from("# Written by `ctx init --interactive`.\n\n");
+    let _ = writeln!(out, "[db]\npath = {}\n", quote(&answers.db_path));
+    out.push_str("[chunking]\nmax_tokens = 700\noverlap_tokens = 0\n\n");
+    out.push_str("[retrieval]\nfinal_limit = 12\n\n");
+    let _ = writeln!(out, "[server]\nbind = \"127.0.0.1:{}\"\n", answers.port);
+
+    match &answers.embedding {
+        EmbeddingAnswer::None => {}
+        EmbeddingAnswer::Local => out.push_str("[embedding]\nprovider = \"local\"\n\n"),
+        EmbeddingAnswer::OpenAi { model, dims } => {
+            let _ = writeln!(
+                out,
+                "[embedding]\nprovider = \"openai\"\nmodel = {}\ndims = {}\n\
+                 # Reads OPENAI_API_KEY, or set api_key = \"secret://openai\" after `ctx secrets set openai`.\n",
+                quote(model),
+                dims
+            );
+        }
+        EmbeddingAnswer::Ollama { url, model, dims } => {
+            let _ = writeln!(
+                out,
+                "[embedding]\nprovider = \"ollama\"\nurl = {}\nmodel = {}\ndims = {}\n",
+                quote(url),
+                quote(model),
+                dims
+            );
+        }
+    }
+
+    for source in &answers.sources {
+        match source {
+            SourceAnswer::Filesystem { name, root } => {
+                let _ = writeln!(
+                    out,
+                    "[connectors.filesystem.{}]\nroot = {}\n",
+                    name,
+                    quote(root)
+                );
+            }
+            SourceAnswer::Git { name, url, branch } => {
+                let _ = writeln!(
+                    out,
+                    "[connectors.git.{}]\nurl = {}\nbranch = {}\n",
+                    name,
+                    quote(url),
+                    quote(branch)
+                );
+            }
+            SourceAnswer::S3 {
+                name,
+                bucket,
+                prefix,
+                region,
+            } => {
+                let _ = writeln!(
+                    out,
+                    "[connectors.s3.{}]\nbucket = {}\nprefix = {}\nregion = {}\n",
+                    name,
+                    quote(bucket),
+                    quote(prefix),
+                    quote(region)
+                );
+            }
+        }
+    }
+    out.trim_end().to_string() + "\n"
+}
+
+/// Run the wizard and write its config file, returning the file's path.
+///
+/// The file is `--config` or `CTX_CONFIG` when given, otherwise
+/// `./.ctx/config.toml`. An existing file is only replaced after
+/// confirmation; declining keeps it and continues with `ctx init`.
+///
+/// # Errors
+///
+/// Returns an error if stdin ends before the questions are answered, or
+/// if the rendered config fails validation (the target is then left as
+/// it was).
+pub fn run_wizard(explicit_path: Option<&Path>) -> Result<PathBuf> {
+    let paths = ctx_dirs::config_paths(explicit_path.map(Path::to_path_buf));
+    let (path, default_db_path) = match paths.resolve().path {
+        Some(path) if paths.has_explicit_source() => (path, "./data/ctx.sqlite"),
+        _ => {
+            config::create_workspace_dirs()?;
+            (ctx_dirs::workspace_config_path(), ".ctx/data/ctx.sqlite")
+        }
+    };
+
+    let mut prompter = Prompter::stdin();
+    eprintln!("ctx setup — writing {}", path.display());
+    if path.exists()
+        && !prompter.confirm(&format!("{} exists. Replace it?", path.display()), false)?
+    {
+        eprintln!("Keeping the existing config.");
+        return Ok(path);
+    }
+
+    let answers = prompter.collect(default_db_path)?;
+    let staged = path.with_extension("toml.new");
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&staged, render_config(&answers))?;
+    if let Err(e) = config::load_config(&staged) {
+        let _ = std::fs::remove_file(&staged);
+        return Err(e.context("The generated config is invalid"));
+    }
+    std::fs::rename(&staged, &path)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    eprintln!("Wrote {}", path.display());
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collects_sources_with_unique_names() {
+        let input = "\n\
+            directory\n./docs\n\n\
+            directory\n./notes\n\n\
+            git\nhttps://github.com/acme/platform.git\n\n\n\
+            done\n\
+            ollama\n\nnomic-embed-text\n768\n\
+            8080\n";
+        let answers = Prompter::new(input.as_bytes())
+            .collect("./data/ctx.sqlite")
+            .unwrap();
+        assert_eq!(answers.db_path, "./data/ctx.sqlite");
+        assert_eq!(
+            answers.sources,
+            vec![
+                SourceAnswer::Filesystem {
+                    name: "docs".into(),
+                    root: "./docs".into()
+                },
+                SourceAnswer::Filesystem {
+                    name: "docs-2".into(),
+                    root: "./notes".into()
+                },
+                SourceAnswer::Git {
+                    name: "platform".into(),
+                    url: "https://github.com/acme/platform.git".into(),
+                    branch: "main".into()
+                },
+            ]
+        );
+        assert_eq!(answers.port, 8080);
+    }
+
+    #[test]
+    fn reasks_invalid_answers_and_fails_on_eof() {
+        let answers = Prompter::new("\nmaybe\ndone\nnone\n0\n7331\n".as_bytes())
+            .collect("db.sqlite")
+            .unwrap();
+        assert!(answers.sources.is_empty());
+        assert_eq!(answers.embedding, EmbeddingAnswer::None);
+        assert_eq!(answers.port, 7331);
+
+        assert!(Prompter::new("\n".as_bytes()).collect("db.sqlite").is_err());
+    }
+
+    #[test]
+    fn renders_quoted_values() {
+        let toml = render_config(&WizardAnswers {
+            db_path: "C:\\ctx\\db.sqlite".into(),
+            sources: vec![SourceAnswer::S3 {
+                name: "docs".into(),
+                bucket: "acme-docs".into(),
+                prefix: "wiki/".into(),
+                region: "eu-west-1".into(),
+            }],
+            embedding: EmbeddingAnswer::Local,
+            port: 9000,
+        });
+        let value: toml::Value = toml::from_str(&toml).unwrap();
+        assert_eq!(value["db"]["path"].as_str(), Some("C:\\ctx\\db.sqlite"));
+        assert_eq!(value["server"]["bind"].as_str(), Some("127.0.0.1:9000"));
+        assert_eq!(
+            value["connectors"]["s3"]["docs"]["prefix"].as_str(),
+            Some("wiki/")
+        );
+        assert_eq!(value["embedding"]["provider"].as_str(), Some("local"));
+    }
+
+    #[test]
+    fn repo_names_come_from_the_url() {
+        assert_eq!(
+            repo_name("https://github.com/acme/platform.git"),
+            "platform"
+        );
+        assert_eq!(repo_name("git@github.com:acme/Runbooks/"), "Runbooks");
+        assert_eq!(sanitize_name("My Repo!"), "my-repo");
+    }
+}
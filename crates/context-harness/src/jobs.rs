@@ -0,0 +1,475 @@
+//! Job registry: `ctx jobs` and `/admin/jobs`.
+//!
+//! Every sync (including each sync of `ctx watch`) and every `ctx embed
+//! pending` or `ctx embed rebuild` registers in the `jobs` table while it
+//! runs. Other processes control it through the same table:
+//!
+//! | Command | Endpoint | Effect |
+//! |---------|----------|--------|
+//! | `ctx jobs list` | `GET /admin/jobs` | Running and paused jobs (`--all` adds finished ones) |
+//! | `ctx jobs pause <id>` | `POST /admin/jobs/{id}/pause` | Stop before the next document (sync) or batch (embed) |
+//! | `ctx jobs resume <id>` | `POST /admin/jobs/{id}/resume` | Continue a paused job |
+//! | `ctx jobs cancel <id>` | `POST /admin/jobs/{id}/cancel` | Stop like Ctrl-C, paused or not |
+//!
+//! A running job checks its row for a requested action every
+//! [`POLL_INTERVAL`] and records a heartbeat. A job whose heartbeat is
+//! older than [`LOST_AFTER_SECS`] (its process exited without finishing
+//! it) is listed as `lost`. IDs may be abbreviated to a unique prefix.
+//!
+//! Cancelling a sync fires the same token as Ctrl-C, so documents written
+//! so far are kept and the stopped connector's checkpoint is left
+//! unchanged (see [`crate::ingest`]). Cancelling an embed leaves the
+//! remaining chunks for `ctx embed pending`. A paused sync still counts
+//! toward `[sync] timeout_secs`.
+
+use anyhow::{bail, Result};
+use chrono::Utc;
+use serde::Serialize;
+use sqlx::SqlitePool;
+use std::time::Duration;
+use tokio::sync::watch;
+
+use crate::config::Config;
+use crate::db;
+use crate::migrate;
+use crate::scrub;
+use crate::stats;
+use crate::traits::CancellationToken;
+
+/// How often a running job checks for requested actions.
+pub const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Seconds without a heartbeat after which a job is listed as `lost`.
+pub const LOST_AFTER_SECS: i64 = 30;
+
+/// Days finished jobs are kept.
+const KEEP_FINISHED_DAYS: i64 = 7;
+
+/// A row of the `jobs` table.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct JobRow {
+    pub id: String,
+    /// `sync` or `embed`.
+    pub kind: String,
+    /// Connectors of a sync; `pending` or `rebuild` for an embed.
+    pub target: String,
+    pub pid: i64,
+    /// `running`, `paused`, `completed`, `failed`, `cancelled`, or `lost`.
+    pub state: String,
+    /// Action requested but not yet applied: `pause`, `resume`, or `cancel`.
+    pub requested: Option<String>,
+    pub started_at: i64,
+    pub heartbeat_at: i64,
+    pub finished_at: Option<i64>,
+    pub error: Option<String>,
+}
+
+type Row = (
+    String,
+    String,
+    String,
+    i64,
+    String,
+    Option<String>,
+    i64,
+    i64,
+    Option<i64>,
+    Option<String>,
+);
+
+const COLUMNS: &str =
+    "id, kind, target, pid, state, requested, started_at, heartbeat_at, finished_at, error";
+
+impl JobRow {
+    fn from_row(row: Row, now: i64) -> Self {
+        let (id, kind, target, pid, state, requested, started_at, heartbeat_at, finished_at, error) =
+            row;
+        let state = if is_active(&state) && now - heartbeat_at > LOST_AFTER_SECS {
+            "lost".to_string()
+        } else {
+            state
+        };
+        Self {
+            id,
+            kind,
+            target,
+            pid,
+            state,
+            requested,
+            started_at,
+            heartbeat_at,
+            finished_at,
+            error,
+        }
+    }
+}
+
+fn is_active(state: &str) -> bool {
+    matches!(state, "running" | "paused")
+}
+
+/// A registered job of this process. Dropping it without [`Job::finish`]
+/// stops the heartbeat, so the job is eventually listed as `lost`.
+pub struct Job {
+    pool: SqlitePool,
+    id: String,
+    control: JobControl,
+    poller: tokio::task::JoinHandle<()>,
+}
+
+/// Pause and cancel state of a running job, for the loop doing its work.
+#[derive(Clone)]
+pub struct JobControl {
+    cancel: CancellationToken,
+    paused: watch::Receiver<bool>,
+}
+
+impl JobControl {
+    /// Wait while the job is paused, or until `cancel` fires.
+    pub async fn wait_while_paused(&self, cancel: &CancellationToken) {
+        let mut paused = self.paused.clone();
+        tokio::select! {
+            _ = paused.wait_for(|paused| !*paused) => {}
+            _ = cancel.cancelled() => {}
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+
+    /// Wait while the job is paused; `false` once it was cancelled.
+    pub async fn proceed(&self) -> bool {
+        self.wait_while_paused(&self.cancel).await;
+        !self.cancel.is_cancelled()
+    }
+}
+
+impl Job {
+    /// Register a job of `kind` on `target`, cancelled through `cancel`.
+    pub async fn start(
+        config: &Config,
+        kind: &str,
+        target: &str,
+        cancel: CancellationToken,
+    ) -> Result<Self> {
+        let pool = db::connect(config).await?;
+        migrate::create_jobs_table(&pool).await?;
+        let now = Utc::now().timestamp();
+        sqlx::query("DELETE FROM jobs WHERE finished_at < ?")
+            .bind(now - KEEP_FINISHED_DAYS * 86_400)
+            .execute(&pool)
+            .await?;
+        let id = uuid::Uuid::new_v4().simple().to_string()[..8].to_string();
+        sqlx::query(
+            "INSERT INTO jobs (id, kind, target, pid, state, started_at, heartbeat_at) \
+             VALUES (?, ?, ?, ?, 'running', ?, ?)",
+        )
+        .bind(&id)
+        .bind(kind)
+        .bind(target)
+        .bind(std::process::id() as i64)
+        .bind(now)
+        .bind(now)
+        .execute(&pool)
+        .await?;
+        tracing::debug!(job = %id, kind, target, "job started");
+
+        let (paused_tx, paused) = watch::channel(false);
+        let poller = tokio::spawn(poll(pool.clone(), id.clone(), cancel.clone(), paused_tx));
+        Ok(Self {
+            pool,
+            id,
+            control: JobControl { cancel, paused },
+            poller,
+        })
+    }
+
+    #[cfg(test)]
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn control(&self) -> JobControl {
+        self.control.clone()
+    }
+
+    /// Record how the job ended: `cancelled` if its token fired, else
+    /// `completed` or `failed` by `result`.
+    pub async fn finish<T>(self, result: &Result<T>) {
+        self.poller.abort();
+        let (state, error) = if self.control.cancel.is_cancelled() {
+            ("cancelled", None)
+        } else {
+            match result {
+                Ok(_) => ("completed", None),
+                Err(e) => ("failed", Some(scrub::scrub(&format!("{:#}", e)))),
+            }
+        };
+        let now = Utc::now().timestamp();
+        let recorded = sqlx::query(
+            "UPDATE jobs SET state = ?, requested = NULL, error = ?, \
+             heartbeat_at = ?, finished_at = ? WHERE id = ?",
+        )
+        .bind(state)
+        .bind(error)
+        .bind(now)
+        .bind(now)
+        .bind(&self.id)
+        .execute(&self.pool)
+        .await;
+        if let Err(e) = recorded {
+            tracing::warn!("failed to record end of job {}: {}", self.id, e);
+        }
+        self.pool.close().await;
+    }
+}
+
+/// Record heartbeats of job `id` and apply its requested actions.
+async fn poll(
+    pool: SqlitePool,
+    id: String,
+    cancel: CancellationToken,
+    paused: watch::Sender<bool>,
+) {
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+    loop {
+        interval.tick().await;
+        if let Err(e) = apply_requested(&pool, &id, &cancel, &paused).await {
+            tracing::warn!("job {}: {:#}", id, e);
+        }
+    }
+}
+
+async fn apply_requested(
+    pool: &SqlitePool,
+    id: &str,
+    cancel: &CancellationToken,
+    paused: &watch::Sender<bool>,
+) -> Result<()> {
+    let requested: Option<String> = sqlx::query_scalar("SELECT requested FROM jobs WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await?
+        .flatten();
+    let state = match requested.as_deref() {
+        Some("cancel") => {
+            if !cancel.is_cancelled() {
+                eprintln!("Job {} cancelled: finishing the current document", id);
+                cancel.cancel();
+            }
+            None
+        }
+        Some("pause") => {
+            paused.send_replace(true);
+            Some("paused")
+        }
+        Some("resume") => {
+            paused.send_replace(false);
+            Some("running")
+        }
+        _ => None,
+    };
+    let now = Utc::now().timestamp();
+    match state {
+        Some(state) => {
+            tracing::info!(job = %id, state, "job {}", state);
+            sqlx::query(
+                "UPDATE jobs SET state = ?, requested = NULL, heartbeat_at = ? WHERE id = ?",
+            )
+            .bind(state)
+            .bind(now)
+            .bind(id)
+            .execute(pool)
+            .await?;
+        }
+        None => {
+            sqlx::query("UPDATE jobs SET heartbeat_at = ? WHERE id = ?")
+                .bind(now)
+                .bind(id)
+                .execute(pool)
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Running and paused jobs, newest first; with `all`, finished jobs too.
+pub async fn list(pool: &SqlitePool, all: bool) -> Result<Vec<JobRow>> {
+    migrate::create_jobs_table(pool).await?;
+    let sql = if all {
+        format!("SELECT {} FROM jobs ORDER BY started_at DESC, id", COLUMNS)
+    } else {
+        format!(
+            "SELECT {} FROM jobs WHERE state IN ('running', 'paused') \
+             ORDER BY started_at DESC, id",
+            COLUMNS
+        )
+    };
+    let rows: Vec<Row> = sqlx::query_as(&sql).fetch_all(pool).await?;
+    let now = Utc::now().timestamp();
+    Ok(rows
+        .into_iter()
+        .map(|row| JobRow::from_row(row, now))
+        .collect())
+}
+
+/// The job whose ID starts with `id`; `None` unless exactly one does.
+pub async fn find(pool: &SqlitePool, id: &str) -> Result<Option<JobRow>> {
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Ok(None);
+    }
+    migrate::create_jobs_table(pool).await?;
+    let rows: Vec<Row> = sqlx::query_as(&format!(
+        "SELECT {} FROM jobs WHERE id LIKE ? ORDER BY id LIMIT 2",
+        COLUMNS
+    ))
+    .bind(format!("{}%", id))
+    .fetch_all(pool)
+    .await?;
+    let now = Utc::now().timestamp();
+    let mut rows = rows.into_iter().map(|row| JobRow::from_row(row, now));
+    match (rows.next(), rows.next()) {
+        (Some(job), None) => Ok(Some(job)),
+        _ => Ok(None),
+    }
+}
+
+/// Request `action` (`pause`, `resume`, or `cancel`) of `job`, which
+/// applies it within [`POLL_INTERVAL`].
+pub async fn request(pool: &SqlitePool, job: &JobRow, action: &str) -> Result<JobRow> {
+    if !matches!(action, "pause" | "resume" | "cancel") {
+        bail!(
+            "Unknown job action '{}'. Expected pause, resume, or cancel.",
+            action
+        );
+    }
+    let mut job = job.clone();
+    if job.state == "lost" {
+        bail!(
+            "Job {} is not responding: its process (pid {}) stopped without finishing it.",
+            job.id,
+            job.pid
+        );
+    }
+    if !is_active(&job.state) {
+        bail!("Job {} already {}.", job.id, job.state);
+    }
+    // A pending cancel is not replaced by a pause or resume.
+    if job.requested.as_deref() != Some("cancel") {
+        sqlx::query("UPDATE jobs SET requested = ? WHERE id = ?")
+            .bind(action)
+            .bind(&job.id)
+            .execute(pool)
+            .await?;
+        job.requested = Some(action.to_string());
+    }
+    Ok(job)
+}
+
+/// `ctx jobs list`.
+pub async fn run_list(config: &Config, all: bool, json: bool) -> Result<()> {
+    let pool = db::connect(config).await?;
+    let jobs = list(&pool, all).await?;
+    pool.close().await;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&jobs)?);
+        return Ok(());
+    }
+    if jobs.is_empty() {
+        println!("No {}jobs.", if all { "" } else { "running " });
+        return Ok(());
+    }
+    println!(
+        "{:<10} {:<6} {:<18} {:<16} TARGET",
+        "ID", "KIND", "STATE", "STARTED"
+    );
+    for job in &jobs {
+        let state = match job.requested.as_deref() {
+            Some(action) if is_active(&job.state) => format!("{} ({})", job.state, action),
+            _ => job.state.clone(),
+        };
+        println!(
+            "{:<10} {:<6} {:<18} {:<16} {}",
+            job.id,
+            job.kind,
+            state,
+            stats::format_ts_relative(job.started_at),
+            job.target
+        );
+        if let Some(ref error) = job.error {
+            println!("{:<10} error: {}", "", error);
+        }
+    }
+    Ok(())
+}
+
+/// `ctx jobs pause|resume|cancel <id>`.
+pub async fn run_request(config: &Config, id: &str, action: &str) -> Result<()> {
+    let pool = db::connect(config).await?;
+    let job = match find(&pool, id).await {
+        Ok(Some(job)) => request(&pool, &job, action).await,
+        Ok(None) => Err(anyhow::anyhow!(
+            "No job or more than one matches '{}'. See `ctx jobs list`.",
+            id
+        )),
+        Err(e) => Err(e),
+    };
+    pool.close().await;
+    let job = job?;
+    println!(
+        "Requested {} of {} job {} ({}).",
+        action, job.kind, job.id, job.target
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_store::SqliteAppStore;
+
+    #[tokio::test]
+    async fn requested_actions_pause_resume_and_cancel_a_job() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let mut config = Config::minimal();
+        config.db.path = tmp.path().join("ctx.sqlite");
+        SqliteAppStore::initialize_config(&config).await.unwrap();
+        let pool = db::connect(&config).await.unwrap();
+
+        let cancel = CancellationToken::new();
+        let job = Job::start(&config, "embed", "rebuild", cancel.clone())
+            .await
+            .unwrap();
+        let control = job.control();
+        let row = find(&pool, &job.id()[..4]).await.unwrap().unwrap();
+        assert_eq!(
+            (row.kind.as_str(), row.state.as_str()),
+            ("embed", "running")
+        );
+        assert!(find(&pool, "xyz").await.unwrap().is_none());
+
+        request(&pool, &row, "pause").await.unwrap();
+        tokio::time::sleep(POLL_INTERVAL * 2).await;
+        assert_eq!(list(&pool, false).await.unwrap()[0].state, "paused");
+        let waiting = tokio::spawn(async move { control.proceed().await });
+        tokio::time::sleep(POLL_INTERVAL / 2).await;
+        assert!(!waiting.is_finished());
+
+        request(&pool, &row, "resume").await.unwrap();
+        assert!(waiting.await.unwrap());
+
+        request(&pool, &row, "cancel").await.unwrap();
+        tokio::time::sleep(POLL_INTERVAL * 2).await;
+        assert!(cancel.is_cancelled());
+        job.finish(&Ok::<(), anyhow::Error>(())).await;
+        assert!(list(&pool, false).await.unwrap().is_empty());
+        assert_eq!(list(&pool, true).await.unwrap()[0].state, "cancelled");
+        let row = find(&pool, &row.id).await.unwrap().unwrap();
+        assert!(request(&pool, &row, "resume")
+            .await
+            .unwrap_err()
+            .to_string()
+            .contains("already cancelled"));
+    }
+}
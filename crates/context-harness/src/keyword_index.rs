@@ -0,0 +1,717 @@
+//! Optional OpenSearch / Elasticsearch keyword backend.
+//!
+//! SQLite remains the canonical store for documents, chunks, FTS5 rows, and
+//! embeddings. With `[keyword_index] backend = "opensearch"` (or
+//! `"elasticsearch"`), every chunk is also indexed into an external cluster
+//! and keyword candidates come from a BM25 `multi_match` query over its
+//! `text`, `title`, and (with `[summary]` enabled) `summary` fields, boosted
+//! by `[retrieval.weights]`. Vector candidates and hybrid scoring stay
+//! local, so keyword search can move onto an existing cluster without
+//! moving the vectors.
+//!
+//! # Writes
+//!
+//! [`SqliteAppStore`](crate::app_store::SqliteAppStore) mirrors a document
+//! after replacing its SQLite chunks: a delete-by-query on `document_id`,
+//! then one `_bulk` request. Sync refreshes the index once at the end, and
+//! storage eviction deletes evicted documents the same way.
+//! `ctx keyword-index rebuild` recreates the index from SQLite, e.g. after
+//! pointing at a new cluster.
+//!
+//! # Failures
+//!
+//! With `fallback = "sqlite"` (the default) a failed query is answered from
+//! FTS5, and the first failed write is logged and stops mirroring for the
+//! rest of the command, leaving the cluster stale until a rebuild. With
+//! `fallback = "disabled"` both are errors.
+//!
+//! Text analysis (tokenizer, stop words, stemming) comes from the index
+//! mapping; `[retrieval] fts_tokenizer` and `stop_words` apply to FTS5 only.
+//! `[retrieval.bm25]` `k1` and `b` become the index's default similarity
+//! when it is created, so changing them takes `ctx keyword-index rebuild`.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use reqwest::{Method, RequestBuilder, StatusCode};
+use serde_json::{json, Value};
+use sqlx::{Row, SqlitePool};
+use tokio::sync::OnceCell;
+
+use context_harness_core::models::{Chunk, Document};
+use context_harness_core::store::{ChunkCandidate, DocumentMetadata, DocumentResponse, Store};
+
+use crate::config::{Bm25Params, Config, FtsWeights, KeywordIndexConfig};
+use crate::db;
+use crate::secrets;
+
+/// Chunks sent per `_bulk` request during a rebuild.
+const REBUILD_BATCH: i64 = 500;
+/// Snippet length when the cluster returns no highlight.
+const SNIPPET_CHARS: usize = 240;
+
+/// A chunk as stored in the external index.
+#[derive(Debug, Clone)]
+pub struct IndexedChunk {
+    pub chunk_id: String,
+    pub document_id: String,
+    pub source: String,
+    pub title: Option<String>,
+    pub summary: Option<String>,
+    pub text: String,
+    pub updated_at: i64,
+}
+
+enum Auth {
+    None,
+    Basic {
+        username: String,
+        password: Option<String>,
+    },
+    ApiKey(String),
+}
+
+/// Client for one OpenSearch or Elasticsearch index.
+pub struct OpenSearchIndex {
+    client: reqwest::Client,
+    url: String,
+    index: String,
+    backend: String,
+    auth: Auth,
+    fallback: bool,
+    /// BM25 constants of the index similarity, applied on creation.
+    bm25: Bm25Params,
+    /// Set after the first tolerated write failure; later writes are skipped.
+    failed: AtomicBool,
+    created: OnceCell<()>,
+}
+
+impl OpenSearchIndex {
+    pub fn from_config(config: &KeywordIndexConfig, bm25: Bm25Params) -> Result<Self> {
+        let url = config
+            .url
+            .as_deref()
+            .context("keyword_index.url is not set")?;
+        let auth = if let Some(key) = &config.api_key {
+            Auth::ApiKey(secrets::resolve(key)?)
+        } else if let Some(username) = &config.username {
+            Auth::Basic {
+                username: username.clone(),
+                password: config
+                    .password
+                    .as_deref()
+                    .map(secrets::resolve)
+                    .transpose()?,
+            }
+        } else {
+            Auth::None
+        };
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .build()?;
+        Ok(Self {
+            client,
+            url: url.trim_end_matches('/').to_string(),
+            index: config.index.clone(),
+            backend: config.backend.clone(),
+            auth,
+            fallback: config.fallback == "sqlite",
+            bm25,
+            failed: AtomicBool::new(false),
+            created: OnceCell::new(),
+        })
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let request = self
+            .client
+            .request(method, format!("{}/{}", self.url, path));
+        match &self.auth {
+            Auth::None => request,
+            Auth::Basic { username, password } => request.basic_auth(username, password.as_ref()),
+            Auth::ApiKey(key) => {
+                request.header(reqwest::header::AUTHORIZATION, format!("ApiKey {}", key))
+            }
+        }
+    }
+
+    /// Send a request and parse the JSON body, failing on non-2xx statuses.
+    async fn send(&self, request: RequestBuilder, what: &str) -> Result<Value> {
+        let response = request
+            .send()
+            .await
+            .with_context(|| format!("{} {} request to {} failed", self.backend, what, self.url))?;
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        if !status.is_success() {
+            bail!(
+                "{} {} returned {}: {}",
+                self.backend,
+                what,
+                status,
+                text.trim()
+            );
+        }
+        if text.trim().is_empty() {
+            return Ok(Value::Null);
+        }
+        serde_json::from_str(&text)
+            .with_context(|| format!("{} {} returned invalid JSON", self.backend, what))
+    }
+
+    /// Create the index with the chunk mapping unless it already exists.
+    async fn ensure_index(&self) -> Result<()> {
+        self.created
+            .get_or_try_init(|| async {
+                let head = self
+                    .request(Method::HEAD, &self.index)
+                    .send()
+                    .await
+                    .with_context(|| format!("{} request to {} failed", self.backend, self.url))?;
+                if head.status() == StatusCode::NOT_FOUND {
+                    self.create_index().await?;
+                }
+                Ok(())
+            })
+            .await
+            .map(|_| ())
+    }
+
+    async fn create_index(&self) -> Result<()> {
+        let mapping = json!({
+            "settings": {
+                "index": {
+                    "similarity": {
+                        "default": { "type": "BM25", "k1": self.bm25.k1, "b": self.bm25.b }
+                    }
+                }
+            },
+            "mappings": {
+                "properties": {
+                    "chunk_id": { "type": "keyword" },
+                    "document_id": { "type": "keyword" },
+                    "source": { "type": "keyword" },
+                    "title": { "type": "text" },
+                    "summary": { "type": "text" },
+                    "text": { "type": "text" },
+                    "updated_at": { "type": "long" },
+                }
+            }
+        });
+        self.send(
+            self.request(Method::PUT, &self.index).json(&mapping),
+            "create index",
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn delete_index(&self) -> Result<()> {
+        let response = self
+            .request(Method::DELETE, &self.index)
+            .send()
+            .await
+            .with_context(|| format!("{} request to {} failed", self.backend, self.url))?;
+        let status = response.status();
+        if !status.is_success() && status != StatusCode::NOT_FOUND {
+            let text = response.text().await.unwrap_or_default();
+            bail!(
+                "{} delete index returned {}: {}",
+                self.backend,
+                status,
+                text.trim()
+            );
+        }
+        Ok(())
+    }
+
+    /// Index chunks in one `_bulk` request, keyed by chunk ID.
+    async fn bulk(&self, chunks: &[IndexedChunk]) -> Result<()> {
+        if chunks.is_empty() {
+            return Ok(());
+        }
+        let mut body = String::new();
+        for chunk in chunks {
+            let action = json!({ "index": { "_index": self.index, "_id": chunk.chunk_id } });
+            let doc = json!({
+                "chunk_id": chunk.chunk_id,
+                "document_id": chunk.document_id,
+                "source": chunk.source,
+                "title": chunk.title,
+                "summary": chunk.summary,
+                "text": chunk.text,
+                "updated_at": chunk.updated_at,
+            });
+            body.push_str(&action.to_string());
+            body.push('\n');
+            body.push_str(&doc.to_string());
+            body.push('\n');
+        }
+        let response = self
+            .send(
+                self.request(Method::POST, "_bulk")
+                    .header(reqwest::header::CONTENT_TYPE, "application/x-ndjson")
+                    .body(body),
+                "bulk",
+            )
+            .await?;
+        if response["errors"].as_bool() == Some(true) {
+            let reason = response["items"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .find_map(|item| item["index"]["error"]["reason"].as_str())
+                .unwrap_or("unknown error");
+            bail!("{} bulk indexing failed: {}", self.backend, reason);
+        }
+        Ok(())
+    }
+
+    async fn delete_by_document(&self, ids: &[String]) -> Result<()> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        let body = json!({ "query": { "terms": { "document_id": ids } } });
+        self.send(
+            self.request(
+                Method::POST,
+                &format!("{}/_delete_by_query?conflicts=proceed", self.index),
+            )
+            .json(&body),
+            "delete",
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn refresh(&self) -> Result<()> {
+        self.send(
+            self.request(Method::POST, &format!("{}/_refresh", self.index)),
+            "refresh",
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn count(&self) -> Result<u64> {
+        let response = self
+            .send(
+                self.request(Method::GET, &format!("{}/_count", self.index)),
+                "count",
+            )
+            .await?;
+        Ok(response["count"].as_u64().unwrap_or(0))
+    }
+
+    /// BM25 keyword candidates, best first.
+    pub async fn search(
+        &self,
+        query: &str,
+        limit: i64,
+        weights: FtsWeights,
+        fuzzy: bool,
+    ) -> Result<Vec<ChunkCandidate>> {
+        let mut fields = Vec::new();
+        if weights.text > 0.0 {
+            fields.push(format!("text^{}", weights.text));
+        }
+        if weights.title > 0.0 {
+            fields.push(format!("title^{}", weights.title));
+        }
+        if weights.summary > 0.0 {
+            fields.push(format!("summary^{}", weights.summary));
+        }
+        let mut multi_match = json!({ "query": query, "fields": fields });
+        if fuzzy {
+            multi_match["fuzziness"] = json!("AUTO");
+        }
+        let body = json!({
+            "size": limit.max(0),
+            "_source": ["document_id", "text"],
+            "query": { "multi_match": multi_match },
+            "highlight": {
+                "pre_tags": [">>>"],
+                "post_tags": ["<<<"],
+                "fields": { "text": { "fragment_size": 200, "number_of_fragments": 1 } }
+            }
+        });
+        let response = self
+            .send(
+                self.request(Method::POST, &format!("{}/_search", self.index))
+                    .json(&body),
+                "search",
+            )
+            .await?;
+        Ok(parse_hits(&response))
+    }
+
+    /// Replace a document's chunks in the index with its current SQLite chunks.
+    async fn index_document(&self, pool: &SqlitePool, doc_id: &str) -> Result<()> {
+        self.ensure_index().await?;
+        let rows = sqlx::query(
+            r#"
+            SELECT c.id, c.document_id, c.text, d.source, d.title, d.summary, d.updated_at
+            FROM chunks c
+            JOIN documents d ON d.id = c.document_id
+            WHERE c.document_id = ?
+            ORDER BY c.chunk_index
+            "#,
+        )
+        .bind(doc_id)
+        .fetch_all(pool)
+        .await?;
+        self.delete_by_document(&[doc_id.to_string()]).await?;
+        self.bulk(&chunk_rows(&rows)).await
+    }
+
+    /// Run a write unless an earlier one failed, applying the fallback policy.
+    async fn mirror<F>(&self, what: &str, write: F) -> Result<()>
+    where
+        F: std::future::Future<Output = Result<()>>,
+    {
+        if self.failed.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+        match write.await {
+            Err(err) if self.fallback => {
+                self.failed.store(true, Ordering::Relaxed);
+                tracing::warn!(
+                    "keyword index '{}' {} failed; SQLite FTS stays current, run `ctx keyword-index rebuild` once the cluster is reachable: {:#}",
+                    self.backend,
+                    what,
+                    err
+                );
+                Ok(())
+            }
+            other => other,
+        }
+    }
+
+    /// Mirror a document after its SQLite chunks were replaced.
+    pub async fn mirror_document(&self, pool: &SqlitePool, doc_id: &str) -> Result<()> {
+        self.mirror("update", self.index_document(pool, doc_id))
+            .await
+    }
+
+    /// Remove documents deleted from SQLite.
+    pub async fn mirror_deletes(&self, ids: &[String]) -> Result<()> {
+        self.mirror("delete", async {
+            self.ensure_index().await?;
+            self.delete_by_document(ids).await
+        })
+        .await
+    }
+
+    /// Make writes since the last refresh visible to search.
+    pub async fn mirror_refresh(&self) -> Result<()> {
+        self.mirror("refresh", async {
+            self.ensure_index().await?;
+            self.refresh().await
+        })
+        .await
+    }
+
+    /// Drop and recreate the index from every SQLite chunk. Returns the
+    /// number of chunks indexed.
+    pub async fn rebuild(&self, pool: &SqlitePool) -> Result<u64> {
+        self.delete_index().await?;
+        self.create_index().await?;
+        let mut offset = 0i64;
+        let mut total = 0u64;
+        loop {
+            let rows = sqlx::query(
+                r#"
+                SELECT c.id, c.document_id, c.text, d.source, d.title, d.summary, d.updated_at
+                FROM chunks c
+                JOIN documents d ON d.id = c.document_id
+                ORDER BY c.rowid
+                LIMIT ? OFFSET ?
+                "#,
+            )
+            .bind(REBUILD_BATCH)
+            .bind(offset)
+            .fetch_all(pool)
+            .await?;
+            if rows.is_empty() {
+                break;
+            }
+            offset += rows.len() as i64;
+            total += rows.len() as u64;
+            self.bulk(&chunk_rows(&rows)).await?;
+        }
+        self.refresh().await?;
+        Ok(total)
+    }
+}
+
+fn chunk_rows(rows: &[sqlx::sqlite::SqliteRow]) -> Vec<IndexedChunk> {
+    rows.iter()
+        .map(|row| IndexedChunk {
+            chunk_id: row.get("id"),
+            document_id: row.get("document_id"),
+            source: row.get("source"),
+            title: row.get("title"),
+            summary: row.get("summary"),
+            text: row.get("text"),
+            updated_at: row.get("updated_at"),
+        })
+        .collect()
+}
+
+/// Convert a `_search` response into candidates, preferring the highlight
+/// as the snippet.
+fn parse_hits(response: &Value) -> Vec<ChunkCandidate> {
+    response["hits"]["hits"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|hit| {
+            let chunk_id = hit["_id"].as_str()?.to_string();
+            let document_id = hit["_source"]["document_id"].as_str()?.to_string();
+            let snippet = hit["highlight"]["text"][0]
+                .as_str()
+                .map(str::to_string)
+                .unwrap_or_else(|| {
+                    hit["_source"]["text"]
+                        .as_str()
+                        .unwrap_or_default()
+                        .chars()
+                        .take(SNIPPET_CHARS)
+                        .collect()
+                });
+            Some(ChunkCandidate {
+                chunk_id,
+                document_id,
+                raw_score: hit["_score"].as_f64().unwrap_or(0.0),
+                snippet,
+            })
+        })
+        .collect()
+}
+
+/// The configured cluster client, or `None` for the SQLite backend.
+pub fn configured_index(config: &Config) -> Result<Option<OpenSearchIndex>> {
+    if !config.keyword_index.is_external() {
+        return Ok(None);
+    }
+    OpenSearchIndex::from_config(&config.keyword_index, config.retrieval.bm25.chunks()).map(Some)
+}
+
+/// Store wrapper that preserves all behavior of `inner` except keyword
+/// candidates, which come from the configured cluster when there is one.
+pub struct KeywordIndexedStore<S> {
+    inner: S,
+    index: Option<OpenSearchIndex>,
+    weights: FtsWeights,
+    fuzzy: bool,
+}
+
+/// Wrap `inner` so keyword search follows `[keyword_index]`.
+pub fn with_keyword_index<S: Store>(config: &Config, inner: S) -> Result<KeywordIndexedStore<S>> {
+    Ok(KeywordIndexedStore {
+        inner,
+        index: configured_index(config)?,
+        weights: FtsWeights {
+            // Without `[summary]`, documents have no summary to match.
+            summary: if config.summary.enabled {
+                config.retrieval.weights.summary
+            } else {
+                0.0
+            },
+            ..config.retrieval.weights
+        },
+        fuzzy: config.retrieval.fuzzy,
+    })
+}
+
+#[async_trait]
+impl<S: Store> Store for KeywordIndexedStore<S> {
+    async fn upsert_document(&self, doc: &Document) -> Result<String> {
+        self.inner.upsert_document(doc).await
+    }
+
+    async fn replace_chunks(
+        &self,
+        doc_id: &str,
+        chunks: &[Chunk],
+        vectors: Option<&[Vec<f32>]>,
+    ) -> Result<()> {
+        self.inner.replace_chunks(doc_id, chunks, vectors).await
+    }
+
+    async fn upsert_embedding(
+        &self,
+        chunk_id: &str,
+        doc_id: &str,
+        vector: &[f32],
+        model: &str,
+        dims: usize,
+        content_hash: &str,
+    ) -> Result<()> {
+        self.inner
+            .upsert_embedding(chunk_id, doc_id, vector, model, dims, content_hash)
+            .await
+    }
+
+    async fn get_document(&self, id: &str) -> Result<Option<DocumentResponse>> {
+        self.inner.get_document(id).await
+    }
+
+    async fn get_document_metadata(&self, id: &str) -> Result<Option<DocumentMetadata>> {
+        self.inner.get_document_metadata(id).await
+    }
+
+    async fn get_chunk_metadata(
+        &self,
+        chunk_id: &str,
+    ) -> Result<Option<serde_json::Map<String, serde_json::Value>>> {
+        self.inner.get_chunk_metadata(chunk_id).await
+    }
+
+    async fn keyword_search(
+        &self,
+        query: &str,
+        limit: i64,
+        source: Option<&str>,
+        since: Option<&str>,
+    ) -> Result<Vec<ChunkCandidate>> {
+        let Some(index) = &self.index else {
+            return self.inner.keyword_search(query, limit, source, since).await;
+        };
+        match index.search(query, limit, self.weights, self.fuzzy).await {
+            Ok(candidates) => Ok(candidates),
+            Err(err) if index.fallback => {
+                tracing::warn!(
+                    "keyword index '{}' failed; falling back to SQLite FTS: {:#}",
+                    index.backend,
+                    err
+                );
+                self.inner.keyword_search(query, limit, source, since).await
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    async fn vector_search(
+        &self,
+        query_vec: &[f32],
+        limit: i64,
+        source: Option<&str>,
+        since: Option<&str>,
+    ) -> Result<Vec<ChunkCandidate>> {
+        self.inner
+            .vector_search(query_vec, limit, source, since)
+            .await
+    }
+
+    async fn unembedded_chunks(&self, chunk_ids: &[String]) -> Result<HashSet<String>> {
+        self.inner.unembedded_chunks(chunk_ids).await
+    }
+
+    async fn chunk_indexes(&self, chunk_ids: &[String]) -> Result<HashMap<String, i64>> {
+        self.inner.chunk_indexes(chunk_ids).await
+    }
+}
+
+/// Backend, location, and chunk counts reported by `ctx keyword-index status`.
+#[derive(Debug, Clone)]
+pub struct KeywordIndexStatus {
+    pub backend: String,
+    pub url: Option<String>,
+    pub index: Option<String>,
+    pub sqlite_chunks: u64,
+    /// Chunks in the cluster; `None` for the SQLite backend or when the
+    /// cluster could not be reached.
+    pub indexed_chunks: Option<u64>,
+    pub message: Option<String>,
+}
+
+impl KeywordIndexStatus {
+    /// Whether the cluster holds exactly the SQLite chunks.
+    pub fn fresh(&self) -> bool {
+        self.indexed_chunks.is_none_or(|n| n == self.sqlite_chunks)
+    }
+}
+
+async fn sqlite_chunk_count(pool: &SqlitePool) -> Result<u64> {
+    let n: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM chunks")
+        .fetch_one(pool)
+        .await?;
+    Ok(n as u64)
+}
+
+fn status_for(config: &Config, sqlite_chunks: u64) -> KeywordIndexStatus {
+    let external = config.keyword_index.is_external();
+    KeywordIndexStatus {
+        backend: config.keyword_index.backend.clone(),
+        url: config.keyword_index.url.clone().filter(|_| external),
+        index: external.then(|| config.keyword_index.index.clone()),
+        sqlite_chunks,
+        indexed_chunks: None,
+        message: None,
+    }
+}
+
+/// Compare the cluster's chunk count with SQLite.
+pub async fn keyword_index_status(config: &Config) -> Result<KeywordIndexStatus> {
+    let pool = db::connect(config).await?;
+    let mut status = status_for(config, sqlite_chunk_count(&pool).await?);
+    pool.close().await;
+
+    match configured_index(config)? {
+        None => status.message = Some("keyword search uses SQLite FTS5".to_string()),
+        Some(index) => match index.count().await {
+            Ok(n) => status.indexed_chunks = Some(n),
+            Err(err) => status.message = Some(format!("{:#}", err)),
+        },
+    }
+    Ok(status)
+}
+
+/// Recreate the external index from SQLite for `ctx keyword-index rebuild`.
+pub async fn rebuild_configured_keyword_index(config: &Config) -> Result<KeywordIndexStatus> {
+    let Some(index) = configured_index(config)? else {
+        bail!("keyword_index.backend is 'sqlite'; FTS5 is maintained by sync and needs no rebuild");
+    };
+    let pool = db::connect(config).await?;
+    let indexed = index.rebuild(&pool).await;
+    let mut status = status_for(config, sqlite_chunk_count(&pool).await?);
+    pool.close().await;
+    status.indexed_chunks = Some(indexed?);
+    Ok(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_hits_prefers_highlight_and_falls_back_to_text() {
+        let response = json!({
+            "hits": { "hits": [
+                {
+                    "_id": "c1",
+                    "_score": 3.5,
+                    "_source": { "document_id": "d1", "text": "full text" },
+                    "highlight": { "text": ["the >>>deploy<<< step"] }
+                },
+                {
+                    "_id": "c2",
+                    "_score": 1.25,
+                    "_source": { "document_id": "d2", "text": "x".repeat(300) }
+                },
+                { "_id": "c3", "_source": {} }
+            ]}
+        });
+        let hits = parse_hits(&response);
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].chunk_id, "c1");
+        assert_eq!(hits[0].document_id, "d1");
+        assert_eq!(hits[0].raw_score, 3.5);
+        assert_eq!(hits[0].snippet, "the >>>deploy<<< step");
+        assert_eq!(hits[1].snippet.len(), SNIPPET_CHARS);
+    }
+}
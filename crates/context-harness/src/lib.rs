@@ -73,23 +73,83 @@
 //! | [`connector_fs`] | Filesystem connector: walk local directories |
 //! | [`connector_git`] | Git connector: clone/pull repos with per-file metadata |
 //! | [`connector_s3`] | S3 connector: list and download objects with SigV4 signing |
+//! | [`connector_sharepoint`] | SharePoint / OneDrive connector via Microsoft Graph delta queries |
+//! | [`connector_zendesk`] | Zendesk connector: tickets with comment threads and help-center articles via incremental exports |
+//! | [`connector_stackoverflow`] | Stack Overflow connector: questions with accepted and top answers, from Teams or public sites, synced by activity date |
+//! | [`archive`] | `.zip` / `.tar` / `.tar.gz` reading for archive ingestion (filesystem, S3) |
 //! | [`connector_script`] | Lua scripted connectors: custom data sources via Lua 5.4 scripts |
+//! | [`connector_scaffold`] | `ctx connector init --lang rust`: native connector module scaffolding |
 //! | [`lua_runtime`] | Shared Lua 5.4 VM runtime: sandboxing, host APIs, value conversions |
+//! | [`lua_spec`] | Spec runner for Lua tools and agents: `describe`/`it`, HTTP mocks, JUnit XML |
+//! | [`script_cache`] | Per-script key/value cache with TTL behind the Lua `cache` module |
 //! | [`tool_script`] | Lua MCP tool extensions: load, validate, execute Lua tool scripts |
+//! | [`audit`] | Tool invocation audit log (`ctx tool log`) |
 //! | [`traits`] | Extension traits: `Connector`, `Tool`, `ToolContext`, registries |
 //! | [`agents`] | Agent system: `Agent` trait, `AgentPrompt`, `AgentRegistry`, `TomlAgent` |
 //! | [`agent_script`] | Lua scripted agents: load, resolve, scaffold, test |
-//! | [`chunk`] | Paragraph-boundary text chunker |
+//! | [`agent_run`] | `ctx agent run`: chat loop with tool calling against OpenAI/Ollama |
+//! | [`chunk`] | Paragraph- and line-boundary text chunker, overlap, and fingerprints |
+//! | [`contextualize`] | Contextual chunk headers prepended before embedding (`[chunking.contextualize]`) |
+//! | [`normalize`] | Markdown, front matter, emoji, and boilerplate cleanup before chunking (`[chunking.normalize]`) |
+//! | [`summarize`] | Ingest-time summaries of long documents, indexed and returned with results (`[summary]`) |
+//! | [`classify`] | Ingest-time document types (runbook, ADR, API reference, meeting notes) stored as `doc_type` (`[classify]`) |
+//! | [`text`] | Character-safe truncation shared by the contextualize, summarize, and classify passes |
+//! | [`stitch`] | Stitched documents combining a connector's small files, with per-file chunk citations |
+//! | [`content_dedup`] | Cross-connector deduplication by content hash (`[sync] content_dedup`) |
 //! | [`embedding`] | Embedding provider trait, OpenAI implementation, vector utilities |
+//! | [`aws_sigv4`] | AWS SigV4 request signing and credentials (S3, Bedrock) |
 //! | [`embed_cmd`] | Embedding CLI commands: `pending` and `rebuild` |
-//! | [`export`] | JSON export for static site search (`ctx export`) |
+//! | [`doc_vectors`] | Document-level vectors that route semantic search to the closest documents |
+//! | [`export`] | JSON export for static site search and Markdown export (`ctx export`) |
 //! | [`stats`] | Database statistics: document, chunk, and embedding counts |
+//! | [`storage`] | Index size budget, eviction after sync, and `ctx db size` |
+//! | [`gc`] | Orphan cleanup, FTS repair, and vacuum (`ctx db gc`) |
+//! | [`warmup`] | `[db] warm_up`: read index and vector pages before `ctx serve mcp` listens |
 //! | [`ingest`] | Ingestion pipeline: connector → normalize → chunk → embed → store |
+//! | [`add`] | `ctx add`: index one file or stdin note through the sync pipeline |
+//! | [`memory`] | The `memory.append` tool: conversation transcripts indexed under a `memory` source, with dedup and retention (`[memory]`) |
+//! | [`bench`] | `ctx bench ingest`: per-stage timing and throughput on a connector sample |
+//! | [`ui`] | `ctx ui`: terminal dashboard with live search, preview, source status, and job progress |
+//! | [`watch`] | `ctx watch`: sync filesystem connectors as files change, deleting removed files |
+//! | [`jobs`] | Registry of running syncs and embeds, paused, resumed, and cancelled by `ctx jobs` and `/admin/jobs` |
+//! | [`redact`] | Ingest-time PII and credential redaction |
+//! | [`scrub`] | Secret masking in logs, error messages, and server error responses |
+//! | [`raw_items`] | Source items kept as delivered for `ctx reprocess` (`[sync] keep_raw`) |
+//! | [`notify`] | Webhook notifications (JSON, Slack) when sync and embed runs finish |
 //! | [`search`] | Keyword, semantic, and hybrid search with score normalization |
+//! | [`assemble`] | Cited, token-budgeted context blocks from search results (`ctx search --context`) |
+//! | [`similar`] | Documents nearest to a given document (`ctx similar`, `similar` tool) |
+//! | [`curate`] | Search pins, boosts, and blocklist (`ctx curate`) |
+//! | [`popularity`] | Retrieval-count popularity boost and `ctx stats --popular` |
+//! | [`keyword_index`] | Optional OpenSearch/Elasticsearch keyword backend (vectors stay local) |
+//! | [`bm25`] | BM25 rescoring with configured `k1` and `b` (`[retrieval.bm25]`) |
+//! | `grpc` | gRPC interface for the server, generated from `proto/context_harness.proto` (`--features grpc`) |
+//! | [`federation`] | Federated search across other databases and remote ctx servers |
+//! | [`query_cache`] | SQLite cache of query embeddings with TTL and hit metrics |
+//! | [`search_cache`] | Server-side LRU of `search` responses, invalidated by sync/embed |
+//! | [`search_debug`] | `ctx search --debug-miss`: why an expected document is missing from the results |
+//! | [`search_repl`] | `ctx search --repl`: interactive search session with history and slash commands |
+//! | [`init_wizard`] | `ctx init --interactive`: setup wizard that writes a validated config |
+//! | [`titles`] | Title and heading index for document-level lookups (`ctx search --in titles`) |
 //! | [`get`] | Document retrieval by UUID |
+//! | [`rm`] | `ctx rm`: delete documents by UUID or by source and date, with confirmation and audit |
 //! | [`sources`] | Connector health and status listing |
+//! | [`sync_failures`] | Per-item sync failures, the partial-success report, and `ctx sync --retry-failed` |
+//! | [`sync_status`] | Per-source sync outcomes and staleness thresholds (`ctx sources`) |
+//! | [`capabilities`] | Search modes, filters, query syntax, and sources an index supports (`capabilities` tool) |
+//! | [`hooks`] | Git post-commit/post-merge hooks for targeted local sync |
+//! | [`secrets`] | `secret://` references resolved from env, file, or OS keychain |
+//! | [`self_update`] | `ctx self update`: verified in-place upgrades from GitHub releases, and the startup version check (`[update]`) |
 //! | [`server`] | MCP-compatible HTTP server (Axum) with CORS |
+//! | [`auth`] | Server API keys and per-key source scopes (`[server.auth]`) |
+//! | [`limits`] | Global and per-tool concurrency limits for server tool calls (`[server.limits]`) |
+//! | [`response_limits`] | Body, chunk, and snippet size limits of `get` and `search` responses, with ranged follow-ups (`[server.responses]`) |
+//! | [`reload`] | Runtime reload (`/admin/reload`, file watching) of tools, agents, and connectors |
 //! | [`db`] | SQLite connection pool with WAL mode |
+//! | [`logging`] | Structured `tracing` diagnostics: `--log-level` filters, text/JSON output |
+//! | [`telemetry`] | OpenTelemetry trace export over OTLP/HTTP (`[telemetry]`) |
+//! | [`download`] | Bandwidth limiting and resumable-download checkpoints for S3/Git |
+//! | [`http_retry`] | `429`/`503` retries honouring `Retry-After` for the SharePoint, Zendesk, and Stack Overflow connectors |
 //! | [`migrate`] | Database schema migrations (idempotent) |
 //!
 //! ## Configuration
@@ -98,37 +158,98 @@
 //! See [`config`] for all available options and [`config::load_config`] for
 //! validation rules.
 
+pub mod add;
+pub mod agent_run;
 pub mod agent_script;
 pub mod agents;
 pub mod app_store;
+pub mod archive;
+pub mod assemble;
+pub mod audit;
+pub mod auth;
+pub mod aws_sigv4;
+pub mod bench;
+pub mod bm25;
+pub mod capabilities;
 pub mod chunk;
+pub mod classify;
 pub mod config;
 pub mod connector_fs;
 pub mod connector_git;
 pub mod connector_s3;
+pub mod connector_scaffold;
 pub mod connector_script;
+pub mod connector_sharepoint;
+pub mod connector_stackoverflow;
+pub mod connector_zendesk;
+pub mod content_dedup;
+pub mod contextualize;
 pub mod ctx_dirs;
+pub mod curate;
 pub mod db;
+pub mod doc_vectors;
+pub mod download;
 pub mod embed_cmd;
 pub mod embedding;
 pub mod export;
 pub mod extract;
+pub mod federation;
+pub mod gc;
 pub mod get;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod hooks;
+pub mod http_retry;
 pub mod ingest;
+pub mod init_wizard;
+pub mod jobs;
+pub mod keyword_index;
+pub mod limits;
+pub mod logging;
 pub mod lua_runtime;
+pub mod lua_spec;
 pub mod mcp;
+pub mod memory;
 pub mod migrate;
 pub mod models;
+pub mod normalize;
+pub mod notify;
+pub mod popularity;
 pub mod progress;
+pub mod query_cache;
+pub mod raw_items;
+pub mod redact;
 pub mod registry;
+pub mod reload;
+pub mod response_limits;
+pub mod rm;
+pub mod script_cache;
+pub mod scrub;
 pub mod search;
+pub mod search_cache;
+pub mod search_debug;
+pub mod search_repl;
+pub mod secrets;
+pub mod self_update;
 pub mod server;
+pub mod similar;
 pub mod sources;
 pub mod sqlite_store;
 pub mod stats;
+pub mod stitch;
+pub mod storage;
+pub mod summarize;
+pub mod sync_failures;
+pub mod sync_status;
+pub mod telemetry;
+pub mod text;
+pub mod titles;
 pub mod tool_script;
 pub mod traits;
+pub mod ui;
 pub mod vector_index;
+pub mod warmup;
+pub mod watch;
 
 pub use agents::{Agent, AgentPrompt, AgentRegistry, TomlAgent};
 pub use context_harness_core::store;
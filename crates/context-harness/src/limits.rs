@@ -0,0 +1,255 @@
+//! Tool call concurrency limits for the MCP server.
+//!
+//! Every tool call served over `POST /tools/{name}`, MCP (`/mcp`, `/rpc`),
+//! or gRPC takes a slot from the global limit and from its tool's own
+//! limit before it runs. Lua tools each occupy a blocking thread while they
+//! run, so without limits a burst of calls can exhaust the blocking pool
+//! and starve everything else.
+//!
+//! A call that cannot get its slots at once waits in a bounded queue. When
+//! the queue is full, or the wait exceeds `queue_timeout_ms`, the call is
+//! rejected: HTTP returns `429` with code `rate_limited` and a
+//! `Retry-After` header, MCP returns an error, and gRPC returns
+//! `RESOURCE_EXHAUSTED`.
+//!
+//! # Configuration
+//!
+//! ```toml
+//! [server.limits]
+//! max_concurrent = 32     # all tools; 0 = unlimited
+//! max_queued = 64
+//! queue_timeout_ms = 5000
+//! retry_after_secs = 1
+//!
+//! [server.limits.tools]
+//! summarize = 2
+//! ```
+//!
+//! Queue depth and rejections are exported on `GET /metrics`.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::config::ServerLimitsConfig;
+
+/// Error code of rejected calls in HTTP error bodies.
+pub const RATE_LIMITED: &str = "rate_limited";
+
+/// A call rejected because its slots stayed taken.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Saturated {
+    /// Name of the tool whose call was rejected.
+    pub tool: String,
+    /// `"queue full"` or `"timed out after <n>ms"`.
+    pub reason: String,
+    /// Seconds the client should wait before retrying.
+    pub retry_after_secs: u64,
+}
+
+impl fmt::Display for Saturated {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: server is at its tool call limit ({}); retry after {}s",
+            self.tool, self.reason, self.retry_after_secs
+        )
+    }
+}
+
+impl std::error::Error for Saturated {}
+
+/// Slots held by a running tool call; released on drop.
+#[derive(Debug)]
+pub struct ToolPermit {
+    _tool: Option<OwnedSemaphorePermit>,
+    _global: Option<OwnedSemaphorePermit>,
+}
+
+/// Limiter counters for `/metrics`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LimiterStats {
+    pub queued: usize,
+    pub rejected: u64,
+}
+
+/// Global and per-tool concurrency limits with a bounded wait queue.
+pub struct ToolLimiter {
+    global: Option<Arc<Semaphore>>,
+    tools: HashMap<String, Arc<Semaphore>>,
+    max_queued: usize,
+    queue_timeout: Duration,
+    retry_after_secs: u64,
+    queued: AtomicUsize,
+    rejected: AtomicU64,
+}
+
+impl ToolLimiter {
+    /// Build the limiter from `[server.limits]`, or `None` when no limit is set.
+    pub fn from_config(config: &ServerLimitsConfig) -> Option<Self> {
+        if !config.is_enabled() {
+            return None;
+        }
+        Some(Self {
+            global: (config.max_concurrent > 0)
+                .then(|| Arc::new(Semaphore::new(config.max_concurrent))),
+            tools: config
+                .tools
+                .iter()
+                .map(|(name, &n)| (name.clone(), Arc::new(Semaphore::new(n))))
+                .collect(),
+            max_queued: config.max_queued,
+            queue_timeout: Duration::from_millis(config.queue_timeout_ms),
+            retry_after_secs: config.retry_after_secs,
+            queued: AtomicUsize::new(0),
+            rejected: AtomicU64::new(0),
+        })
+    }
+
+    /// Take the slots for one call of `tool`, waiting in the queue if
+    /// they are taken.
+    ///
+    /// The tool's own slot is taken before the global one, so calls
+    /// waiting on a busy tool don't hold global slots other tools could use.
+    pub async fn acquire(&self, tool: &str) -> Result<ToolPermit, Saturated> {
+        let tool_sem = self.tools.get(tool);
+        let fast_tool = match tool_sem {
+            Some(sem) => match sem.clone().try_acquire_owned() {
+                Ok(permit) => Some(Some(permit)),
+                Err(_) => None,
+            },
+            None => Some(None),
+        };
+        if let Some(tool_permit) = fast_tool {
+            let fast_global = match &self.global {
+                Some(sem) => sem.clone().try_acquire_owned().ok().map(Some),
+                None => Some(None),
+            };
+            if let Some(global_permit) = fast_global {
+                return Ok(ToolPermit {
+                    _tool: tool_permit,
+                    _global: global_permit,
+                });
+            }
+        }
+
+        if self.queued.fetch_add(1, Ordering::SeqCst) >= self.max_queued {
+            self.queued.fetch_sub(1, Ordering::SeqCst);
+            return Err(self.reject(tool, "queue full".to_string()));
+        }
+        let waited = tokio::time::timeout(self.queue_timeout, async {
+            let tool_permit = match tool_sem {
+                Some(sem) => Some(sem.clone().acquire_owned().await.ok()?),
+                None => None,
+            };
+            let global_permit = match &self.global {
+                Some(sem) => Some(sem.clone().acquire_owned().await.ok()?),
+                None => None,
+            };
+            Some(ToolPermit {
+                _tool: tool_permit,
+                _global: global_permit,
+            })
+        })
+        .await;
+        self.queued.fetch_sub(1, Ordering::SeqCst);
+
+        match waited {
+            Ok(Some(permit)) => Ok(permit),
+            _ => Err(self.reject(
+                tool,
+                format!("timed out after {}ms", self.queue_timeout.as_millis()),
+            )),
+        }
+    }
+
+    fn reject(&self, tool: &str, reason: String) -> Saturated {
+        self.rejected.fetch_add(1, Ordering::Relaxed);
+        tracing::warn!(
+            tool,
+            reason = reason.as_str(),
+            "tool call rejected by limits"
+        );
+        Saturated {
+            tool: tool.to_string(),
+            reason,
+            retry_after_secs: self.retry_after_secs,
+        }
+    }
+
+    /// Current queue depth and rejections since start.
+    pub fn stats(&self) -> LimiterStats {
+        LimiterStats {
+            queued: self.queued.load(Ordering::SeqCst),
+            rejected: self.rejected.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn limiter(max_concurrent: usize, tools: &[(&str, usize)], max_queued: usize) -> ToolLimiter {
+        ToolLimiter::from_config(&ServerLimitsConfig {
+            max_concurrent,
+            tools: tools
+                .iter()
+                .map(|(name, n)| (name.to_string(), *n))
+                .collect::<BTreeMap<_, _>>(),
+            max_queued,
+            queue_timeout_ms: 50,
+            retry_after_secs: 2,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn no_limits_means_no_limiter() {
+        assert!(ToolLimiter::from_config(&ServerLimitsConfig::default()).is_none());
+    }
+
+    #[tokio::test]
+    async fn per_tool_limit_times_out_queued_calls() {
+        let limiter = limiter(0, &[("summarize", 1)], 4);
+        let held = limiter.acquire("summarize").await.unwrap();
+
+        let err = limiter.acquire("summarize").await.unwrap_err();
+        assert_eq!(err.reason, "timed out after 50ms");
+        assert_eq!(err.retry_after_secs, 2);
+        // Other tools are not affected.
+        limiter.acquire("search").await.unwrap();
+
+        drop(held);
+        limiter.acquire("summarize").await.unwrap();
+        assert_eq!(limiter.stats().rejected, 1);
+        assert_eq!(limiter.stats().queued, 0);
+    }
+
+    #[tokio::test]
+    async fn full_queue_rejects_immediately() {
+        let limiter = limiter(1, &[], 0);
+        let _held = limiter.acquire("search").await.unwrap();
+        let err = limiter.acquire("search").await.unwrap_err();
+        assert_eq!(err.reason, "queue full");
+    }
+
+    #[tokio::test]
+    async fn queued_call_runs_when_a_slot_frees_up() {
+        let limiter = Arc::new(limiter(1, &[], 4));
+        let held = limiter.acquire("search").await.unwrap();
+        let waiter = {
+            let limiter = limiter.clone();
+            tokio::spawn(async move { limiter.acquire("get").await.map(|_| ()) })
+        };
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(limiter.stats().queued, 1);
+        drop(held);
+        assert!(waiter.await.unwrap().is_ok());
+    }
+}
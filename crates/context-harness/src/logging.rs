@@ -0,0 +1,152 @@
+//! Structured diagnostics via `tracing`.
+//!
+//! Warnings and progress from connectors, ingestion, search, the HTTP
+//! server, and Lua scripts (`log.info(...)` etc.) are emitted as `tracing`
+//! events on stderr. Command output — search results, sync summaries,
+//! documents — is still printed to stdout and is not affected.
+//!
+//! # Verbosity
+//!
+//! `--log-level` (or the `CTX_LOG` environment variable) accepts either a
+//! bare level or a comma-separated list of filter directives:
+//!
+//! ```text
+//! ctx --log-level debug sync all                  # ctx and Lua at debug, dependencies at warn
+//! ctx --log-level info,ctx::connector_git=debug sync git
+//! CTX_LOG=warn,lua=debug ctx serve mcp
+//! ```
+//!
+//! A bare level applies to ctx's own modules (`ctx::<module>` in the CLI,
+//! `context_harness::<module>` when embedded) and to Lua scripts (`lua`);
+//! dependencies stay at `warn` or quieter. Directives are passed through to
+//! [`EnvFilter`] unchanged. The default is `info`.
+//!
+//! # Formats
+//!
+//! - `text` (default) — one compact line per event: `WARN scan failed: …`.
+//! - `json` — one JSON object per line with timestamp, level, target,
+//!   fields, and the enclosing span (e.g. the HTTP request ID), for log
+//!   aggregation systems.
+//!
+//! Both formats mask configured secrets and credential patterns (see
+//! [`crate::scrub`]).
+//!
+//! # Traces
+//!
+//! With `[telemetry]` enabled, spans are also exported over OTLP, filtered
+//! independently of `--log-level` — see [`crate::telemetry`].
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::fmt;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer};
+
+use crate::config::TelemetryConfig;
+use crate::scrub;
+use crate::telemetry::{self, Telemetry};
+
+/// Environment variable read when `--log-level` is not given.
+pub const LOG_ENV: &str = "CTX_LOG";
+
+/// Target used for messages logged by Lua scripts.
+pub const LUA_TARGET: &str = "lua";
+
+/// Log line format for stderr.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum LogFormat {
+    /// Compact human-readable lines.
+    #[default]
+    Text,
+    /// Newline-delimited JSON objects.
+    Json,
+}
+
+/// Install the global subscriber. Later calls are no-ops.
+///
+/// `level` is the `--log-level` value; without it, `CTX_LOG` is used, and
+/// without that, `info`. With `[telemetry]` enabled, spans are also
+/// exported; keep the returned handle alive until exit so queued spans
+/// are flushed.
+pub fn init(
+    level: Option<&str>,
+    format: LogFormat,
+    telemetry: Option<&TelemetryConfig>,
+) -> Result<Option<Telemetry>> {
+    let directives = match level {
+        Some(level) => level.to_string(),
+        None => std::env::var(LOG_ENV).unwrap_or_else(|_| "info".to_string()),
+    };
+    let filter = build_filter(&directives)?;
+
+    let (export, handle) = match telemetry.filter(|t| t.enabled) {
+        Some(config) => {
+            let (layer, handle) = telemetry::layer(config)?;
+            (Some(layer), Some(handle))
+        }
+        None => (None, None),
+    };
+    let stderr = match format {
+        LogFormat::Text => fmt::layer()
+            .with_writer(|| scrub::Writer(std::io::stderr()))
+            .with_ansi(atty::is(atty::Stream::Stderr))
+            .without_time()
+            .with_target(false)
+            .boxed(),
+        LogFormat::Json => fmt::layer()
+            .json()
+            .with_writer(|| scrub::Writer(std::io::stderr()))
+            .with_current_span(true)
+            .with_span_list(false)
+            .boxed(),
+    };
+    let installed = tracing_subscriber::registry()
+        .with(export)
+        .with(stderr.with_filter(filter))
+        .try_init();
+    Ok(installed.ok().and(handle))
+}
+
+/// Parse a bare level or `EnvFilter` directives.
+fn build_filter(directives: &str) -> Result<EnvFilter> {
+    let directives = directives.trim();
+    let expanded = match directives.parse::<LevelFilter>() {
+        Ok(level) => {
+            let deps = level.min(LevelFilter::WARN);
+            format!(
+                "{deps},ctx={level},context_harness={level},{LUA_TARGET}={level}",
+                deps = deps,
+                level = level
+            )
+        }
+        Err(_) => directives.to_string(),
+    };
+    EnvFilter::try_new(&expanded).with_context(|| format!("Invalid log level '{}'", directives))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bare_level_keeps_dependencies_quiet() {
+        let filter = build_filter("debug").unwrap().to_string();
+        assert!(filter.contains("ctx=debug"), "{}", filter);
+        assert!(filter.contains("lua=debug"), "{}", filter);
+        assert!(filter.contains("warn"), "{}", filter);
+
+        let filter = build_filter("error").unwrap().to_string();
+        assert!(!filter.contains("warn"), "{}", filter);
+    }
+
+    #[test]
+    fn directives_pass_through_and_garbage_errors() {
+        let filter = build_filter("info,ctx::connector_git=trace")
+            .unwrap()
+            .to_string();
+        assert!(filter.contains("ctx::connector_git=trace"), "{}", filter);
+        assert!(build_filter("info,=[bad").is_err());
+    }
+}
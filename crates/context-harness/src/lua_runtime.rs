@@ -9,28 +9,35 @@
 //!
 //! | Module | Functions |
 //! |--------|-----------|
-//! | `http` | `get`, `post`, `put` |
+//! | `http` | `get`, `post`, `put`, `parallel` |
 //! | `json` | `parse`, `encode` |
-//! | `env` | `get` |
+//! | `env` | `get` (limited by `env_allowlist`; see [`EnvAccess`]) |
+//! | `secrets` | `get` (env, secrets file, OS keychain; see [`crate::secrets`]), limited like `env.get` |
 //! | `log` | `info`, `warn`, `error`, `debug` |
 //! | `fs` | `read`, `list` (sandboxed to script directory) |
 //! | `base64` | `encode`, `decode` |
 //! | `crypto` | `sha256`, `hmac_sha256` |
 //! | `sleep` | `sleep(seconds)` |
+//! | `cache` | `get`, `set` (per-script SQLite cache with TTL; see [`crate::script_cache`]) |
 //!
 //! # Sandboxing
 //!
 //! Dangerous Lua standard libraries (`os`, `io`, `debug`, `loadfile`, `dofile`)
 //! are removed. Filesystem access is restricted to a configurable sandbox root
-//! directory.
+//! directory. `env.get` sees only the variables in the script's
+//! `env_allowlist`, and `secrets.get` only the secrets named there; scripts
+//! installed from a registry see none without one.
 
 use globset::Glob;
 use hmac::{Hmac, Mac};
 use mlua::prelude::*;
 use sha2::{Digest, Sha256};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::Duration;
 
+use crate::script_cache::ScriptCache;
+
 // ═══════════════════════════════════════════════════════════════════════
 // Public helpers
 // ═══════════════════════════════════════════════════════════════════════
@@ -43,22 +50,28 @@ use std::time::Duration;
 /// # Arguments
 ///
 /// * `lua` — the Lua VM instance to configure.
-/// * `script_name` — logical name used for log prefixes (e.g. `"script:jira"`).
+/// * `script_name` — logical name logged in the `script` field (e.g. `"script:jira"`).
 /// * `sandbox_root` — directory that `fs.read` / `fs.list` are confined to.
+/// * `db_path` — SQLite database holding the `cache` module's entries.
+/// * `env` — environment variables `env.get` and secrets `secrets.get` may read.
 pub(crate) fn register_all_host_apis(
     lua: &Lua,
     script_name: &str,
     sandbox_root: &Path,
+    db_path: &Path,
+    env: &EnvAccess,
 ) -> LuaResult<()> {
     sandbox_globals(lua)?;
     register_http_api(lua)?;
     register_json_api(lua)?;
-    register_env_api(lua)?;
+    register_env_api(lua, script_name, env.clone())?;
+    register_secrets_api(lua, script_name, env.clone())?;
     register_log_api(lua, script_name)?;
     register_fs_api(lua, sandbox_root)?;
     register_base64_api(lua)?;
     register_crypto_api(lua)?;
     register_sleep(lua)?;
+    register_cache_api(lua, script_name, db_path)?;
     Ok(())
 }
 
@@ -121,93 +134,240 @@ fn register_http_api(lua: &Lua) -> LuaResult<()> {
         )?,
     )?;
 
+    // http.parallel(requests, opts?) → responses
+    let c = client.clone();
+    http.set(
+        "parallel",
+        lua.create_function(move |lua, (requests, opts): (LuaTable, Option<LuaTable>)| {
+            let concurrency = opts
+                .as_ref()
+                .and_then(|o| o.get::<usize>("concurrency").ok())
+                .unwrap_or(DEFAULT_HTTP_CONCURRENCY)
+                .clamp(1, MAX_HTTP_CONCURRENCY);
+
+            let mut specs = Vec::new();
+            for entry in requests.sequence_values::<LuaTable>() {
+                specs.push(request_spec_from_lua(&entry?)?);
+            }
+
+            let results = execute_parallel(&c, &specs, concurrency);
+
+            let out = lua.create_table()?;
+            for (i, result) in results.into_iter().enumerate() {
+                let table = match result {
+                    Ok(resp) => response_to_lua(lua, resp)?,
+                    Err(e) => {
+                        let t = lua.create_table()?;
+                        t.set("status", 0)?;
+                        t.set("ok", false)?;
+                        t.set("error", e)?;
+                        t
+                    }
+                };
+                out.set(i + 1, table)?;
+            }
+            Ok(out)
+        })?,
+    )?;
+
     lua.globals().set("http", http)?;
     Ok(())
 }
 
-/// Execute an HTTP request and return a Lua table with the response.
-fn do_http_request(
-    lua: &Lua,
-    client: &reqwest::blocking::Client,
+/// Default number of in-flight requests for `http.parallel`.
+const DEFAULT_HTTP_CONCURRENCY: usize = 8;
+
+/// Upper bound on `http.parallel` concurrency, regardless of script options.
+const MAX_HTTP_CONCURRENCY: usize = 32;
+
+/// A fully-resolved HTTP request, detached from the Lua VM so it can be
+/// executed on worker threads.
+struct HttpRequestSpec {
+    method: String,
+    url: String,
+    body: Option<String>,
+    headers: Vec<(String, String)>,
+    params: Vec<(String, String)>,
+    timeout: Option<Duration>,
+}
+
+/// A completed HTTP response, converted to a Lua table by [`response_to_lua`].
+struct HttpResponseData {
+    status: u16,
+    ok: bool,
+    headers: Vec<(String, String)>,
+    body: String,
+}
+
+/// Build a request spec from the `opts` table shared by `get`/`post`/`put`.
+fn request_spec(
     method: &str,
     url: &str,
     body: Option<&str>,
-    opts: Option<LuaTable>,
-) -> LuaResult<LuaTable> {
-    let mut builder = match method {
-        "GET" => client.get(url),
-        "POST" => client.post(url),
-        "PUT" => client.put(url),
-        "DELETE" => client.delete(url),
-        "PATCH" => client.patch(url),
-        _ => {
-            return Err(mlua::Error::external(anyhow::anyhow!(
-                "unsupported HTTP method: {}",
-                method
-            )))
-        }
+    opts: Option<&LuaTable>,
+) -> LuaResult<HttpRequestSpec> {
+    let mut spec = HttpRequestSpec {
+        method: method.to_uppercase(),
+        url: url.to_string(),
+        body: body.map(|b| b.to_string()),
+        headers: Vec::new(),
+        params: Vec::new(),
+        timeout: None,
     };
 
-    if let Some(ref opts) = opts {
+    if let Some(opts) = opts {
         // Headers
         if let Ok(headers) = opts.get::<LuaTable>("headers") {
             for pair in headers.pairs::<String, String>() {
-                let (k, v) = pair?;
-                builder = builder.header(k, v);
+                spec.headers.push(pair?);
             }
         }
 
         // Query parameters
         if let Ok(params) = opts.get::<LuaTable>("params") {
-            let mut param_vec: Vec<(String, String)> = Vec::new();
             for pair in params.pairs::<String, String>() {
-                let (k, v) = pair?;
-                param_vec.push((k, v));
+                spec.params.push(pair?);
             }
-            builder = builder.query(&param_vec);
         }
 
         // Custom timeout
         if let Ok(timeout) = opts.get::<f64>("timeout") {
-            builder = builder.timeout(Duration::from_secs_f64(timeout));
+            spec.timeout = Some(Duration::from_secs_f64(timeout));
         }
     }
 
+    Ok(spec)
+}
+
+/// Build a request spec from an `http.parallel` entry:
+/// `{ url = "...", method = "GET", body = "...", headers = {...}, params = {...}, timeout = 10 }`.
+fn request_spec_from_lua(entry: &LuaTable) -> LuaResult<HttpRequestSpec> {
+    let url: String = entry.get("url").map_err(|_| {
+        mlua::Error::external(anyhow::anyhow!("http.parallel: each request needs a url"))
+    })?;
+    let method: String = entry
+        .get::<Option<String>>("method")?
+        .unwrap_or_else(|| "GET".to_string());
+    let body: Option<String> = entry.get("body")?;
+    request_spec(&method, &url, body.as_deref(), Some(entry))
+}
+
+/// Execute a request spec with the blocking client.
+fn execute_request(
+    client: &reqwest::blocking::Client,
+    spec: &HttpRequestSpec,
+) -> anyhow::Result<HttpResponseData> {
+    let mut builder = match spec.method.as_str() {
+        "GET" => client.get(&spec.url),
+        "POST" => client.post(&spec.url),
+        "PUT" => client.put(&spec.url),
+        "DELETE" => client.delete(&spec.url),
+        "PATCH" => client.patch(&spec.url),
+        other => anyhow::bail!("unsupported HTTP method: {}", other),
+    };
+
+    for (k, v) in &spec.headers {
+        builder = builder.header(k, v);
+    }
+    if !spec.params.is_empty() {
+        builder = builder.query(&spec.params);
+    }
+    if let Some(timeout) = spec.timeout {
+        builder = builder.timeout(timeout);
+    }
+
     // Request body
-    if let Some(body) = body {
-        builder = builder.body(body.to_string());
+    if let Some(ref body) = spec.body {
+        builder = builder.body(body.clone());
     }
 
     // Execute the request
-    let response = builder.send().map_err(|e| {
-        mlua::Error::external(anyhow::anyhow!("HTTP {} {} failed: {}", method, url, e))
-    })?;
+    let response = builder
+        .send()
+        .map_err(|e| anyhow::anyhow!("HTTP {} {} failed: {}", spec.method, spec.url, e))?;
 
     let status = response.status().as_u16();
     let ok = response.status().is_success();
 
     // Collect response headers
-    let headers_table = lua.create_table()?;
-    for (name, value) in response.headers() {
-        if let Ok(v) = value.to_str() {
-            headers_table.set(name.as_str(), v.to_string())?;
-        }
-    }
+    let headers = response
+        .headers()
+        .iter()
+        .filter_map(|(name, value)| {
+            value
+                .to_str()
+                .ok()
+                .map(|v| (name.as_str().to_string(), v.to_string()))
+        })
+        .collect();
 
     // Read body
-    let body_text = response.text().map_err(|e| {
-        mlua::Error::external(anyhow::anyhow!("Failed to read response body: {}", e))
-    })?;
+    let body = response
+        .text()
+        .map_err(|e| anyhow::anyhow!("Failed to read response body: {}", e))?;
+
+    Ok(HttpResponseData {
+        status,
+        ok,
+        headers,
+        body,
+    })
+}
+
+/// Execute many requests with at most `concurrency` in flight.
+///
+/// Workers pull the next unstarted request from a shared counter, so slow
+/// requests don't hold up a whole batch. Results are returned in input order.
+fn execute_parallel(
+    client: &reqwest::blocking::Client,
+    specs: &[HttpRequestSpec],
+    concurrency: usize,
+) -> Vec<Result<HttpResponseData, String>> {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<Result<HttpResponseData, String>>>> =
+        Mutex::new((0..specs.len()).map(|_| None).collect());
+
+    std::thread::scope(|scope| {
+        for _ in 0..concurrency.min(specs.len()) {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::SeqCst);
+                if i >= specs.len() {
+                    break;
+                }
+                let result = execute_request(client, &specs[i]).map_err(|e| e.to_string());
+                results.lock().unwrap_or_else(|e| e.into_inner())[i] = Some(result);
+            });
+        }
+    });
+
+    results
+        .into_inner()
+        .unwrap_or_else(|e| e.into_inner())
+        .into_iter()
+        .map(|r| r.unwrap_or_else(|| Err("request was not executed".to_string())))
+        .collect()
+}
+
+/// Convert a response into the Lua table shape returned by all `http.*` calls:
+/// `{ status, headers, body, ok, json? }`.
+fn response_to_lua(lua: &Lua, resp: HttpResponseData) -> LuaResult<LuaTable> {
+    let headers_table = lua.create_table()?;
+    for (name, value) in &resp.headers {
+        headers_table.set(name.as_str(), value.as_str())?;
+    }
 
     // Try to parse as JSON
-    let json_value = serde_json::from_str::<serde_json::Value>(&body_text).ok();
+    let json_value = serde_json::from_str::<serde_json::Value>(&resp.body).ok();
 
     // Build result table
     let result = lua.create_table()?;
-    result.set("status", status)?;
+    result.set("status", resp.status)?;
     result.set("headers", headers_table)?;
-    result.set("body", body_text)?;
-    result.set("ok", ok)?;
+    result.set("body", resp.body)?;
+    result.set("ok", resp.ok)?;
     if let Some(json) = json_value {
         result.set("json", json_value_to_lua(lua, &json)?)?;
     }
@@ -215,6 +375,20 @@ fn do_http_request(
     Ok(result)
 }
 
+/// Execute an HTTP request and return a Lua table with the response.
+fn do_http_request(
+    lua: &Lua,
+    client: &reqwest::blocking::Client,
+    method: &str,
+    url: &str,
+    body: Option<&str>,
+    opts: Option<LuaTable>,
+) -> LuaResult<LuaTable> {
+    let spec = request_spec(method, url, body, opts.as_ref())?;
+    let resp = execute_request(client, &spec).map_err(mlua::Error::external)?;
+    response_to_lua(lua, resp)
+}
+
 // ═══════════════════════════════════════════════════════════════════════
 // Host API: json
 // ═══════════════════════════════════════════════════════════════════════
@@ -248,18 +422,91 @@ fn register_json_api(lua: &Lua) -> LuaResult<()> {
 // Host API: env
 // ═══════════════════════════════════════════════════════════════════════
 
-fn register_env_api(lua: &Lua) -> LuaResult<()> {
+/// Which environment variables a script's `env.get` may read.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum EnvAccess {
+    /// The whole process environment.
+    #[default]
+    All,
+    /// Only these names; `env.get` returns `nil` for any other.
+    Only(Vec<String>),
+}
+
+impl EnvAccess {
+    /// The access for a script with `env_allowlist` from its config. Without
+    /// an allowlist, scripts installed from a registry get no variables and
+    /// others get all of them.
+    pub fn resolve(allowlist: Option<&[String]>, installed: bool) -> Self {
+        match allowlist {
+            Some(names) => Self::Only(names.to_vec()),
+            None if installed => Self::Only(Vec::new()),
+            None => Self::All,
+        }
+    }
+
+    /// Whether `name` may be read.
+    pub fn allows(&self, name: &str) -> bool {
+        match self {
+            Self::All => true,
+            Self::Only(names) => names.iter().any(|n| n == name),
+        }
+    }
+}
+
+fn register_env_api(lua: &Lua, script_name: &str, access: EnvAccess) -> LuaResult<()> {
     let env = lua.create_table()?;
 
+    let script = script_name.to_string();
     env.set(
         "get",
-        lua.create_function(|_lua, name: String| Ok(std::env::var(&name).ok()))?,
+        lua.create_function(move |_lua, name: String| {
+            if !access.allows(&name) {
+                tracing::warn!(
+                    target: "lua",
+                    script = %script,
+                    "env.get(\"{}\") denied: not in env_allowlist",
+                    name
+                );
+                return Ok(None);
+            }
+            Ok(std::env::var(&name).ok())
+        })?,
     )?;
 
     lua.globals().set("env", env)?;
     Ok(())
 }
 
+// ═══════════════════════════════════════════════════════════════════════
+// Host API: secrets
+// ═══════════════════════════════════════════════════════════════════════
+
+/// Secrets resolve through the environment too, so `secrets.get` obeys the
+/// same [`EnvAccess`] as `env.get`.
+fn register_secrets_api(lua: &Lua, script_name: &str, access: EnvAccess) -> LuaResult<()> {
+    let secrets = lua.create_table()?;
+
+    let script = script_name.to_string();
+    secrets.set(
+        "get",
+        lua.create_function(move |_lua, name: String| {
+            if !access.allows(&name) {
+                tracing::warn!(
+                    target: "lua",
+                    script = %script,
+                    "secrets.get(\"{}\") denied: not in env_allowlist",
+                    name
+                );
+                return Ok(None);
+            }
+            crate::secrets::get(&name).map_err(mlua::Error::external)
+        })?,
+    )?;
+
+    lua.globals().set("secrets", secrets)?;
+    Ok(())
+}
+
 // ═══════════════════════════════════════════════════════════════════════
 // Host API: log
 // ═══════════════════════════════════════════════════════════════════════
@@ -271,7 +518,7 @@ fn register_log_api(lua: &Lua, script_name: &str) -> LuaResult<()> {
     log.set(
         "info",
         lua.create_function(move |_lua, msg: String| {
-            eprintln!("[{}] INFO: {}", n, msg);
+            tracing::info!(target: "lua", script = %n, "{}", msg);
             Ok(())
         })?,
     )?;
@@ -280,7 +527,7 @@ fn register_log_api(lua: &Lua, script_name: &str) -> LuaResult<()> {
     log.set(
         "warn",
         lua.create_function(move |_lua, msg: String| {
-            eprintln!("[{}] WARN: {}", n, msg);
+            tracing::warn!(target: "lua", script = %n, "{}", msg);
             Ok(())
         })?,
     )?;
@@ -289,7 +536,7 @@ fn register_log_api(lua: &Lua, script_name: &str) -> LuaResult<()> {
     log.set(
         "error",
         lua.create_function(move |_lua, msg: String| {
-            eprintln!("[{}] ERROR: {}", n, msg);
+            tracing::error!(target: "lua", script = %n, "{}", msg);
             Ok(())
         })?,
     )?;
@@ -298,7 +545,7 @@ fn register_log_api(lua: &Lua, script_name: &str) -> LuaResult<()> {
     log.set(
         "debug",
         lua.create_function(move |_lua, msg: String| {
-            eprintln!("[{}] DEBUG: {}", n, msg);
+            tracing::debug!(target: "lua", script = %n, "{}", msg);
             Ok(())
         })?,
     )?;
@@ -313,25 +560,14 @@ fn register_log_api(lua: &Lua, script_name: &str) -> LuaResult<()> {
 
 fn register_fs_api(lua: &Lua, sandbox_root: &Path) -> LuaResult<()> {
     let fs = lua.create_table()?;
-    let root = sandbox_root
-        .canonicalize()
-        .unwrap_or_else(|_| sandbox_root.to_path_buf());
+    let root = canonicalize_path(sandbox_root).unwrap_or_else(|_| sandbox_root.to_path_buf());
 
     // fs.read(path) → string
     let r = root.clone();
     fs.set(
         "read",
         lua.create_function(move |_lua, path: String| {
-            let target = r.join(&path);
-            let canonical = target
-                .canonicalize()
-                .map_err(|e| mlua::Error::external(anyhow::anyhow!("fs.read: {}: {}", path, e)))?;
-            if !canonical.starts_with(&r) {
-                return Err(mlua::Error::external(anyhow::anyhow!(
-                    "fs.read: path escapes sandbox: {}",
-                    path
-                )));
-            }
+            let canonical = resolve_in_sandbox(&r, &path, "fs.read")?;
             std::fs::read_to_string(&canonical)
                 .map_err(|e| mlua::Error::external(anyhow::anyhow!("fs.read: {}: {}", path, e)))
         })?,
@@ -342,16 +578,7 @@ fn register_fs_api(lua: &Lua, sandbox_root: &Path) -> LuaResult<()> {
     fs.set(
         "list",
         lua.create_function(move |lua, (dir, glob_pattern): (String, Option<String>)| {
-            let target = r.join(&dir);
-            let canonical = target
-                .canonicalize()
-                .map_err(|e| mlua::Error::external(anyhow::anyhow!("fs.list: {}: {}", dir, e)))?;
-            if !canonical.starts_with(&r) {
-                return Err(mlua::Error::external(anyhow::anyhow!(
-                    "fs.list: path escapes sandbox: {}",
-                    dir
-                )));
-            }
+            let canonical = resolve_in_sandbox(&r, &dir, "fs.list")?;
 
             let matcher = glob_pattern
                 .as_deref()
@@ -406,6 +633,43 @@ fn register_fs_api(lua: &Lua, sandbox_root: &Path) -> LuaResult<()> {
     Ok(())
 }
 
+/// Resolve `path` relative to the sandbox root, rejecting anything that
+/// canonicalizes outside of it (via `..`, symlinks, or absolute paths).
+fn resolve_in_sandbox(root: &Path, path: &str, op: &str) -> LuaResult<PathBuf> {
+    let canonical = canonicalize_path(&root.join(path))
+        .map_err(|e| mlua::Error::external(anyhow::anyhow!("{}: {}: {}", op, path, e)))?;
+    if !canonical.starts_with(root) {
+        return Err(mlua::Error::external(anyhow::anyhow!(
+            "{}: path escapes sandbox: {}",
+            op,
+            path
+        )));
+    }
+    Ok(canonical)
+}
+
+/// Canonicalize a path without Windows verbatim prefixes.
+///
+/// On Windows, [`Path::canonicalize`] returns `\\?\C:\...` or
+/// `\\?\UNC\server\share\...`. Those forms break `starts_with` checks
+/// against non-verbatim roots and leak into paths handed to scripts, so they
+/// are rewritten to `C:\...` and `\\server\share\...`.
+fn canonicalize_path(path: &Path) -> std::io::Result<PathBuf> {
+    path.canonicalize().map(strip_verbatim_prefix)
+}
+
+fn strip_verbatim_prefix(path: PathBuf) -> PathBuf {
+    let s = path.to_string_lossy();
+    if let Some(rest) = s.strip_prefix(r"\\?\UNC\") {
+        return PathBuf::from(format!(r"\\{}", rest));
+    }
+    match s.strip_prefix(r"\\?\") {
+        // Only drive-letter paths; other verbatim forms have no plain equivalent.
+        Some(rest) if rest.as_bytes().get(1) == Some(&b':') => PathBuf::from(rest),
+        _ => path,
+    }
+}
+
 // ═══════════════════════════════════════════════════════════════════════
 // Host API: base64
 // ═══════════════════════════════════════════════════════════════════════
@@ -481,13 +745,59 @@ fn register_sleep(lua: &Lua) -> LuaResult<()> {
     Ok(())
 }
 
+// ═══════════════════════════════════════════════════════════════════════
+// Host API: cache
+// ═══════════════════════════════════════════════════════════════════════
+
+fn register_cache_api(lua: &Lua, script_name: &str, db_path: &Path) -> LuaResult<()> {
+    let cache = lua.create_table()?;
+    let store = Arc::new(ScriptCache::new(script_name, db_path.to_path_buf()));
+
+    // cache.get(key) → value | nil
+    let s = Arc::clone(&store);
+    cache.set(
+        "get",
+        lua.create_function(move |lua, key: String| {
+            let value = tokio::runtime::Handle::current()
+                .block_on(s.get(&key))
+                .map_err(|e| mlua::Error::external(anyhow::anyhow!("cache.get: {}", e)))?;
+            match value {
+                Some(v) => json_value_to_lua(lua, &v),
+                None => Ok(LuaValue::Nil),
+            }
+        })?,
+    )?;
+
+    // cache.set(key, value, ttl_secs?)
+    let s = store;
+    cache.set(
+        "set",
+        lua.create_function(
+            move |_lua, (key, value, ttl_secs): (String, LuaValue, Option<i64>)| {
+                let value = lua_value_to_json(value)?;
+                tokio::runtime::Handle::current()
+                    .block_on(s.set(&key, &value, ttl_secs.unwrap_or(0)))
+                    .map_err(|e| mlua::Error::external(anyhow::anyhow!("cache.set: {}", e)))
+            },
+        )?,
+    )?;
+
+    lua.globals().set("cache", cache)?;
+    Ok(())
+}
+
 // ═══════════════════════════════════════════════════════════════════════
 // Value Conversions: TOML → Lua
 // ═══════════════════════════════════════════════════════════════════════
 
-/// Convert a TOML value to a Lua value, expanding `${VAR}` in strings.
+/// Convert a TOML value to a Lua value, expanding `${VAR}` in strings and
+/// resolving `secret://` references.
 pub(crate) fn toml_value_to_lua(lua: &Lua, value: &toml::Value) -> LuaResult<LuaValue> {
     match value {
+        toml::Value::String(s) if crate::secrets::is_reference(s) => {
+            let secret = crate::secrets::resolve(s).map_err(mlua::Error::external)?;
+            lua.create_string(&secret).map(LuaValue::String)
+        }
         toml::Value::String(s) => {
             let expanded = expand_env_vars(s);
             lua.create_string(&expanded).map(LuaValue::String)
@@ -600,3 +910,62 @@ pub(crate) fn lua_value_to_json(value: LuaValue) -> LuaResult<serde_json::Value>
         _ => Ok(serde_json::Value::Null),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strip_verbatim_prefix_handles_drive_and_unc() {
+        assert_eq!(
+            strip_verbatim_prefix(PathBuf::from(r"\\?\C:\work\repo")),
+            PathBuf::from(r"C:\work\repo")
+        );
+        assert_eq!(
+            strip_verbatim_prefix(PathBuf::from(r"\\?\UNC\server\share\docs")),
+            PathBuf::from(r"\\server\share\docs")
+        );
+        assert_eq!(
+            strip_verbatim_prefix(PathBuf::from(r"\\?\Volume{abc}\x")),
+            PathBuf::from(r"\\?\Volume{abc}\x")
+        );
+        assert_eq!(
+            strip_verbatim_prefix(PathBuf::from("/home/ctx/repo")),
+            PathBuf::from("/home/ctx/repo")
+        );
+    }
+
+    #[test]
+    fn resolve_in_sandbox_rejects_escapes() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let root = canonicalize_path(tmp.path()).unwrap();
+        std::fs::create_dir_all(root.join("sub")).unwrap();
+        std::fs::write(root.join("sub").join("a.txt"), "a").unwrap();
+
+        let ok = resolve_in_sandbox(&root, "sub/a.txt", "fs.read").unwrap();
+        assert!(ok.starts_with(&root));
+        assert!(resolve_in_sandbox(&root, "../", "fs.read").is_err());
+    }
+
+    #[test]
+    fn env_get_respects_allowlist() {
+        let read_path = |access: EnvAccess| {
+            let lua = Lua::new();
+            register_env_api(&lua, "tool:test", access).unwrap();
+            lua.load(r#"return env.get("PATH")"#)
+                .eval::<Option<String>>()
+                .unwrap()
+        };
+        assert!(read_path(EnvAccess::All).is_some());
+        assert!(read_path(EnvAccess::Only(vec!["PATH".to_string()])).is_some());
+        assert!(read_path(EnvAccess::Only(vec!["HOME".to_string()])).is_none());
+
+        let allowlist = ["PATH".to_string()];
+        assert_eq!(EnvAccess::resolve(None, false), EnvAccess::All);
+        assert_eq!(EnvAccess::resolve(None, true), EnvAccess::Only(Vec::new()));
+        assert_eq!(
+            EnvAccess::resolve(Some(&allowlist), true),
+            EnvAccess::Only(allowlist.to_vec())
+        );
+    }
+}
@@ -0,0 +1,682 @@
+//! Spec runner for Lua tools and agents: `ctx tool test --spec`.
+//!
+//! Spec files are Lua scripts (conventionally `tests/*.lua`) that load an
+//! extension, call it, and check the result without a database, a server,
+//! or network access:
+//!
+//! ```lua
+//! local tool = load_tool("../tools/create-jira-ticket.lua")
+//!
+//! describe("create_jira_ticket", function()
+//!     it("posts the issue", function()
+//!         http.mock("POST https://jira.example.com/rest/api/2/issue",
+//!             { status = 201, json = { key = "OPS-1" } })
+//!         local result = tool.execute({ title = "Fix auth" },
+//!             mock_context({ config = { url = "https://jira.example.com" } }))
+//!         assert.equal("OPS-1", result.key)
+//!         assert.equal(1, #http.calls())
+//!     end)
+//! end)
+//! ```
+//!
+//! # Spec API
+//!
+//! Specs get every host API of [`crate::lua_runtime`] (with `fs` confined to
+//! the spec's directory), plus:
+//!
+//! | Function | Purpose |
+//! |----------|---------|
+//! | `describe(name, fn)` | Group tests; names are joined into each test's name |
+//! | `it(name, fn)` | Register a test |
+//! | `before_each(fn)` | Run `fn` before every test of the enclosing group |
+//! | `load_tool(path)` / `load_agent(path)` | Run a script (relative to the spec) and return its `tool` / `agent` table |
+//! | `mock_context(overrides?)` | A `context` table; see below |
+//! | `http.mock(url, response)` | Answer requests to `url` (`"POST <url>"` for one method, a trailing `*` for a prefix) |
+//! | `http.calls()` | Requests made in the current test: `{ method, url, body, headers, params }` |
+//! | `assert.equal(expected, actual, msg?)` | Deep equality; also `not_equal` |
+//! | `assert.truthy(v)`, `falsy`, `is_nil`, `not_nil` | Value checks |
+//! | `assert.matches(pattern, s)` | Lua pattern match |
+//! | `assert.has_error(fn, substring?)` | `fn` raises an error containing `substring` |
+//!
+//! `assert(v, msg)` still works as in plain Lua.
+//!
+//! A mock `response` is a table `{ status = 200, body = "...", json = {...},
+//! headers = {...} }` (`json` is encoded as the body when `body` is absent),
+//! a string body, or a function receiving the request and returning either.
+//! Requests nothing mocks fail the test, so specs never reach the network.
+//! Mocks registered inside a test or `before_each` last for that test;
+//! mocks registered at load time last for the whole file.
+//!
+//! `mock_context` returns empty results from `search` and `sources`;
+//! `get`, `call_tool`, and `sample` fail unless overridden. Each override
+//! is a function or a fixed return value; `config` becomes
+//! `context.config`.
+//!
+//! `cache` is an in-memory table cleared before every test, and `sleep`
+//! returns at once, so retry loops run without waiting. Each test has
+//! [`SPEC_TIMEOUT_SECS`] seconds.
+//!
+//! # Output
+//!
+//! Results are printed per file. With `--junit <file>`, a JUnit XML report
+//! is written as well, one `<testsuite>` per spec file, for CI systems.
+//! The command fails when any test fails or a spec file does not load.
+
+use anyhow::{bail, Context, Result};
+use mlua::prelude::*;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::config::Config;
+use crate::lua_runtime::{register_all_host_apis, EnvAccess};
+use crate::registry::RegistryManager;
+
+/// Seconds each test (and the loading of each spec file) may run.
+pub const SPEC_TIMEOUT_SECS: u64 = 30;
+
+/// Test name recorded when a spec file fails before its tests run.
+const LOAD_CASE: &str = "(load spec file)";
+
+/// The spec API, defined in Lua on top of the host APIs.
+const PRELUDE: &str = r#"
+local root = { befores = {} }
+local stack = { root }
+local spec = { tests = {} }
+__spec = spec
+
+function describe(name, fn)
+    table.insert(stack, { name = name, befores = {} })
+    fn()
+    table.remove(stack)
+end
+
+function before_each(fn)
+    table.insert(stack[#stack].befores, fn)
+end
+
+function it(name, fn)
+    local groups, names = {}, {}
+    for _, group in ipairs(stack) do
+        table.insert(groups, group)
+        if group.name then table.insert(names, group.name) end
+    end
+    table.insert(names, name)
+    table.insert(spec.tests, { name = table.concat(names, " "), fn = fn, groups = groups })
+end
+
+-- Assertions
+
+local function show(v)
+    if type(v) == "string" then return string.format("%q", v) end
+    if type(v) == "table" then
+        local ok, encoded = pcall(json.encode, v)
+        if ok then return encoded end
+    end
+    return tostring(v)
+end
+
+local function deep_equal(a, b)
+    if a == b then return true end
+    if type(a) ~= "table" or type(b) ~= "table" then return false end
+    for k, v in pairs(a) do
+        if not deep_equal(v, b[k]) then return false end
+    end
+    for k in pairs(b) do
+        if a[k] == nil then return false end
+    end
+    return true
+end
+
+-- Level 3 reports the line of the spec that called the assertion.
+local function fail(msg, default)
+    error(msg or default, 3)
+end
+
+assert = setmetatable({}, {
+    __call = function(_, v, msg, ...)
+        if not v then fail(msg, "assertion failed!") end
+        return v, msg, ...
+    end,
+})
+
+function assert.equal(expected, actual, msg)
+    if not deep_equal(expected, actual) then
+        fail(msg, "expected " .. show(expected) .. ", got " .. show(actual))
+    end
+end
+
+function assert.not_equal(unexpected, actual, msg)
+    if deep_equal(unexpected, actual) then
+        fail(msg, "expected a value other than " .. show(unexpected))
+    end
+end
+
+function assert.truthy(v, msg)
+    if not v then fail(msg, "expected a truthy value, got " .. show(v)) end
+end
+
+function assert.falsy(v, msg)
+    if v then fail(msg, "expected a falsy value, got " .. show(v)) end
+end
+
+function assert.is_nil(v, msg)
+    if v ~= nil then fail(msg, "expected nil, got " .. show(v)) end
+end
+
+function assert.not_nil(v, msg)
+    if v == nil then fail(msg, "expected a value, got nil") end
+end
+
+function assert.matches(pattern, s, msg)
+    if type(s) ~= "string" or not string.find(s, pattern) then
+        fail(msg, "expected " .. show(s) .. " to match " .. show(pattern))
+    end
+end
+
+function assert.has_error(fn, substring, msg)
+    local ok, err = pcall(fn)
+    if ok then fail(msg, "expected an error") end
+    if substring and not string.find(tostring(err), substring, 1, true) then
+        fail(msg, "expected an error containing " .. show(substring) .. ", got " .. show(tostring(err)))
+    end
+end
+
+-- HTTP mocks
+
+local file_mocks, test_mocks, calls = {}, {}, {}
+local in_test = false
+
+local function find_mock(method, url)
+    for _, list in ipairs({ test_mocks, file_mocks }) do
+        for i = #list, 1, -1 do
+            local mock = list[i]
+            local url_matches
+            if mock.url:sub(-1) == "*" then
+                url_matches = url:sub(1, #mock.url - 1) == mock.url:sub(1, -2)
+            else
+                url_matches = url == mock.url
+            end
+            if url_matches and (mock.method == nil or mock.method == method) then
+                return mock
+            end
+        end
+    end
+end
+
+local function respond(method, url, body, opts)
+    opts = opts or {}
+    local request = {
+        method = method,
+        url = url,
+        body = body,
+        headers = opts.headers or {},
+        params = opts.params or {},
+    }
+    table.insert(calls, request)
+    local mock = find_mock(method, url)
+    if not mock then
+        error("unmocked HTTP request: " .. method .. " " .. url, 3)
+    end
+    local r = mock.response
+    if type(r) == "function" then r = r(request) end
+    if type(r) == "string" then r = { body = r } end
+    r = r or {}
+    local status = r.status or 200
+    local response = {
+        status = status,
+        ok = status >= 200 and status < 300,
+        headers = r.headers or {},
+        body = r.body,
+        json = r.json,
+    }
+    if response.body == nil then
+        response.body = r.json ~= nil and json.encode(r.json) or ""
+    end
+    if response.json == nil then
+        local ok, parsed = pcall(json.parse, response.body)
+        if ok then response.json = parsed end
+    end
+    return response
+end
+
+http = {}
+
+function http.mock(url, response)
+    local method, target = url:match("^(%u+)%s+(%S+)$")
+    local mock = { method = method, url = target or url, response = response }
+    table.insert(in_test and test_mocks or file_mocks, mock)
+end
+
+function http.calls()
+    return calls
+end
+
+function http.get(url, opts) return respond("GET", url, nil, opts) end
+function http.post(url, body, opts) return respond("POST", url, body, opts) end
+function http.put(url, body, opts) return respond("PUT", url, body, opts) end
+
+function http.parallel(requests)
+    local out = {}
+    for i, r in ipairs(requests) do
+        out[i] = respond(string.upper(r.method or "GET"), r.url, r.body, r)
+    end
+    return out
+end
+
+-- Context, cache, and sleep
+
+function mock_context(overrides)
+    overrides = overrides or {}
+    local context = { config = overrides.config or {} }
+    local defaults = {
+        search = function() return {} end,
+        sources = function() return {} end,
+    }
+    for _, name in ipairs({ "search", "get", "sources", "call_tool", "sample" }) do
+        local value = overrides[name]
+        if type(value) == "function" then
+            context[name] = value
+        elseif value ~= nil then
+            context[name] = function() return value end
+        else
+            context[name] = defaults[name] or function()
+                error("context." .. name .. " is not mocked; pass it to mock_context", 2)
+            end
+        end
+    end
+    return context
+end
+
+local cache_entries = {}
+cache = {
+    get = function(key) return cache_entries[key] end,
+    set = function(key, value) cache_entries[key] = value end,
+}
+
+sleep = function() end
+
+function spec.run(i)
+    local test = spec.tests[i]
+    test_mocks, calls, cache_entries = {}, {}, {}
+    in_test = true
+    local ok, err = pcall(function()
+        for _, group in ipairs(test.groups) do
+            for _, fn in ipairs(group.befores) do fn() end
+        end
+        test.fn()
+    end)
+    in_test = false
+    if not ok then error(tostring(err), 0) end
+end
+"#;
+
+/// One test's outcome.
+#[derive(Debug, Clone)]
+pub struct SpecCase {
+    /// Names of the enclosing `describe` groups and the test, space-joined.
+    pub name: String,
+    pub duration: Duration,
+    /// The error message; `None` when the test passed.
+    pub failure: Option<String>,
+}
+
+/// The tests of one spec file.
+#[derive(Debug, Clone)]
+pub struct SpecFileReport {
+    pub path: PathBuf,
+    pub cases: Vec<SpecCase>,
+}
+
+impl SpecFileReport {
+    fn failures(&self) -> usize {
+        self.cases.iter().filter(|c| c.failure.is_some()).count()
+    }
+
+    fn duration(&self) -> Duration {
+        self.cases.iter().map(|c| c.duration).sum()
+    }
+}
+
+/// The spec files under `path`: the file itself, or every `.lua` file
+/// below a directory, in path order.
+pub fn spec_files(path: &Path) -> Result<Vec<PathBuf>> {
+    if !path.exists() {
+        bail!("spec path not found: {}", path.display());
+    }
+    if path.is_file() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    let mut files: Vec<PathBuf> = walkdir::WalkDir::new(path)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .map(|e| e.into_path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "lua"))
+        .collect();
+    files.sort();
+    if files.is_empty() {
+        bail!("no .lua spec files under {}", path.display());
+    }
+    Ok(files)
+}
+
+/// Run every spec under `path`, print the results, and write a JUnit XML
+/// report to `junit` if given.
+///
+/// # Errors
+///
+/// Returns an error if any test fails or a spec file cannot be loaded.
+pub async fn run_specs(config: &Config, path: &Path, junit: Option<&Path>) -> Result<()> {
+    let mut reports = Vec::new();
+    for file in spec_files(path)? {
+        let config = config.clone();
+        let report = tokio::task::spawn_blocking(move || run_spec_file(&file, &config))
+            .await
+            .context("Lua spec task panicked")?;
+
+        println!("{}", report.path.display());
+        for case in &report.cases {
+            let millis = case.duration.as_millis();
+            match &case.failure {
+                None => println!("  ✓ {} ({} ms)", case.name, millis),
+                Some(message) => {
+                    println!("  ✗ {} ({} ms)", case.name, millis);
+                    for line in message.lines() {
+                        println!("      {}", line);
+                    }
+                }
+            }
+        }
+        reports.push(report);
+    }
+
+    let total: usize = reports.iter().map(|r| r.cases.len()).sum();
+    let failed: usize = reports.iter().map(|r| r.failures()).sum();
+    println!();
+    println!(
+        "{} passed, {} failed ({} files)",
+        total - failed,
+        failed,
+        reports.len()
+    );
+
+    if let Some(junit) = junit {
+        std::fs::write(junit, junit_xml(&reports))
+            .with_context(|| format!("Failed to write JUnit report: {}", junit.display()))?;
+        println!("JUnit report: {}", junit.display());
+    }
+
+    if failed > 0 {
+        bail!("{} of {} specs failed", failed, total);
+    }
+    Ok(())
+}
+
+/// Load one spec file and run its tests. A file that fails to load is
+/// reported as a single failed case.
+fn run_spec_file(path: &Path, config: &Config) -> SpecFileReport {
+    let mut cases = Vec::new();
+    let started = Instant::now();
+    if let Err(e) = run_cases(path, config, &mut cases) {
+        cases.push(SpecCase {
+            name: LOAD_CASE.to_string(),
+            duration: started.elapsed(),
+            failure: Some(format!("{:#}", e)),
+        });
+    }
+    SpecFileReport {
+        path: path.to_path_buf(),
+        cases,
+    }
+}
+
+fn run_cases(path: &Path, config: &Config, cases: &mut Vec<SpecCase>) -> Result<()> {
+    let source = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read spec file: {}", path.display()))?;
+    let spec_dir = path.parent().unwrap_or(Path::new(".")).to_path_buf();
+
+    let lua = Lua::new();
+    let timeout = Duration::from_secs(SPEC_TIMEOUT_SECS);
+    let deadline = Arc::new(Mutex::new(Instant::now() + timeout));
+    let hook_deadline = Arc::clone(&deadline);
+    lua.set_hook(
+        mlua::HookTriggers::new().every_nth_instruction(10_000),
+        move |_lua, _debug| {
+            if Instant::now() > *hook_deadline.lock().unwrap_or_else(|e| e.into_inner()) {
+                Err(mlua::Error::RuntimeError(format!(
+                    "spec timed out after {} seconds",
+                    SPEC_TIMEOUT_SECS
+                )))
+            } else {
+                Ok(mlua::VmState::Continue)
+            }
+        },
+    );
+
+    let log_name = format!("spec:{}", path.display());
+    register_all_host_apis(&lua, &log_name, &spec_dir, &config.db.path, &EnvAccess::All)?;
+    register_loaders(&lua, config, &spec_dir)?;
+    lua.load(PRELUDE).set_name("spec prelude").exec()?;
+    lua.load(&source)
+        .set_name(path.to_string_lossy())
+        .exec()
+        .map_err(|e| anyhow::anyhow!("{}", error_message(&e)))?;
+
+    let spec: LuaTable = lua.globals().get("__spec")?;
+    let tests: LuaTable = spec.get("tests")?;
+    let run: LuaFunction = spec.get("run")?;
+    for i in 1..=tests.raw_len() {
+        let test: LuaTable = tests.get(i)?;
+        *deadline.lock().unwrap_or_else(|e| e.into_inner()) = Instant::now() + timeout;
+        let started = Instant::now();
+        let failure = run.call::<()>(i).err().map(|e| error_message(&e));
+        cases.push(SpecCase {
+            name: test.get("name")?,
+            duration: started.elapsed(),
+            failure,
+        });
+    }
+    Ok(())
+}
+
+/// Register `load_tool(path)` and `load_agent(path)`, which run a script
+/// in the spec's VM (so its `http` calls hit the mocks) and return the
+/// global table it defines. Scripts from a registry must pass its checks.
+fn register_loaders(lua: &Lua, config: &Config, spec_dir: &Path) -> LuaResult<()> {
+    let registries = Arc::new(RegistryManager::from_config(config));
+    for (function, global) in [("load_tool", "tool"), ("load_agent", "agent")] {
+        let dir = spec_dir.to_path_buf();
+        let registries = Arc::clone(&registries);
+        let loader = lua.create_function(move |lua, path: String| {
+            let path = dir.join(path);
+            let source = std::fs::read_to_string(&path)
+                .map_err(|e| mlua::Error::external(anyhow::anyhow!("{}: {}", path.display(), e)))?;
+            registries
+                .script_trust(&path)
+                .verify(source.as_bytes())
+                .map_err(mlua::Error::external)?;
+            lua.globals().set(global, LuaValue::Nil)?;
+            lua.load(&source).set_name(path.to_string_lossy()).exec()?;
+            lua.globals().get::<LuaTable>(global).map_err(|_| {
+                mlua::Error::external(anyhow::anyhow!(
+                    "{} does not define a global '{}' table",
+                    path.display(),
+                    global
+                ))
+            })
+        })?;
+        lua.globals().set(function, loader)?;
+    }
+    Ok(())
+}
+
+/// A Lua error without mlua's prefix and stack traceback.
+fn error_message(error: &mlua::Error) -> String {
+    let text = match error {
+        mlua::Error::RuntimeError(message) => message.clone(),
+        mlua::Error::CallbackError { cause, .. } => return error_message(cause),
+        other => other.to_string(),
+    };
+    text.split("\nstack traceback:")
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_string()
+}
+
+/// Render the reports as JUnit XML.
+fn junit_xml(reports: &[SpecFileReport]) -> String {
+    let total: usize = reports.iter().map(|r| r.cases.len()).sum();
+    let failed: usize = reports.iter().map(|r| r.failures()).sum();
+    let seconds: f64 = reports.iter().map(|r| r.duration().as_secs_f64()).sum();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuites name=\"ctx tool test\" tests=\"{}\" failures=\"{}\" time=\"{:.3}\">\n",
+        total, failed, seconds
+    ));
+    for report in reports {
+        let suite = xml_escape(&report.path.display().to_string());
+        xml.push_str(&format!(
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" time=\"{:.3}\">\n",
+            suite,
+            report.cases.len(),
+            report.failures(),
+            report.duration().as_secs_f64()
+        ));
+        for case in &report.cases {
+            let open = format!(
+                "    <testcase name=\"{}\" classname=\"{}\" time=\"{:.3}\"",
+                xml_escape(&case.name),
+                suite,
+                case.duration.as_secs_f64()
+            );
+            match &case.failure {
+                None => xml.push_str(&format!("{}/>\n", open)),
+                Some(message) => xml.push_str(&format!(
+                    "{}>\n      <failure message=\"{}\">{}</failure>\n    </testcase>\n",
+                    open,
+                    xml_escape(message.lines().next().unwrap_or_default()),
+                    xml_escape(message)
+                )),
+            }
+        }
+        xml.push_str("  </testsuite>\n");
+    }
+    xml.push_str("</testsuites>\n");
+    xml
+}
+
+/// Escape text for an XML element or attribute.
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(spec: &str) -> Vec<SpecCase> {
+        let tmp = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir_all(tmp.path().join("tools")).unwrap();
+        std::fs::write(
+            tmp.path().join("tools/lookup.lua"),
+            r#"
+tool = { name = "lookup" }
+function tool.execute(params, context)
+    local resp = http.get(context.config.url .. "/items/" .. params.id)
+    if not resp.ok then error("lookup failed: " .. resp.status) end
+    return { name = resp.json.name, hits = #context.search(resp.json.name) }
+end
+"#,
+        )
+        .unwrap();
+        std::fs::create_dir_all(tmp.path().join("tests")).unwrap();
+        let path = tmp.path().join("tests/lookup_spec.lua");
+        std::fs::write(&path, spec).unwrap();
+
+        run_spec_file(&path, &Config::minimal()).cases
+    }
+
+    #[test]
+    fn specs_run_tools_against_http_mocks() {
+        let cases = run(r#"
+local tool = load_tool("../tools/lookup.lua")
+local ctx = mock_context({ config = { url = "https://api.test" }, search = { {}, {} } })
+
+describe("lookup", function()
+    it("returns the item name", function()
+        http.mock("GET https://api.test/items/*", { json = { name = "widget" } })
+        assert.equal({ name = "widget", hits = 2 }, tool.execute({ id = "7" }, ctx))
+        assert.equal("https://api.test/items/7", http.calls()[1].url)
+    end)
+
+    it("fails on errors", function()
+        http.mock("https://api.test/items/8", { status = 404 })
+        assert.has_error(function() tool.execute({ id = "8" }, ctx) end, "lookup failed: 404")
+    end)
+
+    it("does not reach the network", function()
+        tool.execute({ id = "9" }, ctx)
+    end)
+
+    it("reports the failing line", function()
+        assert.equal(1, 2)
+    end)
+end)
+"#);
+        let names: Vec<&str> = cases.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "lookup returns the item name",
+                "lookup fails on errors",
+                "lookup does not reach the network",
+                "lookup reports the failing line",
+            ]
+        );
+        assert!(cases[0].failure.is_none(), "{:?}", cases[0].failure);
+        assert!(cases[1].failure.is_none(), "{:?}", cases[1].failure);
+        let unmocked = cases[2].failure.as_deref().unwrap();
+        assert!(unmocked.contains("unmocked HTTP request: GET https://api.test/items/9"));
+        let mismatch = cases[3].failure.as_deref().unwrap();
+        assert!(mismatch.contains(":22:"), "{}", mismatch);
+        assert!(mismatch.contains("expected 1, got 2"), "{}", mismatch);
+    }
+
+    #[test]
+    fn spec_load_errors_become_a_failed_case() {
+        let cases = run("this is not lua");
+        assert_eq!(cases.len(), 1);
+        assert_eq!(cases[0].name, LOAD_CASE);
+        assert!(cases[0].failure.is_some());
+    }
+
+    #[test]
+    fn junit_report_escapes_and_counts() {
+        let report = SpecFileReport {
+            path: PathBuf::from("tests/a.lua"),
+            cases: vec![
+                SpecCase {
+                    name: "ok".to_string(),
+                    duration: Duration::from_millis(2),
+                    failure: None,
+                },
+                SpecCase {
+                    name: "compares <tags>".to_string(),
+                    duration: Duration::from_millis(1),
+                    failure: Some("expected \"a\" & \"b\"\nmore".to_string()),
+                },
+            ],
+        };
+        let xml = junit_xml(&[report]);
+        assert!(xml.contains("<testsuites name=\"ctx tool test\" tests=\"2\" failures=\"1\""));
+        assert!(xml.contains("<testcase name=\"ok\" classname=\"tests/a.lua\" time=\"0.002\"/>"));
+        assert!(xml.contains("name=\"compares &lt;tags&gt;\""));
+        assert!(xml.contains("<failure message=\"expected &quot;a&quot; &amp; &quot;b&quot;\">"));
+    }
+}
@@ -15,13 +15,20 @@
 //! | Command | Description |
 //! |---------|-------------|
 //! | `ctx init` | Create the SQLite database and run schema migrations |
-//! | `ctx sources` | List all connectors and their health status |
-//! | `ctx sync <connector>` | Ingest data from a connector (filesystem, git, s3) |
+//! | `ctx sources` | List all connectors, probe their health, and show sync status and staleness |
+//! | `ctx sync <connector>` | Ingest data from a connector (filesystem, git, s3, sharepoint, zendesk, stackoverflow) |
+//! | `ctx reprocess --source <connector>` | Re-run the pipeline on items kept by `[sync] keep_raw` |
 //! | `ctx search "<query>"` | Search indexed documents |
 //! | `ctx get <id>` | Retrieve a full document by UUID |
+//! | `ctx similar <id>` | Find documents similar to a document |
+//! | `ctx curate pin <id> --query <q>` | Pin, boost, or block documents in search results |
+//! | `ctx db size` | Show database size by table and source against the budget |
+//! | `ctx db gc` | Remove orphaned rows, repair FTS, and vacuum |
+//! | `ctx bench ingest --connector <name>` | Time each ingest stage on a sample of a connector |
 //! | `ctx embed pending` | Backfill missing or stale embeddings |
 //! | `ctx embed rebuild` | Delete and regenerate all embeddings |
 //! | `ctx serve mcp` | Start the MCP-compatible HTTP server |
+//! | `ctx self update` | Replace the binary with the latest verified GitHub release |
 //!
 //! ## Examples
 //!
@@ -45,38 +52,99 @@
 //! ctx serve mcp --config ./config/ctx.toml
 //! ```
 
+mod add;
+mod agent_run;
 mod agent_script;
 mod agents;
 mod app_store;
+mod archive;
+mod assemble;
+mod audit;
+mod auth;
+mod aws_sigv4;
+mod bench;
+mod bm25;
+mod capabilities;
 mod chunk;
+mod classify;
 mod config;
 mod connector_fs;
 mod connector_git;
 mod connector_s3;
+mod connector_scaffold;
 mod connector_script;
+mod connector_sharepoint;
+mod connector_stackoverflow;
+mod connector_zendesk;
+mod content_dedup;
+mod contextualize;
 mod ctx_dirs;
+mod curate;
 mod db;
+mod doc_vectors;
+mod download;
 mod embed_cmd;
 mod embedding;
 mod export;
 mod extract;
+mod federation;
+mod gc;
 mod get;
+#[cfg(feature = "grpc")]
+mod grpc;
+mod hooks;
+mod http_retry;
 mod ingest;
+mod init_wizard;
+mod jobs;
+mod keyword_index;
+mod limits;
+mod logging;
 mod lua_runtime;
+mod lua_spec;
 mod mcp;
+mod memory;
 mod migrate;
 mod models;
+mod normalize;
+mod notify;
+mod popularity;
 mod progress;
+mod query_cache;
+mod raw_items;
+mod redact;
 mod registry;
+mod reload;
+mod response_limits;
+mod rm;
+mod script_cache;
+mod scrub;
 mod search;
+mod search_cache;
+mod search_debug;
+mod search_repl;
+mod secrets;
+mod self_update;
 mod server;
+mod similar;
 mod sources;
 mod sqlite_store;
 mod stats;
+mod stitch;
+mod storage;
+mod summarize;
+mod sync_failures;
+mod sync_status;
+mod telemetry;
+mod text;
+mod titles;
 mod tool_script;
 #[allow(dead_code)]
 mod traits;
+mod ui;
 mod vector_index;
+mod warmup;
+mod watch;
 
 use clap::{CommandFactory, Parser, Subcommand};
 use clap_complete::{generate, Shell};
@@ -106,6 +174,24 @@ struct Cli {
     #[arg(long, global = true)]
     config: Option<PathBuf>,
 
+    /// Diagnostic log verbosity: a level (`error`, `warn`, `info`, `debug`,
+    /// `trace`) or filter directives such as `info,ctx::connector_git=debug`.
+    ///
+    /// Defaults to the CTX_LOG environment variable, then `info`.
+    #[arg(long, global = true, value_name = "FILTER")]
+    log_level: Option<String>,
+
+    /// Diagnostic log format on stderr.
+    #[arg(long, global = true, value_enum, default_value_t = logging::LogFormat::Text)]
+    log_format: logging::LogFormat,
+
+    /// Run registry extensions that have no checksum or signature.
+    ///
+    /// Sets `allow_unsigned` on every registry. Checksum mismatches are
+    /// still rejected.
+    #[arg(long, global = true)]
+    allow_unsigned: bool,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -118,21 +204,61 @@ enum Commands {
     /// Creates the SQLite database file and all required tables
     /// (documents, chunks, checkpoints, chunks_fts, embeddings, chunk_vectors).
     /// This command is idempotent — running it multiple times is safe.
-    Init,
+    Init {
+        /// Ask for sources, embeddings, and the server port, write a
+        /// validated config, and offer an initial sync.
+        #[arg(long)]
+        interactive: bool,
+    },
 
     /// Show database statistics.
     ///
     /// Displays document, chunk, and embedding counts with a per-source
     /// breakdown and last sync timestamps. Useful for verifying that
-    /// syncs and embeddings completed successfully.
-    Stats,
+    /// syncs and embeddings completed successfully. Also reports index
+    /// health (average chunk length, FTS and vector storage size) and growth
+    /// since the snapshots sync records each day.
+    Stats {
+        /// Print the full report, including daily history, as JSON.
+        #[arg(long)]
+        json: bool,
+
+        /// List the documents search and get return most often, weighted
+        /// toward recent retrievals.
+        #[arg(long)]
+        popular: bool,
+
+        /// Number of documents listed by --popular.
+        #[arg(long, default_value = "20", requires = "popular")]
+        limit: usize,
+    },
+
+    /// Inspect the database file.
+    ///
+    /// Subcommands for checking disk usage against the `[storage]` budget
+    /// and reclaiming space.
+    Db {
+        #[command(subcommand)]
+        action: DbAction,
+    },
 
     /// List available connectors and their status.
     ///
     /// Shows which connectors are configured and whether they pass
-    /// their health checks. Useful for verifying configuration before
+    /// their health checks. Each connector makes one live request (S3
+    /// `HeadBucket`, `git ls-remote`, ...) and the latency and any error
+    /// are shown. Useful for verifying configuration before
     /// running a sync.
-    Sources,
+    ///
+    /// Also shows each connector's last successful sync, the items it
+    /// fetched, its documents in the index, the error of a failed last
+    /// sync, and whether it is stale by `[sync] stale_after_hours`.
+    /// Stale and never-synced connectors are repeated as warnings on stderr.
+    Sources {
+        /// Print the statuses as JSON.
+        #[arg(long)]
+        json: bool,
+    },
 
     /// Ingest data from a connector.
     ///
@@ -143,7 +269,7 @@ enum Commands {
     /// Connector format: `all`, `<type>`, or `<type>:<name>`.
     /// Examples: `all`, `git`, `git:platform`, `filesystem:docs`, `s3:runbooks`.
     Sync {
-        /// Connector specifier: `all`, a type (`git`, `filesystem`, `s3`, `script`),
+        /// Connector specifier: `all`, a type (`git`, `filesystem`, `s3`, `sharepoint`, `zendesk`, `stackoverflow`, `script`),
         /// or a specific instance (`git:platform`).
         connector: String,
 
@@ -167,6 +293,19 @@ enum Commands {
         #[arg(long)]
         limit: Option<usize>,
 
+        /// Embed chunks while syncing, overriding `[sync] embed = false`.
+        #[arg(long, conflicts_with = "no_embed")]
+        embed: bool,
+
+        /// Skip embedding; leave new chunks for `ctx embed pending`.
+        #[arg(long)]
+        no_embed: bool,
+
+        /// Embed even when stored vectors have a different dimension than
+        /// the provider (sets `[embedding] allow_dims_mismatch`).
+        #[arg(long)]
+        force: bool,
+
         /// Progress output: `human` (default when stderr is a TTY) or `json` (one JSON object per line on stderr).
         #[arg(long, value_name = "MODE", value_parser = ["human", "json"])]
         progress: Option<String>,
@@ -174,6 +313,81 @@ enum Commands {
         /// Disable progress output (e.g. for scripts that parse stdout).
         #[arg(long)]
         no_progress: bool,
+
+        /// Fail on the first item that cannot be fetched, extracted, or
+        /// written, instead of recording it and continuing (sets `[sync] strict`).
+        #[arg(long)]
+        strict: bool,
+
+        /// Scan again and ingest only the items earlier syncs recorded as
+        /// failed, leaving checkpoints unchanged.
+        #[arg(long, conflicts_with_all = ["full", "dry_run", "since", "until", "limit"])]
+        retry_failed: bool,
+    },
+
+    /// Index one note from a file or stdin.
+    ///
+    /// Runs the item through the sync pipeline (extraction, redaction,
+    /// chunking, keyword index, and embedding) and prints the document
+    /// UUID. Adding the same `--source` and `--id` again replaces it.
+    Add {
+        /// File to index; PDF and Office files are extracted.
+        #[arg(long, required_unless_present = "stdin", conflicts_with = "stdin")]
+        file: Option<PathBuf>,
+
+        /// Read the note from stdin.
+        #[arg(long)]
+        stdin: bool,
+
+        /// Document title (default: the file name).
+        #[arg(long)]
+        title: Option<String>,
+
+        /// Source label to file the document under.
+        #[arg(long, default_value = add::DEFAULT_SOURCE)]
+        source: String,
+
+        /// Source ID (default: the file's absolute path, or a new UUID for stdin).
+        #[arg(long)]
+        id: Option<String>,
+
+        /// URL to show and cite for the document (default: the file's `file://` URL).
+        #[arg(long)]
+        url: Option<String>,
+
+        /// Embed the note, overriding `[sync] embed = false`.
+        #[arg(long, conflicts_with = "no_embed")]
+        embed: bool,
+
+        /// Skip embedding; leave the chunks for `ctx embed pending`.
+        #[arg(long)]
+        no_embed: bool,
+    },
+
+    /// Re-run extraction, redaction, chunking, and embedding on kept items.
+    ///
+    /// Replays the items stored for connectors in `[sync] keep_raw`
+    /// through the pipeline without contacting their sources, e.g. after
+    /// changing `[redaction]` or `[chunking]`. Checkpoints are not touched.
+    Reprocess {
+        /// Connectors to reprocess: `all`, a type, or an instance (`script:jira`).
+        #[arg(long, default_value = "all")]
+        source: String,
+    },
+
+    /// Keep filesystem connectors in sync as files change.
+    ///
+    /// Syncs the connectors, then polls their files: added or modified
+    /// files trigger an incremental sync, and removed files have their
+    /// documents, chunks, and vectors deleted immediately. Runs until Ctrl-C.
+    Watch {
+        /// Connector specifier: `all`, `filesystem`, or `filesystem:<name>`.
+        #[arg(default_value = "all")]
+        connector: String,
+
+        /// Seconds between polls.
+        #[arg(long, default_value_t = 2, value_parser = clap::value_parser!(u64).range(1..))]
+        interval: u64,
     },
 
     /// Search indexed documents.
@@ -181,8 +395,9 @@ enum Commands {
     /// Queries the SQLite database using the specified search mode and
     /// returns ranked results with scores and snippets.
     Search {
-        /// The search query string.
-        query: String,
+        /// The search query string (optional with `--repl`, where it runs first).
+        #[arg(required_unless_present = "repl")]
+        query: Option<String>,
 
         /// Search mode: `keyword` (FTS5), `semantic` (vector), or `hybrid` (weighted merge).
         /// Semantic and hybrid modes require an embedding provider to be configured.
@@ -197,6 +412,11 @@ enum Commands {
         #[arg(long)]
         since: Option<String>,
 
+        /// Search the corpus as it stood on this date (YYYY-MM-DD): documents
+        /// updated after the end of that day are excluded.
+        #[arg(long)]
+        as_of: Option<String>,
+
         /// Maximum number of results to return.
         #[arg(long)]
         limit: Option<i64>,
@@ -204,14 +424,173 @@ enum Commands {
         /// Show scoring breakdown per result (keyword, semantic, hybrid scores and alpha).
         #[arg(long)]
         explain: bool,
+
+        /// Filter on chunk metadata as `key=value` (repeatable), e.g.
+        /// `--filter heading=install`. String values match as case-insensitive substrings.
+        #[arg(long = "filter", value_parser = parse_key_val)]
+        filters: Vec<(String, String)>,
+
+        /// Only return documents of this type (repeatable): a document type
+        /// assigned by `[classify]` (`runbook`, `adr`), a file extension, or
+        /// a content type. Same as an inline `type:` modifier.
+        #[arg(long = "type", value_name = "TYPE")]
+        types: Vec<String>,
+
+        /// Group results under their parent document (attachments, comments,
+        /// sub-pages), ordered by each group's best score.
+        #[arg(long)]
+        group_by_parent: bool,
+
+        /// Collapse results by `path_prefix` (leading directories of the
+        /// path or S3 key), `source`, or `content_type`, with a hit count
+        /// per group.
+        #[arg(
+            long,
+            value_name = "FIELD",
+            value_parser = ["path_prefix", "source", "content_type"],
+            conflicts_with = "group_by_parent"
+        )]
+        group_by: Option<String>,
+
+        /// Directories kept in a `path_prefix` group (`docs/runbooks/` is 2).
+        #[arg(long, value_name = "N", default_value_t = search::DEFAULT_PREFIX_DEPTH)]
+        prefix_depth: usize,
+
+        /// Tolerate typos in keyword terms by also matching indexed terms
+        /// within a small edit distance (overrides `[retrieval] fuzzy`).
+        #[arg(long)]
+        fuzzy: bool,
+
+        /// Also search the indexes under `[federation.indexes]` and merge
+        /// the results (implied by `[federation] enabled = true`).
+        #[arg(long)]
+        federated: bool,
+
+        /// What to search: `chunks` (document content) or `titles`
+        /// (document titles and markdown headings, one hit per document).
+        #[arg(long = "in", value_name = "SCOPE", default_value = "chunks", value_parser = ["chunks", "titles"])]
+        scope: String,
+
+        /// Print the top N chunks as one ready-to-paste context block, each
+        /// under a numbered source citation (overrides `--limit`).
+        #[arg(long, value_name = "N")]
+        context: Option<usize>,
+
+        /// Token budget of the `--context` block (estimated at 4 chars/token);
+        /// the chunk crossing it is cut and later chunks are left out.
+        #[arg(long, value_name = "TOKENS", default_value_t = assemble::DEFAULT_MAX_TOKENS)]
+        max_context_tokens: usize,
+
+        /// Run semantic and hybrid searches even when stored vectors have a
+        /// different dimension than the provider (sets `[embedding] allow_dims_mismatch`).
+        #[arg(long)]
+        force: bool,
+
+        /// Start an interactive session: one query per line, numbered
+        /// results, `open N` to print a document, and slash commands to
+        /// change settings (`/help` lists them).
+        #[arg(
+            long,
+            conflicts_with_all = ["context", "group_by", "group_by_parent", "federated", "types"]
+        )]
+        repl: bool,
+
+        /// Explain why a document is missing from the results: whether it
+        /// is indexed, which filters exclude it, how its chunks rank in
+        /// keyword and vector search, and where it lands against the cutoff.
+        #[arg(
+            long,
+            value_name = "DOC_ID",
+            conflicts_with_all = ["repl", "context", "group_by", "group_by_parent", "federated"]
+        )]
+        debug_miss: Option<String>,
     },
 
     /// Retrieve a document by its UUID.
     ///
     /// Prints the document's metadata, full body text, and all chunks.
+    /// Use `--ids` to retrieve several documents in one call.
     Get {
+        /// Document UUID.
+        #[arg(required_unless_present = "ids", conflicts_with = "ids")]
+        id: Option<String>,
+
+        /// Comma-separated document UUIDs to retrieve in order.
+        #[arg(long, value_delimiter = ',')]
+        ids: Vec<String>,
+
+        /// Omit chunks from the output (batch mode only).
+        #[arg(long, requires = "ids")]
+        no_chunks: bool,
+
+        /// Truncate each body to this many characters (batch mode only).
+        #[arg(long, requires = "ids")]
+        max_body_chars: Option<usize>,
+    },
+
+    /// Delete documents by UUID or by source and date.
+    ///
+    /// Removes the documents with their chunks, FTS rows, embeddings, and
+    /// vectors in one transaction, after listing them and asking for
+    /// confirmation. Deletions are recorded in the `[tools.audit]` log.
+    Rm {
+        /// Document UUIDs; an archive's UUID removes its members too.
+        #[arg(
+            required_unless_present_any = ["source", "before"],
+            conflicts_with_all = ["source", "before"]
+        )]
+        ids: Vec<String>,
+
+        /// Delete every document of this source label (e.g. `s3:old`).
+        #[arg(long)]
+        source: Option<String>,
+
+        /// Delete documents last updated before this date (YYYY-MM-DD).
+        #[arg(long)]
+        before: Option<String>,
+
+        /// Delete without asking (required when stdin is not a terminal).
+        #[arg(long, short = 'y')]
+        yes: bool,
+    },
+
+    /// Find the documents most similar to a document.
+    ///
+    /// Compares the document's embedding (the mean of its chunk vectors)
+    /// against the vector index. Requires an embedding provider. Scores are
+    /// raw similarities, so near-duplicates score close to 1.0.
+    Similar {
         /// Document UUID.
         id: String,
+
+        /// Only return documents from this connector source.
+        #[arg(long)]
+        source: Option<String>,
+
+        /// Only return documents updated on or after this date (YYYY-MM-DD).
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Only return documents last updated on or before this date (YYYY-MM-DD).
+        #[arg(long)]
+        as_of: Option<String>,
+
+        /// Maximum number of results to return.
+        #[arg(long)]
+        limit: Option<i64>,
+
+        /// Filter on chunk metadata as `key=value` (repeatable), as for `ctx search`.
+        #[arg(long = "filter", value_parser = parse_key_val)]
+        filters: Vec<(String, String)>,
+    },
+
+    /// Pin, boost, or block documents in search results.
+    ///
+    /// Curation is applied by every search (CLI, HTTP, MCP) after ranking.
+    /// `--explain` shows each result's boost and whether it was pinned.
+    Curate {
+        #[command(subcommand)]
+        action: CurateAction,
     },
 
     /// Manage embedding vectors.
@@ -223,12 +602,29 @@ enum Commands {
         action: EmbedAction,
     },
 
+    /// List, pause, resume, and cancel running syncs and embeds.
+    ///
+    /// Every sync (including those of `ctx watch`) and every `ctx embed
+    /// pending` or `rebuild` registers as a job in the database while it
+    /// runs. A job applies a request within a second: pause and cancel
+    /// take effect before its next document or embedding batch.
+    Jobs {
+        #[command(subcommand)]
+        action: JobsAction,
+    },
+
     /// Manage the derived vector-index sidecar.
     VectorIndex {
         #[command(subcommand)]
         action: VectorIndexAction,
     },
 
+    /// Manage the optional OpenSearch/Elasticsearch keyword index.
+    KeywordIndex {
+        #[command(subcommand)]
+        action: KeywordIndexAction,
+    },
+
     /// Start the MCP-compatible HTTP server.
     ///
     /// Exposes Context Harness functionality via a JSON API for integration
@@ -274,6 +670,24 @@ enum Commands {
         action: RegistryAction,
     },
 
+    /// Manage git hooks that keep the index fresh as you commit.
+    ///
+    /// Installs `post-commit` and `post-merge` hooks in the current repository
+    /// that run a targeted `ctx sync git:<name>` in the background.
+    Hooks {
+        #[command(subcommand)]
+        action: HooksAction,
+    },
+
+    /// Store and check secrets referenced as `secret://<name>` in config.
+    ///
+    /// Secrets resolve from `CTX_SECRET_<NAME>` environment variables, the
+    /// user secrets file, or the OS keychain.
+    Secrets {
+        #[command(subcommand)]
+        action: SecretsAction,
+    },
+
     /// Generate shell completions for bash, zsh, or fish.
     ///
     /// Prints completion script to stdout. Redirect to the appropriate
@@ -283,14 +697,44 @@ enum Commands {
         shell: Shell,
     },
 
-    /// Export the search index as a JSON file for static site search.
+    /// Export the search index as JSON or a Markdown directory.
     ///
-    /// Exports all documents and chunks to a JSON file that can be
-    /// used with `ctx-search.js` for client-side search on static sites.
+    /// JSON holds all documents and chunks for `ctx-search.js` and
+    /// client-side search on static sites. Markdown writes each document
+    /// as a front-mattered `.md` file under a directory per source, for
+    /// Obsidian or a static site generator.
     Export {
-        /// Output file path (defaults to stdout).
-        #[arg(short, long)]
+        /// `json` (default) or `markdown`.
+        #[arg(long, default_value = "json", value_parser = ["json", "markdown"])]
+        format: String,
+        /// Output file for JSON (defaults to stdout), or directory for
+        /// Markdown.
+        #[arg(short, long, alias = "out")]
         output: Option<PathBuf>,
+        /// Include chunk embeddings, for semantic and hybrid search in the
+        /// browser build of `context-harness-core`.
+        #[arg(long)]
+        vectors: bool,
+    },
+
+    /// Open the interactive terminal dashboard.
+    ///
+    /// Live search with a keyword/hybrid toggle, a document preview marking
+    /// chunk boundaries, connector status, and sync/embed progress on one
+    /// screen. Needs an interactive terminal.
+    Ui,
+
+    /// Measure where sync time goes.
+    Bench {
+        #[command(subcommand)]
+        action: BenchAction,
+    },
+
+    /// Update ctx itself from GitHub releases.
+    #[command(name = "self")]
+    SelfUpdate {
+        #[command(subcommand)]
+        action: SelfAction,
     },
 }
 
@@ -326,6 +770,36 @@ enum EmbedAction {
     },
 }
 
+/// Job control subcommands.
+#[derive(Subcommand)]
+enum JobsAction {
+    /// List running and paused jobs.
+    List {
+        /// Include completed, failed, and cancelled jobs of the last 7 days.
+        #[arg(long)]
+        all: bool,
+
+        /// Print the jobs as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Pause a job before its next document or batch.
+    Pause {
+        /// Job ID, or a unique prefix of it.
+        id: String,
+    },
+    /// Resume a paused job.
+    Resume {
+        /// Job ID, or a unique prefix of it.
+        id: String,
+    },
+    /// Cancel a job, paused or not. Work written so far is kept.
+    Cancel {
+        /// Job ID, or a unique prefix of it.
+        id: String,
+    },
+}
+
 /// Vector-index management subcommands.
 #[derive(Subcommand)]
 enum VectorIndexAction {
@@ -335,6 +809,111 @@ enum VectorIndexAction {
     Rebuild,
 }
 
+/// Keyword-index management subcommands.
+#[derive(Subcommand)]
+enum KeywordIndexAction {
+    /// Show configured backend and compare cluster and SQLite chunk counts.
+    Status,
+    /// Recreate the cluster index from canonical SQLite chunks.
+    Rebuild,
+}
+
+/// Benchmarking subcommands.
+#[derive(Subcommand)]
+enum BenchAction {
+    /// Ingest a sample of one connector's items into a scratch database
+    /// and print per-stage timing and throughput.
+    Ingest {
+        /// Connector instance to benchmark (e.g., `filesystem:docs`).
+        #[arg(long)]
+        connector: String,
+
+        /// Maximum number of items in the sample.
+        #[arg(long, default_value_t = 100)]
+        limit: usize,
+
+        /// Skip inline embedding even when `[sync] embed` is on.
+        #[arg(long)]
+        no_embed: bool,
+
+        /// Also write the stage times as collapsed stacks for flamegraph tools.
+        #[arg(long, value_name = "PATH")]
+        folded: Option<PathBuf>,
+    },
+}
+
+/// Search curation subcommands.
+#[derive(Subcommand)]
+enum CurateAction {
+    /// Always list a document first when a search matches the query.
+    ///
+    /// Matches searches containing the query's words in order, ignoring
+    /// case and punctuation (`deploy` matches "how do I deploy?").
+    Pin {
+        /// Document UUID.
+        id: String,
+
+        /// Query words the pin applies to.
+        #[arg(long)]
+        query: String,
+    },
+
+    /// Remove a document's pin for one query, or all its pins.
+    Unpin {
+        /// Document UUID.
+        id: String,
+
+        /// Only remove the pin for this query.
+        #[arg(long)]
+        query: Option<String>,
+    },
+
+    /// Multiply a document's search score by a weight (e.g. 1.5 or 0.5).
+    Boost {
+        /// Document UUID.
+        id: String,
+
+        /// Score multiplier (must be positive).
+        #[arg(long)]
+        weight: f64,
+    },
+
+    /// Remove a document's boost.
+    Unboost {
+        /// Document UUID.
+        id: String,
+    },
+
+    /// Never return a document from search.
+    Block {
+        /// Document UUID.
+        id: String,
+
+        /// Why the document is blocked (shown by `ctx curate list`).
+        #[arg(long)]
+        reason: Option<String>,
+    },
+
+    /// Allow a blocked document in search results again.
+    Unblock {
+        /// Document UUID.
+        id: String,
+    },
+
+    /// List all pins, boosts, and blocks.
+    List,
+}
+
+/// Database inspection subcommands.
+#[derive(Subcommand)]
+enum DbAction {
+    /// Show file size, used pages, and a per-table and per-source breakdown.
+    Size,
+    /// Remove orphaned chunks, embeddings, and vectors, repair the FTS
+    /// indexes, and return free pages to the file system.
+    Gc,
+}
+
 /// Connector management subcommands.
 #[derive(Subcommand)]
 enum ConnectorAction {
@@ -351,29 +930,45 @@ enum ConnectorAction {
     },
     /// Scaffold a new connector from a template.
     ///
-    /// Creates `connectors/<name>.lua` with a commented template.
+    /// Creates `connectors/<name>.lua` with a commented template. With
+    /// `--lang rust`, run from a context-harness checkout, creates a native
+    /// connector module with a config struct and tests instead, and prints
+    /// how to register it.
     Init {
         /// Name for the new connector (e.g., `jira`, `confluence`).
         name: String,
+        /// Template language: `lua` (script connector) or `rust` (built-in connector).
+        #[arg(long, default_value = "lua", value_parser = ["lua", "rust"])]
+        lang: String,
     },
 }
 
 /// Tool management subcommands.
 #[derive(Subcommand)]
 enum ToolAction {
-    /// Test a Lua tool script with sample parameters.
+    /// Test a Lua tool script with sample parameters, or run spec files.
     ///
     /// Loads the script, executes `tool.execute()` with the given parameters,
-    /// and prints the result. Useful for development and debugging.
+    /// and prints the result. Useful for development and debugging. With
+    /// `--spec`, runs `describe`/`it` spec files against tools and agents
+    /// instead, with HTTP requests answered by mocks.
     Test {
         /// Path to the `.lua` tool script.
-        path: PathBuf,
+        #[arg(required_unless_present = "spec")]
+        path: Option<PathBuf>,
         /// Tool parameters as `key=value` pairs.
         #[arg(long = "param", value_parser = parse_key_val)]
         params: Vec<(String, String)>,
         /// Use config from a named tool entry in ctx.toml.
         #[arg(long)]
         source: Option<String>,
+        /// Run the spec file, or every `.lua` file under the directory
+        /// (e.g. `tests/`); fails if any test fails.
+        #[arg(long, value_name = "PATH", conflicts_with_all = ["path", "params", "source"])]
+        spec: Option<PathBuf>,
+        /// Also write the spec results as JUnit XML to this file.
+        #[arg(long, value_name = "FILE", requires = "spec")]
+        junit: Option<PathBuf>,
     },
     /// Scaffold a new tool from a template.
     ///
@@ -384,6 +979,20 @@ enum ToolAction {
     },
     /// List all configured tools (built-in and Lua).
     List,
+    /// Show recorded tool invocations from the audit log.
+    ///
+    /// Requires `[tools.audit] enabled = true` for calls to be recorded.
+    Log {
+        /// Only show calls to this tool.
+        #[arg(long)]
+        tool: Option<String>,
+        /// Maximum number of entries to show (newest first).
+        #[arg(short = 'n', long, default_value = "50")]
+        limit: usize,
+        /// Print entries as JSON.
+        #[arg(long)]
+        json: bool,
+    },
 }
 
 /// Agent management subcommands.
@@ -402,6 +1011,21 @@ enum AgentAction {
         #[arg(long = "arg", value_parser = parse_key_val)]
         args: Vec<(String, String)>,
     },
+    /// Chat with an agent using the `[llm]` model.
+    ///
+    /// Resolves the agent's prompt, then runs a tool-calling loop in which
+    /// the model's calls to the agent's tools are executed locally. Reads
+    /// messages from stdin until `/exit` unless `--message` is given.
+    Run {
+        /// Agent name (as defined in `[agents.inline.<name>]` or `[agents.script.<name>]`).
+        name: String,
+        /// Agent arguments as `key=value` pairs.
+        #[arg(long = "arg", value_parser = parse_key_val)]
+        args: Vec<(String, String)>,
+        /// Send a single message and exit after the answer.
+        #[arg(short, long)]
+        message: Option<String>,
+    },
     /// Scaffold a new Lua agent script from a template.
     ///
     /// Creates `agents/<name>.lua` with a commented template showing
@@ -461,6 +1085,62 @@ enum RegistryAction {
     Init,
 }
 
+/// Git hook subcommands.
+#[derive(Subcommand)]
+enum HooksAction {
+    /// Install post-commit/post-merge hooks for the current repository.
+    ///
+    /// Existing hooks are preserved; the ctx section is appended or replaced.
+    Install {
+        /// Git connector to sync (`[connectors.git.<name>]`). Detected from
+        /// the repository path or `origin` remote when omitted.
+        #[arg(long)]
+        connector: Option<String>,
+    },
+    /// Remove the ctx section from this repository's hooks.
+    Uninstall,
+}
+
+/// Secrets subcommands.
+#[derive(Subcommand)]
+enum SecretsAction {
+    /// Store a secret, reading its value from stdin.
+    Set {
+        /// Secret name, as used in `secret://<name>`.
+        name: String,
+        /// Where to store it: `file` or `keychain`.
+        #[arg(long, default_value = "file")]
+        backend: String,
+    },
+    /// Report which backend a secret resolves from, without printing it.
+    Check {
+        /// Secret name or full `secret://<name>` reference.
+        name: String,
+    },
+}
+
+/// `ctx self` subcommands.
+#[derive(Subcommand)]
+enum SelfAction {
+    /// Download the latest release and replace this binary with it.
+    ///
+    /// The archive's SHA-256 checksum, and its minisign signature when
+    /// `[update] public_key` is set, are verified before the binary is
+    /// swapped in place.
+    Update {
+        /// Only report whether a newer release exists.
+        #[arg(long)]
+        check: bool,
+        /// Install this release (e.g. `v0.9.0`) instead of the latest,
+        /// including an older one.
+        #[arg(long, value_name = "TAG")]
+        tag: Option<String>,
+        /// Reinstall even when the release is already installed.
+        #[arg(long)]
+        force: bool,
+    },
+}
+
 /// Parse a `key=value` pair for `--param` arguments.
 fn parse_key_val(s: &str) -> Result<(String, String), String> {
     let pos = s
@@ -469,6 +1149,23 @@ fn parse_key_val(s: &str) -> Result<(String, String), String> {
     Ok((s[..pos].to_string(), s[pos + 1..].to_string()))
 }
 
+/// Run `ctx tool test`: the spec files under `spec`, or one script with
+/// sample parameters.
+async fn run_tool_test(
+    cfg: &config::Config,
+    path: Option<PathBuf>,
+    params: Vec<(String, String)>,
+    source: Option<String>,
+    spec: Option<PathBuf>,
+    junit: Option<PathBuf>,
+) -> anyhow::Result<()> {
+    match (spec, path) {
+        (Some(spec), _) => lua_spec::run_specs(cfg, &spec, junit.as_deref()).await,
+        (None, Some(path)) => tool_script::test_tool(&path, params, cfg, source.as_deref()).await,
+        (None, None) => anyhow::bail!("ctx tool test needs a script path or --spec"),
+    }
+}
+
 /// Server subcommands.
 #[derive(Subcommand)]
 enum ServeService {
@@ -476,13 +1173,65 @@ enum ServeService {
     ///
     /// Binds to the address configured in `[server].bind` and serves
     /// the Context Harness API endpoints.
-    Mcp,
+    Mcp {
+        /// Async worker threads serving requests (default: one per CPU core).
+        /// Tool call concurrency is limited separately by `[server.limits]`.
+        #[arg(long, value_parser = clap::value_parser!(u16).range(1..))]
+        workers: Option<u16>,
+    },
 }
 
-#[tokio::main]
-async fn main() -> anyhow::Result<()> {
-    let cli = Cli::parse();
+fn main() -> Result<(), scrub::Scrubbed> {
+    start(Cli::parse()).map_err(scrub::Scrubbed)
+}
 
+fn start(cli: Cli) -> anyhow::Result<()> {
+    // `ctx ui` owns the terminal; diagnostics would draw over it.
+    let log_level = match cli.command {
+        Commands::Ui => Some("off"),
+        _ => cli.log_level.as_deref(),
+    };
+    // Only `[telemetry]` and `[update]` are read here; commands report
+    // config errors themselves once they load it.
+    let (telemetry, update) = match config::load_config_for_cli(cli.config.clone()) {
+        Ok(resolved) => (
+            Some(resolved.config.telemetry),
+            Some(resolved.config.update),
+        ),
+        Err(_) => (None, None),
+    };
+    // Installed outside the runtime: the exporter's blocking HTTP client
+    // must be created and dropped off the async worker threads.
+    let _telemetry = logging::init(log_level, cli.log_format, telemetry.as_ref())?;
+    // `ctx ui` owns the terminal, completions are piped into a file, and
+    // `ctx self` looks up releases itself.
+    let update_check = update.filter(|update| {
+        update.check
+            && !matches!(
+                cli.command,
+                Commands::Ui | Commands::Completions { .. } | Commands::SelfUpdate { .. }
+            )
+    });
+
+    let mut runtime = tokio::runtime::Builder::new_multi_thread();
+    runtime.enable_all();
+    if let Commands::Serve {
+        service: ServeService::Mcp {
+            workers: Some(workers),
+        },
+    } = &cli.command
+    {
+        runtime.worker_threads(usize::from(*workers));
+    }
+    runtime.build()?.block_on(async {
+        if let Some(update) = update_check {
+            self_update::notify_if_outdated(&update).await;
+        }
+        run(cli).await
+    })
+}
+
+async fn run(cli: Cli) -> anyhow::Result<()> {
     // Commands that don't require config
     match &cli.command {
         Commands::Completions { shell } => {
@@ -491,18 +1240,26 @@ async fn main() -> anyhow::Result<()> {
             return Ok(());
         }
         Commands::Connector {
-            action: ConnectorAction::Init { name },
+            action: ConnectorAction::Init { name, lang },
         } => {
-            connector_script::scaffold_connector(name)?;
+            match lang.as_str() {
+                "rust" => {
+                    connector_scaffold::scaffold_rust_connector(&std::env::current_dir()?, name)?
+                }
+                _ => connector_script::scaffold_connector(name)?,
+            }
             return Ok(());
         }
         Commands::Connector {
             action: ConnectorAction::Test { path, source },
         } => {
             // Use config if available, otherwise a minimal default
-            let cfg = config::load_config_for_cli(cli.config.clone())
+            let mut cfg = config::load_config_for_cli(cli.config.clone())
                 .map(|resolved| resolved.config)
                 .unwrap_or_else(|_| config::Config::minimal());
+            if cli.allow_unsigned {
+                cfg.allow_unsigned_extensions();
+            }
             connector_script::test_script(path, &cfg, source.as_deref()).await?;
             return Ok(());
         }
@@ -512,6 +1269,28 @@ async fn main() -> anyhow::Result<()> {
             tool_script::scaffold_tool(name)?;
             return Ok(());
         }
+        Commands::Hooks {
+            action: HooksAction::Uninstall,
+        } => {
+            hooks::run_uninstall()?;
+            return Ok(());
+        }
+        Commands::Secrets { action } => {
+            match action {
+                SecretsAction::Set { name, backend } => secrets::run_set(name, backend)?,
+                SecretsAction::Check { name } => secrets::run_check(name)?,
+            }
+            return Ok(());
+        }
+        Commands::SelfUpdate {
+            action: SelfAction::Update { check, tag, force },
+        } => {
+            let cfg = config::load_config_for_cli(cli.config.clone())
+                .map(|resolved| resolved.config)
+                .unwrap_or_else(|_| config::Config::minimal());
+            self_update::run_update(&cfg.update, tag.as_deref(), *check, *force).await?;
+            return Ok(());
+        }
         Commands::Agent {
             action: AgentAction::Init { name },
         } => {
@@ -548,34 +1327,48 @@ async fn main() -> anyhow::Result<()> {
             action: ToolAction::Test { path, source, .. },
         } if source.is_none() => {
             // Without --source, use minimal config
-            let cfg = config::load_config_for_cli(cli.config.clone())
+            let mut cfg = config::load_config_for_cli(cli.config.clone())
                 .map(|resolved| resolved.config)
                 .unwrap_or_else(|_| config::Config::minimal());
+            if cli.allow_unsigned {
+                cfg.allow_unsigned_extensions();
+            }
             if let Commands::Tool {
                 action:
                     ToolAction::Test {
                         path,
                         params,
                         source,
+                        spec,
+                        junit,
                     },
             } = cli.command
             {
-                tool_script::test_tool(&path, params, &cfg, source.as_deref()).await?;
+                run_tool_test(&cfg, path, params, source, spec, junit).await?;
             }
             return Ok(());
         }
         _ => {}
     }
 
-    if matches!(&cli.command, Commands::Init) {
-        config::ensure_workspace_config_for_init(cli.config.as_deref())?;
+    match &cli.command {
+        Commands::Init { interactive: true } => {
+            init_wizard::run_wizard(cli.config.as_deref())?;
+        }
+        Commands::Init { interactive: false } => {
+            config::ensure_workspace_config_for_init(cli.config.as_deref())?;
+        }
+        _ => {}
     }
     let resolved_config = config::load_config_for_cli(cli.config.clone())?;
     let config_path = resolved_config.path.clone();
-    let cfg = resolved_config.config;
+    let mut cfg = resolved_config.config;
+    if cli.allow_unsigned {
+        cfg.allow_unsigned_extensions();
+    }
 
     match cli.command {
-        Commands::Init => {
+        Commands::Init { interactive } => {
             SqliteAppStore::initialize_config(&cfg).await?;
             println!("Database initialized successfully.");
 
@@ -591,17 +1384,51 @@ async fn main() -> anyhow::Result<()> {
                             .ok_or_else(|| anyhow::anyhow!("No config path available"))
                             .and_then(registry::cmd_init_community);
                         if let Err(e) = registry_result {
-                            eprintln!("Warning: failed to install community registry: {}", e);
+                            tracing::warn!("failed to install community registry: {}", e);
                         }
                     }
                 }
             }
+
+            if interactive
+                && !sources::get_sources(&cfg).is_empty()
+                && init_wizard::Prompter::stdin().confirm("Run an initial sync now?", true)?
+            {
+                let reporter = progress::ProgressMode::default_for_tty().reporter();
+                ingest::run_sync(
+                    &cfg,
+                    "all",
+                    false,
+                    false,
+                    None,
+                    None,
+                    None,
+                    Some(reporter.as_ref()),
+                )
+                .await?;
+            }
         }
-        Commands::Stats => {
-            stats::run_stats(&cfg).await?;
+        Commands::Stats {
+            json,
+            popular,
+            limit,
+        } => {
+            if popular {
+                popularity::run_popular(&cfg, limit, json).await?;
+            } else {
+                stats::run_stats(&cfg, json).await?;
+            }
         }
-        Commands::Sources => {
-            sources::list_sources(&cfg)?;
+        Commands::Db { action } => match action {
+            DbAction::Size => {
+                storage::run_db_size(&cfg).await?;
+            }
+            DbAction::Gc => {
+                gc::run_db_gc(&cfg).await?;
+            }
+        },
+        Commands::Sources { json } => {
+            sources::list_sources(&cfg, json).await?;
         }
         Commands::Sync {
             connector,
@@ -610,9 +1437,20 @@ async fn main() -> anyhow::Result<()> {
             since,
             until,
             limit,
+            embed,
+            no_embed,
+            force,
             progress,
             no_progress,
+            strict,
+            retry_failed,
         } => {
+            let mut cfg = cfg;
+            if embed || no_embed {
+                cfg.sync.embed = embed;
+            }
+            cfg.embedding.allow_dims_mismatch |= force;
+            cfg.sync.strict |= strict;
             let progress_mode = if no_progress {
                 progress::ProgressMode::Off
             } else if let Some(ref mode) = progress {
@@ -624,31 +1462,198 @@ async fn main() -> anyhow::Result<()> {
                 progress::ProgressMode::default_for_tty()
             };
             let reporter = progress_mode.reporter();
-            ingest::run_sync(
-                &cfg,
-                &connector,
-                full,
-                dry_run,
-                since,
-                until,
-                limit,
-                Some(reporter.as_ref()),
-            )
-            .await?;
+            if retry_failed {
+                ingest::run_retry_failed(&cfg, &connector, Some(reporter.as_ref())).await?;
+            } else {
+                ingest::run_sync(
+                    &cfg,
+                    &connector,
+                    full,
+                    dry_run,
+                    since,
+                    until,
+                    limit,
+                    Some(reporter.as_ref()),
+                )
+                .await?;
+            }
+        }
+        Commands::Add {
+            file,
+            stdin: _,
+            title,
+            source,
+            id,
+            url,
+            embed,
+            no_embed,
+        } => {
+            let mut cfg = cfg;
+            if embed || no_embed {
+                cfg.sync.embed = embed;
+            }
+            let options = add::AddOptions {
+                file: file.as_deref(),
+                title: title.as_deref(),
+                source: &source,
+                source_id: id.as_deref(),
+                source_url: url.as_deref(),
+            };
+            add::run_add(&cfg, &options).await?;
+        }
+        Commands::Reprocess { source } => {
+            ingest::run_reprocess(&cfg, &source).await?;
+        }
+        Commands::Watch {
+            connector,
+            interval,
+        } => {
+            watch::run_watch(&cfg, &connector, std::time::Duration::from_secs(interval)).await?;
         }
         Commands::Search {
             query,
             mode,
             source,
             since,
+            as_of,
             limit,
             explain,
+            filters,
+            types,
+            group_by_parent,
+            group_by,
+            prefix_depth,
+            fuzzy,
+            federated,
+            scope,
+            context,
+            max_context_tokens,
+            force,
+            repl,
+            debug_miss,
         } => {
-            search::run_search(&cfg, &query, &mode, source, since, limit, explain).await?;
+            let mut cfg = cfg;
+            cfg.embedding.allow_dims_mismatch |= force;
+            if let Some(doc_id) = debug_miss {
+                if scope == "titles" {
+                    anyhow::bail!(
+                        "--debug-miss diagnoses chunk search and cannot be used with --in titles"
+                    );
+                }
+                search_debug::run_debug_miss(
+                    &cfg,
+                    query.as_deref().unwrap_or_default(),
+                    &mode,
+                    &doc_id,
+                    source,
+                    since,
+                    as_of,
+                    limit,
+                    filters,
+                    types,
+                    fuzzy,
+                )
+                .await?;
+            } else if repl {
+                if scope == "titles" {
+                    anyhow::bail!("--repl searches chunks and cannot be used with --in titles");
+                }
+                let settings = search_repl::ReplSettings {
+                    mode,
+                    source,
+                    since,
+                    as_of,
+                    limit,
+                    explain,
+                    filters,
+                    fuzzy,
+                };
+                search_repl::run_repl(&cfg, settings, query.as_deref()).await?;
+            } else {
+                search::run_search(
+                    &cfg,
+                    query.as_deref().unwrap_or_default(),
+                    &mode,
+                    source,
+                    since,
+                    as_of,
+                    limit,
+                    explain,
+                    filters,
+                    types,
+                    group_by_parent,
+                    group_by.as_deref(),
+                    prefix_depth,
+                    fuzzy,
+                    federated,
+                    &scope,
+                    context,
+                    max_context_tokens,
+                )
+                .await?;
+            }
         }
-        Commands::Get { id } => {
-            get::run_get(&cfg, &id).await?;
+        Commands::Get {
+            id,
+            ids,
+            no_chunks,
+            max_body_chars,
+        } => match id {
+            Some(id) => get::run_get(&cfg, &id).await?,
+            None => {
+                let options = get::GetOptions {
+                    include_chunks: !no_chunks,
+                    max_body_chars,
+                    ..get::GetOptions::default()
+                };
+                get::run_get_many(&cfg, &ids, &options).await?;
+            }
+        },
+        Commands::Rm {
+            ids,
+            source,
+            before,
+            yes,
+        } => {
+            let filter = rm::RmFilter {
+                ids: &ids,
+                source: source.as_deref(),
+                before: before.as_deref(),
+            };
+            rm::run_rm(&cfg, &filter, yes).await?;
         }
+        Commands::Similar {
+            id,
+            source,
+            since,
+            as_of,
+            limit,
+            filters,
+        } => {
+            let query = similar::SimilarQuery {
+                id: &id,
+                source: source.as_deref(),
+                since: since.as_deref(),
+                as_of: as_of.as_deref(),
+                limit,
+                chunk_filters: &filters,
+                allowed_sources: None,
+            };
+            similar::run_similar(&cfg, &query).await?;
+        }
+        Commands::Curate { action } => match action {
+            CurateAction::Pin { id, query } => curate::run_pin(&cfg, &id, &query).await?,
+            CurateAction::Unpin { id, query } => {
+                curate::run_unpin(&cfg, &id, query.as_deref()).await?
+            }
+            CurateAction::Boost { id, weight } => curate::run_boost(&cfg, &id, weight).await?,
+            CurateAction::Unboost { id } => curate::run_unboost(&cfg, &id).await?,
+            CurateAction::Block { id, reason } => {
+                curate::run_block(&cfg, &id, reason.as_deref()).await?
+            }
+            CurateAction::Unblock { id } => curate::run_unblock(&cfg, &id).await?,
+            CurateAction::List => curate::run_list(&cfg).await?,
+        },
         Commands::Embed { action } => match action {
             EmbedAction::Pending {
                 limit,
@@ -661,6 +1666,12 @@ async fn main() -> anyhow::Result<()> {
                 embed_cmd::run_embed_rebuild(&cfg, batch_size).await?;
             }
         },
+        Commands::Jobs { action } => match action {
+            JobsAction::List { all, json } => jobs::run_list(&cfg, all, json).await?,
+            JobsAction::Pause { id } => jobs::run_request(&cfg, &id, "pause").await?,
+            JobsAction::Resume { id } => jobs::run_request(&cfg, &id, "resume").await?,
+            JobsAction::Cancel { id } => jobs::run_request(&cfg, &id, "cancel").await?,
+        },
         Commands::VectorIndex { action } => match action {
             VectorIndexAction::Status => {
                 let status = vector_index::vector_index_status(&cfg).await?;
@@ -698,9 +1709,46 @@ async fn main() -> anyhow::Result<()> {
                 println!("  fresh: {}", status.fresh);
             }
         },
+        Commands::KeywordIndex { action } => {
+            let (heading, status) = match action {
+                KeywordIndexAction::Status => (
+                    "keyword-index status",
+                    keyword_index::keyword_index_status(&cfg).await?,
+                ),
+                KeywordIndexAction::Rebuild => (
+                    "keyword-index rebuild",
+                    keyword_index::rebuild_configured_keyword_index(&cfg).await?,
+                ),
+            };
+            println!("{}", heading);
+            println!("  backend: {}", status.backend);
+            if let Some(url) = &status.url {
+                println!("  url: {}", url);
+            }
+            if let Some(index) = &status.index {
+                println!("  index: {}", index);
+            }
+            println!("  sqlite chunks: {}", status.sqlite_chunks);
+            if let Some(n) = status.indexed_chunks {
+                println!("  indexed chunks: {}", n);
+            }
+            println!("  fresh: {}", status.fresh());
+            if let Some(message) = &status.message {
+                println!("  message: {}", message);
+            }
+        }
         Commands::Serve { service } => match service {
-            ServeService::Mcp => {
-                server::run_server(&cfg).await?;
+            ServeService::Mcp { .. } => {
+                let explicit = cli.config.clone();
+                let allow_unsigned = cli.allow_unsigned;
+                let loader = reload::ConfigLoader::new(config_path.clone(), move || {
+                    let mut cfg = config::load_config_for_cli(explicit.clone())?.config;
+                    if allow_unsigned {
+                        cfg.allow_unsigned_extensions();
+                    }
+                    Ok(cfg)
+                });
+                server::run_server_with_loader(&cfg, loader).await?;
             }
         },
         Commands::Connector { action } => match action {
@@ -717,20 +1765,56 @@ async fn main() -> anyhow::Result<()> {
                 path,
                 params,
                 source,
+                spec,
+                junit,
             } => {
-                tool_script::test_tool(&path, params, &cfg, source.as_deref()).await?;
+                run_tool_test(&cfg, path, params, source, spec, junit).await?;
             }
             ToolAction::List => {
                 tool_script::list_tools(&cfg)?;
             }
+            ToolAction::Log { tool, limit, json } => {
+                audit::run_tool_log(&cfg, tool.as_deref(), limit, json).await?;
+            }
             ToolAction::Init { .. } => {
                 // Handled above (before config loading)
                 unreachable!()
             }
         },
-        Commands::Export { output } => {
-            export::run_export(&cfg, output.as_deref()).await?;
+        Commands::Hooks { action } => match action {
+            HooksAction::Install { connector } => {
+                hooks::run_install(&cfg, config_path.as_deref(), connector.as_deref())?;
+            }
+            HooksAction::Uninstall => {
+                // Handled above (before config loading)
+                unreachable!()
+            }
+        },
+        Commands::Secrets { .. } => {
+            // Handled above (before config loading)
+            unreachable!()
+        }
+        Commands::Export {
+            format,
+            output,
+            vectors,
+        } => {
+            export::run_export(&cfg, &format, output.as_deref(), vectors).await?;
         }
+        Commands::Ui => {
+            ui::run_ui(&cfg, config_path.as_deref()).await?;
+        }
+        Commands::Bench { action } => match action {
+            BenchAction::Ingest {
+                connector,
+                limit,
+                no_embed,
+                folded,
+            } => {
+                bench::run_bench_ingest(&cfg, &connector, limit, no_embed, folded.as_deref())
+                    .await?;
+            }
+        },
         Commands::Registry { action } => match action {
             RegistryAction::List => {
                 registry::cmd_list(&cfg);
@@ -756,7 +1840,7 @@ async fn main() -> anyhow::Result<()> {
                 unreachable!()
             }
         },
-        Commands::Completions { .. } => unreachable!(),
+        Commands::Completions { .. } | Commands::SelfUpdate { .. } => unreachable!(),
         Commands::Agent { action } => match action {
             AgentAction::List => {
                 agent_script::list_agents(&cfg)?;
@@ -764,6 +1848,13 @@ async fn main() -> anyhow::Result<()> {
             AgentAction::Test { name, args } => {
                 agent_script::test_agent(&name, args, &cfg).await?;
             }
+            AgentAction::Run {
+                name,
+                args,
+                message,
+            } => {
+                agent_run::run_agent(&name, args, message, &cfg).await?;
+            }
             AgentAction::Init { .. } => {
                 // Handled above (before config loading)
                 unreachable!()
@@ -6,28 +6,174 @@
 //!
 //! * **Tools** are exposed as MCP tools via `list_tools` / `call_tool`.
 //! * **Agents** are exposed as MCP prompts via `list_prompts` / `get_prompt`.
+//!
+//! # Plain JSON-RPC
+//!
+//! The Streamable HTTP transport at `/mcp` negotiates sessions and answers
+//! with server-sent events. Clients that only speak request/response
+//! JSON-RPC can instead `POST` to `/rpc`, which [`McpBridge::handle_jsonrpc`]
+//! serves statelessly with `application/json` replies:
+//!
+//! | Method | Result |
+//! |--------|--------|
+//! | `initialize` | Server info and capabilities; echoes a supported `protocolVersion` |
+//! | `ping` | `{}` |
+//! | `tools/list`, `tools/call` | Same tools as `/mcp` |
+//! | `prompts/list`, `prompts/get` | Agents as prompts |
+//! | `notifications/*` | Accepted without a reply |
+//!
+//! # Sampling
+//!
+//! When the client declares the `sampling` capability at `initialize`,
+//! tools and agents called over `/mcp` can ask the client's model for a
+//! completion (`context.sample(prompt, opts)` in Lua, see
+//! [`ToolContext::sample`]). The request goes back to the client as
+//! `sampling/createMessage` on the session, so the server needs no model
+//! credentials. `/rpc` is stateless and has no channel back to the client,
+//! so sampling fails there.
+//!
+//! API keys limited to some sources (see [`crate::auth`]) only see and call
+//! the built-in tools and TOML agents, and their calls stay inside the
+//! key's scope, on both transports.
+//!
+//! Batches (JSON arrays) are supported. Errors use the JSON-RPC codes:
+//! `-32700` parse error, `-32600` invalid request, `-32601` unknown method
+//! or tool, `-32602` invalid params, `-32603` internal error.
 
 use std::borrow::Cow;
 use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Context as _;
+use async_trait::async_trait;
 
 use rmcp::model::*;
 use rmcp::{ErrorData as McpError, ServerHandler};
+use serde_json::{json, Value};
 
-use crate::agents::AgentRegistry;
+use crate::agents::{AgentPrompt, AgentRegistry};
+use crate::audit::{self, AuditEntry, AuditLog};
+use crate::auth::{self, ApiKey, SourceScope};
 use crate::config::Config;
-use crate::traits::{ToolContext, ToolRegistry};
+use crate::limits::{self, ToolLimiter};
+use crate::reload::{Extensions, LiveExtensions};
+use crate::scrub;
+use crate::search_cache::{self, SearchCache};
+use crate::server::ServerLifecycle;
+use crate::telemetry::{self, RequestTrace};
+use crate::tool_script::validate_params;
+use crate::traits::{
+    ContentPart, SampleRequest, SampleResponse, Sampler, ToolContext, ToolRegistry,
+};
+
+/// How long a `sampling/createMessage` request may wait for the client,
+/// which may be waiting for its user to approve it.
+const SAMPLE_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// [`Sampler`] that sends `sampling/createMessage` to the MCP client of a
+/// session.
+struct McpSampler {
+    peer: rmcp::Peer<rmcp::RoleServer>,
+}
+
+impl McpSampler {
+    /// The sampler for the client behind `context`, or `None` when it did
+    /// not declare the `sampling` capability.
+    fn for_client(
+        context: &rmcp::service::RequestContext<rmcp::RoleServer>,
+    ) -> Option<Arc<dyn Sampler>> {
+        let info = context.peer.peer_info()?;
+        info.capabilities.sampling.as_ref()?;
+        Some(Arc::new(Self {
+            peer: context.peer.clone(),
+        }))
+    }
+}
+
+#[async_trait]
+impl Sampler for McpSampler {
+    async fn sample(&self, request: SampleRequest) -> anyhow::Result<SampleResponse> {
+        let params = serde_json::from_value(create_message_params(&request))
+            .context("building sampling/createMessage")?;
+        let result = tokio::time::timeout(SAMPLE_TIMEOUT, self.peer.create_message(params))
+            .await
+            .map_err(|_| {
+                anyhow::anyhow!(
+                    "sampling/createMessage: no reply from the client within {}s",
+                    SAMPLE_TIMEOUT.as_secs()
+                )
+            })?
+            .map_err(|e| anyhow::anyhow!("sampling/createMessage: {}", e))?;
+        sample_response(serde_json::to_value(result)?)
+    }
+}
+
+/// `sampling/createMessage` params for `request`, in the MCP wire format.
+fn create_message_params(request: &SampleRequest) -> Value {
+    let mut params = json!({
+        "messages": [{
+            "role": "user",
+            "content": { "type": "text", "text": request.prompt },
+        }],
+        "maxTokens": request.max_tokens,
+        "includeContext": "none",
+    });
+    if let Some(ref system) = request.system {
+        params["systemPrompt"] = json!(system);
+    }
+    if let Some(temperature) = request.temperature {
+        params["temperature"] = json!(temperature);
+    }
+    if !request.stop_sequences.is_empty() {
+        params["stopSequences"] = json!(request.stop_sequences);
+    }
+    if !request.model_hints.is_empty() {
+        let hints: Vec<Value> = request
+            .model_hints
+            .iter()
+            .map(|name| json!({ "name": name }))
+            .collect();
+        params["modelPreferences"] = json!({ "hints": hints });
+    }
+    params
+}
+
+/// Read a `sampling/createMessage` result. Its content is one block or,
+/// from newer clients, a list of blocks; the text blocks are joined.
+fn sample_response(result: Value) -> anyhow::Result<SampleResponse> {
+    let blocks = match &result["content"] {
+        Value::Array(blocks) => blocks.iter().collect(),
+        block => vec![block],
+    };
+    let text: Vec<&str> = blocks
+        .iter()
+        .filter(|b| b["type"] == "text")
+        .filter_map(|b| b["text"].as_str())
+        .collect();
+    if text.is_empty() {
+        anyhow::bail!("sampling/createMessage: the client returned no text");
+    }
+    Ok(SampleResponse {
+        text: text.join(""),
+        model: result["model"].as_str().unwrap_or_default().to_string(),
+        stop_reason: result["stopReason"].as_str().map(str::to_string),
+    })
+}
 
 /// Bridges the existing registries to the MCP JSON-RPC protocol.
 ///
 /// Each MCP session receives a clone of this struct (everything is
-/// behind `Arc`), so all sessions share the same tool set and agents.
+/// behind `Arc`), so all sessions share the same tool set and agents,
+/// including after a reload (see [`crate::reload`]).
 #[derive(Clone)]
 pub struct McpBridge {
-    config: Arc<Config>,
-    tools: Arc<ToolRegistry>,
+    live: Arc<LiveExtensions>,
     extra_tools: Arc<ToolRegistry>,
-    agents: Arc<AgentRegistry>,
     extra_agents: Arc<AgentRegistry>,
+    audit: Option<Arc<AuditLog>>,
+    lifecycle: Option<Arc<ServerLifecycle>>,
+    search_cache: Option<Arc<SearchCache>>,
+    limiter: Option<Arc<ToolLimiter>>,
 }
 
 impl McpBridge {
@@ -38,23 +184,90 @@ impl McpBridge {
         agents: Arc<AgentRegistry>,
         extra_agents: Arc<AgentRegistry>,
     ) -> Self {
+        let extensions = Extensions::new(config, tools, agents);
         Self {
-            config,
-            tools,
+            live: Arc::new(LiveExtensions::new(extensions, None)),
             extra_tools,
-            agents,
             extra_agents,
+            audit: None,
+            lifecycle: None,
+            search_cache: None,
+            limiter: None,
         }
     }
 
-    fn find_tool(&self, name: &str) -> Option<&dyn crate::traits::Tool> {
-        self.tools
-            .find(name)
-            .or_else(|| self.extra_tools.find(name))
+    /// Serve the server's reloadable tool and agent set instead of the
+    /// registries passed to [`McpBridge::new`].
+    pub fn with_live(mut self, live: Arc<LiveExtensions>) -> Self {
+        self.live = live;
+        self
+    }
+
+    /// Record every `tools/call` in the given audit log.
+    pub fn with_audit(mut self, audit: Option<Arc<AuditLog>>) -> Self {
+        self.audit = audit;
+        self
+    }
+
+    /// Count tool calls against the server's drain state, rejecting new
+    /// calls once shutdown has started.
+    pub fn with_lifecycle(mut self, lifecycle: Arc<ServerLifecycle>) -> Self {
+        self.lifecycle = Some(lifecycle);
+        self
     }
 
-    fn find_agent(&self, name: &str) -> Option<&dyn crate::agents::Agent> {
-        self.agents
+    /// Serve repeated `search` calls from the server's response cache.
+    pub fn with_search_cache(mut self, cache: Option<Arc<SearchCache>>) -> Self {
+        self.search_cache = cache;
+        self
+    }
+
+    /// Apply the server's `[server.limits]` to `tools/call`.
+    pub fn with_limiter(mut self, limiter: Option<Arc<ToolLimiter>>) -> Self {
+        self.limiter = limiter;
+        self
+    }
+
+    /// Caller identity for an MCP request: the API key fingerprint from the
+    /// HTTP headers when present, otherwise the client name sent at initialize.
+    fn caller(context: &rmcp::service::RequestContext<rmcp::RoleServer>) -> Option<String> {
+        context
+            .extensions
+            .get::<axum::http::request::Parts>()
+            .and_then(|parts| audit::caller_from_headers(&parts.headers))
+            .or_else(|| {
+                context
+                    .peer
+                    .peer_info()
+                    .map(|info| format!("client:{}", info.client_info.name))
+            })
+    }
+
+    /// Source scope of the API key the MCP request was authenticated with.
+    fn scope(
+        context: &rmcp::service::RequestContext<rmcp::RoleServer>,
+    ) -> Option<Arc<SourceScope>> {
+        context
+            .extensions
+            .get::<axum::http::request::Parts>()
+            .and_then(|parts| parts.extensions.get::<ApiKey>())
+            .and_then(|key| key.scope.clone())
+    }
+
+    fn find_tool<'a>(
+        &'a self,
+        ext: &'a Extensions,
+        name: &str,
+    ) -> Option<&'a dyn crate::traits::Tool> {
+        ext.tools.find(name).or_else(|| self.extra_tools.find(name))
+    }
+
+    fn find_agent<'a>(
+        &'a self,
+        ext: &'a Extensions,
+        name: &str,
+    ) -> Option<&'a dyn crate::agents::Agent> {
+        ext.agents
             .find(name)
             .or_else(|| self.extra_agents.find(name))
     }
@@ -73,13 +286,56 @@ impl McpBridge {
             description: Some(Cow::Owned(tool.description().to_string())),
             input_schema,
             output_schema: None,
-            annotations: Some(ToolAnnotations::new().read_only(true)),
+            annotations: Self::to_mcp_annotations(tool.annotations()),
             execution: None,
             icons: None,
             meta: None,
         }
     }
 
+    /// Convert a tool's behavior hints into rmcp `ToolAnnotations`, or
+    /// `None` when it declares none.
+    fn to_mcp_annotations(hints: crate::traits::ToolAnnotations) -> Option<ToolAnnotations> {
+        if hints.is_empty() {
+            return None;
+        }
+        Some(ToolAnnotations {
+            title: hints.title,
+            read_only_hint: hints.read_only,
+            destructive_hint: hints.destructive,
+            idempotent_hint: hints.idempotent,
+            open_world_hint: hints.open_world,
+        })
+    }
+
+    /// The content of a successful `tools/call` result: the tool's typed
+    /// parts, or its JSON result as one text part.
+    fn to_mcp_content(result: &Value) -> Vec<Content> {
+        let Some(parts) = ContentPart::parse(result) else {
+            let text = serde_json::to_string_pretty(result).unwrap_or_default();
+            return vec![Content::text(text)];
+        };
+        parts
+            .into_iter()
+            .map(|part| match part {
+                ContentPart::Text { text } => Content::text(text),
+                ContentPart::Image { data, mime_type } => Content::image(data, mime_type),
+                ContentPart::ResourceLink {
+                    uri,
+                    name,
+                    title,
+                    description,
+                    mime_type,
+                } => Content::resource_link(RawResource {
+                    title,
+                    description,
+                    mime_type,
+                    ..RawResource::new(uri, name)
+                }),
+            })
+            .collect()
+    }
+
     /// Convert a context-harness agent into an rmcp `Prompt` descriptor.
     fn to_mcp_prompt(agent: &dyn crate::agents::Agent) -> Prompt {
         let arguments: Option<Vec<PromptArgument>> = {
@@ -111,121 +367,160 @@ impl McpBridge {
     }
 }
 
-impl ServerHandler for McpBridge {
-    fn get_info(&self) -> ServerInfo {
-        ServerInfo {
-            protocol_version: ProtocolVersion::LATEST,
-            capabilities: ServerCapabilities::builder()
-                .enable_tools()
-                .enable_prompts()
-                .build(),
-            server_info: Implementation {
-                name: "context-harness".to_string(),
-                title: Some("Context Harness".to_string()),
-                version: env!("CARGO_PKG_VERSION").to_string(),
-                description: None,
-                icons: None,
-                website_url: None,
-            },
-            instructions: Some(
-                "Context Harness — local-first context ingestion and retrieval for AI tools. \
-                 Use the search tool to find relevant documents, get to retrieve a specific \
-                 document by ID, and sources to list connector status. \
-                 Agents are available as prompts — use list_prompts to discover them."
-                    .to_string(),
-            ),
-        }
-    }
-
-    // ── Tools ────────────────────────────────────────────────────────────
-
-    fn list_tools(
-        &self,
-        _request: Option<PaginatedRequestParams>,
-        _context: rmcp::service::RequestContext<rmcp::RoleServer>,
-    ) -> impl std::future::Future<Output = Result<ListToolsResult, McpError>> + Send + '_ {
-        let mut tools: Vec<Tool> = self
+impl McpBridge {
+    /// Descriptors for every registered tool (built-in, Lua, and extras),
+    /// or only the built-in ones for a scoped key.
+    fn all_tools(&self, scoped: bool) -> Vec<Tool> {
+        self.live
+            .current()
             .tools
             .tools()
             .iter()
+            .chain(self.extra_tools.tools())
+            .filter(|t| !scoped || t.is_builtin())
             .map(|t| Self::to_mcp_tool(t.as_ref()))
-            .collect();
-        for t in self.extra_tools.tools() {
-            tools.push(Self::to_mcp_tool(t.as_ref()));
-        }
-        std::future::ready(Ok(ListToolsResult::with_all_items(tools)))
+            .collect()
     }
 
-    fn get_tool(&self, name: &str) -> Option<Tool> {
-        self.find_tool(name).map(Self::to_mcp_tool)
+    /// Descriptors for every registered agent, or only the TOML ones for a
+    /// scoped key.
+    fn all_prompts(&self, scoped: bool) -> Vec<Prompt> {
+        self.live
+            .current()
+            .agents
+            .agents()
+            .iter()
+            .chain(self.extra_agents.agents())
+            .filter(|a| !scoped || a.source() == "toml")
+            .map(|a| Self::to_mcp_prompt(a.as_ref()))
+            .collect()
     }
 
-    async fn call_tool(
+    /// Run a tool for `tools/call`, shared by both transports.
+    ///
+    /// Unknown tools and arguments that fail the tool's schema are protocol
+    /// errors; failures inside the tool are reported as an error result.
+    async fn invoke_tool(
         &self,
         request: CallToolRequestParams,
-        _context: rmcp::service::RequestContext<rmcp::RoleServer>,
+        caller: Option<String>,
+        scope: Option<Arc<SourceScope>>,
+        sampler: Option<Arc<dyn Sampler>>,
     ) -> Result<CallToolResult, McpError> {
-        let tool = self.find_tool(&request.name).ok_or_else(|| {
+        let ext = self.live.current();
+        let tool = self.find_tool(&ext, &request.name).ok_or_else(|| {
             McpError::new(
                 ErrorCode::METHOD_NOT_FOUND,
                 format!("no tool registered with name: {}", request.name),
                 None,
             )
         })?;
+        if scope.is_some() && !tool.is_builtin() {
+            return Err(McpError::new(
+                ErrorCode::INVALID_REQUEST,
+                format!(
+                    "{}: tool '{}' is not available to API keys limited to some sources",
+                    auth::FORBIDDEN,
+                    request.name
+                ),
+                None,
+            ));
+        }
+
+        let _in_flight = match &self.lifecycle {
+            Some(lifecycle) => Some(lifecycle.begin_call().ok_or_else(|| {
+                McpError::new(ErrorCode::INTERNAL_ERROR, "server is shutting down", None)
+            })?),
+            None => None,
+        };
 
         let params = request
             .arguments
-            .map(serde_json::Value::Object)
-            .unwrap_or(serde_json::Value::Object(serde_json::Map::new()));
-
-        let ctx = ToolContext::new(self.config.clone());
-        match tool.execute(params, &ctx).await {
-            Ok(result) => {
-                let text = serde_json::to_string_pretty(&result).unwrap_or_default();
-                Ok(CallToolResult::success(vec![Content::text(text)]))
-            }
-            Err(e) => Ok(CallToolResult::error(vec![Content::text(e.to_string())])),
-        }
-    }
+            .map(Value::Object)
+            .unwrap_or(Value::Object(serde_json::Map::new()));
+        let validated = validate_params(&tool.parameters_schema(), &params).map_err(|e| {
+            McpError::new(
+                ErrorCode::INVALID_PARAMS,
+                format!("tool '{}': {}", request.name, e),
+                None,
+            )
+        })?;
 
-    // ── Prompts (agents) ─────────────────────────────────────────────────
+        let _permit = match &self.limiter {
+            Some(limiter) => Some(limiter.acquire(&request.name).await.map_err(|e| {
+                McpError::new(
+                    ErrorCode::INTERNAL_ERROR,
+                    format!("{}: {}", limits::RATE_LIMITED, e),
+                    Some(json!({ "retry_after_secs": e.retry_after_secs })),
+                )
+            })?),
+            None => None,
+        };
 
-    fn list_prompts(
-        &self,
-        _request: Option<PaginatedRequestParams>,
-        _context: rmcp::service::RequestContext<rmcp::RoleServer>,
-    ) -> impl std::future::Future<Output = Result<ListPromptsResult, McpError>> + Send + '_ {
-        let mut prompts: Vec<Prompt> = self
-            .agents
-            .agents()
-            .iter()
-            .map(|a| Self::to_mcp_prompt(a.as_ref()))
-            .collect();
-        for a in self.extra_agents.agents() {
-            prompts.push(Self::to_mcp_prompt(a.as_ref()));
+        let ctx = ToolContext::new(ext.config.clone())
+            .with_scope(scope)
+            .with_tools(ext.tools.clone())
+            .with_audit(self.audit.clone(), caller.clone())
+            .with_sampler(sampler);
+        let started = std::time::Instant::now();
+        let result =
+            search_cache::execute_tool(self.search_cache.as_deref(), tool, validated, &ctx).await;
+
+        if let Some(ref audit) = self.audit {
+            let entry = AuditEntry::new(
+                &request.name,
+                caller,
+                "mcp",
+                params,
+                started.elapsed(),
+                result.as_ref().err().map(|e| e.to_string()),
+            );
+            audit.record(entry).await;
+        }
+
+        match result {
+            Ok(result) => Ok(CallToolResult::success(Self::to_mcp_content(&result))),
+            Err(e) => Ok(CallToolResult::error(vec![Content::text(scrub::scrub(
+                &e.to_string(),
+            ))])),
         }
-        std::future::ready(Ok(ListPromptsResult::with_all_items(prompts)))
     }
 
-    async fn get_prompt(
+    /// Resolve an agent for `prompts/get`, shared by both transports.
+    async fn resolve_prompt(
         &self,
         request: GetPromptRequestParams,
-        _context: rmcp::service::RequestContext<rmcp::RoleServer>,
+        scope: Option<Arc<SourceScope>>,
+        sampler: Option<Arc<dyn Sampler>>,
     ) -> Result<GetPromptResult, McpError> {
-        let agent = self.find_agent(&request.name).ok_or_else(|| {
+        let ext = self.live.current();
+        let agent = self.find_agent(&ext, &request.name).ok_or_else(|| {
             McpError::new(
                 ErrorCode::METHOD_NOT_FOUND,
                 format!("no agent registered with name: {}", request.name),
                 None,
             )
         })?;
+        if scope.is_some() && agent.source() != "toml" {
+            return Err(McpError::new(
+                ErrorCode::INVALID_REQUEST,
+                format!(
+                    "{}: agent '{}' is not available to API keys limited to some sources",
+                    auth::FORBIDDEN,
+                    request.name
+                ),
+                None,
+            ));
+        }
 
         let args = request
             .arguments
-            .map(serde_json::Value::Object)
-            .unwrap_or(serde_json::Value::Object(serde_json::Map::new()));
+            .map(Value::Object)
+            .unwrap_or(Value::Object(serde_json::Map::new()));
 
-        let ctx = ToolContext::new(self.config.clone());
+        let ctx = ToolContext::new(ext.config.clone())
+            .with_scope(scope)
+            .with_sampler(sampler);
         let resolved = agent.resolve(args, &ctx).await.map_err(|e| {
             McpError::new(
                 ErrorCode::INTERNAL_ERROR,
@@ -253,9 +548,573 @@ impl ServerHandler for McpBridge {
             messages.push(PromptMessage::new_text(role, &msg.content));
         }
 
+        // Attached documents become embedded resources. A document that has
+        // since been deleted is noted instead of failing the whole prompt.
+        for id in &resolved.resources {
+            match ctx.get(id).await {
+                Ok(doc) => messages.push(PromptMessage::new_resource(
+                    PromptMessageRole::User,
+                    format!("ctx://documents/{}", doc.id),
+                    Some(doc.content_type.clone()),
+                    Some(doc.body),
+                    None,
+                    None,
+                    None,
+                )),
+                Err(e) => messages.push(PromptMessage::new_text(
+                    PromptMessageRole::User,
+                    format!("(resource {} unavailable: {})", id, e),
+                )),
+            }
+        }
+
+        // MCP prompts have no field for tool calls or sampling parameters,
+        // so hints travel as a trailing text message.
+        if let Some(hints) = prompt_hints_text(&resolved) {
+            messages.push(PromptMessage::new_text(PromptMessageRole::User, hints));
+        }
+
         Ok(GetPromptResult {
             description: Some(agent.description().to_string()),
             messages,
         })
     }
 }
+
+/// Render an agent's suggested tool calls and model hints as prompt text.
+fn prompt_hints_text(prompt: &AgentPrompt) -> Option<String> {
+    if prompt.tool_calls.is_empty() && prompt.model.is_empty() {
+        return None;
+    }
+    let mut text = String::new();
+    if !prompt.tool_calls.is_empty() {
+        text.push_str("Suggested first tool calls:\n");
+        for call in &prompt.tool_calls {
+            text.push_str(&format!("- {} {}", call.tool, call.arguments));
+            if let Some(ref reason) = call.reason {
+                text.push_str(&format!(" ({})", reason));
+            }
+            text.push('\n');
+        }
+    }
+    if !prompt.model.is_empty() {
+        text.push_str(&format!(
+            "Model parameters: {}\n",
+            serde_json::to_string(&prompt.model).unwrap_or_default()
+        ));
+    }
+    Some(text.trim_end().to_string())
+}
+
+impl ServerHandler for McpBridge {
+    fn get_info(&self) -> ServerInfo {
+        ServerInfo {
+            protocol_version: ProtocolVersion::LATEST,
+            capabilities: ServerCapabilities::builder()
+                .enable_tools()
+                .enable_prompts()
+                .build(),
+            server_info: Implementation {
+                name: "context-harness".to_string(),
+                title: Some("Context Harness".to_string()),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                description: None,
+                icons: None,
+                website_url: None,
+            },
+            instructions: Some(
+                "Context Harness — local-first context ingestion and retrieval for AI tools. \
+                 Use the search tool to find relevant documents, get to retrieve a specific \
+                 document by ID, and sources to list connector status. \
+                 Agents are available as prompts — use list_prompts to discover them."
+                    .to_string(),
+            ),
+        }
+    }
+
+    // ── Tools ────────────────────────────────────────────────────────────
+
+    fn list_tools(
+        &self,
+        _request: Option<PaginatedRequestParams>,
+        context: rmcp::service::RequestContext<rmcp::RoleServer>,
+    ) -> impl std::future::Future<Output = Result<ListToolsResult, McpError>> + Send + '_ {
+        let scoped = Self::scope(&context).is_some();
+        std::future::ready(Ok(ListToolsResult::with_all_items(self.all_tools(scoped))))
+    }
+
+    fn get_tool(&self, name: &str) -> Option<Tool> {
+        let ext = self.live.current();
+        self.find_tool(&ext, name).map(Self::to_mcp_tool)
+    }
+
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParams,
+        context: rmcp::service::RequestContext<rmcp::RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        // The session task runs outside the HTTP request's span.
+        let id = context.id.to_string();
+        let parent = context
+            .extensions
+            .get::<axum::http::request::Parts>()
+            .and_then(|parts| parts.extensions.get::<RequestTrace>());
+        let span = telemetry::mcp_request_span("tools/call", &id, parent);
+        let call = self.invoke_tool(
+            request,
+            Self::caller(&context),
+            Self::scope(&context),
+            McpSampler::for_client(&context),
+        );
+        telemetry::in_mcp_request(span, &id, call).await
+    }
+
+    // ── Prompts (agents) ─────────────────────────────────────────────────
+
+    fn list_prompts(
+        &self,
+        _request: Option<PaginatedRequestParams>,
+        context: rmcp::service::RequestContext<rmcp::RoleServer>,
+    ) -> impl std::future::Future<Output = Result<ListPromptsResult, McpError>> + Send + '_ {
+        let scoped = Self::scope(&context).is_some();
+        std::future::ready(Ok(ListPromptsResult::with_all_items(
+            self.all_prompts(scoped),
+        )))
+    }
+
+    async fn get_prompt(
+        &self,
+        request: GetPromptRequestParams,
+        context: rmcp::service::RequestContext<rmcp::RoleServer>,
+    ) -> Result<GetPromptResult, McpError> {
+        self.resolve_prompt(
+            request,
+            Self::scope(&context),
+            McpSampler::for_client(&context),
+        )
+        .await
+    }
+}
+
+// ── Plain JSON-RPC ───────────────────────────────────────────────────────
+
+/// Protocol versions a client may request at `initialize`.
+const SUPPORTED_PROTOCOL_VERSIONS: &[&str] = &["2024-11-05", "2025-03-26", "2025-06-18"];
+
+impl McpBridge {
+    /// Handle a JSON-RPC request body (a single message or a batch).
+    ///
+    /// Returns `None` when nothing needs to be sent back, i.e. the body held
+    /// only notifications or responses. `scope` is the source scope of the
+    /// caller's API key, if it has one.
+    pub async fn handle_jsonrpc(
+        &self,
+        body: &[u8],
+        caller: Option<String>,
+        scope: Option<Arc<SourceScope>>,
+    ) -> Option<Value> {
+        let message: Value = match serde_json::from_slice(body) {
+            Ok(v) => v,
+            Err(e) => {
+                return Some(error_response(
+                    Value::Null,
+                    McpError::new(ErrorCode::PARSE_ERROR, e.to_string(), None),
+                ))
+            }
+        };
+
+        match message {
+            Value::Array(batch) if batch.is_empty() => Some(error_response(
+                Value::Null,
+                McpError::new(ErrorCode::INVALID_REQUEST, "empty batch", None),
+            )),
+            Value::Array(batch) => {
+                let mut replies = Vec::new();
+                for message in batch {
+                    if let Some(reply) = self
+                        .handle_message(message, caller.clone(), scope.clone())
+                        .await
+                    {
+                        replies.push(reply);
+                    }
+                }
+                (!replies.is_empty()).then_some(Value::Array(replies))
+            }
+            message => self.handle_message(message, caller, scope).await,
+        }
+    }
+
+    /// Handle one JSON-RPC message.
+    async fn handle_message(
+        &self,
+        message: Value,
+        caller: Option<String>,
+        scope: Option<Arc<SourceScope>>,
+    ) -> Option<Value> {
+        let Value::Object(mut message) = message else {
+            return Some(error_response(
+                Value::Null,
+                McpError::new(
+                    ErrorCode::INVALID_REQUEST,
+                    "message must be an object",
+                    None,
+                ),
+            ));
+        };
+
+        let id = message.remove("id");
+        let method = message.get("method").and_then(Value::as_str);
+        if message.get("jsonrpc").and_then(Value::as_str) != Some("2.0") || method.is_none() {
+            // Replies to server-initiated requests carry no method; we never
+            // send any, so there is nothing to match them against.
+            let is_response = method.is_none()
+                && (message.contains_key("result") || message.contains_key("error"));
+            return match id {
+                Some(id) if !is_response => Some(error_response(
+                    id,
+                    McpError::new(
+                        ErrorCode::INVALID_REQUEST,
+                        "expected a JSON-RPC 2.0 request with a method",
+                        None,
+                    ),
+                )),
+                _ => None,
+            };
+        }
+
+        // Notifications (no id) never get a reply.
+        let id = id?;
+        let method = method.unwrap_or_default().to_string();
+        let params = message.remove("params").unwrap_or(Value::Null);
+
+        let request_id = match id {
+            Value::String(ref s) => s.clone(),
+            ref other => other.to_string(),
+        };
+        let span = telemetry::mcp_request_span(&method, &request_id, None);
+        let result = telemetry::in_mcp_request(
+            span,
+            &request_id,
+            self.dispatch(&method, params, caller, scope),
+        )
+        .await;
+        Some(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(e) => error_response(id, e),
+        })
+    }
+
+    /// Route a request to its handler and serialize the result.
+    async fn dispatch(
+        &self,
+        method: &str,
+        params: Value,
+        caller: Option<String>,
+        scope: Option<Arc<SourceScope>>,
+    ) -> Result<Value, McpError> {
+        let scoped = scope.is_some();
+        match method {
+            "initialize" => {
+                let mut info = to_json(self.get_info())?;
+                if let Some(requested) = params.get("protocolVersion").and_then(Value::as_str) {
+                    if SUPPORTED_PROTOCOL_VERSIONS.contains(&requested) {
+                        info["protocolVersion"] = json!(requested);
+                    }
+                }
+                Ok(info)
+            }
+            "ping" => Ok(json!({})),
+            "tools/list" => to_json(ListToolsResult::with_all_items(self.all_tools(scoped))),
+            "tools/call" => to_json(
+                self.invoke_tool(parse_params(params)?, caller, scope, None)
+                    .await?,
+            ),
+            "prompts/list" => to_json(ListPromptsResult::with_all_items(self.all_prompts(scoped))),
+            "prompts/get" => to_json(
+                self.resolve_prompt(parse_params(params)?, scope, None)
+                    .await?,
+            ),
+            other => Err(McpError::new(
+                ErrorCode::METHOD_NOT_FOUND,
+                format!("method not found: {}", other),
+                None,
+            )),
+        }
+    }
+}
+
+/// Deserialize request params, mapping failures to `-32602`.
+fn parse_params<T: serde::de::DeserializeOwned>(params: Value) -> Result<T, McpError> {
+    serde_json::from_value(params)
+        .map_err(|e| McpError::new(ErrorCode::INVALID_PARAMS, e.to_string(), None))
+}
+
+fn to_json<T: serde::Serialize>(value: T) -> Result<Value, McpError> {
+    serde_json::to_value(value)
+        .map_err(|e| McpError::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))
+}
+
+fn error_response(id: Value, mut error: McpError) -> Value {
+    error.message = Cow::Owned(scrub::scrub(&error.message));
+    json!({ "jsonrpc": "2.0", "id": id, "error": error })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bridge() -> McpBridge {
+        let config = Arc::new(Config::minimal());
+        McpBridge::new(
+            config,
+            Arc::new(ToolRegistry::with_builtins()),
+            Arc::new(ToolRegistry::new()),
+            Arc::new(AgentRegistry::new()),
+            Arc::new(AgentRegistry::new()),
+        )
+    }
+
+    async fn rpc(body: &str) -> Option<Value> {
+        bridge().handle_jsonrpc(body.as_bytes(), None, None).await
+    }
+
+    #[tokio::test]
+    async fn initialize_negotiates_protocol_version() {
+        let reply = rpc(r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"protocolVersion":"2024-11-05","capabilities":{},"clientInfo":{"name":"t","version":"1"}}}"#)
+            .await
+            .unwrap();
+        assert_eq!(reply["id"], 1);
+        assert_eq!(reply["result"]["protocolVersion"], "2024-11-05");
+        assert_eq!(reply["result"]["serverInfo"]["name"], "context-harness");
+        assert!(reply["result"]["capabilities"]["tools"].is_object());
+
+        let reply = rpc(r#"{"jsonrpc":"2.0","id":2,"method":"initialize","params":{"protocolVersion":"1999-01-01"}}"#)
+            .await
+            .unwrap();
+        assert_eq!(reply["result"]["protocolVersion"], "2025-03-26");
+    }
+
+    #[tokio::test]
+    async fn notifications_and_batches() {
+        assert!(
+            rpc(r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#)
+                .await
+                .is_none()
+        );
+
+        let reply = rpc(r#"[{"jsonrpc":"2.0","method":"notifications/initialized"},{"jsonrpc":"2.0","id":"a","method":"ping"},{"jsonrpc":"2.0","id":"b","method":"tools/list"}]"#)
+            .await
+            .unwrap();
+        let replies = reply.as_array().unwrap();
+        assert_eq!(replies.len(), 2);
+        assert_eq!(
+            replies[0],
+            json!({"jsonrpc": "2.0", "id": "a", "result": {}})
+        );
+        let names: Vec<&str> = replies[1]["result"]["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|t| t["name"].as_str())
+            .collect();
+        assert!(names.contains(&"search"), "tools: {:?}", names);
+    }
+
+    #[tokio::test]
+    async fn tools_declare_annotations_and_return_content_parts() {
+        struct Ticket;
+
+        #[async_trait]
+        impl crate::traits::Tool for Ticket {
+            fn name(&self) -> &str {
+                "create_ticket"
+            }
+            fn description(&self) -> &str {
+                "Open a ticket"
+            }
+            fn parameters_schema(&self) -> Value {
+                json!({ "type": "object", "properties": {} })
+            }
+            fn annotations(&self) -> crate::traits::ToolAnnotations {
+                crate::traits::ToolAnnotations {
+                    read_only: Some(false),
+                    destructive: Some(false),
+                    open_world: Some(true),
+                    ..Default::default()
+                }
+            }
+            async fn execute(&self, _params: Value, _ctx: &ToolContext) -> anyhow::Result<Value> {
+                Ok(json!({ "content": [
+                    { "type": "text", "text": "Created OPS-1" },
+                    { "type": "resource_link", "uri": "https://jira.example.com/browse/OPS-1", "name": "OPS-1", "mime_type": "text/html" }
+                ] }))
+            }
+        }
+
+        let mut extras = ToolRegistry::new();
+        extras.register(Box::new(Ticket));
+        let bridge = McpBridge::new(
+            Arc::new(Config::minimal()),
+            Arc::new(ToolRegistry::with_builtins()),
+            Arc::new(extras),
+            Arc::new(AgentRegistry::new()),
+            Arc::new(AgentRegistry::new()),
+        );
+        let call = |body: &'static str| bridge.handle_jsonrpc(body.as_bytes(), None, None);
+
+        let reply = call(r#"{"jsonrpc":"2.0","id":1,"method":"tools/list"}"#)
+            .await
+            .unwrap();
+        let tools = reply["result"]["tools"].as_array().unwrap();
+        let annotations =
+            |name: &str| tools.iter().find(|t| t["name"] == name).unwrap()["annotations"].clone();
+        assert_eq!(
+            annotations("search"),
+            json!({ "readOnlyHint": true, "openWorldHint": false })
+        );
+        assert_eq!(
+            annotations("create_ticket"),
+            json!({ "readOnlyHint": false, "destructiveHint": false, "openWorldHint": true })
+        );
+
+        let reply = call(
+            r#"{"jsonrpc":"2.0","id":2,"method":"tools/call","params":{"name":"create_ticket"}}"#,
+        )
+        .await
+        .unwrap();
+        let content = &reply["result"]["content"];
+        assert_eq!(
+            content[0],
+            json!({ "type": "text", "text": "Created OPS-1" })
+        );
+        assert_eq!(content[1]["type"], "resource_link");
+        assert_eq!(content[1]["uri"], "https://jira.example.com/browse/OPS-1");
+        assert_eq!(content[1]["mimeType"], "text/html");
+    }
+
+    #[tokio::test]
+    async fn protocol_errors_use_jsonrpc_codes() {
+        let code = |reply: Option<Value>| reply.unwrap()["error"]["code"].as_i64().unwrap();
+
+        assert_eq!(code(rpc("{not json").await), -32700);
+        assert_eq!(code(rpc("[]").await), -32600);
+        assert_eq!(code(rpc(r#"{"id":1,"method":"ping"}"#).await), -32600);
+        assert_eq!(
+            code(rpc(r#"{"jsonrpc":"2.0","id":1,"method":"resources/list"}"#).await),
+            -32601
+        );
+        assert_eq!(
+            code(
+                rpc(r#"{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"name":"nope"}}"#)
+                    .await
+            ),
+            -32601
+        );
+        assert_eq!(
+            code(rpc(r#"{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{}}"#).await),
+            -32602
+        );
+        assert_eq!(
+            code(rpc(r#"{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"name":"search","arguments":{}}}"#).await),
+            -32602
+        );
+    }
+
+    #[test]
+    fn sampling_uses_the_mcp_wire_format() {
+        let params = create_message_params(&SampleRequest {
+            prompt: "Classify: disk full".into(),
+            system: Some("Answer with one word.".into()),
+            max_tokens: 16,
+            temperature: None,
+            stop_sequences: vec!["\n".into()],
+            model_hints: vec!["claude".into()],
+        });
+        assert_eq!(
+            params,
+            json!({
+                "messages": [{
+                    "role": "user",
+                    "content": { "type": "text", "text": "Classify: disk full" },
+                }],
+                "maxTokens": 16,
+                "includeContext": "none",
+                "systemPrompt": "Answer with one word.",
+                "stopSequences": ["\n"],
+                "modelPreferences": { "hints": [{ "name": "claude" }] },
+            })
+        );
+
+        let reply = sample_response(json!({
+            "role": "assistant",
+            "content": { "type": "text", "text": "storage" },
+            "model": "m-1",
+            "stopReason": "endTurn",
+        }))
+        .unwrap();
+        assert_eq!(reply.text, "storage");
+        assert_eq!(reply.model, "m-1");
+        assert_eq!(reply.stop_reason.as_deref(), Some("endTurn"));
+
+        let reply = sample_response(json!({
+            "role": "assistant",
+            "content": [{ "type": "text", "text": "a" }, { "type": "text", "text": "b" }],
+            "model": "m-1",
+        }))
+        .unwrap();
+        assert_eq!(reply.text, "ab");
+        assert!(sample_response(json!({ "content": { "type": "image" } })).is_err());
+    }
+
+    #[tokio::test]
+    async fn prompt_carries_tool_call_and_model_hints() {
+        use crate::agents::{ModelHints, SuggestedToolCall, TomlAgent};
+
+        let mut agents = AgentRegistry::new();
+        agents.register(Box::new(
+            TomlAgent::new(
+                "helper".into(),
+                "Helps".into(),
+                vec!["search".into()],
+                "Be brief.".into(),
+            )
+            .with_hints(
+                vec![SuggestedToolCall {
+                    tool: "search".into(),
+                    arguments: json!({ "query": "deploy" }),
+                    reason: Some("find runbooks".into()),
+                }],
+                vec![],
+                ModelHints {
+                    temperature: Some(0.25),
+                    ..Default::default()
+                },
+            ),
+        ));
+        let bridge = McpBridge::new(
+            Arc::new(Config::minimal()),
+            Arc::new(ToolRegistry::with_builtins()),
+            Arc::new(ToolRegistry::new()),
+            Arc::new(agents),
+            Arc::new(AgentRegistry::new()),
+        );
+
+        let reply = bridge
+            .handle_jsonrpc(
+                br#"{"jsonrpc":"2.0","id":1,"method":"prompts/get","params":{"name":"helper"}}"#,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        let messages = reply["result"]["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 2);
+        let hints = messages[1]["content"]["text"].as_str().unwrap();
+        assert!(
+            hints.contains(r#"- search {"query":"deploy"} (find runbooks)"#),
+            "{}",
+            hints
+        );
+        assert!(hints.contains(r#"Model parameters: {"temperature":0.25}"#));
+    }
+}
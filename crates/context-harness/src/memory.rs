@@ -0,0 +1,313 @@
+//! Conversation memory: the `memory.append` tool.
+//!
+//! With `[memory] enabled = true`, `ctx serve mcp` registers
+//! `memory.append`, which takes a chat transcript (a Claude or Cursor
+//! session, notes an agent wrote) with optional metadata and indexes it
+//! like `ctx add` does: extraction, redaction, chunking, the keyword
+//! index, and inline embedding (see [`crate::ingest::ingest_one`]). The
+//! transcript is stored under the `[memory] source` label (default
+//! `memory`), so later sessions find it with `source:memory`.
+//!
+//! # Identity and dedup
+//!
+//! A transcript with a `session` is stored as `session:<session>`;
+//! appending the same session again replaces the document and keeps its
+//! UUID, so a client can re-send a growing transcript. Without a session,
+//! each append is a new document.
+//!
+//! With dedup on (`[memory] dedup`, or `dedup` in the call), a transcript
+//! whose text is already stored under the memory source is not added
+//! again: the call returns the stored document with `deduplicated: true`.
+//! Texts are compared by the SHA-256 of their trimmed text with line
+//! endings normalized, kept in the document metadata as `text_sha256`.
+//!
+//! # Retention
+//!
+//! With `[memory] retention_days`, every append deletes memory documents
+//! last updated longer ago than that, and reports how many in `pruned`.
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::app_store::SqliteAppStore;
+use crate::config::Config;
+use crate::ingest;
+use crate::models::SourceItem;
+use crate::search_cache;
+
+/// Characters of the first line used as the title of untitled transcripts.
+const TITLE_CHARS: usize = 80;
+
+/// A transcript passed to `memory.append`.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryEntry {
+    pub text: String,
+    pub title: Option<String>,
+    /// Conversation ID; re-appending a session replaces its document.
+    pub session: Option<String>,
+    /// Where the transcript came from, e.g. `"claude"` or `"cursor"`.
+    pub client: Option<String>,
+    pub tags: Vec<String>,
+    /// When the conversation happened. Default: now.
+    pub timestamp: Option<DateTime<Utc>>,
+    /// Extra metadata stored with the document.
+    pub metadata: Map<String, Value>,
+    /// Override of `[memory] dedup` for this call.
+    pub dedup: Option<bool>,
+}
+
+/// Result of [`append`].
+#[derive(Debug, Clone, Serialize)]
+pub struct MemoryAppend {
+    /// UUID of the document, ready for `get`.
+    pub id: String,
+    pub source: String,
+    pub source_id: String,
+    /// The text was already stored; nothing was written.
+    pub deduplicated: bool,
+    /// Memory documents deleted by `[memory] retention_days`.
+    pub pruned: usize,
+}
+
+/// Index `entry` under the memory source, then apply retention.
+///
+/// # Errors
+///
+/// Returns an error if the text is empty or larger than `[memory]
+/// max_bytes`, or ingestion fails.
+pub async fn append(config: &Config, entry: MemoryEntry) -> Result<MemoryAppend> {
+    let memory = &config.memory;
+    let text = entry.text.replace("\r\n", "\n");
+    if text.trim().is_empty() {
+        bail!("text must not be empty");
+    }
+    if text.len() > memory.max_bytes {
+        bail!(
+            "transcript is {} bytes; [memory] max_bytes allows {}",
+            text.len(),
+            memory.max_bytes
+        );
+    }
+    let text_sha256 = hex::encode(Sha256::digest(text.trim().as_bytes()));
+
+    if entry.dedup.unwrap_or(memory.dedup) {
+        if let Some((id, source_id)) = find_text(config, &text_sha256).await? {
+            let pruned = prune(config).await?;
+            return Ok(MemoryAppend {
+                id,
+                source: memory.source.clone(),
+                source_id,
+                deduplicated: true,
+                pruned,
+            });
+        }
+    }
+
+    let item = build_item(config, entry, text, &text_sha256);
+    let source_id = item.source_id.clone();
+    let id = ingest::ingest_one(config, item).await?;
+    let pruned = prune(config).await?;
+    Ok(MemoryAppend {
+        id,
+        source: memory.source.clone(),
+        source_id,
+        deduplicated: false,
+        pruned,
+    })
+}
+
+/// The [`SourceItem`] a transcript is stored as.
+fn build_item(config: &Config, entry: MemoryEntry, text: String, text_sha256: &str) -> SourceItem {
+    let source_id = match &entry.session {
+        Some(session) => format!("session:{}", session),
+        None => Uuid::new_v4().to_string(),
+    };
+    let title = entry.title.clone().or_else(|| {
+        text.lines()
+            .map(|l| l.trim().trim_start_matches('#').trim())
+            .find(|l| !l.is_empty())
+            .map(|l| l.chars().take(TITLE_CHARS).collect())
+    });
+
+    let mut metadata = entry.metadata;
+    if let Some(session) = entry.session {
+        metadata.insert("session".to_string(), Value::String(session));
+    }
+    if let Some(ref client) = entry.client {
+        metadata.insert("client".to_string(), Value::String(client.clone()));
+    }
+    if !entry.tags.is_empty() {
+        metadata.insert("tags".to_string(), Value::from(entry.tags));
+    }
+    metadata.insert(
+        "text_sha256".to_string(),
+        Value::String(text_sha256.to_string()),
+    );
+
+    let at = entry.timestamp.unwrap_or_else(Utc::now);
+    SourceItem {
+        source: config.memory.source.clone(),
+        source_id,
+        source_url: None,
+        title,
+        author: entry.client,
+        created_at: at,
+        updated_at: at,
+        content_type: "text/markdown".to_string(),
+        body: text,
+        metadata_json: Value::Object(metadata).to_string(),
+        raw_json: None,
+        raw_bytes: None,
+        parent_source_id: None,
+    }
+}
+
+/// The stored memory document with this text hash, as `(id, source_id)`.
+async fn find_text(config: &Config, text_sha256: &str) -> Result<Option<(String, String)>> {
+    let store = SqliteAppStore::connect(config).await?;
+    let row: Option<(String, String)> = sqlx::query_as(
+        "SELECT id, source_id FROM documents \
+         WHERE source = ? AND json_extract(metadata_json, '$.text_sha256') = ? LIMIT 1",
+    )
+    .bind(&config.memory.source)
+    .bind(text_sha256)
+    .fetch_optional(store.pool())
+    .await?;
+    store.close().await;
+    Ok(row)
+}
+
+/// Delete memory documents older than `[memory] retention_days`.
+async fn prune(config: &Config) -> Result<usize> {
+    let Some(days) = config.memory.retention_days else {
+        return Ok(0);
+    };
+    let cutoff = Utc::now().timestamp() - days as i64 * 86_400;
+    let store = SqliteAppStore::connect(config).await?;
+    let ids: Vec<String> =
+        sqlx::query_scalar("SELECT id FROM documents WHERE source = ? AND updated_at < ?")
+            .bind(&config.memory.source)
+            .bind(cutoff)
+            .fetch_all(store.pool())
+            .await?;
+    if !ids.is_empty() {
+        store.delete_documents(&ids).await?;
+        // Drop cached searches that could still return the pruned memories.
+        search_cache::bump_index_generation(store.pool()).await?;
+    }
+    store.close().await;
+    Ok(ids.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn memory_config(tmp: &tempfile::TempDir) -> Config {
+        let mut config = Config::minimal();
+        config.db.path = tmp.path().join("ctx.sqlite");
+        config.memory.enabled = true;
+        SqliteAppStore::initialize_config(&config).await.unwrap();
+        config
+    }
+
+    fn entry(text: &str) -> MemoryEntry {
+        MemoryEntry {
+            text: text.to_string(),
+            ..MemoryEntry::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn dedup_returns_stored_transcript() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let config = memory_config(&tmp).await;
+
+        let first = append(&config, entry("# Rollback plan\n\nUse the blue pool.\n"))
+            .await
+            .unwrap();
+        assert!(!first.deduplicated);
+        assert_eq!(first.source, "memory");
+
+        let again = append(&config, entry("# Rollback plan\r\n\r\nUse the blue pool."))
+            .await
+            .unwrap();
+        assert!(again.deduplicated);
+        assert_eq!(again.id, first.id);
+
+        let forced = append(
+            &config,
+            MemoryEntry {
+                dedup: Some(false),
+                ..entry("# Rollback plan\n\nUse the blue pool.\n")
+            },
+        )
+        .await
+        .unwrap();
+        assert!(!forced.deduplicated);
+        assert_ne!(forced.id, first.id);
+    }
+
+    #[tokio::test]
+    async fn sessions_replace_and_retention_prunes() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let mut config = memory_config(&tmp).await;
+
+        let session = |text: &str| MemoryEntry {
+            session: Some("abc".to_string()),
+            client: Some("cursor".to_string()),
+            ..entry(text)
+        };
+        let first = append(&config, session("user: hi")).await.unwrap();
+        let second = append(&config, session("user: hi\nassistant: hello"))
+            .await
+            .unwrap();
+        assert_eq!(first.id, second.id);
+        assert_eq!(second.source_id, "session:abc");
+
+        config.memory.retention_days = Some(30);
+        let old = MemoryEntry {
+            timestamp: Some(Utc::now() - chrono::Duration::days(45)),
+            ..entry("an old conversation")
+        };
+        let result = append(&config, old).await.unwrap();
+        assert_eq!(result.pruned, 1);
+
+        // Pruning invalidates cached searches.
+        config.memory.retention_days = None;
+        let stale = MemoryEntry {
+            timestamp: Some(Utc::now() - chrono::Duration::days(45)),
+            ..entry("another old conversation")
+        };
+        append(&config, stale).await.unwrap();
+        config.memory.retention_days = Some(30);
+        let store = SqliteAppStore::connect(&config).await.unwrap();
+        let before = search_cache::index_generation(store.pool()).await.unwrap();
+        assert_eq!(prune(&config).await.unwrap(), 1);
+        let after = search_cache::index_generation(store.pool()).await.unwrap();
+        store.close().await;
+        assert!(after > before);
+    }
+
+    #[test]
+    fn untitled_transcripts_use_their_first_line() {
+        let config = Config::minimal();
+        let item = build_item(
+            &config,
+            MemoryEntry {
+                tags: vec!["deploy".to_string()],
+                ..entry("")
+            },
+            "\n## Deploy checklist\nstep one".to_string(),
+            "hash",
+        );
+        assert_eq!(item.title.as_deref(), Some("Deploy checklist"));
+        let metadata: Value = serde_json::from_str(&item.metadata_json).unwrap();
+        assert_eq!(metadata["tags"][0], "deploy");
+        assert_eq!(metadata["text_sha256"], "hash");
+    }
+}
@@ -14,23 +14,27 @@
 //! │ source_id    │     │ chunk_index  │     │ dims         │
 //! │ source_url   │     │ text         │     │ created_at   │
 //! │ title        │     │ hash         │     │ hash         │
-//! │ author       │     └──────────────┘     └──────────────┘
-//! │ created_at   │
+//! │ author       │     │ metadata_json│     └──────────────┘
+//! │ created_at   │     └──────────────┘
 //! │ updated_at   │     ┌──────────────┐     ┌──────────────┐
 //! │ content_type │     │  chunks_fts  │     │chunk_vectors │
 //! │ body         │     │  (FTS5)      │     │              │
 //! │ metadata_json│     │ chunk_id     │     │ chunk_id(PK) │
 //! │ raw_json     │     │ document_id  │     │ document_id  │
 //! │ dedup_hash   │     │ text         │     │ embedding    │
-//! └──────────────┘     └──────────────┘     └──────────────┘
-//!
-//! ┌──────────────┐
-//! │ checkpoints  │
-//! │              │
-//! │ source (PK)  │
-//! │ cursor       │
-//! │ updated_at   │
+//! │ parent_      │     └──────────────┘     └──────────────┘
+//! │  source_id   │
 //! └──────────────┘
+//!
+//! ┌──────────────┐     ┌──────────────┐
+//! │ checkpoints  │     │  tool_audit  │
+//! │              │     │              │
+//! │ source (PK)  │     │ id (PK)      │
+//! │ cursor       │     │ ts, tool     │
+//! │ updated_at   │     │ caller       │
+//! │ state        │     │ params_json  │
+//! └──────────────┘     │ outcome      │
+//!                      └──────────────┘
 //! ```
 //!
 //! # Tables
@@ -38,18 +42,38 @@
 //! | Table | Purpose |
 //! |-------|---------|
 //! | `documents` | Normalized document metadata and body text |
-//! | `chunks` | Text segments with content hashes |
-//! | `checkpoints` | Incremental sync cursors per connector |
-//! | `chunks_fts` | FTS5 full-text index over chunk text (BM25) |
-//! | `embeddings` | Embedding metadata (model, dims, hash) |
+//! | `chunks` | Text segments with content hashes and chunker fingerprint |
+//! | `checkpoints` | Incremental sync cursors per connector, plus the state a connector saved (`Connector::checkpoint_state`, Lua `checkpoint.set`) |
+//! | `chunks_fts` | FTS5 full-text index over chunk text, document title, and document summary (BM25), tokenized per `[retrieval] fts_tokenizer` |
+//! | `titles_fts` | FTS5 index of document titles and markdown headings, one row per document (`ctx search --in titles`) |
+//! | `embeddings` | Embedding metadata (model, dims, text hash, chunker fingerprint) |
 //! | `chunk_vectors` | Embedding vectors stored as BLOBs |
+//! | `document_vectors` | One vector per document for routing semantic search (`embedding.document_vectors`) |
+//! | `tool_audit` | Tool invocation audit log (when `[tools.audit]` is enabled) |
+//! | `query_embedding_cache` | Cached query embeddings keyed by normalized query + model |
+//! | `query_cache_stats` | Lifetime query cache hit/miss counters |
+//! | `index_generation` | Counter bumped by sync/embed runs; invalidates the server's search cache |
+//! | `document_access` | When and how often each document was returned by search/get (LRU eviction, popularity) |
+//! | `stats_snapshots` | Daily corpus totals recorded by sync, for growth trends in `ctx stats` |
+//! | `curation_pins` | Documents pinned to the top for a query (`ctx curate pin`) |
+//! | `curation_boosts` | Per-document score multipliers (`ctx curate boost`) |
+//! | `curation_blocks` | Documents excluded from search (`ctx curate block`) |
+//! | `script_cache` | Values stored by Lua scripts with `cache.set`, per script, with optional expiry |
+//! | `raw_items` | Compressed source items as delivered by connectors in `[sync] keep_raw` (`ctx reprocess`) |
+//! | `stitch_members` | Items matching a stitch rule, kept to rebuild their stitched document |
+//! | `source_sync_status` | Last attempt, last completed sync, item count, and last error per connector (`ctx sources`) |
+//! | `sync_failures` | Items that failed to fetch, extract, or write, for `ctx sync --retry-failed` |
+//! | `jobs` | Running and finished sync and embed jobs, and pause/cancel requests (`ctx jobs`) |
 //!
 //! # Indexes
 //!
 //! - `idx_chunks_document_id` — fast chunk lookup by document
 //! - `idx_documents_source` — fast document filtering by connector
 //! - `idx_documents_updated_at` — efficient date range queries
+//! - `idx_documents_parent` — child lookup by `(source, parent_source_id)`
 //! - `idx_chunk_vectors_document_id` — fast vector lookup by document
+//! - `idx_tool_audit_ts` — newest-first audit log queries
+//! - `idx_raw_items_connector` — raw item lookup by connector for `ctx reprocess`
 //!
 //! # Idempotency
 //!
@@ -57,9 +81,12 @@
 //! objects before creation. Running `ctx init` multiple times is safe.
 
 use anyhow::Result;
+use sqlx::SqlitePool;
 
+use crate::chunk;
 use crate::config::Config;
 use crate::db;
+use crate::titles;
 
 /// Run all database migrations.
 ///
@@ -69,11 +96,24 @@ use crate::db;
 /// # Tables Created
 ///
 /// - `documents` — normalized document storage
-/// - `chunks` — text segments with content hashes
-/// - `checkpoints` — incremental sync cursors
+/// - `chunks` — text segments with content hashes and chunker fingerprint
+/// - `checkpoints` — incremental sync cursors and Lua connector state
 /// - `chunks_fts` — FTS5 full-text search index
-/// - `embeddings` — embedding metadata (model, dims, staleness hash)
+/// - `titles_fts` — FTS5 index of document titles and headings
+/// - `embeddings` — embedding metadata (model, dims, staleness hash, chunker fingerprint)
 /// - `chunk_vectors` — embedding vector BLOBs
+/// - `document_vectors` — document-level vectors for search routing
+/// - `tool_audit` — tool invocation audit log
+/// - `query_embedding_cache`, `query_cache_stats` — query embedding cache
+/// - `index_generation` — index change counter for the search response cache
+/// - `document_access` — retrieval counts and timestamps for LRU eviction and popularity
+/// - `stats_snapshots` — daily corpus totals for `ctx stats` growth trends
+/// - `script_cache` — the Lua `cache` module's values
+/// - `raw_items` — kept source items for `ctx reprocess`
+/// - `stitch_members` — items belonging to stitched documents
+/// - `source_sync_status` — per-connector sync outcomes for `ctx sources`
+/// - `sync_failures` — failed items for `ctx sync --retry-failed`
+/// - `jobs` — sync and embed jobs for `ctx jobs`
 ///
 /// # Errors
 ///
@@ -115,6 +155,7 @@ pub async fn run_migrations(config: &Config) -> Result<()> {
             chunk_index INTEGER NOT NULL,
             text TEXT NOT NULL,
             hash TEXT NOT NULL,
+            metadata_json TEXT NOT NULL DEFAULT '{}',
             UNIQUE(document_id, chunk_index),
             FOREIGN KEY (document_id) REFERENCES documents(id)
         )
@@ -123,6 +164,31 @@ pub async fn run_migrations(config: &Config) -> Result<()> {
     .execute(&pool)
     .await?;
 
+    // Chunk-level metadata (added after the initial schema)
+    add_column_if_missing(
+        &pool,
+        "chunks",
+        "metadata_json",
+        "TEXT NOT NULL DEFAULT '{}'",
+    )
+    .await?;
+
+    // Chunker fingerprint of the settings each chunk was produced under
+    // (added after the initial schema). Older chunks were produced under the
+    // global `[chunking]` settings.
+    add_column_if_missing(&pool, "chunks", "chunker", "TEXT").await?;
+    sqlx::query("UPDATE chunks SET chunker = ? WHERE chunker IS NULL")
+        .bind(chunk::config_fingerprint(&config.chunking))
+        .execute(&pool)
+        .await?;
+
+    // Parent/child document hierarchy (added after the initial schema)
+    add_column_if_missing(&pool, "documents", "parent_source_id", "TEXT").await?;
+
+    // Ingest-time summary of long documents (`[summary]`, added after the
+    // initial schema)
+    add_column_if_missing(&pool, "documents", "summary", "TEXT").await?;
+
     // Create checkpoints table
     sqlx::query(
         r#"
@@ -135,27 +201,11 @@ pub async fn run_migrations(config: &Config) -> Result<()> {
     )
     .execute(&pool)
     .await?;
+    add_column_if_missing(&pool, "checkpoints", "state", "TEXT").await?;
 
     // Create FTS5 virtual table over chunks (not idempotent natively, check first)
-    let fts_exists: bool = sqlx::query_scalar(
-        "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type='table' AND name='chunks_fts'",
-    )
-    .fetch_one(&pool)
-    .await?;
-
-    if !fts_exists {
-        sqlx::query(
-            r#"
-            CREATE VIRTUAL TABLE chunks_fts USING fts5(
-                chunk_id UNINDEXED,
-                document_id UNINDEXED,
-                text
-            )
-            "#,
-        )
-        .execute(&pool)
-        .await?;
-    }
+    ensure_fts_table(&pool, &config.retrieval.fts_tokenizer).await?;
+    ensure_titles_table(&pool, &config.retrieval.fts_tokenizer).await?;
 
     // Embeddings metadata table
     sqlx::query(
@@ -173,6 +223,14 @@ pub async fn run_migrations(config: &Config) -> Result<()> {
     .execute(&pool)
     .await?;
 
+    // Chunker fingerprint (added after the initial schema). Embeddings that
+    // predate the column are assumed to match the current chunking settings.
+    add_column_if_missing(&pool, "embeddings", "chunker", "TEXT").await?;
+    sqlx::query("UPDATE embeddings SET chunker = ? WHERE chunker IS NULL")
+        .bind(chunk::config_fingerprint(&config.chunking))
+        .execute(&pool)
+        .await?;
+
     // Chunk vectors table (stores embedding BLOBs)
     sqlx::query(
         r#"
@@ -200,12 +258,579 @@ pub async fn run_migrations(config: &Config) -> Result<()> {
     )
     .execute(&pool)
     .await?;
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_documents_parent ON documents(source, parent_source_id)",
+    )
+    .execute(&pool)
+    .await?;
     sqlx::query(
         "CREATE INDEX IF NOT EXISTS idx_chunk_vectors_document_id ON chunk_vectors(document_id)",
     )
     .execute(&pool)
     .await?;
 
+    create_tool_audit_table(&pool).await?;
+    create_query_cache_tables(&pool).await?;
+    create_index_generation_table(&pool).await?;
+    create_document_access_table(&pool).await?;
+    create_stats_snapshots_table(&pool).await?;
+    create_curation_tables(&pool).await?;
+    create_script_cache_table(&pool).await?;
+    create_raw_items_table(&pool).await?;
+    create_stitch_members_table(&pool).await?;
+    create_document_vectors_table(&pool).await?;
+    create_sync_status_table(&pool).await?;
+    create_sync_failures_table(&pool).await?;
+    create_jobs_table(&pool).await?;
+
     pool.close().await;
     Ok(())
 }
+
+/// Create `chunks_fts` with the configured tokenizer, rebuilding it if the
+/// existing index was built with a different one or predates the `title`
+/// or `summary` column.
+///
+/// A rebuild drops the FTS table and repopulates it from `chunks` and
+/// document titles and summaries, so no re-sync is needed.
+async fn ensure_fts_table(pool: &SqlitePool, tokenizer: &str) -> Result<()> {
+    let existing: Option<String> = sqlx::query_scalar(
+        "SELECT sql FROM sqlite_master WHERE type='table' AND name='chunks_fts'",
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    if let Some(sql) = &existing {
+        let has_columns = fts_has_column(sql, "title") && fts_has_column(sql, "summary");
+        if fts_tokenizer_of(sql) == tokenizer && has_columns {
+            return Ok(());
+        }
+        if has_columns {
+            tracing::info!(
+                "rebuilding keyword index with the '{}' tokenizer (was '{}')",
+                tokenizer,
+                fts_tokenizer_of(sql)
+            );
+        } else {
+            tracing::info!("rebuilding keyword index to include document titles and summaries");
+        }
+        sqlx::query("DROP TABLE chunks_fts").execute(pool).await?;
+    }
+
+    let tokenize = match tokenizer {
+        "porter" => ", tokenize = 'porter unicode61'",
+        "trigram" => ", tokenize = 'trigram'",
+        _ => "",
+    };
+    sqlx::query(&format!(
+        "CREATE VIRTUAL TABLE chunks_fts USING fts5(chunk_id UNINDEXED, document_id UNINDEXED, text, title, summary{})",
+        tokenize
+    ))
+    .execute(pool)
+    .await?;
+
+    if existing.is_some() {
+        sqlx::query(
+            r#"
+            INSERT INTO chunks_fts (chunk_id, document_id, text, title, summary)
+            SELECT c.id, c.document_id, c.text, d.title, d.summary
+            FROM chunks c LEFT JOIN documents d ON d.id = c.document_id
+            "#,
+        )
+        .execute(pool)
+        .await?;
+    }
+    Ok(())
+}
+
+/// Create `titles_fts` with the configured tokenizer and fill it from the
+/// stored documents, rebuilding it if the tokenizer changed.
+///
+/// Called from [`run_migrations`] and at the start of every sync, so
+/// databases created before the title index get it without re-running
+/// `ctx init`.
+pub async fn ensure_titles_table(pool: &SqlitePool, tokenizer: &str) -> Result<()> {
+    let existing: Option<String> = sqlx::query_scalar(
+        "SELECT sql FROM sqlite_master WHERE type='table' AND name='titles_fts'",
+    )
+    .fetch_optional(pool)
+    .await?;
+    if let Some(sql) = &existing {
+        if fts_tokenizer_of(sql) == tokenizer {
+            return Ok(());
+        }
+        sqlx::query("DROP TABLE titles_fts").execute(pool).await?;
+    }
+
+    let tokenize = match tokenizer {
+        "porter" => ", tokenize = 'porter unicode61'",
+        "trigram" => ", tokenize = 'trigram'",
+        _ => "",
+    };
+    sqlx::query(&format!(
+        "CREATE VIRTUAL TABLE titles_fts USING fts5(document_id UNINDEXED, title, headings{})",
+        tokenize
+    ))
+    .execute(pool)
+    .await?;
+
+    let mut indexed = 0usize;
+    let mut last_rowid = 0i64;
+    loop {
+        let rows: Vec<(i64, String, String, String, Option<String>, String)> = sqlx::query_as(
+            "SELECT rowid, id, source_id, content_type, title, body FROM documents \
+             WHERE rowid > ? ORDER BY rowid LIMIT 500",
+        )
+        .bind(last_rowid)
+        .fetch_all(pool)
+        .await?;
+        let Some(last) = rows.last() else { break };
+        last_rowid = last.0;
+
+        let mut tx = db::begin_write(pool).await?;
+        for (_, id, source_id, content_type, title, body) in &rows {
+            sqlx::query("INSERT INTO titles_fts (document_id, title, headings) VALUES (?, ?, ?)")
+                .bind(id)
+                .bind(title)
+                .bind(titles::headings_text(source_id, content_type, body))
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        indexed += rows.len();
+    }
+    if indexed > 0 {
+        tracing::info!(documents = indexed, "built title index");
+    }
+    Ok(())
+}
+
+/// Infer the tokenizer of an existing `chunks_fts` table from its DDL.
+pub(crate) fn fts_tokenizer_of(sql: &str) -> &'static str {
+    let sql = sql.to_lowercase();
+    if sql.contains("trigram") {
+        "trigram"
+    } else if sql.contains("porter") {
+        "porter"
+    } else {
+        "unicode61"
+    }
+}
+
+/// Whether an existing `chunks_fts` DDL has the column `name`.
+fn fts_has_column(sql: &str, name: &str) -> bool {
+    sql.to_lowercase()
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .any(|word| word == name)
+}
+
+/// Add a column to an existing table unless it is already present.
+///
+/// SQLite has no `ADD COLUMN IF NOT EXISTS`, so the column list is checked
+/// via `pragma_table_info` first. Used to upgrade databases created by
+/// earlier versions in place.
+async fn add_column_if_missing(
+    pool: &SqlitePool,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> Result<()> {
+    let exists: bool =
+        sqlx::query_scalar("SELECT COUNT(*) > 0 FROM pragma_table_info(?) WHERE name = ?")
+            .bind(table)
+            .bind(column)
+            .fetch_one(pool)
+            .await?;
+
+    if !exists {
+        sqlx::query(&format!(
+            "ALTER TABLE {} ADD COLUMN {} {}",
+            table, column, definition
+        ))
+        .execute(pool)
+        .await?;
+    }
+    Ok(())
+}
+
+/// Tables every command expects once `ctx init` has run.
+const REQUIRED_TABLES: &[&str] = &[
+    "documents",
+    "chunks",
+    "checkpoints",
+    "chunks_fts",
+    "embeddings",
+    "chunk_vectors",
+];
+
+/// Required tables that do not exist yet (empty once migrations have run).
+///
+/// Used by the server's `/readyz` endpoint.
+pub async fn missing_tables(pool: &SqlitePool) -> Result<Vec<&'static str>> {
+    let existing: Vec<String> =
+        sqlx::query_scalar("SELECT name FROM sqlite_master WHERE type = 'table'")
+            .fetch_all(pool)
+            .await?;
+    Ok(REQUIRED_TABLES
+        .iter()
+        .copied()
+        .filter(|t| !existing.iter().any(|e| e == t))
+        .collect())
+}
+
+/// Create the `tool_audit` table used by the tool invocation audit log.
+///
+/// Called from [`run_migrations`] and lazily by [`crate::audit`] when the
+/// SQLite sink is opened, so enabling auditing on an existing database does
+/// not require re-running `ctx init`.
+pub async fn create_tool_audit_table(pool: &SqlitePool) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS tool_audit (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            ts INTEGER NOT NULL,
+            tool TEXT NOT NULL,
+            caller TEXT,
+            transport TEXT NOT NULL,
+            params_json TEXT NOT NULL,
+            duration_ms INTEGER NOT NULL,
+            outcome TEXT NOT NULL,
+            error TEXT
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_tool_audit_ts ON tool_audit(ts DESC)")
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Create the query embedding cache and its hit/miss counters.
+///
+/// Called from [`run_migrations`] and lazily by [`crate::query_cache`], so
+/// existing databases pick the cache up without re-running `ctx init`.
+pub async fn create_query_cache_tables(pool: &SqlitePool) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS query_embedding_cache (
+            cache_key TEXT PRIMARY KEY,
+            model TEXT NOT NULL,
+            query TEXT NOT NULL,
+            embedding BLOB NOT NULL,
+            created_at INTEGER NOT NULL,
+            hits INTEGER NOT NULL DEFAULT 0
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS query_cache_stats (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            hits INTEGER NOT NULL DEFAULT 0,
+            misses INTEGER NOT NULL DEFAULT 0
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query("INSERT OR IGNORE INTO query_cache_stats (id, hits, misses) VALUES (1, 0, 0)")
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Create the single-row `index_generation` counter.
+///
+/// Called from [`run_migrations`] and lazily by [`crate::search_cache`].
+pub async fn create_index_generation_table(pool: &SqlitePool) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS index_generation (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            generation INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        "INSERT OR IGNORE INTO index_generation (id, generation, updated_at) VALUES (1, 0, 0)",
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Create the `document_access` table used by LRU eviction and popularity.
+///
+/// Called from [`run_migrations`] and lazily by [`crate::storage`].
+pub async fn create_document_access_table(pool: &SqlitePool) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS document_access (
+            document_id TEXT PRIMARY KEY,
+            last_retrieved_at INTEGER NOT NULL,
+            retrievals INTEGER NOT NULL DEFAULT 0
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Create the `stats_snapshots` table holding one row of totals per day.
+///
+/// Called from [`run_migrations`] and lazily by [`crate::stats`].
+pub async fn create_stats_snapshots_table(pool: &SqlitePool) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS stats_snapshots (
+            day TEXT PRIMARY KEY,
+            recorded_at INTEGER NOT NULL,
+            total_docs INTEGER NOT NULL,
+            total_chunks INTEGER NOT NULL,
+            total_embedded INTEGER NOT NULL,
+            db_size_bytes INTEGER NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Create the `curation_pins`, `curation_boosts`, and `curation_blocks` tables.
+///
+/// Called from [`run_migrations`] and lazily by [`crate::curate`].
+pub async fn create_curation_tables(pool: &SqlitePool) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS curation_pins (
+            document_id TEXT NOT NULL,
+            query TEXT NOT NULL,
+            created_at INTEGER NOT NULL,
+            PRIMARY KEY (document_id, query)
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS curation_boosts (
+            document_id TEXT PRIMARY KEY,
+            weight REAL NOT NULL,
+            created_at INTEGER NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS curation_blocks (
+            document_id TEXT PRIMARY KEY,
+            reason TEXT,
+            created_at INTEGER NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Create the `script_cache` table behind the Lua `cache` module.
+///
+/// Called from [`run_migrations`] and lazily by [`crate::script_cache`].
+pub async fn create_script_cache_table(pool: &SqlitePool) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS script_cache (
+            script TEXT NOT NULL,
+            key TEXT NOT NULL,
+            value_json TEXT NOT NULL,
+            expires_at INTEGER,
+            PRIMARY KEY (script, key)
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Create the `raw_items` table holding source items kept for `ctx reprocess`.
+///
+/// Called from [`run_migrations`] and lazily by sync and `ctx reprocess`.
+pub async fn create_raw_items_table(pool: &SqlitePool) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS raw_items (
+            source TEXT NOT NULL,
+            source_id TEXT NOT NULL,
+            connector TEXT NOT NULL,
+            updated_at INTEGER NOT NULL,
+            stored_at INTEGER NOT NULL,
+            payload BLOB NOT NULL,
+            PRIMARY KEY (source, source_id)
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_raw_items_connector ON raw_items(connector)")
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Create the `stitch_members` table holding the items of stitched
+/// documents (see [`crate::stitch`]).
+///
+/// Called from [`run_migrations`] and lazily by sync.
+pub async fn create_stitch_members_table(pool: &SqlitePool) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS stitch_members (
+            source TEXT NOT NULL,
+            source_id TEXT NOT NULL,
+            group_id TEXT NOT NULL,
+            source_url TEXT,
+            title TEXT,
+            author TEXT,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL,
+            content_type TEXT NOT NULL,
+            body TEXT NOT NULL,
+            metadata_json TEXT NOT NULL,
+            pending INTEGER NOT NULL DEFAULT 1,
+            PRIMARY KEY (source, source_id)
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_stitch_members_group ON stitch_members(source, group_id)",
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Create the `document_vectors` table holding one vector per document
+/// (see [`crate::doc_vectors`]).
+///
+/// Called from [`run_migrations`] and lazily by `ctx embed` and sync.
+pub async fn create_document_vectors_table(pool: &SqlitePool) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS document_vectors (
+            document_id TEXT PRIMARY KEY,
+            method TEXT NOT NULL,
+            model TEXT NOT NULL,
+            dims INTEGER NOT NULL,
+            created_at INTEGER NOT NULL,
+            embedding BLOB NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Create the `source_sync_status` table (see [`crate::sync_status`]).
+///
+/// Called from [`run_migrations`] and lazily by sync and `ctx sources`.
+pub async fn create_sync_status_table(pool: &SqlitePool) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS source_sync_status (
+            source TEXT PRIMARY KEY,
+            last_attempt_at INTEGER,
+            last_success_at INTEGER,
+            last_items INTEGER,
+            last_error TEXT
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Index document content hashes and duplicate links in `metadata_json`
+/// (see [`crate::content_dedup`]).
+///
+/// Called lazily by sync when `[sync] content_dedup` is not `keep-all`, so
+/// other databases don't pay for the index.
+pub async fn create_content_hash_indexes(pool: &SqlitePool) -> Result<()> {
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_documents_content_sha256 \
+         ON documents(json_extract(metadata_json, '$.content_sha256'))",
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_documents_duplicate_of \
+         ON documents(json_extract(metadata_json, '$.duplicate_of'))",
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Create the `sync_failures` table (see [`crate::sync_failures`]).
+///
+/// Called from [`run_migrations`] and lazily by sync.
+pub async fn create_sync_failures_table(pool: &SqlitePool) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS sync_failures (
+            source TEXT NOT NULL,
+            source_id TEXT NOT NULL,
+            stage TEXT NOT NULL,
+            error TEXT NOT NULL,
+            attempts INTEGER NOT NULL DEFAULT 1,
+            failed_at INTEGER NOT NULL,
+            PRIMARY KEY (source, source_id)
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Create the `jobs` table (see [`crate::jobs`]).
+///
+/// Called from [`run_migrations`] and lazily by sync, embed, and `ctx jobs`.
+pub async fn create_jobs_table(pool: &SqlitePool) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS jobs (
+            id TEXT PRIMARY KEY,
+            kind TEXT NOT NULL,
+            target TEXT NOT NULL,
+            pid INTEGER NOT NULL,
+            state TEXT NOT NULL,
+            requested TEXT,
+            started_at INTEGER NOT NULL,
+            heartbeat_at INTEGER NOT NULL,
+            finished_at INTEGER,
+            error TEXT
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
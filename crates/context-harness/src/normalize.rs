@@ -0,0 +1,305 @@
+//! Chunk text normalization: `[chunking.normalize]`.
+//!
+//! Markdown syntax, front matter, and navigation boilerplate end up in
+//! embeddings and snippets when a document body is chunked as-is. When
+//! enabled, the body is cleaned just before chunking. The stored document
+//! body is unchanged, so `ctx get` still returns the original text; chunk
+//! text, snippets, keyword search, and embeddings see the normalized text.
+//!
+//! # Steps
+//!
+//! Applied in this order, each switchable on its own:
+//!
+//! | Setting | Effect |
+//! |---------|--------|
+//! | `strip_frontmatter` | Drops a leading `---` YAML or `+++` TOML front matter block |
+//! | `remove` | Deletes every match of each regex (navigation, footers, banners) |
+//! | `strip_markdown` | Unwraps links, images, emphasis, and inline code; drops fence lines, HTML tags, blockquote markers, rules, and link definitions |
+//! | `strip_emoji` | Removes emoji, including variation selectors and joiners |
+//! | `collapse_whitespace` | Collapses runs of spaces and blank lines; trims line ends |
+//!
+//! Headings keep their `#` markers, so heading breadcrumbs and
+//! heading-aware splitting still work. Text inside fenced code blocks is
+//! left alone by `strip_markdown`, and `collapse_whitespace` keeps leading
+//! indentation. `remove` patterns run in multi-line mode, so `^` and `$`
+//! match at line boundaries.
+//!
+//! # Example
+//!
+//! ```toml
+//! [chunking.normalize]
+//! enabled = true
+//! strip_emoji = true
+//! remove = ['(?s)<nav>.*?</nav>', '^Was this page helpful\?.*$']
+//! ```
+
+use anyhow::{Context, Result};
+use regex::Regex;
+use std::sync::LazyLock;
+
+use crate::config::NormalizeConfig;
+
+static IMAGE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"!\[([^\]\n]*)\]\([^)\n]*\)").unwrap());
+static LINK: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\[([^\]\n]+)\](?:\([^)\n]*\)|\[[^\]\n]*\])").unwrap());
+static AUTOLINK: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"<((?:https?|mailto):[^>\s]+)>").unwrap());
+static HTML_TAG: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"</?[A-Za-z][A-Za-z0-9-]*(?:\s[^<>]*)?/?>|<!--.*?-->").unwrap());
+static STRONG: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(\*\*|__|~~)(\S(?:[^\n]*?\S)?)(?:\*\*|__|~~)").unwrap());
+static EMPHASIS: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\*(\S(?:[^*\n]*?\S)?)\*").unwrap());
+static INLINE_CODE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"`([^`\n]+)`").unwrap());
+static LINK_DEFINITION: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^ {0,3}\[[^\]]+\]:\s+\S+").unwrap());
+static RULE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^ {0,3}(?:(?:\* *){3,}|(?:- *){3,}|(?:_ *){3,})$").unwrap());
+static TABLE_DIVIDER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*\|?\s*:?-{3,}:?\s*(?:\|\s*:?-{3,}:?\s*)*\|?\s*$").unwrap());
+
+/// Compiled `[chunking.normalize]` settings.
+#[derive(Debug, Clone)]
+pub struct Normalizer {
+    settings: NormalizeConfig,
+    remove: Vec<Regex>,
+}
+
+impl Normalizer {
+    /// The normalizer for `settings`, or `None` when it is disabled.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a `remove` pattern is not a valid regex.
+    pub fn from_config(settings: &NormalizeConfig) -> Result<Option<Self>> {
+        if !settings.enabled {
+            return Ok(None);
+        }
+        let remove = settings
+            .remove
+            .iter()
+            .map(|pattern| {
+                Regex::new(&format!("(?m){}", pattern))
+                    .with_context(|| format!("Invalid regex in normalize.remove: {}", pattern))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Some(Self {
+            settings: settings.clone(),
+            remove,
+        }))
+    }
+
+    /// `text` with the enabled steps applied.
+    pub fn normalize(&self, text: &str) -> String {
+        let mut out = text.replace("\r\n", "\n");
+        if self.settings.strip_frontmatter {
+            out = strip_frontmatter(&out).to_string();
+        }
+        for re in &self.remove {
+            out = re.replace_all(&out, "").into_owned();
+        }
+        if self.settings.strip_markdown {
+            out = strip_markdown(&out);
+        }
+        if self.settings.strip_emoji {
+            out.retain(|c| !is_emoji(c));
+        }
+        if self.settings.collapse_whitespace {
+            out = collapse_whitespace(&out);
+        }
+        out
+    }
+}
+
+/// `text` without a leading `---` or `+++` front matter block. Text whose
+/// opening fence is never closed is returned unchanged.
+fn strip_frontmatter(text: &str) -> &str {
+    let body = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let fence = match body.lines().next().map(str::trim_end) {
+        Some("---") => "---",
+        Some("+++") => "+++",
+        _ => return text,
+    };
+    let mut offset = body.find('\n').map_or(body.len(), |i| i + 1);
+    for line in body[offset..].split_inclusive('\n') {
+        offset += line.len();
+        let line = line.trim_end();
+        if line == fence || (fence == "---" && line == "...") {
+            return &body[offset..];
+        }
+    }
+    text
+}
+
+/// `text` with markdown syntax removed outside fenced code blocks.
+fn strip_markdown(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut fence: Option<&str> = None;
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        let marker = if trimmed.starts_with("```") {
+            Some("```")
+        } else if trimmed.starts_with("~~~") {
+            Some("~~~")
+        } else {
+            None
+        };
+        match (fence, marker) {
+            (None, Some(open)) => {
+                fence = Some(open);
+                continue;
+            }
+            (Some(open), Some(close)) if open == close => {
+                fence = None;
+                continue;
+            }
+            (Some(_), _) => {
+                out.push_str(line);
+                out.push('\n');
+                continue;
+            }
+            (None, None) => {}
+        }
+
+        if LINK_DEFINITION.is_match(line) || RULE.is_match(line) || TABLE_DIVIDER.is_match(line) {
+            continue;
+        }
+        let mut line = line;
+        while let Some(rest) = line.trim_start().strip_prefix('>') {
+            line = rest.strip_prefix(' ').unwrap_or(rest);
+        }
+        let line = IMAGE.replace_all(line, "$1");
+        let line = LINK.replace_all(&line, "$1");
+        let line = AUTOLINK.replace_all(&line, "$1");
+        let line = HTML_TAG.replace_all(&line, "");
+        let line = INLINE_CODE.replace_all(&line, "$1");
+        let line = STRONG.replace_all(&line, "$2");
+        let line = EMPHASIS.replace_all(&line, "$1");
+        out.push_str(&line);
+        out.push('\n');
+    }
+    out
+}
+
+/// Whether `c` is an emoji or an emoji modifier (variation selector,
+/// zero-width joiner, keycap, tag).
+fn is_emoji(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x1F000..=0x1FAFF
+            | 0x2600..=0x27BF
+            | 0x2B00..=0x2BFF
+            | 0xFE0E..=0xFE0F
+            | 0x200D
+            | 0x20E3
+            | 0xE0020..=0xE007F
+    )
+}
+
+/// `text` with trailing spaces trimmed, inner runs of spaces and tabs
+/// collapsed to one space, and runs of blank lines collapsed to one.
+/// Leading indentation is kept.
+fn collapse_whitespace(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut blank_run = 0usize;
+    for line in text.lines() {
+        let line = line.replace('\u{a0}', " ");
+        let content = line.trim();
+        if content.is_empty() {
+            blank_run += 1;
+            continue;
+        }
+        if !out.is_empty() {
+            out.push_str(if blank_run > 0 { "\n\n" } else { "\n" });
+        }
+        blank_run = 0;
+        out.push_str(&line[..line.len() - line.trim_start().len()]);
+        let mut space = false;
+        for c in content.chars() {
+            if c == ' ' || c == '\t' {
+                space = true;
+                continue;
+            }
+            if space {
+                out.push(' ');
+                space = false;
+            }
+            out.push(c);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalizer(settings: NormalizeConfig) -> Normalizer {
+        Normalizer::from_config(&NormalizeConfig {
+            enabled: true,
+            ..settings
+        })
+        .unwrap()
+        .unwrap()
+    }
+
+    #[test]
+    fn disabled_by_default() {
+        assert!(Normalizer::from_config(&NormalizeConfig::default())
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn strips_markdown_and_frontmatter() {
+        let text = "---\ntitle: Deploy\ntags: [ops]\n---\n\n# Deploy\n\n\
+                    Run **`make deploy`** from the [release branch](https://x.test/r) \
+                    <br/> after *review*.\n\n> Note: ![diagram](d.png) first.\n\n---\n\n\
+                    ```sh\nfoo **bar** [x](y)\n```\n\n[ref]: https://x.test\n";
+        let out = normalizer(NormalizeConfig::default()).normalize(text);
+        assert_eq!(
+            out,
+            "# Deploy\n\nRun make deploy from the release branch after review.\n\n\
+             Note: diagram first.\n\nfoo **bar** [x](y)"
+        );
+    }
+
+    #[test]
+    fn removes_patterns_and_emoji() {
+        let text = "Home | Docs | Blog\nThe cache 🚀 warms\u{fe0f} up   in 5s.\n\n\n\n\
+                    Was this page helpful? Yes No\n";
+        let out = normalizer(NormalizeConfig {
+            strip_emoji: true,
+            remove: vec![
+                "^Home \\| Docs.*$".to_string(),
+                "^Was this page helpful\\?.*$".to_string(),
+            ],
+            ..NormalizeConfig::default()
+        })
+        .normalize(text);
+        assert_eq!(out, "The cache warms up in 5s.");
+    }
+
+    #[test]
+    fn keeps_indentation_and_unclosed_frontmatter() {
+        let text = "---\nnot front matter\n\n    indented  code\n";
+        let out = normalizer(NormalizeConfig {
+            strip_markdown: false,
+            ..NormalizeConfig::default()
+        })
+        .normalize(text);
+        assert_eq!(out, "---\nnot front matter\n\n    indented code");
+    }
+
+    #[test]
+    fn rejects_invalid_patterns() {
+        let err = Normalizer::from_config(&NormalizeConfig {
+            enabled: true,
+            remove: vec!["(".to_string()],
+            ..NormalizeConfig::default()
+        })
+        .unwrap_err();
+        assert!(err.to_string().contains("normalize.remove"));
+    }
+}
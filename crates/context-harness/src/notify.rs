@@ -0,0 +1,199 @@
+//! Webhook notifications for finished sync and embed runs.
+//!
+//! After `ctx sync` and `ctx embed pending|rebuild` finish (dry runs
+//! excepted), a [`RunReport`] is sent to every `[[notifications.webhooks]]`
+//! entry whose `runs` and `on` filters match:
+//!
+//! - `format = "json"` posts the report itself, plus an `event` field such
+//!   as `"sync.failure"`.
+//! - `format = "slack"` posts an incoming-webhook message (`{"text": …}`)
+//!   with the outcome, counts, and errors.
+//!
+//! A run fails when it returns an error, when any connector scan fails
+//! (sync), or when any chunk could not be embedded (embed).
+//!
+//! Delivery is best-effort: a webhook that cannot be reached or answers
+//! with an error status is logged as a warning and never changes the
+//! outcome of the run.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::config::{Config, WebhookConfig};
+use crate::secrets;
+
+/// Summary of a finished sync or embed run.
+#[derive(Debug, Clone, Serialize)]
+pub struct RunReport {
+    /// `"sync"` or `"embed"`.
+    pub run: String,
+    /// What ran: the connector argument for sync, `pending` or `rebuild` for embed.
+    pub target: String,
+    /// `"success"` or `"failure"`.
+    pub status: String,
+    pub duration_ms: u64,
+    /// RFC 3339 timestamp of when the run finished.
+    pub finished_at: String,
+    /// Named counts, e.g. `documents_upserted` or `embedded`.
+    pub counts: BTreeMap<String, u64>,
+    /// The run's error and any per-connector scan failures.
+    pub errors: Vec<String>,
+}
+
+impl RunReport {
+    /// Build a report from a run's counts, non-fatal errors, and result.
+    ///
+    /// The run is a failure if `result` is an error or `errors` is non-empty.
+    pub fn new<T>(
+        run: &str,
+        target: &str,
+        duration: Duration,
+        counts: BTreeMap<String, u64>,
+        mut errors: Vec<String>,
+        result: &Result<T>,
+    ) -> Self {
+        if let Err(e) = result {
+            errors.push(format!("{:#}", e));
+        }
+        Self {
+            run: run.to_string(),
+            target: target.to_string(),
+            status: if errors.is_empty() {
+                "success"
+            } else {
+                "failure"
+            }
+            .to_string(),
+            duration_ms: duration.as_millis() as u64,
+            finished_at: chrono::Utc::now().to_rfc3339(),
+            counts,
+            errors,
+        }
+    }
+
+    pub fn succeeded(&self) -> bool {
+        self.status == "success"
+    }
+}
+
+/// Send `report` to every matching webhook. Failures are logged, not returned.
+pub async fn send(config: &Config, report: &RunReport) {
+    let hooks: Vec<&WebhookConfig> = config
+        .notifications
+        .webhooks
+        .iter()
+        .filter(|hook| hook.runs.contains(&report.run) && hook.on.contains(&report.status))
+        .collect();
+    if hooks.is_empty() {
+        return;
+    }
+
+    let client = match reqwest::Client::builder()
+        .timeout(Duration::from_secs(config.notifications.timeout_secs))
+        .build()
+    {
+        Ok(client) => client,
+        Err(e) => {
+            tracing::warn!("notification client could not be built: {}", e);
+            return;
+        }
+    };
+
+    for hook in hooks {
+        if let Err(e) = post(&client, hook, report).await {
+            tracing::warn!("notification webhook failed: {:#}", e);
+        }
+    }
+}
+
+async fn post(client: &reqwest::Client, hook: &WebhookConfig, report: &RunReport) -> Result<()> {
+    let url = secrets::resolve(&hook.url)?;
+    let body = match hook.format.as_str() {
+        "slack" => slack_payload(report),
+        _ => json_payload(report),
+    };
+    let resp = client.post(&url).json(&body).send().await?;
+    if !resp.status().is_success() {
+        bail!("{} returned HTTP {}", redact_url(&url), resp.status());
+    }
+    Ok(())
+}
+
+/// The report with an `event` field (`"<run>.<status>"`).
+fn json_payload(report: &RunReport) -> Value {
+    let mut body = serde_json::to_value(report).unwrap_or_else(|_| json!({}));
+    body["event"] = json!(format!("{}.{}", report.run, report.status));
+    body
+}
+
+/// A Slack incoming-webhook message summarizing the report.
+fn slack_payload(report: &RunReport) -> Value {
+    let (icon, outcome) = if report.succeeded() {
+        (":white_check_mark:", "succeeded")
+    } else {
+        (":x:", "failed")
+    };
+    let mut text = format!(
+        "{} `ctx {} {}` {} in {:.1}s",
+        icon,
+        report.run,
+        report.target,
+        outcome,
+        report.duration_ms as f64 / 1000.0
+    );
+    for (name, n) in &report.counts {
+        text.push_str(&format!("\n• {}: {}", name.replace('_', " "), n));
+    }
+    if !report.errors.is_empty() {
+        text.push_str("\n*Errors:*");
+        for err in &report.errors {
+            text.push_str(&format!("\n> {}", err));
+        }
+    }
+    json!({ "text": text })
+}
+
+/// Scheme and host of a webhook URL, so logs don't leak tokens in its path.
+fn redact_url(url: &str) -> String {
+    match reqwest::Url::parse(url) {
+        Ok(u) => format!("{}://{}", u.scheme(), u.host_str().unwrap_or("")),
+        Err(_) => "webhook".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slack_payload_lists_counts_and_errors() {
+        let mut counts = BTreeMap::new();
+        counts.insert("documents_upserted".to_string(), 3);
+        let result: Result<()> = Err(anyhow::anyhow!("database is locked"));
+        let report = RunReport::new(
+            "sync",
+            "all",
+            Duration::from_millis(1500),
+            counts,
+            vec!["git:platform: clone failed".to_string()],
+            &result,
+        );
+        assert!(!report.succeeded());
+
+        let text = slack_payload(&report)["text"].as_str().unwrap().to_string();
+        assert!(text.starts_with(":x: `ctx sync all` failed in 1.5s"));
+        assert!(text.contains("• documents upserted: 3"));
+        assert!(text.contains("> git:platform: clone failed"));
+        assert!(text.contains("> database is locked"));
+
+        assert_eq!(json_payload(&report)["event"], "sync.failure");
+        assert_eq!(
+            redact_url("https://hooks.slack.com/services/T0/B0/secret"),
+            "https://hooks.slack.com"
+        );
+    }
+}
@@ -0,0 +1,224 @@
+//! Popularity from retrieval history (`ctx stats --popular`).
+//!
+//! Search and get record each document they return in `document_access`
+//! (see [`crate::storage::record_retrievals`]): how many times it was
+//! returned and when it last was. A document's popularity is the log of
+//! that count, halved for every `[retrieval] popularity_half_life_days`
+//! since the last retrieval:
+//!
+//! ```text
+//! popularity = ln(1 + retrievals) × 0.5 ^ (days since last retrieval / half_life)
+//! ```
+//!
+//! With `[retrieval] popularity_weight` above zero, search multiplies each
+//! result's score by `1 + weight × popularity` and re-sorts, before the
+//! pins, boosts, and blocks of [`crate::curate`]. A document returned 100
+//! times this week scores about 4.6, so a weight of `0.1` lifts it by 46%;
+//! one last used two half-lives ago gets a quarter of that. The boost only
+//! reorders documents that matched the query, and `--explain` shows it as
+//! `popularity=x1.46`.
+
+use anyhow::Result;
+use context_harness_core::search::format_ts_iso;
+use serde::Serialize;
+use sqlx::{Row, SqlitePool};
+use std::collections::HashMap;
+
+use crate::config::{Config, RetrievalConfig};
+use crate::db;
+use crate::search::SearchResultItem;
+
+const SECS_PER_DAY: f64 = 86_400.0;
+
+/// Popularity of a document retrieved `retrievals` times, last at
+/// `last_retrieved_at`, as of `now` (Unix seconds).
+pub fn popularity(retrievals: i64, last_retrieved_at: i64, now: i64, half_life_days: f64) -> f64 {
+    let age_days = (now - last_retrieved_at).max(0) as f64 / SECS_PER_DAY;
+    (1.0 + retrievals.max(0) as f64).ln() * 0.5f64.powf(age_days / half_life_days)
+}
+
+/// Whether `document_access` exists; it is created lazily on first use.
+async fn has_access_table(pool: &SqlitePool) -> Result<bool> {
+    Ok(sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type='table' AND name='document_access')",
+    )
+    .fetch_one(pool)
+    .await?)
+}
+
+/// Apply the popularity boost to ranked results and re-sort them.
+///
+/// Does nothing unless `[retrieval] popularity_weight` is above zero.
+pub async fn apply(
+    pool: &SqlitePool,
+    retrieval: &RetrievalConfig,
+    mut results: Vec<SearchResultItem>,
+) -> Result<Vec<SearchResultItem>> {
+    if retrieval.popularity_weight <= 0.0 || results.is_empty() || !has_access_table(pool).await? {
+        return Ok(results);
+    }
+
+    let placeholders = vec!["?"; results.len()].join(", ");
+    let sql = format!(
+        "SELECT document_id, retrievals, last_retrieved_at FROM document_access \
+         WHERE document_id IN ({})",
+        placeholders
+    );
+    let mut query = sqlx::query(&sql);
+    for item in &results {
+        query = query.bind(&item.id);
+    }
+    let now = chrono::Utc::now().timestamp();
+    let boosts: HashMap<String, f64> = query
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(|row| {
+            let p = popularity(
+                row.get("retrievals"),
+                row.get("last_retrieved_at"),
+                now,
+                retrieval.popularity_half_life_days,
+            );
+            (
+                row.get("document_id"),
+                1.0 + retrieval.popularity_weight * p,
+            )
+        })
+        .collect();
+    if boosts.is_empty() {
+        return Ok(results);
+    }
+
+    for item in &mut results {
+        if let Some(&boost) = boosts.get(&item.id) {
+            item.score *= boost;
+            if let Some(ex) = item.explain.as_mut() {
+                ex.popularity = boost;
+            }
+        }
+    }
+    results.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(b.updated_at.cmp(&a.updated_at))
+            .then(a.id.cmp(&b.id))
+    });
+    Ok(results)
+}
+
+/// A frequently retrieved document, as listed by `ctx stats --popular`.
+#[derive(Debug, Clone, Serialize)]
+pub struct PopularDocument {
+    pub id: String,
+    pub title: Option<String>,
+    pub source: String,
+    pub source_id: String,
+    /// Times search or get returned the document.
+    pub retrievals: i64,
+    /// Last retrieval (ISO 8601).
+    pub last_retrieved_at: String,
+    /// See the module docs.
+    pub popularity: f64,
+}
+
+/// The `limit` most popular documents, most popular first.
+pub async fn popular_documents(config: &Config, limit: usize) -> Result<Vec<PopularDocument>> {
+    let pool = db::connect_reader(config).await?;
+    if !has_access_table(&pool).await? {
+        db::close_reader(config, pool).await;
+        return Ok(Vec::new());
+    }
+    let rows = sqlx::query(
+        "SELECT d.id, d.title, d.source, d.source_id, a.retrievals, a.last_retrieved_at \
+         FROM document_access a JOIN documents d ON d.id = a.document_id \
+         WHERE a.retrievals > 0",
+    )
+    .fetch_all(&pool)
+    .await?;
+    db::close_reader(config, pool).await;
+
+    let now = chrono::Utc::now().timestamp();
+    let mut docs: Vec<PopularDocument> = rows
+        .into_iter()
+        .map(|row| {
+            let retrievals: i64 = row.get("retrievals");
+            let last: i64 = row.get("last_retrieved_at");
+            PopularDocument {
+                id: row.get("id"),
+                title: row.get("title"),
+                source: row.get("source"),
+                source_id: row.get("source_id"),
+                retrievals,
+                last_retrieved_at: format_ts_iso(last),
+                popularity: popularity(
+                    retrievals,
+                    last,
+                    now,
+                    config.retrieval.popularity_half_life_days,
+                ),
+            }
+        })
+        .collect();
+    docs.sort_by(|a, b| {
+        b.popularity
+            .partial_cmp(&a.popularity)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(b.retrievals.cmp(&a.retrievals))
+            .then(a.id.cmp(&b.id))
+    });
+    docs.truncate(limit);
+    Ok(docs)
+}
+
+/// Run `ctx stats --popular`: list the most popular documents.
+pub async fn run_popular(config: &Config, limit: usize, json: bool) -> Result<()> {
+    let docs = popular_documents(config, limit).await?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&docs)?);
+        return Ok(());
+    }
+    if docs.is_empty() {
+        if config.retrieval.track_access || config.storage.tracks_retrievals() {
+            println!("No retrievals recorded yet.");
+        } else {
+            println!("No retrievals recorded ([retrieval] track_access is off).");
+        }
+        return Ok(());
+    }
+
+    println!(
+        "  {:>4} {:>10} {:>10}   {:<20}   DOCUMENT",
+        "#", "POPULARITY", "RETRIEVALS", "LAST RETRIEVED"
+    );
+    println!("  {}", "-".repeat(76));
+    for (i, doc) in docs.iter().enumerate() {
+        println!(
+            "  {:>4} {:>10.2} {:>10}   {:<20}   {} / {}",
+            i + 1,
+            doc.popularity,
+            doc.retrievals,
+            doc.last_retrieved_at,
+            doc.source,
+            doc.title.as_deref().unwrap_or(&doc.source_id)
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn popularity_grows_with_retrievals_and_decays_with_age() {
+        let day = SECS_PER_DAY as i64;
+        let fresh = popularity(100, 10 * day, 10 * day, 30.0);
+        assert!((fresh - 101f64.ln()).abs() < 1e-9);
+        assert!(popularity(10, 0, 0, 30.0) < fresh);
+        let aged = popularity(100, 0, 60 * day, 30.0);
+        assert!((aged - fresh / 4.0).abs() < 1e-9);
+        assert_eq!(popularity(0, 0, 0, 30.0), 0.0);
+    }
+}
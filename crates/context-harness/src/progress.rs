@@ -28,6 +28,16 @@ pub enum SyncProgressEvent {
         connector: String,
         n: u64,
         total: u64,
+        /// Chunks embedded so far, when the sync embeds inline.
+        embedded: Option<u64>,
+    },
+    /// An item could not be fetched, extracted, or written; the sync goes on.
+    ItemFailed {
+        connector: String,
+        source_id: String,
+        /// `fetch`, `extract`, or `write`.
+        stage: String,
+        error: String,
     },
 }
 
@@ -50,14 +60,27 @@ impl SyncProgressReporter for StderrProgress {
                 connector,
                 n,
                 total,
+                embedded,
             } => {
                 let n_fmt = format_number(*n);
                 let total_fmt = format_number(*total);
+                let embedded_fmt = embedded
+                    .map(|e| format!(", {} chunks embedded", format_number(e)))
+                    .unwrap_or_default();
                 format!(
-                    "sync {}  ingesting  {} / {} items\n",
-                    connector, n_fmt, total_fmt
+                    "sync {}  ingesting  {} / {} items{}\n",
+                    connector, n_fmt, total_fmt, embedded_fmt
                 )
             }
+            SyncProgressEvent::ItemFailed {
+                connector,
+                source_id,
+                stage,
+                error,
+            } => format!(
+                "sync {}  failed  {} ({}): {}\n",
+                connector, source_id, stage, error
+            ),
         };
         let _ = std::io::stderr().lock().write_all(line.as_bytes());
         let _ = std::io::stderr().lock().flush();
@@ -79,12 +102,31 @@ impl SyncProgressReporter for JsonProgress {
                 connector,
                 n,
                 total,
+                embedded,
+            } => {
+                let mut obj = serde_json::json!({
+                    "event": "progress",
+                    "connector": connector,
+                    "phase": "ingesting",
+                    "n": n,
+                    "total": total
+                });
+                if let Some(embedded) = embedded {
+                    obj["embedded"] = serde_json::json!(embedded);
+                }
+                obj
+            }
+            SyncProgressEvent::ItemFailed {
+                connector,
+                source_id,
+                stage,
+                error,
             } => serde_json::json!({
-                "event": "progress",
+                "event": "item_failed",
                 "connector": connector,
-                "phase": "ingesting",
-                "n": n,
-                "total": total
+                "source_id": source_id,
+                "stage": stage,
+                "error": error
             }),
         };
         if let Ok(line) = serde_json::to_string(&obj) {
@@ -0,0 +1,204 @@
+//! Query embedding cache.
+//!
+//! Semantic and hybrid searches embed the query text on every call. Agents
+//! tend to repeat the same queries, so the resulting vectors are cached in
+//! SQLite (`query_embedding_cache`), keyed by the normalized query text and
+//! the embedding provider, model, and dimensions.
+//!
+//! # Normalization
+//!
+//! Queries are trimmed, lowercased, and have runs of whitespace collapsed,
+//! so `"Deploy  Runbook"` and `"deploy runbook"` share one entry.
+//!
+//! # Expiry
+//!
+//! Entries older than `[embedding] query_cache_ttl_secs` (default one day)
+//! are ignored and replaced on the next miss. Setting the TTL to `0`
+//! disables the cache entirely.
+//!
+//! # Metrics
+//!
+//! Lifetime hit/miss counters are persisted in `query_cache_stats` and shown
+//! by `ctx stats`. The server additionally exposes per-process counters on
+//! `GET /metrics`.
+
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::config::EmbeddingConfig;
+use crate::embedding::{self, blob_to_vec, vec_to_blob, EmbeddingProvider};
+use crate::migrate;
+
+static PROCESS_HITS: AtomicU64 = AtomicU64::new(0);
+static PROCESS_MISSES: AtomicU64 = AtomicU64::new(0);
+
+/// Query cache counters and size.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QueryCacheStats {
+    /// Entries currently stored, including expired ones.
+    pub entries: i64,
+    pub hits: i64,
+    pub misses: i64,
+}
+
+impl QueryCacheStats {
+    /// Percentage of lookups served from the cache (0–100).
+    pub fn hit_rate(&self) -> i64 {
+        let total = self.hits + self.misses;
+        if total > 0 {
+            self.hits * 100 / total
+        } else {
+            0
+        }
+    }
+}
+
+/// Embed a search query, reusing a cached vector when one is available.
+///
+/// Cache failures (e.g. a read-only database) fall back to calling the
+/// provider directly; they never fail the search.
+pub async fn embed_query_cached(
+    pool: &SqlitePool,
+    provider: &dyn EmbeddingProvider,
+    config: &EmbeddingConfig,
+    query: &str,
+) -> Result<Vec<f32>> {
+    if config.query_cache_ttl_secs == 0 {
+        return embedding::embed_query(provider, config, query).await;
+    }
+
+    let normalized = normalize_query(query);
+    let key = cache_key(config, provider, &normalized);
+
+    match lookup(pool, &key, config.query_cache_ttl_secs).await {
+        Ok(Some(vec)) => {
+            PROCESS_HITS.fetch_add(1, Ordering::Relaxed);
+            return Ok(vec);
+        }
+        Ok(None) => {}
+        Err(e) => {
+            tracing::warn!("query cache lookup failed: {}", e);
+            return embedding::embed_query(provider, config, query).await;
+        }
+    }
+
+    PROCESS_MISSES.fetch_add(1, Ordering::Relaxed);
+    let vec = embedding::embed_query(provider, config, query).await?;
+    if let Err(e) = store(pool, &key, provider.model_name(), &normalized, &vec).await {
+        tracing::warn!("query cache write failed: {}", e);
+    }
+    Ok(vec)
+}
+
+/// Normalize query text for cache keying.
+pub fn normalize_query(query: &str) -> String {
+    query
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+fn cache_key(
+    config: &EmbeddingConfig,
+    provider: &dyn EmbeddingProvider,
+    normalized: &str,
+) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(config.provider.as_bytes());
+    hasher.update([0]);
+    hasher.update(provider.model_name().as_bytes());
+    hasher.update([0]);
+    hasher.update(provider.dims().to_le_bytes());
+    hasher.update([0]);
+    hasher.update(embedding::query_input(config, normalized).as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+async fn lookup(pool: &SqlitePool, key: &str, ttl_secs: u64) -> Result<Option<Vec<f32>>> {
+    migrate::create_query_cache_tables(pool).await?;
+    let cutoff = chrono::Utc::now().timestamp() - ttl_secs as i64;
+
+    let blob: Option<Vec<u8>> = sqlx::query_scalar(
+        "SELECT embedding FROM query_embedding_cache WHERE cache_key = ? AND created_at >= ?",
+    )
+    .bind(key)
+    .bind(cutoff)
+    .fetch_optional(pool)
+    .await?;
+
+    let Some(blob) = blob else {
+        sqlx::query("UPDATE query_cache_stats SET misses = misses + 1 WHERE id = 1")
+            .execute(pool)
+            .await?;
+        return Ok(None);
+    };
+
+    sqlx::query("UPDATE query_embedding_cache SET hits = hits + 1 WHERE cache_key = ?")
+        .bind(key)
+        .execute(pool)
+        .await?;
+    sqlx::query("UPDATE query_cache_stats SET hits = hits + 1 WHERE id = 1")
+        .execute(pool)
+        .await?;
+    Ok(Some(blob_to_vec(&blob)))
+}
+
+async fn store(
+    pool: &SqlitePool,
+    key: &str,
+    model: &str,
+    normalized: &str,
+    vec: &[f32],
+) -> Result<()> {
+    sqlx::query(
+        "INSERT OR REPLACE INTO query_embedding_cache \
+         (cache_key, model, query, embedding, created_at, hits) VALUES (?, ?, ?, ?, ?, 0)",
+    )
+    .bind(key)
+    .bind(model)
+    .bind(normalized)
+    .bind(vec_to_blob(vec))
+    .bind(chrono::Utc::now().timestamp())
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Lifetime cache statistics persisted in the database.
+pub async fn cache_stats(pool: &SqlitePool) -> Result<QueryCacheStats> {
+    migrate::create_query_cache_tables(pool).await?;
+    let entries: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM query_embedding_cache")
+        .fetch_one(pool)
+        .await?;
+    let (hits, misses): (i64, i64) =
+        sqlx::query_as("SELECT hits, misses FROM query_cache_stats WHERE id = 1")
+            .fetch_one(pool)
+            .await?;
+    Ok(QueryCacheStats {
+        entries,
+        hits,
+        misses,
+    })
+}
+
+/// Hits and misses recorded by this process since startup.
+pub fn process_counters() -> (u64, u64) {
+    (
+        PROCESS_HITS.load(Ordering::Relaxed),
+        PROCESS_MISSES.load(Ordering::Relaxed),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_collapses_case_and_whitespace() {
+        assert_eq!(normalize_query("  Deploy\t Runbook \n"), "deploy runbook");
+        assert_eq!(normalize_query("deploy runbook"), "deploy runbook");
+    }
+}
@@ -0,0 +1,218 @@
+//! Raw source item storage for `ctx reprocess`.
+//!
+//! With `[sync] keep_raw` listing a connector, every item it delivers is
+//! kept in the `raw_items` table exactly as the connector produced it:
+//! before extraction, redaction, and chunking. `ctx reprocess` replays
+//! these items through the pipeline, so changes to extraction,
+//! `[redaction]`, or `[chunking]` can be applied without fetching from
+//! the source again.
+//!
+//! ```toml
+//! [sync]
+//! keep_raw = ["script:jira", "s3"]   # "all", a type, or an instance
+//! ```
+//!
+//! Each row holds one item as gzip-compressed JSON, keyed by the
+//! document's `(source, source_id)` and tagged with the connector that
+//! produced it. Rows are replaced by the next sync of the item and deleted
+//! with its document. Raw items are stored unredacted.
+
+use anyhow::{Context, Result};
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+use std::io::Read;
+
+use crate::config::SyncConfig;
+use crate::models::SourceItem;
+
+/// Whether items of a connector's source label are kept.
+///
+/// Entries in `[sync] keep_raw` use the same selectors as `ctx sync`:
+/// `"all"`, a connector type (`"script"`), or a named instance
+/// (`"script:jira"`).
+pub fn enabled_for(config: &SyncConfig, source_label: &str) -> bool {
+    config.keep_raw.iter().any(|c| matches(c, source_label))
+}
+
+/// Whether `selector` (`"all"`, a type, or an instance) selects `source_label`.
+pub fn matches(selector: &str, source_label: &str) -> bool {
+    let kind = source_label.split(':').next().unwrap_or(source_label);
+    selector == "all" || selector == source_label || selector == kind
+}
+
+/// The serialized form of a [`SourceItem`]; `source` and `source_id` are
+/// table columns.
+#[derive(Serialize, Deserialize)]
+struct StoredItem {
+    source_url: Option<String>,
+    title: Option<String>,
+    author: Option<String>,
+    created_at: i64,
+    updated_at: i64,
+    content_type: String,
+    body: String,
+    metadata_json: String,
+    raw_json: Option<String>,
+    /// Base64 of the bytes extraction runs on.
+    raw_bytes: Option<String>,
+    parent_source_id: Option<String>,
+}
+
+fn encode(item: &SourceItem) -> Result<Vec<u8>> {
+    let stored = StoredItem {
+        source_url: item.source_url.clone(),
+        title: item.title.clone(),
+        author: item.author.clone(),
+        created_at: item.created_at.timestamp(),
+        updated_at: item.updated_at.timestamp(),
+        content_type: item.content_type.clone(),
+        body: item.body.clone(),
+        metadata_json: item.metadata_json.clone(),
+        raw_json: item.raw_json.clone(),
+        raw_bytes: item
+            .raw_bytes
+            .as_ref()
+            .map(|b| base64::engine::general_purpose::STANDARD.encode(b)),
+        parent_source_id: item.parent_source_id.clone(),
+    };
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    serde_json::to_writer(&mut encoder, &stored)?;
+    Ok(encoder.finish()?)
+}
+
+fn decode(source: String, source_id: String, payload: &[u8]) -> Result<SourceItem> {
+    let mut json = Vec::new();
+    GzDecoder::new(payload).read_to_end(&mut json)?;
+    let stored: StoredItem = serde_json::from_slice(&json)?;
+    let timestamp = |ts: i64| DateTime::<Utc>::from_timestamp(ts, 0).unwrap_or_default();
+    Ok(SourceItem {
+        source,
+        source_id,
+        source_url: stored.source_url,
+        title: stored.title,
+        author: stored.author,
+        created_at: timestamp(stored.created_at),
+        updated_at: timestamp(stored.updated_at),
+        content_type: stored.content_type,
+        body: stored.body,
+        metadata_json: stored.metadata_json,
+        raw_json: stored.raw_json,
+        raw_bytes: stored
+            .raw_bytes
+            .map(|b| base64::engine::general_purpose::STANDARD.decode(b))
+            .transpose()?,
+        parent_source_id: stored.parent_source_id,
+    })
+}
+
+/// Keep `item` as delivered by `connector`, replacing any earlier copy.
+pub async fn store(pool: &SqlitePool, connector: &str, item: &SourceItem) -> Result<()> {
+    let payload = encode(item)?;
+    sqlx::query(
+        "INSERT INTO raw_items (source, source_id, connector, updated_at, stored_at, payload) \
+         VALUES (?, ?, ?, ?, ?, ?) \
+         ON CONFLICT(source, source_id) DO UPDATE SET \
+         connector = excluded.connector, updated_at = excluded.updated_at, \
+         stored_at = excluded.stored_at, payload = excluded.payload",
+    )
+    .bind(&item.source)
+    .bind(&item.source_id)
+    .bind(connector)
+    .bind(item.updated_at.timestamp())
+    .bind(Utc::now().timestamp())
+    .bind(payload)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Connectors with kept items, sorted.
+pub async fn connectors(pool: &SqlitePool) -> Result<Vec<String>> {
+    Ok(
+        sqlx::query_scalar("SELECT DISTINCT connector FROM raw_items ORDER BY connector")
+            .fetch_all(pool)
+            .await?,
+    )
+}
+
+/// Up to `limit` items kept for `connector`, in `(source, source_id)`
+/// order, starting after the `(source, source_id)` in `after`.
+pub async fn page(
+    pool: &SqlitePool,
+    connector: &str,
+    after: Option<&(String, String)>,
+    limit: i64,
+) -> Result<Vec<SourceItem>> {
+    let (after_source, after_id) = after.cloned().unwrap_or_default();
+    let rows = sqlx::query(
+        "SELECT source, source_id, payload FROM raw_items \
+         WHERE connector = ? AND (source > ? OR (source = ? AND source_id > ?)) \
+         ORDER BY source, source_id LIMIT ?",
+    )
+    .bind(connector)
+    .bind(&after_source)
+    .bind(&after_source)
+    .bind(&after_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    rows.into_iter()
+        .map(|row| {
+            let source: String = row.get("source");
+            let source_id: String = row.get("source_id");
+            let payload: Vec<u8> = row.get("payload");
+            decode(source.clone(), source_id.clone(), &payload)
+                .with_context(|| format!("corrupt raw item {} in {}", source_id, source))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_items_with_raw_bytes() {
+        let item = SourceItem {
+            source: "s3:docs".to_string(),
+            source_id: "guide.pdf".to_string(),
+            source_url: Some("s3://docs/guide.pdf".to_string()),
+            title: Some("guide.pdf".to_string()),
+            author: None,
+            created_at: DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+            updated_at: DateTime::from_timestamp(1_700_000_500, 0).unwrap(),
+            content_type: "application/pdf".to_string(),
+            body: String::new(),
+            metadata_json: r#"{"size":3}"#.to_string(),
+            raw_json: Some(r#"{"ETag":"abc"}"#.to_string()),
+            raw_bytes: Some(vec![0, 159, 255]),
+            parent_source_id: None,
+        };
+        let payload = encode(&item).unwrap();
+        let decoded = decode(item.source.clone(), item.source_id.clone(), &payload).unwrap();
+        assert_eq!(decoded.raw_bytes, item.raw_bytes);
+        assert_eq!(decoded.raw_json, item.raw_json);
+        assert_eq!(decoded.updated_at, item.updated_at);
+        assert_eq!(decoded.metadata_json, item.metadata_json);
+        assert_eq!(decoded.source_url, item.source_url);
+    }
+
+    #[test]
+    fn selectors_match_like_sync() {
+        let config = SyncConfig {
+            keep_raw: vec!["script".to_string(), "s3:docs".to_string()],
+            ..SyncConfig::default()
+        };
+        assert!(enabled_for(&config, "script:jira"));
+        assert!(enabled_for(&config, "s3:docs"));
+        assert!(!enabled_for(&config, "s3:runbooks"));
+        assert!(!enabled_for(&SyncConfig::default(), "git:platform"));
+        assert!(matches("all", "git:platform"));
+    }
+}
@@ -29,18 +29,39 @@
 //!     agent.lua
 //!     README.md
 //! ```
+//!
+//! # Trust
+//!
+//! Registry scripts run with network access, so connectors, tools, and
+//! agents from a registry are checked before they load (see
+//! [`ScriptTrust`]):
+//!
+//! - An entry's `sha256` must match the script file.
+//! - When the registry has a `public_key` in ctx.toml, the script must carry
+//!   a valid [minisign](https://jedisct1.github.io/minisign/) signature:
+//!   the entry's `signature` file, or `<script>.minisig` next to it.
+//! - Scripts from a git-backed registry (one with a `url`), or from one with
+//!   a `public_key`, that are not verified this way are refused unless the
+//!   registry sets `allow_unsigned = true` or `--allow-unsigned` is passed.
+//!
+//! A checksum or signature that does not match is always an error. Local
+//! registries and the project-local `.ctx/` directory are trusted as is.
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use serde::Deserialize;
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
 use crate::config::Config;
 use crate::ctx_dirs;
+use crate::scrub;
 
 const COMMUNITY_REGISTRY_URL: &str = "https://github.com/parallax-labs/ctx-registry.git";
 const DEFAULT_BRANCH: &str = "main";
+/// Name of the registry loaded from the project's `.ctx/` directory.
+const PROJECT_LOCAL: &str = "project-local";
 
 // ═══════════════════════════════════════════════════════════════════════
 // Manifest Types
@@ -102,6 +123,13 @@ pub struct ExtensionEntry {
     /// Tools this agent exposes (agents only).
     #[serde(default)]
     pub tools: Vec<String>,
+    /// Hex SHA-256 of the script file, checked before it loads.
+    #[serde(default)]
+    pub sha256: Option<String>,
+    /// Minisign signature of the script, relative to the registry root.
+    /// Defaults to `<path>.minisig`.
+    #[serde(default)]
+    pub signature: Option<String>,
 }
 
 /// A resolved extension with its absolute script path and source registry.
@@ -163,8 +191,8 @@ pub fn pull_registry(registry_dir: &Path) -> Result<()> {
     }
 
     if is_dirty(registry_dir)? {
-        eprintln!(
-            "Warning: registry at {} has uncommitted changes, skipping update",
+        tracing::warn!(
+            "registry at {} has uncommitted changes, skipping update",
             registry_dir.display()
         );
         return Ok(());
@@ -248,6 +276,8 @@ fn discover_manifest(registry_dir: &Path) -> RegistryManifest {
                                 required_config: Vec::new(),
                                 host_apis: Vec::new(),
                                 tools: Vec::new(),
+                                sha256: None,
+                                signature: None,
                             },
                         );
                     }
@@ -290,6 +320,11 @@ struct LoadedRegistry {
     path: PathBuf,
     manifest: RegistryManifest,
     readonly: bool,
+    /// Cloned from a `url`, so its scripts must be verified.
+    remote: bool,
+    /// Minisign public key its scripts must be signed with.
+    public_key: Option<String>,
+    allow_unsigned: bool,
 }
 
 impl RegistryManager {
@@ -302,10 +337,10 @@ impl RegistryManager {
         let mut registries = Vec::new();
 
         for (name, reg_cfg) in &config.registries {
-            let path = expand_tilde(&reg_cfg.path);
+            let path = ctx_dirs::expand_tilde(&reg_cfg.path);
             if !path.exists() {
-                eprintln!(
-                    "Warning: registry '{}' path does not exist: {}",
+                tracing::warn!(
+                    "registry '{}' path does not exist: {}",
                     name,
                     path.display()
                 );
@@ -315,8 +350,8 @@ impl RegistryManager {
             let manifest = match load_manifest(&path) {
                 Ok(m) => m,
                 Err(_) => {
-                    eprintln!(
-                        "Warning: registry '{}' has no valid registry.toml, using directory scan",
+                    tracing::warn!(
+                        "registry '{}' has no valid registry.toml, using directory scan",
                         name
                     );
                     discover_manifest(&path)
@@ -328,6 +363,9 @@ impl RegistryManager {
                 path,
                 manifest,
                 readonly: reg_cfg.readonly,
+                remote: reg_cfg.url.is_some(),
+                public_key: reg_cfg.public_key.clone(),
+                allow_unsigned: reg_cfg.allow_unsigned,
             });
         }
 
@@ -338,10 +376,13 @@ impl RegistryManager {
                 Err(_) => discover_manifest(&ctx_dir),
             };
             registries.push(LoadedRegistry {
-                name: "project-local".to_string(),
+                name: PROJECT_LOCAL.to_string(),
                 path: ctx_dir,
                 manifest,
                 readonly: false,
+                remote: false,
+                public_key: None,
+                allow_unsigned: false,
             });
         }
 
@@ -442,6 +483,55 @@ impl RegistryManager {
             .map(|r| r.path.as_path())
     }
 
+    /// What the script at `script` must prove before it loads: the checks
+    /// of the registry holding it, or none when no registry does.
+    ///
+    /// A script under a registry's directory that its manifest does not list
+    /// is held to the same checks, as an extension without a checksum.
+    pub fn script_trust(&self, script: &Path) -> ScriptTrust {
+        let script = canonical(script);
+        let holder = self
+            .registries
+            .iter()
+            .map(|reg| (reg, canonical(&reg.path)))
+            .filter(|(_, root)| script.starts_with(root))
+            .max_by_key(|(_, root)| root.components().count());
+        let Some((reg, root)) = holder else {
+            return ScriptTrust::default();
+        };
+
+        let listed = [
+            ("connectors", &reg.manifest.connectors),
+            ("tools", &reg.manifest.tools),
+            ("agents", &reg.manifest.agents),
+        ]
+        .into_iter()
+        .flat_map(|(kind, entries)| entries.iter().map(move |(name, e)| (kind, name, e)))
+        .find(|(_, _, entry)| canonical(&root.join(&entry.path)) == script);
+
+        let (extension, sha256, signature) = match listed {
+            Some((kind, name, entry)) => (
+                format!("{}/{}", kind, name),
+                entry.sha256.clone(),
+                entry.signature.as_ref().map(|sig| root.join(sig)),
+            ),
+            None => (script.display().to_string(), None, None),
+        };
+        let mut default_signature = script.clone().into_os_string();
+        default_signature.push(".minisig");
+
+        ScriptTrust {
+            origin: Some(TrustOrigin {
+                extension,
+                registry: reg.name.clone(),
+                sha256,
+                signature: signature.unwrap_or_else(|| PathBuf::from(default_signature)),
+                public_key: reg.public_key.clone(),
+                required: (reg.remote || reg.public_key.is_some()) && !reg.allow_unsigned,
+            }),
+        }
+    }
+
     /// Get the loaded registries (for listing/status).
     pub fn registries(&self) -> Vec<RegistryInfo> {
         self.registries
@@ -470,6 +560,143 @@ pub struct RegistryInfo {
     pub agents: usize,
 }
 
+// ═══════════════════════════════════════════════════════════════════════
+// Trust
+// ═══════════════════════════════════════════════════════════════════════
+
+/// Checks a script must pass before it loads, from the registry it belongs
+/// to (see [`RegistryManager::script_trust`]). The default, for scripts
+/// outside every registry, passes everything.
+#[derive(Debug, Clone, Default)]
+pub struct ScriptTrust {
+    origin: Option<TrustOrigin>,
+}
+
+#[derive(Debug, Clone)]
+struct TrustOrigin {
+    /// `kind/name`, or the script path when the manifest does not list it.
+    extension: String,
+    registry: String,
+    sha256: Option<String>,
+    signature: PathBuf,
+    public_key: Option<String>,
+    /// Refuse the script unless its checksum or signature verifies.
+    required: bool,
+}
+
+impl ScriptTrust {
+    /// Check `script`, the contents of the script file, against its
+    /// registry's checksum and signature.
+    ///
+    /// # Errors
+    ///
+    /// Fails when the checksum or signature does not match, or when the
+    /// registry requires verification and the script has neither.
+    pub fn verify(&self, script: &[u8]) -> Result<()> {
+        let Some(origin) = &self.origin else {
+            return Ok(());
+        };
+        let subject = format!("{} from registry '{}'", origin.extension, origin.registry);
+
+        let mut verified = false;
+        if let Some(expected) = &origin.sha256 {
+            let actual = hex::encode(Sha256::digest(script));
+            if !actual.eq_ignore_ascii_case(expected.trim()) {
+                bail!(
+                    "{} failed its checksum: registry.toml lists sha256 {}, but the script \
+                     hashes to {}. It changed after the registry was published; reinstall or \
+                     update the registry before running it.",
+                    subject,
+                    expected.trim(),
+                    actual
+                );
+            }
+            verified = origin.public_key.is_none();
+        }
+        if let Some(key) = &origin.public_key {
+            if origin.signature.exists() {
+                std::fs::read_to_string(&origin.signature)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|signature| verify_minisign(key, &signature, script))
+                    .with_context(|| {
+                        format!(
+                            "{} has an invalid signature ({})",
+                            subject,
+                            origin.signature.display()
+                        )
+                    })?;
+                verified = true;
+            }
+        }
+        if verified || !origin.required {
+            return Ok(());
+        }
+
+        let missing = if origin.public_key.is_some() {
+            format!(
+                "is not signed: registry '{}' has a public_key, but there is no signature at {}",
+                origin.registry,
+                origin.signature.display()
+            )
+        } else {
+            "has no sha256 in registry.toml".to_string()
+        };
+        bail!(
+            "{} {}. Pass --allow-unsigned, or set allow_unsigned = true under \
+             [registries.{}], to run unverified scripts from it.",
+            subject,
+            missing,
+            origin.registry
+        )
+    }
+
+    /// Whether the script was installed from a configured registry, rather
+    /// than written in place or kept in the project-local `.ctx/`.
+    pub fn installed(&self) -> bool {
+        self.origin
+            .as_ref()
+            .is_some_and(|origin| origin.registry != PROJECT_LOCAL)
+    }
+
+    /// [`verify`](Self::verify) the file at `path`.
+    pub fn verify_file(&self, path: &Path) -> Result<()> {
+        if self.origin.is_none() {
+            return Ok(());
+        }
+        let script = std::fs::read(path)
+            .with_context(|| format!("Failed to read script: {}", path.display()))?;
+        self.verify(&script)
+    }
+}
+
+/// The trust checks for `script` under the registries in `config`.
+pub fn script_trust(config: &Config, script: &Path) -> ScriptTrust {
+    RegistryManager::from_config(config).script_trust(script)
+}
+
+/// Parse a minisign `public_key`: the base64 key, or the contents of a
+/// `.pub` file.
+pub fn minisign_public_key(public_key: &str) -> Result<minisign_verify::PublicKey> {
+    let key_line = public_key.trim().lines().last().unwrap_or_default().trim();
+    minisign_verify::PublicKey::from_base64(key_line)
+        .map_err(|e| anyhow::anyhow!("not a minisign public key: {}", e))
+}
+
+/// Verify a minisign `signature` (the contents of a `.minisig` file) over
+/// `bytes` with `public_key`. Shared by script trust and `ctx update`.
+pub fn verify_minisign(public_key: &str, signature: &str, bytes: &[u8]) -> Result<()> {
+    let key = minisign_public_key(public_key)?;
+    let signature = minisign_verify::Signature::decode(signature)
+        .map_err(|e| anyhow::anyhow!("unreadable signature: {}", e))?;
+    key.verify(bytes, &signature, false)
+        .map_err(|e| anyhow::anyhow!("{}", e))
+}
+
+/// `path` with symlinks and `..` resolved, or as given when it does not exist.
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
 // ═══════════════════════════════════════════════════════════════════════
 // .ctx/ Directory Discovery
 // ═══════════════════════════════════════════════════════════════════════
@@ -573,7 +800,7 @@ pub fn cmd_install(config: &Config, name: Option<&str>) -> Result<()> {
             }
         };
 
-        let target = expand_tilde(&reg_cfg.path);
+        let target = ctx_dirs::expand_tilde(&reg_cfg.path);
         if target.exists() {
             println!(
                 "Registry '{}' already installed at {}",
@@ -623,7 +850,7 @@ pub fn cmd_update(config: &Config, name: Option<&str>) -> Result<()> {
             }
         }
 
-        let path = expand_tilde(&reg_cfg.path);
+        let path = ctx_dirs::expand_tilde(&reg_cfg.path);
         if !path.exists() {
             eprintln!(
                 "Registry '{}' not installed at {}. Run `ctx registry install` first.",
@@ -647,7 +874,11 @@ pub fn cmd_update(config: &Config, name: Option<&str>) -> Result<()> {
                 updated += 1;
             }
             Err(e) => {
-                eprintln!("  Failed to update '{}': {}", reg_name, e);
+                eprintln!(
+                    "  Failed to update '{}': {}",
+                    reg_name,
+                    scrub::scrub(&e.to_string())
+                );
             }
         }
     }
@@ -932,22 +1163,6 @@ pub fn cmd_init_community(config_path: &Path) -> Result<()> {
 // Utilities
 // ═══════════════════════════════════════════════════════════════════════
 
-/// Expand `~` at the start of a path to the user's home directory.
-fn expand_tilde(path: &Path) -> PathBuf {
-    let s = path.to_string_lossy();
-    if s.starts_with("~/") || s == "~" {
-        if let Some(home) = home_dir() {
-            return home.join(s.strip_prefix("~/").unwrap_or(""));
-        }
-    }
-    path.to_path_buf()
-}
-
-/// Get the user's home directory.
-fn home_dir() -> Option<PathBuf> {
-    std::env::var_os("HOME").map(PathBuf::from)
-}
-
 /// Recursively copy a directory and all its contents.
 fn copy_dir_recursive(src: &Path, dst: &Path) -> Result<()> {
     std::fs::create_dir_all(dst)?;
@@ -1038,19 +1253,6 @@ tools = ["search", "get"]
         assert_eq!(manifest.agents.len(), 0);
     }
 
-    #[test]
-    fn expand_tilde_works() {
-        let expanded = expand_tilde(Path::new("~/foo/bar"));
-        assert!(!expanded.to_string_lossy().starts_with("~"));
-        assert!(expanded.to_string_lossy().ends_with("foo/bar"));
-    }
-
-    #[test]
-    fn expand_tilde_noop_for_absolute() {
-        let path = Path::new("/usr/local/bin");
-        assert_eq!(expand_tilde(path), path.to_path_buf());
-    }
-
     #[test]
     fn discover_manifest_empty_dir() {
         let dir = tempfile::tempdir().unwrap();
@@ -1111,6 +1313,8 @@ tools = ["search", "get"]
                         path: dir_a.path().to_path_buf(),
                         readonly: true,
                         auto_update: false,
+                        public_key: None,
+                        allow_unsigned: false,
                     },
                 );
                 m.insert(
@@ -1121,6 +1325,8 @@ tools = ["search", "get"]
                         path: dir_b.path().to_path_buf(),
                         readonly: false,
                         auto_update: false,
+                        public_key: None,
+                        allow_unsigned: false,
                     },
                 );
                 m
@@ -1137,6 +1343,126 @@ tools = ["search", "get"]
         assert_eq!(connectors[0].name, "jira");
     }
 
+    /// Minisign key and signature of `-- jira\n`, for the signature tests.
+    const PUBLIC_KEY: &str = "RWS43i3famiUEdJhuANcZmHBo55NvbZFTgTsMofR6OgJK6kJ6jnnftpV";
+    const SIGNATURE: &str = "untrusted comment: signature from minisign secret key
+RUS43i3famiUEX2QFr39siJ7tCLd5w7rs7wUYsxLUbKDCG5JK5bOHi2bpuMuGaBX45t65x0T2glGlGh9Ivl+v5K93CxytEBHhw8=
+trusted comment: timestamp:1760000000\tfile:connector.lua\thashed
+CC2VabeQBQktgWUIiRz9NKXdL//wVJoyCzdtbiI0/7Ntz1wMLBo3i5Q0ZY9byq+OlBEB4jJ5lL2ZpfDkaTwgBA==
+";
+
+    fn registry_at(
+        dir: &Path,
+        url: Option<&str>,
+        public_key: Option<&str>,
+        allow_unsigned: bool,
+    ) -> RegistryManager {
+        let config = Config {
+            registries: HashMap::from([(
+                "acme".to_string(),
+                RegistryConfig {
+                    url: url.map(String::from),
+                    branch: None,
+                    path: dir.to_path_buf(),
+                    readonly: true,
+                    auto_update: false,
+                    public_key: public_key.map(String::from),
+                    allow_unsigned,
+                },
+            )]),
+            ..Config::minimal()
+        };
+        RegistryManager::from_config(&config)
+    }
+
+    fn write_script(dir: &Path, rel: &str, body: &str) -> PathBuf {
+        let path = dir.join(rel);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, body).unwrap();
+        path
+    }
+
+    #[test]
+    fn script_trust_checks_checksums() {
+        let dir = tempfile::tempdir().unwrap();
+        let jira = write_script(dir.path(), "connectors/jira/connector.lua", "-- jira\n");
+        let summarize = write_script(dir.path(), "tools/summarize/tool.lua", "-- summarize\n");
+        let manifest = format!(
+            "[connectors.jira]\npath = \"connectors/jira/connector.lua\"\nsha256 = \"{}\"\n\n\
+             [tools.summarize]\npath = \"tools/summarize/tool.lua\"\n",
+            hex::encode(Sha256::digest(b"-- jira\n"))
+        );
+        std::fs::write(dir.path().join("registry.toml"), manifest).unwrap();
+        let url = Some("https://example.com/registry.git");
+
+        let mgr = registry_at(dir.path(), url, None, false);
+        mgr.script_trust(&jira).verify(b"-- jira\n").unwrap();
+        let err = mgr
+            .script_trust(&jira)
+            .verify(b"-- tampered\n")
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("connectors/jira from registry 'acme' failed its checksum"),
+            "{}",
+            err
+        );
+        let err = mgr
+            .script_trust(&summarize)
+            .verify(b"-- summarize\n")
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("tools/summarize from registry 'acme' has no sha256")
+                && err.contains("--allow-unsigned"),
+            "{}",
+            err
+        );
+
+        // allow_unsigned admits unverified scripts, never mismatched ones.
+        let mgr = registry_at(dir.path(), url, None, true);
+        mgr.script_trust(&summarize)
+            .verify(b"-- summarize\n")
+            .unwrap();
+        assert!(mgr.script_trust(&jira).verify(b"-- tampered\n").is_err());
+
+        // Local registries and scripts outside registries are trusted.
+        let mgr = registry_at(dir.path(), None, None, false);
+        mgr.script_trust(&summarize)
+            .verify(b"-- summarize\n")
+            .unwrap();
+        ScriptTrust::default().verify(b"anything").unwrap();
+    }
+
+    #[test]
+    fn script_trust_verifies_minisign_signatures() {
+        let dir = tempfile::tempdir().unwrap();
+        let jira = write_script(dir.path(), "connectors/jira/connector.lua", "-- jira\n");
+        let signature = write_script(
+            dir.path(),
+            "connectors/jira/connector.lua.minisig",
+            SIGNATURE,
+        );
+        std::fs::write(
+            dir.path().join("registry.toml"),
+            "[connectors.jira]\npath = \"connectors/jira/connector.lua\"\n",
+        )
+        .unwrap();
+
+        let trust = registry_at(dir.path(), None, Some(PUBLIC_KEY), false).script_trust(&jira);
+        trust.verify(b"-- jira\n").unwrap();
+        let err = format!("{:#}", trust.verify(b"-- evil\n").unwrap_err());
+        assert!(err.contains("has an invalid signature"), "{}", err);
+
+        std::fs::remove_file(signature).unwrap();
+        let err = trust.verify(b"-- jira\n").unwrap_err().to_string();
+        assert!(
+            err.contains("connectors/jira from registry 'acme' is not signed"),
+            "{}",
+            err
+        );
+    }
+
     #[test]
     fn find_ctx_dir_from_nested() {
         let root = tempfile::tempdir().unwrap();
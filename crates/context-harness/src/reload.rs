@@ -0,0 +1,418 @@
+//! Runtime reload of tools, agents, and connectors.
+//!
+//! The MCP server builds its tool and agent registries from the config file,
+//! Lua scripts, and extension registries at startup. [`LiveExtensions`] holds
+//! that working set behind a lock so it can be rebuilt without restarting
+//! the server — via `POST /admin/reload`, or automatically when
+//! `[server] watch = true` and a watched file changes.
+//!
+//! A reload re-reads the config file (when the server was started with a
+//! [`ConfigLoader`]), re-discovers registry extensions, and loads every tool,
+//! agent, and connector script. The new set replaces the old one only if all
+//! of that succeeds; otherwise the error is reported and the server keeps
+//! serving the previous set. Requests already running finish against the set
+//! they started with.
+//!
+//! # What is reloaded
+//!
+//! - `[tools.script.*]`, `[agents.inline.*]`, `[agents.script.*]`, and
+//!   registry tools and agents
+//! - `[connectors.*]` (used by the `sources` tool; script connectors must load)
+//! - `[retrieval]`, `[embedding]`, and other settings read by tool calls
+//!
+//! `[server]`, `[db]`, and `[tools.audit]` are read once at startup; a reload
+//! that changes them logs a warning and the change takes effect on restart.
+//!
+//! # Watching
+//!
+//! ```toml
+//! [server]
+//! watch = true
+//! watch_interval_secs = 2
+//! ```
+//!
+//! The watcher polls the modification times of the config file, every
+//! configured tool, agent, and connector script, and every registry
+//! manifest and extension script, and reloads when any of them changes.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+
+use crate::agent_script::{load_agent_definitions, LuaAgentAdapter};
+use crate::agents::AgentRegistry;
+use crate::config::{self, Config};
+use crate::connector_script;
+use crate::ctx_dirs;
+use crate::lua_runtime::EnvAccess;
+use crate::registry::RegistryManager;
+use crate::search_cache::SearchCache;
+use crate::tool_script::{load_tool_definitions, LuaToolAdapter};
+use crate::traits::{ConnectorRegistry, MemoryAppendTool, ToolRegistry};
+
+/// Re-reads the configuration for a reload.
+#[derive(Clone)]
+pub struct ConfigLoader {
+    path: Option<PathBuf>,
+    load: Arc<dyn Fn() -> Result<Config> + Send + Sync>,
+}
+
+impl ConfigLoader {
+    /// A loader calling `load`; `path` is the config file to watch, if any.
+    pub fn new(
+        path: Option<PathBuf>,
+        load: impl Fn() -> Result<Config> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            path,
+            load: Arc::new(load),
+        }
+    }
+
+    /// A loader reading the config file at `path`.
+    #[allow(dead_code)]
+    pub fn from_path(path: PathBuf) -> Self {
+        let file = path.clone();
+        Self::new(Some(path), move || config::load_config(&file))
+    }
+
+    /// The config file, if known.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+}
+
+/// One generation of configuration-defined tools and agents.
+pub struct Extensions {
+    /// Configuration the tools and agents were built from.
+    pub config: Arc<Config>,
+    /// Built-in, Lua, and registry tools.
+    pub tools: Arc<ToolRegistry>,
+    /// TOML, Lua, and registry agents.
+    pub agents: Arc<AgentRegistry>,
+    /// Configured connector instances.
+    pub connectors: usize,
+    /// Incremented by every successful reload; `0` at startup.
+    pub generation: u64,
+}
+
+impl Extensions {
+    /// Wrap already-built registries (generation `0`).
+    pub fn new(config: Arc<Config>, tools: Arc<ToolRegistry>, agents: Arc<AgentRegistry>) -> Self {
+        let connectors = ConnectorRegistry::from_config(&config).len();
+        Self {
+            config,
+            tools,
+            agents,
+            connectors,
+            generation: 0,
+        }
+    }
+
+    /// Build tools and agents from `config`, loading every script.
+    ///
+    /// Fails if a configured tool or agent script cannot be loaded. Registry extensions that fail to load are skipped with a
+    /// warning, as at startup.
+    pub fn load(config: Config) -> Result<Self> {
+        let config = Arc::new(config);
+
+        // ── Tools ──
+        let mut tools = ToolRegistry::with_builtins();
+        if config.memory.enabled {
+            tools.register(Box::new(MemoryAppendTool));
+        }
+
+        let lua_defs = load_tool_definitions(&config)?;
+        let configured_tool_names: Vec<String> = lua_defs.iter().map(|d| d.name.clone()).collect();
+        for def in lua_defs {
+            tools.register(Box::new(LuaToolAdapter::new(def, config.clone())));
+        }
+
+        // Auto-discover tools from registries (lower precedence than config)
+        let reg_mgr = RegistryManager::from_config(&config);
+        for ext in reg_mgr.list_tools() {
+            if configured_tool_names.iter().any(|n| n == &ext.name) {
+                continue;
+            }
+            if !ext.script_path.exists() {
+                continue;
+            }
+            let tool_cfg = crate::config::ScriptToolConfig {
+                path: ext.script_path.clone(),
+                timeout: 30,
+                env_allowlist: None,
+                extra: toml::Table::new(),
+            };
+            match crate::tool_script::load_single_tool(
+                &ext.name,
+                &tool_cfg,
+                &reg_mgr.script_trust(&ext.script_path),
+            ) {
+                Ok(def) => {
+                    tools.register(Box::new(LuaToolAdapter::new(def, config.clone())));
+                }
+                Err(e) => {
+                    tracing::warn!("failed to load registry tool '{}': {}", ext.name, e);
+                }
+            }
+        }
+
+        // ── Agents ──
+        let mut agents = AgentRegistry::from_config(&config)?;
+
+        let lua_agents = load_agent_definitions(&config)?;
+        let configured_agent_names: Vec<String> =
+            lua_agents.iter().map(|d| d.name.clone()).collect();
+        for def in lua_agents {
+            agents.register(Box::new(LuaAgentAdapter::new(def, config.clone())));
+        }
+
+        // Auto-discover agents from registries (lower precedence than config)
+        for ext in reg_mgr.list_agents() {
+            if configured_agent_names.iter().any(|n| n == &ext.name) {
+                continue;
+            }
+            if !ext.script_path.exists() {
+                continue;
+            }
+            if ext.script_path.extension().is_some_and(|e| e == "lua") {
+                let agent_cfg = crate::config::ScriptAgentConfig {
+                    path: ext.script_path.clone(),
+                    timeout: 30,
+                    env_allowlist: None,
+                    extra: toml::Table::new(),
+                };
+                match crate::agent_script::load_single_agent(
+                    &ext.name,
+                    &agent_cfg,
+                    &reg_mgr.script_trust(&ext.script_path),
+                ) {
+                    Ok(def) => {
+                        agents.register(Box::new(LuaAgentAdapter::new(def, config.clone())));
+                    }
+                    Err(e) => {
+                        tracing::warn!("failed to load registry agent '{}': {}", ext.name, e);
+                    }
+                }
+            }
+        }
+
+        Ok(Self::new(config, Arc::new(tools), Arc::new(agents)))
+    }
+}
+
+/// Outcome of a successful reload, returned by `POST /admin/reload`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReloadSummary {
+    /// Generation now being served.
+    pub generation: u64,
+    /// Tools registered (excluding custom Rust tools).
+    pub tools: usize,
+    /// Agents registered (excluding custom Rust agents).
+    pub agents: usize,
+    /// Configured connector instances.
+    pub connectors: usize,
+    /// Changed settings that only take effect after a restart.
+    pub restart_required: Vec<String>,
+}
+
+/// The tools and agents currently being served, replaceable at runtime.
+pub struct LiveExtensions {
+    current: RwLock<Arc<Extensions>>,
+    loader: Option<ConfigLoader>,
+    search_cache: Option<Arc<SearchCache>>,
+    next_generation: AtomicU64,
+    reloading: tokio::sync::Mutex<()>,
+}
+
+impl LiveExtensions {
+    /// Serve `extensions`; without a loader, reloads re-read scripts but
+    /// keep the current configuration.
+    pub fn new(extensions: Extensions, loader: Option<ConfigLoader>) -> Self {
+        Self {
+            next_generation: AtomicU64::new(extensions.generation + 1),
+            current: RwLock::new(Arc::new(extensions)),
+            loader,
+            search_cache: None,
+            reloading: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Clear the server's search cache after each successful reload, since
+    /// retrieval settings may have changed.
+    pub fn with_search_cache(mut self, cache: Option<Arc<SearchCache>>) -> Self {
+        self.search_cache = cache;
+        self
+    }
+
+    /// Snapshot of the current set. Hold it for the duration of a request.
+    pub fn current(&self) -> Arc<Extensions> {
+        self.current.read().unwrap().clone()
+    }
+
+    /// Rebuild the set, replacing the current one only on success.
+    ///
+    /// Also checks that every `[connectors.script.*]` script loads.
+    /// Concurrent reloads are serialized.
+    pub async fn reload(&self) -> Result<ReloadSummary> {
+        let _guard = self.reloading.lock().await;
+        let old = self.current();
+
+        let loader = self.loader.clone();
+        let current_config = (*old.config).clone();
+        let mut extensions = tokio::task::spawn_blocking(move || {
+            let config = match loader {
+                Some(loader) => (loader.load)().context("Failed to load config")?,
+                None => current_config,
+            };
+            // Connector scripts run during sync, not in the server, so they
+            // are only checked here rather than at startup.
+            let registries = RegistryManager::from_config(&config);
+            for (name, script) in &config.connectors.script {
+                let trust = registries.script_trust(&script.path);
+                trust.verify_file(&script.path)?;
+                let env = EnvAccess::resolve(script.env_allowlist.as_deref(), trust.installed());
+                connector_script::check_script(name, script, &config.db.path, &env)?;
+            }
+            Extensions::load(config)
+        })
+        .await
+        .context("reload task panicked")??;
+
+        extensions.generation = self.next_generation.fetch_add(1, Ordering::SeqCst);
+        let summary = ReloadSummary {
+            generation: extensions.generation,
+            tools: extensions.tools.len(),
+            agents: extensions.agents.len(),
+            connectors: extensions.connectors,
+            restart_required: restart_required(&old.config, &extensions.config),
+        };
+        *self.current.write().unwrap() = Arc::new(extensions);
+        if let Some(ref cache) = self.search_cache {
+            cache.clear();
+        }
+
+        for setting in &summary.restart_required {
+            tracing::warn!("{} changed; restart the server to apply it", setting);
+        }
+        tracing::info!(
+            generation = summary.generation,
+            tools = summary.tools,
+            agents = summary.agents,
+            connectors = summary.connectors,
+            "reloaded tools, agents, and connectors"
+        );
+        Ok(summary)
+    }
+
+    /// Poll watched files every `interval` and reload when any changes.
+    ///
+    /// Runs until the task is dropped. Failed reloads are logged and the
+    /// previous set stays active until the files change again.
+    pub async fn watch(self: Arc<Self>, interval: Duration) {
+        let mut seen = self.fingerprint();
+        loop {
+            tokio::time::sleep(interval).await;
+            let now = self.fingerprint();
+            if now == seen {
+                continue;
+            }
+            seen = now;
+            match self.reload().await {
+                Ok(_) => seen = self.fingerprint(),
+                Err(e) => {
+                    tracing::warn!("reload failed, keeping previous tools and agents: {:#}", e)
+                }
+            }
+        }
+    }
+
+    /// Modification times of every watched file.
+    fn fingerprint(&self) -> Vec<(PathBuf, Option<SystemTime>)> {
+        let config_path = self.loader.as_ref().and_then(|l| l.path());
+        watched_paths(&self.current().config, config_path)
+            .into_iter()
+            .map(|path| {
+                let modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok();
+                (path, modified)
+            })
+            .collect()
+    }
+}
+
+/// Files whose changes trigger a reload when watching.
+pub fn watched_paths(config: &Config, config_path: Option<&Path>) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = config_path.map(Path::to_path_buf).into_iter().collect();
+    paths.extend(config.tools.script.values().map(|t| t.path.clone()));
+    paths.extend(config.agents.script.values().map(|a| a.path.clone()));
+    paths.extend(config.connectors.script.values().map(|c| c.path.clone()));
+    for registry in config.registries.values() {
+        paths.push(ctx_dirs::expand_tilde(&registry.path).join("registry.toml"));
+    }
+    paths.extend(
+        RegistryManager::from_config(config)
+            .list_all()
+            .into_iter()
+            .map(|ext| ext.script_path),
+    );
+    paths.sort();
+    paths.dedup();
+    paths
+}
+
+/// Settings read only at startup that differ between `old` and `new`.
+fn restart_required(old: &Config, new: &Config) -> Vec<String> {
+    let mut changed = Vec::new();
+    if old.server.bind != new.server.bind {
+        changed.push("server.bind".to_string());
+    }
+    if old.db.path != new.db.path {
+        changed.push("db.path".to_string());
+    }
+    let (a, b) = (&old.tools.audit, &new.tools.audit);
+    if a.enabled != b.enabled || a.sink != b.sink || a.path != b.path || a.redact != b.redact {
+        changed.push("tools.audit".to_string());
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn restart_required_lists_startup_settings() {
+        let old = Config::minimal();
+        let mut new = old.clone();
+        new.retrieval.final_limit += 1;
+        assert!(restart_required(&old, &new).is_empty());
+
+        new.server.bind = "0.0.0.0:9000".to_string();
+        new.db.path = PathBuf::from("/tmp/other.sqlite");
+        assert_eq!(restart_required(&old, &new), vec!["server.bind", "db.path"]);
+    }
+
+    #[test]
+    fn watched_paths_include_config_and_scripts() {
+        let mut config = Config::minimal();
+        config.tools.script.insert(
+            "echo".to_string(),
+            crate::config::ScriptToolConfig {
+                path: PathBuf::from("tools/echo.lua"),
+                timeout: 30,
+                env_allowlist: None,
+                extra: toml::Table::new(),
+            },
+        );
+        let paths = watched_paths(&config, Some(Path::new("ctx.toml")));
+        assert!(paths.contains(&PathBuf::from("ctx.toml")), "{:?}", paths);
+        assert!(
+            paths.contains(&PathBuf::from("tools/echo.lua")),
+            "{:?}",
+            paths
+        );
+    }
+}
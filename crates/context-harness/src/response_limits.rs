@@ -0,0 +1,322 @@
+//! Response size limits for the `get` and `search` tools.
+//!
+//! A single `get` of a large document can overflow an LLM's context window
+//! or an MCP client's message limit. Both tools take optional limits, with
+//! defaults from `[server.responses]`:
+//!
+//! | Parameter | `get` | `search` |
+//! |-----------|-------|----------|
+//! | `max_body_bytes` | Bytes of the body | Bytes of all snippets together |
+//! | `max_chunks` | Chunks | Results |
+//!
+//! A call's parameters override the defaults, and `0` turns a limit off.
+//!
+//! # Truncation and ranges
+//!
+//! A cut response carries a `truncation` object with what was returned, the
+//! totals, and the `next_offset` to request for the rest: `body_offset` and
+//! `chunk_offset` for `get`, `offset` for `search`. A cut body also ends in
+//! a marker line (not counted in `max_body_bytes`), so a reader of the
+//! text alone knows it is incomplete. Body offsets are in bytes and are
+//! moved back to the nearest character boundary.
+//!
+//! ```json
+//! { "body": "...\n\n[truncated: 100000 of 412733 bytes; request body_offset=100000 for the rest]",
+//!   "truncation": { "body": { "offset": 0, "returned_bytes": 100000,
+//!                             "total_bytes": 412733, "next_offset": 100000 } } }
+//! ```
+//!
+//! `search` offsets page through the ranked results of one `limit`:
+//! `limit: 40, offset: 20` returns results 21–40.
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::config::ServerResponsesConfig;
+use crate::get::DocumentResponse;
+use crate::search::SearchResultItem;
+
+/// Size limits of one response; `None` is unlimited.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SizeLimits {
+    pub max_body_bytes: Option<usize>,
+    pub max_chunks: Option<usize>,
+}
+
+impl SizeLimits {
+    /// The limits of a tool call: its `max_body_bytes` and `max_chunks`
+    /// parameters, or the `[server.responses]` defaults.
+    pub fn from_params(params: &Value, defaults: &ServerResponsesConfig) -> Self {
+        let limit = |key: &str, default: Option<usize>| match params[key].as_u64() {
+            Some(0) => None,
+            Some(n) => Some(n as usize),
+            None => default,
+        };
+        Self {
+            max_body_bytes: limit("max_body_bytes", defaults.max_body_bytes),
+            max_chunks: limit("max_chunks", defaults.max_chunks),
+        }
+    }
+}
+
+/// The part of a document body that was returned.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BodyRange {
+    pub offset: usize,
+    pub returned_bytes: usize,
+    pub total_bytes: usize,
+    /// `body_offset` of the rest; absent when the body ran to its end.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_offset: Option<usize>,
+}
+
+/// The part of a list (chunks, search results) that was returned.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ListRange {
+    pub offset: usize,
+    pub returned: usize,
+    pub total: usize,
+    /// Offset of the rest; absent when the list ran to its end.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_offset: Option<usize>,
+}
+
+impl ListRange {
+    fn new(offset: usize, returned: usize, total: usize) -> Self {
+        let end = offset + returned;
+        Self {
+            offset,
+            returned,
+            total,
+            next_offset: (end < total).then_some(end),
+        }
+    }
+
+    /// Whether anything before or after the returned part was left out.
+    fn is_partial(&self) -> bool {
+        self.offset > 0 || self.next_offset.is_some()
+    }
+}
+
+/// What [`limit_document`] left out of a document.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct DocumentTruncation {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body: Option<BodyRange>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chunks: Option<ListRange>,
+}
+
+/// Cut `doc` to `limits`, starting its body at byte `body_offset` and its
+/// chunks at `chunk_offset`. Returns `None` when the whole document fits.
+pub fn limit_document(
+    doc: &mut DocumentResponse,
+    limits: &SizeLimits,
+    body_offset: usize,
+    chunk_offset: usize,
+) -> Option<DocumentTruncation> {
+    let mut truncation = DocumentTruncation::default();
+
+    let total = doc.body.len();
+    let start = floor_char_boundary(&doc.body, body_offset.min(total));
+    let mut end = limits.max_body_bytes.map_or(total, |max| {
+        floor_char_boundary(&doc.body, start.saturating_add(max).min(total))
+    });
+    if end == start && start < total {
+        // Return at least one character so ranged reads always advance.
+        end = (start + 1..=total)
+            .find(|&i| doc.body.is_char_boundary(i))
+            .unwrap_or(total);
+    }
+    if start > 0 || end < total {
+        let mut body = doc.body[start..end].to_string();
+        if end < total {
+            body.push_str(&format!(
+                "\n\n[truncated: {} of {} bytes; request body_offset={} for the rest]",
+                end, total, end
+            ));
+        }
+        doc.body = body;
+        truncation.body = Some(BodyRange {
+            offset: start,
+            returned_bytes: end - start,
+            total_bytes: total,
+            next_offset: (end < total).then_some(end),
+        });
+    }
+
+    let total = doc.chunks.len();
+    let offset = chunk_offset.min(total);
+    let returned = limits
+        .max_chunks
+        .map_or(total - offset, |max| max.min(total - offset));
+    let range = ListRange::new(offset, returned, total);
+    if range.is_partial() {
+        doc.chunks = doc.chunks.drain(offset..offset + returned).collect();
+        truncation.chunks = Some(range);
+    }
+
+    (truncation.body.is_some() || truncation.chunks.is_some()).then_some(truncation)
+}
+
+/// Cut ranked `results` to `limits`, starting at `offset`. At least one
+/// result is kept; if its snippet alone exceeds `max_body_bytes`, the
+/// snippet is shortened. Returns `None` when every result fits.
+pub fn limit_results(
+    results: &mut Vec<SearchResultItem>,
+    limits: &SizeLimits,
+    offset: usize,
+) -> Option<ListRange> {
+    let total = results.len();
+    let offset = offset.min(total);
+    let max_chunks = limits.max_chunks.unwrap_or(usize::MAX);
+    let max_bytes = limits.max_body_bytes.unwrap_or(usize::MAX);
+
+    let mut kept: Vec<SearchResultItem> = Vec::new();
+    let mut bytes = 0usize;
+    for mut result in results.drain(..).skip(offset) {
+        if kept.len() == max_chunks {
+            break;
+        }
+        let len = result.snippet.len();
+        if bytes.saturating_add(len) > max_bytes {
+            if kept.is_empty() {
+                let end = floor_char_boundary(&result.snippet, max_bytes);
+                result.snippet.truncate(end);
+                result.snippet.push('…');
+                kept.push(result);
+            }
+            break;
+        }
+        bytes += len;
+        kept.push(result);
+    }
+
+    let range = ListRange::new(offset, kept.len(), total);
+    *results = kept;
+    range.is_partial().then_some(range)
+}
+
+/// The largest character boundary of `s` at or before `index`.
+fn floor_char_boundary(s: &str, mut index: usize) -> usize {
+    while !s.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::get::ChunkResponse;
+
+    fn document(body: &str, chunks: usize) -> DocumentResponse {
+        DocumentResponse {
+            id: "doc".to_string(),
+            source: "filesystem:docs".to_string(),
+            source_id: "big.md".to_string(),
+            source_url: None,
+            title: None,
+            author: None,
+            created_at: String::new(),
+            updated_at: String::new(),
+            content_type: "text/markdown".to_string(),
+            body: body.to_string(),
+            summary: None,
+            metadata: Value::Null,
+            chunks: (0..chunks as i64)
+                .map(|index| ChunkResponse {
+                    index,
+                    text: format!("chunk {}", index),
+                    metadata: serde_json::Map::new(),
+                })
+                .collect(),
+            parent_id: None,
+            children: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn ranged_body_reads_cover_the_document() {
+        let body = "héllo wörld, ".repeat(10);
+        let limits = SizeLimits {
+            max_body_bytes: Some(16),
+            max_chunks: Some(2),
+        };
+
+        let mut doc = document(&body, 5);
+        let cut = limit_document(&mut doc, &limits, 0, 0).unwrap();
+        let first = cut.body.unwrap();
+        assert!(doc.body.contains("[truncated: "));
+        assert_eq!(first.offset, 0);
+        assert!(first.returned_bytes <= 16);
+        assert_eq!(cut.chunks.unwrap().next_offset, Some(2));
+        assert_eq!(doc.chunks.len(), 2);
+
+        let mut rebuilt = String::new();
+        let mut offset = Some(0);
+        while let Some(at) = offset {
+            let mut doc = document(&body, 5);
+            let range = limit_document(&mut doc, &limits, at, 0)
+                .and_then(|t| t.body)
+                .unwrap();
+            rebuilt.push_str(&body[range.offset..range.offset + range.returned_bytes]);
+            offset = range.next_offset;
+        }
+        assert_eq!(rebuilt, body);
+
+        let mut doc = document("short", 1);
+        assert_eq!(limit_document(&mut doc, &limits, 0, 0), None);
+        assert_eq!(doc.body, "short");
+    }
+
+    #[test]
+    fn results_stop_at_the_snippet_budget() {
+        let result = |snippet: &str| SearchResultItem {
+            id: snippet.to_string(),
+            score: 1.0,
+            title: None,
+            source: "filesystem:docs".to_string(),
+            source_id: snippet.to_string(),
+            content_type: None,
+            updated_at: String::new(),
+            snippet: snippet.to_string(),
+            source_url: None,
+            heading: None,
+            section_source_id: None,
+            section_source_url: None,
+            chunk_id: None,
+            parent_id: None,
+            explain: None,
+            origin: None,
+            summary: None,
+            doc_type: None,
+        };
+        let all = || vec![result("aaaa"), result("bbbb"), result("cccc")];
+        let limits = SizeLimits {
+            max_body_bytes: Some(9),
+            max_chunks: None,
+        };
+
+        let mut results = all();
+        let range = limit_results(&mut results, &limits, 0).unwrap();
+        assert_eq!((range.returned, range.next_offset), (2, Some(2)));
+
+        let mut results = all();
+        let range = limit_results(&mut results, &limits, 2).unwrap();
+        assert_eq!(results[0].id, "cccc");
+        assert_eq!((range.returned, range.next_offset), (1, None));
+
+        let mut results = all();
+        let tiny = SizeLimits {
+            max_body_bytes: Some(2),
+            max_chunks: None,
+        };
+        limit_results(&mut results, &tiny, 0).unwrap();
+        assert_eq!(results[0].snippet, "aa…");
+
+        let mut results = all();
+        assert_eq!(limit_results(&mut results, &SizeLimits::default(), 0), None);
+        assert_eq!(results.len(), 3);
+    }
+}
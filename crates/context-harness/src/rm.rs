@@ -0,0 +1,227 @@
+//! Document deletion: `ctx rm`.
+//!
+//! Removes documents by UUID, or every document matching a filter, with
+//! their chunks, FTS and title index rows, embeddings, and vectors (see
+//! [`SqliteAppStore::delete_documents`]). All selected documents are
+//! deleted in one transaction, so a failure leaves every one of them in
+//! place.
+//!
+//! # Usage
+//!
+//! ```bash
+//! # One document (an archive's members go with it)
+//! ctx rm 550e8400-e29b-41d4-a716-446655440000
+//!
+//! # Everything a retired connector indexed before 2023
+//! ctx rm --source s3:old --before 2023-01-01 --yes
+//! ```
+//!
+//! The documents are listed and confirmed on the terminal first; `--yes`
+//! skips the prompt, and without a terminal it is required. With
+//! `[tools.audit]` enabled, every deleted document is recorded as a
+//! `delete` entry with transport `cli` (see [`crate::audit`]).
+//!
+//! Documents of a connector that still has the item come back on its next
+//! `ctx sync --full`.
+
+use anyhow::{bail, Result};
+use chrono::NaiveDate;
+use sqlx::{Row, SqlitePool};
+use std::io::Write;
+use std::time::Instant;
+
+use crate::app_store::SqliteAppStore;
+use crate::audit::{AuditEntry, AuditLog};
+use crate::config::Config;
+use crate::search_cache;
+use crate::sqlite_store::SqliteStore;
+
+/// Documents listed before the confirmation prompt.
+const LIST_LIMIT: usize = 20;
+
+/// Which documents `ctx rm` deletes: the documents `ids`, or every document
+/// matching `source` and `before`.
+pub struct RmFilter<'a> {
+    pub ids: &'a [String],
+    /// Exact source label, e.g. `s3:old`.
+    pub source: Option<&'a str>,
+    /// Only documents last updated before this date (YYYY-MM-DD).
+    pub before: Option<&'a str>,
+}
+
+/// A document selected for deletion.
+struct Target {
+    id: String,
+    source: String,
+    source_id: String,
+}
+
+/// CLI entry point for `ctx rm`.
+///
+/// # Errors
+///
+/// Returns an error if an ID doesn't exist, `--before` is not a date, the
+/// deletion is not confirmed, or a database operation fails.
+pub async fn run_rm(config: &Config, filter: &RmFilter<'_>, yes: bool) -> Result<()> {
+    let before_ts = filter.before.map(parse_date).transpose()?;
+    let store = SqliteAppStore::connect(config).await?;
+    let targets = match select(store.pool(), filter, before_ts).await {
+        Ok(targets) => targets,
+        Err(e) => {
+            store.close().await;
+            return Err(e);
+        }
+    };
+    if targets.is_empty() {
+        println!("No documents match.");
+        store.close().await;
+        return Ok(());
+    }
+
+    for target in targets.iter().take(LIST_LIMIT) {
+        println!("  {}  {} / {}", target.id, target.source, target.source_id);
+    }
+    if targets.len() > LIST_LIMIT {
+        println!("  ... and {} more", targets.len() - LIST_LIMIT);
+    }
+    if !yes && !confirm(targets.len())? {
+        store.close().await;
+        bail!("Aborted; nothing was deleted.");
+    }
+
+    let started = Instant::now();
+    let ids: Vec<String> = targets.iter().map(|t| t.id.clone()).collect();
+    let result = store.delete_documents(&ids).await;
+    if let Some(audit) = AuditLog::open(config).await? {
+        let error = result.as_ref().err().map(|e| e.to_string());
+        for target in &targets {
+            let params = serde_json::json!({
+                "id": target.id,
+                "source": target.source,
+                "source_id": target.source_id,
+            });
+            audit
+                .record(AuditEntry::new(
+                    "delete",
+                    None,
+                    "cli",
+                    params,
+                    started.elapsed(),
+                    error.clone(),
+                ))
+                .await;
+        }
+    }
+    result?;
+
+    search_cache::bump_index_generation(store.pool()).await?;
+    store.refresh_keyword_index().await?;
+    store.close().await;
+    println!("deleted documents: {}", ids.len());
+    Ok(())
+}
+
+/// Ask on the terminal whether to delete `count` documents.
+fn confirm(count: usize) -> Result<bool> {
+    if !atty::is(atty::Stream::Stdin) {
+        bail!(
+            "Refusing to delete {} document(s) without confirmation; pass --yes.",
+            count
+        );
+    }
+    eprint!("Delete {} document(s)? [y/N] ", count);
+    std::io::stderr().flush()?;
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input)?;
+    let answer = input.trim().to_lowercase();
+    Ok(answer == "y" || answer == "yes")
+}
+
+/// Unix timestamp of midnight UTC on a `YYYY-MM-DD` date.
+fn parse_date(date: &str) -> Result<i64> {
+    let Ok(day) = NaiveDate::parse_from_str(date, "%Y-%m-%d") else {
+        bail!("--before must be a date (YYYY-MM-DD), got '{}'", date);
+    };
+    Ok(day.and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp())
+}
+
+/// The documents `filter` selects, sorted by source and `source_id`. An
+/// archive's ID selects its members too.
+async fn select(
+    pool: &SqlitePool,
+    filter: &RmFilter<'_>,
+    before_ts: Option<i64>,
+) -> Result<Vec<Target>> {
+    if !filter.ids.is_empty() {
+        let store = SqliteStore::new(pool.clone());
+        let mut ids = Vec::new();
+        for id in filter.ids {
+            let Some(target) = target(pool, id).await? else {
+                bail!("Document not found: {}", id);
+            };
+            ids.extend(
+                store
+                    .document_ids_for_source_id(&target.source, &target.source_id)
+                    .await?,
+            );
+        }
+        ids.sort();
+        ids.dedup();
+        let mut targets = Vec::with_capacity(ids.len());
+        for id in ids {
+            targets.extend(target(pool, &id).await?);
+        }
+        targets.sort_by(|a, b| (&a.source, &a.source_id).cmp(&(&b.source, &b.source_id)));
+        return Ok(targets);
+    }
+
+    let mut sql = String::from("SELECT id, source, source_id FROM documents WHERE 1 = 1");
+    if filter.source.is_some() {
+        sql.push_str(" AND source = ?");
+    }
+    if before_ts.is_some() {
+        sql.push_str(" AND updated_at < ?");
+    }
+    sql.push_str(" ORDER BY source, source_id");
+
+    let mut q = sqlx::query(&sql);
+    if let Some(source) = filter.source {
+        q = q.bind(source);
+    }
+    if let Some(ts) = before_ts {
+        q = q.bind(ts);
+    }
+    Ok(q.fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(|row| Target {
+            id: row.get("id"),
+            source: row.get("source"),
+            source_id: row.get("source_id"),
+        })
+        .collect())
+}
+
+/// The document with UUID `id`, if it exists.
+async fn target(pool: &SqlitePool, id: &str) -> Result<Option<Target>> {
+    let row = sqlx::query("SELECT source, source_id FROM documents WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+    Ok(row.map(|row| Target {
+        id: id.to_string(),
+        source: row.get("source"),
+        source_id: row.get("source_id"),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn before_is_midnight_utc() {
+        assert_eq!(parse_date("2023-01-01").unwrap(), 1_672_531_200);
+        assert!(parse_date("01/01/2023").is_err());
+    }
+}
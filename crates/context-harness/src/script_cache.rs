@@ -0,0 +1,156 @@
+//! Key/value cache for Lua scripts: the `cache` host module.
+//!
+//! Tools, agents, and connectors that call slow or rate-limited APIs can
+//! keep results between invocations instead of re-fetching unchanged data:
+//!
+//! ```lua
+//! local key = "issues:" .. project
+//! local issues = cache.get(key)
+//! if issues == nil then
+//!     issues = http.get(url).json
+//!     cache.set(key, issues, 300) -- keep for five minutes
+//! end
+//! ```
+//!
+//! Values are stored as JSON in the `script_cache` table, keyed by the
+//! script (`tool:<name>`, `agent:<name>`, or `script:<name>` for
+//! connectors) and the key, so scripts cannot see each other's entries.
+//! Entries survive restarts and are shared by every process using the
+//! database.
+//!
+//! | Call | Behavior |
+//! |------|----------|
+//! | `cache.get(key)` | The stored value, or `nil` when missing or expired |
+//! | `cache.set(key, value, ttl_secs)` | Store `value` for `ttl_secs` seconds; no `ttl_secs` (or `0`) keeps it until replaced |
+//! | `cache.set(key, nil)` | Remove the entry |
+//!
+//! Expired entries of a script are deleted on its next `cache.set`.
+
+use anyhow::{bail, Result};
+use serde_json::Value;
+use sqlx::SqlitePool;
+use std::path::PathBuf;
+use tokio::sync::OnceCell;
+
+use crate::db;
+use crate::migrate;
+
+/// The cache entries of one script.
+///
+/// The database is opened on first use, so scripts that never call
+/// `cache` do not connect to it.
+pub struct ScriptCache {
+    script: String,
+    db_path: PathBuf,
+    pool: OnceCell<SqlitePool>,
+}
+
+impl ScriptCache {
+    /// The cache of `script` (e.g. `"tool:jira"`) in the database at `db_path`.
+    pub fn new(script: &str, db_path: PathBuf) -> Self {
+        Self {
+            script: script.to_string(),
+            db_path,
+            pool: OnceCell::new(),
+        }
+    }
+
+    async fn pool(&self) -> Result<&SqlitePool> {
+        self.pool
+            .get_or_try_init(|| async {
+                let pool = db::connect_path(&self.db_path).await?;
+                migrate::create_script_cache_table(&pool).await?;
+                Ok(pool)
+            })
+            .await
+    }
+
+    /// The value stored under `key`, or `None` when missing or expired.
+    pub async fn get(&self, key: &str) -> Result<Option<Value>> {
+        let pool = self.pool().await?;
+        let value: Option<String> = sqlx::query_scalar(
+            "SELECT value_json FROM script_cache \
+             WHERE script = ? AND key = ? AND (expires_at IS NULL OR expires_at > ?)",
+        )
+        .bind(&self.script)
+        .bind(key)
+        .bind(chrono::Utc::now().timestamp())
+        .fetch_optional(pool)
+        .await?;
+        Ok(value.map(|v| serde_json::from_str(&v)).transpose()?)
+    }
+
+    /// Store `value` under `key` for `ttl_secs` seconds (`0`: no expiry),
+    /// or remove the entry when `value` is null.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `ttl_secs` is negative or the database write fails.
+    pub async fn set(&self, key: &str, value: &Value, ttl_secs: i64) -> Result<()> {
+        if ttl_secs < 0 {
+            bail!("ttl_secs must not be negative (got {})", ttl_secs);
+        }
+        let pool = self.pool().await?;
+        let now = chrono::Utc::now().timestamp();
+        sqlx::query("DELETE FROM script_cache WHERE script = ? AND expires_at <= ?")
+            .bind(&self.script)
+            .bind(now)
+            .execute(pool)
+            .await?;
+
+        if value.is_null() {
+            sqlx::query("DELETE FROM script_cache WHERE script = ? AND key = ?")
+                .bind(&self.script)
+                .bind(key)
+                .execute(pool)
+                .await?;
+            return Ok(());
+        }
+
+        let expires_at = (ttl_secs > 0).then(|| now + ttl_secs);
+        sqlx::query(
+            "INSERT OR REPLACE INTO script_cache (script, key, value_json, expires_at) \
+             VALUES (?, ?, ?, ?)",
+        )
+        .bind(&self.script)
+        .bind(key)
+        .bind(value.to_string())
+        .bind(expires_at)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn entries_are_scoped_by_script_and_expire() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let db_path = tmp.path().join("ctx.sqlite");
+        let jira = ScriptCache::new("tool:jira", db_path.clone());
+        let github = ScriptCache::new("tool:github", db_path);
+
+        jira.set("issues", &json!({ "open": 3 }), 0).await.unwrap();
+        assert_eq!(
+            jira.get("issues").await.unwrap(),
+            Some(json!({ "open": 3 }))
+        );
+        assert_eq!(github.get("issues").await.unwrap(), None);
+
+        jira.set("issues", &Value::Null, 0).await.unwrap();
+        assert_eq!(jira.get("issues").await.unwrap(), None);
+
+        jira.set("stale", &json!("x"), 1).await.unwrap();
+        sqlx::query("UPDATE script_cache SET expires_at = expires_at - 10")
+            .execute(jira.pool().await.unwrap())
+            .await
+            .unwrap();
+        assert_eq!(jira.get("stale").await.unwrap(), None);
+
+        assert!(jira.set("issues", &json!(1), -1).await.is_err());
+    }
+}
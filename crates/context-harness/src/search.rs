@@ -8,23 +8,54 @@
 //!
 //! # Search Modes
 //!
-//! - **Keyword** — FTS5 full-text search using BM25 scoring.
+//! - **Keyword** — FTS5 full-text search using BM25 scoring, optionally
+//!   typo-tolerant via `[retrieval] fuzzy` / `--fuzzy`. With
+//!   `[keyword_index]` set to a cluster, candidates come from OpenSearch or
+//!   Elasticsearch instead (see [`crate::keyword_index`]).
 //! - **Semantic** — Cosine similarity over stored embedding vectors.
 //! - **Hybrid** — Weighted merge of keyword and semantic results.
+//!
+//! # Partial Embedding Coverage
+//!
+//! Semantic and hybrid searches count how many chunks in their scope (the
+//! source, `since`, and `as_of` filters and the caller's source scope) have
+//! embeddings, and report it as [`EmbeddingCoverage`]. Hybrid search scores
+//! chunks without an embedding on keyword relevance alone; when nothing in
+//! scope is embedded it runs as a keyword search without embedding the
+//! query, and semantic search returns no results.
+//!
+//! Every mode then applies the popularity boost, if configured (see
+//! [`crate::popularity`]), and the pins, boosts, and blocklist managed by
+//! `ctx curate` (see [`crate::curate`]).
 
 use anyhow::{bail, Result};
+use chrono::NaiveDate;
+use sqlx::{Row, SqlitePool};
 
+#[allow(unused_imports)]
+pub use context_harness_core::query::{parse_query, ParsedQuery, REF_KEY};
 #[allow(unused_imports)]
 pub use context_harness_core::search::{
-    normalize_scores, ScoreExplanation, SearchParams, SearchRequest, SearchResultItem,
+    end_of_day_ts, facets, group_by_facet, group_by_parent, normalize_scores,
+    source_filter_matches, source_matches, type_matches, EmbeddingCoverage, FacetField,
+    ScoreExplanation, SearchFacets, SearchParams, SearchRequest, SearchResultGroup,
+    SearchResultItem, DEFAULT_PREFIX_DEPTH,
 };
 #[allow(unused_imports)]
 pub use context_harness_core::store::ChunkCandidate;
 
+use crate::assemble;
 use crate::config::Config;
+use crate::curate::Curation;
 use crate::db;
 use crate::embedding;
+use crate::federation::{self, FederatedQuery};
+use crate::keyword_index;
+use crate::popularity;
+use crate::query_cache;
 use crate::sqlite_store::SqliteStore;
+use crate::storage;
+use crate::titles;
 use crate::vector_index;
 
 /// Core search function returning structured results.
@@ -41,8 +72,94 @@ pub async fn search_documents(
     limit: Option<i64>,
     explain: bool,
 ) -> Result<Vec<SearchResultItem>> {
+    search_documents_filtered(
+        config,
+        query,
+        mode,
+        source_filter,
+        since,
+        None,
+        limit,
+        explain,
+        &[],
+        &[],
+        None,
+    )
+    .await
+}
+
+/// Like [`search_documents`], additionally restricting results to chunks whose
+/// metadata matches every `(key, value)` filter (e.g. `heading=install`), to
+/// documents of one of `types` (see [`type_matches`]) when it is not empty,
+/// and, with `as_of` (`YYYY-MM-DD`), to documents last updated by the end of
+/// that day.
+/// With `allowed_sources` (an API key's scope, see [`crate::auth`]), only
+/// documents from matching sources are returned.
+///
+/// See [`context_harness_core::search::chunk_metadata_matches`] for matching rules.
+#[allow(clippy::too_many_arguments)]
+pub async fn search_documents_filtered(
+    config: &Config,
+    query: &str,
+    mode: &str,
+    source_filter: Option<&str>,
+    since: Option<&str>,
+    as_of: Option<&str>,
+    limit: Option<i64>,
+    explain: bool,
+    chunk_filters: &[(String, String)],
+    types: &[String],
+    allowed_sources: Option<&[String]>,
+) -> Result<Vec<SearchResultItem>> {
+    let outcome = search_with_coverage(
+        config,
+        query,
+        mode,
+        source_filter,
+        since,
+        as_of,
+        limit,
+        explain,
+        chunk_filters,
+        types,
+        allowed_sources,
+    )
+    .await?;
+    Ok(outcome.results)
+}
+
+/// Search results together with the embedding coverage of their scope.
+#[derive(Debug, Clone)]
+pub struct SearchOutcome {
+    pub results: Vec<SearchResultItem>,
+    /// Set for semantic and hybrid searches.
+    pub coverage: Option<EmbeddingCoverage>,
+}
+
+/// Like [`search_documents_filtered`], also returning the
+/// [`EmbeddingCoverage`] of a semantic or hybrid search's scope.
+#[allow(clippy::too_many_arguments)]
+pub async fn search_with_coverage(
+    config: &Config,
+    query: &str,
+    mode: &str,
+    source_filter: Option<&str>,
+    since: Option<&str>,
+    as_of: Option<&str>,
+    limit: Option<i64>,
+    explain: bool,
+    chunk_filters: &[(String, String)],
+    types: &[String],
+    allowed_sources: Option<&[String]>,
+) -> Result<SearchOutcome> {
     if query.trim().is_empty() {
-        return Ok(Vec::new());
+        return Ok(SearchOutcome {
+            results: Vec::new(),
+            coverage: None,
+        });
+    }
+    if let Some(date) = as_of {
+        end_of_day_ts(date)?;
     }
 
     match mode {
@@ -60,20 +177,47 @@ pub async fn search_documents(
         );
     }
 
-    let pool = db::connect(config).await?;
+    let pool = db::connect_reader(config).await?;
+
+    let coverage = if mode != "keyword" {
+        Some(embedding_coverage(&pool, source_filter, since, as_of, allowed_sources).await?)
+    } else {
+        None
+    };
+    let mode = match coverage {
+        Some(c) if c.embedded_chunks == 0 && mode == "semantic" => {
+            db::close_reader(config, pool).await;
+            return Ok(SearchOutcome {
+                results: Vec::new(),
+                coverage,
+            });
+        }
+        Some(c) if c.embedded_chunks == 0 => "keyword",
+        _ => mode,
+    };
 
     let query_vec = if mode != "keyword" {
-        let provider = embedding::create_provider(&config.embedding)?;
-        Some(embedding::embed_query(provider.as_ref(), &config.embedding, query).await?)
+        let provider = embedding::create_checked_provider(&config.embedding, &pool).await?;
+        let cache = db::writer_for(config, &pool).await?;
+        let embedded =
+            query_cache::embed_query_cached(&cache, provider.as_ref(), &config.embedding, query)
+                .await;
+        if config.db.read_pool {
+            cache.close().await;
+        }
+        Some(embedded?)
     } else {
         None
     };
 
+    let curation = Curation::load(&pool).await?;
+    let final_limit = limit.unwrap_or(config.retrieval.final_limit);
     let params = SearchParams {
         hybrid_alpha: config.retrieval.hybrid_alpha,
         candidate_k_keyword: config.retrieval.candidate_k_keyword,
         candidate_k_vector: config.retrieval.candidate_k_vector,
-        final_limit: limit.unwrap_or(config.retrieval.final_limit),
+        final_limit: final_limit + curation.extra_candidates(),
+        auto_merge_min_chunks: config.retrieval.auto_merge_min_chunks,
     };
 
     let req = SearchRequest {
@@ -81,43 +225,232 @@ pub async fn search_documents(
         query_vec: query_vec.as_deref(),
         mode,
         source_filter,
+        allowed_sources,
         since,
+        as_of,
         params,
         explain,
+        chunk_filters,
+        types,
     };
 
     let results = if mode == "keyword" {
-        let store = SqliteStore::new(pool.clone());
+        let store = keyword_index::with_keyword_index(
+            config,
+            SqliteStore::new(pool.clone()).with_retrieval(&config.retrieval),
+        )?;
         context_harness_core::search::search(&store, &req).await?
     } else {
-        let store = vector_index::configured_vector_store(config, pool.clone()).await?;
+        let store = keyword_index::with_keyword_index(
+            config,
+            vector_index::configured_vector_store(config, pool.clone()).await?,
+        )?;
         context_harness_core::search::search(&store, &req).await?
     };
+    let results = popularity::apply(&pool, &config.retrieval, results).await?;
+    let results = curation
+        .apply(&pool, &req, results, final_limit.max(0) as usize)
+        .await?;
+
+    tracing::debug!(mode, results = results.len(), "search completed");
 
-    pool.close().await;
-    Ok(results)
+    let ids: Vec<&str> = results.iter().map(|r| r.id.as_str()).collect();
+    storage::record_retrievals(config, &pool, &ids).await;
+
+    db::close_reader(config, pool).await;
+    Ok(SearchOutcome { results, coverage })
+}
+
+/// Count the chunks a search can match — those of documents passing
+/// `source_filter` and `allowed_sources`, updated on or after `since` and
+/// by the end of `as_of` — and how many of them have embeddings.
+pub async fn embedding_coverage(
+    pool: &SqlitePool,
+    source_filter: Option<&str>,
+    since: Option<&str>,
+    as_of: Option<&str>,
+    allowed_sources: Option<&[String]>,
+) -> Result<EmbeddingCoverage> {
+    let since_ts = since
+        .map(|date| -> Result<i64> {
+            let day = NaiveDate::parse_from_str(date, "%Y-%m-%d")?;
+            Ok(day.and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp())
+        })
+        .transpose()?;
+    let as_of_ts = as_of.map(end_of_day_ts).transpose()?;
+
+    let mut sql = String::from(
+        "SELECT d.source, COUNT(c.id) AS chunks, COUNT(cv.chunk_id) AS embedded \
+         FROM chunks c JOIN documents d ON d.id = c.document_id \
+         LEFT JOIN chunk_vectors cv ON cv.chunk_id = c.id WHERE 1 = 1",
+    );
+    if since_ts.is_some() {
+        sql.push_str(" AND d.updated_at >= ?");
+    }
+    if as_of_ts.is_some() {
+        sql.push_str(" AND d.updated_at <= ?");
+    }
+    sql.push_str(" GROUP BY d.source");
+
+    let mut q = sqlx::query(&sql);
+    if let Some(ts) = since_ts {
+        q = q.bind(ts);
+    }
+    if let Some(ts) = as_of_ts {
+        q = q.bind(ts);
+    }
+    let (mut embedded, mut total) = (0, 0);
+    for row in q.fetch_all(pool).await? {
+        let source: String = row.get("source");
+        if source_filter.is_some_and(|filter| !source_filter_matches(filter, &source))
+            || allowed_sources.is_some_and(|patterns| !source_matches(patterns, &source))
+        {
+            continue;
+        }
+        embedded += row.get::<i64, _>("embedded");
+        total += row.get::<i64, _>("chunks");
+    }
+    Ok(EmbeddingCoverage::new(embedded, total))
 }
 
 /// CLI entry point — calls [`search_documents`] and prints results to stdout.
+///
+/// With `group_by_parent`, results sharing a parent document are printed
+/// together, children indented beneath the first result of their group.
+/// With `group_by` (`path_prefix`, `source`, or `content_type`), results
+/// are printed under one `N hits in <value>` header per value instead;
+/// `prefix_depth` sets how many directories a path prefix keeps.
+/// `fuzzy` enables typo-tolerant keyword matching for this query (see
+/// `[retrieval] fuzzy`). With `as_of`, a header line notes the snapshot date.
+/// `federated` (or `[federation] enabled`) searches every federated index via
+/// [`crate::federation`], printing each result's origin and a warning for
+/// every index that failed. `scope = "titles"` searches the title index via
+/// [`crate::titles::search_titles`] instead; it is keyword-only and local.
+/// With `context = Some(n)`, the top `n` chunks are printed as one cited
+/// block of at most `max_context_tokens` via [`crate::assemble`] instead.
+/// A semantic or hybrid search over a partially embedded scope prints a
+/// coverage warning on stderr.
+///
+/// Inline modifiers in `query` (`source:git after:2024-01-01 type:md`, see
+/// [`parse_query`]) fill the filters that were not given as flags; `types`
+/// from `--type` are added to the inline `type:` ones, and `ref:` is added
+/// to `filters`.
+#[allow(clippy::too_many_arguments)]
 pub async fn run_search(
     config: &Config,
     query: &str,
     mode: &str,
     source_filter: Option<String>,
     since: Option<String>,
+    as_of: Option<String>,
     limit: Option<i64>,
     explain: bool,
+    mut filters: Vec<(String, String)>,
+    types: Vec<String>,
+    group: bool,
+    group_by: Option<&str>,
+    prefix_depth: usize,
+    fuzzy: bool,
+    federated: bool,
+    scope: &str,
+    context: Option<usize>,
+    max_context_tokens: usize,
 ) -> Result<()> {
-    let results = search_documents(
-        config,
-        query,
-        mode,
-        source_filter.as_deref(),
-        since.as_deref(),
-        limit,
-        explain,
-    )
-    .await?;
+    let mut config = config.clone();
+    config.retrieval.fuzzy |= fuzzy;
+    let group_by = group_by.map(FacetField::parse).transpose()?;
+    let mut parsed = parse_query(query);
+    if parsed.text.is_empty() && parsed.has_modifiers() {
+        bail!("query has no search terms besides its modifiers");
+    }
+    let query = parsed.text.as_str();
+    filters.extend(parsed.ref_filter());
+    let source_filter = source_filter.or(parsed.source);
+    let since = since.or(parsed.since);
+    let as_of = as_of.or(parsed.as_of);
+    parsed.types.extend(types);
+    if let Some(n) = context {
+        if scope == "titles" {
+            bail!("--context assembles chunks and cannot be used with --in titles");
+        }
+        if n == 0 || max_context_tokens == 0 {
+            bail!("--context and --max-context-tokens must be at least 1");
+        }
+    }
+    let limit = context.map_or(limit, |n| Some(n as i64));
+    let (results, coverage) = if scope == "titles" {
+        if mode != "keyword" {
+            bail!("--in titles only supports --mode keyword");
+        }
+        if !filters.is_empty() {
+            bail!("--filter and ref: apply to chunks and cannot be used with --in titles");
+        }
+        let results = titles::search_titles(
+            &config,
+            query,
+            source_filter.as_deref(),
+            since.as_deref(),
+            as_of.as_deref(),
+            &parsed.types,
+            limit,
+            None,
+        )
+        .await?;
+        (results, None)
+    } else if federated || config.federation.enabled {
+        let federated = federation::federated_search(
+            &config,
+            &FederatedQuery {
+                query: query.to_string(),
+                mode: mode.to_string(),
+                source: source_filter.clone(),
+                since: since.clone(),
+                as_of: as_of.clone(),
+                limit,
+                explain,
+                chunk_filters: filters,
+                types: parsed.types.clone(),
+                fuzzy,
+                allowed_sources: None,
+            },
+        )
+        .await?;
+        for (index, err) in &federated.errors {
+            tracing::warn!("federated index '{}' failed: {}", index, err);
+        }
+        (federated.results, None)
+    } else {
+        let outcome = search_with_coverage(
+            &config,
+            query,
+            mode,
+            source_filter.as_deref(),
+            since.as_deref(),
+            as_of.as_deref(),
+            limit,
+            explain,
+            &filters,
+            &parsed.types,
+            None,
+        )
+        .await?;
+        (outcome.results, outcome.coverage)
+    };
+
+    if let Some(coverage) = coverage.filter(|c| !c.is_complete()) {
+        eprintln!("{}", coverage_warning(mode, &coverage));
+    }
+
+    if context.is_some() {
+        let assembled = assemble::assemble_context(&config, &results, max_context_tokens).await?;
+        assemble::print_context(&assembled);
+        return Ok(());
+    }
+
+    if let Some(ref date) = as_of {
+        println!("As of {} (documents updated later are excluded)", date);
+        println!();
+    }
 
     if results.is_empty() {
         println!("No results.");
@@ -130,37 +463,137 @@ pub async fn run_search(
                 "Search: mode={}, alpha={:.2}, candidates: {} keyword + {} vector",
                 mode, ex.alpha, ex.keyword_candidates, ex.vector_candidates
             );
+            if mode != "semantic" {
+                println!("{}", keyword_ranking(&config));
+            }
             println!();
         }
     }
 
-    for (i, result) in results.iter().enumerate() {
-        let title_display = result.title.as_deref().unwrap_or("(untitled)");
-        println!(
-            "{}. [{:.2}] {} / {}",
-            i + 1,
-            result.score,
-            result.source,
-            title_display
-        );
-        if let Some(ref ex) = result.explain {
-            println!(
-                "    scoring: keyword={:.3}  semantic={:.3}  → hybrid={:.3}",
-                ex.keyword_score, ex.semantic_score, result.score
-            );
+    if let Some(field) = group_by {
+        for group in group_by_facet(results, field, prefix_depth) {
+            let n = group.results.len();
+            let value = match group.value.as_str() {
+                "" => "(no directory)",
+                value => value,
+            };
+            println!("{} hit{} in {}", n, if n == 1 { "" } else { "s" }, value);
+            println!();
+            for (i, result) in group.results.iter().enumerate() {
+                print_result(&format!("  {}.", i + 1), "      ", result);
+            }
         }
-        println!("    updated: {}", result.updated_at);
-        println!("    source: {}", result.source);
-        if let Some(ref url) = result.source_url {
-            println!("    url: {}", url);
+    } else if group {
+        for (i, group) in group_by_parent(results).iter().enumerate() {
+            for (j, result) in group.results.iter().enumerate() {
+                if j == 0 {
+                    print_result(&format!("{}.", i + 1), "    ", result);
+                } else {
+                    print_result("    ↳", "        ", result);
+                }
+            }
+        }
+    } else {
+        for (i, result) in results.iter().enumerate() {
+            print_result(&format!("{}.", i + 1), "    ", result);
         }
-        println!(
-            "    excerpt: \"{}\"",
-            result.snippet.replace('\n', " ").trim()
-        );
-        println!("    id: {}", result.id);
-        println!();
     }
 
     Ok(())
 }
+
+/// The `--explain` line describing keyword ranking: BM25 constants and
+/// column weights, and whether the constants are applied by rescoring.
+fn keyword_ranking(config: &Config) -> String {
+    let bm25 = config.retrieval.bm25.chunks();
+    let weights = config.retrieval.weights;
+    let applied = if config.keyword_index.is_external() {
+        " (index similarity)"
+    } else if bm25.is_builtin() {
+        ""
+    } else {
+        " (rescored)"
+    };
+    format!(
+        "Keyword: bm25 k1={:.2} b={:.2}{}, weights text={:.2} title={:.2} summary={:.2}",
+        bm25.k1, bm25.b, applied, weights.text, weights.title, weights.summary
+    )
+}
+
+/// The CLI warning for a semantic or hybrid search whose scope is not
+/// fully embedded.
+fn coverage_warning(mode: &str, coverage: &EmbeddingCoverage) -> String {
+    let missing = coverage.total_chunks - coverage.embedded_chunks;
+    let effect = if mode == "semantic" {
+        "semantic search can't find them"
+    } else if coverage.embedded_chunks == 0 {
+        "the search ran on keywords alone"
+    } else {
+        "they are ranked by keyword relevance alone"
+    };
+    format!(
+        "Warning: {} of {} chunks in scope have no embedding ({:.0}% coverage); {}. \
+         Run `ctx embed pending` to embed them.",
+        missing,
+        coverage.total_chunks,
+        coverage.ratio * 100.0,
+        effect
+    )
+}
+
+pub(crate) fn print_result(label: &str, indent: &str, result: &SearchResultItem) {
+    let title_display = result.title.as_deref().unwrap_or("(untitled)");
+    println!(
+        "{} [{:.2}] {} / {}",
+        label, result.score, result.source, title_display
+    );
+    if let Some(ref ex) = result.explain {
+        let mut curation = String::new();
+        if ex.boost != 1.0 {
+            curation.push_str(&format!("  boost=x{:.2}", ex.boost));
+        }
+        if ex.popularity != 1.0 {
+            curation.push_str(&format!("  popularity=x{:.2}", ex.popularity));
+        }
+        if ex.pinned {
+            curation.push_str("  pinned");
+        }
+        println!(
+            "{}scoring: keyword={:.3}  semantic={:.3}{}  → hybrid={:.3}",
+            indent, ex.keyword_score, ex.semantic_score, curation, result.score
+        );
+    }
+    println!("{}updated: {}", indent, result.updated_at);
+    println!("{}source: {}", indent, result.source);
+    if let Some(ref doc_type) = result.doc_type {
+        println!("{}type: {}", indent, doc_type);
+    }
+    if let Some(ref origin) = result.origin {
+        println!("{}index: {}", indent, origin);
+    }
+    if let Some(ref url) = result.source_url {
+        println!("{}url: {}", indent, url);
+    }
+    if let Some(ref file) = result.section_source_id {
+        println!("{}file: {}", indent, file);
+    }
+    if let Some(ref url) = result.section_source_url {
+        println!("{}file url: {}", indent, url);
+    }
+    if let Some(ref heading) = result.heading {
+        println!("{}section: {}", indent, heading);
+    }
+    if let Some(ref summary) = result.summary {
+        println!("{}summary: {}", indent, summary);
+    }
+    println!(
+        "{}excerpt: \"{}\"",
+        indent,
+        result.snippet.replace('\n', " ").trim()
+    );
+    println!("{}id: {}", indent, result.id);
+    if let Some(ref parent) = result.parent_id {
+        println!("{}parent: {}", indent, parent);
+    }
+    println!();
+}
@@ -0,0 +1,284 @@
+//! Search response cache for the MCP server.
+//!
+//! Agents tend to issue the same `search` call many times per session. The
+//! server keeps an in-memory LRU of built-in `search` tool responses, keyed by
+//! the call's parameters (query, mode, filters, limit, …) with the query text
+//! normalized the same way as the [`crate::query_cache`].
+//!
+//! # Invalidation
+//!
+//! `ctx sync` and `ctx embed` bump a counter in the `index_generation` table
+//! whenever they change the index. The cache reads the counter before each
+//! lookup and drops every entry when it has moved, so results never outlive
+//! the data they were computed from, even though syncs run in a separate
+//! process. Entries also expire after `[server] search_cache_ttl_secs`.
+//!
+//! # Configuration
+//!
+//! ```toml
+//! [server]
+//! search_cache_size = 256        # entries; 0 disables the cache
+//! search_cache_ttl_secs = 300
+//! ```
+//!
+//! Hit, miss, invalidation, and entry counts are exported on `GET /metrics`.
+
+use anyhow::Result;
+use serde_json::Value;
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::OnceCell;
+use tracing::Instrument;
+
+use crate::config::Config;
+use crate::db;
+use crate::migrate;
+use crate::query_cache::normalize_query;
+use crate::traits::{Tool, ToolContext};
+
+/// Name of the only tool whose responses are cached.
+const CACHED_TOOL: &str = "search";
+
+struct CacheEntry {
+    value: Value,
+    inserted: Instant,
+    last_used: u64,
+}
+
+/// Search cache counters for `/metrics`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SearchCacheStats {
+    pub entries: usize,
+    pub hits: u64,
+    pub misses: u64,
+    pub invalidations: u64,
+}
+
+/// In-memory LRU of `search` tool responses.
+pub struct SearchCache {
+    config: Arc<Config>,
+    capacity: usize,
+    ttl: Duration,
+    entries: Mutex<HashMap<String, CacheEntry>>,
+    generation: Mutex<Option<i64>>,
+    clock: AtomicU64,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    invalidations: AtomicU64,
+    pool: OnceCell<SqlitePool>,
+}
+
+impl SearchCache {
+    /// Build the cache from `[server]` settings, or `None` when disabled.
+    pub fn from_config(config: Arc<Config>) -> Option<Self> {
+        let capacity = config.server.search_cache_size;
+        let ttl = Duration::from_secs(config.server.search_cache_ttl_secs);
+        if capacity == 0 || ttl.is_zero() {
+            return None;
+        }
+        Some(Self {
+            config,
+            capacity,
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+            generation: Mutex::new(None),
+            clock: AtomicU64::new(0),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            invalidations: AtomicU64::new(0),
+            pool: OnceCell::new(),
+        })
+    }
+
+    /// Look up a cached response for these parameters.
+    pub async fn get(&self, params: &Value) -> Option<Value> {
+        if let Err(e) = self.check_generation().await {
+            tracing::warn!("search cache generation check failed: {}", e);
+            self.clear();
+        }
+
+        let key = cache_key(params);
+        let mut entries = self.entries.lock().unwrap();
+        let hit = match entries.get_mut(&key) {
+            Some(entry) if entry.inserted.elapsed() < self.ttl => {
+                entry.last_used = self.clock.fetch_add(1, Ordering::Relaxed);
+                Some(entry.value.clone())
+            }
+            Some(_) => {
+                entries.remove(&key);
+                None
+            }
+            None => None,
+        };
+        match hit {
+            Some(_) => self.hits.fetch_add(1, Ordering::Relaxed),
+            None => self.misses.fetch_add(1, Ordering::Relaxed),
+        };
+        hit
+    }
+
+    /// Store a response, evicting the least recently used entry when full.
+    pub fn insert(&self, params: &Value, value: Value) {
+        let mut entries = self.entries.lock().unwrap();
+        let key = cache_key(params);
+        if entries.len() >= self.capacity && !entries.contains_key(&key) {
+            if let Some(oldest) = entries
+                .iter()
+                .min_by_key(|(_, e)| e.last_used)
+                .map(|(k, _)| k.clone())
+            {
+                entries.remove(&oldest);
+            }
+        }
+        entries.insert(
+            key,
+            CacheEntry {
+                value,
+                inserted: Instant::now(),
+                last_used: self.clock.fetch_add(1, Ordering::Relaxed),
+            },
+        );
+    }
+
+    /// Current counters.
+    pub fn stats(&self) -> SearchCacheStats {
+        SearchCacheStats {
+            entries: self.entries.lock().unwrap().len(),
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            invalidations: self.invalidations.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Drop every cached response.
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+
+    /// Drop all entries if the index generation has changed since last seen.
+    async fn check_generation(&self) -> Result<()> {
+        let pool = self
+            .pool
+            .get_or_try_init(|| async {
+                let pool = db::connect(&self.config).await?;
+                migrate::create_index_generation_table(&pool).await?;
+                anyhow::Ok(pool)
+            })
+            .await?;
+        let current = index_generation(pool).await?;
+        let mut seen = self.generation.lock().unwrap();
+        if seen.is_some_and(|g| g != current) {
+            self.invalidations.fetch_add(1, Ordering::Relaxed);
+            self.clear();
+        }
+        *seen = Some(current);
+        Ok(())
+    }
+}
+
+/// Execute a tool, serving built-in `search` calls from the cache when possible.
+///
+/// Runs inside a `tool` span; `cached` is set when the cache answered.
+///
+/// Only successful responses are cached. Calls limited to an API key's
+/// source scope always run, so results filtered for one key are never
+/// served to another.
+pub async fn execute_tool(
+    cache: Option<&SearchCache>,
+    tool: &dyn Tool,
+    params: Value,
+    ctx: &ToolContext,
+) -> Result<Value> {
+    let span = tracing::info_span!("tool", tool = %tool.name(), cached = false);
+    let cache =
+        cache.filter(|_| tool.is_builtin() && tool.name() == CACHED_TOOL && ctx.scope().is_none());
+    let Some(cache) = cache else {
+        return tool.execute(params, ctx).instrument(span).await;
+    };
+
+    if let Some(hit) = cache.get(&params).await {
+        span.record("cached", true);
+        return Ok(hit);
+    }
+    let result = tool.execute(params.clone(), ctx).instrument(span).await?;
+    cache.insert(&params, result.clone());
+    Ok(result)
+}
+
+/// Cache key: the parameters as canonical JSON with the query normalized.
+fn cache_key(params: &Value) -> String {
+    let mut params = params.clone();
+    if let Some(query) = params.get_mut("query") {
+        if let Some(q) = query.as_str() {
+            *query = Value::String(normalize_query(q));
+        }
+    }
+    params.to_string()
+}
+
+/// Read the index generation counter.
+///
+/// Expects the table to exist (see [`migrate::create_index_generation_table`]).
+pub async fn index_generation(pool: &SqlitePool) -> Result<i64> {
+    let generation: i64 =
+        sqlx::query_scalar("SELECT generation FROM index_generation WHERE id = 1")
+            .fetch_one(pool)
+            .await?;
+    Ok(generation)
+}
+
+/// Record that the index changed, invalidating server search caches.
+pub async fn bump_index_generation(pool: &SqlitePool) -> Result<()> {
+    migrate::create_index_generation_table(pool).await?;
+    sqlx::query(
+        "UPDATE index_generation SET generation = generation + 1, updated_at = ? WHERE id = 1",
+    )
+    .bind(chrono::Utc::now().timestamp())
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn cache(capacity: usize) -> SearchCache {
+        let mut config = Config::minimal();
+        config.server.search_cache_size = capacity;
+        SearchCache::from_config(Arc::new(config)).unwrap()
+    }
+
+    #[test]
+    fn key_normalizes_query_only() {
+        let a = json!({"query": "  Deploy   Runbook", "mode": "keyword", "limit": 5});
+        let b = json!({"limit": 5, "mode": "keyword", "query": "deploy runbook"});
+        let c = json!({"query": "deploy runbook", "mode": "hybrid", "limit": 5});
+        assert_eq!(cache_key(&a), cache_key(&b));
+        assert_ne!(cache_key(&a), cache_key(&c));
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let cache = cache(2);
+        let (a, b, c) = (
+            json!({"query": "a"}),
+            json!({"query": "b"}),
+            json!({"query": "c"}),
+        );
+        cache.insert(&a, json!(1));
+        cache.insert(&b, json!(2));
+        // Refresh `a` so `b` becomes the eviction candidate.
+        cache.insert(&a, json!(1));
+        cache.insert(&c, json!(3));
+
+        let entries = cache.entries.lock().unwrap();
+        assert!(entries.contains_key(&cache_key(&a)));
+        assert!(!entries.contains_key(&cache_key(&b)));
+        assert!(entries.contains_key(&cache_key(&c)));
+    }
+}
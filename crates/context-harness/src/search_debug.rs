@@ -0,0 +1,634 @@
+//! `ctx search --debug-miss <DOC_ID>`: why a document was not found.
+//!
+//! Given a query and the document the user expected, [`debug_miss`] walks
+//! the same pipeline as [`crate::search::search_with_coverage`] and reports,
+//! for that one document:
+//!
+//! 1. **Index** — whether it is indexed, with how many chunks and embeddings.
+//! 2. **Filters** — which of `--source`, `--since`, `--as-of`, `--type`,
+//!    `--filter`, and the curation blocklist exclude it.
+//! 3. **Keyword / semantic** — the rank and raw score of each of its chunks
+//!    among all matching chunks, and whether that rank is inside the
+//!    candidate set (`candidate_k_keyword` / `candidate_k_vector`).
+//! 4. **Ranking** — its position in the full result list against the cutoff
+//!    (`--limit` or `final_limit`), with its score breakdown.
+//!
+//! It ends with a one-line verdict naming the first reason the document
+//! missed. The diagnostic run does not count as a retrieval for popularity
+//! or LRU eviction.
+
+use anyhow::{bail, Result};
+use chrono::NaiveDate;
+use sqlx::Row;
+
+use context_harness_core::search::chunk_metadata_matches;
+use context_harness_core::store::Store;
+
+use crate::config::Config;
+use crate::curate::Curation;
+use crate::db;
+use crate::embedding;
+use crate::keyword_index;
+use crate::query_cache;
+use crate::search::{
+    end_of_day_ts, parse_query, search_with_coverage, source_filter_matches, type_matches,
+    ChunkCandidate, ScoreExplanation,
+};
+use crate::sqlite_store::SqliteStore;
+use crate::vector_index;
+
+/// One chunk of the document in a keyword or vector ranking.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChunkRank {
+    pub chunk_index: i64,
+    pub heading: Option<String>,
+    /// 1-based rank among all matching chunks; `None` if it didn't match.
+    pub rank: Option<usize>,
+    /// BM25 score (negated, higher is better) or cosine similarity.
+    pub raw_score: Option<f64>,
+    /// Whether the chunk passes the `--filter` chunk filters.
+    pub passes_filters: bool,
+}
+
+/// The document's chunks in one retrieval space.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpaceReport {
+    /// Chunks across the index that matched the query.
+    pub matched: usize,
+    /// Candidates the search keeps from this space.
+    pub candidate_k: i64,
+    pub chunks: Vec<ChunkRank>,
+}
+
+impl SpaceReport {
+    /// Best rank of a chunk that passes the chunk filters.
+    pub fn best_rank(&self) -> Option<usize> {
+        self.chunks
+            .iter()
+            .filter(|c| c.passes_filters)
+            .filter_map(|c| c.rank)
+            .min()
+    }
+
+    /// Whether a filtered chunk ranks inside the candidate set.
+    pub fn is_candidate(&self) -> bool {
+        self.best_rank()
+            .is_some_and(|rank| rank as i64 <= self.candidate_k)
+    }
+}
+
+/// A filter and whether the document passes it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilterCheck {
+    pub name: &'static str,
+    /// `None` when it passes; otherwise why it excludes the document.
+    pub excluded: Option<String>,
+}
+
+/// Everything `--debug-miss` found out about one document.
+#[derive(Debug, Clone)]
+pub struct MissReport {
+    pub doc_id: String,
+    pub title: Option<String>,
+    pub source: String,
+    pub chunks: usize,
+    pub embedded: usize,
+    pub filters: Vec<FilterCheck>,
+    pub keyword: Option<SpaceReport>,
+    /// `None` in keyword mode or when nothing is embedded.
+    pub semantic: Option<SpaceReport>,
+    /// 1-based position in the full result list.
+    pub position: Option<usize>,
+    pub explain: Option<ScoreExplanation>,
+    /// Documents the full search returned.
+    pub ranked: usize,
+    pub cutoff: usize,
+}
+
+impl MissReport {
+    /// The first reason the document is missing, or where it was found.
+    pub fn verdict(&self) -> String {
+        if let Some(check) = self.filters.iter().find(|f| f.excluded.is_some()) {
+            return format!(
+                "excluded by {}: {}",
+                check.name,
+                check.excluded.as_deref().unwrap_or_default()
+            );
+        }
+        if let Some(position) = self.position {
+            return if position <= self.cutoff {
+                format!("found at rank {} (limit {})", position, self.cutoff)
+            } else {
+                format!(
+                    "ranked {}, below the cutoff of {}; raise --limit or refine the query",
+                    position, self.cutoff
+                )
+            };
+        }
+        let spaces = [("keyword", &self.keyword), ("semantic", &self.semantic)];
+        let searched: Vec<(&str, &SpaceReport)> = spaces
+            .iter()
+            .filter_map(|&(name, space)| space.as_ref().map(|s| (name, s)))
+            .collect();
+        if searched.iter().all(|(_, s)| s.best_rank().is_none()) {
+            return "no chunk matches the query".to_string();
+        }
+        if searched.iter().all(|(_, s)| !s.is_candidate()) {
+            let (name, space) = searched
+                .iter()
+                .find(|(_, s)| s.best_rank().is_some())
+                .unwrap();
+            return format!(
+                "best chunk ranks {} in {} search, outside candidate_k_{} = {}",
+                space.best_rank().unwrap_or_default(),
+                name,
+                if *name == "keyword" {
+                    "keyword"
+                } else {
+                    "vector"
+                },
+                space.candidate_k
+            );
+        }
+        "a chunk was a candidate but the document was not returned".to_string()
+    }
+}
+
+/// Diagnose why `doc_id` is missing from the results of `query`.
+///
+/// Takes the same arguments as `ctx search`. Returns `Ok(None)` if the
+/// document is not indexed.
+///
+/// # Errors
+///
+/// Returns an error for an invalid mode or date, or if the database or
+/// the embedding provider fails.
+#[allow(clippy::too_many_arguments)]
+pub async fn debug_miss(
+    config: &Config,
+    query: &str,
+    mode: &str,
+    doc_id: &str,
+    source_filter: Option<&str>,
+    since: Option<&str>,
+    as_of: Option<&str>,
+    limit: Option<i64>,
+    chunk_filters: &[(String, String)],
+    types: &[String],
+) -> Result<Option<MissReport>> {
+    let pool = db::connect_reader(config).await?;
+    let store = SqliteStore::new(pool.clone()).with_retrieval(&config.retrieval);
+    let Some(meta) = store.get_document_metadata(doc_id).await? else {
+        db::close_reader(config, pool).await;
+        return Ok(None);
+    };
+
+    let rows = sqlx::query(
+        "SELECT c.id, c.chunk_index, cv.chunk_id IS NOT NULL AS embedded \
+         FROM chunks c LEFT JOIN chunk_vectors cv ON cv.chunk_id = c.id \
+         WHERE c.document_id = ? ORDER BY c.chunk_index",
+    )
+    .bind(&meta.id)
+    .fetch_all(&pool)
+    .await?;
+    let mut chunks = Vec::new();
+    for row in rows {
+        let id: String = row.get("id");
+        let chunk_meta = store.get_chunk_metadata(&id).await?.unwrap_or_default();
+        chunks.push(DocChunk {
+            heading: chunk_meta
+                .get("heading")
+                .and_then(|h| h.as_str())
+                .map(str::to_string),
+            passes_filters: chunk_metadata_matches(&chunk_meta, chunk_filters),
+            id,
+            index: row.get("chunk_index"),
+            embedded: row.get("embedded"),
+        });
+    }
+    let embedded = chunks.iter().filter(|c| c.embedded).count();
+
+    let mut filters = Vec::new();
+    if let Some(filter) = source_filter {
+        filters.push(FilterCheck {
+            name: "--source",
+            excluded: (!source_filter_matches(filter, &meta.source))
+                .then(|| format!("source is {}, not {}", meta.source, filter)),
+        });
+    }
+    if let Some(date) = since {
+        let since_ts = NaiveDate::parse_from_str(date, "%Y-%m-%d")?
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+            .and_utc()
+            .timestamp();
+        filters.push(FilterCheck {
+            name: "--since",
+            excluded: (meta.updated_at < since_ts)
+                .then(|| format!("updated {}, before {}", format_day(meta.updated_at), date)),
+        });
+    }
+    if let Some(date) = as_of {
+        filters.push(FilterCheck {
+            name: "--as-of",
+            excluded: (meta.updated_at > end_of_day_ts(date)?)
+                .then(|| format!("updated {}, after {}", format_day(meta.updated_at), date)),
+        });
+    }
+    if !types.is_empty() {
+        filters.push(FilterCheck {
+            name: "--type",
+            excluded: (!type_matches(
+                types,
+                &meta.source_id,
+                &meta.content_type,
+                meta.doc_type.as_deref(),
+            ))
+            .then(|| {
+                format!(
+                    "{} ({}) is not {}",
+                    meta.doc_type.as_deref().unwrap_or("untyped"),
+                    meta.content_type,
+                    types.join(", ")
+                )
+            }),
+        });
+    }
+    if !chunk_filters.is_empty() {
+        let passing = chunks.iter().filter(|c| c.passes_filters).count();
+        filters.push(FilterCheck {
+            name: "--filter",
+            excluded: (passing == 0).then(|| format!("none of {} chunks match", chunks.len())),
+        });
+    }
+    let curation = Curation::load(&pool).await?;
+    filters.push(FilterCheck {
+        name: "curation",
+        excluded: curation
+            .is_blocked(&meta.id)
+            .then(|| "blocked with ctx curate block".to_string()),
+    });
+
+    let total_chunks: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM chunks")
+        .fetch_one(&pool)
+        .await?;
+    let keyword = if mode == "keyword" || mode == "hybrid" {
+        let store = keyword_index::with_keyword_index(
+            config,
+            SqliteStore::new(pool.clone()).with_retrieval(&config.retrieval),
+        )?;
+        let candidates = store
+            .keyword_search(query, total_chunks.max(1), source_filter, since)
+            .await?;
+        Some(space_report(
+            &candidates,
+            &chunks,
+            config.retrieval.candidate_k_keyword,
+        ))
+    } else {
+        None
+    };
+    let semantic = if mode != "keyword" && config.embedding.is_enabled() && embedded > 0 {
+        let provider = embedding::create_checked_provider(&config.embedding, &pool).await?;
+        let cache = db::writer_for(config, &pool).await?;
+        let query_vec =
+            query_cache::embed_query_cached(&cache, provider.as_ref(), &config.embedding, query)
+                .await;
+        if config.db.read_pool {
+            cache.close().await;
+        }
+        let store = vector_index::configured_vector_store(config, pool.clone()).await?;
+        let candidates = store
+            .vector_search(&query_vec?, total_chunks.max(1), source_filter, since)
+            .await?;
+        Some(space_report(
+            &candidates,
+            &chunks,
+            config.retrieval.candidate_k_vector,
+        ))
+    } else {
+        None
+    };
+    let documents: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM documents")
+        .fetch_one(&pool)
+        .await?;
+    db::close_reader(config, pool).await;
+
+    // Rank every document, without recording the run as retrievals.
+    let mut quiet = config.clone();
+    quiet.retrieval.track_access = false;
+    quiet.storage.max_size_mb = None;
+    let outcome = search_with_coverage(
+        &quiet,
+        query,
+        mode,
+        source_filter,
+        since,
+        as_of,
+        Some(documents.max(1)),
+        true,
+        chunk_filters,
+        types,
+        None,
+    )
+    .await?;
+    let found = outcome
+        .results
+        .iter()
+        .enumerate()
+        .find(|(_, r)| r.id == meta.id);
+
+    Ok(Some(MissReport {
+        doc_id: meta.id.clone(),
+        title: meta.title.clone(),
+        source: meta.source.clone(),
+        chunks: chunks.len(),
+        embedded,
+        filters,
+        keyword,
+        semantic,
+        position: found.map(|(i, _)| i + 1),
+        explain: found.and_then(|(_, r)| r.explain.clone()),
+        ranked: outcome.results.len(),
+        cutoff: limit.unwrap_or(config.retrieval.final_limit).max(0) as usize,
+    }))
+}
+
+/// A chunk of the diagnosed document.
+struct DocChunk {
+    id: String,
+    index: i64,
+    heading: Option<String>,
+    embedded: bool,
+    passes_filters: bool,
+}
+
+/// Where the document's chunks fall among `candidates`, which are sorted
+/// best first.
+fn space_report(
+    candidates: &[ChunkCandidate],
+    chunks: &[DocChunk],
+    candidate_k: i64,
+) -> SpaceReport {
+    SpaceReport {
+        matched: candidates.len(),
+        candidate_k,
+        chunks: chunks
+            .iter()
+            .map(|chunk| {
+                let hit = candidates
+                    .iter()
+                    .enumerate()
+                    .find(|(_, c)| c.chunk_id == chunk.id);
+                ChunkRank {
+                    chunk_index: chunk.index,
+                    heading: chunk.heading.clone(),
+                    rank: hit.map(|(i, _)| i + 1),
+                    raw_score: hit.map(|(_, c)| c.raw_score),
+                    passes_filters: chunk.passes_filters,
+                }
+            })
+            .collect(),
+    }
+}
+
+fn format_day(ts: i64) -> String {
+    chrono::DateTime::from_timestamp(ts, 0)
+        .map(|dt| dt.format("%Y-%m-%d").to_string())
+        .unwrap_or_else(|| ts.to_string())
+}
+
+/// Run `ctx search --debug-miss`: parse inline modifiers like `ctx search`
+/// does, diagnose, and print the report.
+#[allow(clippy::too_many_arguments)]
+pub async fn run_debug_miss(
+    config: &Config,
+    query: &str,
+    mode: &str,
+    doc_id: &str,
+    source_filter: Option<String>,
+    since: Option<String>,
+    as_of: Option<String>,
+    limit: Option<i64>,
+    mut filters: Vec<(String, String)>,
+    types: Vec<String>,
+    fuzzy: bool,
+) -> Result<()> {
+    let mut config = config.clone();
+    config.retrieval.fuzzy |= fuzzy;
+    let mut parsed = parse_query(query);
+    if parsed.text.trim().is_empty() {
+        bail!("--debug-miss needs a query");
+    }
+    match mode {
+        "keyword" | "semantic" | "hybrid" => {}
+        _ => bail!(
+            "Unknown search mode: {}. Use keyword, semantic, or hybrid.",
+            mode
+        ),
+    }
+    if mode != "keyword" && !config.embedding.is_enabled() {
+        bail!(
+            "Mode '{}' requires embeddings. Set [embedding] provider in config.",
+            mode
+        );
+    }
+    filters.extend(parsed.ref_filter());
+    let source_filter = source_filter.or(parsed.source);
+    let since = since.or(parsed.since);
+    let as_of = as_of.or(parsed.as_of);
+    parsed.types.extend(types);
+
+    let report = debug_miss(
+        &config,
+        &parsed.text,
+        mode,
+        doc_id,
+        source_filter.as_deref(),
+        since.as_deref(),
+        as_of.as_deref(),
+        limit,
+        &filters,
+        &parsed.types,
+    )
+    .await?;
+    let Some(report) = report else {
+        println!("Document {} is not indexed.", doc_id);
+        println!();
+        println!("Verdict: not indexed; check the ID with `ctx get` or sync its source");
+        return Ok(());
+    };
+
+    println!(
+        "Document {} — {} ({})",
+        report.doc_id,
+        report.title.as_deref().unwrap_or("(untitled)"),
+        report.source
+    );
+    println!(
+        "  indexed: {} chunks, {} embedded",
+        report.chunks, report.embedded
+    );
+    println!();
+
+    println!("Filters:");
+    for check in &report.filters {
+        match &check.excluded {
+            None => println!("  {:<10} pass", check.name),
+            Some(reason) => println!("  {:<10} EXCLUDED — {}", check.name, reason),
+        }
+    }
+    println!();
+
+    for (name, space) in [("Keyword", &report.keyword), ("Semantic", &report.semantic)] {
+        let Some(space) = space else {
+            continue;
+        };
+        println!(
+            "{}: {} matching chunks, candidate_k = {}",
+            name, space.matched, space.candidate_k
+        );
+        for chunk in &space.chunks {
+            let heading = chunk.heading.as_deref().unwrap_or("");
+            let filtered = if chunk.passes_filters {
+                ""
+            } else {
+                " (filtered out)"
+            };
+            match (chunk.rank, chunk.raw_score) {
+                (Some(rank), Some(score)) => println!(
+                    "  chunk {:<3} rank {:<5} score {:.4}  {}{}{}",
+                    chunk.chunk_index,
+                    rank,
+                    score,
+                    if rank as i64 <= space.candidate_k {
+                        "candidate"
+                    } else {
+                        "beyond candidate_k"
+                    },
+                    filtered,
+                    if heading.is_empty() {
+                        String::new()
+                    } else {
+                        format!("  [{}]", heading)
+                    }
+                ),
+                _ => println!("  chunk {:<3} no match{}", chunk.chunk_index, filtered),
+            }
+        }
+        println!();
+    }
+    if mode != "keyword" && report.semantic.is_none() {
+        println!("Semantic: skipped, no chunks are embedded");
+        println!();
+    }
+
+    match report.position {
+        Some(position) => {
+            println!(
+                "Ranking: position {} of {} (cutoff {})",
+                position, report.ranked, report.cutoff
+            );
+            if let Some(ref ex) = report.explain {
+                println!(
+                    "  keyword={:.3}  semantic={:.3}  alpha={:.2}  boost={:.2}  popularity={:.3}",
+                    ex.keyword_score, ex.semantic_score, ex.alpha, ex.boost, ex.popularity
+                );
+            }
+        }
+        None => println!(
+            "Ranking: not among the {} documents returned (cutoff {})",
+            report.ranked, report.cutoff
+        ),
+    }
+    println!();
+    println!("Verdict: {}", report.verdict());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(chunk_id: &str, raw_score: f64) -> ChunkCandidate {
+        ChunkCandidate {
+            chunk_id: chunk_id.to_string(),
+            document_id: "d".to_string(),
+            raw_score,
+            snippet: String::new(),
+        }
+    }
+
+    fn chunk(id: &str, index: i64, passes_filters: bool) -> DocChunk {
+        DocChunk {
+            id: id.to_string(),
+            index,
+            heading: None,
+            embedded: false,
+            passes_filters,
+        }
+    }
+
+    fn report(keyword: SpaceReport) -> MissReport {
+        MissReport {
+            doc_id: "d".to_string(),
+            title: None,
+            source: "filesystem".to_string(),
+            chunks: keyword.chunks.len(),
+            embedded: 0,
+            filters: Vec::new(),
+            keyword: Some(keyword),
+            semantic: None,
+            position: None,
+            explain: None,
+            ranked: 0,
+            cutoff: 12,
+        }
+    }
+
+    #[test]
+    fn space_report_ranks_filtered_chunks() {
+        let candidates = [
+            candidate("x", 9.0),
+            candidate("a1", 7.0),
+            candidate("y", 5.0),
+            candidate("a0", 3.0),
+        ];
+        let chunks = [
+            chunk("a0", 0, true),
+            chunk("a1", 1, false),
+            chunk("a2", 2, true),
+        ];
+        let space = space_report(&candidates, &chunks, 3);
+        assert_eq!(space.matched, 4);
+        assert_eq!(space.chunks[0].rank, Some(4));
+        assert_eq!(space.chunks[1].raw_score, Some(7.0));
+        assert_eq!(space.chunks[2].rank, None);
+        // a1 ranks 2nd but is filtered out, so the best usable rank is 4.
+        assert_eq!(space.best_rank(), Some(4));
+        assert!(!space.is_candidate());
+
+        let report = report(space);
+        assert_eq!(
+            report.verdict(),
+            "best chunk ranks 4 in keyword search, outside candidate_k_keyword = 3"
+        );
+    }
+
+    #[test]
+    fn verdict_prefers_filters_then_rank() {
+        let space = space_report(&[candidate("a0", 1.0)], &[chunk("a0", 0, true)], 80);
+        let mut report = report(space);
+        report.position = Some(15);
+        assert_eq!(
+            report.verdict(),
+            "ranked 15, below the cutoff of 12; raise --limit or refine the query"
+        );
+
+        report.filters.push(FilterCheck {
+            name: "--source",
+            excluded: Some("source is git:docs, not filesystem".to_string()),
+        });
+        assert!(report.verdict().starts_with("excluded by --source"));
+    }
+}
@@ -0,0 +1,401 @@
+//! `ctx search --repl`: an interactive search session.
+//!
+//! Each line is a query, searched with the session's settings and printed
+//! as numbered results. The config, database pool, and query embeddings
+//! are kept between queries, so iterating on a query costs one search
+//! rather than a process start and a database open.
+//!
+//! | Input | Action |
+//! |-------|--------|
+//! | `<query>` | Search; inline modifiers work as in `ctx search` |
+//! | `open N` | Print document `N` of the last results |
+//! | `!N`, `!!` | Run query `N` of the history, or the last query, again |
+//! | `/mode keyword\|semantic\|hybrid` | Set the search mode |
+//! | `/alpha X` | Set the hybrid weight and re-rank the last query |
+//! | `/source NAME`, `/since DATE` | Set a filter; without a value, clear it |
+//! | `/filter KEY=VALUE` | Add a chunk metadata filter; `/filter` clears them |
+//! | `/limit N` | Set the result count; `/limit` restores the default |
+//! | `/explain`, `/fuzzy` | Toggle score breakdowns or typo tolerance |
+//! | `/history`, `/settings`, `/help` | Show queries, settings, or this table |
+//! | `/quit`, Ctrl-D | End the session |
+//!
+//! Settings start from the `ctx search` flags given with `--repl`.
+
+use anyhow::{bail, Result};
+use std::io::Write;
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+use crate::config::Config;
+use crate::get;
+use crate::scrub;
+use crate::search::{self, SearchResultItem};
+
+const HELP: &str = "\
+  <query>              search (inline modifiers such as source:git work)
+  open N               print document N of the last results
+  !N, !!               run history entry N, or the last query, again
+  /mode MODE           keyword, semantic, or hybrid
+  /alpha X             hybrid weight in [0, 1]; re-ranks the last query
+  /source [NAME]       filter by connector; no value clears
+  /since [DATE]        only documents updated on or after DATE
+  /filter [KEY=VALUE]  add a chunk metadata filter; no value clears all
+  /limit [N]           number of results; no value restores the default
+  /explain, /fuzzy     toggle score breakdowns or typo tolerance
+  /history, /settings  show past queries or current settings
+  /quit                end the session (or Ctrl-D)";
+
+/// Search settings a session starts with, from the `ctx search` flags.
+#[derive(Debug, Clone)]
+pub struct ReplSettings {
+    pub mode: String,
+    pub source: Option<String>,
+    pub since: Option<String>,
+    pub as_of: Option<String>,
+    pub limit: Option<i64>,
+    pub explain: bool,
+    pub filters: Vec<(String, String)>,
+    pub fuzzy: bool,
+}
+
+/// One line of REPL input.
+#[derive(Debug, PartialEq)]
+enum Input {
+    Empty,
+    Query(String),
+    Open(usize),
+    Rerun(Option<usize>),
+    Mode(String),
+    Alpha(f64),
+    Source(Option<String>),
+    Since(Option<String>),
+    Filter(Option<(String, String)>),
+    Limit(Option<i64>),
+    Explain,
+    Fuzzy,
+    History,
+    Settings,
+    Help,
+    Quit,
+}
+
+/// Parse one line. `Err` carries a message for the user.
+fn parse_input(line: &str) -> Result<Input, String> {
+    let line = line.trim();
+    if line.is_empty() {
+        return Ok(Input::Empty);
+    }
+    if line == "!!" {
+        return Ok(Input::Rerun(None));
+    }
+    if let Some(n) = line.strip_prefix('!') {
+        return parse_index(n).map(|n| Input::Rerun(Some(n)));
+    }
+    if let Some(n) = line.strip_prefix("open ") {
+        return parse_index(n).map(Input::Open);
+    }
+    let Some(command) = line.strip_prefix('/') else {
+        return Ok(Input::Query(line.to_string()));
+    };
+
+    let (name, arg) = match command.split_once(char::is_whitespace) {
+        Some((name, arg)) => (name, Some(arg.trim()).filter(|a| !a.is_empty())),
+        None => (command, None),
+    };
+    Ok(match (name, arg) {
+        ("mode", Some(mode @ ("keyword" | "semantic" | "hybrid"))) => Input::Mode(mode.to_string()),
+        ("mode", _) => return Err("usage: /mode keyword|semantic|hybrid".to_string()),
+        ("alpha", Some(value)) => match value.parse::<f64>() {
+            Ok(alpha) if (0.0..=1.0).contains(&alpha) => Input::Alpha(alpha),
+            _ => return Err("alpha must be a number in [0, 1]".to_string()),
+        },
+        ("alpha", None) => return Err("usage: /alpha X".to_string()),
+        ("source", arg) => Input::Source(arg.map(String::from)),
+        ("since", arg) => Input::Since(arg.map(String::from)),
+        ("filter", None) => Input::Filter(None),
+        ("filter", Some(pair)) => match pair.split_once('=') {
+            Some((key, value)) if !key.trim().is_empty() => {
+                Input::Filter(Some((key.trim().to_string(), value.trim().to_string())))
+            }
+            _ => return Err("usage: /filter KEY=VALUE".to_string()),
+        },
+        ("limit", None) => Input::Limit(None),
+        ("limit", Some(n)) => match n.parse::<i64>() {
+            Ok(n) if n >= 1 => Input::Limit(Some(n)),
+            _ => return Err("limit must be a positive number".to_string()),
+        },
+        ("explain", None) => Input::Explain,
+        ("fuzzy", None) => Input::Fuzzy,
+        ("history", None) => Input::History,
+        ("settings", None) => Input::Settings,
+        ("help", None) => Input::Help,
+        ("quit" | "exit" | "q", None) => Input::Quit,
+        _ => return Err(format!("unknown command /{}; try /help", name)),
+    })
+}
+
+/// A 1-based index from user input.
+fn parse_index(text: &str) -> Result<usize, String> {
+    match text.trim().parse::<usize>() {
+        Ok(n) if n >= 1 => Ok(n),
+        _ => Err(format!("expected a number from 1, got '{}'", text.trim())),
+    }
+}
+
+/// State carried between queries.
+struct Session {
+    config: Config,
+    settings: ReplSettings,
+    history: Vec<String>,
+    last_query: Option<String>,
+    results: Vec<SearchResultItem>,
+}
+
+impl Session {
+    /// Handle one line; `false` ends the session.
+    async fn handle(&mut self, input: Input) -> Result<bool> {
+        match input {
+            Input::Empty => {}
+            Input::Query(query) => self.run_query(query, true).await?,
+            Input::Rerun(index) => {
+                let query = match index {
+                    None => self.history.last(),
+                    Some(n) => self.history.get(n - 1),
+                };
+                match query.cloned() {
+                    Some(query) => {
+                        println!("{}", query);
+                        self.run_query(query, true).await?;
+                    }
+                    None => eprintln!("no such history entry"),
+                }
+            }
+            Input::Open(n) => match self.results.get(n - 1) {
+                Some(result) => {
+                    let doc = get::get_document(&self.config, &result.id).await?;
+                    get::print_document(&doc, false);
+                }
+                None => eprintln!("no result {} (last search had {})", n, self.results.len()),
+            },
+            Input::Mode(mode) => {
+                self.settings.mode = mode;
+                self.print_settings();
+            }
+            Input::Alpha(alpha) => {
+                self.config.retrieval.hybrid_alpha = alpha;
+                if self.settings.mode != "hybrid" {
+                    println!(
+                        "alpha = {:.2} (applies to hybrid mode; mode is {})",
+                        alpha, self.settings.mode
+                    );
+                } else if let Some(query) = self.last_query.clone() {
+                    self.run_query(query, false).await?;
+                }
+            }
+            Input::Source(source) => self.settings.source = source,
+            Input::Since(since) => self.settings.since = since,
+            Input::Filter(Some(filter)) => self.settings.filters.push(filter),
+            Input::Filter(None) => self.settings.filters.clear(),
+            Input::Limit(limit) => self.settings.limit = limit,
+            Input::Explain => {
+                self.settings.explain = !self.settings.explain;
+                println!("explain: {}", on_off(self.settings.explain));
+            }
+            Input::Fuzzy => {
+                self.settings.fuzzy = !self.settings.fuzzy;
+                self.config.retrieval.fuzzy = self.settings.fuzzy;
+                println!("fuzzy: {}", on_off(self.settings.fuzzy));
+            }
+            Input::History => {
+                for (i, query) in self.history.iter().enumerate() {
+                    println!("{:>4}  {}", i + 1, query);
+                }
+            }
+            Input::Settings => self.print_settings(),
+            Input::Help => println!("{}", HELP),
+            Input::Quit => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    /// Search for `query` with the current settings and print the results.
+    async fn run_query(&mut self, query: String, record: bool) -> Result<()> {
+        if record {
+            self.history.push(query.clone());
+        }
+        self.last_query = Some(query.clone());
+
+        let parsed = search::parse_query(&query);
+        if parsed.text.is_empty() && parsed.has_modifiers() {
+            bail!("query has no search terms besides its modifiers");
+        }
+        let mut filters = self.settings.filters.clone();
+        filters.extend(parsed.ref_filter());
+        let source = self.settings.source.clone().or(parsed.source);
+        let since = self.settings.since.clone().or(parsed.since);
+        let as_of = self.settings.as_of.clone().or(parsed.as_of);
+        self.results = search::search_documents_filtered(
+            &self.config,
+            &parsed.text,
+            &self.settings.mode,
+            source.as_deref(),
+            since.as_deref(),
+            as_of.as_deref(),
+            self.settings.limit,
+            self.settings.explain,
+            &filters,
+            &parsed.types,
+            None,
+        )
+        .await?;
+
+        if self.results.is_empty() {
+            println!("No results.");
+            return Ok(());
+        }
+        if self.settings.mode == "hybrid" {
+            println!("hybrid, alpha={:.2}", self.config.retrieval.hybrid_alpha);
+            println!();
+        }
+        for (i, result) in self.results.iter().enumerate() {
+            search::print_result(&format!("{}.", i + 1), "    ", result);
+        }
+        Ok(())
+    }
+
+    fn print_settings(&self) {
+        let s = &self.settings;
+        println!("mode:    {}", s.mode);
+        println!("alpha:   {:.2}", self.config.retrieval.hybrid_alpha);
+        println!("source:  {}", s.source.as_deref().unwrap_or("(any)"));
+        println!("since:   {}", s.since.as_deref().unwrap_or("(any)"));
+        if let Some(ref as_of) = s.as_of {
+            println!("as of:   {}", as_of);
+        }
+        let filters: Vec<String> = s
+            .filters
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect();
+        println!(
+            "filters: {}",
+            if filters.is_empty() {
+                "(none)".to_string()
+            } else {
+                filters.join(", ")
+            }
+        );
+        match s.limit {
+            Some(limit) => println!("limit:   {}", limit),
+            None => println!("limit:   {} (default)", self.config.retrieval.final_limit),
+        }
+        println!("explain: {}", on_off(s.explain));
+        println!("fuzzy:   {}", on_off(s.fuzzy));
+    }
+}
+
+fn on_off(flag: bool) -> &'static str {
+    if flag {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+/// Run an interactive search session on stdin, starting with `query` when
+/// one is given.
+///
+/// Errors from a single query (an unknown source, a semantic search without
+/// embeddings) are printed and the session continues.
+pub async fn run_repl(
+    config: &Config,
+    mut settings: ReplSettings,
+    query: Option<&str>,
+) -> Result<()> {
+    let mut config = config.clone();
+    // Keep one read pool open for the whole session.
+    config.db.read_pool = true;
+    config.retrieval.fuzzy |= settings.fuzzy;
+    settings.fuzzy = config.retrieval.fuzzy;
+    let interactive = atty::is(atty::Stream::Stdin);
+    let mut session = Session {
+        config,
+        settings,
+        history: Vec::new(),
+        last_query: None,
+        results: Vec::new(),
+    };
+
+    if interactive {
+        println!("ctx search: type a query, /help for commands, /quit to leave");
+    }
+    if let Some(query) = query {
+        if let Err(e) = session.run_query(query.to_string(), true).await {
+            eprintln!("error: {}", scrub::scrub(&format!("{:#}", e)));
+        }
+    }
+
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    loop {
+        if interactive {
+            print!("ctx> ");
+            std::io::stdout().flush()?;
+        }
+        let Some(line) = lines.next_line().await? else {
+            break;
+        };
+        let input = match parse_input(&line) {
+            Ok(input) => input,
+            Err(message) => {
+                eprintln!("{}", message);
+                continue;
+            }
+        };
+        match session.handle(input).await {
+            Ok(true) => {}
+            Ok(false) => break,
+            Err(e) => eprintln!("error: {}", scrub::scrub(&format!("{:#}", e))),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_queries_and_commands() {
+        assert_eq!(
+            parse_input("  deploy rollback "),
+            Ok(Input::Query("deploy rollback".to_string()))
+        );
+        assert_eq!(parse_input(""), Ok(Input::Empty));
+        assert_eq!(parse_input("open 3"), Ok(Input::Open(3)));
+        assert_eq!(parse_input("!2"), Ok(Input::Rerun(Some(2))));
+        assert_eq!(parse_input("!!"), Ok(Input::Rerun(None)));
+        assert_eq!(
+            parse_input("/mode hybrid"),
+            Ok(Input::Mode("hybrid".to_string()))
+        );
+        assert_eq!(parse_input("/alpha 0.3"), Ok(Input::Alpha(0.3)));
+        assert_eq!(parse_input("/source"), Ok(Input::Source(None)));
+        assert_eq!(
+            parse_input("/filter heading = install"),
+            Ok(Input::Filter(Some((
+                "heading".to_string(),
+                "install".to_string()
+            ))))
+        );
+        assert_eq!(parse_input("/limit"), Ok(Input::Limit(None)));
+        assert_eq!(parse_input("/quit"), Ok(Input::Quit));
+    }
+
+    #[test]
+    fn rejects_bad_commands() {
+        assert!(parse_input("/alpha 1.5").is_err());
+        assert!(parse_input("/mode fast").is_err());
+        assert!(parse_input("/limit 0").is_err());
+        assert!(parse_input("open 0").is_err());
+        assert!(parse_input("/frobnicate").is_err());
+    }
+}
@@ -0,0 +1,434 @@
+//! Secret resolution for connectors, Lua scripts, and embedding providers.
+//!
+//! Config values of the form `secret://<name>` are resolved at the point of
+//! use instead of being stored in plaintext TOML:
+//!
+//! ```toml
+//! [connectors.s3.docs]
+//! bucket = "acme-docs"
+//! access_key_id = "secret://aws-access-key-id"
+//! secret_access_key = "secret://aws-secret-access-key"
+//!
+//! [embedding]
+//! provider = "openai"
+//! api_key = "secret://openai"
+//!
+//! [connectors.script.jira]
+//! path = "connectors/jira.lua"
+//! api_token = "secret://jira-token"
+//! ```
+//!
+//! Lua scripts can also look secrets up directly with `secrets.get(name)`.
+//!
+//! # Backends
+//!
+//! A reference is tried against each backend in order; the first hit wins.
+//! `secret://<backend>/<name>` pins a single backend.
+//!
+//! | Backend | Lookup |
+//! |---------|--------|
+//! | `env` | `CTX_SECRET_<NAME>` (uppercased, non-alphanumerics become `_`) |
+//! | `file` | `name = "value"` in `$CTX_SECRETS_FILE` or `<config dir>/secrets.toml` |
+//! | `keychain` | OS keychain item with service `context-harness` and account `<name>` |
+//!
+//! The keychain backend uses the platform CLI: `security` on macOS and
+//! `secret-tool` (libsecret) on Linux. It is unavailable on other platforms.
+//!
+//! `ctx secrets set <name>` stores a value read from stdin in the file or
+//! keychain backend; `ctx secrets check <name>` reports which backend
+//! resolves it without printing the value.
+
+use anyhow::{bail, Context, Result};
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+use crate::ctx_dirs;
+use crate::scrub;
+
+/// Prefix marking a config value as a secret reference.
+pub const SECRET_SCHEME: &str = "secret://";
+
+/// Keychain service name under which secrets are stored.
+const KEYCHAIN_SERVICE: &str = "context-harness";
+
+/// A source of named secrets.
+pub trait SecretBackend {
+    /// Backend name used in `secret://<backend>/<name>` references.
+    fn name(&self) -> &'static str;
+    /// Look a secret up. `Ok(None)` means the backend does not have it.
+    fn get(&self, name: &str) -> Result<Option<String>>;
+}
+
+/// Environment variables: `secret://github-token` → `CTX_SECRET_GITHUB_TOKEN`.
+pub struct EnvBackend;
+
+impl EnvBackend {
+    pub fn var_name(name: &str) -> String {
+        let name: String = name
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() {
+                    c.to_ascii_uppercase()
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        format!("CTX_SECRET_{}", name)
+    }
+}
+
+impl SecretBackend for EnvBackend {
+    fn name(&self) -> &'static str {
+        "env"
+    }
+
+    fn get(&self, name: &str) -> Result<Option<String>> {
+        Ok(std::env::var(Self::var_name(name)).ok())
+    }
+}
+
+/// A TOML file of `name = "value"` pairs.
+pub struct FileBackend {
+    pub path: PathBuf,
+}
+
+impl FileBackend {
+    /// `$CTX_SECRETS_FILE`, or `secrets.toml` in the global config directory.
+    pub fn default_path() -> PathBuf {
+        std::env::var_os("CTX_SECRETS_FILE")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| ctx_dirs::config_dir().join("secrets.toml"))
+    }
+
+    fn load(&self) -> Result<toml::Table> {
+        if !self.path.exists() {
+            return Ok(toml::Table::new());
+        }
+        let content = std::fs::read_to_string(&self.path)
+            .with_context(|| format!("Failed to read secrets file: {}", self.path.display()))?;
+        content
+            .parse()
+            .with_context(|| format!("Failed to parse secrets file: {}", self.path.display()))
+    }
+
+    /// Insert or replace a secret, keeping the file readable only by its owner.
+    pub fn set(&self, name: &str, value: &str) -> Result<()> {
+        let mut table = self.load()?;
+        table.insert(name.to_string(), toml::Value::String(value.to_string()));
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&self.path, toml::to_string(&table)?)
+            .with_context(|| format!("Failed to write secrets file: {}", self.path.display()))?;
+        restrict_permissions(&self.path)
+    }
+}
+
+impl SecretBackend for FileBackend {
+    fn name(&self) -> &'static str {
+        "file"
+    }
+
+    fn get(&self, name: &str) -> Result<Option<String>> {
+        Ok(self
+            .load()?
+            .get(name)
+            .and_then(|v| v.as_str())
+            .map(str::to_string))
+    }
+}
+
+/// The operating system keychain, via its command-line tool.
+pub struct KeychainBackend;
+
+impl KeychainBackend {
+    /// Store a secret in the keychain.
+    pub fn set(&self, name: &str, value: &str) -> Result<()> {
+        if cfg!(target_os = "macos") {
+            // -U updates an existing item; -w with a value avoids an interactive prompt.
+            run_tool(
+                "security",
+                &[
+                    "add-generic-password",
+                    "-U",
+                    "-s",
+                    KEYCHAIN_SERVICE,
+                    "-a",
+                    name,
+                    "-w",
+                    value,
+                ],
+                None,
+            )?;
+        } else if cfg!(target_os = "linux") {
+            let label = format!("{} {}", KEYCHAIN_SERVICE, name);
+            run_tool(
+                "secret-tool",
+                &[
+                    "store",
+                    "--label",
+                    &label,
+                    "service",
+                    KEYCHAIN_SERVICE,
+                    "account",
+                    name,
+                ],
+                Some(value),
+            )?;
+        } else {
+            bail!("The keychain backend is not supported on this platform");
+        }
+        Ok(())
+    }
+}
+
+impl SecretBackend for KeychainBackend {
+    fn name(&self) -> &'static str {
+        "keychain"
+    }
+
+    fn get(&self, name: &str) -> Result<Option<String>> {
+        let output = if cfg!(target_os = "macos") {
+            Command::new("security")
+                .args([
+                    "find-generic-password",
+                    "-s",
+                    KEYCHAIN_SERVICE,
+                    "-a",
+                    name,
+                    "-w",
+                ])
+                .stderr(Stdio::null())
+                .output()
+        } else if cfg!(target_os = "linux") {
+            Command::new("secret-tool")
+                .args(["lookup", "service", KEYCHAIN_SERVICE, "account", name])
+                .stderr(Stdio::null())
+                .output()
+        } else {
+            return Ok(None);
+        };
+
+        // A missing tool means there is no keychain to consult.
+        let Ok(output) = output else {
+            return Ok(None);
+        };
+        if !output.status.success() || output.stdout.is_empty() {
+            return Ok(None);
+        }
+        let value = String::from_utf8_lossy(&output.stdout);
+        Ok(Some(value.trim_end_matches(['\n', '\r']).to_string()))
+    }
+}
+
+/// The default backend chain: env, then file, then keychain.
+pub fn default_backends() -> Vec<Box<dyn SecretBackend>> {
+    vec![
+        Box::new(EnvBackend),
+        Box::new(FileBackend {
+            path: FileBackend::default_path(),
+        }),
+        Box::new(KeychainBackend),
+    ]
+}
+
+/// Returns `true` if `value` is a `secret://` reference.
+pub fn is_reference(value: &str) -> bool {
+    value.starts_with(SECRET_SCHEME)
+}
+
+/// Look up a secret by name, returning the value and the backend that had it.
+///
+/// `name` may be prefixed with a backend (`keychain/jira-token`) to consult
+/// only that backend.
+pub fn lookup(name: &str) -> Result<Option<(String, &'static str)>> {
+    let backends = default_backends();
+    let (pinned, name) = match name.split_once('/') {
+        Some((backend, rest)) if backends.iter().any(|b| b.name() == backend) => {
+            (Some(backend), rest)
+        }
+        _ => (None, name),
+    };
+    if name.is_empty() {
+        bail!("secret name must not be empty");
+    }
+
+    for backend in &backends {
+        if pinned.is_some_and(|p| p != backend.name()) {
+            continue;
+        }
+        if let Some(value) = backend.get(name)? {
+            return Ok(Some((value, backend.name())));
+        }
+    }
+    Ok(None)
+}
+
+/// Look up a secret by name. Found values are masked in diagnostics from
+/// then on (see [`crate::scrub`]).
+pub fn get(name: &str) -> Result<Option<String>> {
+    let value = lookup(name)?.map(|(value, _)| value);
+    if let Some(value) = &value {
+        scrub::register(value);
+    }
+    Ok(value)
+}
+
+/// Resolve a config value: `secret://<name>` references are looked up, any
+/// other value is returned unchanged.
+pub fn resolve(value: &str) -> Result<String> {
+    let Some(name) = value.strip_prefix(SECRET_SCHEME) else {
+        return Ok(value.to_string());
+    };
+    match get(name)? {
+        Some(secret) => Ok(secret),
+        None => bail!(
+            "Secret '{}' not found (checked ${}, {}, and the OS keychain)",
+            name,
+            EnvBackend::var_name(name.rsplit('/').next().unwrap_or(name)),
+            FileBackend::default_path().display()
+        ),
+    }
+}
+
+/// Resolve an optional config value, falling back to an environment variable.
+pub fn resolve_or_env(value: Option<&str>, env_var: &str) -> Result<Option<String>> {
+    match value {
+        Some(v) => resolve(v).map(Some),
+        None => {
+            let value = std::env::var(env_var).ok();
+            if let Some(value) = &value {
+                scrub::register(value);
+            }
+            Ok(value)
+        }
+    }
+}
+
+/// CLI entry point for `ctx secrets set <name>`.
+///
+/// Reads the value from stdin so it never appears in shell history.
+pub fn run_set(name: &str, backend: &str) -> Result<()> {
+    let mut value = String::new();
+    if atty::is(atty::Stream::Stdin) {
+        eprint!("Value for '{}': ", name);
+        std::io::stderr().flush()?;
+        std::io::stdin().read_line(&mut value)?;
+    } else {
+        std::io::stdin().read_to_string(&mut value)?;
+    }
+    let value = value.trim_end_matches(['\n', '\r']);
+    if value.is_empty() {
+        bail!("Refusing to store an empty secret");
+    }
+
+    match backend {
+        "file" => {
+            let file = FileBackend {
+                path: FileBackend::default_path(),
+            };
+            file.set(name, value)?;
+            println!("Stored '{}' in {}", name, file.path.display());
+        }
+        "keychain" => {
+            KeychainBackend.set(name, value)?;
+            println!("Stored '{}' in the OS keychain", name);
+        }
+        other => bail!("Unknown secrets backend '{}'. Use file or keychain.", other),
+    }
+    println!("Reference it in config as \"{}{}\".", SECRET_SCHEME, name);
+    Ok(())
+}
+
+/// CLI entry point for `ctx secrets check <name>`.
+pub fn run_check(name: &str) -> Result<()> {
+    let name = name.strip_prefix(SECRET_SCHEME).unwrap_or(name);
+    match lookup(name)? {
+        Some((value, backend)) => {
+            println!(
+                "'{}' resolves from the {} backend ({} characters)",
+                name,
+                backend,
+                value.chars().count()
+            );
+            Ok(())
+        }
+        None => {
+            eprintln!("'{}' was not found in any backend", name);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn run_tool(program: &str, args: &[&str], stdin: Option<&str>) -> Result<()> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(if stdin.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to execute '{}'", program))?;
+    if let (Some(input), Some(mut pipe)) = (stdin, child.stdin.take()) {
+        pipe.write_all(input.as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        bail!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn restrict_permissions(path: &std::path::Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn restrict_permissions(_path: &std::path::Path) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn env_var_names_are_normalized() {
+        assert_eq!(
+            EnvBackend::var_name("github-token"),
+            "CTX_SECRET_GITHUB_TOKEN"
+        );
+        assert_eq!(EnvBackend::var_name("aws.key_id"), "CTX_SECRET_AWS_KEY_ID");
+    }
+
+    #[test]
+    fn file_backend_round_trips() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let file = FileBackend {
+            path: tmp.path().join("secrets.toml"),
+        };
+        assert_eq!(file.get("jira").unwrap(), None);
+        file.set("jira", "s3cr3t").unwrap();
+        file.set("other", "x").unwrap();
+        assert_eq!(file.get("jira").unwrap().as_deref(), Some("s3cr3t"));
+    }
+
+    #[test]
+    fn plain_values_pass_through() {
+        assert_eq!(resolve("plain-value").unwrap(), "plain-value");
+        assert!(!is_reference("https://example.com"));
+        assert!(is_reference("secret://jira"));
+    }
+}
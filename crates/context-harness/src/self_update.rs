@@ -0,0 +1,504 @@
+//! Self-update from GitHub releases (`ctx self update`).
+//!
+//! Release builds are published as GitHub release assets named per
+//! platform (`ctx-linux-x86_64.tar.gz`, `ctx-macos-aarch64.tar.gz`,
+//! `ctx-windows-x86_64.zip`, …), each with a `.sha256` checksum file and,
+//! when the release is signed, a [minisign](https://jedisct1.github.io/minisign/)
+//! `.minisig` signature. `ctx self update`:
+//!
+//! 1. looks up the latest release of `[update] repo` (or `--tag`);
+//! 2. downloads this platform's archive and its checksum, and stops if the
+//!    SHA-256 does not match;
+//! 3. with `[update] public_key` set, also requires a valid signature;
+//! 4. extracts `ctx` from the archive, checks that it runs, and swaps it in
+//!    for the running binary.
+//!
+//! The new binary is written next to the old one and renamed over it, so a
+//! failed update leaves the old binary in place. On Windows, where a running
+//! executable cannot be replaced, the old one is first renamed to
+//! `ctx.exe.old`; the next update removes it.
+//!
+//! # Startup check
+//!
+//! With `[update] check = true`, commands other than `ctx ui`,
+//! `ctx completions`, and `ctx self` look up the latest release at most
+//! once per `check_interval_hours` and print a notice on stderr when it is
+//! newer than the running binary. The last answer is cached in
+//! `update-check.json` under the user state directory. The lookup times out
+//! after a few seconds and never fails the command.
+//!
+//! # Configuration
+//!
+//! ```toml
+//! [update]
+//! check = true
+//! check_interval_hours = 24
+//! public_key = "RWS43i3famiUEdJhuANcZmHBo55NvbZFTgTsMofR6OgJK6kJ6jnnftpV"
+//! ```
+//!
+//! Set `GITHUB_TOKEN` to raise the GitHub API rate limit.
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::archive;
+use crate::config::{ArchiveConfig, UpdateConfig};
+use crate::ctx_dirs;
+use crate::registry::verify_minisign;
+
+/// Version of the running binary.
+pub const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Timeout of the startup check's release lookup.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Timeout of `ctx self update` requests, downloads included.
+const UPDATE_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Largest release archive, and binary inside it, accepted.
+const MAX_ARCHIVE_BYTES: u64 = 1 << 30;
+
+/// A published release and its downloadable assets.
+#[derive(Debug)]
+struct Release {
+    tag: String,
+    /// `(name, browser_download_url)` pairs.
+    assets: Vec<(String, String)>,
+}
+
+impl Release {
+    fn asset_url(&self, name: &str) -> Option<&str> {
+        self.assets
+            .iter()
+            .find(|(asset, _)| asset == name)
+            .map(|(_, url)| url.as_str())
+    }
+}
+
+/// `ctx self update`: replace this binary with the latest release, or the
+/// release tagged `tag`.
+///
+/// With `check_only`, only reports whether a newer release exists. `force`
+/// reinstalls a release that is already installed.
+pub async fn run_update(
+    config: &UpdateConfig,
+    tag: Option<&str>,
+    check_only: bool,
+    force: bool,
+) -> Result<()> {
+    let client = client(UPDATE_TIMEOUT)?;
+    let release = fetch_release(&client, config, tag).await?;
+    let newer = is_newer(&release.tag, CURRENT_VERSION);
+
+    if check_only {
+        if newer {
+            println!(
+                "ctx {} is available (installed: {})",
+                release.tag, CURRENT_VERSION
+            );
+            println!("Run `ctx self update` to install it.");
+        } else {
+            println!("ctx {} is up to date", CURRENT_VERSION);
+        }
+        return Ok(());
+    }
+    let installed = parse_version(&release.tag) == parse_version(CURRENT_VERSION);
+    if !force && (installed || (tag.is_none() && !newer)) {
+        println!("ctx {} is up to date", CURRENT_VERSION);
+        return Ok(());
+    }
+
+    let archive_name = platform_archive().with_context(|| {
+        format!(
+            "no prebuilt release for this platform ({}-{}); build from source with `cargo install`",
+            std::env::consts::OS,
+            std::env::consts::ARCH
+        )
+    })?;
+    let asset = |name: &str| {
+        release
+            .asset_url(name)
+            .with_context(|| format!("release {} has no asset {}", release.tag, name))
+    };
+    let archive_url = asset(archive_name)?;
+    let checksum_url = asset(&format!("{}.sha256", archive_name))?;
+    // Resolve the signature before downloading, so an unsigned release fails fast.
+    let signature_url = match &config.public_key {
+        Some(_) => Some(asset(&format!("{}.minisig", archive_name))?),
+        None => None,
+    };
+
+    println!("Downloading {} ({})...", archive_name, release.tag);
+    let bytes = download(&client, archive_url).await?;
+    let checksum = download(&client, checksum_url).await?;
+    verify_checksum(archive_name, &bytes, &String::from_utf8_lossy(&checksum))?;
+    if let (Some(key), Some(url)) = (&config.public_key, signature_url) {
+        let signature = download(&client, url).await?;
+        verify_minisign(key, &String::from_utf8_lossy(&signature), &bytes)
+            .with_context(|| format!("{} failed signature verification", archive_name))?;
+    }
+    let binary = extract_binary(archive_name, &bytes)?;
+
+    let exe = current_exe()?;
+    let staged = stage(&exe, &binary)?;
+    if let Err(e) = check_runs(&staged).await {
+        let _ = std::fs::remove_file(&staged);
+        return Err(e);
+    }
+    swap(&staged, &exe)?;
+    println!(
+        "Updated ctx {} → {} ({})",
+        CURRENT_VERSION,
+        release.tag,
+        exe.display()
+    );
+    Ok(())
+}
+
+/// Print a notice on stderr when a release newer than this binary exists.
+///
+/// Uses the cached answer while it is younger than `check_interval_hours`.
+/// Errors are logged at debug level and otherwise ignored.
+pub async fn notify_if_outdated(config: &UpdateConfig) {
+    match latest_release_cached(config).await {
+        Ok(Some(latest)) if is_newer(&latest, CURRENT_VERSION) => {
+            eprintln!(
+                "ctx {} is available (installed: {}). Run `ctx self update` to upgrade.",
+                latest, CURRENT_VERSION
+            );
+        }
+        Ok(_) => {}
+        Err(e) => tracing::debug!("update check failed: {:#}", e),
+    }
+}
+
+/// The startup check's cached answer.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CheckCache {
+    /// API URL and repository the answer came from.
+    source: String,
+    /// Unix time of the last lookup, successful or not.
+    checked_at: i64,
+    /// Latest release tag, from the last successful lookup.
+    latest: Option<String>,
+}
+
+async fn latest_release_cached(config: &UpdateConfig) -> Result<Option<String>> {
+    let path = ctx_dirs::state_dir().join("update-check.json");
+    let source = format!("{}/{}", config.api_url.trim_end_matches('/'), config.repo);
+    let mut cache = std::fs::read(&path)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice::<CheckCache>(&bytes).ok())
+        .filter(|cache| cache.source == source)
+        .unwrap_or_default();
+    let now = chrono::Utc::now().timestamp();
+    let interval =
+        i64::try_from(config.check_interval_hours.saturating_mul(3600)).unwrap_or(i64::MAX);
+    if now - cache.checked_at < interval {
+        return Ok(cache.latest);
+    }
+
+    // Record failed lookups too, so an offline machine is not slowed down
+    // by every command.
+    let result = fetch_release(&client(CHECK_TIMEOUT)?, config, None).await;
+    cache.source = source;
+    cache.checked_at = now;
+    if let Ok(release) = &result {
+        cache.latest = Some(release.tag.clone());
+    }
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(&path, serde_json::to_vec(&cache)?)?;
+    result.map(|release| Some(release.tag))
+}
+
+fn client(timeout: Duration) -> Result<reqwest::Client> {
+    Ok(reqwest::Client::builder()
+        .user_agent(format!("ctx/{}", CURRENT_VERSION))
+        .timeout(timeout)
+        .build()?)
+}
+
+/// The latest release, or the one tagged `tag` (`v` prefix optional).
+async fn fetch_release(
+    client: &reqwest::Client,
+    config: &UpdateConfig,
+    tag: Option<&str>,
+) -> Result<Release> {
+    let base = config.api_url.trim_end_matches('/');
+    let url = match tag {
+        Some(tag) if tag.starts_with('v') => {
+            format!("{}/repos/{}/releases/tags/{}", base, config.repo, tag)
+        }
+        Some(tag) => format!("{}/repos/{}/releases/tags/v{}", base, config.repo, tag),
+        None => format!("{}/repos/{}/releases/latest", base, config.repo),
+    };
+    let mut request = client
+        .get(&url)
+        .header("Accept", "application/vnd.github+json");
+    if let Ok(token) = std::env::var("GITHUB_TOKEN") {
+        if !token.is_empty() {
+            request = request.bearer_auth(token);
+        }
+    }
+    let response = request
+        .send()
+        .await
+        .with_context(|| format!("failed to reach {}", url))?;
+    let status = response.status();
+    if status == reqwest::StatusCode::NOT_FOUND {
+        match tag {
+            Some(tag) => bail!("{} has no release {}", config.repo, tag),
+            None => bail!("{} has no published releases", config.repo),
+        }
+    }
+    if !status.is_success() {
+        bail!("GitHub API returned HTTP {} for {}", status, url);
+    }
+    parse_release(&response.json().await?)
+}
+
+fn parse_release(body: &Value) -> Result<Release> {
+    let tag = body["tag_name"]
+        .as_str()
+        .context("release has no tag_name")?
+        .to_string();
+    let assets = body["assets"]
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|asset| {
+            Some((
+                asset["name"].as_str()?.to_string(),
+                asset["browser_download_url"].as_str()?.to_string(),
+            ))
+        })
+        .collect();
+    Ok(Release { tag, assets })
+}
+
+async fn download(client: &reqwest::Client, url: &str) -> Result<Vec<u8>> {
+    let mut response = client
+        .get(url)
+        .send()
+        .await
+        .with_context(|| format!("failed to download {}", url))?;
+    if !response.status().is_success() {
+        bail!("download of {} failed: HTTP {}", url, response.status());
+    }
+    if response.content_length().unwrap_or(0) > MAX_ARCHIVE_BYTES {
+        bail!("{} is larger than {} bytes", url, MAX_ARCHIVE_BYTES);
+    }
+    // Content-Length may be missing or wrong; count what actually arrives.
+    let mut bytes = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        if (bytes.len() + chunk.len()) as u64 > MAX_ARCHIVE_BYTES {
+            bail!("{} is larger than {} bytes", url, MAX_ARCHIVE_BYTES);
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    Ok(bytes)
+}
+
+/// Release archive name for the platform this binary was built for.
+fn platform_archive() -> Option<&'static str> {
+    if cfg!(all(
+        target_os = "linux",
+        target_arch = "x86_64",
+        target_env = "musl"
+    )) {
+        Some("ctx-linux-x86_64-musl.tar.gz")
+    } else if cfg!(all(target_os = "linux", target_arch = "x86_64")) {
+        Some("ctx-linux-x86_64.tar.gz")
+    } else if cfg!(all(target_os = "linux", target_arch = "aarch64")) {
+        Some("ctx-linux-aarch64.tar.gz")
+    } else if cfg!(all(target_os = "macos", target_arch = "x86_64")) {
+        Some("ctx-macos-x86_64.tar.gz")
+    } else if cfg!(all(target_os = "macos", target_arch = "aarch64")) {
+        Some("ctx-macos-aarch64.tar.gz")
+    } else if cfg!(all(target_os = "windows", target_arch = "x86_64")) {
+        Some("ctx-windows-x86_64.zip")
+    } else {
+        None
+    }
+}
+
+/// Check `bytes` against a `sha256sum`-style checksum file.
+fn verify_checksum(name: &str, bytes: &[u8], checksum_file: &str) -> Result<()> {
+    let expected = checksum_file
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    if expected.len() != 64 || !expected.chars().all(|c| c.is_ascii_hexdigit()) {
+        bail!("{}.sha256 does not hold a SHA-256 checksum", name);
+    }
+    let actual = hex::encode(Sha256::digest(bytes));
+    if actual != expected {
+        bail!(
+            "checksum mismatch for {}: expected {}, got {}",
+            name,
+            expected,
+            actual
+        );
+    }
+    Ok(())
+}
+
+/// The `ctx` binary inside a release archive.
+fn extract_binary(archive_name: &str, bytes: &[u8]) -> Result<Vec<u8>> {
+    let limits = ArchiveConfig {
+        enabled: true,
+        max_depth: 1,
+        max_entry_bytes: MAX_ARCHIVE_BYTES,
+        max_total_bytes: MAX_ARCHIVE_BYTES,
+    };
+    let binary = if cfg!(windows) { "ctx.exe" } else { "ctx" };
+    archive::read_archive(archive_name, bytes, &limits)?
+        .entries
+        .into_iter()
+        .find(|entry| entry.path.rsplit('/').next() == Some(binary))
+        .map(|entry| entry.bytes)
+        .with_context(|| format!("{} does not contain {}", archive_name, binary))
+}
+
+/// Path of the running binary, with symlinks resolved.
+fn current_exe() -> Result<PathBuf> {
+    let exe = std::env::current_exe().context("cannot locate the running ctx binary")?;
+    Ok(exe.canonicalize().unwrap_or(exe))
+}
+
+/// Write `binary` next to `exe`, executable, ready to be renamed over it.
+fn stage(exe: &Path, binary: &[u8]) -> Result<PathBuf> {
+    let dir = exe.parent().context("ctx binary has no parent directory")?;
+    let name = exe.file_name().unwrap_or_default().to_string_lossy();
+    let staged = dir.join(format!(".{}.new", name));
+    std::fs::write(&staged, binary).with_context(|| {
+        format!(
+            "cannot write to {}; rerun with permission to replace {}",
+            dir.display(),
+            exe.display()
+        )
+    })?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(0o755))?;
+    }
+    Ok(staged)
+}
+
+/// Fail unless the staged binary starts and reports a version.
+async fn check_runs(staged: &Path) -> Result<()> {
+    let output = tokio::process::Command::new(staged)
+        .arg("--version")
+        .output()
+        .await
+        .context("the downloaded binary does not run on this system")?;
+    if !output.status.success() {
+        bail!(
+            "the downloaded binary failed `--version`: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+/// Rename `staged` over `exe`.
+fn swap(staged: &Path, exe: &Path) -> Result<()> {
+    let replace_error = || format!("cannot replace {}", exe.display());
+    if cfg!(windows) {
+        let old = exe.with_extension("exe.old");
+        let _ = std::fs::remove_file(&old);
+        std::fs::rename(exe, &old).with_context(replace_error)?;
+        if let Err(e) = std::fs::rename(staged, exe) {
+            let _ = std::fs::rename(&old, exe);
+            let _ = std::fs::remove_file(staged);
+            return Err(e).with_context(replace_error);
+        }
+        return Ok(());
+    }
+    if let Err(e) = std::fs::rename(staged, exe) {
+        let _ = std::fs::remove_file(staged);
+        return Err(e).with_context(replace_error);
+    }
+    Ok(())
+}
+
+/// `(major, minor, patch, is_release)` of `v1.2.3` or `1.2.3-rc.1`, so that
+/// a pre-release sorts before its release.
+fn parse_version(version: &str) -> Option<(u64, u64, u64, bool)> {
+    // Build metadata (`+...`) does not affect precedence.
+    let version = version.trim().trim_start_matches('v');
+    let version = version.split('+').next().unwrap_or_default();
+    let (core, pre) = match version.split_once('-') {
+        Some((core, _)) => (core, true),
+        None => (version, false),
+    };
+    let mut parts = core.split('.').map(|part| part.parse::<u64>().ok());
+    let major = parts.next()??;
+    let minor = parts.next().unwrap_or(Some(0))?;
+    let patch = parts.next().unwrap_or(Some(0))?;
+    Some((major, minor, patch, !pre))
+}
+
+/// Whether release `latest` is newer than version `current`.
+fn is_newer(latest: &str, current: &str) -> bool {
+    match (parse_version(latest), parse_version(current)) {
+        (Some(latest), Some(current)) => latest > current,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versions_compare_numerically_with_prereleases_first() {
+        assert!(is_newer("v0.10.0", "0.9.3"));
+        assert!(is_newer("v1.0.0", "1.0.0-rc.2"));
+        assert!(!is_newer("v1.0.0-rc.2", "1.0.0"));
+        assert!(!is_newer("v0.8.0", "0.8.0"));
+        assert!(!is_newer("v0.8.0+build.5", "0.8.0"));
+        assert!(!is_newer("nightly", "0.8.0"));
+        assert_eq!(parse_version("v2"), Some((2, 0, 0, true)));
+    }
+
+    #[test]
+    fn checksums_and_signatures_are_verified() {
+        // ctx-linux-x86_64.tar.gz holding a stub `ctx` script.
+        let archive = hex::decode(
+            "1f8b0800000000000203edcd3d0a83401445e157bb8a911476fafc19872c270e\
+             823606e2082edf413b7b83e0f99a03b7b93eac72358d9cb57ba37355eb5a4aab\
+             ae6c5d73ecadad1a312a7fb0cce1f38b97f24cafb4e8c6a99887a4f7c3d7643e\
+             ac46f377ae59220000000000000000000000000080fbda00d859731a00280000",
+        )
+        .unwrap();
+        let name = "ctx-linux-x86_64.tar.gz";
+        let digest = hex::encode(Sha256::digest(&archive));
+        verify_checksum(name, &archive, &format!("{}  {}\n", digest, name)).unwrap();
+        verify_checksum(name, &archive, &digest.to_uppercase()).unwrap();
+        let err = verify_checksum(name, b"tampered", &digest).unwrap_err();
+        assert!(err.to_string().contains("checksum mismatch"), "{}", err);
+        assert!(verify_checksum(name, &archive, "").is_err());
+
+        const PUBLIC_KEY: &str = "RWSTCJb+oiHbgAKbVqbRdhxwbOuK/dijzPOBxWaT1pin8CWKfD6hmx9A";
+        const SIGNATURE: &str = "untrusted comment: signature from minisign secret key
+RUSTCJb+oiHbgH8HyO/WpVFJ4Y1mSdbh3oE9UPXU2c9JZhMJKxnPNm/BEcKp/1BB4webswlZjk2sfZDRyLkS2zjFJC40d0Mxjgg=
+trusted comment: timestamp:1760000000\tfile:ctx-linux-x86_64.tar.gz\thashed
+emBQnZNpmvm9vIudJoahOZkCxNuA8/kz0IVGV7SiTCh2gRE5tZkAxZyn5JXrf5J1fvEEn+tWgnFan2LpS+JGAQ==
+";
+        verify_minisign(PUBLIC_KEY, SIGNATURE, &archive).unwrap();
+        let mut tampered = archive.clone();
+        tampered[20] ^= 1;
+        assert!(verify_minisign(PUBLIC_KEY, SIGNATURE, &tampered).is_err());
+        assert!(verify_minisign(PUBLIC_KEY, "not a signature", &archive).is_err());
+    }
+}
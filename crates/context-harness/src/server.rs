@@ -16,9 +16,21 @@
 //! |--------|------|-------------|
 //! | `GET`  | `/tools/list` | List all registered tools with schemas |
 //! | `POST` | `/tools/{name}` | Call any registered tool by name |
+//! | `GET`  | `/capabilities` | Search modes, filters, query syntax, sources, and collections (the `capabilities` tool) |
 //! | `GET`  | `/agents/list` | List all registered agents with metadata |
 //! | `POST` | `/agents/{name}/prompt` | Resolve an agent's system prompt |
 //! | `GET`  | `/health` | Health check (returns version) |
+//! | `GET`  | `/livez` | Liveness probe (same body as `/health`) |
+//! | `GET`  | `/readyz` | Readiness probe: database reachable, migrations applied, not draining |
+//! | `GET`  | `/metrics` | Prometheus-format counters (query embedding and search caches, embedding calls per provider, in-flight, queued, and rejected tool calls) |
+//! | `POST` | `/admin/reload` | Reload tool, agent, and connector definitions (see [`crate::reload`]) |
+//! | `GET`  | `/admin/jobs` | Running and paused syncs and embeds; `?all=true` adds finished ones (see [`crate::jobs`]) |
+//! | `POST` | `/admin/jobs/{id}/{action}` | Pause, resume, or cancel a job |
+//! | `POST` | `/rpc` | MCP over plain JSON-RPC 2.0: `initialize`, `tools/*`, `prompts/*`, batches, notifications |
+//! | `*`    | `/mcp` | MCP Streamable HTTP transport (sessions, server-sent events) |
+//!
+//! Builds with `--features grpc` can also serve a gRPC interface on
+//! `[server] grpc_bind`, sharing the tool handlers (see `grpc`).
 //!
 //! # Error Contract
 //!
@@ -28,8 +40,58 @@
 //! { "error": { "code": "bad_request", "message": "query must not be empty" } }
 //! ```
 //!
-//! Error codes: `bad_request` (400), `not_found` (404), `embeddings_disabled` (400),
-//! `timeout` (408), `tool_error` (500), `internal` (500).
+//! Error codes: `bad_request` (400), `unauthorized` (401), `forbidden` (403),
+//! `not_found` (404), `embeddings_disabled` (400), `timeout` (408),
+//! `reload_failed` (422), `rate_limited` (429, with `Retry-After`),
+//! `tool_error` (500), `internal` (500), `unavailable` (503).
+//!
+//! # Authentication
+//!
+//! With `[[server.auth.keys]]` configured, every endpoint except the health,
+//! readiness, and metrics probes requires an API key, and keys can be
+//! limited to some sources. See [`crate::auth`].
+//!
+//! # Search Cache
+//!
+//! Built-in `search` responses are cached in memory (see
+//! [`crate::search_cache`]) and invalidated when a sync or embed run changes
+//! the index.
+//!
+//! # Concurrency Limits
+//!
+//! `[server.limits]` caps how many tool calls run at once, globally and per
+//! tool. Calls over the limit queue briefly and are then rejected with
+//! `429 rate_limited`. See [`crate::limits`].
+//!
+//! # Reload
+//!
+//! `POST /admin/reload` rebuilds the tool and agent registries from the
+//! config file and scripts without a restart; with `[server] watch = true`
+//! this happens automatically when a watched file changes. If anything fails
+//! to load, the error is returned and the previous set stays active. See
+//! [`crate::reload`].
+//!
+//! # Shutdown
+//!
+//! On SIGTERM or Ctrl-C the server stops accepting connections, `/readyz`
+//! starts returning `503`, and new tool calls are rejected with
+//! `unavailable`. Tool calls already running are allowed to finish; once
+//! none remain, MCP sessions are closed and the process exits. If calls are
+//! still running after `[server] shutdown_grace_secs` (default 30), the
+//! server exits anyway.
+//!
+//! # Request Logging
+//!
+//! Every request runs inside a `request` tracing span carrying an ID,
+//! method, and path, so events logged while handling it (tool warnings, Lua
+//! `log.*` calls) can be correlated — see [`crate::logging`]. The ID is taken
+//! from an incoming `X-Request-Id` header or generated, and is echoed in the
+//! response's `X-Request-Id`. Completed requests are logged with status and
+//! latency (`debug` for health, readiness, and metrics probes).
+//!
+//! With `[telemetry]` enabled, these spans, and the MCP request, tool, and
+//! embedding spans inside them, are exported as OpenTelemetry traces; see
+//! [`crate::telemetry`].
 //!
 //! # CORS
 //!
@@ -51,36 +113,103 @@
 //! ```
 
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    extract::{Path, Query, Request, State},
+    http::{HeaderMap, HeaderValue, StatusCode},
+    middleware::{self, Next},
     response::{IntoResponse, Response},
     routing::{get, post},
-    Json, Router,
+    Extension, Json, Router,
 };
+use rmcp::transport::streamable_http_server::StreamableHttpServerConfig;
 use rmcp::transport::streamable_http_server::{
     session::local::LocalSessionManager, StreamableHttpService,
 };
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::future::IntoFuture;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tower_http::cors::{Any, CorsLayer};
+use tracing::Instrument;
 
-use crate::agent_script::{load_agent_definitions, LuaAgentAdapter};
 use crate::agents::{AgentInfo, AgentRegistry};
+use crate::audit::{self, AuditEntry, AuditLog};
+use crate::auth::{self, ApiKey, ApiKeys, SourceScope};
 use crate::config::Config;
+use crate::db;
+use crate::embedding;
+use crate::jobs::{self, JobRow};
+use crate::limits::{self, Saturated, ToolLimiter};
 use crate::mcp::McpBridge;
-use crate::registry::RegistryManager;
-use crate::tool_script::{load_tool_definitions, validate_params, LuaToolAdapter, ToolInfo};
+use crate::migrate;
+use crate::query_cache;
+use crate::reload::{ConfigLoader, Extensions, LiveExtensions, ReloadSummary};
+use crate::scrub;
+use crate::search_cache::{self, SearchCache};
+use crate::telemetry;
+use crate::tool_script::{validate_params, ToolInfo};
 use crate::traits::{ToolContext, ToolRegistry};
 
 /// Shared application state passed to all route handlers via Axum's `State` extractor.
 #[derive(Clone)]
-struct AppState {
-    /// Application configuration (wrapped in `Arc` for cheap cloning across handlers).
+pub(crate) struct AppState {
+    /// Configuration the server was started with (database path, bind address).
     config: Arc<Config>,
-    /// Unified tool registry containing built-in, Lua, and custom Rust tools.
-    tools: Arc<ToolRegistry>,
-    /// Agent registry containing TOML, Lua, and custom Rust agents.
-    agents: Arc<AgentRegistry>,
+    /// Reloadable built-in, Lua, and registry tools and agents, with the
+    /// configuration tool calls run against.
+    live: Arc<LiveExtensions>,
+    /// Tool invocation audit log (`None` when `[tools.audit]` is disabled).
+    audit: Option<Arc<AuditLog>>,
+    /// Drain flag and in-flight tool call count.
+    lifecycle: Arc<ServerLifecycle>,
+    /// `search` response cache (`None` when `[server] search_cache_size = 0`).
+    search_cache: Option<Arc<SearchCache>>,
+    /// MCP bridge serving the plain JSON-RPC endpoint (`POST /rpc`).
+    rpc: McpBridge,
+    /// Tool call concurrency limits (`None` when `[server.limits]` sets none).
+    limiter: Option<Arc<ToolLimiter>>,
+}
+
+/// Shutdown state shared by the HTTP handlers, the MCP bridge, and the
+/// shutdown task.
+#[derive(Debug, Default)]
+pub struct ServerLifecycle {
+    draining: AtomicBool,
+    in_flight: AtomicUsize,
+}
+
+impl ServerLifecycle {
+    /// `true` once a shutdown signal has been received.
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::SeqCst)
+    }
+
+    /// Number of tool calls currently executing.
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::SeqCst)
+    }
+
+    /// Count a tool call as in flight until the returned guard is dropped.
+    ///
+    /// Returns `None` while draining, in which case the call should be
+    /// rejected.
+    pub fn begin_call(self: &Arc<Self>) -> Option<InFlightGuard> {
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        let guard = InFlightGuard(self.clone());
+        // Checked after incrementing so the drain loop never observes zero
+        // while a call that is about to run slips past the flag.
+        (!self.is_draining()).then_some(guard)
+    }
+}
+
+/// Marks one tool call as in flight; see [`ServerLifecycle::begin_call`].
+#[derive(Debug)]
+pub struct InFlightGuard(Arc<ServerLifecycle>);
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.0.in_flight.fetch_sub(1, Ordering::SeqCst);
+    }
 }
 
 /// Extra extensions (custom Rust tools and agents) passed alongside the main `AppState`.
@@ -89,9 +218,9 @@ type ExtState = (Arc<ToolRegistry>, Arc<AgentRegistry>);
 /// Starts the MCP-compatible HTTP server.
 ///
 /// Binds to the address configured in `[server].bind` and registers all
-/// route handlers. The server runs indefinitely until the process is terminated.
+/// route handlers. The server runs until it receives SIGTERM or Ctrl-C, then
+/// drains in-flight tool calls (see the module docs).
 ///
-/// This is the standard entry point used by the `ctx serve mcp` command.
 /// For custom binaries with Rust extensions, use
 /// [`run_server_with_extensions`] instead.
 ///
@@ -102,6 +231,7 @@ type ExtState = (Arc<ToolRegistry>, Arc<AgentRegistry>);
 /// # Returns
 ///
 /// Returns `Ok(())` when the server shuts down, or an error if binding fails.
+#[allow(dead_code)]
 pub async fn run_server(config: &Config) -> anyhow::Result<()> {
     run_server_with_extensions(
         config,
@@ -136,50 +266,44 @@ pub async fn run_server(config: &Config) -> anyhow::Result<()> {
 /// # Ok(())
 /// # }
 /// ```
+#[allow(dead_code)]
 pub async fn run_server_with_extensions(
     config: &Config,
     extra_tools: Arc<ToolRegistry>,
     extra_agents: Arc<AgentRegistry>,
 ) -> anyhow::Result<()> {
-    let bind_addr = config.server.bind.clone();
-    let config = Arc::new(config.clone());
+    serve(config, None, extra_tools, extra_agents).await
+}
 
-    // ── Tools ──
-    let mut tool_registry = ToolRegistry::with_builtins();
+/// Starts the MCP server, re-reading the configuration through `loader`
+/// on `POST /admin/reload` and, with `[server] watch = true`, whenever the
+/// config file or a script changes.
+///
+/// Used by `ctx serve mcp`. With [`run_server`] and
+/// [`run_server_with_extensions`], a reload re-reads scripts but keeps the
+/// configuration the server was started with. See [`crate::reload`].
+pub async fn run_server_with_loader(config: &Config, loader: ConfigLoader) -> anyhow::Result<()> {
+    serve(
+        config,
+        Some(loader),
+        Arc::new(ToolRegistry::new()),
+        Arc::new(AgentRegistry::new()),
+    )
+    .await
+}
 
-    // Load and register Lua tools from config
-    let lua_defs = load_tool_definitions(&config)?;
-    let configured_tool_names: Vec<String> = lua_defs.iter().map(|d| d.name.clone()).collect();
-    for def in lua_defs {
-        tool_registry.register(Box::new(LuaToolAdapter::new(def, config.clone())));
-    }
+async fn serve(
+    config: &Config,
+    loader: Option<ConfigLoader>,
+    extra_tools: Arc<ToolRegistry>,
+    extra_agents: Arc<AgentRegistry>,
+) -> anyhow::Result<()> {
+    let bind_addr = config.server.bind.clone();
+    let config = Arc::new(config.clone());
 
-    // Auto-discover tools from registries (lower precedence than config)
-    let reg_mgr = RegistryManager::from_config(&config);
-    for ext in reg_mgr.list_tools() {
-        if configured_tool_names.iter().any(|n| n == &ext.name) {
-            continue;
-        }
-        if !ext.script_path.exists() {
-            continue;
-        }
-        let tool_cfg = crate::config::ScriptToolConfig {
-            path: ext.script_path.clone(),
-            timeout: 30,
-            extra: toml::Table::new(),
-        };
-        match crate::tool_script::load_single_tool(&ext.name, &tool_cfg) {
-            Ok(def) => {
-                tool_registry.register(Box::new(LuaToolAdapter::new(def, config.clone())));
-            }
-            Err(e) => {
-                eprintln!(
-                    "Warning: failed to load registry tool '{}': {}",
-                    ext.name, e
-                );
-            }
-        }
-    }
+    let extensions = Extensions::load(config.as_ref().clone())?;
+    let tool_registry = &extensions.tools;
+    let agent_registry = &extensions.agents;
 
     // Print registered tools
     let tool_count = tool_registry.len() + extra_tools.len();
@@ -194,44 +318,6 @@ pub async fn run_server_with_extensions(
         }
     }
 
-    // ── Agents ──
-    let mut agent_registry = AgentRegistry::from_config(&config)?;
-
-    // Load and register Lua agents from config
-    let lua_agents = load_agent_definitions(&config)?;
-    let configured_agent_names: Vec<String> = lua_agents.iter().map(|d| d.name.clone()).collect();
-    for def in lua_agents {
-        agent_registry.register(Box::new(LuaAgentAdapter::new(def, config.clone())));
-    }
-
-    // Auto-discover agents from registries (lower precedence than config)
-    for ext in reg_mgr.list_agents() {
-        if configured_agent_names.iter().any(|n| n == &ext.name) {
-            continue;
-        }
-        if !ext.script_path.exists() {
-            continue;
-        }
-        if ext.script_path.extension().is_some_and(|e| e == "lua") {
-            let agent_cfg = crate::config::ScriptAgentConfig {
-                path: ext.script_path.clone(),
-                timeout: 30,
-                extra: toml::Table::new(),
-            };
-            match crate::agent_script::load_single_agent(&ext.name, &agent_cfg) {
-                Ok(def) => {
-                    agent_registry.register(Box::new(LuaAgentAdapter::new(def, config.clone())));
-                }
-                Err(e) => {
-                    eprintln!(
-                        "Warning: failed to load registry agent '{}': {}",
-                        ext.name, e
-                    );
-                }
-            }
-        }
-    }
-
     let agent_count = agent_registry.len() + extra_agents.len();
     if agent_count > 0 {
         println!("Registered {} agents:", agent_count);
@@ -253,35 +339,67 @@ pub async fn run_server_with_extensions(
         }
     }
 
-    let tools = Arc::new(tool_registry);
-    let agents = Arc::new(agent_registry);
+    let api_keys = ApiKeys::from_config(&config.server.auth)?.map(Arc::new);
+    if api_keys.is_some() {
+        tracing::info!(
+            "API key authentication enabled ({} keys)",
+            config.server.auth.keys.len()
+        );
+    }
+
+    let audit = AuditLog::open(&config).await?.map(Arc::new);
+    if audit.is_some() {
+        tracing::info!("Tool audit log enabled ({})", config.tools.audit.sink);
+    }
+
+    let lifecycle = Arc::new(ServerLifecycle::default());
+    let search_cache = SearchCache::from_config(config.clone()).map(Arc::new);
+    let limiter = ToolLimiter::from_config(&config.server.limits).map(Arc::new);
+    if limiter.is_some() {
+        tracing::info!(
+            "Tool call limits enabled (max_concurrent {}, {} per-tool)",
+            config.server.limits.max_concurrent,
+            config.server.limits.tools.len()
+        );
+    }
+    let live =
+        Arc::new(LiveExtensions::new(extensions, loader).with_search_cache(search_cache.clone()));
+
+    // One bridge serves both MCP transports: each Streamable HTTP session
+    // gets a clone, and `/rpc` uses it directly.
+    let current = live.current();
+    let bridge = McpBridge::new(
+        config.clone(),
+        current.tools.clone(),
+        extra_tools.clone(),
+        current.agents.clone(),
+        extra_agents.clone(),
+    )
+    .with_live(live.clone())
+    .with_audit(audit.clone())
+    .with_lifecycle(lifecycle.clone())
+    .with_search_cache(search_cache.clone())
+    .with_limiter(limiter.clone());
 
     let state = AppState {
         config: config.clone(),
-        tools: tools.clone(),
-        agents: agents.clone(),
+        live: live.clone(),
+        audit: audit.clone(),
+        lifecycle: lifecycle.clone(),
+        search_cache: search_cache.clone(),
+        rpc: bridge.clone(),
+        limiter,
     };
 
-    // MCP Streamable HTTP endpoint at /mcp — clone before moving into extra_state
-    let mcp_tools = tools.clone();
-    let mcp_extra = extra_tools.clone();
-    let mcp_agents = agents.clone();
-    let mcp_extra_agents = extra_agents.clone();
-    let mcp_config = config.clone();
+    // MCP Streamable HTTP endpoint at /mcp
+    let mcp_http_config = StreamableHttpServerConfig::default();
+    let mcp_cancel = mcp_http_config.cancellation_token.clone();
 
     let extra_state = (extra_tools.clone(), extra_agents);
     let mcp_service = StreamableHttpService::new(
-        move || {
-            Ok(McpBridge::new(
-                mcp_config.clone(),
-                mcp_tools.clone(),
-                mcp_extra.clone(),
-                mcp_agents.clone(),
-                mcp_extra_agents.clone(),
-            ))
-        },
+        move || Ok(bridge.clone()),
         Arc::new(LocalSessionManager::default()),
-        Default::default(),
+        mcp_http_config,
     );
 
     let cors = CorsLayer::new()
@@ -289,25 +407,159 @@ pub async fn run_server_with_extensions(
         .allow_methods(Any)
         .allow_headers(Any);
 
+    #[cfg(feature = "grpc")]
+    let grpc_service =
+        crate::grpc::GrpcService::new(state.clone(), extra_tools.clone(), api_keys.clone());
+
     let app = Router::new()
         .route("/tools/list", get(handle_list_tools))
         .route("/tools/{name}", post(handle_tool_call))
+        .route("/capabilities", get(handle_capabilities))
         .route("/agents/list", get(handle_list_agents))
         .route("/agents/{name}/prompt", post(handle_resolve_agent))
         .route("/health", get(handle_health))
+        .route("/livez", get(handle_health))
+        .route("/readyz", get(handle_ready))
+        .route("/metrics", get(handle_metrics))
+        .route("/rpc", post(handle_jsonrpc))
+        .route("/admin/reload", post(handle_reload))
+        .route("/admin/jobs", get(handle_list_jobs))
+        .route("/admin/jobs/{id}/{action}", post(handle_job_action))
         .with_state((state, extra_state))
-        .nest_service("/mcp", mcp_service)
-        .layer(cors);
+        .nest_service("/mcp", mcp_service);
+    let app = match api_keys {
+        Some(keys) => app.layer(middleware::from_fn_with_state(keys, require_api_key)),
+        None => app,
+    };
+    let app = app.layer(cors).layer(middleware::from_fn(trace_request));
+
+    if config.db.warm_up {
+        match crate::warmup::warm_up(&config).await {
+            Ok(report) => println!(
+                "Warmed up database: {} from {} tables in {} ms",
+                crate::stats::format_bytes(report.bytes),
+                report.tables,
+                report.elapsed.as_millis()
+            ),
+            Err(e) => tracing::warn!("database warm-up failed: {:#}", e),
+        }
+    }
 
     println!("MCP server listening on http://{}", bind_addr);
     println!("  MCP endpoint: http://{}/mcp", bind_addr);
+    println!("  JSON-RPC endpoint: http://{}/rpc", bind_addr);
 
     let listener = tokio::net::TcpListener::bind(&bind_addr).await?;
-    axum::serve(listener, app).await?;
+
+    #[cfg(feature = "grpc")]
+    let grpc = match config.server.grpc_bind {
+        Some(ref grpc_bind) => {
+            let task = crate::grpc::serve(grpc_bind, grpc_service, shutdown_signal()).await?;
+            println!("  gRPC endpoint: {}", grpc_bind);
+            Some(task)
+        }
+        None => None,
+    };
+    #[cfg(not(feature = "grpc"))]
+    if config.server.grpc_bind.is_some() {
+        anyhow::bail!("[server] grpc_bind requires ctx to be built with --features grpc");
+    }
+
+    let watcher = config.server.watch.then(|| {
+        tracing::info!(
+            "Watching config and scripts for changes (every {}s)",
+            config.server.watch_interval_secs
+        );
+        tokio::spawn(
+            live.clone()
+                .watch(Duration::from_secs(config.server.watch_interval_secs)),
+        )
+    });
+
+    let gc = config.db.gc_interval_hours.map(|hours| {
+        tracing::info!("Scheduled database gc every {}h", hours);
+        tokio::spawn(crate::gc::run_scheduled(
+            config.clone(),
+            Duration::from_secs(hours * 3600),
+        ))
+    });
+
+    let grace = Duration::from_secs(config.server.shutdown_grace_secs);
+    let signalled = Arc::new(tokio::sync::Notify::new());
+    let drain = {
+        let lifecycle = lifecycle.clone();
+        let signalled = signalled.clone();
+        async move {
+            shutdown_signal().await;
+            lifecycle.draining.store(true, Ordering::SeqCst);
+            signalled.notify_one();
+            tracing::info!(
+                "Shutting down: draining {} in-flight tool call(s) (grace period {}s)",
+                lifecycle.in_flight(),
+                grace.as_secs()
+            );
+            while lifecycle.in_flight() > 0 {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+            // MCP sessions hold streaming connections open; close them so
+            // the HTTP server can finish.
+            mcp_cancel.cancel();
+        }
+    };
+    let deadline = async {
+        signalled.notified().await;
+        tokio::time::sleep(grace).await;
+    };
+
+    let server = axum::serve(listener, app).with_graceful_shutdown(drain);
+    tokio::select! {
+        result = server.into_future() => result?,
+        _ = deadline => {
+            tracing::warn!(
+                "shutdown grace period elapsed with {} tool call(s) still running",
+                lifecycle.in_flight()
+            );
+        }
+    }
+    if let Some(watcher) = watcher {
+        watcher.abort();
+    }
+    if let Some(gc) = gc {
+        gc.abort();
+    }
+    #[cfg(feature = "grpc")]
+    if let Some(grpc) = grpc {
+        grpc.abort();
+    }
+    tracing::info!("Server stopped");
 
     Ok(())
 }
 
+/// Resolve when the process receives Ctrl-C or (on Unix) SIGTERM.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(_) => std::future::pending::<()>().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
+
 // ============ Error response ============
 
 /// JSON error response body, matching `docs/SCHEMAS.md` error schema.
@@ -326,10 +578,13 @@ struct ErrorDetail {
 }
 
 /// Internal error type that converts into an Axum HTTP response.
-struct AppError {
+pub(crate) struct AppError {
     status: StatusCode,
-    code: String,
-    message: String,
+    /// Machine-readable error code, as in [`ErrorDetail`].
+    pub(crate) code: String,
+    pub(crate) message: String,
+    /// Seconds sent in a `Retry-After` header (rate-limited calls only).
+    retry_after: Option<u64>,
 }
 
 impl IntoResponse for AppError {
@@ -337,10 +592,16 @@ impl IntoResponse for AppError {
         let body = ErrorBody {
             error: ErrorDetail {
                 code: self.code,
-                message: self.message,
+                message: scrub::scrub(&self.message),
             },
         };
-        (self.status, Json(body)).into_response()
+        let mut response = (self.status, Json(body)).into_response();
+        if let Some(secs) = self.retry_after {
+            response
+                .headers_mut()
+                .insert(axum::http::header::RETRY_AFTER, HeaderValue::from(secs));
+        }
+        response
     }
 }
 
@@ -350,6 +611,27 @@ fn bad_request(message: impl Into<String>) -> AppError {
         status: StatusCode::BAD_REQUEST,
         code: "bad_request".to_string(),
         message: message.into(),
+        retry_after: None,
+    }
+}
+
+/// Constructs a 401 error for a missing or unknown API key.
+fn unauthorized(message: impl Into<String>) -> AppError {
+    AppError {
+        status: StatusCode::UNAUTHORIZED,
+        code: "unauthorized".to_string(),
+        message: message.into(),
+        retry_after: None,
+    }
+}
+
+/// Constructs a 403 error for a request outside the API key's scope.
+fn forbidden(message: impl Into<String>) -> AppError {
+    AppError {
+        status: StatusCode::FORBIDDEN,
+        code: auth::FORBIDDEN.to_string(),
+        message: message.into(),
+        retry_after: None,
     }
 }
 
@@ -359,6 +641,17 @@ fn not_found(message: impl Into<String>) -> AppError {
         status: StatusCode::NOT_FOUND,
         code: "not_found".to_string(),
         message: message.into(),
+        retry_after: None,
+    }
+}
+
+/// Constructs a 500 error for a failure outside any tool.
+fn internal(message: impl Into<String>) -> AppError {
+    AppError {
+        status: StatusCode::INTERNAL_SERVER_ERROR,
+        code: "internal".to_string(),
+        message: message.into(),
+        retry_after: None,
     }
 }
 
@@ -368,6 +661,27 @@ fn timeout_error(message: impl Into<String>) -> AppError {
         status: StatusCode::REQUEST_TIMEOUT,
         code: "timeout".to_string(),
         message: message.into(),
+        retry_after: None,
+    }
+}
+
+/// Constructs a 503 error for requests arriving during shutdown.
+fn unavailable(message: impl Into<String>) -> AppError {
+    AppError {
+        status: StatusCode::SERVICE_UNAVAILABLE,
+        code: "unavailable".to_string(),
+        message: message.into(),
+        retry_after: None,
+    }
+}
+
+/// Constructs a 429 error for a tool call rejected by `[server.limits]`.
+fn rate_limited(err: Saturated) -> AppError {
+    AppError {
+        status: StatusCode::TOO_MANY_REQUESTS,
+        code: limits::RATE_LIMITED.to_string(),
+        message: err.to_string(),
+        retry_after: Some(err.retry_after_secs),
     }
 }
 
@@ -377,6 +691,7 @@ fn tool_error(message: impl Into<String>) -> AppError {
         status: StatusCode::INTERNAL_SERVER_ERROR,
         code: "tool_error".to_string(),
         message: message.into(),
+        retry_after: None,
     }
 }
 
@@ -387,7 +702,9 @@ fn tool_error(message: impl Into<String>) -> AppError {
 fn classify_tool_error(tool_name: &str, err: anyhow::Error) -> AppError {
     let msg = err.to_string();
 
-    if msg.contains("not found") {
+    if msg.starts_with(auth::FORBIDDEN) {
+        forbidden(format!("{}: {}", tool_name, msg))
+    } else if msg.contains("not found") {
         not_found(format!("{}: {}", tool_name, msg))
     } else if msg.contains("must not be empty")
         || msg.contains("embeddings")
@@ -430,6 +747,245 @@ async fn handle_health() -> Json<HealthResponse> {
     })
 }
 
+// ============ Request logging ============
+
+/// Header carrying the per-request ID.
+const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Run the request inside a `request` span and log its outcome.
+///
+/// Reuses a well-formed incoming `X-Request-Id` (at most 128 visible ASCII
+/// characters) or generates one, and sets it on the response. A W3C
+/// `traceparent` header makes the span part of the caller's trace.
+async fn trace_request(mut req: Request, next: Next) -> Response {
+    let id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|v| !v.is_empty() && v.len() <= 128 && v.bytes().all(|b| b.is_ascii_graphic()))
+        .map(str::to_string)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().simple().to_string());
+    let path = req.uri().path().to_string();
+    let span = tracing::info_span!("request", id = %id, method = %req.method(), path = %path);
+    telemetry::set_remote_parent(&span, req.headers());
+    req.extensions_mut().insert(telemetry::request_trace(&span));
+
+    let started = Instant::now();
+    let mut response = next.run(req).instrument(span.clone()).await;
+    let status = response.status().as_u16();
+    let latency_ms = started.elapsed().as_millis() as u64;
+    span.in_scope(|| {
+        if is_probe(&path) {
+            tracing::debug!(status, latency_ms, "request completed");
+        } else {
+            tracing::info!(status, latency_ms, "request completed");
+        }
+    });
+
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+// ============ Authentication ============
+
+/// Whether `path` is a probe that load balancers call without a key.
+fn is_probe(path: &str) -> bool {
+    matches!(path, "/health" | "/livez" | "/readyz" | "/metrics")
+}
+
+/// Reject requests without a configured API key (`401 unauthorized`) and
+/// make the authenticated [`ApiKey`] available to handlers as an extension.
+async fn require_api_key(
+    State(keys): State<Arc<ApiKeys>>,
+    mut req: Request,
+    next: Next,
+) -> Response {
+    if is_probe(req.uri().path()) {
+        return next.run(req).await;
+    }
+    match keys.authenticate(req.headers()) {
+        Some(key) => {
+            tracing::debug!(key = %key.name, "authenticated");
+            let key = key.clone();
+            req.extensions_mut().insert(key);
+            next.run(req).await
+        }
+        None => unauthorized("missing or invalid API key").into_response(),
+    }
+}
+
+/// The source scope of the request's API key, if it has one.
+fn scope_of(key: &Option<Extension<ApiKey>>) -> Option<Arc<SourceScope>> {
+    key.as_ref().and_then(|Extension(key)| key.scope.clone())
+}
+
+// ============ GET /readyz ============
+
+/// JSON response body for `GET /readyz`.
+#[derive(Serialize)]
+struct ReadyResponse {
+    /// `"ready"` or `"not_ready"`.
+    status: &'static str,
+    /// `"ok"` or the connection error.
+    database: String,
+    /// `"ok"`, or the tables that `ctx init` has not created yet.
+    migrations: String,
+    /// `true` once shutdown has started.
+    draining: bool,
+}
+
+/// Handler for `GET /readyz`.
+///
+/// Returns `200` when the database is reachable, all migrations have been
+/// applied, and the server is not shutting down; `503` otherwise, so load
+/// balancers stop routing to a draining or misconfigured instance.
+async fn handle_ready(
+    State((state, _)): State<(AppState, ExtState)>,
+) -> (StatusCode, Json<ReadyResponse>) {
+    let draining = state.lifecycle.is_draining();
+    let (database, migrations) = match db::connect(&state.config).await {
+        Ok(pool) => {
+            let migrations = match migrate::missing_tables(&pool).await {
+                Ok(missing) if missing.is_empty() => "ok".to_string(),
+                Ok(missing) => format!("missing tables: {} (run ctx init)", missing.join(", ")),
+                Err(e) => format!("error: {}", e),
+            };
+            pool.close().await;
+            ("ok".to_string(), migrations)
+        }
+        Err(e) => (format!("error: {}", e), "unknown".to_string()),
+    };
+
+    let ready = !draining && database == "ok" && migrations == "ok";
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (
+        status,
+        Json(ReadyResponse {
+            status: if ready { "ready" } else { "not_ready" },
+            database,
+            migrations,
+            draining,
+        }),
+    )
+}
+
+// ============ GET /metrics ============
+
+/// Handler for `GET /metrics`.
+///
+/// Returns counters in the Prometheus text exposition format. Query cache
+/// hits and misses, and embedding calls per provider, are counted since
+/// server start; the entry count is read from the database.
+async fn handle_metrics(
+    State((state, _)): State<(AppState, ExtState)>,
+) -> ([(axum::http::header::HeaderName, &'static str); 1], String) {
+    let (hits, misses) = query_cache::process_counters();
+    let mut body = String::new();
+    body.push_str("# HELP ctx_query_cache_hits_total Query embeddings served from the cache.\n");
+    body.push_str("# TYPE ctx_query_cache_hits_total counter\n");
+    body.push_str(&format!("ctx_query_cache_hits_total {}\n", hits));
+    body.push_str(
+        "# HELP ctx_query_cache_misses_total Query embeddings computed by the provider.\n",
+    );
+    body.push_str("# TYPE ctx_query_cache_misses_total counter\n");
+    body.push_str(&format!("ctx_query_cache_misses_total {}\n", misses));
+    let providers = embedding::provider_counters();
+    if !providers.is_empty() {
+        body.push_str(
+            "# HELP ctx_embedding_calls_total Embedding calls per provider, served or failed.\n",
+        );
+        body.push_str("# TYPE ctx_embedding_calls_total counter\n");
+        for (provider, counters) in providers {
+            for (outcome, value) in [("served", counters.served), ("failed", counters.failed)] {
+                body.push_str(&format!(
+                    "ctx_embedding_calls_total{{provider=\"{}\",outcome=\"{}\"}} {}\n",
+                    provider, outcome, value
+                ));
+            }
+        }
+    }
+    body.push_str("# HELP ctx_tool_calls_in_flight Tool calls currently executing.\n");
+    body.push_str("# TYPE ctx_tool_calls_in_flight gauge\n");
+    body.push_str(&format!(
+        "ctx_tool_calls_in_flight {}\n",
+        state.lifecycle.in_flight()
+    ));
+
+    if let Some(ref limiter) = state.limiter {
+        let stats = limiter.stats();
+        body.push_str(
+            "# HELP ctx_tool_calls_queued Tool calls waiting for a [server.limits] slot.\n",
+        );
+        body.push_str("# TYPE ctx_tool_calls_queued gauge\n");
+        body.push_str(&format!("ctx_tool_calls_queued {}\n", stats.queued));
+        body.push_str(
+            "# HELP ctx_tool_calls_rejected_total Tool calls rejected by [server.limits].\n",
+        );
+        body.push_str("# TYPE ctx_tool_calls_rejected_total counter\n");
+        body.push_str(&format!(
+            "ctx_tool_calls_rejected_total {}\n",
+            stats.rejected
+        ));
+    }
+
+    if let Some(ref cache) = state.search_cache {
+        let stats = cache.stats();
+        for (name, kind, help, value) in [
+            (
+                "ctx_search_cache_hits_total",
+                "counter",
+                "Search responses served from the server cache.",
+                stats.hits,
+            ),
+            (
+                "ctx_search_cache_misses_total",
+                "counter",
+                "Search calls that missed the server cache.",
+                stats.misses,
+            ),
+            (
+                "ctx_search_cache_invalidations_total",
+                "counter",
+                "Times the search cache was cleared after a sync or embed run.",
+                stats.invalidations,
+            ),
+            (
+                "ctx_search_cache_entries",
+                "gauge",
+                "Search responses currently cached.",
+                stats.entries as u64,
+            ),
+        ] {
+            body.push_str(&format!("# HELP {} {}\n", name, help));
+            body.push_str(&format!("# TYPE {} {}\n", name, kind));
+            body.push_str(&format!("{} {}\n", name, value));
+        }
+    }
+
+    if let Ok(pool) = db::connect(&state.config).await {
+        if let Ok(stats) = query_cache::cache_stats(&pool).await {
+            body.push_str("# HELP ctx_query_cache_entries Query embeddings stored in the cache.\n");
+            body.push_str("# TYPE ctx_query_cache_entries gauge\n");
+            body.push_str(&format!("ctx_query_cache_entries {}\n", stats.entries));
+        }
+        pool.close().await;
+    }
+
+    (
+        [(
+            axum::http::header::CONTENT_TYPE,
+            "text/plain; version=0.0.4",
+        )],
+        body,
+    )
+}
+
 // ============ GET /tools/list ============
 
 /// JSON response body for `GET /tools/list`.
@@ -443,11 +999,26 @@ struct ToolListResponse {
 ///
 /// Returns all registered tools with their OpenAI function-calling parameter
 /// schemas. Built-in tools have `builtin: true`; Lua and custom Rust tools
-/// have `builtin: false`.
+/// have `builtin: false`. Keys limited to some sources only see the
+/// built-in tools.
 async fn handle_list_tools(
     State((state, (extra_tools, _extra_agents))): State<(AppState, ExtState)>,
+    key: Option<Extension<ApiKey>>,
 ) -> Json<ToolListResponse> {
-    let mut tools: Vec<ToolInfo> = state
+    let tools = list_tools(&state, &extra_tools, scope_of(&key).is_some());
+    Json(ToolListResponse { tools })
+}
+
+/// Registered tools: built-in and Lua tools, then custom Rust tools.
+/// `scoped` callers (keys limited to some sources) only get the built-in
+/// tools. Shared by `GET /tools/list` and the gRPC `ListTools` call.
+pub(crate) fn list_tools(
+    state: &AppState,
+    extra_tools: &ToolRegistry,
+    scoped: bool,
+) -> Vec<ToolInfo> {
+    let ext = state.live.current();
+    let mut tools: Vec<ToolInfo> = ext
         .tools
         .tools()
         .iter()
@@ -456,6 +1027,7 @@ async fn handle_list_tools(
             description: t.description().to_string(),
             builtin: t.is_builtin(),
             parameters: t.parameters_schema(),
+            annotations: t.annotations(),
         })
         .collect();
 
@@ -466,10 +1038,38 @@ async fn handle_list_tools(
             description: t.description().to_string(),
             builtin: false,
             parameters: t.parameters_schema(),
+            annotations: t.annotations(),
         });
     }
 
-    Json(ToolListResponse { tools })
+    if scoped {
+        tools.retain(|t| t.builtin);
+    }
+    tools
+}
+
+// ============ GET /capabilities ============
+
+/// Handler for `GET /capabilities`.
+///
+/// Runs the built-in `capabilities` tool and returns its result unwrapped,
+/// so clients can discover search modes and filters with a plain GET.
+async fn handle_capabilities(
+    State((state, (extra_tools, _extra_agents))): State<(AppState, ExtState)>,
+    headers: HeaderMap,
+    key: Option<Extension<ApiKey>>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let result = call_tool(
+        &state,
+        &extra_tools,
+        "capabilities",
+        serde_json::json!({}),
+        audit::caller_from_headers(&headers),
+        "http",
+        scope_of(&key),
+    )
+    .await?;
+    Ok(Json(result))
 }
 
 // ============ POST /tools/{name} ============
@@ -481,31 +1081,115 @@ async fn handle_list_tools(
 /// and executes it.
 ///
 /// Returns `404` if the tool is not found, `400` for parameter validation
-/// errors, `408` for timeout, and `500` for execution errors.
+/// errors, `403` for calls outside the API key's scope, `408` for timeout,
+/// and `500` for execution errors.
 async fn handle_tool_call(
     State((state, (extra_tools, _extra_agents))): State<(AppState, ExtState)>,
     Path(name): Path<String>,
+    headers: HeaderMap,
+    key: Option<Extension<ApiKey>>,
     Json(params): Json<serde_json::Value>,
 ) -> Result<Json<serde_json::Value>, AppError> {
+    let result = call_tool(
+        &state,
+        &extra_tools,
+        &name,
+        params,
+        audit::caller_from_headers(&headers),
+        "http",
+        scope_of(&key),
+    )
+    .await?;
+    Ok(Json(serde_json::json!({ "result": result })))
+}
+
+/// Look up, validate, and run a tool, recording the call in the audit log
+/// under `transport`. Shared by `POST /tools/{name}` and the gRPC service.
+pub(crate) async fn call_tool(
+    state: &AppState,
+    extra_tools: &ToolRegistry,
+    name: &str,
+    params: serde_json::Value,
+    caller: Option<String>,
+    transport: &str,
+    scope: Option<Arc<SourceScope>>,
+) -> Result<serde_json::Value, AppError> {
     // Look up the tool in the main registry, then extras
-    let tool = state
+    let ext = state.live.current();
+    let tool = ext
         .tools
-        .find(&name)
-        .or_else(|| extra_tools.find(&name))
+        .find(name)
+        .or_else(|| extra_tools.find(name))
         .ok_or_else(|| not_found(format!("no tool registered with name: {}", name)))?;
 
+    if scope.is_some() && !tool.is_builtin() {
+        return Err(forbidden(format!(
+            "tool '{}' is not available to API keys limited to some sources",
+            name
+        )));
+    }
+
+    let _in_flight = state
+        .lifecycle
+        .begin_call()
+        .ok_or_else(|| unavailable("server is shutting down"))?;
+
     // Validate parameters against the tool's schema
     let validated_params = validate_params(&tool.parameters_schema(), &params)
         .map_err(|e| bad_request(e.to_string()))?;
 
+    let _permit = match state.limiter {
+        Some(ref limiter) => Some(limiter.acquire(name).await.map_err(rate_limited)?),
+        None => None,
+    };
+
     // Execute via the Tool trait
-    let ctx = ToolContext::new(state.config.clone());
-    let result = tool
-        .execute(validated_params, &ctx)
-        .await
-        .map_err(|e| classify_tool_error(&name, e))?;
+    let ctx = ToolContext::new(ext.config.clone())
+        .with_scope(scope)
+        .with_tools(ext.tools.clone())
+        .with_audit(state.audit.clone(), caller.clone());
+    let started = std::time::Instant::now();
+    let result =
+        search_cache::execute_tool(state.search_cache.as_deref(), tool, validated_params, &ctx)
+            .await;
 
-    Ok(Json(serde_json::json!({ "result": result })))
+    if let Some(ref audit) = state.audit {
+        let entry = AuditEntry::new(
+            name,
+            caller,
+            transport,
+            params,
+            started.elapsed(),
+            result.as_ref().err().map(|e| e.to_string()),
+        );
+        audit.record(entry).await;
+    }
+
+    result.map_err(|e| classify_tool_error(name, e))
+}
+
+// ============ POST /rpc ============
+
+/// Handler for `POST /rpc`.
+///
+/// Serves MCP requests as plain JSON-RPC 2.0 without sessions or SSE (see
+/// [`McpBridge::handle_jsonrpc`]). Protocol errors are JSON-RPC error
+/// objects with status `200`; bodies holding only notifications get `202`
+/// with no content.
+async fn handle_jsonrpc(
+    State((state, _)): State<(AppState, ExtState)>,
+    headers: HeaderMap,
+    key: Option<Extension<ApiKey>>,
+    body: axum::body::Bytes,
+) -> Response {
+    match state
+        .rpc
+        .handle_jsonrpc(&body, audit::caller_from_headers(&headers), scope_of(&key))
+        .await
+    {
+        Some(reply) => Json(reply).into_response(),
+        None => StatusCode::ACCEPTED.into_response(),
+    }
 }
 
 // ============ GET /agents/list ============
@@ -520,11 +1204,14 @@ struct AgentListResponse {
 /// Handler for `GET /agents/list`.
 ///
 /// Returns all registered agents with their metadata, tool lists, and
-/// argument schemas. Includes TOML, Lua, and custom Rust agents.
+/// argument schemas. Includes TOML, Lua, and custom Rust agents; keys
+/// limited to some sources only see TOML agents.
 async fn handle_list_agents(
     State((state, (_extra_tools, extra_agents))): State<(AppState, ExtState)>,
+    key: Option<Extension<ApiKey>>,
 ) -> Json<AgentListResponse> {
-    let mut agents: Vec<AgentInfo> = state
+    let ext = state.live.current();
+    let mut agents: Vec<AgentInfo> = ext
         .agents
         .agents()
         .iter()
@@ -548,6 +1235,10 @@ async fn handle_list_agents(
         });
     }
 
+    if scope_of(&key).is_some() {
+        agents.retain(|a| a.source == "toml");
+    }
+
     Json(AgentListResponse { agents })
 }
 
@@ -560,19 +1251,30 @@ async fn handle_list_agents(
 /// executes the script's `agent.resolve()` function with the provided
 /// arguments and access to the context bridge (search, get, sources).
 ///
-/// Returns `404` if the agent is not found.
+/// Returns `404` if the agent is not found, and `403` for Lua and custom
+/// Rust agents when the API key is limited to some sources.
 async fn handle_resolve_agent(
     State((state, (_extra_tools, extra_agents))): State<(AppState, ExtState)>,
     Path(name): Path<String>,
+    key: Option<Extension<ApiKey>>,
     Json(args): Json<serde_json::Value>,
 ) -> Result<Json<serde_json::Value>, AppError> {
-    let agent = state
+    let ext = state.live.current();
+    let agent = ext
         .agents
         .find(&name)
         .or_else(|| extra_agents.find(&name))
         .ok_or_else(|| not_found(format!("no agent registered with name: {}", name)))?;
 
-    let ctx = ToolContext::new(state.config.clone());
+    let scope = scope_of(&key);
+    if scope.is_some() && agent.source() != "toml" {
+        return Err(forbidden(format!(
+            "agent '{}' is not available to API keys limited to some sources",
+            name
+        )));
+    }
+
+    let ctx = ToolContext::new(ext.config.clone()).with_scope(scope);
     let prompt = agent
         .resolve(args, &ctx)
         .await
@@ -582,3 +1284,93 @@ async fn handle_resolve_agent(
         tool_error(format!("failed to serialize agent prompt: {}", e))
     })?))
 }
+
+// ============ POST /admin/reload ============
+
+/// Handler for `POST /admin/reload`.
+///
+/// Re-reads the config file and reloads tool, agent, and connector
+/// definitions (see [`crate::reload`]). Returns the new generation and
+/// counts, or `422 reload_failed` with the validation error, in which case
+/// the previous tools and agents keep being served. Keys limited to some
+/// sources get `403`.
+async fn handle_reload(
+    State((state, _)): State<(AppState, ExtState)>,
+    key: Option<Extension<ApiKey>>,
+) -> Result<Json<ReloadSummary>, AppError> {
+    if scope_of(&key).is_some() {
+        return Err(forbidden(
+            "reload requires an API key without source limits",
+        ));
+    }
+    state.live.reload().await.map(Json).map_err(|e| {
+        tracing::warn!("reload failed, keeping previous tools and agents: {:#}", e);
+        AppError {
+            status: StatusCode::UNPROCESSABLE_ENTITY,
+            code: "reload_failed".to_string(),
+            message: format!("{:#}", e),
+            retry_after: None,
+        }
+    })
+}
+
+// ============ /admin/jobs ============
+
+/// Query parameters of `GET /admin/jobs`.
+#[derive(Debug, Deserialize)]
+struct ListJobsParams {
+    /// Include finished jobs.
+    #[serde(default)]
+    all: bool,
+}
+
+/// Handler for `GET /admin/jobs`.
+///
+/// Lists running and paused jobs, as `ctx jobs list --json` does. Keys
+/// limited to some sources get `403`.
+async fn handle_list_jobs(
+    State((state, _)): State<(AppState, ExtState)>,
+    key: Option<Extension<ApiKey>>,
+    Query(params): Query<ListJobsParams>,
+) -> Result<Json<Vec<JobRow>>, AppError> {
+    if scope_of(&key).is_some() {
+        return Err(forbidden("jobs require an API key without source limits"));
+    }
+    let pool = db::connect(&state.config)
+        .await
+        .map_err(|e| internal(format!("{:#}", e)))?;
+    let listed = jobs::list(&pool, params.all).await;
+    pool.close().await;
+    listed.map(Json).map_err(|e| internal(format!("{:#}", e)))
+}
+
+/// Handler for `POST /admin/jobs/{id}/{action}`.
+///
+/// Requests `pause`, `resume`, or `cancel` of the job whose ID starts with
+/// `id` and returns the job. `404` when no single job matches, `400` for
+/// an unknown action or a job that already finished. Keys limited to some
+/// sources get `403`.
+async fn handle_job_action(
+    State((state, _)): State<(AppState, ExtState)>,
+    key: Option<Extension<ApiKey>>,
+    Path((id, action)): Path<(String, String)>,
+) -> Result<Json<JobRow>, AppError> {
+    if scope_of(&key).is_some() {
+        return Err(forbidden("jobs require an API key without source limits"));
+    }
+    let pool = db::connect(&state.config)
+        .await
+        .map_err(|e| internal(format!("{:#}", e)))?;
+    let result = match jobs::find(&pool, &id).await {
+        Ok(Some(job)) => jobs::request(&pool, &job, &action)
+            .await
+            .map_err(|e| bad_request(format!("{:#}", e))),
+        Ok(None) => Err(not_found(format!(
+            "no job or more than one matches '{}'",
+            id
+        ))),
+        Err(e) => Err(internal(format!("{:#}", e))),
+    };
+    pool.close().await;
+    result.map(Json)
+}
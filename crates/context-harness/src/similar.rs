@@ -0,0 +1,282 @@
+//! "More like this": the documents nearest to a given document.
+//!
+//! Backs `ctx similar <id>` and the `similar` tool (`POST /tools/similar`).
+//! The target's vector is the mean of its chunk vectors for the configured
+//! embedding model. A document without stored vectors (e.g. synced with
+//! `[sync] embed = false`) is embedded on the fly from its title and body,
+//! cut to `[embedding] max_input_tokens`.
+//!
+//! The nearest chunks are fetched from the vector index and grouped by
+//! document, keeping each document's closest chunk. Results are filtered
+//! like search (`source`, `since`, `as_of`, chunk metadata, an API key's
+//! source scope, and `ctx curate block`), and the target itself is
+//! excluded. A target outside the API key's scope is `forbidden`.
+//!
+//! Unlike search, scores are not min-max normalized: each is the raw
+//! similarity of the document's closest chunk to the target (cosine for
+//! the SQLite index), so scores compare across calls and near-duplicates
+//! score close to `1.0`.
+
+use anyhow::{bail, Result};
+use chrono::NaiveDate;
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+
+use context_harness_core::chunk::{HEADING_KEY, SECTION_SOURCE_ID_KEY, SECTION_SOURCE_URL_KEY};
+use context_harness_core::embedding::blob_to_vec;
+use context_harness_core::search::{chunk_metadata_matches, format_ts_iso, source_matches};
+use context_harness_core::store::{ChunkCandidate, Store};
+
+use crate::config::Config;
+use crate::curate::Curation;
+use crate::db;
+use crate::embedding::{self, EmbeddingProvider};
+use crate::search::{end_of_day_ts, SearchResultItem};
+use crate::storage;
+use crate::vector_index;
+
+/// Inputs of [`similar_documents`].
+#[derive(Debug, Clone, Default)]
+pub struct SimilarQuery<'a> {
+    /// UUID of the target document.
+    pub id: &'a str,
+    /// Only return documents from this source.
+    pub source: Option<&'a str>,
+    /// Only return documents updated on or after this date (`YYYY-MM-DD`).
+    pub since: Option<&'a str>,
+    /// Only return documents last updated on or before this date (`YYYY-MM-DD`).
+    pub as_of: Option<&'a str>,
+    /// Maximum results (default: `[retrieval] final_limit`).
+    pub limit: Option<i64>,
+    /// Chunk metadata filters, as for search.
+    pub chunk_filters: &'a [(String, String)],
+    /// Sources an API key may read; `None` allows every source.
+    pub allowed_sources: Option<&'a [String]>,
+}
+
+/// Documents nearest to `query.id`, most similar first.
+///
+/// # Errors
+///
+/// Returns an error if embeddings are disabled, the document does not
+/// exist, a date is malformed, or the embedding provider fails.
+pub async fn similar_documents(
+    config: &Config,
+    query: &SimilarQuery<'_>,
+) -> Result<Vec<SearchResultItem>> {
+    if !config.embedding.is_enabled() {
+        bail!("similar requires embeddings. Set [embedding] provider in config.");
+    }
+    let since_ts = query
+        .since
+        .map(|s| -> Result<i64> {
+            Ok(NaiveDate::parse_from_str(s, "%Y-%m-%d")?
+                .and_hms_opt(0, 0, 0)
+                .unwrap()
+                .and_utc()
+                .timestamp())
+        })
+        .transpose()?;
+    let as_of_ts = query.as_of.map(end_of_day_ts).transpose()?;
+
+    let pool = db::connect_reader(config).await?;
+    let result = nearest(config, &pool, query, since_ts, as_of_ts).await;
+    if let Ok(ref results) = result {
+        let ids: Vec<&str> = results.iter().map(|r| r.id.as_str()).collect();
+        storage::record_retrievals(config, &pool, &ids).await;
+    }
+    db::close_reader(config, pool).await;
+    result
+}
+
+async fn nearest(
+    config: &Config,
+    pool: &SqlitePool,
+    query: &SimilarQuery<'_>,
+    since_ts: Option<i64>,
+    as_of_ts: Option<i64>,
+) -> Result<Vec<SearchResultItem>> {
+    let store = vector_index::configured_vector_store(config, pool.clone()).await?;
+    let Some(target) = store.get_document_metadata(query.id).await? else {
+        bail!("document not found: {}", query.id);
+    };
+    if let Some(patterns) = query.allowed_sources {
+        if !source_matches(patterns, &target.source) {
+            bail!(
+                "{}: document {} is outside the sources this API key may read",
+                crate::auth::FORBIDDEN,
+                query.id
+            );
+        }
+    }
+
+    let provider = embedding::create_checked_provider(&config.embedding, pool).await?;
+    let vectors = chunk_vectors(pool, query.id, provider.as_ref()).await?;
+    let target_vec = match mean(&vectors) {
+        Some(vector) => vector,
+        None => embed_document(config, &store, query.id, provider.as_ref()).await?,
+    };
+
+    let curation = Curation::load(pool).await?;
+    let limit = query.limit.unwrap_or(config.retrieval.final_limit).max(0) as usize;
+    // The target's own chunks are its nearest neighbours; fetch past them.
+    let candidate_k =
+        config.retrieval.candidate_k_vector + vectors.len() as i64 + curation.extra_candidates();
+    let candidates = store
+        .vector_search(&target_vec, candidate_k, query.source, query.since)
+        .await?;
+
+    let mut best: HashMap<&str, &ChunkCandidate> = HashMap::new();
+    for candidate in &candidates {
+        if candidate.document_id == query.id || curation.is_blocked(&candidate.document_id) {
+            continue;
+        }
+        if !query.chunk_filters.is_empty() {
+            let meta = store.get_chunk_metadata(&candidate.chunk_id).await?;
+            if !meta.is_some_and(|m| chunk_metadata_matches(&m, query.chunk_filters)) {
+                continue;
+            }
+        }
+        let entry = best.entry(&candidate.document_id).or_insert(candidate);
+        if candidate.raw_score > entry.raw_score {
+            *entry = candidate;
+        }
+    }
+
+    let mut results = Vec::new();
+    for (doc_id, candidate) in best {
+        let Some(meta) = store.get_document_metadata(doc_id).await? else {
+            continue;
+        };
+        if query.source.is_some_and(|s| meta.source != s)
+            || query
+                .allowed_sources
+                .is_some_and(|patterns| !source_matches(patterns, &meta.source))
+            || since_ts.is_some_and(|since| meta.updated_at < since)
+            || as_of_ts.is_some_and(|as_of| meta.updated_at > as_of)
+        {
+            continue;
+        }
+        let chunk_meta = store
+            .get_chunk_metadata(&candidate.chunk_id)
+            .await?
+            .unwrap_or_default();
+        let chunk_field = |key: &str| {
+            chunk_meta
+                .get(key)
+                .and_then(|v| v.as_str())
+                .map(str::to_string)
+        };
+        results.push(SearchResultItem {
+            id: meta.id,
+            score: candidate.raw_score,
+            title: meta.title,
+            source: meta.source,
+            source_id: meta.source_id,
+            content_type: Some(meta.content_type),
+            updated_at: format_ts_iso(meta.updated_at),
+            snippet: candidate.snippet.clone(),
+            source_url: meta.source_url,
+            heading: chunk_field(HEADING_KEY),
+            section_source_id: chunk_field(SECTION_SOURCE_ID_KEY),
+            section_source_url: chunk_field(SECTION_SOURCE_URL_KEY),
+            chunk_id: Some(candidate.chunk_id.clone()),
+            parent_id: meta.parent_id,
+            explain: None,
+            origin: None,
+            summary: meta.summary,
+            doc_type: meta.doc_type,
+        });
+    }
+
+    results.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(b.updated_at.cmp(&a.updated_at))
+            .then(a.id.cmp(&b.id))
+    });
+    results.truncate(limit);
+    Ok(results)
+}
+
+/// Vectors of the document's chunks embedded with the provider's model and dims.
+async fn chunk_vectors(
+    pool: &SqlitePool,
+    document_id: &str,
+    provider: &dyn EmbeddingProvider,
+) -> Result<Vec<Vec<f32>>> {
+    let blobs: Vec<Vec<u8>> = sqlx::query_scalar(
+        "SELECT cv.embedding FROM chunk_vectors cv \
+         JOIN embeddings e ON e.chunk_id = cv.chunk_id \
+         WHERE cv.document_id = ? AND e.model = ? AND e.dims = ?",
+    )
+    .bind(document_id)
+    .bind(provider.model_name())
+    .bind(provider.dims() as i64)
+    .fetch_all(pool)
+    .await?;
+    Ok(blobs.iter().map(|b| blob_to_vec(b)).collect())
+}
+
+/// Embed a document that has no stored vectors from its title and body.
+async fn embed_document<S: Store>(
+    config: &Config,
+    store: &S,
+    id: &str,
+    provider: &dyn EmbeddingProvider,
+) -> Result<Vec<f32>> {
+    let Some(doc) = store.get_document(id).await? else {
+        bail!("document not found: {}", id);
+    };
+    let text = match doc.title {
+        Some(title) => format!("{}\n\n{}", title, doc.body),
+        None => doc.body,
+    };
+    embedding::embed_texts(provider, &config.embedding, &[text])
+        .await?
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("Empty embedding response"))
+}
+
+/// Element-wise mean of equal-length vectors; `None` when there are none.
+fn mean(vectors: &[Vec<f32>]) -> Option<Vec<f32>> {
+    let dims = vectors.first()?.len();
+    let mut sum = vec![0.0f32; dims];
+    let mut n = 0usize;
+    for vector in vectors.iter().filter(|v| v.len() == dims) {
+        for (total, x) in sum.iter_mut().zip(vector) {
+            *total += x;
+        }
+        n += 1;
+    }
+    Some(sum.into_iter().map(|total| total / n as f32).collect())
+}
+
+/// CLI entry point for `ctx similar`: prints results like `ctx search`.
+pub async fn run_similar(config: &Config, query: &SimilarQuery<'_>) -> Result<()> {
+    let results = similar_documents(config, query).await?;
+    if results.is_empty() {
+        println!("No results.");
+        return Ok(());
+    }
+    for (i, result) in results.iter().enumerate() {
+        crate::search::print_result(&format!("{}.", i + 1), "    ", result);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mean_averages_each_dimension() {
+        assert_eq!(mean(&[]), None);
+        assert_eq!(
+            mean(&[vec![1.0, 0.0], vec![0.0, 1.0], vec![2.0, 2.0]]),
+            Some(vec![1.0, 1.0])
+        );
+    }
+}
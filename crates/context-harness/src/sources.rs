@@ -5,19 +5,57 @@
 //!
 //! # Health Checks
 //!
-//! Each connector performs a lightweight health check:
+//! [`get_sources`] only inspects the config and local state:
 //!
 //! | Connector | Healthy When |
 //! |-----------|-------------|
 //! | `filesystem` | Configured root directory exists |
 //! | `git` | `git --version` succeeds (binary is on PATH) |
 //! | `s3` | Always `true` if configured (credentials checked at sync time) |
-//! | `slack`, `jira` | Placeholder — always `NOT CONFIGURED` |
+//! | `sharepoint` | Always `true` if configured (credentials checked at sync time) |
+//! | `zendesk` | Always `true` if configured (credentials checked at sync time) |
+//! | `stackoverflow` | Always `true` if configured (credentials checked at sync time) |
+//! | `script` | Script file exists |
+//!
+//! [`probe_sources`] additionally calls each connector's
+//! [`Connector::health`](crate::traits::Connector::health), which makes one real request:
+//!
+//! | Connector | Probe |
+//! |-----------|-------|
+//! | `filesystem` | Read the root directory |
+//! | `git` | `git ls-remote --heads <url> <branch>` |
+//! | `s3` | Signed `HeadBucket` |
+//! | `sharepoint` | Acquire a token and resolve the drive |
+//! | `zendesk` | `GET /api/v2/users/me` and check the token belongs to an agent |
+//! | `stackoverflow` | `GET /questions?pagesize=1` on the site or team, reporting the remaining quota |
+//! | `script` | Load the script and call `connector.health(config)` if defined |
+//!
+//! Probes run one at a time, each bounded by [`ConnectorHealth::TIMEOUT`].
+//! `ctx sources` always probes; the `sources` tool probes when called
+//! with `probe: true`.
+//!
+//! # Sync Status
+//!
+//! `ctx sources` also shows what the database records about each
+//! connector ([`add_sync_status`]): when it last synced successfully, how
+//! many items that sync fetched, how many documents it has in the index,
+//! the error of a failed last sync, and whether the index is stale by the
+//! `[sync] stale_after_hours` thresholds (see [`crate::sync_status`]).
+//! Stale and never-synced sources are repeated as warnings on stderr, and
+//! `ctx sources --json` prints everything as JSON.
+
+use std::collections::HashMap;
 
 use anyhow::Result;
+use chrono::Utc;
 use serde::Serialize;
+use sqlx::Row;
 
 use crate::config::Config;
+use crate::db;
+use crate::stats::format_ts_relative;
+use crate::sync_status::{self, Staleness, SyncRecord};
+use crate::traits::{ConnectorHealth, ConnectorRegistry};
 
 /// Health and configuration status of a single connector.
 ///
@@ -33,6 +71,37 @@ pub struct SourceStatus {
     pub healthy: bool,
     /// Optional diagnostic notes (e.g., `"root directory does not exist"`, `"repo: https://…"`).
     pub notes: Option<String>,
+    /// Duration of the live probe, when one ran. See [`probe_sources`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+    /// Why the live probe failed (e.g. `"access denied to bucket …"`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Sync history and staleness, when attached by [`add_sync_status`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sync: Option<SourceSync>,
+}
+
+/// What the database records about a connector's syncs.
+///
+/// Times are Unix timestamps. See [`crate::sync_status`].
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceSync {
+    /// End of the last completed sync.
+    pub last_success_at: Option<i64>,
+    /// End of the last completed or failed sync.
+    pub last_attempt_at: Option<i64>,
+    /// Items the last completed sync fetched.
+    pub last_items: Option<u64>,
+    /// Documents in the index from this source.
+    pub documents: i64,
+    /// Why the last sync failed; cleared by the next completed sync.
+    pub last_error: Option<String>,
+    /// `"fresh"`, `"stale"`, or `"never"` (no completed sync).
+    pub staleness: Staleness,
+    /// Threshold for `staleness` from `[sync] stale_after_hours` or
+    /// `[sync.stale_after]`.
+    pub stale_after_hours: u64,
 }
 
 /// Returns the configuration and health status of all known connectors.
@@ -53,6 +122,9 @@ pub fn get_sources(config: &Config) -> Vec<SourceStatus> {
                 configured: true,
                 healthy: true,
                 notes: Some(format!("root: {}", fs_config.root.display())),
+                latency_ms: None,
+                error: None,
+                sync: None,
             });
         } else {
             sources.push(SourceStatus {
@@ -60,6 +132,9 @@ pub fn get_sources(config: &Config) -> Vec<SourceStatus> {
                 configured: true,
                 healthy: false,
                 notes: Some("root directory does not exist".to_string()),
+                latency_ms: None,
+                error: None,
+                sync: None,
             });
         }
     }
@@ -78,6 +153,9 @@ pub fn get_sources(config: &Config) -> Vec<SourceStatus> {
                 configured: true,
                 healthy: true,
                 notes: Some(format!("repo: {}", git_config.url)),
+                latency_ms: None,
+                error: None,
+                sync: None,
             });
         } else {
             sources.push(SourceStatus {
@@ -85,6 +163,9 @@ pub fn get_sources(config: &Config) -> Vec<SourceStatus> {
                 configured: true,
                 healthy: false,
                 notes: Some("git binary not found".to_string()),
+                latency_ms: None,
+                error: None,
+                sync: None,
             });
         }
     }
@@ -96,6 +177,68 @@ pub fn get_sources(config: &Config) -> Vec<SourceStatus> {
             configured: true,
             healthy: true,
             notes: Some(format!("bucket: {}", s3_config.bucket)),
+            latency_ms: None,
+            error: None,
+            sync: None,
+        });
+    }
+
+    // SharePoint / OneDrive connectors
+    for (name, sp_config) in &config.connectors.sharepoint {
+        let target = match (&sp_config.site, &sp_config.drive_id, &sp_config.user) {
+            (Some(site), _, _) => format!("site: {}", site),
+            (_, Some(drive_id), _) => format!("drive: {}", drive_id),
+            (_, _, Some(user)) => format!("onedrive: {}", user),
+            _ => "no drive selected".to_string(),
+        };
+        sources.push(SourceStatus {
+            name: format!("sharepoint:{}", name),
+            configured: true,
+            healthy: true,
+            notes: Some(target),
+            latency_ms: None,
+            error: None,
+            sync: None,
+        });
+    }
+
+    // Zendesk connectors
+    for (name, zd_config) in &config.connectors.zendesk {
+        let content = match (zd_config.tickets, zd_config.articles) {
+            (true, true) => "tickets, articles",
+            (true, false) => "tickets",
+            _ => "articles",
+        };
+        sources.push(SourceStatus {
+            name: format!("zendesk:{}", name),
+            configured: true,
+            healthy: true,
+            notes: Some(format!("{} ({})", zd_config.api_base(), content)),
+            latency_ms: None,
+            error: None,
+            sync: None,
+        });
+    }
+
+    // Stack Overflow connectors
+    for (name, so_config) in &config.connectors.stackoverflow {
+        let scope = match &so_config.team {
+            Some(team) => format!("team {}", team),
+            None => format!("site {}", so_config.site),
+        };
+        let notes = if so_config.tags.is_empty() {
+            scope
+        } else {
+            format!("{} (tags: {})", scope, so_config.tags.join(", "))
+        };
+        sources.push(SourceStatus {
+            name: format!("stackoverflow:{}", name),
+            configured: true,
+            healthy: true,
+            notes: Some(notes),
+            latency_ms: None,
+            error: None,
+            sync: None,
         });
     }
 
@@ -114,22 +257,163 @@ pub fn get_sources(config: &Config) -> Vec<SourceStatus> {
                     script_config.path.display()
                 ))
             },
+            latency_ms: None,
+            error: None,
+            sync: None,
         });
     }
 
     sources
 }
 
+/// Like [`get_sources`], but also runs each connector's live health probe.
+///
+/// A healthy probe replaces the notes with what was checked; a failed one
+/// keeps the notes and records the reason in `error`. Connectors that
+/// don't implement [`Connector::health`](crate::traits::Connector::health) keep their static status.
+pub async fn probe_sources(config: &Config) -> Vec<SourceStatus> {
+    let mut sources = get_sources(config);
+    let registry = ConnectorRegistry::from_config(config);
+
+    for connector in registry.connectors() {
+        let label = connector.source_label();
+        let Some(status) = sources.iter_mut().find(|s| s.name == label) else {
+            continue;
+        };
+        apply_health(status, connector.health().await);
+    }
+
+    sources
+}
+
+/// Fold a probe result into a statically computed status.
+fn apply_health(status: &mut SourceStatus, health: ConnectorHealth) {
+    if health.latency_ms.is_none() {
+        return;
+    }
+    status.healthy = health.healthy;
+    status.latency_ms = health.latency_ms;
+    if health.healthy {
+        status.notes = Some(health.message);
+    } else {
+        status.error = Some(health.message);
+    }
+}
+
+/// Attach each connector's sync history and staleness from the database.
+///
+/// Without a database file, every source is reported as never synced.
+pub async fn add_sync_status(config: &Config, sources: &mut [SourceStatus]) -> Result<()> {
+    let mut records: HashMap<String, SyncRecord> = HashMap::new();
+    let mut documents: HashMap<String, i64> = HashMap::new();
+    if config.db.path.exists() {
+        let pool = db::connect(config).await?;
+        records = sync_status::load(&pool).await?;
+        let rows = sqlx::query("SELECT source, COUNT(*) AS n FROM documents GROUP BY source")
+            .fetch_all(&pool)
+            .await?;
+        for row in rows {
+            documents.insert(row.get("source"), row.get("n"));
+        }
+        pool.close().await;
+    }
+
+    let now = Utc::now().timestamp();
+    for status in sources.iter_mut() {
+        let record = records.remove(&status.name).unwrap_or_default();
+        let stale_after_hours = sync_status::stale_after_hours(&config.sync, &status.name);
+        status.sync = Some(SourceSync {
+            last_success_at: record.last_success_at,
+            last_attempt_at: record.last_attempt_at,
+            last_items: record.last_items,
+            documents: documents.get(&status.name).copied().unwrap_or(0),
+            last_error: record.last_error,
+            staleness: sync_status::staleness(record.last_success_at, stale_after_hours, now),
+            stale_after_hours,
+        });
+    }
+    Ok(())
+}
+
+/// Warning for a stale or never-synced source; `None` when it is fresh.
+fn staleness_warning(status: &SourceStatus) -> Option<String> {
+    let sync = status.sync.as_ref()?;
+    let failing = match &sync.last_error {
+        Some(e) => format!("; last sync failed: {}", e),
+        None => String::new(),
+    };
+    match (sync.staleness, sync.last_success_at) {
+        (Staleness::Stale, Some(ts)) => Some(format!(
+            "Warning: {} last synced {} (stale after {}h){}",
+            status.name,
+            format_ts_relative(ts),
+            sync.stale_after_hours,
+            failing
+        )),
+        (Staleness::Never, _) => Some(format!(
+            "Warning: {} has never synced{}",
+            status.name, failing
+        )),
+        _ => None,
+    }
+}
+
 /// CLI entry point for `ctx sources`.
 ///
-/// Calls [`get_sources`] and prints a formatted table of connector statuses to stdout.
-pub fn list_sources(config: &Config) -> Result<()> {
-    let sources = get_sources(config);
+/// Calls [`probe_sources`] and [`add_sync_status`] and prints a table of
+/// connector statuses to stdout, with the probe latency, the last
+/// successful sync, item and document counts, and staleness, followed by
+/// warnings for stale sources on stderr. With `json`, prints the statuses
+/// as a JSON array instead.
+pub async fn list_sources(config: &Config, json: bool) -> Result<()> {
+    let mut sources = probe_sources(config).await;
+    add_sync_status(config, &mut sources).await?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&sources)?);
+        return Ok(());
+    }
 
-    println!("{:<16} {:<12} HEALTHY", "CONNECTOR", "STATUS");
+    println!(
+        "{:<20} {:<12} {:<8} {:<8} {:<14} {:<7} {:<7} {:<6} DETAILS",
+        "CONNECTOR", "STATUS", "HEALTHY", "LATENCY", "LAST SYNC", "ITEMS", "DOCS", "FRESH"
+    );
     for s in &sources {
         let status_str = if s.configured { "OK" } else { "NOT CONFIGURED" };
-        println!("{:<16} {:<12} {}", s.name, status_str, s.healthy);
+        let latency = s
+            .latency_ms
+            .map(|ms| format!("{}ms", ms))
+            .unwrap_or_else(|| "-".to_string());
+        let sync = s.sync.as_ref();
+        let last_sync = sync
+            .and_then(|sync| sync.last_success_at)
+            .map(format_ts_relative)
+            .unwrap_or_else(|| "-".to_string());
+        let items = sync
+            .and_then(|sync| sync.last_items)
+            .map(|n| n.to_string())
+            .unwrap_or_else(|| "-".to_string());
+        let docs = sync.map(|sync| sync.documents).unwrap_or(0);
+        let fresh = sync.map(|sync| sync.staleness.as_str()).unwrap_or("-");
+        let sync_error = sync
+            .and_then(|sync| sync.last_error.as_deref())
+            .map(|e| format!("last sync failed: {}", e));
+        let details = s
+            .error
+            .clone()
+            .or(sync_error)
+            .or_else(|| s.notes.clone())
+            .unwrap_or_default();
+        println!(
+            "{:<20} {:<12} {:<8} {:<8} {:<14} {:<7} {:<7} {:<6} {}",
+            s.name, status_str, s.healthy, latency, last_sync, items, docs, fresh, details
+        );
+    }
+
+    for s in &sources {
+        if let Some(warning) = staleness_warning(s) {
+            eprintln!("{}", warning);
+        }
     }
 
     Ok(())
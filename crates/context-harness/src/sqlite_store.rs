@@ -2,17 +2,93 @@
 //!
 //! Maps each [`Store`] operation to the existing SQLite queries used by
 //! the ingestion, search, and retrieval modules.
+//!
+//! # Fuzzy Keyword Search
+//!
+//! With [`SqliteStore::with_fuzzy`], each query term of four or more
+//! characters is OR-ed with indexed terms within edit distance 1 (2 for
+//! terms longer than six characters), found through an `fts5vocab` view of
+//! `chunks_fts`. Expansion is skipped for trigram-tokenized indexes, which
+//! already match substrings.
+//!
+//! # Keyword Ranking
+//!
+//! `chunks_fts` indexes each chunk's text together with its document's
+//! title and summary. Keyword results are ranked by `bm25()` with the
+//! configured `[retrieval.weights]` for the three columns, and `[retrieval] stop_words`
+//! are removed from the query first (see [`SqliteStore::with_retrieval`]).
+//! With `[retrieval.bm25]` constants other than FTS5's, the best matches
+//! are rescored with them (see [`crate::bm25`]).
+//!
+//! # Document Routing
+//!
+//! With [`SqliteStore::with_document_routing`] and document vectors stored
+//! (see [`crate::doc_vectors`]), vector search first ranks document vectors
+//! against the query and then scores only the chunk vectors of the closest
+//! `embedding.route_documents` documents, plus those of documents that have
+//! no document vector yet. Without routing every chunk vector is scanned.
+//!
+//! # Document Writes
+//!
+//! [`SqliteStore::write_document`] upserts a document and replaces its
+//! chunks, FTS rows, and title index row in one transaction, so a sync
+//! that dies mid-document leaves either the old version or the new one,
+//! never a document with a partial chunk set. Writes are keyed by
+//! `(source, source_id, dedup_hash)`: when the stored document has the same
+//! hash and identical chunks, the chunks are kept as they are, with their
+//! IDs and embeddings, which makes retrying a write a no-op.
 
 use anyhow::Result;
 use async_trait::async_trait;
-use sqlx::{Row, SqlitePool};
+use sqlx::{Row, SqliteConnection, SqlitePool};
+use std::collections::{HashMap, HashSet};
 
 use context_harness_core::embedding::{blob_to_vec, cosine_similarity, vec_to_blob};
 use context_harness_core::models::{Chunk, Document};
 use context_harness_core::store::{
-    ChunkCandidate, ChunkResponse, DocumentMetadata, DocumentResponse, Store,
+    ChunkCandidate, ChunkResponse, DocumentChild, DocumentMetadata, DocumentResponse, Store,
 };
 
+use crate::bm25;
+use crate::config::{Bm25Config, Bm25Params, EmbeddingConfig, FtsWeights, RetrievalConfig};
+use crate::db;
+use crate::titles;
+
+/// Upsert of one `documents` row, returning the ID the row ends up with
+/// (the existing one when `(source, source_id)` is already stored).
+const UPSERT_DOCUMENT_SQL: &str = r#"
+    INSERT INTO documents (id, source, source_id, source_url, title, author,
+                           created_at, updated_at, content_type, body,
+                           metadata_json, raw_json, dedup_hash, parent_source_id, summary)
+    VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+    ON CONFLICT(source, source_id) DO UPDATE SET
+        source_url = excluded.source_url,
+        title = excluded.title,
+        author = excluded.author,
+        updated_at = excluded.updated_at,
+        content_type = excluded.content_type,
+        body = excluded.body,
+        metadata_json = excluded.metadata_json,
+        raw_json = excluded.raw_json,
+        dedup_hash = excluded.dedup_hash,
+        parent_source_id = excluded.parent_source_id,
+        summary = excluded.summary
+    RETURNING id
+"#;
+
+/// Outcome of [`SqliteStore::write_document`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocumentWrite {
+    /// ID of the stored document.
+    pub id: String,
+    /// IDs of the stored chunks, in chunk order. These are the existing
+    /// IDs when `unchanged`.
+    pub chunk_ids: Vec<String>,
+    /// The document already had this `dedup_hash` and these chunks, so
+    /// its chunks were left as they are.
+    pub unchanged: bool,
+}
+
 /// SQLite implementation of the [`Store`] trait.
 ///
 /// Wraps a [`SqlitePool`] and translates every `Store` method into one
@@ -20,145 +96,621 @@ use context_harness_core::store::{
 /// chunks_fts, chunk_vectors, embeddings).
 pub struct SqliteStore {
     pool: SqlitePool,
+    fuzzy: bool,
+    weights: FtsWeights,
+    bm25: Bm25Params,
+    stop_words: Vec<String>,
+    /// Documents kept by the document-vector stage; `0` scans every chunk.
+    route_documents: usize,
 }
 
 impl SqliteStore {
     pub fn new(pool: SqlitePool) -> Self {
-        Self { pool }
+        Self {
+            pool,
+            fuzzy: false,
+            weights: FtsWeights::default(),
+            bm25: Bm25Config::default().chunks(),
+            stop_words: Vec::new(),
+            route_documents: 0,
+        }
+    }
+
+    /// Route vector search through document vectors when
+    /// `embedding.document_vectors` is enabled (see [`crate::doc_vectors`]).
+    pub fn with_document_routing(mut self, embedding: &EmbeddingConfig) -> Self {
+        self.route_documents = if embedding.document_vectors == "off" {
+            0
+        } else {
+            embedding.route_documents
+        };
+        self
+    }
+
+    /// The `route_documents` documents whose vectors are closest to
+    /// `query_vec`, or `None` when routing would not narrow the scan.
+    async fn routed_documents(&self, query_vec: &[f32]) -> Result<Option<Vec<String>>> {
+        let exists: bool = sqlx::query_scalar(
+            "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type='table' AND name='document_vectors')",
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        if !exists {
+            return Ok(None);
+        }
+        let rows =
+            sqlx::query("SELECT document_id, embedding FROM document_vectors WHERE dims = ?")
+                .bind(query_vec.len() as i64)
+                .fetch_all(&self.pool)
+                .await?;
+        if rows.len() <= self.route_documents {
+            return Ok(None);
+        }
+
+        let mut scored: Vec<(f32, String)> = rows
+            .iter()
+            .map(|row| {
+                let blob: Vec<u8> = row.get("embedding");
+                (
+                    cosine_similarity(query_vec, &blob_to_vec(&blob)),
+                    row.get("document_id"),
+                )
+            })
+            .collect();
+        scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+        scored.truncate(self.route_documents);
+        Ok(Some(scored.into_iter().map(|(_, id)| id).collect()))
+    }
+
+    /// Enable edit-distance expansion of keyword query terms.
+    #[allow(dead_code)]
+    pub fn with_fuzzy(mut self, fuzzy: bool) -> Self {
+        self.fuzzy = fuzzy;
+        self
+    }
+
+    /// Apply the keyword settings from `[retrieval]`: `fuzzy`, column
+    /// `weights`, `bm25` constants, and `stop_words`.
+    pub fn with_retrieval(mut self, retrieval: &RetrievalConfig) -> Self {
+        self.fuzzy = retrieval.fuzzy;
+        self.weights = retrieval.weights;
+        self.bm25 = retrieval.bm25.chunks();
+        self.stop_words = retrieval
+            .stop_words
+            .iter()
+            .map(|w| w.to_lowercase())
+            .collect();
+        self
+    }
+
+    /// The user's query as FTS terms, without stop words.
+    pub(crate) fn query_terms(&self, query: &str) -> String {
+        self.query_term_list(query).join(" ")
+    }
+
+    /// The terms of [`Self::query_terms`]; quoted phrases stay one term.
+    fn query_term_list(&self, query: &str) -> Vec<String> {
+        let terms = fts_terms_from_user_text(query);
+        if self.stop_words.is_empty() {
+            return terms;
+        }
+        let kept: Vec<String> = terms
+            .iter()
+            .filter(|t| t.starts_with('"') || !self.stop_words.contains(&t.to_lowercase()))
+            .cloned()
+            .collect();
+        if kept.is_empty() {
+            terms
+        } else {
+            kept
+        }
     }
 
     #[allow(dead_code)]
     pub fn pool(&self) -> &SqlitePool {
         &self.pool
     }
-}
-
-fn fts_query_from_user_text(query: &str) -> String {
-    query
-        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
-        .filter(|term| !term.is_empty())
-        .collect::<Vec<_>>()
-        .join(" ")
-}
 
-fn format_ts_iso(ts: i64) -> String {
-    chrono::DateTime::from_timestamp(ts, 0)
-        .map(|dt| dt.format("%Y-%m-%dT%H:%M:%SZ").to_string())
-        .unwrap_or_else(|| ts.to_string())
-}
+    /// Build an FTS query in which each term also matches nearby spellings
+    /// from the index vocabulary: `("deploy" OR "deploys") AND ("runbook")`.
+    async fn fuzzy_fts_query(&self, query: &str) -> Result<String> {
+        let fts_sql: Option<String> = sqlx::query_scalar(
+            "SELECT sql FROM sqlite_master WHERE type='table' AND name='chunks_fts'",
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        if fts_sql.is_none_or(|sql| crate::migrate::fts_tokenizer_of(&sql) == "trigram") {
+            return Ok(self.query_terms(query));
+        }
 
-#[async_trait]
-impl Store for SqliteStore {
-    async fn upsert_document(&self, doc: &Document) -> Result<String> {
+        // The vocab view lives in the connection's temp schema, so create and
+        // query it on the same connection.
+        let mut conn = self.pool.acquire().await?;
         sqlx::query(
-            r#"
-            INSERT INTO documents (id, source, source_id, source_url, title, author,
-                                   created_at, updated_at, content_type, body,
-                                   metadata_json, raw_json, dedup_hash)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
-            ON CONFLICT(source, source_id) DO UPDATE SET
-                source_url = excluded.source_url,
-                title = excluded.title,
-                author = excluded.author,
-                updated_at = excluded.updated_at,
-                content_type = excluded.content_type,
-                body = excluded.body,
-                metadata_json = excluded.metadata_json,
-                raw_json = excluded.raw_json,
-                dedup_hash = excluded.dedup_hash
-            "#,
+            "CREATE VIRTUAL TABLE IF NOT EXISTS temp.chunks_fts_vocab USING fts5vocab(main, chunks_fts, row)",
         )
-        .bind(&doc.id)
-        .bind(&doc.source)
-        .bind(&doc.source_id)
-        .bind(&doc.source_url)
-        .bind(&doc.title)
-        .bind(&doc.author)
-        .bind(doc.created_at)
-        .bind(doc.updated_at)
-        .bind(&doc.content_type)
-        .bind(&doc.body)
-        .bind(&doc.metadata_json)
-        .bind(&doc.raw_json)
-        .bind(&doc.dedup_hash)
-        .execute(&self.pool)
+        .execute(&mut *conn)
         .await?;
 
-        Ok(doc.id.clone())
+        let mut groups = Vec::new();
+        for term in self.query_term_list(query) {
+            if term.starts_with('"') {
+                // Phrases match exactly.
+                groups.push(term);
+                continue;
+            }
+            let lower = term.to_lowercase();
+            let len = lower.chars().count();
+            let max_distance = fuzzy_max_distance(len);
+            let mut variants = vec![fts_quote(&lower)];
+            if max_distance > 0 {
+                let rows: Vec<(String, i64)> = sqlx::query_as(
+                    "SELECT term, doc FROM temp.chunks_fts_vocab WHERE length(term) BETWEEN ? AND ?",
+                )
+                .bind((len - max_distance) as i64)
+                .bind((len + max_distance) as i64)
+                .fetch_all(&mut *conn)
+                .await?;
+                let mut near: Vec<(usize, i64, String)> = rows
+                    .into_iter()
+                    .filter(|(t, _)| *t != lower)
+                    .filter_map(|(t, doc)| {
+                        let d = edit_distance(&lower, &t);
+                        (d <= max_distance).then_some((d, -doc, t))
+                    })
+                    .collect();
+                near.sort();
+                variants.extend(
+                    near.into_iter()
+                        .take(MAX_FUZZY_VARIANTS)
+                        .map(|(_, _, t)| fts_quote(&t)),
+                );
+            }
+            groups.push(format!("({})", variants.join(" OR ")));
+        }
+        Ok(groups.join(" AND "))
     }
 
-    async fn replace_chunks(
+    /// Delete documents with their chunks, FTS and title index rows,
+    /// embeddings, vectors, access records, kept raw items, and stitched
+    /// members, in one transaction.
+    pub async fn delete_documents(&self, ids: &[String]) -> Result<()> {
+        // The title index is created by the first sync after an upgrade,
+        // raw items by the first sync that keeps them, stitched members by
+        // the first sync after an upgrade, and document vectors by the
+        // first embedding run with `document_vectors` enabled.
+        let tables: Vec<String> = sqlx::query_scalar(
+            "SELECT name FROM sqlite_master WHERE type='table' \
+             AND name IN ('titles_fts', 'raw_items', 'stitch_members', 'document_vectors')",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        let has_titles = tables.iter().any(|t| t == "titles_fts");
+        let has_raw_items = tables.iter().any(|t| t == "raw_items");
+        let has_stitch_members = tables.iter().any(|t| t == "stitch_members");
+        let has_document_vectors = tables.iter().any(|t| t == "document_vectors");
+        let mut tx = db::begin_write(&self.pool).await?;
+        for id in ids {
+            if has_raw_items {
+                sqlx::query(
+                    "DELETE FROM raw_items WHERE EXISTS (SELECT 1 FROM documents d \
+                     WHERE d.id = ? AND d.source = raw_items.source AND d.source_id = raw_items.source_id)",
+                )
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+            }
+            if has_stitch_members {
+                sqlx::query(
+                    "DELETE FROM stitch_members WHERE EXISTS (SELECT 1 FROM documents d \
+                     WHERE d.id = ? AND d.source = stitch_members.source \
+                     AND d.source_id = stitch_members.group_id)",
+                )
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+            }
+            if has_titles {
+                sqlx::query("DELETE FROM titles_fts WHERE document_id = ?")
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+            if has_document_vectors {
+                sqlx::query("DELETE FROM document_vectors WHERE document_id = ?")
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+            sqlx::query(
+                "DELETE FROM embeddings WHERE chunk_id IN (SELECT id FROM chunks WHERE document_id = ?)",
+            )
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+            for table in ["chunk_vectors", "chunks_fts", "chunks", "document_access"] {
+                sqlx::query(&format!("DELETE FROM {} WHERE document_id = ?", table))
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+            sqlx::query("DELETE FROM documents WHERE id = ?")
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Upsert `doc` and replace its chunks (stamped with the `chunker`
+    /// fingerprint), FTS rows, and title index row in one transaction.
+    ///
+    /// When the stored document has the same `dedup_hash` and its chunks
+    /// match `chunks` in text, metadata, and chunker, only the document row
+    /// and title index are refreshed, and the stored chunk IDs are
+    /// returned. Otherwise `chunks` are written under the document's
+    /// stored ID, which may differ from `doc.id` when another writer stored
+    /// the document first.
+    pub async fn write_document(
         &self,
-        doc_id: &str,
+        doc: &Document,
         chunks: &[Chunk],
-        vectors: Option<&[Vec<f32>]>,
-    ) -> Result<()> {
-        let mut tx = self.pool.begin().await?;
+        chunker: &str,
+    ) -> Result<DocumentWrite> {
+        let mut tx = db::begin_write(&self.pool).await?;
 
-        sqlx::query(
-            "DELETE FROM chunk_vectors WHERE chunk_id IN (SELECT id FROM chunks WHERE document_id = ?)",
+        let stored: Option<(String, String, Option<String>)> = sqlx::query_as(
+            "SELECT id, dedup_hash, summary FROM documents WHERE source = ? AND source_id = ?",
         )
-        .bind(doc_id)
-        .execute(&mut *tx)
+        .bind(&doc.source)
+        .bind(&doc.source_id)
+        .fetch_optional(&mut *tx)
         .await?;
+        let summary_changed = stored
+            .as_ref()
+            .is_some_and(|(_, _, summary)| *summary != doc.summary);
+        let unchanged_ids = match stored {
+            Some((ref id, ref hash, _)) if *hash == doc.dedup_hash => {
+                stored_chunk_ids_if_same(&mut tx, id, chunks, chunker).await?
+            }
+            _ => None,
+        };
 
-        sqlx::query(
-            "DELETE FROM embeddings WHERE chunk_id IN (SELECT id FROM chunks WHERE document_id = ?)",
-        )
-        .bind(doc_id)
-        .execute(&mut *tx)
-        .await?;
+        let id: String = sqlx::query_scalar(UPSERT_DOCUMENT_SQL)
+            .bind(&doc.id)
+            .bind(&doc.source)
+            .bind(&doc.source_id)
+            .bind(&doc.source_url)
+            .bind(&doc.title)
+            .bind(&doc.author)
+            .bind(doc.created_at)
+            .bind(doc.updated_at)
+            .bind(&doc.content_type)
+            .bind(&doc.body)
+            .bind(&doc.metadata_json)
+            .bind(&doc.raw_json)
+            .bind(&doc.dedup_hash)
+            .bind(&doc.parent_source_id)
+            .bind(&doc.summary)
+            .fetch_one(&mut *tx)
+            .await?;
 
-        sqlx::query("DELETE FROM chunks_fts WHERE document_id = ?")
-            .bind(doc_id)
+        sqlx::query("DELETE FROM titles_fts WHERE document_id = ?")
+            .bind(&id)
             .execute(&mut *tx)
             .await?;
-
-        sqlx::query("DELETE FROM chunks WHERE document_id = ?")
-            .bind(doc_id)
+        sqlx::query("INSERT INTO titles_fts (document_id, title, headings) VALUES (?, ?, ?)")
+            .bind(&id)
+            .bind(&doc.title)
+            .bind(titles::headings_text(
+                &doc.source_id,
+                &doc.content_type,
+                &doc.body,
+            ))
             .execute(&mut *tx)
             .await?;
 
-        for (i, chunk) in chunks.iter().enumerate() {
-            sqlx::query(
-                "INSERT INTO chunks (id, document_id, chunk_index, text, hash) VALUES (?, ?, ?, ?, ?)",
-            )
-            .bind(&chunk.id)
-            .bind(&chunk.document_id)
-            .bind(chunk.chunk_index)
-            .bind(&chunk.text)
-            .bind(&chunk.hash)
+        if let Some(chunk_ids) = unchanged_ids {
+            if summary_changed {
+                sqlx::query("UPDATE chunks_fts SET summary = ? WHERE document_id = ?")
+                    .bind(&doc.summary)
+                    .bind(&id)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+            tx.commit().await?;
+            return Ok(DocumentWrite {
+                id,
+                chunk_ids,
+                unchanged: true,
+            });
+        }
+
+        let chunks: Vec<Chunk> = chunks
+            .iter()
+            .map(|c| Chunk {
+                document_id: id.clone(),
+                ..c.clone()
+            })
+            .collect();
+        write_chunks(&mut tx, &id, &chunks, None).await?;
+        sqlx::query("UPDATE chunks SET chunker = ? WHERE document_id = ?")
+            .bind(chunker)
+            .bind(&id)
             .execute(&mut *tx)
             .await?;
+        tx.commit().await?;
+
+        Ok(DocumentWrite {
+            id,
+            chunk_ids: chunks.into_iter().map(|c| c.id).collect(),
+            unchanged: false,
+        })
+    }
+
+    /// IDs of the documents stored for `source_id` in `source`: the
+    /// document itself and, when `source_id` is an archive, its members
+    /// (`<archive>!/<path>`).
+    pub async fn document_ids_for_source_id(
+        &self,
+        source: &str,
+        source_id: &str,
+    ) -> Result<Vec<String>> {
+        let member_prefix = format!("{}{}", source_id, crate::archive::SEPARATOR);
+        Ok(sqlx::query_scalar(
+            "SELECT id FROM documents WHERE source = ? \
+             AND (source_id = ? OR substr(source_id, 1, length(?)) = ?) ORDER BY source_id",
+        )
+        .bind(source)
+        .bind(source_id)
+        .bind(&member_prefix)
+        .bind(&member_prefix)
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    /// `source_id`s of every document stored for `source`.
+    pub async fn source_ids(&self, source: &str) -> Result<Vec<String>> {
+        Ok(sqlx::query_scalar(
+            "SELECT source_id FROM documents WHERE source = ? ORDER BY source_id",
+        )
+        .bind(source)
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    /// Look up the UUID of a parent document by its `source_id`.
+    async fn resolve_parent_id(
+        &self,
+        source: &str,
+        parent_source_id: Option<&str>,
+    ) -> Result<Option<String>> {
+        let Some(parent_source_id) = parent_source_id else {
+            return Ok(None);
+        };
+        Ok(
+            sqlx::query_scalar("SELECT id FROM documents WHERE source = ? AND source_id = ?")
+                .bind(source)
+                .bind(parent_source_id)
+                .fetch_optional(&self.pool)
+                .await?,
+        )
+    }
+}
 
-            sqlx::query("INSERT INTO chunks_fts (chunk_id, document_id, text) VALUES (?, ?, ?)")
+/// IDs of the chunks stored for `doc_id`, in order, if they match `chunks`
+/// in text hash, metadata, and chunker fingerprint.
+async fn stored_chunk_ids_if_same(
+    conn: &mut SqliteConnection,
+    doc_id: &str,
+    chunks: &[Chunk],
+    chunker: &str,
+) -> Result<Option<Vec<String>>> {
+    let rows = sqlx::query(
+        "SELECT id, chunk_index, hash, metadata_json, chunker FROM chunks \
+         WHERE document_id = ? ORDER BY chunk_index",
+    )
+    .bind(doc_id)
+    .fetch_all(&mut *conn)
+    .await?;
+    if rows.len() != chunks.len() {
+        return Ok(None);
+    }
+    let mut ids = Vec::with_capacity(rows.len());
+    for (row, chunk) in rows.iter().zip(chunks) {
+        let metadata: String = row.get("metadata_json");
+        let same = row.get::<i64, _>("chunk_index") == chunk.chunk_index
+            && row.get::<String, _>("hash") == chunk.hash
+            && row.get::<Option<String>, _>("chunker").as_deref() == Some(chunker)
+            && parse_chunk_metadata(&metadata) == chunk.metadata;
+        if !same {
+            return Ok(None);
+        }
+        ids.push(row.get("id"));
+    }
+    Ok(Some(ids))
+}
+
+/// Replace the chunks, FTS rows, embeddings, and vectors of `doc_id`.
+async fn write_chunks(
+    conn: &mut SqliteConnection,
+    doc_id: &str,
+    chunks: &[Chunk],
+    vectors: Option<&[Vec<f32>]>,
+) -> Result<()> {
+    sqlx::query(
+        "DELETE FROM chunk_vectors WHERE chunk_id IN (SELECT id FROM chunks WHERE document_id = ?)",
+    )
+    .bind(doc_id)
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query(
+        "DELETE FROM embeddings WHERE chunk_id IN (SELECT id FROM chunks WHERE document_id = ?)",
+    )
+    .bind(doc_id)
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query("DELETE FROM chunks_fts WHERE document_id = ?")
+        .bind(doc_id)
+        .execute(&mut *conn)
+        .await?;
+
+    sqlx::query("DELETE FROM chunks WHERE document_id = ?")
+        .bind(doc_id)
+        .execute(&mut *conn)
+        .await?;
+
+    for (i, chunk) in chunks.iter().enumerate() {
+        sqlx::query(
+            "INSERT INTO chunks (id, document_id, chunk_index, text, hash, metadata_json) VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&chunk.id)
+        .bind(&chunk.document_id)
+        .bind(chunk.chunk_index)
+        .bind(&chunk.text)
+        .bind(&chunk.hash)
+        .bind(serde_json::Value::Object(chunk.metadata.clone()).to_string())
+        .execute(&mut *conn)
+        .await?;
+
+        sqlx::query(
+            "INSERT INTO chunks_fts (chunk_id, document_id, text, title, summary) VALUES (?, ?, ?, \
+             (SELECT title FROM documents WHERE id = ?), (SELECT summary FROM documents WHERE id = ?))",
+        )
+        .bind(&chunk.id)
+        .bind(&chunk.document_id)
+        .bind(&chunk.text)
+        .bind(&chunk.document_id)
+        .bind(&chunk.document_id)
+        .execute(&mut *conn)
+        .await?;
+
+        if let Some(vecs) = vectors {
+            if let Some(vec) = vecs.get(i) {
+                let blob = vec_to_blob(vec);
+                sqlx::query(
+                    r#"
+                    INSERT INTO chunk_vectors (chunk_id, document_id, embedding)
+                    VALUES (?, ?, ?)
+                    ON CONFLICT(chunk_id) DO UPDATE SET
+                        document_id = excluded.document_id,
+                        embedding = excluded.embedding
+                    "#,
+                )
                 .bind(&chunk.id)
-                .bind(&chunk.document_id)
-                .bind(&chunk.text)
-                .execute(&mut *tx)
+                .bind(doc_id)
+                .bind(&blob)
+                .execute(&mut *conn)
                 .await?;
+            }
+        }
+    }
+    Ok(())
+}
 
-            if let Some(vecs) = vectors {
-                if let Some(vec) = vecs.get(i) {
-                    let blob = vec_to_blob(vec);
-                    sqlx::query(
-                        r#"
-                        INSERT INTO chunk_vectors (chunk_id, document_id, embedding)
-                        VALUES (?, ?, ?)
-                        ON CONFLICT(chunk_id) DO UPDATE SET
-                            document_id = excluded.document_id,
-                            embedding = excluded.embedding
-                        "#,
-                    )
-                    .bind(&chunk.id)
-                    .bind(doc_id)
-                    .bind(&blob)
-                    .execute(&mut *tx)
-                    .await?;
-                }
+/// Split user text into FTS terms: words, and for text in double quotes one
+/// phrase term (`"exact phrase"`) that matches the words in order.
+fn fts_terms_from_user_text(query: &str) -> Vec<String> {
+    let words = |text: &str| -> Vec<String> {
+        text.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .filter(|term| !term.is_empty())
+            .map(str::to_string)
+            .collect()
+    };
+    let mut terms = Vec::new();
+    for (i, part) in query.split('"').enumerate() {
+        let part_words = words(part);
+        if i % 2 == 1 && part_words.len() > 1 {
+            terms.push(format!("\"{}\"", part_words.join(" ")));
+        } else {
+            terms.extend(part_words);
+        }
+    }
+    terms
+}
+
+/// Maximum indexed variants OR-ed into a fuzzy query term.
+const MAX_FUZZY_VARIANTS: usize = 8;
+
+/// Edit distance allowed for a query term of `len` characters.
+fn fuzzy_max_distance(len: usize) -> usize {
+    match len {
+        0..=3 => 0,
+        4..=6 => 1,
+        _ => 2,
+    }
+}
+
+/// Levenshtein distance, with adjacent transpositions counted as one edit.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut prev_prev = vec![0; b.len() + 1];
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        let mut cur = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            cur[j] = (prev[j] + 1).min(cur[j - 1] + 1).min(prev[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                cur[j] = cur[j].min(prev_prev[j - 2] + 1);
             }
         }
+        prev_prev = std::mem::replace(&mut prev, cur);
+    }
+    prev[b.len()]
+}
+
+/// Quote a term as an FTS5 string literal.
+fn fts_quote(term: &str) -> String {
+    format!("\"{}\"", term.replace('"', "\"\""))
+}
+
+fn format_ts_iso(ts: i64) -> String {
+    chrono::DateTime::from_timestamp(ts, 0)
+        .map(|dt| dt.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+        .unwrap_or_else(|| ts.to_string())
+}
 
+/// Parse a chunk's `metadata_json` column, treating invalid JSON as empty.
+fn parse_chunk_metadata(json: &str) -> serde_json::Map<String, serde_json::Value> {
+    serde_json::from_str(json).unwrap_or_default()
+}
+
+#[async_trait]
+impl Store for SqliteStore {
+    async fn upsert_document(&self, doc: &Document) -> Result<String> {
+        Ok(sqlx::query_scalar(UPSERT_DOCUMENT_SQL)
+            .bind(&doc.id)
+            .bind(&doc.source)
+            .bind(&doc.source_id)
+            .bind(&doc.source_url)
+            .bind(&doc.title)
+            .bind(&doc.author)
+            .bind(doc.created_at)
+            .bind(doc.updated_at)
+            .bind(&doc.content_type)
+            .bind(&doc.body)
+            .bind(&doc.metadata_json)
+            .bind(&doc.raw_json)
+            .bind(&doc.dedup_hash)
+            .bind(&doc.parent_source_id)
+            .bind(&doc.summary)
+            .fetch_one(&self.pool)
+            .await?)
+    }
+
+    async fn replace_chunks(
+        &self,
+        doc_id: &str,
+        chunks: &[Chunk],
+        vectors: Option<&[Vec<f32>]>,
+    ) -> Result<()> {
+        let mut tx = db::begin_write(&self.pool).await?;
+        write_chunks(&mut tx, doc_id, chunks, vectors).await?;
         tx.commit().await?;
         Ok(())
     }
@@ -214,7 +766,7 @@ impl Store for SqliteStore {
 
     async fn get_document(&self, id: &str) -> Result<Option<DocumentResponse>> {
         let doc_row = sqlx::query(
-            "SELECT id, source, source_id, source_url, title, author, created_at, updated_at, content_type, body, metadata_json FROM documents WHERE id = ?",
+            "SELECT id, source, source_id, source_url, title, author, created_at, updated_at, content_type, body, summary, metadata_json, parent_source_id FROM documents WHERE id = ?",
         )
         .bind(id)
         .fetch_optional(&self.pool)
@@ -233,7 +785,7 @@ impl Store for SqliteStore {
             serde_json::from_str(&metadata_json).unwrap_or(serde_json::json!({}));
 
         let chunk_rows = sqlx::query(
-            "SELECT chunk_index, text FROM chunks WHERE document_id = ? ORDER BY chunk_index ASC",
+            "SELECT chunk_index, text, metadata_json FROM chunks WHERE document_id = ? ORDER BY chunk_index ASC",
         )
         .bind(id)
         .fetch_all(&self.pool)
@@ -244,13 +796,36 @@ impl Store for SqliteStore {
             .map(|row| ChunkResponse {
                 index: row.get("chunk_index"),
                 text: row.get("text"),
+                metadata: parse_chunk_metadata(row.get("metadata_json")),
             })
             .collect();
 
+        let source: String = doc_row.get("source");
+        let source_id: String = doc_row.get("source_id");
+        let parent_source_id: Option<String> = doc_row.get("parent_source_id");
+        let parent_id = self
+            .resolve_parent_id(&source, parent_source_id.as_deref())
+            .await?;
+
+        let children: Vec<DocumentChild> = sqlx::query(
+            "SELECT id, source_id, title FROM documents WHERE source = ? AND parent_source_id = ? ORDER BY source_id",
+        )
+        .bind(&source)
+        .bind(&source_id)
+        .fetch_all(&self.pool)
+        .await?
+        .iter()
+        .map(|row| DocumentChild {
+            id: row.get("id"),
+            source_id: row.get("source_id"),
+            title: row.get("title"),
+        })
+        .collect();
+
         Ok(Some(DocumentResponse {
             id: doc_row.get("id"),
-            source: doc_row.get("source"),
-            source_id: doc_row.get("source_id"),
+            source,
+            source_id,
             source_url: doc_row.get("source_url"),
             title: doc_row.get("title"),
             author: doc_row.get("author"),
@@ -258,29 +833,59 @@ impl Store for SqliteStore {
             updated_at: format_ts_iso(updated_at),
             content_type: doc_row.get("content_type"),
             body: doc_row.get("body"),
+            summary: doc_row.get("summary"),
             metadata,
             chunks,
+            parent_id,
+            children,
         }))
     }
 
     async fn get_document_metadata(&self, id: &str) -> Result<Option<DocumentMetadata>> {
         let row = sqlx::query(
-            "SELECT id, title, source, source_id, updated_at, source_url FROM documents WHERE id = ?",
+            "SELECT id, title, source, source_id, updated_at, source_url, content_type, parent_source_id, summary, \
+             CASE WHEN json_valid(metadata_json) THEN json_extract(metadata_json, '$.doc_type') END AS doc_type \
+             FROM documents WHERE id = ?",
         )
         .bind(id)
         .fetch_optional(&self.pool)
         .await?;
 
-        Ok(row.map(|r| DocumentMetadata {
+        let Some(r) = row else {
+            return Ok(None);
+        };
+        let source: String = r.get("source");
+        let parent_source_id: Option<String> = r.get("parent_source_id");
+        let parent_id = self
+            .resolve_parent_id(&source, parent_source_id.as_deref())
+            .await?;
+
+        Ok(Some(DocumentMetadata {
             id: r.get("id"),
             title: r.get("title"),
-            source: r.get("source"),
+            source,
             source_id: r.get("source_id"),
             source_url: r.get("source_url"),
+            content_type: r.get("content_type"),
             updated_at: r.get("updated_at"),
+            parent_id,
+            summary: r.get("summary"),
+            doc_type: r.get("doc_type"),
         }))
     }
 
+    async fn get_chunk_metadata(
+        &self,
+        chunk_id: &str,
+    ) -> Result<Option<serde_json::Map<String, serde_json::Value>>> {
+        let row = sqlx::query("SELECT metadata_json FROM chunks WHERE id = ?")
+            .bind(chunk_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(|r| parse_chunk_metadata(r.get("metadata_json"))))
+    }
+
     async fn keyword_search(
         &self,
         query: &str,
@@ -288,14 +893,25 @@ impl Store for SqliteStore {
         _source: Option<&str>,
         _since: Option<&str>,
     ) -> Result<Vec<ChunkCandidate>> {
-        let fts_query = fts_query_from_user_text(query);
+        let fts_query = if self.fuzzy {
+            self.fuzzy_fts_query(query).await?
+        } else {
+            self.query_terms(query)
+        };
         if fts_query.is_empty() {
             return Ok(Vec::new());
         }
 
+        let rescore = !self.bm25.is_builtin();
+        let fetch = if rescore {
+            limit.saturating_mul(bm25::RESCORE_POOL)
+        } else {
+            limit
+        };
         let rows = sqlx::query(
             r#"
-            SELECT chunk_id, document_id, rank,
+            SELECT rowid, chunk_id, document_id,
+                   bm25(chunks_fts, 0.0, 0.0, ?, ?, ?) AS rank,
                    snippet(chunks_fts, 2, '>>>', '<<<', '...', 48) AS snippet
             FROM chunks_fts
             WHERE chunks_fts MATCH ?
@@ -303,12 +919,15 @@ impl Store for SqliteStore {
             LIMIT ?
             "#,
         )
-        .bind(fts_query)
-        .bind(limit)
+        .bind(self.weights.text)
+        .bind(self.weights.title)
+        .bind(self.weights.summary)
+        .bind(&fts_query)
+        .bind(fetch)
         .fetch_all(&self.pool)
         .await?;
 
-        let candidates: Vec<ChunkCandidate> = rows
+        let mut candidates: Vec<ChunkCandidate> = rows
             .iter()
             .map(|row| {
                 let rank: f64 = row.get("rank");
@@ -321,6 +940,33 @@ impl Store for SqliteStore {
             })
             .collect();
 
+        if rescore {
+            let rowids: Vec<i64> = rows.iter().map(|row| row.get("rowid")).collect();
+            let weights = [
+                (2, self.weights.text),
+                (3, self.weights.title),
+                (4, self.weights.summary),
+            ];
+            let scores = bm25::scores(
+                &self.pool,
+                bm25::CHUNKS,
+                &weights,
+                self.bm25,
+                &fts_query,
+                &rowids,
+            )
+            .await?;
+            for (candidate, rowid) in candidates.iter_mut().zip(&rowids) {
+                candidate.raw_score = scores.get(rowid).copied().unwrap_or(0.0);
+            }
+            candidates.sort_by(|a, b| {
+                b.raw_score
+                    .partial_cmp(&a.raw_score)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            });
+            candidates.truncate(limit.max(0) as usize);
+        }
+
         Ok(candidates)
     }
 
@@ -331,16 +977,34 @@ impl Store for SqliteStore {
         _source: Option<&str>,
         _since: Option<&str>,
     ) -> Result<Vec<ChunkCandidate>> {
-        let rows = sqlx::query(
-            r#"
-            SELECT cv.chunk_id, cv.document_id, cv.embedding,
-                   COALESCE(substr(c.text, 1, 240), '') AS snippet
-            FROM chunk_vectors cv
-            JOIN chunks c ON c.id = cv.chunk_id
-            "#,
-        )
-        .fetch_all(&self.pool)
-        .await?;
+        let routed = if self.route_documents > 0 {
+            self.routed_documents(query_vec).await?
+        } else {
+            None
+        };
+
+        let mut sql = String::from(
+            "SELECT cv.chunk_id, cv.document_id, cv.embedding, \
+                    COALESCE(substr(c.text, 1, 240), '') AS snippet \
+             FROM chunk_vectors cv \
+             JOIN chunks c ON c.id = cv.chunk_id",
+        );
+        if let Some(ids) = &routed {
+            // Documents without a vector yet are always scanned.
+            sql.push_str(&format!(
+                " WHERE cv.document_id IN ({}) \
+                 OR cv.document_id NOT IN (SELECT document_id FROM document_vectors WHERE dims = ?)",
+                vec!["?"; ids.len()].join(", ")
+            ));
+        }
+        let mut query = sqlx::query(&sql);
+        if let Some(ids) = &routed {
+            for id in ids {
+                query = query.bind(id);
+            }
+            query = query.bind(query_vec.len() as i64);
+        }
+        let rows = query.fetch_all(&self.pool).await?;
 
         let mut candidates: Vec<ChunkCandidate> = rows
             .iter()
@@ -366,4 +1030,70 @@ impl Store for SqliteStore {
 
         Ok(candidates)
     }
+
+    async fn unembedded_chunks(&self, chunk_ids: &[String]) -> Result<HashSet<String>> {
+        if chunk_ids.is_empty() {
+            return Ok(HashSet::new());
+        }
+        let placeholders = vec!["?"; chunk_ids.len()].join(", ");
+        let sql = format!(
+            "SELECT c.id FROM chunks c LEFT JOIN chunk_vectors cv ON cv.chunk_id = c.id \
+             WHERE c.id IN ({}) AND cv.chunk_id IS NULL",
+            placeholders
+        );
+        let mut query = sqlx::query_scalar::<_, String>(&sql);
+        for id in chunk_ids {
+            query = query.bind(id);
+        }
+        Ok(query.fetch_all(&self.pool).await?.into_iter().collect())
+    }
+
+    async fn chunk_indexes(&self, chunk_ids: &[String]) -> Result<HashMap<String, i64>> {
+        if chunk_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let placeholders = vec!["?"; chunk_ids.len()].join(", ");
+        let sql = format!(
+            "SELECT id, chunk_index FROM chunks WHERE id IN ({})",
+            placeholders
+        );
+        let mut query = sqlx::query_as::<_, (String, i64)>(&sql);
+        for id in chunk_ids {
+            query = query.bind(id);
+        }
+        Ok(query.fetch_all(&self.pool).await?.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edit_distance_counts_typos_and_transpositions() {
+        assert_eq!(edit_distance("deploy", "deploy"), 0);
+        assert_eq!(edit_distance("deploy", "deplyo"), 1);
+        assert_eq!(edit_distance("runbook", "runbooks"), 1);
+        assert_eq!(edit_distance("kubernetes", "kubernets"), 1);
+        assert_eq!(edit_distance("auth", "oath"), 2);
+    }
+
+    #[test]
+    fn short_terms_are_not_expanded() {
+        assert_eq!(fuzzy_max_distance(3), 0);
+        assert_eq!(fuzzy_max_distance(5), 1);
+        assert_eq!(fuzzy_max_distance(9), 2);
+    }
+
+    #[test]
+    fn quoted_text_becomes_a_phrase_term() {
+        assert_eq!(
+            fts_terms_from_user_text(r#"deploy "blue-green rollout" now"#),
+            vec!["deploy", "\"blue green rollout\"", "now"]
+        );
+        assert_eq!(
+            fts_terms_from_user_text(r#""single" "unterminated phrase"#),
+            vec!["single", "\"unterminated phrase\""]
+        );
+    }
 }
@@ -1,39 +1,454 @@
 //! Database statistics and health overview.
 //!
 //! Provides a quick summary of what's indexed: document counts, chunk counts,
-//! embedding coverage, and per-source breakdowns. Used by `ctx stats` to give
-//! confidence that syncs and embeddings are working as expected.
+//! embedding coverage and freshness, and per-source breakdowns. Used by
+//! `ctx stats` to give confidence that syncs and embeddings are working as
+//! expected.
+//!
+//! [`collection_overview`] returns the same numbers plus top directories and
+//! recent additions as a single JSON-serializable value. It backs the
+//! built-in `overview` tool, giving agents a map of the corpus in one call.
+//!
+//! # Trends and index health
+//!
+//! Every sync records the day's totals in `stats_snapshots` (one row per UTC
+//! day, overwritten by later syncs that day). [`stats_report`] compares the
+//! current totals with the snapshots from 1, 7, and 30 days ago and adds
+//! index health: embedding coverage, average chunk length, FTS5 and vector
+//! storage size, days since each source last synced, and document counts
+//! per classified `doc_type` (see [`crate::classify`]). `ctx stats --json`
+//! prints the report as JSON.
+
+use std::path::Path;
 
 use anyhow::Result;
+use chrono::{Duration, NaiveDate, Utc};
+use serde::Serialize;
+use sqlx::{Row, SqlitePool};
 
-use crate::app_store::{AppStore, SqliteAppStore};
+use crate::app_store::{
+    AppStore, DirectoryCount, EmbeddingFreshness, RecentDocument, SourceStats, SqliteAppStore,
+};
+use crate::chunk;
 use crate::config::Config;
+use crate::embedding;
+use crate::migrate;
+use crate::query_cache;
+use crate::storage;
+use crate::vector_index;
+
+/// Growth windows reported by [`stats_report`], in days.
+const GROWTH_WINDOWS: [i64; 3] = [1, 7, 30];
+/// Snapshots included in the report's history.
+const HISTORY_DAYS: i64 = 30;
+
+/// Whole-corpus summary returned by the `overview` tool.
+#[derive(Debug, Clone, Serialize)]
+pub struct Overview {
+    pub total_docs: i64,
+    pub total_chunks: i64,
+    pub total_embedded: i64,
+    /// Percentage of chunks with an embedding (0–100).
+    pub embedding_coverage: i64,
+    pub db_size_bytes: u64,
+    pub sources: Vec<SourceStats>,
+    /// Directories (by `source_id` prefix) holding the most documents.
+    pub top_directories: Vec<DirectoryCount>,
+    /// Most recently updated documents.
+    pub recent: Vec<RecentDocument>,
+}
+
+/// Build a corpus overview with up to `limit` top directories and recent documents.
+pub async fn collection_overview(config: &Config, limit: usize) -> Result<Overview> {
+    let store = SqliteAppStore::connect(config).await?;
+    let stats = store.stats().await?;
+    let top_directories = store.top_directories(limit).await?;
+    let recent = store.recent_documents(limit).await?;
+    store.close().await;
+
+    Ok(Overview {
+        total_docs: stats.total_docs,
+        total_chunks: stats.total_chunks,
+        total_embedded: stats.total_embedded,
+        embedding_coverage: coverage_percent(stats.total_embedded, stats.total_chunks),
+        db_size_bytes: stats.db_size_bytes,
+        sources: stats.sources,
+        top_directories,
+        recent,
+    })
+}
+
+impl Overview {
+    /// Drop every source for which `keep` is false and recompute the totals
+    /// from the remaining sources. The database size is left as is.
+    pub fn retain_sources(&mut self, keep: impl Fn(&str) -> bool) {
+        self.sources.retain(|s| keep(&s.source));
+        self.top_directories.retain(|d| keep(&d.source));
+        self.recent.retain(|d| keep(&d.source));
+        self.total_docs = self.sources.iter().map(|s| s.doc_count).sum();
+        self.total_chunks = self.sources.iter().map(|s| s.chunk_count).sum();
+        self.total_embedded = self.sources.iter().map(|s| s.embedded_count).sum();
+        self.embedding_coverage = coverage_percent(self.total_embedded, self.total_chunks);
+    }
+}
+
+/// Corpus totals for one day, recorded by sync.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StatsSnapshot {
+    /// UTC date, `YYYY-MM-DD`.
+    pub day: String,
+    pub total_docs: i64,
+    pub total_chunks: i64,
+    pub total_embedded: i64,
+    pub db_size_bytes: u64,
+}
+
+/// Change in totals since the snapshot taken `days` days ago (or the newest
+/// one before that).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Growth {
+    pub days: i64,
+    /// Day of the snapshot the current totals are compared with.
+    pub since: String,
+    pub docs: i64,
+    pub chunks: i64,
+    pub embedded: i64,
+    pub db_size_bytes: i64,
+}
+
+/// Per-source counts plus how long ago the source last synced.
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceHealth {
+    #[serde(flatten)]
+    pub stats: SourceStats,
+    /// Whole days since the last sync; `None` if it never synced.
+    pub days_since_sync: Option<i64>,
+}
+
+/// Documents of one classified type (see [`crate::classify`]).
+#[derive(Debug, Clone, Serialize)]
+pub struct DocTypeCount {
+    /// `None` for documents without a `doc_type`.
+    pub doc_type: Option<String>,
+    pub count: i64,
+}
+
+/// Everything `ctx stats` reports, serialized by `ctx stats --json`.
+#[derive(Debug, Clone, Serialize)]
+pub struct StatsReport {
+    pub database: String,
+    pub db_size_bytes: u64,
+    pub total_docs: i64,
+    pub total_chunks: i64,
+    pub total_embedded: i64,
+    /// Percentage of chunks with an embedding, one decimal place.
+    pub embedding_coverage_percent: f64,
+    /// Mean chunk length in characters.
+    pub avg_chunk_chars: f64,
+    /// FTS5 index size including shadow tables; `None` when SQLite was
+    /// built without the `dbstat` table.
+    pub fts_index_bytes: Option<u64>,
+    /// `chunk_vectors` and `embeddings` tables plus any vector-index
+    /// sidecar; `None` when SQLite was built without the `dbstat` table.
+    pub vector_store_bytes: Option<u64>,
+    /// Embedding freshness against the configured model; `None` when
+    /// embeddings are disabled or the provider cannot be built.
+    pub freshness: Option<EmbeddingFreshness>,
+    pub sources: Vec<SourceHealth>,
+    /// Documents per `doc_type`, most common first; empty until a sync
+    /// with `[classify] enabled = true` has tagged at least one document.
+    pub doc_types: Vec<DocTypeCount>,
+    /// Growth over the last 1, 7, and 30 days, for windows with a snapshot.
+    pub growth: Vec<Growth>,
+    /// Daily snapshots from the last 30 days, oldest first.
+    pub history: Vec<StatsSnapshot>,
+}
+
+/// Record today's totals in `stats_snapshots`. Called at the end of sync.
+pub async fn record_snapshot(store: &SqliteAppStore) -> Result<()> {
+    record_snapshot_on(store, Utc::now().date_naive()).await
+}
+
+/// Record the store's current totals as the snapshot for `day`.
+pub async fn record_snapshot_on(store: &SqliteAppStore, day: NaiveDate) -> Result<()> {
+    let stats = store.stats().await?;
+    migrate::create_stats_snapshots_table(store.pool()).await?;
+    sqlx::query(
+        r#"
+        INSERT INTO stats_snapshots
+            (day, recorded_at, total_docs, total_chunks, total_embedded, db_size_bytes)
+        VALUES (?, ?, ?, ?, ?, ?)
+        ON CONFLICT(day) DO UPDATE SET
+            recorded_at = excluded.recorded_at,
+            total_docs = excluded.total_docs,
+            total_chunks = excluded.total_chunks,
+            total_embedded = excluded.total_embedded,
+            db_size_bytes = excluded.db_size_bytes
+        "#,
+    )
+    .bind(day.format("%Y-%m-%d").to_string())
+    .bind(Utc::now().timestamp())
+    .bind(stats.total_docs)
+    .bind(stats.total_chunks)
+    .bind(stats.total_embedded)
+    .bind(stats.db_size_bytes as i64)
+    .execute(store.pool())
+    .await?;
+    Ok(())
+}
 
-/// Run the stats command: query the database and print a summary.
-pub async fn run_stats(config: &Config) -> Result<()> {
+/// Snapshots recorded on or after `from`, oldest first.
+async fn snapshots_since(pool: &SqlitePool, from: NaiveDate) -> Result<Vec<StatsSnapshot>> {
+    migrate::create_stats_snapshots_table(pool).await?;
+    let rows = sqlx::query(
+        "SELECT day, total_docs, total_chunks, total_embedded, db_size_bytes \
+         FROM stats_snapshots WHERE day >= ? ORDER BY day",
+    )
+    .bind(from.format("%Y-%m-%d").to_string())
+    .fetch_all(pool)
+    .await?;
+    Ok(rows
+        .iter()
+        .map(|row| StatsSnapshot {
+            day: row.get("day"),
+            total_docs: row.get("total_docs"),
+            total_chunks: row.get("total_chunks"),
+            total_embedded: row.get("total_embedded"),
+            db_size_bytes: row.get::<i64, _>("db_size_bytes") as u64,
+        })
+        .collect())
+}
+
+/// The newest snapshot taken on or before `day`.
+async fn snapshot_at(pool: &SqlitePool, day: NaiveDate) -> Result<Option<StatsSnapshot>> {
+    let row = sqlx::query(
+        "SELECT day, total_docs, total_chunks, total_embedded, db_size_bytes \
+         FROM stats_snapshots WHERE day <= ? ORDER BY day DESC LIMIT 1",
+    )
+    .bind(day.format("%Y-%m-%d").to_string())
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.map(|row| StatsSnapshot {
+        day: row.get("day"),
+        total_docs: row.get("total_docs"),
+        total_chunks: row.get("total_chunks"),
+        total_embedded: row.get("total_embedded"),
+        db_size_bytes: row.get::<i64, _>("db_size_bytes") as u64,
+    }))
+}
+
+/// Total size of the files under `path` (0 if it does not exist).
+fn dir_size(path: &Path) -> u64 {
+    walkdir::WalkDir::new(path)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter_map(|e| e.metadata().ok())
+        .filter(|m| m.is_file())
+        .map(|m| m.len())
+        .sum()
+}
+
+/// Gather counts, index health, per-source staleness, and growth trends.
+pub async fn stats_report(config: &Config) -> Result<StatsReport> {
     let store = SqliteAppStore::connect(config).await?;
     let stats = store.stats().await?;
+    let pool = store.pool();
+
+    let freshness = match embedding::create_provider(&config.embedding) {
+        Ok(provider) if config.embedding.is_enabled() => Some(
+            store
+                .embedding_freshness(provider.model_name(), provider.dims())
+                .await?,
+        ),
+        _ => None,
+    };
+
+    let avg_chunk_chars: f64 =
+        sqlx::query_scalar("SELECT COALESCE(AVG(LENGTH(text)), 0.0) FROM chunks")
+            .fetch_one(pool)
+            .await?;
+
+    let tables = storage::table_sizes(pool).await.unwrap_or_default();
+    let table_bytes = |names: &[&str]| -> u64 {
+        tables
+            .iter()
+            .filter(|t| names.contains(&t.name.as_str()))
+            .map(|t| t.bytes)
+            .sum()
+    };
+    let (fts_index_bytes, vector_store_bytes) = if tables.is_empty() {
+        (None, None)
+    } else {
+        let sidecar = dir_size(&vector_index::resolve_vector_index_path(config));
+        (
+            Some(table_bytes(&["chunks_fts"])),
+            Some(table_bytes(&["chunk_vectors", "embeddings"]) + sidecar),
+        )
+    };
+
+    let now = Utc::now();
+    let today = now.date_naive();
+    let mut growth = Vec::new();
+    for days in GROWTH_WINDOWS {
+        let Some(base) = snapshot_at(pool, today - Duration::days(days)).await? else {
+            continue;
+        };
+        growth.push(Growth {
+            days,
+            since: base.day,
+            docs: stats.total_docs - base.total_docs,
+            chunks: stats.total_chunks - base.total_chunks,
+            embedded: stats.total_embedded - base.total_embedded,
+            db_size_bytes: stats.db_size_bytes as i64 - base.db_size_bytes as i64,
+        });
+    }
+    let history = snapshots_since(pool, today - Duration::days(HISTORY_DAYS)).await?;
+    let doc_types = doc_type_counts(pool).await?;
+    store.close().await;
+
+    let sources = stats
+        .sources
+        .into_iter()
+        .map(|s| SourceHealth {
+            days_since_sync: s
+                .last_sync_ts
+                .map(|ts| ((now.timestamp() - ts) / 86400).max(0)),
+            stats: s,
+        })
+        .collect();
+
+    let coverage = if stats.total_chunks > 0 {
+        (stats.total_embedded as f64 * 1000.0 / stats.total_chunks as f64).round() / 10.0
+    } else {
+        0.0
+    };
+
+    Ok(StatsReport {
+        database: config.db.path.display().to_string(),
+        db_size_bytes: stats.db_size_bytes,
+        total_docs: stats.total_docs,
+        total_chunks: stats.total_chunks,
+        total_embedded: stats.total_embedded,
+        embedding_coverage_percent: coverage,
+        avg_chunk_chars: (avg_chunk_chars * 10.0).round() / 10.0,
+        fts_index_bytes,
+        vector_store_bytes,
+        freshness,
+        sources,
+        doc_types,
+        growth,
+        history,
+    })
+}
+
+/// Documents per `doc_type` in `metadata_json`; empty when none is typed.
+async fn doc_type_counts(pool: &SqlitePool) -> Result<Vec<DocTypeCount>> {
+    let rows = sqlx::query(
+        "SELECT CASE WHEN json_valid(metadata_json) \
+         THEN json_extract(metadata_json, '$.doc_type') END AS doc_type, COUNT(*) AS n \
+         FROM documents GROUP BY 1 ORDER BY n DESC, doc_type",
+    )
+    .fetch_all(pool)
+    .await?;
+    let counts: Vec<DocTypeCount> = rows
+        .iter()
+        .map(|row| DocTypeCount {
+            doc_type: row.get("doc_type"),
+            count: row.get("n"),
+        })
+        .collect();
+    if counts.iter().all(|c| c.doc_type.is_none()) {
+        return Ok(Vec::new());
+    }
+    Ok(counts)
+}
+
+/// Run the stats command: query the database and print a summary, or the
+/// full [`StatsReport`] as JSON with `json`.
+pub async fn run_stats(config: &Config, json: bool) -> Result<()> {
+    let report = stats_report(config).await?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
 
     println!("Context Harness — Database Stats");
     println!("================================");
     println!();
-    println!("  Database:    {}", config.db.path.display());
-    println!("  Size:        {}", format_bytes(stats.db_size_bytes));
+    println!("  Database:    {}", report.database);
+    println!("  Size:        {}", format_bytes(report.db_size_bytes));
     println!();
-    println!("  Documents:   {}", stats.total_docs);
-    println!("  Chunks:      {}", stats.total_chunks);
+    println!("  Documents:   {}", report.total_docs);
+    println!("  Chunks:      {}", report.total_chunks);
     println!(
         "  Embedded:    {} / {} ({}%)",
-        stats.total_embedded,
-        stats.total_chunks,
-        if stats.total_chunks > 0 {
-            (stats.total_embedded * 100) / stats.total_chunks
-        } else {
-            0
-        }
+        report.total_embedded,
+        report.total_chunks,
+        coverage_percent(report.total_embedded, report.total_chunks)
     );
+    println!("  Avg chunk:   {:.0} chars", report.avg_chunk_chars);
+    if let Some(bytes) = report.fts_index_bytes {
+        println!("  FTS index:   {}", format_bytes(bytes));
+    }
+    if let Some(bytes) = report.vector_store_bytes {
+        println!("  Vectors:     {}", format_bytes(bytes));
+    }
+    for g in &report.growth {
+        println!(
+            "  Growth {:>2}d:  {:+} docs, {:+} chunks, {:+} embedded (since {})",
+            g.days, g.docs, g.chunks, g.embedded, g.since
+        );
+    }
 
-    if !stats.sources.is_empty() {
+    if config.embedding.is_enabled() {
+        // Freshness is measured against the configured model, dims, and
+        // chunking settings; it is absent if the provider can't be built here.
+        if let (Some(freshness), Ok(provider)) = (
+            report.freshness,
+            embedding::create_provider(&config.embedding),
+        ) {
+            println!(
+                "  Freshness:   {} fresh, {} stale, {} missing",
+                freshness.fresh,
+                freshness.stale(),
+                freshness.missing
+            );
+            if freshness.stale() > 0 {
+                println!(
+                    "               stale by reason: model {}, dims {}, chunking {}, text {}",
+                    freshness.stale_model,
+                    freshness.stale_dims,
+                    freshness.stale_chunker,
+                    freshness.stale_text
+                );
+            }
+            if freshness.resync > 0 {
+                println!(
+                    "               {} chunks from older chunking settings (run ctx sync)",
+                    freshness.resync
+                );
+            }
+            println!(
+                "  Fingerprint: {} ({} dims), chunker {}",
+                provider.model_name(),
+                provider.dims(),
+                chunk::config_fingerprint(&config.chunking)
+            );
+        }
+
+        let store = SqliteAppStore::connect(config).await?;
+        let cache = query_cache::cache_stats(store.pool()).await?;
+        store.close().await;
+        println!(
+            "  Query cache: {} entries, {} hits / {} misses ({}% hit rate)",
+            cache.entries,
+            cache.hits,
+            cache.misses,
+            cache.hit_rate()
+        );
+    }
+
+    if !report.sources.is_empty() {
         println!();
         println!("  By source:");
         println!(
@@ -42,7 +457,7 @@ pub async fn run_stats(config: &Config) -> Result<()> {
         );
         println!("  {}", "-".repeat(76));
 
-        for s in &stats.sources {
+        for s in report.sources.iter().map(|h| &h.stats) {
             let sync_display = match s.last_sync_ts {
                 Some(ts) => format_ts_relative(ts),
                 None => "never".to_string(),
@@ -54,14 +469,33 @@ pub async fn run_stats(config: &Config) -> Result<()> {
         }
     }
 
-    println!();
+    if !report.doc_types.is_empty() {
+        println!();
+        println!("  By type:");
+        for t in &report.doc_types {
+            println!(
+                "  {:<24} {:>6}",
+                t.doc_type.as_deref().unwrap_or("(untyped)"),
+                t.count
+            );
+        }
+    }
 
-    store.close().await;
+    println!();
     Ok(())
 }
 
+/// Integer percentage of embedded chunks.
+fn coverage_percent(embedded: i64, chunks: i64) -> i64 {
+    if chunks > 0 {
+        (embedded * 100) / chunks
+    } else {
+        0
+    }
+}
+
 /// Format a byte count as a human-readable string.
-fn format_bytes(bytes: u64) -> String {
+pub(crate) fn format_bytes(bytes: u64) -> String {
     if bytes < 1024 {
         format!("{} B", bytes)
     } else if bytes < 1024 * 1024 {
@@ -74,7 +508,7 @@ fn format_bytes(bytes: u64) -> String {
 }
 
 /// Format a Unix timestamp as a relative time string (e.g. "3 hours ago").
-fn format_ts_relative(ts: i64) -> String {
+pub fn format_ts_relative(ts: i64) -> String {
     let now = chrono::Utc::now().timestamp();
     let delta = now - ts;
 
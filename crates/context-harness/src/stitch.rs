@@ -0,0 +1,326 @@
+//! Stitched documents: `[[connectors.<type>.<name>.stitch]]`.
+//!
+//! A docs site split across dozens of small markdown files retrieves
+//! poorly: every page is a document of its own, and a hit on one carries
+//! none of the context of its neighbours. A stitch rule combines the items
+//! of a connector whose `source_id` matches one of its `include` globs into
+//! one logical document:
+//!
+//! - `group_by = "directory"` (default): the matching items of each
+//!   directory become one document, with `source_id` `"<directory>/"` and
+//!   the directory as its title.
+//! - `group_by = "rule"`: every matching item becomes part of one document,
+//!   with the rule's `name` as `source_id` and title.
+//!
+//! ```toml
+//! [[connectors.filesystem.docs.stitch]]
+//! include = ["guides/**/*.md"]
+//! ```
+//!
+//! The stitched body is the bodies of its files in `source_id` order. It is
+//! chunked file by file, so no chunk spans two files, and every chunk
+//! records the file it came from under the `section_source_id` and
+//! `section_source_url` chunk metadata keys. Search results report that
+//! file, and `context.assemble` cites it; `ctx get` returns the whole
+//! stitched document. An item matching several rules joins the first.
+//!
+//! # Storage
+//!
+//! Items matching a rule are kept in the `stitch_members` table, after
+//! extraction and redaction, instead of being written as documents. Once
+//! a connector's items are ingested, every stitched document with a new or
+//! changed member is rebuilt from the stored members, so an incremental
+//! sync that delivers one changed file still writes the whole document.
+//! A file that had a document of its own loses it when a rule starts
+//! matching it. Deleting a file (see [`crate::ingest::delete_source_items`])
+//! rebuilds its stitched document, and deleting a stitched document
+//! deletes its members.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use sqlx::{Row, SqlitePool};
+
+use crate::config::{Config, StitchRule};
+use crate::models::SourceItem;
+
+/// The compiled `stitch` rules of one connector.
+pub struct Stitcher {
+    rules: Vec<(GlobSet, StitchRule)>,
+}
+
+impl Stitcher {
+    /// The stitcher for the rules of `source_label`, or `None` when it has
+    /// none.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an `include` glob is invalid.
+    pub fn from_config(config: &Config, source_label: &str) -> Result<Option<Self>> {
+        let rules = config.connectors.stitch_rules(source_label);
+        if rules.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Self::new(rules)?))
+    }
+
+    fn new(rules: &[StitchRule]) -> Result<Self> {
+        let mut compiled = Vec::with_capacity(rules.len());
+        for rule in rules {
+            let mut globs = GlobSetBuilder::new();
+            for glob in &rule.include {
+                globs.add(Glob::new(glob)?);
+            }
+            compiled.push((globs.build()?, rule.clone()));
+        }
+        Ok(Self { rules: compiled })
+    }
+
+    /// The `source_id` of the stitched document an item with `source_id`
+    /// belongs to, or `None` when no rule matches it.
+    pub fn group(&self, source_id: &str) -> Option<String> {
+        let (_, rule) = self
+            .rules
+            .iter()
+            .find(|(globs, _)| globs.is_match(source_id))?;
+        match rule.group_by.as_str() {
+            "rule" => rule.name.clone(),
+            _ => Some(format!("{}/", directory(source_id))),
+        }
+    }
+}
+
+/// Parent directory of a path-like `source_id` (`"guides/deploy/canary.md"`
+/// → `"guides/deploy"`); `"."` at the top level.
+fn directory(source_id: &str) -> &str {
+    match source_id.trim_end_matches('/').rsplit_once(['/', '\\']) {
+        Some((dir, _)) if !dir.is_empty() => dir,
+        _ => ".",
+    }
+}
+
+/// Keep `item` as a member of the stitched document `group`, replacing any
+/// earlier copy, and mark the group for rebuilding.
+pub async fn store_member(pool: &SqlitePool, group: &str, item: &SourceItem) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO stitch_members (source, source_id, group_id, source_url, title, author, \
+         created_at, updated_at, content_type, body, metadata_json, pending) \
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 1) \
+         ON CONFLICT(source, source_id) DO UPDATE SET \
+         group_id = excluded.group_id, source_url = excluded.source_url, \
+         title = excluded.title, author = excluded.author, created_at = excluded.created_at, \
+         updated_at = excluded.updated_at, content_type = excluded.content_type, \
+         body = excluded.body, metadata_json = excluded.metadata_json, pending = 1",
+    )
+    .bind(&item.source)
+    .bind(&item.source_id)
+    .bind(group)
+    .bind(&item.source_url)
+    .bind(&item.title)
+    .bind(&item.author)
+    .bind(item.created_at.timestamp())
+    .bind(item.updated_at.timestamp())
+    .bind(&item.content_type)
+    .bind(&item.body)
+    .bind(&item.metadata_json)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Stitched documents of `source` with members stored since they were
+/// last built, sorted.
+pub async fn pending_groups(pool: &SqlitePool, source: &str) -> Result<Vec<String>> {
+    Ok(sqlx::query_scalar(
+        "SELECT DISTINCT group_id FROM stitch_members \
+         WHERE source = ? AND pending = 1 ORDER BY group_id",
+    )
+    .bind(source)
+    .fetch_all(pool)
+    .await?)
+}
+
+/// Mark the stitched document `group` of `source` as built.
+pub async fn mark_built(pool: &SqlitePool, source: &str, group: &str) -> Result<()> {
+    sqlx::query("UPDATE stitch_members SET pending = 0 WHERE source = ? AND group_id = ?")
+        .bind(source)
+        .bind(group)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// The members of the stitched document `group` of `source`, in
+/// `source_id` order.
+pub async fn members(pool: &SqlitePool, source: &str, group: &str) -> Result<Vec<SourceItem>> {
+    let rows = sqlx::query(
+        "SELECT source_id, source_url, title, author, created_at, updated_at, content_type, \
+         body, metadata_json FROM stitch_members \
+         WHERE source = ? AND group_id = ? ORDER BY source_id",
+    )
+    .bind(source)
+    .bind(group)
+    .fetch_all(pool)
+    .await?;
+    let timestamp = |ts: i64| DateTime::<Utc>::from_timestamp(ts, 0).unwrap_or_default();
+    Ok(rows
+        .into_iter()
+        .map(|row| SourceItem {
+            source: source.to_string(),
+            source_id: row.get("source_id"),
+            source_url: row.get("source_url"),
+            title: row.get("title"),
+            author: row.get("author"),
+            created_at: timestamp(row.get("created_at")),
+            updated_at: timestamp(row.get("updated_at")),
+            content_type: row.get("content_type"),
+            body: row.get("body"),
+            metadata_json: row.get("metadata_json"),
+            raw_json: None,
+            raw_bytes: None,
+            parent_source_id: None,
+        })
+        .collect())
+}
+
+/// Remove the items `source_ids` of `source` from their stitched
+/// documents. Returns the stitched documents they left, sorted.
+pub async fn remove_members(
+    pool: &SqlitePool,
+    source: &str,
+    source_ids: &[String],
+) -> Result<Vec<String>> {
+    let mut groups = Vec::new();
+    for source_id in source_ids {
+        let group: Option<String> = sqlx::query_scalar(
+            "DELETE FROM stitch_members WHERE source = ? AND source_id = ? RETURNING group_id",
+        )
+        .bind(source)
+        .bind(source_id)
+        .fetch_optional(pool)
+        .await?;
+        groups.extend(group);
+    }
+    groups.sort();
+    groups.dedup();
+    Ok(groups)
+}
+
+/// The stitched document `group` of `source`, made of `members` in order.
+///
+/// Its body joins the members' bodies with blank lines; it was created
+/// with the oldest member and updated with the newest. The members'
+/// `source_id`s are listed in its metadata under `stitched_from`.
+pub fn stitched_item(source: &str, group: &str, members: &[SourceItem]) -> SourceItem {
+    let content_type = match members.first() {
+        Some(first) if members.iter().all(|m| m.content_type == first.content_type) => {
+            first.content_type.clone()
+        }
+        _ => "text/plain".to_string(),
+    };
+    let stitched_from: Vec<&str> = members.iter().map(|m| m.source_id.as_str()).collect();
+    SourceItem {
+        source: source.to_string(),
+        source_id: group.to_string(),
+        source_url: None,
+        title: Some(group.trim_end_matches('/').to_string()),
+        author: None,
+        created_at: members
+            .iter()
+            .map(|m| m.created_at)
+            .min()
+            .unwrap_or_default(),
+        updated_at: members
+            .iter()
+            .map(|m| m.updated_at)
+            .max()
+            .unwrap_or_default(),
+        content_type,
+        body: members
+            .iter()
+            .map(|m| m.body.as_str())
+            .collect::<Vec<_>>()
+            .join("\n\n"),
+        metadata_json: serde_json::json!({ "stitched_from": stitched_from }).to_string(),
+        raw_json: None,
+        raw_bytes: None,
+        parent_source_id: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(serde::Deserialize)]
+    struct Rules {
+        stitch: Vec<StitchRule>,
+    }
+
+    fn stitcher(rules: &str) -> Stitcher {
+        let rules: Rules = toml::from_str(rules).unwrap();
+        Stitcher::new(&rules.stitch).unwrap()
+    }
+
+    fn item(source_id: &str, body: &str, updated_at: i64) -> SourceItem {
+        SourceItem {
+            source: "filesystem:docs".to_string(),
+            source_id: source_id.to_string(),
+            source_url: None,
+            title: None,
+            author: None,
+            created_at: DateTime::from_timestamp(updated_at, 0).unwrap(),
+            updated_at: DateTime::from_timestamp(updated_at, 0).unwrap(),
+            content_type: "text/markdown".to_string(),
+            body: body.to_string(),
+            metadata_json: "{}".to_string(),
+            raw_json: None,
+            raw_bytes: None,
+            parent_source_id: None,
+        }
+    }
+
+    #[test]
+    fn items_are_grouped_by_directory_or_rule() {
+        let stitcher = stitcher(
+            r#"
+            [[stitch]]
+            include = ["adr/*.md"]
+            group_by = "rule"
+            name = "Architecture decisions"
+
+            [[stitch]]
+            include = ["guides/**/*.md", "*.md"]
+            "#,
+        );
+        assert_eq!(
+            stitcher.group("adr/0001-sqlite.md").as_deref(),
+            Some("Architecture decisions")
+        );
+        assert_eq!(
+            stitcher.group("guides/deploy/canary.md").as_deref(),
+            Some("guides/deploy/")
+        );
+        assert_eq!(stitcher.group("README.md").as_deref(), Some("./"));
+        assert_eq!(stitcher.group("notes/todo.txt"), None);
+    }
+
+    #[test]
+    fn stitched_item_joins_members_in_order() {
+        let members = [
+            item("guides/a.md", "# A\n\nFirst.", 100),
+            item("guides/b.md", "# B\n\nSecond.", 300),
+        ];
+        let stitched = stitched_item("filesystem:docs", "guides/", &members);
+        assert_eq!(stitched.source_id, "guides/");
+        assert_eq!(stitched.title.as_deref(), Some("guides"));
+        assert_eq!(stitched.body, "# A\n\nFirst.\n\n# B\n\nSecond.");
+        assert_eq!(stitched.content_type, "text/markdown");
+        assert_eq!(stitched.created_at.timestamp(), 100);
+        assert_eq!(stitched.updated_at.timestamp(), 300);
+        assert_eq!(
+            stitched.metadata_json,
+            r#"{"stitched_from":["guides/a.md","guides/b.md"]}"#
+        );
+    }
+}
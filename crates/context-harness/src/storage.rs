@@ -0,0 +1,453 @@
+//! Index size budgeting and eviction.
+//!
+//! With `[storage] max_size_mb` set, every `ctx sync` ends by checking the
+//! database size against the budget. If it is over, whole documents (with
+//! their chunks, FTS rows, and embeddings) are evicted and the file is
+//! vacuumed until it fits again.
+//!
+//! # Eviction Policies
+//!
+//! | Policy | Evicted first |
+//! |--------|---------------|
+//! | `lru` (default) | Documents least recently returned by `search` or `get` |
+//! | `oldest` | Documents with the oldest `updated_at` |
+//!
+//! For `lru`, the retrievals that search and get record in the
+//! `document_access` table decide (see [`record_retrievals`]). Documents
+//! that were never retrieved count as last used at their `updated_at`, so
+//! freshly synced documents are not the first to go.
+//!
+//! Evicted documents are not re-ingested by incremental syncs unless they
+//! change at the source; `ctx sync <connector> --full` brings them back.
+//!
+//! # Size Accounting
+//!
+//! The budget is compared against the pages SQLite is actually using
+//! (`page_count - freelist_count`), which excludes space that a vacuum
+//! would reclaim. Per-document sizes used to pick eviction candidates are
+//! estimates (body, raw payload, chunk text counted twice for the FTS
+//! copy, and vectors), scaled by the ratio of used pages to the estimate.
+//!
+//! `ctx db size` prints the same numbers with a per-table and per-source
+//! breakdown.
+
+use anyhow::Result;
+use serde::Serialize;
+use sqlx::{Row, SqlitePool};
+use std::collections::BTreeMap;
+
+use crate::config::Config;
+use crate::db;
+use crate::keyword_index;
+use crate::migrate;
+use crate::search_cache;
+use crate::sqlite_store::SqliteStore;
+use crate::stats::format_bytes;
+
+/// Estimated bytes stored for document `d`, including its chunks and vectors.
+const DOC_BYTES_SQL: &str = r#"
+    LENGTH(CAST(d.body AS BLOB))
+    + COALESCE(LENGTH(CAST(d.raw_json AS BLOB)), 0)
+    + LENGTH(CAST(d.metadata_json AS BLOB))
+    + COALESCE((SELECT SUM(2 * LENGTH(CAST(c.text AS BLOB)) + LENGTH(CAST(c.metadata_json AS BLOB)))
+                FROM chunks c WHERE c.document_id = d.id), 0)
+    + COALESCE((SELECT SUM(LENGTH(v.embedding))
+                FROM chunk_vectors v WHERE v.document_id = d.id), 0)
+"#;
+
+/// Upper bound on evict-and-vacuum rounds per sync.
+const MAX_EVICTION_ROUNDS: usize = 8;
+
+/// Candidates fetched per query while filling an eviction batch.
+const CANDIDATE_PAGE: i64 = 500;
+
+/// Space used by one table, its indexes, and (for FTS) its shadow tables.
+#[derive(Debug, Clone, Serialize)]
+pub struct TableSize {
+    pub name: String,
+    pub bytes: u64,
+}
+
+/// Estimated space used by one source's documents.
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceSize {
+    pub source: String,
+    pub docs: i64,
+    pub bytes: u64,
+}
+
+/// Result of [`size_report`], printed by `ctx db size`.
+#[derive(Debug, Clone, Serialize)]
+pub struct SizeReport {
+    /// Size of the main database file.
+    pub file_bytes: u64,
+    /// Size of the `-wal` file next to it.
+    pub wal_bytes: u64,
+    /// Bytes in pages holding data.
+    pub used_bytes: u64,
+    /// Bytes in free pages that `VACUUM` would release.
+    pub free_bytes: u64,
+    /// Per-table usage; empty when SQLite lacks the `dbstat` table.
+    pub tables: Vec<TableSize>,
+    pub sources: Vec<SourceSize>,
+    pub budget_bytes: Option<u64>,
+}
+
+/// Outcome of [`enforce_budget`].
+#[derive(Debug, Clone, Default)]
+pub struct EvictionReport {
+    pub budget_bytes: u64,
+    pub size_before: u64,
+    pub size_after: u64,
+    /// Evicted document counts by source.
+    pub evicted: BTreeMap<String, u64>,
+}
+
+impl EvictionReport {
+    /// Total number of evicted documents.
+    pub fn total_evicted(&self) -> u64 {
+        self.evicted.values().sum()
+    }
+}
+
+/// Bytes in the pages SQLite is currently using.
+pub async fn used_bytes(pool: &SqlitePool) -> Result<u64> {
+    let (used, _) = page_usage(pool).await?;
+    Ok(used)
+}
+
+/// `(used, free)` bytes according to the page counters.
+async fn page_usage(pool: &SqlitePool) -> Result<(u64, u64)> {
+    let page_size: i64 = sqlx::query_scalar("PRAGMA page_size")
+        .fetch_one(pool)
+        .await?;
+    let page_count: i64 = sqlx::query_scalar("PRAGMA page_count")
+        .fetch_one(pool)
+        .await?;
+    let freelist: i64 = sqlx::query_scalar("PRAGMA freelist_count")
+        .fetch_one(pool)
+        .await?;
+    let used = (page_count - freelist).max(0) * page_size;
+    Ok((used as u64, (freelist * page_size) as u64))
+}
+
+/// Measure the database: file sizes, page usage, and per-table and
+/// per-source breakdowns.
+pub async fn size_report(config: &Config) -> Result<SizeReport> {
+    let pool = db::connect(config).await?;
+    let (used_bytes, free_bytes) = page_usage(&pool).await?;
+    let tables = table_sizes(&pool).await.unwrap_or_default();
+    let sources = source_sizes(&pool).await?;
+    pool.close().await;
+
+    let file_len = |suffix: &str| {
+        let mut path = config.db.path.clone().into_os_string();
+        path.push(suffix);
+        std::fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+    };
+
+    Ok(SizeReport {
+        file_bytes: file_len(""),
+        wal_bytes: file_len("-wal"),
+        used_bytes,
+        free_bytes,
+        tables,
+        sources,
+        budget_bytes: config.storage.budget_bytes(),
+    })
+}
+
+/// Per-table usage from the `dbstat` virtual table, largest first.
+///
+/// Indexes are attributed to their table and FTS shadow tables
+/// (`chunks_fts_data`, …) to the FTS table.
+pub(crate) async fn table_sizes(pool: &SqlitePool) -> Result<Vec<TableSize>> {
+    let rows = sqlx::query(
+        r#"
+        SELECT COALESCE(m.tbl_name, s.name) AS tbl, SUM(s.pgsize) AS bytes
+        FROM dbstat s
+        LEFT JOIN sqlite_master m ON m.name = s.name
+        GROUP BY tbl
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut totals: BTreeMap<String, u64> = BTreeMap::new();
+    for row in rows {
+        let name: String = row.get("tbl");
+        let bytes: i64 = row.get("bytes");
+        let name = match name.strip_prefix("chunks_fts_") {
+            Some(_) => "chunks_fts".to_string(),
+            None => name,
+        };
+        *totals.entry(name).or_default() += bytes as u64;
+    }
+
+    let mut tables: Vec<TableSize> = totals
+        .into_iter()
+        .map(|(name, bytes)| TableSize { name, bytes })
+        .collect();
+    tables.sort_by(|a, b| b.bytes.cmp(&a.bytes).then(a.name.cmp(&b.name)));
+    Ok(tables)
+}
+
+/// Estimated bytes per source, largest first.
+async fn source_sizes(pool: &SqlitePool) -> Result<Vec<SourceSize>> {
+    let rows = sqlx::query(&format!(
+        "SELECT d.source, COUNT(*) AS docs, COALESCE(SUM({}), 0) AS bytes \
+         FROM documents d GROUP BY d.source ORDER BY bytes DESC, d.source",
+        DOC_BYTES_SQL
+    ))
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| SourceSize {
+            source: row.get("source"),
+            docs: row.get("docs"),
+            bytes: row.get::<i64, _>("bytes") as u64,
+        })
+        .collect())
+}
+
+/// Evict documents until the database fits `[storage] max_size_mb`.
+///
+/// Returns `None` when no budget is configured. Bumps the index generation
+/// when anything was evicted so cached search responses are dropped.
+pub async fn enforce_budget(config: &Config, pool: &SqlitePool) -> Result<Option<EvictionReport>> {
+    let Some(budget) = config.storage.budget_bytes() else {
+        return Ok(None);
+    };
+
+    let size_before = used_bytes(pool).await?;
+    let mut report = EvictionReport {
+        budget_bytes: budget,
+        size_before,
+        size_after: size_before,
+        evicted: BTreeMap::new(),
+    };
+    if size_before <= budget {
+        return Ok(Some(report));
+    }
+
+    migrate::create_document_access_table(pool).await?;
+
+    for _ in 0..MAX_EVICTION_ROUNDS {
+        if report.size_after <= budget {
+            break;
+        }
+        let excess = report.size_after - budget;
+        let batch =
+            pick_evictions(pool, &config.storage.eviction, excess, report.size_after).await?;
+        if batch.is_empty() {
+            break;
+        }
+
+        let ids: Vec<String> = batch.iter().map(|(id, _)| id.clone()).collect();
+        SqliteStore::new(pool.clone())
+            .delete_documents(&ids)
+            .await?;
+        if let Some(index) = keyword_index::configured_index(config)? {
+            index.mirror_deletes(&ids).await?;
+            index.mirror_refresh().await?;
+        }
+        for (_, source) in batch {
+            *report.evicted.entry(source).or_default() += 1;
+        }
+
+        sqlx::query("VACUUM").execute(pool).await?;
+        sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)")
+            .execute(pool)
+            .await?;
+        report.size_after = used_bytes(pool).await?;
+    }
+
+    if report.total_evicted() > 0 {
+        search_cache::bump_index_generation(pool).await?;
+    }
+    Ok(Some(report))
+}
+
+/// Choose `(document_id, source)` pairs whose estimated size covers `excess`.
+///
+/// Estimates are scaled by `used / total_estimate` so page overhead and
+/// indexes are accounted for.
+async fn pick_evictions(
+    pool: &SqlitePool,
+    policy: &str,
+    excess: u64,
+    used: u64,
+) -> Result<Vec<(String, String)>> {
+    let total_estimate: i64 = sqlx::query_scalar(&format!(
+        "SELECT COALESCE(SUM({}), 0) FROM documents d",
+        DOC_BYTES_SQL
+    ))
+    .fetch_one(pool)
+    .await?;
+    if total_estimate <= 0 {
+        return Ok(Vec::new());
+    }
+    let scale = used as f64 / total_estimate as f64;
+
+    let order = match policy {
+        "oldest" => "d.updated_at ASC",
+        _ => "COALESCE(a.last_retrieved_at, d.updated_at) ASC, d.updated_at ASC",
+    };
+
+    let mut picked = Vec::new();
+    let mut covered = 0f64;
+    let mut offset = 0i64;
+    loop {
+        let rows = sqlx::query(&format!(
+            "SELECT d.id, d.source, {} AS bytes FROM documents d \
+             LEFT JOIN document_access a ON a.document_id = d.id \
+             ORDER BY {}, d.id LIMIT ? OFFSET ?",
+            DOC_BYTES_SQL, order
+        ))
+        .bind(CANDIDATE_PAGE)
+        .bind(offset)
+        .fetch_all(pool)
+        .await?;
+        if rows.is_empty() {
+            return Ok(picked);
+        }
+        offset += rows.len() as i64;
+
+        for row in rows {
+            let bytes: i64 = row.get("bytes");
+            picked.push((row.get("id"), row.get("source")));
+            covered += bytes as f64 * scale;
+            if covered >= excess as f64 {
+                return Ok(picked);
+            }
+        }
+    }
+}
+
+/// Warning text when `used` has reached `[storage] warn_at_percent` of the budget.
+pub fn budget_warning(config: &Config, used: u64) -> Option<String> {
+    let budget = config.storage.budget_bytes()?;
+    let percent = used.saturating_mul(100) / budget;
+    if percent < config.storage.warn_at_percent as u64 {
+        return None;
+    }
+    Some(format!(
+        "database uses {} of the {} storage budget ({}%)",
+        format_bytes(used),
+        format_bytes(budget),
+        percent
+    ))
+}
+
+/// Record that documents were returned to a caller, for LRU eviction and
+/// [`crate::popularity`].
+///
+/// Active with `[retrieval] track_access` (the default) or a budget with
+/// `eviction = "lru"`. Best-effort: failures never affect the search or get
+/// that triggered them.
+pub async fn record_retrievals(config: &Config, pool: &SqlitePool, ids: &[&str]) {
+    let tracks = config.retrieval.track_access || config.storage.tracks_retrievals();
+    if !tracks || ids.is_empty() {
+        return;
+    }
+    let recorded = async {
+        let writer = db::writer_for(config, pool).await?;
+        let result = db::retry_busy(config.db.write_retries, || {
+            try_record_retrievals(&writer, ids)
+        })
+        .await;
+        if config.db.read_pool {
+            writer.close().await;
+        }
+        result
+    };
+    if let Err(e) = recorded.await {
+        tracing::warn!("could not record document access: {}", e);
+    }
+}
+
+async fn try_record_retrievals(pool: &SqlitePool, ids: &[&str]) -> Result<()> {
+    migrate::create_document_access_table(pool).await?;
+    let now = chrono::Utc::now().timestamp();
+    let mut tx = db::begin_write(pool).await?;
+    for id in ids {
+        sqlx::query(
+            r#"
+            INSERT INTO document_access (document_id, last_retrieved_at, retrievals)
+            VALUES (?, ?, 1)
+            ON CONFLICT(document_id) DO UPDATE SET
+                last_retrieved_at = excluded.last_retrieved_at,
+                retrievals = retrievals + 1
+            "#,
+        )
+        .bind(id)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Run `ctx db size`: print file usage, the budget, and breakdowns.
+pub async fn run_db_size(config: &Config) -> Result<()> {
+    let report = size_report(config).await?;
+
+    println!("Context Harness — Database Size");
+    println!("===============================");
+    println!();
+    println!("  Database:  {}", config.db.path.display());
+    println!(
+        "  File:      {} (+ {} WAL)",
+        format_bytes(report.file_bytes),
+        format_bytes(report.wal_bytes)
+    );
+    println!(
+        "  Used:      {} (+ {} in free pages)",
+        format_bytes(report.used_bytes),
+        format_bytes(report.free_bytes)
+    );
+    match report.budget_bytes {
+        Some(budget) => println!(
+            "  Budget:    {} ({}% used, eviction: {})",
+            format_bytes(budget),
+            report.used_bytes.saturating_mul(100) / budget,
+            config.storage.eviction
+        ),
+        None => println!("  Budget:    none (set [storage] max_size_mb)"),
+    }
+
+    if !report.tables.is_empty() {
+        println!();
+        println!("  By table:");
+        println!("  {:<28} {:>10}", "TABLE", "SIZE");
+        println!("  {}", "-".repeat(39));
+        for t in &report.tables {
+            println!("  {:<28} {:>10}", t.name, format_bytes(t.bytes));
+        }
+    }
+
+    if !report.sources.is_empty() {
+        println!();
+        println!("  By source (estimated):");
+        println!("  {:<28} {:>6} {:>10}", "SOURCE", "DOCS", "SIZE");
+        println!("  {}", "-".repeat(46));
+        for s in &report.sources {
+            println!(
+                "  {:<28} {:>6} {:>10}",
+                s.source,
+                s.docs,
+                format_bytes(s.bytes)
+            );
+        }
+    }
+
+    if let Some(warning) = budget_warning(config, report.used_bytes) {
+        println!();
+        println!("  Warning: {}", warning);
+    }
+    println!();
+    Ok(())
+}
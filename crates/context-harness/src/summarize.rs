@@ -0,0 +1,324 @@
+//! Ingest-time document summaries: `[summary]`.
+//!
+//! A search hit on a 50,000-character document tells an agent where to
+//! look but not what the document is; fetching the body to find out costs
+//! most of a context window. When enabled, each document whose body is at
+//! least `min_chars` long gets a summary of at most `max_chars`:
+//!
+//! - **Extractive** (default): the body is split into sentences, each scored
+//!   by how frequent its content words are across the whole document, with
+//!   a bonus for sentences near the start. The best sentences that fit are
+//!   kept in their original order.
+//! - **LLM** (`llm = true`): the `[llm]` model reads the document (cut to
+//!   `max_document_tokens`) and writes the summary. The summary is reused
+//!   while the document's `dedup_hash` is unchanged, so re-syncing does not
+//!   call the model again. A failed call is logged and falls back to the
+//!   extractive summary.
+//!
+//! The summary is stored in `documents.summary` and indexed in `chunks_fts`
+//! with every chunk of its document, weighted by `[retrieval.weights]
+//! summary`. Search results and `ctx get` return it as `summary`. It is not
+//! embedded.
+//!
+//! Shorter documents have no summary. Changing the settings affects
+//! documents as they are next written; `ctx sync <connector> --full`
+//! summarizes the ones already indexed.
+
+use anyhow::Result;
+use serde_json::json;
+use sqlx::SqlitePool;
+use std::collections::{HashMap, HashSet};
+use std::sync::LazyLock;
+
+use crate::agent_run::ChatClient;
+use crate::config::{Config, NormalizeConfig, SummaryConfig};
+use crate::models::Document;
+use crate::normalize::Normalizer;
+use crate::text::truncate_chars;
+
+/// Characters per token, the same estimate the chunker uses.
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Sentences outside this length range are not picked.
+const MIN_SENTENCE_CHARS: usize = 40;
+const MAX_SENTENCE_CHARS: usize = 400;
+
+/// Share of content words above which a sentence counts as a repeat of one
+/// already picked.
+const MAX_OVERLAP: f64 = 0.6;
+
+/// Common English words that say nothing about what a document is about.
+const STOP_WORDS: &[&str] = &[
+    "about", "after", "all", "also", "and", "any", "are", "because", "been", "before", "but",
+    "can", "could", "does", "each", "for", "from", "had", "has", "have", "her", "his", "how",
+    "into", "its", "may", "more", "most", "must", "not", "now", "only", "other", "our", "out",
+    "over", "should", "some", "such", "than", "that", "the", "their", "them", "then", "there",
+    "these", "they", "this", "those", "through", "use", "used", "using", "was", "were", "what",
+    "when", "where", "which", "while", "who", "will", "with", "would", "you", "your",
+];
+
+/// Markdown, front matter, and whitespace cleanup applied before sentences
+/// are extracted.
+static CLEANUP: LazyLock<Normalizer> = LazyLock::new(|| {
+    Normalizer::from_config(&NormalizeConfig {
+        enabled: true,
+        ..NormalizeConfig::default()
+    })
+    .expect("default normalization has no patterns")
+    .expect("enabled")
+});
+
+/// Writes the summary of each long document.
+pub struct Summarizer {
+    settings: SummaryConfig,
+    client: Option<ChatClient>,
+}
+
+impl Summarizer {
+    /// The summarizer for `[summary]`, or `None` when it is disabled.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `llm = true` and the `[llm]` client cannot be
+    /// created (for example, a missing API key).
+    pub fn from_config(config: &Config) -> Result<Option<Self>> {
+        let settings = &config.summary;
+        if !settings.enabled {
+            return Ok(None);
+        }
+        let client = if settings.llm {
+            Some(ChatClient::from_config(&config.llm)?)
+        } else {
+            None
+        };
+        Ok(Some(Self {
+            settings: settings.clone(),
+            client,
+        }))
+    }
+
+    /// The summary of `doc`, or `None` when its body is shorter than
+    /// `min_chars` or has no usable sentences.
+    pub async fn summarize(&self, pool: &SqlitePool, doc: &Document) -> Result<Option<String>> {
+        if doc.body.chars().count() < self.settings.min_chars {
+            return Ok(None);
+        }
+        let Some(ref client) = self.client else {
+            return Ok(extractive_summary(&doc.body, self.settings.max_chars));
+        };
+
+        let previous: Option<Option<String>> =
+            sqlx::query_scalar("SELECT summary FROM documents WHERE id = ? AND dedup_hash = ?")
+                .bind(&doc.id)
+                .bind(&doc.dedup_hash)
+                .fetch_optional(pool)
+                .await?;
+        if let Some(Some(summary)) = previous {
+            return Ok(Some(summary));
+        }
+
+        let document = truncate_chars(
+            &doc.body,
+            self.settings.max_document_tokens * CHARS_PER_TOKEN,
+        );
+        let prompt = format!(
+            "<document>\n{}\n</document>\n\
+             Summarize the document above in at most {} characters of plain text: \
+             what it is, what it covers, and its key facts or conclusions. \
+             Answer only with the summary and nothing else.",
+            document, self.settings.max_chars
+        );
+        let messages = [json!({ "role": "user", "content": prompt })];
+        match client.complete(&messages).await {
+            Ok(reply) => {
+                let reply = reply.split_whitespace().collect::<Vec<_>>().join(" ");
+                if !reply.is_empty() {
+                    return Ok(Some(truncate_words(&reply, self.settings.max_chars)));
+                }
+                tracing::warn!("summary {}: empty reply", doc.source_id);
+            }
+            Err(e) => tracing::warn!("summary {}: {:#}", doc.source_id, e),
+        }
+        Ok(extractive_summary(&doc.body, self.settings.max_chars))
+    }
+}
+
+/// The most representative sentences of `body` that fit in `max_chars`,
+/// in document order.
+fn extractive_summary(body: &str, max_chars: usize) -> Option<String> {
+    let text = CLEANUP.normalize(body);
+    let paragraphs = paragraphs(&text);
+    let sentences: Vec<&str> = paragraphs.iter().flat_map(|p| sentences(p)).collect();
+    if sentences.is_empty() {
+        return None;
+    }
+
+    let mut frequency: HashMap<String, f64> = HashMap::new();
+    for sentence in &sentences {
+        for word in content_words(sentence) {
+            *frequency.entry(word).or_default() += 1.0;
+        }
+    }
+    let max_frequency = frequency.values().copied().fold(1.0, f64::max);
+
+    let count = sentences.len() as f64;
+    let words: Vec<HashSet<String>> = sentences
+        .iter()
+        .map(|s| content_words(s).collect())
+        .collect();
+    let mut ranked: Vec<(usize, f64)> = words
+        .iter()
+        .enumerate()
+        .map(|(i, words)| {
+            let weight: f64 = words.iter().map(|w| frequency[w] / max_frequency).sum();
+            let position = 1.0 + 0.25 * (1.0 - i as f64 / count);
+            (i, weight / (words.len().max(1) as f64).sqrt() * position)
+        })
+        .collect();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+
+    let mut picked: Vec<usize> = Vec::new();
+    let mut length = 0;
+    for (i, _) in ranked {
+        let len = sentences[i].chars().count() + usize::from(!picked.is_empty());
+        // Near-repeats of a picked sentence add length but no information.
+        let repeats = picked
+            .iter()
+            .any(|&j| overlap(&words[i], &words[j]) > MAX_OVERLAP);
+        if length + len <= max_chars && !repeats {
+            picked.push(i);
+            length += len;
+        }
+    }
+    if picked.is_empty() {
+        return Some(truncate_words(sentences[0], max_chars));
+    }
+    picked.sort_unstable();
+    Some(
+        picked
+            .iter()
+            .map(|&i| sentences[i])
+            .collect::<Vec<_>>()
+            .join(" "),
+    )
+}
+
+/// Paragraphs of `text`, each joined into one line. Headings and table
+/// rows are left out.
+fn paragraphs(text: &str) -> Vec<String> {
+    text.split("\n\n")
+        .map(|paragraph| {
+            paragraph
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.starts_with('#') && !line.starts_with('|'))
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect()
+}
+
+/// Prose sentences of `paragraph`, split at `.`, `!`, or `?` followed by
+/// whitespace. Short fragments, overlong runs, and text that is mostly
+/// symbols (code) are skipped.
+fn sentences(paragraph: &str) -> Vec<&str> {
+    let mut out = Vec::new();
+    let mut start = 0;
+    let mut chars = paragraph.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let end = match (c, chars.peek()) {
+            ('.' | '!' | '?', Some((_, next))) if next.is_whitespace() => i + c.len_utf8(),
+            (_, None) => paragraph.len(),
+            _ => continue,
+        };
+        let sentence = paragraph[start..end].trim();
+        start = end;
+        let len = sentence.chars().count();
+        let letters = sentence
+            .chars()
+            .filter(|c| c.is_alphabetic() || c.is_whitespace())
+            .count();
+        if (MIN_SENTENCE_CHARS..=MAX_SENTENCE_CHARS).contains(&len) && letters * 10 >= len * 8 {
+            out.push(sentence);
+        }
+    }
+    out
+}
+
+/// Jaccard similarity of two word sets.
+fn overlap(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 1.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+/// Lowercased words of `sentence` that are at least three letters long and
+/// not stop words.
+fn content_words(sentence: &str) -> impl Iterator<Item = String> + '_ {
+    sentence
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.chars().count() >= 3)
+        .map(str::to_lowercase)
+        .filter(|w| !STOP_WORDS.contains(&w.as_str()))
+}
+
+/// `text` cut to at most `max_chars` at a word boundary, with `…` appended
+/// when cut.
+fn truncate_words(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let cut = truncate_chars(text, max_chars.saturating_sub(1));
+    let cut = match cut.rfind(char::is_whitespace) {
+        Some(end) if end > 0 => &cut[..end],
+        _ => cut,
+    };
+    format!("{}…", cut.trim_end())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RUNBOOK: &str = "# Deploy runbook\n\n\
+        This runbook describes how the payments service is deployed to production clusters. \
+        Lunch is served in the cafeteria between noon and two on most weekdays.\n\n\
+        ```sh\nkubectl rollout restart deploy/payments\n```\n\n\
+        Each deploy of the payments service starts with a canary in one production cluster. \
+        If the canary deploy shows errors, the payments service is rolled back automatically.\n\n\
+        | Step | Owner |\n|------|-------|\n| Canary | SRE |\n";
+
+    #[test]
+    fn extractive_summary_keeps_representative_sentences_in_order() {
+        let summary = extractive_summary(RUNBOOK, 260).unwrap();
+        assert!(summary.starts_with("This runbook describes"), "{}", summary);
+        assert!(summary.contains("rolled back"), "{}", summary);
+        assert!(!summary.contains("cafeteria"), "{}", summary);
+        assert!(!summary.contains("kubectl"), "{}", summary);
+        assert!(summary.chars().count() <= 260);
+    }
+
+    #[test]
+    fn extractive_summary_cuts_a_sentence_that_does_not_fit() {
+        let summary = extractive_summary(RUNBOOK, 40).unwrap();
+        assert!(summary.ends_with('…'), "{}", summary);
+        assert!(summary.chars().count() <= 40, "{}", summary);
+    }
+
+    #[test]
+    fn extractive_summary_needs_prose() {
+        assert_eq!(
+            extractive_summary("```\nfn main() {}\n```\n| a | b |\n", 600),
+            None
+        );
+        assert_eq!(extractive_summary("Too short.", 600), None);
+    }
+
+    #[test]
+    fn truncate_words_breaks_at_whitespace() {
+        assert_eq!(truncate_words("alpha beta gamma", 12), "alpha beta…");
+        assert_eq!(truncate_words("alpha beta", 12), "alpha beta");
+    }
+}
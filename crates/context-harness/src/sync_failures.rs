@@ -0,0 +1,180 @@
+//! Per-item sync failures and `ctx sync --retry-failed`.
+//!
+//! One bad item no longer stops a sync. An item whose extraction or write
+//! fails, or that a connector could not fetch (an unreadable file, an S3
+//! object whose download ran out of retries), is recorded as an
+//! [`ItemFailure`] and the sync moves on. Each connector's summary lists
+//! its failures, and the sync ends with a report of every failed item.
+//!
+//! Failures are kept in the `sync_failures` table, keyed by `(source,
+//! source_id)`, until the item is written again. Because the checkpoint
+//! still advances past them, an incremental sync does not revisit failed
+//! items; `ctx sync <connector> --retry-failed` scans the connector again
+//! and ingests only those items, leaving the checkpoint unchanged.
+//!
+//! With `--strict` (or `[sync] strict = true`), the first failed item
+//! fails the sync, as before.
+
+use std::collections::HashSet;
+
+use anyhow::Result;
+use chrono::Utc;
+use sqlx::SqlitePool;
+
+use crate::migrate;
+use crate::scrub;
+
+/// Failures listed under each connector's summary before the rest are
+/// only counted.
+const PRINT_LIMIT: usize = 20;
+
+/// An item that could not be fetched or ingested.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ItemFailure {
+    pub source_id: String,
+    /// Where it failed: `fetch`, `extract`, or `write`.
+    pub stage: String,
+    pub error: String,
+}
+
+impl ItemFailure {
+    pub fn new(source_id: &str, stage: &str, error: impl std::fmt::Display) -> Self {
+        Self {
+            source_id: source_id.to_string(),
+            stage: stage.to_string(),
+            error: scrub::scrub(&error.to_string()),
+        }
+    }
+}
+
+/// Record `failures` of `source`, counting repeated failures of an item.
+pub async fn record(pool: &SqlitePool, source: &str, failures: &[ItemFailure]) -> Result<()> {
+    if failures.is_empty() {
+        return Ok(());
+    }
+    migrate::create_sync_failures_table(pool).await?;
+    let now = Utc::now().timestamp();
+    for failure in failures {
+        sqlx::query(
+            r#"
+            INSERT INTO sync_failures (source, source_id, stage, error, attempts, failed_at)
+            VALUES (?, ?, ?, ?, 1, ?)
+            ON CONFLICT(source, source_id) DO UPDATE SET
+                stage = excluded.stage,
+                error = excluded.error,
+                attempts = sync_failures.attempts + 1,
+                failed_at = excluded.failed_at
+            "#,
+        )
+        .bind(source)
+        .bind(&failure.source_id)
+        .bind(&failure.stage)
+        .bind(&failure.error)
+        .bind(now)
+        .execute(pool)
+        .await?;
+    }
+    Ok(())
+}
+
+/// Forget the failure of an item that has now been written.
+pub async fn clear(pool: &SqlitePool, source: &str, source_id: &str) -> Result<()> {
+    sqlx::query("DELETE FROM sync_failures WHERE source = ? AND source_id = ?")
+        .bind(source)
+        .bind(source_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Source IDs of the recorded failures of `source`.
+pub async fn failed_ids(pool: &SqlitePool, source: &str) -> Result<HashSet<String>> {
+    migrate::create_sync_failures_table(pool).await?;
+    let ids: Vec<String> =
+        sqlx::query_scalar("SELECT source_id FROM sync_failures WHERE source = ?")
+            .bind(source)
+            .fetch_all(pool)
+            .await?;
+    Ok(ids.into_iter().collect())
+}
+
+/// Print a connector's failures under its sync summary.
+pub fn print_failures(failures: &[ItemFailure]) {
+    if failures.is_empty() {
+        return;
+    }
+    println!("  failed items: {}", failures.len());
+    for failure in failures.iter().take(PRINT_LIMIT) {
+        println!(
+            "    {} ({}): {}",
+            failure.source_id, failure.stage, failure.error
+        );
+    }
+    if failures.len() > PRINT_LIMIT {
+        println!("    ... and {} more", failures.len() - PRINT_LIMIT);
+    }
+}
+
+/// Print the end-of-sync report: failed items per connector and how to
+/// retry them.
+pub fn print_report(by_source: &[(String, usize)]) {
+    let total: usize = by_source.iter().map(|(_, n)| n).sum();
+    if total == 0 {
+        return;
+    }
+    println!();
+    println!(
+        "{} item{} failed and {} skipped until retried:",
+        total,
+        if total == 1 { "" } else { "s" },
+        if total == 1 { "was" } else { "were" }
+    );
+    for (source, n) in by_source.iter().filter(|(_, n)| *n > 0) {
+        println!("  {}: {}  (ctx sync {} --retry-failed)", source, n, source);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_store::SqliteAppStore;
+    use crate::config::Config;
+    use crate::db;
+
+    #[tokio::test]
+    async fn failures_count_attempts_until_cleared() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let mut config = Config::minimal();
+        config.db.path = tmp.path().join("ctx.sqlite");
+        SqliteAppStore::initialize_config(&config).await.unwrap();
+        let pool = db::connect(&config).await.unwrap();
+
+        let failure = ItemFailure::new("docs/broken.pdf", "extract", "PDF extraction failed");
+        record(&pool, "filesystem:docs", std::slice::from_ref(&failure))
+            .await
+            .unwrap();
+        record(&pool, "filesystem:docs", std::slice::from_ref(&failure))
+            .await
+            .unwrap();
+        let (stage, attempts): (String, i64) =
+            sqlx::query_as("SELECT stage, attempts FROM sync_failures WHERE source_id = ?")
+                .bind(&failure.source_id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!((stage.as_str(), attempts), ("extract", 2));
+        assert!(failed_ids(&pool, "filesystem:docs")
+            .await
+            .unwrap()
+            .contains("docs/broken.pdf"));
+        assert!(failed_ids(&pool, "git:platform").await.unwrap().is_empty());
+
+        clear(&pool, "filesystem:docs", "docs/broken.pdf")
+            .await
+            .unwrap();
+        assert!(failed_ids(&pool, "filesystem:docs")
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,214 @@
+//! Per-source sync history and staleness for `ctx sources`.
+//!
+//! `ctx sync` records the outcome of each connector's run in the
+//! `source_sync_status` table: the time of the last attempt, the time and
+//! item count of the last completed sync, and the error of the last failed
+//! one (cleared by the next completed sync). Interrupted runs and dry runs
+//! are not recorded.
+//!
+//! A source is **stale** when its last completed sync is older than its
+//! threshold, and **never** synced when it has no completed sync at all.
+//! Sources synced before this table existed fall back to their checkpoint
+//! time. Thresholds come from `[sync]`:
+//!
+//! ```toml
+//! [sync]
+//! stale_after_hours = 48   # default for every source
+//!
+//! [sync.stale_after]       # per connector type or instance, in hours
+//! "git:platform" = 6
+//! s3 = 168
+//! ```
+//!
+//! An instance entry wins over a type entry, which wins over the default.
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use chrono::Utc;
+use serde::Serialize;
+use sqlx::{Row, SqlitePool};
+
+use crate::config::SyncConfig;
+use crate::migrate;
+
+/// One row of `source_sync_status`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncRecord {
+    pub last_attempt_at: Option<i64>,
+    pub last_success_at: Option<i64>,
+    /// Items the last completed sync fetched.
+    pub last_items: Option<u64>,
+    pub last_error: Option<String>,
+}
+
+/// Freshness of a source's index, by the age of its last completed sync.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Staleness {
+    Fresh,
+    Stale,
+    Never,
+}
+
+impl Staleness {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Staleness::Fresh => "fresh",
+            Staleness::Stale => "stale",
+            Staleness::Never => "never",
+        }
+    }
+}
+
+/// Record a completed sync of `source` that fetched `items` items.
+pub async fn record_success(pool: &SqlitePool, source: &str, items: u64) -> Result<()> {
+    migrate::create_sync_status_table(pool).await?;
+    let now = Utc::now().timestamp();
+    sqlx::query(
+        r#"
+        INSERT INTO source_sync_status
+            (source, last_attempt_at, last_success_at, last_items, last_error)
+        VALUES (?, ?, ?, ?, NULL)
+        ON CONFLICT(source) DO UPDATE SET
+            last_attempt_at = excluded.last_attempt_at,
+            last_success_at = excluded.last_success_at,
+            last_items = excluded.last_items,
+            last_error = NULL
+        "#,
+    )
+    .bind(source)
+    .bind(now)
+    .bind(now)
+    .bind(items as i64)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Record a failed or timed-out sync of `source`, keeping its last success.
+pub async fn record_failure(pool: &SqlitePool, source: &str, error: &str) -> Result<()> {
+    migrate::create_sync_status_table(pool).await?;
+    sqlx::query(
+        r#"
+        INSERT INTO source_sync_status (source, last_attempt_at, last_error)
+        VALUES (?, ?, ?)
+        ON CONFLICT(source) DO UPDATE SET
+            last_attempt_at = excluded.last_attempt_at,
+            last_error = excluded.last_error
+        "#,
+    )
+    .bind(source)
+    .bind(Utc::now().timestamp())
+    .bind(error)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Sync records by source label, with checkpoint times filling in the last
+/// success of sources that have no record.
+pub async fn load(pool: &SqlitePool) -> Result<HashMap<String, SyncRecord>> {
+    migrate::create_sync_status_table(pool).await?;
+    let mut records = HashMap::new();
+    let rows = sqlx::query(
+        "SELECT source, last_attempt_at, last_success_at, last_items, last_error \
+         FROM source_sync_status",
+    )
+    .fetch_all(pool)
+    .await?;
+    for row in rows {
+        records.insert(
+            row.get::<String, _>("source"),
+            SyncRecord {
+                last_attempt_at: row.get("last_attempt_at"),
+                last_success_at: row.get("last_success_at"),
+                last_items: row
+                    .get::<Option<i64>, _>("last_items")
+                    .map(|n| n.max(0) as u64),
+                last_error: row.get("last_error"),
+            },
+        );
+    }
+
+    let checkpoints = sqlx::query("SELECT source, updated_at FROM checkpoints")
+        .fetch_all(pool)
+        .await?;
+    for row in checkpoints {
+        let record = records.entry(row.get::<String, _>("source")).or_default();
+        if record.last_success_at.is_none() {
+            record.last_success_at = Some(row.get("updated_at"));
+        }
+    }
+    Ok(records)
+}
+
+/// Hours after which `source_label` counts as stale.
+pub fn stale_after_hours(config: &SyncConfig, source_label: &str) -> u64 {
+    let kind = source_label.split(':').next().unwrap_or(source_label);
+    config
+        .stale_after
+        .get(source_label)
+        .or_else(|| config.stale_after.get(kind))
+        .copied()
+        .unwrap_or(config.stale_after_hours)
+}
+
+/// Staleness of a source whose last completed sync was at `last_success_at`.
+pub fn staleness(last_success_at: Option<i64>, stale_after_hours: u64, now: i64) -> Staleness {
+    match last_success_at {
+        None => Staleness::Never,
+        Some(ts) if now - ts > stale_after_hours as i64 * 3600 => Staleness::Stale,
+        Some(_) => Staleness::Fresh,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_store::SqliteAppStore;
+    use crate::config::Config;
+    use crate::db;
+
+    #[test]
+    fn thresholds_prefer_instance_then_type() {
+        let mut sync = SyncConfig::default();
+        sync.stale_after.insert("git".to_string(), 12);
+        sync.stale_after.insert("git:platform".to_string(), 6);
+        assert_eq!(stale_after_hours(&sync, "git:platform"), 6);
+        assert_eq!(stale_after_hours(&sync, "git:docs"), 12);
+        assert_eq!(stale_after_hours(&sync, "s3:docs"), 48);
+
+        let now = 1_700_000_000;
+        assert_eq!(staleness(None, 6, now), Staleness::Never);
+        assert_eq!(staleness(Some(now - 5 * 3600), 6, now), Staleness::Fresh);
+        assert_eq!(staleness(Some(now - 7 * 3600), 6, now), Staleness::Stale);
+    }
+
+    #[tokio::test]
+    async fn failure_keeps_last_success() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let mut config = Config::minimal();
+        config.db.path = tmp.path().join("ctx.sqlite");
+        SqliteAppStore::initialize_config(&config).await.unwrap();
+        let pool = db::connect(&config).await.unwrap();
+
+        record_success(&pool, "git:platform", 12).await.unwrap();
+        record_failure(&pool, "git:platform", "git:platform: auth failed")
+            .await
+            .unwrap();
+        let records = load(&pool).await.unwrap();
+        let record = &records["git:platform"];
+        assert!(record.last_success_at.is_some());
+        assert_eq!(record.last_items, Some(12));
+        assert_eq!(
+            record.last_error.as_deref(),
+            Some("git:platform: auth failed")
+        );
+
+        record_success(&pool, "git:platform", 3).await.unwrap();
+        let records = load(&pool).await.unwrap();
+        assert_eq!(records["git:platform"].last_error, None);
+        assert_eq!(records["git:platform"].last_items, Some(3));
+    }
+}
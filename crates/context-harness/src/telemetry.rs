@@ -0,0 +1,262 @@
+//! OpenTelemetry trace export: `[telemetry]`.
+//!
+//! Logs say what happened; traces say where the time went. When enabled,
+//! ctx's `tracing` spans are exported over OTLP/HTTP (protobuf) to a
+//! collector or any backend that accepts OTLP (Jaeger, Tempo, Honeycomb,
+//! …). Spans come from ctx's own modules at `info` and above, whatever
+//! `--log-level` is; events logged inside a span are attached to it.
+//!
+//! # Spans
+//!
+//! | Span | Covers | Attributes |
+//! |------|--------|------------|
+//! | `sync` | One connector in `ctx sync` | `connector` |
+//! | `ingest.document` | Writing and embedding one extracted and chunked document | `source_id` |
+//! | `store.write` | Writing a document and its chunks to SQLite and the indexes | `chunks` |
+//! | `embedding.request` | One call to the embedding provider | `provider`, `model`, `inputs`, `query` |
+//! | `request` | One HTTP request to `ctx serve mcp` | `id`, `method`, `path` |
+//! | `mcp.request` | One MCP JSON-RPC request on `/mcp` or `/rpc` | `mcp.method`, `mcp.request_id` |
+//! | `tool` | One tool execution | `tool`, `cached` |
+//!
+//! # Propagation and links
+//!
+//! An HTTP request with a W3C `traceparent` header continues the caller's
+//! trace. MCP requests on `/mcp` run in the session's task rather than the
+//! HTTP request's, so their `mcp.request` span is parented to the request
+//! that delivered them explicitly.
+//!
+//! Each `embedding.request` made while serving an MCP request carries a
+//! span link to that request's `mcp.request` span, with the
+//! `mcp.request_id` as a link attribute. Backends that index links can go
+//! from a slow or failing embedding call straight to the MCP request that
+//! caused it.
+//!
+//! # Example
+//!
+//! ```toml
+//! [telemetry]
+//! enabled = true
+//! endpoint = "http://otel-collector:4318"   # default: OTEL_EXPORTER_OTLP_ENDPOINT
+//! sample_ratio = 0.25
+//!
+//! [telemetry.headers]
+//! x-honeycomb-team = "secret://honeycomb-key"
+//! ```
+//!
+//! Spans are batched and sent from a background thread; the batch still
+//! queued when ctx exits is flushed before the process ends.
+
+use anyhow::{Context as _, Result};
+use axum::http::HeaderMap;
+use opentelemetry::propagation::Extractor;
+use opentelemetry::trace::{SpanContext, TraceContextExt, TracerProvider as _};
+use opentelemetry::{Context, KeyValue};
+use opentelemetry_otlp::{WithExportConfig, WithHttpConfig};
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::{Sampler, SdkTracerProvider};
+use opentelemetry_sdk::Resource;
+use std::collections::HashMap;
+use std::future::Future;
+use std::time::Duration;
+use tracing::{Instrument, Span};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::filter::{LevelFilter, Targets};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+use crate::config::TelemetryConfig;
+use crate::logging::LUA_TARGET;
+use crate::secrets;
+
+/// Instrumentation scope name on exported spans.
+const TRACER_NAME: &str = "context-harness";
+
+/// The MCP request being served by the current task.
+#[derive(Clone)]
+struct McpRequest {
+    span: SpanContext,
+    id: String,
+}
+
+tokio::task_local! {
+    static MCP_REQUEST: McpRequest;
+}
+
+/// Trace context of an HTTP request, stored in the request's extensions so
+/// MCP handlers can parent their spans to it.
+#[derive(Clone)]
+pub struct RequestTrace(pub Context);
+
+/// Owns the exporter. Dropping it flushes queued spans and stops export.
+pub struct Telemetry {
+    provider: SdkTracerProvider,
+}
+
+impl Drop for Telemetry {
+    fn drop(&mut self) {
+        if let Err(e) = self.provider.shutdown() {
+            eprintln!("telemetry: failed to flush spans: {}", e);
+        }
+    }
+}
+
+/// The `tracing` layer exporting spans for `[telemetry]`, and the handle
+/// that keeps it running.
+///
+/// # Errors
+///
+/// Returns an error if a header value names a secret that cannot be
+/// resolved, or the exporter cannot be built (for example, an invalid
+/// endpoint URL).
+pub fn layer<S>(config: &TelemetryConfig) -> Result<(impl Layer<S>, Telemetry)>
+where
+    S: tracing::Subscriber + for<'a> LookupSpan<'a>,
+{
+    let mut headers = HashMap::new();
+    for (name, value) in &config.headers {
+        let value = secrets::resolve(value)
+            .with_context(|| format!("telemetry.headers.{}: cannot resolve value", name))?;
+        headers.insert(name.clone(), value);
+    }
+    let mut exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_http()
+        .with_headers(headers)
+        .with_timeout(Duration::from_secs(config.timeout_secs));
+    if let Some(ref endpoint) = config.endpoint {
+        exporter = exporter.with_endpoint(traces_url(endpoint));
+    }
+    let exporter = exporter
+        .build()
+        .context("Failed to create the OTLP trace exporter")?;
+
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+            config.sample_ratio,
+        ))))
+        .with_resource(
+            Resource::builder()
+                .with_service_name(config.service_name.clone())
+                .build(),
+        )
+        .build();
+    opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+
+    let targets = Targets::new()
+        .with_target("ctx", LevelFilter::INFO)
+        .with_target("context_harness", LevelFilter::INFO)
+        .with_target(LUA_TARGET, LevelFilter::INFO);
+    let layer = tracing_opentelemetry::layer()
+        .with_tracer(provider.tracer(TRACER_NAME))
+        .with_filter(targets);
+    Ok((layer, Telemetry { provider }))
+}
+
+/// The OTLP/HTTP traces URL for a base `endpoint`.
+fn traces_url(endpoint: &str) -> String {
+    let endpoint = endpoint.trim_end_matches('/');
+    if endpoint.ends_with("/v1/traces") {
+        endpoint.to_string()
+    } else {
+        format!("{}/v1/traces", endpoint)
+    }
+}
+
+/// Continue the trace named by a `traceparent` header in `headers`, if any.
+pub fn set_remote_parent(span: &Span, headers: &HeaderMap) {
+    if !headers.contains_key("traceparent") {
+        return;
+    }
+    let parent = opentelemetry::global::get_text_map_propagator(|propagator| {
+        propagator.extract(&HeaderExtractor(headers))
+    });
+    if parent.span().span_context().is_remote() {
+        // Without the exporter layer there is no trace to continue.
+        let _ = span.set_parent(parent);
+    }
+}
+
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|name| name.as_str()).collect()
+    }
+}
+
+/// The [`RequestTrace`] of an HTTP request's `span`.
+pub fn request_trace(span: &Span) -> RequestTrace {
+    RequestTrace(span.context())
+}
+
+/// The `mcp.request` span for JSON-RPC request `id`. `parent` is the HTTP
+/// request that delivered it when the handler runs outside that request's
+/// span.
+pub fn mcp_request_span(method: &str, id: &str, parent: Option<&RequestTrace>) -> Span {
+    let span = tracing::info_span!(
+        "mcp.request",
+        mcp.method = %method,
+        mcp.request_id = %id
+    );
+    if let Some(RequestTrace(parent)) = parent {
+        let _ = span.set_parent(parent.clone());
+    }
+    span
+}
+
+/// Run `future` inside `span`, the `mcp.request` span for request `id`, so
+/// embedding calls it makes link back to the request.
+pub async fn in_mcp_request<F: Future>(span: Span, id: &str, future: F) -> F::Output {
+    let request = McpRequest {
+        span: span.context().span().span_context().clone(),
+        id: id.to_string(),
+    };
+    MCP_REQUEST.scope(request, future.instrument(span)).await
+}
+
+/// The `embedding.request` span for one provider call, linked to the MCP
+/// request being served, if any.
+pub fn embedding_span(provider: &str, model: &str, inputs: usize, query: bool) -> Span {
+    let span = tracing::info_span!(
+        "embedding.request",
+        provider = %provider,
+        model = %model,
+        inputs,
+        query,
+        otel.status_code = tracing::field::Empty
+    );
+    let _ = MCP_REQUEST.try_with(|request| {
+        if request.span.is_valid() {
+            span.add_link_with_attributes(
+                request.span.clone(),
+                vec![KeyValue::new("mcp.request_id", request.id.clone())],
+            );
+        }
+    });
+    span
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn traces_url_appends_signal_path_once() {
+        assert_eq!(
+            traces_url("http://collector:4318"),
+            "http://collector:4318/v1/traces"
+        );
+        assert_eq!(
+            traces_url("https://api.example.com/otlp/"),
+            "https://api.example.com/otlp/v1/traces"
+        );
+        assert_eq!(
+            traces_url("http://collector:4318/v1/traces"),
+            "http://collector:4318/v1/traces"
+        );
+    }
+}
@@ -0,0 +1,27 @@
+//! Small string helpers shared by the ingest-time model passes.
+//!
+//! [`contextualize`](crate::contextualize), [`summarize`](crate::summarize),
+//! and [`classify`](crate::classify) all cap how much of a document they
+//! send to a model. They cut by characters rather than bytes so a limit
+//! never splits a multi-byte character.
+
+/// The first `max_chars` characters of `text`.
+pub fn truncate_chars(text: &str, max_chars: usize) -> &str {
+    match text.char_indices().nth(max_chars) {
+        Some((end, _)) => &text[..end],
+        None => text,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncate_chars_counts_characters_not_bytes() {
+        assert_eq!(truncate_chars("héllo wörld", 5), "héllo");
+        assert_eq!(truncate_chars("héllo", 5), "héllo");
+        assert_eq!(truncate_chars("héllo", 10), "héllo");
+        assert_eq!(truncate_chars("héllo", 0), "");
+    }
+}
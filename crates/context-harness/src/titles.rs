@@ -0,0 +1,266 @@
+//! Title index: find documents by name.
+//!
+//! `titles_fts` holds one row per document: its title and, for markdown
+//! documents, the text of its headings. Searching it (`ctx search --in
+//! titles`, or `scope: "titles"` on the `search` tool) returns
+//! document-level hits straight from one small FTS5 table, without chunk
+//! retrieval or score aggregation, which makes it the fast path for "open
+//! the deploy runbook" style lookups.
+//!
+//! # Ranking
+//!
+//! Hits are ranked by `bm25()` with title matches weighted
+//! [`TITLE_WEIGHT`] times as much as heading matches, then scaled so the
+//! best hit scores `1.0`. `[retrieval.bm25.titles]` (or `[retrieval.bm25]`)
+//! constants other than FTS5's rescore every hit (see [`crate::bm25`]). When a heading matched, it is returned as the
+//! result's `heading`; the snippet is the matching title or heading with
+//! the matched terms between `>>>` and `<<<`.
+//!
+//! Title search is keyword-only: stop words from `[retrieval]` are
+//! dropped like in chunk search, and `ctx curate` pins, boosts, and blocks
+//! apply. The index is maintained on every document upsert and built for
+//! existing documents by [`crate::migrate::ensure_titles_table`].
+
+use anyhow::{bail, Result};
+use chrono::NaiveDate;
+use sqlx::{Row, SqlitePool};
+
+use context_harness_core::models::Document;
+use context_harness_core::search::{
+    end_of_day_ts, format_ts_iso, source_filter_matches, source_matches, type_matches,
+    SearchParams, SearchRequest, SearchResultItem,
+};
+use context_harness_core::store::Store;
+
+use crate::bm25;
+use crate::chunk;
+use crate::config::Config;
+use crate::curate::Curation;
+use crate::db;
+use crate::popularity;
+use crate::sqlite_store::SqliteStore;
+use crate::storage;
+
+/// `bm25()` weight of the title column relative to the headings column.
+pub const TITLE_WEIGHT: f64 = 2.0;
+
+/// The headings stored for a document, one per line; empty unless the
+/// document is markdown.
+pub fn headings_text(source_id: &str, content_type: &str, body: &str) -> String {
+    if !chunk::is_markdown(source_id, content_type) {
+        return String::new();
+    }
+    chunk::markdown_headings(body).join("\n")
+}
+
+/// Replace the title index row of `doc`.
+pub async fn index_document(pool: &SqlitePool, doc: &Document) -> Result<()> {
+    let mut tx = db::begin_write(pool).await?;
+    sqlx::query("DELETE FROM titles_fts WHERE document_id = ?")
+        .bind(&doc.id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("INSERT INTO titles_fts (document_id, title, headings) VALUES (?, ?, ?)")
+        .bind(&doc.id)
+        .bind(&doc.title)
+        .bind(headings_text(&doc.source_id, &doc.content_type, &doc.body))
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Search document titles and headings.
+///
+/// Takes the same filters as [`crate::search::search_documents_filtered`]
+/// except chunk metadata filters, which do not apply to documents.
+#[allow(clippy::too_many_arguments)]
+pub async fn search_titles(
+    config: &Config,
+    query: &str,
+    source_filter: Option<&str>,
+    since: Option<&str>,
+    as_of: Option<&str>,
+    types: &[String],
+    limit: Option<i64>,
+    allowed_sources: Option<&[String]>,
+) -> Result<Vec<SearchResultItem>> {
+    if query.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    let since_ts = since
+        .map(|date| -> Result<i64> {
+            let date = NaiveDate::parse_from_str(date, "%Y-%m-%d")?;
+            Ok(date.and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp())
+        })
+        .transpose()?;
+    let as_of_ts = as_of.map(end_of_day_ts).transpose()?;
+
+    let pool = db::connect_reader(config).await?;
+    let exists: Option<String> = sqlx::query_scalar(
+        "SELECT name FROM sqlite_master WHERE type='table' AND name='titles_fts'",
+    )
+    .fetch_optional(&pool)
+    .await?;
+    if exists.is_none() {
+        bail!("the title index has not been built yet; run `ctx sync` or `ctx init`");
+    }
+
+    let store = SqliteStore::new(pool.clone()).with_retrieval(&config.retrieval);
+    let fts_query = store.query_terms(query);
+    if fts_query.is_empty() {
+        db::close_reader(config, pool).await;
+        return Ok(Vec::new());
+    }
+
+    let curation = Curation::load(&pool).await?;
+    let final_limit = limit.unwrap_or(config.retrieval.final_limit);
+    let mut sql = String::from(
+        "SELECT titles_fts.rowid AS rowid, titles_fts.document_id AS id, d.source AS source, \
+         bm25(titles_fts, 0.0, ?, 1.0) AS rank, \
+         highlight(titles_fts, 1, '>>>', '<<<') AS title_hl, \
+         highlight(titles_fts, 2, '>>>', '<<<') AS headings_hl \
+         FROM titles_fts JOIN documents d ON d.id = titles_fts.document_id \
+         WHERE titles_fts MATCH ?",
+    );
+    if since_ts.is_some() {
+        sql.push_str(" AND d.updated_at >= ?");
+    }
+    if as_of_ts.is_some() {
+        sql.push_str(" AND d.updated_at <= ?");
+    }
+    sql.push_str(" ORDER BY rank");
+
+    let mut q = sqlx::query(&sql).bind(TITLE_WEIGHT).bind(&fts_query);
+    if let Some(ts) = since_ts {
+        q = q.bind(ts);
+    }
+    if let Some(ts) = as_of_ts {
+        q = q.bind(ts);
+    }
+    let rows = q.fetch_all(&pool).await?;
+    let mut ranked: Vec<(f64, _)> = rows
+        .into_iter()
+        .map(|row| (-row.get::<f64, _>("rank"), row))
+        .collect();
+    let params = config.retrieval.bm25.titles();
+    if !params.is_builtin() {
+        let rowids: Vec<i64> = ranked.iter().map(|(_, row)| row.get("rowid")).collect();
+        let scores = bm25::scores(
+            &pool,
+            bm25::TITLES,
+            &[(1, TITLE_WEIGHT), (2, 1.0)],
+            params,
+            &fts_query,
+            &rowids,
+        )
+        .await?;
+        for ((raw, _), rowid) in ranked.iter_mut().zip(&rowids) {
+            *raw = scores.get(rowid).copied().unwrap_or(0.0);
+        }
+        ranked.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+    }
+
+    let wanted = (final_limit + curation.extra_candidates()).max(0) as usize;
+    let mut results = Vec::new();
+    let mut top = None;
+    for (raw, row) in ranked {
+        let source: String = row.get("source");
+        if source_filter.is_some_and(|filter| !source_filter_matches(filter, &source))
+            || allowed_sources.is_some_and(|patterns| !source_matches(patterns, &source))
+        {
+            continue;
+        }
+        let id: String = row.get("id");
+        let Some(meta) = store.get_document_metadata(&id).await? else {
+            continue;
+        };
+        if !type_matches(
+            types,
+            &meta.source_id,
+            &meta.content_type,
+            meta.doc_type.as_deref(),
+        ) {
+            continue;
+        }
+        let top = *top.get_or_insert(raw);
+        let title_hl: Option<String> = row.get("title_hl");
+        let headings_hl: Option<String> = row.get("headings_hl");
+        let heading = headings_hl
+            .as_deref()
+            .and_then(|h| h.lines().find(|line| line.contains(">>>")));
+        let snippet = match title_hl.as_deref() {
+            Some(title) if title.contains(">>>") => title.to_string(),
+            _ => heading.unwrap_or_default().to_string(),
+        };
+
+        results.push(SearchResultItem {
+            id: meta.id,
+            score: if top > 0.0 { raw / top } else { 1.0 },
+            title: meta.title,
+            source: meta.source,
+            source_id: meta.source_id,
+            content_type: Some(meta.content_type),
+            updated_at: format_ts_iso(meta.updated_at),
+            snippet,
+            source_url: meta.source_url,
+            heading: heading.map(|h| h.replace(">>>", "").replace("<<<", "")),
+            section_source_id: None,
+            section_source_url: None,
+            chunk_id: None,
+            parent_id: meta.parent_id,
+            explain: None,
+            origin: None,
+            summary: meta.summary,
+            doc_type: meta.doc_type,
+        });
+        if results.len() >= wanted {
+            break;
+        }
+    }
+
+    let req = SearchRequest {
+        query,
+        query_vec: None,
+        mode: "keyword",
+        source_filter,
+        allowed_sources,
+        since,
+        as_of,
+        params: SearchParams {
+            hybrid_alpha: config.retrieval.hybrid_alpha,
+            candidate_k_keyword: config.retrieval.candidate_k_keyword,
+            candidate_k_vector: config.retrieval.candidate_k_vector,
+            final_limit,
+            auto_merge_min_chunks: 0,
+        },
+        explain: false,
+        chunk_filters: &[],
+        types,
+    };
+    let results = popularity::apply(&pool, &config.retrieval, results).await?;
+    let results = curation
+        .apply(&pool, &req, results, final_limit.max(0) as usize)
+        .await?;
+
+    let ids: Vec<&str> = results.iter().map(|r| r.id.as_str()).collect();
+    storage::record_retrievals(config, &pool, &ids).await;
+
+    db::close_reader(config, pool).await;
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headings_are_indexed_for_markdown_only() {
+        let body = "# Deploy\n\n## Rollback\n\ntext";
+        assert_eq!(
+            headings_text("runbooks/deploy.md", "text/plain", body),
+            "Deploy\nRollback"
+        );
+        assert_eq!(headings_text("notes.txt", "text/plain", body), "");
+    }
+}
@@ -2,7 +2,7 @@
 //!
 //! Loads `.lua` tool scripts at server startup, extracts their parameter schemas,
 //! and provides execution with a context bridge back into the Rust core
-//! (search, get, sources).
+//! (search, get, sources, and the other registered tools).
 //!
 //! # Architecture
 //!
@@ -13,6 +13,10 @@
 //! - `context.search(query, opts?)` — search the knowledge base
 //! - `context.get(id)` — retrieve a document by UUID
 //! - `context.sources()` — list connector status
+//! - `context.call_tool(name, params?)` — call another registered tool
+//!   (see [`ToolContext::call_tool`])
+//! - `context.sample(prompt, opts?)` — ask the MCP client's model for a
+//!   completion (see [`ToolContext::sample`])
 //! - `context.config` — tool-specific configuration from `ctx.toml`
 //!
 //! # Script Interface
@@ -26,6 +30,7 @@
 //!     parameters = {
 //!         { name = "query", type = "string", required = true, description = "Search query" },
 //!     },
+//!     annotations = { read_only = true, open_world = false },
 //! }
 //!
 //! function tool.execute(params, context)
@@ -34,6 +39,11 @@
 //! end
 //! ```
 //!
+//! `annotations` sets the MCP behavior hints (`title`, `read_only`,
+//! `destructive`, `idempotent`, `open_world`; see [`ToolAnnotations`]).
+//! A result of the form `{ content = { { type = "text", text = "…" }, … } }`
+//! reaches MCP clients as typed content parts (see [`ContentPart`](crate::traits::ContentPart)).
+//!
 //! # Configuration
 //!
 //! ```toml
@@ -56,11 +66,15 @@ use std::time::{Duration, Instant};
 use crate::config::{Config, ScriptToolConfig};
 use crate::get::{get_document, DocumentResponse};
 use crate::lua_runtime::{
-    json_value_to_lua, lua_value_to_json, register_all_host_apis, toml_table_to_lua,
+    json_value_to_lua, lua_value_to_json, register_all_host_apis, toml_table_to_lua, EnvAccess,
 };
+use crate::registry::{self, RegistryManager, ScriptTrust};
 use crate::search::{search_documents, SearchResultItem};
 use crate::sources::{get_sources, SourceStatus};
-use crate::traits::{Tool, ToolContext};
+use crate::traits::{SampleRequest, Tool, ToolAnnotations, ToolContext, ToolRegistry};
+
+/// `max_tokens` of `context.sample` when the script sets none.
+const DEFAULT_SAMPLE_MAX_TOKENS: u32 = 1024;
 
 // ═══════════════════════════════════════════════════════════════════════
 // Types
@@ -79,6 +93,8 @@ pub struct ToolDefinition {
     pub description: String,
     /// OpenAI function-calling JSON Schema for the tool's parameters.
     pub parameters_schema: serde_json::Value,
+    /// MCP behavior hints from `tool.annotations`.
+    pub annotations: ToolAnnotations,
     /// Path to the `.lua` script file.
     pub script_path: PathBuf,
     /// Raw Lua source code (cached to avoid re-reading on every call).
@@ -87,6 +103,8 @@ pub struct ToolDefinition {
     pub config: toml::Table,
     /// Maximum execution time in seconds.
     pub timeout: u64,
+    /// Environment variables the script's `env.get` may read.
+    pub env: EnvAccess,
 }
 
 /// Serializable tool info for the `/tools/list` endpoint.
@@ -100,6 +118,9 @@ pub struct ToolInfo {
     pub builtin: bool,
     /// OpenAI function-calling JSON Schema.
     pub parameters: serde_json::Value,
+    /// MCP behavior hints; omitted when none are set.
+    #[serde(skip_serializing_if = "ToolAnnotations::is_empty")]
+    pub annotations: ToolAnnotations,
 }
 
 // ═══════════════════════════════════════════════════════════════════════
@@ -138,14 +159,19 @@ impl Tool for LuaToolAdapter {
         self.definition.parameters_schema.clone()
     }
 
+    fn annotations(&self) -> ToolAnnotations {
+        self.definition.annotations.clone()
+    }
+
     async fn execute(
         &self,
         params: serde_json::Value,
-        _ctx: &ToolContext,
+        ctx: &ToolContext,
     ) -> Result<serde_json::Value> {
         // Delegate to the existing Lua execution path, which has its own
         // context bridge (search, get, sources) built into the Lua VM.
-        execute_tool(&self.definition, params, &self.config).await
+        // `ctx` carries the registry behind `context.call_tool`.
+        execute_tool(&self.definition, params, &self.config, ctx).await
     }
 }
 
@@ -163,8 +189,10 @@ impl Tool for LuaToolAdapter {
 pub fn load_tool_definitions(config: &Config) -> Result<Vec<ToolDefinition>> {
     let mut tools = Vec::new();
 
+    let registries = RegistryManager::from_config(config);
     for (name, tool_config) in &config.tools.script {
-        let tool_def = load_single_tool(name, tool_config)
+        let trust = registries.script_trust(&tool_config.path);
+        let tool_def = load_single_tool(name, tool_config, &trust)
             .with_context(|| format!("Failed to load tool script '{}'", name))?;
         tools.push(tool_def);
     }
@@ -173,9 +201,17 @@ pub fn load_tool_definitions(config: &Config) -> Result<Vec<ToolDefinition>> {
 }
 
 /// Load a single tool script and extract its definition.
-pub fn load_single_tool(name: &str, tool_config: &ScriptToolConfig) -> Result<ToolDefinition> {
+///
+/// The script must pass `trust`, the checks of the registry it comes from
+/// (see [`crate::registry`]), before it runs.
+pub fn load_single_tool(
+    name: &str,
+    tool_config: &ScriptToolConfig,
+    trust: &ScriptTrust,
+) -> Result<ToolDefinition> {
     let script_src = std::fs::read_to_string(&tool_config.path)
         .with_context(|| format!("Failed to read tool script: {}", tool_config.path.display()))?;
+    trust.verify(script_src.as_bytes())?;
 
     // Create a temporary Lua VM just to extract metadata
     let lua = Lua::new();
@@ -204,15 +240,18 @@ pub fn load_single_tool(name: &str, tool_config: &ScriptToolConfig) -> Result<To
         .unwrap_or_else(|_| lua.create_table().expect("create_table"));
 
     let schema = lua_params_to_json_schema(&params_table)?;
+    let annotations = lua_annotations(&tool_table)?;
 
     Ok(ToolDefinition {
         name: name.to_string(),
         description,
         parameters_schema: schema,
+        annotations,
         script_path: tool_config.path.clone(),
         script_source: script_src,
         config: tool_config.extra.clone(),
         timeout: tool_config.timeout,
+        env: EnvAccess::resolve(tool_config.env_allowlist.as_deref(), trust.installed()),
     })
 }
 
@@ -308,6 +347,57 @@ fn lua_params_to_json_schema(params: &LuaTable) -> Result<serde_json::Value> {
     Ok(serde_json::Value::Object(schema))
 }
 
+/// Read the optional `tool.annotations` table:
+///
+/// ```lua
+/// annotations = { title = "Create ticket", read_only = false, destructive = false, open_world = true }
+/// ```
+fn lua_annotations(tool_table: &LuaTable) -> Result<ToolAnnotations> {
+    const KEYS: &[&str] = &[
+        "title",
+        "read_only",
+        "destructive",
+        "idempotent",
+        "open_world",
+    ];
+    let table = match tool_table.get::<LuaValue>("annotations")? {
+        LuaValue::Nil => return Ok(ToolAnnotations::default()),
+        LuaValue::Table(table) => table,
+        other => bail!(
+            "tool.annotations must be a table, got {}",
+            other.type_name()
+        ),
+    };
+    for pair in table.pairs::<String, LuaValue>() {
+        let (key, _) = pair?;
+        if !KEYS.contains(&key.as_str()) {
+            bail!(
+                "unknown key tool.annotations.{} (expected one of: {})",
+                key,
+                KEYS.join(", ")
+            );
+        }
+    }
+    let flag = |key: &str| -> Result<Option<bool>> {
+        match table.get::<LuaValue>(key)? {
+            LuaValue::Nil => Ok(None),
+            LuaValue::Boolean(b) => Ok(Some(b)),
+            other => bail!(
+                "tool.annotations.{} must be a boolean, got {}",
+                key,
+                other.type_name()
+            ),
+        }
+    };
+    Ok(ToolAnnotations {
+        title: table.get::<Option<String>>("title")?,
+        read_only: flag("read_only")?,
+        destructive: flag("destructive")?,
+        idempotent: flag("idempotent")?,
+        open_world: flag("open_world")?,
+    })
+}
+
 // ═══════════════════════════════════════════════════════════════════════
 // Parameter Validation
 // ═══════════════════════════════════════════════════════════════════════
@@ -424,6 +514,7 @@ fn json_type_name(value: &serde_json::Value) -> &'static str {
 /// * `tool` — tool definition (script source, config, timeout).
 /// * `params` — validated JSON parameters for the tool.
 /// * `app_config` — full application config (needed for context bridge).
+/// * `ctx` — context of the call, used by `context.call_tool`.
 ///
 /// # Returns
 ///
@@ -432,11 +523,13 @@ pub async fn execute_tool(
     tool: &ToolDefinition,
     params: serde_json::Value,
     app_config: &Config,
+    ctx: &ToolContext,
 ) -> Result<serde_json::Value> {
     let tool = tool.clone();
     let config = app_config.clone();
+    let ctx = ctx.clone();
 
-    tokio::task::spawn_blocking(move || run_lua_tool(&tool, params, &config))
+    tokio::task::spawn_blocking(move || run_lua_tool(&tool, params, &config, ctx))
         .await
         .context("Lua tool task panicked")?
 }
@@ -446,6 +539,7 @@ fn run_lua_tool(
     tool: &ToolDefinition,
     params: serde_json::Value,
     config: &Config,
+    ctx: ToolContext,
 ) -> Result<serde_json::Value> {
     let script_dir = tool
         .script_path
@@ -474,10 +568,10 @@ fn run_lua_tool(
 
     // Register all shared host APIs
     let log_name = format!("tool:{}", tool.name);
-    register_all_host_apis(&lua, &log_name, &script_dir)?;
+    register_all_host_apis(&lua, &log_name, &script_dir, &config.db.path, &tool.env)?;
 
     // Register context bridge
-    register_context_bridge(&lua, config, &tool.config)?;
+    register_context_bridge(&lua, config, &tool.config, ctx)?;
 
     // Load and execute the script
     lua.load(&tool.script_source)
@@ -532,10 +626,16 @@ fn run_lua_tool(
 
 /// Register the `context` table in the Lua VM.
 ///
-/// Provides `context.search`, `context.get`, `context.sources`, and
-/// `context.config`. The first three call back into Rust's async core
-/// via `tokio::runtime::Handle::block_on`.
-fn register_context_bridge(lua: &Lua, config: &Config, tool_config: &toml::Table) -> LuaResult<()> {
+/// Provides `context.search`, `context.get`, `context.sources`,
+/// `context.call_tool`, `context.sample`, and `context.config`. The
+/// functions call back into Rust's async core via
+/// `tokio::runtime::Handle::block_on`.
+fn register_context_bridge(
+    lua: &Lua,
+    config: &Config,
+    tool_config: &toml::Table,
+    tool_ctx: ToolContext,
+) -> LuaResult<()> {
     let ctx = lua.create_table()?;
 
     // context.config — tool-specific config from ctx.toml
@@ -601,10 +701,67 @@ fn register_context_bridge(lua: &Lua, config: &Config, tool_config: &toml::Table
         })?,
     )?;
 
+    // context.call_tool(name, params?) → tool result
+    let sample_ctx = tool_ctx.clone();
+    ctx.set(
+        "call_tool",
+        lua.create_function(move |lua, (name, params): (String, Option<LuaValue>)| {
+            let params = match params {
+                Some(LuaValue::Nil) | None => serde_json::json!({}),
+                Some(value) => lua_value_to_json(value)?,
+            };
+            let handle = tokio::runtime::Handle::current();
+            let result = handle
+                .block_on(tool_ctx.call_tool(&name, params))
+                .map_err(mlua::Error::external)?;
+            json_value_to_lua(lua, &result)
+        })?,
+    )?;
+
+    // context.sample(prompt, opts?) → { text, model, stop_reason }
+    ctx.set("sample", sample_function(lua, sample_ctx)?)?;
+
     lua.globals().set("context", ctx)?;
     Ok(())
 }
 
+/// The `context.sample(prompt, opts?)` function for tools and agents.
+///
+/// Options: `system`, `max_tokens` (default 1024), `temperature`, `stop`
+/// (a list of stop sequences), and `model` (a preferred model name, or a
+/// list of them). Returns `{ text, model, stop_reason }`.
+pub(crate) fn sample_function(lua: &Lua, tool_ctx: ToolContext) -> LuaResult<LuaFunction> {
+    lua.create_function(move |lua, (prompt, opts): (String, Option<LuaTable>)| {
+        let mut request = SampleRequest {
+            prompt,
+            max_tokens: DEFAULT_SAMPLE_MAX_TOKENS,
+            ..Default::default()
+        };
+        if let Some(opts) = opts {
+            request.system = opts.get("system")?;
+            if let Some(max_tokens) = opts.get::<Option<u32>>("max_tokens")? {
+                request.max_tokens = max_tokens;
+            }
+            request.temperature = opts.get("temperature")?;
+            request.stop_sequences = opts.get::<Option<Vec<String>>>("stop")?.unwrap_or_default();
+            request.model_hints = match opts.get::<LuaValue>("model")? {
+                LuaValue::Nil => Vec::new(),
+                LuaValue::String(name) => vec![name.to_str()?.to_string()],
+                other => lua.unpack::<Vec<String>>(other)?,
+            };
+        }
+        let handle = tokio::runtime::Handle::current();
+        let response = handle
+            .block_on(tool_ctx.sample(request))
+            .map_err(mlua::Error::external)?;
+        let table = lua.create_table()?;
+        table.set("text", response.text)?;
+        table.set("model", response.model)?;
+        table.set("stop_reason", response.stop_reason)?;
+        Ok(table)
+    })
+}
+
 /// Convert search results to a Lua array table.
 fn search_results_to_lua(lua: &Lua, results: &[SearchResultItem]) -> LuaResult<LuaTable> {
     let table = lua.create_table()?;
@@ -689,12 +846,17 @@ pub fn build_tool_list(lua_tools: &[ToolDefinition]) -> Vec<ToolInfo> {
         name: "search".to_string(),
         description: "Search the knowledge base".to_string(),
         builtin: true,
+        annotations: ToolAnnotations::read_only(),
         parameters: serde_json::json!({
             "type": "object",
             "properties": {
-                "query": { "type": "string", "description": "Search query" },
+                "query": { "type": "string", "description": "Search query; may include modifiers source:<source>, after:<YYYY-MM-DD>, before:<YYYY-MM-DD>, type:<ext> and \"exact phrases\"" },
                 "mode": { "type": "string", "enum": ["keyword", "semantic", "hybrid"], "default": "keyword" },
                 "limit": { "type": "integer", "description": "Max results", "default": 12 },
+                "group_by_parent": { "type": "boolean", "description": "Group results under their parent document", "default": false },
+                "group_by": { "type": "string", "enum": ["path_prefix", "source", "content_type"], "description": "Group results by leading directories, source, or content type" },
+                "prefix_depth": { "type": "integer", "description": "Directories kept in a path_prefix group or facet", "default": 2 },
+                "fuzzy": { "type": "boolean", "description": "Tolerate typos in keyword terms", "default": false },
                 "filters": {
                     "type": "object",
                     "properties": {
@@ -711,25 +873,56 @@ pub fn build_tool_list(lua_tools: &[ToolDefinition]) -> Vec<ToolInfo> {
         name: "get".to_string(),
         description: "Retrieve a document by UUID".to_string(),
         builtin: true,
-        parameters: serde_json::json!({
-            "type": "object",
-            "properties": {
-                "id": { "type": "string", "description": "Document UUID" }
-            },
-            "required": ["id"]
-        }),
+        annotations: ToolAnnotations::read_only(),
+        parameters: crate::traits::get_parameters_schema(),
+    });
+
+    tools.push(ToolInfo {
+        name: "similar".to_string(),
+        description: "Find documents similar to a given document".to_string(),
+        builtin: true,
+        annotations: ToolAnnotations::read_only(),
+        parameters: crate::traits::similar_parameters_schema(),
     });
 
     tools.push(ToolInfo {
         name: "sources".to_string(),
         description: "List connector configuration and health status".to_string(),
         builtin: true,
+        annotations: ToolAnnotations::read_only(),
         parameters: serde_json::json!({
             "type": "object",
             "properties": {}
         }),
     });
 
+    tools.push(ToolInfo {
+        name: "overview".to_string(),
+        description:
+            "Summarize what is indexed: sources, counts, top directories, and recent additions"
+                .to_string(),
+        builtin: true,
+        annotations: ToolAnnotations::read_only(),
+        parameters: crate::traits::overview_parameters_schema(),
+    });
+
+    tools.push(ToolInfo {
+        name: "capabilities".to_string(),
+        description: "Describe the search modes, filters, query syntax, sources, and collections this index supports".to_string(),
+        builtin: true,
+        annotations: ToolAnnotations::read_only(),
+        parameters: crate::traits::capabilities_parameters_schema(),
+    });
+
+    tools.push(ToolInfo {
+        name: "context.assemble".to_string(),
+        description: "Search and return the top chunks as one cited, token-budgeted context block"
+            .to_string(),
+        builtin: true,
+        annotations: ToolAnnotations::read_only(),
+        parameters: crate::traits::context_assemble_parameters_schema(),
+    });
+
     // Lua tools
     for tool in lua_tools {
         tools.push(ToolInfo {
@@ -737,6 +930,7 @@ pub fn build_tool_list(lua_tools: &[ToolDefinition]) -> Vec<ToolInfo> {
             description: tool.description.clone(),
             builtin: false,
             parameters: tool.parameters_schema.clone(),
+            annotations: tool.annotations.clone(),
         });
     }
 
@@ -841,6 +1035,8 @@ pub async fn test_tool(
 ) -> Result<()> {
     let script_src = std::fs::read_to_string(path)
         .with_context(|| format!("Failed to read tool script: {}", path.display()))?;
+    let trust = registry::script_trust(config, path);
+    trust.verify(script_src.as_bytes())?;
 
     let tool_config_extra = if let Some(name) = source {
         config
@@ -852,6 +1048,9 @@ pub async fn test_tool(
     } else {
         toml::Table::new()
     };
+    let env_allowlist = source
+        .and_then(|name| config.tools.script.get(name))
+        .and_then(|sc| sc.env_allowlist.as_deref());
 
     let timeout = source
         .and_then(|name| config.tools.script.get(name))
@@ -875,15 +1074,30 @@ pub async fn test_tool(
         name: name.clone(),
         description: String::new(),
         parameters_schema: serde_json::json!({"type": "object", "properties": {}}),
+        annotations: ToolAnnotations::default(),
         script_path: path.to_path_buf(),
         script_source: script_src,
         config: tool_config_extra,
         timeout,
+        env: EnvAccess::resolve(env_allowlist, trust.installed()),
     };
 
     println!("  ✓ Script loaded");
 
-    let result = execute_tool(&tool_def, params_value, config).await?;
+    // Give the script the same tools it can call under `ctx serve`.
+    let config = Arc::new(config.clone());
+    let mut registry = ToolRegistry::with_builtins();
+    match load_tool_definitions(&config) {
+        Ok(defs) => {
+            for def in defs {
+                registry.register(Box::new(LuaToolAdapter::new(def, config.clone())));
+            }
+        }
+        Err(e) => tracing::warn!("configured tools unavailable to context.call_tool: {:#}", e),
+    }
+    let ctx = ToolContext::new(config.clone()).with_tools(Arc::new(registry));
+
+    let result = execute_tool(&tool_def, params_value, &config, &ctx).await?;
 
     println!("  ✓ Execution completed");
     println!();
@@ -900,7 +1114,22 @@ pub async fn test_tool(
 /// List all configured tools and print their info.
 pub fn list_tools(config: &Config) -> Result<()> {
     let tool_defs = load_tool_definitions(config)?;
-    let tools = build_tool_list(&tool_defs);
+    let mut tools = build_tool_list(&tool_defs);
+    if config.memory.enabled {
+        let builtins = tools.iter().take_while(|t| t.builtin).count();
+        tools.insert(
+            builtins,
+            ToolInfo {
+                name: "memory.append".to_string(),
+                description:
+                    "Store a conversation transcript in the index so later sessions can search it"
+                        .to_string(),
+                builtin: true,
+                parameters: crate::traits::memory_append_parameters_schema(),
+                annotations: crate::traits::MemoryAppendTool.annotations(),
+            },
+        );
+    }
 
     if tools.is_empty() {
         println!("No tools configured.");
@@ -47,14 +47,33 @@
 
 use anyhow::Result;
 use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::BTreeMap;
 use std::sync::Arc;
+pub use tokio_util::sync::CancellationToken;
 
+use crate::assemble::{assemble_context, DEFAULT_MAX_TOKENS};
+use crate::audit::{AuditEntry, AuditLog};
+use crate::auth::SourceScope;
+use crate::capabilities::{capabilities, Capabilities};
 use crate::config::Config;
-use crate::get::{get_document, DocumentResponse};
+use crate::federation::{federated_search, FederatedQuery};
+use crate::get::{get_document, get_documents, DocumentResponse, GetOptions};
+use crate::memory::{MemoryAppend, MemoryEntry};
 use crate::models::SourceItem;
-use crate::search::{search_documents, SearchResultItem};
-use crate::sources::{get_sources, SourceStatus};
+use crate::registry::RegistryManager;
+use crate::response_limits::{limit_document, limit_results, SizeLimits};
+use crate::search::{
+    facets, group_by_facet, group_by_parent, parse_query, search_documents_filtered,
+    search_with_coverage, FacetField, SearchResultItem, DEFAULT_PREFIX_DEPTH,
+};
+use crate::similar::{similar_documents, SimilarQuery};
+use crate::sources::{get_sources, probe_sources, SourceStatus};
+use crate::stats::{collection_overview, Overview};
+use crate::sync_failures::ItemFailure;
+use crate::titles::search_titles;
+use crate::tool_script::validate_params;
 
 // ═══════════════════════════════════════════════════════════════════════
 // Connector Trait
@@ -98,6 +117,9 @@ use crate::sources::{get_sources, SourceStatus};
 ///     }
 /// }
 /// ```
+/// Sending half of the channel a [`Connector::scan_batches`] call feeds.
+pub type ItemSink = tokio::sync::mpsc::Sender<Vec<SourceItem>>;
+
 #[async_trait]
 pub trait Connector: Send + Sync {
     /// Returns the connector instance name (e.g. `"docs"`, `"platform"`).
@@ -137,6 +159,140 @@ pub trait Connector: Send + Sync {
     /// ingestion pipeline. Items with empty `body` or `source_id` are
     /// skipped with a warning.
     async fn scan(&self) -> Result<Vec<SourceItem>>;
+
+    /// Scan the data source, sending items to `sink` in batches as they
+    /// are produced.
+    ///
+    /// The ingest pipeline calls this rather than [`scan`](Connector::scan)
+    /// and ingests each batch while the scan continues. `sink` is bounded,
+    /// so sending waits while earlier batches are still being ingested.
+    ///
+    /// The default implementation sends the result of `scan` as a single
+    /// batch. Override it when items can be produced incrementally, so
+    /// large sources are ingested with bounded memory.
+    async fn scan_batches(&self, sink: ItemSink) -> Result<()> {
+        let items = self.scan().await?;
+        sink.send(items)
+            .await
+            .map_err(|_| anyhow::anyhow!("ingest stopped before the scan finished"))
+    }
+
+    /// Like [`scan_batches`](Connector::scan_batches), stopping early once
+    /// `cancel` fires (Ctrl-C or `[sync] timeout_secs`).
+    ///
+    /// The default implementation drops the scan at its next `.await` and
+    /// returns an error. Override it when the scan does work the runtime
+    /// cannot interrupt, e.g. on a blocking thread, and check
+    /// `cancel.is_cancelled()` between units of work.
+    async fn scan_cancellable(&self, sink: ItemSink, cancel: CancellationToken) -> Result<()> {
+        tokio::select! {
+            result = self.scan_batches(sink) => result,
+            _ = cancel.cancelled() => Err(anyhow::anyhow!("scan cancelled")),
+        }
+    }
+
+    /// Counts of items the last [`scan`](Connector::scan) deliberately
+    /// left out, keyed by reason (e.g. `"binary"`, `"too large"`).
+    ///
+    /// Printed in the sync summary. The default reports nothing.
+    fn skipped(&self) -> BTreeMap<String, u64> {
+        BTreeMap::new()
+    }
+
+    /// Items the last scan found but could not fetch, such as an unreadable
+    /// file or an S3 object whose download ran out of retries.
+    ///
+    /// Reported and recorded like items that fail to ingest, for `ctx sync
+    /// --retry-failed` (see [`crate::sync_failures`]). The default reports
+    /// nothing.
+    fn failed(&self) -> Vec<ItemFailure> {
+        Vec::new()
+    }
+
+    /// Hand the connector the state it saved with its checkpoint at the
+    /// end of the last completed sync (see
+    /// [`checkpoint_state`](Connector::checkpoint_state)).
+    ///
+    /// Called before each scan, with `None` on the first sync and with
+    /// `--full`. The default ignores it.
+    fn resume_from(&self, _state: Option<String>) {}
+
+    /// State to save with the checkpoint once the scan completes, handed
+    /// back through [`resume_from`](Connector::resume_from) on the next
+    /// sync. Lets a connector keep its own cursor (a page token, an API
+    /// timestamp) instead of relying on `updated_at`.
+    ///
+    /// The default saves nothing.
+    fn checkpoint_state(&self) -> Option<String> {
+        None
+    }
+
+    /// Check that the source is reachable with the configured credentials.
+    ///
+    /// Called by `ctx sources` and the `sources` tool with `probe: true`.
+    /// Implementations should make one cheap request (e.g. S3
+    /// `HeadBucket`, `git ls-remote`) and wrap it in
+    /// [`ConnectorHealth::probe`] to record latency and errors.
+    ///
+    /// The default implementation does not probe and reports healthy.
+    async fn health(&self) -> ConnectorHealth {
+        ConnectorHealth::unchecked()
+    }
+}
+
+/// Result of a [`Connector::health`] probe.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct ConnectorHealth {
+    /// Whether the probe succeeded.
+    pub healthy: bool,
+    /// Wall-clock time of the probe; `None` if nothing was probed.
+    pub latency_ms: Option<u64>,
+    /// What was checked on success, or the error on failure.
+    pub message: String,
+}
+
+impl ConnectorHealth {
+    /// Upper bound on a single probe.
+    pub const TIMEOUT: std::time::Duration = std::time::Duration::from_secs(15);
+
+    /// Healthy without a live check, for connectors that don't implement one.
+    pub fn unchecked() -> Self {
+        Self {
+            healthy: true,
+            latency_ms: None,
+            message: "no live check".to_string(),
+        }
+    }
+
+    /// Time `check` and turn its outcome into a health result.
+    ///
+    /// `Ok(detail)` is healthy with `detail` as the message; an error or
+    /// running past [`ConnectorHealth::TIMEOUT`] is unhealthy.
+    pub async fn probe<F>(check: F) -> Self
+    where
+        F: std::future::Future<Output = Result<String>>,
+    {
+        let started = std::time::Instant::now();
+        let outcome = tokio::time::timeout(Self::TIMEOUT, check).await;
+        let latency_ms = Some(started.elapsed().as_millis() as u64);
+        match outcome {
+            Ok(Ok(message)) => Self {
+                healthy: true,
+                latency_ms,
+                message,
+            },
+            Ok(Err(e)) => Self {
+                healthy: false,
+                latency_ms,
+                message: format!("{:#}", e),
+            },
+            Err(_) => Self {
+                healthy: false,
+                latency_ms,
+                message: format!("timed out after {}s", Self::TIMEOUT.as_secs()),
+            },
+        }
+    }
 }
 
 // ═══════════════════════════════════════════════════════════════════════
@@ -201,7 +357,7 @@ pub trait Tool: Send + Sync {
     /// Agents use this to decide whether to call the tool.
     fn description(&self) -> &str;
 
-    /// Whether this tool is a built-in (true for search/get/sources).
+    /// Whether this tool is a built-in (true for search/get/sources/overview/context.assemble).
     ///
     /// Built-in tools are marked with `"builtin": true` in the
     /// `GET /tools/list` response. Defaults to `false`.
@@ -215,6 +371,14 @@ pub trait Tool: Send + Sync {
     /// `properties`, and optionally `required`.
     fn parameters_schema(&self) -> Value;
 
+    /// Behavior hints for MCP clients, listed as the tool's `annotations`.
+    ///
+    /// Defaults to none, which MCP clients read as a tool that may modify
+    /// or delete data.
+    fn annotations(&self) -> ToolAnnotations {
+        ToolAnnotations::default()
+    }
+
     /// Execute the tool with validated parameters.
     ///
     /// Called each time an agent invokes the tool via `POST /tools/{name}`.
@@ -227,10 +391,104 @@ pub trait Tool: Send + Sync {
     /// # Returns
     ///
     /// A JSON value that will be wrapped in `{ "result": ... }` in the
-    /// HTTP response.
+    /// HTTP response. MCP clients get it as one JSON text block, or as
+    /// typed parts when it has the form `{ "content": [...] }` (see
+    /// [`ContentPart`]).
     async fn execute(&self, params: Value, ctx: &ToolContext) -> Result<Value>;
 }
 
+/// Behavior hints a tool declares to MCP clients (`annotations` in
+/// `tools/list`). Clients may use them to decide whether to confirm a call;
+/// nothing enforces them.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ToolAnnotations {
+    /// Human-readable display name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// `readOnlyHint`: the tool does not change anything.
+    #[serde(rename = "readOnlyHint", skip_serializing_if = "Option::is_none")]
+    pub read_only: Option<bool>,
+    /// `destructiveHint`: the tool may delete or overwrite data, rather
+    /// than only add it. Only meaningful when not read-only.
+    #[serde(rename = "destructiveHint", skip_serializing_if = "Option::is_none")]
+    pub destructive: Option<bool>,
+    /// `idempotentHint`: repeating a call with the same arguments has no
+    /// further effect. Only meaningful when not read-only.
+    #[serde(rename = "idempotentHint", skip_serializing_if = "Option::is_none")]
+    pub idempotent: Option<bool>,
+    /// `openWorldHint`: the tool reaches systems outside the index, such
+    /// as a ticketing API.
+    #[serde(rename = "openWorldHint", skip_serializing_if = "Option::is_none")]
+    pub open_world: Option<bool>,
+}
+
+impl ToolAnnotations {
+    /// Hints for a tool that only reads the index.
+    pub fn read_only() -> Self {
+        Self {
+            read_only: Some(true),
+            open_world: Some(false),
+            ..Self::default()
+        }
+    }
+
+    /// Whether no hint is set.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// A typed part of a tool result, as MCP sends them in `tools/call`.
+///
+/// A tool whose result is an object with a `content` array and no other
+/// keys, every element of which is a part, is answered with those parts:
+///
+/// ```json
+/// { "content": [
+///     { "type": "text", "text": "Created OPS-123" },
+///     { "type": "resource_link", "uri": "https://jira.example.com/browse/OPS-123", "name": "OPS-123" }
+/// ] }
+/// ```
+///
+/// Any other result is sent as a single JSON text part.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentPart {
+    /// Plain text.
+    Text { text: String },
+    /// A base64-encoded image.
+    Image {
+        data: String,
+        #[serde(alias = "mimeType")]
+        mime_type: String,
+    },
+    /// A link to a resource the client can fetch or show.
+    ResourceLink {
+        uri: String,
+        name: String,
+        title: Option<String>,
+        description: Option<String>,
+        #[serde(alias = "mimeType")]
+        mime_type: Option<String>,
+    },
+}
+
+impl ContentPart {
+    /// The parts of a tool result in the `{ "content": [...] }` form, or
+    /// `None` for any other result.
+    pub fn parse(result: &Value) -> Option<Vec<ContentPart>> {
+        let object = result.as_object().filter(|o| o.len() == 1)?;
+        let parts = object
+            .get("content")?
+            .as_array()
+            .filter(|p| !p.is_empty())?;
+        parts
+            .iter()
+            .map(|part| serde_json::from_value(part.clone()).ok())
+            .collect()
+    }
+}
+
 // ═══════════════════════════════════════════════════════════════════════
 // ToolContext
 // ═══════════════════════════════════════════════════════════════════════
@@ -246,6 +504,42 @@ pub struct SearchOptions {
     pub source: Option<String>,
 }
 
+/// A completion requested through [`ToolContext::sample`], as
+/// `context.sample(prompt, opts)` does from Lua.
+#[derive(Debug, Clone, Default)]
+pub struct SampleRequest {
+    /// The user message to complete.
+    pub prompt: String,
+    /// System prompt; the client may ignore or change it.
+    pub system: Option<String>,
+    /// Upper bound on generated tokens.
+    pub max_tokens: u32,
+    pub temperature: Option<f64>,
+    pub stop_sequences: Vec<String>,
+    /// Preferred model names, most preferred first. Only hints: the
+    /// client picks the model.
+    pub model_hints: Vec<String>,
+}
+
+/// The completion returned for a [`SampleRequest`].
+#[derive(Debug, Clone)]
+pub struct SampleResponse {
+    pub text: String,
+    /// Name of the model that generated the text.
+    pub model: String,
+    pub stop_reason: Option<String>,
+}
+
+/// Runs [`SampleRequest`]s on a model the server holds no credentials for.
+///
+/// The MCP server implements this with `sampling/createMessage`, so tools
+/// called by an MCP client use the client's model (see
+/// [`crate::mcp`]).
+#[async_trait]
+pub trait Sampler: Send + Sync {
+    async fn sample(&self, request: SampleRequest) -> Result<SampleResponse>;
+}
+
 /// Context bridge for tool execution.
 ///
 /// Provides tools with access to the Context Harness knowledge base
@@ -253,14 +547,84 @@ pub struct SearchOptions {
 ///
 /// All methods delegate to the same core functions used by the CLI
 /// and HTTP server, ensuring tools have identical capabilities.
+///
+/// When the caller's API key is limited to some sources (see
+/// [`crate::auth`]), the context carries its [`SourceScope`] and every
+/// method stays inside it.
+///
+/// With a registry attached ([`with_tools`](ToolContext::with_tools)),
+/// tools can also call other tools through
+/// [`call_tool`](ToolContext::call_tool). With a [`Sampler`] attached
+/// ([`with_sampler`](ToolContext::with_sampler)), they can request
+/// completions through [`sample`](ToolContext::sample).
+#[derive(Clone)]
 pub struct ToolContext {
     config: Arc<Config>,
+    scope: Option<Arc<SourceScope>>,
+    tools: Option<Arc<ToolRegistry>>,
+    audit: Option<Arc<AuditLog>>,
+    caller: Option<String>,
+    sampler: Option<Arc<dyn Sampler>>,
+    depth: usize,
 }
 
 impl ToolContext {
     /// Create a new tool context from the application config.
     pub fn new(config: Arc<Config>) -> Self {
-        Self { config }
+        Self {
+            config,
+            scope: None,
+            tools: None,
+            audit: None,
+            caller: None,
+            sampler: None,
+            depth: 0,
+        }
+    }
+
+    /// Let tools call the tools of `tools` via [`call_tool`](ToolContext::call_tool).
+    pub fn with_tools(mut self, tools: Arc<ToolRegistry>) -> Self {
+        self.tools = Some(tools);
+        self
+    }
+
+    /// Record nested tool calls in `audit` under the outer call's caller.
+    pub fn with_audit(mut self, audit: Option<Arc<AuditLog>>, caller: Option<String>) -> Self {
+        self.audit = audit;
+        self.caller = caller;
+        self
+    }
+
+    /// Let tools request completions via [`sample`](ToolContext::sample).
+    pub fn with_sampler(mut self, sampler: Option<Arc<dyn Sampler>>) -> Self {
+        self.sampler = sampler;
+        self
+    }
+
+    /// Limit the context to the sources of an API key's scope.
+    pub fn with_scope(mut self, scope: Option<Arc<SourceScope>>) -> Self {
+        self.scope = scope;
+        self
+    }
+
+    /// The caller's source scope, or `None` when every source is readable.
+    pub fn scope(&self) -> Option<&SourceScope> {
+        self.scope.as_deref()
+    }
+
+    /// Fail with a `forbidden` error if `source` is outside the scope. A bare
+    /// connector type (`git`) is let through; its results are scoped anyway.
+    fn check_source(&self, source: Option<&str>) -> Result<()> {
+        match (self.scope(), source) {
+            (Some(scope), Some(source)) if source.contains(':') && !scope.allows(source) => {
+                anyhow::bail!(
+                    "{}: source {} is outside the sources this API key may read",
+                    crate::auth::FORBIDDEN,
+                    source
+                )
+            }
+            _ => Ok(()),
+        }
     }
 
     /// Search the knowledge base.
@@ -281,14 +645,19 @@ impl ToolContext {
     /// # }
     /// ```
     pub async fn search(&self, query: &str, opts: SearchOptions) -> Result<Vec<SearchResultItem>> {
-        search_documents(
+        self.check_source(opts.source.as_deref())?;
+        search_documents_filtered(
             &self.config,
             query,
             opts.mode.as_deref().unwrap_or("keyword"),
             opts.source.as_deref(),
             None,
+            None,
             opts.limit,
             false,
+            &[],
+            &[],
+            self.scope().map(SourceScope::patterns),
         )
         .await
     }
@@ -297,14 +666,146 @@ impl ToolContext {
     ///
     /// Equivalent to `POST /tools/get` or `ctx get`.
     pub async fn get(&self, id: &str) -> Result<DocumentResponse> {
-        get_document(&self.config, id).await
+        let doc = get_document(&self.config, id).await?;
+        if let Some(scope) = self.scope() {
+            scope.check_document(id, &doc.source)?;
+        }
+        Ok(doc)
     }
 
     /// List all configured connectors and their status.
     ///
     /// Equivalent to `GET /tools/sources` or `ctx sources`.
     pub fn sources(&self) -> Result<Vec<SourceStatus>> {
-        Ok(get_sources(&self.config))
+        Ok(self.in_scope(get_sources(&self.config)))
+    }
+
+    /// Drop sources outside the scope.
+    fn in_scope(&self, mut sources: Vec<SourceStatus>) -> Vec<SourceStatus> {
+        if let Some(scope) = self.scope() {
+            sources.retain(|s| scope.allows(&s.name));
+        }
+        sources
+    }
+
+    /// Call another registered tool, as `context.call_tool(name, params)`
+    /// does from Lua.
+    ///
+    /// Parameters are validated against the tool's schema as for a client
+    /// call, and the callee runs with this context one level deeper. Calls
+    /// nested more than `[tools] max_call_depth` levels fail, which stops
+    /// runaway recursion. Each call is recorded in the audit log with the
+    /// outer call's caller and transport `tool`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use context_harness::traits::ToolContext;
+    /// # async fn example(ctx: &ToolContext) -> anyhow::Result<()> {
+    /// let hits = ctx
+    ///     .call_tool("search", serde_json::json!({ "query": "rollback", "limit": 3 }))
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn call_tool(&self, name: &str, params: Value) -> Result<Value> {
+        let max_depth = self.config.tools.max_call_depth;
+        if self.depth >= max_depth {
+            anyhow::bail!(
+                "tool call depth limit exceeded calling '{}': tools may nest {} levels ([tools] max_call_depth)",
+                name,
+                max_depth
+            );
+        }
+        let registry = self
+            .tools
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("calling '{}': no tool registry available", name))?;
+        let tool = registry
+            .find(name)
+            .ok_or_else(|| anyhow::anyhow!("no tool registered with name: {}", name))?;
+        if self.scope.is_some() && !tool.is_builtin() {
+            anyhow::bail!(
+                "{}: tool '{}' is not available to API keys limited to some sources",
+                crate::auth::FORBIDDEN,
+                name
+            );
+        }
+        let validated = validate_params(&tool.parameters_schema(), &params)
+            .map_err(|e| anyhow::anyhow!("tool '{}': {}", name, e))?;
+
+        let nested = ToolContext {
+            depth: self.depth + 1,
+            ..self.clone()
+        };
+        let started = std::time::Instant::now();
+        let result = tool.execute(validated, &nested).await;
+
+        if let Some(ref audit) = self.audit {
+            let entry = AuditEntry::new(
+                name,
+                self.caller.clone(),
+                "tool",
+                params,
+                started.elapsed(),
+                result.as_ref().err().map(|e| e.to_string()),
+            );
+            audit.record(entry).await;
+        }
+        result
+    }
+
+    /// Ask the caller's model for a completion, as
+    /// `context.sample(prompt, opts)` does from Lua.
+    ///
+    /// Only available to tools and agents invoked over MCP (`/mcp`) by a
+    /// client that declared the `sampling` capability; the client may ask
+    /// its user to approve or edit the request, or refuse it. Nested tool
+    /// calls share the outer call's sampler.
+    pub async fn sample(&self, request: SampleRequest) -> Result<SampleResponse> {
+        let sampler = self.sampler.as_ref().ok_or_else(|| {
+            anyhow::anyhow!(
+                "sampling is unavailable: it needs a call over MCP from a client that supports sampling"
+            )
+        })?;
+        sampler.sample(request).await
+    }
+
+    /// Summarize the whole corpus: sources, counts, top directories, and
+    /// recent additions.
+    ///
+    /// Equivalent to `POST /tools/overview`.
+    pub async fn overview(&self, limit: usize) -> Result<Overview> {
+        let mut overview = collection_overview(&self.config, limit).await?;
+        if let Some(scope) = self.scope() {
+            overview.retain_sources(|source| scope.allows(source));
+        }
+        Ok(overview)
+    }
+
+    /// Index a conversation transcript under the `[memory]` source.
+    ///
+    /// Equivalent to `POST /tools/memory.append`. Keys limited to some
+    /// sources may only append when their scope includes the memory source.
+    pub async fn append_memory(&self, entry: MemoryEntry) -> Result<MemoryAppend> {
+        if let Some(scope) = self.scope() {
+            if !scope.allows(&self.config.memory.source) {
+                anyhow::bail!(
+                    "{}: source {} is outside the sources this API key may read",
+                    crate::auth::FORBIDDEN,
+                    self.config.memory.source
+                );
+            }
+        }
+        crate::memory::append(&self.config, entry).await
+    }
+
+    /// Describe the search modes, filters, query syntax, sources, and
+    /// collections available to this context.
+    ///
+    /// Equivalent to `POST /tools/capabilities` or `GET /capabilities`.
+    pub async fn capabilities(&self) -> Result<Capabilities> {
+        capabilities(&self.config, self.scope()).await
     }
 }
 
@@ -329,18 +830,33 @@ impl Tool for SearchTool {
         true
     }
 
+    fn annotations(&self) -> ToolAnnotations {
+        ToolAnnotations::read_only()
+    }
+
     fn parameters_schema(&self) -> Value {
         serde_json::json!({
             "type": "object",
             "properties": {
-                "query": { "type": "string", "description": "Search query" },
+                "query": { "type": "string", "description": "Search query; may include modifiers source:<source>, after:<YYYY-MM-DD>, before:<YYYY-MM-DD>, type:<ext> and \"exact phrases\"" },
                 "mode": { "type": "string", "enum": ["keyword", "semantic", "hybrid"], "default": "keyword" },
                 "limit": { "type": "integer", "description": "Max results", "default": 12 },
+                "group_by_parent": { "type": "boolean", "description": "Group results under their parent document", "default": false },
+                "group_by": { "type": "string", "enum": ["path_prefix", "source", "content_type"], "description": "Group results by leading directories, source, or content type" },
+                "prefix_depth": { "type": "integer", "description": "Directories kept in a path_prefix group or facet", "default": 2 },
+                "fuzzy": { "type": "boolean", "description": "Tolerate typos in keyword terms", "default": false },
+                "federated": { "type": "boolean", "description": "Also search the indexes under [federation.indexes] (default: [federation] enabled)" },
+                "scope": { "type": "string", "enum": ["chunks", "titles"], "description": "Search document content, or only titles and headings (one hit per document, keyword mode)", "default": "chunks" },
+                "max_chunks": { "type": "integer", "description": "Return at most this many results; 0 = no limit (default: [server.responses])" },
+                "max_body_bytes": { "type": "integer", "description": "Return at most this many snippet bytes in all; 0 = no limit (default: [server.responses])" },
+                "offset": { "type": "integer", "description": "Skip this many ranked results, e.g. truncation.next_offset", "default": 0 },
                 "filters": {
                     "type": "object",
                     "properties": {
                         "source": { "type": "string", "description": "Filter by connector source" },
-                        "since": { "type": "string", "description": "Only results updated after this date (YYYY-MM-DD)" }
+                        "since": { "type": "string", "description": "Only results updated after this date (YYYY-MM-DD)" },
+                        "as_of": { "type": "string", "description": "Search the corpus as of this date (YYYY-MM-DD); excludes documents updated later" },
+                        "metadata": { "type": "object", "description": "Chunk metadata filters, e.g. {\"heading\": \"install\"}" }
                     }
                 }
             },
@@ -353,23 +869,155 @@ impl Tool for SearchTool {
         if query.trim().is_empty() {
             anyhow::bail!("query must not be empty");
         }
+        // Filter parameters take precedence over inline modifiers.
+        let parsed = parse_query(query);
+        if parsed.text.is_empty() {
+            anyhow::bail!("query has no search terms besides its modifiers");
+        }
+        let query = parsed.text.as_str();
 
         let mode = params["mode"].as_str().unwrap_or("keyword");
         let limit = params["limit"].as_i64().unwrap_or(12);
+        let group_by = params["group_by"]
+            .as_str()
+            .map(FacetField::parse)
+            .transpose()?;
+        let prefix_depth = params["prefix_depth"]
+            .as_u64()
+            .map_or(DEFAULT_PREFIX_DEPTH, |d| d as usize);
 
         let source = params
             .get("filters")
             .and_then(|f| f.get("source"))
-            .and_then(|s| s.as_str());
+            .and_then(|s| s.as_str())
+            .or(parsed.source.as_deref());
         let since = params
             .get("filters")
             .and_then(|f| f.get("since"))
-            .and_then(|s| s.as_str());
-
-        let results =
-            search_documents(&ctx.config, query, mode, source, since, Some(limit), false).await?;
+            .and_then(|s| s.as_str())
+            .or(parsed.since.as_deref());
+        let as_of = params
+            .get("filters")
+            .and_then(|f| f.get("as_of"))
+            .and_then(|s| s.as_str())
+            .or(parsed.as_of.as_deref());
 
-        Ok(serde_json::json!({ "results": results }))
+        let mut chunk_filters: Vec<(String, String)> = params
+            .get("filters")
+            .and_then(|f| f.get("metadata"))
+            .and_then(|m| m.as_object())
+            .map(|m| {
+                m.iter()
+                    .map(|(k, v)| {
+                        let v = v
+                            .as_str()
+                            .map(str::to_string)
+                            .unwrap_or_else(|| v.to_string());
+                        (k.clone(), v)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        chunk_filters.extend(parsed.ref_filter());
+
+        ctx.check_source(source)?;
+        let allowed_sources = ctx.scope().map(SourceScope::patterns);
+
+        let fuzzy = params["fuzzy"].as_bool().unwrap_or(false);
+        let federated = params["federated"]
+            .as_bool()
+            .unwrap_or(ctx.config.federation.enabled);
+        let scope = params["scope"].as_str().unwrap_or("chunks");
+        let mut config = (*ctx.config).clone();
+        config.retrieval.fuzzy |= fuzzy;
+        let (mut results, errors, coverage) = if scope == "titles" {
+            if mode != "keyword" {
+                anyhow::bail!(
+                    "invalid mode '{}' for scope \"titles\" (only \"keyword\" is supported)",
+                    mode
+                );
+            }
+            let results = search_titles(
+                &config,
+                query,
+                source,
+                since,
+                as_of,
+                &parsed.types,
+                Some(limit),
+                allowed_sources,
+            )
+            .await?;
+            (results, Vec::new(), None)
+        } else if scope != "chunks" {
+            anyhow::bail!(
+                "invalid scope '{}' (expected \"chunks\" or \"titles\")",
+                scope
+            );
+        } else if federated {
+            let federated = federated_search(
+                &config,
+                &FederatedQuery {
+                    query: query.to_string(),
+                    mode: mode.to_string(),
+                    source: source.map(str::to_string),
+                    since: since.map(str::to_string),
+                    as_of: as_of.map(str::to_string),
+                    limit: Some(limit),
+                    explain: false,
+                    chunk_filters,
+                    types: parsed.types.clone(),
+                    fuzzy,
+                    allowed_sources: allowed_sources.map(<[String]>::to_vec),
+                },
+            )
+            .await?;
+            (federated.results, federated.errors, None)
+        } else {
+            let outcome = search_with_coverage(
+                &config,
+                query,
+                mode,
+                source,
+                since,
+                as_of,
+                Some(limit),
+                false,
+                &chunk_filters,
+                &parsed.types,
+                allowed_sources,
+            )
+            .await?;
+            (outcome.results, Vec::new(), outcome.coverage)
+        };
+
+        let facets = facets(&results, prefix_depth);
+        let truncation = limit_results(
+            &mut results,
+            &SizeLimits::from_params(&params, &ctx.config.server.responses),
+            params["offset"].as_u64().unwrap_or(0) as usize,
+        );
+        let mut response = if let Some(field) = group_by {
+            serde_json::json!({ "groups": group_by_facet(results, field, prefix_depth) })
+        } else if params["group_by_parent"].as_bool().unwrap_or(false) {
+            serde_json::json!({ "groups": group_by_parent(results) })
+        } else {
+            serde_json::json!({ "results": results })
+        };
+        response["facets"] = serde_json::to_value(facets)?;
+        if let Some(truncation) = truncation {
+            response["truncation"] = serde_json::to_value(truncation)?;
+        }
+        if let Some(coverage) = coverage {
+            response["coverage"] = serde_json::to_value(coverage)?;
+        }
+        if !errors.is_empty() {
+            response["errors"] = errors
+                .into_iter()
+                .map(|(index, error)| serde_json::json!({ "index": index, "error": error }))
+                .collect();
+        }
+        Ok(response)
     }
 }
 
@@ -390,14 +1038,108 @@ impl Tool for GetTool {
         true
     }
 
+    fn annotations(&self) -> ToolAnnotations {
+        ToolAnnotations::read_only()
+    }
+
     fn parameters_schema(&self) -> Value {
-        serde_json::json!({
-            "type": "object",
-            "properties": {
-                "id": { "type": "string", "description": "Document UUID" }
+        get_parameters_schema()
+    }
+
+    async fn execute(&self, params: Value, ctx: &ToolContext) -> Result<Value> {
+        if let Some(ids) = params.get("ids").and_then(|v| v.as_array()) {
+            let ids: Vec<String> = ids
+                .iter()
+                .filter_map(|v| v.as_str().map(str::to_string))
+                .collect();
+            let options = GetOptions {
+                include_chunks: params["include_chunks"].as_bool().unwrap_or(true),
+                max_body_chars: params["max_body_chars"].as_u64().map(|n| n as usize),
+                limits: SizeLimits::from_params(&params, &ctx.config.server.responses),
+            };
+            let mut documents = get_documents(&ctx.config, &ids, &options).await?;
+            if let Some(scope) = ctx.scope() {
+                for item in &mut documents {
+                    let denied = item
+                        .document
+                        .as_ref()
+                        .and_then(|doc| scope.check_document(&item.id, &doc.source).err());
+                    if let Some(err) = denied {
+                        item.document = None;
+                        item.truncated = false;
+                        item.truncation = None;
+                        item.error = Some(err.to_string());
+                    }
+                }
+            }
+            return Ok(serde_json::json!({ "documents": documents }));
+        }
+
+        let id = params["id"].as_str().unwrap_or("");
+        if id.trim().is_empty() {
+            anyhow::bail!("id must not be empty");
+        }
+
+        let mut doc = ctx.get(id).await?;
+        let limits = SizeLimits::from_params(&params, &ctx.config.server.responses);
+        let truncation = limit_document(
+            &mut doc,
+            &limits,
+            params["body_offset"].as_u64().unwrap_or(0) as usize,
+            params["chunk_offset"].as_u64().unwrap_or(0) as usize,
+        );
+        let mut response = serde_json::to_value(&doc)?;
+        if let Some(truncation) = truncation {
+            response["truncation"] = serde_json::to_value(truncation)?;
+        }
+        Ok(response)
+    }
+}
+
+/// JSON Schema for the built-in `get` tool, shared with `build_tool_list`.
+pub(crate) fn get_parameters_schema() -> Value {
+    serde_json::json!({
+        "type": "object",
+        "properties": {
+            "id": { "type": "string", "description": "Document UUID" },
+            "ids": {
+                "type": "array",
+                "items": { "type": "string" },
+                "description": "Batch mode: document UUIDs, returned in order with per-id errors"
             },
-            "required": ["id"]
-        })
+            "include_chunks": { "type": "boolean", "description": "Batch mode: include chunks", "default": true },
+            "max_body_chars": { "type": "integer", "description": "Batch mode: truncate bodies to this many characters" },
+            "max_body_bytes": { "type": "integer", "description": "Return at most this many body bytes; 0 = no limit (default: [server.responses])" },
+            "max_chunks": { "type": "integer", "description": "Return at most this many chunks; 0 = no limit (default: [server.responses])" },
+            "body_offset": { "type": "integer", "description": "Single mode: body byte to start at, e.g. truncation.body.next_offset", "default": 0 },
+            "chunk_offset": { "type": "integer", "description": "Single mode: chunk to start at, e.g. truncation.chunks.next_offset", "default": 0 }
+        }
+    })
+}
+
+/// Built-in "more like this" tool. Delegates to [`similar_documents`].
+pub struct SimilarTool;
+
+#[async_trait]
+impl Tool for SimilarTool {
+    fn name(&self) -> &str {
+        "similar"
+    }
+
+    fn description(&self) -> &str {
+        "Find documents similar to a given document"
+    }
+
+    fn is_builtin(&self) -> bool {
+        true
+    }
+
+    fn annotations(&self) -> ToolAnnotations {
+        ToolAnnotations::read_only()
+    }
+
+    fn parameters_schema(&self) -> Value {
+        similar_parameters_schema()
     }
 
     async fn execute(&self, params: Value, ctx: &ToolContext) -> Result<Value> {
@@ -406,12 +1148,71 @@ impl Tool for GetTool {
             anyhow::bail!("id must not be empty");
         }
 
-        let doc = get_document(&ctx.config, id).await?;
-        Ok(serde_json::to_value(&doc)?)
+        let filter = |key: &str| {
+            params
+                .get("filters")
+                .and_then(|f| f.get(key))
+                .and_then(|s| s.as_str())
+        };
+        let (source, since, as_of) = (filter("source"), filter("since"), filter("as_of"));
+        let chunk_filters: Vec<(String, String)> = params
+            .get("filters")
+            .and_then(|f| f.get("metadata"))
+            .and_then(|m| m.as_object())
+            .map(|m| {
+                m.iter()
+                    .map(|(k, v)| {
+                        let v = v
+                            .as_str()
+                            .map(str::to_string)
+                            .unwrap_or_else(|| v.to_string());
+                        (k.clone(), v)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        ctx.check_source(source)?;
+        let results = similar_documents(
+            &ctx.config,
+            &SimilarQuery {
+                id,
+                source,
+                since,
+                as_of,
+                limit: Some(params["limit"].as_i64().unwrap_or(12)),
+                chunk_filters: &chunk_filters,
+                allowed_sources: ctx.scope().map(SourceScope::patterns),
+            },
+        )
+        .await?;
+        Ok(serde_json::json!({ "results": results }))
     }
 }
 
-/// Built-in sources listing tool. Delegates to [`get_sources`].
+/// JSON Schema for the built-in `similar` tool, shared with `build_tool_list`.
+pub(crate) fn similar_parameters_schema() -> Value {
+    serde_json::json!({
+        "type": "object",
+        "properties": {
+            "id": { "type": "string", "description": "UUID of the document to find neighbours of" },
+            "limit": { "type": "integer", "description": "Max results", "default": 12 },
+            "filters": {
+                "type": "object",
+                "properties": {
+                    "source": { "type": "string", "description": "Filter by connector source" },
+                    "since": { "type": "string", "description": "Only results updated after this date (YYYY-MM-DD)" },
+                    "as_of": { "type": "string", "description": "Only documents last updated on or before this date (YYYY-MM-DD)" },
+                    "metadata": { "type": "object", "description": "Chunk metadata filters, e.g. {\"heading\": \"install\"}" }
+                }
+            }
+        },
+        "required": ["id"]
+    })
+}
+
+/// Built-in sources listing tool. Delegates to [`get_sources`], or to
+/// [`probe_sources`] when called with `probe: true`.
 pub struct SourcesTool;
 
 #[async_trait]
@@ -428,19 +1229,318 @@ impl Tool for SourcesTool {
         true
     }
 
+    fn annotations(&self) -> ToolAnnotations {
+        ToolAnnotations::read_only()
+    }
+
     fn parameters_schema(&self) -> Value {
         serde_json::json!({
             "type": "object",
-            "properties": {}
+            "properties": {
+                "probe": {
+                    "type": "boolean",
+                    "description": "Make a live request to each source and report latency and errors",
+                    "default": false
+                }
+            }
         })
     }
 
+    async fn execute(&self, params: Value, ctx: &ToolContext) -> Result<Value> {
+        let sources = if params["probe"].as_bool().unwrap_or(false) {
+            probe_sources(&ctx.config).await
+        } else {
+            get_sources(&ctx.config)
+        };
+        Ok(serde_json::json!({ "sources": ctx.in_scope(sources) }))
+    }
+}
+
+/// Built-in corpus overview tool. Delegates to [`collection_overview`].
+pub struct OverviewTool;
+
+#[async_trait]
+impl Tool for OverviewTool {
+    fn name(&self) -> &str {
+        "overview"
+    }
+
+    fn description(&self) -> &str {
+        "Summarize what is indexed: sources, counts, top directories, and recent additions"
+    }
+
+    fn is_builtin(&self) -> bool {
+        true
+    }
+
+    fn annotations(&self) -> ToolAnnotations {
+        ToolAnnotations::read_only()
+    }
+
+    fn parameters_schema(&self) -> Value {
+        overview_parameters_schema()
+    }
+
+    async fn execute(&self, params: Value, ctx: &ToolContext) -> Result<Value> {
+        let limit = params["limit"].as_u64().unwrap_or(10).clamp(1, 100) as usize;
+        let overview = ctx.overview(limit).await?;
+        Ok(serde_json::to_value(&overview)?)
+    }
+}
+
+/// Parameter schema for the `overview` tool, shared with `build_tool_list`.
+pub(crate) fn overview_parameters_schema() -> Value {
+    serde_json::json!({
+        "type": "object",
+        "properties": {
+            "limit": {
+                "type": "integer",
+                "description": "Max top directories and recent documents to return",
+                "default": 10
+            }
+        }
+    })
+}
+
+/// Built-in capabilities tool. Delegates to [`ToolContext::capabilities`].
+pub struct CapabilitiesTool;
+
+#[async_trait]
+impl Tool for CapabilitiesTool {
+    fn name(&self) -> &str {
+        "capabilities"
+    }
+
+    fn description(&self) -> &str {
+        "Describe the search modes, filters, query syntax, sources, and collections this index supports"
+    }
+
+    fn is_builtin(&self) -> bool {
+        true
+    }
+
+    fn annotations(&self) -> ToolAnnotations {
+        ToolAnnotations::read_only()
+    }
+
+    fn parameters_schema(&self) -> Value {
+        capabilities_parameters_schema()
+    }
+
     async fn execute(&self, _params: Value, ctx: &ToolContext) -> Result<Value> {
-        let sources = get_sources(&ctx.config);
-        Ok(serde_json::json!({ "sources": sources }))
+        Ok(serde_json::to_value(ctx.capabilities().await?)?)
     }
 }
 
+/// Parameter schema for the `capabilities` tool, shared with `build_tool_list`.
+pub(crate) fn capabilities_parameters_schema() -> Value {
+    serde_json::json!({
+        "type": "object",
+        "properties": {}
+    })
+}
+
+/// Built-in context assembly tool: searches, then joins the top chunks into
+/// one cited block within a token budget via [`assemble_context`].
+///
+/// The dotted name follows the `context.*` namespace; OpenAI function
+/// calling rejects dots, so agents run by `ctx agent run` should not list it.
+pub struct ContextAssembleTool;
+
+#[async_trait]
+impl Tool for ContextAssembleTool {
+    fn name(&self) -> &str {
+        "context.assemble"
+    }
+
+    fn description(&self) -> &str {
+        "Search and return the top chunks as one cited, token-budgeted context block"
+    }
+
+    fn is_builtin(&self) -> bool {
+        true
+    }
+
+    fn annotations(&self) -> ToolAnnotations {
+        ToolAnnotations::read_only()
+    }
+
+    fn parameters_schema(&self) -> Value {
+        context_assemble_parameters_schema()
+    }
+
+    async fn execute(&self, params: Value, ctx: &ToolContext) -> Result<Value> {
+        let query = params["query"].as_str().unwrap_or("");
+        if query.trim().is_empty() {
+            anyhow::bail!("query must not be empty");
+        }
+        let mode = params["mode"].as_str().unwrap_or("keyword");
+        let limit = params["limit"].as_i64().unwrap_or(5);
+        let max_tokens = params["max_tokens"]
+            .as_u64()
+            .unwrap_or(DEFAULT_MAX_TOKENS as u64) as usize;
+        if limit < 1 || max_tokens == 0 {
+            anyhow::bail!("invalid limit or max_tokens (both must be at least 1)");
+        }
+
+        let filter = |key: &str| {
+            params
+                .get("filters")
+                .and_then(|f| f.get(key))
+                .and_then(|s| s.as_str())
+        };
+        let (source, since, as_of) = (filter("source"), filter("since"), filter("as_of"));
+        let chunk_filters: Vec<(String, String)> = params
+            .get("filters")
+            .and_then(|f| f.get("metadata"))
+            .and_then(|m| m.as_object())
+            .map(|m| {
+                m.iter()
+                    .map(|(k, v)| {
+                        let v = v
+                            .as_str()
+                            .map(str::to_string)
+                            .unwrap_or_else(|| v.to_string());
+                        (k.clone(), v)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        ctx.check_source(source)?;
+        let allowed_sources = ctx.scope().map(SourceScope::patterns);
+
+        let mut config = (*ctx.config).clone();
+        config.retrieval.fuzzy |= params["fuzzy"].as_bool().unwrap_or(false);
+        let results = search_documents_filtered(
+            &config,
+            query,
+            mode,
+            source,
+            since,
+            as_of,
+            Some(limit),
+            false,
+            &chunk_filters,
+            &[],
+            allowed_sources,
+        )
+        .await?;
+        let assembled = assemble_context(&config, &results, max_tokens).await?;
+        Ok(serde_json::to_value(&assembled)?)
+    }
+}
+
+/// Parameter schema for the `context.assemble` tool, shared with
+/// `build_tool_list`.
+pub(crate) fn context_assemble_parameters_schema() -> Value {
+    serde_json::json!({
+        "type": "object",
+        "properties": {
+            "query": { "type": "string", "description": "Search query" },
+            "mode": { "type": "string", "enum": ["keyword", "semantic", "hybrid"], "default": "keyword" },
+            "limit": { "type": "integer", "description": "Max chunks to assemble", "default": 5 },
+            "max_tokens": { "type": "integer", "description": "Token budget of the block (estimated at 4 chars/token)", "default": DEFAULT_MAX_TOKENS },
+            "fuzzy": { "type": "boolean", "description": "Tolerate typos in keyword terms", "default": false },
+            "filters": {
+                "type": "object",
+                "properties": {
+                    "source": { "type": "string", "description": "Filter by connector source" },
+                    "since": { "type": "string", "description": "Only results updated after this date (YYYY-MM-DD)" },
+                    "as_of": { "type": "string", "description": "Search the corpus as of this date (YYYY-MM-DD); excludes documents updated later" },
+                    "metadata": { "type": "object", "description": "Chunk metadata filters, e.g. {\"heading\": \"install\"}" }
+                }
+            }
+        },
+        "required": ["query"]
+    })
+}
+
+/// Built-in conversation memory tool, registered when `[memory] enabled`
+/// is set. Delegates to [`ToolContext::append_memory`].
+///
+/// Like `context.assemble`, the dotted name is not usable with OpenAI
+/// function calling.
+pub struct MemoryAppendTool;
+
+#[async_trait]
+impl Tool for MemoryAppendTool {
+    fn name(&self) -> &str {
+        "memory.append"
+    }
+
+    fn description(&self) -> &str {
+        "Store a conversation transcript in the index so later sessions can search it"
+    }
+
+    fn is_builtin(&self) -> bool {
+        true
+    }
+
+    fn annotations(&self) -> ToolAnnotations {
+        // Re-sending a session replaces its document, and retention prunes
+        // old memories.
+        ToolAnnotations {
+            read_only: Some(false),
+            destructive: Some(true),
+            open_world: Some(false),
+            ..ToolAnnotations::default()
+        }
+    }
+
+    fn parameters_schema(&self) -> Value {
+        memory_append_parameters_schema()
+    }
+
+    async fn execute(&self, params: Value, ctx: &ToolContext) -> Result<Value> {
+        let string = |key: &str| params[key].as_str().map(str::to_string);
+        let timestamp = match params["timestamp"].as_str() {
+            Some(ts) => Some(
+                chrono::DateTime::parse_from_rfc3339(ts)
+                    .map_err(|e| anyhow::anyhow!("invalid timestamp '{}': {}", ts, e))?
+                    .with_timezone(&chrono::Utc),
+            ),
+            None => None,
+        };
+        let entry = MemoryEntry {
+            text: params["text"].as_str().unwrap_or("").to_string(),
+            title: string("title"),
+            session: string("session"),
+            client: string("client"),
+            tags: params["tags"]
+                .as_array()
+                .map(|tags| {
+                    tags.iter()
+                        .filter_map(|t| t.as_str().map(str::to_string))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            timestamp,
+            metadata: params["metadata"].as_object().cloned().unwrap_or_default(),
+            dedup: params["dedup"].as_bool(),
+        };
+        Ok(serde_json::to_value(ctx.append_memory(entry).await?)?)
+    }
+}
+
+/// Parameter schema for the `memory.append` tool, shared with `build_tool_list`.
+pub(crate) fn memory_append_parameters_schema() -> Value {
+    serde_json::json!({
+        "type": "object",
+        "properties": {
+            "text": { "type": "string", "description": "Transcript or notes to store" },
+            "title": { "type": "string", "description": "Document title; defaults to the first line" },
+            "session": { "type": "string", "description": "Conversation ID; appending the same session again replaces it" },
+            "client": { "type": "string", "description": "Where the transcript came from, e.g. claude or cursor" },
+            "tags": { "type": "array", "items": { "type": "string" }, "description": "Tags stored in the document metadata" },
+            "timestamp": { "type": "string", "description": "When the conversation happened (RFC 3339); defaults to now" },
+            "metadata": { "type": "object", "description": "Extra metadata stored with the document" },
+            "dedup": { "type": "boolean", "description": "Skip the transcript if its text is already stored; defaults to [memory] dedup" }
+        },
+        "required": ["text"]
+    })
+}
+
 // ═══════════════════════════════════════════════════════════════════════
 // Registries
 // ═══════════════════════════════════════════════════════════════════════
@@ -473,13 +1573,16 @@ impl ConnectorRegistry {
 
     /// Create a registry pre-loaded with all built-in connectors from the config.
     ///
-    /// This resolves all filesystem, git, S3, and script connector instances
+    /// This resolves all filesystem, git, S3, SharePoint, Zendesk, Stack Overflow, and script connector instances
     /// from the TOML config and wraps them as trait objects.
     pub fn from_config(config: &Config) -> Self {
         use crate::connector_fs::FilesystemConnector;
         use crate::connector_git::GitConnector;
         use crate::connector_s3::S3Connector;
         use crate::connector_script::ScriptConnector;
+        use crate::connector_sharepoint::SharePointConnector;
+        use crate::connector_stackoverflow::StackOverflowConnector;
+        use crate::connector_zendesk::ZendeskConnector;
 
         let mut registry = Self::new();
 
@@ -493,14 +1596,41 @@ impl ConnectorRegistry {
             registry.register(Box::new(GitConnector::new(
                 name.clone(),
                 cfg.clone(),
+                config.sync.clone(),
                 config.db.path.clone(),
             )));
         }
         for (name, cfg) in &config.connectors.s3 {
-            registry.register(Box::new(S3Connector::new(name.clone(), cfg.clone())));
+            registry.register(Box::new(S3Connector::new(
+                name.clone(),
+                cfg.clone(),
+                config.sync.clone(),
+                config.db.path.clone(),
+            )));
+        }
+        for (name, cfg) in &config.connectors.sharepoint {
+            registry.register(Box::new(SharePointConnector::new(
+                name.clone(),
+                cfg.clone(),
+            )));
+        }
+        for (name, cfg) in &config.connectors.zendesk {
+            registry.register(Box::new(ZendeskConnector::new(name.clone(), cfg.clone())));
+        }
+        for (name, cfg) in &config.connectors.stackoverflow {
+            registry.register(Box::new(StackOverflowConnector::new(
+                name.clone(),
+                cfg.clone(),
+            )));
         }
-        for (name, cfg) in &config.connectors.script {
-            registry.register(Box::new(ScriptConnector::new(name.clone(), cfg.clone())));
+        if !config.connectors.script.is_empty() {
+            let registries = RegistryManager::from_config(config);
+            for (name, cfg) in &config.connectors.script {
+                registry.register(Box::new(
+                    ScriptConnector::new(name.clone(), cfg.clone(), config.db.path.clone())
+                        .with_trust(registries.script_trust(&cfg.path)),
+                ));
+            }
         }
 
         registry
@@ -553,7 +1683,7 @@ impl Default for ConnectorRegistry {
 /// Registry for tools (built-in, Lua, and custom Rust).
 ///
 /// Use [`ToolRegistry::with_builtins`] to create a registry pre-loaded
-/// with the core `search`, `get`, and `sources` tools, then optionally
+/// with the core `search`, `get`, `similar`, and `sources` tools, then optionally
 /// call [`register`](ToolRegistry::register) to add custom ones.
 ///
 /// # Example
@@ -574,12 +1704,17 @@ impl ToolRegistry {
         Self { tools: Vec::new() }
     }
 
-    /// Create a tool registry pre-loaded with built-in tools (search, get, sources).
+    /// Create a tool registry pre-loaded with built-in tools (search, get,
+    /// similar, sources, overview, capabilities, context.assemble).
     pub fn with_builtins() -> Self {
         let mut registry = Self::new();
         registry.register(Box::new(SearchTool));
         registry.register(Box::new(GetTool));
+        registry.register(Box::new(SimilarTool));
         registry.register(Box::new(SourcesTool));
+        registry.register(Box::new(OverviewTool));
+        registry.register(Box::new(CapabilitiesTool));
+        registry.register(Box::new(ContextAssembleTool));
         registry
     }
 
@@ -0,0 +1,903 @@
+//! `ctx ui`: an interactive terminal dashboard for debugging relevance.
+//!
+//! One screen, four panes:
+//!
+//! | Pane | Shows |
+//! |------|-------|
+//! | Search | Results for the query as it is typed, in keyword or hybrid mode |
+//! | Preview | The selected document chunk by chunk, the matching chunk highlighted |
+//! | Sources | Configured connectors: health, document/chunk/embedding counts, last sync |
+//! | Jobs | Progress and output of a sync or embed started from the dashboard |
+//!
+//! | Key | Action |
+//! |-----|--------|
+//! | typing, Backspace, Ctrl-U | Edit the query (Ctrl-U clears it) |
+//! | Tab | Toggle keyword / hybrid mode |
+//! | Up / Down | Select a result |
+//! | PgUp / PgDn | Scroll the preview |
+//! | Ctrl-S | `ctx sync all` |
+//! | Ctrl-E | `ctx embed pending` |
+//! | Esc, Ctrl-C | Quit (stops a running job) |
+//!
+//! Queries accept the same inline modifiers as `ctx search` (see
+//! [`search::parse_query`]) and run once typing pauses for
+//! [`SEARCH_DEBOUNCE`]. Unlike `ctx search`, they are not recorded for
+//! [`crate::popularity`], so browsing does not skew ranking.
+//!
+//! Sync and embed run as child `ctx` processes with the same config: their
+//! output goes to the Jobs pane instead of over the screen, and `--progress
+//! json` events from sync drive its progress bar. When a job ends, the
+//! Sources pane and the current search are refreshed.
+
+use anyhow::{bail, Context, Result};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Gauge, List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+use sqlx::SqlitePool;
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::time::{Duration, Instant};
+
+use context_harness_core::chunk::HEADING_KEY;
+use context_harness_core::store::Store;
+
+use crate::app_store::SourceStats;
+use crate::config::Config;
+use crate::db;
+use crate::get::DocumentResponse;
+use crate::search::{self, SearchResultItem};
+use crate::sources::{self, SourceStatus};
+use crate::sqlite_store::SqliteStore;
+use crate::stats;
+
+/// How long typing must pause before the query runs.
+pub const SEARCH_DEBOUNCE: Duration = Duration::from_millis(250);
+/// How long to wait for a key before redrawing and polling the running job.
+const TICK: Duration = Duration::from_millis(100);
+/// Job output lines kept for the Jobs pane.
+const LOG_LINES: usize = 500;
+/// Lines moved by PgUp / PgDn in the preview.
+const SCROLL_STEP: usize = 10;
+
+/// Run the dashboard until the user quits.
+///
+/// `config_path` is passed to the `ctx` processes started for jobs.
+///
+/// # Errors
+///
+/// Returns an error if stdin or stdout is not a terminal, or the database
+/// cannot be opened.
+pub async fn run_ui(config: &Config, config_path: Option<&Path>) -> Result<()> {
+    if !atty::is(atty::Stream::Stdout) || !atty::is(atty::Stream::Stdin) {
+        bail!("ctx ui needs an interactive terminal");
+    }
+    let mut app = App::new(config, config_path).await?;
+    let mut terminal = ratatui::init();
+    let result = app.run(&mut terminal).await;
+    ratatui::restore();
+    app.stop_job();
+    db::close_reader(&app.config, app.pool).await;
+    result
+}
+
+/// Search mode toggled with Tab.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Mode {
+    Keyword,
+    Hybrid,
+}
+
+impl Mode {
+    fn as_str(self) -> &'static str {
+        match self {
+            Mode::Keyword => "keyword",
+            Mode::Hybrid => "hybrid",
+        }
+    }
+}
+
+/// One line of the Sources pane.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SourceRow {
+    name: String,
+    /// Health check result; `None` for sources with documents that are no
+    /// longer configured.
+    healthy: Option<bool>,
+    docs: i64,
+    chunks: i64,
+    embedded: i64,
+    last_sync_ts: Option<i64>,
+}
+
+/// The document shown in the Preview pane.
+struct Preview {
+    doc: DocumentResponse,
+    /// Index of the chunk the search result matched.
+    chunk: Option<i64>,
+    /// Scroll to the matching chunk on the next draw.
+    jump: bool,
+}
+
+/// Progress bar state, from a sync's `--progress json` events.
+#[derive(Debug, Clone, PartialEq)]
+struct Progress {
+    label: String,
+    /// Fraction done, when the total is known.
+    ratio: Option<f64>,
+}
+
+/// A sync or embed running as a child process.
+struct Job {
+    label: &'static str,
+    child: Child,
+    /// Lines from the child's stdout and stderr; disconnects when both close.
+    lines: Receiver<String>,
+    started: Instant,
+}
+
+struct App {
+    config: Config,
+    config_path: Option<PathBuf>,
+    pool: SqlitePool,
+    query: String,
+    mode: Mode,
+    /// When the query was last edited, while its search is pending.
+    edited: Option<Instant>,
+    results: Vec<SearchResultItem>,
+    list: ListState,
+    preview: Option<Preview>,
+    scroll: usize,
+    sources: Vec<SourceRow>,
+    totals: String,
+    job: Option<Job>,
+    progress: Option<Progress>,
+    log: VecDeque<String>,
+    status: String,
+}
+
+impl App {
+    async fn new(config: &Config, config_path: Option<&Path>) -> Result<Self> {
+        let mut config = config.clone();
+        config.retrieval.track_access = false;
+        let pool = db::connect_reader(&config).await?;
+        let mut app = Self {
+            config,
+            config_path: config_path.map(Path::to_path_buf),
+            pool,
+            query: String::new(),
+            mode: Mode::Keyword,
+            edited: None,
+            results: Vec::new(),
+            list: ListState::default(),
+            preview: None,
+            scroll: 0,
+            sources: Vec::new(),
+            totals: String::new(),
+            job: None,
+            progress: None,
+            log: VecDeque::new(),
+            status: String::new(),
+        };
+        app.refresh_sources().await;
+        Ok(app)
+    }
+
+    async fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            if event::poll(TICK)? {
+                if let Event::Key(key) = event::read()? {
+                    if key.kind == KeyEventKind::Press && !self.handle_key(key).await {
+                        return Ok(());
+                    }
+                }
+            }
+            self.poll_job().await;
+            if self
+                .edited
+                .is_some_and(|at| at.elapsed() >= SEARCH_DEBOUNCE)
+            {
+                self.search().await;
+            }
+        }
+    }
+
+    /// Apply one key press. Returns `false` to quit.
+    async fn handle_key(&mut self, key: KeyEvent) -> bool {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Esc => return false,
+            KeyCode::Char('c') if ctrl => return false,
+            KeyCode::Char('s') if ctrl => {
+                self.start_job("sync", &["sync", "all", "--progress", "json"])
+            }
+            KeyCode::Char('e') if ctrl => self.start_job("embed", &["embed", "pending"]),
+            KeyCode::Char('u') if ctrl => {
+                self.query.clear();
+                self.edited = Some(Instant::now());
+            }
+            KeyCode::Char(c) if !ctrl => {
+                self.query.push(c);
+                self.edited = Some(Instant::now());
+            }
+            KeyCode::Backspace => {
+                self.query.pop();
+                self.edited = Some(Instant::now());
+            }
+            KeyCode::Enter => self.search().await,
+            KeyCode::Tab => self.toggle_mode().await,
+            KeyCode::Up => self.select(-1).await,
+            KeyCode::Down => self.select(1).await,
+            KeyCode::PageUp => self.scroll = self.scroll.saturating_sub(SCROLL_STEP),
+            KeyCode::PageDown => self.scroll += SCROLL_STEP,
+            _ => {}
+        }
+        true
+    }
+
+    async fn toggle_mode(&mut self) {
+        self.mode = match self.mode {
+            Mode::Keyword if !self.config.embedding.is_enabled() => {
+                self.status = "hybrid search needs an [embedding] provider".to_string();
+                return;
+            }
+            Mode::Keyword => Mode::Hybrid,
+            Mode::Hybrid => Mode::Keyword,
+        };
+        self.search().await;
+    }
+
+    async fn search(&mut self) {
+        self.edited = None;
+        let parsed = search::parse_query(&self.query);
+        let filters: Vec<(String, String)> = parsed.ref_filter().into_iter().collect();
+        let results = search::search_documents_filtered(
+            &self.config,
+            &parsed.text,
+            self.mode.as_str(),
+            parsed.source.as_deref(),
+            parsed.since.as_deref(),
+            parsed.as_of.as_deref(),
+            None,
+            false,
+            &filters,
+            &parsed.types,
+            None,
+        )
+        .await;
+        match results {
+            Ok(results) => {
+                self.results = results;
+                self.status.clear();
+            }
+            Err(e) => {
+                self.results.clear();
+                self.status = format!("search failed: {}", e);
+            }
+        }
+        self.list.select((!self.results.is_empty()).then_some(0));
+        self.load_preview().await;
+    }
+
+    async fn select(&mut self, delta: isize) {
+        let Some(current) = self.list.selected() else {
+            return;
+        };
+        let next = current
+            .saturating_add_signed(delta)
+            .min(self.results.len().saturating_sub(1));
+        if next != current {
+            self.list.select(Some(next));
+            self.load_preview().await;
+        }
+    }
+
+    /// Load the selected result's document, without recording a retrieval.
+    async fn load_preview(&mut self) {
+        self.preview = None;
+        self.scroll = 0;
+        let Some(item) = self.list.selected().and_then(|i| self.results.get(i)) else {
+            return;
+        };
+        let (id, chunk_id) = (item.id.clone(), item.chunk_id.clone());
+        match SqliteStore::new(self.pool.clone()).get_document(&id).await {
+            Ok(Some(doc)) => {
+                let chunk = match chunk_id {
+                    Some(chunk_id) => chunk_index(&self.pool, &chunk_id).await,
+                    None => None,
+                };
+                self.preview = Some(Preview {
+                    doc,
+                    chunk,
+                    jump: true,
+                });
+            }
+            Ok(None) => self.status = format!("document not found: {}", id),
+            Err(e) => self.status = format!("preview failed: {}", e),
+        }
+    }
+
+    async fn refresh_sources(&mut self) {
+        let stats = match stats::collection_overview(&self.config, 0).await {
+            Ok(overview) => {
+                self.totals = format!(
+                    "{} docs · {} chunks · {}% embedded",
+                    overview.total_docs, overview.total_chunks, overview.embedding_coverage
+                );
+                overview.sources
+            }
+            Err(e) => {
+                self.status = format!("could not read stats: {}", e);
+                Vec::new()
+            }
+        };
+        self.sources = source_rows(sources::get_sources(&self.config), stats);
+    }
+
+    fn start_job(&mut self, label: &'static str, args: &[&str]) {
+        if let Some(job) = &self.job {
+            self.status = format!("{} is still running", job.label);
+            return;
+        }
+        match spawn_job(self.config_path.as_deref(), args) {
+            Ok((child, lines)) => {
+                self.log.clear();
+                self.progress = None;
+                self.status = format!("{} started", label);
+                self.job = Some(Job {
+                    label,
+                    child,
+                    lines,
+                    started: Instant::now(),
+                });
+            }
+            Err(e) => self.status = format!("could not start {}: {}", label, e),
+        }
+    }
+
+    /// Drain the running job's output and, once it has exited, refresh.
+    async fn poll_job(&mut self) {
+        let Some(job) = &mut self.job else {
+            return;
+        };
+        loop {
+            match job.lines.try_recv() {
+                Ok(line) => match parse_progress(&line) {
+                    Some(progress) => self.progress = Some(progress),
+                    None => {
+                        if self.log.len() == LOG_LINES {
+                            self.log.pop_front();
+                        }
+                        self.log.push_back(line);
+                    }
+                },
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => break,
+            }
+        }
+
+        // Both pipes closed: the child is exiting.
+        let exit = job.child.wait();
+        let (label, elapsed) = (job.label, job.started.elapsed());
+        self.job = None;
+        self.progress = None;
+        self.status = match exit {
+            Ok(exit) if exit.success() => {
+                format!("{} finished in {:.1}s", label, elapsed.as_secs_f64())
+            }
+            Ok(exit) => format!("{} failed ({})", label, exit),
+            Err(e) => format!("{} failed: {}", label, e),
+        };
+        self.refresh_sources().await;
+        if !self.query.trim().is_empty() {
+            let status = std::mem::take(&mut self.status);
+            self.search().await;
+            if self.status.is_empty() {
+                self.status = status;
+            }
+        }
+    }
+
+    fn stop_job(&mut self) {
+        if let Some(mut job) = self.job.take() {
+            let _ = job.child.kill();
+            let _ = job.child.wait();
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [search_area, main_area, bottom_area, help_area] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Min(8),
+            Constraint::Length(10),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [results_area, preview_area] =
+            Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)])
+                .areas(main_area);
+        let [sources_area, job_area] =
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .areas(bottom_area);
+
+        self.draw_search(frame, search_area);
+        self.draw_results(frame, results_area);
+        self.draw_preview(frame, preview_area);
+        self.draw_sources(frame, sources_area);
+        self.draw_job(frame, job_area);
+        self.draw_help(frame, help_area);
+    }
+
+    fn draw_search(&self, frame: &mut Frame, area: Rect) {
+        let title = format!(" Search · {} ", self.mode.as_str());
+        let input =
+            Paragraph::new(format!("> {}", self.query)).block(Block::bordered().title(title));
+        frame.render_widget(input, area);
+        let cursor = area.x + 3 + self.query.chars().count() as u16;
+        frame.set_cursor_position((cursor.min(area.right().saturating_sub(2)), area.y + 1));
+    }
+
+    fn draw_results(&mut self, frame: &mut Frame, area: Rect) {
+        let items: Vec<ListItem> = self
+            .results
+            .iter()
+            .map(|r| {
+                ListItem::new(Line::from(vec![
+                    Span::styled(format!("{:.3} ", r.score), Style::new().fg(Color::Cyan)),
+                    Span::raw(r.title.clone().unwrap_or_else(|| r.source_id.clone())),
+                    Span::styled(format!("  {}", r.source), Style::new().fg(Color::DarkGray)),
+                ]))
+            })
+            .collect();
+        let list = List::new(items)
+            .block(Block::bordered().title(format!(" Results ({}) ", self.results.len())))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+            .highlight_symbol("▶ ");
+        frame.render_stateful_widget(list, area, &mut self.list);
+    }
+
+    fn draw_preview(&mut self, frame: &mut Frame, area: Rect) {
+        let Some(preview) = &mut self.preview else {
+            let hint = Paragraph::new("Select a result to preview it.")
+                .style(Style::new().fg(Color::DarkGray))
+                .block(Block::bordered().title(" Preview "));
+            frame.render_widget(hint, area);
+            return;
+        };
+        let width = usize::from(area.width.saturating_sub(2));
+        let (lines, chunk_line) = preview_lines(&preview.doc, preview.chunk, width);
+        if preview.jump {
+            self.scroll = chunk_line.unwrap_or(0);
+            preview.jump = false;
+        }
+        self.scroll = self.scroll.min(lines.len().saturating_sub(1));
+        let doc = &preview.doc;
+        let title = format!(
+            " Preview · {} ",
+            doc.title.as_deref().unwrap_or(&doc.source_id)
+        );
+        let paragraph = Paragraph::new(lines)
+            .block(Block::bordered().title(title))
+            .scroll((u16::try_from(self.scroll).unwrap_or(u16::MAX), 0));
+        frame.render_widget(paragraph, area);
+    }
+
+    fn draw_sources(&self, frame: &mut Frame, area: Rect) {
+        let block = Block::bordered().title(format!(" Sources · {} ", self.totals));
+        if self.sources.is_empty() {
+            let hint = Paragraph::new("No connectors configured.")
+                .style(Style::new().fg(Color::DarkGray))
+                .block(block);
+            frame.render_widget(hint, area);
+            return;
+        }
+        let items: Vec<ListItem> = self
+            .sources
+            .iter()
+            .map(|s| {
+                let (mark, color) = match s.healthy {
+                    Some(true) => ("✓", Color::Green),
+                    Some(false) => ("✗", Color::Red),
+                    None => ("-", Color::DarkGray),
+                };
+                let last_sync = s
+                    .last_sync_ts
+                    .map(stats::format_ts_relative)
+                    .unwrap_or_else(|| "never".to_string());
+                ListItem::new(Line::from(vec![
+                    Span::styled(format!("{} ", mark), Style::new().fg(color)),
+                    Span::raw(format!(
+                        "{:<22} {:>6} docs {:>7} chunks {:>7} embedded  ",
+                        s.name, s.docs, s.chunks, s.embedded
+                    )),
+                    Span::styled(last_sync, Style::new().fg(Color::DarkGray)),
+                ]))
+            })
+            .collect();
+        frame.render_widget(List::new(items).block(block), area);
+    }
+
+    fn draw_job(&self, frame: &mut Frame, area: Rect) {
+        let title = match &self.job {
+            Some(job) => format!(
+                " Jobs · {} (running {}s) ",
+                job.label,
+                job.started.elapsed().as_secs()
+            ),
+            None => " Jobs ".to_string(),
+        };
+        let block = Block::bordered().title(title);
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+        if self.job.is_none() && self.log.is_empty() {
+            let hint = Paragraph::new("Ctrl-S syncs all connectors, Ctrl-E embeds pending chunks.")
+                .style(Style::new().fg(Color::DarkGray));
+            frame.render_widget(hint, inner);
+            return;
+        }
+
+        let [progress_area, log_area] =
+            Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(inner);
+        match &self.progress {
+            Some(Progress {
+                label,
+                ratio: Some(ratio),
+            }) => {
+                let gauge = Gauge::default()
+                    .gauge_style(Style::new().fg(Color::Green))
+                    .ratio(*ratio)
+                    .label(label.as_str());
+                frame.render_widget(gauge, progress_area);
+            }
+            Some(Progress { label, ratio: None }) => {
+                frame.render_widget(Paragraph::new(label.as_str()), progress_area);
+            }
+            None => {}
+        }
+        let shown = usize::from(log_area.height);
+        let lines: Vec<Line> = self
+            .log
+            .iter()
+            .skip(self.log.len().saturating_sub(shown))
+            .map(|line| Line::raw(line.as_str()))
+            .collect();
+        frame.render_widget(Paragraph::new(lines), log_area);
+    }
+
+    fn draw_help(&self, frame: &mut Frame, area: Rect) {
+        let key = Style::new().add_modifier(Modifier::BOLD);
+        let mut spans = Vec::new();
+        for (keys, action) in [
+            ("Tab", "mode"),
+            ("↑↓", "select"),
+            ("PgUp/PgDn", "scroll"),
+            ("^S", "sync"),
+            ("^E", "embed"),
+            ("Esc", "quit"),
+        ] {
+            spans.push(Span::styled(format!(" {}", keys), key));
+            spans.push(Span::raw(format!(" {} ", action)));
+        }
+        if !self.status.is_empty() {
+            spans.push(Span::styled(
+                format!(" {}", self.status),
+                Style::new().fg(Color::Yellow),
+            ));
+        }
+        frame.render_widget(Paragraph::new(Line::from(spans)), area);
+    }
+}
+
+/// Start `ctx <args>` with the dashboard's config, streaming its stdout and
+/// stderr lines.
+fn spawn_job(config_path: Option<&Path>, args: &[&str]) -> Result<(Child, Receiver<String>)> {
+    let exe = std::env::current_exe().context("could not locate the ctx binary")?;
+    let mut command = Command::new(exe);
+    if let Some(path) = config_path {
+        command.arg("--config").arg(path);
+    }
+    let mut child = command
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    let (tx, rx) = mpsc::channel();
+    let stdout = child
+        .stdout
+        .take()
+        .map(|s| Box::new(s) as Box<dyn Read + Send>);
+    let stderr = child
+        .stderr
+        .take()
+        .map(|s| Box::new(s) as Box<dyn Read + Send>);
+    for pipe in [stdout, stderr].into_iter().flatten() {
+        let tx = tx.clone();
+        std::thread::spawn(move || {
+            for line in BufReader::new(pipe).lines().map_while(Result::ok) {
+                if tx.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+    Ok((child, rx))
+}
+
+/// Parse a `--progress json` line from sync (see [`crate::progress::JsonProgress`]).
+fn parse_progress(line: &str) -> Option<Progress> {
+    let event: serde_json::Value = serde_json::from_str(line).ok()?;
+    if event["event"] != "progress" {
+        return None;
+    }
+    let connector = event["connector"].as_str()?;
+    match event["phase"].as_str()? {
+        "discovering" => Some(Progress {
+            label: format!("{}  discovering...", connector),
+            ratio: None,
+        }),
+        "ingesting" => {
+            let n = event["n"].as_u64()?;
+            let total = event["total"].as_u64()?;
+            let embedded = event["embedded"]
+                .as_u64()
+                .map(|e| format!(", {} chunks embedded", e))
+                .unwrap_or_default();
+            let ratio = if total == 0 {
+                1.0
+            } else {
+                (n as f64 / total as f64).min(1.0)
+            };
+            Some(Progress {
+                label: format!("{}  {} / {} items{}", connector, n, total, embedded),
+                ratio: Some(ratio),
+            })
+        }
+        _ => None,
+    }
+}
+
+/// The `chunk_index` of a chunk, by id.
+async fn chunk_index(pool: &SqlitePool, chunk_id: &str) -> Option<i64> {
+    sqlx::query_scalar("SELECT chunk_index FROM chunks WHERE id = ?")
+        .bind(chunk_id)
+        .fetch_optional(pool)
+        .await
+        .ok()
+        .flatten()
+}
+
+/// Merge connector health with per-source counts: configured connectors
+/// first, in config order, then sources that only have stored documents.
+fn source_rows(statuses: Vec<SourceStatus>, mut stats: Vec<SourceStats>) -> Vec<SourceRow> {
+    let mut rows = Vec::new();
+    for status in statuses {
+        let counts = stats
+            .iter()
+            .position(|s| s.source == status.name)
+            .map(|i| stats.remove(i));
+        rows.push(SourceRow {
+            healthy: Some(status.healthy),
+            docs: counts.as_ref().map_or(0, |c| c.doc_count),
+            chunks: counts.as_ref().map_or(0, |c| c.chunk_count),
+            embedded: counts.as_ref().map_or(0, |c| c.embedded_count),
+            last_sync_ts: counts.and_then(|c| c.last_sync_ts),
+            name: status.name,
+        });
+    }
+    rows.extend(stats.into_iter().map(|s| SourceRow {
+        name: s.source,
+        healthy: None,
+        docs: s.doc_count,
+        chunks: s.chunk_count,
+        embedded: s.embedded_count,
+        last_sync_ts: s.last_sync_ts,
+    }));
+    rows
+}
+
+/// Lines of the Preview pane, wrapped to `width`: each chunk under a rule
+/// naming its position and heading. With `highlight`, that chunk's rule is
+/// emphasized and the other chunks are dimmed. Also returns the line where
+/// the highlighted chunk starts.
+fn preview_lines(
+    doc: &DocumentResponse,
+    highlight: Option<i64>,
+    width: usize,
+) -> (Vec<Line<'static>>, Option<usize>) {
+    let mut lines = Vec::new();
+    let mut highlight_at = None;
+    if doc.chunks.is_empty() {
+        for line in doc.body.lines() {
+            lines.extend(wrap(line, width).into_iter().map(Line::raw));
+        }
+        return (lines, None);
+    }
+
+    let total = doc.chunks.len();
+    for chunk in &doc.chunks {
+        let matched = highlight == Some(chunk.index);
+        if matched {
+            highlight_at = Some(lines.len());
+        }
+        let mut label = format!("── chunk {}/{} ", chunk.index + 1, total);
+        if let Some(heading) = chunk.metadata.get(HEADING_KEY).and_then(|h| h.as_str()) {
+            label.push_str(&format!("· {} ", heading));
+        }
+        let fill = width.saturating_sub(label.chars().count());
+        label.push_str(&"─".repeat(fill));
+        let (rule_style, text_style) = match (highlight, matched) {
+            (_, true) => (
+                Style::new().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+                Style::new(),
+            ),
+            (Some(_), false) => (
+                Style::new().fg(Color::DarkGray),
+                Style::new().add_modifier(Modifier::DIM),
+            ),
+            (None, false) => (Style::new().fg(Color::DarkGray), Style::new()),
+        };
+        lines.push(Line::styled(label, rule_style));
+        for line in chunk.text.lines() {
+            lines.extend(
+                wrap(line, width)
+                    .into_iter()
+                    .map(|piece| Line::styled(piece, text_style)),
+            );
+        }
+    }
+    (lines, highlight_at)
+}
+
+/// Hard-wrap `line` every `width` characters.
+fn wrap(line: &str, width: usize) -> Vec<String> {
+    if width == 0 || line.is_empty() {
+        return vec![line.to_string()];
+    }
+    let chars: Vec<char> = line.chars().collect();
+    chars.chunks(width).map(|c| c.iter().collect()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use context_harness_core::store::ChunkResponse;
+
+    fn doc(chunks: &[(&str, Option<&str>)]) -> DocumentResponse {
+        DocumentResponse {
+            id: "doc-1".to_string(),
+            source: "filesystem:docs".to_string(),
+            source_id: "guide.md".to_string(),
+            source_url: None,
+            title: Some("Guide".to_string()),
+            author: None,
+            created_at: String::new(),
+            updated_at: String::new(),
+            content_type: "text/markdown".to_string(),
+            body: String::new(),
+            summary: None,
+            metadata: serde_json::json!({}),
+            chunks: chunks
+                .iter()
+                .enumerate()
+                .map(|(i, (text, heading))| {
+                    let mut metadata = serde_json::Map::new();
+                    if let Some(heading) = heading {
+                        metadata.insert(HEADING_KEY.to_string(), serde_json::json!(heading));
+                    }
+                    ChunkResponse {
+                        index: i as i64,
+                        text: text.to_string(),
+                        metadata,
+                    }
+                })
+                .collect(),
+            parent_id: None,
+            children: Vec::new(),
+        }
+    }
+
+    fn text(line: &Line) -> String {
+        line.spans.iter().map(|s| s.content.as_ref()).collect()
+    }
+
+    #[test]
+    fn preview_marks_chunk_boundaries_and_the_match() {
+        let doc = doc(&[
+            ("Intro text", None),
+            (
+                "Install with cargo\nthen run ctx init",
+                Some("Guide > Install"),
+            ),
+        ]);
+        let (lines, at) = preview_lines(&doc, Some(1), 30);
+        let rendered: Vec<String> = lines.iter().map(text).collect();
+        assert_eq!(rendered[0], format!("── chunk 1/2 {}", "─".repeat(17)));
+        assert_eq!(rendered[1], "Intro text");
+        assert_eq!(rendered[2], "── chunk 2/2 · Guide > Install ");
+        assert_eq!(&rendered[3..], ["Install with cargo", "then run ctx init"]);
+        assert_eq!(at, Some(2));
+        assert_eq!(lines[2].style.fg, Some(Color::Yellow));
+        assert!(lines[1].style.add_modifier.contains(Modifier::DIM));
+
+        let (lines, at) = preview_lines(&doc, None, 30);
+        assert_eq!(at, None);
+        assert!(!lines[1].style.add_modifier.contains(Modifier::DIM));
+    }
+
+    #[test]
+    fn wrap_splits_long_lines() {
+        assert_eq!(wrap("abcdefg", 3), ["abc", "def", "g"]);
+        assert_eq!(wrap("", 3), [""]);
+        assert_eq!(wrap("héllo", 0), ["héllo"]);
+    }
+
+    #[test]
+    fn parses_sync_progress_events() {
+        let progress = parse_progress(
+            r#"{"event":"progress","connector":"git:platform","phase":"ingesting","n":5,"total":20,"embedded":12}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            progress.label,
+            "git:platform  5 / 20 items, 12 chunks embedded"
+        );
+        assert_eq!(progress.ratio, Some(0.25));
+
+        let progress = parse_progress(
+            r#"{"event":"progress","connector":"git:platform","phase":"discovering"}"#,
+        )
+        .unwrap();
+        assert_eq!(progress.ratio, None);
+
+        assert_eq!(parse_progress("sync git:platform"), None);
+        assert_eq!(parse_progress(r#"{"event":"summary"}"#), None);
+    }
+
+    #[test]
+    fn source_rows_merge_health_and_counts() {
+        let status = |name: &str, healthy| SourceStatus {
+            name: name.to_string(),
+            configured: true,
+            healthy,
+            notes: None,
+            latency_ms: None,
+            error: None,
+            sync: None,
+        };
+        let counts = |source: &str, docs| SourceStats {
+            source: source.to_string(),
+            doc_count: docs,
+            chunk_count: docs * 2,
+            embedded_count: docs,
+            last_sync_ts: Some(1_700_000_000),
+        };
+        let rows = source_rows(
+            vec![
+                status("filesystem:docs", true),
+                status("git:platform", false),
+            ],
+            vec![counts("script:old", 3), counts("filesystem:docs", 10)],
+        );
+        let summary: Vec<(&str, Option<bool>, i64)> = rows
+            .iter()
+            .map(|r| (r.name.as_str(), r.healthy, r.docs))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("filesystem:docs", Some(true), 10),
+                ("git:platform", Some(false), 0),
+                ("script:old", None, 3),
+            ]
+        );
+        assert_eq!(rows[1].last_sync_ts, None);
+    }
+}
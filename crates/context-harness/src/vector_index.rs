@@ -4,6 +4,7 @@
 //! embedding metadata. A [`VectorIndex`] only retrieves vector candidates for
 //! semantic search; core hybrid scoring still consumes [`ChunkCandidate`]s.
 
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 #[cfg(feature = "zvec-bundled")]
@@ -206,9 +207,10 @@ impl VectorIndexRouter {
             match primary.search(query_vec, options).await {
                 Ok(candidates) => return Ok(candidates),
                 Err(err) if self.fallback.is_some() => {
-                    eprintln!(
-                        "Warning: vector index '{}' failed; falling back to SQLite: {}",
-                        self.backend, err
+                    tracing::warn!(
+                        "vector index '{}' failed; falling back to SQLite: {}",
+                        self.backend,
+                        err
                     );
                 }
                 Err(err) => return Err(err),
@@ -283,6 +285,13 @@ impl Store for VectorIndexedStore {
         self.sqlite.get_document_metadata(id).await
     }
 
+    async fn get_chunk_metadata(
+        &self,
+        chunk_id: &str,
+    ) -> Result<Option<serde_json::Map<String, serde_json::Value>>> {
+        self.sqlite.get_chunk_metadata(chunk_id).await
+    }
+
     async fn keyword_search(
         &self,
         query: &str,
@@ -313,13 +322,21 @@ impl Store for VectorIndexedStore {
             )
             .await
     }
+
+    async fn unembedded_chunks(&self, chunk_ids: &[String]) -> Result<HashSet<String>> {
+        self.sqlite.unembedded_chunks(chunk_ids).await
+    }
+
+    async fn chunk_indexes(&self, chunk_ids: &[String]) -> Result<HashMap<String, i64>> {
+        self.sqlite.chunk_indexes(chunk_ids).await
+    }
 }
 
 pub async fn configured_vector_store(
     config: &Config,
     pool: SqlitePool,
 ) -> Result<VectorIndexedStore> {
-    let sqlite = SqliteStore::new(pool.clone());
+    let sqlite = SqliteStore::new(pool.clone()).with_retrieval(&config.retrieval);
     let router = configured_vector_index(config, pool).await?;
     Ok(VectorIndexedStore::new(sqlite, router))
 }
@@ -329,7 +346,11 @@ pub async fn configured_vector_index(
     pool: SqlitePool,
 ) -> Result<VectorIndexRouter> {
     let backend = config.vector_index.backend.as_str();
-    let sqlite_fallback = || BruteForceSqliteVectorIndex::new(SqliteStore::new(pool.clone()));
+    let sqlite_fallback = || {
+        BruteForceSqliteVectorIndex::new(
+            SqliteStore::new(pool.clone()).with_document_routing(&config.embedding),
+        )
+    };
     let fallback = match config.vector_index.fallback.as_str() {
         "sqlite" => Some(sqlite_fallback()),
         _ => None,
@@ -365,7 +386,7 @@ async fn configured_auto_zvec(
             backend: "zvec".to_string(),
         }),
         Err(err) => {
-            eprintln!("Warning: zvec unavailable; using SQLite vector fallback: {err}");
+            tracing::warn!("zvec unavailable; using SQLite vector fallback: {err}");
             Ok(VectorIndexRouter {
                 primary: None,
                 fallback,
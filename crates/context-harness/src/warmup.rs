@@ -0,0 +1,116 @@
+//! Database warm-up for `ctx serve mcp`: `[db] warm_up`.
+//!
+//! A freshly started server answers its first queries from disk: the FTS5
+//! index and the vector table are read page by page as queries touch them.
+//! With `[db] warm_up = true`, [`warm_up`] reads those tables once before
+//! the server starts listening and runs the keyword-search and metadata
+//! statements once, so they are prepared on a pooled connection.
+//!
+//! The pages land in the OS page cache, in the memory-mapped region when
+//! `[db] profile = "read-heavy"` enables `mmap_size`, and in the page cache
+//! of the connection that read them. Only the shared pool of `[db]
+//! read_pool = true` keeps that connection (and its prepared statements)
+//! for later requests; without it, the OS cache still helps.
+
+use anyhow::Result;
+use std::time::{Duration, Instant};
+
+use context_harness_core::store::Store;
+
+use crate::config::Config;
+use crate::db;
+use crate::sqlite_store::SqliteStore;
+
+/// Tables read by [`warm_up`], with the column holding their data.
+const WARM_TABLES: [(&str, &str); 5] = [
+    ("chunks_fts_data", "block"),
+    ("chunks_fts_idx", "term"),
+    ("titles_fts_data", "block"),
+    ("chunk_vectors", "embedding"),
+    ("document_vectors", "embedding"),
+];
+
+/// What [`warm_up`] read.
+#[derive(Debug, Clone, Default)]
+pub struct WarmUpReport {
+    /// Tables that exist and were read.
+    pub tables: usize,
+    /// Bytes of index and vector data read.
+    pub bytes: u64,
+    pub elapsed: Duration,
+}
+
+/// Read the FTS and vector tables and prepare the search statements.
+///
+/// # Errors
+///
+/// Returns an error if the database can't be opened or read.
+pub async fn warm_up(config: &Config) -> Result<WarmUpReport> {
+    let started = Instant::now();
+    let pool = db::connect_reader(config).await?;
+    let mut report = WarmUpReport::default();
+
+    for (table, column) in WARM_TABLES {
+        let exists: bool = sqlx::query_scalar(
+            "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?)",
+        )
+        .bind(table)
+        .fetch_one(&pool)
+        .await?;
+        if !exists {
+            continue;
+        }
+        // The CAST loads each value; `LENGTH(column)` alone can skip
+        // overflow pages.
+        let bytes: i64 = sqlx::query_scalar(&format!(
+            "SELECT COALESCE(SUM(LENGTH(CAST({} AS BLOB))), 0) FROM {}",
+            column, table
+        ))
+        .fetch_one(&pool)
+        .await?;
+        report.tables += 1;
+        report.bytes += bytes.max(0) as u64;
+    }
+
+    let store = SqliteStore::new(pool.clone()).with_retrieval(&config.retrieval);
+    store.keyword_search("warm up", 1, None, None).await?;
+    store.get_document_metadata("").await?;
+
+    db::close_reader(config, pool).await;
+    report.elapsed = started.elapsed();
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_store::SqliteAppStore;
+
+    #[tokio::test]
+    async fn warm_up_reads_existing_tables() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let mut config = Config::minimal();
+        config.db.path = tmp.path().join("ctx.sqlite");
+        SqliteAppStore::initialize_config(&config).await.unwrap();
+
+        let pool = db::connect(&config).await.unwrap();
+        let mut conn = pool.acquire().await.unwrap();
+        sqlx::query("PRAGMA foreign_keys = OFF")
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+        sqlx::query(
+            "INSERT INTO chunk_vectors (chunk_id, document_id, embedding) VALUES ('c1', 'd1', ?)",
+        )
+        .bind(vec![0u8; 64])
+        .execute(&mut *conn)
+        .await
+        .unwrap();
+        drop(conn);
+        pool.close().await;
+
+        let report = warm_up(&config).await.unwrap();
+        assert!(report.tables >= 3, "{:?}", report);
+        assert!(report.bytes >= 64, "{:?}", report);
+    }
+}
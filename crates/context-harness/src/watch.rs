@@ -0,0 +1,164 @@
+//! `ctx watch`: keep filesystem connectors in sync while files change.
+//!
+//! Polls each watched connector's files every `--interval` seconds, reading
+//! only their size and modification time (see [`connector_fs::list_files`]):
+//!
+//! | Change | Action |
+//! |--------|--------|
+//! | File added or modified | Full sync of the connector ([`ingest::run_sync`]) |
+//! | File removed | Its document, chunks, FTS rows, and vectors are deleted at once ([`ingest::delete_source_items`]) |
+//!
+//! Removing an archive deletes the documents of all its members. Each
+//! deletion is recorded in the `[tools.audit]` log.
+//!
+//! On start, the connectors are synced and documents whose files were
+//! removed while nothing was watching are deleted. A poll that fails is
+//! logged and retried on the next one. Runs until Ctrl-C.
+//!
+//! Only filesystem connectors can be watched: other connectors have no
+//! cheap way to notice changes, and keep being synced by `ctx sync`.
+
+use anyhow::{bail, Result};
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::Duration;
+
+use crate::archive;
+use crate::config::{Config, FilesystemConnectorConfig};
+use crate::connector_fs::{self, FileStamp};
+use crate::db;
+use crate::ingest;
+use crate::sqlite_store::SqliteStore;
+
+/// Files of one connector as of the last poll, by `source_id`.
+type Snapshot = BTreeMap<String, FileStamp>;
+
+/// Watch the filesystem connectors matching `connector` (`all`,
+/// `filesystem`, or `filesystem:<name>`) until Ctrl-C.
+///
+/// # Errors
+///
+/// Returns an error if `connector` matches no filesystem connector, or if
+/// the initial sync or cleanup fails.
+pub async fn run_watch(config: &Config, connector: &str, interval: Duration) -> Result<()> {
+    let targets = resolve_targets(config, connector)?;
+
+    let mut seen: BTreeMap<String, Snapshot> = BTreeMap::new();
+    for (label, fs_config) in &targets {
+        ingest::run_sync(config, label, false, false, None, None, None, None).await?;
+        let files = connector_fs::list_files(fs_config)?;
+        let stored = stored_files(config, label).await?;
+        let gone: Vec<String> = stored
+            .into_iter()
+            .filter(|id| !files.contains_key(id))
+            .collect();
+        delete_removed(config, label, &gone).await?;
+        seen.insert(label.clone(), files);
+    }
+
+    let labels: Vec<&str> = targets.iter().map(|(label, _)| label.as_str()).collect();
+    println!(
+        "Watching {} every {}s (Ctrl-C to stop)",
+        labels.join(", "),
+        interval.as_secs()
+    );
+
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {}
+            _ = tokio::signal::ctrl_c() => break,
+        }
+        for (label, fs_config) in &targets {
+            let previous = seen.get(label).cloned().unwrap_or_default();
+            match poll(config, label, fs_config, &previous).await {
+                Ok(files) => {
+                    seen.insert(label.clone(), files);
+                }
+                Err(e) => tracing::warn!("watch {}: {:#}", label, e),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Compare a connector's files with the previous poll: delete the
+/// documents of removed files and sync if anything was added or modified.
+async fn poll(
+    config: &Config,
+    label: &str,
+    fs_config: &FilesystemConnectorConfig,
+    previous: &Snapshot,
+) -> Result<Snapshot> {
+    let files = connector_fs::list_files(fs_config)?;
+    let removed: Vec<String> = previous
+        .keys()
+        .filter(|id| !files.contains_key(*id))
+        .cloned()
+        .collect();
+    let changed = files
+        .iter()
+        .any(|(id, stamp)| previous.get(id) != Some(stamp));
+
+    delete_removed(config, label, &removed).await?;
+    if changed {
+        // Full sync: the checkpoint has second resolution, so an edit made
+        // in the same second as the last sync would be skipped.
+        ingest::run_sync(config, label, true, false, None, None, None, None).await?;
+    }
+    Ok(files)
+}
+
+/// Delete the documents of `removed` files and print a summary.
+async fn delete_removed(config: &Config, label: &str, removed: &[String]) -> Result<()> {
+    if removed.is_empty() {
+        return Ok(());
+    }
+    let deleted = ingest::delete_source_items(config, label, removed).await?;
+    println!("watch {}", label);
+    println!("  removed files: {}", removed.len());
+    println!("  deleted documents: {}", deleted);
+    Ok(())
+}
+
+/// Files with documents in the database for `label`; an archive's members
+/// count as the archive file.
+async fn stored_files(config: &Config, label: &str) -> Result<BTreeSet<String>> {
+    let pool = db::connect(config).await?;
+    let source_ids = SqliteStore::new(pool.clone()).source_ids(label).await?;
+    pool.close().await;
+    Ok(source_ids
+        .into_iter()
+        .map(|id| match id.split_once(archive::SEPARATOR) {
+            Some((file, _)) => file.to_string(),
+            None => id,
+        })
+        .collect())
+}
+
+/// Expand the connector argument into `(label, config)` pairs.
+fn resolve_targets(
+    config: &Config,
+    connector: &str,
+) -> Result<Vec<(String, FilesystemConnectorConfig)>> {
+    let filesystem = &config.connectors.filesystem;
+    let mut names: Vec<&String> = match connector {
+        "all" | "filesystem" => filesystem.keys().collect(),
+        other => match other.split_once(':') {
+            Some(("filesystem", name)) => match filesystem.get_key_value(name) {
+                Some((name, _)) => vec![name],
+                None => bail!("No filesystem connector '{}'.", name),
+            },
+            _ => bail!(
+                "Cannot watch '{}': only filesystem connectors can be watched. Use: all, filesystem, or filesystem:name",
+                other
+            ),
+        },
+    };
+    if names.is_empty() {
+        bail!("No filesystem connectors configured.");
+    }
+    names.sort();
+    Ok(names
+        .into_iter()
+        .map(|name| (format!("filesystem:{}", name), filesystem[name].clone()))
+        .collect())
+}
@@ -1,13 +1,16 @@
 use chrono::Utc;
 use context_harness::app_store::{AppStore, SqliteAppStore};
-use context_harness::chunk::chunk_text;
+use context_harness::chunk::{annotate_chunks, chunk_text, chunk_text_with, ChunkStrategy};
 use context_harness::config::Config;
 use context_harness::models::{Document, SourceItem};
 use context_harness::sqlite_store::SqliteStore;
+use context_harness::stats;
 use context_harness::vector_index::{
     self, BruteForceSqliteVectorIndex, DisabledVectorIndex, VectorIndex, VectorSearchOptions,
 };
-use context_harness_core::search::{search, SearchParams, SearchRequest};
+use context_harness_core::bundle::ExportData;
+use context_harness_core::search::{group_by_parent, search, SearchParams, SearchRequest};
+use context_harness_core::store::memory::InMemoryStore;
 use context_harness_core::store::Store;
 use tempfile::TempDir;
 
@@ -68,6 +71,8 @@ fn document(id: &str, source: &str, source_id: &str, body: &str) -> Document {
         metadata_json: "{}".to_string(),
         raw_json: None,
         dedup_hash: format!("hash-{id}"),
+        parent_source_id: None,
+        summary: None,
     }
 }
 
@@ -108,7 +113,7 @@ async fn seed_vector_documents(store: &SqliteAppStore) {
     )
     .await;
 
-    let pending = store.find_pending_chunks("model-a", None).await.unwrap();
+    let pending = store.find_pending_chunks("model-a", 4, None).await.unwrap();
     for item in pending {
         let vector = if item.document_id == "doc-a" {
             vec![1.0, 0.0, 0.0, 0.0]
@@ -143,6 +148,27 @@ async fn sqlite_app_store_checkpoint_round_trip() {
         store.get_checkpoint("filesystem:docs").await.unwrap(),
         Some(1_700_000_000)
     );
+
+    assert_eq!(
+        store.get_checkpoint_state("filesystem:docs").await.unwrap(),
+        None
+    );
+    store
+        .set_checkpoint_state("filesystem:docs", r#"{"page":2}"#)
+        .await
+        .unwrap();
+    store
+        .set_checkpoint("filesystem:docs", 1_700_000_100)
+        .await
+        .unwrap();
+    assert_eq!(
+        store
+            .get_checkpoint_state("filesystem:docs")
+            .await
+            .unwrap()
+            .as_deref(),
+        Some(r#"{"page":2}"#)
+    );
 }
 
 #[tokio::test]
@@ -151,7 +177,7 @@ async fn find_pending_chunks_detects_missing_and_stale_embeddings() {
     let store = initialized_store(&tmp).await;
     seed_document(&store, "doc-a", "filesystem:test", "a.md", "alpha beta").await;
 
-    let pending = store.find_pending_chunks("model-a", None).await.unwrap();
+    let pending = store.find_pending_chunks("model-a", 2, None).await.unwrap();
     assert_eq!(pending.len(), 1);
     assert_eq!(pending[0].document_id, "doc-a");
 
@@ -166,7 +192,7 @@ async fn find_pending_chunks_detects_missing_and_stale_embeddings() {
         )
         .await
         .unwrap();
-    let stale = store.find_pending_chunks("model-a", None).await.unwrap();
+    let stale = store.find_pending_chunks("model-a", 2, None).await.unwrap();
     assert_eq!(stale.len(), 1);
 
     store
@@ -181,18 +207,145 @@ async fn find_pending_chunks_detects_missing_and_stale_embeddings() {
         .await
         .unwrap();
     assert!(store
-        .find_pending_chunks("model-a", None)
+        .find_pending_chunks("model-a", 2, None)
         .await
         .unwrap()
         .is_empty());
 }
 
+#[tokio::test]
+async fn chunker_and_dims_changes_mark_embeddings_stale() {
+    let tmp = TempDir::new().unwrap();
+    let store = initialized_store(&tmp).await;
+    seed_document(&store, "doc-a", "filesystem:test", "a.md", "alpha beta").await;
+    seed_document(&store, "doc-b", "filesystem:test", "b.md", "gamma delta").await;
+
+    for item in store.find_pending_chunks("model-a", 2, None).await.unwrap() {
+        store
+            .upsert_embedding(
+                &item.chunk_id,
+                &item.document_id,
+                &[1.0, 0.0],
+                "model-a",
+                2,
+                &item.text_hash,
+            )
+            .await
+            .unwrap();
+    }
+    let freshness = store.embedding_freshness("model-a", 2).await.unwrap();
+    assert_eq!(freshness.fresh, 2);
+    assert_eq!(freshness.stale(), 0);
+
+    // Same model, different dims.
+    assert_eq!(
+        store
+            .find_pending_chunks("model-a", 3, None)
+            .await
+            .unwrap()
+            .len(),
+        2
+    );
+    assert_eq!(
+        store
+            .embedding_freshness("model-a", 3)
+            .await
+            .unwrap()
+            .stale_dims,
+        2
+    );
+    assert_eq!(
+        store
+            .embedding_freshness("model-b", 2)
+            .await
+            .unwrap()
+            .stale_model,
+        2
+    );
+
+    // Reopen with different chunking settings.
+    let mut cfg = test_config(&tmp);
+    cfg.chunking.max_tokens = 300;
+    let rechunked = SqliteAppStore::connect(&cfg).await.unwrap();
+    let pending = rechunked
+        .find_pending_chunks("model-a", 2, None)
+        .await
+        .unwrap();
+    assert_eq!(pending.len(), 2);
+    assert_eq!(
+        rechunked
+            .get_embedding_hash(&pending[0].chunk_id, "model-a", 2)
+            .await
+            .unwrap(),
+        None
+    );
+    let freshness = rechunked.embedding_freshness("model-a", 2).await.unwrap();
+    assert_eq!(freshness.stale_chunker, 2);
+    assert_eq!(freshness.fresh, 0);
+}
+
+#[tokio::test]
+async fn connector_chunking_overrides_are_tracked_per_source() {
+    let tmp = TempDir::new().unwrap();
+    let store = initialized_store(&tmp).await;
+    seed_document(&store, "doc-a", "filesystem:test", "a.md", "alpha beta").await;
+    seed_document(&store, "doc-b", "filesystem:other", "b.md", "gamma delta").await;
+    for item in store.find_pending_chunks("model-a", 2, None).await.unwrap() {
+        store
+            .upsert_embedding(
+                &item.chunk_id,
+                &item.document_id,
+                &[1.0, 0.0],
+                "model-a",
+                2,
+                &item.text_hash,
+            )
+            .await
+            .unwrap();
+    }
+
+    // Override chunking for one connector only.
+    let mut cfg = test_config(&tmp);
+    cfg.connectors.filesystem.insert(
+        "test".to_string(),
+        toml::from_str("root = \".\"\n[chunking]\nstrategy = \"line\"\nmax_tokens = 300\n")
+            .unwrap(),
+    );
+    let chunking = cfg.chunking_for("filesystem:test");
+    assert_eq!(chunking.strategy, "line");
+    assert_eq!(chunking.max_tokens, 300);
+    assert_eq!(cfg.chunking_for("filesystem:other").strategy, "paragraph");
+
+    let rechunked = SqliteAppStore::connect(&cfg).await.unwrap();
+    let pending = rechunked
+        .find_pending_chunks("model-a", 2, None)
+        .await
+        .unwrap();
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].document_id, "doc-a");
+    let freshness = rechunked.embedding_freshness("model-a", 2).await.unwrap();
+    assert_eq!(freshness.stale_chunker, 1);
+    assert_eq!(freshness.fresh, 1);
+    assert_eq!(freshness.resync, 1);
+
+    // Re-chunking the document records the override's fingerprint.
+    let chunks = chunk_text_with("doc-a", "alpha beta", ChunkStrategy::Line, 300);
+    rechunked
+        .replace_chunks("doc-a", &chunks, None)
+        .await
+        .unwrap();
+    let freshness = rechunked.embedding_freshness("model-a", 2).await.unwrap();
+    assert_eq!(freshness.resync, 0);
+    assert_eq!(freshness.missing, 1);
+    assert_eq!(freshness.fresh, 1);
+}
+
 #[tokio::test]
 async fn clear_embeddings_removes_vectors_but_keeps_documents_and_chunks() {
     let tmp = TempDir::new().unwrap();
     let store = initialized_store(&tmp).await;
     seed_document(&store, "doc-a", "filesystem:test", "a.md", "alpha beta").await;
-    let pending = store.find_pending_chunks("model-a", None).await.unwrap();
+    let pending = store.find_pending_chunks("model-a", 2, None).await.unwrap();
     store
         .upsert_embedding(
             &pending[0].chunk_id,
@@ -223,7 +376,7 @@ async fn export_index_matches_ctx_export_json_shape() {
     let store = initialized_store(&tmp).await;
     seed_document(&store, "doc-a", "filesystem:test", "a.md", "alpha beta").await;
 
-    let exported = store.export_index().await.unwrap();
+    let exported = store.export_index(false).await.unwrap();
     let json = serde_json::to_value(&exported).unwrap();
 
     assert_eq!(json["documents"][0]["id"], "doc-a");
@@ -234,6 +387,50 @@ async fn export_index_matches_ctx_export_json_shape() {
     assert_eq!(json["chunks"][0]["document_id"], "doc-a");
     assert_eq!(json["chunks"][0]["chunk_index"], 0);
     assert_eq!(json["chunks"][0]["text"], "alpha beta");
+    assert!(json["chunks"][0].get("vector").is_none());
+    assert!(json.get("embedding_model").is_none());
+}
+
+#[tokio::test]
+async fn exported_vectors_load_into_memory_store_for_hybrid_search() {
+    let tmp = TempDir::new().unwrap();
+    let store = initialized_store(&tmp).await;
+    seed_vector_documents(&store).await;
+
+    let exported = store.export_index(true).await.unwrap();
+    assert_eq!(exported.embedding_model.as_deref(), Some("model-a"));
+    assert_eq!(exported.embedding_dims, Some(4));
+    assert_eq!(exported.vector_count(), 2);
+
+    let json = serde_json::to_string(&exported).unwrap();
+    let bundle = ExportData::from_json(&json).unwrap();
+    let memory = InMemoryStore::new();
+    bundle.load_into(&memory).await.unwrap();
+
+    let query_vec = [0.0, 1.0, 0.0, 0.0];
+    let req = SearchRequest {
+        query: "alpha",
+        query_vec: Some(&query_vec),
+        mode: "hybrid",
+        source_filter: None,
+        allowed_sources: None,
+        since: None,
+        as_of: None,
+        params: SearchParams {
+            hybrid_alpha: 0.9,
+            candidate_k_keyword: 10,
+            candidate_k_vector: 10,
+            final_limit: 10,
+            auto_merge_min_chunks: 0,
+        },
+        explain: false,
+        chunk_filters: &[],
+        types: &[],
+    };
+    let results = search(&memory, &req).await.unwrap();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].id, "doc-b");
+    assert_eq!(results[0].title.as_deref(), Some("b.md"));
 }
 
 #[tokio::test]
@@ -278,15 +475,20 @@ async fn hybrid_search_treats_hyphenated_terms_as_user_text() {
         candidate_k_keyword: 10,
         candidate_k_vector: 10,
         final_limit: 10,
+        auto_merge_min_chunks: 0,
     };
     let req = SearchRequest {
         query: "deployment local-first MCP-compatible multi-repo",
         query_vec: Some(&[0.9, 0.1]),
         mode: "hybrid",
         source_filter: None,
+        allowed_sources: None,
         since: None,
+        as_of: None,
         params,
         explain: true,
+        chunk_filters: &[],
+        types: &[],
     };
 
     let results = search(&sqlite, &req).await.unwrap();
@@ -295,6 +497,64 @@ async fn hybrid_search_treats_hyphenated_terms_as_user_text() {
     assert_eq!(results[0].explain.as_ref().unwrap().keyword_candidates, 1);
 }
 
+#[tokio::test]
+async fn chunk_metadata_round_trips_and_filters_search() {
+    let tmp = TempDir::new().unwrap();
+    let store = initialized_store(&tmp).await;
+
+    let body = "# Runbook\n\nGeneral notes.\n\n## Rollback\n\nRollback the deployment with helm.";
+    let doc = document("doc-md", "filesystem:test", "runbook.md", body);
+    store.upsert_document(&doc).await.unwrap();
+    let mut chunks = chunk_text("doc-md", body, 5);
+    let mut inherited = serde_json::Map::new();
+    inherited.insert("team".to_string(), serde_json::json!("platform"));
+    annotate_chunks(&mut chunks, body, &inherited, true);
+    store.replace_chunks("doc-md", &chunks, None).await.unwrap();
+
+    let fetched = store.get_document("doc-md").await.unwrap().unwrap();
+    let rollback = fetched
+        .chunks
+        .iter()
+        .find(|c| c.text.contains("helm"))
+        .unwrap();
+    assert_eq!(rollback.metadata["heading"], "Runbook > Rollback");
+    assert_eq!(rollback.metadata["team"], "platform");
+
+    let sqlite = SqliteStore::new(store.pool().clone());
+    let params = SearchParams {
+        hybrid_alpha: 0.0,
+        candidate_k_keyword: 10,
+        candidate_k_vector: 10,
+        final_limit: 10,
+        auto_merge_min_chunks: 0,
+    };
+    let matching = vec![("heading".to_string(), "rollback".to_string())];
+    let req = SearchRequest {
+        query: "helm",
+        query_vec: None,
+        mode: "keyword",
+        source_filter: None,
+        allowed_sources: None,
+        since: None,
+        as_of: None,
+        params: params.clone(),
+        explain: false,
+        chunk_filters: &matching,
+        types: &[],
+    };
+    let results = search(&sqlite, &req).await.unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].heading.as_deref(), Some("Runbook > Rollback"));
+
+    let non_matching = vec![("team".to_string(), "security".to_string())];
+    let req = SearchRequest {
+        chunk_filters: &non_matching,
+        types: &[],
+        ..req
+    };
+    assert!(search(&sqlite, &req).await.unwrap().is_empty());
+}
+
 #[tokio::test]
 async fn stats_match_document_chunk_and_embedding_counts() {
     let tmp = TempDir::new().unwrap();
@@ -302,7 +562,7 @@ async fn stats_match_document_chunk_and_embedding_counts() {
     seed_document(&store, "doc-a", "filesystem:test", "a.md", "alpha beta").await;
     seed_document(&store, "doc-b", "filesystem:test", "b.md", "gamma delta").await;
 
-    let pending = store.find_pending_chunks("model-a", None).await.unwrap();
+    let pending = store.find_pending_chunks("model-a", 2, None).await.unwrap();
     store
         .upsert_embedding(
             &pending[0].chunk_id,
@@ -326,6 +586,46 @@ async fn stats_match_document_chunk_and_embedding_counts() {
     assert_eq!(stats.sources[0].embedded_count, 1);
 }
 
+#[tokio::test]
+async fn stats_report_tracks_growth_since_snapshots() {
+    let tmp = TempDir::new().unwrap();
+    let cfg = test_config(&tmp);
+    let store = initialized_store(&tmp).await;
+    seed_document(&store, "doc-a", "filesystem:test", "a.md", "alpha beta").await;
+    let today = Utc::now().date_naive();
+    stats::record_snapshot_on(&store, today - chrono::Duration::days(7))
+        .await
+        .unwrap();
+
+    seed_document(&store, "doc-b", "filesystem:test", "b.md", "gamma delta").await;
+    seed_document(&store, "doc-c", "filesystem:test", "c.md", "epsilon").await;
+    store.set_checkpoint("filesystem:test", 1).await.unwrap();
+    stats::record_snapshot(&store).await.unwrap();
+    store.close().await;
+
+    let report = stats::stats_report(&cfg).await.unwrap();
+    assert_eq!(report.total_docs, 3);
+    assert_eq!(report.embedding_coverage_percent, 0.0);
+    assert!((report.avg_chunk_chars - 28.0 / 3.0).abs() < 0.1);
+    assert_eq!(report.history.len(), 2);
+    assert_eq!(report.history[1].total_docs, 3);
+
+    // Yesterday and a week ago both resolve to the 7-day-old snapshot; there
+    // is none old enough for the 30-day window.
+    let windows: Vec<i64> = report.growth.iter().map(|g| g.days).collect();
+    assert_eq!(windows, vec![1, 7]);
+    let week = &report.growth[1];
+    assert_eq!(week.docs, 2);
+    assert_eq!(week.chunks, 2);
+
+    assert_eq!(report.sources.len(), 1);
+    assert_eq!(report.sources[0].days_since_sync, Some(0));
+
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(json["sources"][0]["source"], "filesystem:test");
+    assert!(json["sources"][0]["days_since_sync"].is_number());
+}
+
 #[tokio::test]
 async fn brute_force_vector_index_matches_sqlite_vector_search_ordering() {
     let tmp = TempDir::new().unwrap();
@@ -333,7 +633,7 @@ async fn brute_force_vector_index_matches_sqlite_vector_search_ordering() {
     seed_document(&store, "doc-a", "filesystem:test", "a.md", "alpha beta").await;
     seed_document(&store, "doc-b", "filesystem:test", "b.md", "gamma delta").await;
 
-    let pending = store.find_pending_chunks("model-a", None).await.unwrap();
+    let pending = store.find_pending_chunks("model-a", 2, None).await.unwrap();
     store
         .upsert_embedding(
             &pending[0].chunk_id,
@@ -513,15 +813,20 @@ async fn zvec_semantic_and_hybrid_search_remain_compatible() {
         candidate_k_keyword: 10,
         candidate_k_vector: 10,
         final_limit: 10,
+        auto_merge_min_chunks: 0,
     };
     let semantic_req = SearchRequest {
         query: "deployment",
         query_vec: Some(&[0.9, 0.1, 0.0, 0.0]),
         mode: "semantic",
         source_filter: None,
+        allowed_sources: None,
         since: None,
+        as_of: None,
         params: params.clone(),
         explain: true,
+        chunk_filters: &[],
+        types: &[],
     };
     let sqlite_semantic = search(&sqlite, &semantic_req).await.unwrap();
     let zvec_semantic = search(&indexed, &semantic_req).await.unwrap();
@@ -534,9 +839,13 @@ async fn zvec_semantic_and_hybrid_search_remain_compatible() {
         query_vec: Some(&[0.9, 0.1, 0.0, 0.0]),
         mode: "hybrid",
         source_filter: None,
+        allowed_sources: None,
         since: None,
+        as_of: None,
         params,
         explain: true,
+        chunk_filters: &[],
+        types: &[],
     };
     let hybrid = search(&indexed, &hybrid_req).await.unwrap();
     let explain = hybrid[0].explain.as_ref().unwrap();
@@ -635,6 +944,7 @@ async fn sqlite_app_store_upserts_source_items_as_canonical_documents() {
         metadata_json: "{}".to_string(),
         raw_json: None,
         raw_bytes: None,
+        parent_source_id: None,
     };
 
     let first_id = store.upsert_source_item(&item).await.unwrap();
@@ -646,3 +956,306 @@ async fn sqlite_app_store_upserts_source_items_as_canonical_documents() {
     assert_eq!(doc.source_id, "source.md");
     assert_eq!(doc.body, "source body");
 }
+
+async fn chunk_ids(store: &SqliteAppStore, doc_id: &str) -> Vec<String> {
+    sqlx::query_scalar("SELECT id FROM chunks WHERE document_id = ? ORDER BY chunk_index")
+        .bind(doc_id)
+        .fetch_all(store.pool())
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn write_document_is_idempotent_under_retries() {
+    let tmp = TempDir::new().unwrap();
+    let store = initialized_store(&tmp).await;
+    let body = "First paragraph.\n\nSecond paragraph.";
+    let doc = document("doc-w", "filesystem:test", "w.md", body);
+
+    let first = store
+        .write_document(&doc, &chunk_text("doc-w", body, 2))
+        .await
+        .unwrap();
+    assert!(!first.unchanged);
+    assert!(first.chunk_ids.len() > 1);
+    store
+        .upsert_embedding(&first.chunk_ids[0], "doc-w", &[1.0, 0.0], "m", 2, "h")
+        .await
+        .unwrap();
+
+    // A retry re-chunks with fresh chunk IDs; the stored chunks and their
+    // embeddings are kept.
+    let retry = store
+        .write_document(&doc, &chunk_text("doc-w", body, 2))
+        .await
+        .unwrap();
+    assert!(retry.unchanged);
+    assert_eq!(retry.chunk_ids, first.chunk_ids);
+    assert_eq!(chunk_ids(&store, "doc-w").await, first.chunk_ids);
+    assert_eq!(
+        store
+            .get_embedding_hash(&first.chunk_ids[0], "m", 2)
+            .await
+            .unwrap()
+            .as_deref(),
+        Some("h")
+    );
+
+    // A new content hash replaces the chunks.
+    let mut changed = document("doc-w", "filesystem:test", "w.md", "Rewritten.");
+    changed.dedup_hash = "hash-changed".to_string();
+    let rewritten = store
+        .write_document(&changed, &chunk_text("doc-w", "Rewritten.", 2))
+        .await
+        .unwrap();
+    assert!(!rewritten.unchanged);
+    assert_eq!(chunk_ids(&store, "doc-w").await, rewritten.chunk_ids);
+}
+
+#[tokio::test]
+async fn failed_document_write_leaves_previous_version() {
+    let tmp = TempDir::new().unwrap();
+    let store = initialized_store(&tmp).await;
+    let doc = document("doc-f", "filesystem:test", "f.md", "Old body.");
+    let old = store
+        .write_document(&doc, &chunk_text("doc-f", "Old body.", 700))
+        .await
+        .unwrap();
+
+    // The second chunk fails mid-write (duplicate chunk index), after the
+    // document row and first chunk were written in the transaction.
+    let mut next = document("doc-f", "filesystem:test", "f.md", "New body.");
+    next.dedup_hash = "hash-next".to_string();
+    let mut chunks = chunk_text("doc-f", "New body.\n\nMore.", 2);
+    chunks[1].chunk_index = 0;
+    assert!(store.write_document(&next, &chunks).await.is_err());
+
+    let stored = store.get_document("doc-f").await.unwrap().unwrap();
+    assert_eq!(stored.body, "Old body.");
+    assert_eq!(chunk_ids(&store, "doc-f").await, old.chunk_ids);
+    let fts: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM chunks_fts WHERE document_id = ?")
+        .bind("doc-f")
+        .fetch_one(store.pool())
+        .await
+        .unwrap();
+    assert_eq!(fts, 1);
+
+    // A partial chunk set left by an older, non-transactional write is
+    // not mistaken for an unchanged document.
+    let body = "One.\n\nTwo.";
+    let full = store
+        .write_document(&next, &chunk_text("doc-f", body, 2))
+        .await
+        .unwrap();
+    sqlx::query("DELETE FROM chunks WHERE id = ?")
+        .bind(&full.chunk_ids[1])
+        .execute(store.pool())
+        .await
+        .unwrap();
+    let repaired = store
+        .write_document(&next, &chunk_text("doc-f", body, 2))
+        .await
+        .unwrap();
+    assert!(!repaired.unchanged);
+    assert_eq!(chunk_ids(&store, "doc-f").await, repaired.chunk_ids);
+    assert_eq!(repaired.chunk_ids.len(), full.chunk_ids.len());
+}
+
+#[tokio::test]
+async fn write_document_uses_the_stored_id_when_another_writer_won() {
+    let tmp = TempDir::new().unwrap();
+    let store = initialized_store(&tmp).await;
+    let body = "Shared body.";
+    let winner = document("doc-1", "filesystem:test", "same.md", body);
+    store
+        .write_document(&winner, &chunk_text("doc-1", body, 700))
+        .await
+        .unwrap();
+
+    // Resolved before the winner committed, so it carries a fresh ID.
+    let mut loser = document("doc-2", "filesystem:test", "same.md", "Other body.");
+    loser.dedup_hash = "hash-other".to_string();
+    let written = store
+        .write_document(&loser, &chunk_text("doc-2", "Other body.", 700))
+        .await
+        .unwrap();
+    assert_eq!(written.id, "doc-1");
+    assert_eq!(chunk_ids(&store, "doc-1").await, written.chunk_ids);
+    assert!(chunk_ids(&store, "doc-2").await.is_empty());
+}
+
+#[tokio::test]
+async fn child_documents_link_to_parent_in_get_and_search() {
+    let tmp = TempDir::new().unwrap();
+    let store = initialized_store(&tmp).await;
+
+    seed_document(
+        &store,
+        "doc-page",
+        "script:confluence",
+        "PAGE-1",
+        "Deployment guide for the payments service.",
+    )
+    .await;
+    let mut attachment = document(
+        "doc-attachment",
+        "script:confluence",
+        "PAGE-1/diagram.pdf",
+        "Payments deployment topology diagram.",
+    );
+    attachment.parent_source_id = Some("PAGE-1".to_string());
+    store.upsert_document(&attachment).await.unwrap();
+    let chunks = chunk_text("doc-attachment", &attachment.body, 700);
+    store
+        .replace_chunks("doc-attachment", &chunks, None)
+        .await
+        .unwrap();
+
+    let page = store.get_document("doc-page").await.unwrap().unwrap();
+    assert_eq!(page.parent_id, None);
+    assert_eq!(page.children.len(), 1);
+    assert_eq!(page.children[0].id, "doc-attachment");
+
+    let child = store.get_document("doc-attachment").await.unwrap().unwrap();
+    assert_eq!(child.parent_id.as_deref(), Some("doc-page"));
+    assert!(child.children.is_empty());
+
+    let sqlite = SqliteStore::new(store.pool().clone());
+    let req = SearchRequest {
+        query: "payments deployment",
+        query_vec: None,
+        mode: "keyword",
+        source_filter: None,
+        allowed_sources: None,
+        since: None,
+        as_of: None,
+        params: SearchParams {
+            hybrid_alpha: 0.0,
+            candidate_k_keyword: 10,
+            candidate_k_vector: 10,
+            final_limit: 10,
+            auto_merge_min_chunks: 0,
+        },
+        explain: false,
+        chunk_filters: &[],
+        types: &[],
+    };
+    let groups = group_by_parent(search(&sqlite, &req).await.unwrap());
+    assert_eq!(groups.len(), 1);
+    assert_eq!(groups[0].parent_id, "doc-page");
+    assert_eq!(groups[0].results.len(), 2);
+}
+
+#[tokio::test]
+async fn fuzzy_keyword_search_tolerates_typos() {
+    let tmp = TempDir::new().unwrap();
+    let store = initialized_store(&tmp).await;
+    seed_document(
+        &store,
+        "doc-k8s",
+        "filesystem:test",
+        "k8s.md",
+        "Kubernetes deployment runbook.",
+    )
+    .await;
+
+    let req = SearchRequest {
+        query: "kubernets deploymnet",
+        query_vec: None,
+        mode: "keyword",
+        source_filter: None,
+        allowed_sources: None,
+        since: None,
+        as_of: None,
+        params: SearchParams {
+            hybrid_alpha: 0.0,
+            candidate_k_keyword: 10,
+            candidate_k_vector: 10,
+            final_limit: 10,
+            auto_merge_min_chunks: 0,
+        },
+        explain: false,
+        chunk_filters: &[],
+        types: &[],
+    };
+    let exact = SqliteStore::new(store.pool().clone());
+    assert!(search(&exact, &req).await.unwrap().is_empty());
+
+    let fuzzy = SqliteStore::new(store.pool().clone()).with_fuzzy(true);
+    let results = search(&fuzzy, &req).await.unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].id, "doc-k8s");
+}
+
+#[tokio::test]
+async fn keyword_search_weights_titles_and_drops_stop_words() {
+    let tmp = TempDir::new().unwrap();
+    let store = initialized_store(&tmp).await;
+    // Titles are the source ids: "deploy.md" matches "deploy" only by title.
+    seed_document(
+        &store,
+        "doc-title",
+        "filesystem:test",
+        "deploy.md",
+        "Steps to release the service.",
+    )
+    .await;
+    seed_document(
+        &store,
+        "doc-body",
+        "filesystem:test",
+        "notes.md",
+        "Notes: deploy after review.",
+    )
+    .await;
+
+    let request = |query| SearchRequest {
+        query,
+        query_vec: None,
+        mode: "keyword",
+        source_filter: None,
+        allowed_sources: None,
+        since: None,
+        as_of: None,
+        params: SearchParams {
+            hybrid_alpha: 0.0,
+            candidate_k_keyword: 10,
+            candidate_k_vector: 10,
+            final_limit: 10,
+            auto_merge_min_chunks: 0,
+        },
+        explain: false,
+        chunk_filters: &[],
+        types: &[],
+    };
+    let store_with = |retrieval: &str| {
+        let mut config = test_config(&tmp);
+        config.retrieval = toml::from_str(retrieval).unwrap();
+        SqliteStore::new(store.pool().clone()).with_retrieval(&config.retrieval)
+    };
+
+    let title_heavy = store_with("[weights]\ntitle = 10.0");
+    let results = search(&title_heavy, &request("deploy")).await.unwrap();
+    let ids: Vec<&str> = results.iter().map(|r| r.id.as_str()).collect();
+    assert_eq!(ids, ["doc-title", "doc-body"]);
+
+    let titles_ignored = store_with("[weights]\ntitle = 0.0");
+    let results = search(&titles_ignored, &request("deploy")).await.unwrap();
+    assert_eq!(results[0].id, "doc-body");
+
+    let plain = store_with("");
+    assert!(search(&plain, &request("an deploy review"))
+        .await
+        .unwrap()
+        .is_empty());
+    let stop_words = store_with("stop_words = [\"an\", \"The\"]");
+    let results = search(&stop_words, &request("an deploy review"))
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].id, "doc-body");
+    // Only stop words: the query is kept rather than emptied.
+    let results = search(&stop_words, &request("the")).await.unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].id, "doc-title");
+}
@@ -280,6 +280,56 @@ fn file_support_skipped_on_failure() {
     );
 }
 
+// Failed items are reported, recorded, and ingested by --retry-failed once fixed.
+#[test]
+fn file_support_failed_items_retried() {
+    let (_tmp, config_path) = setup_file_support_env(true, false);
+    let files_dir = _tmp.path().join("files");
+    fs::write(files_dir.join("bad.pdf"), b"not a valid pdf").unwrap();
+
+    run_ctx(&config_path, &["init"]);
+    let (stdout, stderr, success) = run_ctx(&config_path, &["sync", "filesystem:test"]);
+    assert!(
+        success,
+        "sync must succeed: stdout={}, stderr={}",
+        stdout, stderr
+    );
+    assert!(stdout.contains("failed items: 1"), "{}", stdout);
+    assert!(stdout.contains("bad.pdf (extract)"), "{}", stdout);
+    assert!(
+        stdout.contains("ctx sync filesystem:test --retry-failed"),
+        "{}",
+        stdout
+    );
+
+    let (_, stderr, success) = run_ctx(
+        &config_path,
+        &["sync", "filesystem:test", "--full", "--strict"],
+    );
+    assert!(!success, "strict sync must fail on the bad PDF");
+    assert!(stderr.contains("bad.pdf"), "{}", stderr);
+
+    fs::write(files_dir.join("bad.pdf"), minimal_pdf_with_phrase()).unwrap();
+    let (stdout, stderr, success) =
+        run_ctx(&config_path, &["sync", "filesystem:test", "--retry-failed"]);
+    assert!(
+        success,
+        "retry must succeed: stdout={}, stderr={}",
+        stdout, stderr
+    );
+    assert!(stdout.contains("upserted documents: 1"), "{}", stdout);
+    assert!(
+        stdout.contains("checkpoint: unchanged (retry)"),
+        "{}",
+        stdout
+    );
+
+    let (stdout, _, success) =
+        run_ctx(&config_path, &["sync", "filesystem:test", "--retry-failed"]);
+    assert!(success);
+    assert!(stdout.contains("No failed items to retry"), "{}", stdout);
+}
+
 // §8.1 (PDF) — Separate test: sync a real extractable PDF and assert search finds text from it
 #[test]
 fn file_support_pdf_extractable_search() {
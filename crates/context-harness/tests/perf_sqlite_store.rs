@@ -103,6 +103,7 @@ async fn run_sqlite_benchmark(scenario: Scenario) -> BenchmarkReport {
         candidate_k_keyword: scenario.candidate_k,
         candidate_k_vector: scenario.candidate_k,
         final_limit: 12,
+        auto_merge_min_chunks: 0,
     };
 
     // Warm the OS and SQLite page cache before collecting timings.
@@ -139,9 +140,13 @@ async fn run_sqlite_benchmark(scenario: Scenario) -> BenchmarkReport {
             query_vec: Some(&query_vec),
             mode: "hybrid",
             source_filter: None,
+            allowed_sources: None,
             since: None,
+            as_of: None,
             params: params.clone(),
             explain: true,
+            chunk_filters: &[],
+            types: &[],
         };
         search::search(&store, &req).await.unwrap().len()
     })
@@ -0,0 +1,135 @@
+use std::time::Duration;
+
+use axum::http::HeaderMap;
+use context_harness::audit::{self, AuditEntry, AuditLog};
+use context_harness::config::Config;
+use serde_json::json;
+use tempfile::TempDir;
+
+fn audit_config(tmp: &TempDir, sink: &str) -> Config {
+    let db_path = tmp.path().join("ctx.sqlite");
+    let config_content = format!(
+        r#"
+[db]
+path = "{}"
+
+[chunking]
+max_tokens = 700
+
+[retrieval]
+final_limit = 12
+
+[server]
+bind = "127.0.0.1:0"
+
+[tools.audit]
+enabled = true
+sink = "{}"
+"#,
+        db_path.display(),
+        sink
+    );
+    toml::from_str(&config_content).unwrap()
+}
+
+fn entry(tool: &str, params: serde_json::Value, error: Option<&str>) -> AuditEntry {
+    AuditEntry::new(
+        tool,
+        Some(audit::key_fingerprint("secret-key")),
+        "http",
+        params,
+        Duration::from_millis(12),
+        error.map(|e| e.to_string()),
+    )
+}
+
+#[test]
+fn redact_params_is_case_insensitive_and_recursive() {
+    let keys = vec!["token".to_string(), "password".to_string()];
+    let params = json!({
+        "query": "deploy",
+        "Token": "abc",
+        "nested": { "password": "hunter2", "keep": 1 },
+        "list": [{ "TOKEN": "x" }]
+    });
+
+    let redacted = audit::redact_params(&params, &keys);
+
+    assert_eq!(redacted["query"], "deploy");
+    assert_eq!(redacted["Token"], "[REDACTED]");
+    assert_eq!(redacted["nested"]["password"], "[REDACTED]");
+    assert_eq!(redacted["nested"]["keep"], 1);
+    assert_eq!(redacted["list"][0]["TOKEN"], "[REDACTED]");
+}
+
+#[test]
+fn caller_from_headers_fingerprints_key() {
+    let mut headers = HeaderMap::new();
+    assert_eq!(audit::caller_from_headers(&headers), None);
+
+    headers.insert("authorization", "Bearer sk-test-123".parse().unwrap());
+    let caller = audit::caller_from_headers(&headers).unwrap();
+    assert_eq!(caller, audit::key_fingerprint("sk-test-123"));
+    assert!(caller.starts_with("key:"));
+    assert!(!caller.contains("sk-test-123"));
+
+    // X-Api-Key takes precedence over Authorization
+    headers.insert("x-api-key", "other-key".parse().unwrap());
+    assert_eq!(
+        audit::caller_from_headers(&headers).unwrap(),
+        audit::key_fingerprint("other-key")
+    );
+}
+
+#[tokio::test]
+async fn sqlite_sink_records_and_filters() {
+    let tmp = TempDir::new().unwrap();
+    let config = audit_config(&tmp, "sqlite");
+    let log = AuditLog::open(&config).await.unwrap().unwrap();
+
+    log.record(entry("search", json!({"query": "a", "api_key": "k"}), None))
+        .await;
+    log.record(entry("get", json!({"id": "x"}), Some("document not found")))
+        .await;
+
+    let all = audit::read_entries(&config, None, 10).await.unwrap();
+    assert_eq!(all.len(), 2);
+    assert_eq!(all[0].tool, "get");
+    assert_eq!(all[0].outcome, "error");
+    assert_eq!(all[0].error.as_deref(), Some("document not found"));
+
+    let search = audit::read_entries(&config, Some("search"), 10)
+        .await
+        .unwrap();
+    assert_eq!(search.len(), 1);
+    assert_eq!(search[0].outcome, "ok");
+    assert_eq!(search[0].transport, "http");
+    assert_eq!(search[0].duration_ms, 12);
+    assert_eq!(search[0].params["api_key"], "[REDACTED]");
+    assert_eq!(search[0].params["query"], "a");
+}
+
+#[tokio::test]
+async fn jsonl_sink_records_newest_first() {
+    let tmp = TempDir::new().unwrap();
+    let config = audit_config(&tmp, "jsonl");
+    let log = AuditLog::open(&config).await.unwrap().unwrap();
+
+    for i in 0..3 {
+        log.record(entry("search", json!({"query": i}), None)).await;
+    }
+
+    assert!(tmp.path().join("tool-audit.jsonl").exists());
+    let entries = audit::read_entries(&config, None, 2).await.unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].params["query"], 2);
+    assert_eq!(entries[1].params["query"], 1);
+}
+
+#[tokio::test]
+async fn disabled_audit_opens_nothing() {
+    let tmp = TempDir::new().unwrap();
+    let mut config = audit_config(&tmp, "sqlite");
+    config.tools.audit.enabled = false;
+    assert!(AuditLog::open(&config).await.unwrap().is_none());
+}
@@ -8,6 +8,7 @@ use anyhow::Result;
 use async_trait::async_trait;
 use chrono::Utc;
 use context_harness::agents::{Agent, AgentPrompt, AgentRegistry};
+use context_harness::auth::SourceScope;
 use context_harness::config::Config;
 use context_harness::ingest::run_sync_with_extensions;
 use context_harness::migrate;
@@ -62,6 +63,7 @@ impl Connector for InMemoryConnector {
                 metadata_json: "{}".to_string(),
                 raw_json: None,
                 raw_bytes: None,
+                parent_source_id: None,
             })
             .collect())
     }
@@ -259,6 +261,156 @@ async fn test_custom_connector_sync_and_search() {
     assert!(!results.is_empty(), "Should find Docker/Kubernetes docs");
 }
 
+/// Prove that the built-in overview tool summarizes synced documents.
+#[tokio::test]
+async fn test_overview_tool_summarizes_corpus() {
+    let tmp = TempDir::new().unwrap();
+    let cfg = test_config(&tmp);
+    migrate::run_migrations(&cfg).await.unwrap();
+
+    let mut connectors = ConnectorRegistry::new();
+    connectors.register(Box::new(InMemoryConnector::new(vec![
+        ("docs/a.md".into(), "A".into(), "Alpha body text.".into()),
+        ("docs/b.md".into(), "B".into(), "Beta body text.".into()),
+        (
+            "README.md".into(),
+            "Readme".into(),
+            "Top-level readme.".into(),
+        ),
+    ])));
+    run_sync_with_extensions(
+        &cfg,
+        "custom:inmemory",
+        true,
+        false,
+        None,
+        None,
+        None,
+        &connectors,
+    )
+    .await
+    .unwrap();
+
+    let tools = ToolRegistry::with_builtins();
+    let tool = tools.find("overview").expect("overview is a built-in");
+    let ctx = ToolContext::new(Arc::new(cfg));
+    let result = tool.execute(json!({ "limit": 2 }), &ctx).await.unwrap();
+
+    assert_eq!(result["total_docs"], 3);
+    assert_eq!(result["sources"][0]["source"], "custom:inmemory");
+    assert_eq!(result["sources"][0]["doc_count"], 3);
+    assert_eq!(result["top_directories"][0]["directory"], "docs");
+    assert_eq!(result["top_directories"][0]["doc_count"], 2);
+    assert_eq!(result["recent"].as_array().unwrap().len(), 2);
+}
+
+/// Prove that the built-in capabilities tool reports the available modes
+/// and only the collections inside a key's scope.
+#[tokio::test]
+async fn test_capabilities_tool_respects_scope() {
+    let tmp = TempDir::new().unwrap();
+    let mut cfg = test_config(&tmp);
+    migrate::run_migrations(&cfg).await.unwrap();
+    cfg.server
+        .auth
+        .collections
+        .insert("docs".to_string(), vec!["custom:docs".to_string()]);
+    cfg.server
+        .auth
+        .collections
+        .insert("hr".to_string(), vec!["custom:hr".to_string()]);
+
+    let tools = ToolRegistry::with_builtins();
+    let tool = tools
+        .find("capabilities")
+        .expect("capabilities is a built-in");
+    let cfg = Arc::new(cfg);
+
+    let result = tool
+        .execute(json!({}), &ToolContext::new(cfg.clone()))
+        .await
+        .unwrap();
+    assert_eq!(result["modes"], json!(["keyword"]));
+    assert_eq!(result["embeddings"]["enabled"], false);
+    assert_eq!(result["group_by"][0], "path_prefix");
+    assert!(result["collections"]["hr"].is_array());
+
+    let scope = SourceScope::new(vec!["custom:docs".to_string()]);
+    let ctx = ToolContext::new(cfg).with_scope(Some(Arc::new(scope)));
+    let result = tool.execute(json!({}), &ctx).await.unwrap();
+    assert_eq!(result["collections"], json!({ "docs": ["custom:docs"] }));
+}
+
+/// Prove that the built-in get tool retrieves a batch in request order.
+#[tokio::test]
+async fn test_get_tool_batch_preserves_order_and_reports_missing() {
+    let tmp = TempDir::new().unwrap();
+    let cfg = test_config(&tmp);
+    migrate::run_migrations(&cfg).await.unwrap();
+
+    let mut connectors = ConnectorRegistry::new();
+    connectors.register(Box::new(InMemoryConnector::new(vec![
+        ("a.md".into(), "Alpha".into(), "Alpha body text.".into()),
+        ("b.md".into(), "Beta".into(), "Beta body text.".into()),
+    ])));
+    run_sync_with_extensions(
+        &cfg,
+        "custom:inmemory",
+        true,
+        false,
+        None,
+        None,
+        None,
+        &connectors,
+    )
+    .await
+    .unwrap();
+
+    let tools = ToolRegistry::with_builtins();
+    let ctx = ToolContext::new(Arc::new(cfg));
+    let hits = tools
+        .find("search")
+        .unwrap()
+        .execute(json!({ "query": "body" }), &ctx)
+        .await
+        .unwrap();
+    let id_for = |title: &str| {
+        hits["results"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|r| r["title"] == title)
+            .unwrap()["id"]
+            .as_str()
+            .unwrap()
+            .to_string()
+    };
+    let (alpha, beta) = (id_for("Alpha"), id_for("Beta"));
+
+    let result = tools
+        .find("get")
+        .unwrap()
+        .execute(
+            json!({
+                "ids": [beta, "missing-id", alpha],
+                "include_chunks": false,
+                "max_body_chars": 4
+            }),
+            &ctx,
+        )
+        .await
+        .unwrap();
+
+    let docs = result["documents"].as_array().unwrap();
+    assert_eq!(docs.len(), 3);
+    assert_eq!(docs[0]["document"]["title"], "Beta");
+    assert_eq!(docs[0]["document"]["body"], "Beta");
+    assert_eq!(docs[0]["truncated"], true);
+    assert_eq!(docs[0]["document"]["chunks"].as_array().unwrap().len(), 0);
+    assert!(docs[1]["error"].as_str().unwrap().contains("not found"));
+    assert_eq!(docs[2]["document"]["title"], "Alpha");
+}
+
 /// Prove that syncing "all" includes custom connectors.
 #[tokio::test]
 async fn test_custom_connector_included_in_sync_all() {
@@ -446,7 +598,12 @@ async fn test_tool_list_includes_builtins_and_custom() {
     // Built-in tools should always be present
     assert!(tool_names.contains(&"search"), "Missing built-in: search");
     assert!(tool_names.contains(&"get"), "Missing built-in: get");
+    assert!(tool_names.contains(&"similar"), "Missing built-in: similar");
     assert!(tool_names.contains(&"sources"), "Missing built-in: sources");
+    assert!(
+        tool_names.contains(&"overview"),
+        "Missing built-in: overview"
+    );
 
     // Custom tool should also be present
     assert!(
@@ -485,7 +642,7 @@ impl Agent for TestAgent {
         Ok(AgentPrompt {
             system: format!("You are a test agent focused on {}.", topic),
             tools: self.tools(),
-            messages: vec![],
+            ..Default::default()
         })
     }
 }